@@ -21,6 +21,7 @@ fn create_test_database() -> Database {
             unique: true,
             default: None,
             references: None,
+            collation: None,
         },
         Column {
             name: "name".to_string(),
@@ -30,6 +31,7 @@ fn create_test_database() -> Database {
             unique: false,
             default: None,
             references: None,
+            collation: None,
         },
         Column {
             name: "age".to_string(),
@@ -39,6 +41,7 @@ fn create_test_database() -> Database {
             unique: false,
             default: None,
             references: None,
+            collation: None,
         },
         Column {
             name: "active".to_string(),
@@ -48,6 +51,7 @@ fn create_test_database() -> Database {
             unique: false,
             default: Some("true".to_string()),
             references: None,
+            collation: None,
         },
     ];
 
@@ -0,0 +1,9 @@
+// Generate the gRPC service from its definition, with the protoc that protoc-bin-vendored
+// bundles so that building does not need one installed
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let protoc = protoc_bin_vendored::protoc_bin_path()?;
+    // SAFETY: the build script sets the variable before it starts any thread
+    unsafe { std::env::set_var("PROTOC", protoc) };
+    tonic_build::compile_protos("proto/yamlbase.proto")?;
+    Ok(())
+}
@@ -105,6 +105,7 @@ fn main() {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
             ],
         );
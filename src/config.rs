@@ -1,19 +1,48 @@
-use clap::Parser;
+use clap::parser::ValueSource;
+use clap::{ArgMatches, CommandFactory, FromArgMatches, Parser};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::HashSet;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
+use tracing::info;
+
+use crate::database::{Collation, TimeZone};
+use crate::protocol::password::Password;
+use crate::sql::{RecursionLimits, WriteMode};
+use crate::yaml::remote::is_url;
+use crate::yaml::{Coercion, Grants, UserConfig};
 
 #[derive(Debug, Clone, Parser, Serialize, Deserialize)]
 #[command(name = "yamlbase")]
 #[command(author, version, about, long_about = None)]
+#[command(subcommand_negates_reqs = true)]
 pub struct Config {
-    #[arg(short, long, value_name = "FILE", help = "Path to YAML database file")]
-    pub file: PathBuf,
+    #[command(subcommand)]
+    #[serde(skip)]
+    pub command: Option<Command>,
+
+    #[arg(
+        short = 'f',
+        long = "file",
+        value_name = "FILE",
+        help = "Path to YAML database file, to a directory of YAML files, or an http(s), s3 or gs URL; may be repeated to serve several databases"
+    )]
+    pub files: Vec<PathBuf>,
+
+    #[arg(
+        short = 'c',
+        long,
+        value_name = "FILE",
+        help = "TOML file of settings, which options on the command line override (default: yamlbase.toml, if there is one)"
+    )]
+    #[serde(skip)]
+    pub config: Option<PathBuf>,
 
     #[arg(
         short,
         long,
-        help = "Port to listen on (default: 5432 for postgres, 3306 for mysql)"
+        help = "Port to listen on (default: 5432 for postgres, 3306 for mysql, 1433 for sqlserver, 8080 for http, 50051 for grpc)"
     )]
     pub port: Option<u16>,
 
@@ -28,6 +57,10 @@ pub struct Config {
     )]
     pub protocol: Protocol,
 
+    #[command(flatten)]
+    #[serde(flatten)]
+    pub listener_ports: ListenerPorts,
+
     #[arg(
         short = 'u',
         long,
@@ -44,9 +77,43 @@ pub struct Config {
     )]
     pub password: String,
 
+    // A bcrypt or argon2 hash of the password, from `auth.password_hash` of the YAML file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[clap(skip)]
+    pub password_hash: Option<String>,
+
+    // Users besides `username`, from the `auth.users` list of the YAML file
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[clap(skip)]
+    pub users: Vec<UserConfig>,
+
+    #[arg(
+        long,
+        value_enum,
+        help = "How PostgreSQL clients send plain text passwords: scram-sha-256 or password (default: scram-sha-256)"
+    )]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auth_method: Option<AuthMethod>,
+
     #[arg(long, help = "Enable hot-reloading of YAML file changes")]
     pub hot_reload: bool,
 
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        help = "How often a database loaded from a URL is fetched again with --hot-reload (default: 30)"
+    )]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reload_interval: Option<u64>,
+
+    #[arg(
+        long = "url-header",
+        value_name = "HEADER",
+        help = "Header sent when fetching a database from a URL, as 'Name: value'; may be repeated"
+    )]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub url_headers: Vec<String>,
+
     #[arg(short, long, help = "Enable verbose logging")]
     pub verbose: bool,
 
@@ -66,31 +133,277 @@ pub struct Config {
     )]
     pub allow_anonymous: bool,
 
-    // Connection management settings (not exposed via CLI - configured via YAML)
-    #[serde(skip_serializing_if = "Option::is_none")]
-    #[clap(skip)]
+    #[arg(
+        long,
+        value_name = "SEED",
+        help = "Seed RAND()/RANDOM() so query results are reproducible"
+    )]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub random_seed: Option<u64>,
+
+    #[arg(
+        long,
+        value_name = "ZONE",
+        help = "Default time zone for TIMESTAMPTZ values, e.g. Europe/Amsterdam (default: UTC)"
+    )]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timezone: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "N",
+        help = "Maximum iterations of a recursive CTE (default: 1000)"
+    )]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cte_max_recursion_depth: Option<usize>,
+
+    #[arg(
+        long,
+        value_name = "N",
+        help = "Maximum rows a recursive CTE may produce (default: 1000000)"
+    )]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cte_max_rows: Option<usize>,
+
+    #[arg(
+        long,
+        value_name = "NAME",
+        help = "Default collation for text comparisons: binary, ci_ascii or ci_unicode (default: binary)"
+    )]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub collation: Option<String>,
+
+    #[arg(
+        long,
+        help = "Reject values in data: that are not already of their column's type, instead of converting them"
+    )]
+    #[serde(default)]
+    pub strict_types: bool,
+
+    #[arg(
+        long,
+        help = "Apply INSERT, UPDATE and DELETE to the data every connection sees (default: each connection only sees its own changes)"
+    )]
+    #[serde(default)]
+    pub mutable: bool,
+
+    #[arg(
+        long,
+        requires = "mutable",
+        help = "Write changes made in --mutable mode back to the YAML file"
+    )]
+    #[serde(default)]
+    pub persist: bool,
+
+    #[arg(
+        long,
+        value_name = "FILE",
+        requires = "tls_key",
+        help = "PEM certificate chain for TLS connections (default: a self-signed certificate for localhost)"
+    )]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls_cert: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "FILE",
+        requires = "tls_cert",
+        help = "PEM private key of --tls-cert"
+    )]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls_key: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "PEM certificates of the CAs that sign client certificates; a client whose certificate's common name is the user it logs in as needs no password"
+    )]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls_client_ca: Option<PathBuf>,
+
+    #[arg(
+        long,
+        requires = "tls_client_ca",
+        help = "Refuse clients that do not log in with a certificate signed by --tls-client-ca"
+    )]
+    #[serde(default)]
+    pub tls_require_client_cert: bool,
+
+    #[arg(
+        long,
+        help = "Expect every connection to start with a HAProxy PROXY protocol v2 header, and take the client address from it"
+    )]
+    #[serde(default)]
+    pub proxy_protocol: bool,
+
+    #[arg(
+        long,
+        value_name = "N",
+        help = "Most connections served at once; more clients are refused with their protocol's too-many-connections error (default: 1000)"
+    )]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub max_connections: Option<usize>,
 
+    #[arg(
+        long,
+        value_name = "DURATION",
+        value_parser = humantime::parse_duration,
+        help = "Close connections that have not finished their handshake and login in this time, e.g. 10s (default: 30s)"
+    )]
+    #[serde(default, alias = "connection_timeout", with = "humantime_serde")]
     #[serde(skip_serializing_if = "Option::is_none")]
-    #[serde(with = "humantime_serde")]
-    #[clap(skip)]
-    pub connection_timeout: Option<Duration>,
+    pub connect_timeout: Option<Duration>,
 
+    #[arg(
+        long,
+        value_name = "DURATION",
+        value_parser = humantime::parse_duration,
+        help = "Close sessions that send no statement for this long, e.g. 5min (default: never)"
+    )]
+    #[serde(default, with = "humantime_serde")]
     #[serde(skip_serializing_if = "Option::is_none")]
-    #[serde(with = "humantime_serde")]
-    #[clap(skip)]
     pub idle_timeout: Option<Duration>,
 
+    #[arg(
+        long,
+        value_name = "DURATION",
+        value_parser = humantime::parse_duration,
+        help = "Cancel statements that run longer than this, 0s for no limit; sessions can override it with SET statement_timeout (default: 60s)"
+    )]
+    #[serde(default, with = "humantime_serde")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub statement_timeout: Option<Duration>,
+
+    #[arg(
+        long,
+        value_name = "N",
+        help = "Statements each client may run a second; more are refused with a rate limit error"
+    )]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rate_limit_qps: Option<u32>,
+
+    #[arg(
+        long,
+        value_name = "N",
+        help = "Result rows each client may read a minute; past them its statements are refused until the quota refills"
+    )]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rate_limit_rows: Option<u64>,
+
+    #[arg(
+        long,
+        value_enum,
+        value_name = "KEY",
+        default_value = "ip",
+        help = "Whom --rate-limit-qps and --rate-limit-rows apply to: each client address (ip) or each user"
+    )]
+    #[serde(default)]
+    pub rate_limit_by: RateLimitKey,
+
+    // Connection management settings (not exposed via CLI - configured via YAML)
     #[serde(default)]
     #[clap(skip)]
     pub enable_keepalive: bool,
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, clap::ValueEnum)]
+/// Listeners for several protocols at once, serving the same databases. When any is given
+/// they replace the single listener of `--protocol` and `--port`.
+#[derive(Debug, Clone, Default, clap::Args, Serialize, Deserialize)]
+pub struct ListenerPorts {
+    #[arg(
+        long,
+        value_name = "PORT",
+        conflicts_with_all = ["port", "protocol"],
+        help = "Serve PostgreSQL clients on this port, besides the other --<protocol>-port listeners"
+    )]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub postgres_port: Option<u16>,
+
+    #[arg(
+        long,
+        value_name = "PORT",
+        conflicts_with_all = ["port", "protocol"],
+        help = "Serve MySQL clients on this port, besides the other --<protocol>-port listeners"
+    )]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mysql_port: Option<u16>,
+
+    #[arg(
+        long,
+        value_name = "PORT",
+        conflicts_with_all = ["port", "protocol"],
+        help = "Serve SQL Server clients on this port, besides the other --<protocol>-port listeners"
+    )]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sqlserver_port: Option<u16>,
+
+    #[arg(
+        long,
+        value_name = "PORT",
+        conflicts_with_all = ["port", "protocol"],
+        help = "Serve WebSocket queries on this port, besides the other --<protocol>-port listeners"
+    )]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub http_port: Option<u16>,
+
+    #[arg(
+        long,
+        value_name = "PORT",
+        conflicts_with_all = ["port", "protocol"],
+        help = "Serve the gRPC service on this port, besides the other --<protocol>-port listeners"
+    )]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub grpc_port: Option<u16>,
+}
+
+#[derive(Debug, Clone, clap::Subcommand)]
+pub enum Command {
+    /// Check database files for errors, without serving them; exits nonzero on problems
+    Validate {
+        #[arg(
+            short = 'f',
+            long = "file",
+            value_name = "FILE",
+            required = true,
+            help = "YAML database file or directory to check; may be repeated"
+        )]
+        files: Vec<PathBuf>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum AuthMethod {
+    /// SCRAM-SHA-256, so the password never crosses the connection
+    #[default]
+    #[value(name = "scram-sha-256")]
+    #[serde(rename = "scram-sha-256")]
+    ScramSha256,
+    /// The password in clear text, as some older clients need
+    Password,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
 pub enum Protocol {
     Postgres,
     Mysql,
     Sqlserver,
+    /// Queries and reload events over WebSocket
+    Http,
+    /// The gRPC query service of proto/yamlbase.proto
+    Grpc,
+}
+
+/// What the clients that rate limits apply to are told apart by
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum RateLimitKey {
+    /// The address a client connects from
+    #[default]
+    Ip,
+    /// The user a client logs in as
+    User,
 }
 
 impl Config {
@@ -99,9 +412,114 @@ impl Config {
             Protocol::Postgres => 5432,
             Protocol::Mysql => 3306,
             Protocol::Sqlserver => 1433,
+            Protocol::Http => 8080,
+            Protocol::Grpc => 50051,
         })
     }
 
+    /// The protocols served and the port of each: those of the `--<protocol>-port` options,
+    /// or else `--protocol` on `--port`
+    pub fn listeners(&self) -> Vec<(Protocol, u16)> {
+        let ports = &self.listener_ports;
+        let listeners: Vec<(Protocol, u16)> = [
+            (Protocol::Postgres, ports.postgres_port),
+            (Protocol::Mysql, ports.mysql_port),
+            (Protocol::Sqlserver, ports.sqlserver_port),
+            (Protocol::Http, ports.http_port),
+            (Protocol::Grpc, ports.grpc_port),
+        ]
+        .into_iter()
+        .filter_map(|(protocol, port)| Some((protocol, port?)))
+        .collect();
+        if listeners.is_empty() {
+            vec![(self.protocol, self.effective_port())]
+        } else {
+            listeners
+        }
+    }
+
+    /// The password of a user that may connect, and the grants that limit it if any
+    pub fn user(&self, username: &str) -> Option<(Password<'_>, Option<&Grants>)> {
+        if username == self.username {
+            let password = Password::new(&self.password, self.password_hash.as_deref());
+            return Some((password, None));
+        }
+        self.users
+            .iter()
+            .find(|user| user.username == username)
+            .map(|user| {
+                let password = Password::new(&user.password, user.password_hash.as_deref());
+                (password, user.grants.as_ref())
+            })
+    }
+
+    /// How many connections are served at once
+    pub fn max_connections(&self) -> usize {
+        self.max_connections.unwrap_or(1000)
+    }
+
+    /// How long a connection may take to finish its handshake and log in
+    pub fn connect_timeout(&self) -> Duration {
+        self.connect_timeout.unwrap_or(Duration::from_secs(30))
+    }
+
+    /// How long a statement may run before it is cancelled, zero for no limit
+    pub fn statement_timeout(&self) -> Duration {
+        self.statement_timeout.unwrap_or(Duration::from_secs(60))
+    }
+
+    /// Recursive CTE limits for new sessions, from the command line or the defaults
+    pub fn recursion_limits(&self) -> RecursionLimits {
+        let defaults = RecursionLimits::default();
+        RecursionLimits {
+            max_iterations: self
+                .cte_max_recursion_depth
+                .unwrap_or(defaults.max_iterations),
+            max_rows: self.cte_max_rows.unwrap_or(defaults.max_rows),
+        }
+    }
+
+    /// Collation for columns that do not declare their own
+    pub fn collation(&self) -> crate::Result<Collation> {
+        match &self.collation {
+            Some(name) => Collation::parse(name)
+                .map_err(|e| crate::YamlBaseError::Config(format!("--collation: {}", e))),
+            None => Ok(Collation::default()),
+        }
+    }
+
+    /// Set the server-wide defaults that apply while files are loaded: the time zone of
+    /// TIMESTAMPTZ values and how values of `data:` are converted
+    pub fn set_load_defaults(&self) -> crate::Result<()> {
+        if let Some(name) = &self.timezone {
+            let zone = TimeZone::parse(name)
+                .map_err(|e| crate::YamlBaseError::Config(format!("--timezone: {}", e)))?;
+            info!("Using default time zone {}", zone.name());
+            TimeZone::set_default(zone);
+        }
+        if self.strict_types {
+            info!("Values of data: must be of their column's type");
+            Coercion::set_default(Coercion::Strict);
+        }
+        Ok(())
+    }
+
+    /// How often a database loaded from a URL is fetched again for hot reload
+    pub fn reload_interval(&self) -> Duration {
+        Duration::from_secs(self.reload_interval.unwrap_or(30).max(1))
+    }
+
+    /// Where the writes of new sessions go, for the database loaded from `file`
+    pub fn write_mode(&self, file: &Path) -> WriteMode {
+        if self.persist {
+            WriteMode::Persistent(file.to_path_buf())
+        } else if self.mutable {
+            WriteMode::Shared
+        } else {
+            WriteMode::Session
+        }
+    }
+
     pub fn init_logging(&self) -> anyhow::Result<()> {
         let log_level = if self.verbose {
             "debug"
@@ -123,3 +541,206 @@ impl Config {
         Ok(())
     }
 }
+
+// Every option can also be given as an environment variable named after it, as
+// YAMLBASE_STATEMENT_TIMEOUT=5s for --statement-timeout, and settings can come from a TOML
+// file, given with --config or found as yamlbase.toml in the working directory, and from a
+// `server:` section of the first database file. The keys of the files are the names of the
+// options, as in `statement_timeout = "5s"` or `postgres_port = 5433`, and they can also list
+// `users`. The command line takes precedence over the environment, which takes precedence
+// over the TOML file, and that over the `server:` section.
+
+/// The prefix of the environment variables that options can be given in
+pub const ENV_PREFIX: &str = "YAMLBASE_";
+
+/// The settings file read when --config is not given, if it exists
+pub const DEFAULT_CONFIG_FILE: &str = "yamlbase.toml";
+
+/// Settings that are paths, taken relative to the file that sets them
+const PATH_SETTINGS: &[&str] = &["files", "tls_cert", "tls_key", "tls_client_ca"];
+
+/// Settings that only files can give
+const FILE_ONLY_SETTINGS: &[&str] = &["users", "password_hash", "enable_keepalive"];
+
+/// Settings that choose the listeners. When the command line or the environment gives any
+/// of them, the listeners of the files are ignored.
+const LISTENER_SETTINGS: &[&str] = &[
+    "port",
+    "protocol",
+    "postgres_port",
+    "mysql_port",
+    "sqlserver_port",
+    "http_port",
+    "grpc_port",
+];
+
+type Settings = serde_json::Map<String, serde_json::Value>;
+
+impl Config {
+    /// The settings of the command line and the environment, completed by those of the
+    /// settings files. Exits with clap's message when the command line is not valid.
+    pub fn load() -> crate::Result<Self> {
+        Self::from_matches(Self::command_line().get_matches())
+    }
+
+    /// The settings of the command line `args` and the environment, completed by those of
+    /// the settings files
+    pub fn try_load_from<I, T>(args: I) -> crate::Result<Self>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<OsString> + Clone,
+    {
+        let matches = Self::command_line()
+            .try_get_matches_from(args)
+            .map_err(|e| crate::YamlBaseError::Config(e.to_string()))?;
+        Self::from_matches(matches)
+    }
+
+    /// The options of `Config`, each of which can also be given in the environment
+    fn command_line() -> clap::Command {
+        Self::command().mut_args(|arg| {
+            let Some(long) = arg.get_long() else {
+                return arg;
+            };
+            let name = format!("{}{}", ENV_PREFIX, long.replace('-', "_").to_uppercase());
+            // Keep the password out of --help
+            let hide_value = long == "password";
+            arg.env(name).hide_env_values(hide_value)
+        })
+    }
+
+    fn from_matches(matches: ArgMatches) -> crate::Result<Self> {
+        let mut config = Self::from_arg_matches(&matches)
+            .map_err(|e| crate::YamlBaseError::Config(e.to_string()))?;
+        let mut given: HashSet<String> = matches
+            .ids()
+            .map(|id| id.as_str())
+            .filter(|id| {
+                matches!(
+                    matches.value_source(id),
+                    Some(ValueSource::CommandLine | ValueSource::EnvVariable)
+                )
+            })
+            .map(str::to_string)
+            .collect();
+        if LISTENER_SETTINGS.iter().any(|key| given.contains(*key)) {
+            given.extend(LISTENER_SETTINGS.iter().map(|key| key.to_string()));
+        }
+
+        let default_file = Path::new(DEFAULT_CONFIG_FILE);
+        let toml_file = match &config.config {
+            Some(file) => Some(file.clone()),
+            None if default_file.is_file() => Some(default_file.to_path_buf()),
+            None => None,
+        };
+        if let Some(file) = toml_file {
+            let settings = read_toml_settings(&file)?;
+            config.apply_settings(settings, &file, &mut given)?;
+        }
+        if let Some(file) = config.files.first().cloned()
+            && let Some(settings) = read_yaml_settings(&file)?
+        {
+            config.apply_settings(settings, &file, &mut given)?;
+        }
+
+        if config.files.is_empty() && config.command.is_none() {
+            return Err(crate::YamlBaseError::Config(
+                "No database file: give one with -f or as `files` in the settings file".to_string(),
+            ));
+        }
+        Ok(config)
+    }
+
+    /// Take the settings of `file` that are not in `given`, and add them to it
+    fn apply_settings(
+        &mut self,
+        settings: Settings,
+        file: &Path,
+        given: &mut HashSet<String>,
+    ) -> crate::Result<()> {
+        let error = |message: String| {
+            crate::YamlBaseError::Config(format!("{}: {}", file.display(), message))
+        };
+        let known: HashSet<String> = Self::command()
+            .get_arguments()
+            .map(|arg| arg.get_id().to_string())
+            .filter(|id| !matches!(id.as_str(), "help" | "version" | "config"))
+            .chain(FILE_ONLY_SETTINGS.iter().map(|key| key.to_string()))
+            .collect();
+        let directory = file.parent().unwrap_or(Path::new(""));
+
+        let mut merged = match serde_json::to_value(&*self) {
+            Ok(serde_json::Value::Object(merged)) => merged,
+            _ => return Err(error("cannot merge settings".to_string())),
+        };
+        let mut taken = Vec::new();
+        for (key, mut value) in settings {
+            let key = key.replace('-', "_");
+            if !known.contains(&key) {
+                return Err(error(format!("unknown setting `{}`", key)));
+            }
+            if given.contains(&key) {
+                continue;
+            }
+            if PATH_SETTINGS.contains(&key.as_str()) {
+                relative_to(directory, &mut value);
+            }
+            merged.insert(key.clone(), value);
+            taken.push(key);
+        }
+
+        let mut config: Config = serde_json::from_value(serde_json::Value::Object(merged))
+            .map_err(|e| error(e.to_string()))?;
+        config.command = self.command.take();
+        config.config = self.config.take();
+        *self = config;
+        given.extend(taken);
+        Ok(())
+    }
+}
+
+fn read_toml_settings(file: &Path) -> crate::Result<Settings> {
+    let error =
+        |message: String| crate::YamlBaseError::Config(format!("{}: {}", file.display(), message));
+    let content = std::fs::read_to_string(file).map_err(|e| error(e.to_string()))?;
+    let table: toml::Table = toml::from_str(&content).map_err(|e| error(e.to_string()))?;
+    serde_json::to_value(table)
+        .ok()
+        .and_then(|value| match value {
+            serde_json::Value::Object(settings) => Some(settings),
+            _ => None,
+        })
+        .ok_or_else(|| error("not a table of settings".to_string()))
+}
+
+/// The `server:` section of a local database file, read without going past it so that a
+/// large file is not loaded whole
+fn read_yaml_settings(file: &Path) -> crate::Result<Option<Settings>> {
+    if is_url(file) || !file.is_file() {
+        return Ok(None);
+    }
+    let server = match crate::yaml::read_top_entry(file, "server")? {
+        None | Some(serde_yaml::Value::Null) => return Ok(None),
+        Some(server) => server,
+    };
+    serde_yaml::from_value(server)
+        .map(Some)
+        .map_err(|e| crate::YamlBaseError::Config(format!("{}: server: {}", file.display(), e)))
+}
+
+/// Make the relative paths of a setting relative to `directory`
+fn relative_to(directory: &Path, value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::String(path)
+            if !is_url(Path::new(path.as_str())) && Path::new(path.as_str()).is_relative() =>
+        {
+            *path = directory.join(path.as_str()).to_string_lossy().into_owned();
+        }
+        serde_json::Value::Array(paths) => {
+            for path in paths {
+                relative_to(directory, path);
+            }
+        }
+        _ => {}
+    }
+}
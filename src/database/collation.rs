@@ -0,0 +1,123 @@
+// Collations decide how text values compare. `binary` compares code points like
+// PostgreSQL's C collation; the case-insensitive collations give MySQL's `_ci` behaviour,
+// so that `name = 'alice'` also matches 'Alice'.
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::fmt;
+
+use crate::YamlBaseError;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Collation {
+    /// Compare code points, case-sensitively
+    #[default]
+    Binary,
+    /// Ignore the case of ASCII letters only
+    CiAscii,
+    /// Ignore case using Unicode lowercase mappings
+    CiUnicode,
+}
+
+impl Collation {
+    /// Parse a collation name. MySQL names ending in `_ci` and `_bin` map to the
+    /// case-insensitive and binary collations.
+    pub fn parse(name: &str) -> crate::Result<Self> {
+        let lower = name.trim().to_ascii_lowercase();
+        match lower.as_str() {
+            "binary" | "c" | "posix" | "ucs_basic" => Ok(Self::Binary),
+            "ci_ascii" | "ascii_general_ci" => Ok(Self::CiAscii),
+            "ci_unicode" => Ok(Self::CiUnicode),
+            _ if lower.ends_with("_bin") => Ok(Self::Binary),
+            _ if lower.ends_with("_ci") => Ok(Self::CiUnicode),
+            _ => Err(YamlBaseError::Database {
+                message: format!(
+                    "Unknown collation '{}' (expected binary, ci_ascii or ci_unicode)",
+                    name
+                ),
+            }),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Binary => "binary",
+            Self::CiAscii => "ci_ascii",
+            Self::CiUnicode => "ci_unicode",
+        }
+    }
+
+    pub fn is_case_insensitive(&self) -> bool {
+        *self != Self::Binary
+    }
+
+    /// The text as this collation sees it, so that texts which compare equal fold alike.
+    /// GROUP BY and DISTINCT use it as the key of a value.
+    pub fn fold<'a>(&self, s: &'a str) -> Cow<'a, str> {
+        match self {
+            Self::Binary => Cow::Borrowed(s),
+            Self::CiAscii => Cow::Owned(s.to_ascii_lowercase()),
+            Self::CiUnicode => Cow::Owned(s.chars().flat_map(char::to_lowercase).collect()),
+        }
+    }
+
+    pub fn compare(&self, a: &str, b: &str) -> Ordering {
+        match self {
+            Self::Binary => a.cmp(b),
+            Self::CiAscii => a
+                .bytes()
+                .map(|c| c.to_ascii_lowercase())
+                .cmp(b.bytes().map(|c| c.to_ascii_lowercase())),
+            Self::CiUnicode => a
+                .chars()
+                .flat_map(char::to_lowercase)
+                .cmp(b.chars().flat_map(char::to_lowercase)),
+        }
+    }
+}
+
+impl fmt::Display for Collation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Collation::parse("CI_ASCII").unwrap(), Collation::CiAscii);
+        assert_eq!(
+            Collation::parse("utf8mb4_0900_ai_ci").unwrap(),
+            Collation::CiUnicode
+        );
+        assert_eq!(Collation::parse("utf8mb4_bin").unwrap(), Collation::Binary);
+        assert!(Collation::parse("klingon").is_err());
+    }
+
+    #[test]
+    fn test_compare() {
+        assert_eq!(
+            Collation::Binary.compare("alice", "Alice"),
+            Ordering::Greater
+        );
+        assert_eq!(
+            Collation::CiAscii.compare("alice", "ALICE"),
+            Ordering::Equal
+        );
+        assert_eq!(Collation::CiAscii.compare("Émile", "émile"), Ordering::Less);
+        assert_eq!(
+            Collation::CiUnicode.compare("Émile", "émile"),
+            Ordering::Equal
+        );
+        assert_eq!(Collation::CiUnicode.compare("b", "A"), Ordering::Greater);
+    }
+
+    #[test]
+    fn test_fold() {
+        assert_eq!(Collation::Binary.fold("Alice"), "Alice");
+        assert_eq!(Collation::CiAscii.fold("ÉMILE Zola"), "Émile zola");
+        assert_eq!(Collation::CiUnicode.fold("ÉMILE Zola"), "émile zola");
+    }
+}
@@ -0,0 +1,64 @@
+use std::sync::Arc;
+
+use crate::database::Storage;
+
+/// The databases one server serves. A connection uses the first one until it names
+/// another, with the Postgres startup `database` parameter or MySQL's `USE`.
+#[derive(Clone)]
+pub struct Databases {
+    storages: Vec<Arc<Storage>>,
+}
+
+impl Databases {
+    /// Serve these databases, the first being the default. There must be at least one.
+    pub fn new(storages: Vec<Arc<Storage>>) -> Self {
+        assert!(!storages.is_empty(), "a server needs a database");
+        Self { storages }
+    }
+
+    /// The database at a position, 0 being the default; the same position as the file it
+    /// was loaded from has in `Config::files`
+    pub fn get(&self, index: usize) -> &Arc<Storage> {
+        &self.storages[index]
+    }
+
+    /// The position of the database with this name, ignoring case. A server with a single
+    /// database answers to any name, as clients often pass one that does not matter to them.
+    pub fn find(&self, name: &str) -> Option<usize> {
+        self.storages
+            .iter()
+            .position(|storage| storage.name().eq_ignore_ascii_case(name))
+            .or((self.storages.len() == 1).then_some(0))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Arc<Storage>> {
+        self.storages.iter()
+    }
+}
+
+impl From<Arc<Storage>> for Databases {
+    fn from(storage: Arc<Storage>) -> Self {
+        Self::new(vec![storage])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Database;
+
+    fn storage(name: &str) -> Arc<Storage> {
+        Arc::new(Storage::new(Database::new(name.to_string())))
+    }
+
+    #[tokio::test]
+    async fn test_find_by_name() {
+        let databases = Databases::new(vec![storage("sales"), storage("hr")]);
+        assert_eq!(databases.get(0).name(), "sales");
+        assert_eq!(databases.find("HR"), Some(1));
+        assert_eq!(databases.find("finance"), None);
+
+        let single = Databases::from(storage("sales"));
+        assert_eq!(single.find("anything"), Some(0));
+    }
+}
@@ -1,6 +1,16 @@
+pub mod collation;
+pub mod databases;
 pub mod index;
+pub mod overlay;
 pub mod schema;
 pub mod storage;
+pub mod timezone;
 
-pub use schema::{Column, Database, Table, Value};
-pub use storage::Storage;
+pub use collation::Collation;
+pub use databases::Databases;
+pub use overlay::{OverlayTables, SessionOverlay};
+pub use schema::{AutoIncrement, CheckConstraint, Column, ComputedColumn, Database, Table, Value};
+pub use storage::{
+    Notification, RELOAD_CHANNEL, ReloadOutcome, ReloadRequest, ReloadStatus, Storage, TableChanges,
+};
+pub use timezone::TimeZone;
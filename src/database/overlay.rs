@@ -0,0 +1,259 @@
+// Writes from a client connection go to its own overlay instead of the shared database, so
+// every connection starts from the YAML data and only sees the rows it changed itself.
+use indexmap::IndexMap;
+use std::sync::{Arc, Mutex};
+
+use crate::YamlBaseError;
+use crate::database::{Database, Table};
+
+#[derive(Debug, Clone, Default)]
+pub struct SessionOverlay {
+    tables: Arc<Mutex<OverlayTables>>,
+}
+
+/// The overlay's tables at some point, to go back to with `SessionOverlay::restore`
+#[derive(Debug, Clone, Default)]
+pub struct OverlayTables {
+    // Changed tables by their name in the shared database
+    changed: IndexMap<String, Arc<Table>>,
+    // Tables created by the connection, which hide shared tables of the same name
+    temporary: IndexMap<String, Arc<Table>>,
+}
+
+impl OverlayTables {
+    fn find(&self, table_name: &str) -> Option<(&String, &Arc<Table>)> {
+        self.temporary
+            .iter()
+            .chain(self.changed.iter())
+            .find(|(name, _)| name.eq_ignore_ascii_case(table_name))
+    }
+}
+
+impl SessionOverlay {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        let tables = self.tables.lock().unwrap();
+        tables.changed.is_empty() && tables.temporary.is_empty()
+    }
+
+    /// Whether the connection has changed or created the table
+    pub fn contains(&self, table_name: &str) -> bool {
+        self.tables.lock().unwrap().find(table_name).is_some()
+    }
+
+    /// Whether the table is one the connection created itself
+    pub fn is_temporary(&self, table_name: &str) -> bool {
+        self.tables
+            .lock()
+            .unwrap()
+            .temporary
+            .keys()
+            .any(|name| name.eq_ignore_ascii_case(table_name))
+    }
+
+    /// The table as this connection sees it: its own copy if it created or changed the
+    /// table, the shared table otherwise
+    pub fn table(&self, shared: &Database, table_name: &str) -> Option<Arc<Table>> {
+        let tables = self.tables.lock().unwrap();
+        tables
+            .find(table_name)
+            .or_else(|| {
+                shared
+                    .tables
+                    .iter()
+                    .find(|(name, _)| name.eq_ignore_ascii_case(table_name))
+            })
+            .map(|(_, table)| Arc::clone(table))
+    }
+
+    /// The shared database with this connection's tables in place of the shared ones, or
+    /// `None` when it has none
+    pub fn apply(&self, shared: &Database) -> Option<Database> {
+        let tables = self.tables.lock().unwrap();
+        if tables.changed.is_empty() && tables.temporary.is_empty() {
+            return None;
+        }
+
+        let mut database = shared.clone();
+        for (name, table) in tables.changed.iter() {
+            database.tables.insert(name.clone(), Arc::clone(table));
+        }
+        for (name, table) in tables.temporary.iter() {
+            database
+                .tables
+                .retain(|shared_name, _| !shared_name.eq_ignore_ascii_case(name));
+            database.tables.insert(name.clone(), Arc::clone(table));
+        }
+        Some(database)
+    }
+
+    /// Change a table for this connection only. The first change copies the table out of
+    /// the shared database; `change` should validate before it modifies the table, so a
+    /// failed statement leaves no partial changes behind.
+    pub fn update<T>(
+        &self,
+        shared: &Database,
+        table_name: &str,
+        change: impl FnOnce(&mut Table) -> crate::Result<T>,
+    ) -> crate::Result<T> {
+        let mut guard = self.tables.lock().unwrap();
+        let tables = &mut *guard;
+
+        if let Some(table) = tables
+            .temporary
+            .iter_mut()
+            .find(|(name, _)| name.eq_ignore_ascii_case(table_name))
+            .map(|(_, table)| table)
+        {
+            return change(Arc::make_mut(table));
+        }
+
+        let name = match tables
+            .changed
+            .keys()
+            .find(|name| name.eq_ignore_ascii_case(table_name))
+        {
+            Some(name) => name.clone(),
+            None => {
+                if let Some(view_name) = shared.materialized_view_name(table_name) {
+                    return Err(YamlBaseError::Database {
+                        message: format!(
+                            "Materialized view '{}' cannot be changed, use REFRESH MATERIALIZED \
+                             VIEW to recompute it",
+                            view_name
+                        ),
+                    });
+                }
+                let table =
+                    shared
+                        .get_table(table_name)
+                        .ok_or_else(|| YamlBaseError::Database {
+                            message: format!("Table '{}' not found", table_name),
+                        })?;
+                tables
+                    .changed
+                    .insert(table.name.clone(), Arc::new(table.clone()));
+                table.name.clone()
+            }
+        };
+
+        change(Arc::make_mut(tables.changed.get_mut(&name).unwrap()))
+    }
+
+    /// Add a table that only this connection sees, in place of any table of that name
+    pub fn create_temporary(&self, table: Table) {
+        let mut tables = self.tables.lock().unwrap();
+        tables
+            .temporary
+            .retain(|name, _| !name.eq_ignore_ascii_case(&table.name));
+        tables.temporary.insert(table.name.clone(), Arc::new(table));
+    }
+
+    /// Drop a table the connection created; false if there is no such table
+    pub fn drop_temporary(&self, table_name: &str) -> bool {
+        let mut tables = self.tables.lock().unwrap();
+        let before = tables.temporary.len();
+        tables
+            .temporary
+            .retain(|name, _| !name.eq_ignore_ascii_case(table_name));
+        tables.temporary.len() < before
+    }
+
+    /// The tables as they are now, to go back to with `restore`. Tables are only copied
+    /// again when they are next changed.
+    pub fn snapshot(&self) -> OverlayTables {
+        self.tables.lock().unwrap().clone()
+    }
+
+    /// Undo every change made since `snapshot` was taken
+    pub fn restore(&self, snapshot: OverlayTables) {
+        *self.tables.lock().unwrap() = snapshot;
+    }
+
+    /// Remove the changed shared tables from the overlay, to apply them to the shared
+    /// database. Tables the connection created stay.
+    pub fn take(&self) -> IndexMap<String, Arc<Table>> {
+        std::mem::take(&mut self.tables.lock().unwrap().changed)
+    }
+
+    /// Forget every change, going back to the shared data
+    pub fn clear(&self) {
+        *self.tables.lock().unwrap() = OverlayTables::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{Column, Value};
+    use crate::yaml::schema::SqlType;
+
+    fn database() -> Database {
+        let mut table = Table::new(
+            "items".to_string(),
+            vec![Column {
+                name: "id".to_string(),
+                sql_type: SqlType::Integer,
+                primary_key: true,
+                nullable: false,
+                unique: true,
+                default: None,
+                references: None,
+                collation: None,
+            }],
+        );
+        table.insert_row(vec![Value::Integer(1)]).unwrap();
+
+        let mut database = Database::new("test_db".to_string());
+        database.add_table(table).unwrap();
+        database
+    }
+
+    #[test]
+    fn test_changes_stay_in_the_overlay() {
+        let shared = database();
+        let overlay = SessionOverlay::new();
+        assert!(overlay.apply(&shared).is_none());
+
+        overlay
+            .update(&shared, "ITEMS", |table| {
+                table.insert_row(vec![Value::Integer(2)])
+            })
+            .unwrap();
+        assert!(overlay.contains("items"));
+        assert_eq!(overlay.table(&shared, "Items").unwrap().rows.len(), 2);
+
+        let session = overlay.apply(&shared).unwrap();
+        assert_eq!(session.get_table("items").unwrap().rows.len(), 2);
+        assert_eq!(shared.get_table("items").unwrap().rows.len(), 1);
+
+        let snapshot = overlay.snapshot();
+        overlay
+            .update(&shared, "items", |table| {
+                table.insert_row(vec![Value::Integer(3)])
+            })
+            .unwrap();
+        overlay.restore(snapshot);
+        assert_eq!(overlay.table(&shared, "items").unwrap().rows.len(), 2);
+
+        // Clones of an overlay belong to the same connection
+        assert!(overlay.clone().contains("items"));
+        overlay.clear();
+        assert!(overlay.is_empty());
+        assert!(overlay.update(&shared, "missing", |_| Ok(())).is_err());
+
+        // A created table hides the shared one and survives taking the changes
+        overlay.create_temporary(Table::new("Items".to_string(), vec![]));
+        assert!(overlay.is_temporary("items"));
+        let session = overlay.apply(&shared).unwrap();
+        assert_eq!(session.tables.len(), 1);
+        assert!(session.get_table("items").unwrap().columns.is_empty());
+        assert!(overlay.take().is_empty());
+        assert!(overlay.drop_temporary("ITEMS"));
+        assert!(!overlay.drop_temporary("items"));
+        assert!(overlay.is_empty());
+    }
+}
@@ -1,27 +1,72 @@
-use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime};
 use indexmap::IndexMap;
 use rust_decimal::Decimal;
 use serde_json::Value as JsonValue;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::Arc;
 use uuid::Uuid;
 
+use crate::database::collation::Collation;
+use crate::database::timezone::{
+    TimeZone, format_timestamptz, parse_naive_timestamp, parse_timestamptz,
+};
 use crate::yaml::schema::SqlType;
 
 #[derive(Debug, Clone)]
 pub struct Database {
     pub name: String,
-    pub tables: IndexMap<String, Table>,
+    /// Tables are shared between clones of the database and copied on first write
+    pub tables: IndexMap<String, Arc<Table>>,
+    /// The query of each materialized view, whose result is kept in `tables`
+    pub materialized_views: IndexMap<String, String>,
+    /// The YAML file each table was loaded from
+    pub table_files: HashMap<String, PathBuf>,
+    /// The file holding the rows of each table that declares a `data_file:`
+    pub data_files: HashMap<String, PathBuf>,
+    /// The tables with rows made by a `generate:` block, which are not written back
+    pub generated_tables: HashSet<String>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Table {
     pub name: String,
     pub columns: Vec<Column>,
     pub column_index: IndexMap<String, usize>,
     pub rows: Vec<Vec<Value>>,
     pub primary_key_index: Option<usize>,
+    pub auto_increment: Option<AutoIncrement>,
+    pub checks: Vec<CheckConstraint>,
+    /// Columns whose values are computed from the other columns of the row, in the order
+    /// they are computed
+    pub computed: Vec<ComputedColumn>,
 }
 
-#[derive(Debug, Clone)]
+/// A CHECK constraint: no row may make the expression false
+#[derive(Debug, Clone, PartialEq)]
+pub struct CheckConstraint {
+    /// The expression as it was written, for error messages
+    pub sql: String,
+    pub expr: sqlparser::ast::Expr,
+}
+
+/// A column whose value is an expression over the other columns of its row
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComputedColumn {
+    pub column: usize,
+    /// The expression as it was written, for error messages
+    pub sql: String,
+    pub expr: sqlparser::ast::Expr,
+}
+
+/// An AUTO_INCREMENT or SERIAL column and the last value it was given
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AutoIncrement {
+    pub column: usize,
+    pub last: i64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Column {
     pub name: String,
     pub sql_type: SqlType,
@@ -30,6 +75,8 @@ pub struct Column {
     pub unique: bool,
     pub default: Option<String>,
     pub references: Option<(String, String)>, // (table, column)
+    /// Collation for comparisons on this column; the server default applies when unset
+    pub collation: Option<Collation>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -42,10 +89,14 @@ pub enum Value {
     Text(String),
     Boolean(bool),
     Timestamp(NaiveDateTime),
+    /// An instant, kept with the offset of the zone it is displayed in
+    TimestampTz(DateTime<FixedOffset>),
     Date(NaiveDate),
     Time(NaiveTime),
     Uuid(Uuid),
     Json(JsonValue),
+    Bytes(Vec<u8>),
+    Array(Vec<Value>),
 }
 
 // Implement Eq manually, treating NaN values as equal
@@ -100,6 +151,19 @@ impl std::hash::Hash for Value {
                 11u8.hash(state);
                 j.to_string().hash(state);
             }
+            Value::TimestampTz(ts) => {
+                // Equal instants are equal whatever their offset
+                12u8.hash(state);
+                ts.naive_utc().hash(state);
+            }
+            Value::Bytes(b) => {
+                13u8.hash(state);
+                b.hash(state);
+            }
+            Value::Array(items) => {
+                14u8.hash(state);
+                items.hash(state);
+            }
         }
     }
 }
@@ -115,10 +179,84 @@ impl std::fmt::Display for Value {
             Value::Text(s) => write!(f, "{}", s),
             Value::Boolean(b) => write!(f, "{}", b),
             Value::Timestamp(ts) => write!(f, "{}", ts.format("%Y-%m-%d %H:%M:%S")),
+            Value::TimestampTz(ts) => write!(f, "{}", format_timestamptz(ts)),
             Value::Date(d) => write!(f, "{}", d.format("%Y-%m-%d")),
             Value::Time(t) => write!(f, "{}", t.format("%H:%M:%S")),
             Value::Uuid(u) => write!(f, "{}", u),
             Value::Json(j) => write!(f, "{}", j),
+            // As PostgreSQL shows bytea, in hex after `\x`
+            Value::Bytes(b) => write!(f, "\\x{}", hex::encode(b)),
+            // As PostgreSQL shows arrays, e.g. `{1,NULL,"a b"}`
+            Value::Array(items) => {
+                write!(f, "{{")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    match item {
+                        Value::Null | Value::Array(_) => write!(f, "{}", item)?,
+                        item => write!(f, "{}", array_element(&item.to_string()))?,
+                    }
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+/// An array element as PostgreSQL writes it, in double quotes when it would not read back
+fn array_element(text: &str) -> String {
+    let quoted = text.is_empty()
+        || text.eq_ignore_ascii_case("NULL")
+        || text
+            .chars()
+            .any(|c| matches!(c, '{' | '}' | ',' | '"' | '\\') || c.is_whitespace());
+    if quoted {
+        format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+    } else {
+        text.to_string()
+    }
+}
+
+/// The elements of an array written as PostgreSQL writes it, such as `{1,NULL,"a b"}`, with
+/// `None` for NULL
+pub(crate) fn parse_array_literal(text: &str) -> Option<Vec<Option<String>>> {
+    let inner = text.trim().strip_prefix('{')?.strip_suffix('}')?;
+    let mut elements = Vec::new();
+    if inner.trim().is_empty() {
+        return Some(elements);
+    }
+
+    let mut chars = inner.chars().peekable();
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        let element = if chars.next_if_eq(&'"').is_some() {
+            let mut element = String::new();
+            loop {
+                match chars.next()? {
+                    '"' => break,
+                    '\\' => element.push(chars.next()?),
+                    c => element.push(c),
+                }
+            }
+            while chars.next_if(|c| c.is_whitespace()).is_some() {}
+            Some(element)
+        } else {
+            let mut element = String::new();
+            while let Some(c) = chars.next_if(|&c| c != ',') {
+                if matches!(c, '{' | '}' | '"') {
+                    return None;
+                }
+                element.push(c);
+            }
+            let element = element.trim();
+            (!element.eq_ignore_ascii_case("NULL")).then(|| element.to_string())
+        };
+        elements.push(element);
+        match chars.next() {
+            Some(',') => {}
+            None => return Some(elements),
+            Some(_) => return None,
         }
     }
 }
@@ -128,6 +266,10 @@ impl Database {
         Self {
             name,
             tables: IndexMap::new(),
+            materialized_views: IndexMap::new(),
+            table_files: HashMap::new(),
+            data_files: HashMap::new(),
+            generated_tables: HashSet::new(),
         }
     }
 
@@ -137,10 +279,17 @@ impl Database {
                 message: format!("Table '{}' already exists", table.name),
             });
         }
-        self.tables.insert(table.name.clone(), table);
+        self.tables.insert(table.name.clone(), Arc::new(table));
         Ok(())
     }
 
+    /// The name a materialized view was declared with, if `name` is one
+    pub fn materialized_view_name(&self, name: &str) -> Option<&String> {
+        self.materialized_views
+            .keys()
+            .find(|view| view.eq_ignore_ascii_case(name))
+    }
+
     pub fn get_table(&self, name: &str) -> Option<&Table> {
         // First try exact match
         if let Some(table) = self.tables.get(name) {
@@ -160,7 +309,7 @@ impl Database {
     pub fn get_table_mut(&mut self, name: &str) -> Option<&mut Table> {
         // First try exact match
         if self.tables.contains_key(name) {
-            return self.tables.get_mut(name);
+            return self.tables.get_mut(name).map(Arc::make_mut);
         }
 
         // Fall back to case-insensitive search
@@ -169,7 +318,7 @@ impl Database {
             if table_name.to_lowercase() == name_lower {
                 // Need to clone the key to avoid borrow checker issues
                 let key = table_name.clone();
-                return self.tables.get_mut(&key);
+                return self.tables.get_mut(&key).map(Arc::make_mut);
             }
         }
         None
@@ -194,7 +343,54 @@ impl Table {
             column_index,
             rows: Vec::new(),
             primary_key_index,
+            auto_increment: None,
+            checks: Vec::new(),
+            computed: Vec::new(),
+        }
+    }
+
+    /// Whether the values of a column are computed
+    pub fn is_computed(&self, column: usize) -> bool {
+        self.computed
+            .iter()
+            .any(|computed| computed.column == column)
+    }
+
+    /// Generate the values of an integer column on INSERT, continuing after the largest
+    /// value the table holds
+    pub fn set_auto_increment(&mut self, column: usize) -> crate::Result<()> {
+        let definition = &self.columns[column];
+        if !matches!(definition.sql_type, SqlType::Integer | SqlType::BigInt) {
+            return Err(crate::YamlBaseError::Database {
+                message: format!(
+                    "Auto-increment column '{}' of table '{}' must be an integer",
+                    definition.name, self.name
+                ),
+            });
         }
+        if self
+            .auto_increment
+            .is_some_and(|auto_increment| auto_increment.column != column)
+        {
+            return Err(crate::YamlBaseError::Database {
+                message: format!(
+                    "Table '{}' has more than one auto-increment column",
+                    self.name
+                ),
+            });
+        }
+
+        let last = self
+            .rows
+            .iter()
+            .filter_map(|row| match row[column] {
+                Value::Integer(value) => Some(value),
+                _ => None,
+            })
+            .max()
+            .unwrap_or(0);
+        self.auto_increment = Some(AutoIncrement { column, last });
+        Ok(())
     }
 
     pub fn insert_row(&mut self, row: Vec<Value>) -> crate::Result<()> {
@@ -209,7 +405,7 @@ impl Table {
         }
 
         // Validate data types
-        for (value, column) in row.iter().zip(self.columns.iter()) {
+        for (index, (value, column)) in row.iter().zip(self.columns.iter()).enumerate() {
             if !value.is_compatible_with(&column.sql_type) {
                 return Err(crate::YamlBaseError::TypeConversion(format!(
                     "Value {:?} is not compatible with column '{}' of type {:?}",
@@ -217,7 +413,8 @@ impl Table {
                 )));
             }
 
-            if !column.nullable && matches!(value, Value::Null) {
+            // Computed columns are filled in once the row is complete
+            if !column.nullable && matches!(value, Value::Null) && !self.is_computed(index) {
                 return Err(crate::YamlBaseError::Database {
                     message: format!("Column '{}' cannot be NULL", column.name),
                 });
@@ -246,6 +443,19 @@ impl Table {
 }
 
 impl Value {
+    /// The instant of a TIMESTAMPTZ, or of a TIMESTAMP or DATE taken as wall-clock time in
+    /// the default time zone
+    pub fn as_timestamptz(&self) -> Option<DateTime<FixedOffset>> {
+        match self {
+            Value::TimestampTz(ts) => Some(*ts),
+            Value::Timestamp(ts) => Some(TimeZone::default_zone().from_local(ts)),
+            Value::Date(d) => {
+                Some(TimeZone::default_zone().from_local(&d.and_time(NaiveTime::MIN)))
+            }
+            _ => None,
+        }
+    }
+
     pub fn is_compatible_with(&self, sql_type: &SqlType) -> bool {
         matches!(
             (self, sql_type),
@@ -261,10 +471,16 @@ impl Value {
                 )
                 | (Value::Boolean(_), SqlType::Boolean)
                 | (Value::Timestamp(_), SqlType::Timestamp)
+                | (Value::TimestampTz(_), SqlType::TimestampTz)
                 | (Value::Date(_), SqlType::Date)
                 | (Value::Time(_), SqlType::Time)
                 | (Value::Uuid(_), SqlType::Uuid)
                 | (Value::Json(_), SqlType::Json)
+                | (Value::Bytes(_), SqlType::Blob)
+        ) || matches!(
+            (self, sql_type),
+            (Value::Array(items), SqlType::Array(element_type))
+                if items.iter().all(|item| item.is_compatible_with(element_type))
         )
     }
 
@@ -287,6 +503,29 @@ impl Value {
             (Value::Date(a), Value::Date(b)) => Some(a.cmp(b)),
             (Value::Time(a), Value::Time(b)) => Some(a.cmp(b)),
             (Value::Uuid(a), Value::Uuid(b)) => Some(a.cmp(b)),
+            (Value::Bytes(a), Value::Bytes(b)) => Some(a.cmp(b)),
+            (Value::Array(a), Value::Array(b)) => {
+                for (a, b) in a.iter().zip(b) {
+                    match a.compare(b)? {
+                        Ordering::Equal => {}
+                        ordering => return Some(ordering),
+                    }
+                }
+                Some(a.len().cmp(&b.len()))
+            }
+
+            // A date compares as midnight of that day, e.g. against `CURRENT_DATE - INTERVAL ...`
+            (Value::Date(a), Value::Timestamp(b)) => a.and_hms_opt(0, 0, 0).map(|a| a.cmp(b)),
+            (Value::Timestamp(a), Value::Date(b)) => b.and_hms_opt(0, 0, 0).map(|b| a.cmp(&b)),
+
+            // Timestamps without a zone are read in the default zone, as in PostgreSQL
+            (Value::TimestampTz(a), Value::TimestampTz(b)) => Some(a.cmp(b)),
+            (Value::TimestampTz(a), Value::Timestamp(_) | Value::Date(_)) => {
+                other.as_timestamptz().map(|b| a.cmp(&b))
+            }
+            (Value::Timestamp(_) | Value::Date(_), Value::TimestampTz(b)) => {
+                self.as_timestamptz().map(|a| a.cmp(b))
+            }
 
             // Handle cross-type numeric comparisons
             (Value::Integer(a), Value::Double(b)) => (*a as f64).partial_cmp(b),
@@ -320,6 +559,44 @@ impl Value {
                 Decimal::from_f32(*a).map(|a_decimal| a_decimal.cmp(b))
             }
 
+            // Text meeting a number or a date is read as one, e.g. `'10' > 9`
+            (Value::Text(_), _) => self.coerce_text_like(other)?.compare(other),
+            (_, Value::Text(_)) => self.compare(&other.coerce_text_like(self)?),
+
+            _ => None,
+        }
+    }
+
+    /// Text read as the kind of value `other` is, the implicit conversion comparisons
+    /// apply between a string and a number or date. `None` for other values, or text that
+    /// does not read as one.
+    pub fn coerce_text_like(&self, other: &Value) -> Option<Value> {
+        let Value::Text(text) = self else {
+            return None;
+        };
+        let text = text.trim();
+        match other {
+            Value::Integer(_) | Value::Float(_) | Value::Double(_) | Value::Decimal(_) => {
+                match text.parse::<i64>() {
+                    Ok(i) if matches!(other, Value::Integer(_)) => Some(Value::Integer(i)),
+                    _ => text
+                        .parse::<Decimal>()
+                        .map(Value::Decimal)
+                        .or_else(|_| text.parse::<f64>().map(Value::Double))
+                        .ok(),
+                }
+            }
+            Value::Date(_) => NaiveDate::parse_from_str(text, "%Y-%m-%d")
+                .map(Value::Date)
+                .ok()
+                .or_else(|| parse_naive_timestamp(text).map(Value::Timestamp)),
+            Value::Timestamp(_) => parse_naive_timestamp(text).map(Value::Timestamp),
+            Value::TimestampTz(_) => {
+                parse_timestamptz(text, &TimeZone::default_zone()).map(Value::TimestampTz)
+            }
+            Value::Time(_) => NaiveTime::parse_from_str(text, "%H:%M:%S%.f")
+                .map(Value::Time)
+                .ok(),
             _ => None,
         }
     }
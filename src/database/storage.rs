@@ -1,19 +1,48 @@
+use chrono::{Local, NaiveDateTime};
 use dashmap::DashMap;
-use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::fmt;
+use std::sync::{Arc, OnceLock};
+use tokio::sync::{Mutex, OwnedMutexGuard, RwLock, broadcast, mpsc, oneshot};
 
-use crate::database::{Database, Value};
+use crate::YamlBaseError;
+use crate::database::{Database, Table, Value};
 
 pub struct Storage {
+    name: String,
     database: Arc<RwLock<Database>>,
     primary_key_index: Arc<DashMap<String, DashMap<Value, usize>>>, // table -> pk_value -> row_idx
+    writer: Arc<Mutex<()>>,
+    reload_status: Arc<std::sync::Mutex<ReloadStatus>>,
+    reload_requests: Arc<OnceLock<mpsc::UnboundedSender<ReloadRequest>>>,
+    notifications: broadcast::Sender<Notification>,
+}
+
+/// A reload asked for with `Storage::request_reload`, answered with how it went
+pub type ReloadRequest = oneshot::Sender<ReloadStatus>;
+
+/// The channel every reload that is applied is announced on, with the changes as payload
+pub const RELOAD_CHANNEL: &str = "yamlbase_reload";
+
+/// A NOTIFY, for the connections of the database listening on its channel
+#[derive(Debug, Clone, PartialEq)]
+pub struct Notification {
+    pub channel: String,
+    pub payload: String,
 }
 
 impl Storage {
     pub fn new(database: Database) -> Self {
         let storage = Self {
+            name: database.name.clone(),
             database: Arc::new(RwLock::new(database)),
             primary_key_index: Arc::new(DashMap::new()),
+            writer: Arc::new(Mutex::new(())),
+            reload_status: Arc::new(std::sync::Mutex::new(ReloadStatus::new(
+                ReloadOutcome::Loaded,
+                String::new(),
+            ))),
+            reload_requests: Arc::new(OnceLock::new()),
+            notifications: broadcast::channel(256).0,
         };
 
         // Build initial indexes - try to spawn if in tokio context, otherwise do it synchronously
@@ -36,28 +65,155 @@ impl Storage {
         storage
     }
 
+    /// The name the database is served under, which a reload does not change
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
     pub fn database(&self) -> Arc<RwLock<Database>> {
         Arc::clone(&self.database)
     }
 
+    /// Serialize statements and transactions that change the shared database. The guard
+    /// is held from reading the rows to change until the new rows are in place, so
+    /// concurrent writers cannot overwrite each other's changes.
+    pub async fn lock_writes(&self) -> OwnedMutexGuard<()> {
+        Arc::clone(&self.writer).lock_owned().await
+    }
+
     pub async fn rebuild_indexes(&self) {
         let db = self.database.read().await;
 
         for (table_name, table) in &db.tables {
-            if let Some(pk_idx) = table.primary_key_index {
-                let table_index = self
-                    .primary_key_index
-                    .entry(table_name.clone())
-                    .or_default();
+            self.rebuild_table_index(table_name, table);
+        }
+    }
+
+    fn rebuild_table_index(&self, table_name: &str, table: &Table) {
+        if let Some(pk_idx) = table.primary_key_index {
+            let table_index = self
+                .primary_key_index
+                .entry(table_name.to_string())
+                .or_default();
+
+            table_index.clear();
 
-                table_index.clear();
+            for (row_idx, row) in table.rows.iter().enumerate() {
+                let pk_value = row[pk_idx].clone();
+                table_index.insert(pk_value, row_idx);
+            }
+        } else {
+            self.primary_key_index.remove(table_name);
+        }
+    }
 
-                for (row_idx, row) in table.rows.iter().enumerate() {
-                    let pk_value = row[pk_idx].clone();
-                    table_index.insert(pk_value, row_idx);
+    /// Replace the database with one loaded again. Tables that are the same as before are
+    /// kept, and only the indexes of the tables that changed are rebuilt. The tables are
+    /// compared with a snapshot, so queries are only held up while the new ones are swapped in.
+    pub async fn reload(&self, mut database: Database) -> TableChanges {
+        let snapshot = self.database.read().await.tables.clone();
+
+        let mut changes = TableChanges::default();
+        let mut kept = Vec::new();
+        for (name, table) in database.tables.iter_mut() {
+            match snapshot.get(name) {
+                Some(old) if Arc::ptr_eq(old, table) || old == table => {
+                    *table = Arc::clone(old);
+                    kept.push(name.clone());
                 }
+                Some(_) => changes.changed.push(name.clone()),
+                None => changes.added.push(name.clone()),
+            }
+        }
+
+        let mut db = self.database.write().await;
+        // A table written since the snapshot is replaced by its loaded version after all
+        for name in kept {
+            if !db
+                .tables
+                .get(&name)
+                .is_some_and(|current| Arc::ptr_eq(current, &database.tables[&name]))
+            {
+                changes.changed.push(name);
             }
         }
+        changes.removed = db
+            .tables
+            .keys()
+            .filter(|name| !database.tables.contains_key(*name))
+            .cloned()
+            .collect();
+
+        *db = database;
+        for name in &changes.removed {
+            self.primary_key_index.remove(name);
+        }
+        for name in changes.changed.iter().chain(&changes.added) {
+            self.rebuild_table_index(name, &db.tables[name]);
+        }
+        self.set_reload_status(ReloadStatus::new(
+            ReloadOutcome::Accepted,
+            changes.to_string(),
+        ));
+        drop(db);
+
+        // Listeners are told once the new data can be read
+        self.notify(RELOAD_CHANNEL, &changes.to_string());
+        changes
+    }
+
+    /// Send a notification to the connections listening on the channel. Nobody may be.
+    pub fn notify(&self, channel: &str, payload: &str) {
+        let _ = self.notifications.send(Notification {
+            channel: channel.to_string(),
+            payload: payload.to_string(),
+        });
+    }
+
+    /// Receive the notifications sent from now on, of every channel
+    pub fn subscribe(&self) -> broadcast::Receiver<Notification> {
+        self.notifications.subscribe()
+    }
+
+    /// Record a reload that was not applied, leaving the database as it is
+    pub fn reject_reload(&self, problems: &[String]) {
+        self.set_reload_status(ReloadStatus::new(
+            ReloadOutcome::Rejected,
+            problems.join("; "),
+        ));
+    }
+
+    /// Receive the reloads asked for with `request_reload`. Only the first call gets them.
+    pub fn reload_requests(&self) -> Option<mpsc::UnboundedReceiver<ReloadRequest>> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.reload_requests.set(tx).ok().map(|()| rx)
+    }
+
+    /// Load the database files again, whether or not they changed, and wait for the outcome
+    pub async fn request_reload(&self) -> crate::Result<ReloadStatus> {
+        let unavailable = || YamlBaseError::Database {
+            message: format!("Database '{}' cannot be reloaded", self.name),
+        };
+        let (tx, rx) = oneshot::channel();
+        let requests = self.reload_requests.get().ok_or_else(unavailable)?;
+        requests.send(tx).map_err(|_| unavailable())?;
+        rx.await.map_err(|_| unavailable())
+    }
+
+    /// How the last reload went, or when the database was first loaded if it was not
+    /// reloaded yet
+    pub fn reload_status(&self) -> ReloadStatus {
+        self.reload_status
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    fn set_reload_status(&self, status: ReloadStatus) {
+        *self
+            .reload_status
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = status;
     }
 
     pub async fn find_by_primary_key(
@@ -65,23 +221,98 @@ impl Storage {
         table_name: &str,
         pk_value: &Value,
     ) -> Option<Vec<Value>> {
-        if let Some(table_index) = self.primary_key_index.get(table_name) {
-            if let Some(row_idx) = table_index.get(pk_value) {
-                let db = self.database.read().await;
-                if let Some(table) = db.get_table(table_name) {
-                    return table.rows.get(*row_idx).cloned();
-                }
+        if let Some(table_index) = self.primary_key_index.get(table_name)
+            && let Some(row_idx) = table_index.get(pk_value)
+        {
+            let db = self.database.read().await;
+            if let Some(table) = db.get_table(table_name) {
+                return table.rows.get(*row_idx).cloned();
             }
         }
         None
     }
 }
 
+/// The tables a reload changed, added and removed
+#[derive(Debug, Default, PartialEq)]
+pub struct TableChanges {
+    pub changed: Vec<String>,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+impl TableChanges {
+    pub fn is_empty(&self) -> bool {
+        self.changed.is_empty() && self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+impl fmt::Display for TableChanges {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "no tables changed");
+        }
+        let kinds = [
+            ("changed", &self.changed),
+            ("added", &self.added),
+            ("removed", &self.removed),
+        ];
+        let parts: Vec<String> = kinds
+            .iter()
+            .filter(|(_, names)| !names.is_empty())
+            .map(|(kind, names)| format!("{} {}", kind, names.join(", ")))
+            .collect();
+        write!(f, "tables {}", parts.join("; "))
+    }
+}
+
+/// Whether the database was loaded at startup, or reloaded with or without the change
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReloadOutcome {
+    Loaded,
+    Accepted,
+    Rejected,
+}
+
+impl fmt::Display for ReloadOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReloadOutcome::Loaded => write!(f, "loaded"),
+            ReloadOutcome::Accepted => write!(f, "reloaded"),
+            ReloadOutcome::Rejected => write!(f, "rejected, keeping previous snapshot"),
+        }
+    }
+}
+
+/// The outcome of the last load of the database, with the tables it changed or the
+/// problems that rejected it
+#[derive(Debug, Clone)]
+pub struct ReloadStatus {
+    pub outcome: ReloadOutcome,
+    pub at: NaiveDateTime,
+    pub detail: String,
+}
+
+impl ReloadStatus {
+    fn new(outcome: ReloadOutcome, detail: String) -> Self {
+        Self {
+            outcome,
+            at: Local::now().naive_local(),
+            detail,
+        }
+    }
+}
+
 impl Clone for Storage {
     fn clone(&self) -> Self {
         Self {
+            name: self.name.clone(),
             database: Arc::clone(&self.database),
             primary_key_index: Arc::clone(&self.primary_key_index),
+            writer: Arc::clone(&self.writer),
+            reload_status: Arc::clone(&self.reload_status),
+            reload_requests: Arc::clone(&self.reload_requests),
+            notifications: self.notifications.clone(),
         }
     }
 }
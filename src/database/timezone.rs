@@ -0,0 +1,280 @@
+// Time zones for TIMESTAMPTZ values and AT TIME ZONE. Named zones come from the IANA tz
+// database compiled into the binary by chrono-tz, so they work without system tzdata.
+use chrono::{Duration, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, Offset, TimeZone as _};
+use std::sync::RwLock;
+
+use crate::YamlBaseError;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimeZone {
+    name: String,
+    kind: ZoneKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ZoneKind {
+    Fixed(i32),
+    Named(chrono_tz::Tz),
+}
+
+static DEFAULT_TIMEZONE: RwLock<Option<TimeZone>> = RwLock::new(None);
+
+tokio::task_local! {
+    /// The zone the connection whose statement is running chose with SET TIME ZONE
+    static SESSION_ZONE: TimeZone;
+}
+
+impl TimeZone {
+    pub fn utc() -> Self {
+        Self {
+            name: "UTC".to_string(),
+            kind: ZoneKind::Fixed(0),
+        }
+    }
+
+    /// Look up a time zone by IANA name (`Europe/Amsterdam`), `UTC`/`GMT`/`Z` or a fixed
+    /// offset such as `+02:00`, `-0530` or `+05`
+    pub fn parse(name: &str) -> crate::Result<Self> {
+        let name = name.trim();
+        if ["UTC", "GMT", "Z", "ZULU"].contains(&name.to_uppercase().as_str()) {
+            return Ok(Self::utc());
+        }
+        if let Some(offset) = parse_fixed_offset(name) {
+            return Ok(Self {
+                name: name.to_string(),
+                kind: ZoneKind::Fixed(offset),
+            });
+        }
+
+        let zone = name
+            .parse::<chrono_tz::Tz>()
+            .map_err(|_| YamlBaseError::Database {
+                message: format!("time zone \"{}\" not recognized", name),
+            })?;
+        Ok(Self {
+            name: name.to_string(),
+            kind: ZoneKind::Named(zone),
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The zone used for TIMESTAMPTZ values given without an offset and for displaying
+    /// TIMESTAMPTZ values: the session's zone inside `in_session_zone`, the server-wide
+    /// default otherwise
+    pub fn default_zone() -> Self {
+        SESSION_ZONE
+            .try_with(Clone::clone)
+            .unwrap_or_else(|_| Self::server_default())
+    }
+
+    /// The server-wide default zone, UTC unless set with `set_default`
+    pub fn server_default() -> Self {
+        DEFAULT_TIMEZONE
+            .read()
+            .unwrap()
+            .clone()
+            .unwrap_or_else(Self::utc)
+    }
+
+    /// Run `future` with `default_zone` returning `zone`, when a session has chosen one
+    pub async fn in_session_zone<F: std::future::Future>(
+        zone: Option<TimeZone>,
+        future: F,
+    ) -> F::Output {
+        match zone {
+            Some(zone) => SESSION_ZONE.scope(zone, future).await,
+            None => future.await,
+        }
+    }
+
+    pub fn set_default(zone: TimeZone) {
+        *DEFAULT_TIMEZONE.write().unwrap() = Some(zone);
+    }
+
+    /// The offset from UTC in effect at a UTC date and time
+    pub fn offset_at_utc(&self, utc: &NaiveDateTime) -> FixedOffset {
+        let seconds = match self.kind {
+            ZoneKind::Fixed(offset) => offset,
+            ZoneKind::Named(zone) => zone.offset_from_utc_datetime(utc).fix().local_minus_utc(),
+        };
+        FixedOffset::east_opt(seconds).unwrap_or(FixedOffset::east_opt(0).unwrap())
+    }
+
+    /// The offset that applies to a wall-clock time in this zone. Ambiguous times at the
+    /// end of daylight saving resolve to standard time; times skipped at its start use the
+    /// offset from before the jump, as in PostgreSQL.
+    pub fn offset_at_local(&self, local: &NaiveDateTime) -> FixedOffset {
+        let before = self.offset_at_utc(&(*local - Duration::days(1)));
+        let after = self.offset_at_utc(&(*local + Duration::days(1)));
+        let valid = |offset: FixedOffset| {
+            self.offset_at_utc(&(*local - Duration::seconds(offset.local_minus_utc() as i64)))
+                == offset
+        };
+        match (valid(before), valid(after)) {
+            (true, true) => {
+                if before.local_minus_utc() <= after.local_minus_utc() {
+                    before
+                } else {
+                    after
+                }
+            }
+            (false, true) => after,
+            _ => before,
+        }
+    }
+
+    /// Interpret a wall-clock time in this zone as an instant
+    pub fn from_local(&self, local: &NaiveDateTime) -> chrono::DateTime<FixedOffset> {
+        let offset = self.offset_at_local(local);
+        let utc = *local - Duration::seconds(offset.local_minus_utc() as i64);
+        utc.and_utc().with_timezone(&offset)
+    }
+
+    /// Express an instant in this zone
+    pub fn from_instant<Tz: chrono::TimeZone>(
+        &self,
+        instant: &chrono::DateTime<Tz>,
+    ) -> chrono::DateTime<FixedOffset> {
+        let utc = instant.naive_utc();
+        utc.and_utc().with_timezone(&self.offset_at_utc(&utc))
+    }
+}
+
+/// Parse a TIMESTAMPTZ text value. Text with an explicit offset is converted to `zone`;
+/// text without one is read as wall-clock time in `zone`.
+pub fn parse_timestamptz(text: &str, zone: &TimeZone) -> Option<chrono::DateTime<FixedOffset>> {
+    let text = text.trim();
+    if let Ok(instant) = chrono::DateTime::parse_from_rfc3339(text) {
+        return Some(zone.from_instant(&instant));
+    }
+    for format in ["%Y-%m-%d %H:%M:%S%.f%#z", "%Y-%m-%dT%H:%M:%S%.f%#z"] {
+        if let Ok(instant) = chrono::DateTime::parse_from_str(text, format) {
+            return Some(zone.from_instant(&instant));
+        }
+    }
+    if let Some(utc) = text
+        .strip_suffix(" UTC")
+        .or_else(|| text.strip_suffix('Z'))
+        .and_then(parse_naive_timestamp)
+    {
+        return Some(zone.from_instant(&utc.and_utc()));
+    }
+    parse_naive_timestamp(text).map(|local| zone.from_local(&local))
+}
+
+/// Parse a timestamp without a zone, in the forms CAST(... AS TIMESTAMP) accepts
+pub fn parse_naive_timestamp(text: &str) -> Option<NaiveDateTime> {
+    [
+        "%Y-%m-%d %H:%M:%S%.f",
+        "%Y-%m-%dT%H:%M:%S%.f",
+        "%Y-%m-%d %H:%M",
+    ]
+    .iter()
+    .find_map(|format| NaiveDateTime::parse_from_str(text, format).ok())
+    .or_else(|| {
+        NaiveDate::parse_from_str(text, "%Y-%m-%d")
+            .ok()
+            .map(|date| date.and_time(NaiveTime::MIN))
+    })
+}
+
+/// Format a TIMESTAMPTZ like PostgreSQL: `2024-03-01 12:00:00+01`, with minutes in the
+/// offset only when they are not zero
+pub fn format_timestamptz(value: &chrono::DateTime<FixedOffset>) -> String {
+    let offset = value.offset().local_minus_utc();
+    let sign = if offset < 0 { '-' } else { '+' };
+    let (hours, minutes) = (offset.abs() / 3600, offset.abs() % 3600 / 60);
+    let mut text = format!("{}{}{:02}", value.format("%Y-%m-%d %H:%M:%S"), sign, hours);
+    if minutes != 0 {
+        text.push_str(&format!(":{:02}", minutes));
+    }
+    text
+}
+
+/// `+02:00`, `-0530`, `+05` as seconds east of UTC
+fn parse_fixed_offset(text: &str) -> Option<i32> {
+    let (sign, digits) = match text.as_bytes().first()? {
+        b'+' => (1, &text[1..]),
+        b'-' => (-1, &text[1..]),
+        _ => return None,
+    };
+    let digits = digits.replace(':', "");
+    if digits.is_empty() || digits.len() > 4 || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let (hours, minutes) = if digits.len() <= 2 {
+        (digits.parse::<i32>().ok()?, 0)
+    } else {
+        let split = digits.len() - 2;
+        (
+            digits[..split].parse::<i32>().ok()?,
+            digits[split..].parse::<i32>().ok()?,
+        )
+    };
+    (hours <= 15 && minutes < 60).then_some(sign * (hours * 3600 + minutes * 60))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn datetime(text: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+
+    #[test]
+    fn test_fixed_offsets() {
+        assert_eq!(parse_fixed_offset("+02:00"), Some(7200));
+        assert_eq!(parse_fixed_offset("-0530"), Some(-19800));
+        assert_eq!(parse_fixed_offset("+5"), Some(18000));
+        assert_eq!(parse_fixed_offset("+25"), None);
+        assert_eq!(parse_fixed_offset("Europe/Amsterdam"), None);
+
+        let zone = TimeZone::parse("-03:30").unwrap();
+        assert_eq!(
+            zone.offset_at_utc(&datetime("2024-01-01 00:00:00"))
+                .local_minus_utc(),
+            -12600
+        );
+    }
+
+    #[test]
+    fn test_named_zone() {
+        let zone = TimeZone::parse("Europe/Amsterdam").unwrap();
+        let at = |text: &str| zone.offset_at_utc(&datetime(text)).local_minus_utc();
+
+        // DST runs from 01:00 UTC on the last Sunday of March to 01:00 UTC on the last
+        // Sunday of October
+        assert_eq!(at("2040-03-25 00:59:59"), 3600);
+        assert_eq!(at("2040-03-25 01:00:00"), 7200);
+        assert_eq!(at("2040-10-28 00:59:59"), 7200);
+        assert_eq!(at("2040-10-28 01:00:00"), 3600);
+
+        // Skipped wall-clock times use the offset from before the jump
+        assert_eq!(
+            zone.offset_at_local(&datetime("2024-03-31 02:30:00"))
+                .local_minus_utc(),
+            3600
+        );
+    }
+
+    #[test]
+    fn test_rejects_unsafe_zone_names() {
+        for name in ["../etc/passwd", "/etc/passwd", "Europe//Amsterdam", ""] {
+            assert!(TimeZone::parse(name).is_err(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_format_timestamptz() {
+        let zone = TimeZone::parse("+05:30").unwrap();
+        let value = zone.from_instant(&datetime("2024-03-01 12:00:00").and_utc());
+        assert_eq!(format_timestamptz(&value), "2024-03-01 17:30:00+05:30");
+
+        let value = TimeZone::utc().from_local(&datetime("2024-03-01 12:00:00"));
+        assert_eq!(format_timestamptz(&value), "2024-03-01 12:00:00+00");
+    }
+}
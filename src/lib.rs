@@ -40,6 +40,34 @@ pub enum YamlBaseError {
 
     #[error("Not implemented: {0}")]
     NotImplemented(String),
+
+    #[error("rate limit exceeded: {0}")]
+    RateLimited(String),
+
+    #[error("permission denied for {0}")]
+    PermissionDenied(String),
+
+    #[error("canceling statement due to user request")]
+    QueryCancelled,
+
+    #[error("prepared statement \"{0}\" does not exist")]
+    UnknownPreparedStatement(String),
+
+    #[error("prepared statement \"{0}\" already exists")]
+    DuplicatePreparedStatement(String),
+
+    #[error("duplicate value {value} for unique column '{column}' in table '{table}'")]
+    UniqueViolation {
+        table: String,
+        column: String,
+        value: String,
+    },
+
+    #[error("current transaction is aborted, commands ignored until end of transaction block")]
+    TransactionAborted,
+
+    #[error("Row violates CHECK ({check}) of table '{table}'")]
+    CheckViolation { table: String, check: String },
 }
 
 pub type Result<T> = std::result::Result<T, YamlBaseError>;
@@ -1,19 +1,33 @@
 #![allow(clippy::uninlined_format_args)]
 
-use clap::Parser;
 use tracing::info;
+use yamlbase::config::Command;
+use yamlbase::yaml::validate::validate;
 use yamlbase::{Config, Server};
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    // Parse command line arguments
-    let config = Config::parse();
+    // Parse command line arguments, completed by the settings files
+    let config = Config::load()?;
+
+    if let Some(Command::Validate { files }) = &config.command {
+        config.set_load_defaults()?;
+        let mut valid = true;
+        for file in files {
+            let validation = validate(file).await;
+            println!("{}", validation);
+            valid &= validation.is_valid();
+        }
+        std::process::exit(if valid { 0 } else { 1 });
+    }
 
     // Initialize logging
     config.init_logging()?;
 
     info!("Starting YamlBase v{}", env!("CARGO_PKG_VERSION"));
-    info!("Loading database from: {}", config.file.display());
+    for file in &config.files {
+        info!("Loading database from: {}", file.display());
+    }
 
     // Create and run server
     let server = Server::new(config).await?;
@@ -0,0 +1,97 @@
+// Query cancellation. A PostgreSQL CancelRequest and a MySQL KILL QUERY arrive on another
+// connection than the query they stop, so every connection registers here under the id its
+// clients know it by: the process id of BackendKeyData, or the MySQL connection id.
+use rand::RngCore;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+
+/// The connections whose queries can be cancelled, shared by all connections
+#[derive(Clone, Default)]
+pub struct Cancellations {
+    registry: Arc<Mutex<Registry>>,
+}
+
+#[derive(Default)]
+struct Registry {
+    last_id: u32,
+    sessions: HashMap<u32, (u32, Arc<Notify>)>,
+}
+
+/// A connection's entry, removed when it is dropped
+pub struct CancelKey {
+    id: u32,
+    secret: u32,
+    signal: Arc<Notify>,
+    cancellations: Cancellations,
+}
+
+impl Cancellations {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a connection under a new id, with a random secret key
+    pub fn register(&self) -> CancelKey {
+        let mut registry = self.lock();
+        let mut id = registry.last_id;
+        loop {
+            id = id.wrapping_add(1).max(1);
+            if !registry.sessions.contains_key(&id) {
+                break;
+            }
+        }
+        registry.last_id = id;
+
+        let secret = rand::thread_rng().next_u32();
+        let signal = Arc::new(Notify::new());
+        registry.sessions.insert(id, (secret, Arc::clone(&signal)));
+        CancelKey {
+            id,
+            secret,
+            signal,
+            cancellations: self.clone(),
+        }
+    }
+
+    /// Cancel the query the connection with this id is running, if it is running one.
+    /// PostgreSQL clients must also give the connection's secret key. Returns whether the
+    /// connection exists.
+    pub fn cancel(&self, id: u32, secret: Option<u32>) -> bool {
+        let registry = self.lock();
+        match registry.sessions.get(&id) {
+            Some((key, signal)) if secret.is_none_or(|secret| secret == *key) => {
+                signal.notify_waiters();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Registry> {
+        self.registry
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl CancelKey {
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn secret(&self) -> u32 {
+        self.secret
+    }
+
+    /// Notified when the connection's query is cancelled, for `QueryExecutor::with_cancel_signal`
+    pub fn signal(&self) -> Arc<Notify> {
+        Arc::clone(&self.signal)
+    }
+}
+
+impl Drop for CancelKey {
+    fn drop(&mut self) {
+        self.cancellations.lock().sessions.remove(&self.id);
+    }
+}
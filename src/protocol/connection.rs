@@ -1,38 +1,150 @@
 use std::sync::Arc;
 use tokio::net::TcpStream;
-use tracing::error;
 
 use crate::config::{Config, Protocol};
-use crate::database::Storage;
-use crate::protocol::{MySqlProtocol, PostgresProtocol};
+use crate::database::Databases;
+use crate::protocol::cancel::Cancellations;
+use crate::protocol::timeouts::{LoginSignal, within_connect_timeout};
+use crate::protocol::tls::TlsAcceptor;
+use crate::protocol::{
+    GrpcProtocol, MySqlProtocol, PostgresProtocol, TdsProtocol, WebSocketProtocol,
+};
+use crate::server::rate_limit::ClientRateLimiter;
 
 pub struct Connection {
     config: Arc<Config>,
-    storage: Arc<Storage>,
+    databases: Databases,
+    protocol: Protocol,
+    tls: Option<TlsAcceptor>,
+    cancellations: Cancellations,
+    rate_limiter: ClientRateLimiter,
 }
 
 impl Connection {
-    pub fn new(config: Arc<Config>, storage: Arc<Storage>) -> Self {
-        Self { config, storage }
+    pub fn new(config: Arc<Config>, databases: Databases) -> Self {
+        Self {
+            protocol: config.protocol,
+            config,
+            databases,
+            tls: None,
+            cancellations: Cancellations::new(),
+            rate_limiter: ClientRateLimiter::default(),
+        }
+    }
+
+    /// Speak another protocol than `--protocol`, for a listener of its own
+    pub fn with_protocol(mut self, protocol: Protocol) -> Self {
+        self.protocol = protocol;
+        self
+    }
+
+    /// Accept TLS for clients that ask for it
+    pub fn with_tls(mut self, tls: Option<TlsAcceptor>) -> Self {
+        self.tls = tls;
+        self
     }
 
+    /// Share the connections other connections can cancel the queries of
+    pub fn with_cancellations(mut self, cancellations: Cancellations) -> Self {
+        self.cancellations = cancellations;
+        self
+    }
+
+    /// Count the client's statements against its rate limits
+    pub fn with_rate_limiter(mut self, rate_limiter: ClientRateLimiter) -> Self {
+        self.rate_limiter = rate_limiter;
+        self
+    }
+
+    /// Serve a client, which has --connect-timeout to log in
     pub async fn handle(&self, stream: TcpStream) -> crate::Result<()> {
-        match self.config.protocol {
+        let login = LoginSignal::default();
+        let connection = self.serve(stream, login.clone());
+        within_connect_timeout(self.config.connect_timeout(), &login, connection).await
+    }
+
+    /// Turn a client away beyond --max-connections, with the error its protocol has for it.
+    /// It has --connect-timeout to take the error.
+    pub async fn refuse(&self, stream: TcpStream) -> crate::Result<()> {
+        let refusal = async {
+            match self.protocol {
+                Protocol::Postgres => {
+                    PostgresProtocol::new(self.config.clone(), self.databases.clone())
+                        .await?
+                        .with_tls(self.tls.clone())
+                        .refuse_connection(stream)
+                        .await
+                }
+                Protocol::Mysql => {
+                    MySqlProtocol::new(self.config.clone(), self.databases.clone())
+                        .await?
+                        .refuse_connection(stream)
+                        .await
+                }
+                Protocol::Sqlserver => {
+                    TdsProtocol::new(self.config.clone(), self.databases.clone())
+                        .await?
+                        .refuse_connection(stream)
+                        .await
+                }
+                Protocol::Http => {
+                    WebSocketProtocol::new(self.config.clone(), self.databases.clone())
+                        .refuse_connection(stream)
+                        .await
+                }
+                Protocol::Grpc => {
+                    GrpcProtocol::new(self.config.clone(), self.databases.clone())
+                        .refuse_connection(stream)
+                        .await
+                }
+            }
+        };
+        tokio::time::timeout(self.config.connect_timeout(), refusal)
+            .await
+            .unwrap_or(Ok(()))
+    }
+
+    async fn serve(&self, stream: TcpStream, login: LoginSignal) -> crate::Result<()> {
+        match self.protocol {
             Protocol::Postgres => {
                 let mut protocol =
-                    PostgresProtocol::new(self.config.clone(), self.storage.clone()).await?;
+                    PostgresProtocol::new(self.config.clone(), self.databases.clone())
+                        .await?
+                        .with_tls(self.tls.clone())
+                        .with_cancellations(self.cancellations.clone())
+                        .with_login_signal(login)
+                        .with_rate_limiter(self.rate_limiter.clone());
                 protocol.handle_connection(stream).await
             }
             Protocol::Mysql => {
-                let protocol =
-                    MySqlProtocol::new(self.config.clone(), self.storage.clone()).await?;
+                let mut protocol = MySqlProtocol::new(self.config.clone(), self.databases.clone())
+                    .await?
+                    .with_tls(self.tls.clone())
+                    .with_cancellations(self.cancellations.clone())
+                    .with_login_signal(login)
+                    .with_rate_limiter(self.rate_limiter.clone());
                 protocol.handle_connection(stream).await
             }
             Protocol::Sqlserver => {
-                error!("SQL Server protocol not yet implemented");
-                Err(crate::YamlBaseError::NotImplemented(
-                    "SQL Server protocol not yet implemented".to_string(),
-                ))
+                let mut protocol = TdsProtocol::new(self.config.clone(), self.databases.clone())
+                    .await?
+                    .with_login_signal(login)
+                    .with_rate_limiter(self.rate_limiter.clone());
+                protocol.handle_connection(stream).await
+            }
+            Protocol::Http => {
+                let mut protocol =
+                    WebSocketProtocol::new(self.config.clone(), self.databases.clone())
+                        .with_login_signal(login)
+                        .with_rate_limiter(self.rate_limiter.clone());
+                protocol.handle_connection(stream).await
+            }
+            // Requests carry their own credentials, with no login to wait for
+            Protocol::Grpc => {
+                login.complete();
+                let mut protocol = GrpcProtocol::new(self.config.clone(), self.databases.clone())
+                    .with_rate_limiter(self.rate_limiter.clone());
+                protocol.handle_connection(stream).await
             }
         }
     }
@@ -0,0 +1,407 @@
+// A gRPC service, for clients that would rather not carry a SQL driver: `ExecuteQuery`
+// runs SQL, `ListTables` lists the tables of a database and `GetSchema` describes their
+// columns. The service is defined in proto/yamlbase.proto. Requests authenticate with an
+// `authorization: Basic <base64 user:password>` metadata entry, and each one runs in a
+// session of its own, so nothing a request sets outlives it.
+use hyper::server::conn::http2;
+use hyper::service::service_fn;
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use hyper_util::service::TowerToHyperService;
+use std::convert::Infallible;
+use std::sync::Arc;
+use tokio::net::TcpStream;
+use tonic::{Request, Response, Status};
+use tracing::{debug, info};
+
+use crate::YamlBaseError;
+use crate::config::Config;
+use crate::database::{Column, Database, Databases, Value};
+use crate::protocol::password::authenticate_basic;
+use crate::server::rate_limit::ClientRateLimiter;
+use crate::sql::{QueryExecutor, parse_sql};
+use crate::yaml::Grants;
+use crate::yaml::schema::SqlType;
+
+/// The messages and service generated from proto/yamlbase.proto
+pub mod proto {
+    tonic::include_proto!("yamlbase.v1");
+}
+
+use proto::yamlbase_server::{Yamlbase, YamlbaseServer};
+use proto::{
+    Array, ColumnSchema, DataType, ExecuteQueryRequest, ExecuteQueryResponse, ForeignKey,
+    GetSchemaRequest, GetSchemaResponse, ListTablesRequest, ListTablesResponse, Row, TableSchema,
+    value::Kind,
+};
+
+pub struct GrpcProtocol {
+    config: Arc<Config>,
+    databases: Databases,
+    rate_limiter: ClientRateLimiter,
+}
+
+impl GrpcProtocol {
+    pub fn new(config: Arc<Config>, databases: Databases) -> Self {
+        Self {
+            config,
+            databases,
+            rate_limiter: ClientRateLimiter::default(),
+        }
+    }
+
+    /// Count the client's statements against its rate limits
+    pub fn with_rate_limiter(mut self, rate_limiter: ClientRateLimiter) -> Self {
+        self.rate_limiter = rate_limiter;
+        self
+    }
+
+    /// Turn a client away: every request on the connection fails with RESOURCE_EXHAUSTED
+    pub async fn refuse_connection(&self, stream: TcpStream) -> crate::Result<()> {
+        let refuse = service_fn(|_| async {
+            Ok::<_, Infallible>(Status::resource_exhausted("Too many connections").into_http())
+        });
+        if let Err(e) = http2::Builder::new(TokioExecutor::new())
+            .serve_connection(TokioIo::new(stream), refuse)
+            .await
+        {
+            debug!("Refused gRPC connection ended with an error: {}", e);
+        }
+        Ok(())
+    }
+
+    pub async fn handle_connection(&mut self, stream: TcpStream) -> crate::Result<()> {
+        info!("New gRPC connection");
+        let service = YamlbaseServer::new(QueryService {
+            config: Arc::clone(&self.config),
+            databases: self.databases.clone(),
+            rate_limiter: self.rate_limiter.clone(),
+        });
+        // Each connection is served on its own, as the connections of the other protocols are
+        if let Err(e) = http2::Builder::new(TokioExecutor::new())
+            .serve_connection(TokioIo::new(stream), TowerToHyperService::new(service))
+            .await
+        {
+            debug!("gRPC connection ended with an error: {}", e);
+        }
+        info!("gRPC client disconnected");
+        Ok(())
+    }
+}
+
+struct QueryService {
+    config: Arc<Config>,
+    databases: Databases,
+    rate_limiter: ClientRateLimiter,
+}
+
+// Failed requests are answered with tonic's status, however large it is
+#[allow(clippy::result_large_err)]
+impl QueryService {
+    /// The user a request authenticates as, and its grants
+    fn authenticate<T>(
+        &self,
+        request: &Request<T>,
+    ) -> Result<(Option<String>, Option<Grants>), Status> {
+        let authorization = request
+            .metadata()
+            .get("authorization")
+            .and_then(|value| value.to_str().ok());
+        authenticate_basic(&self.config, authorization)
+            .ok_or_else(|| Status::unauthenticated("Authentication required"))
+    }
+
+    /// The index of the database a request names, the first one when it names none
+    fn database(&self, name: &str) -> Result<usize, Status> {
+        if name.is_empty() {
+            return Ok(0);
+        }
+        self.databases
+            .find(name)
+            .ok_or_else(|| Status::not_found(format!("Database '{}' does not exist", name)))
+    }
+
+    async fn executor(
+        &self,
+        database: usize,
+        user: Option<&str>,
+        grants: Option<Grants>,
+    ) -> crate::Result<QueryExecutor> {
+        let mut executor = QueryExecutor::new(Arc::clone(self.databases.get(database))).await?;
+        if let Some(seed) = self.config.random_seed {
+            executor = executor.with_random_seed(seed);
+        }
+        let names = self
+            .databases
+            .iter()
+            .map(|storage| storage.name().to_string())
+            .collect();
+        Ok(executor
+            .with_databases(names)
+            .with_recursion_limits(self.config.recursion_limits())
+            .with_timeout(self.config.statement_timeout())
+            .with_collation(self.config.collation()?)
+            .with_write_mode(self.config.write_mode(&self.config.files[database]))
+            .with_grants(grants)
+            .with_quota(self.rate_limiter.quota(user)))
+    }
+}
+
+#[tonic::async_trait]
+impl Yamlbase for QueryService {
+    async fn execute_query(
+        &self,
+        request: Request<ExecuteQueryRequest>,
+    ) -> Result<Response<ExecuteQueryResponse>, Status> {
+        let (user, grants) = self.authenticate(&request)?;
+        let request = request.into_inner();
+        let database = self.database(&request.database)?;
+        let executor = self
+            .executor(database, user.as_deref(), grants)
+            .await
+            .map_err(status)?;
+
+        let statements = parse_sql(&request.sql).map_err(status)?;
+        let mut response = ExecuteQueryResponse::default();
+        for statement in &statements {
+            debug!("Executing statement: {:?}", statement);
+            let result = executor.execute(statement).await.map_err(status)?;
+            response = match result.affected_rows {
+                Some(affected_rows) => ExecuteQueryResponse {
+                    affected_rows: Some(affected_rows),
+                    ..Default::default()
+                },
+                None => ExecuteQueryResponse {
+                    columns: result
+                        .columns
+                        .iter()
+                        .enumerate()
+                        .map(|(i, name)| proto::Column {
+                            name: name.clone(),
+                            r#type: result_type(
+                                result.column_types.get(i),
+                                result.rows.iter().filter_map(|row| row.get(i)),
+                            ) as i32,
+                        })
+                        .collect(),
+                    rows: result
+                        .rows
+                        .iter()
+                        .map(|row| Row {
+                            values: row.iter().map(value).collect(),
+                        })
+                        .collect(),
+                    affected_rows: None,
+                },
+            };
+        }
+        Ok(Response::new(response))
+    }
+
+    async fn list_tables(
+        &self,
+        request: Request<ListTablesRequest>,
+    ) -> Result<Response<ListTablesResponse>, Status> {
+        let (_, grants) = self.authenticate(&request)?;
+        let database = self.database(&request.get_ref().database)?;
+        let db_arc = self.databases.get(database).database();
+        let db = db_arc.read().await;
+        let tables = db
+            .tables
+            .keys()
+            .filter(|key| grants.as_ref().is_none_or(|grants| grants.may_read(key)))
+            .cloned()
+            .collect();
+        Ok(Response::new(ListTablesResponse { tables }))
+    }
+
+    async fn get_schema(
+        &self,
+        request: Request<GetSchemaRequest>,
+    ) -> Result<Response<GetSchemaResponse>, Status> {
+        let (_, grants) = self.authenticate(&request)?;
+        let request = request.into_inner();
+        let database = self.database(&request.database)?;
+        let storage = self.databases.get(database);
+        let db_arc = storage.database();
+        let db = db_arc.read().await;
+        let may_read = |key: &str| grants.as_ref().is_none_or(|grants| grants.may_read(key));
+
+        let tables = if request.tables.is_empty() {
+            db.tables
+                .iter()
+                .filter(|(key, _)| may_read(key))
+                .map(|(key, table)| table_schema(&db, key, &table.columns))
+                .collect()
+        } else {
+            let mut tables = Vec::new();
+            for name in &request.tables {
+                let table = db
+                    .tables
+                    .iter()
+                    .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                    .filter(|(key, _)| may_read(key));
+                let Some((key, table)) = table else {
+                    return Err(Status::not_found(format!(
+                        "Table '{}' does not exist",
+                        name
+                    )));
+                };
+                tables.push(table_schema(&db, key, &table.columns));
+            }
+            tables
+        };
+        Ok(Response::new(GetSchemaResponse {
+            database: storage.name().to_string(),
+            tables,
+        }))
+    }
+}
+
+fn table_schema(db: &Database, name: &str, columns: &[Column]) -> TableSchema {
+    TableSchema {
+        name: name.to_string(),
+        columns: columns
+            .iter()
+            .map(|column| column_schema(db, column))
+            .collect(),
+    }
+}
+
+fn column_schema(db: &Database, column: &Column) -> ColumnSchema {
+    let (length, precision, scale) = match column.sql_type {
+        SqlType::Char(length) | SqlType::Varchar(length) => (length as u32, 0, 0),
+        SqlType::Decimal(precision, scale) => (0, precision, scale),
+        _ => (0, 0, 0),
+    };
+    let element_type = match &column.sql_type {
+        SqlType::Array(element) => data_type(element),
+        _ => DataType::Unspecified,
+    };
+    ColumnSchema {
+        name: column.name.clone(),
+        r#type: data_type(&column.sql_type) as i32,
+        length,
+        precision,
+        scale,
+        element_type: element_type as i32,
+        nullable: column.nullable,
+        primary_key: column.primary_key,
+        unique: column.unique,
+        default: column.default.clone(),
+        references: column
+            .references
+            .as_ref()
+            .map(|(table, column)| foreign_key(db, table, column)),
+    }
+}
+
+/// The table and column a foreign key references, named as they were declared rather than
+/// as the reference was written
+fn foreign_key(db: &Database, table: &str, column: &str) -> ForeignKey {
+    let Some((key, referenced)) = db
+        .tables
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(table))
+    else {
+        return ForeignKey {
+            table: table.to_string(),
+            column: column.to_string(),
+        };
+    };
+    let column = referenced
+        .columns
+        .iter()
+        .find(|declared| declared.name.eq_ignore_ascii_case(column))
+        .map_or(column, |declared| &declared.name);
+    ForeignKey {
+        table: key.clone(),
+        column: column.to_string(),
+    }
+}
+
+fn data_type(sql_type: &SqlType) -> DataType {
+    match sql_type {
+        SqlType::Integer => DataType::Integer,
+        SqlType::BigInt => DataType::Bigint,
+        SqlType::Float => DataType::Float,
+        SqlType::Double => DataType::Double,
+        SqlType::Decimal(..) => DataType::Decimal,
+        SqlType::Char(_) => DataType::Char,
+        SqlType::Varchar(_) => DataType::Varchar,
+        SqlType::Text => DataType::Text,
+        SqlType::Boolean => DataType::Boolean,
+        SqlType::Date => DataType::Date,
+        SqlType::Time => DataType::Time,
+        SqlType::Timestamp => DataType::Timestamp,
+        SqlType::TimestampTz => DataType::Timestamptz,
+        SqlType::Uuid => DataType::Uuid,
+        SqlType::Json => DataType::Json,
+        SqlType::Blob => DataType::Blob,
+        SqlType::Array(_) => DataType::Array,
+    }
+}
+
+/// The type of a result column: its SQL type when the result knows it, otherwise the type
+/// of its first value that is not null
+fn result_type<'a>(
+    sql_type: Option<&SqlType>,
+    mut values: impl Iterator<Item = &'a Value>,
+) -> DataType {
+    if let Some(sql_type) = sql_type {
+        return data_type(sql_type);
+    }
+    match values.find(|value| !matches!(value, Value::Null)) {
+        Some(Value::Integer(_)) => DataType::Bigint,
+        Some(Value::Float(_)) => DataType::Float,
+        Some(Value::Double(_)) => DataType::Double,
+        Some(Value::Decimal(_)) => DataType::Decimal,
+        Some(Value::Text(_)) => DataType::Text,
+        Some(Value::Boolean(_)) => DataType::Boolean,
+        Some(Value::Timestamp(_)) => DataType::Timestamp,
+        Some(Value::TimestampTz(_)) => DataType::Timestamptz,
+        Some(Value::Date(_)) => DataType::Date,
+        Some(Value::Time(_)) => DataType::Time,
+        Some(Value::Uuid(_)) => DataType::Uuid,
+        Some(Value::Json(_)) => DataType::Json,
+        Some(Value::Bytes(_)) => DataType::Blob,
+        Some(Value::Array(_)) => DataType::Array,
+        Some(Value::Null) | None => DataType::Unspecified,
+    }
+}
+
+fn value(value: &Value) -> proto::Value {
+    let kind = match value {
+        Value::Null => Kind::Null(true),
+        Value::Integer(i) => Kind::Integer(*i),
+        Value::Float(f) => Kind::Double(*f as f64),
+        Value::Double(d) => Kind::Double(*d),
+        Value::Decimal(d) => Kind::Decimal(d.to_string()),
+        Value::Text(s) => Kind::Text(s.clone()),
+        Value::Boolean(b) => Kind::Boolean(*b),
+        Value::Timestamp(ts) => Kind::Timestamp(ts.format("%Y-%m-%dT%H:%M:%S%.f").to_string()),
+        Value::TimestampTz(ts) => Kind::Timestamp(ts.to_rfc3339()),
+        Value::Date(d) => Kind::Date(d.to_string()),
+        Value::Time(t) => Kind::Time(t.to_string()),
+        Value::Uuid(u) => Kind::Uuid(u.to_string()),
+        Value::Json(j) => Kind::Json(j.to_string()),
+        Value::Bytes(bytes) => Kind::Bytes(bytes.clone()),
+        Value::Array(values) => Kind::Array(Array {
+            values: values.iter().map(self::value).collect(),
+        }),
+    };
+    proto::Value { kind: Some(kind) }
+}
+
+/// The status a failed request is answered with
+fn status(error: YamlBaseError) -> Status {
+    match error {
+        YamlBaseError::PermissionDenied(_) => Status::permission_denied(error.to_string()),
+        YamlBaseError::QueryCancelled => Status::cancelled(error.to_string()),
+        YamlBaseError::RateLimited(_) => Status::resource_exhausted(error.to_string()),
+        YamlBaseError::NotImplemented(_) => Status::unimplemented(error.to_string()),
+        YamlBaseError::UniqueViolation { .. } => Status::already_exists(error.to_string()),
+        YamlBaseError::TransactionAborted => Status::failed_precondition(error.to_string()),
+        YamlBaseError::Io(_) => Status::internal(error.to_string()),
+        _ => Status::invalid_argument(error.to_string()),
+    }
+}
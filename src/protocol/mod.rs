@@ -1,9 +1,23 @@
+pub mod cancel;
 pub mod connection;
+pub mod grpc;
+pub mod mysql_binary;
 pub mod mysql_caching_sha2;
+pub mod mysql_compression;
 pub mod mysql_simple;
+pub mod password;
 pub mod postgres;
+pub mod postgres_copy;
 pub mod postgres_extended;
+pub mod postgres_scram;
+pub mod tds;
+pub mod timeouts;
+pub mod tls;
+pub mod websocket;
 
 pub use connection::Connection;
+pub use grpc::GrpcProtocol;
 pub use mysql_simple::MySqlProtocol;
 pub use postgres::PostgresProtocol;
+pub use tds::TdsProtocol;
+pub use websocket::WebSocketProtocol;
@@ -0,0 +1,404 @@
+// The binary protocol of MySQL prepared statements. COM_STMT_EXECUTE sends parameters with
+// their types rather than as SQL text, and the rows of the result come back typed as well:
+// numbers and dates in fixed layouts, everything else as length-encoded strings.
+use bytes::{BufMut, BytesMut};
+use chrono::{Datelike, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+
+use crate::YamlBaseError;
+use crate::database::Value;
+use crate::protocol::mysql_simple::put_lenenc_int;
+use crate::sql::value_to_json;
+use crate::yaml::schema::SqlType;
+
+// Column types
+const MYSQL_TYPE_DECIMAL: u8 = 0;
+const MYSQL_TYPE_TINY: u8 = 1;
+const MYSQL_TYPE_SHORT: u8 = 2;
+const MYSQL_TYPE_LONG: u8 = 3;
+const MYSQL_TYPE_FLOAT: u8 = 4;
+const MYSQL_TYPE_DOUBLE: u8 = 5;
+const MYSQL_TYPE_NULL: u8 = 6;
+const MYSQL_TYPE_TIMESTAMP: u8 = 7;
+const MYSQL_TYPE_LONGLONG: u8 = 8;
+const MYSQL_TYPE_INT24: u8 = 9;
+const MYSQL_TYPE_DATE: u8 = 10;
+const MYSQL_TYPE_TIME: u8 = 11;
+const MYSQL_TYPE_DATETIME: u8 = 12;
+const MYSQL_TYPE_YEAR: u8 = 13;
+pub(crate) const MYSQL_TYPE_JSON: u8 = 245;
+const MYSQL_TYPE_NEWDECIMAL: u8 = 246;
+pub(crate) const MYSQL_TYPE_BLOB: u8 = 252;
+pub(crate) const MYSQL_TYPE_VAR_STRING: u8 = 253;
+
+// Column flags and character sets
+const BLOB_FLAG: u16 = 0x0010;
+const BINARY_FLAG: u16 = 0x0080;
+const BINARY_CHARSET: u16 = 63;
+const UTF8MB4_CHARSET: u16 = 33;
+
+/// The flag of an unsigned parameter type
+const UNSIGNED_PARAMETER: u8 = 0x80;
+
+/// The column definition packet of a result column, or of a parameter of a prepared
+/// statement
+pub(crate) fn column_definition(name: &str, column_type: u8) -> BytesMut {
+    let mut packet = BytesMut::new();
+
+    // Catalog (def), schema, table and original table
+    packet.put_u8(3);
+    packet.put_slice(b"def");
+    packet.put_u8(0);
+    packet.put_u8(0);
+    packet.put_u8(0);
+
+    // Column name and original column name
+    for _ in 0..2 {
+        put_lenenc_int(&mut packet, name.len() as u64);
+        packet.put_slice(name.as_bytes());
+    }
+
+    // Length of fixed fields (0x0c)
+    packet.put_u8(0x0c);
+
+    // Character set, column length, type, flags and decimals
+    let (charset, length, flags, decimals) = match column_type {
+        MYSQL_TYPE_BLOB | MYSQL_TYPE_JSON => (BINARY_CHARSET, 65535, BLOB_FLAG | BINARY_FLAG, 0),
+        MYSQL_TYPE_TINY => (BINARY_CHARSET, 1, BINARY_FLAG, 0),
+        MYSQL_TYPE_LONGLONG => (BINARY_CHARSET, 20, BINARY_FLAG, 0),
+        MYSQL_TYPE_FLOAT => (BINARY_CHARSET, 12, BINARY_FLAG, 31),
+        MYSQL_TYPE_DOUBLE => (BINARY_CHARSET, 22, BINARY_FLAG, 31),
+        MYSQL_TYPE_NEWDECIMAL => (BINARY_CHARSET, 65, BINARY_FLAG, 0),
+        MYSQL_TYPE_DATE => (BINARY_CHARSET, 10, BINARY_FLAG, 0),
+        MYSQL_TYPE_DATETIME => (BINARY_CHARSET, 26, BINARY_FLAG, 6),
+        MYSQL_TYPE_TIME => (BINARY_CHARSET, 15, BINARY_FLAG, 6),
+        _ => (UTF8MB4_CHARSET, 255, 0, 0),
+    };
+    packet.put_u16_le(charset);
+    packet.put_u32_le(length);
+    packet.put_u8(column_type);
+    packet.put_u16_le(flags);
+    packet.put_u8(decimals);
+
+    // Filler
+    packet.put_u16_le(0);
+    packet
+}
+
+/// A value as the text protocol sends it: binary values as they are, and arrays, which MySQL
+/// does not have, as JSON arrays
+pub(crate) fn text_bytes(value: &Value) -> Vec<u8> {
+    match value {
+        Value::Bytes(bytes) => bytes.clone(),
+        Value::Array(_) => value_to_json(value).to_string().into_bytes(),
+        value => value.to_string().into_bytes(),
+    }
+}
+
+/// The type a result column is sent as in the binary protocol: the MySQL type of its SQL
+/// type when every value in it has that type, otherwise a string, which any value can be
+pub(crate) fn column_type<'a>(
+    sql_type: Option<&SqlType>,
+    mut values: impl Iterator<Item = &'a Value>,
+) -> u8 {
+    let column_type = match sql_type {
+        Some(SqlType::Integer | SqlType::BigInt) => MYSQL_TYPE_LONGLONG,
+        Some(SqlType::Boolean) => MYSQL_TYPE_TINY,
+        Some(SqlType::Float) => MYSQL_TYPE_FLOAT,
+        Some(SqlType::Double) => MYSQL_TYPE_DOUBLE,
+        Some(SqlType::Decimal(..)) => MYSQL_TYPE_NEWDECIMAL,
+        Some(SqlType::Date) => MYSQL_TYPE_DATE,
+        Some(SqlType::Timestamp) => MYSQL_TYPE_DATETIME,
+        Some(SqlType::Time) => MYSQL_TYPE_TIME,
+        Some(SqlType::Blob) => return MYSQL_TYPE_BLOB,
+        Some(SqlType::Array(_)) => return MYSQL_TYPE_JSON,
+        _ => return MYSQL_TYPE_VAR_STRING,
+    };
+    let typed = values.all(|value| {
+        matches!(
+            (column_type, value),
+            (_, Value::Null)
+                | (MYSQL_TYPE_LONGLONG, Value::Integer(_))
+                | (MYSQL_TYPE_TINY, Value::Boolean(_))
+                | (MYSQL_TYPE_FLOAT, Value::Float(_))
+                | (MYSQL_TYPE_DOUBLE, Value::Double(_))
+                | (MYSQL_TYPE_NEWDECIMAL, Value::Decimal(_))
+                | (MYSQL_TYPE_DATE, Value::Date(_))
+                | (MYSQL_TYPE_DATETIME, Value::Timestamp(_))
+                | (MYSQL_TYPE_TIME, Value::Time(_))
+        )
+    });
+    if typed {
+        column_type
+    } else {
+        MYSQL_TYPE_VAR_STRING
+    }
+}
+
+/// A row of a binary result set: a header, a NULL bitmap offset by two bits and the values
+/// that are not NULL, in the types `column_type` chose for their columns
+pub(crate) fn put_row(packet: &mut BytesMut, row: &[Value], column_types: &[u8]) {
+    packet.put_u8(0x00);
+
+    let mut null_bitmap = vec![0u8; (row.len() + 2).div_ceil(8)];
+    for (i, value) in row.iter().enumerate() {
+        if matches!(value, Value::Null) {
+            null_bitmap[(i + 2) / 8] |= 1 << ((i + 2) % 8);
+        }
+    }
+    packet.put_slice(&null_bitmap);
+
+    for (value, &column_type) in row.iter().zip(column_types) {
+        match (column_type, value) {
+            (_, Value::Null) => {}
+            (MYSQL_TYPE_LONGLONG, Value::Integer(n)) => packet.put_i64_le(*n),
+            (MYSQL_TYPE_TINY, Value::Boolean(b)) => packet.put_u8(u8::from(*b)),
+            (MYSQL_TYPE_FLOAT, Value::Float(f)) => packet.put_f32_le(*f),
+            (MYSQL_TYPE_DOUBLE, Value::Double(f)) => packet.put_f64_le(*f),
+            (MYSQL_TYPE_DATE, Value::Date(date)) => {
+                packet.put_u8(4);
+                put_date(packet, date);
+            }
+            (MYSQL_TYPE_DATETIME, Value::Timestamp(timestamp)) => {
+                let micros = timestamp.nanosecond() / 1000;
+                packet.put_u8(if micros == 0 { 7 } else { 11 });
+                put_date(packet, &timestamp.date());
+                packet.put_u8(timestamp.hour() as u8);
+                packet.put_u8(timestamp.minute() as u8);
+                packet.put_u8(timestamp.second() as u8);
+                if micros != 0 {
+                    packet.put_u32_le(micros);
+                }
+            }
+            (MYSQL_TYPE_TIME, Value::Time(time)) => {
+                let micros = time.nanosecond() / 1000;
+                packet.put_u8(if micros == 0 { 8 } else { 12 });
+                // Not negative, and no days
+                packet.put_u8(0);
+                packet.put_u32_le(0);
+                packet.put_u8(time.hour() as u8);
+                packet.put_u8(time.minute() as u8);
+                packet.put_u8(time.second() as u8);
+                if micros != 0 {
+                    packet.put_u32_le(micros);
+                }
+            }
+            (_, value) => {
+                let bytes = text_bytes(value);
+                put_lenenc_int(packet, bytes.len() as u64);
+                packet.put_slice(&bytes);
+            }
+        }
+    }
+}
+
+fn put_date(packet: &mut BytesMut, date: &NaiveDate) {
+    packet.put_u16_le(date.year() as u16);
+    packet.put_u8(date.month() as u8);
+    packet.put_u8(date.day() as u8);
+}
+
+/// The parameters of a COM_STMT_EXECUTE, read from the packet after its iteration count.
+/// Clients only send the parameter types when they differ from the last execution's, so
+/// `types` keeps them between executions. Values sent ahead in `long_data` are taken from it.
+pub(crate) fn read_parameters(
+    data: &[u8],
+    count: usize,
+    types: &mut Vec<(u8, bool)>,
+    long_data: &mut HashMap<usize, Vec<u8>>,
+) -> crate::Result<Vec<Value>> {
+    if count == 0 {
+        return Ok(Vec::new());
+    }
+    let mut reader = Reader { data };
+    let null_bitmap = reader.take(count.div_ceil(8))?;
+    if reader.u8()? == 1 {
+        *types = (0..count)
+            .map(|_| Ok((reader.u8()?, reader.u8()? & UNSIGNED_PARAMETER != 0)))
+            .collect::<crate::Result<_>>()?;
+    }
+    if types.len() != count {
+        return Err(malformed());
+    }
+
+    (0..count)
+        .map(|i| {
+            if null_bitmap[i / 8] & (1 << (i % 8)) != 0 {
+                return Ok(Value::Null);
+            }
+            if let Some(bytes) = long_data.remove(&i) {
+                return Ok(bytes_value(bytes));
+            }
+            let (column_type, unsigned) = types[i];
+            reader.value(column_type, unsigned)
+        })
+        .collect()
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, length: usize) -> crate::Result<&'a [u8]> {
+        if self.data.len() < length {
+            return Err(malformed());
+        }
+        let (taken, rest) = self.data.split_at(length);
+        self.data = rest;
+        Ok(taken)
+    }
+
+    fn array<const N: usize>(&mut self) -> crate::Result<[u8; N]> {
+        self.take(N)?.try_into().map_err(|_| malformed())
+    }
+
+    fn u8(&mut self) -> crate::Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn lenenc_int(&mut self) -> crate::Result<u64> {
+        Ok(match self.u8()? {
+            0xfc => u16::from_le_bytes(self.array()?) as u64,
+            0xfd => {
+                let [a, b, c] = self.array()?;
+                u32::from_le_bytes([a, b, c, 0]) as u64
+            }
+            0xfe => u64::from_le_bytes(self.array()?),
+            n => n as u64,
+        })
+    }
+
+    fn value(&mut self, column_type: u8, unsigned: bool) -> crate::Result<Value> {
+        Ok(match column_type {
+            MYSQL_TYPE_NULL => Value::Null,
+            MYSQL_TYPE_TINY => {
+                let [n] = self.array()?;
+                Value::Integer(if unsigned { n as i64 } else { n as i8 as i64 })
+            }
+            MYSQL_TYPE_SHORT | MYSQL_TYPE_YEAR => {
+                let bytes = self.array()?;
+                Value::Integer(if unsigned {
+                    u16::from_le_bytes(bytes) as i64
+                } else {
+                    i16::from_le_bytes(bytes) as i64
+                })
+            }
+            MYSQL_TYPE_LONG | MYSQL_TYPE_INT24 => {
+                let bytes = self.array()?;
+                Value::Integer(if unsigned {
+                    u32::from_le_bytes(bytes) as i64
+                } else {
+                    i32::from_le_bytes(bytes) as i64
+                })
+            }
+            MYSQL_TYPE_LONGLONG => {
+                let bytes = self.array()?;
+                if unsigned {
+                    let n = u64::from_le_bytes(bytes);
+                    i64::try_from(n)
+                        .map(Value::Integer)
+                        .unwrap_or_else(|_| Value::Decimal(Decimal::from(n)))
+                } else {
+                    Value::Integer(i64::from_le_bytes(bytes))
+                }
+            }
+            MYSQL_TYPE_FLOAT => Value::Float(f32::from_le_bytes(self.array()?)),
+            MYSQL_TYPE_DOUBLE => Value::Double(f64::from_le_bytes(self.array()?)),
+            MYSQL_TYPE_DATE | MYSQL_TYPE_DATETIME | MYSQL_TYPE_TIMESTAMP => self.datetime()?,
+            MYSQL_TYPE_TIME => self.time()?,
+            MYSQL_TYPE_DECIMAL | MYSQL_TYPE_NEWDECIMAL => match self.lenenc_bytes()? {
+                Value::Text(text) => text
+                    .parse()
+                    .map(Value::Decimal)
+                    .unwrap_or(Value::Text(text)),
+                value => value,
+            },
+            _ => self.lenenc_bytes()?,
+        })
+    }
+
+    fn lenenc_bytes(&mut self) -> crate::Result<Value> {
+        let length = usize::try_from(self.lenenc_int()?).map_err(|_| malformed())?;
+        Ok(bytes_value(self.take(length)?.to_vec()))
+    }
+
+    /// A date, or a timestamp when the value has a time of day
+    fn datetime(&mut self) -> crate::Result<Value> {
+        let length = self.u8()? as usize;
+        let fields = self.take(length)?;
+        // The zero date, which has no NaiveDate
+        if length == 0 {
+            return Ok(Value::Null);
+        }
+        if length < 4 {
+            return Err(malformed());
+        }
+        let year = u16::from_le_bytes([fields[0], fields[1]]) as i32;
+        let date = NaiveDate::from_ymd_opt(year, fields[2] as u32, fields[3] as u32)
+            .ok_or_else(malformed)?;
+        if length == 4 {
+            return Ok(Value::Date(date));
+        }
+        if length < 7 {
+            return Err(malformed());
+        }
+        let micros = match fields.get(7..11) {
+            Some(micros) => u32::from_le_bytes(micros.try_into().map_err(|_| malformed())?),
+            None => 0,
+        };
+        let time = NaiveTime::from_hms_micro_opt(
+            fields[4] as u32,
+            fields[5] as u32,
+            fields[6] as u32,
+            micros,
+        )
+        .ok_or_else(malformed)?;
+        Ok(Value::Timestamp(NaiveDateTime::new(date, time)))
+    }
+
+    /// A time of day, or text for the negative and multi-day intervals MySQL's TIME holds
+    fn time(&mut self) -> crate::Result<Value> {
+        let length = self.u8()? as usize;
+        let fields = self.take(length)?;
+        if length == 0 {
+            return Ok(Value::Time(NaiveTime::default()));
+        }
+        if length < 8 {
+            return Err(malformed());
+        }
+        let negative = fields[0] == 1;
+        let days = u32::from_le_bytes([fields[1], fields[2], fields[3], fields[4]]);
+        let (hour, minute, second) = (fields[5] as u32, fields[6] as u32, fields[7] as u32);
+        let micros = match fields.get(8..12) {
+            Some(micros) => u32::from_le_bytes(micros.try_into().map_err(|_| malformed())?),
+            None => 0,
+        };
+        if negative || days > 0 {
+            let sign = if negative { "-" } else { "" };
+            return Ok(Value::Text(format!(
+                "{}{:02}:{:02}:{:02}.{:06}",
+                sign,
+                days * 24 + hour,
+                minute,
+                second,
+                micros
+            )));
+        }
+        NaiveTime::from_hms_micro_opt(hour, minute, second, micros)
+            .map(Value::Time)
+            .ok_or_else(malformed)
+    }
+}
+
+/// A string parameter, or bytes for one that is not UTF-8
+fn bytes_value(bytes: Vec<u8>) -> Value {
+    String::from_utf8(bytes)
+        .map(Value::Text)
+        .unwrap_or_else(|error| Value::Bytes(error.into_bytes()))
+}
+
+fn malformed() -> YamlBaseError {
+    YamlBaseError::Protocol("Malformed COM_STMT_EXECUTE packet".to_string())
+}
@@ -1,10 +1,15 @@
 use bytes::{BufMut, BytesMut};
+use rsa::pkcs8::{EncodePublicKey, LineEnding};
+use rsa::{Oaep, RsaPrivateKey, RsaPublicKey};
+use sha1::Sha1;
 use sha2::{Digest, Sha256};
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use tokio::net::TcpStream;
+use tokio::sync::OnceCell;
 use tracing::debug;
 
 use crate::YamlBaseError;
+use crate::protocol::password::Password;
+use crate::protocol::tls::Stream;
 
 // MySQL packet types
 const AUTH_MORE_DATA: u8 = 0x01;
@@ -13,6 +18,13 @@ const AUTH_SWITCH_REQUEST: u8 = 0xfe;
 // Caching SHA2 authentication states
 const FAST_AUTH_SUCCESS: u8 = 0x03;
 const PERFORM_FULL_AUTH: u8 = 0x04;
+const REQUEST_PUBLIC_KEY: u8 = 0x02;
+
+const RSA_KEY_BITS: usize = 2048;
+
+/// The key clients encrypt their password with on connections without TLS, generated when
+/// the first of them asks for it
+static RSA_KEY: OnceCell<RsaPrivateKey> = OnceCell::const_new();
 
 pub const CACHING_SHA2_PLUGIN_NAME: &str = "caching_sha2_password";
 
@@ -26,102 +38,104 @@ impl CachingSha2Auth {
         Self { auth_data }
     }
 
-    /// Handle the full caching_sha2_password authentication flow
-    #[allow(clippy::too_many_arguments)]
+    /// Handle the full caching_sha2_password authentication flow. A plain text password is
+    /// checked against the scramble the client answered the switch with (fast auth); a hash,
+    /// or a scramble that does not match, needs the password itself (full auth).
     pub async fn authenticate(
         &self,
-        stream: &mut TcpStream,
+        stream: &mut Stream,
         sequence_id: &mut u8,
-        username: &str,
-        password: &str,
-        expected_username: &str,
-        expected_password: &str,
+        password: Password<'_>,
         auth_response: Vec<u8>,
     ) -> crate::Result<bool> {
-        debug!(
-            "Starting caching_sha2_password authentication for user: {}",
-            username
-        );
-
-        // Verify username
-        if username != expected_username {
-            debug!("Username mismatch: {} != {}", username, expected_username);
-            return Ok(false);
-        }
-
-        // If client sent empty auth response, we need to request full authentication
-        if auth_response.is_empty() && !password.is_empty() {
-            debug!("Empty auth response, requesting full authentication");
-            self.send_auth_more_data(stream, sequence_id, PERFORM_FULL_AUTH)
-                .await?;
-
-            // Read the password
-            let password_packet = self.read_packet(stream, sequence_id).await?;
-            if password_packet.is_empty() {
-                debug!("Empty password packet received");
-                return Ok(false);
-            }
-
-            // Remove null terminator if present
-            let client_password = if password_packet.last() == Some(&0) {
-                std::str::from_utf8(&password_packet[..password_packet.len() - 1])
-                    .map_err(|_| YamlBaseError::Protocol("Invalid UTF-8 in password".to_string()))?
-            } else {
-                std::str::from_utf8(&password_packet)
-                    .map_err(|_| YamlBaseError::Protocol("Invalid UTF-8 in password".to_string()))?
-            };
-
-            debug!("Received password in clear text");
-            return Ok(client_password == expected_password);
-        }
-
-        // Compute expected auth response
-        let expected = compute_auth_response(expected_password, &self.auth_data);
-
-        // Check if auth response matches
-        if auth_response == expected {
+        if let Password::Plain(password) = password
+            && auth_response == compute_auth_response(password, &self.auth_data)
+        {
             debug!("Fast authentication successful");
-            // Send fast auth success
-            self.send_auth_more_data(stream, sequence_id, FAST_AUTH_SUCCESS)
+            self.send_auth_more_data(stream, sequence_id, &[FAST_AUTH_SUCCESS])
                 .await?;
             return Ok(true);
         }
 
-        // Auth failed, request full authentication
-        debug!("Fast authentication failed, requesting full authentication");
-        self.send_auth_more_data(stream, sequence_id, PERFORM_FULL_AUTH)
+        debug!("Requesting full authentication");
+        self.send_auth_more_data(stream, sequence_id, &[PERFORM_FULL_AUTH])
             .await?;
-
-        // Read the password in clear text
-        let password_packet = self.read_packet(stream, sequence_id).await?;
-        if password_packet.is_empty() {
-            debug!("Empty password packet received");
+        let Some(given) = self.read_full_auth_password(stream, sequence_id).await? else {
+            debug!("No password received");
             return Ok(false);
+        };
+        Ok(password.verify(&given))
+    }
+
+    /// The password of a full authentication. Over TLS the client sends it in plain text;
+    /// without, it asks for the server's public key and sends the password encrypted with it,
+    /// XORed with the scramble. Clients that send it in plain text anyway are accepted.
+    async fn read_full_auth_password(
+        &self,
+        stream: &mut Stream,
+        sequence_id: &mut u8,
+    ) -> crate::Result<Option<String>> {
+        let mut packet = self.read_packet(stream, sequence_id).await?;
+        if !matches!(stream, Stream::Tls(_)) {
+            if packet == [REQUEST_PUBLIC_KEY] {
+                debug!("Sending the RSA public key");
+                let public_key = RsaPublicKey::from(rsa_key().await?)
+                    .to_public_key_pem(LineEnding::LF)
+                    .map_err(|e| YamlBaseError::Protocol(format!("Invalid RSA key: {}", e)))?;
+                self.send_auth_more_data(stream, sequence_id, public_key.as_bytes())
+                    .await?;
+                packet = self.read_packet(stream, sequence_id).await?;
+                let Some(decrypted) = self.decrypt_password(&packet).await? else {
+                    return Ok(None);
+                };
+                packet = decrypted;
+            } else if packet.len() == RSA_KEY_BITS / 8 {
+                // Encrypted with a public key the client was configured with
+                let Some(decrypted) = self.decrypt_password(&packet).await? else {
+                    return Ok(None);
+                };
+                packet = decrypted;
+            }
         }
 
-        // Remove null terminator if present
-        let client_password = if password_packet.last() == Some(&0) {
-            std::str::from_utf8(&password_packet[..password_packet.len() - 1])
-                .map_err(|_| YamlBaseError::Protocol("Invalid UTF-8 in password".to_string()))?
-        } else {
-            std::str::from_utf8(&password_packet)
-                .map_err(|_| YamlBaseError::Protocol("Invalid UTF-8 in password".to_string()))?
-        };
+        let password = packet.strip_suffix(&[0]).unwrap_or(&packet);
+        if password.is_empty() {
+            return Ok(None);
+        }
+        String::from_utf8(password.to_vec())
+            .map(Some)
+            .map_err(|_| YamlBaseError::Protocol("Invalid UTF-8 in password".to_string()))
+    }
 
-        debug!("Checking clear text password");
-        Ok(client_password == expected_password)
+    /// Decrypt a password encrypted with the public key, undoing the XOR with the scramble.
+    /// A password that does not decrypt has none.
+    async fn decrypt_password(&self, encrypted: &[u8]) -> crate::Result<Option<Vec<u8>>> {
+        let decrypted = match rsa_key().await?.decrypt(Oaep::new::<Sha1>(), encrypted) {
+            Ok(decrypted) => decrypted,
+            Err(e) => {
+                debug!("Cannot decrypt the password: {}", e);
+                return Ok(None);
+            }
+        };
+        Ok(Some(
+            decrypted
+                .iter()
+                .zip(self.auth_data.iter().cycle())
+                .map(|(byte, scramble)| byte ^ scramble)
+                .collect(),
+        ))
     }
 
     /// Send an auth more data packet
     async fn send_auth_more_data(
         &self,
-        stream: &mut TcpStream,
+        stream: &mut Stream,
         sequence_id: &mut u8,
-        status: u8,
+        data: &[u8],
     ) -> crate::Result<()> {
         let mut packet = BytesMut::new();
         packet.put_u8(AUTH_MORE_DATA);
-        packet.put_u8(status);
+        packet.put_slice(data);
 
         self.write_packet(stream, sequence_id, &packet).await
     }
@@ -129,7 +143,7 @@ impl CachingSha2Auth {
     /// Send an auth switch request
     pub async fn send_auth_switch_request(
         &self,
-        stream: &mut TcpStream,
+        stream: &mut Stream,
         sequence_id: &mut u8,
     ) -> crate::Result<()> {
         debug!("Sending auth switch request for caching_sha2_password");
@@ -146,7 +160,7 @@ impl CachingSha2Auth {
 
     async fn write_packet(
         &self,
-        stream: &mut TcpStream,
+        stream: &mut Stream,
         sequence_id: &mut u8,
         payload: &[u8],
     ) -> crate::Result<()> {
@@ -180,7 +194,7 @@ impl CachingSha2Auth {
 
     async fn read_packet(
         &self,
-        stream: &mut TcpStream,
+        stream: &mut Stream,
         sequence_id: &mut u8,
     ) -> crate::Result<Vec<u8>> {
         let mut header = [0u8; 4];
@@ -203,6 +217,20 @@ impl CachingSha2Auth {
     }
 }
 
+async fn rsa_key() -> crate::Result<&'static RsaPrivateKey> {
+    RSA_KEY
+        .get_or_try_init(|| async {
+            tokio::task::spawn_blocking(|| {
+                RsaPrivateKey::new(&mut rand::thread_rng(), RSA_KEY_BITS).map_err(|e| {
+                    YamlBaseError::Protocol(format!("Cannot generate an RSA key: {}", e))
+                })
+            })
+            .await
+            .map_err(|e| YamlBaseError::Protocol(format!("Cannot generate an RSA key: {}", e)))?
+        })
+        .await
+}
+
 /// Compute the caching_sha2_password auth response
 pub fn compute_auth_response(password: &str, auth_data: &[u8]) -> Vec<u8> {
     if password.is_empty() {
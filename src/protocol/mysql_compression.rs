@@ -0,0 +1,137 @@
+// Compressed MySQL connections, which clients ask for with CLIENT_COMPRESS (zlib) or
+// CLIENT_ZSTD_COMPRESSION_ALGORITHM. Once the client has been sent the OK of its login, packets
+// travel inside compressed packets: a header with the compressed length, a sequence id of
+// their own and the uncompressed length, 0 for a payload too short to be worth compressing.
+use flate2::Compression as ZlibLevel;
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use std::io::{Read, Write};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+use crate::YamlBaseError;
+use crate::protocol::tls::Stream;
+
+pub const CLIENT_COMPRESS: u32 = 0x00000020;
+pub const CLIENT_ZSTD_COMPRESSION_ALGORITHM: u32 = 0x04000000;
+
+/// Payloads shorter than this are sent uncompressed
+const MIN_COMPRESS_LENGTH: usize = 50;
+
+/// The largest payload of a compressed packet, whose lengths are three bytes
+const MAX_PAYLOAD_LENGTH: usize = 0xffffff;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Algorithm {
+    Zlib,
+    /// With the level the client asked for
+    Zstd(i32),
+}
+
+#[derive(Debug)]
+pub struct Compression {
+    algorithm: Algorithm,
+    sequence_id: u8,
+    /// Uncompressed bytes read that the connection has not taken yet
+    inbound: Vec<u8>,
+}
+
+impl Compression {
+    /// The compression a client asked for with the capabilities of its handshake response.
+    /// The zstd level is the last byte of the response, after the fields this server reads.
+    pub fn negotiate(client_flags: u32, handshake_response: &[u8]) -> Option<Self> {
+        let algorithm = if client_flags & CLIENT_ZSTD_COMPRESSION_ALGORITHM != 0 {
+            Algorithm::Zstd(handshake_response.last().copied().unwrap_or(3) as i32)
+        } else if client_flags & CLIENT_COMPRESS != 0 {
+            Algorithm::Zlib
+        } else {
+            return None;
+        };
+        Some(Self {
+            algorithm,
+            sequence_id: 0,
+            inbound: Vec::new(),
+        })
+    }
+
+    /// Fill `buf` with the uncompressed bytes that come next, reading compressed packets as
+    /// needed. The next packet sent continues the sequence of the last one read.
+    pub async fn read_exact(&mut self, stream: &mut Stream, buf: &mut [u8]) -> crate::Result<()> {
+        while self.inbound.len() < buf.len() {
+            let mut header = [0u8; 7];
+            stream.read_exact(&mut header).await?;
+            let length = u32::from_le_bytes([header[0], header[1], header[2], 0]) as usize;
+            let uncompressed_length = u32::from_le_bytes([header[4], header[5], header[6], 0]);
+            self.sequence_id = header[3].wrapping_add(1);
+
+            let mut payload = vec![0u8; length];
+            stream.read_exact(&mut payload).await?;
+            if uncompressed_length == 0 {
+                self.inbound.extend_from_slice(&payload);
+            } else {
+                let uncompressed = self.decompress(&payload, uncompressed_length as usize)?;
+                self.inbound.extend_from_slice(&uncompressed);
+            }
+        }
+        buf.copy_from_slice(&self.inbound[..buf.len()]);
+        self.inbound.drain(..buf.len());
+        Ok(())
+    }
+
+    /// Send packets, with their headers, in as many compressed packets as they need
+    pub async fn write_all(&mut self, stream: &mut Stream, data: &[u8]) -> crate::Result<()> {
+        for chunk in data.chunks(MAX_PAYLOAD_LENGTH) {
+            let compressed = if chunk.len() < MIN_COMPRESS_LENGTH {
+                None
+            } else {
+                Some(self.compress(chunk)?)
+            };
+            let (payload, uncompressed_length) = match &compressed {
+                Some(compressed) => (compressed.as_slice(), chunk.len()),
+                None => (chunk, 0),
+            };
+
+            let mut packet = Vec::with_capacity(7 + payload.len());
+            packet.extend_from_slice(&(payload.len() as u32).to_le_bytes()[..3]);
+            packet.push(self.sequence_id);
+            packet.extend_from_slice(&(uncompressed_length as u32).to_le_bytes()[..3]);
+            packet.extend_from_slice(payload);
+            self.sequence_id = self.sequence_id.wrapping_add(1);
+
+            stream.write_all(&packet).await?;
+        }
+        stream.flush().await?;
+        Ok(())
+    }
+
+    fn compress(&self, data: &[u8]) -> crate::Result<Vec<u8>> {
+        match self.algorithm {
+            Algorithm::Zlib => {
+                let mut encoder = ZlibEncoder::new(Vec::new(), ZlibLevel::default());
+                encoder.write_all(data)?;
+                Ok(encoder.finish()?)
+            }
+            Algorithm::Zstd(level) => Ok(zstd::bulk::compress(data, level)?),
+        }
+    }
+
+    fn decompress(&self, data: &[u8], uncompressed_length: usize) -> crate::Result<Vec<u8>> {
+        let uncompressed = match self.algorithm {
+            Algorithm::Zlib => {
+                // One byte past the declared length is enough to tell the packet lies about
+                // it, without inflating whatever it expands to
+                let mut uncompressed = Vec::with_capacity(uncompressed_length);
+                ZlibDecoder::new(data)
+                    .take(uncompressed_length as u64 + 1)
+                    .read_to_end(&mut uncompressed)?;
+                uncompressed
+            }
+            Algorithm::Zstd(_) => zstd::bulk::decompress(data, uncompressed_length)?,
+        };
+        if uncompressed.len() != uncompressed_length {
+            return Err(YamlBaseError::Protocol(
+                "Compressed packet does not have its uncompressed length".to_string(),
+            ));
+        }
+        Ok(uncompressed)
+    }
+}
@@ -1,26 +1,63 @@
 use bytes::{BufMut, BytesMut};
 use sha1::{Digest, Sha1};
+use sqlparser::ast::{Expr, KillType, Statement, Value as SqlValue, visit_expressions};
+use std::collections::HashMap;
+use std::ops::ControlFlow;
 use std::sync::Arc;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::TcpStream;
+use tokio::sync::Notify;
 use tracing::{debug, info};
 
 use crate::YamlBaseError;
 use crate::config::Config;
-use crate::database::Storage;
+use crate::database::{Databases, Value};
+use crate::protocol::cancel::Cancellations;
+use crate::protocol::mysql_binary::{
+    MYSQL_TYPE_BLOB, MYSQL_TYPE_JSON, MYSQL_TYPE_VAR_STRING, column_definition, column_type,
+    put_row, read_parameters, text_bytes,
+};
 use crate::protocol::mysql_caching_sha2::{CACHING_SHA2_PLUGIN_NAME, CachingSha2Auth};
-use crate::sql::{QueryExecutor, parse_sql};
+use crate::protocol::mysql_compression::{
+    CLIENT_COMPRESS, CLIENT_ZSTD_COMPRESSION_ALGORITHM, Compression,
+};
+use crate::protocol::password::Password;
+use crate::protocol::timeouts::{LoginSignal, next_request};
+use crate::protocol::tls::{CertificateLogin, Stream, TlsAcceptor, certificate_login};
+use crate::server::rate_limit::{ClientRateLimiter, Quota};
+use crate::sql::executor::QueryResult;
+use crate::sql::{
+    MYSQL_VERSION, QueryExecutor, SqlDialect, is_reload_database, parse_refresh_materialized_view,
+    parse_set_system_variables, parse_sql, parse_sql_with_dialect, shown_database,
+    split_statements,
+};
+use crate::yaml::Grants;
+use crate::yaml::schema::SqlType;
 
 // MySQL Protocol Constants
 const PROTOCOL_VERSION: u8 = 10;
-const SERVER_VERSION: &str = "8.0.35-yamlbase";
+const SERVER_VERSION: &str = MYSQL_VERSION;
 const AUTH_PLUGIN_NAME: &str = "mysql_native_password";
+const CLEAR_PASSWORD_PLUGIN_NAME: &str = "mysql_clear_password";
 
 // Command bytes
 const COM_QUIT: u8 = 0x01;
 const COM_INIT_DB: u8 = 0x02;
 const COM_QUERY: u8 = 0x03;
 const COM_PING: u8 = 0x0e;
+const COM_STMT_PREPARE: u8 = 0x16;
+const COM_STMT_EXECUTE: u8 = 0x17;
+const COM_STMT_SEND_LONG_DATA: u8 = 0x18;
+const COM_STMT_CLOSE: u8 = 0x19;
+const COM_STMT_RESET: u8 = 0x1a;
+const COM_SET_OPTION: u8 = 0x1b;
+
+/// ER_CON_COUNT_ERROR, sent to clients beyond --max-connections
+const ER_CON_COUNT_ERROR: u16 = 1040;
+
+// The options of COM_SET_OPTION
+const MYSQL_OPTION_MULTI_STATEMENTS_ON: u16 = 0;
+const MYSQL_OPTION_MULTI_STATEMENTS_OFF: u16 = 1;
 
 // Capability flags
 const CLIENT_LONG_PASSWORD: u32 = 0x00000001;
@@ -28,85 +65,212 @@ const CLIENT_FOUND_ROWS: u32 = 0x00000002;
 const CLIENT_LONG_FLAG: u32 = 0x00000004;
 const CLIENT_CONNECT_WITH_DB: u32 = 0x00000008;
 const CLIENT_PROTOCOL_41: u32 = 0x00000200;
+const CLIENT_SSL: u32 = 0x00000800;
 const CLIENT_SECURE_CONNECTION: u32 = 0x00008000;
+const CLIENT_MULTI_STATEMENTS: u32 = 0x00010000;
+const CLIENT_MULTI_RESULTS: u32 = 0x00020000;
 const CLIENT_PLUGIN_AUTH: u32 = 0x00080000;
 const _CLIENT_DEPRECATE_EOF: u32 = 0x01000000;
 
-// Column types
-const MYSQL_TYPE_VAR_STRING: u8 = 253;
+/// The length of an SSL request: capabilities, maximum packet size, character set and filler
+const SSL_REQUEST_LENGTH: usize = 32;
 
 // Status flags
+const SERVER_STATUS_IN_TRANS: u16 = 0x0001;
 const SERVER_STATUS_AUTOCOMMIT: u16 = 0x0002;
+const SERVER_MORE_RESULTS_EXISTS: u16 = 0x0008;
 
 pub struct MySqlProtocol {
     config: Arc<Config>,
+    databases: Databases,
+    // The position of the current database in `databases`, and its session
+    database: usize,
     executor: QueryExecutor,
-    _database_name: String,
+    // The sessions of the databases the connection used before
+    other_sessions: HashMap<usize, QueryExecutor>,
+    // The tables the user of the connection is limited to, if it is
+    grants: Option<Grants>,
+    // The rate limits the user's statements count against
+    quota: Option<Arc<Quota>>,
+    tls: Option<TlsAcceptor>,
+    cancellations: Cancellations,
+    // Notified by a KILL QUERY of the connection, in the sessions of every database
+    cancel_signal: Arc<Notify>,
+    // The statements prepared with COM_STMT_PREPARE, by the id the client was given
+    prepared_statements: HashMap<u32, PreparedStatement>,
+    next_statement_id: u32,
+    login: LoginSignal,
+    rate_limiter: ClientRateLimiter,
+}
+
+/// A statement prepared with COM_STMT_PREPARE
+struct PreparedStatement {
+    /// `None` for a SET the connection ignores
+    statement: Option<Statement>,
+    parameter_count: usize,
+    /// The parameter types of the last execution, which clients only send when they change
+    parameter_types: Vec<(u8, bool)>,
+    /// Parameter values sent ahead of the execution with COM_STMT_SEND_LONG_DATA
+    long_data: HashMap<usize, Vec<u8>>,
 }
 
 struct ConnectionState {
     sequence_id: u8,
-    _capabilities: u32,
+    connection_id: u32,
+    capabilities: u32,
     auth_data: Vec<u8>,
     client_auth_plugin: Option<String>,
+    // Set while sending a result that is followed by another of the same query
+    more_results: bool,
+    // Set once the login is done, for clients that asked for compression
+    compression: Option<Compression>,
 }
 
 impl Default for ConnectionState {
     fn default() -> Self {
         Self {
             sequence_id: 0,
-            _capabilities: 0,
+            connection_id: 0,
+            capabilities: 0,
             auth_data: generate_auth_data(),
             client_auth_plugin: None,
+            more_results: false,
+            compression: None,
         }
     }
 }
 
 impl MySqlProtocol {
-    pub async fn new(config: Arc<Config>, storage: Arc<Storage>) -> crate::Result<Self> {
-        let executor = QueryExecutor::new(storage).await?;
+    pub async fn new(config: Arc<Config>, databases: Databases) -> crate::Result<Self> {
+        let executor = Self::executor(&config, &databases, 0).await?;
         Ok(Self {
             config,
+            databases,
+            database: 0,
             executor,
-            _database_name: String::new(), // Will be set later if needed
+            other_sessions: HashMap::new(),
+            grants: None,
+            quota: None,
+            tls: None,
+            cancellations: Cancellations::new(),
+            cancel_signal: Arc::new(Notify::new()),
+            prepared_statements: HashMap::new(),
+            next_statement_id: 1,
+            login: LoginSignal::default(),
+            rate_limiter: ClientRateLimiter::default(),
         })
     }
 
-    pub async fn handle_connection(&self, mut stream: TcpStream) -> crate::Result<()> {
-        info!("New MySQL connection");
+    /// Offer TLS in the handshake and upgrade the connections of clients that ask for it
+    pub fn with_tls(mut self, tls: Option<TlsAcceptor>) -> Self {
+        self.tls = tls;
+        self
+    }
 
-        let mut state = ConnectionState::default();
+    /// Register the connection where KILL QUERY looks for it, and let it kill queries
+    pub fn with_cancellations(mut self, cancellations: Cancellations) -> Self {
+        self.cancellations = cancellations;
+        self
+    }
 
-        // Send initial handshake
-        self.send_handshake(&mut stream, &mut state).await?;
+    /// Tell `login` once the client has logged in, for --connect-timeout
+    pub fn with_login_signal(mut self, login: LoginSignal) -> Self {
+        self.login = login;
+        self
+    }
 
-        // Read handshake response
-        let response_packet = self.read_packet(&mut stream, &mut state).await?;
-        let (username, auth_response, _database, client_plugin) =
-            self.parse_handshake_response(&response_packet)?;
-        state.client_auth_plugin = client_plugin;
+    /// Count the client's statements against its rate limits
+    pub fn with_rate_limiter(mut self, rate_limiter: ClientRateLimiter) -> Self {
+        self.rate_limiter = rate_limiter;
+        self
+    }
 
-        // Simple authentication check
-        debug!(
-            "Authentication check - username: {}, expected: {}",
-            username, self.config.username
-        );
-        if username != self.config.username {
-            debug!("Username mismatch");
-            self.send_error(&mut stream, &mut state, 1045, "28000", "Access denied")
-                .await?;
-            return Ok(());
+    /// An executor for a session on one of the databases
+    async fn executor(
+        config: &Config,
+        databases: &Databases,
+        index: usize,
+    ) -> crate::Result<QueryExecutor> {
+        let mut executor = QueryExecutor::new(databases.get(index).clone())
+            .await?
+            .with_dialect(SqlDialect::MySQL);
+        if let Some(seed) = config.random_seed {
+            executor = executor.with_random_seed(seed);
         }
+        let names = databases
+            .iter()
+            .map(|storage| storage.name().to_string())
+            .collect();
+        Ok(executor
+            .with_databases(names)
+            .with_recursion_limits(config.recursion_limits())
+            .with_timeout(config.statement_timeout())
+            .with_collation(config.collation()?)
+            .with_write_mode(config.write_mode(&config.files[index])))
+    }
 
-        // Verify password
-        let expected = compute_auth_response(&self.config.password, &state.auth_data);
-        debug!(
-            "Password check - auth_response len: {}, expected len: {}, config password: {}",
-            auth_response.len(),
-            expected.len(),
-            self.config.password
-        );
+    /// Switch to the named database, as `USE` and COM_INIT_DB do, returning whether it
+    /// exists. Each database keeps its own session, so the changes a connection made to
+    /// one are still there when it comes back to it. A schema of the current database
+    /// is used like a database, by looking unqualified table names up in it.
+    async fn use_database(&mut self, name: &str) -> crate::Result<bool> {
+        let Some(index) = self.databases.find(name) else {
+            let db_arc = self.executor.storage().database();
+            if !db_arc.read().await.has_schema(name) {
+                return Ok(false);
+            }
+            self.executor.set_search_path(vec![name.to_string()]);
+            return Ok(true);
+        };
+        if index != self.database {
+            let executor = match self.other_sessions.remove(&index) {
+                Some(executor) => executor,
+                None => self.new_session(index).await?,
+            };
+            let previous = std::mem::replace(&mut self.executor, executor);
+            self.other_sessions.insert(self.database, previous);
+            self.database = index;
+            debug!("Using database {}", self.databases.get(index).name());
+        }
+        Ok(true)
+    }
+
+    /// A session of the connection's user on one of the databases
+    async fn new_session(&self, index: usize) -> crate::Result<QueryExecutor> {
+        Ok(Self::executor(&self.config, &self.databases, index)
+            .await?
+            .with_grants(self.grants.clone())
+            .with_quota(self.quota.clone())
+            .with_cancel_signal(Arc::clone(&self.cancel_signal))
+            .with_settings_of(&self.executor))
+    }
+
+    /// Execute a statement in the current session, or a SHOW that names another of the
+    /// databases in the session on that database, which USE would switch to
+    async fn execute(&mut self, statement: &Statement) -> crate::Result<QueryResult> {
+        let index = shown_database(statement)
+            .and_then(|name| self.databases.find(&name))
+            .unwrap_or(self.database);
+        if index == self.database {
+            return self.executor.execute(statement).await;
+        }
+        if !self.other_sessions.contains_key(&index) {
+            let executor = self.new_session(index).await?;
+            self.other_sessions.insert(index, executor);
+        }
+        self.other_sessions[&index].execute(statement).await
+    }
 
+    /// Check the password of the handshake, by mysql_native_password, by switching to
+    /// caching_sha2_password when the client asked for it, or by switching to
+    /// mysql_clear_password for a hash
+    async fn check_password(
+        &self,
+        stream: &mut Stream,
+        state: &mut ConnectionState,
+        password: Password<'_>,
+        auth_response: Vec<u8>,
+    ) -> crate::Result<bool> {
         // Check if client requested caching_sha2_password
         let client_wants_caching = state
             .client_auth_plugin
@@ -114,62 +278,139 @@ impl MySqlProtocol {
             .map(|p| p == CACHING_SHA2_PLUGIN_NAME)
             .unwrap_or(false);
 
-        if client_wants_caching || auth_response.is_empty() {
-            // Switch to caching_sha2_password
-            debug!("Client requested caching_sha2_password or sent empty auth");
+        match password {
+            Password::Plain(password) if !client_wants_caching && !auth_response.is_empty() => {
+                // Use mysql_native_password authentication
+                let expected = compute_auth_response(password, &state.auth_data);
+                if auth_response != expected {
+                    debug!(
+                        "Password mismatch - expected: {:?}, got: {:?}",
+                        expected, auth_response
+                    );
+                }
+                Ok(auth_response == expected)
+            }
+            // A hash can only be checked against the password itself, which the client sends
+            // after switching to mysql_clear_password
+            Password::Hash(_) if !client_wants_caching => {
+                let given = self.read_clear_password(stream, state).await?;
+                Ok(password.verify(&given))
+            }
+            _ => {
+                debug!("Client requested caching_sha2_password or sent empty auth");
 
-            // Generate new auth data for caching_sha2
-            let caching_auth_data = generate_auth_data();
-            let caching_auth = CachingSha2Auth::new(caching_auth_data.clone());
+                // Generate new auth data for caching_sha2
+                let caching_auth = CachingSha2Auth::new(generate_auth_data());
+                caching_auth
+                    .send_auth_switch_request(stream, &mut state.sequence_id)
+                    .await?;
 
-            // Send auth switch request
-            caching_auth
-                .send_auth_switch_request(&mut stream, &mut state.sequence_id)
-                .await?;
+                // Read client's response to auth switch
+                let auth_switch_response = self.read_packet(stream, state).await?;
+                caching_auth
+                    .authenticate(
+                        stream,
+                        &mut state.sequence_id,
+                        password,
+                        auth_switch_response,
+                    )
+                    .await
+            }
+        }
+    }
 
-            // Read client's response to auth switch
-            let auth_switch_response = self.read_packet(&mut stream, &mut state).await?;
-
-            // Authenticate using caching_sha2_password
-            let auth_success = caching_auth
-                .authenticate(
-                    &mut stream,
-                    &mut state.sequence_id,
-                    &username,
-                    "", // password will be sent in clear text
-                    &self.config.username,
-                    &self.config.password,
-                    auth_switch_response,
-                )
+    /// Switch to mysql_clear_password and read the password the client sends
+    async fn read_clear_password(
+        &self,
+        stream: &mut Stream,
+        state: &mut ConnectionState,
+    ) -> crate::Result<String> {
+        let mut packet = BytesMut::new();
+        packet.put_u8(0xFE); // Auth switch request
+        packet.put_slice(CLEAR_PASSWORD_PLUGIN_NAME.as_bytes());
+        packet.put_u8(0);
+        self.write_packet(stream, state, &packet).await?;
+
+        let response = self.read_packet(stream, state).await?;
+        let password = response.strip_suffix(&[0]).unwrap_or(&response);
+        String::from_utf8(password.to_vec())
+            .map_err(|_| YamlBaseError::Protocol("Invalid UTF-8 in password".to_string()))
+    }
+
+    pub async fn handle_connection(&mut self, stream: TcpStream) -> crate::Result<()> {
+        info!("New MySQL connection");
+
+        let mut state = ConnectionState::default();
+        let cancel_key = self.cancellations.register();
+        state.connection_id = cancel_key.id();
+        self.cancel_signal = cancel_key.signal();
+
+        // Send initial handshake
+        let mut stream = Stream::Plain(stream);
+        self.send_handshake(&mut stream, &mut state).await?;
+
+        // Read handshake response
+        let (mut stream, response_packet) =
+            self.read_handshake_response(stream, &mut state).await?;
+        let (username, auth_response, database, client_plugin) =
+            self.parse_handshake_response(&response_packet)?;
+        state.client_auth_plugin = client_plugin;
+
+        // Simple authentication check
+        debug!("Authentication check - username: {}", username);
+        let config = Arc::clone(&self.config);
+        let Some((password, grants)) = config.user(&username) else {
+            debug!("Unknown user");
+            self.send_error(&mut stream, &mut state, 1045, "28000", "Access denied")
                 .await?;
+            return Ok(());
+        };
 
-            if !auth_success {
-                self.send_error(&mut stream, &mut state, 1045, "28000", "Access denied")
-                    .await?;
-                return Ok(());
-            }
-        } else {
-            // Use mysql_native_password authentication
-            if auth_response != expected {
-                debug!(
-                    "Password mismatch - expected: {:?}, got: {:?}",
-                    expected, auth_response
-                );
-                self.send_error(&mut stream, &mut state, 1045, "28000", "Access denied")
-                    .await?;
-                return Ok(());
+        let authenticated = match certificate_login(&config, &stream, &username) {
+            CertificateLogin::Accepted => true,
+            CertificateLogin::Refused => false,
+            CertificateLogin::Password => {
+                self.check_password(&mut stream, &mut state, password, auth_response)
+                    .await?
             }
+        };
+        if !authenticated {
+            self.send_error(&mut stream, &mut state, 1045, "28000", "Access denied")
+                .await?;
+            return Ok(());
+        }
+
+        self.grants = grants.cloned();
+        self.quota = self.rate_limiter.quota(Some(&username));
+        self.executor = self
+            .executor
+            .clone()
+            .with_grants(self.grants.clone())
+            .with_quota(self.quota.clone())
+            .with_cancel_signal(Arc::clone(&self.cancel_signal));
+
+        if let Some(name) = database.filter(|name| !name.is_empty())
+            && !self.use_database(&name).await?
+        {
+            let message = format!("Unknown database '{}'", name);
+            self.send_error(&mut stream, &mut state, 1049, "42000", &message)
+                .await?;
+            return Ok(());
         }
 
         // Send OK packet
         self.send_ok(&mut stream, &mut state, 0, 0).await?;
+        state.compression = Compression::negotiate(state.capabilities, &response_packet);
+        self.login.complete();
         info!("MySQL authentication successful, entering command loop");
 
         // Main command loop
+        let idle_timeout = self.config.idle_timeout;
         loop {
-            let packet = match self.read_packet(&mut stream, &mut state).await {
-                Ok(p) => p,
-                Err(_) => break,
+            let read = self.read_packet(&mut stream, &mut state);
+            let packet = match next_request(idle_timeout, read).await {
+                Some(Ok(p)) => p,
+                Some(Err(_)) | None => break,
             };
 
             if packet.is_empty() {
@@ -184,6 +425,74 @@ impl MySqlProtocol {
                     })?;
                     self.handle_query(&mut stream, &mut state, query).await?;
                 }
+                COM_STMT_PREPARE => {
+                    let query = std::str::from_utf8(&packet[1..]).map_err(|_| {
+                        YamlBaseError::Protocol("Invalid UTF-8 in query".to_string())
+                    })?;
+                    self.prepare(&mut stream, &mut state, query).await?;
+                }
+                COM_STMT_EXECUTE => {
+                    self.execute_prepared(&mut stream, &mut state, &packet[1..])
+                        .await?;
+                }
+                // Neither COM_STMT_SEND_LONG_DATA nor COM_STMT_CLOSE is answered
+                COM_STMT_SEND_LONG_DATA => {
+                    if let Some(prepared) = statement_id(&packet[1..])
+                        .and_then(|id| self.prepared_statements.get_mut(&id))
+                        && let Some(&[low, high]) = packet.get(5..7)
+                    {
+                        prepared
+                            .long_data
+                            .entry(u16::from_le_bytes([low, high]) as usize)
+                            .or_default()
+                            .extend_from_slice(&packet[7..]);
+                    }
+                }
+                COM_STMT_CLOSE => {
+                    if let Some(id) = statement_id(&packet[1..]) {
+                        self.prepared_statements.remove(&id);
+                    }
+                }
+                COM_STMT_RESET => {
+                    match statement_id(&packet[1..])
+                        .and_then(|id| self.prepared_statements.get_mut(&id))
+                    {
+                        Some(prepared) => {
+                            prepared.long_data.clear();
+                            self.send_ok(&mut stream, &mut state, 0, 0).await?;
+                        }
+                        None => {
+                            self.send_unknown_statement(&mut stream, &mut state, &packet[1..])
+                                .await?;
+                        }
+                    }
+                }
+                // Turns multi-statement queries on or off, answered with an EOF
+                COM_SET_OPTION => {
+                    match packet
+                        .get(1..3)
+                        .map(|option| u16::from_le_bytes([option[0], option[1]]))
+                    {
+                        Some(MYSQL_OPTION_MULTI_STATEMENTS_ON) => {
+                            state.capabilities |= CLIENT_MULTI_STATEMENTS;
+                            self.send_eof(&mut stream, &mut state).await?;
+                        }
+                        Some(MYSQL_OPTION_MULTI_STATEMENTS_OFF) => {
+                            state.capabilities &= !CLIENT_MULTI_STATEMENTS;
+                            self.send_eof(&mut stream, &mut state).await?;
+                        }
+                        _ => {
+                            self.send_error(
+                                &mut stream,
+                                &mut state,
+                                1047,
+                                "08S01",
+                                "Unknown command",
+                            )
+                            .await?;
+                        }
+                    }
+                }
                 COM_QUIT => {
                     info!("Client disconnected");
                     break;
@@ -192,10 +501,16 @@ impl MySqlProtocol {
                     self.send_ok(&mut stream, &mut state, 0, 0).await?;
                 }
                 COM_INIT_DB => {
-                    let _db_name = std::str::from_utf8(&packet[1..]).map_err(|_| {
+                    let db_name = std::str::from_utf8(&packet[1..]).map_err(|_| {
                         YamlBaseError::Protocol("Invalid UTF-8 in database name".to_string())
                     })?;
-                    self.send_ok(&mut stream, &mut state, 0, 0).await?;
+                    if self.use_database(db_name).await? {
+                        self.send_ok(&mut stream, &mut state, 0, 0).await?;
+                    } else {
+                        let message = format!("Unknown database '{}'", db_name);
+                        self.send_error(&mut stream, &mut state, 1049, "42000", &message)
+                            .await?;
+                    }
                 }
                 _ => {
                     debug!("Unhandled command: 0x{:02x}", command);
@@ -210,7 +525,7 @@ impl MySqlProtocol {
 
     async fn send_handshake(
         &self,
-        stream: &mut TcpStream,
+        stream: &mut Stream,
         state: &mut ConnectionState,
     ) -> crate::Result<()> {
         let mut packet = BytesMut::new();
@@ -222,8 +537,8 @@ impl MySqlProtocol {
         packet.put_slice(SERVER_VERSION.as_bytes());
         packet.put_u8(0);
 
-        // Connection ID
-        packet.put_u32_le(1);
+        // Connection ID, which KILL QUERY refers to the connection by
+        packet.put_u32_le(state.connection_id);
 
         // Auth data part 1 (8 bytes)
         packet.put_slice(&state.auth_data[..8]);
@@ -232,13 +547,20 @@ impl MySqlProtocol {
         packet.put_u8(0);
 
         // Capability flags (lower 2 bytes)
-        let capabilities = CLIENT_LONG_PASSWORD
+        let mut capabilities = CLIENT_LONG_PASSWORD
             | CLIENT_FOUND_ROWS
             | CLIENT_LONG_FLAG
             | CLIENT_CONNECT_WITH_DB
             | CLIENT_PROTOCOL_41
             | CLIENT_SECURE_CONNECTION
-            | CLIENT_PLUGIN_AUTH;
+            | CLIENT_MULTI_STATEMENTS
+            | CLIENT_MULTI_RESULTS
+            | CLIENT_PLUGIN_AUTH
+            | CLIENT_COMPRESS
+            | CLIENT_ZSTD_COMPRESSION_ALGORITHM;
+        if self.tls.is_some() {
+            capabilities |= CLIENT_SSL;
+        }
         packet.put_u16_le((capabilities & 0xFFFF) as u16);
 
         // Character set (utf8mb4)
@@ -268,6 +590,29 @@ impl MySqlProtocol {
         Ok(())
     }
 
+    /// Read the handshake response. A client that wants TLS first sends an SSL request, the
+    /// capability part of the response alone, and the whole response once the connection is
+    /// upgraded.
+    async fn read_handshake_response(
+        &self,
+        mut stream: Stream,
+        state: &mut ConnectionState,
+    ) -> crate::Result<(Stream, Vec<u8>)> {
+        let packet = self.read_packet(&mut stream, state).await?;
+        state.capabilities = client_flags(&packet);
+        let wants_tls = packet.len() == SSL_REQUEST_LENGTH && state.capabilities & CLIENT_SSL != 0;
+        match (&self.tls, stream) {
+            (Some(tls), Stream::Plain(plain)) if wants_tls => {
+                let mut stream = Stream::Tls(Box::new(tls.accept(plain).await?));
+                debug!("Connection upgraded to TLS");
+                let packet = self.read_packet(&mut stream, state).await?;
+                state.capabilities = client_flags(&packet);
+                Ok((stream, packet))
+            }
+            (_, stream) => Ok((stream, packet)),
+        }
+    }
+
     #[allow(clippy::type_complexity)]
     fn parse_handshake_response(
         &self,
@@ -323,49 +668,23 @@ impl MySqlProtocol {
         };
         pos += auth_len;
 
-        // Database (optional, null-terminated)
-        let database = if pos < packet.len() {
-            let db_end = packet[pos..]
-                .iter()
-                .position(|&b| b == 0)
-                .unwrap_or(packet.len() - pos);
-            if db_end > 0 {
-                Some(
-                    std::str::from_utf8(&packet[pos..pos + db_end])
-                        .map_err(|_| {
-                            YamlBaseError::Protocol("Invalid UTF-8 in database".to_string())
-                        })?
-                        .to_string(),
-                )
-            } else {
-                None
+        // Database and auth plugin name, null-terminated, each if the client says it sends it
+        let mut next_string = |flag: u32| -> crate::Result<Option<String>> {
+            if client_flags & flag == 0 || pos >= packet.len() {
+                return Ok(None);
             }
-        } else {
-            None
-        };
-
-        // Try to read auth plugin name if present
-        let auth_plugin = if pos < packet.len() {
-            // Skip to auth plugin name (may have client attributes first)
-            // For simplicity, we'll just check if there's more data
-            let plugin_end = packet[pos..]
+            let end = packet[pos..]
                 .iter()
                 .position(|&b| b == 0)
                 .unwrap_or(packet.len() - pos);
-            if plugin_end > 0 {
-                Some(
-                    std::str::from_utf8(&packet[pos..pos + plugin_end])
-                        .map_err(|_| {
-                            YamlBaseError::Protocol("Invalid UTF-8 in auth plugin".to_string())
-                        })?
-                        .to_string(),
-                )
-            } else {
-                None
-            }
-        } else {
-            None
+            let value = std::str::from_utf8(&packet[pos..pos + end])
+                .map_err(|_| YamlBaseError::Protocol("Invalid UTF-8 in handshake".to_string()))?
+                .to_string();
+            pos += end + 1;
+            Ok(Some(value).filter(|value| !value.is_empty()))
         };
+        let database = next_string(CLIENT_CONNECT_WITH_DB)?;
+        let auth_plugin = next_string(CLIENT_PLUGIN_AUTH)?;
 
         debug!("Client auth plugin: {:?}", auth_plugin);
 
@@ -373,22 +692,52 @@ impl MySqlProtocol {
     }
 
     async fn handle_query(
-        &self,
-        stream: &mut TcpStream,
+        &mut self,
+        stream: &mut Stream,
         state: &mut ConnectionState,
         query: &str,
     ) -> crate::Result<()> {
-        let query_trimmed = query.trim();
-        let query_upper = query_trimmed.to_uppercase();
+        let statements = split_statements(query, SqlDialect::MySQL);
 
         // Handle empty queries
-        if query_trimmed.is_empty() {
+        if statements.is_empty() {
             debug!("Empty query received");
             self.send_error(stream, state, 1064, "42000", "Syntax error: Empty query")
                 .await?;
             return Ok(());
         }
 
+        // Only clients that asked for CLIENT_MULTI_STATEMENTS may send several at once
+        if statements.len() > 1 && state.capabilities & CLIENT_MULTI_STATEMENTS == 0 {
+            let message = "Syntax error: multiple statements in one query need the \
+                           CLIENT_MULTI_STATEMENTS capability";
+            self.send_error(stream, state, 1064, "42000", message)
+                .await?;
+            return Ok(());
+        }
+
+        // Each statement of a multi-statement query gets its own result. All but the last
+        // tell the client more results follow, and the first error ends the query.
+        for (i, statement) in statements.iter().enumerate() {
+            state.more_results = i + 1 < statements.len();
+            if !self.handle_statement(stream, state, statement).await? {
+                break;
+            }
+        }
+        state.more_results = false;
+        Ok(())
+    }
+
+    /// Run one statement of a query and send its result, returning whether it succeeded
+    async fn handle_statement(
+        &mut self,
+        stream: &mut Stream,
+        state: &mut ConnectionState,
+        query: &str,
+    ) -> crate::Result<bool> {
+        let query_trimmed = query.trim();
+        let query_upper = query_trimmed.to_uppercase();
+
         // Handle queries with system variables by preprocessing them
         let mut processed_query = if query_trimmed.contains("@@") {
             self.preprocess_system_variables(query_trimmed)
@@ -404,18 +753,105 @@ impl MySqlProtocol {
 
         // Handle SET NAMES command (ignore it - we always use UTF-8)
         if query_upper.starts_with("SET NAMES") || query_upper.starts_with("SET CHARACTER SET") {
-            debug!("Ignoring SET NAMES/CHARACTER SET command: {}", query);
-            return self.send_ok(stream, state, 0, 0).await;
+            debug!(
+                "Ignoring SET NAMES/CHARACTER SET command: {}",
+                query_trimmed
+            );
+            self.send_ok(stream, state, 0, 0).await?;
+            return Ok(true);
+        }
+
+        // USE switches to another of the databases served
+        if let Some(name) = processed_query
+            .get(..4)
+            .filter(|keyword| keyword.eq_ignore_ascii_case("USE "))
+            .map(|_| processed_query[4..].trim().trim_end_matches(';').trim())
+        {
+            return if self.use_database(name).await? {
+                self.send_ok(stream, state, 0, 0).await?;
+                Ok(true)
+            } else {
+                let message = format!("Unknown database '{}'", name);
+                self.send_error(stream, state, 1049, "42000", &message)
+                    .await?;
+                Ok(false)
+            };
         }
 
-        // Handle other SET commands that MySQL clients might send
+        // Handle other SET commands that MySQL clients might send. System variables and the
+        // session settings the executor knows about are applied, the rest are ignored.
         if query_upper.starts_with("SET ") {
-            debug!("Ignoring SET command: {}", query);
-            return self.send_ok(stream, state, 0, 0).await;
+            if let Some(assignments) = parse_set_system_variables(&processed_query) {
+                return match self.executor.set_system_variables(&assignments) {
+                    Ok(()) => {
+                        self.send_ok(stream, state, 0, 0).await?;
+                        Ok(true)
+                    }
+                    Err(e) => {
+                        self.send_error(stream, state, 1231, "42000", &e.to_string())
+                            .await?;
+                        Ok(false)
+                    }
+                };
+            }
+            if let Ok(statements) = parse_sql(&processed_query)
+                && let [statement] = statements.as_slice()
+                && QueryExecutor::is_session_setting(statement)
+            {
+                return match self.executor.execute(statement).await {
+                    Ok(_) => {
+                        self.send_ok(stream, state, 0, 0).await?;
+                        Ok(true)
+                    }
+                    Err(e) => {
+                        self.send_error(stream, state, 1231, "42000", &e.to_string())
+                            .await?;
+                        Ok(false)
+                    }
+                };
+            }
+            debug!("Ignoring SET command: {}", query_trimmed);
+            self.send_ok(stream, state, 0, 0).await?;
+            return Ok(true);
+        }
+
+        if is_reload_database(&processed_query) {
+            return match self.executor.reload_database().await {
+                Ok(()) => {
+                    self.send_ok(stream, state, 0, 0).await?;
+                    Ok(true)
+                }
+                Err(e) => {
+                    let (code, sql_state) = error_code(&e, (1105, "HY000"));
+                    self.send_error(stream, state, code, sql_state, &e.to_string())
+                        .await?;
+                    Ok(false)
+                }
+            };
+        }
+
+        if let Some(view) = parse_refresh_materialized_view(&processed_query) {
+            return match self.executor.refresh_materialized_view(&view).await {
+                Ok(()) => {
+                    self.send_ok(stream, state, 0, 0).await?;
+                    Ok(true)
+                }
+                Err(e) => {
+                    let (code, sql_state) = error_code(&e, (1146, "42S02"));
+                    self.send_error(stream, state, code, sql_state, &e.to_string())
+                        .await?;
+                    Ok(false)
+                }
+            };
         }
 
-        // Parse SQL
-        let statements = match parse_sql(&processed_query) {
+        // Parse SQL. sqlparser reads SHOW VARIABLES and SHOW STATUS in the MySQL dialect only.
+        let statements = if query_upper.starts_with("SHOW ") {
+            parse_sql_with_dialect(&processed_query, SqlDialect::MySQL)
+        } else {
+            parse_sql(&processed_query)
+        };
+        let statements = match statements {
             Ok(stmts) => stmts,
             Err(e) => {
                 self.send_error(
@@ -426,22 +862,21 @@ impl MySqlProtocol {
                     &format!("Syntax error: {}", e),
                 )
                 .await?;
-                return Ok(());
+                return Ok(false);
             }
         };
 
         for statement in statements {
             debug!("Executing statement: {:?}", statement);
 
-            // Check if this is a transaction command that should return OK
-            let is_transaction_command = matches!(
-                statement,
-                sqlparser::ast::Statement::StartTransaction { .. }
-                    | sqlparser::ast::Statement::Commit { .. }
-                    | sqlparser::ast::Statement::Rollback { .. }
-            );
+            if let sqlparser::ast::Statement::Kill { modifier, id } = statement {
+                if !self.kill(stream, state, modifier, id).await? {
+                    return Ok(false);
+                }
+                continue;
+            }
 
-            match self.executor.execute(&statement).await {
+            match self.execute(&statement).await {
                 Ok(result) => {
                     debug!(
                         "Query executed successfully. Result: {} columns, {} rows",
@@ -449,99 +884,319 @@ impl MySqlProtocol {
                         result.rows.len()
                     );
 
-                    // Send OK packet for transaction commands or empty results
-                    if is_transaction_command
-                        || (result.columns.is_empty() && result.rows.is_empty())
+                    if let Some((affected_rows, last_insert_id)) =
+                        self.ok_result(&statement, &result)
                     {
                         debug!("Sending OK packet for transaction command or empty result");
-                        self.send_ok(stream, state, 0, 0).await?;
+                        self.send_ok(stream, state, affected_rows, last_insert_id)
+                            .await?;
                     } else {
                         self.send_query_result(stream, state, &result).await?;
                     }
                 }
                 Err(e) => {
                     debug!("Query execution error: {}", e);
-                    self.send_error(stream, state, 1146, "42S02", &e.to_string())
+                    let (code, sql_state) = error_code(&e, (1146, "42S02"));
+                    self.send_error(stream, state, code, sql_state, &e.to_string())
                         .await?;
+                    return Ok(false);
                 }
             }
         }
 
-        Ok(())
+        Ok(true)
     }
 
-    fn preprocess_system_variables(&self, query: &str) -> String {
-        use once_cell::sync::Lazy;
-        use regex::Regex;
+    /// The affected rows and last insert id of a statement answered with an OK packet rather
+    /// than a result set: transaction commands, writes and empty results
+    fn ok_result(&self, statement: &Statement, result: &QueryResult) -> Option<(u64, u64)> {
+        let is_transaction_command = matches!(
+            statement,
+            Statement::StartTransaction { .. }
+                | Statement::Commit { .. }
+                | Statement::Rollback { .. }
+                | Statement::Savepoint { .. }
+                | Statement::ReleaseSavepoint { .. }
+        );
+        let is_empty = result.columns.is_empty() && result.rows.is_empty();
+        if !is_transaction_command && !is_empty {
+            return None;
+        }
+        // Clients read LAST_INSERT_ID() of an INSERT from the OK packet
+        let last_insert_id = match statement {
+            Statement::Insert(_) => self.executor.last_insert_id() as u64,
+            _ => 0,
+        };
+        Some((result.affected_rows.unwrap_or(0), last_insert_id))
+    }
 
-        // Only preprocess SELECT queries that contain system variables
-        let query_upper = query.to_uppercase();
-        if !query_upper.starts_with("SELECT") || !query.contains("@@") {
-            return query.to_string();
+    /// COM_STMT_PREPARE: parse a statement for COM_STMT_EXECUTE to run, and describe its
+    /// parameters and, for a query, its columns
+    async fn prepare(
+        &mut self,
+        stream: &mut Stream,
+        state: &mut ConnectionState,
+        query: &str,
+    ) -> crate::Result<()> {
+        let query_trimmed = query.trim();
+        let mut processed_query = if query_trimmed.contains("@@") {
+            self.preprocess_system_variables(query_trimmed)
+        } else {
+            query_trimmed.to_string()
+        };
+        processed_query = processed_query.replace('`', "");
+
+        let is_use = processed_query
+            .get(..4)
+            .is_some_and(|keyword| keyword.eq_ignore_ascii_case("USE "));
+        if is_use
+            || is_reload_database(&processed_query)
+            || parse_refresh_materialized_view(&processed_query).is_some()
+        {
+            let message = "This command is not supported in the prepared statement protocol yet";
+            return self.send_error(stream, state, 1295, "HY000", message).await;
         }
 
-        static VERSION_RE: Lazy<Result<Regex, regex::Error>> = Lazy::new(|| {
-            Regex::new(
-                r"@@(?:(?:global|GLOBAL|Global|session|SESSION|Session)\.)?(?:version|VERSION|Version)\b",
-            )
-        });
+        // The MySQL dialect reads `?` as a placeholder
+        let statements = parse_sql_with_dialect(&processed_query, SqlDialect::MySQL);
+        let statement = if query_trimmed.to_uppercase().starts_with("SET ") {
+            // As in a query, SET statements other than the session settings are ignored
+            statements
+                .ok()
+                .and_then(|statements| <[Statement; 1]>::try_from(statements).ok())
+                .map(|[statement]| statement)
+                .filter(QueryExecutor::is_session_setting)
+        } else {
+            let statements = match statements {
+                Ok(statements) => statements,
+                Err(e) => {
+                    let message = format!("Syntax error: {}", e);
+                    return self
+                        .send_error(stream, state, 1064, "42000", &message)
+                        .await;
+                }
+            };
+            match <[Statement; 1]>::try_from(statements) {
+                Ok([Statement::Kill { .. }]) => {
+                    let message =
+                        "This command is not supported in the prepared statement protocol yet";
+                    return self.send_error(stream, state, 1295, "HY000", message).await;
+                }
+                Ok([statement]) => Some(statement),
+                Err(_) => {
+                    let message = "Syntax error: a prepared statement is a single statement";
+                    return self.send_error(stream, state, 1064, "42000", message).await;
+                }
+            }
+        };
+        let parameter_count = statement.as_ref().map_or(0, parameter_count);
+
+        // The columns of a query are those it has when run with NULL parameters
+        let mut columns = Vec::new();
+        if let Some(statement @ Statement::Query(_)) = &statement
+            && let Ok(result) = self
+                .executor
+                .execute_with_parameters(statement, &vec![Value::Null; parameter_count])
+                .await
+        {
+            columns = result_columns(&result);
+        }
 
-        static VERSION_COMMENT_RE: Lazy<Result<Regex, regex::Error>> = Lazy::new(|| {
-            Regex::new(
-                r"@@(?:(?:global|GLOBAL|Global|session|SESSION|Session)\.)?(?:version_comment|VERSION_COMMENT|Version_Comment)\b",
-            )
-        });
+        let id = self.next_statement_id;
+        self.next_statement_id = self.next_statement_id.wrapping_add(1);
+        self.prepared_statements.insert(
+            id,
+            PreparedStatement {
+                statement,
+                parameter_count,
+                parameter_types: Vec::new(),
+                long_data: HashMap::new(),
+            },
+        );
+        debug!("Prepared statement {}: {}", id, query_trimmed);
 
-        static MAX_ALLOWED_PACKET_RE: Lazy<Result<Regex, regex::Error>> = Lazy::new(|| {
-            Regex::new(
-                r"@@(?:(?:global|GLOBAL|Global|session|SESSION|Session)\.)?(?:max_allowed_packet|MAX_ALLOWED_PACKET|Max_Allowed_Packet)\b",
-            )
-        });
+        let mut packet = BytesMut::new();
+        packet.put_u8(0x00);
+        packet.put_u32_le(id);
+        packet.put_u16_le(columns.len() as u16);
+        packet.put_u16_le(parameter_count as u16);
+        packet.put_u8(0x00);
+        // Warnings
+        packet.put_u16_le(0);
+        self.write_packet(stream, state, &packet).await?;
 
-        static SYSTEM_VAR_RE: Lazy<Result<Regex, regex::Error>> = Lazy::new(|| {
-            Regex::new(
-                r"@@(?:(?:global|GLOBAL|Global|session|SESSION|Session)\.)?([a-zA-Z_][a-zA-Z0-9_]*)\b",
-            )
-        });
+        if parameter_count > 0 {
+            for _ in 0..parameter_count {
+                let definition = column_definition("?", MYSQL_TYPE_VAR_STRING);
+                self.write_packet(stream, state, &definition).await?;
+            }
+            self.send_eof(stream, state).await?;
+        }
+        if !columns.is_empty() {
+            for (name, column_type) in &columns {
+                let definition = column_definition(name, *column_type);
+                self.write_packet(stream, state, &definition).await?;
+            }
+            self.send_eof(stream, state).await?;
+        }
+        Ok(())
+    }
 
-        let mut result = query.to_string();
+    /// COM_STMT_EXECUTE: run a prepared statement with the parameters of the packet, and
+    /// send its rows in the binary protocol
+    async fn execute_prepared(
+        &mut self,
+        stream: &mut Stream,
+        state: &mut ConnectionState,
+        data: &[u8],
+    ) -> crate::Result<()> {
+        let Some(prepared) =
+            statement_id(data).and_then(|id| self.prepared_statements.get_mut(&id))
+        else {
+            return self.send_unknown_statement(stream, state, data).await;
+        };
 
-        // First handle @@version specifically
-        if let Ok(ref version_re) = *VERSION_RE {
-            result = version_re
-                .replace_all(&result, "'8.0.35-yamlbase'")
-                .to_string();
-        } else {
-            // If regex compilation failed, skip this replacement
-            debug!("Failed to compile VERSION_RE regex");
+        // The statement id is followed by the cursor flags, which are not supported and so
+        // ignored, and the iteration count, which is always 1
+        let parameters = match data.get(9..) {
+            Some(data) => read_parameters(
+                data,
+                prepared.parameter_count,
+                &mut prepared.parameter_types,
+                &mut prepared.long_data,
+            ),
+            None => Err(YamlBaseError::Protocol(
+                "Malformed COM_STMT_EXECUTE packet".to_string(),
+            )),
+        };
+        prepared.long_data.clear();
+        let statement = prepared.statement.clone();
+        let parameters = match parameters {
+            Ok(parameters) => parameters,
+            Err(e) => {
+                return self
+                    .send_error(stream, state, 1210, "HY000", &e.to_string())
+                    .await;
+            }
+        };
+        let Some(statement) = statement else {
+            return self.send_ok(stream, state, 0, 0).await;
+        };
+
+        debug!("Executing prepared statement: {:?}", statement);
+        match self
+            .executor
+            .execute_with_parameters(&statement, &parameters)
+            .await
+        {
+            Ok(result) => match self.ok_result(&statement, &result) {
+                Some((affected_rows, last_insert_id)) => {
+                    self.send_ok(stream, state, affected_rows, last_insert_id)
+                        .await
+                }
+                None => self.send_binary_result_set(stream, state, &result).await,
+            },
+            Err(e) => {
+                debug!("Prepared statement execution error: {}", e);
+                let (code, sql_state) = error_code(&e, (1146, "42S02"));
+                self.send_error(stream, state, code, sql_state, &e.to_string())
+                    .await
+            }
         }
+    }
 
-        // Handle @@version_comment
-        if let Ok(ref version_comment_re) = *VERSION_COMMENT_RE {
-            result = version_comment_re.replace_all(&result, "'1'").to_string();
-        } else {
-            debug!("Failed to compile VERSION_COMMENT_RE regex");
+    async fn send_binary_result_set(
+        &self,
+        stream: &mut Stream,
+        state: &mut ConnectionState,
+        result: &QueryResult,
+    ) -> crate::Result<()> {
+        let columns = result_columns(result);
+        let mut packet = BytesMut::new();
+        put_lenenc_int(&mut packet, columns.len() as u64);
+        self.write_packet(stream, state, &packet).await?;
+        for (name, column_type) in &columns {
+            let definition = column_definition(name, *column_type);
+            self.write_packet(stream, state, &definition).await?;
+        }
+        self.send_eof(stream, state).await?;
+
+        let column_types: Vec<u8> = columns
+            .iter()
+            .map(|(_, column_type)| *column_type)
+            .collect();
+        for row in &result.rows {
+            let mut packet = BytesMut::new();
+            put_row(&mut packet, row, &column_types);
+            self.write_packet(stream, state, &packet).await?;
         }
+        self.send_eof(stream, state).await
+    }
+
+    async fn send_unknown_statement(
+        &self,
+        stream: &mut Stream,
+        state: &mut ConnectionState,
+        data: &[u8],
+    ) -> crate::Result<()> {
+        let id = statement_id(data).unwrap_or_default();
+        let e = YamlBaseError::UnknownPreparedStatement(id.to_string());
+        let (code, sql_state) = error_code(&e, (1243, "HY000"));
+        self.send_error(stream, state, code, sql_state, &e.to_string())
+            .await
+    }
 
-        // Handle @@max_allowed_packet - MySQL default is 64MB (67108864 bytes)
-        if let Ok(ref max_packet_re) = *MAX_ALLOWED_PACKET_RE {
-            result = max_packet_re.replace_all(&result, "67108864").to_string();
+    /// `KILL QUERY id` stops the query another connection is running, returning whether
+    /// that connection exists
+    async fn kill(
+        &self,
+        stream: &mut Stream,
+        state: &mut ConnectionState,
+        modifier: Option<KillType>,
+        id: u64,
+    ) -> crate::Result<bool> {
+        if modifier != Some(KillType::Query) {
+            self.send_error(stream, state, 1235, "42000", "Only KILL QUERY is supported")
+                .await?;
+            return Ok(false);
+        }
+        let killed = u32::try_from(id).is_ok_and(|id| self.cancellations.cancel(id, None));
+        if killed {
+            self.send_ok(stream, state, 0, 0).await?;
         } else {
-            debug!("Failed to compile MAX_ALLOWED_PACKET_RE regex");
+            let message = format!("Unknown thread id: {}", id);
+            self.send_error(stream, state, 1094, "HY000", &message)
+                .await?;
         }
+        Ok(killed)
+    }
+
+    fn preprocess_system_variables(&self, query: &str) -> String {
+        use once_cell::sync::Lazy;
+        use regex::{Captures, Regex};
 
-        // Check if we already replaced all instances
-        if !result.contains("@@") {
-            debug!("Preprocessed query: {} -> {}", query, result);
-            return result;
+        // Only preprocess SELECT queries that contain system variables
+        let query_upper = query.to_uppercase();
+        if !query_upper.starts_with("SELECT") || !query.contains("@@") {
+            return query.to_string();
         }
 
-        // Replace remaining system variables with '1'
-        if let Ok(ref system_var_re) = *SYSTEM_VAR_RE {
-            result = system_var_re.replace_all(&result, "'1'").to_string();
-        } else {
+        static SYSTEM_VAR_RE: Lazy<Result<Regex, regex::Error>> =
+            Lazy::new(|| Regex::new(r"(?i)@@(?:(?:global|session|local)\.)?([a-z_][a-z0-9_]*)\b"));
+
+        // Replace each system variable with the value the connection has for it
+        let Ok(ref system_var_re) = *SYSTEM_VAR_RE else {
             debug!("Failed to compile SYSTEM_VAR_RE regex");
-        }
+            return query.to_string();
+        };
+        let result = system_var_re
+            .replace_all(query, |captures: &Captures| {
+                match self.executor.system_variable_value(&captures[1]) {
+                    Value::Integer(number) => number.to_string(),
+                    value => format!("'{}'", value.to_string().replace('\'', "''")),
+                }
+            })
+            .to_string();
 
         debug!("Preprocessed query: {} -> {}", query, result);
         result
@@ -549,9 +1204,9 @@ impl MySqlProtocol {
 
     async fn send_query_result(
         &self,
-        stream: &mut TcpStream,
+        stream: &mut Stream,
         state: &mut ConnectionState,
-        result: &crate::sql::executor::QueryResult,
+        result: &QueryResult,
     ) -> crate::Result<()> {
         debug!(
             "Sending query result with {} columns and {} rows",
@@ -559,33 +1214,42 @@ impl MySqlProtocol {
             result.rows.len()
         );
 
-        // Convert to string representation
+        // Convert to string representation, except for binary values sent as they are and
+        // arrays, which MySQL does not have, sent as JSON arrays
         let columns: Vec<&str> = result.columns.iter().map(|s| s.as_str()).collect();
         debug!("Columns: {:?}", columns);
+        let column_types: Vec<u8> = (0..columns.len())
+            .map(|idx| match result.column_types.get(idx) {
+                Some(SqlType::Blob) => MYSQL_TYPE_BLOB,
+                Some(SqlType::Array(_)) => MYSQL_TYPE_JSON,
+                _ => MYSQL_TYPE_VAR_STRING,
+            })
+            .collect();
 
-        let rows: Vec<Vec<String>> = result
+        let rows: Vec<Vec<Vec<u8>>> = result
             .rows
             .iter()
-            .map(|row| row.iter().map(|val| val.to_string()).collect())
+            .map(|row| row.iter().map(text_bytes).collect())
             .collect();
         debug!("Converted {} rows to strings", rows.len());
 
-        let string_rows: Vec<Vec<&str>> = rows
+        let byte_rows: Vec<Vec<&[u8]>> = rows
             .iter()
-            .map(|row| row.iter().map(|s| s.as_str()).collect())
+            .map(|row| row.iter().map(|s| s.as_slice()).collect())
             .collect();
 
         debug!("Calling send_simple_result_set");
-        self.send_simple_result_set(stream, state, &columns, &string_rows)
+        self.send_simple_result_set(stream, state, &columns, &column_types, &byte_rows)
             .await
     }
 
     async fn send_simple_result_set(
         &self,
-        stream: &mut TcpStream,
+        stream: &mut Stream,
         state: &mut ConnectionState,
         columns: &[&str],
-        rows: &[Vec<&str>],
+        column_types: &[u8],
+        rows: &[Vec<&[u8]>],
     ) -> crate::Result<()> {
         debug!(
             "send_simple_result_set: {} columns, {} rows",
@@ -603,60 +1267,17 @@ impl MySqlProtocol {
         debug!("Writing {} column definitions", columns.len());
         for (idx, column) in columns.iter().enumerate() {
             debug!("Writing column definition {}: {}", idx, column);
-            let mut col_packet = BytesMut::new();
-
-            // Catalog (def)
-            col_packet.put_u8(3);
-            col_packet.put_slice(b"def");
-
-            // Schema
-            col_packet.put_u8(0);
-
-            // Table
-            col_packet.put_u8(0);
-
-            // Original table
-            col_packet.put_u8(0);
-
-            // Column name
-            col_packet.put_u8(column.len() as u8);
-            col_packet.put_slice(column.as_bytes());
-
-            // Original column name
-            col_packet.put_u8(column.len() as u8);
-            col_packet.put_slice(column.as_bytes());
-
-            // Length of fixed fields (0x0c)
-            col_packet.put_u8(0x0c);
-
-            // Character set (utf8mb4)
-            col_packet.put_u16_le(33);
-
-            // Column length
-            col_packet.put_u32_le(255);
-
-            // Column type (VAR_STRING)
-            col_packet.put_u8(MYSQL_TYPE_VAR_STRING);
-
-            // Flags
-            col_packet.put_u16_le(0);
-
-            // Decimals
-            col_packet.put_u8(0);
-
-            // Filler
-            col_packet.put_u16_le(0);
-
+            let column_type = column_types
+                .get(idx)
+                .copied()
+                .unwrap_or(MYSQL_TYPE_VAR_STRING);
+            let col_packet = column_definition(column, column_type);
             self.write_packet(stream, state, &col_packet).await?;
         }
 
         // Send EOF packet after column definitions (for clients that don't support CLIENT_DEPRECATE_EOF)
         debug!("Sending EOF packet after column definitions");
-        let mut eof_packet = BytesMut::new();
-        eof_packet.put_u8(0xfe); // EOF marker
-        eof_packet.put_u16_le(0); // warnings
-        eof_packet.put_u16_le(SERVER_STATUS_AUTOCOMMIT); // status flags
-        self.write_packet(stream, state, &eof_packet).await?;
+        self.send_eof(stream, state).await?;
 
         // Send rows with intelligent batching for performance
         debug!("Sending {} rows", rows.len());
@@ -676,7 +1297,7 @@ impl MySqlProtocol {
                 for (_i, row) in rows.iter().enumerate().skip(batch_start) {
                     let estimated_row_size: usize = row
                         .iter()
-                        .map(|v| if *v == "NULL" { 1 } else { v.len() + 5 }) // +5 for length encoding overhead
+                        .map(|v| if *v == b"NULL" { 1 } else { v.len() + 5 }) // +5 for length encoding overhead
                         .sum();
 
                     if batch_memory + estimated_row_size > MAX_BATCH_MEMORY && batch_size > 0 {
@@ -706,12 +1327,12 @@ impl MySqlProtocol {
                     debug!("Sending row {} with {} values", global_idx, row.len());
                     let mut row_packet = BytesMut::new();
                     for (col_idx, value) in row.iter().enumerate() {
-                        if *value == "NULL" {
+                        if *value == b"NULL" {
                             debug!("  Column {}: NULL", col_idx);
                             row_packet.put_u8(0xfb); // NULL value
                         } else {
-                            let bytes = value.as_bytes();
-                            debug!("  Column {}: '{}' ({} bytes)", col_idx, value, bytes.len());
+                            let bytes = *value;
+                            debug!("  Column {}: {} bytes", col_idx, bytes.len());
                             // MySQL uses length-encoded strings for result rows
                             if bytes.len() < 251 {
                                 row_packet.put_u8(bytes.len() as u8);
@@ -742,12 +1363,12 @@ impl MySqlProtocol {
                 debug!("Sending row {} with {} values", idx, row.len());
                 let mut row_packet = BytesMut::new();
                 for (col_idx, value) in row.iter().enumerate() {
-                    if *value == "NULL" {
+                    if *value == b"NULL" {
                         debug!("  Column {}: NULL", col_idx);
                         row_packet.put_u8(0xfb); // NULL value
                     } else {
-                        let bytes = value.as_bytes();
-                        debug!("  Column {}: '{}' ({} bytes)", col_idx, value, bytes.len());
+                        let bytes = *value;
+                        debug!("  Column {}: {} bytes", col_idx, bytes.len());
                         // MySQL uses length-encoded strings for result rows
                         if bytes.len() < 251 {
                             row_packet.put_u8(bytes.len() as u8);
@@ -773,19 +1394,41 @@ impl MySqlProtocol {
 
         // Send EOF packet after rows
         debug!("Sending final EOF packet");
+        self.send_eof(stream, state).await
+    }
+
+    async fn send_eof(
+        &self,
+        stream: &mut Stream,
+        state: &mut ConnectionState,
+    ) -> crate::Result<()> {
         let mut eof_packet = BytesMut::new();
         eof_packet.put_u8(0xfe); // EOF marker
         eof_packet.put_u16_le(0); // warnings
-        eof_packet.put_u16_le(SERVER_STATUS_AUTOCOMMIT); // status flags
+        eof_packet.put_u16_le(self.status_flags(state)); // status flags
         self.write_packet(stream, state, &eof_packet).await
     }
 
+    fn status_flags(&self, state: &ConnectionState) -> u16 {
+        let mut flags = 0;
+        if self.executor.system_variable("autocommit").as_deref() != Some("OFF") {
+            flags |= SERVER_STATUS_AUTOCOMMIT;
+        }
+        if self.executor.in_transaction() {
+            flags |= SERVER_STATUS_IN_TRANS;
+        }
+        if state.more_results {
+            flags |= SERVER_MORE_RESULTS_EXISTS;
+        }
+        flags
+    }
+
     async fn send_ok(
         &self,
-        stream: &mut TcpStream,
+        stream: &mut Stream,
         state: &mut ConnectionState,
         affected_rows: u64,
-        _info: u64,
+        last_insert_id: u64,
     ) -> crate::Result<()> {
         let mut packet = BytesMut::new();
 
@@ -796,10 +1439,10 @@ impl MySqlProtocol {
         put_lenenc_int(&mut packet, affected_rows);
 
         // Last insert ID
-        put_lenenc_int(&mut packet, 0);
+        put_lenenc_int(&mut packet, last_insert_id);
 
         // Status flags
-        packet.put_u16_le(SERVER_STATUS_AUTOCOMMIT);
+        packet.put_u16_le(self.status_flags(state));
 
         // Warnings
         packet.put_u16_le(0);
@@ -807,9 +1450,21 @@ impl MySqlProtocol {
         self.write_packet(stream, state, &packet).await
     }
 
+    /// Turn a client away, as MySQL does beyond max_connections: with an ERR packet in place
+    /// of the handshake. The client has not told its capabilities yet, so it has no SQL state.
+    pub async fn refuse_connection(&self, stream: TcpStream) -> crate::Result<()> {
+        let mut packet = BytesMut::new();
+        packet.put_u8(0xff);
+        packet.put_u16_le(ER_CON_COUNT_ERROR);
+        packet.put_slice(b"Too many connections");
+        let mut state = ConnectionState::default();
+        self.write_packet(&mut Stream::Plain(stream), &mut state, &packet)
+            .await
+    }
+
     async fn send_error(
         &self,
-        stream: &mut TcpStream,
+        stream: &mut Stream,
         state: &mut ConnectionState,
         error_code: u16,
         sql_state: &str,
@@ -837,7 +1492,7 @@ impl MySqlProtocol {
 
     async fn write_packet(
         &self,
-        stream: &mut TcpStream,
+        stream: &mut Stream,
         state: &mut ConnectionState,
         payload: &[u8],
     ) -> crate::Result<()> {
@@ -867,8 +1522,7 @@ impl MySqlProtocol {
             // Payload
             packet.put_slice(payload);
 
-            stream.write_all(&packet).await?;
-            stream.flush().await?;
+            self.write_bytes(stream, state, &packet).await?;
         } else {
             // Large payload - split into multiple packets
             debug!(
@@ -905,8 +1559,7 @@ impl MySqlProtocol {
                 // Payload chunk
                 packet.put_slice(chunk);
 
-                stream.write_all(&packet).await?;
-                stream.flush().await?;
+                self.write_bytes(stream, state, &packet).await?;
 
                 offset += chunk_size;
             }
@@ -917,20 +1570,113 @@ impl MySqlProtocol {
 
     async fn read_packet(
         &self,
-        stream: &mut TcpStream,
+        stream: &mut Stream,
         state: &mut ConnectionState,
     ) -> crate::Result<Vec<u8>> {
         let mut header = [0u8; 4];
-        stream.read_exact(&mut header).await?;
+        self.read_bytes(stream, state, &mut header).await?;
 
         let len = (header[0] as usize) | ((header[1] as usize) << 8) | ((header[2] as usize) << 16);
         state.sequence_id = header[3].wrapping_add(1);
 
         let mut payload = vec![0u8; len];
-        stream.read_exact(&mut payload).await?;
+        self.read_bytes(stream, state, &mut payload).await?;
 
         Ok(payload)
     }
+
+    /// Write packets to the connection, compressed if it is
+    async fn write_bytes(
+        &self,
+        stream: &mut Stream,
+        state: &mut ConnectionState,
+        bytes: &[u8],
+    ) -> crate::Result<()> {
+        match &mut state.compression {
+            Some(compression) => compression.write_all(stream, bytes).await,
+            None => {
+                stream.write_all(bytes).await?;
+                stream.flush().await?;
+                Ok(())
+            }
+        }
+    }
+
+    async fn read_bytes(
+        &self,
+        stream: &mut Stream,
+        state: &mut ConnectionState,
+        buf: &mut [u8],
+    ) -> crate::Result<()> {
+        match &mut state.compression {
+            Some(compression) => compression.read_exact(stream, buf).await,
+            None => {
+                stream.read_exact(buf).await?;
+                Ok(())
+            }
+        }
+    }
+}
+
+/// The error number and SQLSTATE of the error a statement failed with, `default` for the
+/// errors MySQL has no number of its own for
+fn error_code(error: &YamlBaseError, default: (u16, &'static str)) -> (u16, &'static str) {
+    match error {
+        YamlBaseError::PermissionDenied(_) => (1142, "42000"),
+        YamlBaseError::QueryCancelled => (1317, "70100"),
+        // ER_USER_LIMIT_REACHED, as for MySQL's own per-user resource limits
+        YamlBaseError::RateLimited(_) => (1226, "42000"),
+        YamlBaseError::UnknownPreparedStatement(_) => (1243, "HY000"),
+        // ER_DUP_ENTRY
+        YamlBaseError::UniqueViolation { .. } => (1062, "23000"),
+        // ER_CHECK_CONSTRAINT_VIOLATED
+        YamlBaseError::CheckViolation { .. } => (3819, "HY000"),
+        _ => default,
+    }
+}
+
+/// The capability flags a handshake response starts with
+fn client_flags(packet: &[u8]) -> u32 {
+    packet
+        .get(..4)
+        .map(|flags| u32::from_le_bytes([flags[0], flags[1], flags[2], flags[3]]))
+        .unwrap_or_default()
+}
+
+/// The statement id the COM_STMT_* packets start with
+fn statement_id(data: &[u8]) -> Option<u32> {
+    Some(u32::from_le_bytes(data.get(..4)?.try_into().ok()?))
+}
+
+/// The number of `?` placeholders of a statement, which the parser numbered `$1` to `$n`
+fn parameter_count(statement: &Statement) -> usize {
+    let mut count = 0;
+    let _ = visit_expressions(statement, |expr| {
+        if let Expr::Value(SqlValue::Placeholder(name)) = expr
+            && let Some(number) = name.strip_prefix('$').and_then(|n| n.parse().ok())
+        {
+            count = usize::max(count, number);
+        }
+        ControlFlow::<()>::Continue(())
+    });
+    count
+}
+
+/// The names of the columns of a result and the types they are sent as in the binary
+/// protocol
+fn result_columns(result: &QueryResult) -> Vec<(String, u8)> {
+    result
+        .columns
+        .iter()
+        .enumerate()
+        .map(|(i, name)| {
+            let values = result.rows.iter().filter_map(|row| row.get(i));
+            (
+                name.clone(),
+                column_type(result.column_types.get(i), values),
+            )
+        })
+        .collect()
 }
 
 fn generate_auth_data() -> Vec<u8> {
@@ -970,7 +1716,7 @@ fn compute_auth_response(password: &str, auth_data: &[u8]) -> Vec<u8> {
         .collect()
 }
 
-fn put_lenenc_int(buf: &mut BytesMut, value: u64) {
+pub(crate) fn put_lenenc_int(buf: &mut BytesMut, value: u64) {
     if value < 251 {
         buf.put_u8(value as u8);
     } else if value < 65536 {
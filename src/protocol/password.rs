@@ -0,0 +1,88 @@
+// The password a user connects with, given in plain text as `password:` or as a bcrypt or
+// argon2 hash as `password_hash:`. A hash can only be checked against the password itself,
+// so clients are asked for it in plain text rather than for a challenge response.
+use argon2::{Argon2, PasswordHash, PasswordVerifier};
+
+use crate::YamlBaseError;
+use crate::config::Config;
+use crate::yaml::Grants;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Password<'a> {
+    Plain(&'a str),
+    Hash(&'a str),
+}
+
+impl<'a> Password<'a> {
+    /// The hash when there is one, otherwise the plain text password
+    pub fn new(password: &'a str, hash: Option<&'a str>) -> Self {
+        match hash {
+            Some(hash) => Password::Hash(hash),
+            None => Password::Plain(password),
+        }
+    }
+
+    /// Whether a client sent this password
+    pub fn verify(&self, given: &str) -> bool {
+        match *self {
+            Password::Plain(password) => given == password,
+            Password::Hash(hash) if is_argon2(hash) => PasswordHash::new(hash).is_ok_and(|hash| {
+                Argon2::default()
+                    .verify_password(given.as_bytes(), &hash)
+                    .is_ok()
+            }),
+            Password::Hash(hash) => bcrypt::verify(given, hash).unwrap_or(false),
+        }
+    }
+}
+
+/// Check that a `password_hash:` is a bcrypt or argon2 hash
+pub fn check_hash(hash: &str) -> crate::Result<()> {
+    let valid = if is_argon2(hash) {
+        PasswordHash::new(hash).is_ok_and(|hash| hash.hash.is_some())
+    } else {
+        hash.parse::<bcrypt::HashParts>().is_ok()
+    };
+    if valid {
+        Ok(())
+    } else {
+        Err(YamlBaseError::Config(format!(
+            "password_hash '{}' is not a bcrypt or argon2 hash",
+            hash
+        )))
+    }
+}
+
+/// The user that the value of an HTTP `Authorization: Basic` header names and its grants, if
+/// its password is right. Without `--allow-anonymous` a request has to have one; anonymous
+/// requests have no user.
+pub fn authenticate_basic(
+    config: &Config,
+    authorization: Option<&str>,
+) -> Option<(Option<String>, Option<Grants>)> {
+    use base64::Engine;
+
+    let credentials = authorization
+        .and_then(|value| value.strip_prefix("Basic "))
+        .and_then(|encoded| {
+            base64::engine::general_purpose::STANDARD
+                .decode(encoded.trim())
+                .ok()
+        })
+        .and_then(|decoded| String::from_utf8(decoded).ok());
+    let Some(credentials) = credentials else {
+        return config.allow_anonymous.then_some((None, None));
+    };
+    let (username, password) = credentials.split_once(':')?;
+    tracing::debug!("Authentication check - username: {}", username);
+    match config.user(username) {
+        Some((expected, grants)) if expected.verify(password) => {
+            Some((Some(username.to_string()), grants.cloned()))
+        }
+        _ => None,
+    }
+}
+
+fn is_argon2(hash: &str) -> bool {
+    hash.starts_with("$argon2")
+}
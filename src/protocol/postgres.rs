@@ -1,21 +1,45 @@
-use bytes::{Buf, BufMut, BytesMut};
+use bytes::{BufMut, BytesMut};
+use rand::RngCore;
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::TcpStream;
-use tracing::{debug, info};
+use tokio::sync::broadcast;
+use tracing::{debug, info, warn};
 
 use crate::YamlBaseError;
-use crate::config::Config;
-use crate::database::{Storage, Value};
-use crate::protocol::postgres_extended::ExtendedProtocol;
-use crate::sql::{QueryExecutor, parse_sql};
+use crate::config::{AuthMethod, Config};
+use crate::database::{Databases, Notification, Value};
+use crate::protocol::cancel::Cancellations;
+use crate::protocol::password::Password;
+use crate::protocol::postgres_copy::execute_copy;
+use crate::protocol::postgres_extended::{ExtendedProtocol, deallocate_target, sql_type_to_oid};
+use crate::protocol::postgres_scram::{ScramSha256, read_password_message};
+use crate::protocol::timeouts::{LoginSignal, next_request};
+use crate::protocol::tls::{CertificateLogin, Stream, TlsAcceptor, certificate_login};
+use crate::server::rate_limit::ClientRateLimiter;
+use crate::sql::executor::QueryResult;
+use crate::sql::{
+    QueryExecutor, SqlDialect, is_reload_database, parse_refresh_materialized_view, parse_reset,
+    parse_sql, parse_unlisten, split_statements,
+};
+use crate::yaml::schema::SqlType;
+use sqlparser::ast::Statement;
+
+// The protocol version of an SSLRequest, sent before the startup message
+const SSL_REQUEST_CODE: u32 = 80877103;
+// The protocol version of a CancelRequest, sent instead of a startup message
+const CANCEL_REQUEST_CODE: u32 = 80877102;
 
 pub struct PostgresProtocol {
     config: Arc<Config>,
+    databases: Databases,
     executor: QueryExecutor,
-    _database_name: String,
     extended_protocol: ExtendedProtocol,
+    tls: Option<TlsAcceptor>,
+    cancellations: Cancellations,
+    login: LoginSignal,
+    rate_limiter: ClientRateLimiter,
 }
 
 #[derive(Debug, Default)]
@@ -24,33 +48,123 @@ struct ConnectionState {
     username: Option<String>,
     database: Option<String>,
     parameters: HashMap<String, String>,
+    // The process id and secret key of BackendKeyData, for cancel requests
+    backend_key: (u32, u32),
+}
+
+// A statement of a simple query. sqlparser cannot parse REFRESH MATERIALIZED VIEW, so it
+// is recognised before parsing.
+enum BatchStatement {
+    Copy(Box<Statement>),
+    RefreshMaterializedView(String),
+    ReloadDatabase,
+    // UNLISTEN, which sqlparser cannot parse either, with `None` for all channels
+    Unlisten(Option<String>),
+    // RESET, with `None` for all parameters
+    Reset(Option<String>),
+    // DEALLOCATE of a statement prepared with Parse, with `None` for all statements
+    Deallocate(Option<String>),
+    Sql(Box<Statement>),
 }
 
 impl PostgresProtocol {
-    pub async fn new(config: Arc<Config>, storage: Arc<Storage>) -> crate::Result<Self> {
-        let executor = QueryExecutor::new(storage).await?;
+    pub async fn new(config: Arc<Config>, databases: Databases) -> crate::Result<Self> {
+        let executor = Self::executor(&config, &databases, 0).await?;
         Ok(Self {
             config,
+            databases,
             executor,
-            _database_name: String::new(), // Will be set later if needed
             extended_protocol: ExtendedProtocol::new(),
+            tls: None,
+            cancellations: Cancellations::new(),
+            login: LoginSignal::default(),
+            rate_limiter: ClientRateLimiter::default(),
         })
     }
 
-    pub async fn handle_connection(&mut self, mut stream: TcpStream) -> crate::Result<()> {
+    /// Upgrade connections to TLS when the client sends an SSLRequest
+    pub fn with_tls(mut self, tls: Option<TlsAcceptor>) -> Self {
+        self.tls = tls;
+        self
+    }
+
+    /// Register the connection where CancelRequests look for it, and answer them
+    pub fn with_cancellations(mut self, cancellations: Cancellations) -> Self {
+        self.cancellations = cancellations;
+        self
+    }
+
+    /// Tell `login` once the client has logged in, for --connect-timeout
+    pub fn with_login_signal(mut self, login: LoginSignal) -> Self {
+        self.login = login;
+        self
+    }
+
+    /// Count the client's statements against its rate limits
+    pub fn with_rate_limiter(mut self, rate_limiter: ClientRateLimiter) -> Self {
+        self.rate_limiter = rate_limiter;
+        self
+    }
+
+    /// An executor for a session on one of the databases
+    async fn executor(
+        config: &Config,
+        databases: &Databases,
+        index: usize,
+    ) -> crate::Result<QueryExecutor> {
+        let mut executor = QueryExecutor::new(databases.get(index).clone()).await?;
+        if let Some(seed) = config.random_seed {
+            executor = executor.with_random_seed(seed);
+        }
+        Ok(executor
+            .with_recursion_limits(config.recursion_limits())
+            .with_timeout(config.statement_timeout())
+            .with_collation(config.collation()?)
+            .with_write_mode(config.write_mode(&config.files[index])))
+    }
+
+    pub async fn handle_connection(&mut self, stream: TcpStream) -> crate::Result<()> {
         info!("New PostgreSQL connection");
 
         let mut buffer = BytesMut::with_capacity(4096);
         let mut state = ConnectionState::default();
+        let mut stream = self.negotiate_tls(stream, &mut buffer).await?;
+        if buffer.is_empty() {
+            stream.read_buf(&mut buffer).await?;
+        }
+
+        // A CancelRequest comes on a connection of its own, which is closed without a reply
+        if buffer.len() >= 16 && buffer[4..8] == CANCEL_REQUEST_CODE.to_be_bytes() {
+            let process_id = u32::from_be_bytes([buffer[8], buffer[9], buffer[10], buffer[11]]);
+            let secret = u32::from_be_bytes([buffer[12], buffer[13], buffer[14], buffer[15]]);
+            if !self.cancellations.cancel(process_id, Some(secret)) {
+                debug!("Cancel request for unknown process {}", process_id);
+            }
+            return Ok(());
+        }
+
+        let cancel_key = self.cancellations.register();
+        state.backend_key = (cancel_key.id(), cancel_key.secret());
 
         // Read startup message
         self.read_startup_message(&mut stream, &mut buffer, &mut state)
             .await?;
+        self.login.complete();
+        self.executor = self
+            .executor
+            .clone()
+            .with_cancel_signal(cancel_key.signal());
+        let mut notifications = self.executor.storage().subscribe();
 
         // Main message loop
         loop {
             // Read more data if buffer is empty
-            if buffer.is_empty() && stream.read_buf(&mut buffer).await? == 0 {
+            if buffer.is_empty()
+                && self
+                    .read_messages(&mut stream, &mut buffer, &mut notifications)
+                    .await?
+                    == 0
+            {
                 info!("Client disconnected");
                 break;
             }
@@ -58,7 +172,11 @@ impl PostgresProtocol {
             // Check if we have enough data for a message header
             if buffer.len() < 5 {
                 // Read more data
-                if stream.read_buf(&mut buffer).await? == 0 {
+                if self
+                    .read_messages(&mut stream, &mut buffer, &mut notifications)
+                    .await?
+                    == 0
+                {
                     info!("Client disconnected");
                     break;
                 }
@@ -71,70 +189,38 @@ impl PostgresProtocol {
             // Check if we have the complete message
             if buffer.len() < length + 1 {
                 // Read more data
-                if stream.read_buf(&mut buffer).await? == 0 {
+                if self
+                    .read_messages(&mut stream, &mut buffer, &mut notifications)
+                    .await?
+                    == 0
+                {
                     return Ok(());
                 }
                 continue;
             }
 
-            // Process message
+            // Process message. It is taken off the buffer first, as COPY FROM STDIN reads the
+            // messages after it.
+            let message = buffer.split_to(length + 1);
             match msg_type {
                 b'Q' => {
                     // Simple query
-                    let query = self.parse_query(&buffer[5..length + 1])?;
-                    self.handle_query(&mut stream, &query).await?;
-                }
-                b'P' => {
-                    // Parse (extended query protocol)
-                    self.extended_protocol
-                        .handle_parse(&mut stream, &buffer[5..length + 1])
-                        .await?;
+                    let query = self.parse_query(&message[5..])?;
+                    self.handle_query(&mut stream, &mut buffer, &query).await?;
                 }
-                b'B' => {
-                    // Bind (extended query protocol)
+                b'P' | b'B' | b'D' | b'E' | b'S' | b'C' | b'H' => {
+                    // Parse, Bind, Describe, Execute, Sync, Close and Flush of the extended
+                    // query protocol
                     self.extended_protocol
-                        .handle_bind(&mut stream, &buffer[5..length + 1])
+                        .handle_message(
+                            msg_type,
+                            &mut stream,
+                            &mut buffer,
+                            &message[5..],
+                            &self.executor,
+                        )
                         .await?;
                 }
-                b'D' => {
-                    // Describe (extended query protocol)
-                    self.extended_protocol
-                        .handle_describe(&mut stream, &buffer[5..length + 1], &self.executor)
-                        .await?;
-                }
-                b'E' => {
-                    // Execute (extended query protocol)
-                    self.extended_protocol
-                        .handle_execute(&mut stream, &buffer[5..length + 1], &self.executor)
-                        .await?;
-                }
-                b'S' => {
-                    // Sync (extended query protocol)
-                    self.extended_protocol.handle_sync(&mut stream).await?;
-                }
-                b'C' => {
-                    // Close (extended query protocol)
-                    let close_type = buffer[5];
-                    let name_end = buffer[6..length + 1]
-                        .iter()
-                        .position(|&b| b == 0)
-                        .unwrap_or(length - 5);
-                    let name = std::str::from_utf8(&buffer[6..6 + name_end]).map_err(|_| {
-                        YamlBaseError::Protocol("Invalid UTF-8 in close name".to_string())
-                    })?;
-
-                    if close_type == b'S' {
-                        self.extended_protocol.close_statement(name);
-                    } else if close_type == b'P' {
-                        self.extended_protocol.close_portal(name);
-                    }
-
-                    // Send CloseComplete
-                    let mut close_buf = BytesMut::new();
-                    close_buf.put_u8(b'3');
-                    close_buf.put_u32(4);
-                    stream.write_all(&close_buf).await?;
-                }
                 b'X' => {
                     // Terminate
                     info!("Client requested termination");
@@ -146,22 +232,98 @@ impl PostgresProtocol {
                         .await?;
                 }
             }
-
-            // Remove the processed message from the buffer
-            buffer.advance(length + 1);
         }
 
         Ok(())
     }
 
-    async fn read_startup_message(
+    /// Read more of the client's messages. Meanwhile the notifications of the channels the
+    /// connection listens on are sent as they come.
+    async fn read_messages(
         &self,
-        stream: &mut TcpStream,
+        stream: &mut Stream,
+        buffer: &mut BytesMut,
+        notifications: &mut broadcast::Receiver<Notification>,
+    ) -> crate::Result<usize> {
+        let read = async {
+            loop {
+                tokio::select! {
+                    read = stream.read_buf(buffer) => return Ok(read?),
+                    notification = notifications.recv() => match notification {
+                        Ok(notification) if self.executor.is_listening(&notification.channel) => {
+                            send_notification(stream, &notification).await?;
+                        }
+                        Ok(_) => {}
+                        Err(broadcast::error::RecvError::Lagged(missed)) => {
+                            warn!("{} notifications were not delivered", missed);
+                        }
+                        Err(broadcast::error::RecvError::Closed) => {
+                            return Ok(stream.read_buf(buffer).await?);
+                        }
+                    },
+                }
+            }
+        };
+        // A session closed for being idle is as good as disconnected
+        next_request(self.config.idle_timeout, read)
+            .await
+            .unwrap_or(Ok(0))
+    }
+
+    /// Turn a client away, as PostgreSQL does beyond max_connections: the startup message
+    /// is read and answered with a FATAL error
+    pub async fn refuse_connection(&self, stream: TcpStream) -> crate::Result<()> {
+        let mut buffer = BytesMut::with_capacity(4096);
+        let mut stream = self.negotiate_tls(stream, &mut buffer).await?;
+        if buffer.is_empty() {
+            stream.read_buf(&mut buffer).await?;
+        }
+        send_error_response(
+            &mut stream,
+            "FATAL",
+            "53300",
+            "sorry, too many clients already",
+        )
+        .await
+    }
+
+    /// Answer an SSLRequest, upgrading the connection to TLS when it is configured. The
+    /// startup message that follows is left to `read_startup_message`.
+    async fn negotiate_tls(
+        &self,
+        mut stream: TcpStream,
+        buffer: &mut BytesMut,
+    ) -> crate::Result<Stream> {
+        stream.read_buf(buffer).await?;
+        if buffer.len() < 8 || buffer[4..8] != SSL_REQUEST_CODE.to_be_bytes() {
+            return Ok(Stream::Plain(stream));
+        }
+
+        buffer.clear();
+        match &self.tls {
+            Some(tls) => {
+                stream.write_all(b"S").await?;
+                let stream = tls.accept(stream).await?;
+                debug!("Connection upgraded to TLS");
+                Ok(Stream::Tls(Box::new(stream)))
+            }
+            None => {
+                stream.write_all(b"N").await?;
+                Ok(Stream::Plain(stream))
+            }
+        }
+    }
+
+    async fn read_startup_message(
+        &mut self,
+        stream: &mut Stream,
         buffer: &mut BytesMut,
         state: &mut ConnectionState,
     ) -> crate::Result<()> {
-        // Read startup packet
-        stream.read_buf(buffer).await?;
+        // Read startup packet, unless it came with the first read
+        if buffer.is_empty() {
+            stream.read_buf(buffer).await?;
+        }
 
         if buffer.len() < 8 {
             return Err(YamlBaseError::Protocol(
@@ -169,19 +331,7 @@ impl PostgresProtocol {
             ));
         }
 
-        let mut length = u32::from_be_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]) as usize;
-        let version = u32::from_be_bytes([buffer[4], buffer[5], buffer[6], buffer[7]]);
-
-        // Check for SSL request
-        if version == 80877103 {
-            // SSL request - we don't support it
-            stream.write_all(b"N").await?;
-            buffer.clear();
-            stream.read_buf(buffer).await?;
-
-            // Re-read the actual startup message
-            length = u32::from_be_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]) as usize;
-        }
+        let length = u32::from_be_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]) as usize;
 
         // Parse startup parameters
         let mut pos = 8;
@@ -212,52 +362,126 @@ impl PostgresProtocol {
             state.parameters.insert(key, val);
         }
 
-        // Send authentication request
-        self.send_auth_request(stream).await?;
-
-        // Read authentication response
+        let user = state
+            .username
+            .as_deref()
+            .and_then(|name| self.config.user(name));
+        debug!(
+            "Auth check - Got user: {:?}, known: {}, Allow anonymous: {}",
+            state.username,
+            user.is_some(),
+            self.config.allow_anonymous
+        );
+
+        // The startup message has been read
         buffer.clear();
-        stream.read_buf(buffer).await?;
-
-        if buffer.len() >= 5 && buffer[0] == b'p' {
-            // Password message
-            let msg_len = u32::from_be_bytes([buffer[1], buffer[2], buffer[3], buffer[4]]) as usize;
-            let password = self.parse_password_message(&buffer[5..5 + msg_len - 4])?;
-
-            // Verify credentials
-            debug!(
-                "Auth check - Expected: {}:{}, Got: {:?}:{}, Allow anonymous: {}",
-                self.config.username,
-                self.config.password,
-                state.username,
-                password,
-                self.config.allow_anonymous
-            );
-
-            if self.config.allow_anonymous
-                || (state.username.as_deref() == Some(&self.config.username)
-                    && password == self.config.password)
-            {
-                state.authenticated = true;
-                self.send_auth_ok(stream, state).await?;
-
-                // Clear the buffer after processing password message
-                buffer.advance(1 + msg_len);
-            } else {
-                self.send_error(stream, "28P01", "Authentication failed")
-                    .await?;
-                return Err(YamlBaseError::Protocol("Authentication failed".to_string()));
+        let username = state.username.as_deref().unwrap_or_default();
+        let authenticated = match certificate_login(&self.config, stream, username) {
+            CertificateLogin::Accepted => true,
+            CertificateLogin::Refused => {
+                let message = "connection requires a valid client certificate";
+                self.send_error(stream, "28000", message).await?;
+                return Err(YamlBaseError::Protocol(message.to_string()));
+            }
+            CertificateLogin::Password => {
+                self.authenticate(stream, buffer, user.map(|(password, _)| password))
+                    .await?
             }
+        };
+        if authenticated {
+            state.authenticated = true;
+            if let Some(name) = &state.database {
+                let Some(index) = self.databases.find(name) else {
+                    let message = format!("database \"{}\" does not exist", name);
+                    self.send_error(stream, "3D000", &message).await?;
+                    return Err(YamlBaseError::Protocol(message));
+                };
+                self.executor = Self::executor(&self.config, &self.databases, index).await?;
+            }
+            let grants = user.and_then(|(_, grants)| grants.cloned());
+            let quota = self.rate_limiter.quota(state.username.as_deref());
+            self.executor = self.executor.clone().with_grants(grants).with_quota(quota);
+            self.apply_startup_parameters(state);
+            self.send_auth_ok(stream, state).await?;
         } else {
-            return Err(YamlBaseError::Protocol(
-                "Expected password message".to_string(),
-            ));
+            self.send_error(stream, "28P01", "Authentication failed")
+                .await?;
+            return Err(YamlBaseError::Protocol("Authentication failed".to_string()));
         }
 
         Ok(())
     }
 
-    async fn send_auth_request(&self, stream: &mut TcpStream) -> crate::Result<()> {
+    /// Apply the settings the client sent with its startup message: parameters such as
+    /// `application_name` and `TimeZone`, and `-c name=value` in `options`. Like the
+    /// settings themselves, parameters the server does not know are ignored.
+    fn apply_startup_parameters(&self, state: &ConnectionState) {
+        let mut parameters = Vec::new();
+        for (name, value) in &state.parameters {
+            match name.as_str() {
+                "user" | "database" | "replication" => {}
+                "options" => {
+                    let mut words = value.split_whitespace();
+                    while let Some(word) = words.next() {
+                        let setting = match word.strip_prefix("--") {
+                            Some(setting) => Some(setting),
+                            None if word == "-c" => words.next(),
+                            None => word.strip_prefix("-c"),
+                        };
+                        if let Some((name, value)) = setting.and_then(|s| s.split_once('=')) {
+                            parameters.push((name.replace('-', "_"), value.to_string()));
+                        }
+                    }
+                }
+                _ => parameters.push((name.clone(), value.clone())),
+            }
+        }
+        for (name, value) in parameters {
+            if let Err(e) = self.executor.set_startup_parameter(&name, &value) {
+                debug!("Ignoring startup parameter {}: {}", name, e);
+            }
+        }
+    }
+
+    /// Ask for the password of the user. Plain text passwords are checked with
+    /// SCRAM-SHA-256 unless `--auth-method password` is given; a hash can only be
+    /// checked against the password itself, so it is asked for in clear text.
+    async fn authenticate(
+        &self,
+        stream: &mut Stream,
+        buffer: &mut BytesMut,
+        expected: Option<Password<'_>>,
+    ) -> crate::Result<bool> {
+        let scram = !self.config.allow_anonymous
+            && self.config.auth_method.unwrap_or_default() == AuthMethod::ScramSha256;
+        match expected {
+            Some(Password::Plain(password)) if scram => {
+                ScramSha256::new(password)
+                    .authenticate(stream, buffer)
+                    .await
+            }
+            None if scram => {
+                // An unknown user goes through the same exchange, which cannot succeed,
+                // so clients cannot tell whether a user exists
+                let mut password = [0u8; 16];
+                rand::thread_rng().fill_bytes(&mut password);
+                let password = hex::encode(password);
+                ScramSha256::new(&password)
+                    .authenticate(stream, buffer)
+                    .await?;
+                Ok(false)
+            }
+            _ => {
+                self.send_auth_request(stream).await?;
+                let message = read_password_message(stream, buffer).await?;
+                let password = self.parse_password_message(&message)?;
+                Ok(self.config.allow_anonymous
+                    || expected.is_some_and(|expected| expected.verify(&password)))
+            }
+        }
+    }
+
+    async fn send_auth_request(&self, stream: &mut Stream) -> crate::Result<()> {
         // Request clear text password authentication
         let mut buf = BytesMut::new();
         buf.put_u8(b'R');
@@ -270,8 +494,8 @@ impl PostgresProtocol {
 
     async fn send_auth_ok(
         &self,
-        stream: &mut TcpStream,
-        _state: &ConnectionState,
+        stream: &mut Stream,
+        state: &ConnectionState,
     ) -> crate::Result<()> {
         // Authentication OK
         let mut buf = BytesMut::new();
@@ -284,21 +508,14 @@ impl PostgresProtocol {
         buf.clear();
         buf.put_u8(b'K');
         buf.put_u32(12);
-        buf.put_u32(12345); // Process ID
-        buf.put_u32(67890); // Secret key
+        buf.put_u32(state.backend_key.0); // Process ID
+        buf.put_u32(state.backend_key.1); // Secret key
         stream.write_all(&buf).await?;
 
         // Send parameter status messages
-        self.send_parameter_status(stream, "server_version", "14.0")
-            .await?;
-        self.send_parameter_status(stream, "server_encoding", "UTF8")
-            .await?;
-        self.send_parameter_status(stream, "client_encoding", "UTF8")
-            .await?;
-        self.send_parameter_status(stream, "DateStyle", "ISO, MDY")
-            .await?;
-        self.send_parameter_status(stream, "TimeZone", "UTC")
-            .await?;
+        for (name, value) in self.executor.reported_parameters() {
+            send_parameter_status(stream, &name, &value).await?;
+        }
 
         // Ready for query
         self.send_ready_for_query(stream).await?;
@@ -306,68 +523,169 @@ impl PostgresProtocol {
         Ok(())
     }
 
-    async fn send_parameter_status(
-        &self,
-        stream: &mut TcpStream,
-        name: &str,
-        value: &str,
-    ) -> crate::Result<()> {
-        let mut buf = BytesMut::new();
-        buf.put_u8(b'S');
-        let length = 4 + name.len() + 1 + value.len() + 1;
-        buf.put_u32(length as u32);
-        buf.put_slice(name.as_bytes());
-        buf.put_u8(0);
-        buf.put_slice(value.as_bytes());
-        buf.put_u8(0);
-
-        stream.write_all(&buf).await?;
-        Ok(())
-    }
-
-    async fn send_ready_for_query(&self, stream: &mut TcpStream) -> crate::Result<()> {
+    async fn send_ready_for_query(&self, stream: &mut Stream) -> crate::Result<()> {
         let mut buf = BytesMut::new();
         buf.put_u8(b'Z');
         buf.put_u32(5);
-        buf.put_u8(b'I'); // Idle
+        buf.put_u8(transaction_status(&self.executor));
 
         stream.write_all(&buf).await?;
         Ok(())
     }
 
-    async fn handle_query(&self, stream: &mut TcpStream, query: &str) -> crate::Result<()> {
+    async fn handle_query(
+        &mut self,
+        stream: &mut Stream,
+        buffer: &mut BytesMut,
+        query: &str,
+    ) -> crate::Result<()> {
         debug!("Executing query: {}", query);
 
-        // Parse SQL
-        let statements = match parse_sql(query) {
-            Ok(stmts) => stmts,
-            Err(e) => {
-                self.send_error(stream, "42601", &format!("Syntax error: {}", e))
-                    .await?;
-                self.send_ready_for_query(stream).await?;
-                return Ok(());
+        // A query may hold several statements; they are all parsed before any of them runs
+        let mut statements = Vec::new();
+        for sql in split_statements(query, SqlDialect::PostgreSQL) {
+            if is_reload_database(sql) {
+                statements.push(BatchStatement::ReloadDatabase);
+                continue;
             }
-        };
+            if let Some(view) = parse_refresh_materialized_view(sql) {
+                statements.push(BatchStatement::RefreshMaterializedView(view));
+                continue;
+            }
+            if let Some(channel) = parse_unlisten(sql) {
+                statements.push(BatchStatement::Unlisten(channel));
+                continue;
+            }
+            if let Some(parameter) = parse_reset(sql) {
+                statements.push(BatchStatement::Reset(parameter));
+                continue;
+            }
+            match parse_sql(sql) {
+                Ok(parsed) => statements.extend(parsed.into_iter().map(|s| match s {
+                    Statement::Copy { .. } => BatchStatement::Copy(Box::new(s)),
+                    Statement::Deallocate { name, .. } => {
+                        BatchStatement::Deallocate(deallocate_target(&name).0.map(str::to_string))
+                    }
+                    s => BatchStatement::Sql(Box::new(s)),
+                })),
+                Err(e) => {
+                    self.send_error(stream, "42601", &format!("Syntax error: {}", e))
+                        .await?;
+                    self.send_ready_for_query(stream).await?;
+                    return Ok(());
+                }
+            }
+        }
 
-        for statement in statements {
-            match self.executor.execute(&statement).await {
-                Ok(result) => {
-                    self.send_query_result(stream, &result).await?;
+        if statements.is_empty() {
+            self.send_empty_query_response(stream).await?;
+            self.send_ready_for_query(stream).await?;
+            return Ok(());
+        }
+
+        // Like PostgreSQL, run a multi-statement query as one transaction unless it manages
+        // transactions itself, and stop at the first error
+        let implicit_transaction = statements.len() > 1
+            && !statements.iter().any(|statement| {
+                matches!(statement, BatchStatement::Sql(statement)
+                    if QueryExecutor::is_transaction_statement(statement))
+            })
+            && self.executor.begin_implicit_transaction();
+
+        let mut succeeded = true;
+        for statement in &statements {
+            let outcome = match statement {
+                BatchStatement::Copy(statement) => {
+                    match execute_copy(stream, buffer, &self.executor, statement).await {
+                        Ok(rows) => {
+                            self.send_command_complete(stream, &format!("COPY {}", rows))
+                                .await?;
+                            Ok(())
+                        }
+                        Err(e) => Err(e),
+                    }
                 }
-                Err(e) => {
-                    self.send_error(stream, "XX000", &e.to_string()).await?;
+                BatchStatement::RefreshMaterializedView(view) => {
+                    match self.executor.refresh_materialized_view(view).await {
+                        Ok(()) => {
+                            self.send_command_complete(stream, "REFRESH MATERIALIZED VIEW")
+                                .await?;
+                            Ok(())
+                        }
+                        Err(e) => Err(e),
+                    }
+                }
+                BatchStatement::Unlisten(channel) => {
+                    self.executor.unlisten(channel.as_deref());
+                    self.send_command_complete(stream, "UNLISTEN").await?;
+                    Ok(())
                 }
+                BatchStatement::Reset(parameter) => {
+                    match self.executor.reset_parameter(parameter.as_deref()) {
+                        Ok(changed) => {
+                            for (name, value) in changed {
+                                send_parameter_status(stream, &name, &value).await?;
+                            }
+                            self.send_command_complete(stream, "RESET").await?;
+                            Ok(())
+                        }
+                        Err(e) => Err(e),
+                    }
+                }
+                BatchStatement::Deallocate(name) => {
+                    match self.extended_protocol.deallocate(name.as_deref()) {
+                        Ok(()) => {
+                            let tag = match name {
+                                Some(_) => "DEALLOCATE",
+                                None => "DEALLOCATE ALL",
+                            };
+                            self.send_command_complete(stream, tag).await?;
+                            Ok(())
+                        }
+                        Err(e) => Err(e),
+                    }
+                }
+                BatchStatement::ReloadDatabase => match self.executor.reload_database().await {
+                    Ok(()) => {
+                        self.send_command_complete(stream, "RELOAD").await?;
+                        Ok(())
+                    }
+                    Err(e) => Err(e),
+                },
+                BatchStatement::Sql(statement) => match self.executor.execute(statement).await {
+                    Ok(result) => {
+                        if let Some((name, value)) = self.executor.changed_parameter(statement) {
+                            send_parameter_status(stream, &name, &value).await?;
+                        }
+                        self.send_query_result(stream, statement, &result).await?;
+                        Ok(())
+                    }
+                    Err(e) => Err(e),
+                },
+            };
+            if let Err(e) = outcome {
+                self.send_error(stream, sqlstate(&e), &e.to_string())
+                    .await?;
+                succeeded = false;
+                break;
             }
         }
 
+        if implicit_transaction
+            && let Err(e) = self.executor.end_implicit_transaction(succeeded).await
+        {
+            self.send_error(stream, "XX000", &e.to_string()).await?;
+        }
+
         self.send_ready_for_query(stream).await?;
         Ok(())
     }
 
     async fn send_query_result(
         &self,
-        stream: &mut TcpStream,
-        result: &crate::sql::executor::QueryResult,
+        stream: &mut Stream,
+        statement: &Statement,
+        result: &QueryResult,
     ) -> crate::Result<()> {
         // For empty results (like transaction commands), skip row description
         if !result.columns.is_empty() {
@@ -391,8 +709,14 @@ impl PostgresProtocol {
                 buf.put_u16(i as u16); // Column number
 
                 // For simple protocol, we always send text format, so declare as text
-                // to match the text data we send
-                buf.put_u32(25); // text OID
+                // to match the text data we send; bytea's text format is its hex and
+                // an array's is `{a,b}`
+                match result.column_types.get(i) {
+                    Some(sql_type @ (SqlType::Blob | SqlType::Array(_))) => {
+                        buf.put_u32(sql_type_to_oid(sql_type))
+                    }
+                    _ => buf.put_u32(25), // text OID
+                }
 
                 buf.put_i16(-1); // Type size
                 buf.put_i32(-1); // Type modifier
@@ -435,15 +759,13 @@ impl PostgresProtocol {
             stream.write_all(&buf).await?;
         }
 
-        // Send command complete
+        self.send_command_complete(stream, &command_tag(statement, result))
+            .await
+    }
+
+    async fn send_command_complete(&self, stream: &mut Stream, tag: &str) -> crate::Result<()> {
         let mut buf = BytesMut::new();
         buf.put_u8(b'C');
-        let tag = if result.columns.is_empty() {
-            // For transaction commands, use appropriate command tag
-            "BEGIN".to_string() // This is generic - ideally we'd track the actual command
-        } else {
-            format!("SELECT {}", result.rows.len())
-        };
         buf.put_u32(4 + tag.len() as u32 + 1);
         buf.put_slice(tag.as_bytes());
         buf.put_u8(0);
@@ -452,34 +774,22 @@ impl PostgresProtocol {
         Ok(())
     }
 
+    async fn send_empty_query_response(&self, stream: &mut Stream) -> crate::Result<()> {
+        let mut buf = BytesMut::new();
+        buf.put_u8(b'I');
+        buf.put_u32(4);
+
+        stream.write_all(&buf).await?;
+        Ok(())
+    }
+
     async fn send_error(
         &self,
-        stream: &mut TcpStream,
+        stream: &mut Stream,
         code: &str,
         message: &str,
     ) -> crate::Result<()> {
-        let mut buf = BytesMut::new();
-        buf.put_u8(b'E');
-
-        let error_fields = vec![(b'S', "ERROR"), (b'C', code), (b'M', message)];
-
-        let mut length = 4; // Length field
-        for (_, val) in &error_fields {
-            length += 1 + val.len() + 1; // Field type + value + null
-        }
-        length += 1; // Final null
-
-        buf.put_u32(length as u32);
-
-        for (field_type, val) in error_fields {
-            buf.put_u8(field_type);
-            buf.put_slice(val.as_bytes());
-            buf.put_u8(0);
-        }
-        buf.put_u8(0); // End of fields
-
-        stream.write_all(&buf).await?;
-        Ok(())
+        send_error_response(stream, "ERROR", code, message).await
     }
 
     fn parse_query(&self, data: &[u8]) -> crate::Result<String> {
@@ -496,3 +806,123 @@ impl PostgresProtocol {
             .to_string())
     }
 }
+
+/// The ReadyForQuery status: idle, in a transaction, or in a failed transaction
+pub(crate) fn transaction_status(executor: &QueryExecutor) -> u8 {
+    if !executor.in_transaction() {
+        b'I'
+    } else if executor.transaction_failed() {
+        b'E'
+    } else {
+        b'T'
+    }
+}
+
+/// The SQLSTATE of the error a statement failed with
+pub(crate) fn sqlstate(error: &YamlBaseError) -> &'static str {
+    match error {
+        YamlBaseError::PermissionDenied(_) => "42501",
+        YamlBaseError::NotImplemented(_) => "0A000",
+        YamlBaseError::QueryCancelled => "57014",
+        YamlBaseError::RateLimited(_) => "53400",
+        YamlBaseError::UnknownPreparedStatement(_) => "26000",
+        YamlBaseError::DuplicatePreparedStatement(_) => "42P05",
+        YamlBaseError::UniqueViolation { .. } => "23505",
+        YamlBaseError::TransactionAborted => "25P02",
+        YamlBaseError::CheckViolation { .. } => "23514",
+        YamlBaseError::Protocol(_) => "08P01",
+        _ => "XX000",
+    }
+}
+
+/// Tell the client the value of a parameter, at startup and when SET or RESET changes it
+pub(crate) async fn send_parameter_status(
+    stream: &mut Stream,
+    name: &str,
+    value: &str,
+) -> crate::Result<()> {
+    let mut buf = BytesMut::new();
+    buf.put_u8(b'S');
+    let length = 4 + name.len() + 1 + value.len() + 1;
+    buf.put_u32(length as u32);
+    buf.put_slice(name.as_bytes());
+    buf.put_u8(0);
+    buf.put_slice(value.as_bytes());
+    buf.put_u8(0);
+
+    stream.write_all(&buf).await?;
+    Ok(())
+}
+
+/// NotificationResponse. The process id of the sender is not kept, so it is sent as 0.
+async fn send_notification(stream: &mut Stream, notification: &Notification) -> crate::Result<()> {
+    let mut buf = BytesMut::new();
+    buf.put_u8(b'A');
+    buf.put_u32((4 + 4 + notification.channel.len() + 1 + notification.payload.len() + 1) as u32);
+    buf.put_u32(0);
+    buf.put_slice(notification.channel.as_bytes());
+    buf.put_u8(0);
+    buf.put_slice(notification.payload.as_bytes());
+    buf.put_u8(0);
+    stream.write_all(&buf).await?;
+    Ok(())
+}
+
+/// An ErrorResponse of the given severity: ERROR ends a statement, FATAL the connection
+async fn send_error_response(
+    stream: &mut Stream,
+    severity: &str,
+    code: &str,
+    message: &str,
+) -> crate::Result<()> {
+    let mut buf = BytesMut::new();
+    buf.put_u8(b'E');
+
+    let error_fields = vec![(b'S', severity), (b'C', code), (b'M', message)];
+
+    let mut length = 4; // Length field
+    for (_, val) in &error_fields {
+        length += 1 + val.len() + 1; // Field type + value + null
+    }
+    length += 1; // Final null
+
+    buf.put_u32(length as u32);
+
+    for (field_type, val) in error_fields {
+        buf.put_u8(field_type);
+        buf.put_slice(val.as_bytes());
+        buf.put_u8(0);
+    }
+    buf.put_u8(0); // End of fields
+
+    stream.write_all(&buf).await?;
+    Ok(())
+}
+
+/// The CommandComplete tag for a statement, with the row count clients read from it
+pub(crate) fn command_tag(statement: &Statement, result: &QueryResult) -> String {
+    let affected_rows = result.affected_rows.unwrap_or(0);
+    match statement {
+        // The 0 is the OID of the inserted row, which PostgreSQL no longer reports
+        Statement::Insert(_) => format!("INSERT 0 {}", affected_rows),
+        Statement::Update { .. } => format!("UPDATE {}", affected_rows),
+        Statement::Delete(_) => format!("DELETE {}", affected_rows),
+        Statement::Merge { .. } => format!("MERGE {}", affected_rows),
+        // CREATE TABLE ... AS reports the rows it stored, like a SELECT
+        Statement::CreateTable(create) if create.query.is_some() => {
+            format!("SELECT {}", affected_rows)
+        }
+        Statement::CreateTable(_) => "CREATE TABLE".to_string(),
+        Statement::Drop { .. } => "DROP TABLE".to_string(),
+        Statement::StartTransaction { .. } => "BEGIN".to_string(),
+        Statement::Commit { .. } => "COMMIT".to_string(),
+        Statement::Rollback { .. } => "ROLLBACK".to_string(),
+        Statement::Savepoint { .. } => "SAVEPOINT".to_string(),
+        Statement::ReleaseSavepoint { .. } => "RELEASE".to_string(),
+        Statement::SetVariable { .. } | Statement::SetTimeZone { .. } => "SET".to_string(),
+        Statement::ShowVariable { .. } => "SHOW".to_string(),
+        Statement::LISTEN { .. } => "LISTEN".to_string(),
+        Statement::NOTIFY { .. } => "NOTIFY".to_string(),
+        _ => format!("SELECT {}", result.rows.len()),
+    }
+}
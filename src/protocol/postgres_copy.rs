@@ -0,0 +1,519 @@
+// COPY over the PostgreSQL protocol: the rows of a table or query are sent to the client in
+// text or CSV format, as `psql \copy` and bulk extraction tools expect them, and rows the
+// client sends are inserted into a table, like the INSERTs of a seed script.
+use bytes::{Buf, BufMut, BytesMut};
+use sqlparser::ast::{
+    CopyLegacyCsvOption, CopyLegacyOption, CopyOption, CopySource, CopyTarget, ObjectName,
+    Statement,
+};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+use crate::YamlBaseError;
+use crate::database::Value;
+use crate::protocol::tls::Stream;
+use crate::sql::{QueryExecutor, parse_sql};
+
+/// Run `COPY ... TO STDOUT` or `COPY ... FROM STDIN`, reading the rows the client sends
+/// from the connection. Returns the number of rows, for the command tag.
+pub(crate) async fn execute_copy(
+    stream: &mut Stream,
+    buffer: &mut BytesMut,
+    executor: &QueryExecutor,
+    statement: &Statement,
+) -> crate::Result<usize> {
+    let Statement::Copy {
+        source,
+        to,
+        target,
+        options,
+        legacy_options,
+        ..
+    } = statement
+    else {
+        return Err(YamlBaseError::Protocol(
+            "Expected a COPY statement".to_string(),
+        ));
+    };
+
+    let options = CopyOptions::new(options, legacy_options)?;
+    match (*to, target) {
+        (true, CopyTarget::Stdout) => copy_to_stdout(stream, executor, source, &options).await,
+        (false, CopyTarget::Stdin) => {
+            copy_from_stdin(stream, buffer, executor, source, &options).await
+        }
+        _ => Err(YamlBaseError::NotImplemented(
+            "COPY is only supported TO STDOUT and FROM STDIN".to_string(),
+        )),
+    }
+}
+
+/// CopyOutResponse, a CopyData message for each line, then CopyDone
+async fn copy_to_stdout(
+    stream: &mut Stream,
+    executor: &QueryExecutor,
+    source: &CopySource,
+    options: &CopyOptions,
+) -> crate::Result<usize> {
+    let result = executor.execute(&copy_query(source)?).await?;
+
+    let mut buf = BytesMut::new();
+    put_copy_response(&mut buf, b'H', result.columns.len());
+    let header = options.header.then(|| options.header_line(&result.columns));
+    let rows = result.rows.iter().map(|row| options.row_line(row));
+    for line in header.into_iter().chain(rows) {
+        buf.put_u8(b'd');
+        buf.put_u32(4 + line.len() as u32);
+        buf.put_slice(line.as_bytes());
+    }
+
+    buf.put_u8(b'c');
+    buf.put_u32(4);
+    stream.write_all(&buf).await?;
+    Ok(result.rows.len())
+}
+
+/// CopyInResponse, then the client's CopyData messages up to CopyDone. The rows are
+/// inserted with one INSERT once they have all arrived, so they go wherever an INSERT
+/// would put them and a bad row leaves the table as it was.
+async fn copy_from_stdin(
+    stream: &mut Stream,
+    buffer: &mut BytesMut,
+    executor: &QueryExecutor,
+    source: &CopySource,
+    options: &CopyOptions,
+) -> crate::Result<usize> {
+    let CopySource::Table {
+        table_name,
+        columns,
+    } = source
+    else {
+        return Err(YamlBaseError::Database {
+            message: "COPY FROM needs a table, not a query".to_string(),
+        });
+    };
+
+    // The table and its columns are checked before the client starts sending rows
+    let sql = format!("{} LIMIT 0", select_columns(table_name, columns));
+    let columns = match parse_sql(&sql)?.first() {
+        Some(statement) => executor.execute(statement).await?.columns,
+        None => Vec::new(),
+    };
+
+    let mut buf = BytesMut::new();
+    put_copy_response(&mut buf, b'G', columns.len());
+    stream.write_all(&buf).await?;
+
+    let data = read_copy_data(stream, buffer).await?;
+    let data = String::from_utf8(data).map_err(|_| YamlBaseError::Database {
+        message: "COPY data is not valid UTF-8".to_string(),
+    })?;
+    let rows = options.parse_rows(&data)?;
+    if rows.is_empty() {
+        return Ok(0);
+    }
+
+    let mut sql = format!(
+        "INSERT INTO {} ({}) VALUES ",
+        table_name,
+        columns
+            .iter()
+            .map(|column| format!("\"{}\"", column.replace('"', "\"\"")))
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    for (i, row) in rows.iter().enumerate() {
+        if row.len() != columns.len() {
+            return Err(YamlBaseError::Database {
+                message: format!(
+                    "COPY row {} has {} fields but {} columns are copied",
+                    i + 1,
+                    row.len(),
+                    columns.len()
+                ),
+            });
+        }
+        let values = row
+            .iter()
+            .map(|field| match field {
+                Some(field) => format!("'{}'", field.replace('\'', "''")),
+                None => "NULL".to_string(),
+            })
+            .collect::<Vec<_>>();
+        if i > 0 {
+            sql.push_str(", ");
+        }
+        sql.push('(');
+        sql.push_str(&values.join(", "));
+        sql.push(')');
+    }
+
+    let Some(insert) = parse_sql(&sql)?.into_iter().next() else {
+        return Ok(0);
+    };
+    let result = executor.execute(&insert).await?;
+    Ok(result.affected_rows.unwrap_or(0) as usize)
+}
+
+/// CopyInResponse or CopyOutResponse, with every column in text format
+fn put_copy_response(buf: &mut BytesMut, kind: u8, columns: usize) {
+    buf.put_u8(kind);
+    buf.put_u32(4 + 1 + 2 + 2 * columns as u32);
+    buf.put_u8(0);
+    buf.put_u16(columns as u16);
+    for _ in 0..columns {
+        buf.put_u16(0);
+    }
+}
+
+/// The contents of the client's CopyData messages, up to CopyDone. Flush and Sync are
+/// ignored while copying, as PostgreSQL does: clients send them ahead of the data.
+async fn read_copy_data(stream: &mut Stream, buffer: &mut BytesMut) -> crate::Result<Vec<u8>> {
+    let mut data = Vec::new();
+    loop {
+        if buffer.len() >= 5 {
+            let length = u32::from_be_bytes([buffer[1], buffer[2], buffer[3], buffer[4]]) as usize;
+            if length < 4 {
+                return Err(YamlBaseError::Protocol(
+                    "Invalid message length".to_string(),
+                ));
+            }
+            if buffer.len() > length {
+                let kind = buffer[0];
+                let mut message = buffer.split_to(length + 1);
+                message.advance(5);
+                match kind {
+                    b'd' => data.extend_from_slice(&message),
+                    b'c' => return Ok(data),
+                    b'f' => {
+                        let reason = String::from_utf8_lossy(&message);
+                        return Err(YamlBaseError::Database {
+                            message: format!(
+                                "COPY from stdin failed: {}",
+                                reason.trim_end_matches('\0')
+                            ),
+                        });
+                    }
+                    b'H' | b'S' => {}
+                    kind => {
+                        return Err(YamlBaseError::Protocol(format!(
+                            "Unexpected message type '{}' during COPY from stdin",
+                            kind as char
+                        )));
+                    }
+                }
+                continue;
+            }
+        }
+        if stream.read_buf(buffer).await? == 0 {
+            return Err(YamlBaseError::Protocol(
+                "Connection closed during COPY from stdin".to_string(),
+            ));
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CopyFormat {
+    Text,
+    Csv,
+}
+
+/// The options of a COPY statement, with PostgreSQL's defaults for its format
+#[derive(Debug, Clone, PartialEq)]
+struct CopyOptions {
+    pub format: CopyFormat,
+    pub delimiter: char,
+    pub null: String,
+    pub header: bool,
+    pub quote: char,
+    pub escape: char,
+}
+
+impl CopyOptions {
+    pub fn new(options: &[CopyOption], legacy_options: &[CopyLegacyOption]) -> crate::Result<Self> {
+        let csv = options.iter().any(|option| {
+            matches!(option, CopyOption::Format(format) if format.value.eq_ignore_ascii_case("csv"))
+        }) || legacy_options
+            .iter()
+            .any(|option| matches!(option, CopyLegacyOption::Csv(_)));
+        let mut copy = if csv {
+            Self {
+                format: CopyFormat::Csv,
+                delimiter: ',',
+                null: String::new(),
+                header: false,
+                quote: '"',
+                escape: '"',
+            }
+        } else {
+            Self {
+                format: CopyFormat::Text,
+                delimiter: '\t',
+                null: "\\N".to_string(),
+                header: false,
+                quote: '"',
+                escape: '"',
+            }
+        };
+
+        let mut escape = None;
+        for option in options {
+            match option {
+                CopyOption::Format(format) => match format.value.to_lowercase().as_str() {
+                    "text" | "csv" => {}
+                    "binary" => return Err(binary_not_supported()),
+                    other => {
+                        return Err(YamlBaseError::Database {
+                            message: format!("COPY format \"{}\" not recognized", other),
+                        });
+                    }
+                },
+                CopyOption::Delimiter(delimiter) => copy.delimiter = *delimiter,
+                CopyOption::Null(null) => copy.null = null.clone(),
+                CopyOption::Header(header) => copy.header = *header,
+                CopyOption::Quote(quote) => copy.quote = *quote,
+                CopyOption::Escape(e) => escape = Some(*e),
+                // Other options only change how rows are read or where they go
+                _ => {}
+            }
+        }
+        for option in legacy_options {
+            match option {
+                CopyLegacyOption::Binary => return Err(binary_not_supported()),
+                CopyLegacyOption::Delimiter(delimiter) => copy.delimiter = *delimiter,
+                CopyLegacyOption::Null(null) => copy.null = null.clone(),
+                CopyLegacyOption::Csv(csv_options) => {
+                    for csv_option in csv_options {
+                        match csv_option {
+                            CopyLegacyCsvOption::Header => copy.header = true,
+                            CopyLegacyCsvOption::Quote(quote) => copy.quote = *quote,
+                            CopyLegacyCsvOption::Escape(e) => escape = Some(*e),
+                            _ => {}
+                        }
+                    }
+                }
+            }
+        }
+        // The escape character defaults to the quote character
+        copy.escape = escape.unwrap_or(copy.quote);
+        Ok(copy)
+    }
+
+    /// A line of the column names, for HEADER
+    pub fn header_line(&self, columns: &[String]) -> String {
+        self.line(columns.iter().map(|column| Some(column.clone())))
+    }
+
+    /// A row as one line of the format, ending in a newline
+    pub fn row_line(&self, row: &[Value]) -> String {
+        self.line(row.iter().map(|value| match value {
+            Value::Null => None,
+            value => Some(value.to_string()),
+        }))
+    }
+
+    fn line(&self, fields: impl Iterator<Item = Option<String>>) -> String {
+        let mut line = String::new();
+        for (i, field) in fields.enumerate() {
+            if i > 0 {
+                line.push(self.delimiter);
+            }
+            match field {
+                None => line.push_str(&self.null),
+                Some(field) => match self.format {
+                    CopyFormat::Text => self.push_text(&mut line, &field),
+                    CopyFormat::Csv => self.push_csv(&mut line, &field),
+                },
+            }
+        }
+        line.push('\n');
+        line
+    }
+
+    fn push_text(&self, line: &mut String, field: &str) {
+        for c in field.chars() {
+            match c {
+                '\\' => line.push_str("\\\\"),
+                '\n' => line.push_str("\\n"),
+                '\r' => line.push_str("\\r"),
+                '\t' => line.push_str("\\t"),
+                c if c == self.delimiter => {
+                    line.push('\\');
+                    line.push(c);
+                }
+                c => line.push(c),
+            }
+        }
+    }
+
+    // Fields are quoted when they hold special characters, or would read back as NULL
+    fn push_csv(&self, line: &mut String, field: &str) {
+        let quoted = field == self.null
+            || field.contains([self.delimiter, self.quote, '\n', '\r'])
+            || (self.escape != self.quote && field.contains(self.escape));
+        if !quoted {
+            line.push_str(field);
+            return;
+        }
+        line.push(self.quote);
+        for c in field.chars() {
+            if c == self.quote || c == self.escape {
+                line.push(self.escape);
+            }
+            line.push(c);
+        }
+        line.push(self.quote);
+    }
+
+    /// The rows of COPY data, with `None` for NULL fields, without the header line
+    pub fn parse_rows(&self, data: &str) -> crate::Result<Vec<Vec<Option<String>>>> {
+        let mut rows = match self.format {
+            CopyFormat::Text => data
+                .lines()
+                .take_while(|line| *line != "\\.")
+                .map(|line| self.split_text(line))
+                .collect(),
+            CopyFormat::Csv => self.split_csv(data)?,
+        };
+        if self.header && !rows.is_empty() {
+            rows.remove(0);
+        }
+        Ok(rows)
+    }
+
+    fn split_text(&self, line: &str) -> Vec<Option<String>> {
+        let mut fields = Vec::new();
+        // The field as sent is what is compared with the NULL string
+        let mut raw = String::new();
+        let mut field = String::new();
+        let mut chars = line.chars();
+        loop {
+            match chars.next() {
+                Some(c) if c == self.delimiter => {
+                    fields.push((raw != self.null).then(|| field.clone()));
+                    raw.clear();
+                    field.clear();
+                }
+                Some('\\') => {
+                    raw.push('\\');
+                    if let Some(c) = chars.next() {
+                        raw.push(c);
+                        field.push(match c {
+                            'b' => '\u{8}',
+                            'f' => '\u{c}',
+                            'n' => '\n',
+                            'r' => '\r',
+                            't' => '\t',
+                            'v' => '\u{b}',
+                            c => c,
+                        });
+                    }
+                }
+                Some(c) => {
+                    raw.push(c);
+                    field.push(c);
+                }
+                None => {
+                    fields.push((raw != self.null).then_some(field));
+                    return fields;
+                }
+            }
+        }
+    }
+
+    // Quoted fields may hold delimiters and line breaks, and are never NULL
+    fn split_csv(&self, data: &str) -> crate::Result<Vec<Vec<Option<String>>>> {
+        let mut rows = Vec::new();
+        let mut row = Vec::new();
+        let mut field = String::new();
+        let mut quoted = false;
+        let mut in_quotes = false;
+        let finish = |field: &mut String, quoted: &mut bool| {
+            let value = (*quoted || *field != self.null).then(|| std::mem::take(field));
+            field.clear();
+            *quoted = false;
+            value
+        };
+
+        let mut chars = data.chars().peekable();
+        while let Some(c) = chars.next() {
+            if in_quotes {
+                if c == self.escape
+                    && chars
+                        .peek()
+                        .is_some_and(|&next| next == self.quote || next == self.escape)
+                {
+                    field.extend(chars.next());
+                } else if c == self.quote {
+                    in_quotes = false;
+                } else {
+                    field.push(c);
+                }
+            } else if c == self.quote {
+                in_quotes = true;
+                quoted = true;
+            } else if c == self.delimiter {
+                row.push(finish(&mut field, &mut quoted));
+            } else if c == '\n' || c == '\r' {
+                if c == '\r' && chars.peek() == Some(&'\n') {
+                    chars.next();
+                }
+                // A line of just \. marks the end of the data
+                if row.is_empty() && !quoted && field == "\\." {
+                    return Ok(rows);
+                }
+                row.push(finish(&mut field, &mut quoted));
+                rows.push(std::mem::take(&mut row));
+            } else {
+                field.push(c);
+            }
+        }
+        if in_quotes {
+            return Err(YamlBaseError::Database {
+                message: "COPY data ends inside a quoted CSV field".to_string(),
+            });
+        }
+        if !row.is_empty() || !field.is_empty() || quoted {
+            if row.is_empty() && !quoted && field == "\\." {
+                return Ok(rows);
+            }
+            row.push(finish(&mut field, &mut quoted));
+            rows.push(row);
+        }
+        Ok(rows)
+    }
+}
+
+fn binary_not_supported() -> YamlBaseError {
+    YamlBaseError::NotImplemented("COPY in binary format is not supported".to_string())
+}
+
+/// The query whose rows `COPY ... TO` sends: the query itself, or the columns of a table
+fn copy_query(source: &CopySource) -> crate::Result<Statement> {
+    match source {
+        CopySource::Query(query) => Ok(Statement::Query(query.clone())),
+        CopySource::Table {
+            table_name,
+            columns,
+        } => parse_sql(&select_columns(table_name, columns))?
+            .into_iter()
+            .next()
+            .ok_or_else(|| YamlBaseError::Database {
+                message: format!("Cannot copy from {}", table_name),
+            }),
+    }
+}
+
+/// A SELECT of the columns a COPY names, or all of them
+fn select_columns(table_name: &ObjectName, columns: &[sqlparser::ast::Ident]) -> String {
+    let columns = if columns.is_empty() {
+        "*".to_string()
+    } else {
+        columns
+            .iter()
+            .map(|column| column.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    format!("SELECT {} FROM {}", columns, table_name)
+}
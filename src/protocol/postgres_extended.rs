@@ -1,16 +1,23 @@
 use bytes::{BufMut, BytesMut};
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
 use std::collections::HashMap;
+use std::sync::Arc;
 use tokio::io::AsyncWriteExt;
-use tokio::net::TcpStream;
 use tracing::debug;
 
 use crate::YamlBaseError;
-use crate::database::Value;
+use crate::database::{Table, Value};
+use crate::protocol::postgres::{command_tag, send_parameter_status, sqlstate, transaction_status};
+use crate::protocol::postgres_copy::execute_copy;
+use crate::protocol::tls::Stream;
 use crate::sql::executor::QueryResult;
 use crate::sql::{QueryExecutor, parse_sql};
 use crate::yaml::schema::SqlType;
 use sqlparser::ast::{
-    Expr, FunctionArg, FunctionArgExpr, FunctionArguments, SelectItem, Statement, Value as SqlValue,
+    AssignmentTarget, Expr, FunctionArg, FunctionArgExpr, FunctionArguments, SelectItem, SetExpr,
+    Statement, Value as SqlValue,
 };
 
 #[derive(Debug, Clone)]
@@ -19,6 +26,8 @@ pub struct PreparedStatement {
     pub query: String,
     pub parameter_types: Vec<SqlType>,
     pub parsed_statements: Vec<sqlparser::ast::Statement>,
+    /// The column types a Describe reported, which binary results are encoded as
+    pub result_types: Option<Vec<SqlType>>,
 }
 
 #[derive(Debug, Clone)]
@@ -27,11 +36,16 @@ pub struct Portal {
     pub statement: PreparedStatement,
     pub parameters: Vec<Value>,
     pub result_formats: Vec<u16>,
+    /// Rows produced but not sent yet: those of a query a Describe ran, or those an Execute
+    /// with a row limit left for the next Execute
+    pub pending: Option<QueryResult>,
 }
 
 pub struct ExtendedProtocol {
     pub prepared_statements: HashMap<String, PreparedStatement>,
     pub portals: HashMap<String, Portal>,
+    /// Set when a message fails; the messages after it are skipped until the next Sync
+    failed: bool,
 }
 
 impl ExtendedProtocol {
@@ -39,6 +53,7 @@ impl ExtendedProtocol {
         Self {
             prepared_statements: HashMap::new(),
             portals: HashMap::new(),
+            failed: false,
         }
     }
 }
@@ -50,7 +65,53 @@ impl Default for ExtendedProtocol {
 }
 
 impl ExtendedProtocol {
-    pub async fn handle_parse(&mut self, stream: &mut TcpStream, data: &[u8]) -> crate::Result<()> {
+    /// Handle a message of the extended query protocol. A message that fails is answered
+    /// with an ErrorResponse, as PostgreSQL does, and the connection stays usable from the
+    /// next Sync on.
+    pub async fn handle_message(
+        &mut self,
+        msg_type: u8,
+        stream: &mut Stream,
+        buffer: &mut BytesMut,
+        data: &[u8],
+        executor: &QueryExecutor,
+    ) -> crate::Result<()> {
+        if msg_type == b'S' {
+            return self.handle_sync(stream, executor).await;
+        }
+        if self.failed {
+            debug!("Skipping message {} until Sync", msg_type as char);
+            return Ok(());
+        }
+        let result = match msg_type {
+            b'P' => self.handle_parse(stream, data, executor).await,
+            b'B' => self.handle_bind(stream, data).await,
+            b'D' => self.handle_describe(stream, data, executor).await,
+            b'E' => self.handle_execute(stream, buffer, data, executor).await,
+            b'C' => self.handle_close(stream, data).await,
+            // Every message is written as soon as it is ready, so there is nothing to flush
+            b'H' => Ok(()),
+            _ => Err(YamlBaseError::Protocol(format!(
+                "Unknown extended query message: {}",
+                msg_type as char
+            ))),
+        };
+        match result {
+            Err(e @ YamlBaseError::Io(_)) => Err(e),
+            Err(e) => {
+                self.failed = true;
+                send_error_response(stream, sqlstate(&e), &e.to_string()).await
+            }
+            Ok(()) => Ok(()),
+        }
+    }
+
+    pub async fn handle_parse(
+        &mut self,
+        stream: &mut Stream,
+        data: &[u8],
+        executor: &QueryExecutor,
+    ) -> crate::Result<()> {
         debug!("Handling Parse message");
 
         let mut pos = 0;
@@ -84,8 +145,8 @@ impl ExtendedProtocol {
         let param_count = u16::from_be_bytes([data[pos], data[pos + 1]]) as usize;
         pos += 2;
 
-        // Read parameter types
-        let mut parameter_types = Vec::new();
+        // Read parameter types. OID 0 leaves the type of a parameter to the server.
+        let mut declared_types = Vec::new();
         for _ in 0..param_count {
             if pos + 4 > data.len() {
                 return Err(YamlBaseError::Protocol(
@@ -93,19 +154,35 @@ impl ExtendedProtocol {
                 ));
             }
             let oid = u32::from_be_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]]);
-            parameter_types.push(oid_to_sql_type(oid));
+            declared_types.push((oid != 0).then(|| oid_to_sql_type(oid)));
             pos += 4;
         }
 
+        // The unnamed statement is replaced by the next Parse; named ones must be closed first
+        if !name.is_empty() && self.prepared_statements.contains_key(&name) {
+            return Err(YamlBaseError::DuplicatePreparedStatement(name));
+        }
+
         // Parse the SQL
         let parsed_statements = parse_sql(&query)?;
+        if parsed_statements.len() > 1 {
+            return Err(YamlBaseError::Database {
+                message: "cannot insert multiple commands into a prepared statement".to_string(),
+            });
+        }
 
-        // If no parameter types were provided, we need to infer them from the query
-        if parameter_types.is_empty() && !parsed_statements.is_empty() {
-            if let Statement::Query(query_ref) = &parsed_statements[0] {
-                let inferred_types = infer_parameter_types(query_ref);
-                debug!("Inferred {} parameters from query", inferred_types.len());
-                parameter_types = inferred_types;
+        // Parameters the client gave no type for take the type of what they are compared
+        // with or assigned to
+        let mut parameter_types = match parsed_statements.first() {
+            Some(statement) => infer_parameter_types(statement, executor).await,
+            None => Vec::new(),
+        };
+        for (index, declared) in declared_types.into_iter().enumerate() {
+            match (declared, parameter_types.get_mut(index)) {
+                (Some(declared), Some(parameter_type)) => *parameter_type = declared,
+                (Some(declared), None) => parameter_types.push(declared),
+                (None, Some(_)) => {}
+                (None, None) => parameter_types.push(SqlType::Text),
             }
         }
 
@@ -121,6 +198,7 @@ impl ExtendedProtocol {
             query,
             parameter_types,
             parsed_statements,
+            result_types: None,
         };
 
         self.prepared_statements.insert(name, stmt);
@@ -134,7 +212,7 @@ impl ExtendedProtocol {
         Ok(())
     }
 
-    pub async fn handle_bind(&mut self, stream: &mut TcpStream, data: &[u8]) -> crate::Result<()> {
+    pub async fn handle_bind(&mut self, stream: &mut Stream, data: &[u8]) -> crate::Result<()> {
         debug!("Handling Bind message");
 
         let mut pos = 0;
@@ -163,9 +241,7 @@ impl ExtendedProtocol {
         let statement = self
             .prepared_statements
             .get(&stmt_name)
-            .ok_or_else(|| {
-                YamlBaseError::Protocol(format!("Unknown prepared statement: {}", stmt_name))
-            })?
+            .ok_or_else(|| YamlBaseError::UnknownPreparedStatement(stmt_name.clone()))?
             .clone();
 
         // Read parameter format codes
@@ -197,6 +273,14 @@ impl ExtendedProtocol {
         }
         let param_value_count = u16::from_be_bytes([data[pos], data[pos + 1]]) as usize;
         pos += 2;
+        if param_value_count != statement.parameter_types.len() {
+            return Err(YamlBaseError::Protocol(format!(
+                "bind message supplies {} parameters, but prepared statement \"{}\" requires {}",
+                param_value_count,
+                stmt_name,
+                statement.parameter_types.len()
+            )));
+        }
 
         let mut parameters = Vec::new();
         for i in 0..param_value_count {
@@ -255,6 +339,7 @@ impl ExtendedProtocol {
             statement,
             parameters,
             result_formats,
+            pending: None,
         };
 
         self.portals.insert(portal_name, portal);
@@ -269,8 +354,8 @@ impl ExtendedProtocol {
     }
 
     pub async fn handle_describe(
-        &self,
-        stream: &mut TcpStream,
+        &mut self,
+        stream: &mut Stream,
         data: &[u8],
         executor: &QueryExecutor,
     ) -> crate::Result<()> {
@@ -305,7 +390,9 @@ impl ExtendedProtocol {
                     stream.write_all(&buf).await?;
 
                     // For SELECT queries, we need to describe the result
-                    if !stmt.parsed_statements.is_empty() {
+                    if stmt.parsed_statements.is_empty() {
+                        send_no_data(stream).await?;
+                    } else {
                         if let sqlparser::ast::Statement::Query(query) = &stmt.parsed_statements[0]
                         {
                             // Try to extract column information from the query
@@ -316,6 +403,9 @@ impl ExtendedProtocol {
                                     stream, &columns, &types,
                                 )
                                 .await?;
+                                if let Some(stmt) = self.prepared_statements.get_mut(name) {
+                                    stmt.result_types = Some(types);
+                                }
                             } else {
                                 // Send NoData if we can't determine columns
                                 buf.clear();
@@ -323,6 +413,23 @@ impl ExtendedProtocol {
                                 buf.put_u32(4);
                                 stream.write_all(&buf).await?;
                             }
+                        } else if let show @ sqlparser::ast::Statement::ShowVariable { .. } =
+                            &stmt.parsed_statements[0]
+                            && let Ok(result) = executor.execute(show).await
+                        {
+                            send_row_description_for_columns_with_types(
+                                stream,
+                                &result.columns,
+                                &result.column_types,
+                            )
+                            .await?;
+                        } else if let sqlparser::ast::Statement::Insert(insert) =
+                            &stmt.parsed_statements[0]
+                            && insert.returning.is_some()
+                            && let Ok((columns, types)) = executor.describe_returning(insert).await
+                        {
+                            send_row_description_for_columns_with_types(stream, &columns, &types)
+                                .await?;
                         } else {
                             // Non-SELECT statements don't return data
                             buf.clear();
@@ -332,45 +439,51 @@ impl ExtendedProtocol {
                         }
                     }
                 } else {
-                    return Err(YamlBaseError::Protocol(format!(
-                        "Unknown statement: {}",
-                        name
-                    )));
+                    return Err(YamlBaseError::UnknownPreparedStatement(name.to_string()));
                 }
             }
             b'P' => {
                 // Describe portal
-                if let Some(portal) = self.portals.get(name) {
-                    // For SELECT queries, describe the result
-                    if !portal.statement.parsed_statements.is_empty() {
-                        if let sqlparser::ast::Statement::Query(_) =
-                            &portal.statement.parsed_statements[0]
-                        {
-                            match executor
-                                .execute(&portal.statement.parsed_statements[0])
+                let Some(portal) = self.portals.get_mut(name) else {
+                    return Err(YamlBaseError::Protocol(format!(
+                        "portal \"{}\" does not exist",
+                        name
+                    )));
+                };
+                match portal.statement.parsed_statements.first() {
+                    // The query runs with the portal's parameters to learn its columns, and
+                    // its rows are kept for the Execute that follows
+                    Some(
+                        statement @ (sqlparser::ast::Statement::Query(_)
+                        | sqlparser::ast::Statement::ShowVariable { .. }),
+                    ) => {
+                        if portal.pending.is_none() {
+                            portal.pending = executor
+                                .execute_with_parameters(statement, &portal.parameters)
                                 .await
-                            {
-                                Ok(result) => {
-                                    send_row_description(stream, &result).await?;
-                                }
-                                Err(_) => {
-                                    // Send NoData
-                                    let mut buf = BytesMut::new();
-                                    buf.put_u8(b'n');
-                                    buf.put_u32(4);
-                                    stream.write_all(&buf).await?;
-                                }
+                                .ok();
+                        }
+                        match &portal.pending {
+                            Some(result) => {
+                                send_row_description(stream, result, &portal.result_formats).await?
                             }
-                        } else {
-                            // Send NoData
-                            let mut buf = BytesMut::new();
-                            buf.put_u8(b'n');
-                            buf.put_u32(4);
-                            stream.write_all(&buf).await?;
+                            None => send_no_data(stream).await?,
                         }
                     }
-                } else {
-                    return Err(YamlBaseError::Protocol(format!("Unknown portal: {}", name)));
+                    Some(sqlparser::ast::Statement::Insert(insert))
+                        if insert.returning.is_some() =>
+                    {
+                        match executor.describe_returning(insert).await {
+                            Ok((columns, types)) => {
+                                send_row_description_for_columns_with_types(
+                                    stream, &columns, &types,
+                                )
+                                .await?
+                            }
+                            Err(_) => send_no_data(stream).await?,
+                        }
+                    }
+                    _ => send_no_data(stream).await?,
                 }
             }
             _ => {
@@ -385,8 +498,9 @@ impl ExtendedProtocol {
     }
 
     pub async fn handle_execute(
-        &self,
-        stream: &mut TcpStream,
+        &mut self,
+        stream: &mut Stream,
+        buffer: &mut BytesMut,
         data: &[u8],
         executor: &QueryExecutor,
     ) -> crate::Result<()> {
@@ -403,73 +517,148 @@ impl ExtendedProtocol {
             .map_err(|_| YamlBaseError::Protocol("Invalid UTF-8 in portal name".to_string()))?;
         pos += name_end + 1;
 
-        // Read row limit
+        // Read row limit, 0 for all rows
         if pos + 4 > data.len() {
             return Err(YamlBaseError::Protocol(
                 "Incomplete execute message".to_string(),
             ));
         }
-        let _row_limit =
-            u32::from_be_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]]);
+        let row_limit =
+            u32::from_be_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]]) as usize;
 
         // Get portal
-        let portal = self
-            .portals
-            .get(portal_name)
-            .ok_or_else(|| YamlBaseError::Protocol(format!("Unknown portal: {}", portal_name)))?;
-
-        // Execute the statement with parameter substitution
-        if !portal.statement.parsed_statements.is_empty() {
-            // Clone the statement and substitute parameters
-            let mut statement = portal.statement.parsed_statements[0].clone();
-            substitute_parameters(&mut statement, &portal.parameters)?;
-
-            match executor.execute(&statement).await {
-                Ok(result) => {
-                    debug!(
-                        "Execute result: {} rows, {} columns: {:?}",
-                        result.rows.len(),
-                        result.columns.len(),
-                        result.columns
-                    );
-                    if !result.rows.is_empty() {
-                        debug!("First row: {:?}", result.rows[0]);
-                    }
+        let portal = self.portals.get_mut(portal_name).ok_or_else(|| {
+            YamlBaseError::Protocol(format!("portal \"{}\" does not exist", portal_name))
+        })?;
+        let Some(statement) = portal.statement.parsed_statements.first() else {
+            // EmptyQueryResponse
+            let mut buf = BytesMut::new();
+            buf.put_u8(b'I');
+            buf.put_u32(4);
+            stream.write_all(&buf).await?;
+            return Ok(());
+        };
 
-                    // Pass the result formats from the portal
-                    send_data_rows(stream, &result, &portal.result_formats).await?;
+        // COPY sends and receives its rows as CopyData rather than DataRow messages
+        if let Statement::Copy { .. } = statement {
+            let rows = execute_copy(stream, buffer, executor, statement).await?;
+            return send_command_complete(stream, &format!("COPY {}", rows)).await;
+        }
 
-                    // Send CommandComplete
-                    let mut buf = BytesMut::new();
-                    buf.put_u8(b'C');
-                    let tag = format!("SELECT {}", result.rows.len());
-                    buf.put_u32(4 + tag.len() as u32 + 1);
-                    buf.put_slice(tag.as_bytes());
-                    buf.put_u8(0);
-                    stream.write_all(&buf).await?;
-                }
-                Err(e) => {
-                    send_error_response(stream, "XX000", &e.to_string()).await?;
-                }
+        // Prepared statements are kept here rather than by the executor
+        if let Statement::Deallocate { name, .. } = statement {
+            let (name, tag) = deallocate_target(name);
+            let name = name.map(str::to_string);
+            self.deallocate(name.as_deref())?;
+            return send_command_complete(stream, tag).await;
+        }
+
+        // Execute the statement with the portal's parameters bound to its placeholders,
+        // unless a Describe or an earlier Execute already did
+        let mut result = match portal.pending.take() {
+            Some(result) => result,
+            None => {
+                executor
+                    .execute_with_parameters(statement, &portal.parameters)
+                    .await?
             }
+        };
+        debug!(
+            "Execute result: {} rows, {} columns: {:?}",
+            result.rows.len(),
+            result.columns.len(),
+            result.columns
+        );
+
+        // With a row limit the rest of the rows are kept for the next Execute
+        let rest = (row_limit > 0 && result.rows.len() > row_limit).then(|| QueryResult {
+            columns: result.columns.clone(),
+            column_types: result.column_types.clone(),
+            rows: result.rows.split_off(row_limit),
+            affected_rows: result.affected_rows,
+        });
+
+        // Encode binary values as the types the client was told about
+        let column_types = match &portal.statement.result_types {
+            Some(types) if types.len() == result.columns.len() => types,
+            _ => &result.column_types,
+        };
+        send_data_rows(stream, &result, column_types, &portal.result_formats).await?;
+
+        if rest.is_some() {
+            portal.pending = rest;
+            // PortalSuspended
+            let mut buf = BytesMut::new();
+            buf.put_u8(b's');
+            buf.put_u32(4);
+            stream.write_all(&buf).await?;
+            return Ok(());
+        }
+
+        if let Some((name, value)) = executor.changed_parameter(statement) {
+            send_parameter_status(stream, &name, &value).await?;
         }
+        send_command_complete(stream, &command_tag(statement, &result)).await?;
 
+        // A portal whose rows have all been sent has none left for another Execute
+        if !result.columns.is_empty() {
+            result.rows.clear();
+            portal.pending = Some(result);
+        }
         Ok(())
     }
 
-    pub async fn handle_sync(&self, stream: &mut TcpStream) -> crate::Result<()> {
+    pub async fn handle_sync(
+        &mut self,
+        stream: &mut Stream,
+        executor: &QueryExecutor,
+    ) -> crate::Result<()> {
         debug!("Handling Sync message");
 
+        // Portals live until the end of the transaction they were bound in
+        self.failed = false;
+        if !executor.in_transaction() {
+            self.portals.clear();
+        }
+
         // Send ReadyForQuery
         let mut buf = BytesMut::new();
         buf.put_u8(b'Z');
         buf.put_u32(5);
-        buf.put_u8(b'I'); // Idle
+        buf.put_u8(transaction_status(executor));
         stream.write_all(&buf).await?;
 
         Ok(())
     }
 
+    /// Close a statement or portal. Closing one that does not exist is not an error.
+    pub async fn handle_close(&mut self, stream: &mut Stream, data: &[u8]) -> crate::Result<()> {
+        let Some((&close_type, rest)) = data.split_first() else {
+            return Err(YamlBaseError::Protocol("Empty close message".to_string()));
+        };
+        let name_end = rest.iter().position(|&b| b == 0).unwrap_or(rest.len());
+        let name = std::str::from_utf8(&rest[..name_end])
+            .map_err(|_| YamlBaseError::Protocol("Invalid UTF-8 in close name".to_string()))?;
+
+        match close_type {
+            b'S' => self.close_statement(name),
+            b'P' => self.close_portal(name),
+            _ => {
+                return Err(YamlBaseError::Protocol(format!(
+                    "Unknown close type: {}",
+                    close_type as char
+                )));
+            }
+        }
+
+        // Send CloseComplete
+        let mut buf = BytesMut::new();
+        buf.put_u8(b'3');
+        buf.put_u32(4);
+        stream.write_all(&buf).await?;
+        Ok(())
+    }
+
     pub fn close_statement(&mut self, name: &str) {
         self.prepared_statements.remove(name);
     }
@@ -477,9 +666,29 @@ impl ExtendedProtocol {
     pub fn close_portal(&mut self, name: &str) {
         self.portals.remove(name);
     }
+
+    /// `DEALLOCATE name`, or `DEALLOCATE ALL` for `None`. Statements prepared with Parse are
+    /// the ones deallocated, as there is no PREPARE.
+    pub fn deallocate(&mut self, name: Option<&str>) -> crate::Result<()> {
+        match name {
+            Some(name) => self
+                .prepared_statements
+                .remove(name)
+                .map(|_| ())
+                .ok_or_else(|| YamlBaseError::UnknownPreparedStatement(name.to_string())),
+            None => {
+                self.prepared_statements.clear();
+                Ok(())
+            }
+        }
+    }
 }
 
-async fn send_row_description(stream: &mut TcpStream, result: &QueryResult) -> crate::Result<()> {
+async fn send_row_description(
+    stream: &mut Stream,
+    result: &QueryResult,
+    result_formats: &[u16],
+) -> crate::Result<()> {
     let mut buf = BytesMut::new();
     buf.put_u8(b'T');
 
@@ -508,7 +717,7 @@ async fn send_row_description(stream: &mut TcpStream, result: &QueryResult) -> c
 
         buf.put_i16(-1); // Type size
         buf.put_i32(-1); // Type modifier
-        buf.put_i16(0); // Format code (text)
+        buf.put_u16(column_format(result_formats, i));
     }
 
     stream.write_all(&buf).await?;
@@ -516,7 +725,7 @@ async fn send_row_description(stream: &mut TcpStream, result: &QueryResult) -> c
 }
 
 async fn send_row_description_for_columns_with_types(
-    stream: &mut TcpStream,
+    stream: &mut Stream,
     columns: &[String],
     types: &[SqlType],
 ) -> crate::Result<()> {
@@ -568,8 +777,17 @@ fn extract_columns_and_types_from_select(
                 match expr {
                     Expr::Identifier(ident) => {
                         columns.push(ident.value.clone());
-                        // Try to infer type from column name in WHERE clause context
-                        types.push(infer_type_from_column_name(&ident.value));
+                        // Binary and array columns are sent as bytea and arrays, which
+                        // clients read as bytes and lists rather than text; other types
+                        // are inferred from the name
+                        let column_type =
+                            from_column_type(select, executor, &ident.value).filter(|sql_type| {
+                                matches!(sql_type, SqlType::Blob | SqlType::Array(_))
+                            });
+                        types.push(
+                            column_type
+                                .unwrap_or_else(|| infer_type_from_column_name(&ident.value)),
+                        );
                     }
                     Expr::Function(func) => {
                         let func_name = func
@@ -630,16 +848,14 @@ fn extract_columns_and_types_from_select(
             }
             sqlparser::ast::SelectItem::Wildcard(_) => {
                 // For SELECT *, we need to get all columns from the table
-                if let Some(table) = select.from.first() {
-                    if let Some(table_name) = get_table_name_from_relation(&table.relation) {
-                        if let Ok(db) = executor.storage().database().try_read() {
-                            if let Some(table) = db.get_table(&table_name) {
-                                for col in &table.columns {
-                                    columns.push(col.name.clone());
-                                    types.push(col.sql_type.clone());
-                                }
-                            }
-                        }
+                if let Some(table) = select.from.first()
+                    && let Some(table_name) = get_table_name_from_relation(&table.relation)
+                    && let Ok(db) = executor.storage().database().try_read()
+                    && let Some(table) = db.get_table(&table_name)
+                {
+                    for col in &table.columns {
+                        columns.push(col.name.clone());
+                        types.push(col.sql_type.clone());
                     }
                 }
             }
@@ -654,6 +870,20 @@ fn extract_columns_and_types_from_select(
     (columns, types)
 }
 
+/// The type of a column of the first table in FROM
+fn from_column_type(
+    select: &sqlparser::ast::Select,
+    executor: &QueryExecutor,
+    name: &str,
+) -> Option<SqlType> {
+    let table_name = get_table_name_from_relation(&select.from.first()?.relation)?;
+    let database = executor.storage().database();
+    let db = database.try_read().ok()?;
+    let table = db.get_table(&table_name)?;
+    let column = table.get_column_index(name)?;
+    Some(table.columns[column].sql_type.clone())
+}
+
 fn infer_type_from_column_name(name: &str) -> SqlType {
     match name.to_lowercase().as_str() {
         "age" | "id" | "count" | "quantity" => SqlType::Integer,
@@ -675,139 +905,234 @@ fn get_table_name_from_relation(relation: &sqlparser::ast::TableFactor) -> Optio
 }
 
 async fn send_data_rows(
-    stream: &mut TcpStream,
+    stream: &mut Stream,
     result: &QueryResult,
+    column_types: &[SqlType],
     result_formats: &[u16],
 ) -> crate::Result<()> {
     for row in &result.rows {
         let mut buf = BytesMut::new();
         buf.put_u8(b'D');
+        buf.put_u32(0); // Length, filled in below
+        buf.put_u16(row.len() as u16);
 
-        // First pass: calculate row length
-        let mut row_length = 6; // 4 bytes for length + 2 bytes for field count
         for (col_idx, val) in row.iter().enumerate() {
             if matches!(val, Value::Null) {
-                row_length += 4; // 4 bytes for -1 (NULL indicator)
+                buf.put_i32(-1); // NULL
+            } else if column_format(result_formats, col_idx) == 1 {
+                let column_type = column_types.get(col_idx).unwrap_or(&SqlType::Text);
+                let value = binary_value(val, column_type);
+                buf.put_i32(value.len() as i32);
+                buf.put_slice(&value);
             } else {
-                // Check the format for this column
-                let format = if result_formats.is_empty() {
-                    0 // Default to text
-                } else if result_formats.len() == 1 {
-                    result_formats[0] // Use the single format for all columns
-                } else if col_idx < result_formats.len() {
-                    result_formats[col_idx] // Use the specific format for this column
-                } else {
-                    0 // Default to text if not specified
-                };
-
-                if format == 1 {
-                    // Binary format
-                    match val {
-                        Value::Integer(_) => {
-                            // Check the column type to determine size
-                            let col_type = result.column_types.get(col_idx);
-                            match col_type {
-                                Some(SqlType::BigInt) => row_length += 4 + 8, // int8 (i64)
-                                Some(SqlType::Integer) => row_length += 4 + 4, // int4 (i32)
-                                _ => row_length += 4 + 4, // Default to int4 for compatibility
-                            }
-                        }
-                        Value::Boolean(_) => row_length += 4 + 1, // 4 bytes for length + 1 byte for bool
-                        Value::Float(_) => row_length += 4 + 4, // 4 bytes for length + 4 bytes for f32
-                        Value::Double(_) => row_length += 4 + 8, // 4 bytes for length + 8 bytes for f64
-                        _ => {
-                            // For other types, fall back to text
-                            let val_str = val.to_string();
-                            row_length += 4 + val_str.len();
-                        }
-                    }
-                } else {
-                    // Text format
-                    let val_str = val.to_string();
-                    row_length += 4 + val_str.len();
-                }
+                let val_str = val.to_string();
+                buf.put_i32(val_str.len() as i32);
+                buf.put_slice(val_str.as_bytes());
             }
         }
 
-        buf.put_u32(row_length as u32);
-        buf.put_u16(row.len() as u16);
+        let length = buf.len() as u32 - 1;
+        buf[1..5].copy_from_slice(&length.to_be_bytes());
+        stream.write_all(&buf).await?;
+    }
+    Ok(())
+}
 
-        // Second pass: send field values
-        for (col_idx, val) in row.iter().enumerate() {
-            if matches!(val, Value::Null) {
-                buf.put_i32(-1); // NULL
-            } else {
-                // Check the format for this column
-                let format = if result_formats.is_empty() {
-                    0 // Default to text
-                } else if result_formats.len() == 1 {
-                    result_formats[0] // Use the single format for all columns
-                } else if col_idx < result_formats.len() {
-                    result_formats[col_idx] // Use the specific format for this column
-                } else {
-                    0 // Default to text if not specified
-                };
+/// The format of a result column, 0 for text and 1 for binary. One format applies to every
+/// column, otherwise each column has its own and text is the default.
+fn column_format(result_formats: &[u16], index: usize) -> u16 {
+    match result_formats {
+        [format] => *format,
+        formats => formats.get(index).copied().unwrap_or(0),
+    }
+}
 
-                if format == 1 {
-                    // Binary format
-                    match val {
-                        Value::Integer(i) => {
-                            // Check the column type to determine size
-                            let col_type = result.column_types.get(col_idx);
-                            match col_type {
-                                Some(SqlType::BigInt) => {
-                                    buf.put_i32(8); // Length of i64
-                                    buf.put_i64(*i); // Send as 8-byte big-endian integer
-                                }
-                                Some(SqlType::Integer) => {
-                                    buf.put_i32(4); // Length of i32
-                                    buf.put_i32(*i as i32); // Send as 4-byte big-endian integer
-                                }
-                                _ => {
-                                    // Default to int4 for compatibility
-                                    buf.put_i32(4); // Length of i32
-                                    buf.put_i32(*i as i32); // Send as 4-byte big-endian integer
-                                }
-                            }
-                        }
-                        Value::Boolean(b) => {
-                            buf.put_i32(1); // Length of bool
-                            buf.put_u8(if *b { 1 } else { 0 });
-                        }
-                        Value::Float(f) => {
-                            buf.put_i32(4); // Length of f32
-                            buf.put_f32(*f);
-                        }
-                        Value::Double(d) => {
-                            buf.put_i32(8); // Length of f64
-                            buf.put_f64(*d);
-                        }
-                        _ => {
-                            // For other types, fall back to text
-                            let val_str = val.to_string();
-                            buf.put_i32(val_str.len() as i32);
-                            buf.put_slice(val_str.as_bytes());
-                        }
-                    }
-                } else {
-                    // Text format
-                    let val_str = val.to_string();
-                    buf.put_i32(val_str.len() as i32);
-                    buf.put_slice(val_str.as_bytes());
-                }
-            }
+/// A value in the binary format of the type its column was described with. Values of
+/// another type are converted when they can be, otherwise sent as their text.
+fn binary_value(value: &Value, sql_type: &SqlType) -> BytesMut {
+    let mut buf = BytesMut::new();
+    match (sql_type, value) {
+        (SqlType::Boolean, Value::Boolean(b)) => buf.put_u8(*b as u8),
+        (SqlType::Integer, Value::Integer(i)) => buf.put_i32(*i as i32),
+        (SqlType::BigInt, Value::Integer(i)) => buf.put_i64(*i),
+        (SqlType::Float, Value::Float(f)) => buf.put_f32(*f),
+        (SqlType::Float, Value::Double(d)) => buf.put_f32(*d as f32),
+        (SqlType::Float, Value::Integer(i)) => buf.put_f32(*i as f32),
+        (SqlType::Float, Value::Decimal(d)) => buf.put_f32(d.to_f32().unwrap_or(f32::NAN)),
+        (SqlType::Double, Value::Double(d)) => buf.put_f64(*d),
+        (SqlType::Double, Value::Float(f)) => buf.put_f64(*f as f64),
+        (SqlType::Double, Value::Integer(i)) => buf.put_f64(*i as f64),
+        (SqlType::Double, Value::Decimal(d)) => buf.put_f64(d.to_f64().unwrap_or(f64::NAN)),
+        (SqlType::Decimal(_, _), Value::Decimal(d)) => binary_numeric(&mut buf, Some(*d)),
+        (SqlType::Decimal(_, _), Value::Integer(i)) => {
+            binary_numeric(&mut buf, Some(Decimal::from(*i)))
+        }
+        (SqlType::Decimal(_, _), Value::Double(d)) => {
+            binary_numeric(&mut buf, Decimal::from_f64_retain(*d))
+        }
+        (SqlType::Decimal(_, _), Value::Float(f)) => {
+            binary_numeric(&mut buf, Decimal::from_f32_retain(*f))
+        }
+        (SqlType::Date, Value::Date(date)) => {
+            buf.put_i32((*date - postgres_epoch().date()).num_days() as i32)
+        }
+        (SqlType::Time, Value::Time(time)) => {
+            let since_midnight = *time - NaiveTime::MIN;
+            buf.put_i64(since_midnight.num_microseconds().unwrap_or(0))
+        }
+        (SqlType::Timestamp, Value::Timestamp(timestamp)) => {
+            buf.put_i64(microseconds_since_epoch(timestamp))
+        }
+        (SqlType::TimestampTz, Value::TimestampTz(timestamp)) => {
+            buf.put_i64(microseconds_since_epoch(&timestamp.naive_utc()))
+        }
+        (SqlType::TimestampTz, Value::Timestamp(timestamp)) => {
+            buf.put_i64(microseconds_since_epoch(timestamp))
         }
+        (SqlType::Uuid, Value::Uuid(uuid)) => buf.put_slice(uuid.as_bytes()),
+        (SqlType::Json, value) => {
+            // Columns are described as jsonb, which starts with its format version
+            buf.put_u8(1);
+            buf.put_slice(value.to_string().as_bytes());
+        }
+        (SqlType::Blob, Value::Bytes(b)) => buf.put_slice(b),
+        (SqlType::Array(element_type), Value::Array(items)) => {
+            binary_array(&mut buf, items, element_type)
+        }
+        (_, value) => buf.put_slice(value.to_string().as_bytes()),
+    }
+    buf
+}
 
-        stream.write_all(&buf).await?;
+/// An array in binary format: its dimension, then each element with its length. Arrays of
+/// arrays are described as arrays of text, so their elements are sent as text.
+fn binary_array(buf: &mut BytesMut, items: &[Value], element_type: &SqlType) {
+    let element_type = match element_type {
+        SqlType::Array(_) => &SqlType::Text,
+        element_type => element_type,
+    };
+
+    buf.put_i32(if items.is_empty() { 0 } else { 1 }); // Dimensions
+    buf.put_i32(items.contains(&Value::Null) as i32); // Whether there are NULLs
+    buf.put_u32(sql_type_to_oid(element_type));
+    if !items.is_empty() {
+        buf.put_i32(items.len() as i32);
+        buf.put_i32(1); // Lower bound
+    }
+
+    for item in items {
+        if matches!(item, Value::Null) {
+            buf.put_i32(-1);
+        } else {
+            let element = binary_value(item, element_type);
+            buf.put_i32(element.len() as i32);
+            buf.put_slice(&element);
+        }
+    }
+}
+
+/// A numeric in binary format: its base 10000 digits, the weight of the first one, the sign
+/// and the number of decimal places. A value that is no decimal, such as NaN, is sent as NaN.
+fn binary_numeric(buf: &mut BytesMut, decimal: Option<Decimal>) {
+    const POSITIVE: u16 = 0x0000;
+    const NEGATIVE: u16 = 0x4000;
+    const NAN: u16 = 0xC000;
+
+    let Some(decimal) = decimal else {
+        buf.put_i16(0); // Digits
+        buf.put_i16(0); // Weight
+        buf.put_u16(NAN);
+        buf.put_u16(0); // Scale
+        return;
+    };
+
+    // Pad the integer and fractional parts to whole groups of four decimal digits
+    let scale = decimal.scale() as usize;
+    let digits = format!(
+        "{:0>width$}",
+        decimal.mantissa().unsigned_abs(),
+        width = scale
+    );
+    let (integer, fraction) = digits.split_at(digits.len() - scale);
+    let integer = format!(
+        "{:0>width$}",
+        integer,
+        width = integer.len().div_ceil(4) * 4
+    );
+    let fraction = format!("{:0<width$}", fraction, width = scale.div_ceil(4) * 4);
+
+    let mut groups: Vec<i16> = integer
+        .as_bytes()
+        .chunks(4)
+        .chain(fraction.as_bytes().chunks(4))
+        .map(|group| std::str::from_utf8(group).unwrap().parse().unwrap())
+        .collect();
+    let mut weight = (integer.len() / 4) as i16 - 1;
+    while groups.first() == Some(&0) {
+        groups.remove(0);
+        weight -= 1;
+    }
+    while groups.last() == Some(&0) {
+        groups.pop();
+    }
+    if groups.is_empty() {
+        weight = 0;
+    }
+
+    buf.put_i16(groups.len() as i16);
+    buf.put_i16(weight);
+    buf.put_u16(if decimal.is_sign_negative() && !groups.is_empty() {
+        NEGATIVE
+    } else {
+        POSITIVE
+    });
+    buf.put_u16(scale as u16);
+    for group in groups {
+        buf.put_i16(group);
+    }
+}
+
+// Dates and timestamps are sent relative to 2000-01-01
+fn postgres_epoch() -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(2000, 1, 1)
+        .unwrap()
+        .and_time(NaiveTime::MIN)
+}
+
+fn microseconds_since_epoch(timestamp: &NaiveDateTime) -> i64 {
+    (*timestamp - postgres_epoch())
+        .num_microseconds()
+        .unwrap_or(i64::MAX)
+}
+
+/// The statement a `DEALLOCATE` names, `None` for `DEALLOCATE ALL`, with its command tag
+pub(crate) fn deallocate_target(name: &sqlparser::ast::Ident) -> (Option<&str>, &'static str) {
+    if name.quote_style.is_none() && name.value.eq_ignore_ascii_case("ALL") {
+        (None, "DEALLOCATE ALL")
+    } else {
+        (Some(&name.value), "DEALLOCATE")
     }
+}
+
+async fn send_no_data(stream: &mut Stream) -> crate::Result<()> {
+    let mut buf = BytesMut::new();
+    buf.put_u8(b'n');
+    buf.put_u32(4);
+    stream.write_all(&buf).await?;
     Ok(())
 }
 
-async fn send_error_response(
-    stream: &mut TcpStream,
-    code: &str,
-    message: &str,
-) -> crate::Result<()> {
+async fn send_command_complete(stream: &mut Stream, tag: &str) -> crate::Result<()> {
+    let mut buf = BytesMut::new();
+    buf.put_u8(b'C');
+    buf.put_u32(4 + tag.len() as u32 + 1);
+    buf.put_slice(tag.as_bytes());
+    buf.put_u8(0);
+    stream.write_all(&buf).await?;
+    Ok(())
+}
+
+async fn send_error_response(stream: &mut Stream, code: &str, message: &str) -> crate::Result<()> {
     let mut buf = BytesMut::new();
     buf.put_u8(b'E');
 
@@ -846,14 +1171,39 @@ fn oid_to_sql_type(oid: u32) -> SqlType {
         1082 => SqlType::Date,           // date
         1083 => SqlType::Time,           // time
         1114 => SqlType::Timestamp,      // timestamp
+        1184 => SqlType::TimestampTz,    // timestamptz
+        17 => SqlType::Blob,             // bytea
         1700 => SqlType::Decimal(38, 0), // numeric
         2950 => SqlType::Uuid,           // uuid
         3802 => SqlType::Json,           // jsonb
-        _ => SqlType::Text,              // Default to text
+        _ => match ARRAY_OIDS.iter().find(|(_, array_oid)| *array_oid == oid) {
+            Some((element_oid, _)) => SqlType::Array(Box::new(oid_to_sql_type(*element_oid))),
+            None => SqlType::Text, // Default to text
+        },
     }
 }
 
-fn sql_type_to_oid(sql_type: &SqlType) -> u32 {
+/// The OID of each type with the OID of arrays of it
+const ARRAY_OIDS: [(u32, u32); 16] = [
+    (16, 1000),   // bool[]
+    (17, 1001),   // bytea[]
+    (20, 1016),   // int8[]
+    (23, 1007),   // int4[]
+    (25, 1009),   // text[]
+    (700, 1021),  // float4[]
+    (701, 1022),  // float8[]
+    (1042, 1014), // bpchar[]
+    (1043, 1015), // varchar[]
+    (1082, 1182), // date[]
+    (1083, 1183), // time[]
+    (1114, 1115), // timestamp[]
+    (1184, 1185), // timestamptz[]
+    (1700, 1231), // numeric[]
+    (2950, 2951), // uuid[]
+    (3802, 3807), // jsonb[]
+];
+
+pub(crate) fn sql_type_to_oid(sql_type: &SqlType) -> u32 {
     match sql_type {
         SqlType::Boolean => 16,
         SqlType::Integer => 23, // int4 - PostgreSQL INTEGER type
@@ -867,161 +1217,168 @@ fn sql_type_to_oid(sql_type: &SqlType) -> u32 {
         SqlType::Date => 1082,
         SqlType::Time => 1083,
         SqlType::Timestamp => 1114,
+        SqlType::TimestampTz => 1184,
         SqlType::Uuid => 2950,
         SqlType::Json => 3802,
-    }
-}
-
-fn substitute_parameters(statement: &mut Statement, parameters: &[Value]) -> crate::Result<()> {
-    match statement {
-        Statement::Query(query) => {
-            substitute_parameters_in_query(query, parameters)?;
-        }
-        _ => {
-            return Err(YamlBaseError::Protocol(
-                "Parameter substitution only supported for queries".to_string(),
-            ));
+        SqlType::Blob => 17,
+        // Arrays of arrays are sent as arrays of text
+        SqlType::Array(element_type) => {
+            let element_oid = sql_type_to_oid(element_type);
+            ARRAY_OIDS
+                .iter()
+                .find(|(oid, _)| *oid == element_oid)
+                .map_or(1009, |(_, array_oid)| *array_oid)
         }
     }
-    Ok(())
 }
 
-fn substitute_parameters_in_query(
-    query: &mut sqlparser::ast::Query,
-    parameters: &[Value],
-) -> crate::Result<()> {
-    if let sqlparser::ast::SetExpr::Select(select) = &mut *query.body {
-        if let Some(selection) = &mut select.selection {
-            substitute_parameters_in_expr(selection, parameters)?;
-        }
-    }
-    Ok(())
-}
+/// The types of a statement's parameters, from the columns they are compared with or
+/// assigned to. Parameters whose type cannot be told are text.
+async fn infer_parameter_types(statement: &Statement, executor: &QueryExecutor) -> Vec<SqlType> {
+    let tables = statement_tables(statement, executor).await;
+    let mut parameter_types = std::collections::HashMap::new();
 
-fn substitute_parameters_in_expr(expr: &mut Expr, parameters: &[Value]) -> crate::Result<()> {
-    match expr {
-        Expr::Value(SqlValue::Placeholder(s)) => {
-            // Parse placeholder like "$1", "$2", etc.
-            if let Some(num_str) = s.strip_prefix('$') {
-                if let Ok(param_idx) = num_str.parse::<usize>() {
-                    if param_idx > 0 && param_idx <= parameters.len() {
-                        let param_value = &parameters[param_idx - 1];
-                        *expr = value_to_sql_expr(param_value);
+    match statement {
+        Statement::Query(query) => infer_types_in_query(query, &tables, &mut parameter_types),
+        Statement::Insert(insert) => {
+            if let Some(source) = &insert.source {
+                if let SetExpr::Values(values) = &*source.body {
+                    // The values go to the listed columns, or to all of them in order
+                    let columns: Vec<String> = if insert.columns.is_empty() {
+                        tables
+                            .first()
+                            .map(|table| table.columns.iter().map(|c| c.name.clone()).collect())
+                            .unwrap_or_default()
                     } else {
-                        return Err(YamlBaseError::Protocol(format!(
-                            "Invalid parameter index: ${}",
-                            param_idx
-                        )));
+                        insert.columns.iter().map(|c| c.value.clone()).collect()
+                    };
+                    for row in &values.rows {
+                        for (expr, column) in row.iter().zip(&columns) {
+                            if let Some(param_num) = placeholder_number(expr)
+                                && let Some(column_type) = column_type(&tables, column)
+                            {
+                                parameter_types.insert(param_num, column_type);
+                            }
+                        }
                     }
                 } else {
-                    return Err(YamlBaseError::Protocol(format!(
-                        "Invalid placeholder format: {}",
-                        s
-                    )));
+                    infer_types_in_query(source, &tables, &mut parameter_types);
                 }
             }
         }
-        Expr::BinaryOp { left, right, .. } => {
-            substitute_parameters_in_expr(left, parameters)?;
-            substitute_parameters_in_expr(right, parameters)?;
-        }
-        Expr::UnaryOp { expr, .. } => {
-            substitute_parameters_in_expr(expr, parameters)?;
-        }
-        Expr::InList { expr, list, .. } => {
-            substitute_parameters_in_expr(expr, parameters)?;
-            for item in list {
-                substitute_parameters_in_expr(item, parameters)?;
-            }
-        }
-        Expr::Between {
-            expr, low, high, ..
+        Statement::Update {
+            assignments,
+            selection,
+            ..
         } => {
-            substitute_parameters_in_expr(expr, parameters)?;
-            substitute_parameters_in_expr(low, parameters)?;
-            substitute_parameters_in_expr(high, parameters)?;
-        }
-        Expr::Case {
-            operand,
-            conditions,
-            results,
-            else_result,
-        } => {
-            if let Some(op) = operand {
-                substitute_parameters_in_expr(op, parameters)?;
-            }
-            for cond in conditions {
-                substitute_parameters_in_expr(cond, parameters)?;
-            }
-            for res in results {
-                substitute_parameters_in_expr(res, parameters)?;
+            for assignment in assignments {
+                if let AssignmentTarget::ColumnName(name) = &assignment.target
+                    && let Some(column) = name.0.last()
+                    && let Some(param_num) = placeholder_number(&assignment.value)
+                    && let Some(column_type) = column_type(&tables, &column.value)
+                {
+                    parameter_types.insert(param_num, column_type);
+                }
             }
-            if let Some(else_res) = else_result {
-                substitute_parameters_in_expr(else_res, parameters)?;
+            if let Some(selection) = selection {
+                infer_types_in_expr(selection, &tables, &mut parameter_types);
             }
         }
-        Expr::Nested(inner) => {
-            substitute_parameters_in_expr(inner, parameters)?;
-        }
-        Expr::IsNull(inner) | Expr::IsNotNull(inner) => {
-            substitute_parameters_in_expr(inner, parameters)?;
-        }
-        Expr::Like { expr, pattern, .. } => {
-            substitute_parameters_in_expr(expr, parameters)?;
-            substitute_parameters_in_expr(pattern, parameters)?;
+        Statement::Delete(delete) => {
+            if let Some(selection) = &delete.selection {
+                infer_types_in_expr(selection, &tables, &mut parameter_types);
+            }
         }
         _ => {}
     }
-    Ok(())
-}
 
-fn value_to_sql_expr(value: &Value) -> Expr {
-    match value {
-        Value::Null => Expr::Value(SqlValue::Null),
-        Value::Boolean(b) => Expr::Value(SqlValue::Boolean(*b)),
-        Value::Integer(i) => Expr::Value(SqlValue::Number(i.to_string(), false)),
-        Value::Float(f) => Expr::Value(SqlValue::Number(f.to_string(), false)),
-        Value::Double(d) => Expr::Value(SqlValue::Number(d.to_string(), false)),
-        Value::Text(s) => Expr::Value(SqlValue::SingleQuotedString(s.clone())),
-        Value::Date(d) => Expr::Value(SqlValue::SingleQuotedString(d.to_string())),
-        Value::Time(t) => Expr::Value(SqlValue::SingleQuotedString(t.to_string())),
-        Value::Timestamp(ts) => Expr::Value(SqlValue::SingleQuotedString(ts.to_string())),
-        Value::Uuid(u) => Expr::Value(SqlValue::SingleQuotedString(u.to_string())),
-        Value::Json(j) => Expr::Value(SqlValue::SingleQuotedString(j.to_string())),
-        Value::Decimal(d) => Expr::Value(SqlValue::Number(d.to_string(), false)),
+    // Convert HashMap to Vec, using the parameter index as the key
+    let mut max_param = parameter_types.keys().max().copied().unwrap_or(0);
+    let _ = sqlparser::ast::visit_expressions(statement, |expr| {
+        if let Some(param_num) = placeholder_number(expr) {
+            max_param = max_param.max(param_num);
+        }
+        std::ops::ControlFlow::<()>::Continue(())
+    });
+    let mut result = Vec::new();
+    for i in 1..=max_param {
+        result.push(parameter_types.get(&i).cloned().unwrap_or(SqlType::Text));
     }
+    result
 }
 
-fn infer_parameter_types(query: &sqlparser::ast::Query) -> Vec<SqlType> {
-    let mut parameter_types = std::collections::HashMap::new();
-
-    if let sqlparser::ast::SetExpr::Select(select) = &*query.body {
+fn infer_types_in_query(
+    query: &sqlparser::ast::Query,
+    tables: &[Arc<Table>],
+    parameter_types: &mut std::collections::HashMap<usize, SqlType>,
+) {
+    if let SetExpr::Select(select) = &*query.body {
         if let Some(selection) = &select.selection {
-            infer_types_in_expr(selection, &mut parameter_types);
+            infer_types_in_expr(selection, tables, parameter_types);
         }
 
         // Also check projection for parameters in aggregate functions
         for item in &select.projection {
             match item {
                 SelectItem::UnnamedExpr(expr) | SelectItem::ExprWithAlias { expr, .. } => {
-                    infer_types_in_projection_expr(expr, &mut parameter_types);
+                    infer_types_in_projection_expr(expr, tables, parameter_types);
                 }
                 _ => {}
             }
         }
     }
+}
 
-    // Convert HashMap to Vec, using the parameter index as the key
-    let max_param = parameter_types.keys().max().copied().unwrap_or(0);
-    let mut result = Vec::new();
-    for i in 1..=max_param {
-        result.push(parameter_types.get(&i).cloned().unwrap_or(SqlType::Text));
+/// The tables a statement names, as the connection sees them
+async fn statement_tables(statement: &Statement, executor: &QueryExecutor) -> Vec<Arc<Table>> {
+    let mut names = Vec::new();
+    let _ = sqlparser::ast::visit_relations(statement, |name| {
+        names.push(name.clone());
+        std::ops::ControlFlow::<()>::Continue(())
+    });
+    let database = executor.storage().database();
+    let db = database.read().await;
+    names
+        .iter()
+        .filter_map(|name| {
+            executor
+                .session()
+                .table(&db, &executor.table_key(&db, name))
+        })
+        .collect()
+}
+
+/// The type of a parameter compared with or assigned to a column. Common column names
+/// give the type, as they do for result columns; otherwise it is the type of the column in
+/// the first of the tables that has it.
+fn column_type(tables: &[Arc<Table>], name: &str) -> Option<SqlType> {
+    match name.to_lowercase().as_str() {
+        "age" | "id" | "count" | "quantity" | "value" => Some(SqlType::Integer),
+        "price" | "amount" | "total" => Some(SqlType::Double),
+        "active" | "enabled" | "deleted" | "is_active" | "in_stock" => Some(SqlType::Boolean),
+        "name" | "username" | "email" | "description" | "status" | "customer_name" => {
+            Some(SqlType::Text)
+        }
+        "created_at" | "updated_at" => Some(SqlType::Timestamp),
+        "created_date" => Some(SqlType::Date),
+        _ => tables.iter().find_map(|table| {
+            let index = table.get_column_index(name)?;
+            Some(table.columns[index].sql_type.clone())
+        }),
+    }
+}
+
+/// The number of a `$n` placeholder
+fn placeholder_number(expr: &Expr) -> Option<usize> {
+    match expr {
+        Expr::Value(SqlValue::Placeholder(s)) => s.strip_prefix('$')?.parse().ok(),
+        _ => None,
     }
-    result
 }
 
 fn infer_types_in_expr(
     expr: &Expr,
+    tables: &[Arc<Table>],
     parameter_types: &mut std::collections::HashMap<usize, SqlType>,
 ) {
     match expr {
@@ -1035,48 +1392,58 @@ fn infer_types_in_expr(
                 | sqlparser::ast::BinaryOperator::Gt
                 | sqlparser::ast::BinaryOperator::GtEq => {
                     // If one side is a parameter and the other is a column, infer type
-                    if let Expr::Value(SqlValue::Placeholder(s)) = &**left {
-                        if let Some(num_str) = s.strip_prefix('$') {
-                            if let Ok(param_num) = num_str.parse::<usize>() {
-                                if let Some(inferred_type) = infer_type_from_expr(right) {
-                                    parameter_types.insert(param_num, inferred_type);
-                                }
-                            }
-                        }
+                    if let Expr::Value(SqlValue::Placeholder(s)) = &**left
+                        && let Some(num_str) = s.strip_prefix('$')
+                        && let Ok(param_num) = num_str.parse::<usize>()
+                        && let Some(inferred_type) = infer_type_from_expr(right, tables)
+                    {
+                        parameter_types.insert(param_num, inferred_type);
                     }
-                    if let Expr::Value(SqlValue::Placeholder(s)) = &**right {
-                        if let Some(num_str) = s.strip_prefix('$') {
-                            if let Ok(param_num) = num_str.parse::<usize>() {
-                                if let Some(inferred_type) = infer_type_from_expr(left) {
-                                    parameter_types.insert(param_num, inferred_type);
-                                }
-                            }
-                        }
+                    if let Expr::Value(SqlValue::Placeholder(s)) = &**right
+                        && let Some(num_str) = s.strip_prefix('$')
+                        && let Ok(param_num) = num_str.parse::<usize>()
+                        && let Some(inferred_type) = infer_type_from_expr(left, tables)
+                    {
+                        parameter_types.insert(param_num, inferred_type);
                     }
                 }
                 sqlparser::ast::BinaryOperator::And | sqlparser::ast::BinaryOperator::Or => {
                     // For AND/OR, recurse into both sides
-                    infer_types_in_expr(left, parameter_types);
-                    infer_types_in_expr(right, parameter_types);
+                    infer_types_in_expr(left, tables, parameter_types);
+                    infer_types_in_expr(right, tables, parameter_types);
                 }
                 _ => {}
             }
         }
         Expr::UnaryOp { expr, .. } => {
-            infer_types_in_expr(expr, parameter_types);
+            infer_types_in_expr(expr, tables, parameter_types);
         }
         Expr::InList { expr, list, .. } => {
-            infer_types_in_expr(expr, parameter_types);
+            infer_types_in_expr(expr, tables, parameter_types);
+            // Parameters in the list take the type of what is looked for
+            let expr_type = infer_type_from_expr(expr, tables);
             for item in list {
-                infer_types_in_expr(item, parameter_types);
+                match (placeholder_number(item), &expr_type) {
+                    (Some(param_num), Some(expr_type)) => {
+                        parameter_types.insert(param_num, expr_type.clone());
+                    }
+                    _ => infer_types_in_expr(item, tables, parameter_types),
+                }
             }
         }
         Expr::Between {
             expr, low, high, ..
         } => {
-            infer_types_in_expr(expr, parameter_types);
-            infer_types_in_expr(low, parameter_types);
-            infer_types_in_expr(high, parameter_types);
+            infer_types_in_expr(expr, tables, parameter_types);
+            let expr_type = infer_type_from_expr(expr, tables);
+            for bound in [low, high] {
+                match (placeholder_number(bound), &expr_type) {
+                    (Some(param_num), Some(expr_type)) => {
+                        parameter_types.insert(param_num, expr_type.clone());
+                    }
+                    _ => infer_types_in_expr(bound, tables, parameter_types),
+                }
+            }
         }
         Expr::Case {
             operand,
@@ -1085,61 +1452,49 @@ fn infer_types_in_expr(
             else_result,
         } => {
             if let Some(op) = operand {
-                infer_types_in_expr(op, parameter_types);
+                infer_types_in_expr(op, tables, parameter_types);
             }
             for cond in conditions {
-                infer_types_in_expr(cond, parameter_types);
+                infer_types_in_expr(cond, tables, parameter_types);
             }
             for res in results {
-                infer_types_in_expr(res, parameter_types);
+                infer_types_in_expr(res, tables, parameter_types);
             }
             if let Some(else_res) = else_result {
-                infer_types_in_expr(else_res, parameter_types);
+                infer_types_in_expr(else_res, tables, parameter_types);
             }
         }
         Expr::Nested(inner) => {
-            infer_types_in_expr(inner, parameter_types);
+            infer_types_in_expr(inner, tables, parameter_types);
         }
         Expr::IsNull(inner) | Expr::IsNotNull(inner) => {
-            infer_types_in_expr(inner, parameter_types);
+            infer_types_in_expr(inner, tables, parameter_types);
         }
         Expr::Like { expr, pattern, .. } => {
             // For LIKE expressions, both sides should be text
-            infer_types_in_expr(expr, parameter_types);
+            infer_types_in_expr(expr, tables, parameter_types);
 
             // If the pattern is a parameter, mark it as text
             if let Expr::Value(SqlValue::Placeholder(s)) = &**pattern {
-                if let Some(num_str) = s.strip_prefix('$') {
-                    if let Ok(param_num) = num_str.parse::<usize>() {
-                        parameter_types.insert(param_num, SqlType::Text);
-                    }
+                if let Some(num_str) = s.strip_prefix('$')
+                    && let Ok(param_num) = num_str.parse::<usize>()
+                {
+                    parameter_types.insert(param_num, SqlType::Text);
                 }
             } else {
-                infer_types_in_expr(pattern, parameter_types);
+                infer_types_in_expr(pattern, tables, parameter_types);
             }
         }
         _ => {}
     }
 }
 
-fn infer_type_from_expr(expr: &Expr) -> Option<SqlType> {
+fn infer_type_from_expr(expr: &Expr, tables: &[Arc<Table>]) -> Option<SqlType> {
     match expr {
-        Expr::Identifier(ident) => {
-            // Try to infer type from column name
-            match ident.value.to_lowercase().as_str() {
-                "age" | "id" | "count" | "quantity" | "value" => Some(SqlType::Integer),
-                "price" | "amount" | "total" => Some(SqlType::Double),
-                "active" | "enabled" | "deleted" | "is_active" | "in_stock" => {
-                    Some(SqlType::Boolean)
-                }
-                "name" | "username" | "email" | "description" | "status" | "customer_name" => {
-                    Some(SqlType::Text)
-                }
-                "created_at" | "updated_at" => Some(SqlType::Timestamp),
-                "created_date" => Some(SqlType::Date),
-                _ => None,
-            }
+        Expr::CompoundIdentifier(parts) => {
+            infer_type_from_expr(&Expr::Identifier(parts.last()?.clone()), tables)
         }
+        Expr::Identifier(ident) => column_type(tables, &ident.value),
         Expr::Value(SqlValue::Boolean(_)) => Some(SqlType::Boolean),
         Expr::Value(SqlValue::Number(_, _)) => Some(SqlType::Integer),
         Expr::Value(SqlValue::SingleQuotedString(_)) => Some(SqlType::Text),
@@ -1149,6 +1504,7 @@ fn infer_type_from_expr(expr: &Expr) -> Option<SqlType> {
 
 fn infer_types_in_projection_expr(
     expr: &Expr,
+    tables: &[Arc<Table>],
     parameter_types: &mut std::collections::HashMap<usize, SqlType>,
 ) {
     match expr {
@@ -1157,18 +1513,61 @@ fn infer_types_in_projection_expr(
             if let FunctionArguments::List(args) = &func.args {
                 for arg in &args.args {
                     if let FunctionArg::Unnamed(FunctionArgExpr::Expr(arg_expr)) = arg {
-                        infer_types_in_expr(arg_expr, parameter_types);
+                        infer_types_in_expr(arg_expr, tables, parameter_types);
                     }
                 }
             }
         }
         _ => {
             // For non-function expressions in projection, just use regular inference
-            infer_types_in_expr(expr, parameter_types);
+            infer_types_in_expr(expr, tables, parameter_types);
         }
     }
 }
 
+/// A one-dimensional array parameter in binary format
+fn parse_binary_array(data: &[u8], element_type: &SqlType) -> crate::Result<Value> {
+    let invalid = || YamlBaseError::Protocol("Invalid array parameter".to_string());
+    let mut data = data;
+    let next_i32 = |data: &mut &[u8]| -> crate::Result<i32> {
+        let (bytes, rest) = data.split_first_chunk::<4>().ok_or_else(invalid)?;
+        *data = rest;
+        Ok(i32::from_be_bytes(*bytes))
+    };
+
+    let dimensions = next_i32(&mut data)?;
+    let _has_nulls = next_i32(&mut data)?;
+    let _element_oid = next_i32(&mut data)?;
+    let length = match dimensions {
+        0 => 0,
+        1 => {
+            let length = next_i32(&mut data)?;
+            let _lower_bound = next_i32(&mut data)?;
+            length
+        }
+        _ => {
+            return Err(YamlBaseError::Protocol(
+                "Only one-dimensional arrays are supported".to_string(),
+            ));
+        }
+    };
+
+    let mut items = Vec::new();
+    for _ in 0..length {
+        let item_length = next_i32(&mut data)?;
+        if item_length < 0 {
+            items.push(Value::Null);
+            continue;
+        }
+        let (item, rest) = data
+            .split_at_checked(item_length as usize)
+            .ok_or_else(invalid)?;
+        items.push(parse_parameter_value(item, element_type)?);
+        data = rest;
+    }
+    Ok(Value::Array(items))
+}
+
 fn parse_parameter_value(data: &[u8], sql_type: &SqlType) -> crate::Result<Value> {
     match sql_type {
         SqlType::Integer => {
@@ -1234,6 +1633,20 @@ fn parse_parameter_value(data: &[u8], sql_type: &SqlType) -> crate::Result<Value
                 Err(YamlBaseError::Protocol("Invalid boolean size".to_string()))
             }
         }
+        SqlType::Blob => {
+            // Hex after `\x` in text format, the bytes themselves in binary format
+            let hex_digits = data
+                .strip_prefix(b"\\x")
+                .and_then(|digits| hex::decode(digits).ok());
+            Ok(Value::Bytes(hex_digits.unwrap_or_else(|| data.to_vec())))
+        }
+        // Text format, `{a,b}`, is converted to the column's type when it is used
+        SqlType::Array(_) if data.first() == Some(&b'{') => {
+            let text = std::str::from_utf8(data)
+                .map_err(|_| YamlBaseError::Protocol("Invalid UTF-8 in parameter".to_string()))?;
+            Ok(Value::Text(text.to_string()))
+        }
+        SqlType::Array(element_type) => parse_binary_array(data, element_type),
         _ => {
             // For text types, assume UTF-8 encoding
             let text = std::str::from_utf8(data)
@@ -0,0 +1,199 @@
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use bytes::{BufMut, BytesMut};
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tracing::debug;
+
+use crate::YamlBaseError;
+use crate::protocol::tls::Stream;
+
+pub const SCRAM_SHA_256: &str = "SCRAM-SHA-256";
+
+// PostgreSQL authentication request codes
+const AUTH_SASL: u32 = 10;
+const AUTH_SASL_CONTINUE: u32 = 11;
+const AUTH_SASL_FINAL: u32 = 12;
+
+// The iteration count PostgreSQL uses for new SCRAM secrets
+const ITERATIONS: u32 = 4096;
+
+/// The server side of a SCRAM-SHA-256 exchange (RFC 5802, RFC 7677) for a password
+/// known in plain text. Channel binding is not offered, so clients send the `n` or `y`
+/// GS2 header.
+#[derive(Debug)]
+pub struct ScramSha256<'a> {
+    password: &'a str,
+}
+
+impl<'a> ScramSha256<'a> {
+    pub fn new(password: &'a str) -> Self {
+        Self { password }
+    }
+
+    /// Run the SASL exchange; true when the client proved it knows the password
+    pub async fn authenticate(
+        &self,
+        stream: &mut Stream,
+        buffer: &mut BytesMut,
+    ) -> crate::Result<bool> {
+        let mut mechanisms = BytesMut::new();
+        mechanisms.put_slice(SCRAM_SHA_256.as_bytes());
+        mechanisms.put_u8(0);
+        mechanisms.put_u8(0);
+        send_auth(stream, AUTH_SASL, &mechanisms).await?;
+
+        // SASLInitialResponse: the mechanism, then the length of the client-first-message
+        let message = read_password_message(stream, buffer).await?;
+        let name_end = message
+            .iter()
+            .position(|&b| b == 0)
+            .ok_or_else(|| malformed("SASLInitialResponse"))?;
+        if &message[..name_end] != SCRAM_SHA_256.as_bytes() || message.len() < name_end + 5 {
+            return Err(YamlBaseError::Protocol(
+                "Unsupported SASL mechanism".to_string(),
+            ));
+        }
+        let client_first = utf8(&message[name_end + 5..])?;
+
+        // client-first-message = gs2-header client-first-message-bare
+        let (gs2_header, client_first_bare) = match client_first.match_indices(',').nth(1) {
+            Some((index, _)) => client_first.split_at(index + 1),
+            None => return Err(malformed("client-first-message")),
+        };
+        if !(gs2_header.starts_with("n,") || gs2_header.starts_with("y,")) {
+            return Err(YamlBaseError::Protocol(
+                "SCRAM channel binding is not supported".to_string(),
+            ));
+        }
+        let client_nonce = attribute(client_first_bare, 'r')?;
+
+        let mut salt = [0u8; 16];
+        let mut server_nonce = [0u8; 18];
+        rand::thread_rng().fill_bytes(&mut salt);
+        rand::thread_rng().fill_bytes(&mut server_nonce);
+        let nonce = format!("{}{}", client_nonce, BASE64.encode(server_nonce));
+        let server_first = format!("r={},s={},i={}", nonce, BASE64.encode(salt), ITERATIONS);
+        send_auth(stream, AUTH_SASL_CONTINUE, server_first.as_bytes()).await?;
+
+        // SASLResponse: client-final-message = c=..,r=..,p=proof
+        let message = read_password_message(stream, buffer).await?;
+        let client_final = utf8(&message)?;
+        let (without_proof, proof) = client_final
+            .rsplit_once(",p=")
+            .ok_or_else(|| malformed("client-final-message"))?;
+        let proof = BASE64
+            .decode(proof)
+            .map_err(|_| malformed("client proof"))?;
+        if attribute(without_proof, 'c')? != BASE64.encode(gs2_header)
+            || attribute(without_proof, 'r')? != nonce
+            || proof.len() != 32
+        {
+            debug!("SCRAM client-final-message does not match the exchange");
+            return Ok(false);
+        }
+
+        let salted_password = hi(&saslprep(self.password), &salt, ITERATIONS);
+        let client_key = hmac(&salted_password, b"Client Key");
+        let stored_key = Sha256::digest(client_key);
+        let auth_message = format!("{},{},{}", client_first_bare, server_first, without_proof);
+        let client_signature = hmac(&stored_key, auth_message.as_bytes());
+        let given_key: Vec<u8> = proof
+            .iter()
+            .zip(client_signature)
+            .map(|(p, s)| p ^ s)
+            .collect();
+        if Sha256::digest(&given_key) != stored_key {
+            debug!("SCRAM proof does not match the password");
+            return Ok(false);
+        }
+
+        let server_key = hmac(&salted_password, b"Server Key");
+        let server_signature = hmac(&server_key, auth_message.as_bytes());
+        let server_final = format!("v={}", BASE64.encode(server_signature));
+        send_auth(stream, AUTH_SASL_FINAL, server_final.as_bytes()).await?;
+        Ok(true)
+    }
+}
+
+/// Read a password message ('p'), which carries every SASL response
+pub(crate) async fn read_password_message(
+    stream: &mut Stream,
+    buffer: &mut BytesMut,
+) -> crate::Result<BytesMut> {
+    loop {
+        if buffer.len() >= 5 {
+            let length = u32::from_be_bytes([buffer[1], buffer[2], buffer[3], buffer[4]]) as usize;
+            if buffer[0] != b'p' || length < 4 {
+                return Err(YamlBaseError::Protocol(
+                    "Expected password message".to_string(),
+                ));
+            }
+            if buffer.len() > length {
+                let mut message = buffer.split_to(length + 1);
+                return Ok(message.split_off(5));
+            }
+        }
+        if stream.read_buf(buffer).await? == 0 {
+            return Err(YamlBaseError::Protocol(
+                "Connection closed during authentication".to_string(),
+            ));
+        }
+    }
+}
+
+async fn send_auth(stream: &mut Stream, code: u32, data: &[u8]) -> crate::Result<()> {
+    let mut buf = BytesMut::new();
+    buf.put_u8(b'R');
+    buf.put_u32(8 + data.len() as u32);
+    buf.put_u32(code);
+    buf.put_slice(data);
+    stream.write_all(&buf).await?;
+    Ok(())
+}
+
+/// The value of a `name=value` attribute of a SCRAM message
+fn attribute(message: &str, name: char) -> crate::Result<&str> {
+    message
+        .split(',')
+        .find_map(|part| part.strip_prefix(name)?.strip_prefix('='))
+        .ok_or_else(|| malformed("SCRAM message"))
+}
+
+fn utf8(data: &[u8]) -> crate::Result<&str> {
+    std::str::from_utf8(data).map_err(|_| malformed("SCRAM message"))
+}
+
+fn malformed(what: &str) -> YamlBaseError {
+    YamlBaseError::Protocol(format!("Malformed {}", what))
+}
+
+// Like PostgreSQL, use the password as is when it cannot be normalized
+fn saslprep(password: &str) -> String {
+    stringprep::saslprep(password)
+        .map(|password| password.into_owned())
+        .unwrap_or_else(|_| password.to_string())
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> [u8; 32] {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(data);
+    mac.finalize().into_bytes().into()
+}
+
+// Hi() of RFC 5802, which is PBKDF2 with HMAC-SHA-256
+fn hi(password: &str, salt: &[u8], iterations: u32) -> [u8; 32] {
+    let mut block = salt.to_vec();
+    block.extend_from_slice(&1u32.to_be_bytes());
+    let mut u = hmac(password.as_bytes(), &block);
+    let mut result = u;
+    for _ in 1..iterations {
+        u = hmac(password.as_bytes(), &u);
+        for (r, b) in result.iter_mut().zip(u) {
+            *r ^= b;
+        }
+    }
+    result
+}
@@ -0,0 +1,988 @@
+// Microsoft SQL Server's TDS protocol, as of version 7.4: the PRELOGIN and LOGIN7
+// handshake, SQL batches and the result sets of their statements, so that applications
+// written against SQL Server can be pointed at a YAML file. Statements are read as T-SQL.
+// The connection is not encrypted, so clients have to turn encryption off (`encrypt=false`
+// in JDBC, `Encrypt=no` in ODBC), and remote procedure calls, which drivers send for
+// parameterized queries, are answered with an error.
+use bytes::{BufMut, BytesMut};
+use chrono::{Datelike, NaiveDate, NaiveTime, Timelike};
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tracing::{debug, info};
+
+use crate::YamlBaseError;
+use crate::config::Config;
+use crate::database::{Databases, Value};
+use crate::protocol::timeouts::{LoginSignal, next_request};
+use crate::server::rate_limit::{ClientRateLimiter, Quota};
+use crate::sql::executor::QueryResult;
+use crate::sql::{QueryExecutor, SqlDialect, parse_sql_with_dialect, split_statements};
+use crate::yaml::Grants;
+use crate::yaml::schema::SqlType;
+
+// Packet types
+const SQL_BATCH: u8 = 0x01;
+const RPC: u8 = 0x03;
+const TABULAR_RESULT: u8 = 0x04;
+const ATTENTION: u8 = 0x06;
+const LOGIN7: u8 = 0x10;
+const PRELOGIN: u8 = 0x12;
+
+/// The status of the last packet of a message
+const STATUS_END_OF_MESSAGE: u8 = 0x01;
+const HEADER_LENGTH: usize = 8;
+const DEFAULT_PACKET_SIZE: usize = 4096;
+/// The packets of the negotiated size a message may take up
+const MAX_MESSAGE_PACKETS: usize = 4096;
+
+// PRELOGIN options
+const PRELOGIN_VERSION: u8 = 0x00;
+const PRELOGIN_ENCRYPTION: u8 = 0x01;
+const PRELOGIN_INSTOPT: u8 = 0x02;
+const PRELOGIN_THREADID: u8 = 0x03;
+const PRELOGIN_MARS: u8 = 0x04;
+const PRELOGIN_TERMINATOR: u8 = 0xff;
+const ENCRYPT_NOT_SUP: u8 = 0x02;
+
+// Tokens
+const COLMETADATA: u8 = 0x81;
+const ERROR: u8 = 0xaa;
+const LOGINACK: u8 = 0xad;
+const FEATUREEXTACK: u8 = 0xae;
+const ROW: u8 = 0xd1;
+const ENVCHANGE: u8 = 0xe3;
+const DONE: u8 = 0xfd;
+
+// DONE status
+const DONE_FINAL: u16 = 0x00;
+const DONE_MORE: u16 = 0x01;
+const DONE_ERROR: u16 = 0x02;
+const DONE_COUNT: u16 = 0x10;
+const DONE_ATTN: u16 = 0x20;
+
+// ENVCHANGE types
+const ENV_DATABASE: u8 = 1;
+const ENV_PACKET_SIZE: u8 = 4;
+const ENV_SQL_COLLATION: u8 = 7;
+
+// Data types
+const INTNTYPE: u8 = 0x26;
+const GUIDTYPE: u8 = 0x24;
+const DATENTYPE: u8 = 0x28;
+const TIMENTYPE: u8 = 0x29;
+const DATETIME2NTYPE: u8 = 0x2a;
+const DATETIMEOFFSETNTYPE: u8 = 0x2b;
+const BITNTYPE: u8 = 0x68;
+const DECIMALNTYPE: u8 = 0x6a;
+const FLTNTYPE: u8 = 0x6d;
+const BIGVARBINARYTYPE: u8 = 0xa5;
+const NVARCHARTYPE: u8 = 0xe7;
+
+/// The TDS version the server speaks, the latest of those clients ask for it accepts
+const TDS_7_4: u32 = 0x74000004;
+/// The first version with the date and time types
+const TDS_7_3: u32 = 0x730a0003;
+/// The SQL Server version clients are told the server is
+const SERVER_VERSION: [u8; 4] = [16, 0, 0x10, 0x00];
+const SERVER_NAME: &str = "yamlbase";
+/// Latin1_General_CI_AS, the collation of text columns
+const COLLATION: [u8; 5] = [0x09, 0x04, 0xd0, 0x00, 0x34];
+/// The number of characters or bytes past which a column is sent as `(MAX)`
+const MAX_NVARCHAR_CHARS: usize = 4000;
+const MAX_VARBINARY_BYTES: usize = 8000;
+/// The fractional second digits of the time types, in 100 nanosecond units
+const TIME_SCALE: u8 = 7;
+/// The length of a PLP value whose length is not sent ahead, and of a NULL one
+const PLP_NULL: u64 = u64::MAX;
+
+// Error numbers
+const LOGIN_FAILED: i32 = 18456;
+const TOO_MANY_CONNECTIONS: i32 = 17809;
+const SYNTAX_ERROR: i32 = 102;
+const UNKNOWN_DATABASE: i32 = 911;
+const USER_ERROR: i32 = 50000;
+
+pub struct TdsProtocol {
+    config: Arc<Config>,
+    databases: Databases,
+    // The position of the current database in `databases`, and its session
+    database: usize,
+    executor: QueryExecutor,
+    // The sessions of the databases the connection used before
+    other_sessions: HashMap<usize, QueryExecutor>,
+    // The tables the user of the connection is limited to, if it is
+    grants: Option<Grants>,
+    // The rate limits the user's statements count against
+    quota: Option<Arc<Quota>>,
+    tds_version: u32,
+    packet_size: usize,
+    packet_id: u8,
+    login: LoginSignal,
+    rate_limiter: ClientRateLimiter,
+}
+
+/// The fields of a LOGIN7 message the server uses
+struct Login {
+    tds_version: u32,
+    packet_size: usize,
+    username: String,
+    password: String,
+    database: String,
+    feature_extension: bool,
+}
+
+/// The TDS type a column of a result is sent as
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ColumnType {
+    Int,
+    Bit,
+    Float,
+    Decimal { scale: u32 },
+    Date,
+    Time,
+    DateTime2,
+    DateTimeOffset,
+    Guid,
+    NVarChar { max: bool },
+    VarBinary { max: bool },
+}
+
+impl TdsProtocol {
+    pub async fn new(config: Arc<Config>, databases: Databases) -> crate::Result<Self> {
+        let executor = Self::executor(&config, &databases, 0).await?;
+        Ok(Self {
+            config,
+            databases,
+            database: 0,
+            executor,
+            other_sessions: HashMap::new(),
+            grants: None,
+            quota: None,
+            tds_version: TDS_7_4,
+            packet_size: DEFAULT_PACKET_SIZE,
+            packet_id: 1,
+            login: LoginSignal::default(),
+            rate_limiter: ClientRateLimiter::default(),
+        })
+    }
+
+    /// Tell `login` once the client has logged in, for --connect-timeout
+    pub fn with_login_signal(mut self, login: LoginSignal) -> Self {
+        self.login = login;
+        self
+    }
+
+    /// Count the client's statements against its rate limits
+    pub fn with_rate_limiter(mut self, rate_limiter: ClientRateLimiter) -> Self {
+        self.rate_limiter = rate_limiter;
+        self
+    }
+
+    /// Turn a client away, as SQL Server does beyond its user connections: the login is
+    /// answered with an error
+    pub async fn refuse_connection(&mut self, mut stream: TcpStream) -> crate::Result<()> {
+        self.read_message(&mut stream).await?;
+        self.send_prelogin(&mut stream).await?;
+        self.read_message(&mut stream).await?;
+        let message = format!(
+            "Could not connect because the maximum number of '{}' user connections has \
+             already been reached.",
+            self.config.max_connections()
+        );
+        self.send_error(&mut stream, TOO_MANY_CONNECTIONS, &message)
+            .await
+    }
+
+    /// An executor for a session on one of the databases
+    async fn executor(
+        config: &Config,
+        databases: &Databases,
+        index: usize,
+    ) -> crate::Result<QueryExecutor> {
+        let mut executor = QueryExecutor::new(databases.get(index).clone())
+            .await?
+            .with_dialect(SqlDialect::TSql);
+        if let Some(seed) = config.random_seed {
+            executor = executor.with_random_seed(seed);
+        }
+        let names = databases
+            .iter()
+            .map(|storage| storage.name().to_string())
+            .collect();
+        Ok(executor
+            .with_databases(names)
+            .with_recursion_limits(config.recursion_limits())
+            .with_timeout(config.statement_timeout())
+            .with_collation(config.collation()?)
+            .with_write_mode(config.write_mode(&config.files[index])))
+    }
+
+    /// Switch to the named database, as `USE` and the database of LOGIN7 do, returning
+    /// whether it exists. Each database keeps its own session; a schema of the current one
+    /// becomes its search path.
+    async fn use_database(&mut self, name: &str) -> crate::Result<bool> {
+        let Some(index) = self.databases.find(name) else {
+            let db_arc = self.executor.storage().database();
+            if !db_arc.read().await.has_schema(name) {
+                return Ok(false);
+            }
+            self.executor.set_search_path(vec![name.to_string()]);
+            return Ok(true);
+        };
+        if index != self.database {
+            let executor = match self.other_sessions.remove(&index) {
+                Some(executor) => executor,
+                None => Self::executor(&self.config, &self.databases, index)
+                    .await?
+                    .with_grants(self.grants.clone())
+                    .with_quota(self.quota.clone()),
+            };
+            let previous = std::mem::replace(&mut self.executor, executor);
+            self.other_sessions.insert(self.database, previous);
+            self.database = index;
+            debug!("Using database {}", self.databases.get(index).name());
+        }
+        Ok(true)
+    }
+
+    pub async fn handle_connection(&mut self, mut stream: TcpStream) -> crate::Result<()> {
+        info!("New SQL Server connection");
+
+        let (packet_type, _) = self.read_message(&mut stream).await?;
+        if packet_type != PRELOGIN {
+            return Err(YamlBaseError::Protocol(format!(
+                "Expected PRELOGIN, got packet type 0x{:02x}",
+                packet_type
+            )));
+        }
+        self.send_prelogin(&mut stream).await?;
+
+        let (packet_type, payload) = self.read_message(&mut stream).await?;
+        if packet_type != LOGIN7 {
+            return Err(YamlBaseError::Protocol(format!(
+                "Expected LOGIN7, got packet type 0x{:02x}; clients have to turn encryption off",
+                packet_type
+            )));
+        }
+        let login = parse_login(&payload)?;
+        if !self.login(&mut stream, &login).await? {
+            return Ok(());
+        }
+        self.login.complete();
+        info!("SQL Server authentication successful, entering command loop");
+
+        let idle_timeout = self.config.idle_timeout;
+        loop {
+            let read = self.read_message(&mut stream);
+            let (packet_type, payload) = match next_request(idle_timeout, read).await {
+                Some(Ok(message)) => message,
+                Some(Err(_)) | None => break,
+            };
+            match packet_type {
+                SQL_BATCH => {
+                    let batch = decode_utf16(skip_all_headers(&payload))?;
+                    self.handle_batch(&mut stream, &batch).await?;
+                }
+                // A query runs to its end before the next message is read, so there is
+                // nothing left to cancel
+                ATTENTION => {
+                    let mut response = BytesMut::new();
+                    put_done(&mut response, DONE_ATTN, 0);
+                    self.write_message(&mut stream, &response).await?;
+                }
+                RPC => {
+                    let message = "Remote procedure calls are not supported; send parameters \
+                                   in the SQL batch";
+                    self.send_error(&mut stream, USER_ERROR, message).await?;
+                }
+                _ => {
+                    debug!("Unhandled packet type: 0x{:02x}", packet_type);
+                    let message = format!("Unsupported packet type 0x{:02x}", packet_type);
+                    self.send_error(&mut stream, USER_ERROR, &message).await?;
+                }
+            }
+        }
+        info!("SQL Server client disconnected");
+        Ok(())
+    }
+
+    /// Answer PRELOGIN with the server's version, and that it does not encrypt
+    async fn send_prelogin(&mut self, stream: &mut TcpStream) -> crate::Result<()> {
+        let options: [(u8, &[u8]); 5] = [
+            (PRELOGIN_VERSION, &[16, 0, 0x10, 0x00, 0, 0]),
+            (PRELOGIN_ENCRYPTION, &[ENCRYPT_NOT_SUP]),
+            (PRELOGIN_INSTOPT, &[0]),
+            (PRELOGIN_THREADID, &[0, 0, 0, 0]),
+            (PRELOGIN_MARS, &[0]),
+        ];
+        let mut offset = options.len() * 5 + 1;
+        let mut packet = BytesMut::new();
+        for (option, data) in &options {
+            packet.put_u8(*option);
+            packet.put_u16(offset as u16);
+            packet.put_u16(data.len() as u16);
+            offset += data.len();
+        }
+        packet.put_u8(PRELOGIN_TERMINATOR);
+        for (_, data) in &options {
+            packet.put_slice(data);
+        }
+        self.write_message(stream, &packet).await
+    }
+
+    /// Check the user of LOGIN7 and answer it, returning whether the login succeeded
+    async fn login(&mut self, stream: &mut TcpStream, login: &Login) -> crate::Result<bool> {
+        debug!("Authentication check - username: {}", login.username);
+        let config = Arc::clone(&self.config);
+        let authenticated = match config.user(&login.username) {
+            Some((password, grants)) if password.verify(&login.password) => {
+                self.grants = grants.cloned();
+                true
+            }
+            _ => false,
+        };
+        if !authenticated {
+            let message = format!("Login failed for user '{}'.", login.username);
+            self.send_error(stream, LOGIN_FAILED, &message).await?;
+            return Ok(false);
+        }
+        self.quota = self.rate_limiter.quota(Some(&login.username));
+        self.executor = self
+            .executor
+            .clone()
+            .with_grants(self.grants.clone())
+            .with_quota(self.quota.clone());
+
+        if !login.database.is_empty() && !self.use_database(&login.database).await? {
+            let message = format!(
+                "Database '{}' does not exist. Make sure that the name is entered correctly.",
+                login.database
+            );
+            self.send_error(stream, UNKNOWN_DATABASE, &message).await?;
+            return Ok(false);
+        }
+
+        self.tds_version = login.tds_version.min(TDS_7_4);
+        let packet_size = login.packet_size.clamp(512, 32767);
+
+        let mut response = BytesMut::new();
+        let database = self.databases.get(self.database).name().to_string();
+        put_env_change(&mut response, ENV_DATABASE, |token| {
+            put_b_varchar(token, &database);
+            put_b_varchar(token, "master");
+        });
+        put_env_change(&mut response, ENV_SQL_COLLATION, |token| {
+            token.put_u8(COLLATION.len() as u8);
+            token.put_slice(&COLLATION);
+            token.put_u8(0);
+        });
+        put_env_change(&mut response, ENV_PACKET_SIZE, |token| {
+            put_b_varchar(token, &packet_size.to_string());
+            put_b_varchar(token, &DEFAULT_PACKET_SIZE.to_string());
+        });
+
+        let mut ack = BytesMut::new();
+        ack.put_u8(1); // SQL_TSQL
+        ack.put_u32(self.tds_version);
+        put_b_varchar(&mut ack, "Microsoft SQL Server");
+        ack.put_slice(&SERVER_VERSION);
+        response.put_u8(LOGINACK);
+        response.put_u16_le(ack.len() as u16);
+        response.put_slice(&ack);
+
+        // No feature extension the client asked for is acknowledged
+        if login.feature_extension {
+            response.put_u8(FEATUREEXTACK);
+            response.put_u8(0xff);
+        }
+        put_done(&mut response, DONE_FINAL, 0);
+        self.write_message(stream, &response).await?;
+        self.packet_size = packet_size;
+        Ok(true)
+    }
+
+    /// Run the statements of a batch in order, each with its result. The first error ends
+    /// the batch.
+    async fn handle_batch(&mut self, stream: &mut TcpStream, batch: &str) -> crate::Result<()> {
+        let statements = split_statements(batch, SqlDialect::TSql);
+        let mut response = BytesMut::new();
+        if statements.is_empty() {
+            put_done(&mut response, DONE_FINAL, 0);
+            return self.write_message(stream, &response).await;
+        }
+
+        for (i, sql) in statements.iter().enumerate() {
+            let more = if i + 1 < statements.len() {
+                DONE_MORE
+            } else {
+                DONE_FINAL
+            };
+            match self.run_statement(sql).await {
+                Ok(Statement::Result(result)) => {
+                    self.put_result(&mut response, &result);
+                    let count = result.affected_rows.unwrap_or(result.rows.len() as u64);
+                    put_done(&mut response, more | DONE_COUNT, count);
+                }
+                Ok(Statement::UseDatabase { database, previous }) => {
+                    put_env_change(&mut response, ENV_DATABASE, |token| {
+                        put_b_varchar(token, &database);
+                        put_b_varchar(token, &previous);
+                    });
+                    put_done(&mut response, more, 0);
+                }
+                Ok(Statement::Done) => put_done(&mut response, more, 0),
+                Err((number, message)) => {
+                    put_error(&mut response, number, &message);
+                    put_done(&mut response, DONE_ERROR, 0);
+                    break;
+                }
+            }
+        }
+        self.write_message(stream, &response).await
+    }
+
+    /// Run one statement of a batch, or fail with the error number and message to send
+    async fn run_statement(&mut self, sql: &str) -> Result<Statement, (i32, String)> {
+        // USE switches to another of the databases served
+        if let Some(name) = sql
+            .get(..4)
+            .filter(|keyword| keyword.eq_ignore_ascii_case("USE "))
+            .map(|_| {
+                sql[4..]
+                    .trim()
+                    .trim_start_matches('[')
+                    .trim_end_matches(']')
+            })
+        {
+            let previous = self.databases.get(self.database).name().to_string();
+            return match self.use_database(name).await {
+                Ok(true) => Ok(Statement::UseDatabase {
+                    database: self.databases.get(self.database).name().to_string(),
+                    previous,
+                }),
+                Ok(false) => Err((
+                    UNKNOWN_DATABASE,
+                    format!(
+                        "Database '{}' does not exist. Make sure that the name is entered \
+                         correctly.",
+                        name
+                    ),
+                )),
+                Err(e) => Err((USER_ERROR, e.to_string())),
+            };
+        }
+
+        let statements = parse_sql_with_dialect(sql, SqlDialect::TSql)
+            .map_err(|e| (SYNTAX_ERROR, format!("Incorrect syntax: {}", e)));
+        // The connection options drivers set, such as SET NOCOUNT ON and SET ANSI_NULLS ON,
+        // are ignored; the engine's own settings are applied
+        if sql
+            .get(..4)
+            .is_some_and(|keyword| keyword.eq_ignore_ascii_case("SET "))
+        {
+            return match statements {
+                Ok(statements) => match statements.as_slice() {
+                    [statement] if QueryExecutor::is_session_setting(statement) => self
+                        .executor
+                        .execute(statement)
+                        .await
+                        .map(|_| Statement::Done)
+                        .map_err(|e| (USER_ERROR, e.to_string())),
+                    _ => Ok(Statement::Done),
+                },
+                Err(_) => Ok(Statement::Done),
+            };
+        }
+
+        let mut result = None;
+        for statement in statements? {
+            debug!("Executing statement: {:?}", statement);
+            result = Some(
+                self.executor
+                    .execute(&statement)
+                    .await
+                    .map_err(|e| (USER_ERROR, e.to_string()))?,
+            );
+        }
+        Ok(result.map_or(Statement::Done, Statement::Result))
+    }
+
+    /// The COLMETADATA and ROW tokens of a result; nothing for a statement without rows
+    fn put_result(&self, response: &mut BytesMut, result: &QueryResult) {
+        if result.columns.is_empty() {
+            return;
+        }
+        let types: Vec<ColumnType> = (0..result.columns.len())
+            .map(|i| {
+                column_type(
+                    result.rows.iter().map(|row| &row[i]),
+                    result.column_types.get(i),
+                    self.tds_version >= TDS_7_3,
+                )
+            })
+            .collect();
+
+        response.put_u8(COLMETADATA);
+        response.put_u16_le(result.columns.len() as u16);
+        for (name, column_type) in result.columns.iter().zip(&types) {
+            response.put_u32_le(0); // user type
+            response.put_u16_le(0x0001); // nullable
+            put_type_info(response, *column_type);
+            put_b_varchar(response, name);
+        }
+
+        for row in &result.rows {
+            response.put_u8(ROW);
+            for (value, column_type) in row.iter().zip(&types) {
+                put_value(response, *column_type, value);
+            }
+        }
+    }
+
+    /// Send an ERROR token and the DONE that ends the response
+    async fn send_error(
+        &mut self,
+        stream: &mut TcpStream,
+        number: i32,
+        message: &str,
+    ) -> crate::Result<()> {
+        let mut response = BytesMut::new();
+        put_error(&mut response, number, message);
+        put_done(&mut response, DONE_ERROR, 0);
+        self.write_message(stream, &response).await
+    }
+
+    /// Read a message, joining the packets it was sent in. A message longer than
+    /// `MAX_MESSAGE_PACKETS` packets of the negotiated size is answered with an error and
+    /// ends the connection.
+    async fn read_message(&mut self, stream: &mut TcpStream) -> crate::Result<(u8, Vec<u8>)> {
+        let max_length = self.packet_size * MAX_MESSAGE_PACKETS;
+        let mut payload = Vec::new();
+        loop {
+            let mut header = [0u8; HEADER_LENGTH];
+            stream.read_exact(&mut header).await?;
+            let length = u16::from_be_bytes([header[2], header[3]]) as usize;
+            if length < HEADER_LENGTH {
+                return Err(YamlBaseError::Protocol(format!(
+                    "Invalid TDS packet length {}",
+                    length
+                )));
+            }
+            let start = payload.len();
+            payload.resize(start + length - HEADER_LENGTH, 0);
+            stream.read_exact(&mut payload[start..]).await?;
+            if payload.len() > max_length {
+                let message = format!("A message may be at most {} bytes long", max_length);
+                self.send_error(stream, USER_ERROR, &message).await?;
+                return Err(YamlBaseError::Protocol(message));
+            }
+            if header[1] & STATUS_END_OF_MESSAGE != 0 {
+                return Ok((header[0], payload));
+            }
+        }
+    }
+
+    /// Send a tabular result, in packets of the size the client asked for
+    async fn write_message(&mut self, stream: &mut TcpStream, payload: &[u8]) -> crate::Result<()> {
+        let chunk_size = self.packet_size - HEADER_LENGTH;
+        let chunks: Vec<&[u8]> = if payload.is_empty() {
+            vec![&[]]
+        } else {
+            payload.chunks(chunk_size).collect()
+        };
+        let mut packets = BytesMut::with_capacity(payload.len() + chunks.len() * HEADER_LENGTH);
+        for (i, chunk) in chunks.iter().enumerate() {
+            packets.put_u8(TABULAR_RESULT);
+            packets.put_u8(if i + 1 == chunks.len() {
+                STATUS_END_OF_MESSAGE
+            } else {
+                0
+            });
+            packets.put_u16((chunk.len() + HEADER_LENGTH) as u16);
+            packets.put_u16(0); // SPID
+            packets.put_u8(self.packet_id);
+            packets.put_u8(0); // window
+            packets.put_slice(chunk);
+            self.packet_id = self.packet_id.wrapping_add(1);
+        }
+        stream.write_all(&packets).await?;
+        stream.flush().await?;
+        Ok(())
+    }
+}
+
+/// What a statement of a batch sends back
+enum Statement {
+    Result(QueryResult),
+    UseDatabase { database: String, previous: String },
+    Done,
+}
+
+/// The fields of LOGIN7 the server uses. Strings are UTF-16 at the offsets and lengths, in
+/// characters, of a table after the fixed fields.
+fn parse_login(payload: &[u8]) -> crate::Result<Login> {
+    let invalid = || YamlBaseError::Protocol("Invalid LOGIN7 message".to_string());
+    let u32_at = |offset: usize| -> crate::Result<u32> {
+        payload
+            .get(offset..offset + 4)
+            .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+            .ok_or_else(invalid)
+    };
+    let string_at = |entry: usize| -> crate::Result<Vec<u8>> {
+        let bytes = payload.get(entry..entry + 4).ok_or_else(invalid)?;
+        let offset = u16::from_le_bytes([bytes[0], bytes[1]]) as usize;
+        let length = u16::from_le_bytes([bytes[2], bytes[3]]) as usize * 2;
+        payload
+            .get(offset..offset + length)
+            .map(<[u8]>::to_vec)
+            .ok_or_else(invalid)
+    };
+
+    let tds_version = u32_at(4)?;
+    let packet_size = u32_at(8)? as usize;
+    let option_flags3 = *payload.get(27).ok_or_else(invalid)?;
+    let username = decode_utf16(&string_at(40)?)?;
+    // Each byte of the password has its nibbles swapped and is XORed with 0xA5
+    let password: Vec<u8> = string_at(44)?
+        .iter()
+        .map(|byte| (byte ^ 0xa5).rotate_left(4))
+        .collect();
+    let password = decode_utf16(&password)?;
+    let database = decode_utf16(&string_at(68)?)?;
+    Ok(Login {
+        tds_version,
+        packet_size: if packet_size == 0 {
+            DEFAULT_PACKET_SIZE
+        } else {
+            packet_size
+        },
+        username,
+        password,
+        database,
+        feature_extension: option_flags3 & 0x10 != 0,
+    })
+}
+
+/// The SQL of a batch, after the headers TDS 7.2 and later put before it
+fn skip_all_headers(payload: &[u8]) -> &[u8] {
+    let Some(bytes) = payload.get(..4) else {
+        return payload;
+    };
+    let length = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize;
+    // Without headers the batch starts with text, whose first characters are no length
+    if (4..=payload.len()).contains(&length) && length < 1024 {
+        &payload[length..]
+    } else {
+        payload
+    }
+}
+
+fn decode_utf16(bytes: &[u8]) -> crate::Result<String> {
+    let units: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+        .collect();
+    String::from_utf16(&units)
+        .map_err(|_| YamlBaseError::Protocol("Invalid UTF-16 in message".to_string()))
+}
+
+fn put_utf16(buffer: &mut BytesMut, text: &str) {
+    for unit in text.encode_utf16() {
+        buffer.put_u16_le(unit);
+    }
+}
+
+/// A string with its length in characters in one byte
+fn put_b_varchar(buffer: &mut BytesMut, text: &str) {
+    buffer.put_u8(text.encode_utf16().count() as u8);
+    put_utf16(buffer, text);
+}
+
+/// A string with its length in characters in two bytes
+fn put_us_varchar(buffer: &mut BytesMut, text: &str) {
+    buffer.put_u16_le(text.encode_utf16().count() as u16);
+    put_utf16(buffer, text);
+}
+
+fn put_env_change(buffer: &mut BytesMut, env_type: u8, values: impl FnOnce(&mut BytesMut)) {
+    let mut token = BytesMut::new();
+    token.put_u8(env_type);
+    values(&mut token);
+    buffer.put_u8(ENVCHANGE);
+    buffer.put_u16_le(token.len() as u16);
+    buffer.put_slice(&token);
+}
+
+fn put_done(buffer: &mut BytesMut, status: u16, row_count: u64) {
+    buffer.put_u8(DONE);
+    buffer.put_u16_le(status);
+    buffer.put_u16_le(0); // current command
+    buffer.put_u64_le(row_count);
+}
+
+fn put_error(buffer: &mut BytesMut, number: i32, message: &str) {
+    let mut token = BytesMut::new();
+    token.put_i32_le(number);
+    token.put_u8(1); // state
+    token.put_u8(if number == LOGIN_FAILED { 14 } else { 16 }); // class
+    put_us_varchar(&mut token, message);
+    put_b_varchar(&mut token, SERVER_NAME);
+    put_b_varchar(&mut token, ""); // procedure
+    token.put_i32_le(1); // line
+    buffer.put_u8(ERROR);
+    buffer.put_u16_le(token.len() as u16);
+    buffer.put_slice(&token);
+}
+
+/// The type a column is sent as, from its values when it has any and otherwise from its
+/// SQL type. Columns whose values have no common TDS type are sent as text.
+fn column_type<'a>(
+    values: impl Iterator<Item = &'a Value>,
+    sql_type: Option<&SqlType>,
+    date_types: bool,
+) -> ColumnType {
+    let mut column_type: Option<ColumnType> = None;
+    let mut longest = 0;
+    for value in values {
+        let value_type = match value {
+            Value::Null => continue,
+            Value::Integer(_) => ColumnType::Int,
+            Value::Boolean(_) => ColumnType::Bit,
+            Value::Float(_) | Value::Double(_) => ColumnType::Float,
+            Value::Decimal(decimal) => ColumnType::Decimal {
+                scale: decimal.scale(),
+            },
+            Value::Date(_) if date_types => ColumnType::Date,
+            Value::Time(_) if date_types => ColumnType::Time,
+            Value::Timestamp(_) if date_types => ColumnType::DateTime2,
+            Value::TimestampTz(_) if date_types => ColumnType::DateTimeOffset,
+            Value::Uuid(_) => ColumnType::Guid,
+            Value::Bytes(bytes) => {
+                longest = longest.max(bytes.len());
+                ColumnType::VarBinary { max: false }
+            }
+            _ => {
+                longest = longest.max(text(value).encode_utf16().count());
+                ColumnType::NVarChar { max: false }
+            }
+        };
+        column_type = Some(match (column_type, value_type) {
+            (None, value_type) => value_type,
+            (Some(current), value_type) if current == value_type => current,
+            (Some(ColumnType::Int), ColumnType::Float)
+            | (Some(ColumnType::Float), ColumnType::Int) => ColumnType::Float,
+            (Some(ColumnType::Decimal { scale }), ColumnType::Int)
+            | (Some(ColumnType::Int), ColumnType::Decimal { scale }) => {
+                ColumnType::Decimal { scale }
+            }
+            (Some(ColumnType::Decimal { scale }), ColumnType::Decimal { scale: other }) => {
+                ColumnType::Decimal {
+                    scale: scale.max(other),
+                }
+            }
+            _ => ColumnType::NVarChar { max: false },
+        });
+    }
+
+    let column_type = column_type.unwrap_or(match sql_type {
+        Some(SqlType::Integer | SqlType::BigInt) => ColumnType::Int,
+        Some(SqlType::Boolean) => ColumnType::Bit,
+        Some(SqlType::Float | SqlType::Double) => ColumnType::Float,
+        Some(SqlType::Decimal(_, scale)) => ColumnType::Decimal { scale: *scale },
+        Some(SqlType::Date) if date_types => ColumnType::Date,
+        Some(SqlType::Time) if date_types => ColumnType::Time,
+        Some(SqlType::Timestamp) if date_types => ColumnType::DateTime2,
+        Some(SqlType::TimestampTz) if date_types => ColumnType::DateTimeOffset,
+        Some(SqlType::Uuid) => ColumnType::Guid,
+        Some(SqlType::Blob) => ColumnType::VarBinary { max: false },
+        _ => ColumnType::NVarChar { max: false },
+    });
+    match column_type {
+        ColumnType::NVarChar { .. } => ColumnType::NVarChar {
+            max: longest > MAX_NVARCHAR_CHARS,
+        },
+        ColumnType::VarBinary { .. } => ColumnType::VarBinary {
+            max: longest > MAX_VARBINARY_BYTES,
+        },
+        // A DECIMAL holds at most 38 digits, and rust_decimal at most 28 of them
+        ColumnType::Decimal { scale } => ColumnType::Decimal {
+            scale: scale.min(28),
+        },
+        other => other,
+    }
+}
+
+fn put_type_info(buffer: &mut BytesMut, column_type: ColumnType) {
+    match column_type {
+        ColumnType::Int => {
+            buffer.put_u8(INTNTYPE);
+            buffer.put_u8(8);
+        }
+        ColumnType::Bit => {
+            buffer.put_u8(BITNTYPE);
+            buffer.put_u8(1);
+        }
+        ColumnType::Float => {
+            buffer.put_u8(FLTNTYPE);
+            buffer.put_u8(8);
+        }
+        ColumnType::Decimal { scale } => {
+            buffer.put_u8(DECIMALNTYPE);
+            buffer.put_u8(17);
+            buffer.put_u8(38); // precision
+            buffer.put_u8(scale as u8);
+        }
+        ColumnType::Date => buffer.put_u8(DATENTYPE),
+        ColumnType::Time => {
+            buffer.put_u8(TIMENTYPE);
+            buffer.put_u8(TIME_SCALE);
+        }
+        ColumnType::DateTime2 => {
+            buffer.put_u8(DATETIME2NTYPE);
+            buffer.put_u8(TIME_SCALE);
+        }
+        ColumnType::DateTimeOffset => {
+            buffer.put_u8(DATETIMEOFFSETNTYPE);
+            buffer.put_u8(TIME_SCALE);
+        }
+        ColumnType::Guid => {
+            buffer.put_u8(GUIDTYPE);
+            buffer.put_u8(16);
+        }
+        ColumnType::NVarChar { max } => {
+            buffer.put_u8(NVARCHARTYPE);
+            buffer.put_u16_le(if max {
+                0xffff
+            } else {
+                (MAX_NVARCHAR_CHARS * 2) as u16
+            });
+            buffer.put_slice(&COLLATION);
+        }
+        ColumnType::VarBinary { max } => {
+            buffer.put_u8(BIGVARBINARYTYPE);
+            buffer.put_u16_le(if max {
+                0xffff
+            } else {
+                MAX_VARBINARY_BYTES as u16
+            });
+        }
+    }
+}
+
+fn put_value(buffer: &mut BytesMut, column_type: ColumnType, value: &Value) {
+    match (column_type, value) {
+        (ColumnType::NVarChar { max: true } | ColumnType::VarBinary { max: true }, Value::Null) => {
+            buffer.put_u64_le(PLP_NULL);
+        }
+        (
+            ColumnType::NVarChar { max: false } | ColumnType::VarBinary { max: false },
+            Value::Null,
+        ) => {
+            buffer.put_u16_le(0xffff);
+        }
+        (_, Value::Null) => buffer.put_u8(0),
+        (ColumnType::Int, Value::Integer(number)) => {
+            buffer.put_u8(8);
+            buffer.put_i64_le(*number);
+        }
+        (ColumnType::Bit, Value::Boolean(bit)) => {
+            buffer.put_u8(1);
+            buffer.put_u8(*bit as u8);
+        }
+        (ColumnType::Float, value) => {
+            let number = match value {
+                Value::Integer(number) => *number as f64,
+                Value::Float(number) => *number as f64,
+                Value::Double(number) => *number,
+                _ => 0.0,
+            };
+            buffer.put_u8(8);
+            buffer.put_f64_le(number);
+        }
+        (ColumnType::Decimal { scale }, value) => {
+            let mut decimal = match value {
+                Value::Integer(number) => Decimal::from(*number),
+                Value::Decimal(decimal) => *decimal,
+                _ => Decimal::ZERO,
+            };
+            decimal.rescale(scale);
+            let mantissa = decimal.mantissa();
+            buffer.put_u8(17);
+            buffer.put_u8((mantissa >= 0) as u8);
+            buffer.put_u128_le(mantissa.unsigned_abs());
+        }
+        (ColumnType::Date, Value::Date(date)) => {
+            buffer.put_u8(3);
+            put_date(buffer, *date);
+        }
+        (ColumnType::Time, Value::Time(time)) => {
+            buffer.put_u8(5);
+            put_time(buffer, *time);
+        }
+        (ColumnType::DateTime2, Value::Timestamp(timestamp)) => {
+            buffer.put_u8(8);
+            put_time(buffer, timestamp.time());
+            put_date(buffer, timestamp.date());
+        }
+        // The date and time in UTC, and the offset in minutes
+        (ColumnType::DateTimeOffset, Value::TimestampTz(timestamp)) => {
+            let utc = timestamp.naive_utc();
+            buffer.put_u8(10);
+            put_time(buffer, utc.time());
+            put_date(buffer, utc.date());
+            buffer.put_i16_le((timestamp.offset().local_minus_utc() / 60) as i16);
+        }
+        (ColumnType::Guid, Value::Uuid(uuid)) => {
+            buffer.put_u8(16);
+            buffer.put_slice(&uuid.to_bytes_le());
+        }
+        (ColumnType::VarBinary { max }, Value::Bytes(bytes)) => put_bytes(buffer, max, bytes),
+        (ColumnType::NVarChar { max }, value) => {
+            let mut bytes = BytesMut::new();
+            put_utf16(&mut bytes, &text(value));
+            put_bytes(buffer, max, &bytes);
+        }
+        // column_type only gives a column a type all its values have
+        (_, value) => unreachable!("{:?} in a {:?} column", value, column_type),
+    }
+}
+
+/// Bytes with their length in two bytes, or for a `(MAX)` column in one chunk of a PLP
+/// value
+fn put_bytes(buffer: &mut BytesMut, max: bool, bytes: &[u8]) {
+    if max {
+        buffer.put_u64_le(bytes.len() as u64);
+        if !bytes.is_empty() {
+            buffer.put_u32_le(bytes.len() as u32);
+            buffer.put_slice(bytes);
+        }
+        buffer.put_u32_le(0);
+    } else {
+        buffer.put_u16_le(bytes.len() as u16);
+        buffer.put_slice(bytes);
+    }
+}
+
+/// The days since 0001-01-01, in three bytes
+fn put_date(buffer: &mut BytesMut, date: NaiveDate) {
+    let days = (date.num_days_from_ce() - 1) as u32;
+    buffer.put_slice(&days.to_le_bytes()[..3]);
+}
+
+/// The 100 nanosecond units since midnight, in five bytes
+fn put_time(buffer: &mut BytesMut, time: NaiveTime) {
+    let units = time.num_seconds_from_midnight() as u64 * 10_000_000
+        + (time.nanosecond() % 1_000_000_000) as u64 / 100;
+    buffer.put_slice(&units.to_le_bytes()[..5]);
+}
+
+/// A value of a text column: text as it is, other values as they are displayed
+fn text(value: &Value) -> String {
+    match value {
+        Value::Text(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
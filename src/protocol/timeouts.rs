@@ -0,0 +1,58 @@
+// How long connections may keep the server waiting. A client has --connect-timeout to finish
+// its handshake and log in, and a session that sends nothing for --idle-timeout is closed.
+// Statements themselves are limited by the executor, with --statement-timeout.
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Notify;
+use tracing::info;
+
+use crate::YamlBaseError;
+
+/// Given to a protocol to tell once its client has logged in
+#[derive(Clone, Default)]
+pub struct LoginSignal(Arc<Notify>);
+
+impl LoginSignal {
+    pub fn complete(&self) {
+        self.0.notify_one();
+    }
+}
+
+/// Serve a connection, failing it if the client has not logged in within `timeout`
+pub async fn within_connect_timeout(
+    timeout: Duration,
+    login: &LoginSignal,
+    connection: impl Future<Output = crate::Result<()>>,
+) -> crate::Result<()> {
+    tokio::pin!(connection);
+    tokio::select! {
+        result = &mut connection => return result,
+        logged_in = tokio::time::timeout(timeout, login.0.notified()) => {
+            if logged_in.is_err() {
+                return Err(YamlBaseError::Protocol(format!(
+                    "Connect timeout after {}: the client did not log in",
+                    humantime::format_duration(timeout)
+                )));
+            }
+        }
+    }
+    connection.await
+}
+
+/// Wait for the next request of a session, or `None` once it has been idle for `idle`
+pub async fn next_request<F: Future>(idle: Option<Duration>, read: F) -> Option<F::Output> {
+    let Some(idle) = idle else {
+        return Some(read.await);
+    };
+    match tokio::time::timeout(idle, read).await {
+        Ok(output) => Some(output),
+        Err(_) => {
+            info!(
+                "Closing session idle for {}",
+                humantime::format_duration(idle)
+            );
+            None
+        }
+    }
+}
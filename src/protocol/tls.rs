@@ -0,0 +1,208 @@
+// TLS for client connections. The certificate and key come from --tls-cert and --tls-key,
+// or are generated for localhost when the server starts, so clients that require TLS can
+// connect without any setup as long as they do not verify the certificate.
+//
+// With --tls-client-ca, clients may also present a certificate signed by one of those CAs.
+// A verified certificate whose common name is the user a client logs in as stands in for
+// its password, and --tls-require-client-cert makes it the only way to log in.
+use std::io;
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::net::TcpStream;
+use tokio_rustls::rustls::crypto::ring;
+use tokio_rustls::rustls::pki_types::pem::PemObject;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer};
+use tokio_rustls::rustls::server::WebPkiClientVerifier;
+use tokio_rustls::rustls::{RootCertStore, ServerConfig};
+use tokio_rustls::server::TlsStream;
+use tracing::{debug, info};
+use x509_cert::Certificate;
+use x509_cert::der::asn1::{Ia5StringRef, PrintableStringRef, Utf8StringRef};
+use x509_cert::der::{Decode, oid::db::rfc4519};
+
+pub use tokio_rustls::TlsAcceptor;
+
+use crate::YamlBaseError;
+use crate::config::Config;
+
+/// An acceptor for the configured certificate, or for a new self-signed one
+pub fn acceptor(config: &Config) -> crate::Result<TlsAcceptor> {
+    let (certs, key) = match (&config.tls_cert, &config.tls_key) {
+        (Some(cert), Some(key)) => {
+            info!("Using TLS certificate {}", cert.display());
+            (read_certs("--tls-cert", cert)?, read_key(key)?)
+        }
+        (None, None) => self_signed()?,
+        _ => {
+            return Err(YamlBaseError::Config(
+                "--tls-cert and --tls-key must be given together".to_string(),
+            ));
+        }
+    };
+
+    let provider = Arc::new(ring::default_provider());
+    let builder = ServerConfig::builder_with_provider(Arc::clone(&provider))
+        .with_safe_default_protocol_versions()
+        .map_err(|e| YamlBaseError::Config(format!("TLS: {}", e)))?;
+    let builder = match &config.tls_client_ca {
+        Some(path) => {
+            let mut roots = RootCertStore::empty();
+            for cert in read_certs("--tls-client-ca", path)? {
+                roots.add(cert).map_err(|e| {
+                    YamlBaseError::Config(format!("--tls-client-ca {}: {}", path.display(), e))
+                })?;
+            }
+            let verifier = WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider);
+            let verifier = if config.tls_require_client_cert {
+                verifier
+            } else {
+                verifier.allow_unauthenticated()
+            };
+            let verifier = verifier.build().map_err(|e| {
+                YamlBaseError::Config(format!("--tls-client-ca {}: {}", path.display(), e))
+            })?;
+            builder.with_client_cert_verifier(verifier)
+        }
+        None => builder.with_no_client_auth(),
+    };
+    let server_config = builder
+        .with_single_cert(certs, key)
+        .map_err(|e| YamlBaseError::Config(format!("TLS certificate: {}", e)))?;
+    Ok(TlsAcceptor::from(Arc::new(server_config)))
+}
+
+fn read_certs(option: &str, path: &Path) -> crate::Result<Vec<CertificateDer<'static>>> {
+    let certs = CertificateDer::pem_file_iter(path)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .map_err(|e| YamlBaseError::Config(format!("{} {}: {}", option, path.display(), e)))?;
+    if certs.is_empty() {
+        return Err(YamlBaseError::Config(format!(
+            "{} {}: no certificates found",
+            option,
+            path.display()
+        )));
+    }
+    Ok(certs)
+}
+
+fn read_key(path: &Path) -> crate::Result<PrivateKeyDer<'static>> {
+    PrivateKeyDer::from_pem_file(path)
+        .map_err(|e| YamlBaseError::Config(format!("--tls-key {}: {}", path.display(), e)))
+}
+
+fn self_signed() -> crate::Result<(Vec<CertificateDer<'static>>, PrivateKeyDer<'static>)> {
+    let names = vec!["localhost".to_string(), "127.0.0.1".to_string()];
+    let certified = rcgen::generate_simple_self_signed(names)
+        .map_err(|e| YamlBaseError::Config(format!("Cannot generate a TLS certificate: {}", e)))?;
+    info!("Using a self-signed TLS certificate for localhost");
+    let key = PrivatePkcs8KeyDer::from(certified.key_pair.serialize_der());
+    Ok((vec![certified.cert.der().clone()], key.into()))
+}
+
+/// A client connection, upgraded to TLS when the client asked for it
+pub enum Stream {
+    Plain(TcpStream),
+    Tls(Box<TlsStream<TcpStream>>),
+}
+
+impl Stream {
+    /// The common name of the certificate the client presented. The handshake has verified
+    /// it against --tls-client-ca.
+    pub fn client_common_name(&self) -> Option<String> {
+        let Stream::Tls(stream) = self else {
+            return None;
+        };
+        let cert = stream.get_ref().1.peer_certificates()?.first()?;
+        let cert = Certificate::from_der(cert)
+            .inspect_err(|e| debug!("Cannot parse the client certificate: {}", e))
+            .ok()?;
+        cert.tbs_certificate
+            .subject
+            .0
+            .iter()
+            .flat_map(|name| name.0.iter())
+            .filter(|attribute| attribute.oid == rfc4519::COMMON_NAME)
+            .find_map(|attribute| {
+                let value = &attribute.value;
+                value
+                    .decode_as::<Utf8StringRef>()
+                    .map(|s| s.to_string())
+                    .or_else(|_| {
+                        value
+                            .decode_as::<PrintableStringRef>()
+                            .map(|s| s.to_string())
+                    })
+                    .or_else(|_| value.decode_as::<Ia5StringRef>().map(|s| s.to_string()))
+                    .ok()
+            })
+    }
+}
+
+/// What a client certificate does for the login of a user
+#[derive(Debug, PartialEq, Eq)]
+pub enum CertificateLogin {
+    /// The certificate names the user, who needs no password
+    Accepted,
+    /// --tls-require-client-cert is given and the client has no certificate for the user
+    Refused,
+    /// The user logs in with a password
+    Password,
+}
+
+/// How `user` logs in on `stream`
+pub fn certificate_login(config: &Config, stream: &Stream, user: &str) -> CertificateLogin {
+    if config.tls_client_ca.is_none() {
+        return CertificateLogin::Password;
+    }
+    match stream.client_common_name() {
+        Some(name) if name == user && config.user(user).is_some() => {
+            debug!("Client certificate accepted for user {}", user);
+            CertificateLogin::Accepted
+        }
+        _ if config.tls_require_client_cert => CertificateLogin::Refused,
+        _ => CertificateLogin::Password,
+    }
+}
+
+impl AsyncRead for Stream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Stream::Plain(stream) => Pin::new(stream).poll_read(cx, buf),
+            Stream::Tls(stream) => Pin::new(stream).poll_read(cx, buf),
+        }
+    }
+}
+
+impl AsyncWrite for Stream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        match self.get_mut() {
+            Stream::Plain(stream) => Pin::new(stream).poll_write(cx, buf),
+            Stream::Tls(stream) => Pin::new(stream).poll_write(cx, buf),
+        }
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Stream::Plain(stream) => Pin::new(stream).poll_flush(cx),
+            Stream::Tls(stream) => Pin::new(stream).poll_flush(cx),
+        }
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match self.get_mut() {
+            Stream::Plain(stream) => Pin::new(stream).poll_shutdown(cx),
+            Stream::Tls(stream) => Pin::new(stream).poll_shutdown(cx),
+        }
+    }
+}
@@ -0,0 +1,356 @@
+// Queries over WebSocket, for clients without a SQL driver. A client connects to `/ws`,
+// or `/ws/<database>` for another of the databases served, and sends JSON requests in text
+// frames:
+//
+//   {"query": "SELECT ...", "id": 1, "batch_size": 500}
+//   {"subscribe": "reload"}
+//   {"unsubscribe": "reload"}
+//
+// Each statement of a query is answered with a `columns` frame, its rows in `rows` frames
+// of up to `batch_size` rows, and a `done` frame, so a large result arrives as it is sent
+// rather than in one message. Subscribers to `reload` get a `reload` frame whenever a
+// reload of the database is applied.
+use futures::{SinkExt, StreamExt};
+use serde::Deserialize;
+use serde_json::{Value as JsonValue, json};
+use std::sync::Arc;
+use tokio::net::TcpStream;
+use tokio::sync::broadcast;
+use tokio_tungstenite::WebSocketStream;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tokio_tungstenite::tungstenite::http::{HeaderValue, StatusCode, header};
+use tracing::{debug, info};
+
+use crate::config::Config;
+use crate::database::{Databases, Notification, RELOAD_CHANNEL, Storage};
+use crate::protocol::password::authenticate_basic;
+use crate::protocol::timeouts::LoginSignal;
+use crate::server::rate_limit::ClientRateLimiter;
+use crate::sql::executor::QueryResult;
+use crate::sql::{QueryExecutor, parse_sql, value_to_json};
+use crate::yaml::Grants;
+
+/// The path of the WebSocket endpoint
+const WEBSOCKET_PATH: &str = "/ws";
+/// The rows sent in one frame when the request does not say
+const DEFAULT_BATCH_SIZE: usize = 1000;
+/// The only events there are to subscribe to
+const RELOAD_EVENTS: &str = "reload";
+const UNKNOWN_REQUEST: &str = "Expected {\"query\": \"...\"}, {\"subscribe\": \"reload\"} \
+                               or {\"unsubscribe\": \"reload\"}";
+
+pub struct WebSocketProtocol {
+    config: Arc<Config>,
+    databases: Databases,
+    login: LoginSignal,
+    rate_limiter: ClientRateLimiter,
+}
+
+/// A request in a text frame
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum ClientRequest {
+    Query {
+        query: String,
+        #[serde(default)]
+        id: Option<JsonValue>,
+        #[serde(default)]
+        batch_size: Option<usize>,
+    },
+    Subscribe {
+        subscribe: String,
+    },
+    Unsubscribe {
+        unsubscribe: String,
+    },
+}
+
+impl WebSocketProtocol {
+    pub fn new(config: Arc<Config>, databases: Databases) -> Self {
+        Self {
+            config,
+            databases,
+            login: LoginSignal::default(),
+            rate_limiter: ClientRateLimiter::default(),
+        }
+    }
+
+    /// Count the client's statements against its rate limits
+    pub fn with_rate_limiter(mut self, rate_limiter: ClientRateLimiter) -> Self {
+        self.rate_limiter = rate_limiter;
+        self
+    }
+
+    /// Tell `login` once the client has logged in, for --connect-timeout
+    pub fn with_login_signal(mut self, login: LoginSignal) -> Self {
+        self.login = login;
+        self
+    }
+
+    /// Turn a client away: its upgrade request is answered with 503 Service Unavailable
+    pub async fn refuse_connection(&self, stream: TcpStream) -> crate::Result<()> {
+        #[allow(clippy::result_large_err)]
+        let callback = |_: &Request, _: Response| {
+            let message = "Too many connections".to_string();
+            Err(refuse(StatusCode::SERVICE_UNAVAILABLE, message))
+        };
+        // The handshake fails by design
+        let _ = tokio_tungstenite::accept_hdr_async(stream, callback).await;
+        Ok(())
+    }
+
+    pub async fn handle_connection(&mut self, stream: TcpStream) -> crate::Result<()> {
+        info!("New WebSocket connection");
+
+        let mut session = None;
+        // tungstenite's callback answers a refused handshake with the HTTP response itself
+        #[allow(clippy::result_large_err)]
+        let callback = |request: &Request, response: Response| match self.accept(request) {
+            Ok(accepted) => {
+                session = Some(accepted);
+                Ok(response)
+            }
+            Err((status, message)) => Err(refuse(status, message)),
+        };
+        let mut ws = match tokio_tungstenite::accept_hdr_async(stream, callback).await {
+            Ok(ws) => ws,
+            Err(e) => {
+                debug!("WebSocket handshake failed: {}", e);
+                return Ok(());
+            }
+        };
+        let Some((database, user, grants)) = session else {
+            return Ok(());
+        };
+        self.login.complete();
+
+        let storage = Arc::clone(self.databases.get(database));
+        debug!("WebSocket client using database {}", storage.name());
+        let mut executor = QueryExecutor::new(Arc::clone(&storage)).await?;
+        if let Some(seed) = self.config.random_seed {
+            executor = executor.with_random_seed(seed);
+        }
+        let names = self
+            .databases
+            .iter()
+            .map(|storage| storage.name().to_string())
+            .collect();
+        let executor = executor
+            .with_databases(names)
+            .with_recursion_limits(self.config.recursion_limits())
+            .with_timeout(self.config.statement_timeout())
+            .with_collation(self.config.collation()?)
+            .with_write_mode(self.config.write_mode(&self.config.files[database]))
+            .with_grants(grants)
+            .with_quota(self.rate_limiter.quota(user.as_deref()));
+
+        let mut reloads: Option<broadcast::Receiver<Notification>> = None;
+        loop {
+            tokio::select! {
+                message = ws.next() => match message {
+                    Some(Ok(Message::Text(text))) => {
+                        handle_request(&mut ws, &executor, &storage, &mut reloads, &text).await?;
+                    }
+                    Some(Ok(Message::Binary(_))) => {
+                        let frame = error_frame("Requests are JSON text frames");
+                        send(&mut ws, &None, frame).await?;
+                    }
+                    // Pings are answered by the stream itself
+                    Some(Ok(Message::Ping(_) | Message::Pong(_) | Message::Frame(_))) => {}
+                    Some(Ok(Message::Close(_)) | Err(_)) | None => break,
+                },
+                notification = next_reload(&mut reloads) => match notification {
+                    Ok(notification) => {
+                        let frame = json!({
+                            "type": "reload",
+                            "database": storage.name(),
+                            "changes": notification.payload,
+                        });
+                        send(&mut ws, &None, frame).await?;
+                    }
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        debug!("WebSocket client missed {} reload events", missed);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => reloads = None,
+                },
+            }
+        }
+        info!("WebSocket client disconnected");
+        Ok(())
+    }
+
+    /// The database a handshake asks for, its user and the user's grants, or the HTTP status
+    /// that refuses it
+    #[allow(clippy::type_complexity)]
+    fn accept(
+        &self,
+        request: &Request,
+    ) -> Result<(usize, Option<String>, Option<Grants>), (StatusCode, String)> {
+        let path = request.uri().path();
+        let database = match path.strip_prefix(WEBSOCKET_PATH) {
+            Some("" | "/") => Some(0),
+            Some(name) if name.starts_with('/') => self.databases.find(&name[1..]),
+            _ => None,
+        };
+        let Some(database) = database else {
+            return Err((StatusCode::NOT_FOUND, format!("Not found: {}", path)));
+        };
+
+        let authorization = request
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok());
+        match authenticate_basic(&self.config, authorization) {
+            Some((user, grants)) => Ok((database, user, grants)),
+            None => Err((
+                StatusCode::UNAUTHORIZED,
+                "Authentication required".to_string(),
+            )),
+        }
+    }
+}
+
+/// Answer a request: run a query, or subscribe to reload events or stop
+async fn handle_request(
+    ws: &mut WebSocketStream<TcpStream>,
+    executor: &QueryExecutor,
+    storage: &Storage,
+    reloads: &mut Option<broadcast::Receiver<Notification>>,
+    text: &str,
+) -> crate::Result<()> {
+    match serde_json::from_str::<ClientRequest>(text) {
+        Ok(ClientRequest::Query {
+            query,
+            id,
+            batch_size,
+        }) => {
+            let batch_size = batch_size.unwrap_or(DEFAULT_BATCH_SIZE).max(1);
+            run_query(ws, executor, &query, &id, batch_size).await
+        }
+        Ok(ClientRequest::Subscribe { subscribe }) if subscribe == RELOAD_EVENTS => {
+            reloads.get_or_insert_with(|| storage.subscribe());
+            let frame = json!({"type": "subscribed", "events": RELOAD_EVENTS});
+            send(ws, &None, frame).await
+        }
+        Ok(ClientRequest::Unsubscribe { unsubscribe }) if unsubscribe == RELOAD_EVENTS => {
+            *reloads = None;
+            let frame = json!({"type": "unsubscribed", "events": RELOAD_EVENTS});
+            send(ws, &None, frame).await
+        }
+        Ok(
+            ClientRequest::Subscribe { subscribe: events }
+            | ClientRequest::Unsubscribe {
+                unsubscribe: events,
+            },
+        ) => {
+            let message = format!(
+                "Unknown events '{}'; the only events are '{}'",
+                events, RELOAD_EVENTS
+            );
+            send(ws, &None, error_frame(&message)).await
+        }
+        Err(_) => send(ws, &None, error_frame(UNKNOWN_REQUEST)).await,
+    }
+}
+
+/// Run the statements of a query in order, streaming each result. The first error ends
+/// the query.
+async fn run_query(
+    ws: &mut WebSocketStream<TcpStream>,
+    executor: &QueryExecutor,
+    query: &str,
+    id: &Option<JsonValue>,
+    batch_size: usize,
+) -> crate::Result<()> {
+    let statements = match parse_sql(query) {
+        Ok(statements) => statements,
+        Err(e) => return send(ws, id, error_frame(&e.to_string())).await,
+    };
+    for statement in &statements {
+        debug!("Executing statement: {:?}", statement);
+        match executor.execute(statement).await {
+            Ok(result) => send_result(ws, id, &result, batch_size).await?,
+            Err(e) => return send(ws, id, error_frame(&e.to_string())).await,
+        }
+    }
+    Ok(())
+}
+
+/// The columns of a result, its rows in batches, and how many there were
+async fn send_result(
+    ws: &mut WebSocketStream<TcpStream>,
+    id: &Option<JsonValue>,
+    result: &QueryResult,
+    batch_size: usize,
+) -> crate::Result<()> {
+    if let Some(affected_rows) = result.affected_rows {
+        return send(
+            ws,
+            id,
+            json!({"type": "done", "affected_rows": affected_rows}),
+        )
+        .await;
+    }
+    send(
+        ws,
+        id,
+        json!({"type": "columns", "columns": result.columns}),
+    )
+    .await?;
+    for batch in result.rows.chunks(batch_size) {
+        let rows: Vec<Vec<JsonValue>> = batch
+            .iter()
+            .map(|row| row.iter().map(value_to_json).collect())
+            .collect();
+        send(ws, id, json!({"type": "rows", "rows": rows})).await?;
+    }
+    send(ws, id, json!({"type": "done", "rows": result.rows.len()})).await
+}
+
+/// The next reload event, or never for a connection that is not subscribed
+async fn next_reload(
+    reloads: &mut Option<broadcast::Receiver<Notification>>,
+) -> Result<Notification, broadcast::error::RecvError> {
+    let Some(receiver) = reloads else {
+        return std::future::pending().await;
+    };
+    loop {
+        match receiver.recv().await {
+            Ok(notification) if notification.channel != RELOAD_CHANNEL => continue,
+            other => return other,
+        }
+    }
+}
+
+/// Send a frame, with the id of the request it answers if the request had one
+async fn send(
+    ws: &mut WebSocketStream<TcpStream>,
+    id: &Option<JsonValue>,
+    mut frame: JsonValue,
+) -> crate::Result<()> {
+    if let (Some(id), Some(object)) = (id, frame.as_object_mut()) {
+        object.insert("id".to_string(), id.clone());
+    }
+    ws.send(Message::Text(frame.to_string()))
+        .await
+        .map_err(|e| crate::YamlBaseError::Protocol(format!("WebSocket error: {}", e)))
+}
+
+fn error_frame(message: &str) -> JsonValue {
+    json!({"type": "error", "message": message})
+}
+
+/// The HTTP response to a refused handshake. Clients that were not authenticated are told
+/// to use basic authentication.
+fn refuse(status: StatusCode, message: String) -> ErrorResponse {
+    let mut response = ErrorResponse::new(Some(message));
+    *response.status_mut() = status;
+    if status == StatusCode::UNAUTHORIZED {
+        response.headers_mut().insert(
+            header::WWW_AUTHENTICATE,
+            HeaderValue::from_static("Basic realm=\"yamlbase\""),
+        );
+    }
+    response
+}
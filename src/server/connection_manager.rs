@@ -1,17 +1,20 @@
 use std::collections::HashMap;
+use std::net::SocketAddr;
 use std::sync::{
     Arc,
     atomic::{AtomicUsize, Ordering},
 };
 use std::time::{Duration, Instant};
 use tokio::net::TcpStream;
-use tokio::sync::{RwLock, Semaphore};
-use tokio::time::timeout;
+use tokio::sync::{OwnedSemaphorePermit, RwLock, Semaphore};
 use tracing::{debug, error, info, warn};
 
-use crate::config::Config;
-use crate::database::Storage;
+use crate::config::{Config, Protocol};
+use crate::database::Databases;
 use crate::protocol::Connection;
+use crate::protocol::cancel::Cancellations;
+use crate::protocol::tls::TlsAcceptor;
+use crate::server::rate_limit::RateLimiter;
 
 /// Connection statistics for monitoring
 #[derive(Debug, Clone)]
@@ -34,7 +37,11 @@ struct ConnectionInfo {
 /// Connection manager for handling client connection stability
 pub struct ConnectionManager {
     config: Arc<Config>,
-    storage: Arc<Storage>,
+    databases: Databases,
+    protocol: Protocol,
+    tls: Option<TlsAcceptor>,
+    cancellations: Cancellations,
+    rate_limiter: RateLimiter,
     connections: Arc<RwLock<HashMap<usize, ConnectionInfo>>>,
     connection_counter: AtomicUsize,
     active_connections: AtomicUsize,
@@ -47,7 +54,11 @@ impl Clone for ConnectionManager {
     fn clone(&self) -> Self {
         Self {
             config: self.config.clone(),
-            storage: self.storage.clone(),
+            databases: self.databases.clone(),
+            protocol: self.protocol,
+            tls: self.tls.clone(),
+            cancellations: self.cancellations.clone(),
+            rate_limiter: self.rate_limiter.clone(),
             connections: self.connections.clone(),
             connection_counter: AtomicUsize::new(self.connection_counter.load(Ordering::SeqCst)),
             active_connections: AtomicUsize::new(self.active_connections.load(Ordering::SeqCst)),
@@ -59,12 +70,17 @@ impl Clone for ConnectionManager {
 }
 
 impl ConnectionManager {
-    pub fn new(config: Arc<Config>, storage: Arc<Storage>) -> Self {
-        let max_connections = config.max_connections.unwrap_or(1000);
+    pub fn new(config: Arc<Config>, databases: Databases) -> Self {
+        let max_connections = config.max_connections();
+        let rate_limiter = RateLimiter::new(&config);
 
         Self {
+            protocol: config.protocol,
             config,
-            storage,
+            databases,
+            tls: None,
+            cancellations: Cancellations::new(),
+            rate_limiter,
             connections: Arc::new(RwLock::new(HashMap::new())),
             connection_counter: AtomicUsize::new(0),
             active_connections: AtomicUsize::new(0),
@@ -74,30 +90,47 @@ impl ConnectionManager {
         }
     }
 
-    /// Handle a new client connection with full stability features
+    /// Hand connections to another protocol than `--protocol`, for a listener of its own
+    pub fn with_protocol(mut self, protocol: Protocol) -> Self {
+        self.protocol = protocol;
+        self
+    }
+
+    /// Accept TLS for clients that ask for it
+    pub fn with_tls(mut self, tls: Option<TlsAcceptor>) -> Self {
+        self.tls = tls;
+        self
+    }
+
+    /// A place for one more connection, or `None` while --max-connections are served. The
+    /// limit is shared by the listeners of every protocol.
+    pub fn admit(&self) -> Option<OwnedSemaphorePermit> {
+        self.connection_semaphore.clone().try_acquire_owned().ok()
+    }
+
+    /// Turn away a client that was not admitted, with its protocol's error for it
+    pub async fn refuse_connection(&self, stream: TcpStream, client_addr: String) {
+        warn!(
+            "Refusing connection from {}: {} connections are served already",
+            client_addr,
+            self.config.max_connections()
+        );
+        let connection = Connection::new(self.config.clone(), self.databases.clone())
+            .with_protocol(self.protocol)
+            .with_tls(self.tls.clone());
+        if let Err(e) = connection.refuse(stream).await {
+            debug!("Refusing connection from {} failed: {}", client_addr, e);
+        }
+    }
+
+    /// Handle a new client connection with full stability features, holding its place
+    /// among --max-connections
     pub async fn handle_connection(
         &self,
         mut stream: TcpStream,
         client_addr: String,
+        permit: OwnedSemaphorePermit,
     ) -> crate::Result<()> {
-        // Acquire connection permit (with timeout to prevent hanging)
-        let permit =
-            match timeout(Duration::from_secs(30), self.connection_semaphore.acquire()).await {
-                Ok(Ok(permit)) => permit,
-                Ok(Err(_)) => {
-                    error!("Failed to acquire connection permit");
-                    return Err(crate::YamlBaseError::Database {
-                        message: "Connection pool exhausted".to_string(),
-                    });
-                }
-                Err(_) => {
-                    error!("Timeout acquiring connection permit for {}", client_addr);
-                    return Err(crate::YamlBaseError::Database {
-                        message: "Connection pool timeout".to_string(),
-                    });
-                }
-            };
-
         // Configure TCP socket for stability
         if let Err(e) = self.configure_tcp_socket(&mut stream).await {
             warn!("Failed to configure TCP socket options: {}", e);
@@ -128,7 +161,7 @@ impl ConnectionManager {
 
         // Handle the connection with comprehensive error handling
         let result = self
-            .handle_connection_with_recovery(stream, connection_id, client_addr.clone())
+            .handle_connection_with_recovery(stream, connection_id, &client_addr)
             .await;
 
         // Cleanup connection
@@ -267,36 +300,24 @@ impl ConnectionManager {
         &self,
         stream: TcpStream,
         connection_id: usize,
-        client_addr: String,
+        client_addr: &str,
     ) -> crate::Result<()> {
-        let connection_timeout = self
-            .config
-            .connection_timeout
-            .unwrap_or(Duration::from_secs(30)); // 30 seconds default - more reasonable for SQL queries
-
-        let connection = Connection::new(self.config.clone(), self.storage.clone());
-
-        // Wrap connection handling with timeout
-        let connection_future = async {
-            // Update last activity
-            self.update_connection_activity(connection_id).await;
-
-            // Handle the actual protocol connection
-            connection.handle(stream).await
-        };
-
-        match timeout(connection_timeout, connection_future).await {
-            Ok(result) => result,
-            Err(_) => {
-                warn!(
-                    "Connection {} from {} timed out after {:?}",
-                    connection_id, client_addr, connection_timeout
-                );
-                Err(crate::YamlBaseError::Database {
-                    message: format!("Connection timeout after {:?}", connection_timeout),
-                })
-            }
-        }
+        let client_ip = client_addr
+            .parse::<SocketAddr>()
+            .ok()
+            .map(|address| address.ip());
+        let connection = Connection::new(self.config.clone(), self.databases.clone())
+            .with_protocol(self.protocol)
+            .with_tls(self.tls.clone())
+            .with_cancellations(self.cancellations.clone())
+            .with_rate_limiter(self.rate_limiter.for_client(client_ip));
+
+        // Update last activity
+        self.update_connection_activity(connection_id).await;
+
+        // Handle the actual protocol connection. The protocols apply --connect-timeout and
+        // --idle-timeout themselves, so a long session is not cut off.
+        connection.handle(stream).await
     }
 
     /// Update last activity timestamp for a connection
@@ -339,7 +360,10 @@ impl ConnectionManager {
 
     /// Cleanup idle/stale connections
     pub async fn cleanup_stale_connections(&self) {
-        let idle_timeout = Duration::from_secs(1800); // 30 minutes
+        let idle_timeout = self
+            .config
+            .idle_timeout
+            .unwrap_or(Duration::from_secs(1800)); // 30 minutes
         let now = Instant::now();
         let mut to_remove = Vec::new();
 
@@ -1,12 +1,22 @@
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::net::TcpListener;
-use tracing::{error, info};
+use tokio::sync::mpsc;
+use tokio::task::JoinSet;
+use tracing::{error, info, warn};
 
-use crate::config::Config;
-use crate::database::Storage;
-use crate::yaml::{FileWatcher, parse_yaml_database};
+use crate::config::{Config, Protocol};
+use crate::database::{Databases, Storage};
+use crate::protocol::password::check_hash;
+use crate::protocol::tls::{self, TlsAcceptor};
+use crate::sql::WriteMode;
+use crate::yaml::remote::{is_url, parse_header, set_headers};
+use crate::yaml::validate::new_key_problems;
+use crate::yaml::{FileWatcher, UrlPoller, parse_yaml_database, reload_yaml_files};
 
 mod connection_manager;
+mod proxy_protocol;
+pub mod rate_limit;
 pub use connection_manager::{ConnectionManager, ConnectionStats};
 
 #[cfg(test)]
@@ -14,107 +24,361 @@ mod tests;
 
 pub struct Server {
     config: Arc<Config>,
-    storage: Storage,
+    databases: Databases,
+    tls: Option<TlsAcceptor>,
 }
 
 impl Server {
     pub async fn new(mut config: Config) -> crate::Result<Self> {
-        // Parse initial database
-        let (database, auth_config) = parse_yaml_database(&config.file).await?;
+        // The default time zone applies to TIMESTAMPTZ values in the YAML file, so it has to
+        // be in place before the file is parsed
+        config.set_load_defaults()?;
 
-        // If auth is specified in YAML, override command line args
-        if let Some(auth) = auth_config {
+        let collation = config.collation()?;
+        if collation.is_case_insensitive() {
+            info!("Using default collation {}", collation);
+        }
+
+        let headers = config
+            .url_headers
+            .iter()
+            .map(|header| parse_header(header))
+            .collect::<crate::Result<Vec<_>>>()?;
+        set_headers(headers);
+        if config.persist
+            && let Some(file) = config.files.iter().find(|file| is_url(file))
+        {
+            return Err(crate::YamlBaseError::Config(format!(
+                "--persist cannot write to {}, which is a URL",
+                file.display()
+            )));
+        }
+
+        match config.write_mode(&config.files[0]) {
+            WriteMode::Session => {}
+            WriteMode::Shared => info!("Writes are shared between connections"),
+            WriteMode::Persistent(_) => {
+                info!("Writes are shared and saved to the database files")
+            }
+        }
+
+        // Parse initial databases, one per file
+        let mut storages: Vec<Arc<Storage>> = Vec::new();
+        let mut yaml_auth = None;
+        for file in &config.files {
+            let (database, auth_config) = parse_yaml_database(file).await?;
+            if storages
+                .iter()
+                .any(|storage| storage.name().eq_ignore_ascii_case(&database.name))
+            {
+                return Err(crate::YamlBaseError::Config(format!(
+                    "More than one database is named '{}'",
+                    database.name
+                )));
+            }
+            info!(
+                "Serving database '{}' from {}",
+                database.name,
+                file.display()
+            );
+            yaml_auth = yaml_auth.or(auth_config);
+            storages.push(Arc::new(Storage::new(database)));
+        }
+
+        // If auth is specified in YAML, override command line args. With several files
+        // the first one that has an auth section applies.
+        if let Some(auth) = yaml_auth {
             info!(
                 "Using authentication from YAML file: username={}",
                 auth.username
             );
+            let user_hashes = auth
+                .users
+                .iter()
+                .filter_map(|user| user.password_hash.as_ref());
+            for hash in auth.password_hash.iter().chain(user_hashes) {
+                check_hash(hash)?;
+            }
             config.username = auth.username;
             config.password = auth.password;
+            config.password_hash = auth.password_hash;
+            if !auth.users.is_empty() {
+                let names: Vec<&str> = auth
+                    .users
+                    .iter()
+                    .map(|user| user.username.as_str())
+                    .collect();
+                info!("More users from YAML file: {}", names.join(", "));
+            }
+            config.users = auth.users;
         } else {
             info!("Using default authentication: username={}", config.username);
         }
 
+        let listeners = config.listeners();
+        for (i, (_, port)) in listeners.iter().enumerate() {
+            if listeners[..i].iter().any(|(_, other)| other == port) {
+                return Err(crate::YamlBaseError::Config(format!(
+                    "More than one protocol is given port {}",
+                    port
+                )));
+            }
+        }
+        if config.tls_require_client_cert && config.tls_client_ca.is_none() {
+            return Err(crate::YamlBaseError::Config(
+                "--tls-require-client-cert needs --tls-client-ca".to_string(),
+            ));
+        }
+        if config.tls_require_client_cert
+            && let Some((protocol, _)) = listeners
+                .iter()
+                .find(|(protocol, _)| !matches!(protocol, Protocol::Postgres | Protocol::Mysql))
+        {
+            return Err(crate::YamlBaseError::Config(format!(
+                "--tls-require-client-cert needs TLS, which the {} protocol does not have",
+                format!("{:?}", protocol).to_lowercase()
+            )));
+        }
+        let tls = if listeners
+            .iter()
+            .any(|(protocol, _)| matches!(protocol, Protocol::Postgres | Protocol::Mysql))
+        {
+            Some(tls::acceptor(&config)?)
+        } else {
+            None
+        };
+
         let config = Arc::new(config);
-        let storage = Storage::new(database);
+        let databases = Databases::new(storages);
 
-        Ok(Self { config, storage })
+        Ok(Self {
+            config,
+            databases,
+            tls,
+        })
     }
 
     pub async fn run(self) -> crate::Result<()> {
-        let addr = format!(
-            "{}:{}",
-            self.config.bind_address,
-            self.config.effective_port()
-        );
-        info!("Starting YamlBase server on {}", addr);
+        info!("Starting YamlBase server on {}", self.config.bind_address);
 
-        // Set up hot reload if enabled
-        if self.config.hot_reload {
-            self.setup_hot_reload()?;
+        for (storage, file) in self.databases.iter().zip(&self.config.files) {
+            self.setup_reload(storage, file)?;
         }
+        #[cfg(unix)]
+        self.reload_on_hangup()?;
 
         // Create connection manager for stable connection handling
         let connection_manager =
-            ConnectionManager::new(self.config.clone(), Arc::new(self.storage.clone()));
+            ConnectionManager::new(self.config.clone(), self.databases.clone())
+                .with_tls(self.tls.clone());
 
         // Start background monitoring for connection stability
         let _monitoring_handle = connection_manager.start_monitoring();
 
-        // Start listening
-        let listener = TcpListener::bind(&addr).await?;
-        info!(
-            "Server listening on {} with connection stability features",
-            addr
-        );
+        // Start listening, with a listener for each protocol served. All of them serve the
+        // same databases, so a change made over one protocol is seen over the others.
+        let mut listeners = JoinSet::new();
+        for (protocol, port) in self.config.listeners() {
+            let addr = format!("{}:{}", self.config.bind_address, port);
+            let listener = TcpListener::bind(&addr).await?;
+            info!(
+                "Server listening for {:?} on {} with connection stability features",
+                protocol, addr
+            );
+            let manager = connection_manager.clone().with_protocol(protocol);
+            listeners.spawn(accept_connections(
+                listener,
+                manager,
+                self.config.proxy_protocol,
+            ));
+        }
 
-        // Accept connections with enhanced stability handling
-        loop {
-            let (stream, client_addr) = listener.accept().await?;
-            let client_addr_str = client_addr.to_string();
-            info!("New connection from {}", client_addr_str);
+        // The listeners only stop when accepting a connection fails
+        match listeners.join_next().await {
+            Some(Ok(result)) => result,
+            Some(Err(e)) => Err(crate::YamlBaseError::Io(e.into())),
+            None => Ok(()),
+        }
+    }
+
+    /// `kill -HUP` reloads every database, for files on mounts where changes are not noticed
+    #[cfg(unix)]
+    fn reload_on_hangup(&self) -> crate::Result<()> {
+        use tokio::signal::unix::{SignalKind, signal};
 
-            let manager = connection_manager.clone();
-            tokio::spawn(async move {
-                if let Err(e) = manager
-                    .handle_connection(stream, client_addr_str.clone())
-                    .await
-                {
-                    error!("Connection error from {}: {}", client_addr_str, e);
+        let mut hangups = signal(SignalKind::hangup())?;
+        let databases = self.databases.clone();
+        tokio::spawn(async move {
+            while hangups.recv().await.is_some() {
+                info!("Received SIGHUP, reloading");
+                for storage in databases.iter() {
+                    if let Err(e) = storage.request_reload().await {
+                        error!("{}", e);
+                    }
                 }
-            });
-        }
+            }
+        });
+        Ok(())
     }
 
-    fn setup_hot_reload(&self) -> crate::Result<()> {
-        let (watcher, mut rx) = FileWatcher::new(self.config.file.clone());
-        watcher
-            .start()
-            .map_err(|e| crate::YamlBaseError::Io(std::io::Error::other(e)))?;
+    /// Reload a database when `RELOAD DATABASE` or SIGHUP asks for it, and with
+    /// `--hot-reload` when its files change
+    fn setup_reload(&self, storage: &Arc<Storage>, file: &Path) -> crate::Result<()> {
+        let Some(mut requests) = storage.reload_requests() else {
+            return Ok(());
+        };
+        let directory = file.is_dir();
+        let mut changes = if self.config.hot_reload {
+            self.watch(storage, file, directory)?
+        } else {
+            // Without a sender the channel is closed, so it never yields a change
+            mpsc::channel(1).1
+        };
 
-        let storage = self.storage.clone();
-        let config = self.config.clone();
+        let storage = storage.clone();
+        let file = file.to_path_buf();
 
         tokio::spawn(async move {
-            while let Some(()) = rx.recv().await {
-                info!("Reloading database from file");
-                match parse_yaml_database(&config.file).await {
-                    Ok((new_db, _auth)) => {
-                        // Note: We don't update auth on hot reload for security reasons
-                        // Auth changes require a server restart
-                        let db_arc = storage.database();
-                        let mut db = db_arc.write().await;
-                        *db = new_db;
-                        drop(db);
-                        storage.rebuild_indexes().await;
-                        info!("Database reloaded successfully");
+            loop {
+                tokio::select! {
+                    Some(changed) = changes.recv() => {
+                        reload_database(&storage, &file, directory.then_some(&changed)).await;
                     }
-                    Err(e) => {
-                        error!("Failed to reload database: {}", e);
+                    Some(reply) = requests.recv() => {
+                        info!("Reload of database '{}' requested", storage.name());
+                        reload_database(&storage, &file, None).await;
+                        let _ = reply.send(storage.reload_status());
                     }
+                    else => break,
                 }
             }
         });
 
         Ok(())
     }
+
+    /// The changes to the files of a database. A directory is watched as a whole. Otherwise
+    /// included files are watched as well, and so are CSV data files; files first named by
+    /// a reload are not.
+    fn watch(
+        &self,
+        storage: &Storage,
+        file: &Path,
+        directory: bool,
+    ) -> crate::Result<mpsc::Receiver<Vec<PathBuf>>> {
+        let mut paths = vec![file.to_path_buf()];
+        let db_arc = storage.database();
+        if let Ok(db) = db_arc.try_read() {
+            let table_files = db.table_files.values().filter(|_| !directory);
+            for file in table_files.chain(db.data_files.values()) {
+                if !paths.contains(file) {
+                    paths.push(file.clone());
+                }
+            }
+        }
+        // A database served over HTTP(S) is fetched again at an interval instead
+        if is_url(file) {
+            let (poller, rx) = UrlPoller::new(paths, self.config.reload_interval());
+            poller.start();
+            Ok(rx)
+        } else {
+            let (watcher, rx) = FileWatcher::new(paths);
+            watcher
+                .start()
+                .map_err(|e| crate::YamlBaseError::Io(std::io::Error::other(e)))?;
+            Ok(rx)
+        }
+    }
+}
+
+/// Load a database again, only the `changed` files of a directory when they are known, and
+/// swap it in if it checks out as `yamlbase validate` checks a file. A broken edit leaves
+/// the previous snapshot.
+async fn reload_database(storage: &Storage, file: &Path, changed: Option<&Vec<PathBuf>>) {
+    let reloaded = match changed {
+        Some(changed) => {
+            info!("Reloading {} changed file(s)", changed.len());
+            let current = storage.database().read().await.clone();
+            reload_yaml_files(&current, file, changed).await
+        }
+        None => {
+            info!("Reloading database from {}", file.display());
+            parse_yaml_database(file)
+                .await
+                .map(|(new_db, _auth)| new_db)
+        }
+    };
+    let staged = match reloaded {
+        Ok(new_db) => {
+            let db_arc = storage.database();
+            let problems = new_key_problems(&*db_arc.read().await, &new_db);
+            if problems.is_empty() {
+                Ok(new_db)
+            } else {
+                Err(problems)
+            }
+        }
+        Err(e) => Err(vec![e.to_string()]),
+    };
+    match staged {
+        Ok(new_db) => {
+            // Note: We don't update auth on hot reload for security reasons
+            // Auth changes require a server restart
+            let changes = storage.reload(new_db).await;
+            info!("Database reloaded successfully, {}", changes);
+        }
+        Err(problems) => {
+            error!(
+                "Reload rejected, keeping previous snapshot: {} problem{}",
+                problems.len(),
+                if problems.len() == 1 { "" } else { "s" }
+            );
+            for problem in &problems {
+                error!("  {}", problem);
+            }
+            storage.reject_reload(&problems);
+        }
+    }
+}
+
+/// Accept connections with enhanced stability handling. Behind a load balancer speaking
+/// the PROXY protocol, the client is the one its header names.
+async fn accept_connections(
+    listener: TcpListener,
+    connection_manager: ConnectionManager,
+    proxy_protocol: bool,
+) -> crate::Result<()> {
+    loop {
+        let (mut stream, peer_addr) = listener.accept().await?;
+
+        let manager = connection_manager.clone();
+        // Connections past --max-connections are only kept to be told so
+        let permit = manager.admit();
+        tokio::spawn(async move {
+            let client_addr = if proxy_protocol {
+                match proxy_protocol::read_header(&mut stream, peer_addr).await {
+                    Ok(client_addr) => client_addr,
+                    Err(e) => {
+                        warn!("Refusing connection from {}: {}", peer_addr, e);
+                        return;
+                    }
+                }
+            } else {
+                peer_addr
+            };
+            let client_addr_str = client_addr.to_string();
+            info!("New connection from {}", client_addr_str);
+
+            let Some(permit) = permit else {
+                manager.refuse_connection(stream, client_addr_str).await;
+                return;
+            };
+            if let Err(e) = manager
+                .handle_connection(stream, client_addr_str.clone(), permit)
+                .await
+            {
+                error!("Connection error from {}: {}", client_addr_str, e);
+            }
+        });
+    }
 }
@@ -0,0 +1,145 @@
+// HAProxy's PROXY protocol, version 2. A load balancer in front of yamlbase starts each
+// connection with a binary header giving the address of the client it accepted, so that
+// the client rather than the load balancer is the one logged and limited. With
+// `--proxy-protocol` every connection has to start with such a header.
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use crate::YamlBaseError;
+
+const SIGNATURE: &[u8; 12] = b"\r\n\r\n\0\r\nQUIT\n";
+/// How long a connection may take to send its header
+const HEADER_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Commands, in the low bits of the version byte
+const LOCAL: u8 = 0x0;
+const PROXY: u8 = 0x1;
+
+// Address families, in the high bits of the family byte
+const INET: u8 = 0x1;
+const INET6: u8 = 0x2;
+
+/// Read the header a connection starts with, returning the address of the client it gives.
+/// The load balancer's own health checks (`LOCAL`) and connections of other address
+/// families keep `peer`, the address the connection comes from.
+pub async fn read_header<S: AsyncRead + Unpin>(
+    stream: &mut S,
+    peer: SocketAddr,
+) -> crate::Result<SocketAddr> {
+    tokio::time::timeout(HEADER_TIMEOUT, read(stream, peer))
+        .await
+        .map_err(|_| YamlBaseError::Protocol("No PROXY protocol header was sent".to_string()))?
+}
+
+async fn read<S: AsyncRead + Unpin>(stream: &mut S, peer: SocketAddr) -> crate::Result<SocketAddr> {
+    let mut header = [0u8; 16];
+    stream.read_exact(&mut header).await?;
+    if &header[..12] != SIGNATURE {
+        return Err(YamlBaseError::Protocol(
+            "Connection does not start with a PROXY protocol v2 header".to_string(),
+        ));
+    }
+    let version = header[12] >> 4;
+    if version != 2 {
+        return Err(YamlBaseError::Protocol(format!(
+            "Unsupported PROXY protocol version {}",
+            version
+        )));
+    }
+    let length = u16::from_be_bytes([header[14], header[15]]) as usize;
+    let mut addresses = vec![0u8; length];
+    stream.read_exact(&mut addresses).await?;
+
+    match header[12] & 0x0f {
+        LOCAL => Ok(peer),
+        PROXY => Ok(client_address(header[13] >> 4, &addresses).unwrap_or(peer)),
+        command => Err(YamlBaseError::Protocol(format!(
+            "Unknown PROXY protocol command {}",
+            command
+        ))),
+    }
+}
+
+/// The source address of the address block, which is followed by the destination's
+fn client_address(family: u8, addresses: &[u8]) -> Option<SocketAddr> {
+    let (ip, port) = match family {
+        INET if addresses.len() >= 12 => {
+            let ip: [u8; 4] = addresses[..4].try_into().ok()?;
+            (IpAddr::V4(Ipv4Addr::from(ip)), &addresses[8..10])
+        }
+        INET6 if addresses.len() >= 36 => {
+            let ip: [u8; 16] = addresses[..16].try_into().ok()?;
+            (IpAddr::V6(Ipv6Addr::from(ip)), &addresses[32..34])
+        }
+        _ => return None,
+    };
+    Some(SocketAddr::new(ip, u16::from_be_bytes([port[0], port[1]])))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(command: u8, family: u8, addresses: &[u8]) -> Vec<u8> {
+        let mut header = SIGNATURE.to_vec();
+        header.push(0x20 | command);
+        header.push(family);
+        header.extend((addresses.len() as u16).to_be_bytes());
+        header.extend(addresses);
+        header
+    }
+
+    fn peer() -> SocketAddr {
+        "10.0.0.1:40000".parse().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_client_address() {
+        let tcp4 = [203, 0, 113, 7, 10, 0, 0, 2, 0xc8, 0x52, 0x15, 0x38];
+        let bytes = header(PROXY, 0x11, &tcp4);
+        let address = read_header(&mut bytes.as_slice(), peer()).await.unwrap();
+        assert_eq!(address, "203.0.113.7:51282".parse().unwrap());
+
+        let mut tcp6 = vec![0x20, 0x01, 0x0d, 0xb8];
+        tcp6.extend([0; 11]);
+        tcp6.push(1);
+        tcp6.extend([0; 16]);
+        tcp6.extend([0x04, 0xd2, 0x15, 0x38]);
+        let bytes = header(PROXY, 0x21, &tcp6);
+        let address = read_header(&mut bytes.as_slice(), peer()).await.unwrap();
+        assert_eq!(address, "[2001:db8::1]:1234".parse().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_connection_address_is_kept() {
+        let bytes = header(LOCAL, 0x00, &[]);
+        assert_eq!(
+            read_header(&mut bytes.as_slice(), peer()).await.unwrap(),
+            peer()
+        );
+        // A Unix socket address
+        let bytes = header(PROXY, 0x31, &[0; 216]);
+        assert_eq!(
+            read_header(&mut bytes.as_slice(), peer()).await.unwrap(),
+            peer()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_invalid_headers() {
+        let startup = b"\0\0\0\x08\x04\xd2\x16\x2f and more";
+        assert!(read_header(&mut startup.as_slice(), peer()).await.is_err());
+
+        let mut bytes = header(PROXY, 0x11, &[0; 12]);
+        bytes[12] = 0x11;
+        assert!(read_header(&mut bytes.as_slice(), peer()).await.is_err());
+
+        let bytes = header(0x2, 0x11, &[0; 12]);
+        assert!(read_header(&mut bytes.as_slice(), peer()).await.is_err());
+
+        // The addresses are cut short
+        let bytes = header(PROXY, 0x11, &[0; 12]);
+        assert!(read_header(&mut &bytes[..20], peer()).await.is_err());
+    }
+}
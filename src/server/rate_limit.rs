@@ -0,0 +1,302 @@
+// Per-client rate limits, so that load tests can check how applications back off. Each
+// client, by address or by the user it logs in as, has two token buckets: one refilled with
+// --rate-limit-qps statements a second and one with --rate-limit-rows result rows a minute.
+// A statement takes a token from the first. The rows of its result are taken from the second
+// afterwards, so a client that used up its rows is refused from its next statement until the
+// bucket has refilled. A client whose buckets are full again is forgotten, as a new quota
+// would be the same.
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::YamlBaseError;
+use crate::config::{Config, RateLimitKey};
+
+/// The quotas of every client, shared by the connections of all listeners
+#[derive(Clone, Default)]
+pub struct RateLimiter {
+    queries_per_second: Option<u32>,
+    rows_per_minute: Option<u64>,
+    key: RateLimitKey,
+    clients: Arc<Mutex<Clients>>,
+}
+
+/// How often the quotas of idle clients are looked for
+const SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Default)]
+struct Clients {
+    quotas: HashMap<String, Arc<Quota>>,
+    swept: Option<Instant>,
+}
+
+impl Clients {
+    /// Forget the clients that no connection holds on to and whose buckets have refilled
+    fn evict_idle(&mut self) {
+        self.quotas
+            .retain(|_, quota| Arc::strong_count(quota) > 1 || !quota.is_idle());
+        self.swept = Some(Instant::now());
+    }
+}
+
+impl RateLimiter {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            queries_per_second: config.rate_limit_qps,
+            rows_per_minute: config.rate_limit_rows,
+            key: config.rate_limit_by,
+            clients: Arc::default(),
+        }
+    }
+
+    /// The limiter of a client connecting from `address`
+    pub fn for_client(&self, address: Option<IpAddr>) -> ClientRateLimiter {
+        ClientRateLimiter {
+            limiter: self.clone(),
+            address,
+        }
+    }
+
+    fn quota(&self, key: String) -> Option<Arc<Quota>> {
+        if self.queries_per_second.is_none() && self.rows_per_minute.is_none() {
+            return None;
+        }
+        let mut clients = self.clients.lock().unwrap_or_else(|e| e.into_inner());
+        if clients
+            .swept
+            .is_none_or(|swept| swept.elapsed() >= SWEEP_INTERVAL)
+        {
+            clients.evict_idle();
+        }
+        let quota = clients.quotas.entry(key).or_insert_with(|| {
+            Arc::new(Quota {
+                queries: self
+                    .queries_per_second
+                    .map(|rate| TokenBucket::new(rate as f64, Duration::from_secs(1))),
+                rows: self
+                    .rows_per_minute
+                    .map(|rate| TokenBucket::new(rate as f64, Duration::from_secs(60))),
+            })
+        });
+        Some(Arc::clone(quota))
+    }
+}
+
+/// The rate limiter as one connection sees it
+#[derive(Clone, Default)]
+pub struct ClientRateLimiter {
+    limiter: RateLimiter,
+    address: Option<IpAddr>,
+}
+
+impl ClientRateLimiter {
+    /// The quota the statements of `user` on this connection count against, if any limit
+    /// is set
+    pub fn quota(&self, user: Option<&str>) -> Option<Arc<Quota>> {
+        let key = match self.limiter.key {
+            RateLimitKey::Ip => self.address.map(|address| address.to_string()),
+            RateLimitKey::User => user.map(|user| format!("user {}", user)),
+        };
+        // Anonymous clients share a quota
+        self.limiter.quota(key.unwrap_or_default())
+    }
+}
+
+/// The buckets of one client
+pub struct Quota {
+    queries: Option<Mutex<TokenBucket>>,
+    rows: Option<Mutex<TokenBucket>>,
+}
+
+impl Quota {
+    /// Take the token of a statement, failing if the client has run out of statements or
+    /// rows
+    pub fn start_statement(&self) -> crate::Result<()> {
+        if let Some(rows) = &self.rows
+            && !lock(rows).available()
+        {
+            return Err(YamlBaseError::RateLimited(format!(
+                "more than {} rows a minute",
+                lock(rows).rate
+            )));
+        }
+        if let Some(queries) = &self.queries
+            && !lock(queries).take(1.0)
+        {
+            return Err(YamlBaseError::RateLimited(format!(
+                "more than {} statements a second",
+                lock(queries).rate
+            )));
+        }
+        Ok(())
+    }
+
+    /// Whether every bucket is full, as nothing has been taken since it refilled
+    fn is_idle(&self) -> bool {
+        [&self.queries, &self.rows]
+            .into_iter()
+            .flatten()
+            .all(|bucket| lock(bucket).is_full())
+    }
+
+    /// Count the rows of a statement's result
+    pub fn add_rows(&self, count: usize) {
+        if let Some(rows) = &self.rows {
+            lock(rows).spend(count as f64);
+        }
+    }
+}
+
+fn lock(bucket: &Mutex<TokenBucket>) -> std::sync::MutexGuard<'_, TokenBucket> {
+    bucket.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Holds up to `rate` tokens, and gains `rate` of them every `period`
+struct TokenBucket {
+    rate: f64,
+    period: Duration,
+    tokens: f64,
+    refilled: Instant,
+}
+
+impl TokenBucket {
+    fn new(rate: f64, period: Duration) -> Mutex<Self> {
+        Mutex::new(Self {
+            rate,
+            period,
+            tokens: rate,
+            refilled: Instant::now(),
+        })
+    }
+
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.refilled).as_secs_f64();
+        self.tokens =
+            (self.tokens + elapsed / self.period.as_secs_f64() * self.rate).min(self.rate);
+        self.refilled = now;
+    }
+
+    fn available(&mut self) -> bool {
+        self.refill();
+        self.tokens > 0.0
+    }
+
+    fn is_full(&mut self) -> bool {
+        self.refill();
+        self.tokens >= self.rate
+    }
+
+    fn take(&mut self, tokens: f64) -> bool {
+        self.refill();
+        if self.tokens < tokens {
+            return false;
+        }
+        self.tokens -= tokens;
+        true
+    }
+
+    /// Take tokens that were used already, going into debt if there are not enough
+    fn spend(&mut self, tokens: f64) {
+        self.refill();
+        self.tokens -= tokens;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[test]
+    fn test_token_bucket() {
+        let bucket = TokenBucket::new(2.0, Duration::from_secs(3600));
+        let mut bucket = bucket.lock().unwrap();
+        assert!(bucket.take(1.0));
+        assert!(bucket.take(1.0));
+        assert!(!bucket.take(1.0));
+
+        let bucket = TokenBucket::new(10.0, Duration::from_millis(100));
+        let mut bucket = bucket.lock().unwrap();
+        bucket.spend(25.0);
+        assert!(!bucket.available());
+        std::thread::sleep(Duration::from_millis(300));
+        assert!(bucket.available());
+        // Never more than a period's worth
+        assert!(bucket.tokens <= 10.0);
+    }
+
+    #[test]
+    fn test_quotas_by_key() {
+        let mut config = Config::try_parse_from(["yamlbase", "-f", "db.yaml"]).unwrap();
+        config.rate_limit_qps = Some(1);
+        let limiter = RateLimiter::new(&config);
+        let first = limiter.for_client(Some("10.0.0.1".parse().unwrap()));
+        let second = limiter.for_client(Some("10.0.0.2".parse().unwrap()));
+        assert!(first.quota(Some("app")).unwrap().start_statement().is_ok());
+        assert!(first.quota(Some("app")).unwrap().start_statement().is_err());
+        assert!(second.quota(Some("app")).unwrap().start_statement().is_ok());
+
+        config.rate_limit_by = RateLimitKey::User;
+        let limiter = RateLimiter::new(&config);
+        let first = limiter.for_client(Some("10.0.0.1".parse().unwrap()));
+        let second = limiter.for_client(Some("10.0.0.2".parse().unwrap()));
+        assert!(first.quota(Some("app")).unwrap().start_statement().is_ok());
+        assert!(
+            second
+                .quota(Some("app"))
+                .unwrap()
+                .start_statement()
+                .is_err()
+        );
+        assert!(
+            second
+                .quota(Some("report"))
+                .unwrap()
+                .start_statement()
+                .is_ok()
+        );
+
+        config.rate_limit_qps = None;
+        assert!(
+            RateLimiter::new(&config)
+                .for_client(None)
+                .quota(None)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_evict_idle_clients() {
+        let mut config = Config::try_parse_from(["yamlbase", "-f", "db.yaml"]).unwrap();
+        config.rate_limit_qps = Some(1);
+        let limiter = RateLimiter::new(&config);
+        let client = |address: &str| limiter.for_client(Some(address.parse().unwrap()));
+        let held = client("10.0.0.1").quota(None).unwrap();
+        assert!(held.start_statement().is_ok());
+        assert!(
+            client("10.0.0.2")
+                .quota(None)
+                .unwrap()
+                .start_statement()
+                .is_ok()
+        );
+        client("10.0.0.3").quota(None).unwrap();
+
+        let evict = || {
+            let mut clients = limiter.clients.lock().unwrap();
+            clients.evict_idle();
+            let mut keys: Vec<_> = clients.quotas.keys().cloned().collect();
+            keys.sort();
+            keys
+        };
+        // The client that made no statement is idle already
+        assert_eq!(evict(), ["10.0.0.1", "10.0.0.2"]);
+        std::thread::sleep(Duration::from_millis(1100));
+        // A quota a connection still holds is kept even once it has refilled
+        assert_eq!(evict(), ["10.0.0.1"]);
+        drop(held);
+        assert!(evict().is_empty());
+    }
+}
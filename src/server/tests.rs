@@ -24,21 +24,46 @@ tables:
     temp_file.flush().unwrap();
 
     let config = Config {
-        file: temp_file.path().to_path_buf(),
+        command: None,
+        files: vec![temp_file.path().to_path_buf()],
+        config: None,
         port: None,
         bind_address: "127.0.0.1".to_string(),
         protocol: Protocol::Postgres,
+        listener_ports: Default::default(),
         username: "cli_user".to_string(), // These should be overridden
         password: "cli_pass".to_string(), // by YAML auth
+        auth_method: None,
         hot_reload: false,
+        reload_interval: None,
+        url_headers: Vec::new(),
         verbose: false,
         log_level: "error".to_string(),
         database: None,
         allow_anonymous: false,
+        random_seed: None,
+        timezone: None,
+        cte_max_recursion_depth: None,
+        cte_max_rows: None,
+        collation: None,
+        strict_types: false,
+        mutable: false,
+        persist: false,
+        tls_cert: None,
+        tls_key: None,
+        tls_client_ca: None,
+        tls_require_client_cert: false,
+        proxy_protocol: false,
         max_connections: None,
-        connection_timeout: None,
+        connect_timeout: None,
         idle_timeout: None,
+        statement_timeout: None,
+        rate_limit_qps: None,
+        rate_limit_rows: None,
+        rate_limit_by: Default::default(),
         enable_keepalive: false,
+        users: Vec::new(),
+        password_hash: None,
     };
 
     let server = Server::new(config).await.unwrap();
@@ -65,21 +90,46 @@ tables:
     temp_file.flush().unwrap();
 
     let config = Config {
-        file: temp_file.path().to_path_buf(),
+        command: None,
+        files: vec![temp_file.path().to_path_buf()],
+        config: None,
         port: None,
         bind_address: "127.0.0.1".to_string(),
         protocol: Protocol::Postgres,
+        listener_ports: Default::default(),
         username: "cli_user".to_string(),
         password: "cli_pass".to_string(),
+        auth_method: None,
         hot_reload: false,
+        reload_interval: None,
+        url_headers: Vec::new(),
         verbose: false,
         log_level: "error".to_string(),
         database: None,
         allow_anonymous: false,
+        random_seed: None,
+        timezone: None,
+        cte_max_recursion_depth: None,
+        cte_max_rows: None,
+        collation: None,
+        strict_types: false,
+        mutable: false,
+        persist: false,
+        tls_cert: None,
+        tls_key: None,
+        tls_client_ca: None,
+        tls_require_client_cert: false,
+        proxy_protocol: false,
         max_connections: None,
-        connection_timeout: None,
+        connect_timeout: None,
         idle_timeout: None,
+        statement_timeout: None,
+        rate_limit_qps: None,
+        rate_limit_rows: None,
+        rate_limit_by: Default::default(),
         enable_keepalive: false,
+        users: Vec::new(),
+        password_hash: None,
     };
 
     let server = Server::new(config).await.unwrap();
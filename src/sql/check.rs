@@ -0,0 +1,84 @@
+// CHECK constraints, declared in YAML or with CREATE TABLE. A row breaks a constraint when
+// its expression is false; true and NULL both pass, as in SQL. Rows are checked when a
+// file is loaded and whenever a statement inserts or changes them.
+use sqlparser::dialect::GenericDialect;
+use sqlparser::parser::Parser;
+use std::sync::Arc;
+
+use crate::YamlBaseError;
+use crate::database::{CheckConstraint, Database, Storage, Table, Value};
+use crate::sql::executor::QueryExecutor;
+
+/// A CHECK constraint from the text of its expression
+pub fn parse_check(sql: &str) -> crate::Result<CheckConstraint> {
+    let expr = Parser::new(&GenericDialect {})
+        .try_with_sql(sql)
+        .and_then(|mut parser| parser.parse_expr())
+        .map_err(|e| YamlBaseError::Config(format!("Invalid CHECK '{}': {}", sql, e)))?;
+    Ok(CheckConstraint {
+        sql: sql.to_string(),
+        expr,
+    })
+}
+
+/// Check the rows a table was loaded with against its CHECK constraints
+pub async fn check_loaded_rows(table: &Table) -> crate::Result<()> {
+    if table.checks.is_empty() {
+        return Ok(());
+    }
+    let storage = Arc::new(Storage::new(Database::new(String::new())));
+    let executor = QueryExecutor::new(storage).await?;
+    for (index, row) in table.rows.iter().enumerate() {
+        if let Some(check) = executor.violated_check(table, row)? {
+            return Err(YamlBaseError::Config(format!(
+                "Row {} of table '{}' violates CHECK ({})",
+                index + 1,
+                table.name,
+                check.sql
+            )));
+        }
+    }
+    Ok(())
+}
+
+impl QueryExecutor {
+    /// Check new or changed rows against the table's CHECK constraints
+    pub(super) fn check_constraints(
+        &self,
+        table: &Table,
+        rows: &[Vec<Value>],
+    ) -> crate::Result<()> {
+        for row in rows {
+            if let Some(check) = self.violated_check(table, row)? {
+                return Err(YamlBaseError::CheckViolation {
+                    table: table.name.clone(),
+                    check: check.sql.clone(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// The first CHECK constraint of the table the row makes false
+    fn violated_check<'a>(
+        &self,
+        table: &'a Table,
+        row: &[Value],
+    ) -> crate::Result<Option<&'a CheckConstraint>> {
+        for check in &table.checks {
+            match self.get_expr_value(&check.expr, row, table)? {
+                Value::Boolean(true) | Value::Null => {}
+                Value::Boolean(false) => return Ok(Some(check)),
+                other => {
+                    return Err(YamlBaseError::Database {
+                        message: format!(
+                            "CHECK ({}) of table '{}' gives {} instead of a boolean",
+                            check.sql, table.name, other
+                        ),
+                    });
+                }
+            }
+        }
+        Ok(None)
+    }
+}
@@ -0,0 +1,74 @@
+// Computed columns, declared under `computed:` in YAML. Their values are evaluated from the
+// other columns of the row when a file is loaded and whenever a statement inserts or
+// changes the row, so they cannot be written directly.
+use sqlparser::dialect::GenericDialect;
+use sqlparser::parser::Parser;
+use std::sync::Arc;
+
+use crate::YamlBaseError;
+use crate::database::{ComputedColumn, Database, Storage, Table, Value};
+use crate::sql::executor::QueryExecutor;
+
+/// A computed column of a table from the text of its expression
+pub fn parse_computed(table: &Table, name: &str, sql: &str) -> crate::Result<ComputedColumn> {
+    let column = *table.column_index.get(name).ok_or_else(|| {
+        YamlBaseError::Config(format!(
+            "Computed column '{}' of table '{}' is not one of its columns",
+            name, table.name
+        ))
+    })?;
+    let expr = Parser::new(&GenericDialect {})
+        .try_with_sql(sql)
+        .and_then(|mut parser| parser.parse_expr())
+        .map_err(|e| {
+            YamlBaseError::Config(format!(
+                "Invalid expression '{}' of computed column '{}': {}",
+                sql, name, e
+            ))
+        })?;
+    Ok(ComputedColumn {
+        column,
+        sql: sql.to_string(),
+        expr,
+    })
+}
+
+/// Fill in the computed columns of the rows a table was loaded with
+pub async fn compute_loaded_rows(table: &mut Table) -> crate::Result<()> {
+    if table.computed.is_empty() {
+        return Ok(());
+    }
+    let storage = Arc::new(Storage::new(Database::new(String::new())));
+    let executor = QueryExecutor::new(storage).await?;
+    let mut rows = std::mem::take(&mut table.rows);
+    let computed = executor.compute_columns(table, &mut rows);
+    table.rows = rows;
+    computed.map_err(|e| YamlBaseError::Config(format!("Table '{}': {}", table.name, e)))
+}
+
+impl QueryExecutor {
+    /// Set the computed columns of new or changed rows, each from the columns before it
+    pub(super) fn compute_columns(
+        &self,
+        table: &Table,
+        rows: &mut [Vec<Value>],
+    ) -> crate::Result<()> {
+        for row in rows {
+            for computed in &table.computed {
+                let column = &table.columns[computed.column];
+                let value = self.get_expr_value(&computed.expr, row, table)?;
+                let value = self.coerce_to_column(value, column)?;
+                if matches!(value, Value::Null) && !column.nullable {
+                    return Err(YamlBaseError::Database {
+                        message: format!(
+                            "Computed column '{}' cannot be NULL, but {} is NULL",
+                            column.name, computed.sql
+                        ),
+                    });
+                }
+                row[computed.column] = value;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,157 @@
+// Parsing text into dates and timestamps with the format strings of TO_DATE/TO_TIMESTAMP
+// (PostgreSQL/Oracle templates such as `DD-MON-YYYY HH24:MI`) and STR_TO_DATE (MySQL `%`
+// specifiers), both translated to chrono format strings
+use chrono::format::{Parsed, StrftimeItems};
+use chrono::{NaiveDate, NaiveTime};
+
+/// PostgreSQL/Oracle template patterns and their chrono equivalents. Longer patterns come
+/// first so that e.g. `MONTH` wins over `MON` and `MM`.
+const TEMPLATE_PATTERNS: &[(&str, &str)] = &[
+    ("YYYY", "%Y"),
+    ("YY", "%y"),
+    ("MONTH", "%B"),
+    ("MON", "%b"),
+    ("MM", "%m"),
+    ("DDD", "%j"),
+    ("DD", "%d"),
+    ("DAY", "%A"),
+    ("DY", "%a"),
+    ("HH24", "%H"),
+    ("HH12", "%I"),
+    ("HH", "%I"),
+    ("MI", "%M"),
+    ("SS", "%S"),
+    ("MS", "%3f"),
+    ("US", "%6f"),
+    ("FF", "%f"),
+    ("A.M.", "%p"),
+    ("P.M.", "%p"),
+    ("AM", "%p"),
+    ("PM", "%p"),
+];
+
+/// Translate a TO_DATE/TO_TIMESTAMP template into a chrono format string. Patterns are
+/// case-insensitive, double-quoted text is literal and the `FM` fill-mode prefix is
+/// accepted but has no effect since chrono does not require zero padding when parsing.
+pub(crate) fn template_to_chrono_format(template: &str) -> String {
+    let mut format = String::with_capacity(template.len() * 2);
+    let mut rest = template;
+
+    while let Some(c) = rest.chars().next() {
+        if c == '"' {
+            let literal_end = rest[1..].find('"').map_or(rest.len(), |end| end + 2);
+            push_literal(&mut format, rest[1..literal_end].trim_end_matches('"'));
+            rest = &rest[literal_end..];
+            continue;
+        }
+
+        let upper = rest.get(..rest.len().min(5)).unwrap_or(rest).to_uppercase();
+        if upper.starts_with("FM") {
+            rest = &rest[2..];
+            continue;
+        }
+        match TEMPLATE_PATTERNS
+            .iter()
+            .find(|(pattern, _)| upper.starts_with(pattern))
+        {
+            Some((pattern, chrono)) => {
+                format.push_str(chrono);
+                rest = &rest[pattern.len()..];
+            }
+            None => {
+                push_literal(&mut format, &rest[..c.len_utf8()]);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+    format
+}
+
+fn push_literal(format: &mut String, literal: &str) {
+    for c in literal.chars() {
+        if c == '%' {
+            format.push_str("%%");
+        } else {
+            format.push(c);
+        }
+    }
+}
+
+/// Parse `text` with a chrono format string into its date and time parts. Either part is
+/// None when the format does not contain the fields needed to build it; a time without
+/// seconds or minutes defaults those to zero. Like PostgreSQL and MySQL, text left over
+/// after the format is used up is ignored.
+pub(crate) fn parse_date_time(
+    text: &str,
+    format: &str,
+) -> Result<(Option<NaiveDate>, Option<NaiveTime>), chrono::ParseError> {
+    let mut parsed = Parsed::new();
+    chrono::format::parse_and_remainder(&mut parsed, text.trim(), StrftimeItems::new(format))?;
+
+    let date = if parsed.year().is_some() || parsed.year_mod_100().is_some() {
+        // A year without month or day means the first of January, as in PostgreSQL
+        if parsed.month().is_none() && parsed.ordinal().is_none() {
+            parsed.set_month(1)?;
+        }
+        if parsed.day().is_none() && parsed.ordinal().is_none() {
+            parsed.set_day(1)?;
+        }
+        Some(parsed.to_naive_date()?)
+    } else {
+        None
+    };
+
+    let time = if parsed.hour_mod_12().is_some() {
+        // A 12-hour clock without AM/PM is read as AM
+        if parsed.hour_div_12().is_none() {
+            parsed.set_ampm(false)?;
+        }
+        if parsed.minute().is_none() {
+            parsed.set_minute(0)?;
+        }
+        Some(parsed.to_naive_time()?)
+    } else {
+        None
+    };
+
+    Ok((date, time))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_template_to_chrono_format() {
+        assert_eq!(template_to_chrono_format("YYYY-MM-DD"), "%Y-%m-%d");
+        assert_eq!(
+            template_to_chrono_format("dd-Mon-yyyy hh24:mi:ss.ms"),
+            "%d-%b-%Y %H:%M:%S.%3f"
+        );
+        assert_eq!(template_to_chrono_format("FMMonth DD, YYYY"), "%B %d, %Y");
+        assert_eq!(
+            template_to_chrono_format("\"Day\" DDD \"of\" YYYY 100%"),
+            "Day %j of %Y 100%%"
+        );
+    }
+
+    #[test]
+    fn test_parse_date_time() {
+        let date = NaiveDate::from_ymd_opt(2024, 3, 5).unwrap();
+
+        assert_eq!(
+            parse_date_time("05/03/2024", "%d/%m/%Y").unwrap(),
+            (Some(date), None)
+        );
+        assert_eq!(
+            parse_date_time("2024-03-05 7:30 PM", "%Y-%m-%d %I:%M %p").unwrap(),
+            (Some(date), NaiveTime::from_hms_opt(19, 30, 0))
+        );
+        assert_eq!(
+            parse_date_time("2024", "%Y").unwrap(),
+            (NaiveDate::from_ymd_opt(2024, 1, 1), None)
+        );
+        assert!(parse_date_time("2024-02-30", "%Y-%m-%d").is_err());
+        assert!(parse_date_time("March 5", "%Y-%m-%d").is_err());
+    }
+}
@@ -0,0 +1,364 @@
+// CREATE TABLE and DROP TABLE. Created tables live in the connection's session overlay and
+// disappear with the connection, like temporary tables, whether or not TEMPORARY is given;
+// the tables of the YAML file cannot be dropped.
+use sqlparser::ast::{
+    ColumnDef, ColumnOption, CreateTable, Expr, ObjectName, ObjectType, Statement, TableConstraint,
+    Value as SqlValue,
+};
+
+use crate::YamlBaseError;
+use crate::database::{CheckConstraint, Collation, Column, Table, Value};
+use crate::sql::executor::{QueryExecutor, QueryResult};
+use crate::yaml::parser::parse_default_value;
+use crate::yaml::schema::YamlColumn;
+
+impl QueryExecutor {
+    pub(crate) async fn execute_create_table(
+        &self,
+        create: &CreateTable,
+    ) -> crate::Result<QueryResult> {
+        if create.like.is_some() || create.clone.is_some() || create.external {
+            return Err(YamlBaseError::NotImplemented(
+                "Only CREATE TABLE with column definitions or AS SELECT is supported".to_string(),
+            ));
+        }
+        let table_name = object_table_name(&create.name);
+
+        let exists = {
+            let db_arc = self.storage().database();
+            let shared = db_arc.read().await;
+            self.session().table(&shared, &table_name).is_some()
+        };
+        let is_temporary = self.session().is_temporary(&table_name);
+        if exists && create.if_not_exists {
+            return Ok(empty_result(None));
+        }
+        // Only TEMPORARY tables may hide a table of the YAML file, as in PostgreSQL
+        if exists && !is_temporary && !create.temporary {
+            return Err(YamlBaseError::Database {
+                message: format!("Table '{}' already exists in the YAML database", table_name),
+            });
+        }
+        if is_temporary && !create.or_replace {
+            return Err(YamlBaseError::Database {
+                message: format!("Table '{}' already exists", table_name),
+            });
+        }
+
+        let table = match &create.query {
+            Some(query) => self.create_table_as(table_name, create, query).await?,
+            None => create_table_from_definition(table_name, create)?,
+        };
+        let rows = create.query.as_ref().map(|_| table.rows.len() as u64);
+        self.session().create_temporary(table);
+        Ok(empty_result(rows))
+    }
+
+    pub(crate) fn execute_drop(&self, statement: &Statement) -> crate::Result<QueryResult> {
+        let Statement::Drop {
+            object_type: ObjectType::Table,
+            if_exists,
+            names,
+            ..
+        } = statement
+        else {
+            return Err(YamlBaseError::NotImplemented(
+                "Only DROP TABLE is supported".to_string(),
+            ));
+        };
+
+        // Check every table first, so a failed DROP drops nothing
+        let names: Vec<String> = names.iter().map(object_table_name).collect();
+        for name in &names {
+            if !self.session().is_temporary(name) && !if_exists {
+                return Err(YamlBaseError::Database {
+                    message: format!(
+                        "Table '{}' does not exist or was not created by this connection",
+                        name
+                    ),
+                });
+            }
+        }
+        for name in &names {
+            self.session().drop_temporary(name);
+        }
+        Ok(empty_result(None))
+    }
+
+    async fn create_table_as(
+        &self,
+        table_name: String,
+        create: &CreateTable,
+        query: &sqlparser::ast::Query,
+    ) -> crate::Result<Table> {
+        let result = self.execute_query(query).await?;
+
+        // Columns may be defined before AS, otherwise they come from the query
+        if !create.columns.is_empty() {
+            if create.columns.len() != result.columns.len() {
+                return Err(YamlBaseError::Database {
+                    message: format!(
+                        "CREATE TABLE {} defines {} columns but the query returns {}",
+                        table_name,
+                        create.columns.len(),
+                        result.columns.len()
+                    ),
+                });
+            }
+            let columns = create
+                .columns
+                .iter()
+                .map(column_from_definition)
+                .collect::<crate::Result<Vec<_>>>()?;
+            return self.fill_table(Table::new(table_name, columns), result.rows);
+        }
+        self.table_from_result(table_name, result)
+    }
+
+    /// A table holding a query result, with the column types the result has
+    pub(crate) fn table_from_result(
+        &self,
+        table_name: String,
+        result: QueryResult,
+    ) -> crate::Result<Table> {
+        let columns = result
+            .columns
+            .into_iter()
+            .enumerate()
+            .map(|(index, name)| {
+                // The inferred type, unless the values say otherwise
+                let inferred = result.column_types.get(index).cloned();
+                let mut values = result.rows.iter().map(|row| &row[index]);
+                let sql_type = match inferred {
+                    Some(sql_type)
+                        if values
+                            .clone()
+                            .all(|value| value.is_compatible_with(&sql_type)) =>
+                    {
+                        sql_type
+                    }
+                    _ => values
+                        .find(|value| !matches!(value, Value::Null))
+                        .map(|value| self.infer_value_type(value))
+                        .unwrap_or(crate::yaml::schema::SqlType::Text),
+                };
+                Column {
+                    name,
+                    sql_type,
+                    primary_key: false,
+                    nullable: true,
+                    unique: false,
+                    default: None,
+                    references: None,
+                    collation: None,
+                }
+            })
+            .collect();
+        self.fill_table(Table::new(table_name, columns), result.rows)
+    }
+
+    fn fill_table(&self, mut table: Table, rows: Vec<Vec<Value>>) -> crate::Result<Table> {
+        for row in rows {
+            let row = row
+                .into_iter()
+                .zip(&table.columns)
+                .map(|(value, column)| self.coerce_to_column(value, column))
+                .collect::<crate::Result<Vec<_>>>()?;
+            table.insert_row(row)?;
+        }
+        Ok(table)
+    }
+}
+
+fn create_table_from_definition(table_name: String, create: &CreateTable) -> crate::Result<Table> {
+    if create.columns.is_empty() {
+        return Err(YamlBaseError::Database {
+            message: format!("CREATE TABLE {} has no columns", table_name),
+        });
+    }
+
+    let mut columns = create
+        .columns
+        .iter()
+        .map(column_from_definition)
+        .collect::<crate::Result<Vec<_>>>()?;
+    let auto_increment: Vec<usize> = create
+        .columns
+        .iter()
+        .enumerate()
+        .filter(|(_, definition)| is_serial(definition))
+        .map(|(index, _)| index)
+        .collect();
+
+    let mut checks: Vec<CheckConstraint> = create
+        .columns
+        .iter()
+        .flat_map(|definition| &definition.options)
+        .filter_map(|option| match &option.option {
+            ColumnOption::Check(expr) => Some(check_constraint(expr)),
+            _ => None,
+        })
+        .collect();
+    for constraint in &create.constraints {
+        let (constrained, primary_key) = match constraint {
+            TableConstraint::PrimaryKey { columns, .. } => (columns, true),
+            TableConstraint::Unique { columns, .. } => (columns, false),
+            TableConstraint::Check { expr, .. } => {
+                checks.push(check_constraint(expr));
+                continue;
+            }
+            _ => continue,
+        };
+        if constrained.len() > 1 {
+            return Err(YamlBaseError::NotImplemented(
+                "PRIMARY KEY and UNIQUE constraints over more than one column are not supported"
+                    .to_string(),
+            ));
+        }
+        for ident in constrained {
+            let column = columns
+                .iter_mut()
+                .find(|column| column.name.eq_ignore_ascii_case(&ident.value))
+                .ok_or_else(|| YamlBaseError::Database {
+                    message: format!(
+                        "Column '{}' not found in table '{}'",
+                        ident.value, table_name
+                    ),
+                })?;
+            column.unique = true;
+            if primary_key {
+                column.primary_key = true;
+                column.nullable = false;
+            }
+        }
+    }
+
+    if columns.iter().filter(|column| column.primary_key).count() > 1 {
+        return Err(YamlBaseError::Database {
+            message: format!("Table '{}' has more than one primary key", table_name),
+        });
+    }
+    let mut table = Table::new(table_name, columns);
+    table.checks = checks;
+    for column in auto_increment {
+        table.set_auto_increment(column)?;
+    }
+    Ok(table)
+}
+
+fn check_constraint(expr: &Expr) -> CheckConstraint {
+    CheckConstraint {
+        sql: expr.to_string(),
+        expr: expr.clone(),
+    }
+}
+
+fn is_serial(definition: &ColumnDef) -> bool {
+    YamlColumn::parse(
+        definition.name.value.clone(),
+        &definition.data_type.to_string(),
+    )
+    .is_ok_and(|column| column.is_auto_increment)
+}
+
+fn column_from_definition(definition: &ColumnDef) -> crate::Result<Column> {
+    let parsed = YamlColumn::parse(
+        definition.name.value.clone(),
+        &definition.data_type.to_string(),
+    )?;
+    let mut column = Column {
+        name: definition.name.value.clone(),
+        sql_type: parsed.get_base_type()?,
+        primary_key: false,
+        // SERIAL columns are NOT NULL
+        nullable: parsed.is_nullable,
+        unique: false,
+        default: None,
+        references: None,
+        collation: definition
+            .collation
+            .as_ref()
+            .map(|name| Collation::parse(&object_table_name(name)))
+            .transpose()?,
+    };
+
+    for option in &definition.options {
+        match &option.option {
+            ColumnOption::Null => column.nullable = true,
+            ColumnOption::NotNull => column.nullable = false,
+            ColumnOption::Unique { is_primary, .. } => {
+                column.unique = true;
+                if *is_primary {
+                    column.primary_key = true;
+                    column.nullable = false;
+                }
+            }
+            ColumnOption::Default(expr) => {
+                let default = default_text(expr)?;
+                // Fail now on a default that cannot be stored in the column
+                parse_default_value(&default, &column.sql_type)?;
+                column.default = Some(default);
+            }
+            ColumnOption::ForeignKey {
+                foreign_table,
+                referred_columns,
+                ..
+            } => {
+                column.references = referred_columns
+                    .first()
+                    .map(|referred| (object_table_name(foreign_table), referred.value.clone()));
+            }
+            // Collected into the table's constraints by create_table_from_definition
+            ColumnOption::Check(_) => {}
+            other => {
+                return Err(YamlBaseError::NotImplemented(format!(
+                    "Column option '{}' is not supported",
+                    other
+                )));
+            }
+        }
+    }
+    Ok(column)
+}
+
+// A column default in the form YAML column definitions use
+fn default_text(expr: &Expr) -> crate::Result<String> {
+    match expr {
+        Expr::Value(SqlValue::Number(number, _)) => Ok(number.clone()),
+        Expr::Value(SqlValue::SingleQuotedString(text)) => Ok(text.clone()),
+        Expr::Value(SqlValue::Boolean(b)) => Ok(b.to_string()),
+        Expr::Value(SqlValue::Null) => Ok("NULL".to_string()),
+        Expr::UnaryOp { op, expr } if op.to_string() == "-" => {
+            Ok(format!("-{}", default_text(expr)?))
+        }
+        Expr::Function(function)
+            if function
+                .name
+                .to_string()
+                .eq_ignore_ascii_case("CURRENT_TIMESTAMP")
+                || function.name.to_string().eq_ignore_ascii_case("NOW") =>
+        {
+            Ok("CURRENT_TIMESTAMP".to_string())
+        }
+        _ => Err(YamlBaseError::NotImplemented(format!(
+            "Column default '{}' is not supported",
+            expr
+        ))),
+    }
+}
+
+fn object_table_name(name: &ObjectName) -> String {
+    name.0
+        .last()
+        .map(|ident| ident.value.clone())
+        .unwrap_or_default()
+}
+
+fn empty_result(affected_rows: Option<u64>) -> QueryResult {
+    QueryResult {
+        columns: vec![],
+        column_types: vec![],
+        rows: vec![],
+        affected_rows,
+    }
+}
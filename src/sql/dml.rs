@@ -0,0 +1,745 @@
+// INSERT, UPDATE, DELETE and MERGE. Rows are written to the connection's session overlay, so the
+// YAML data every other connection reads stays as it was loaded, unless the server runs with
+// --mutable: then the changes are moved into the shared database (and with --persist into
+// the YAML file) once the statement, or the transaction it is part of, commits.
+use indexmap::IndexMap;
+use sqlparser::ast::{
+    Assignment, AssignmentTarget, DataType, Delete, Expr, FromTable, Insert, Query, SelectItem,
+    SetExpr, Statement, TableFactor, TableWithJoins,
+};
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use crate::YamlBaseError;
+use crate::database::{Column, Table, Value};
+use crate::sql::SqlDialect;
+use crate::sql::executor::{QueryExecutor, QueryResult, WriteMode};
+use crate::yaml::parser::{is_parquet_file, parse_default_value};
+use crate::yaml::schema::SqlType;
+use crate::yaml::writer::{write_table_csv, write_table_data};
+
+impl QueryExecutor {
+    pub(crate) async fn execute_write(&self, statement: &Statement) -> crate::Result<QueryResult> {
+        // Writers take turns, so no statement reads rows another one is about to replace.
+        // A transaction keeps the lock until it ends and commits all its changes at once.
+        let writer = match self.write_mode() {
+            WriteMode::Session => None,
+            _ if self.hold_writer_for_transaction().await => None,
+            _ => Some(self.storage().lock_writes().await),
+        };
+
+        let before = self.session().snapshot();
+        let result = self.execute_dml(statement).await;
+        match &result {
+            Err(_) => self.session().restore(before),
+            Ok(_) if writer.is_some() => self.commit_session().await?,
+            Ok(_) => {}
+        }
+        result
+    }
+
+    async fn execute_dml(&self, statement: &Statement) -> crate::Result<QueryResult> {
+        match statement {
+            Statement::Insert(insert) => self.execute_insert(insert).await,
+            Statement::Update {
+                table,
+                assignments,
+                from,
+                selection,
+                returning,
+            } => {
+                if from.is_some() || returning.is_some() {
+                    return Err(YamlBaseError::NotImplemented(
+                        "UPDATE ... FROM and RETURNING are not supported".to_string(),
+                    ));
+                }
+                self.execute_update(table, assignments, selection.as_ref())
+                    .await
+            }
+            Statement::Delete(delete) => self.execute_delete(delete).await,
+            Statement::Merge {
+                table,
+                source,
+                on,
+                clauses,
+                ..
+            } => self.execute_merge(table, source, on, clauses).await,
+            _ => unreachable!("not an INSERT, UPDATE, DELETE or MERGE statement"),
+        }
+    }
+
+    /// Move the tables changed in the session overlay into the shared database, writing
+    /// them to the YAML file first when persisting. A failed write changes nothing.
+    pub(crate) async fn commit_session(&self) -> crate::Result<()> {
+        let changed = self.session().take();
+        if changed.is_empty() {
+            return Ok(());
+        }
+
+        let db_arc = self.storage().database();
+        if let WriteMode::Persistent(path) = self.write_mode() {
+            // Each table goes back to the file it was loaded from, which may be an include
+            // or the CSV file holding its rows
+            let mut files: IndexMap<PathBuf, Vec<&Table>> = IndexMap::new();
+            let mut data_files = Vec::new();
+            {
+                let db = db_arc.read().await;
+                for (name, table) in &changed {
+                    if db.generated_tables.contains(name) {
+                        return Err(YamlBaseError::Database {
+                            message: format!(
+                                "Table '{}' has generated rows, which cannot be written",
+                                name
+                            ),
+                        });
+                    }
+                    if let Some(data_file) = db.data_files.get(name) {
+                        if is_parquet_file(data_file) {
+                            return Err(YamlBaseError::Database {
+                                message: format!(
+                                    "Table '{}' is loaded from the Parquet file {}, which cannot be written",
+                                    name,
+                                    data_file.display()
+                                ),
+                            });
+                        }
+                        data_files.push((data_file.clone(), table.as_ref()));
+                        continue;
+                    }
+                    let file = db.table_files.get(name).unwrap_or(path);
+                    files.entry(file.clone()).or_default().push(table.as_ref());
+                }
+            }
+            for (file, tables) in files {
+                write_table_data(&file, &tables).await?;
+            }
+            for (data_file, table) in data_files {
+                write_table_csv(&data_file, table).await?;
+            }
+        }
+
+        let mut db = db_arc.write().await;
+        db.tables.extend(changed);
+        drop(db);
+
+        self.storage().rebuild_indexes().await;
+        Ok(())
+    }
+
+    async fn execute_insert(&self, insert: &Insert) -> crate::Result<QueryResult> {
+        if insert.or.is_some() || insert.ignore || insert.replace_into {
+            return Err(YamlBaseError::NotImplemented(
+                "Only INSERT INTO ... VALUES/SELECT, with ON CONFLICT or ON DUPLICATE KEY \
+                 UPDATE, is supported"
+                    .to_string(),
+            ));
+        }
+        let table_name = insert
+            .table_name
+            .0
+            .last()
+            .map(|ident| ident.value.clone())
+            .unwrap_or_default();
+
+        // `INSERT ... DEFAULT VALUES` has no source and gives one row of defaults
+        let (rows, columns) = match &insert.source {
+            Some(source) => (self.insert_source_rows(source).await?, &insert.columns[..]),
+            None => (vec![vec![]], &[][..]),
+        };
+
+        let db_arc = self.storage().database();
+        let shared = db_arc.read().await;
+        let (affected, written) = self.session().update(&shared, &table_name, |table| {
+            let targets = if columns.is_empty() && insert.source.is_some() {
+                (0..table.columns.len()).collect()
+            } else {
+                insert_targets(table, columns)?
+            };
+            let mut new_rows = rows
+                .into_iter()
+                .map(|values| self.build_insert_row(table, &targets, values))
+                .collect::<crate::Result<Vec<_>>>()?;
+            self.generate_ids(table, &mut new_rows);
+            self.compute_columns(table, &mut new_rows)?;
+            if let Some(on) = &insert.on {
+                return self.upsert_rows(table, on, insert.table_alias.as_ref(), new_rows);
+            }
+            check_unique_columns(table, table.rows.iter(), &new_rows)?;
+            self.check_constraints(table, &new_rows)?;
+
+            let written = match insert.returning {
+                Some(_) => new_rows.clone(),
+                None => Vec::new(),
+            };
+            let count = new_rows.len();
+            table.rows.extend(new_rows);
+            Ok((count, written))
+        })?;
+
+        if let Some(items) = &insert.returning {
+            let table = self.session().table(&shared, &table_name).unwrap();
+            let mut result = self.returning_result(&table, items, &written)?;
+            result.affected_rows = Some(affected as u64);
+            return Ok(result);
+        }
+        Ok(QueryResult {
+            columns: vec![],
+            column_types: vec![],
+            rows: vec![],
+            affected_rows: Some(affected as u64),
+        })
+    }
+
+    /// The columns `INSERT ... RETURNING` gives back, without running the INSERT
+    pub async fn describe_returning(
+        &self,
+        insert: &Insert,
+    ) -> crate::Result<(Vec<String>, Vec<SqlType>)> {
+        let Some(items) = &insert.returning else {
+            return Ok((vec![], vec![]));
+        };
+        let db_arc = self.storage().database();
+        let shared = db_arc.read().await;
+        let table_name = self.table_key(&shared, &insert.table_name);
+        let table =
+            self.session()
+                .table(&shared, &table_name)
+                .ok_or_else(|| YamlBaseError::Database {
+                    message: format!("Table '{}' not found", table_name),
+                })?;
+        returning_columns(&table, items)
+    }
+
+    /// The RETURNING list evaluated over the rows a statement wrote
+    fn returning_result(
+        &self,
+        table: &Table,
+        items: &[SelectItem],
+        rows: &[Vec<Value>],
+    ) -> crate::Result<QueryResult> {
+        let (columns, column_types) = returning_columns(table, items)?;
+        let rows = rows
+            .iter()
+            .map(|row| {
+                let mut values = Vec::with_capacity(columns.len());
+                for item in items {
+                    match item {
+                        SelectItem::Wildcard(_) | SelectItem::QualifiedWildcard(..) => {
+                            values.extend(row.iter().cloned())
+                        }
+                        SelectItem::UnnamedExpr(expr) | SelectItem::ExprWithAlias { expr, .. } => {
+                            values.push(self.get_expr_value(expr, row, table)?)
+                        }
+                    }
+                }
+                Ok(values)
+            })
+            .collect::<crate::Result<Vec<_>>>()?;
+
+        Ok(QueryResult {
+            columns,
+            column_types,
+            rows,
+            affected_rows: None,
+        })
+    }
+
+    async fn execute_update(
+        &self,
+        target: &TableWithJoins,
+        assignments: &[Assignment],
+        selection: Option<&Expr>,
+    ) -> crate::Result<QueryResult> {
+        let table_name = dml_table_name(target)?;
+
+        let db_arc = self.storage().database();
+        let shared = db_arc.read().await;
+        let table =
+            self.session()
+                .table(&shared, &table_name)
+                .ok_or_else(|| YamlBaseError::Database {
+                    message: format!("Table '{}' not found", table_name),
+                })?;
+
+        let columns = assignment_columns(&table, assignments)?;
+
+        // New values are computed from the rows as they were before the statement
+        let mut matched = 0;
+        let mut changes = Vec::new();
+        for (index, row) in table.rows.iter().enumerate() {
+            if let Some(condition) = selection
+                && !self.evaluate_expr_async(condition, row, &table).await?
+            {
+                continue;
+            }
+            matched += 1;
+
+            let new_row = self.assign_row(&table, row, &columns, assignments, |expr| {
+                self.get_expr_value(expr, row, &table)
+            })?;
+            if new_row != *row {
+                changes.push((index, new_row));
+            }
+        }
+
+        let changed = changes.len();
+        if changed > 0 {
+            self.session().update(&shared, &table_name, |table| {
+                let changed_indexes: HashSet<usize> =
+                    changes.iter().map(|(index, _)| *index).collect();
+                let kept = table
+                    .rows
+                    .iter()
+                    .enumerate()
+                    .filter(|(index, _)| !changed_indexes.contains(index))
+                    .map(|(_, row)| row);
+                let new_rows: Vec<Vec<Value>> =
+                    changes.iter().map(|(_, row)| row.clone()).collect();
+                check_unique_columns(table, kept, &new_rows)?;
+                self.check_constraints(table, &new_rows)?;
+
+                for (index, row) in changes {
+                    table.rows[index] = row;
+                }
+                Ok(())
+            })?;
+        }
+
+        // MySQL reports the rows an UPDATE changed, PostgreSQL the rows it matched
+        let affected_rows = match self.dialect() {
+            SqlDialect::MySQL => changed,
+            _ => matched,
+        };
+        Ok(QueryResult {
+            columns: vec![],
+            column_types: vec![],
+            rows: vec![],
+            affected_rows: Some(affected_rows as u64),
+        })
+    }
+
+    async fn execute_delete(&self, delete: &Delete) -> crate::Result<QueryResult> {
+        let from = match &delete.from {
+            FromTable::WithFromKeyword(from) | FromTable::WithoutKeyword(from) => from,
+        };
+        if !delete.tables.is_empty()
+            || delete.using.is_some()
+            || delete.returning.is_some()
+            || !delete.order_by.is_empty()
+            || delete.limit.is_some()
+        {
+            return Err(YamlBaseError::NotImplemented(
+                "Only DELETE FROM <table> [WHERE ...] is supported".to_string(),
+            ));
+        }
+        let [target] = from.as_slice() else {
+            return Err(YamlBaseError::NotImplemented(
+                "DELETE from more than one table is not supported".to_string(),
+            ));
+        };
+        let table_name = dml_table_name(target)?;
+
+        let db_arc = self.storage().database();
+        let shared = db_arc.read().await;
+        let table =
+            self.session()
+                .table(&shared, &table_name)
+                .ok_or_else(|| YamlBaseError::Database {
+                    message: format!("Table '{}' not found", table_name),
+                })?;
+
+        let mut deleted = HashSet::new();
+        for (index, row) in table.rows.iter().enumerate() {
+            let matches = match &delete.selection {
+                Some(condition) => self.evaluate_expr_async(condition, row, &table).await?,
+                None => true,
+            };
+            if matches {
+                deleted.insert(index);
+            }
+        }
+
+        if !deleted.is_empty() {
+            self.session().update(&shared, &table_name, |table| {
+                let mut index = 0;
+                table.rows.retain(|_| {
+                    index += 1;
+                    !deleted.contains(&(index - 1))
+                });
+                Ok(())
+            })?;
+        }
+
+        Ok(QueryResult {
+            columns: vec![],
+            column_types: vec![],
+            rows: vec![],
+            affected_rows: Some(deleted.len() as u64),
+        })
+    }
+
+    /// The row with the assignments of an UPDATE applied to the columns they target, with
+    /// `evaluate` giving the value of an assigned expression
+    pub(super) fn assign_row(
+        &self,
+        table: &Table,
+        row: &[Value],
+        columns: &[usize],
+        assignments: &[Assignment],
+        evaluate: impl Fn(&Expr) -> crate::Result<Value>,
+    ) -> crate::Result<Vec<Value>> {
+        let mut new_row = row.to_vec();
+        for (&column_index, assignment) in columns.iter().zip(assignments) {
+            let column = &table.columns[column_index];
+            if table.is_computed(column_index) {
+                return Err(computed_write(column));
+            }
+            let value = match &assignment.value {
+                Expr::Identifier(ident) if ident.value.eq_ignore_ascii_case("DEFAULT") => {
+                    match &column.default {
+                        Some(default) => parse_default_value(default, &column.sql_type)?,
+                        None => Value::Null,
+                    }
+                }
+                expr => self.coerce_to_column(evaluate(expr)?, column)?,
+            };
+            if matches!(value, Value::Null) && !column.nullable {
+                return Err(YamlBaseError::Database {
+                    message: format!("Column '{}' cannot be NULL", column.name),
+                });
+            }
+            new_row[column_index] = value;
+        }
+        self.compute_columns(table, std::slice::from_mut(&mut new_row))?;
+        Ok(new_row)
+    }
+
+    /// The rows of a VALUES list or query. `None` stands for a DEFAULT in VALUES.
+    pub(super) async fn insert_source_rows(
+        &self,
+        source: &Query,
+    ) -> crate::Result<Vec<Vec<Option<Value>>>> {
+        if let SetExpr::Values(values) = source.body.as_ref()
+            && source.order_by.is_none()
+            && source.limit.is_none()
+        {
+            return values
+                .rows
+                .iter()
+                .map(|row| {
+                    row.iter()
+                        .map(|expr| match expr {
+                            Expr::Identifier(ident)
+                                if ident.value.eq_ignore_ascii_case("DEFAULT") =>
+                            {
+                                Ok(None)
+                            }
+                            _ => self.evaluate_constant_expr(expr).map(Some),
+                        })
+                        .collect()
+                })
+                .collect();
+        }
+
+        let result = self.execute_query(source).await?;
+        Ok(result
+            .rows
+            .into_iter()
+            .map(|row| row.into_iter().map(Some).collect())
+            .collect())
+    }
+
+    /// A full table row from the values given for the target columns, with defaults for the
+    /// other columns. An auto-increment column without a value is left NULL for
+    /// `generate_ids` to fill in.
+    pub(super) fn build_insert_row(
+        &self,
+        table: &Table,
+        targets: &[usize],
+        values: Vec<Option<Value>>,
+    ) -> crate::Result<Vec<Value>> {
+        if values.len() != targets.len() {
+            return Err(YamlBaseError::Database {
+                message: format!(
+                    "INSERT has {} values but {} target columns",
+                    values.len(),
+                    targets.len()
+                ),
+            });
+        }
+
+        let mut given = vec![None; table.columns.len()];
+        for (&target, value) in targets.iter().zip(values) {
+            given[target] = value;
+        }
+
+        let generated = table
+            .auto_increment
+            .map(|auto_increment| auto_increment.column);
+        table
+            .columns
+            .iter()
+            .zip(given)
+            .enumerate()
+            .map(|(index, (column, value))| {
+                if table.is_computed(index) {
+                    if value.is_some() {
+                        return Err(computed_write(column));
+                    }
+                    // Computed once the row is complete
+                    return Ok(Value::Null);
+                }
+                let value = match value {
+                    Some(value) => self.coerce_to_column(value, column)?,
+                    None => match &column.default {
+                        Some(default) => parse_default_value(default, &column.sql_type)?,
+                        None => Value::Null,
+                    },
+                };
+                if generated == Some(index) {
+                    // MySQL also generates an id for 0
+                    return Ok(match value {
+                        Value::Integer(0) if matches!(self.dialect(), SqlDialect::MySQL) => {
+                            Value::Null
+                        }
+                        value => value,
+                    });
+                }
+                if matches!(value, Value::Null) && !column.nullable {
+                    return Err(YamlBaseError::Database {
+                        message: format!("Column '{}' cannot be NULL", column.name),
+                    });
+                }
+                Ok(value)
+            })
+            .collect()
+    }
+
+    /// Convert a value to the type of the column it is stored in, as an assignment cast
+    pub(crate) fn coerce_to_column(&self, value: Value, column: &Column) -> crate::Result<Value> {
+        let incompatible = |value: &Value| {
+            YamlBaseError::TypeConversion(format!(
+                "Value {} is not compatible with column '{}' of type {:?}",
+                value, column.name, column.sql_type
+            ))
+        };
+
+        let value = if value.is_compatible_with(&column.sql_type) {
+            value
+        } else {
+            match (&value, &column.sql_type) {
+                (Value::Text(text), SqlType::Uuid) => uuid::Uuid::parse_str(text)
+                    .map(Value::Uuid)
+                    .map_err(|_| incompatible(&value))?,
+                (Value::Text(text), SqlType::Json) => serde_json::from_str(text)
+                    .map(Value::Json)
+                    .map_err(|_| incompatible(&value))?,
+                (Value::Uuid(_) | Value::Json(_), _) => return Err(incompatible(&value)),
+                _ => {
+                    let cast = self
+                        .cast_value(value.clone(), &column_data_type(&column.sql_type))
+                        .map_err(|_| incompatible(&value))?;
+                    if !cast.is_compatible_with(&column.sql_type) {
+                        return Err(incompatible(&value));
+                    }
+                    cast
+                }
+            }
+        };
+
+        if let (Value::Text(text), SqlType::Char(size) | SqlType::Varchar(size)) =
+            (&value, &column.sql_type)
+            && text.chars().count() > *size
+        {
+            return Err(YamlBaseError::Database {
+                message: format!(
+                    "Value too long for column '{}' ({} characters at most)",
+                    column.name, size
+                ),
+            });
+        }
+        Ok(value)
+    }
+}
+
+fn computed_write(column: &Column) -> YamlBaseError {
+    YamlBaseError::Database {
+        message: format!("Column '{}' is computed and cannot be written", column.name),
+    }
+}
+
+/// The names and types of the columns in a RETURNING list
+fn returning_columns(
+    table: &Table,
+    items: &[SelectItem],
+) -> crate::Result<(Vec<String>, Vec<SqlType>)> {
+    let column_type = |_: Option<&str>, name: &str| {
+        table
+            .get_column_index(name)
+            .map(|index| table.columns[index].sql_type.clone())
+    };
+
+    let mut columns = Vec::new();
+    let mut types = Vec::new();
+    for item in items {
+        let (name, expr) = match item {
+            SelectItem::Wildcard(_) | SelectItem::QualifiedWildcard(..) => {
+                for column in &table.columns {
+                    columns.push(column.name.clone());
+                    types.push(column.sql_type.clone());
+                }
+                continue;
+            }
+            SelectItem::ExprWithAlias { expr, alias } => (alias.value.clone(), expr),
+            SelectItem::UnnamedExpr(expr) => {
+                let name = match expr {
+                    Expr::Identifier(ident) => ident.value.clone(),
+                    Expr::CompoundIdentifier(parts) => parts
+                        .last()
+                        .map(|ident| ident.value.clone())
+                        .unwrap_or_default(),
+                    _ => expr.to_string(),
+                };
+                (name, expr)
+            }
+        };
+        columns.push(name);
+        types.push(QueryExecutor::infer_expr_type(expr, &column_type).unwrap_or(SqlType::Text));
+    }
+    Ok((columns, types))
+}
+
+/// The indexes of the columns an UPDATE assigns to
+pub(super) fn assignment_columns(
+    table: &Table,
+    assignments: &[Assignment],
+) -> crate::Result<Vec<usize>> {
+    let mut columns = Vec::with_capacity(assignments.len());
+    for assignment in assignments {
+        let AssignmentTarget::ColumnName(name) = &assignment.target else {
+            return Err(YamlBaseError::NotImplemented(
+                "Assigning to a tuple of columns is not supported".to_string(),
+            ));
+        };
+        let column = name
+            .0
+            .last()
+            .map(|ident| ident.value.as_str())
+            .unwrap_or("");
+        let index = table
+            .get_column_index(column)
+            .ok_or_else(|| YamlBaseError::Database {
+                message: format!("Column '{}' not found in table '{}'", column, table.name),
+            })?;
+        if columns.contains(&index) {
+            return Err(YamlBaseError::Database {
+                message: format!("Column '{}' assigned more than once", column),
+            });
+        }
+        columns.push(index);
+    }
+    Ok(columns)
+}
+
+/// The indexes of the columns an INSERT names
+pub(super) fn insert_targets(
+    table: &Table,
+    columns: &[sqlparser::ast::Ident],
+) -> crate::Result<Vec<usize>> {
+    let mut targets = Vec::with_capacity(columns.len());
+    for ident in columns {
+        let index =
+            table
+                .get_column_index(&ident.value)
+                .ok_or_else(|| YamlBaseError::Database {
+                    message: format!(
+                        "Column '{}' not found in table '{}'",
+                        ident.value, table.name
+                    ),
+                })?;
+        if targets.contains(&index) {
+            return Err(YamlBaseError::Database {
+                message: format!("Column '{}' specified more than once", ident.value),
+            });
+        }
+        targets.push(index);
+    }
+    Ok(targets)
+}
+
+/// The table an UPDATE or DELETE changes
+pub(super) fn dml_table_name(target: &TableWithJoins) -> crate::Result<String> {
+    match &target.relation {
+        TableFactor::Table { name, .. } if target.joins.is_empty() => Ok(name
+            .0
+            .last()
+            .map(|ident| ident.value.clone())
+            .unwrap_or_default()),
+        _ => Err(YamlBaseError::NotImplemented(
+            "UPDATE and DELETE only support a single table".to_string(),
+        )),
+    }
+}
+
+/// Check that new or changed rows keep primary key and unique columns free of duplicates,
+/// among themselves and with the rows the statement keeps
+pub(super) fn check_unique_columns<'a>(
+    table: &Table,
+    kept: impl Iterator<Item = &'a Vec<Value>> + Clone,
+    new_rows: &[Vec<Value>],
+) -> crate::Result<()> {
+    for (index, column) in table.columns.iter().enumerate() {
+        if !column.primary_key && !column.unique {
+            continue;
+        }
+
+        let mut seen: HashSet<&Value> = kept
+            .clone()
+            .map(|row| &row[index])
+            .filter(|value| !matches!(value, Value::Null))
+            .collect();
+        for row in new_rows {
+            let value = &row[index];
+            if !matches!(value, Value::Null) && !seen.insert(value) {
+                return Err(YamlBaseError::UniqueViolation {
+                    table: table.name.clone(),
+                    column: column.name.clone(),
+                    value: value.to_string(),
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The SQL type `cast_value` converts to for a column type
+fn column_data_type(sql_type: &SqlType) -> DataType {
+    use sqlparser::ast::{ArrayElemTypeDef, CharacterLength, ExactNumberInfo, TimezoneInfo};
+
+    match sql_type {
+        SqlType::Integer => DataType::Integer(None),
+        SqlType::BigInt => DataType::BigInt(None),
+        SqlType::Float => DataType::Real,
+        SqlType::Double => DataType::Double,
+        SqlType::Decimal(precision, scale) => DataType::Decimal(
+            ExactNumberInfo::PrecisionAndScale(*precision as u64, *scale as u64),
+        ),
+        SqlType::Char(size) | SqlType::Varchar(size) => {
+            DataType::Varchar(Some(CharacterLength::IntegerLength {
+                length: *size as u64,
+                unit: None,
+            }))
+        }
+        SqlType::Text | SqlType::Uuid | SqlType::Json => DataType::Text,
+        SqlType::Boolean => DataType::Boolean,
+        SqlType::Date => DataType::Date,
+        SqlType::Time => DataType::Time(None, TimezoneInfo::None),
+        SqlType::Timestamp => DataType::Timestamp(None, TimezoneInfo::None),
+        SqlType::TimestampTz => DataType::Timestamp(None, TimezoneInfo::WithTimeZone),
+        SqlType::Blob => DataType::Bytea,
+        SqlType::Array(element_type) => DataType::Array(ArrayElemTypeDef::SquareBracket(
+            Box::new(column_data_type(element_type)),
+            None,
+        )),
+    }
+}
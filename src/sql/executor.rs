@@ -1,24 +1,84 @@
 use chrono::{self, Datelike, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
-use regex::Regex;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use regex::RegexBuilder;
 use rust_decimal::prelude::*;
 use sqlparser::ast::{
-    BinaryOperator, DataType, DateTimeField, Distinct, DuplicateTreatment, Expr, Function,
-    FunctionArg, FunctionArgExpr, FunctionArguments, GroupByExpr, JoinConstraint, JoinOperator,
-    OrderByExpr, Query, Select, SelectItem, SetExpr, SetOperator, SetQuantifier, Statement,
-    TableFactor, TableWithJoins, UnaryOperator, With,
+    BinaryOperator, Cte, DataType, DateTimeField, Distinct, DuplicateTreatment, Expr, Function,
+    FunctionArg, FunctionArgExpr, FunctionArgumentClause, FunctionArguments, GroupByExpr,
+    JoinConstraint, JoinOperator, OneOrManyWithParens, OrderByExpr, Query, Select, SelectItem,
+    SetExpr, SetOperator, SetQuantifier, Statement, TableFactor, TableWithJoins, TimezoneInfo,
+    UnaryOperator,
 };
-use std::sync::Arc;
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
+use tokio::sync::Notify;
 use tracing::debug;
 
 use crate::YamlBaseError;
-use crate::database::{Column, Database, Storage, Table, Value};
+use crate::database::timezone::{parse_naive_timestamp, parse_timestamptz};
+use crate::database::{
+    Collation, Column, Database, ReloadOutcome, SessionOverlay, Storage, Table, TimeZone, Value,
+};
+use crate::server::rate_limit::Quota;
+use crate::sql::SqlDialect;
+use crate::sql::json::value_to_json;
+use crate::sql::namespace::{SEARCH_PATH_SETTING, default_search_path};
+use crate::sql::recursive_cte::RecursionLimits;
+use crate::sql::sequence::GeneratedIds;
+use crate::sql::settings::SessionSettings;
+use crate::sql::transaction::Transaction;
+use crate::yaml::Grants;
+
+/// Materialized CTE results by lower-cased CTE name
+pub(crate) type CteResults = std::collections::HashMap<String, QueryResult>;
+
+tokio::task_local! {
+    /// The CTEs of the queries being executed, for subqueries and derived tables that
+    /// reference them
+    static CTE_SCOPE: Arc<CteResults>;
+
+    /// The values bound to the `$n` placeholders of the statement being executed
+    static BIND_PARAMETERS: Arc<Vec<Value>>;
+}
 
 #[derive(Clone)]
 pub struct QueryExecutor {
     storage: Arc<Storage>,
     database_name: String,
+    // The names of the databases the server serves, which SHOW DATABASES lists
+    databases: Vec<String>,
     query_timeout: Duration,
+    dialect: SqlDialect,
+    rng: Arc<Mutex<StdRng>>,
+    recursion_limits: Arc<Mutex<RecursionLimits>>,
+    search_path: Arc<Mutex<Vec<String>>>,
+    collation: Collation,
+    session: SessionOverlay,
+    write_mode: WriteMode,
+    transaction: Arc<Mutex<Option<Transaction>>>,
+    generated_ids: Arc<Mutex<GeneratedIds>>,
+    grants: Option<Arc<Grants>>,
+    // The rate limits of the client, by --rate-limit-by
+    quota: Option<Arc<Quota>>,
+    cancel_signal: Arc<Notify>,
+    // The channels LISTEN added
+    listening: Arc<Mutex<HashSet<String>>>,
+    settings: Arc<Mutex<SessionSettings>>,
+}
+
+/// Where INSERT, UPDATE and DELETE put their changes
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum WriteMode {
+    /// Only the connection that made a change sees it
+    #[default]
+    Session,
+    /// Changes are applied to the data every connection reads
+    Shared,
+    /// Like `Shared`, and changes are also written back to this YAML file
+    Persistent(PathBuf),
 }
 
 #[derive(Debug, Clone)]
@@ -26,18 +86,30 @@ pub struct QueryResult {
     pub columns: Vec<String>,
     pub column_types: Vec<crate::yaml::schema::SqlType>,
     pub rows: Vec<Vec<Value>>,
+    /// The number of rows an INSERT, UPDATE or DELETE changed; `None` for queries
+    pub affected_rows: Option<u64>,
 }
 
 #[derive(Debug, Clone)]
 enum ProjectionItem {
     // A column from the table (name, index)
     TableColumn(String, usize),
-    // A constant expression with its computed value and column alias
-    Constant(String, Value),
+    // A constant expression with its column alias, computed value and column type
+    Constant(String, Value, crate::yaml::schema::SqlType),
     // An expression that needs to be evaluated per row
     Expression(String, Box<Expr>),
 }
 
+impl ProjectionItem {
+    fn name(&self) -> &str {
+        match self {
+            ProjectionItem::TableColumn(name, _) => name,
+            ProjectionItem::Constant(name, ..) => name,
+            ProjectionItem::Expression(name, _) => name,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 enum JoinedColumn {
     // A column from a specific table (display_name, table_idx, column_idx)
@@ -62,14 +134,24 @@ impl JoinedColumn {
         }
     }
 
-    fn get_type(&self, tables: &[(String, &Table)]) -> crate::yaml::schema::SqlType {
+    fn get_type(&self, tables: &[(String, &Table)]) -> Option<crate::yaml::schema::SqlType> {
         match self {
             JoinedColumn::TableColumn(_, table_idx, col_idx) => {
-                tables[*table_idx].1.columns[*col_idx].sql_type.clone()
+                Some(tables[*table_idx].1.columns[*col_idx].sql_type.clone())
             }
-            JoinedColumn::Expression(_, _) => {
-                // For expressions, default to Text type (could be enhanced to infer actual type)
-                crate::yaml::schema::SqlType::Text
+            JoinedColumn::Expression(_, expr) => {
+                QueryExecutor::infer_expr_type(expr, &|qualifier, name| {
+                    tables
+                        .iter()
+                        .filter(|(table_ref, _)| qualifier.is_none_or(|q| q == table_ref))
+                        .find_map(|(_, table)| {
+                            table
+                                .columns
+                                .iter()
+                                .find(|column| column.name.eq_ignore_ascii_case(name))
+                        })
+                        .map(|column| column.sql_type.clone())
+                })
             }
         }
     }
@@ -83,9 +165,24 @@ impl QueryExecutor {
         drop(db);
 
         Ok(Self {
+            databases: vec![database_name.clone()],
             storage,
             database_name,
             query_timeout: Duration::from_secs(60), // Default 60 second timeout
+            dialect: SqlDialect::default(),
+            rng: Arc::new(Mutex::new(StdRng::from_entropy())),
+            recursion_limits: Arc::new(Mutex::new(RecursionLimits::default())),
+            search_path: Arc::new(Mutex::new(default_search_path())),
+            collation: Collation::default(),
+            session: SessionOverlay::new(),
+            write_mode: WriteMode::default(),
+            transaction: Arc::new(Mutex::new(None)),
+            generated_ids: Arc::new(Mutex::new(GeneratedIds::default())),
+            grants: None,
+            quota: None,
+            cancel_signal: Arc::new(Notify::new()),
+            listening: Arc::new(Mutex::new(HashSet::new())),
+            settings: Arc::new(Mutex::new(SessionSettings::default())),
         })
     }
 
@@ -94,55 +191,436 @@ impl QueryExecutor {
         self
     }
 
+    /// The statement timeout the server was started with, zero for none
+    pub(crate) fn query_timeout(&self) -> Duration {
+        self.query_timeout
+    }
+
+    /// Select the dialect whose semantics apply where databases disagree,
+    /// e.g. NULL handling in GREATEST/LEAST
+    pub fn with_dialect(mut self, dialect: SqlDialect) -> Self {
+        self.dialect = dialect;
+        self
+    }
+
+    pub fn dialect(&self) -> SqlDialect {
+        self.dialect
+    }
+
+    /// Set the collation for text comparisons on columns that do not declare their own
+    pub fn with_collation(mut self, collation: Collation) -> Self {
+        self.collation = collation;
+        self
+    }
+
+    /// The collation of text columns that do not declare their own
+    pub fn collation(&self) -> Collation {
+        self.collation
+    }
+
+    /// Name the databases the server serves beside this one, for SHOW DATABASES
+    pub fn with_databases(mut self, databases: Vec<String>) -> Self {
+        self.databases = databases;
+        self
+    }
+
+    pub fn databases(&self) -> &[String] {
+        &self.databases
+    }
+
+    /// Choose whether writes stay private to the connection or are shared and persisted
+    pub fn with_write_mode(mut self, write_mode: WriteMode) -> Self {
+        self.write_mode = write_mode;
+        self
+    }
+
+    /// Limit the session to the tables a user was granted, or lift the limits with `None`
+    pub fn with_grants(mut self, grants: Option<Grants>) -> Self {
+        self.grants = grants.map(Arc::new);
+        self
+    }
+
+    /// Count statements and result rows against a client's rate limits
+    pub fn with_quota(mut self, quota: Option<Arc<Quota>>) -> Self {
+        self.quota = quota;
+        self
+    }
+
+    pub fn grants(&self) -> Option<&Grants> {
+        self.grants.as_deref()
+    }
+
+    /// Stop the statement running when the signal is notified, as a cancel request does
+    pub fn with_cancel_signal(mut self, signal: Arc<Notify>) -> Self {
+        self.cancel_signal = signal;
+        self
+    }
+
+    /// Make RAND()/RANDOM() return the same sequence on every run
+    pub fn with_random_seed(mut self, seed: u64) -> Self {
+        self.rng = Arc::new(Mutex::new(StdRng::seed_from_u64(seed)));
+        self
+    }
+
+    /// Next value in [0, 1) from the executor's random number generator
+    pub(crate) fn next_random(&self) -> f64 {
+        self.rng
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .r#gen()
+    }
+
+    pub fn with_recursion_limits(mut self, limits: RecursionLimits) -> Self {
+        self.recursion_limits = Arc::new(Mutex::new(limits));
+        self
+    }
+
+    /// The session's current limits for recursive CTEs
+    pub fn recursion_limits(&self) -> RecursionLimits {
+        *self
+            .recursion_limits
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// The schemas unqualified table names are looked up in, in order
+    pub fn search_path(&self) -> Vec<String> {
+        self.search_path
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    /// Look unqualified table names up in these schemas, as `SET search_path` does
+    pub fn set_search_path(&self, schemas: Vec<String>) {
+        *self
+            .search_path
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()) = schemas;
+    }
+
+    /// Whether `statement` sets a session setting that `execute` applies
+    pub fn is_session_setting(statement: &Statement) -> bool {
+        matches!(
+            Self::set_variable_name(statement).as_deref(),
+            Some(
+                RecursionLimits::MAX_ITERATIONS_SETTING
+                    | RecursionLimits::MAX_ROWS_SETTING
+                    | SEARCH_PATH_SETTING
+            )
+        )
+    }
+
+    fn set_variable_name(statement: &Statement) -> Option<String> {
+        match statement {
+            Statement::SetVariable {
+                variables: OneOrManyWithParens::One(name),
+                ..
+            } => name.0.last().map(|ident| ident.value.to_lowercase()),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn execute_set_variable(&self, statement: &Statement) -> crate::Result<QueryResult> {
+        let Statement::SetVariable { value, .. } = statement else {
+            unreachable!("execute_set_variable called with {:?}", statement);
+        };
+        let name = Self::set_variable_name(statement).unwrap_or_default();
+        if !Self::is_session_setting(statement) {
+            let Statement::SetVariable {
+                variables: OneOrManyWithParens::One(name),
+                ..
+            } = statement
+            else {
+                return Err(YamlBaseError::NotImplemented(
+                    "Only one parameter can be set at a time".to_string(),
+                ));
+            };
+            self.execute_set_parameter(name, value)?;
+            return Ok(QueryResult {
+                columns: vec![],
+                column_types: vec![],
+                rows: vec![],
+                affected_rows: None,
+            });
+        }
+        if name == SEARCH_PATH_SETTING {
+            self.execute_set_search_path(value)?;
+            return Ok(QueryResult {
+                columns: vec![],
+                column_types: vec![],
+                rows: vec![],
+                affected_rows: None,
+            });
+        }
+        let limit = match value.as_slice() {
+            [expr] => self.evaluate_constant_expr(expr)?,
+            _ => Value::Null,
+        };
+        let Value::Integer(limit @ 1..) = limit else {
+            return Err(YamlBaseError::Database {
+                message: format!("{} must be a positive integer", name),
+            });
+        };
+        let limit = limit as usize;
+
+        let mut limits = self
+            .recursion_limits
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if name == RecursionLimits::MAX_ITERATIONS_SETTING {
+            limits.max_iterations = limit;
+        } else {
+            limits.max_rows = limit;
+        }
+        Ok(QueryResult {
+            columns: vec![],
+            column_types: vec![],
+            rows: vec![],
+            affected_rows: None,
+        })
+    }
+
+    /// `RELOAD DATABASE`: load the database files again and swap them in, or fail with the
+    /// problems that kept the previous snapshot
+    pub async fn reload_database(&self) -> crate::Result<()> {
+        if self.grants.is_some() {
+            return Err(YamlBaseError::PermissionDenied(format!(
+                "database {}",
+                self.storage.name()
+            )));
+        }
+        let status = self.storage.request_reload().await?;
+        match status.outcome {
+            ReloadOutcome::Rejected => Err(YamlBaseError::Database {
+                message: format!("Reload {}: {}", status.outcome, status.detail),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// `SHOW RELOAD STATUS`, the admin query for whether the last hot reload was applied
+    fn is_reload_status(variable: &[sqlparser::ast::Ident]) -> bool {
+        matches!(variable, [first, second]
+            if first.value.eq_ignore_ascii_case("RELOAD")
+                && second.value.eq_ignore_ascii_case("STATUS"))
+    }
+
+    fn show_reload_status(&self) -> QueryResult {
+        let status = self.storage.reload_status();
+        QueryResult {
+            columns: vec!["status".to_string(), "at".to_string(), "detail".to_string()],
+            column_types: vec![
+                crate::yaml::schema::SqlType::Text,
+                crate::yaml::schema::SqlType::Timestamp,
+                crate::yaml::schema::SqlType::Text,
+            ],
+            rows: vec![vec![
+                Value::Text(status.outcome.to_string()),
+                Value::Timestamp(status.at),
+                Value::Text(status.detail),
+            ]],
+            affected_rows: None,
+        }
+    }
+
     pub fn storage(&self) -> &Arc<Storage> {
         &self.storage
     }
 
+    /// The tables this connection changed with INSERT, UPDATE or DELETE
+    pub fn session(&self) -> &SessionOverlay {
+        &self.session
+    }
+
+    pub fn write_mode(&self) -> &WriteMode {
+        &self.write_mode
+    }
+
+    pub(crate) fn transaction(&self) -> &Mutex<Option<Transaction>> {
+        &self.transaction
+    }
+
+    pub(crate) fn generated_ids(&self) -> &Mutex<GeneratedIds> {
+        &self.generated_ids
+    }
+
+    pub(crate) fn listening(&self) -> std::sync::MutexGuard<'_, HashSet<String>> {
+        self.listening
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Share the settings of another session of the same connection, as a MySQL connection
+    /// keeps its variables when it switches databases
+    pub fn with_settings_of(mut self, other: &QueryExecutor) -> Self {
+        self.settings = Arc::clone(&other.settings);
+        self
+    }
+
+    /// The parameters the connection set
+    pub(crate) fn settings(&self) -> std::sync::MutexGuard<'_, SessionSettings> {
+        self.settings
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Execute a statement whose `$1`, `$2`, ... placeholders take their values from
+    /// `parameters`. `?` placeholders are numbered in order by `parse_sql`.
+    pub async fn execute_with_parameters(
+        &self,
+        statement: &Statement,
+        parameters: &[Value],
+    ) -> crate::Result<QueryResult> {
+        BIND_PARAMETERS
+            .scope(Arc::new(parameters.to_vec()), self.execute(statement))
+            .await
+    }
+
     pub async fn execute(&self, statement: &Statement) -> crate::Result<QueryResult> {
+        let resolved = self.resolve_table_names(statement).await;
+        let statement = resolved.as_ref().unwrap_or(statement);
         // Wrap execution with timeout to handle client-reported timeout issues
+        if let Some(quota) = &self.quota {
+            quota.start_statement()?;
+        }
         let execution_future = async {
+            self.check_transaction_usable(statement)?;
+            self.check_permissions(statement).await?;
             match statement {
                 Statement::Query(query) => self.execute_query(query).await,
-                Statement::StartTransaction { .. }
-                | Statement::Commit { .. }
-                | Statement::Rollback { .. } => {
-                    // Return empty result for transaction commands (no-op in read-only mode)
+                Statement::Insert(_)
+                | Statement::Update { .. }
+                | Statement::Delete(_)
+                | Statement::Merge { .. } => self.execute_write(statement).await,
+                Statement::CreateTable(create) => self.execute_create_table(create).await,
+                Statement::Drop { .. } => self.execute_drop(statement),
+                Statement::SetVariable { .. } => self.execute_set_variable(statement),
+                Statement::SetTimeZone { value, .. } => {
+                    self.execute_set_time_zone(value)?;
                     Ok(QueryResult {
                         columns: vec![],
                         column_types: vec![],
                         rows: vec![],
+                        affected_rows: None,
                     })
                 }
+                Statement::LISTEN { channel } => self.execute_listen(channel),
+                Statement::NOTIFY { channel, payload } => {
+                    self.execute_notify(channel, payload.as_deref())
+                }
+                Statement::ShowVariable { variable } if Self::is_reload_status(variable) => {
+                    Ok(self.show_reload_status())
+                }
+                Statement::ShowVariable { variable } if Self::is_show_index(variable) => {
+                    self.execute_show_index(variable).await
+                }
+                Statement::ShowVariable { variable } => self.execute_show(variable),
+                Statement::ShowVariables { filter, .. } => {
+                    self.execute_show_variables(filter.as_ref()).await
+                }
+                Statement::ShowDatabases { filter } | Statement::ShowSchemas { filter } => {
+                    self.execute_show_databases(filter.as_ref()).await
+                }
+                Statement::ShowTables {
+                    full,
+                    db_name,
+                    filter,
+                    ..
+                } => {
+                    self.execute_show_tables(*full, db_name.as_ref(), filter.as_ref())
+                        .await
+                }
+                Statement::ShowColumns {
+                    full,
+                    table_name,
+                    filter,
+                    ..
+                } => {
+                    self.execute_show_columns(*full, table_name, filter.as_ref())
+                        .await
+                }
+                // MySQL's DESCRIBE, DESC and EXPLAIN of a table are SHOW COLUMNS
+                Statement::ExplainTable { table_name, .. } => {
+                    self.execute_show_columns(false, table_name, None).await
+                }
+                Statement::StartTransaction { .. }
+                | Statement::Commit { .. }
+                | Statement::Rollback { .. }
+                | Statement::Savepoint { .. }
+                | Statement::ReleaseSavepoint { .. } => {
+                    self.execute_transaction_statement(statement).await
+                }
                 _ => Err(YamlBaseError::NotImplemented(
-                    "Only SELECT queries are supported".to_string(),
+                    "Only SELECT, INSERT, UPDATE, DELETE, MERGE, CREATE TABLE and DROP TABLE \
+                     statements are supported"
+                        .to_string(),
                 )),
             }
         };
 
-        // Apply timeout to prevent client-reported connection timeout issues
-        match tokio::time::timeout(self.query_timeout, execution_future).await {
-            Ok(result) => result,
-            Err(_) => Err(YamlBaseError::Database {
-                message: format!(
-                    "Query execution timeout after {} seconds. Consider optimizing your query or increasing timeout limit.",
-                    self.query_timeout.as_secs()
-                ),
-            }),
+        // Apply timeout to prevent client-reported connection timeout issues, and stop
+        // early when another connection cancels the statement
+        let execution_future =
+            TimeZone::in_session_zone(self.session_time_zone(), execution_future);
+        // SET statement_timeout overrides --statement-timeout; zero is no limit
+        let query_timeout = self
+            .session_statement_timeout()
+            .unwrap_or(self.query_timeout);
+        let execution_future = async {
+            if query_timeout.is_zero() {
+                return execution_future.await;
+            }
+            tokio::time::timeout(query_timeout, execution_future)
+                .await
+                .unwrap_or_else(|_| {
+                    Err(YamlBaseError::Database {
+                        message: format!(
+                            "Query execution timeout after {}. Consider optimizing your query or increasing timeout limit.",
+                            humantime::format_duration(query_timeout)
+                        ),
+                    })
+                })
+        };
+        let cancelled = self.cancel_signal.notified();
+        let mut result = tokio::select! {
+            result = execution_future => result,
+            _ = cancelled => Err(YamlBaseError::QueryCancelled),
+        };
+        if let (Some(quota), Ok(result)) = (&self.quota, &result) {
+            quota.add_rows(result.rows.len());
+        }
+        match &mut result {
+            Ok(result) => self.in_session_time_zone(result),
+            Err(_) => self.fail_transaction(),
         }
+        result
     }
 
-    async fn execute_query(&self, query: &Query) -> crate::Result<QueryResult> {
+    pub(crate) async fn execute_query(&self, query: &Query) -> crate::Result<QueryResult> {
         let start_time = std::time::Instant::now();
         let db_arc = self.storage.database();
-        let db = db_arc.read().await;
-
-        // Handle CTEs if present
-        if let Some(with) = &query.with {
-            return self.execute_query_with_ctes(&db, query, with).await;
+        let shared = db_arc.read().await;
+        // A connection that wrote rows reads its own copies of the tables it changed
+        let session = self.session.apply(&shared);
+        let db = session.as_ref().unwrap_or(&*shared);
+        let introspected =
+            Self::reads_information_schema(query).then(|| self.with_information_schema(db));
+        let db = introspected.as_ref().unwrap_or(db);
+
+        // Handle CTEs if present, and nested queries that read the CTEs of an enclosing query
+        let enclosing_ctes = CTE_SCOPE
+            .try_with(Arc::clone)
+            .ok()
+            .filter(|ctes| Self::reads_table(&query.body, &|name| ctes.contains_key(name)));
+        if query.with.is_some() || enclosing_ctes.is_some() {
+            return self
+                .execute_query_with_ctes(db, query, enclosing_ctes.unwrap_or_default())
+                .await;
         }
 
         let result = match &query.body.as_ref() {
-            SetExpr::Select(select) => self.execute_select(&db, select, query).await,
+            SetExpr::Select(select) => self.execute_select(db, select, query).await,
             SetExpr::SetOperation {
                 op,
                 set_quantifier,
@@ -182,8 +660,11 @@ impl QueryExecutor {
             return self.execute_select_without_from(select).await;
         }
 
-        // Check if query has joins or derived tables
-        if self.has_joins(&select.from) || self.has_derived_tables(&select.from) {
+        // Check if query has joins, derived tables or table functions
+        if self.has_joins(&select.from)
+            || self.has_derived_tables(&select.from)
+            || Self::is_json_table_function(&select.from[0].relation)
+        {
             return self.execute_select_with_joins(db, select, query).await;
         }
 
@@ -213,73 +694,109 @@ impl QueryExecutor {
         // Project columns
         let projected_rows = self.project_columns(&filtered_rows, &columns, table)?;
 
-        // Apply DISTINCT if specified
-        let distinct_rows = if select.distinct.is_some() {
-            self.apply_distinct(projected_rows, &select.distinct, &columns)?
+        // Apply QUALIFY, which filters on window function results
+        let (filtered_rows, mut projected_rows) = if let Some(qualify) = &select.qualify {
+            self.apply_qualify(qualify, filtered_rows, projected_rows, &columns, table)?
         } else {
-            projected_rows
+            (filtered_rows, projected_rows)
         };
 
-        // Apply ORDER BY
+        // DISTINCT ON keys may use columns that are not projected
+        if let Some(Distinct::On(exprs)) = &select.distinct {
+            let column_names: Vec<String> =
+                columns.iter().map(|item| item.name().to_string()).collect();
+            self.append_distinct_on_keys(
+                &mut projected_rows,
+                &filtered_rows,
+                exprs,
+                &column_names,
+                |expr, row| self.get_expr_value(expr, row, table),
+                |expr, resolved| match resolved.map(|idx| &columns[idx]) {
+                    Some(ProjectionItem::TableColumn(_, col_idx)) => {
+                        table.columns[*col_idx].collation.unwrap_or(self.collation)
+                    }
+                    _ => {
+                        self.expr_collation(expr, &|expr| Self::table_column_collation(expr, table))
+                    }
+                },
+            )?;
+        }
+
+        // Apply ORDER BY. Sorting happens before DISTINCT so that DISTINCT ON keeps the first
+        // row of each group; ORDER BY may also reference columns that are not projected.
         let sorted_rows = if let Some(order_by) = &query.order_by {
-            // Convert ProjectionItem to (String, usize) for compatibility with sort_rows
-            let col_info: Vec<(String, usize)> = columns
+            let column_names: Vec<String> =
+                columns.iter().map(|item| item.name().to_string()).collect();
+            let resolved = order_by
+                .exprs
                 .iter()
-                .enumerate()
-                .map(|(idx, item)| match item {
-                    ProjectionItem::TableColumn(name, _) => (name.clone(), idx),
-                    ProjectionItem::Constant(name, _) => (name.clone(), idx),
-                    ProjectionItem::Expression(name, _) => (name.clone(), idx),
-                })
+                .map(|order_expr| self.resolve_order_by_column(&order_expr.expr, &column_names))
+                .collect::<crate::Result<Vec<_>>>()?;
+
+            let mut keys = Vec::with_capacity(projected_rows.len());
+            for (source_row, projected_row) in filtered_rows.iter().zip(&projected_rows) {
+                let mut key = Vec::with_capacity(order_by.exprs.len());
+                for (order_expr, resolved) in order_by.exprs.iter().zip(&resolved) {
+                    key.push(match resolved {
+                        Some(idx) => projected_row[*idx].clone(),
+                        None => self.get_expr_value(&order_expr.expr, source_row, table)?,
+                    });
+                }
+                keys.push(key);
+            }
+            let collations: Vec<Collation> = order_by
+                .exprs
+                .iter()
+                .zip(&resolved)
+                .map(
+                    |(order_expr, resolved)| match resolved.map(|idx| &columns[idx]) {
+                        Some(ProjectionItem::TableColumn(_, col_idx)) => {
+                            table.columns[*col_idx].collation.unwrap_or(self.collation)
+                        }
+                        _ => self.expr_collation(&order_expr.expr, &|expr| {
+                            Self::table_column_collation(expr, table)
+                        }),
+                    },
+                )
                 .collect();
-            self.sort_rows(distinct_rows, &order_by.exprs, &col_info)?
+            Self::sort_rows_by_keys(projected_rows, keys, &order_by.exprs, &collations)
         } else {
-            distinct_rows
+            projected_rows
         };
 
-        // Apply LIMIT and OFFSET
-        let final_rows = if let Some(limit_expr) = &query.limit {
-            self.apply_limit(sorted_rows, limit_expr)?
-        } else {
-            sorted_rows
+        // Apply DISTINCT if specified
+        let sorted_rows = match &select.distinct {
+            Some(Distinct::On(_)) => Self::distinct_on_rows(sorted_rows, columns.len()),
+            Some(_) => {
+                let collations: Vec<Collation> = columns
+                    .iter()
+                    .map(|item| match item {
+                        ProjectionItem::TableColumn(_, col_idx) => {
+                            table.columns[*col_idx].collation.unwrap_or(self.collation)
+                        }
+                        _ => self.collation,
+                    })
+                    .collect();
+                self.apply_distinct(sorted_rows, &select.distinct, &columns, &collations)?
+            }
+            None => sorted_rows,
         };
 
+        // Apply LIMIT and OFFSET
+        let final_rows = self.apply_query_limits(sorted_rows, query)?;
+
         // Get column types
+        let table_column_type = Self::table_column_type(table);
         let column_types = columns
             .iter()
-            .map(|item| {
-                match item {
-                    ProjectionItem::TableColumn(_, idx) => table.columns[*idx].sql_type.clone(),
-                    ProjectionItem::Constant(_, value) => {
-                        // Infer type from value
-                        match value {
-                            Value::Integer(i) => {
-                                if *i > i32::MAX as i64 || *i < i32::MIN as i64 {
-                                    crate::yaml::schema::SqlType::BigInt
-                                } else {
-                                    crate::yaml::schema::SqlType::Integer
-                                }
-                            }
-                            Value::Double(_) | Value::Float(_) => {
-                                crate::yaml::schema::SqlType::Double
-                            }
-                            Value::Boolean(_) => crate::yaml::schema::SqlType::Boolean,
-                            Value::Date(_) => crate::yaml::schema::SqlType::Date,
-                            Value::Time(_) => crate::yaml::schema::SqlType::Time,
-                            Value::Timestamp(_) => crate::yaml::schema::SqlType::Timestamp,
-                            Value::Uuid(_) => crate::yaml::schema::SqlType::Uuid,
-                            Value::Json(_) => crate::yaml::schema::SqlType::Text,
-                            Value::Decimal(_) => crate::yaml::schema::SqlType::Decimal(10, 2),
-                            Value::Text(_) => crate::yaml::schema::SqlType::Text,
-                            Value::Null => crate::yaml::schema::SqlType::Text,
-                        }
-                    }
-                    ProjectionItem::Expression(_, _) => {
-                        // For expressions, default to Text type since we can't easily infer
-                        // This could be improved by analyzing the expression
-                        crate::yaml::schema::SqlType::Text
-                    }
-                }
+            .enumerate()
+            .map(|(position, item)| match item {
+                ProjectionItem::TableColumn(_, idx) => table.columns[*idx].sql_type.clone(),
+                ProjectionItem::Constant(_, _, sql_type) => sql_type.clone(),
+                ProjectionItem::Expression(_, expr) => self.projected_expr_type(
+                    Self::infer_expr_type(expr, &table_column_type),
+                    final_rows.iter().map(|row| &row[position]),
+                ),
             })
             .collect();
 
@@ -288,7 +805,7 @@ impl QueryExecutor {
             .iter()
             .map(|item| match item {
                 ProjectionItem::TableColumn(name, _) => name.clone(),
-                ProjectionItem::Constant(name, _) => name.clone(),
+                ProjectionItem::Constant(name, ..) => name.clone(),
                 ProjectionItem::Expression(name, _) => name.clone(),
             })
             .collect();
@@ -297,6 +814,7 @@ impl QueryExecutor {
             columns: column_names,
             column_types,
             rows: final_rows,
+            affected_rows: None,
         })
     }
 
@@ -310,57 +828,9 @@ impl QueryExecutor {
     ) -> crate::Result<QueryResult> {
         debug!("Executing set operation: {:?}", op);
 
-        // Execute left and right sides by extracting their results directly
-        let db_arc = self.storage.database();
-        let db = db_arc.read().await;
-
-        let left_result = match left {
-            SetExpr::Select(select) => {
-                let left_query = Query {
-                    with: None,
-                    body: Box::new(SetExpr::Select(select.clone())),
-                    order_by: None,
-                    limit: None,
-                    limit_by: vec![],
-                    offset: None,
-                    fetch: None,
-                    locks: vec![],
-                    for_clause: None,
-                    format_clause: None,
-                    settings: None,
-                };
-                self.execute_select(&db, select, &left_query).await?
-            }
-            _ => {
-                return Err(YamlBaseError::NotImplemented(
-                    "Nested set operations not yet supported".to_string(),
-                ));
-            }
-        };
-
-        let right_result = match right {
-            SetExpr::Select(select) => {
-                let right_query = Query {
-                    with: None,
-                    body: Box::new(SetExpr::Select(select.clone())),
-                    order_by: None,
-                    limit: None,
-                    limit_by: vec![],
-                    offset: None,
-                    fetch: None,
-                    locks: vec![],
-                    for_clause: None,
-                    format_clause: None,
-                    settings: None,
-                };
-                self.execute_select(&db, select, &right_query).await?
-            }
-            _ => {
-                return Err(YamlBaseError::NotImplemented(
-                    "Nested set operations not yet supported".to_string(),
-                ));
-            }
-        };
+        // Each side may itself be a set operation or a parenthesized query
+        let left_result = self.execute_set_operand(left).await?;
+        let right_result = self.execute_set_operand(right).await?;
 
         // Check that column counts match
         if left_result.columns.len() != right_result.columns.len() {
@@ -398,18 +868,41 @@ impl QueryExecutor {
             result_rows = self.sort_rows(result_rows, &order_by.exprs, &col_info)?;
         }
 
-        // Apply LIMIT if present
-        if let Some(limit_expr) = &query.limit {
-            result_rows = self.apply_limit(result_rows, limit_expr)?;
-        }
+        // Apply LIMIT/OFFSET if present
+        result_rows = self.apply_query_limits(result_rows, query)?;
 
         Ok(QueryResult {
             columns: left_result.columns,
             column_types: left_result.column_types,
             rows: result_rows,
+            affected_rows: None,
         })
     }
 
+    /// Execute one side of a set operation. A parenthesized side keeps its own ORDER BY and
+    /// LIMIT; CTEs of an enclosing query are picked up from the CTE scope.
+    async fn execute_set_operand(&self, operand: &SetExpr) -> crate::Result<QueryResult> {
+        match operand {
+            SetExpr::Query(query) => Box::pin(self.execute_query(query)).await,
+            _ => {
+                let operand_query = Query {
+                    with: None,
+                    body: Box::new(operand.clone()),
+                    order_by: None,
+                    limit: None,
+                    limit_by: vec![],
+                    offset: None,
+                    fetch: None,
+                    locks: vec![],
+                    for_clause: None,
+                    format_clause: None,
+                    settings: None,
+                };
+                Box::pin(self.execute_query(&operand_query)).await
+            }
+        }
+    }
+
     async fn execute_select_without_from(&self, select: &Select) -> crate::Result<QueryResult> {
         debug!("Executing SELECT without FROM");
         let mut columns = Vec::new();
@@ -438,30 +931,15 @@ impl QueryExecutor {
             }
         }
 
-        // Infer types from the values
-        let column_types = row_values
+        let column_types = select
+            .projection
             .iter()
-            .map(|value| {
-                match value {
-                    Value::Integer(i) => {
-                        // Use BigInt for values that might be larger than i32
-                        if *i > i32::MAX as i64 || *i < i32::MIN as i64 {
-                            crate::yaml::schema::SqlType::BigInt
-                        } else {
-                            crate::yaml::schema::SqlType::Integer
-                        }
-                    }
-                    Value::Double(_) | Value::Float(_) => crate::yaml::schema::SqlType::Double,
-                    Value::Boolean(_) => crate::yaml::schema::SqlType::Boolean,
-                    Value::Date(_) => crate::yaml::schema::SqlType::Date,
-                    Value::Time(_) => crate::yaml::schema::SqlType::Time,
-                    Value::Timestamp(_) => crate::yaml::schema::SqlType::Timestamp,
-                    Value::Uuid(_) => crate::yaml::schema::SqlType::Uuid,
-                    Value::Json(_) => crate::yaml::schema::SqlType::Text,
-                    Value::Decimal(_) => crate::yaml::schema::SqlType::Decimal(10, 2),
-                    Value::Text(_) => crate::yaml::schema::SqlType::Text,
-                    Value::Null => crate::yaml::schema::SqlType::Text,
+            .zip(&row_values)
+            .map(|(item, value)| match item {
+                SelectItem::UnnamedExpr(expr) | SelectItem::ExprWithAlias { expr, .. } => {
+                    self.constant_expr_type(expr, value)
                 }
+                _ => self.constant_value_type(value),
             })
             .collect();
 
@@ -469,6 +947,7 @@ impl QueryExecutor {
             columns: columns.clone(),
             column_types,
             rows: vec![row_values],
+            affected_rows: None,
         };
         debug!(
             "SELECT without FROM complete. Columns: {:?}, Rows: {:?}",
@@ -477,7 +956,7 @@ impl QueryExecutor {
         Ok(result)
     }
 
-    fn evaluate_constant_expr(&self, expr: &Expr) -> crate::Result<Value> {
+    pub(crate) fn evaluate_constant_expr(&self, expr: &Expr) -> crate::Result<Value> {
         debug!("Evaluating constant expression: {:?}", expr);
         match expr {
             Expr::Value(val) => {
@@ -505,6 +984,7 @@ impl QueryExecutor {
                     match val {
                         Value::Integer(i) => Ok(Value::Integer(-i)),
                         Value::Double(d) => Ok(Value::Double(-d)),
+                        Value::Decimal(d) => Ok(Value::Decimal(-d)),
                         _ => Err(YamlBaseError::Database {
                             message: "Cannot negate non-numeric value".to_string(),
                         }),
@@ -515,6 +995,11 @@ impl QueryExecutor {
                 )),
             },
             Expr::BinaryOp { left, op, right } => {
+                if let Some((operand, interval, subtract)) = Self::interval_operand(left, op, right)
+                {
+                    let value = self.evaluate_constant_expr(operand)?;
+                    return self.apply_interval(&value, interval, subtract);
+                }
                 let left_val = self.evaluate_constant_expr(left)?;
                 let right_val = self.evaluate_constant_expr(right)?;
                 self.evaluate_binary_op_constant(&left_val, op, &right_val)
@@ -535,6 +1020,9 @@ impl QueryExecutor {
                         // Return as text for now, as we handle dates as strings
                         Ok(Value::Text(value.clone()))
                     }
+                    DataType::Timestamp(_, TimezoneInfo::WithTimeZone | TimezoneInfo::Tz) => {
+                        self.cast_value(Value::Text(value.clone()), data_type)
+                    }
                     _ => Ok(Value::Text(value.clone())),
                 }
             }
@@ -619,6 +1107,7 @@ impl QueryExecutor {
                     Value::Integer(i) => Ok(Value::Integer(i)),
                     Value::Double(d) => Ok(Value::Double(d.floor())),
                     Value::Float(f) => Ok(Value::Float(f.floor())),
+                    Value::Decimal(d) => Ok(Value::Decimal(d.floor())),
                     Value::Null => Ok(Value::Null),
                     _ => Err(YamlBaseError::Database {
                         message: "FLOOR requires numeric argument".to_string(),
@@ -631,18 +1120,44 @@ impl QueryExecutor {
                     Value::Integer(i) => Ok(Value::Integer(i)),
                     Value::Double(d) => Ok(Value::Double(d.ceil())),
                     Value::Float(f) => Ok(Value::Float(f.ceil())),
+                    Value::Decimal(d) => Ok(Value::Decimal(d.ceil())),
                     Value::Null => Ok(Value::Null),
                     _ => Err(YamlBaseError::Database {
                         message: "CEIL requires numeric argument".to_string(),
                     }),
                 }
             }
+            Expr::Interval(interval) => {
+                Ok(Value::Text(self.evaluate_interval(interval)?.to_string()))
+            }
             Expr::Cast {
-                expr, data_type, ..
+                kind,
+                expr,
+                data_type,
+                ..
             } => {
                 // Handle CAST expression
                 let value = self.evaluate_constant_expr(expr)?;
-                self.cast_value(value, data_type)
+                self.cast_with_kind(value, data_type, kind)
+            }
+            Expr::Array(array) => array
+                .elem
+                .iter()
+                .map(|elem| self.evaluate_constant_expr(elem))
+                .collect::<crate::Result<_>>()
+                .map(Value::Array),
+            Expr::AtTimeZone {
+                timestamp,
+                time_zone,
+            } => Self::at_time_zone(
+                self.evaluate_constant_expr(timestamp)?,
+                self.evaluate_constant_expr(time_zone)?,
+            ),
+            Expr::Nested(inner) => self.evaluate_constant_expr(inner),
+            Expr::Subscript { expr, subscript } => {
+                Self::array_subscript(self.evaluate_constant_expr(expr)?, subscript, |expr| {
+                    self.evaluate_constant_expr(expr)
+                })
             }
             _ => {
                 debug!(
@@ -662,6 +1177,16 @@ impl QueryExecutor {
         op: &BinaryOperator,
         right: &Value,
     ) -> crate::Result<Value> {
+        if let Some(result) = Self::decimal_arithmetic(left, op, right) {
+            return result;
+        }
+        if let Some(result) = Self::evaluate_regex_operator(left, op, right) {
+            return result;
+        }
+        if let Some(result) = Self::evaluate_json_operator(left, op, right) {
+            return result;
+        }
+
         match op {
             BinaryOperator::Plus => match (left, right) {
                 (Value::Integer(a), Value::Integer(b)) => Ok(Value::Integer(a + b)),
@@ -768,6 +1293,7 @@ impl QueryExecutor {
                     Ok(Value::Boolean(false))
                 }
             }
+            BinaryOperator::StringConcat => self.safe_string_concat(left, right),
             _ => Err(YamlBaseError::NotImplemented(
                 "Binary operator not supported in constant expressions".to_string(),
             )),
@@ -837,6 +1363,12 @@ impl QueryExecutor {
     ) -> crate::Result<QueryResult> {
         debug!("Executing SELECT with JOINs");
 
+        if select.qualify.is_some() {
+            return Err(YamlBaseError::NotImplemented(
+                "QUALIFY is not supported with JOINs yet".to_string(),
+            ));
+        }
+
         // First pass: Execute all derived tables and check for real tables
         let mut derived_tables = std::collections::HashMap::<String, Table>::new();
         let mut table_refs = Vec::new(); // (identifier, is_derived_table)
@@ -845,6 +1377,12 @@ impl QueryExecutor {
         for table_with_joins in &select.from {
             // Handle the main table/subquery
             match &table_with_joins.relation {
+                relation if Self::is_json_table_function(relation) => {
+                    let function = Self::json_table_function(relation)?;
+                    let (alias_name, table) = Self::json_table_function_schema(&function)?;
+                    derived_tables.insert(alias_name.clone(), table);
+                    table_refs.push((alias_name, true));
+                }
                 TableFactor::Table { name, alias, .. } => {
                     let table_name = name
                         .0
@@ -903,6 +1441,12 @@ impl QueryExecutor {
             // Handle joined tables
             for join in &table_with_joins.joins {
                 match &join.relation {
+                    relation if Self::is_json_table_function(relation) => {
+                        let function = Self::json_table_function(relation)?;
+                        let (alias_name, table) = Self::json_table_function_schema(&function)?;
+                        derived_tables.insert(alias_name.clone(), table);
+                        table_refs.push((alias_name, true));
+                    }
                     TableFactor::Table { name, alias, .. } => {
                         let table_name = name
                             .0
@@ -1003,47 +1547,98 @@ impl QueryExecutor {
             self.filter_joined_rows(&joined_rows, &select.selection, &all_tables, &table_aliases)?;
 
         // Project columns
-        let projected_rows = self.project_joined_columns(&filtered_rows, &columns, &all_tables)?;
+        let mut projected_rows =
+            self.project_joined_columns(&filtered_rows, &columns, &all_tables)?;
+
+        // DISTINCT ON keys may use columns that are not projected
+        if let Some(Distinct::On(exprs)) = &select.distinct {
+            let column_names: Vec<String> = columns.iter().map(|col| col.get_name()).collect();
+            self.append_distinct_on_keys(
+                &mut projected_rows,
+                &filtered_rows,
+                exprs,
+                &column_names,
+                |expr, row| self.get_join_expr_value(expr, row, &all_tables, &table_aliases),
+                |expr, resolved| match resolved.map(|idx| &columns[idx]) {
+                    Some(JoinedColumn::TableColumn(_, table_idx, col_idx)) => {
+                        all_tables[*table_idx].1.columns[*col_idx]
+                            .collation
+                            .unwrap_or(self.collation)
+                    }
+                    _ => self.expr_collation(expr, &|expr| {
+                        Self::joined_column_collation(expr, &all_tables)
+                    }),
+                },
+            )?;
+        }
 
-        // Apply DISTINCT if specified
-        let distinct_rows = if select.distinct.is_some() {
-            // Convert JoinedColumn to ProjectionItem for compatibility
-            let projection_items: Vec<ProjectionItem> = columns
-                .iter()
-                .enumerate()
-                .map(|(idx, col)| match col {
-                    JoinedColumn::TableColumn(name, _, _) => {
-                        ProjectionItem::TableColumn(name.clone(), idx)
-                    }
-                    JoinedColumn::Expression(name, _) => {
-                        ProjectionItem::TableColumn(name.clone(), idx)
-                    }
-                })
-                .collect();
+        // Convert JoinedColumn to ProjectionItem for compatibility with apply_distinct
+        let projection_items: Vec<ProjectionItem> = columns
+            .iter()
+            .enumerate()
+            .map(|(idx, col)| match col {
+                JoinedColumn::TableColumn(name, _, _) => {
+                    ProjectionItem::TableColumn(name.clone(), idx)
+                }
+                JoinedColumn::Expression(name, _) => ProjectionItem::TableColumn(name.clone(), idx),
+            })
+            .collect();
 
-            self.apply_distinct(projected_rows, &select.distinct, &projection_items)?
+        // Apply ORDER BY before DISTINCT so that DISTINCT ON keeps the first row of each group
+        let sorted_rows = if let Some(order_by) = &query.order_by {
+            self.sort_joined_rows(
+                projected_rows,
+                &order_by.exprs,
+                &columns,
+                &filtered_rows,
+                &all_tables,
+                &table_aliases,
+            )?
         } else {
             projected_rows
         };
 
-        // Apply ORDER BY
-        let sorted_rows = if let Some(order_by) = &query.order_by {
-            self.sort_joined_rows(distinct_rows, &order_by.exprs, &columns)?
-        } else {
-            distinct_rows
+        // Apply DISTINCT if specified
+        let sorted_rows = match &select.distinct {
+            Some(Distinct::On(_)) => Self::distinct_on_rows(sorted_rows, columns.len()),
+            Some(_) => {
+                let collations: Vec<Collation> = columns
+                    .iter()
+                    .map(|col| match col {
+                        JoinedColumn::TableColumn(_, table_idx, col_idx) => {
+                            all_tables[*table_idx].1.columns[*col_idx]
+                                .collation
+                                .unwrap_or(self.collation)
+                        }
+                        JoinedColumn::Expression(..) => self.collation,
+                    })
+                    .collect();
+                self.apply_distinct(
+                    sorted_rows,
+                    &select.distinct,
+                    &projection_items,
+                    &collations,
+                )?
+            }
+            None => sorted_rows,
         };
 
         // Apply LIMIT and OFFSET
-        let final_rows = if let Some(limit_expr) = &query.limit {
-            self.apply_limit(sorted_rows, limit_expr)?
-        } else {
-            sorted_rows
-        };
+        let final_rows = self.apply_query_limits(sorted_rows, query)?;
 
         // Get column types
         let column_types = columns
             .iter()
-            .map(|col| col.get_type(&all_tables))
+            .enumerate()
+            .map(|(position, col)| match col {
+                JoinedColumn::TableColumn(..) => col
+                    .get_type(&all_tables)
+                    .unwrap_or(crate::yaml::schema::SqlType::Text),
+                JoinedColumn::Expression(..) => self.projected_expr_type(
+                    col.get_type(&all_tables),
+                    final_rows.iter().map(|row| &row[position]),
+                ),
+            })
             .collect();
 
         let column_names = columns.iter().map(|col| col.get_name()).collect();
@@ -1052,6 +1647,7 @@ impl QueryExecutor {
             columns: column_names,
             column_types,
             rows: final_rows,
+            affected_rows: None,
         })
     }
 
@@ -1136,7 +1732,10 @@ impl QueryExecutor {
                                     Ok(value) => {
                                         let col_name = format!("column_{}", column_counter);
                                         column_counter += 1;
-                                        columns.push(ProjectionItem::Constant(col_name, value));
+                                        let sql_type = self.constant_expr_type(expr, &value);
+                                        columns.push(ProjectionItem::Constant(
+                                            col_name, value, sql_type,
+                                        ));
                                     }
                                     Err(_) => {
                                         // If constant evaluation fails, treat it as an expression
@@ -1214,9 +1813,11 @@ impl QueryExecutor {
                                 // Constant expression with alias
                                 match self.evaluate_constant_expr(expr) {
                                     Ok(value) => {
+                                        let sql_type = self.constant_expr_type(expr, &value);
                                         columns.push(ProjectionItem::Constant(
                                             alias.value.clone(),
                                             value,
+                                            sql_type,
                                         ));
                                     }
                                     Err(_) => {
@@ -1269,8 +1870,11 @@ impl QueryExecutor {
         table_name: &str,
         selection: &Option<Expr>,
     ) -> crate::Result<Vec<&'a Vec<Value>>> {
-        // Check if this is a simple primary key lookup
-        if let Some(pk_value) = self.extract_primary_key_lookup(selection, table) {
+        // Check if this is a simple primary key lookup. The index only covers the shared
+        // tables, not the copies this connection wrote to.
+        if !self.session.contains(table_name)
+            && let Some(pk_value) = self.extract_primary_key_lookup(selection, table)
+        {
             debug!("Using primary key index for lookup: {:?}", pk_value);
 
             // Use the index for O(1) lookup
@@ -1311,31 +1915,38 @@ impl QueryExecutor {
     fn extract_primary_key_lookup(&self, selection: &Option<Expr>, table: &Table) -> Option<Value> {
         let where_expr = selection.as_ref()?;
 
-        // Check if we have a primary key
+        // Check if we have a primary key. The index matches text exactly, so a
+        // case-insensitive key has to be scanned.
         let pk_idx = table.primary_key_index?;
         let pk_column = &table.columns[pk_idx].name;
+        if table.columns[pk_idx]
+            .collation
+            .unwrap_or(self.collation)
+            .is_case_insensitive()
+        {
+            return None;
+        }
 
         // Look for simple equality: WHERE primary_key = value
-        if let Expr::BinaryOp { left, op, right } = where_expr {
-            if matches!(op, BinaryOperator::Eq) {
-                // Check if left side is the primary key column
-                if let Expr::Identifier(ident) = left.as_ref() {
-                    if ident.value.to_lowercase() == pk_column.to_lowercase() {
-                        // Extract the value from the right side
-                        if let Expr::Value(sql_value) = right.as_ref() {
-                            return self.sql_value_to_db_value(sql_value).ok();
-                        }
-                    }
-                }
-                // Also check if right side is the column (value = primary_key)
-                if let Expr::Identifier(ident) = right.as_ref() {
-                    if ident.value.to_lowercase() == pk_column.to_lowercase() {
-                        if let Expr::Value(sql_value) = left.as_ref() {
-                            return self.sql_value_to_db_value(sql_value).ok();
-                        }
-                    }
+        if let Expr::BinaryOp { left, op, right } = where_expr
+            && matches!(op, BinaryOperator::Eq)
+        {
+            // Check if left side is the primary key column
+            if let Expr::Identifier(ident) = left.as_ref()
+                && ident.value.to_lowercase() == pk_column.to_lowercase()
+            {
+                // Extract the value from the right side
+                if let Expr::Value(sql_value) = right.as_ref() {
+                    return self.sql_value_to_db_value(sql_value).ok();
                 }
             }
+            // Also check if right side is the column (value = primary_key)
+            if let Expr::Identifier(ident) = right.as_ref()
+                && ident.value.to_lowercase() == pk_column.to_lowercase()
+                && let Expr::Value(sql_value) = left.as_ref()
+            {
+                return self.sql_value_to_db_value(sql_value).ok();
+            }
         }
 
         None
@@ -1371,6 +1982,27 @@ impl QueryExecutor {
                 );
                 self.evaluate_like(expr, pattern, *negated, row, table)
             }
+            // MySQL REGEXP / RLIKE, case-insensitive like MySQL's default collation
+            Expr::RLike {
+                expr,
+                pattern,
+                negated,
+                ..
+            } => {
+                let value = self.get_expr_value(expr, row, table)?;
+                let pattern = self.get_expr_value(pattern, row, table)?;
+                let result = Self::regex_matches(&value, &pattern, true, *negated)?;
+                Ok(matches!(result, Value::Boolean(true)))
+            }
+            // Boolean functions such as REGEXP_LIKE, and casts such as `active::boolean`,
+            // used as a predicate
+            Expr::Function(_) | Expr::Cast { .. } => match self.get_expr_value(expr, row, table)? {
+                Value::Boolean(b) => Ok(b),
+                Value::Null => Ok(false),
+                other => Err(YamlBaseError::Database {
+                    message: format!("Expected a boolean condition, got {:?}", other),
+                }),
+            },
             Expr::IsNull(expr) => {
                 debug!("Found IsNull expression: expr={:?}", expr);
                 let value = self.get_expr_value(expr, row, table)?;
@@ -1412,6 +2044,28 @@ impl QueryExecutor {
                 );
                 self.evaluate_in_subquery(expr, subquery, *negated, row, table)
             }
+            Expr::AnyOp {
+                left,
+                compare_op,
+                right,
+                ..
+            }
+            | Expr::AllOp {
+                left,
+                compare_op,
+                right,
+            } => {
+                let value = self.get_expr_value(left, row, table)?;
+                let others =
+                    self.quantified_values(right, |right| self.get_expr_value(right, row, table))?;
+                let all = matches!(expr, Expr::AllOp { .. });
+                Ok(self.evaluate_quantified_comparison(
+                    &value,
+                    compare_op,
+                    others.as_deref(),
+                    all,
+                )? == Some(true))
+            }
             _ => Err(YamlBaseError::NotImplemented(format!(
                 "Expression type not supported: {:?}",
                 expr
@@ -1419,7 +2073,7 @@ impl QueryExecutor {
         }
     }
 
-    fn evaluate_expr_async<'a>(
+    pub(crate) fn evaluate_expr_async<'a>(
         &'a self,
         expr: &'a Expr,
         row: &'a [Value],
@@ -1457,6 +2111,26 @@ impl QueryExecutor {
                     );
                     self.evaluate_like(expr, pattern, *negated, row, table)
                 }
+                Expr::RLike {
+                    expr,
+                    pattern,
+                    negated,
+                    ..
+                } => {
+                    let value = self.get_expr_value_async(expr, row, table).await?;
+                    let pattern = self.get_expr_value_async(pattern, row, table).await?;
+                    let result = Self::regex_matches(&value, &pattern, true, *negated)?;
+                    Ok(matches!(result, Value::Boolean(true)))
+                }
+                Expr::Function(_) | Expr::Cast { .. } => {
+                    match self.get_expr_value_async(expr, row, table).await? {
+                        Value::Boolean(b) => Ok(b),
+                        Value::Null => Ok(false),
+                        other => Err(YamlBaseError::Database {
+                            message: format!("Expected a boolean condition, got {:?}", other),
+                        }),
+                    }
+                }
                 Expr::IsNull(expr) => {
                     debug!("Found IsNull expression: expr={:?}", expr);
                     let value = self.get_expr_value_async(expr, row, table).await?;
@@ -1501,6 +2175,34 @@ impl QueryExecutor {
                     self.evaluate_in_subquery_async(expr, subquery, *negated, row, table)
                         .await
                 }
+                Expr::AnyOp {
+                    left,
+                    compare_op,
+                    right,
+                    ..
+                }
+                | Expr::AllOp {
+                    left,
+                    compare_op,
+                    right,
+                } => {
+                    let value = self.get_expr_value_async(left, row, table).await?;
+                    let others = match Self::quantified_subquery(right) {
+                        Some(query) => {
+                            Some(Self::quantified_rows(self.execute_query(query).await?)?)
+                        }
+                        None => Self::quantified_array(
+                            self.get_expr_value_async(right, row, table).await?,
+                        )?,
+                    };
+                    let all = matches!(expr, Expr::AllOp { .. });
+                    Ok(self.evaluate_quantified_comparison(
+                        &value,
+                        compare_op,
+                        others.as_deref(),
+                        all,
+                    )? == Some(true))
+                }
                 _ => Err(YamlBaseError::NotImplemented(format!(
                     "Expression type not supported: {:?}",
                     expr
@@ -1532,15 +2234,15 @@ impl QueryExecutor {
 
         // Type conversion: if comparing dates with text, try to parse text as date
         if matches!(value, Value::Date(_)) {
-            if let Value::Text(s) = &low_value {
-                if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
-                    low_value = Value::Date(date);
-                }
+            if let Value::Text(s) = &low_value
+                && let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d")
+            {
+                low_value = Value::Date(date);
             }
-            if let Value::Text(s) = &high_value {
-                if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
-                    high_value = Value::Date(date);
-                }
+            if let Value::Text(s) = &high_value
+                && let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d")
+            {
+                high_value = Value::Date(date);
             }
         }
 
@@ -1579,15 +2281,15 @@ impl QueryExecutor {
 
         // Type conversion: if comparing dates with text, try to parse text as date
         if matches!(value, Value::Date(_)) {
-            if let Value::Text(s) = &low_value {
-                if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
-                    low_value = Value::Date(date);
-                }
+            if let Value::Text(s) = &low_value
+                && let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d")
+            {
+                low_value = Value::Date(date);
             }
-            if let Value::Text(s) = &high_value {
-                if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
-                    high_value = Value::Date(date);
-                }
+            if let Value::Text(s) = &high_value
+                && let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d")
+            {
+                high_value = Value::Date(date);
             }
         }
 
@@ -1657,20 +2359,47 @@ impl QueryExecutor {
     fn evaluate_exists_subquery(&self, subquery: &Query, negated: bool) -> crate::Result<bool> {
         debug!("Evaluating EXISTS subquery: negated={}", negated);
 
-        // Handle sync/async bridge for subquery execution
+        let result = self.execute_query_blocking(subquery)?;
+
+        let exists = !result.rows.is_empty();
+        debug!(
+            "EXISTS subquery returned {} rows, exists={}",
+            result.rows.len(),
+            exists
+        );
+
+        Ok(if negated { !exists } else { exists })
+    }
+
+    /// Run a subquery from synchronous evaluation code. The query runs to completion on
+    /// its own thread and runtime, inside the CTE scope and with the bound parameters of
+    /// the calling query.
+    fn execute_query_blocking(&self, subquery: &Query) -> crate::Result<QueryResult> {
         let executor_clone = self.clone();
         let subquery_clone = subquery.clone();
+        let cte_scope = CTE_SCOPE.try_with(Arc::clone).ok();
+        let parameters = BIND_PARAMETERS.try_with(Arc::clone).ok();
+        let run = async move {
+            let query = executor_clone.execute_query(&subquery_clone);
+            let query = async move {
+                match cte_scope {
+                    Some(ctes) => CTE_SCOPE.scope(ctes, query).await,
+                    None => query.await,
+                }
+            };
+            match parameters {
+                Some(parameters) => BIND_PARAMETERS.scope(parameters, query).await,
+                None => query.await,
+            }
+        };
 
-        let result = if let Ok(handle) = tokio::runtime::Handle::try_current() {
-            // We're in a tokio runtime context - use spawn_blocking
+        if tokio::runtime::Handle::try_current().is_ok() {
+            // We're in a tokio runtime context - use separate thread
             let (tx, rx) = std::sync::mpsc::channel();
-            let _handle_clone = handle.clone();
 
             std::thread::spawn(move || {
                 let rt = tokio::runtime::Runtime::new().unwrap();
-                let result =
-                    rt.block_on(async { executor_clone.execute_query(&subquery_clone).await });
-                tx.send(result).unwrap();
+                tx.send(rt.block_on(run)).unwrap();
             });
 
             rx.recv().map_err(|_| YamlBaseError::Database {
@@ -1681,17 +2410,8 @@ impl QueryExecutor {
             let rt = tokio::runtime::Runtime::new().map_err(|_| YamlBaseError::Database {
                 message: "Failed to create tokio runtime".to_string(),
             })?;
-            rt.block_on(async { executor_clone.execute_query(&subquery_clone).await })
-        }?;
-
-        let exists = !result.rows.is_empty();
-        debug!(
-            "EXISTS subquery returned {} rows, exists={}",
-            result.rows.len(),
-            exists
-        );
-
-        Ok(if negated { !exists } else { exists })
+            rt.block_on(run)
+        }
     }
 
     async fn evaluate_in_subquery_async(
@@ -1740,35 +2460,11 @@ impl QueryExecutor {
         debug!(
             "Evaluating IN subquery: expr={:?}, negated={}",
             expr, negated
-        );
-
-        let target_value = self.get_expr_value(expr, row, table)?;
-
-        // Handle sync/async bridge for subquery execution
-        let executor_clone = self.clone();
-        let subquery_clone = subquery.clone();
-
-        let result = if let Ok(_handle) = tokio::runtime::Handle::try_current() {
-            // We're in a tokio runtime context - use separate thread
-            let (tx, rx) = std::sync::mpsc::channel();
-
-            std::thread::spawn(move || {
-                let rt = tokio::runtime::Runtime::new().unwrap();
-                let result =
-                    rt.block_on(async { executor_clone.execute_query(&subquery_clone).await });
-                tx.send(result).unwrap();
-            });
-
-            rx.recv().map_err(|_| YamlBaseError::Database {
-                message: "Failed to receive subquery result".to_string(),
-            })?
-        } else {
-            // Not in tokio context, create runtime
-            let rt = tokio::runtime::Runtime::new().map_err(|_| YamlBaseError::Database {
-                message: "Failed to create tokio runtime".to_string(),
-            })?;
-            rt.block_on(async { executor_clone.execute_query(&subquery_clone).await })
-        }?;
+        );
+
+        let target_value = self.get_expr_value(expr, row, table)?;
+
+        let result = self.execute_query_blocking(subquery)?;
 
         // Check if target_value exists in the first column of subquery results
         let found = result.rows.iter().any(|subquery_row| {
@@ -1787,6 +2483,166 @@ impl QueryExecutor {
         Ok(if negated { !found } else { found })
     }
 
+    /// The value of a scalar subquery: NULL without rows, an error with more than one
+    fn scalar_subquery_value(result: QueryResult) -> crate::Result<Value> {
+        // Scalar subquery should return exactly one row and one column
+        if result.rows.is_empty() {
+            Ok(Value::Null)
+        } else if result.rows.len() == 1 && !result.rows[0].is_empty() {
+            Ok(result.rows[0][0].clone())
+        } else {
+            Err(YamlBaseError::Database {
+                message: format!(
+                    "Scalar subquery returned {} rows, expected 1",
+                    result.rows.len()
+                ),
+            })
+        }
+    }
+
+    /// `array[index]`, counting from 1 as PostgreSQL does, or the slice `array[lower:upper]`.
+    /// An index out of range gives NULL, and a slice keeps the elements within its bounds.
+    fn array_subscript(
+        array: Value,
+        subscript: &sqlparser::ast::Subscript,
+        mut value_of: impl FnMut(&Expr) -> crate::Result<Value>,
+    ) -> crate::Result<Value> {
+        use sqlparser::ast::Subscript;
+
+        let elements = match array {
+            Value::Array(elements) => elements,
+            Value::Null => return Ok(Value::Null),
+            other => {
+                return Err(YamlBaseError::TypeConversion(format!(
+                    "Cannot subscript {}, which is not an array",
+                    other
+                )));
+            }
+        };
+        let mut position = |expr: &Expr| match value_of(expr)? {
+            Value::Integer(position) => Ok(Some(position)),
+            Value::Null => Ok(None),
+            other => Err(YamlBaseError::TypeConversion(format!(
+                "Array subscript must be an integer, got {}",
+                other
+            ))),
+        };
+
+        match subscript {
+            Subscript::Index { index } => Ok(position(index)?
+                .and_then(|index| usize::try_from(index.checked_sub(1)?).ok())
+                .and_then(|index| elements.get(index).cloned())
+                .unwrap_or(Value::Null)),
+            Subscript::Slice {
+                lower_bound,
+                upper_bound,
+                stride: None,
+            } => {
+                let lower = match lower_bound {
+                    Some(bound) => position(bound)?,
+                    None => Some(1),
+                };
+                let upper = match upper_bound {
+                    Some(bound) => position(bound)?,
+                    None => Some(elements.len() as i64),
+                };
+                let (Some(lower), Some(upper)) = (lower, upper) else {
+                    return Ok(Value::Null);
+                };
+                let start = lower.max(1) as usize - 1;
+                let end = upper.clamp(0, elements.len() as i64) as usize;
+                Ok(Value::Array(
+                    elements.get(start..end).unwrap_or_default().to_vec(),
+                ))
+            }
+            Subscript::Slice { .. } => Err(YamlBaseError::NotImplemented(
+                "Array slices with a stride are not supported".to_string(),
+            )),
+        }
+    }
+
+    /// The subquery of `expr op ANY (...)` or `expr op ALL (...)`, if it is one
+    fn quantified_subquery(right: &Expr) -> Option<&Query> {
+        match right {
+            Expr::Subquery(subquery) => Some(subquery),
+            Expr::Nested(inner) => Self::quantified_subquery(inner),
+            _ => None,
+        }
+    }
+
+    /// The values `expr op ANY (...)` or `expr op ALL (...)` compares with: the rows of a
+    /// subquery, or the elements of the array `array_value` evaluates the operand to.
+    /// `None` for a NULL array.
+    fn quantified_values(
+        &self,
+        right: &Expr,
+        array_value: impl FnOnce(&Expr) -> crate::Result<Value>,
+    ) -> crate::Result<Option<Vec<Value>>> {
+        match Self::quantified_subquery(right) {
+            Some(query) => Self::quantified_rows(self.execute_query_blocking(query)?).map(Some),
+            None => Self::quantified_array(array_value(right)?),
+        }
+    }
+
+    /// The values of a one-column ANY/ALL subquery
+    fn quantified_rows(result: QueryResult) -> crate::Result<Vec<Value>> {
+        if result.columns.len() != 1 {
+            return Err(YamlBaseError::Database {
+                message: format!(
+                    "ANY/ALL subquery must return one column, got {}",
+                    result.columns.len()
+                ),
+            });
+        }
+        Ok(result
+            .rows
+            .into_iter()
+            .map(|mut row| row.swap_remove(0))
+            .collect())
+    }
+
+    /// The elements of the array operand of ANY/ALL, `None` when it is NULL
+    fn quantified_array(value: Value) -> crate::Result<Option<Vec<Value>>> {
+        match value {
+            Value::Array(elements) => Ok(Some(elements)),
+            Value::Null => Ok(None),
+            other => Err(YamlBaseError::TypeConversion(format!(
+                "ANY/ALL needs a subquery or an array, got {}",
+                other
+            ))),
+        }
+    }
+
+    /// Compare a value with every value of an ANY/ALL subquery or array. `None` is SQL's
+    /// unknown: no comparison decided the result but at least one involved a NULL, or the
+    /// array is NULL.
+    fn evaluate_quantified_comparison(
+        &self,
+        value: &Value,
+        compare_op: &BinaryOperator,
+        others: Option<&[Value]>,
+        all: bool,
+    ) -> crate::Result<Option<bool>> {
+        let Some(others) = others else {
+            return Ok(None);
+        };
+
+        let mut unknown = false;
+        for other in others {
+            if matches!(value, Value::Null) || matches!(other, Value::Null) {
+                unknown = true;
+                continue;
+            }
+            match self.evaluate_binary_op_constant(value, compare_op, other)? {
+                // ANY is decided by the first match, ALL by the first mismatch
+                Value::Boolean(matched) if matched != all => return Ok(Some(matched)),
+                Value::Boolean(_) => {}
+                _ => unknown = true,
+            }
+        }
+        Ok(if unknown { None } else { Some(all) })
+    }
+
     fn evaluate_in_list(
         &self,
         expr: &Expr,
@@ -1796,10 +2652,12 @@ impl QueryExecutor {
         table: &Table,
     ) -> crate::Result<bool> {
         let value = self.get_expr_value(expr, row, table)?;
+        let collation =
+            self.expr_collation(expr, &|expr| Self::table_column_collation(expr, table));
 
         for list_expr in list {
             let list_value = self.get_expr_value(list_expr, row, table)?;
-            if value == list_value {
+            if Self::collated_equals(collation, &value, &list_value) {
                 return Ok(!negated);
             }
         }
@@ -1816,10 +2674,12 @@ impl QueryExecutor {
         table: &Table,
     ) -> crate::Result<bool> {
         let value = self.get_expr_value_async(expr, row, table).await?;
+        let collation =
+            self.expr_collation(expr, &|expr| Self::table_column_collation(expr, table));
 
         for list_expr in list {
             let list_value = self.get_expr_value_async(list_expr, row, table).await?;
-            if value == list_value {
+            if Self::collated_equals(collation, &value, &list_value) {
                 return Ok(!negated);
             }
         }
@@ -1897,7 +2757,12 @@ impl QueryExecutor {
             }
         }
 
-        let matches = match Regex::new(&format!("^{}$", regex_pattern)) {
+        let collation =
+            self.expr_collation(expr, &|expr| Self::table_column_collation(expr, table));
+        let matches = match RegexBuilder::new(&format!("^{}$", regex_pattern))
+            .case_insensitive(collation.is_case_insensitive())
+            .build()
+        {
             Ok(re) => re.is_match(&value_str),
             Err(_) => {
                 return Err(YamlBaseError::Database {
@@ -1933,6 +2798,17 @@ impl QueryExecutor {
                 // For other operators, evaluate the values first
                 let left_val = self.get_expr_value_async(left, row, table).await?;
                 let right_val = self.get_expr_value_async(right, row, table).await?;
+                if let Some(result) = Self::evaluate_regex_operator(&left_val, op, &right_val) {
+                    return Ok(matches!(result?, Value::Boolean(true)));
+                }
+                let collation = self.comparison_collation(left, right, &|expr| {
+                    Self::table_column_collation(expr, table)
+                });
+                if let Some(result) =
+                    Self::collated_comparison(collation, &left_val, op, &right_val)
+                {
+                    return Ok(result);
+                }
                 debug!(
                     "Comparing values: left={:?}, right={:?}, op={:?}",
                     left_val, right_val, op
@@ -2002,6 +2878,17 @@ impl QueryExecutor {
                 // For other operators, evaluate the values first
                 let left_val = self.get_expr_value(left, row, table)?;
                 let right_val = self.get_expr_value(right, row, table)?;
+                if let Some(result) = Self::evaluate_regex_operator(&left_val, op, &right_val) {
+                    return Ok(matches!(result?, Value::Boolean(true)));
+                }
+                let collation = self.comparison_collation(left, right, &|expr| {
+                    Self::table_column_collation(expr, table)
+                });
+                if let Some(result) =
+                    Self::collated_comparison(collation, &left_val, op, &right_val)
+                {
+                    return Ok(result);
+                }
                 debug!(
                     "Comparing values: left={:?}, right={:?}, op={:?}",
                     left_val, right_val, op
@@ -2087,6 +2974,9 @@ impl QueryExecutor {
                     }
                 }
                 Expr::Value(val) => self.sql_value_to_db_value(val),
+                Expr::Interval(interval) => {
+                    Ok(Value::Text(self.evaluate_interval(interval)?.to_string()))
+                }
                 Expr::TypedString { data_type, value } => {
                     // Handle DATE '2025-01-01' and similar typed strings
                     match data_type {
@@ -2100,6 +2990,9 @@ impl QueryExecutor {
                                 ))),
                             }
                         }
+                        DataType::Timestamp(_, TimezoneInfo::WithTimeZone | TimezoneInfo::Tz) => {
+                            self.cast_value(Value::Text(value.clone()), data_type)
+                        }
                         _ => Ok(Value::Text(value.clone())),
                     }
                 }
@@ -2198,12 +3091,35 @@ impl QueryExecutor {
                     }
                 }
                 Expr::Cast {
-                    expr, data_type, ..
+                    kind,
+                    expr,
+                    data_type,
+                    ..
                 } => {
                     // Handle CAST expression
                     let value = self.get_expr_value_async(expr, row, table).await?;
-                    self.cast_value(value, data_type)
-                }
+                    self.cast_with_kind(value, data_type, kind)
+                }
+                Expr::AtTimeZone {
+                    timestamp,
+                    time_zone,
+                } => Self::at_time_zone(
+                    self.get_expr_value_async(timestamp, row, table).await?,
+                    self.get_expr_value_async(time_zone, row, table).await?,
+                ),
+                Expr::Array(array) => {
+                    let mut elements = Vec::with_capacity(array.elem.len());
+                    for elem in &array.elem {
+                        elements.push(self.get_expr_value_async(elem, row, table).await?);
+                    }
+                    Ok(Value::Array(elements))
+                }
+                // The bounds are plain values; only the array may need a subquery
+                Expr::Subscript { expr, subscript } => Self::array_subscript(
+                    self.get_expr_value_async(expr, row, table).await?,
+                    subscript,
+                    |expr| self.get_expr_value(expr, row, table),
+                ),
                 Expr::Subquery(subquery) => {
                     debug!("Evaluating scalar subquery in expression (async)");
 
@@ -2231,6 +3147,7 @@ impl QueryExecutor {
                         UnaryOperator::Minus => match val {
                             Value::Integer(i) => Ok(Value::Integer(-i)),
                             Value::Double(d) => Ok(Value::Double(-d)),
+                            Value::Decimal(d) => Ok(Value::Decimal(-d)),
                             Value::Null => Ok(Value::Null),
                             _ => Err(YamlBaseError::Database {
                                 message: "Unary minus requires numeric value".to_string(),
@@ -2256,10 +3173,28 @@ impl QueryExecutor {
                     }
                 }
                 Expr::BinaryOp { left, op, right } => {
+                    if let Some((operand, interval, subtract)) =
+                        Self::interval_operand(left, op, right)
+                    {
+                        let value = self.get_expr_value_async(operand, row, table).await?;
+                        return self.apply_interval(&value, interval, subtract);
+                    }
                     // Handle binary operations with row context
                     let left_val = self.get_expr_value_async(left, row, table).await?;
                     let right_val = self.get_expr_value_async(right, row, table).await?;
 
+                    if let Some(result) = Self::decimal_arithmetic(&left_val, op, &right_val) {
+                        return result;
+                    }
+
+                    if let Some(result) = Self::evaluate_regex_operator(&left_val, op, &right_val) {
+                        return result;
+                    }
+
+                    if let Some(result) = Self::evaluate_json_operator(&left_val, op, &right_val) {
+                        return result;
+                    }
+
                     match op {
                         BinaryOperator::Plus => match (&left_val, &right_val) {
                             (Value::Integer(l), Value::Integer(r)) => Ok(Value::Integer(l + r)),
@@ -2442,6 +3377,9 @@ impl QueryExecutor {
                                 message: "Modulo requires numeric values".to_string(),
                             }),
                         },
+                        BinaryOperator::StringConcat => {
+                            self.safe_string_concat(&left_val, &right_val)
+                        }
                         // Comparison operators should not be here - they return boolean in evaluate_expr
                         _ => Err(YamlBaseError::Database {
                             message:
@@ -2490,7 +3428,12 @@ impl QueryExecutor {
         })
     }
 
-    fn get_expr_value(&self, expr: &Expr, row: &[Value], table: &Table) -> crate::Result<Value> {
+    pub(crate) fn get_expr_value(
+        &self,
+        expr: &Expr,
+        row: &[Value],
+        table: &Table,
+    ) -> crate::Result<Value> {
         match expr {
             Expr::Identifier(ident) => {
                 let col_idx = table.get_column_index(&ident.value).ok_or_else(|| {
@@ -2523,6 +3466,9 @@ impl QueryExecutor {
                 }
             }
             Expr::Value(val) => self.sql_value_to_db_value(val),
+            Expr::Interval(interval) => {
+                Ok(Value::Text(self.evaluate_interval(interval)?.to_string()))
+            }
             Expr::TypedString { data_type, value } => {
                 // Handle DATE '2025-01-01' and similar typed strings
                 match data_type {
@@ -2536,6 +3482,9 @@ impl QueryExecutor {
                             ))),
                         }
                     }
+                    DataType::Timestamp(_, TimezoneInfo::WithTimeZone | TimezoneInfo::Tz) => {
+                        self.cast_value(Value::Text(value.clone()), data_type)
+                    }
                     _ => Ok(Value::Text(value.clone())),
                 }
             }
@@ -2637,56 +3586,37 @@ impl QueryExecutor {
                 }
             }
             Expr::Cast {
-                expr, data_type, ..
+                kind,
+                expr,
+                data_type,
+                ..
             } => {
                 // Handle CAST expression
                 let value = self.get_expr_value(expr, row, table)?;
-                self.cast_value(value, data_type)
+                self.cast_with_kind(value, data_type, kind)
+            }
+            Expr::Array(array) => array
+                .elem
+                .iter()
+                .map(|elem| self.get_expr_value(elem, row, table))
+                .collect::<crate::Result<_>>()
+                .map(Value::Array),
+            Expr::AtTimeZone {
+                timestamp,
+                time_zone,
+            } => Self::at_time_zone(
+                self.get_expr_value(timestamp, row, table)?,
+                self.get_expr_value(time_zone, row, table)?,
+            ),
+            Expr::Subscript { expr, subscript } => {
+                Self::array_subscript(self.get_expr_value(expr, row, table)?, subscript, |expr| {
+                    self.get_expr_value(expr, row, table)
+                })
             }
             Expr::Subquery(subquery) => {
                 debug!("Evaluating scalar subquery in expression");
 
-                // Handle sync/async bridge for subquery execution
-                let executor_clone = self.clone();
-                let subquery_clone = subquery.clone();
-
-                let result = if let Ok(_handle) = tokio::runtime::Handle::try_current() {
-                    // We're in a tokio runtime context - use separate thread
-                    let (tx, rx) = std::sync::mpsc::channel();
-
-                    std::thread::spawn(move || {
-                        let rt = tokio::runtime::Runtime::new().unwrap();
-                        let result = rt.block_on(async {
-                            executor_clone.execute_query(&subquery_clone).await
-                        });
-                        tx.send(result).unwrap();
-                    });
-
-                    rx.recv().map_err(|_| YamlBaseError::Database {
-                        message: "Failed to receive subquery result".to_string(),
-                    })?
-                } else {
-                    // Not in tokio context, create runtime
-                    let rt =
-                        tokio::runtime::Runtime::new().map_err(|_| YamlBaseError::Database {
-                            message: "Failed to create tokio runtime".to_string(),
-                        })?;
-                    rt.block_on(async { executor_clone.execute_query(&subquery_clone).await })
-                }?;
-
-                // Scalar subquery should return exactly one row and one column
-                if result.rows.is_empty() {
-                    Ok(Value::Null)
-                } else if result.rows.len() == 1 && !result.rows[0].is_empty() {
-                    Ok(result.rows[0][0].clone())
-                } else {
-                    Err(YamlBaseError::Database {
-                        message: format!(
-                            "Scalar subquery returned {} rows, expected 1",
-                            result.rows.len()
-                        ),
-                    })
-                }
+                Self::scalar_subquery_value(self.execute_query_blocking(subquery)?)
             }
             Expr::UnaryOp { op, expr } => {
                 // Handle unary operations with row context
@@ -2695,6 +3625,7 @@ impl QueryExecutor {
                     UnaryOperator::Minus => match val {
                         Value::Integer(i) => Ok(Value::Integer(-i)),
                         Value::Double(d) => Ok(Value::Double(-d)),
+                        Value::Decimal(d) => Ok(Value::Decimal(-d)),
                         Value::Null => Ok(Value::Null),
                         _ => Err(YamlBaseError::Database {
                             message: "Unary minus requires numeric value".to_string(),
@@ -2720,10 +3651,27 @@ impl QueryExecutor {
                 }
             }
             Expr::BinaryOp { left, op, right } => {
+                if let Some((operand, interval, subtract)) = Self::interval_operand(left, op, right)
+                {
+                    let value = self.get_expr_value(operand, row, table)?;
+                    return self.apply_interval(&value, interval, subtract);
+                }
                 // Handle binary operations with row context
                 let left_val = self.get_expr_value(left, row, table)?;
                 let right_val = self.get_expr_value(right, row, table)?;
 
+                if let Some(result) = Self::decimal_arithmetic(&left_val, op, &right_val) {
+                    return result;
+                }
+
+                if let Some(result) = Self::evaluate_regex_operator(&left_val, op, &right_val) {
+                    return result;
+                }
+
+                if let Some(result) = Self::evaluate_json_operator(&left_val, op, &right_val) {
+                    return result;
+                }
+
                 match op {
                     BinaryOperator::Plus => match (&left_val, &right_val) {
                         (Value::Integer(l), Value::Integer(r)) => Ok(Value::Integer(l + r)),
@@ -2889,7 +3837,18 @@ impl QueryExecutor {
                         }),
                     },
                     BinaryOperator::StringConcat => self.safe_string_concat(&left_val, &right_val),
-                    // Comparison operators that return boolean values
+                    // Comparison operators that return boolean values, unknown (NULL)
+                    // when either side is NULL
+                    BinaryOperator::Eq
+                    | BinaryOperator::NotEq
+                    | BinaryOperator::Lt
+                    | BinaryOperator::LtEq
+                    | BinaryOperator::Gt
+                    | BinaryOperator::GtEq
+                        if matches!(left_val, Value::Null) || matches!(right_val, Value::Null) =>
+                    {
+                        Ok(Value::Null)
+                    }
                     BinaryOperator::Eq => Ok(Value::Boolean(
                         self.compare_values(&left_val, &right_val)? == 0,
                     )),
@@ -2908,6 +3867,29 @@ impl QueryExecutor {
                     BinaryOperator::GtEq => Ok(Value::Boolean(
                         self.compare_values(&left_val, &right_val)? >= 0,
                     )),
+                    // Three-valued logic: NULL unless the other side decides the result
+                    BinaryOperator::And => match (&left_val, &right_val) {
+                        (Value::Boolean(false), _) | (_, Value::Boolean(false)) => {
+                            Ok(Value::Boolean(false))
+                        }
+                        (Value::Boolean(true), Value::Boolean(true)) => Ok(Value::Boolean(true)),
+                        (Value::Null, Value::Boolean(_) | Value::Null)
+                        | (Value::Boolean(_), Value::Null) => Ok(Value::Null),
+                        _ => Err(YamlBaseError::Database {
+                            message: "AND requires boolean values".to_string(),
+                        }),
+                    },
+                    BinaryOperator::Or => match (&left_val, &right_val) {
+                        (Value::Boolean(true), _) | (_, Value::Boolean(true)) => {
+                            Ok(Value::Boolean(true))
+                        }
+                        (Value::Boolean(false), Value::Boolean(false)) => Ok(Value::Boolean(false)),
+                        (Value::Null, Value::Boolean(_) | Value::Null)
+                        | (Value::Boolean(_), Value::Null) => Ok(Value::Null),
+                        _ => Err(YamlBaseError::Database {
+                            message: "OR requires boolean values".to_string(),
+                        }),
+                    },
                     _ => Err(YamlBaseError::NotImplemented(format!(
                         "Binary operator {:?} not supported in get_expr_value",
                         op
@@ -2945,6 +3927,50 @@ impl QueryExecutor {
 
                 Ok(Value::Boolean(if *negated { !found } else { found }))
             }
+            Expr::Nested(inner) => self.get_expr_value(inner, row, table),
+            Expr::IsNull(inner) => Ok(Value::Boolean(matches!(
+                self.get_expr_value(inner, row, table)?,
+                Value::Null
+            ))),
+            Expr::IsNotNull(inner) => Ok(Value::Boolean(!matches!(
+                self.get_expr_value(inner, row, table)?,
+                Value::Null
+            ))),
+            Expr::Between {
+                expr,
+                negated,
+                low,
+                high,
+            } => {
+                let value = self.get_expr_value(expr, row, table)?;
+                let low = self.get_expr_value(low, row, table)?;
+                let high = self.get_expr_value(high, row, table)?;
+                if [&value, &low, &high].contains(&&Value::Null) {
+                    return Ok(Value::Null);
+                }
+                let between = self.compare_values(&value, &low)? >= 0
+                    && self.compare_values(&value, &high)? <= 0;
+                Ok(Value::Boolean(between != *negated))
+            }
+            Expr::AnyOp {
+                left,
+                compare_op,
+                right,
+                ..
+            }
+            | Expr::AllOp {
+                left,
+                compare_op,
+                right,
+            } => {
+                let value = self.get_expr_value(left, row, table)?;
+                let others =
+                    self.quantified_values(right, |right| self.get_expr_value(right, row, table))?;
+                let all = matches!(expr, Expr::AllOp { .. });
+                Ok(self
+                    .evaluate_quantified_comparison(&value, compare_op, others.as_deref(), all)?
+                    .map_or(Value::Null, Value::Boolean))
+            }
             _ => Err(YamlBaseError::NotImplemented(format!(
                 "Expression type not supported in get_expr_value: {:?}",
                 expr
@@ -2952,22 +3978,16 @@ impl QueryExecutor {
         }
     }
 
-    fn sql_value_to_db_value(&self, val: &sqlparser::ast::Value) -> crate::Result<Value> {
+    pub(crate) fn sql_value_to_db_value(
+        &self,
+        val: &sqlparser::ast::Value,
+    ) -> crate::Result<Value> {
         match val {
-            sqlparser::ast::Value::Number(n, _) => {
-                if n.contains('.') {
-                    Ok(Value::Double(n.parse().map_err(|_| {
-                        YamlBaseError::TypeConversion(format!("Invalid number: {}", n))
-                    })?))
-                } else {
-                    Ok(Value::Integer(n.parse().map_err(|_| {
-                        YamlBaseError::TypeConversion(format!("Invalid integer: {}", n))
-                    })?))
-                }
-            }
+            sqlparser::ast::Value::Number(n, _) => Self::parse_number_literal(n),
             sqlparser::ast::Value::SingleQuotedString(s) => Ok(Value::Text(s.clone())),
             sqlparser::ast::Value::Boolean(b) => Ok(Value::Boolean(*b)),
             sqlparser::ast::Value::Null => Ok(Value::Null),
+            sqlparser::ast::Value::Placeholder(name) => Self::bind_parameter(name),
             _ => Err(YamlBaseError::NotImplemented(format!(
                 "Value type not supported: {:?}",
                 val
@@ -2975,6 +3995,43 @@ impl QueryExecutor {
         }
     }
 
+    /// A numeric literal is an INTEGER without a decimal point and an exact DECIMAL with
+    /// one, so `0.1 + 0.2` is `0.3`. Only an exponent or a value past DECIMAL's range makes
+    /// it a DOUBLE.
+    pub(crate) fn parse_number_literal(n: &str) -> crate::Result<Value> {
+        use std::str::FromStr;
+
+        if let Ok(i) = n.parse::<i64>() {
+            return Ok(Value::Integer(i));
+        }
+        if !n.contains(['e', 'E'])
+            && let Ok(d) = rust_decimal::Decimal::from_str_exact(n)
+        {
+            return Ok(Value::Decimal(d));
+        }
+        f64::from_str(n)
+            .map(Value::Double)
+            .map_err(|_| YamlBaseError::TypeConversion(format!("Invalid number: {}", n)))
+    }
+
+    /// The value bound to a `$n` placeholder by `execute_with_parameters`
+    fn bind_parameter(name: &str) -> crate::Result<Value> {
+        let index = name
+            .strip_prefix('$')
+            .and_then(|number| number.parse::<usize>().ok())
+            .filter(|&number| number > 0)
+            .ok_or_else(|| YamlBaseError::Database {
+                message: format!("Invalid parameter placeholder: {}", name),
+            })?;
+        BIND_PARAMETERS
+            .try_with(|parameters| parameters.get(index - 1).cloned())
+            .ok()
+            .flatten()
+            .ok_or_else(|| YamlBaseError::Database {
+                message: format!("No value supplied for parameter {}", name),
+            })
+    }
+
     fn project_columns(
         &self,
         rows: &[&Vec<Value>],
@@ -2993,7 +4050,7 @@ impl QueryExecutor {
                     ProjectionItem::TableColumn(_, idx) => {
                         projected_row.push(row[*idx].clone());
                     }
-                    ProjectionItem::Constant(_, value) => {
+                    ProjectionItem::Constant(_, value, _) => {
                         projected_row.push(value.clone());
                     }
                     ProjectionItem::Expression(_, expr) => {
@@ -3013,6 +4070,103 @@ impl QueryExecutor {
         Ok(result)
     }
 
+    /// Filter rows on a QUALIFY condition. Window functions in the condition are computed
+    /// over the filtered rows like projected ones; the condition may also refer to output
+    /// column aliases and table columns.
+    #[allow(clippy::type_complexity)]
+    fn apply_qualify<'a>(
+        &self,
+        qualify: &Expr,
+        source_rows: Vec<&'a Vec<Value>>,
+        projected_rows: Vec<Vec<Value>>,
+        columns: &[ProjectionItem],
+        table: &Table,
+    ) -> crate::Result<(Vec<&'a Vec<Value>>, Vec<Vec<Value>>)> {
+        let mut window_columns = Vec::new();
+        let condition = Self::extract_qualify_window_functions(qualify, &mut window_columns);
+        let window_rows = self.project_columns(&source_rows, &window_columns, table)?;
+
+        let column_names: Vec<String> = columns
+            .iter()
+            .chain(&window_columns)
+            .map(|item| item.name().to_string())
+            .chain(table.columns.iter().map(|col| col.name.clone()))
+            .collect();
+
+        let mut kept_source_rows = Vec::new();
+        let mut kept_rows = Vec::new();
+        for ((source_row, projected_row), window_row) in
+            source_rows.into_iter().zip(projected_rows).zip(window_rows)
+        {
+            let mut row = projected_row.clone();
+            row.extend(window_row);
+            row.extend(source_row.iter().cloned());
+            if self.evaluate_where_condition_with_columns(&condition, &row, &column_names)? {
+                kept_source_rows.push(source_row);
+                kept_rows.push(projected_row);
+            }
+        }
+
+        Ok((kept_source_rows, kept_rows))
+    }
+
+    /// Replace window functions in a QUALIFY condition with references to hidden columns,
+    /// collecting the window functions as projection items to compute
+    fn extract_qualify_window_functions(
+        expr: &Expr,
+        window_columns: &mut Vec<ProjectionItem>,
+    ) -> Expr {
+        match expr {
+            Expr::Function(func) if func.over.is_some() => {
+                let name = format!("__qualify_{}", window_columns.len());
+                window_columns.push(ProjectionItem::Expression(
+                    name.clone(),
+                    Box::new(expr.clone()),
+                ));
+                Expr::Identifier(sqlparser::ast::Ident::new(name))
+            }
+            Expr::BinaryOp { left, op, right } => Expr::BinaryOp {
+                left: Box::new(Self::extract_qualify_window_functions(left, window_columns)),
+                op: op.clone(),
+                right: Box::new(Self::extract_qualify_window_functions(
+                    right,
+                    window_columns,
+                )),
+            },
+            Expr::Nested(inner) => Expr::Nested(Box::new(Self::extract_qualify_window_functions(
+                inner,
+                window_columns,
+            ))),
+            Expr::Between {
+                expr: inner,
+                negated,
+                low,
+                high,
+            } => Expr::Between {
+                expr: Box::new(Self::extract_qualify_window_functions(
+                    inner,
+                    window_columns,
+                )),
+                negated: *negated,
+                low: Box::new(Self::extract_qualify_window_functions(low, window_columns)),
+                high: Box::new(Self::extract_qualify_window_functions(high, window_columns)),
+            },
+            Expr::InList {
+                expr: inner,
+                list,
+                negated,
+            } => Expr::InList {
+                expr: Box::new(Self::extract_qualify_window_functions(
+                    inner,
+                    window_columns,
+                )),
+                list: list.clone(),
+                negated: *negated,
+            },
+            _ => expr.clone(),
+        }
+    }
+
     fn compute_window_functions(
         &self,
         rows: &[&Vec<Value>],
@@ -3024,14 +4178,13 @@ impl QueryExecutor {
 
         // Find window functions in the projection
         for (col_idx, item) in columns.iter().enumerate() {
-            if let ProjectionItem::Expression(_, expr) = item {
-                if let Some(window_result) =
+            if let ProjectionItem::Expression(_, expr) = item
+                && let Some(window_result) =
                     self.evaluate_window_function_for_all_rows(expr, rows, table)?
-                {
-                    // Store the computed values for each row
-                    for (row_idx, value) in window_result.into_iter().enumerate() {
-                        window_values.insert((row_idx, col_idx), value);
-                    }
+            {
+                // Store the computed values for each row
+                for (row_idx, value) in window_result.into_iter().enumerate() {
+                    window_values.insert((row_idx, col_idx), value);
                 }
             }
         }
@@ -3076,8 +4229,6 @@ impl QueryExecutor {
         window_type: &sqlparser::ast::WindowType,
         table: &Table,
     ) -> crate::Result<Option<Vec<Value>>> {
-        use std::collections::HashMap;
-
         // Extract WindowSpec from WindowType
         let window_spec = match window_type {
             sqlparser::ast::WindowType::WindowSpec(spec) => spec,
@@ -3089,34 +4240,9 @@ impl QueryExecutor {
             }
         };
 
-        // If no PARTITION BY, treat all rows as one partition
-        if window_spec.partition_by.is_empty() {
-            let mut result = Vec::new();
-            for i in 1..=rows.len() {
-                result.push(Value::Integer(i as i64));
-            }
-            return Ok(Some(result));
-        }
-
-        // Group rows by partition values
-        let mut partitions: HashMap<Vec<Value>, Vec<usize>> = HashMap::new();
-
-        for (row_idx, row) in rows.iter().enumerate() {
-            // Evaluate partition expressions for this row
-            let mut partition_key = Vec::new();
-            for partition_expr in &window_spec.partition_by {
-                let partition_value = self.get_expr_value(partition_expr, row, table)?;
-                partition_key.push(partition_value);
-            }
-
-            partitions.entry(partition_key).or_default().push(row_idx);
-        }
-
-        // Compute ROW_NUMBER within each partition
         let mut result = vec![Value::Integer(0); rows.len()];
-
-        for partition_rows in partitions.values() {
-            for (pos, &row_idx) in partition_rows.iter().enumerate() {
+        for partition in self.window_partitions(rows, window_spec, table)? {
+            for (pos, (row_idx, _)) in partition.into_iter().enumerate() {
                 result[row_idx] = Value::Integer((pos + 1) as i64);
             }
         }
@@ -3130,8 +4256,6 @@ impl QueryExecutor {
         window_type: &sqlparser::ast::WindowType,
         table: &Table,
     ) -> crate::Result<Option<Vec<Value>>> {
-        use std::collections::HashMap;
-
         // Extract WindowSpec from WindowType
         let window_spec = match window_type {
             sqlparser::ast::WindowType::WindowSpec(spec) => spec,
@@ -3143,111 +4267,246 @@ impl QueryExecutor {
             }
         };
 
-        // If no PARTITION BY, treat all rows as one partition
-        if window_spec.partition_by.is_empty() {
-            let mut result = Vec::new();
-            for _ in 0..rows.len() {
-                result.push(Value::Integer(1));
+        // Peers (rows with equal ORDER BY keys) share the rank of the first of them
+        let collations = self.window_collations(window_spec, table);
+        let mut result = vec![Value::Integer(0); rows.len()];
+        for partition in self.window_partitions(rows, window_spec, table)? {
+            let mut rank = 1;
+            for (pos, (row_idx, key)) in partition.iter().enumerate() {
+                if pos > 0
+                    && Self::compare_order_keys(
+                        &partition[pos - 1].1,
+                        key,
+                        &window_spec.order_by,
+                        &collations,
+                    )
+                    .is_ne()
+                {
+                    rank = pos + 1;
+                }
+                result[*row_idx] = Value::Integer(rank as i64);
             }
-            return Ok(Some(result));
         }
 
-        // Group rows by partition values
-        let mut partitions: HashMap<Vec<Value>, Vec<usize>> = HashMap::new();
+        Ok(Some(result))
+    }
+
+    /// Split rows into the partitions of a window, each sorted by the window's ORDER BY.
+    /// Returns the row indices together with their ORDER BY keys.
+    #[allow(clippy::type_complexity)]
+    fn window_partitions(
+        &self,
+        rows: &[&Vec<Value>],
+        window_spec: &sqlparser::ast::WindowSpec,
+        table: &Table,
+    ) -> crate::Result<Vec<Vec<(usize, Vec<Value>)>>> {
+        let mut partition_index: std::collections::HashMap<Vec<Value>, usize> =
+            std::collections::HashMap::new();
+        let mut partitions: Vec<Vec<(usize, Vec<Value>)>> = Vec::new();
 
         for (row_idx, row) in rows.iter().enumerate() {
-            // Evaluate partition expressions for this row
-            let mut partition_key = Vec::new();
-            for partition_expr in &window_spec.partition_by {
-                let partition_value = self.get_expr_value(partition_expr, row, table)?;
-                partition_key.push(partition_value);
-            }
+            let partition_key = window_spec
+                .partition_by
+                .iter()
+                .map(|expr| self.get_expr_value(expr, row, table))
+                .collect::<crate::Result<Vec<_>>>()?;
+            let order_key = window_spec
+                .order_by
+                .iter()
+                .map(|order_expr| self.get_expr_value(&order_expr.expr, row, table))
+                .collect::<crate::Result<Vec<_>>>()?;
 
-            partitions.entry(partition_key).or_default().push(row_idx);
+            let idx = *partition_index.entry(partition_key).or_insert_with(|| {
+                partitions.push(Vec::new());
+                partitions.len() - 1
+            });
+            partitions[idx].push((row_idx, order_key));
         }
 
-        // Compute RANK within each partition (for now, all get rank 1)
-        // TODO: Implement proper ranking based on ORDER BY
-        let mut result = vec![Value::Integer(0); rows.len()];
-
-        for partition_rows in partitions.values() {
-            for &row_idx in partition_rows {
-                result[row_idx] = Value::Integer(1);
-            }
+        let collations = self.window_collations(window_spec, table);
+        for partition in &mut partitions {
+            partition.sort_by(|(_, a), (_, b)| {
+                Self::compare_order_keys(a, b, &window_spec.order_by, &collations)
+            });
         }
 
-        Ok(Some(result))
+        Ok(partitions)
+    }
+
+    /// Collations of a window's ORDER BY expressions
+    fn window_collations(
+        &self,
+        window_spec: &sqlparser::ast::WindowSpec,
+        table: &Table,
+    ) -> Vec<Collation> {
+        window_spec
+            .order_by
+            .iter()
+            .map(|order_expr| {
+                self.expr_collation(&order_expr.expr, &|expr| {
+                    Self::table_column_collation(expr, table)
+                })
+            })
+            .collect()
     }
 
     fn sort_rows(
         &self,
-        mut rows: Vec<Vec<Value>>,
+        rows: Vec<Vec<Value>>,
         order_by: &[OrderByExpr],
         columns: &[(String, usize)],
     ) -> crate::Result<Vec<Vec<Value>>> {
-        // Create a mapping from column names to indices in the projected rows
-        let col_map: std::collections::HashMap<&str, usize> = columns
+        let column_names: Vec<String> = columns.iter().map(|(name, _)| name.clone()).collect();
+        let column_map: std::collections::HashMap<String, usize> = column_names
             .iter()
             .enumerate()
-            .map(|(idx, (name, _))| (name.as_str(), idx))
+            .map(|(idx, name)| (name.clone(), idx))
             .collect();
-
-        rows.sort_by(|a, b| {
-            for order_expr in order_by {
-                if let Expr::Identifier(ident) = &order_expr.expr {
-                    if let Some(&idx) = col_map.get(ident.value.as_str()) {
-                        if let Some(ord) = a[idx].compare(&b[idx]) {
-                            let ord = if order_expr.asc.unwrap_or(true) {
-                                ord
-                            } else {
-                                ord.reverse()
-                            };
-                            if !ord.is_eq() {
-                                return ord;
-                            }
-                        }
-                    }
-                }
+        let resolved = order_by
+            .iter()
+            .map(|order_expr| self.resolve_order_by_column(&order_expr.expr, &column_names))
+            .collect::<crate::Result<Vec<_>>>()?;
+
+        // Only the output columns are available here, so anything that is not a reference
+        // to one of them is evaluated against the output row
+        let mut keys = Vec::with_capacity(rows.len());
+        for row in &rows {
+            let mut key = Vec::with_capacity(order_by.len());
+            for (order_expr, resolved) in order_by.iter().zip(&resolved) {
+                key.push(match resolved {
+                    Some(idx) => row[*idx].clone(),
+                    None => self.evaluate_expr_with_row(&order_expr.expr, row, &column_map)?,
+                });
             }
-            std::cmp::Ordering::Equal
-        });
+            keys.push(key);
+        }
 
-        Ok(rows)
+        let collations = vec![self.collation; order_by.len()];
+        Ok(Self::sort_rows_by_keys(rows, keys, order_by, &collations))
     }
 
-    fn get_system_variable(&self, var_name: &str) -> crate::Result<Value> {
-        // Remove @@ prefix and handle session/global prefixes
-        let name = if let Some(stripped) = var_name.strip_prefix("@@") {
-            stripped
-        } else {
-            var_name
-        };
+    // Resolve an ORDER BY expression to an output column: a 1-based position (ORDER BY 2),
+    // an output column name or alias, or an expression spelled the same way as a projected
+    // one (ORDER BY COUNT(*)). Returns None when the expression has to be evaluated.
+    fn resolve_order_by_column(
+        &self,
+        expr: &Expr,
+        column_names: &[String],
+    ) -> crate::Result<Option<usize>> {
+        if let Expr::Value(sqlparser::ast::Value::Number(n, _)) = expr {
+            return match n.parse::<usize>() {
+                Ok(position) if (1..=column_names.len()).contains(&position) => {
+                    Ok(Some(position - 1))
+                }
+                _ => Err(YamlBaseError::Database {
+                    message: format!("ORDER BY position {} is not in select list", n),
+                }),
+            };
+        }
 
-        // Handle session. and global. prefixes
-        let name = if let Some(stripped) = name.strip_prefix("session.") {
-            stripped
-        } else if let Some(stripped) = name.strip_prefix("SESSION.") {
-            stripped
-        } else if let Some(stripped) = name.strip_prefix("global.") {
-            stripped
-        } else if let Some(stripped) = name.strip_prefix("GLOBAL.") {
-            stripped
-        } else {
-            name
+        let name = match expr {
+            Expr::Identifier(ident) => ident.value.clone(),
+            Expr::CompoundIdentifier(parts) => parts
+                .iter()
+                .map(|part| part.value.clone())
+                .collect::<Vec<_>>()
+                .join("."),
+            _ => self.expr_to_string(expr),
         };
 
-        // Convert to lowercase for comparison
-        let name_lower = name.to_lowercase();
+        Ok(column_names
+            .iter()
+            .position(|column| *column == name)
+            .or_else(|| {
+                column_names
+                    .iter()
+                    .position(|column| column.eq_ignore_ascii_case(&name))
+            }))
+    }
+
+    // Sort rows by precomputed ORDER BY key values, one key vector per row. Text keys
+    // compare under the collation of their ORDER BY expression.
+    fn sort_rows_by_keys(
+        rows: Vec<Vec<Value>>,
+        keys: Vec<Vec<Value>>,
+        order_by: &[OrderByExpr],
+        collations: &[Collation],
+    ) -> Vec<Vec<Value>> {
+        let mut keyed: Vec<(Vec<Value>, Vec<Value>)> = keys.into_iter().zip(rows).collect();
+        keyed.sort_by(|(a, _), (b, _)| Self::compare_order_keys(a, b, order_by, collations));
+        keyed.into_iter().map(|(_, row)| row).collect()
+    }
 
-        // Return appropriate values for known system variables
-        match name_lower.as_str() {
-            "version" => Ok(Value::Text("8.0.35-yamlbase".to_string())),
-            "version_comment" => Ok(Value::Text("1".to_string())),
-            _ => {
-                // Default all other system variables to "1"
-                Ok(Value::Text("1".to_string()))
+    /// Compare two sort keys under ORDER BY semantics, honoring ASC/DESC and NULLS FIRST/LAST
+    fn compare_order_keys(
+        a: &[Value],
+        b: &[Value],
+        order_by: &[OrderByExpr],
+        collations: &[Collation],
+    ) -> std::cmp::Ordering {
+        for (idx, order_expr) in order_by.iter().enumerate() {
+            let ord = match (&a[idx], &b[idx], order_expr.nulls_first) {
+                (Value::Null, Value::Null, _) => std::cmp::Ordering::Equal,
+                // An explicit NULLS FIRST / NULLS LAST does not depend on the direction
+                (Value::Null, _, Some(nulls_first)) | (_, Value::Null, Some(nulls_first)) => {
+                    let a_is_null = matches!(a[idx], Value::Null);
+                    return if a_is_null == nulls_first {
+                        std::cmp::Ordering::Less
+                    } else {
+                        std::cmp::Ordering::Greater
+                    };
+                }
+                (Value::Text(left), Value::Text(right), _) => collations
+                    .get(idx)
+                    .copied()
+                    .unwrap_or_default()
+                    .compare(left, right),
+                (left, right, _) => left.compare(right).unwrap_or(std::cmp::Ordering::Equal),
+            };
+            let ord = if order_expr.asc.unwrap_or(true) {
+                ord
+            } else {
+                ord.reverse()
+            };
+            if !ord.is_eq() {
+                return ord;
             }
         }
+        std::cmp::Ordering::Equal
+    }
+
+    // Rewrite ORDER BY expressions that repeat a projected expression into positional
+    // references, e.g. ORDER BY COUNT(*) for SELECT dept, COUNT(*) AS cnt. Only valid when
+    // every projection item produces exactly one output column, as in aggregate results.
+    fn order_by_with_projection_positions(
+        select: &Select,
+        order_by: &[OrderByExpr],
+    ) -> Vec<OrderByExpr> {
+        order_by
+            .iter()
+            .map(|order_expr| {
+                let position = select.projection.iter().position(|item| match item {
+                    SelectItem::UnnamedExpr(expr) | SelectItem::ExprWithAlias { expr, .. } => {
+                        *expr == order_expr.expr
+                    }
+                    _ => false,
+                });
+                match position {
+                    Some(idx) => OrderByExpr {
+                        expr: Expr::Value(sqlparser::ast::Value::Number(
+                            (idx + 1).to_string(),
+                            false,
+                        )),
+                        ..order_expr.clone()
+                    },
+                    None => order_expr.clone(),
+                }
+            })
+            .collect()
+    }
+
+    fn get_system_variable(&self, var_name: &str) -> crate::Result<Value> {
+        Ok(self.system_variable_value(var_name))
     }
 
     fn evaluate_extract_from_value(
@@ -3707,6 +4966,7 @@ impl QueryExecutor {
                                     Value::Integer(i) => result.push_str(&i.to_string()),
                                     Value::Float(f) => result.push_str(&f.to_string()),
                                     Value::Double(d) => result.push_str(&d.to_string()),
+                                    Value::Decimal(d) => result.push_str(&d.to_string()),
                                     Value::Boolean(b) => result.push_str(&b.to_string()),
                                     Value::Null => return Ok(Value::Null), // CONCAT returns NULL if any argument is NULL
                                     _ => result.push_str(&val.to_string()),
@@ -3964,11 +5224,7 @@ impl QueryExecutor {
                                     Ok(Value::Double((d * factor).round() / factor))
                                 }
                                 Value::Decimal(d) => {
-                                    let factor = 10f64.powi(precision);
-                                    let f = d.to_f64().ok_or_else(|| YamlBaseError::Database {
-                                        message: "Cannot convert decimal to float".to_string(),
-                                    })?;
-                                    Ok(Value::Double((f * factor).round() / factor))
+                                    Ok(Value::Decimal(Self::round_decimal(d, precision)))
                                 }
                                 Value::Null => Ok(Value::Null),
                                 _ => Err(YamlBaseError::Database {
@@ -4002,12 +5258,7 @@ impl QueryExecutor {
                                 Value::Integer(n) => Ok(Value::Integer(n)),
                                 Value::Float(f) => Ok(Value::Double((f as f64).floor())),
                                 Value::Double(d) => Ok(Value::Double(d.floor())),
-                                Value::Decimal(d) => {
-                                    let f = d.to_f64().ok_or_else(|| YamlBaseError::Database {
-                                        message: "Cannot convert decimal to float".to_string(),
-                                    })?;
-                                    Ok(Value::Double(f.floor()))
-                                }
+                                Value::Decimal(d) => Ok(Value::Decimal(d.floor())),
                                 Value::Null => Ok(Value::Null),
                                 _ => Err(YamlBaseError::Database {
                                     message: "FLOOR requires numeric argument".to_string(),
@@ -4040,12 +5291,7 @@ impl QueryExecutor {
                                 Value::Integer(n) => Ok(Value::Integer(n)),
                                 Value::Float(f) => Ok(Value::Double((f as f64).ceil())),
                                 Value::Double(d) => Ok(Value::Double(d.ceil())),
-                                Value::Decimal(d) => {
-                                    let f = d.to_f64().ok_or_else(|| YamlBaseError::Database {
-                                        message: "Cannot convert decimal to float".to_string(),
-                                    })?;
-                                    Ok(Value::Double(f.ceil()))
-                                }
+                                Value::Decimal(d) => Ok(Value::Decimal(d.ceil())),
                                 Value::Null => Ok(Value::Null),
                                 _ => Err(YamlBaseError::Database {
                                     message: "CEIL requires numeric argument".to_string(),
@@ -4869,6 +6115,9 @@ impl QueryExecutor {
                     })
                 }
             }
+            name if Self::is_scalar_function(name) => {
+                self.call_scalar_function(name, func, |expr| self.get_expr_value(expr, row, table))
+            }
             // For functions that don't need row context, delegate to constant version
             _ => self.evaluate_constant_function(func),
         }
@@ -5424,6 +6673,7 @@ impl QueryExecutor {
                                 Value::Text(s) => result.push_str(s),
                                 Value::Integer(i) => result.push_str(&i.to_string()),
                                 Value::Double(d) => result.push_str(&d.to_string()),
+                                Value::Decimal(d) => result.push_str(&d.to_string()),
                                 Value::Boolean(b) => result.push_str(&b.to_string()),
                                 Value::Null => {
                                     has_null = true;
@@ -5676,6 +6926,9 @@ impl QueryExecutor {
                                     let rounded = (f * multiplier).round() / multiplier;
                                     Ok(Value::Float(rounded))
                                 }
+                                Value::Decimal(d) => {
+                                    Ok(Value::Decimal(Self::round_decimal(d, precision)))
+                                }
                                 Value::Null => Ok(Value::Null),
                                 _ => Err(YamlBaseError::Database {
                                     message: "ROUND requires numeric argument".to_string(),
@@ -5708,6 +6961,7 @@ impl QueryExecutor {
                                 Value::Integer(i) => Ok(Value::Integer(i)),
                                 Value::Double(d) => Ok(Value::Double(d.floor())),
                                 Value::Float(f) => Ok(Value::Float(f.floor())),
+                                Value::Decimal(d) => Ok(Value::Decimal(d.floor())),
                                 Value::Null => Ok(Value::Null),
                                 _ => Err(YamlBaseError::Database {
                                     message: "FLOOR requires numeric argument".to_string(),
@@ -5740,6 +6994,7 @@ impl QueryExecutor {
                                 Value::Integer(i) => Ok(Value::Integer(i)),
                                 Value::Double(d) => Ok(Value::Double(d.ceil())),
                                 Value::Float(f) => Ok(Value::Float(f.ceil())),
+                                Value::Decimal(d) => Ok(Value::Decimal(d.ceil())),
                                 Value::Null => Ok(Value::Null),
                                 _ => Err(YamlBaseError::Database {
                                     message: "CEIL requires numeric argument".to_string(),
@@ -5772,6 +7027,7 @@ impl QueryExecutor {
                                 Value::Integer(i) => Ok(Value::Integer(i.wrapping_abs())),
                                 Value::Double(d) => Ok(Value::Double(d.abs())),
                                 Value::Float(f) => Ok(Value::Float(f.abs())),
+                                Value::Decimal(d) => Ok(Value::Decimal(d.abs())),
                                 Value::Null => Ok(Value::Null),
                                 _ => Err(YamlBaseError::Database {
                                     message: "ABS requires numeric argument".to_string(),
@@ -5804,6 +7060,13 @@ impl QueryExecutor {
                             let num_val = self.evaluate_constant_expr(num_expr)?;
                             let div_val = self.evaluate_constant_expr(div_expr)?;
 
+                            if let Some(result) = Self::decimal_arithmetic(
+                                &num_val,
+                                &BinaryOperator::Modulo,
+                                &div_val,
+                            ) {
+                                return result;
+                            }
                             match (&num_val, &div_val) {
                                 (Value::Integer(n), Value::Integer(d)) => {
                                     if *d == 0 {
@@ -6610,6 +7873,9 @@ impl QueryExecutor {
                     })
                 }
             }
+            name if Self::is_scalar_function(name) => {
+                self.call_scalar_function(name, func, |expr| self.evaluate_constant_expr(expr))
+            }
             _ => {
                 // Check if this is a window function (has OVER clause)
                 if let Some(_window_spec) = &func.over {
@@ -6638,7 +7904,7 @@ impl QueryExecutor {
         }
     }
 
-    fn mysql_to_chrono_format(&self, mysql_format: &str) -> String {
+    pub(crate) fn mysql_to_chrono_format(&self, mysql_format: &str) -> String {
         // Convert MySQL date format specifiers to chrono format
         // This is a simplified version - MySQL has many more format specifiers
         let mut result = mysql_format.to_string();
@@ -6673,30 +7939,118 @@ impl QueryExecutor {
         result
     }
 
-    fn cast_value(&self, value: Value, data_type: &DataType) -> crate::Result<Value> {
-        use sqlparser::ast::DataType;
+    /// Evaluate `value AT TIME ZONE zone` as PostgreSQL does: a TIMESTAMP is read as wall-clock
+    /// time in the zone and gives a TIMESTAMPTZ, while a TIMESTAMPTZ gives the wall-clock
+    /// TIMESTAMP in the zone
+    fn at_time_zone(value: Value, zone: Value) -> crate::Result<Value> {
+        let zone = match zone {
+            Value::Null => return Ok(Value::Null),
+            Value::Text(name) => TimeZone::parse(&name)?,
+            other => {
+                return Err(YamlBaseError::Database {
+                    message: format!("AT TIME ZONE requires a time zone name, got {}", other),
+                });
+            }
+        };
+        let value = match value {
+            Value::Text(text) => parse_naive_timestamp(text.trim())
+                .map(Value::Timestamp)
+                .or_else(|| {
+                    parse_timestamptz(&text, &TimeZone::default_zone()).map(Value::TimestampTz)
+                })
+                .ok_or_else(|| YamlBaseError::Database {
+                    message: format!("Cannot read '{}' as a timestamp", text),
+                })?,
+            Value::Date(date) => Value::Timestamp(date.and_time(NaiveTime::MIN)),
+            other => other,
+        };
+
+        match value {
+            Value::Null => Ok(Value::Null),
+            Value::Timestamp(ts) => Ok(Value::TimestampTz(
+                TimeZone::default_zone().from_instant(&zone.from_local(&ts)),
+            )),
+            Value::TimestampTz(ts) => Ok(Value::Timestamp(zone.from_instant(&ts).naive_local())),
+            other => Err(YamlBaseError::Database {
+                message: format!("AT TIME ZONE requires a timestamp, got {}", other),
+            }),
+        }
+    }
+
+    /// Apply a CAST, TRY_CAST or SAFE_CAST. The non-throwing variants turn a value that
+    /// cannot be converted into NULL instead of failing the query.
+    fn cast_with_kind(
+        &self,
+        value: Value,
+        data_type: &DataType,
+        kind: &sqlparser::ast::CastKind,
+    ) -> crate::Result<Value> {
+        use sqlparser::ast::CastKind;
+
+        match (self.cast_value(value, data_type), kind) {
+            (Err(YamlBaseError::Database { .. }), CastKind::TryCast | CastKind::SafeCast) => {
+                Ok(Value::Null)
+            }
+            (result, _) => result,
+        }
+    }
+
+    pub(crate) fn cast_value(&self, value: Value, data_type: &DataType) -> crate::Result<Value> {
+        use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+        use sqlparser::ast::{ArrayElemTypeDef, DataType, ExactNumberInfo};
 
         match data_type {
-            DataType::Int(_) | DataType::Integer(_) | DataType::BigInt(_) => match value {
-                Value::Integer(i) => Ok(Value::Integer(i)),
-                Value::Double(d) => Ok(Value::Integer(d as i64)),
-                Value::Float(f) => Ok(Value::Integer(f as i64)),
-                Value::Text(s) => s.trim().parse::<i64>().map(Value::Integer).map_err(|_| {
-                    YamlBaseError::Database {
-                        message: format!("Cannot cast '{}' to INTEGER", s),
+            DataType::Int(_)
+            | DataType::Integer(_)
+            | DataType::BigInt(_)
+            | DataType::SmallInt(_)
+            | DataType::TinyInt(_)
+            | DataType::Int2(_)
+            | DataType::Int4(_)
+            | DataType::Int8(_)
+            | DataType::Int64 => {
+                let out_of_range = || YamlBaseError::Database {
+                    message: format!("Value {} is out of range for type INTEGER", value),
+                };
+                // Fractional values are truncated towards zero
+                let from_f64 = |d: f64| {
+                    let truncated = d.trunc();
+                    if truncated.is_finite()
+                        && truncated >= i64::MIN as f64
+                        && truncated < i64::MAX as f64
+                    {
+                        Ok(Value::Integer(truncated as i64))
+                    } else {
+                        Err(out_of_range())
                     }
-                }),
-                Value::Boolean(b) => Ok(Value::Integer(if b { 1 } else { 0 })),
-                Value::Null => Ok(Value::Null),
-                _ => Err(YamlBaseError::Database {
-                    message: format!("Cannot cast {:?} to INTEGER", value),
-                }),
-            },
-            DataType::Float(_) | DataType::Real => {
+                };
+                match &value {
+                    Value::Integer(i) => Ok(Value::Integer(*i)),
+                    Value::Double(d) => from_f64(*d),
+                    Value::Float(f) => from_f64(*f as f64),
+                    Value::Decimal(d) => d
+                        .trunc()
+                        .to_i64()
+                        .map(Value::Integer)
+                        .ok_or_else(out_of_range),
+                    Value::Text(s) => s.trim().parse::<i64>().map(Value::Integer).map_err(|_| {
+                        YamlBaseError::Database {
+                            message: format!("Cannot cast '{}' to INTEGER", s),
+                        }
+                    }),
+                    Value::Boolean(b) => Ok(Value::Integer(if *b { 1 } else { 0 })),
+                    Value::Null => Ok(Value::Null),
+                    _ => Err(YamlBaseError::Database {
+                        message: format!("Cannot cast {:?} to INTEGER", value),
+                    }),
+                }
+            }
+            DataType::Float(_) | DataType::Real | DataType::Float4 => {
                 match value {
                     Value::Integer(i) => Ok(Value::Float(i as f32)),
                     Value::Double(d) => Ok(Value::Float(d as f32)),
                     Value::Float(f) => Ok(Value::Float(f)),
+                    Value::Decimal(d) => Ok(Value::Float(d.to_f32().unwrap_or(f32::NAN))),
                     Value::Text(s) => s.trim().parse::<f32>().map(Value::Float).map_err(|_| {
                         YamlBaseError::Database {
                             message: format!("Cannot cast '{}' to FLOAT", s),
@@ -6708,29 +8062,91 @@ impl QueryExecutor {
                     }),
                 }
             }
-            DataType::Double | DataType::DoublePrecision => match value {
-                Value::Integer(i) => Ok(Value::Double(i as f64)),
-                Value::Double(d) => Ok(Value::Double(d)),
-                Value::Float(f) => Ok(Value::Double(f as f64)),
-                Value::Text(s) => s.trim().parse::<f64>().map(Value::Double).map_err(|_| {
-                    YamlBaseError::Database {
-                        message: format!("Cannot cast '{}' to DOUBLE", s),
+            DataType::Double | DataType::DoublePrecision | DataType::Float8 | DataType::Float64 => {
+                match value {
+                    Value::Integer(i) => Ok(Value::Double(i as f64)),
+                    Value::Double(d) => Ok(Value::Double(d)),
+                    Value::Float(f) => Ok(Value::Double(f as f64)),
+                    Value::Decimal(d) => Ok(Value::Double(d.to_f64().unwrap_or(f64::NAN))),
+                    Value::Text(s) => s.trim().parse::<f64>().map(Value::Double).map_err(|_| {
+                        YamlBaseError::Database {
+                            message: format!("Cannot cast '{}' to DOUBLE", s),
+                        }
+                    }),
+                    Value::Null => Ok(Value::Null),
+                    _ => Err(YamlBaseError::Database {
+                        message: format!("Cannot cast {:?} to DOUBLE", value),
+                    }),
+                }
+            }
+            DataType::Decimal(info) | DataType::Numeric(info) | DataType::Dec(info) => {
+                let decimal = match &value {
+                    Value::Null => return Ok(Value::Null),
+                    Value::Decimal(d) => *d,
+                    Value::Integer(i) => rust_decimal::Decimal::from(*i),
+                    Value::Double(d) => rust_decimal::Decimal::from_f64(*d).ok_or_else(|| {
+                        YamlBaseError::Database {
+                            message: format!("Cannot cast {} to DECIMAL", d),
+                        }
+                    })?,
+                    Value::Float(f) => rust_decimal::Decimal::from_f32(*f).ok_or_else(|| {
+                        YamlBaseError::Database {
+                            message: format!("Cannot cast {} to DECIMAL", f),
+                        }
+                    })?,
+                    Value::Text(s) => {
+                        let trimmed = s.trim();
+                        trimmed
+                            .parse::<rust_decimal::Decimal>()
+                            .or_else(|_| rust_decimal::Decimal::from_scientific(trimmed))
+                            .map_err(|_| YamlBaseError::Database {
+                                message: format!("Cannot cast '{}' to DECIMAL", s),
+                            })?
                     }
-                }),
-                Value::Null => Ok(Value::Null),
-                _ => Err(YamlBaseError::Database {
-                    message: format!("Cannot cast {:?} to DOUBLE", value),
-                }),
-            },
-            DataType::Varchar(_) | DataType::Char(_) | DataType::Text => match value {
-                Value::Integer(i) => Ok(Value::Text(i.to_string())),
-                Value::Double(d) => Ok(Value::Text(d.to_string())),
-                Value::Float(f) => Ok(Value::Text(f.to_string())),
-                Value::Boolean(b) => Ok(Value::Text(b.to_string())),
+                    Value::Boolean(b) => rust_decimal::Decimal::from(*b as i64),
+                    _ => {
+                        return Err(YamlBaseError::Database {
+                            message: format!("Cannot cast {:?} to DECIMAL", value),
+                        });
+                    }
+                };
+
+                let (precision, scale) = match info {
+                    ExactNumberInfo::None => return Ok(Value::Decimal(decimal)),
+                    ExactNumberInfo::Precision(p) => (*p, 0),
+                    ExactNumberInfo::PrecisionAndScale(p, s) => (*p, *s),
+                };
+                // Round to the target scale, then make sure the integer digits still fit
+                let mut rounded = decimal.round_dp_with_strategy(
+                    scale as u32,
+                    rust_decimal::RoundingStrategy::MidpointAwayFromZero,
+                );
+                rounded.rescale(scale as u32);
+                let integer_digits = rounded
+                    .trunc()
+                    .abs()
+                    .to_string()
+                    .trim_start_matches('0')
+                    .len();
+                if integer_digits as u64 > precision.saturating_sub(scale) {
+                    return Err(YamlBaseError::Database {
+                        message: format!(
+                            "Numeric field overflow: {} does not fit in DECIMAL({}, {})",
+                            decimal, precision, scale
+                        ),
+                    });
+                }
+                Ok(Value::Decimal(rounded))
+            }
+            DataType::Varchar(_)
+            | DataType::Char(_)
+            | DataType::Text
+            | DataType::CharacterVarying(_)
+            | DataType::Nvarchar(_)
+            | DataType::String(_) => match value {
                 Value::Text(s) => Ok(Value::Text(s)),
-                Value::Date(d) => Ok(Value::Text(d.format("%Y-%m-%d").to_string())),
                 Value::Null => Ok(Value::Null),
-                _ => Ok(Value::Text(format!("{:?}", value))),
+                other => Ok(Value::Text(other.to_string())),
             },
             DataType::Date => {
                 match value {
@@ -6749,28 +8165,153 @@ impl QueryExecutor {
                         }
                     }
                     Value::Date(d) => Ok(Value::Date(d)),
+                    Value::Timestamp(ts) => Ok(Value::Date(ts.date())),
+                    Value::TimestampTz(ts) => Ok(Value::Date(
+                        TimeZone::default_zone().from_instant(&ts).date_naive(),
+                    )),
                     Value::Null => Ok(Value::Null),
                     _ => Err(YamlBaseError::Database {
                         message: format!("Cannot cast {:?} to DATE", value),
                     }),
                 }
             }
-            DataType::Boolean => match value {
+            DataType::Timestamp(_, TimezoneInfo::WithTimeZone | TimezoneInfo::Tz) => match value {
+                Value::Null => Ok(Value::Null),
+                Value::Text(s) => parse_timestamptz(&s, &TimeZone::default_zone())
+                    .map(Value::TimestampTz)
+                    .ok_or_else(|| YamlBaseError::Database {
+                        message: format!("Cannot cast '{}' to TIMESTAMPTZ", s),
+                    }),
+                other => other
+                    .as_timestamptz()
+                    .map(|ts| Value::TimestampTz(TimeZone::default_zone().from_instant(&ts)))
+                    .ok_or_else(|| YamlBaseError::Database {
+                        message: format!("Cannot cast {:?} to TIMESTAMPTZ", other),
+                    }),
+            },
+            DataType::Timestamp(_, _) | DataType::Datetime(_) => match value {
+                Value::Timestamp(ts) => Ok(Value::Timestamp(ts)),
+                Value::TimestampTz(ts) => Ok(Value::Timestamp(
+                    TimeZone::default_zone().from_instant(&ts).naive_local(),
+                )),
+                Value::Date(d) => Ok(Value::Timestamp(d.and_hms_opt(0, 0, 0).unwrap_or_default())),
+                Value::Text(s) => {
+                    let trimmed = s.trim();
+                    [
+                        "%Y-%m-%d %H:%M:%S%.f",
+                        "%Y-%m-%dT%H:%M:%S%.f",
+                        "%Y-%m-%d %H:%M",
+                    ]
+                    .iter()
+                    .find_map(|format| chrono::NaiveDateTime::parse_from_str(trimmed, format).ok())
+                    .or_else(|| {
+                        chrono::NaiveDate::parse_from_str(trimmed, "%Y-%m-%d")
+                            .ok()
+                            .and_then(|d| d.and_hms_opt(0, 0, 0))
+                    })
+                    .map(Value::Timestamp)
+                    .ok_or_else(|| YamlBaseError::Database {
+                        message: format!("Cannot cast '{}' to TIMESTAMP", s),
+                    })
+                }
+                Value::Null => Ok(Value::Null),
+                _ => Err(YamlBaseError::Database {
+                    message: format!("Cannot cast {:?} to TIMESTAMP", value),
+                }),
+            },
+            DataType::Time(_, _) => match value {
+                Value::Time(t) => Ok(Value::Time(t)),
+                Value::Timestamp(ts) => Ok(Value::Time(ts.time())),
+                Value::TimestampTz(ts) => Ok(Value::Time(
+                    TimeZone::default_zone().from_instant(&ts).time(),
+                )),
+                Value::Text(s) => {
+                    let trimmed = s.trim();
+                    ["%H:%M:%S%.f", "%H:%M"]
+                        .iter()
+                        .find_map(|format| chrono::NaiveTime::parse_from_str(trimmed, format).ok())
+                        .or_else(|| {
+                            chrono::NaiveDateTime::parse_from_str(trimmed, "%Y-%m-%d %H:%M:%S%.f")
+                                .ok()
+                                .map(|ts| ts.time())
+                        })
+                        .map(Value::Time)
+                        .ok_or_else(|| YamlBaseError::Database {
+                            message: format!("Cannot cast '{}' to TIME", s),
+                        })
+                }
+                Value::Null => Ok(Value::Null),
+                _ => Err(YamlBaseError::Database {
+                    message: format!("Cannot cast {:?} to TIME", value),
+                }),
+            },
+            DataType::Boolean | DataType::Bool => match value {
                 Value::Boolean(b) => Ok(Value::Boolean(b)),
                 Value::Integer(i) => Ok(Value::Boolean(i != 0)),
                 Value::Double(d) => Ok(Value::Boolean(d != 0.0)),
                 Value::Float(f) => Ok(Value::Boolean(f != 0.0)),
-                Value::Text(s) => {
-                    let s_lower = s.to_lowercase();
-                    Ok(Value::Boolean(
-                        s_lower == "true" || s_lower == "1" || s_lower == "yes" || s_lower == "on",
-                    ))
-                }
+                Value::Decimal(d) => Ok(Value::Boolean(!d.is_zero())),
+                Value::Text(s) => match s.trim().to_lowercase().as_str() {
+                    "true" | "t" | "yes" | "y" | "on" | "1" => Ok(Value::Boolean(true)),
+                    "false" | "f" | "no" | "n" | "off" | "0" => Ok(Value::Boolean(false)),
+                    _ => Err(YamlBaseError::Database {
+                        message: format!("Cannot cast '{}' to BOOLEAN", s),
+                    }),
+                },
                 Value::Null => Ok(Value::Null),
                 _ => Err(YamlBaseError::Database {
                     message: format!("Cannot cast {:?} to BOOLEAN", value),
                 }),
             },
+            // Text is read as PostgreSQL reads bytea, hex after `\x` or else its own bytes
+            DataType::Bytea
+            | DataType::Blob(_)
+            | DataType::Binary(_)
+            | DataType::Varbinary(_)
+            | DataType::Bytes(_) => {
+                match value {
+                    Value::Bytes(b) => Ok(Value::Bytes(b)),
+                    Value::Text(s) => match s.strip_prefix("\\x") {
+                        Some(digits) => hex::decode(digits).map(Value::Bytes).map_err(|_| {
+                            YamlBaseError::Database {
+                                message: format!("Invalid hexadecimal bytea: '{}'", s),
+                            }
+                        }),
+                        None => Ok(Value::Bytes(s.into_bytes())),
+                    },
+                    Value::Null => Ok(Value::Null),
+                    _ => Err(YamlBaseError::Database {
+                        message: format!("Cannot cast {:?} to BYTEA", value),
+                    }),
+                }
+            }
+            // Text is read as PostgreSQL writes arrays, `{a,b}`, and each element cast
+            DataType::Array(
+                ArrayElemTypeDef::SquareBracket(element_type, _)
+                | ArrayElemTypeDef::AngleBracket(element_type),
+            ) => {
+                let items = match value {
+                    Value::Array(items) => items,
+                    Value::Text(s) => crate::database::schema::parse_array_literal(&s)
+                        .ok_or_else(|| YamlBaseError::Database {
+                            message: format!("Malformed array literal: '{}'", s),
+                        })?
+                        .into_iter()
+                        .map(|element| element.map_or(Value::Null, Value::Text))
+                        .collect(),
+                    Value::Null => return Ok(Value::Null),
+                    _ => {
+                        return Err(YamlBaseError::Database {
+                            message: format!("Cannot cast {:?} to an array", value),
+                        });
+                    }
+                };
+                items
+                    .into_iter()
+                    .map(|item| self.cast_value(item, element_type))
+                    .collect::<crate::Result<_>>()
+                    .map(Value::Array)
+            }
             _ => Err(YamlBaseError::NotImplemented(format!(
                 "CAST to {:?} is not supported",
                 data_type
@@ -6778,25 +8319,79 @@ impl QueryExecutor {
         }
     }
 
+    /// Append the DISTINCT ON key of each row to it, so that the key travels with the row
+    /// through ORDER BY. As in ORDER BY, a key naming an output column by name or position
+    /// takes its value; any other expression is evaluated against the source row.
+    fn append_distinct_on_keys(
+        &self,
+        rows: &mut [Vec<Value>],
+        source_rows: &[impl AsRef<[Value]>],
+        exprs: &[Expr],
+        column_names: &[String],
+        evaluate: impl Fn(&Expr, &[Value]) -> crate::Result<Value>,
+        collation: impl Fn(&Expr, Option<usize>) -> Collation,
+    ) -> crate::Result<()> {
+        let resolved = exprs
+            .iter()
+            .map(|expr| self.resolve_order_by_column(expr, column_names))
+            .collect::<crate::Result<Vec<_>>>()?;
+        let collations: Vec<Collation> = exprs
+            .iter()
+            .zip(&resolved)
+            .map(|(expr, resolved)| collation(expr, *resolved))
+            .collect();
+        for (row, source_row) in rows.iter_mut().zip(source_rows) {
+            let mut key = Vec::with_capacity(exprs.len());
+            for ((expr, resolved), collation) in exprs.iter().zip(&resolved).zip(&collations) {
+                let value = match resolved {
+                    Some(idx) => row[*idx].clone(),
+                    None => evaluate(expr, source_row.as_ref())?,
+                };
+                key.push(Self::collation_key(*collation, &value));
+            }
+            row.extend(key);
+        }
+        Ok(())
+    }
+
+    /// DISTINCT ON over rows carrying their key after the first `width` values: keep the
+    /// first row of each key and drop the key again
+    fn distinct_on_rows(rows: Vec<Vec<Value>>, width: usize) -> Vec<Vec<Value>> {
+        let mut seen = std::collections::HashSet::new();
+        rows.into_iter()
+            .filter_map(|mut row| {
+                let key = row.split_off(width);
+                seen.insert(key).then_some(row)
+            })
+            .collect()
+    }
+
+    /// Remove duplicate rows. Text compares under the collation of its output column;
+    /// columns beyond `collations` use the server default.
     fn apply_distinct(
         &self,
         rows: Vec<Vec<Value>>,
         distinct: &Option<Distinct>,
         columns: &[ProjectionItem],
+        collations: &[Collation],
     ) -> crate::Result<Vec<Vec<Value>>> {
         if rows.is_empty() {
             return Ok(rows);
         }
+        let column_collation = |idx: usize| collations.get(idx).copied().unwrap_or(self.collation);
 
         match distinct {
             Some(Distinct::Distinct) => {
-                // Standard DISTINCT - remove duplicate rows
+                // Standard DISTINCT - keep the first row of each set of equal rows
                 let mut seen = std::collections::HashSet::new();
                 let mut distinct_rows = Vec::new();
                 for row in rows {
-                    // Create a hashable key from the row
-                    // Note: This assumes Value implements Hash and Eq properly
-                    if seen.insert(row.clone()) {
+                    let key: Vec<Value> = row
+                        .iter()
+                        .enumerate()
+                        .map(|(idx, value)| Self::collation_key(column_collation(idx), value))
+                        .collect();
+                    if seen.insert(key) {
                         distinct_rows.push(row);
                     }
                 }
@@ -6818,13 +8413,19 @@ impl QueryExecutor {
                             .enumerate()
                             .map(|(idx, item)| match item {
                                 ProjectionItem::TableColumn(name, _) => (name.clone(), idx),
-                                ProjectionItem::Constant(name, _) => (name.clone(), idx),
+                                ProjectionItem::Constant(name, ..) => (name.clone(), idx),
                                 ProjectionItem::Expression(name, _) => (name.clone(), idx),
                             })
                             .collect();
 
                         let value = self.evaluate_expr_with_row(expr, &row, &column_map)?;
-                        key_values.push(value);
+                        let collation = match expr {
+                            Expr::Identifier(ident) => column_map
+                                .get(&ident.value)
+                                .map_or(self.collation, |idx| column_collation(*idx)),
+                            _ => self.collation,
+                        };
+                        key_values.push(Self::collation_key(collation, &value));
                     }
 
                     // Check if we've seen this combination of values
@@ -6893,6 +8494,11 @@ impl QueryExecutor {
                 }
             }
             Expr::BinaryOp { left, op, right } => {
+                if let Some((operand, interval, subtract)) = Self::interval_operand(left, op, right)
+                {
+                    let value = self.evaluate_expr_with_row(operand, row, column_map)?;
+                    return self.apply_interval(&value, interval, subtract);
+                }
                 let left_val = self.evaluate_expr_with_row(left, row, column_map)?;
                 let right_val = self.evaluate_expr_with_row(right, row, column_map)?;
 
@@ -6946,21 +8552,12 @@ impl QueryExecutor {
             Expr::Value(value) => {
                 // Convert SQL Value to our Value type
                 match value {
-                    sqlparser::ast::Value::Number(n, _) => {
-                        if let Ok(i) = n.parse::<i64>() {
-                            Ok(Value::Integer(i))
-                        } else if let Ok(f) = n.parse::<f64>() {
-                            Ok(Value::Double(f))
-                        } else {
-                            Err(YamlBaseError::Database {
-                                message: format!("Invalid numeric value: {}", n),
-                            })
-                        }
-                    }
+                    sqlparser::ast::Value::Number(n, _) => Self::parse_number_literal(n),
                     sqlparser::ast::Value::SingleQuotedString(s)
                     | sqlparser::ast::Value::DoubleQuotedString(s) => Ok(Value::Text(s.clone())),
                     sqlparser::ast::Value::Boolean(b) => Ok(Value::Boolean(*b)),
                     sqlparser::ast::Value::Null => Ok(Value::Null),
+                    sqlparser::ast::Value::Placeholder(name) => Self::bind_parameter(name),
                     _ => Err(YamlBaseError::Database {
                         message: format!("Value type {:?} not supported in DISTINCT ON", value),
                     }),
@@ -6988,155 +8585,181 @@ impl QueryExecutor {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             });
         }
 
         // Create the virtual table
         let mut table = Table::new(alias.to_string(), columns);
 
-        // Insert all rows from the query result
-        for row in result.rows {
-            table.insert_row(row)?;
+        // Insert all rows from the query result
+        for row in result.rows {
+            table.insert_row(row)?;
+        }
+
+        Ok(table)
+    }
+
+    fn apply_limit(&self, rows: Vec<Vec<Value>>, limit: &Expr) -> crate::Result<Vec<Vec<Value>>> {
+        let limit_val = self.parse_row_count(limit, "LIMIT")?;
+        Ok(rows.into_iter().take(limit_val).collect())
+    }
+
+    /// Apply OFFSET followed by LIMIT or FETCH FIRST to the final rows of a query
+    fn apply_query_limits(
+        &self,
+        mut rows: Vec<Vec<Value>>,
+        query: &Query,
+    ) -> crate::Result<Vec<Vec<Value>>> {
+        // SELECT TOP n (SQL Server / Teradata) is another spelling of LIMIT n
+        if let SetExpr::Select(select) = query.body.as_ref()
+            && let Some(top) = &select.top
+        {
+            if top.with_ties {
+                return Err(YamlBaseError::NotImplemented(
+                    "TOP ... WITH TIES is not supported yet".to_string(),
+                ));
+            }
+            let count = match &top.quantity {
+                Some(sqlparser::ast::TopQuantity::Constant(n)) => *n as usize,
+                Some(sqlparser::ast::TopQuantity::Expr(expr)) => {
+                    self.parse_row_count(expr, "TOP")?
+                }
+                None => {
+                    return Err(YamlBaseError::Database {
+                        message: "TOP requires a row count".to_string(),
+                    });
+                }
+            };
+            let count = if top.percent {
+                (rows.len() * count.min(100)).div_ceil(100)
+            } else {
+                count
+            };
+            rows.truncate(count);
+        }
+
+        let rows = match &query.offset {
+            Some(offset) => {
+                let skip = self.parse_row_count(&offset.value, "OFFSET")?;
+                rows.into_iter().skip(skip).collect()
+            }
+            None => rows,
+        };
+
+        if let Some(limit_expr) = &query.limit {
+            return self.apply_limit(rows, limit_expr);
         }
 
-        Ok(table)
+        match &query.fetch {
+            Some(fetch) => {
+                if fetch.with_ties {
+                    return Err(YamlBaseError::NotImplemented(
+                        "FETCH FIRST ... WITH TIES is not supported yet".to_string(),
+                    ));
+                }
+                let count = match &fetch.quantity {
+                    // FETCH FIRST ROW ONLY
+                    None => 1,
+                    Some(quantity) if fetch.percent => {
+                        let percent = self.parse_row_count(quantity, "FETCH")?.min(100);
+                        (rows.len() * percent).div_ceil(100)
+                    }
+                    Some(quantity) => self.parse_row_count(quantity, "FETCH")?,
+                };
+                Ok(rows.into_iter().take(count).collect())
+            }
+            None => Ok(rows),
+        }
     }
 
-    fn apply_limit(&self, rows: Vec<Vec<Value>>, limit: &Expr) -> crate::Result<Vec<Vec<Value>>> {
-        if let Expr::Value(sqlparser::ast::Value::Number(n, _)) = limit {
-            // Enhanced validation for LIMIT clause edge cases
-
+    /// Validate the row count of a LIMIT, OFFSET or FETCH clause
+    fn parse_row_count(&self, expr: &Expr, clause: &str) -> crate::Result<usize> {
+        if let Expr::Value(sqlparser::ast::Value::Placeholder(name)) = expr {
+            return match Self::bind_parameter(name)? {
+                Value::Integer(count) if (0..=1_000_000_000).contains(&count) => Ok(count as usize),
+                value => Err(YamlBaseError::Database {
+                    message: format!(
+                        "Invalid {} value: '{}' - must be a non-negative integer",
+                        clause, value
+                    ),
+                }),
+            };
+        }
+        if let Expr::Value(sqlparser::ast::Value::Number(n, _)) = expr {
             // Check for negative values first
             if n.starts_with('-') {
                 return Err(YamlBaseError::Database {
-                    message: "LIMIT value must be non-negative".to_string(),
+                    message: format!("{} value must be non-negative", clause),
                 });
             }
 
             // Validate numeric format and range
-            let limit_val: usize = match n.parse::<i64>() {
-                Ok(val) if val < 0 => {
-                    return Err(YamlBaseError::Database {
-                        message: "LIMIT value must be non-negative".to_string(),
-                    });
-                }
-                Ok(val) if val > 1_000_000_000 => {
-                    return Err(YamlBaseError::Database {
-                        message: "LIMIT value too large (maximum: 1,000,000,000)".to_string(),
-                    });
-                }
-                Ok(val) => val as usize,
+            match n.parse::<i64>() {
+                Ok(val) if val < 0 => Err(YamlBaseError::Database {
+                    message: format!("{} value must be non-negative", clause),
+                }),
+                Ok(val) if val > 1_000_000_000 => Err(YamlBaseError::Database {
+                    message: format!("{} value too large (maximum: 1,000,000,000)", clause),
+                }),
+                Ok(val) => Ok(val as usize),
                 Err(_) => {
                     // Try parsing as u64 to catch overflow cases
                     match n.parse::<u64>() {
-                        Ok(val) if val > 1_000_000_000 => {
-                            return Err(YamlBaseError::Database {
-                                message: "LIMIT value too large (maximum: 1,000,000,000)"
-                                    .to_string(),
-                            });
-                        }
-                        Ok(_) | Err(_) => {
-                            return Err(YamlBaseError::Database {
-                                message: format!(
-                                    "Invalid LIMIT value: '{}' - must be a non-negative integer",
-                                    n
-                                ),
-                            });
-                        }
+                        Ok(val) if val > 1_000_000_000 => Err(YamlBaseError::Database {
+                            message: format!("{} value too large (maximum: 1,000,000,000)", clause),
+                        }),
+                        Ok(_) | Err(_) => Err(YamlBaseError::Database {
+                            message: format!(
+                                "Invalid {} value: '{}' - must be a non-negative integer",
+                                clause, n
+                            ),
+                        }),
                     }
                 }
-            };
-
-            // Apply the validated limit
-            Ok(rows.into_iter().take(limit_val).collect())
+            }
         } else {
-            Err(YamlBaseError::NotImplemented(
-                "LIMIT clause supports only numeric literals (expressions not yet supported)"
-                    .to_string(),
-            ))
+            Err(YamlBaseError::NotImplemented(format!(
+                "{} clause supports only numeric literals (expressions not yet supported)",
+                clause
+            )))
         }
     }
 
-    /// Safe string concatenation with memory limits and proper NULL handling
+    /// String concatenation for `||` with memory limits. NULL on either side gives NULL;
+    /// other values are concatenated in their text form, as PostgreSQL does for
+    /// `'Total: ' || 42`.
     fn safe_string_concat(&self, left: &Value, right: &Value) -> crate::Result<Value> {
         const MAX_CONCAT_LENGTH: usize = 10_000_000; // 10MB limit for string concatenation
 
-        match (left, right) {
-            // Handle NULL values (NULL + anything = NULL in SQL)
-            (Value::Null, _) | (_, Value::Null) => Ok(Value::Null),
-
-            // Text concatenation with memory safety
-            (Value::Text(l), Value::Text(r)) => {
-                // Check individual string lengths first
-                if l.len() > MAX_CONCAT_LENGTH || r.len() > MAX_CONCAT_LENGTH {
-                    return Err(YamlBaseError::Database {
-                        message: format!(
-                            "String too large for concatenation (maximum length: {} characters)",
-                            MAX_CONCAT_LENGTH
-                        ),
-                    });
-                }
-
-                // Check combined length to prevent overflow
-                let total_len = l.len().saturating_add(r.len());
-                if total_len > MAX_CONCAT_LENGTH {
-                    return Err(YamlBaseError::Database {
-                        message: format!(
-                            "Concatenated string would be too large: {} characters (maximum: {})",
-                            total_len, MAX_CONCAT_LENGTH
-                        ),
-                    });
-                }
-
-                Ok(Value::Text(format!("{}{}", l, r)))
-            }
-
-            // Type coercion for common cases (numbers to strings)
-            (Value::Integer(l), Value::Text(r)) => {
-                let l_str = l.to_string();
-                let total_len = l_str.len().saturating_add(r.len());
-                if total_len > MAX_CONCAT_LENGTH {
-                    return Err(YamlBaseError::Database {
-                        message: format!(
-                            "Concatenated string would be too large: {} characters",
-                            total_len
-                        ),
-                    });
-                }
-                Ok(Value::Text(format!("{}{}", l_str, r)))
-            }
-
-            (Value::Text(l), Value::Integer(r)) => {
-                let r_str = r.to_string();
-                let total_len = l.len().saturating_add(r_str.len());
-                if total_len > MAX_CONCAT_LENGTH {
-                    return Err(YamlBaseError::Database {
-                        message: format!(
-                            "Concatenated string would be too large: {} characters",
-                            total_len
-                        ),
-                    });
-                }
-                Ok(Value::Text(format!("{}{}", l, r_str)))
-            }
+        let text = |value: &Value| match value {
+            Value::Text(s) => s.clone(),
+            other => other.to_string(),
+        };
+        let (l, r) = match (left, right) {
+            (Value::Null, _) | (_, Value::Null) => return Ok(Value::Null),
+            (l, r) => (text(l), text(r)),
+        };
 
-            // Reject incompatible types
-            _ => Err(YamlBaseError::Database {
+        let total_len = l.len().saturating_add(r.len());
+        if total_len > MAX_CONCAT_LENGTH {
+            return Err(YamlBaseError::Database {
                 message: format!(
-                    "Cannot concatenate values of types {:?} and {:?} - use explicit CAST() for type conversion",
-                    left, right
+                    "Concatenated string would be too large: {} characters (maximum: {})",
+                    total_len, MAX_CONCAT_LENGTH
                 ),
-            }),
+            });
         }
+        Ok(Value::Text(l + &r))
     }
 
     fn is_aggregate_query(&self, select: &Select) -> bool {
         for item in &select.projection {
             match item {
-                SelectItem::UnnamedExpr(expr) | SelectItem::ExprWithAlias { expr, .. } => {
-                    if Self::contains_aggregate_function(expr) {
-                        return true;
-                    }
+                SelectItem::UnnamedExpr(expr) | SelectItem::ExprWithAlias { expr, .. }
+                    if Self::contains_aggregate_function(expr) =>
+                {
+                    return true;
                 }
                 _ => {}
             }
@@ -7153,7 +8776,7 @@ impl QueryExecutor {
                     .first()
                     .map(|ident| ident.value.to_uppercase())
                     .unwrap_or_default();
-                matches!(func_name.as_str(), "COUNT" | "SUM" | "AVG" | "MIN" | "MAX")
+                Self::is_aggregate_name(&func_name)
             }
             // Recursively check binary operations (e.g., MAX(salary) - MIN(salary))
             Expr::BinaryOp { left, right, .. } => {
@@ -7168,10 +8791,10 @@ impl QueryExecutor {
                 results,
                 else_result,
             } => {
-                if let Some(operand_expr) = operand {
-                    if Self::contains_aggregate_function(operand_expr) {
-                        return true;
-                    }
+                if let Some(operand_expr) = operand
+                    && Self::contains_aggregate_function(operand_expr)
+                {
+                    return true;
                 }
                 for condition in conditions {
                     if Self::contains_aggregate_function(condition) {
@@ -7183,10 +8806,10 @@ impl QueryExecutor {
                         return true;
                     }
                 }
-                if let Some(else_expr) = else_result {
-                    if Self::contains_aggregate_function(else_expr) {
-                        return true;
-                    }
+                if let Some(else_expr) = else_result
+                    && Self::contains_aggregate_function(else_expr)
+                {
+                    return true;
                 }
                 false
             }
@@ -7227,15 +8850,14 @@ impl QueryExecutor {
                         .map(|(idx, name)| (name.clone(), idx))
                         .collect();
 
-                    let sorted_rows = self.sort_rows(result.rows, &order_by.exprs, &col_info)?;
+                    let order_exprs =
+                        Self::order_by_with_projection_positions(select, &order_by.exprs);
+                    let sorted_rows = self.sort_rows(result.rows, &order_exprs, &col_info)?;
                     result.rows = sorted_rows;
                 }
 
                 // Apply LIMIT and OFFSET
-                if let Some(limit_expr) = &_query.limit {
-                    let limit_rows = self.apply_limit(result.rows, limit_expr)?;
-                    result.rows = limit_rows;
-                }
+                result.rows = self.apply_query_limits(result.rows, _query)?;
 
                 return Ok(result);
             }
@@ -7274,13 +8896,14 @@ impl QueryExecutor {
         }
 
         // Determine column types for aggregate results
+        let table_column_type = Self::table_column_type(table);
         let column_types = select
             .projection
             .iter()
-            .map(|item| match item {
-                SelectItem::UnnamedExpr(expr) | SelectItem::ExprWithAlias { expr, .. } => {
-                    self.get_aggregate_result_type(expr)
-                }
+            .zip(&row_values)
+            .map(|(item, value)| match item {
+                SelectItem::UnnamedExpr(expr) | SelectItem::ExprWithAlias { expr, .. } => self
+                    .projected_expr_type(Self::infer_expr_type(expr, &table_column_type), [value]),
                 _ => crate::yaml::schema::SqlType::Text,
             })
             .collect();
@@ -7289,6 +8912,7 @@ impl QueryExecutor {
             columns,
             column_types,
             rows: vec![row_values],
+            affected_rows: None,
         })
     }
 
@@ -7311,17 +8935,36 @@ impl QueryExecutor {
             }
         };
 
-        // Step 1: Evaluate GROUP BY expressions for each row to create groups
-        let mut groups: std::collections::HashMap<Vec<Value>, Vec<&Vec<Value>>> =
-            std::collections::HashMap::new();
+        // Step 1: Evaluate GROUP BY expressions for each row to create groups. Text groups
+        // under its collation; a group reports the values of its first row.
+        let collations: Vec<Collation> = group_by_exprs
+            .iter()
+            .map(|expr| {
+                self.expr_collation(expr, &|expr| Self::table_column_collation(expr, table))
+            })
+            .collect();
+        #[allow(clippy::type_complexity)]
+        let mut groups: std::collections::HashMap<
+            Vec<Value>,
+            (Vec<Value>, Vec<&Vec<Value>>),
+        > = std::collections::HashMap::new();
 
         for row in filtered_rows {
-            let mut group_key = Vec::new();
+            let mut group_values = Vec::new();
             for expr in group_by_exprs {
                 let value = self.get_expr_value(expr, row, table)?;
-                group_key.push(value);
+                group_values.push(value);
             }
-            groups.entry(group_key).or_default().push(row);
+            let group_key = group_values
+                .iter()
+                .zip(&collations)
+                .map(|(value, collation)| Self::collation_key(*collation, value))
+                .collect();
+            groups
+                .entry(group_key)
+                .or_insert_with(|| (group_values, Vec::new()))
+                .1
+                .push(row);
         }
 
         // Step 2: Process each group
@@ -7330,7 +8973,7 @@ impl QueryExecutor {
         let mut column_types = Vec::new();
         let mut first_row = true;
 
-        for (group_values, group_rows) in groups {
+        for (group_values, group_rows) in groups.into_values() {
             let mut row_values = Vec::new();
 
             // Process each projection item
@@ -7403,6 +9046,7 @@ impl QueryExecutor {
             columns,
             column_types,
             rows: result_rows,
+            affected_rows: None,
         })
     }
 
@@ -7445,160 +9089,183 @@ impl QueryExecutor {
                 )?;
 
                 // Perform the binary operation
-                let result = match op {
-                    BinaryOperator::Plus => match (&left_val, &right_val) {
-                        (Value::Integer(l), Value::Integer(r)) => Ok(Value::Integer(l + r)),
-                        (Value::Double(l), Value::Double(r)) => Ok(Value::Double(l + r)),
-                        (Value::Integer(l), Value::Double(r)) => Ok(Value::Double(*l as f64 + r)),
-                        (Value::Double(l), Value::Integer(r)) => Ok(Value::Double(l + *r as f64)),
-                        // Date arithmetic: DATE + INTEGER (days)
-                        (Value::Date(date), Value::Integer(days)) => {
-                            match date.checked_add_days(chrono::Days::new(*days as u64)) {
-                                Some(new_date) => Ok(Value::Date(new_date)),
-                                None => Err(YamlBaseError::Database {
-                                    message: "Date arithmetic overflow".to_string(),
-                                }),
+                let result = match Self::decimal_arithmetic(&left_val, op, &right_val) {
+                    Some(result) => result,
+                    None => match op {
+                        BinaryOperator::Plus => match (&left_val, &right_val) {
+                            (Value::Integer(l), Value::Integer(r)) => Ok(Value::Integer(l + r)),
+                            (Value::Double(l), Value::Double(r)) => Ok(Value::Double(l + r)),
+                            (Value::Integer(l), Value::Double(r)) => {
+                                Ok(Value::Double(*l as f64 + r))
                             }
-                        }
-                        (Value::Integer(days), Value::Date(date)) => {
-                            match date.checked_add_days(chrono::Days::new(*days as u64)) {
-                                Some(new_date) => Ok(Value::Date(new_date)),
-                                None => Err(YamlBaseError::Database {
-                                    message: "Date arithmetic overflow".to_string(),
-                                }),
+                            (Value::Double(l), Value::Integer(r)) => {
+                                Ok(Value::Double(l + *r as f64))
                             }
-                        }
-                        (Value::Date(date), Value::Double(days)) => {
-                            let days_int = days.round() as i64;
-                            if days_int >= 0 {
-                                match date.checked_add_days(chrono::Days::new(days_int as u64)) {
+                            // Date arithmetic: DATE + INTEGER (days)
+                            (Value::Date(date), Value::Integer(days)) => {
+                                match date.checked_add_days(chrono::Days::new(*days as u64)) {
                                     Some(new_date) => Ok(Value::Date(new_date)),
                                     None => Err(YamlBaseError::Database {
                                         message: "Date arithmetic overflow".to_string(),
                                     }),
                                 }
-                            } else {
-                                match date.checked_sub_days(chrono::Days::new((-days_int) as u64)) {
+                            }
+                            (Value::Integer(days), Value::Date(date)) => {
+                                match date.checked_add_days(chrono::Days::new(*days as u64)) {
                                     Some(new_date) => Ok(Value::Date(new_date)),
                                     None => Err(YamlBaseError::Database {
                                         message: "Date arithmetic overflow".to_string(),
                                     }),
                                 }
                             }
-                        }
-                        (Value::Null, _) | (_, Value::Null) => Ok(Value::Null),
-                        _ => Err(YamlBaseError::Database {
-                            message: "Cannot add non-numeric values".to_string(),
-                        }),
-                    },
-                    BinaryOperator::Minus => match (&left_val, &right_val) {
-                        (Value::Integer(l), Value::Integer(r)) => Ok(Value::Integer(l - r)),
-                        (Value::Double(l), Value::Double(r)) => Ok(Value::Double(l - r)),
-                        (Value::Integer(l), Value::Double(r)) => Ok(Value::Double(*l as f64 - r)),
-                        (Value::Double(l), Value::Integer(r)) => Ok(Value::Double(l - *r as f64)),
-                        // Date arithmetic: DATE - INTEGER (days)
-                        (Value::Date(date), Value::Integer(days)) => {
-                            if *days >= 0 {
-                                match date.checked_sub_days(chrono::Days::new(*days as u64)) {
-                                    Some(new_date) => Ok(Value::Date(new_date)),
-                                    None => Err(YamlBaseError::Database {
-                                        message: "Date arithmetic overflow".to_string(),
-                                    }),
+                            (Value::Date(date), Value::Double(days)) => {
+                                let days_int = days.round() as i64;
+                                if days_int >= 0 {
+                                    match date.checked_add_days(chrono::Days::new(days_int as u64))
+                                    {
+                                        Some(new_date) => Ok(Value::Date(new_date)),
+                                        None => Err(YamlBaseError::Database {
+                                            message: "Date arithmetic overflow".to_string(),
+                                        }),
+                                    }
+                                } else {
+                                    match date
+                                        .checked_sub_days(chrono::Days::new((-days_int) as u64))
+                                    {
+                                        Some(new_date) => Ok(Value::Date(new_date)),
+                                        None => Err(YamlBaseError::Database {
+                                            message: "Date arithmetic overflow".to_string(),
+                                        }),
+                                    }
                                 }
-                            } else {
-                                match date.checked_add_days(chrono::Days::new((-days) as u64)) {
-                                    Some(new_date) => Ok(Value::Date(new_date)),
-                                    None => Err(YamlBaseError::Database {
-                                        message: "Date arithmetic overflow".to_string(),
-                                    }),
+                            }
+                            (Value::Null, _) | (_, Value::Null) => Ok(Value::Null),
+                            _ => Err(YamlBaseError::Database {
+                                message: "Cannot add non-numeric values".to_string(),
+                            }),
+                        },
+                        BinaryOperator::Minus => match (&left_val, &right_val) {
+                            (Value::Integer(l), Value::Integer(r)) => Ok(Value::Integer(l - r)),
+                            (Value::Double(l), Value::Double(r)) => Ok(Value::Double(l - r)),
+                            (Value::Integer(l), Value::Double(r)) => {
+                                Ok(Value::Double(*l as f64 - r))
+                            }
+                            (Value::Double(l), Value::Integer(r)) => {
+                                Ok(Value::Double(l - *r as f64))
+                            }
+                            // Date arithmetic: DATE - INTEGER (days)
+                            (Value::Date(date), Value::Integer(days)) => {
+                                if *days >= 0 {
+                                    match date.checked_sub_days(chrono::Days::new(*days as u64)) {
+                                        Some(new_date) => Ok(Value::Date(new_date)),
+                                        None => Err(YamlBaseError::Database {
+                                            message: "Date arithmetic overflow".to_string(),
+                                        }),
+                                    }
+                                } else {
+                                    match date.checked_add_days(chrono::Days::new((-days) as u64)) {
+                                        Some(new_date) => Ok(Value::Date(new_date)),
+                                        None => Err(YamlBaseError::Database {
+                                            message: "Date arithmetic overflow".to_string(),
+                                        }),
+                                    }
+                                }
+                            }
+                            (Value::Date(date), Value::Double(days)) => {
+                                let days_int = days.round() as i64;
+                                if days_int >= 0 {
+                                    match date.checked_sub_days(chrono::Days::new(days_int as u64))
+                                    {
+                                        Some(new_date) => Ok(Value::Date(new_date)),
+                                        None => Err(YamlBaseError::Database {
+                                            message: "Date arithmetic overflow".to_string(),
+                                        }),
+                                    }
+                                } else {
+                                    match date
+                                        .checked_add_days(chrono::Days::new((-days_int) as u64))
+                                    {
+                                        Some(new_date) => Ok(Value::Date(new_date)),
+                                        None => Err(YamlBaseError::Database {
+                                            message: "Date arithmetic overflow".to_string(),
+                                        }),
+                                    }
                                 }
                             }
-                        }
-                        (Value::Date(date), Value::Double(days)) => {
-                            let days_int = days.round() as i64;
-                            if days_int >= 0 {
-                                match date.checked_sub_days(chrono::Days::new(days_int as u64)) {
-                                    Some(new_date) => Ok(Value::Date(new_date)),
-                                    None => Err(YamlBaseError::Database {
-                                        message: "Date arithmetic overflow".to_string(),
-                                    }),
-                                }
-                            } else {
-                                match date.checked_add_days(chrono::Days::new((-days_int) as u64)) {
-                                    Some(new_date) => Ok(Value::Date(new_date)),
-                                    None => Err(YamlBaseError::Database {
-                                        message: "Date arithmetic overflow".to_string(),
-                                    }),
+                            // Date - Date = Integer (days difference)
+                            (Value::Date(date1), Value::Date(date2)) => {
+                                let days_diff = (*date1 - *date2).num_days();
+                                Ok(Value::Integer(days_diff))
+                            }
+                            (Value::Null, _) | (_, Value::Null) => Ok(Value::Null),
+                            _ => Err(YamlBaseError::Database {
+                                message: "Cannot subtract non-numeric values".to_string(),
+                            }),
+                        },
+                        BinaryOperator::Multiply => match (&left_val, &right_val) {
+                            (Value::Integer(l), Value::Integer(r)) => Ok(Value::Integer(l * r)),
+                            (Value::Double(l), Value::Double(r)) => Ok(Value::Double(l * r)),
+                            (Value::Integer(l), Value::Double(r)) => {
+                                Ok(Value::Double(*l as f64 * r))
+                            }
+                            (Value::Double(l), Value::Integer(r)) => {
+                                Ok(Value::Double(l * *r as f64))
+                            }
+                            (Value::Null, _) | (_, Value::Null) => Ok(Value::Null),
+                            _ => Err(YamlBaseError::Database {
+                                message: "Cannot multiply non-numeric values".to_string(),
+                            }),
+                        },
+                        BinaryOperator::Divide => match (&left_val, &right_val) {
+                            (Value::Integer(l), Value::Integer(r)) => {
+                                if *r == 0 {
+                                    Err(YamlBaseError::Database {
+                                        message: "Division by zero".to_string(),
+                                    })
+                                } else {
+                                    Ok(Value::Double(*l as f64 / *r as f64))
                                 }
                             }
-                        }
-                        // Date - Date = Integer (days difference)
-                        (Value::Date(date1), Value::Date(date2)) => {
-                            let days_diff = (*date1 - *date2).num_days();
-                            Ok(Value::Integer(days_diff))
-                        }
-                        (Value::Null, _) | (_, Value::Null) => Ok(Value::Null),
-                        _ => Err(YamlBaseError::Database {
-                            message: "Cannot subtract non-numeric values".to_string(),
-                        }),
-                    },
-                    BinaryOperator::Multiply => match (&left_val, &right_val) {
-                        (Value::Integer(l), Value::Integer(r)) => Ok(Value::Integer(l * r)),
-                        (Value::Double(l), Value::Double(r)) => Ok(Value::Double(l * r)),
-                        (Value::Integer(l), Value::Double(r)) => Ok(Value::Double(*l as f64 * r)),
-                        (Value::Double(l), Value::Integer(r)) => Ok(Value::Double(l * *r as f64)),
-                        (Value::Null, _) | (_, Value::Null) => Ok(Value::Null),
-                        _ => Err(YamlBaseError::Database {
-                            message: "Cannot multiply non-numeric values".to_string(),
-                        }),
-                    },
-                    BinaryOperator::Divide => match (&left_val, &right_val) {
-                        (Value::Integer(l), Value::Integer(r)) => {
-                            if *r == 0 {
-                                Err(YamlBaseError::Database {
-                                    message: "Division by zero".to_string(),
-                                })
-                            } else {
-                                Ok(Value::Double(*l as f64 / *r as f64))
-                            }
-                        }
-                        (Value::Double(l), Value::Double(r)) => {
-                            if *r == 0.0 {
-                                Err(YamlBaseError::Database {
-                                    message: "Division by zero".to_string(),
-                                })
-                            } else {
-                                Ok(Value::Double(l / r))
+                            (Value::Double(l), Value::Double(r)) => {
+                                if *r == 0.0 {
+                                    Err(YamlBaseError::Database {
+                                        message: "Division by zero".to_string(),
+                                    })
+                                } else {
+                                    Ok(Value::Double(l / r))
+                                }
                             }
-                        }
-                        (Value::Integer(l), Value::Double(r)) => {
-                            if *r == 0.0 {
-                                Err(YamlBaseError::Database {
-                                    message: "Division by zero".to_string(),
-                                })
-                            } else {
-                                Ok(Value::Double(*l as f64 / r))
+                            (Value::Integer(l), Value::Double(r)) => {
+                                if *r == 0.0 {
+                                    Err(YamlBaseError::Database {
+                                        message: "Division by zero".to_string(),
+                                    })
+                                } else {
+                                    Ok(Value::Double(*l as f64 / r))
+                                }
                             }
-                        }
-                        (Value::Double(l), Value::Integer(r)) => {
-                            if *r == 0 {
-                                Err(YamlBaseError::Database {
-                                    message: "Division by zero".to_string(),
-                                })
-                            } else {
-                                Ok(Value::Double(l / *r as f64))
+                            (Value::Double(l), Value::Integer(r)) => {
+                                if *r == 0 {
+                                    Err(YamlBaseError::Database {
+                                        message: "Division by zero".to_string(),
+                                    })
+                                } else {
+                                    Ok(Value::Double(l / *r as f64))
+                                }
                             }
+                            (Value::Null, _) | (_, Value::Null) => Ok(Value::Null),
+                            _ => Err(YamlBaseError::Database {
+                                message: "Cannot divide non-numeric values".to_string(),
+                            }),
+                        },
+                        BinaryOperator::StringConcat => {
+                            self.safe_string_concat(&left_val, &right_val)
                         }
-                        (Value::Null, _) | (_, Value::Null) => Ok(Value::Null),
-                        _ => Err(YamlBaseError::Database {
-                            message: "Cannot divide non-numeric values".to_string(),
-                        }),
+                        _ => Err(YamlBaseError::NotImplemented(format!(
+                            "Binary operator {:?} not implemented in GROUP BY context",
+                            op
+                        ))),
                     },
-                    BinaryOperator::StringConcat => self.safe_string_concat(&left_val, &right_val),
-                    _ => Err(YamlBaseError::NotImplemented(format!(
-                        "Binary operator {:?} not implemented in GROUP BY context",
-                        op
-                    ))),
                 }?;
 
                 let col_name = format!(
@@ -7631,9 +9298,18 @@ impl QueryExecutor {
             // If this is an aggregate function, evaluate it over the group
             Expr::Function(func) if self.is_aggregate_function(&func.name.0[0].value) => {
                 let (col_name, value) = self.evaluate_aggregate_expr(expr, group_rows, table, 0)?;
-                let col_type = self.get_aggregate_result_type(expr);
+                let col_type = match value {
+                    Value::Decimal(_) => self.infer_value_type(&value),
+                    _ => self.get_aggregate_result_type(expr),
+                };
                 Ok((col_name, col_type, value))
             }
+            // Literals, e.g. the separator in `name || ' - ' || COUNT(*)`
+            Expr::Value(_) => {
+                let value = self.evaluate_constant_expr(expr)?;
+                let col_type = self.infer_value_type(&value);
+                Ok((self.expr_to_string(expr), col_type, value))
+            }
             // Regular column references in GROUP BY context
             Expr::Identifier(ident) => {
                 // This should be one of the GROUP BY columns
@@ -7730,7 +9406,7 @@ impl QueryExecutor {
         }
     }
 
-    fn infer_value_type(&self, value: &Value) -> crate::yaml::schema::SqlType {
+    pub(crate) fn infer_value_type(&self, value: &Value) -> crate::yaml::schema::SqlType {
         match value {
             Value::Integer(_) => crate::yaml::schema::SqlType::BigInt,
             Value::Float(_) => crate::yaml::schema::SqlType::Float,
@@ -7740,20 +9416,51 @@ impl QueryExecutor {
             Value::Boolean(_) => crate::yaml::schema::SqlType::Boolean,
             Value::Date(_) => crate::yaml::schema::SqlType::Date,
             Value::Timestamp(_) => crate::yaml::schema::SqlType::Timestamp,
+            Value::TimestampTz(_) => crate::yaml::schema::SqlType::TimestampTz,
             Value::Time(_) => crate::yaml::schema::SqlType::Time,
             Value::Uuid(_) => crate::yaml::schema::SqlType::Text, // UUIDs as text
             Value::Json(_) => crate::yaml::schema::SqlType::Json,
+            Value::Bytes(_) => crate::yaml::schema::SqlType::Blob,
+            // The type of the first element that is not NULL
+            Value::Array(items) => crate::yaml::schema::SqlType::Array(Box::new(
+                items
+                    .iter()
+                    .find(|item| !matches!(item, Value::Null))
+                    .map_or(crate::yaml::schema::SqlType::Text, |item| {
+                        self.infer_value_type(item)
+                    }),
+            )),
             Value::Null => crate::yaml::schema::SqlType::Text,
         }
     }
 
     fn is_aggregate_function(&self, func_name: &str) -> bool {
+        Self::is_aggregate_name(&func_name.to_uppercase())
+    }
+
+    // Expects an upper-cased function name
+    fn is_aggregate_name(func_name: &str) -> bool {
+        matches!(func_name, "COUNT" | "SUM" | "AVG" | "MIN" | "MAX")
+            || Self::is_statistical_aggregate(func_name)
+            || Self::is_ordered_set_aggregate(func_name)
+            || Self::is_json_aggregate(func_name)
+    }
+
+    pub(crate) fn is_statistical_aggregate(func_name: &str) -> bool {
         matches!(
-            func_name.to_uppercase().as_str(),
-            "COUNT" | "SUM" | "AVG" | "MIN" | "MAX"
+            func_name,
+            "STDDEV" | "STDDEV_SAMP" | "STDDEV_POP" | "VARIANCE" | "VAR_SAMP" | "VAR_POP"
         )
     }
 
+    fn is_ordered_set_aggregate(func_name: &str) -> bool {
+        matches!(func_name, "PERCENTILE_CONT" | "PERCENTILE_DISC" | "MEDIAN")
+    }
+
+    fn is_json_aggregate(func_name: &str) -> bool {
+        matches!(func_name, "JSON_AGG" | "JSONB_AGG" | "JSON_ARRAYAGG")
+    }
+
     async fn evaluate_case_when_async(
         &self,
         operand: Option<&Expr>,
@@ -7938,6 +9645,11 @@ impl QueryExecutor {
                     "COUNT" => crate::yaml::schema::SqlType::BigInt, // COUNT returns i64
                     "SUM" => crate::yaml::schema::SqlType::Double,   // SUM returns double
                     "AVG" => crate::yaml::schema::SqlType::Double,
+                    name if Self::is_statistical_aggregate(name) => {
+                        crate::yaml::schema::SqlType::Double
+                    }
+                    "PERCENTILE_CONT" | "MEDIAN" => crate::yaml::schema::SqlType::Double,
+                    name if Self::is_json_aggregate(name) => crate::yaml::schema::SqlType::Json,
                     "MIN" | "MAX" => crate::yaml::schema::SqlType::Text, // Depends on input type, default to text
                     _ => crate::yaml::schema::SqlType::Text,
                 }
@@ -7961,131 +9673,73 @@ impl QueryExecutor {
                     .first()
                     .map(|ident| ident.value.to_uppercase())
                     .unwrap_or_default();
+                let rows = &*Self::apply_aggregate_filter(func, rows, |filter, row| {
+                    self.evaluate_expr(filter, row, table)
+                })?;
 
                 match func_name.as_str() {
                     "COUNT" => {
-                        let count = match &func.args {
-                            FunctionArguments::None => {
-                                // COUNT() - should be an error but treat as COUNT(*)
-                                rows.len() as i64
-                            }
-                            FunctionArguments::List(args) => {
-                                // Check for DISTINCT
-                                let is_distinct = args
-                                    .duplicate_treatment
-                                    .as_ref()
-                                    .map(|dt| matches!(dt, DuplicateTreatment::Distinct))
-                                    .unwrap_or(false);
-
-                                if args.args.is_empty() {
-                                    // COUNT() - should be an error but treat as COUNT(*)
-                                    rows.len() as i64
-                                } else if args.args.len() == 1 {
-                                    match &args.args[0] {
-                                        FunctionArg::Unnamed(FunctionArgExpr::Wildcard) => {
-                                            // COUNT(*) - DISTINCT is not allowed with *
-                                            rows.len() as i64
-                                        }
-                                        FunctionArg::Unnamed(FunctionArgExpr::Expr(expr)) => {
-                                            // COUNT(column) or COUNT(DISTINCT column)
-                                            if is_distinct {
-                                                // COUNT(DISTINCT column)
-                                                let mut unique_values =
-                                                    std::collections::HashSet::new();
-                                                for row in rows {
-                                                    let value =
-                                                        self.get_expr_value(expr, row, table)?;
-                                                    if !matches!(value, Value::Null) {
-                                                        unique_values.insert(value);
-                                                    }
-                                                }
-                                                unique_values.len() as i64
-                                            } else {
-                                                // COUNT(column)
-                                                let mut count = 0i64;
-                                                for row in rows {
-                                                    let value =
-                                                        self.get_expr_value(expr, row, table)?;
-                                                    if !matches!(value, Value::Null) {
-                                                        count += 1;
-                                                    }
-                                                }
-                                                count
-                                            }
-                                        }
-                                        _ => {
-                                            return Err(YamlBaseError::NotImplemented(
-                                                "Unsupported COUNT argument".to_string(),
-                                            ));
-                                        }
-                                    }
-                                } else {
-                                    return Err(YamlBaseError::Database {
-                                        message: "COUNT expects at most one argument".to_string(),
-                                    });
-                                }
-                            }
-                            _ => {
-                                return Err(YamlBaseError::NotImplemented(
-                                    "Unsupported function arguments".to_string(),
-                                ));
+                        let arg_exprs = Self::count_argument_exprs(func)?;
+                        let mut argument_values = Vec::with_capacity(arg_exprs.len());
+                        for arg_expr in &arg_exprs {
+                            let mut values = Vec::with_capacity(rows.len());
+                            for row in rows {
+                                values.push(self.get_expr_value(arg_expr, row, table)?);
                             }
-                        };
+                            argument_values.push(values);
+                        }
+                        let is_distinct = Self::is_distinct_aggregate(func);
+                        let collation = arg_exprs.first().map_or(self.collation, |expr| {
+                            self.expr_collation(expr, &|expr| {
+                                Self::table_column_collation(expr, table)
+                            })
+                        });
+                        let count = Self::calculate_count(
+                            rows.len(),
+                            &argument_values,
+                            is_distinct.then_some(collation),
+                        );
+
                         // Generate proper column name
-                        let col_name = match &func.args {
-                            FunctionArguments::List(args) if !args.args.is_empty() => {
-                                match &args.args[0] {
-                                    FunctionArg::Unnamed(FunctionArgExpr::Wildcard) => {
-                                        "COUNT(*)".to_string()
-                                    }
-                                    FunctionArg::Unnamed(FunctionArgExpr::Expr(expr)) => {
-                                        if args
-                                            .duplicate_treatment
-                                            .as_ref()
-                                            .map(|dt| matches!(dt, DuplicateTreatment::Distinct))
-                                            .unwrap_or(false)
-                                        {
-                                            format!("COUNT(DISTINCT {})", self.expr_to_string(expr))
-                                        } else {
-                                            format!("COUNT({})", self.expr_to_string(expr))
-                                        }
-                                    }
-                                    _ => func_name.clone(),
-                                }
+                        let col_name = if !arg_exprs.is_empty() {
+                            let args = arg_exprs
+                                .iter()
+                                .map(|expr| self.expr_to_string(expr))
+                                .collect::<Vec<_>>()
+                                .join(", ");
+                            if is_distinct {
+                                format!("COUNT(DISTINCT {})", args)
+                            } else {
+                                format!("COUNT({})", args)
                             }
-                            _ => func_name.clone(),
+                        } else if matches!(&func.args, FunctionArguments::List(args) if !args.args.is_empty())
+                        {
+                            "COUNT(*)".to_string()
+                        } else {
+                            func_name.clone()
                         };
-                        Ok((col_name, Value::Integer(count)))
+                        Ok((col_name, count))
                     }
                     "SUM" => {
                         match &func.args {
                             FunctionArguments::List(args) if args.args.len() == 1 => {
                                 match &args.args[0] {
                                     FunctionArg::Unnamed(FunctionArgExpr::Expr(expr)) => {
-                                        let mut sum = 0.0;
+                                        let mut values = Vec::with_capacity(rows.len());
                                         for row in rows {
-                                            let value = self.get_expr_value(expr, row, table)?;
-                                            match value {
-                                                Value::Integer(i) => sum += i as f64,
-                                                Value::Double(d) => sum += d,
-                                                Value::Float(f) => sum += f as f64,
-                                                Value::Decimal(d) => {
-                                                    sum +=
-                                                        d.to_string().parse::<f64>().unwrap_or(0.0)
-                                                }
-                                                Value::Null => {} // Skip NULL values
-                                                _ => {
-                                                    return Err(YamlBaseError::Database {
-                                                        message: "Cannot sum non-numeric values"
-                                                            .to_string(),
-                                                    });
-                                                }
-                                            }
+                                            values.push(self.get_expr_value(expr, row, table)?);
                                         }
-                                        // Return as Double, not Text
+                                        let values = Self::apply_aggregate_distinct(func, values);
+                                        // DECIMAL sums stay exact; others are returned as Double
+                                        let sum = match self.calculate_sum(&values)? {
+                                            Value::Decimal(d) => Value::Decimal(d),
+                                            Value::Integer(i) => Value::Double(i as f64),
+                                            Value::Null => Value::Double(0.0),
+                                            sum => sum,
+                                        };
                                         let col_name =
                                             format!("SUM({})", self.expr_to_string(expr));
-                                        Ok((col_name, Value::Double(sum)))
+                                        Ok((col_name, sum))
                                     }
                                     _ => Err(YamlBaseError::NotImplemented(
                                         "Unsupported SUM argument".to_string(),
@@ -8102,42 +9756,18 @@ impl QueryExecutor {
                             if args.args.len() == 1 {
                                 match &args.args[0] {
                                     FunctionArg::Unnamed(FunctionArgExpr::Expr(expr)) => {
-                                        let mut sum = 0.0;
-                                        let mut count = 0;
-
+                                        let mut values = Vec::with_capacity(rows.len());
                                         for row in rows {
-                                            let value = self.get_expr_value(expr, row, table)?;
-                                            match value {
-                                                Value::Integer(i) => {
-                                                    sum += i as f64;
-                                                    count += 1;
-                                                }
-                                                Value::Double(d) => {
-                                                    sum += d;
-                                                    count += 1;
-                                                }
-                                                Value::Float(f) => {
-                                                    sum += f as f64;
-                                                    count += 1;
-                                                }
-                                                Value::Decimal(d) => {
-                                                    sum += d.to_f64().unwrap_or(0.0);
-                                                    count += 1;
-                                                }
-                                                Value::Null => {} // Skip NULL values
-                                                _ => {
-                                                    return Err(YamlBaseError::Database {
-                                                        message: "AVG requires numeric values"
-                                                            .to_string(),
-                                                    });
-                                                }
-                                            }
+                                            values.push(self.get_expr_value(expr, row, table)?);
                                         }
-
-                                        let avg = if count > 0 { sum / count as f64 } else { 0.0 };
+                                        let values = Self::apply_aggregate_distinct(func, values);
+                                        let avg = match self.calculate_avg(&values)? {
+                                            Value::Null => Value::Double(0.0),
+                                            avg => avg,
+                                        };
                                         let col_name =
                                             format!("AVG({})", self.expr_to_string(expr));
-                                        Ok((col_name, Value::Double(avg)))
+                                        Ok((col_name, avg))
                                     }
                                     _ => Err(YamlBaseError::NotImplemented(
                                         "Unsupported AVG argument".to_string(),
@@ -8169,10 +9799,9 @@ impl QueryExecutor {
                                                     Some(current_min) => {
                                                         if let Some(ord) =
                                                             value.compare(current_min)
+                                                            && ord.is_lt()
                                                         {
-                                                            if ord.is_lt() {
-                                                                min_value = Some(value);
-                                                            }
+                                                            min_value = Some(value);
                                                         }
                                                     }
                                                 }
@@ -8213,10 +9842,9 @@ impl QueryExecutor {
                                                     Some(current_max) => {
                                                         if let Some(ord) =
                                                             value.compare(current_max)
+                                                            && ord.is_gt()
                                                         {
-                                                            if ord.is_gt() {
-                                                                max_value = Some(value);
-                                                            }
+                                                            max_value = Some(value);
                                                         }
                                                     }
                                                 }
@@ -8242,6 +9870,52 @@ impl QueryExecutor {
                             ))
                         }
                     }
+                    name if Self::is_statistical_aggregate(name) => match &func.args {
+                        FunctionArguments::List(args) if args.args.len() == 1 => {
+                            match &args.args[0] {
+                                FunctionArg::Unnamed(FunctionArgExpr::Expr(expr)) => {
+                                    let mut values = Vec::with_capacity(rows.len());
+                                    for row in rows {
+                                        values.push(self.get_expr_value(expr, row, table)?);
+                                    }
+                                    let value =
+                                        self.calculate_statistical_aggregate(name, &values)?;
+                                    let col_name =
+                                        format!("{}({})", name, self.expr_to_string(expr));
+                                    Ok((col_name, value))
+                                }
+                                _ => Err(YamlBaseError::NotImplemented(format!(
+                                    "Unsupported {} argument",
+                                    name
+                                ))),
+                            }
+                        }
+                        _ => Err(YamlBaseError::Database {
+                            message: format!("{} requires exactly one argument", name),
+                        }),
+                    },
+                    name if Self::is_json_aggregate(name) => {
+                        let value = Self::calculate_json_agg(name, func, |expr| {
+                            rows.iter()
+                                .map(|row| self.get_expr_value(expr, row, table))
+                                .collect()
+                        })?;
+                        let (value_expr, _) = Self::json_aggregate_input(name, func)?;
+                        let col_name = format!("{}({})", name, self.expr_to_string(value_expr));
+                        Ok((col_name, value))
+                    }
+                    name if Self::is_ordered_set_aggregate(name) => {
+                        let (fraction, value_expr, descending) =
+                            self.ordered_set_aggregate_input(name, func)?;
+                        let mut values = Vec::with_capacity(rows.len());
+                        for row in rows {
+                            values.push(self.get_expr_value(value_expr, row, table)?);
+                        }
+                        let value =
+                            self.calculate_percentile(name, fraction, &values, descending)?;
+                        let col_name = format!("{}({})", name, self.expr_to_string(value_expr));
+                        Ok((col_name, value))
+                    }
                     _ => Err(YamlBaseError::NotImplemented(format!(
                         "Aggregate function {} not supported",
                         func_name
@@ -8269,8 +9943,21 @@ impl QueryExecutor {
         }
 
         // Initialize with rows from the first table
-        for row in &tables[0].1.rows {
-            result_rows.push(row.clone());
+        if let Some(first) = from.first()
+            && Self::is_json_table_function(&first.relation)
+        {
+            let function = Self::json_table_function(&first.relation)?;
+            result_rows = self.join_json_table_function(
+                vec![Vec::new()],
+                &function,
+                &JoinOperator::CrossJoin,
+                &tables[..1],
+                table_aliases,
+            )?;
+        } else {
+            for row in &tables[0].1.rows {
+                result_rows.push(row.clone());
+            }
         }
 
         // Process joins and comma-separated tables
@@ -8288,14 +9975,25 @@ impl QueryExecutor {
                 }
 
                 let join_table = tables[table_idx].1;
-                result_rows = self.apply_join(
-                    result_rows,
-                    join_table,
-                    &JoinOperator::CrossJoin,
-                    tables,
-                    table_aliases,
-                    table_idx,
-                )?;
+                result_rows = if Self::is_json_table_function(&table_with_joins.relation) {
+                    let function = Self::json_table_function(&table_with_joins.relation)?;
+                    self.join_json_table_function(
+                        result_rows,
+                        &function,
+                        &JoinOperator::CrossJoin,
+                        &tables[..=table_idx],
+                        table_aliases,
+                    )?
+                } else {
+                    self.apply_join(
+                        result_rows,
+                        join_table,
+                        &JoinOperator::CrossJoin,
+                        tables,
+                        table_aliases,
+                        table_idx,
+                    )?
+                };
 
                 table_idx += 1;
             }
@@ -8309,14 +10007,25 @@ impl QueryExecutor {
                 }
 
                 let join_table = tables[table_idx].1;
-                result_rows = self.apply_join(
-                    result_rows,
-                    join_table,
-                    &join.join_operator,
-                    tables,
-                    table_aliases,
-                    table_idx,
-                )?;
+                result_rows = if Self::is_json_table_function(&join.relation) {
+                    let function = Self::json_table_function(&join.relation)?;
+                    self.join_json_table_function(
+                        result_rows,
+                        &function,
+                        &join.join_operator,
+                        &tables[..=table_idx],
+                        table_aliases,
+                    )?
+                } else {
+                    self.apply_join(
+                        result_rows,
+                        join_table,
+                        &join.join_operator,
+                        tables,
+                        table_aliases,
+                        table_idx,
+                    )?
+                };
 
                 table_idx += 1;
             }
@@ -8484,7 +10193,7 @@ impl QueryExecutor {
         Ok(result)
     }
 
-    fn evaluate_join_condition(
+    pub(crate) fn evaluate_join_condition(
         &self,
         expr: &Expr,
         row: &[Value],
@@ -8515,6 +10224,14 @@ impl QueryExecutor {
                             self.get_join_expr_value(left, row, tables, table_aliases)?;
                         let right_val =
                             self.get_join_expr_value(right, row, tables, table_aliases)?;
+                        let collation = self.comparison_collation(left, right, &|expr| {
+                            Self::joined_column_collation(expr, tables)
+                        });
+                        if let Some(result) =
+                            Self::collated_comparison(collation, &left_val, op, &right_val)
+                        {
+                            return Ok(result);
+                        }
 
                         match op {
                             BinaryOperator::Eq => Ok(left_val == right_val),
@@ -8574,15 +10291,15 @@ impl QueryExecutor {
 
                 // Type conversion: if comparing dates with text, try to parse text as date
                 if matches!(value, Value::Date(_)) {
-                    if let Value::Text(s) = &low_value {
-                        if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
-                            low_value = Value::Date(date);
-                        }
+                    if let Value::Text(s) = &low_value
+                        && let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d")
+                    {
+                        low_value = Value::Date(date);
                     }
-                    if let Value::Text(s) = &high_value {
-                        if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
-                            high_value = Value::Date(date);
-                        }
+                    if let Value::Text(s) = &high_value
+                        && let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d")
+                    {
+                        high_value = Value::Date(date);
                     }
                 }
 
@@ -8708,6 +10425,10 @@ impl QueryExecutor {
                     self.evaluate_join_condition(inner, row, tables, table_aliases)?;
                 Ok(!inner_result)
             }
+            Expr::AnyOp { .. } | Expr::AllOp { .. } | Expr::Cast { .. } => Ok(matches!(
+                self.get_join_expr_value(expr, row, tables, table_aliases)?,
+                Value::Boolean(true)
+            )),
             _ => Err(YamlBaseError::NotImplemented(format!(
                 "JOIN condition expression type not yet supported: {:?}",
                 expr
@@ -9079,6 +10800,7 @@ impl QueryExecutor {
                                     Value::Integer(i) => result.push_str(&i.to_string()),
                                     Value::Float(f) => result.push_str(&f.to_string()),
                                     Value::Double(d) => result.push_str(&d.to_string()),
+                                    Value::Decimal(d) => result.push_str(&d.to_string()),
                                     Value::Boolean(b) => result.push_str(&b.to_string()),
                                     Value::Null => return Ok(Value::Null), // CONCAT returns NULL if any argument is NULL
                                     _ => result.push_str(&val.to_string()),
@@ -9299,6 +11021,11 @@ impl QueryExecutor {
                     })
                 }
             }
+            name if Self::is_scalar_function(name) => {
+                self.call_scalar_function(name, func, |expr| {
+                    self.get_join_expr_value(expr, row, tables, table_aliases)
+                })
+            }
             // For functions that don't need row context, delegate to constant version
             _ => self.evaluate_constant_function(func),
         }
@@ -9324,11 +11051,11 @@ impl QueryExecutor {
 
                 // Find the table index
                 for (table_idx, (table_name, table)) in tables.iter().enumerate() {
-                    if table_name == table_ref {
-                        if let Some(col_idx) = table.get_column_index(column_name) {
-                            let position = table_offsets[table_idx] + col_idx;
-                            return Ok(row.get(position).cloned().unwrap_or(Value::Null));
-                        }
+                    if table_name == table_ref
+                        && let Some(col_idx) = table.get_column_index(column_name)
+                    {
+                        let position = table_offsets[table_idx] + col_idx;
+                        return Ok(row.get(position).cloned().unwrap_or(Value::Null));
                     }
                 }
 
@@ -9353,16 +11080,10 @@ impl QueryExecutor {
                 })
             }
             Expr::Value(sqlparser::ast::Value::SingleQuotedString(s)) => Ok(Value::Text(s.clone())),
-            Expr::Value(sqlparser::ast::Value::Number(n, _)) => {
-                if let Ok(int_val) = n.parse::<i64>() {
-                    Ok(Value::Integer(int_val))
-                } else if let Ok(float_val) = n.parse::<f64>() {
-                    Ok(Value::Double(float_val))
-                } else {
-                    Err(YamlBaseError::Database {
-                        message: format!("Invalid number: {}", n),
-                    })
-                }
+            Expr::Value(sqlparser::ast::Value::Number(n, _)) => Self::parse_number_literal(n),
+            Expr::BinaryOp { .. } => {
+                let table_aliases = std::collections::HashMap::new();
+                self.get_join_expr_value(expr, row, tables, &table_aliases)
             }
             _ => {
                 // For other expressions, try constant evaluation
@@ -9371,7 +11092,7 @@ impl QueryExecutor {
         }
     }
 
-    fn get_join_expr_value(
+    pub(crate) fn get_join_expr_value(
         &self,
         expr: &Expr,
         row: &[Value],
@@ -9451,6 +11172,11 @@ impl QueryExecutor {
             }
             // Binary operations in JOIN conditions
             Expr::BinaryOp { left, op, right } => {
+                if let Some((operand, interval, subtract)) = Self::interval_operand(left, op, right)
+                {
+                    let value = self.get_join_expr_value(operand, row, tables, table_aliases)?;
+                    return self.apply_interval(&value, interval, subtract);
+                }
                 let left_val = self.get_join_expr_value(left, row, tables, table_aliases)?;
                 let right_val = self.get_join_expr_value(right, row, tables, table_aliases)?;
                 self.evaluate_binary_op_constant(&left_val, op, &right_val)
@@ -9462,6 +11188,7 @@ impl QueryExecutor {
                     UnaryOperator::Minus => match val {
                         Value::Integer(i) => Ok(Value::Integer(-i)),
                         Value::Double(d) => Ok(Value::Double(-d)),
+                        Value::Decimal(d) => Ok(Value::Decimal(-d)),
                         Value::Null => Ok(Value::Null),
                         _ => Err(YamlBaseError::Database {
                             message: "Unary minus requires numeric value".to_string(),
@@ -9544,11 +11271,26 @@ impl QueryExecutor {
             }
             // CAST expressions in JOIN conditions
             Expr::Cast {
-                expr, data_type, ..
+                kind,
+                expr,
+                data_type,
+                ..
             } => {
                 let val = self.get_join_expr_value(expr, row, tables, table_aliases)?;
-                self.cast_value(val, data_type)
-            }
+                self.cast_with_kind(val, data_type, kind)
+            }
+            Expr::AtTimeZone {
+                timestamp,
+                time_zone,
+            } => Self::at_time_zone(
+                self.get_join_expr_value(timestamp, row, tables, table_aliases)?,
+                self.get_join_expr_value(time_zone, row, tables, table_aliases)?,
+            ),
+            Expr::Subscript { expr, subscript } => Self::array_subscript(
+                self.get_join_expr_value(expr, row, tables, table_aliases)?,
+                subscript,
+                |expr| self.get_join_expr_value(expr, row, tables, table_aliases),
+            ),
             // LIKE pattern matching in JOIN conditions
             Expr::Like { expr, pattern, .. } => {
                 let text_val = self.get_join_expr_value(expr, row, tables, table_aliases)?;
@@ -9571,6 +11313,17 @@ impl QueryExecutor {
                     }),
                 }
             }
+            // REGEXP / RLIKE in JOIN conditions
+            Expr::RLike {
+                expr,
+                pattern,
+                negated,
+                ..
+            } => {
+                let value = self.get_join_expr_value(expr, row, tables, table_aliases)?;
+                let pattern = self.get_join_expr_value(pattern, row, tables, table_aliases)?;
+                Self::regex_matches(&value, &pattern, true, *negated)
+            }
             // BETWEEN expressions in JOIN conditions
             Expr::Between {
                 expr, low, high, ..
@@ -9613,6 +11366,26 @@ impl QueryExecutor {
                 // This is a placeholder that returns false for now
                 Ok(Value::Boolean(false))
             }
+            Expr::AnyOp {
+                left,
+                compare_op,
+                right,
+                ..
+            }
+            | Expr::AllOp {
+                left,
+                compare_op,
+                right,
+            } => {
+                let value = self.get_join_expr_value(left, row, tables, table_aliases)?;
+                let others = self.quantified_values(right, |right| {
+                    self.get_join_expr_value(right, row, tables, table_aliases)
+                })?;
+                let all = matches!(expr, Expr::AllOp { .. });
+                Ok(self
+                    .evaluate_quantified_comparison(&value, compare_op, others.as_deref(), all)?
+                    .map_or(Value::Null, Value::Boolean))
+            }
             // TypedString for DATE, TIME, TIMESTAMP literals
             Expr::TypedString { data_type, value } => {
                 use sqlparser::ast::DataType;
@@ -9641,6 +11414,13 @@ impl QueryExecutor {
                             }
                         }
                     }
+                    DataType::Timestamp(_, TimezoneInfo::WithTimeZone | TimezoneInfo::Tz) => {
+                        parse_timestamptz(value, &TimeZone::default_zone())
+                            .map(Value::TimestampTz)
+                            .ok_or_else(|| YamlBaseError::Database {
+                                message: format!("Invalid timestamp format: {}", value),
+                            })
+                    }
                     DataType::Timestamp(_, _) => {
                         // Parse TIMESTAMP '2025-01-01 12:34:56' format
                         match NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S") {
@@ -9676,6 +11456,101 @@ impl QueryExecutor {
         }
     }
 
+    /// Collation of a column reference on a single table, if the column declares one
+    fn table_column_collation(expr: &Expr, table: &Table) -> Option<Collation> {
+        let name = match expr {
+            Expr::Identifier(ident) => &ident.value,
+            Expr::CompoundIdentifier(parts) => &parts.last()?.value,
+            _ => return None,
+        };
+        let idx = table.get_column_index(name)?;
+        table.columns[idx].collation
+    }
+
+    /// Collation of a (qualified) column reference in a JOIN, if the column declares one
+    fn joined_column_collation(expr: &Expr, tables: &[(String, &Table)]) -> Option<Collation> {
+        let (qualifier, name) = match expr {
+            Expr::Identifier(ident) => (None, &ident.value),
+            Expr::CompoundIdentifier(parts) => match parts.as_slice() {
+                [.., qualifier, column] => (Some(&qualifier.value), &column.value),
+                _ => return None,
+            },
+            _ => return None,
+        };
+        tables
+            .iter()
+            .filter(|(table_ref, _)| qualifier.is_none_or(|q| q == table_ref))
+            .find_map(|(_, table)| {
+                let idx = table.get_column_index(name)?;
+                Some(table.columns[idx].collation)
+            })
+            .flatten()
+    }
+
+    /// The collation an expression compares with: its column's, or the server default
+    fn expr_collation(
+        &self,
+        expr: &Expr,
+        column_collation: &dyn Fn(&Expr) -> Option<Collation>,
+    ) -> Collation {
+        column_collation(expr).unwrap_or(self.collation)
+    }
+
+    /// The collation for comparing two expressions. A column with a declared collation
+    /// decides, the left side first; otherwise the server default applies.
+    fn comparison_collation(
+        &self,
+        left: &Expr,
+        right: &Expr,
+        column_collation: &dyn Fn(&Expr) -> Option<Collation>,
+    ) -> Collation {
+        column_collation(left)
+            .or_else(|| column_collation(right))
+            .unwrap_or(self.collation)
+    }
+
+    /// Compare two text values under a case-insensitive collation. `None` leaves other
+    /// values, operators and the binary collation to the usual comparison rules.
+    fn collated_comparison(
+        collation: Collation,
+        left: &Value,
+        op: &BinaryOperator,
+        right: &Value,
+    ) -> Option<bool> {
+        let (Value::Text(a), Value::Text(b)) = (left, right) else {
+            return None;
+        };
+        if !collation.is_case_insensitive() {
+            return None;
+        }
+        let ord = collation.compare(a, b);
+        match op {
+            BinaryOperator::Eq => Some(ord.is_eq()),
+            BinaryOperator::NotEq => Some(ord.is_ne()),
+            BinaryOperator::Lt => Some(ord.is_lt()),
+            BinaryOperator::LtEq => Some(ord.is_le()),
+            BinaryOperator::Gt => Some(ord.is_gt()),
+            BinaryOperator::GtEq => Some(ord.is_ge()),
+            _ => None,
+        }
+    }
+
+    fn collated_equals(collation: Collation, left: &Value, right: &Value) -> bool {
+        Self::collated_comparison(collation, left, &BinaryOperator::Eq, right)
+            .unwrap_or(left == right)
+    }
+
+    /// The grouping key of a value: text folded by the collation, so that values which
+    /// compare equal land in the same group
+    fn collation_key(collation: Collation, value: &Value) -> Value {
+        match value {
+            Value::Text(s) if collation.is_case_insensitive() => {
+                Value::Text(collation.fold(s).into_owned())
+            }
+            _ => value.clone(),
+        }
+    }
+
     /// Compare two values for equality in JOIN contexts
     fn compare_values_equal(&self, left: &Value, right: &Value) -> bool {
         match (left, right) {
@@ -9698,6 +11573,7 @@ impl QueryExecutor {
             (Value::Timestamp(a), Value::Timestamp(b)) => a == b,
             (Value::Uuid(a), Value::Uuid(b)) => a == b,
             (Value::Json(a), Value::Json(b)) => a == b,
+            (Value::Bytes(a), Value::Bytes(b)) => a == b,
             (Value::Null, Value::Null) => true,
             (Value::Null, _) | (_, Value::Null) => false,
             // Different types are not equal
@@ -9728,16 +11604,16 @@ impl QueryExecutor {
 
                             // Find table and column indices
                             for (table_idx, (table_name, table)) in tables.iter().enumerate() {
-                                if table_name == actual_table_name || table_ref == table_name {
-                                    if let Some(col_idx) = table.get_column_index(column_name) {
-                                        let display_name = format!("{}.{}", table_ref, column_name);
-                                        columns.push(JoinedColumn::TableColumn(
-                                            display_name,
-                                            table_idx,
-                                            col_idx,
-                                        ));
-                                        break;
-                                    }
+                                if (table_name == actual_table_name || table_ref == table_name)
+                                    && let Some(col_idx) = table.get_column_index(column_name)
+                                {
+                                    let display_name = format!("{}.{}", table_ref, column_name);
+                                    columns.push(JoinedColumn::TableColumn(
+                                        display_name,
+                                        table_idx,
+                                        col_idx,
+                                    ));
+                                    break;
                                 }
                             }
                         }
@@ -9782,15 +11658,15 @@ impl QueryExecutor {
 
                             // Find table and column indices
                             for (table_idx, (table_name, table)) in tables.iter().enumerate() {
-                                if table_name == actual_table_name || table_ref == table_name {
-                                    if let Some(col_idx) = table.get_column_index(column_name) {
-                                        columns.push(JoinedColumn::TableColumn(
-                                            alias.value.clone(),
-                                            table_idx,
-                                            col_idx,
-                                        ));
-                                        break;
-                                    }
+                                if (table_name == actual_table_name || table_ref == table_name)
+                                    && let Some(col_idx) = table.get_column_index(column_name)
+                                {
+                                    columns.push(JoinedColumn::TableColumn(
+                                        alias.value.clone(),
+                                        table_idx,
+                                        col_idx,
+                                    ));
+                                    break;
                                 }
                             }
                         }
@@ -9959,19 +11835,65 @@ impl QueryExecutor {
     fn sort_joined_rows(
         &self,
         rows: Vec<Vec<Value>>,
-        _order_exprs: &[OrderByExpr],
-        _columns: &[JoinedColumn],
+        order_exprs: &[OrderByExpr],
+        columns: &[JoinedColumn],
+        source_rows: &[Vec<Value>],
+        tables: &[(String, &Table)],
+        table_aliases: &std::collections::HashMap<String, String>,
     ) -> crate::Result<Vec<Vec<Value>>> {
-        // For now, just return unsorted rows
-        // Full implementation would require mapping order expressions to column indices
-        Ok(rows)
+        let column_names: Vec<String> = columns.iter().map(|col| col.get_name()).collect();
+        let resolved = order_exprs
+            .iter()
+            .map(|order_expr| self.resolve_order_by_column(&order_expr.expr, &column_names))
+            .collect::<crate::Result<Vec<_>>>()?;
+
+        // Expressions that are not output columns are evaluated against the joined row
+        let mut keys = Vec::with_capacity(rows.len());
+        for (source_row, projected_row) in source_rows.iter().zip(&rows) {
+            let mut key = Vec::with_capacity(order_exprs.len());
+            for (order_expr, resolved) in order_exprs.iter().zip(&resolved) {
+                key.push(match resolved {
+                    Some(idx) => projected_row[*idx].clone(),
+                    None => self.get_join_expr_value(
+                        &order_expr.expr,
+                        source_row,
+                        tables,
+                        table_aliases,
+                    )?,
+                });
+            }
+            keys.push(key);
+        }
+
+        let collations: Vec<Collation> = order_exprs
+            .iter()
+            .zip(&resolved)
+            .map(
+                |(order_expr, resolved)| match resolved.map(|idx| &columns[idx]) {
+                    Some(JoinedColumn::TableColumn(_, table_idx, col_idx)) => {
+                        tables[*table_idx].1.columns[*col_idx]
+                            .collation
+                            .unwrap_or(self.collation)
+                    }
+                    _ => self.expr_collation(&order_expr.expr, &|expr| {
+                        Self::joined_column_collation(expr, tables)
+                    }),
+                },
+            )
+            .collect();
+        Ok(Self::sort_rows_by_keys(
+            rows,
+            keys,
+            order_exprs,
+            &collations,
+        ))
     }
 
     async fn execute_aggregate_with_joined_rows(
         &self,
         _db: &Database,
         select: &Select,
-        _query: &Query,
+        query: &Query,
         joined_rows: &[Vec<Value>],
         tables: &[(String, &Table)],
         table_aliases: &std::collections::HashMap<String, String>,
@@ -10022,15 +11944,34 @@ impl QueryExecutor {
         match &select.group_by {
             GroupByExpr::Expressions(exprs, _) if !exprs.is_empty() => {
                 // GROUP BY aggregate with JOINs
-                return self
+                let mut result = self
                     .execute_joined_group_by_aggregate(
                         select,
                         &select.group_by,
                         &filtered_rows,
                         &column_mapping,
+                        tables,
                         table_aliases,
                     )
-                    .await;
+                    .await?;
+
+                // Apply ORDER BY to GROUP BY results
+                if let Some(order_by) = &query.order_by {
+                    let col_info: Vec<(String, usize)> = result
+                        .columns
+                        .iter()
+                        .enumerate()
+                        .map(|(idx, name)| (name.clone(), idx))
+                        .collect();
+                    let order_exprs =
+                        Self::order_by_with_projection_positions(select, &order_by.exprs);
+                    result.rows = self.sort_rows(result.rows, &order_exprs, &col_info)?;
+                }
+
+                // Apply LIMIT and OFFSET
+                result.rows = self.apply_query_limits(result.rows, query)?;
+
+                return Ok(result);
             }
             GroupByExpr::All(_) => {
                 return Err(YamlBaseError::NotImplemented(
@@ -10051,6 +11992,7 @@ impl QueryExecutor {
                         expr,
                         &filtered_rows,
                         &column_mapping,
+                        tables,
                         idx,
                     )?;
                     columns.push(col_name);
@@ -10061,6 +12003,7 @@ impl QueryExecutor {
                         expr,
                         &filtered_rows,
                         &column_mapping,
+                        tables,
                         idx,
                     )?;
                     columns.push(alias.value.clone());
@@ -10085,6 +12028,7 @@ impl QueryExecutor {
             columns,
             column_types,
             rows: vec![row_values],
+            affected_rows: None,
         };
 
         Ok(result)
@@ -10096,6 +12040,7 @@ impl QueryExecutor {
         expr: &Expr,
         rows: &[Vec<Value>],
         column_mapping: &std::collections::HashMap<String, usize>,
+        tables: &[(String, &Table)],
         _idx: usize,
     ) -> crate::Result<(String, Value)> {
         match expr {
@@ -10107,11 +12052,48 @@ impl QueryExecutor {
                     .map(|i| i.value.clone())
                     .collect::<Vec<_>>()
                     .join(".");
+                let rows = &*Self::apply_aggregate_filter(func, rows, |filter, row| {
+                    self.evaluate_where_clause_on_joined_row(filter, row, column_mapping)
+                })?;
 
                 match func_name.to_uppercase().as_str() {
                     "COUNT" => {
-                        let count_value = Value::Integer(rows.len() as i64);
-                        Ok(("COUNT(*)".to_string(), count_value))
+                        let arg_exprs = Self::count_argument_exprs(func)?;
+                        let argument_values = arg_exprs
+                            .iter()
+                            .map(|arg_expr| {
+                                self.extract_column_values_for_aggregate(
+                                    arg_expr,
+                                    rows,
+                                    column_mapping,
+                                )
+                            })
+                            .collect::<crate::Result<Vec<_>>>()?;
+                        let is_distinct = Self::is_distinct_aggregate(func);
+                        let collation = arg_exprs.first().map_or(self.collation, |expr| {
+                            self.expr_collation(expr, &|expr| {
+                                Self::joined_column_collation(expr, tables)
+                            })
+                        });
+                        let count_value = Self::calculate_count(
+                            rows.len(),
+                            &argument_values,
+                            is_distinct.then_some(collation),
+                        );
+                        let col_name = if arg_exprs.is_empty() {
+                            "COUNT(*)".to_string()
+                        } else {
+                            format!(
+                                "COUNT({}{})",
+                                if is_distinct { "DISTINCT " } else { "" },
+                                arg_exprs
+                                    .iter()
+                                    .map(|expr| self.expr_to_string(expr))
+                                    .collect::<Vec<_>>()
+                                    .join(", ")
+                            )
+                        };
+                        Ok((col_name, count_value))
                     }
                     "SUM" => {
                         // Extract the column name from SUM(column_name)
@@ -10119,11 +12101,14 @@ impl QueryExecutor {
                             if let Some(FunctionArg::Unnamed(FunctionArgExpr::Expr(col_expr))) =
                                 args.args.first()
                             {
-                                let values = self.extract_column_values_for_aggregate(
-                                    col_expr,
-                                    rows,
-                                    column_mapping,
-                                )?;
+                                let values = Self::apply_aggregate_distinct(
+                                    func,
+                                    self.extract_column_values_for_aggregate(
+                                        col_expr,
+                                        rows,
+                                        column_mapping,
+                                    )?,
+                                );
                                 let sum = self.calculate_sum(&values)?;
                                 Ok((format!("SUM({})", self.expr_to_string(col_expr)), sum))
                             } else {
@@ -10142,11 +12127,14 @@ impl QueryExecutor {
                             if let Some(FunctionArg::Unnamed(FunctionArgExpr::Expr(col_expr))) =
                                 args.args.first()
                             {
-                                let values = self.extract_column_values_for_aggregate(
-                                    col_expr,
-                                    rows,
-                                    column_mapping,
-                                )?;
+                                let values = Self::apply_aggregate_distinct(
+                                    func,
+                                    self.extract_column_values_for_aggregate(
+                                        col_expr,
+                                        rows,
+                                        column_mapping,
+                                    )?,
+                                );
                                 let avg = self.calculate_avg(&values)?;
                                 Ok((format!("AVG({})", self.expr_to_string(col_expr)), avg))
                             } else {
@@ -10206,6 +12194,53 @@ impl QueryExecutor {
                             ))
                         }
                     }
+                    name if Self::is_statistical_aggregate(name) => {
+                        if let FunctionArguments::List(ref args) = func.args
+                            && let Some(FunctionArg::Unnamed(FunctionArgExpr::Expr(col_expr))) =
+                                args.args.first()
+                        {
+                            let values = self.extract_column_values_for_aggregate(
+                                col_expr,
+                                rows,
+                                column_mapping,
+                            )?;
+                            let value = self.calculate_statistical_aggregate(name, &values)?;
+                            Ok((
+                                format!("{}({})", name, self.expr_to_string(col_expr)),
+                                value,
+                            ))
+                        } else {
+                            Err(YamlBaseError::NotImplemented(format!(
+                                "{} requires a column argument",
+                                name
+                            )))
+                        }
+                    }
+                    name if Self::is_json_aggregate(name) => {
+                        let value = Self::calculate_json_agg(name, func, |expr| {
+                            self.extract_column_values_for_aggregate(expr, rows, column_mapping)
+                        })?;
+                        let (value_expr, _) = Self::json_aggregate_input(name, func)?;
+                        Ok((
+                            format!("{}({})", name, self.expr_to_string(value_expr)),
+                            value,
+                        ))
+                    }
+                    name if Self::is_ordered_set_aggregate(name) => {
+                        let (fraction, value_expr, descending) =
+                            self.ordered_set_aggregate_input(name, func)?;
+                        let values = self.extract_column_values_for_aggregate(
+                            value_expr,
+                            rows,
+                            column_mapping,
+                        )?;
+                        let value =
+                            self.calculate_percentile(name, fraction, &values, descending)?;
+                        Ok((
+                            format!("{}({})", name, self.expr_to_string(value_expr)),
+                            value,
+                        ))
+                    }
                     _ => Err(YamlBaseError::NotImplemented(format!(
                         "Aggregate function {} not supported in JOINs yet",
                         func_name
@@ -10225,6 +12260,7 @@ impl QueryExecutor {
         group_by: &GroupByExpr,
         rows: &[Vec<Value>],
         column_mapping: &std::collections::HashMap<String, usize>,
+        tables: &[(String, &Table)],
         _table_aliases: &std::collections::HashMap<String, String>,
     ) -> crate::Result<QueryResult> {
         debug!("Executing GROUP BY aggregate on joined rows");
@@ -10235,21 +12271,40 @@ impl QueryExecutor {
             ));
         };
 
-        // Create groups based on GROUP BY expressions
-        let mut groups: std::collections::HashMap<Vec<Value>, Vec<Vec<Value>>> =
-            std::collections::HashMap::new();
+        // Create groups based on GROUP BY expressions. Text groups under its collation;
+        // a group reports the values of its first row.
+        let collations: Vec<Collation> = group_exprs
+            .iter()
+            .map(|expr| {
+                self.expr_collation(expr, &|expr| Self::joined_column_collation(expr, tables))
+            })
+            .collect();
+        #[allow(clippy::type_complexity)]
+        let mut groups: std::collections::HashMap<
+            Vec<Value>,
+            (Vec<Value>, Vec<Vec<Value>>),
+        > = std::collections::HashMap::new();
 
         for row in rows {
-            let mut group_key = Vec::new();
+            let mut group_values = Vec::new();
 
             // Evaluate each GROUP BY expression for this row
             for group_expr in group_exprs {
                 let group_value =
                     self.evaluate_joined_group_expr(group_expr, row, column_mapping)?;
-                group_key.push(group_value);
+                group_values.push(group_value);
             }
 
-            groups.entry(group_key).or_default().push(row.clone());
+            let group_key = group_values
+                .iter()
+                .zip(&collations)
+                .map(|(value, collation)| Self::collation_key(*collation, value))
+                .collect();
+            groups
+                .entry(group_key)
+                .or_insert_with(|| (group_values, Vec::new()))
+                .1
+                .push(row.clone());
         }
 
         // Now aggregate each group
@@ -10295,7 +12350,7 @@ impl QueryExecutor {
         }
 
         // Process each group
-        for (group_key, group_rows) in groups {
+        for (group_key, group_rows) in groups.into_values() {
             let mut result_row = Vec::new();
             let mut key_idx = 0;
 
@@ -10321,9 +12376,35 @@ impl QueryExecutor {
                                     .map(|i| i.value.clone())
                                     .collect::<Vec<_>>()
                                     .join(".");
+                                let group_rows = Self::apply_aggregate_filter(
+                                    func,
+                                    &group_rows,
+                                    |filter, row| {
+                                        self.evaluate_where_clause_on_joined_row(
+                                            filter,
+                                            row,
+                                            column_mapping,
+                                        )
+                                    },
+                                )?;
                                 match func_name.to_uppercase().as_str() {
                                     "COUNT" => {
-                                        result_row.push(Value::Integer(group_rows.len() as i64));
+                                        let argument_values = Self::count_argument_exprs(func)?
+                                            .into_iter()
+                                            .map(|arg_expr| {
+                                                self.extract_group_column_values(
+                                                    arg_expr,
+                                                    &group_rows,
+                                                    column_mapping,
+                                                )
+                                            })
+                                            .collect::<crate::Result<Vec<_>>>()?;
+                                        result_row.push(Self::calculate_count(
+                                            group_rows.len(),
+                                            &argument_values,
+                                            Self::is_distinct_aggregate(func)
+                                                .then_some(self.collation),
+                                        ));
                                     }
                                     "SUM" => {
                                         if let FunctionArguments::List(ref args) = func.args {
@@ -10331,11 +12412,14 @@ impl QueryExecutor {
                                                 FunctionArgExpr::Expr(col_expr),
                                             )) = args.args.first()
                                             {
-                                                let values = self.extract_group_column_values(
-                                                    col_expr,
-                                                    &group_rows,
-                                                    column_mapping,
-                                                )?;
+                                                let values = Self::apply_aggregate_distinct(
+                                                    func,
+                                                    self.extract_group_column_values(
+                                                        col_expr,
+                                                        &group_rows,
+                                                        column_mapping,
+                                                    )?,
+                                                );
                                                 let sum = self.calculate_sum(&values)?;
                                                 result_row.push(sum);
                                             } else {
@@ -10355,11 +12439,14 @@ impl QueryExecutor {
                                                 FunctionArgExpr::Expr(col_expr),
                                             )) = args.args.first()
                                             {
-                                                let values = self.extract_group_column_values(
-                                                    col_expr,
-                                                    &group_rows,
-                                                    column_mapping,
-                                                )?;
+                                                let values = Self::apply_aggregate_distinct(
+                                                    func,
+                                                    self.extract_group_column_values(
+                                                        col_expr,
+                                                        &group_rows,
+                                                        column_mapping,
+                                                    )?,
+                                                );
                                                 let avg = self.calculate_avg(&values)?;
                                                 result_row.push(avg);
                                             } else {
@@ -10421,6 +12508,54 @@ impl QueryExecutor {
                                             ));
                                         }
                                     }
+                                    name if Self::is_statistical_aggregate(name) => {
+                                        if let FunctionArguments::List(ref args) = func.args
+                                            && let Some(FunctionArg::Unnamed(
+                                                FunctionArgExpr::Expr(col_expr),
+                                            )) = args.args.first()
+                                        {
+                                            let values = self.extract_group_column_values(
+                                                col_expr,
+                                                &group_rows,
+                                                column_mapping,
+                                            )?;
+                                            result_row.push(
+                                                self.calculate_statistical_aggregate(
+                                                    name, &values,
+                                                )?,
+                                            );
+                                        } else {
+                                            return Err(YamlBaseError::NotImplemented(format!(
+                                                "{} requires a column argument",
+                                                name
+                                            )));
+                                        }
+                                    }
+                                    name if Self::is_json_aggregate(name) => {
+                                        result_row.push(Self::calculate_json_agg(
+                                            name,
+                                            func,
+                                            |expr| {
+                                                self.extract_group_column_values(
+                                                    expr,
+                                                    &group_rows,
+                                                    column_mapping,
+                                                )
+                                            },
+                                        )?);
+                                    }
+                                    name if Self::is_ordered_set_aggregate(name) => {
+                                        let (fraction, value_expr, descending) =
+                                            self.ordered_set_aggregate_input(name, func)?;
+                                        let values = self.extract_group_column_values(
+                                            value_expr,
+                                            &group_rows,
+                                            column_mapping,
+                                        )?;
+                                        result_row.push(self.calculate_percentile(
+                                            name, fraction, &values, descending,
+                                        )?);
+                                    }
                                     _ => {
                                         return Err(YamlBaseError::NotImplemented(format!(
                                             "Aggregate function {} not supported in GROUP BY JOINs yet",
@@ -10456,9 +12591,35 @@ impl QueryExecutor {
                                     .map(|i| i.value.clone())
                                     .collect::<Vec<_>>()
                                     .join(".");
+                                let group_rows = Self::apply_aggregate_filter(
+                                    func,
+                                    &group_rows,
+                                    |filter, row| {
+                                        self.evaluate_where_clause_on_joined_row(
+                                            filter,
+                                            row,
+                                            column_mapping,
+                                        )
+                                    },
+                                )?;
                                 match func_name.to_uppercase().as_str() {
                                     "COUNT" => {
-                                        result_row.push(Value::Integer(group_rows.len() as i64));
+                                        let argument_values = Self::count_argument_exprs(func)?
+                                            .into_iter()
+                                            .map(|arg_expr| {
+                                                self.extract_group_column_values(
+                                                    arg_expr,
+                                                    &group_rows,
+                                                    column_mapping,
+                                                )
+                                            })
+                                            .collect::<crate::Result<Vec<_>>>()?;
+                                        result_row.push(Self::calculate_count(
+                                            group_rows.len(),
+                                            &argument_values,
+                                            Self::is_distinct_aggregate(func)
+                                                .then_some(self.collation),
+                                        ));
                                     }
                                     "SUM" => {
                                         if let FunctionArguments::List(ref args) = func.args {
@@ -10466,11 +12627,14 @@ impl QueryExecutor {
                                                 FunctionArgExpr::Expr(col_expr),
                                             )) = args.args.first()
                                             {
-                                                let values = self.extract_group_column_values(
-                                                    col_expr,
-                                                    &group_rows,
-                                                    column_mapping,
-                                                )?;
+                                                let values = Self::apply_aggregate_distinct(
+                                                    func,
+                                                    self.extract_group_column_values(
+                                                        col_expr,
+                                                        &group_rows,
+                                                        column_mapping,
+                                                    )?,
+                                                );
                                                 let sum = self.calculate_sum(&values)?;
                                                 result_row.push(sum);
                                             } else {
@@ -10490,11 +12654,14 @@ impl QueryExecutor {
                                                 FunctionArgExpr::Expr(col_expr),
                                             )) = args.args.first()
                                             {
-                                                let values = self.extract_group_column_values(
-                                                    col_expr,
-                                                    &group_rows,
-                                                    column_mapping,
-                                                )?;
+                                                let values = Self::apply_aggregate_distinct(
+                                                    func,
+                                                    self.extract_group_column_values(
+                                                        col_expr,
+                                                        &group_rows,
+                                                        column_mapping,
+                                                    )?,
+                                                );
                                                 let avg = self.calculate_avg(&values)?;
                                                 result_row.push(avg);
                                             } else {
@@ -10556,6 +12723,54 @@ impl QueryExecutor {
                                             ));
                                         }
                                     }
+                                    name if Self::is_statistical_aggregate(name) => {
+                                        if let FunctionArguments::List(ref args) = func.args
+                                            && let Some(FunctionArg::Unnamed(
+                                                FunctionArgExpr::Expr(col_expr),
+                                            )) = args.args.first()
+                                        {
+                                            let values = self.extract_group_column_values(
+                                                col_expr,
+                                                &group_rows,
+                                                column_mapping,
+                                            )?;
+                                            result_row.push(
+                                                self.calculate_statistical_aggregate(
+                                                    name, &values,
+                                                )?,
+                                            );
+                                        } else {
+                                            return Err(YamlBaseError::NotImplemented(format!(
+                                                "{} requires a column argument",
+                                                name
+                                            )));
+                                        }
+                                    }
+                                    name if Self::is_json_aggregate(name) => {
+                                        result_row.push(Self::calculate_json_agg(
+                                            name,
+                                            func,
+                                            |expr| {
+                                                self.extract_group_column_values(
+                                                    expr,
+                                                    &group_rows,
+                                                    column_mapping,
+                                                )
+                                            },
+                                        )?);
+                                    }
+                                    name if Self::is_ordered_set_aggregate(name) => {
+                                        let (fraction, value_expr, descending) =
+                                            self.ordered_set_aggregate_input(name, func)?;
+                                        let values = self.extract_group_column_values(
+                                            value_expr,
+                                            &group_rows,
+                                            column_mapping,
+                                        )?;
+                                        result_row.push(self.calculate_percentile(
+                                            name, fraction, &values, descending,
+                                        )?);
+                                    }
                                     _ => {
                                         return Err(YamlBaseError::NotImplemented(format!(
                                             "Aggregate function {} not supported in GROUP BY JOINs yet",
@@ -10592,6 +12807,7 @@ impl QueryExecutor {
             columns: result_columns,
             column_types,
             rows: result_rows,
+            affected_rows: None,
         })
     }
 
@@ -10725,6 +12941,11 @@ impl QueryExecutor {
             }
             Expr::Value(v) => Ok(self.sql_value_to_db_value(v)?),
             Expr::BinaryOp { left, op, right } => {
+                if let Some((operand, interval, subtract)) = Self::interval_operand(left, op, right)
+                {
+                    let value = self.evaluate_joined_expression(operand, row, column_mapping)?;
+                    return self.apply_interval(&value, interval, subtract);
+                }
                 let left_val = self.evaluate_joined_expression(left, row, column_mapping)?;
                 let right_val = self.evaluate_joined_expression(right, row, column_mapping)?;
 
@@ -10780,6 +13001,15 @@ impl QueryExecutor {
                 Ok(Value::Boolean(if *negated { !found } else { found }))
             }
             Expr::Nested(inner) => self.evaluate_joined_expression(inner, row, column_mapping),
+            Expr::Cast {
+                kind,
+                expr,
+                data_type,
+                ..
+            } => {
+                let value = self.evaluate_joined_expression(expr, row, column_mapping)?;
+                self.cast_with_kind(value, data_type, kind)
+            }
             _ => Err(YamlBaseError::NotImplemented(format!(
                 "Expression {:?} not supported in WHERE clause for joined rows",
                 expr
@@ -10893,12 +13123,12 @@ impl QueryExecutor {
                     // Evaluate binary operations like price * quantity, amount + tax, etc.
                     let left_val = self.extract_column_values_for_aggregate(
                         left,
-                        &[row.clone()],
+                        std::slice::from_ref(row),
                         column_mapping,
                     )?;
                     let right_val = self.extract_column_values_for_aggregate(
                         right,
-                        &[row.clone()],
+                        std::slice::from_ref(row),
                         column_mapping,
                     )?;
 
@@ -10913,20 +13143,11 @@ impl QueryExecutor {
                 Expr::Value(val) => {
                     // Support literal values in aggregate expressions (e.g., SUM(price * 1.1))
                     match val {
-                        sqlparser::ast::Value::Number(n, _) => {
-                            if let Ok(int_val) = n.parse::<i64>() {
-                                Value::Integer(int_val)
-                            } else if let Ok(float_val) = n.parse::<f64>() {
-                                Value::Float(float_val as f32)
-                            } else {
-                                return Err(YamlBaseError::Database {
-                                    message: format!("Invalid number: {}", n),
-                                });
-                            }
-                        }
+                        sqlparser::ast::Value::Number(n, _) => Self::parse_number_literal(n)?,
                         sqlparser::ast::Value::SingleQuotedString(s) => Value::Text(s.clone()),
                         sqlparser::ast::Value::Boolean(b) => Value::Boolean(*b),
                         sqlparser::ast::Value::Null => Value::Null,
+                        sqlparser::ast::Value::Placeholder(name) => Self::bind_parameter(name)?,
                         _ => {
                             return Err(YamlBaseError::Database {
                                 message: "Unsupported literal value in aggregate".to_string(),
@@ -10938,7 +13159,7 @@ impl QueryExecutor {
                     // Support unary operations like -price, +amount
                     let operand_vals = self.extract_column_values_for_aggregate(
                         expr,
-                        &[row.clone()],
+                        std::slice::from_ref(row),
                         column_mapping,
                     )?;
                     if operand_vals.is_empty() {
@@ -10981,6 +13202,91 @@ impl QueryExecutor {
         Ok(values)
     }
 
+    /// ROUND of a DECIMAL to `places` digits after the point, or before it when negative,
+    /// with halves rounded away from zero as PostgreSQL and MySQL do
+    fn round_decimal(d: Decimal, places: i32) -> Decimal {
+        let strategy = rust_decimal::RoundingStrategy::MidpointAwayFromZero;
+        if places >= 0 {
+            return d.round_dp_with_strategy(places as u32, strategy);
+        }
+        match 10i64.checked_pow(places.unsigned_abs()).map(Decimal::from) {
+            Some(factor) => (d / factor).round_dp_with_strategy(0, strategy) * factor,
+            None => Decimal::ZERO,
+        }
+    }
+
+    /// Exact arithmetic for DECIMAL operands. Integers are widened to DECIMAL; mixing with a
+    /// floating point value falls back to DOUBLE like PostgreSQL's numeric/float8 rules.
+    /// Returns None when neither operand is a DECIMAL or the operator is not arithmetic.
+    fn decimal_arithmetic(
+        left: &Value,
+        op: &BinaryOperator,
+        right: &Value,
+    ) -> Option<crate::Result<Value>> {
+        use rust_decimal::Decimal;
+        use rust_decimal::prelude::ToPrimitive;
+
+        if !matches!(
+            op,
+            BinaryOperator::Plus
+                | BinaryOperator::Minus
+                | BinaryOperator::Multiply
+                | BinaryOperator::Divide
+                | BinaryOperator::Modulo
+        ) {
+            return None;
+        }
+
+        let (a, b) = match (left, right) {
+            (Value::Decimal(a), Value::Decimal(b)) => (*a, *b),
+            (Value::Decimal(a), Value::Integer(b)) => (*a, Decimal::from(*b)),
+            (Value::Integer(a), Value::Decimal(b)) => (Decimal::from(*a), *b),
+            (Value::Decimal(_), Value::Double(_) | Value::Float(_))
+            | (Value::Double(_) | Value::Float(_), Value::Decimal(_)) => {
+                let to_f64 = |v: &Value| match v {
+                    Value::Decimal(d) => d.to_f64().unwrap_or(f64::NAN),
+                    Value::Double(f) => *f,
+                    Value::Float(f) => *f as f64,
+                    _ => f64::NAN,
+                };
+                let (a, b) = (to_f64(left), to_f64(right));
+                return Some(match op {
+                    BinaryOperator::Divide | BinaryOperator::Modulo if b == 0.0 => {
+                        Err(YamlBaseError::Database {
+                            message: "Division by zero".to_string(),
+                        })
+                    }
+                    BinaryOperator::Plus => Ok(Value::Double(a + b)),
+                    BinaryOperator::Minus => Ok(Value::Double(a - b)),
+                    BinaryOperator::Multiply => Ok(Value::Double(a * b)),
+                    BinaryOperator::Divide => Ok(Value::Double(a / b)),
+                    _ => Ok(Value::Double(a % b)),
+                });
+            }
+            _ => return None,
+        };
+
+        let result = match op {
+            BinaryOperator::Plus => a.checked_add(b),
+            BinaryOperator::Minus => a.checked_sub(b),
+            BinaryOperator::Multiply => a.checked_mul(b),
+            BinaryOperator::Divide | BinaryOperator::Modulo if b.is_zero() => {
+                return Some(Err(YamlBaseError::Database {
+                    message: "Division by zero".to_string(),
+                }));
+            }
+            BinaryOperator::Divide => a.checked_div(b).map(|d| d.normalize()),
+            _ => a.checked_rem(b),
+        };
+        Some(
+            result
+                .map(Value::Decimal)
+                .ok_or_else(|| YamlBaseError::Database {
+                    message: "Numeric value out of range".to_string(),
+                }),
+        )
+    }
+
     // Helper method to evaluate arithmetic operations for complex expressions in aggregates
     fn evaluate_arithmetic_operation(
         &self,
@@ -10988,19 +13294,16 @@ impl QueryExecutor {
         op: &BinaryOperator,
         right: &Value,
     ) -> crate::Result<Value> {
+        if let Some(result) = Self::decimal_arithmetic(left, op, right) {
+            return result;
+        }
+
         match op {
             BinaryOperator::Plus => match (left, right) {
                 (Value::Integer(a), Value::Integer(b)) => Ok(Value::Integer(a + b)),
                 (Value::Float(a), Value::Float(b)) => Ok(Value::Float(a + b)),
-                (Value::Decimal(a), Value::Decimal(b)) => Ok(Value::Decimal(a + b)),
                 (Value::Integer(a), Value::Float(b)) => Ok(Value::Float(*a as f32 + b)),
                 (Value::Float(a), Value::Integer(b)) => Ok(Value::Float(a + *b as f32)),
-                (Value::Integer(a), Value::Decimal(b)) => {
-                    Ok(Value::Decimal(rust_decimal::Decimal::from(*a) + b))
-                }
-                (Value::Decimal(a), Value::Integer(b)) => {
-                    Ok(Value::Decimal(a + rust_decimal::Decimal::from(*b)))
-                }
                 _ => Err(YamlBaseError::Database {
                     message: "Cannot add non-numeric values".to_string(),
                 }),
@@ -11008,15 +13311,8 @@ impl QueryExecutor {
             BinaryOperator::Minus => match (left, right) {
                 (Value::Integer(a), Value::Integer(b)) => Ok(Value::Integer(a - b)),
                 (Value::Float(a), Value::Float(b)) => Ok(Value::Float(a - b)),
-                (Value::Decimal(a), Value::Decimal(b)) => Ok(Value::Decimal(a - b)),
                 (Value::Integer(a), Value::Float(b)) => Ok(Value::Float(*a as f32 - b)),
                 (Value::Float(a), Value::Integer(b)) => Ok(Value::Float(a - *b as f32)),
-                (Value::Integer(a), Value::Decimal(b)) => {
-                    Ok(Value::Decimal(rust_decimal::Decimal::from(*a) - b))
-                }
-                (Value::Decimal(a), Value::Integer(b)) => {
-                    Ok(Value::Decimal(a - rust_decimal::Decimal::from(*b)))
-                }
                 _ => Err(YamlBaseError::Database {
                     message: "Cannot subtract non-numeric values".to_string(),
                 }),
@@ -11024,15 +13320,8 @@ impl QueryExecutor {
             BinaryOperator::Multiply => match (left, right) {
                 (Value::Integer(a), Value::Integer(b)) => Ok(Value::Integer(a * b)),
                 (Value::Float(a), Value::Float(b)) => Ok(Value::Float(a * b)),
-                (Value::Decimal(a), Value::Decimal(b)) => Ok(Value::Decimal(a * b)),
                 (Value::Integer(a), Value::Float(b)) => Ok(Value::Float(*a as f32 * b)),
                 (Value::Float(a), Value::Integer(b)) => Ok(Value::Float(a * *b as f32)),
-                (Value::Integer(a), Value::Decimal(b)) => {
-                    Ok(Value::Decimal(rust_decimal::Decimal::from(*a) * b))
-                }
-                (Value::Decimal(a), Value::Integer(b)) => {
-                    Ok(Value::Decimal(a * rust_decimal::Decimal::from(*b)))
-                }
                 _ => Err(YamlBaseError::Database {
                     message: "Cannot multiply non-numeric values".to_string(),
                 }),
@@ -11056,15 +13345,6 @@ impl QueryExecutor {
                         Ok(Value::Float(a / b))
                     }
                 }
-                (Value::Decimal(a), Value::Decimal(b)) => {
-                    if *b == rust_decimal::Decimal::ZERO {
-                        Err(YamlBaseError::Database {
-                            message: "Division by zero".to_string(),
-                        })
-                    } else {
-                        Ok(Value::Decimal(a / b))
-                    }
-                }
                 (Value::Integer(a), Value::Float(b)) => {
                     if *b == 0.0 {
                         Err(YamlBaseError::Database {
@@ -11108,121 +13388,75 @@ impl QueryExecutor {
         }
     }
 
-    // Calculate SUM of numeric values
+    // Calculate SUM of numeric values. DECIMAL values are summed exactly; a FLOAT or DOUBLE
+    // among them makes the sum a DOUBLE.
     fn calculate_sum(&self, values: &[Value]) -> crate::Result<Value> {
         let mut sum_int: i64 = 0;
+        let mut sum_decimal = Decimal::ZERO;
         let mut sum_float: f64 = 0.0;
+        let mut has_decimal = false;
         let mut has_float = false;
         let mut count = 0;
 
         for value in values {
             match value {
                 Value::Integer(i) => {
-                    if has_float {
-                        sum_float += *i as f64;
-                    } else {
-                        sum_int += i;
-                    }
-                    count += 1;
+                    sum_int += i;
+                    sum_decimal += Decimal::from(*i);
+                    sum_float += *i as f64;
+                }
+                Value::Decimal(d) => {
+                    has_decimal = true;
+                    sum_decimal += d;
+                    sum_float += d.to_f64().unwrap_or(f64::NAN);
                 }
                 Value::Float(f) => {
-                    if !has_float {
-                        sum_float = sum_int as f64 + (*f as f64);
-                        has_float = true;
-                    } else {
-                        sum_float += *f as f64;
-                    }
-                    count += 1;
+                    has_float = true;
+                    sum_float += *f as f64;
                 }
                 Value::Double(d) => {
-                    if !has_float {
-                        sum_float = sum_int as f64 + d;
-                        has_float = true;
-                    } else {
-                        sum_float += d;
-                    }
-                    count += 1;
+                    has_float = true;
+                    sum_float += d;
                 }
-                Value::Decimal(d) => {
-                    // Convert Decimal to f64 for summing
-                    let decimal_f64 =
-                        d.to_string()
-                            .parse::<f64>()
-                            .map_err(|_| YamlBaseError::Database {
-                                message: "Failed to convert Decimal to f64 for SUM calculation"
-                                    .to_string(),
-                            })?;
-                    if !has_float {
-                        sum_float = sum_int as f64 + decimal_f64;
-                        has_float = true;
-                    } else {
-                        sum_float += decimal_f64;
-                    }
-                    count += 1;
-                }
-                Value::Null => {} // Skip NULL values
+                Value::Null => continue, // Skip NULL values
                 _ => {
                     return Err(YamlBaseError::Database {
                         message: "SUM can only be applied to numeric columns".to_string(),
                     });
                 }
             }
+            count += 1;
         }
 
         if count == 0 {
             Ok(Value::Null)
         } else if has_float {
             Ok(Value::Double(sum_float))
+        } else if has_decimal {
+            Ok(Value::Decimal(sum_decimal))
         } else {
             Ok(Value::Integer(sum_int))
         }
     }
 
-    // Calculate AVG of numeric values
+    // Calculate AVG of numeric values, exactly when they are DECIMAL values and integers
     fn calculate_avg(&self, values: &[Value]) -> crate::Result<Value> {
-        let mut sum: f64 = 0.0;
-        let mut count = 0;
-
-        for value in values {
-            match value {
-                Value::Integer(i) => {
-                    sum += *i as f64;
-                    count += 1;
-                }
-                Value::Float(f) => {
-                    sum += *f as f64;
-                    count += 1;
-                }
-                Value::Double(d) => {
-                    sum += d;
-                    count += 1;
-                }
-                Value::Decimal(d) => {
-                    // Convert Decimal to f64 for averaging
-                    let decimal_f64 =
-                        d.to_string()
-                            .parse::<f64>()
-                            .map_err(|_| YamlBaseError::Database {
-                                message: "Failed to convert Decimal to f64 for AVG calculation"
-                                    .to_string(),
-                            })?;
-                    sum += decimal_f64;
-                    count += 1;
-                }
-                Value::Null => {} // Skip NULL values
-                _ => {
-                    return Err(YamlBaseError::Database {
-                        message: "AVG can only be applied to numeric columns".to_string(),
-                    });
-                }
-            }
-        }
-
-        if count == 0 {
-            Ok(Value::Null)
-        } else {
-            Ok(Value::Double(sum / count as f64))
-        }
+        let count = values
+            .iter()
+            .filter(|value| !matches!(value, Value::Null))
+            .count();
+        let sum = self
+            .calculate_sum(values)
+            .map_err(|_| YamlBaseError::Database {
+                message: "AVG can only be applied to numeric columns".to_string(),
+            })?;
+        Ok(match sum {
+            Value::Null => Value::Null,
+            Value::Decimal(d) => Value::Decimal(d / Decimal::from(count as i64)),
+            Value::Integer(i) => Value::Double(i as f64 / count as f64),
+            Value::Double(d) => Value::Double(d / count as f64),
+            other => other,
+        })
     }
 
     // Calculate MIN of comparable values
@@ -11269,6 +13503,343 @@ impl QueryExecutor {
         Ok(max_value.unwrap_or(Value::Null))
     }
 
+    // Calculate STDDEV/VARIANCE family of numeric values. STDDEV and VARIANCE follow
+    // PostgreSQL and are the sample variants; the _POP variants divide by N instead of N - 1.
+    fn calculate_statistical_aggregate(
+        &self,
+        func_name: &str,
+        values: &[Value],
+    ) -> crate::Result<Value> {
+        let mut numbers = Vec::with_capacity(values.len());
+        for value in values {
+            match value {
+                Value::Integer(i) => numbers.push(*i as f64),
+                Value::Float(f) => numbers.push(*f as f64),
+                Value::Double(d) => numbers.push(*d),
+                Value::Decimal(d) => numbers.push(d.to_f64().unwrap_or(0.0)),
+                Value::Null => {} // Skip NULL values
+                _ => {
+                    return Err(YamlBaseError::Database {
+                        message: format!("{} can only be applied to numeric columns", func_name),
+                    });
+                }
+            }
+        }
+
+        let population = matches!(func_name, "STDDEV_POP" | "VAR_POP");
+        let n = numbers.len();
+        if n == 0 || (!population && n < 2) {
+            return Ok(Value::Null);
+        }
+
+        let mean = numbers.iter().sum::<f64>() / n as f64;
+        let sum_sq: f64 = numbers.iter().map(|x| (x - mean) * (x - mean)).sum();
+        let divisor = if population { n } else { n - 1 };
+        let variance = sum_sq / divisor as f64;
+
+        match func_name {
+            "STDDEV" | "STDDEV_SAMP" | "STDDEV_POP" => Ok(Value::Double(variance.sqrt())),
+            _ => Ok(Value::Double(variance)),
+        }
+    }
+
+    // Whether the aggregate was invoked as AGG(DISTINCT ...)
+    fn is_distinct_aggregate(func: &Function) -> bool {
+        matches!(
+            &func.args,
+            FunctionArguments::List(args)
+                if matches!(args.duplicate_treatment, Some(DuplicateTreatment::Distinct))
+        )
+    }
+
+    // Apply AGG(DISTINCT ...) semantics to the extracted argument values: NULLs are dropped
+    // and only the first occurrence of each value is kept.
+    fn apply_aggregate_distinct(func: &Function, values: Vec<Value>) -> Vec<Value> {
+        if !Self::is_distinct_aggregate(func) {
+            return values;
+        }
+        let mut seen = std::collections::HashSet::new();
+        values
+            .into_iter()
+            .filter(|value| !matches!(value, Value::Null) && seen.insert(value.clone()))
+            .collect()
+    }
+
+    // Restrict the rows an aggregate sees to those matching its FILTER (WHERE ...) clause.
+    // `matches` evaluates the filter condition against a single row.
+    fn apply_aggregate_filter<'a, R: Clone>(
+        func: &Function,
+        rows: &'a [R],
+        mut matches: impl FnMut(&Expr, &R) -> crate::Result<bool>,
+    ) -> crate::Result<std::borrow::Cow<'a, [R]>> {
+        let Some(filter) = &func.filter else {
+            return Ok(std::borrow::Cow::Borrowed(rows));
+        };
+        let mut filtered = Vec::new();
+        for row in rows {
+            if matches(filter, row)? {
+                filtered.push(row.clone());
+            }
+        }
+        Ok(std::borrow::Cow::Owned(filtered))
+    }
+
+    // The argument expressions of COUNT; empty for COUNT(*) and COUNT()
+    fn count_argument_exprs(func: &Function) -> crate::Result<Vec<&Expr>> {
+        match &func.args {
+            FunctionArguments::None => Ok(Vec::new()),
+            FunctionArguments::List(args) => args
+                .args
+                .iter()
+                .filter_map(|arg| match arg {
+                    FunctionArg::Unnamed(FunctionArgExpr::Wildcard) => None,
+                    FunctionArg::Unnamed(FunctionArgExpr::Expr(expr)) => Some(Ok(expr)),
+                    _ => Some(Err(YamlBaseError::NotImplemented(
+                        "Unsupported COUNT argument".to_string(),
+                    ))),
+                })
+                .collect(),
+            FunctionArguments::Subquery(_) => Err(YamlBaseError::NotImplemented(
+                "Unsupported function arguments".to_string(),
+            )),
+        }
+    }
+
+    // Calculate COUNT from the values of each argument (one vector per argument, aligned by
+    // row). Without arguments every row counts; otherwise rows where any argument is NULL are
+    // skipped, and DISTINCT counts unique argument tuples as in COUNT(DISTINCT a, b), with
+    // text compared under the given collation.
+    fn calculate_count(
+        row_count: usize,
+        argument_values: &[Vec<Value>],
+        distinct: Option<Collation>,
+    ) -> Value {
+        if argument_values.is_empty() {
+            return Value::Integer(row_count as i64);
+        }
+
+        let tuples = (0..row_count).filter_map(|row_idx| {
+            let tuple: Vec<&Value> = argument_values
+                .iter()
+                .map(|values| values.get(row_idx).unwrap_or(&Value::Null))
+                .collect();
+            (!tuple.iter().any(|value| matches!(value, Value::Null))).then_some(tuple)
+        });
+
+        let count = match distinct {
+            Some(collation) => tuples
+                .map(|tuple| {
+                    tuple
+                        .into_iter()
+                        .map(|value| Self::collation_key(collation, value))
+                        .collect::<Vec<_>>()
+                })
+                .collect::<std::collections::HashSet<_>>()
+                .len(),
+            None => tuples.count(),
+        };
+        Value::Integer(count as i64)
+    }
+
+    // Resolve the inputs of an ordered-set aggregate: the requested fraction, the expression
+    // whose values are ranked and whether the ranking is descending.
+    // PERCENTILE_CONT(0.9) WITHIN GROUP (ORDER BY latency) and MEDIAN(latency) are supported.
+    fn ordered_set_aggregate_input<'a>(
+        &self,
+        func_name: &str,
+        func: &'a Function,
+    ) -> crate::Result<(f64, &'a Expr, bool)> {
+        let args: Vec<&Expr> = match &func.args {
+            FunctionArguments::List(list) => list
+                .args
+                .iter()
+                .map(|arg| match arg {
+                    FunctionArg::Unnamed(FunctionArgExpr::Expr(expr)) => Ok(expr),
+                    _ => Err(YamlBaseError::NotImplemented(format!(
+                        "Unsupported {} argument",
+                        func_name
+                    ))),
+                })
+                .collect::<crate::Result<_>>()?,
+            FunctionArguments::None => Vec::new(),
+            FunctionArguments::Subquery(_) => {
+                return Err(YamlBaseError::NotImplemented(format!(
+                    "Unsupported {} argument",
+                    func_name
+                )));
+            }
+        };
+
+        if func_name == "MEDIAN" {
+            if args.len() != 1 || !func.within_group.is_empty() {
+                return Err(YamlBaseError::Database {
+                    message: "MEDIAN requires exactly one argument".to_string(),
+                });
+            }
+            return Ok((0.5, args[0], false));
+        }
+
+        if args.len() != 1 || func.within_group.len() != 1 {
+            return Err(YamlBaseError::Database {
+                message: format!(
+                    "{} requires a fraction argument and WITHIN GROUP (ORDER BY expression)",
+                    func_name
+                ),
+            });
+        }
+
+        let fraction = match self.evaluate_constant_expr(args[0])? {
+            Value::Integer(i) => i as f64,
+            Value::Float(f) => f as f64,
+            Value::Double(d) => d,
+            Value::Decimal(d) => d.to_f64().unwrap_or(f64::NAN),
+            other => {
+                return Err(YamlBaseError::Database {
+                    message: format!("{} fraction must be numeric, got {}", func_name, other),
+                });
+            }
+        };
+        if !(0.0..=1.0).contains(&fraction) {
+            return Err(YamlBaseError::Database {
+                message: format!("{} fraction {} is not between 0 and 1", func_name, fraction),
+            });
+        }
+
+        let order_by = &func.within_group[0];
+        Ok((fraction, &order_by.expr, order_by.asc == Some(false)))
+    }
+
+    // Calculate PERCENTILE_CONT (linear interpolation), PERCENTILE_DISC (first value whose
+    // cumulative distribution reaches the fraction) or MEDIAN over the non-NULL values.
+    fn calculate_percentile(
+        &self,
+        func_name: &str,
+        fraction: f64,
+        values: &[Value],
+        descending: bool,
+    ) -> crate::Result<Value> {
+        let mut sorted: Vec<&Value> = values
+            .iter()
+            .filter(|v| !matches!(v, Value::Null))
+            .collect();
+        if sorted.is_empty() {
+            return Ok(Value::Null);
+        }
+
+        let mut comparison_error = None;
+        sorted.sort_by(|a, b| match self.compare_values(a, b) {
+            Ok(ord) => ord.cmp(&0),
+            Err(e) => {
+                comparison_error.get_or_insert(e);
+                std::cmp::Ordering::Equal
+            }
+        });
+        if let Some(e) = comparison_error {
+            return Err(e);
+        }
+        if descending {
+            sorted.reverse();
+        }
+
+        if func_name == "PERCENTILE_DISC" {
+            let position = (fraction * sorted.len() as f64).ceil() as usize;
+            return Ok(sorted[position.saturating_sub(1)].clone());
+        }
+
+        let mut numbers = Vec::with_capacity(sorted.len());
+        for value in sorted {
+            match value {
+                Value::Integer(i) => numbers.push(*i as f64),
+                Value::Float(f) => numbers.push(*f as f64),
+                Value::Double(d) => numbers.push(*d),
+                Value::Decimal(d) => numbers.push(d.to_f64().unwrap_or(0.0)),
+                _ => {
+                    return Err(YamlBaseError::Database {
+                        message: format!("{} can only be applied to numeric columns", func_name),
+                    });
+                }
+            }
+        }
+
+        let position = fraction * (numbers.len() - 1) as f64;
+        let lower = position.floor() as usize;
+        let upper = position.ceil() as usize;
+        let result = numbers[lower] + (numbers[upper] - numbers[lower]) * (position - lower as f64);
+        Ok(Value::Double(result))
+    }
+
+    // The value expression of JSON_AGG(expr [ORDER BY ...]) and its ORDER BY clause, if any
+    fn json_aggregate_input<'a>(
+        func_name: &str,
+        func: &'a Function,
+    ) -> crate::Result<(&'a Expr, &'a [OrderByExpr])> {
+        match &func.args {
+            FunctionArguments::List(list) if list.args.len() == 1 => {
+                let FunctionArg::Unnamed(FunctionArgExpr::Expr(expr)) = &list.args[0] else {
+                    return Err(YamlBaseError::NotImplemented(format!(
+                        "Unsupported {} argument",
+                        func_name
+                    )));
+                };
+                let order_by = list
+                    .clauses
+                    .iter()
+                    .find_map(|clause| match clause {
+                        FunctionArgumentClause::OrderBy(order_by) => Some(order_by.as_slice()),
+                        _ => None,
+                    })
+                    .unwrap_or_default();
+                Ok((expr, order_by))
+            }
+            _ => Err(YamlBaseError::Database {
+                message: format!("{} requires exactly one argument", func_name),
+            }),
+        }
+    }
+
+    // Calculate JSON_AGG: the argument values of the group as a JSON array, in input order or
+    // sorted by the ORDER BY inside the call. `values_of` evaluates an expression for every
+    // row of the group. As in PostgreSQL NULLs are kept and an empty group gives NULL.
+    fn calculate_json_agg(
+        func_name: &str,
+        func: &Function,
+        mut values_of: impl FnMut(&Expr) -> crate::Result<Vec<Value>>,
+    ) -> crate::Result<Value> {
+        let (value_expr, order_by) = Self::json_aggregate_input(func_name, func)?;
+        let mut values = values_of(value_expr)?;
+        if !order_by.is_empty() {
+            let key_columns = order_by
+                .iter()
+                .map(|order_expr| values_of(&order_expr.expr))
+                .collect::<crate::Result<Vec<_>>>()?;
+            let keys = (0..values.len())
+                .map(|row_idx| {
+                    key_columns
+                        .iter()
+                        .map(|column| column.get(row_idx).cloned().unwrap_or(Value::Null))
+                        .collect()
+                })
+                .collect();
+            let rows = values.into_iter().map(|value| vec![value]).collect();
+            values = Self::sort_rows_by_keys(rows, keys, order_by, &[])
+                .into_iter()
+                .flatten()
+                .collect();
+        }
+        if values.is_empty() {
+            return Ok(Value::Null);
+        }
+
+        let mut seen = std::collections::HashSet::new();
+        let distinct = Self::is_distinct_aggregate(func);
+        let items = values
+            .iter()
+            .filter(|value| !distinct || seen.insert(*value))
+            .map(value_to_json)
+            .collect();
+        Ok(Value::Json(serde_json::Value::Array(items)))
+    }
+
     // Helper method to extract column values for CTE aggregate calculations
     fn extract_cte_column_values(
         &self,
@@ -11402,8 +13973,10 @@ impl QueryExecutor {
                 }
             }
             (Value::Decimal(a), Value::Decimal(b)) => Ok(a.cmp(b) as i32),
-            _ => Err(YamlBaseError::Database {
-                message: format!("Cannot compare incompatible types: {:?} and {:?}", a, b),
+            _ => a.compare(b).map(|ordering| ordering as i32).ok_or_else(|| {
+                YamlBaseError::Database {
+                    message: format!("Cannot compare incompatible types: {:?} and {:?}", a, b),
+                }
             }),
         }
     }
@@ -11413,34 +13986,41 @@ impl QueryExecutor {
         &self,
         db: &Database,
         query: &Query,
-        with: &With,
+        enclosing_ctes: Arc<CteResults>,
     ) -> crate::Result<QueryResult> {
-        debug!("Executing query with CTEs (recursive: {})", with.recursive);
+        let (recursive, cte_tables) = match &query.with {
+            Some(with) => (with.recursive, with.cte_tables.as_slice()),
+            None => (false, [].as_slice()),
+        };
+        debug!("Executing query with CTEs (recursive: {})", recursive);
 
-        // Store CTE results in a temporary map
-        let mut cte_results: std::collections::HashMap<String, QueryResult> =
-            std::collections::HashMap::new();
+        // Each CTE is materialized once, in order, and later CTEs, the main query and any
+        // subqueries in them read the stored results. The results are shared with nested
+        // queries through CTE_SCOPE.
+        let mut cte_results = enclosing_ctes;
 
-        // Execute each CTE in order - CTEs can reference previously defined CTEs
-        for cte_table in &with.cte_tables {
+        for cte_table in cte_tables {
             let cte_name = cte_table.alias.name.value.clone();
+            let cte_key = Self::cte_key(&cte_name);
             eprintln!(
                 "DEBUG: Starting to execute CTE '{}' (recursive: {})",
-                cte_name, with.recursive
+                cte_name, recursive
             );
             debug!(
                 "Executing CTE: {} (recursive: {}, with {} existing CTEs available)",
                 cte_name,
-                with.recursive,
+                recursive,
                 cte_results.len()
             );
 
-            // Check if this is a RECURSIVE CTE
-            let mut cte_result = if with.recursive {
-                // Handle RECURSIVE CTE
-                self.execute_recursive_cte(db, cte_table, &cte_results)
-                    .await?
-            } else {
+            // In a WITH RECURSIVE list only the CTEs that read themselves are recursive
+            let is_recursive = recursive
+                && Self::reads_table(&cte_table.query.body, &|name| name == cte_key.as_str());
+            let available = Arc::clone(&cte_results);
+            let cte_future = async {
+                if is_recursive {
+                    return self.execute_recursive_cte(db, cte_table, &available).await;
+                }
                 // Execute regular CTE
                 match &cte_table.query.body.as_ref() {
                     SetExpr::Select(select) => {
@@ -11451,7 +14031,7 @@ impl QueryExecutor {
                                 db,
                                 select,
                                 &cte_table.query,
-                                &cte_results,
+                                &available,
                             )
                             .await?;
                         eprintln!(
@@ -11460,7 +14040,7 @@ impl QueryExecutor {
                             result.columns.len(),
                             result.columns
                         );
-                        result
+                        Ok(result)
                     }
                     SetExpr::SetOperation {
                         op,
@@ -11469,23 +14049,23 @@ impl QueryExecutor {
                         right,
                     } => {
                         // Handle UNION, UNION ALL, INTERSECT, EXCEPT operations within CTEs
-                        self.execute_cte_set_operation(
-                            db,
+                        self.execute_set_operation(
                             op,
                             set_quantifier,
                             left,
                             right,
-                            &cte_results,
+                            &cte_table.query,
                         )
-                        .await?
-                    }
-                    _ => {
-                        return Err(YamlBaseError::NotImplemented(
-                            "This type of query is not yet supported in CTEs".to_string(),
-                        ));
+                        .await
                     }
+                    _ => Err(YamlBaseError::NotImplemented(
+                        "This type of query is not yet supported in CTEs".to_string(),
+                    )),
                 }
             };
+            let mut cte_result = CTE_SCOPE
+                .scope(Arc::clone(&cte_results), cte_future)
+                .await?;
 
             // Strip table prefixes from column names in CTE results
             // This ensures CTEs expose unqualified column names for outer queries
@@ -11502,6 +14082,8 @@ impl QueryExecutor {
                 })
                 .collect();
 
+            Self::apply_cte_column_aliases(cte_table, &mut cte_result)?;
+
             // Store the CTE result for later reference by subsequent CTEs and main query
             eprintln!(
                 "DEBUG: Storing CTE '{}' with columns: {:?}",
@@ -11511,7 +14093,7 @@ impl QueryExecutor {
                 "Storing CTE '{}' with columns: {:?}",
                 cte_name, cte_result.columns
             );
-            cte_results.insert(cte_name.clone(), cte_result);
+            Arc::make_mut(&mut cte_results).insert(cte_key, cte_result);
             debug!(
                 "CTE {} executed successfully, now {} CTEs available",
                 cte_name,
@@ -11520,24 +14102,81 @@ impl QueryExecutor {
         }
 
         // Now execute the main query with CTE results available
-        match &query.body.as_ref() {
-            SetExpr::Select(select) => {
-                self.execute_select_with_cte_context(db, select, query, &cte_results)
-                    .await
+        let main_future = async {
+            match &query.body.as_ref() {
+                SetExpr::Select(select) => {
+                    self.execute_select_with_cte_context(db, select, query, &cte_results)
+                        .await
+                }
+                SetExpr::SetOperation {
+                    op,
+                    set_quantifier,
+                    left,
+                    right,
+                } => {
+                    // Handle UNION, UNION ALL, INTERSECT, EXCEPT operations in main query
+                    self.execute_set_operation(op, set_quantifier, left, right, query)
+                        .await
+                }
+                _ => Err(YamlBaseError::NotImplemented(
+                    "This type of query is not yet supported with CTEs".to_string(),
+                )),
             }
-            SetExpr::SetOperation {
-                op,
-                set_quantifier,
-                left,
-                right,
-            } => {
-                // Handle UNION, UNION ALL, INTERSECT, EXCEPT operations in main query
-                self.execute_cte_set_operation(db, op, set_quantifier, left, right, &cte_results)
-                    .await
+        };
+        CTE_SCOPE.scope(Arc::clone(&cte_results), main_future).await
+    }
+
+    /// Rename the result columns of `WITH name(a, b) AS (...)` to the listed names
+    pub(crate) fn apply_cte_column_aliases(
+        cte: &Cte,
+        result: &mut QueryResult,
+    ) -> crate::Result<()> {
+        let aliases = &cte.alias.columns;
+        if aliases.len() > result.columns.len() {
+            return Err(YamlBaseError::Database {
+                message: format!(
+                    "CTE '{}' has {} columns available but {} columns specified",
+                    cte.alias.name.value,
+                    result.columns.len(),
+                    aliases.len()
+                ),
+            });
+        }
+        for (column, alias) in result.columns.iter_mut().zip(aliases) {
+            *column = alias.value.clone();
+        }
+        Ok(())
+    }
+
+    /// CTE names are case-insensitive like table names
+    pub(crate) fn cte_key(name: &str) -> String {
+        name.to_lowercase()
+    }
+
+    /// Whether a query body reads a table for which `is_match` holds (given the lower-cased
+    /// name) in a FROM clause or JOIN, looking into derived tables and set operations
+    fn reads_table(body: &SetExpr, is_match: &dyn Fn(&str) -> bool) -> bool {
+        let factor_reads = |factor: &TableFactor| match factor {
+            TableFactor::Table { name, .. } => name
+                .0
+                .last()
+                .is_some_and(|ident| is_match(&Self::cte_key(&ident.value))),
+            TableFactor::Derived { subquery, .. } => Self::reads_table(&subquery.body, is_match),
+            _ => false,
+        };
+        match body {
+            SetExpr::Select(select) => select.from.iter().any(|table_with_joins| {
+                factor_reads(&table_with_joins.relation)
+                    || table_with_joins
+                        .joins
+                        .iter()
+                        .any(|join| factor_reads(&join.relation))
+            }),
+            SetExpr::SetOperation { left, right, .. } => {
+                Self::reads_table(left, is_match) || Self::reads_table(right, is_match)
             }
-            _ => Err(YamlBaseError::NotImplemented(
-                "This type of query is not yet supported with CTEs".to_string(),
-            )),
+            SetExpr::Query(query) => Self::reads_table(&query.body, is_match),
+            _ => false,
         }
     }
 
@@ -11547,7 +14186,7 @@ impl QueryExecutor {
         db: &Database,
         select: &Select,
         query: &Query,
-        cte_results: &std::collections::HashMap<String, QueryResult>,
+        cte_results: &CteResults,
     ) -> crate::Result<QueryResult> {
         debug!("Executing SELECT with CTE context");
         eprintln!(
@@ -11570,7 +14209,7 @@ impl QueryExecutor {
                     .map(|ident| ident.value.clone())
                     .unwrap_or_else(String::new);
 
-                if cte_results.contains_key(&table_name) {
+                if cte_results.contains_key(&Self::cte_key(&table_name)) {
                     has_cte_references = true;
                     break;
                 }
@@ -11585,7 +14224,7 @@ impl QueryExecutor {
                         .map(|ident| ident.value.clone())
                         .unwrap_or_else(String::new);
 
-                    if cte_results.contains_key(&table_name) {
+                    if cte_results.contains_key(&Self::cte_key(&table_name)) {
                         has_cte_references = true;
                         break;
                     }
@@ -11639,7 +14278,7 @@ impl QueryExecutor {
                 .map(|ident| ident.value.clone())
                 .unwrap_or_else(String::new);
 
-            if let Some(cte_result) = cte_results.get(&table_name) {
+            if let Some(cte_result) = cte_results.get(&Self::cte_key(&table_name)) {
                 eprintln!(
                     "DEBUG: Found CTE '{}' with {} columns: {:?}",
                     table_name,
@@ -11661,15 +14300,12 @@ impl QueryExecutor {
                     result_rows = self.sort_rows_with_columns(
                         &result_rows,
                         &result_columns,
-                        &order_by.exprs,
+                        &Self::order_by_with_projected_exprs(select, &order_by.exprs),
                     )?;
                 }
 
-                // Apply LIMIT if present
-                if let Some(Expr::Value(sqlparser::ast::Value::Number(n, _))) = &query.limit {
-                    let limit_count = n.parse::<usize>().unwrap_or(0);
-                    result_rows.truncate(limit_count);
-                }
+                // Apply LIMIT/OFFSET if present
+                result_rows = self.apply_query_limits(std::mem::take(&mut result_rows), query)?;
 
                 // Check if we have GROUP BY
                 if !matches!(select.group_by, GroupByExpr::Expressions(ref exprs, _) if exprs.is_empty())
@@ -11692,10 +14328,7 @@ impl QueryExecutor {
                         if let Expr::Function(func) = expr.as_ref() {
                             if let Some(first_part) = func.name.0.first() {
                                 let func_name = first_part.value.to_uppercase();
-                                matches!(
-                                    func_name.as_str(),
-                                    "COUNT" | "SUM" | "AVG" | "MIN" | "MAX"
-                                )
+                                Self::is_aggregate_name(&func_name)
                             } else {
                                 false
                             }
@@ -11703,46 +14336,12 @@ impl QueryExecutor {
                             false
                         }
                     } else {
-                        false
-                    }
-                });
-
-                let projected_rows: Vec<Vec<Value>> = if has_aggregates {
-                    // For aggregate functions without GROUP BY, return one row with aggregated values
-                    let aggregated_row: Vec<Value> = projection_items
-                        .iter()
-                        .map(|item| match item {
-                            CteProjectionItem::Column(_) => {
-                                // Can't mix aggregates with non-aggregate columns without GROUP BY
-                                Value::Null
-                            }
-                            CteProjectionItem::Expression(expr) => {
-                                match expr.as_ref() {
-                                    Expr::Function(func) => {
-                                        let func_name = func
-                                            .name
-                                            .0
-                                            .iter()
-                                            .map(|i| i.value.clone())
-                                            .collect::<Vec<_>>()
-                                            .join(".");
-                                        match func_name.to_uppercase().as_str() {
-                                            "COUNT" => {
-                                                // COUNT(*) or COUNT(column) - count all rows
-                                                Value::Integer(result_rows.len() as i64)
-                                            }
-                                            _ => {
-                                                // For other functions, return null for now
-                                                Value::Null
-                                            }
-                                        }
-                                    }
-                                    _ => Value::Null, // Other expressions not yet supported
-                                }
-                            }
-                        })
-                        .collect();
-                    vec![aggregated_row]
+                        false
+                    }
+                });
+
+                let projected_rows: Vec<Vec<Value>> = if has_aggregates {
+                    vec![self.cte_aggregate_row(&projection_items, &result_rows, &result_columns)]
                 } else {
                     // Non-aggregate projection - process each row
                     result_rows
@@ -11765,16 +14364,44 @@ impl QueryExecutor {
                         .collect()
                 };
 
-                // For CTE results, we need to infer column types
-                let column_types = selected_columns
+                // Columns keep the CTE's column type, expressions are typed from the CTE
+                // columns they read
+                let cte_column_type = |_: Option<&str>, name: &str| {
+                    cte_result
+                        .columns
+                        .iter()
+                        .position(|column| column.eq_ignore_ascii_case(name))
+                        .and_then(|idx| cte_result.column_types.get(idx).cloned())
+                };
+                let column_types = projection_items
                     .iter()
-                    .map(|_| crate::yaml::schema::SqlType::Text)
+                    .enumerate()
+                    .map(|(position, item)| {
+                        let inferred = match item {
+                            CteProjectionItem::Column(idx) => {
+                                cte_result.column_types.get(*idx).cloned()
+                            }
+                            CteProjectionItem::Expression(expr) => {
+                                Self::infer_expr_type(expr, &cte_column_type)
+                            }
+                        };
+                        match item {
+                            CteProjectionItem::Column(_) if !has_aggregates => {
+                                inferred.unwrap_or(crate::yaml::schema::SqlType::Text)
+                            }
+                            _ => self.projected_expr_type(
+                                inferred,
+                                projected_rows.iter().filter_map(|row| row.get(position)),
+                            ),
+                        }
+                    })
                     .collect();
 
                 return Ok(QueryResult {
                     columns: selected_columns,
                     column_types,
                     rows: projected_rows,
+                    affected_rows: None,
                 });
             }
         }
@@ -11789,7 +14416,7 @@ impl QueryExecutor {
         db: &Database,
         select: &Select,
         query: &Query,
-        cte_results: &std::collections::HashMap<String, QueryResult>,
+        cte_results: &CteResults,
     ) -> crate::Result<QueryResult> {
         debug!("Executing complex CTE query with JOINs/aggregates");
 
@@ -11845,15 +14472,18 @@ impl QueryExecutor {
 
             // Apply ORDER BY to GROUP BY results
             if let Some(order_by) = &query.order_by {
-                result.rows =
-                    self.sort_rows_with_columns(&result.rows, &result.columns, &order_by.exprs)?;
+                let col_info: Vec<(String, usize)> = result
+                    .columns
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, name)| (name.clone(), idx))
+                    .collect();
+                let order_exprs = Self::order_by_with_projection_positions(select, &order_by.exprs);
+                result.rows = self.sort_rows(result.rows, &order_exprs, &col_info)?;
             }
 
-            // Apply LIMIT
-            if let Some(Expr::Value(sqlparser::ast::Value::Number(n, _))) = &query.limit {
-                let limit_count = n.parse::<usize>().unwrap_or(0);
-                result.rows.truncate(limit_count);
-            }
+            // Apply LIMIT/OFFSET if present
+            result.rows = self.apply_query_limits(std::mem::take(&mut result.rows), query)?;
 
             Ok(result)
         } else {
@@ -11866,18 +14496,19 @@ impl QueryExecutor {
 
                 // Apply ORDER BY to GROUP BY results
                 if let Some(order_by) = &query.order_by {
-                    result.rows = self.sort_rows_with_columns(
-                        &result.rows,
-                        &result.columns,
-                        &order_by.exprs,
-                    )?;
+                    let col_info: Vec<(String, usize)> = result
+                        .columns
+                        .iter()
+                        .enumerate()
+                        .map(|(idx, name)| (name.clone(), idx))
+                        .collect();
+                    let order_exprs =
+                        Self::order_by_with_projection_positions(select, &order_by.exprs);
+                    result.rows = self.sort_rows(result.rows, &order_exprs, &col_info)?;
                 }
 
-                // Apply LIMIT
-                if let Some(Expr::Value(sqlparser::ast::Value::Number(n, _))) = &query.limit {
-                    let limit_count = n.parse::<usize>().unwrap_or(0);
-                    result.rows.truncate(limit_count);
-                }
+                // Apply LIMIT/OFFSET if present
+                result.rows = self.apply_query_limits(std::mem::take(&mut result.rows), query)?;
 
                 Ok(result)
             } else {
@@ -12066,36 +14697,72 @@ impl QueryExecutor {
         columns: &[String],
         order_by: &[OrderByExpr],
     ) -> crate::Result<Vec<Vec<Value>>> {
-        let mut sorted_rows = rows.to_vec();
-
-        sorted_rows.sort_by(|a, b| {
+        let mut keys = Vec::with_capacity(rows.len());
+        for row in rows {
+            let mut key = Vec::with_capacity(order_by.len());
             for order_expr in order_by {
-                if let Expr::Identifier(ident) = &order_expr.expr {
-                    let column_name = &ident.value;
-                    if let Some(column_idx) = columns.iter().position(|col| col == column_name) {
-                        if let (Some(val_a), Some(val_b)) = (a.get(column_idx), b.get(column_idx)) {
-                            let cmp = val_a.compare(val_b).unwrap_or(std::cmp::Ordering::Equal);
-                            let final_cmp = if let Some(sqlparser::ast::OrderByExpr {
-                                asc: Some(false),
-                                ..
-                            }) = Some(order_expr)
-                            {
-                                cmp.reverse()
-                            } else {
-                                cmp
-                            };
+                let value = match &order_expr.expr {
+                    Expr::Identifier(ident) => columns
+                        .iter()
+                        .position(|col| *col == ident.value)
+                        .and_then(|column_idx| row.get(column_idx).cloned())
+                        .unwrap_or(Value::Null),
+                    expr => self.evaluate_expr_with_columns(expr, row, columns)?,
+                };
+                key.push(value);
+            }
+            keys.push(key);
+        }
+
+        let collations = vec![self.collation; order_by.len()];
+        Ok(Self::sort_rows_by_keys(
+            rows.to_vec(),
+            keys,
+            order_by,
+            &collations,
+        ))
+    }
 
-                            if final_cmp != std::cmp::Ordering::Equal {
-                                return final_cmp;
+    // Replace positional (ORDER BY 2) and alias references in ORDER BY with the projected
+    // expression they name, for sorting rows before the projection is applied
+    fn order_by_with_projected_exprs(
+        select: &Select,
+        order_by: &[OrderByExpr],
+    ) -> Vec<OrderByExpr> {
+        order_by
+            .iter()
+            .map(|order_expr| {
+                let projected = match &order_expr.expr {
+                    Expr::Value(sqlparser::ast::Value::Number(n, _)) => n
+                        .parse::<usize>()
+                        .ok()
+                        .and_then(|position| select.projection.get(position.checked_sub(1)?))
+                        .and_then(|item| match item {
+                            SelectItem::UnnamedExpr(expr)
+                            | SelectItem::ExprWithAlias { expr, .. } => Some(expr),
+                            _ => None,
+                        }),
+                    Expr::Identifier(ident) => {
+                        select.projection.iter().find_map(|item| match item {
+                            SelectItem::ExprWithAlias { expr, alias }
+                                if alias.value.eq_ignore_ascii_case(&ident.value) =>
+                            {
+                                Some(expr)
                             }
-                        }
+                            _ => None,
+                        })
                     }
+                    _ => None,
+                };
+                match projected {
+                    Some(expr) => OrderByExpr {
+                        expr: expr.clone(),
+                        ..order_expr.clone()
+                    },
+                    None => order_expr.clone(),
                 }
-            }
-            std::cmp::Ordering::Equal
-        });
-
-        Ok(sorted_rows)
+            })
+            .collect()
     }
 
     // Helper method to evaluate WHERE conditions with column context
@@ -12128,6 +14795,11 @@ impl QueryExecutor {
             Expr::BinaryOp { left, right, op } => {
                 let left_val = self.evaluate_expr_with_columns(left, row, columns)?;
                 let right_val = self.evaluate_expr_with_columns(right, row, columns)?;
+                if let Some(result) =
+                    Self::collated_comparison(self.collation, &left_val, op, &right_val)
+                {
+                    return Ok(result);
+                }
 
                 match op {
                     BinaryOperator::Eq => Ok(left_val == right_val),
@@ -12233,6 +14905,58 @@ impl QueryExecutor {
 
                 Ok(if *negated { !found } else { found })
             }
+            Expr::InSubquery {
+                expr,
+                subquery,
+                negated,
+            } => {
+                let value = self.evaluate_expr_with_columns(expr, row, columns)?;
+                let result = self.execute_query_blocking(subquery)?;
+                let found = result
+                    .rows
+                    .iter()
+                    .any(|subquery_row| subquery_row.first() == Some(&value));
+                Ok(found != *negated)
+            }
+            Expr::Exists { subquery, negated } => {
+                let result = self.execute_query_blocking(subquery)?;
+                Ok(result.rows.is_empty() == *negated)
+            }
+            Expr::AnyOp {
+                left,
+                compare_op,
+                right,
+                ..
+            }
+            | Expr::AllOp {
+                left,
+                compare_op,
+                right,
+            } => {
+                let value = self.evaluate_expr_with_columns(left, row, columns)?;
+                let others = self.quantified_values(right, |right| {
+                    self.evaluate_expr_with_columns(right, row, columns)
+                })?;
+                let all = matches!(expr, Expr::AllOp { .. });
+                Ok(self.evaluate_quantified_comparison(
+                    &value,
+                    compare_op,
+                    others.as_deref(),
+                    all,
+                )? == Some(true))
+            }
+            Expr::IsNull(inner) => Ok(matches!(
+                self.evaluate_expr_with_columns(inner, row, columns)?,
+                Value::Null
+            )),
+            Expr::IsNotNull(inner) => Ok(!matches!(
+                self.evaluate_expr_with_columns(inner, row, columns)?,
+                Value::Null
+            )),
+            Expr::Cast { .. } => Ok(matches!(
+                self.evaluate_expr_with_columns(expr, row, columns)?,
+                Value::Boolean(true)
+            )),
             _ => Err(YamlBaseError::NotImplemented(format!(
                 "WHERE expression {:?} not supported in CTE context",
                 expr
@@ -12308,11 +15032,31 @@ impl QueryExecutor {
                     ))
                 }
             }
+            Expr::Subquery(subquery) => {
+                Self::scalar_subquery_value(self.execute_query_blocking(subquery)?)
+            }
             Expr::Value(value) => self.sql_value_to_db_value(value),
             Expr::BinaryOp { left, right, op } => {
+                if let Some((operand, interval, subtract)) = Self::interval_operand(left, op, right)
+                {
+                    let value = self.evaluate_expr_with_columns(operand, row, columns)?;
+                    return self.apply_interval(&value, interval, subtract);
+                }
                 let left_val = self.evaluate_expr_with_columns(left, row, columns)?;
                 let right_val = self.evaluate_expr_with_columns(right, row, columns)?;
 
+                if let Some(result) = Self::decimal_arithmetic(&left_val, op, &right_val) {
+                    return result;
+                }
+
+                if let Some(result) = Self::evaluate_regex_operator(&left_val, op, &right_val) {
+                    return result;
+                }
+
+                if let Some(result) = Self::evaluate_json_operator(&left_val, op, &right_val) {
+                    return result;
+                }
+
                 match op {
                     BinaryOperator::Plus => match (&left_val, &right_val) {
                         (Value::Integer(a), Value::Integer(b)) => Ok(Value::Integer(a + b)),
@@ -12460,6 +15204,7 @@ impl QueryExecutor {
                             "Unsupported types for division".to_string(),
                         )),
                     },
+                    BinaryOperator::StringConcat => self.safe_string_concat(&left_val, &right_val),
                     _ => Err(YamlBaseError::NotImplemented(format!(
                         "Binary operator {:?} not supported in CTE expressions",
                         op
@@ -12490,6 +15235,27 @@ impl QueryExecutor {
                 // Handle parenthesized expressions by evaluating the inner expression
                 self.evaluate_expr_with_columns(inner, row, columns)
             }
+            Expr::Cast {
+                kind,
+                expr,
+                data_type,
+                ..
+            } => {
+                let value = self.evaluate_expr_with_columns(expr, row, columns)?;
+                self.cast_with_kind(value, data_type, kind)
+            }
+            Expr::AtTimeZone {
+                timestamp,
+                time_zone,
+            } => Self::at_time_zone(
+                self.evaluate_expr_with_columns(timestamp, row, columns)?,
+                self.evaluate_expr_with_columns(time_zone, row, columns)?,
+            ),
+            Expr::Subscript { expr, subscript } => Self::array_subscript(
+                self.evaluate_expr_with_columns(expr, row, columns)?,
+                subscript,
+                |expr| self.evaluate_expr_with_columns(expr, row, columns),
+            ),
             Expr::InList {
                 expr,
                 list,
@@ -12523,6 +15289,9 @@ impl QueryExecutor {
                             ))),
                         }
                     }
+                    DataType::Timestamp(_, TimezoneInfo::WithTimeZone | TimezoneInfo::Tz) => {
+                        self.cast_value(Value::Text(value.clone()), data_type)
+                    }
                     _ => Ok(Value::Text(value.clone())),
                 }
             }
@@ -12651,6 +15420,11 @@ impl QueryExecutor {
                             })
                         }
                     }
+                    name if Self::is_scalar_function(name) => {
+                        self.call_scalar_function(name, func, |expr| {
+                            self.evaluate_expr_with_columns(expr, row, columns)
+                        })
+                    }
                     _ => Err(YamlBaseError::NotImplemented(format!(
                         "Function {} not supported in CTE context",
                         func_name
@@ -12904,122 +15678,6 @@ impl QueryExecutor {
         }
     }
 
-    // Execute set operations (UNION, UNION ALL, etc.) within CTE definitions
-    async fn execute_cte_set_operation(
-        &self,
-        db: &Database,
-        op: &SetOperator,
-        set_quantifier: &SetQuantifier,
-        left: &SetExpr,
-        right: &SetExpr,
-        cte_results: &std::collections::HashMap<String, QueryResult>,
-    ) -> crate::Result<QueryResult> {
-        debug!("Executing CTE set operation: {:?}", op);
-
-        // Execute left side of the operation
-        let left_result = match left {
-            SetExpr::Select(select) => {
-                // Create a temporary query wrapper for left side
-                let left_query = Query {
-                    with: None,
-                    body: Box::new(left.clone()),
-                    order_by: None,
-                    limit: None,
-                    offset: None,
-                    fetch: None,
-                    locks: vec![],
-                    limit_by: vec![],
-                    for_clause: None,
-                    format_clause: None,
-                    settings: None,
-                };
-                self.execute_select_with_cte_context(db, select, &left_query, cte_results)
-                    .await?
-            }
-            _ => {
-                return Err(YamlBaseError::NotImplemented(
-                    "Complex set operations not yet supported in CTEs".to_string(),
-                ));
-            }
-        };
-
-        // Execute right side of the operation
-        let right_result = match right {
-            SetExpr::Select(select) => {
-                // Create a temporary query wrapper for right side
-                let right_query = Query {
-                    with: None,
-                    body: Box::new(right.clone()),
-                    order_by: None,
-                    limit: None,
-                    offset: None,
-                    fetch: None,
-                    locks: vec![],
-                    limit_by: vec![],
-                    for_clause: None,
-                    format_clause: None,
-                    settings: None,
-                };
-                self.execute_select_with_cte_context(db, select, &right_query, cte_results)
-                    .await?
-            }
-            _ => {
-                return Err(YamlBaseError::NotImplemented(
-                    "Complex set operations not yet supported in CTEs".to_string(),
-                ));
-            }
-        };
-
-        // Validate that both sides have compatible column structures
-        if left_result.columns.len() != right_result.columns.len() {
-            return Err(YamlBaseError::Database {
-                message: format!(
-                    "UNION queries must have the same number of columns: left has {}, right has {}",
-                    left_result.columns.len(),
-                    right_result.columns.len()
-                ),
-            });
-        }
-
-        // Apply the set operation
-        match op {
-            SetOperator::Union => {
-                debug!("Executing UNION operation");
-                let mut combined_rows = left_result.rows;
-
-                // For UNION (without ALL), we need to deduplicate
-                let is_all = matches!(set_quantifier, SetQuantifier::All);
-
-                if is_all {
-                    // UNION ALL - just concatenate all rows
-                    combined_rows.extend(right_result.rows);
-                } else {
-                    // UNION - deduplicate rows
-                    let mut unique_rows: std::collections::HashSet<Vec<Value>> =
-                        combined_rows.into_iter().collect();
-
-                    for row in right_result.rows {
-                        unique_rows.insert(row);
-                    }
-
-                    combined_rows = unique_rows.into_iter().collect();
-                }
-
-                Ok(QueryResult {
-                    columns: left_result.columns,
-                    column_types: left_result.column_types,
-                    rows: combined_rows,
-                })
-            }
-            SetOperator::Intersect => Err(YamlBaseError::NotImplemented(
-                "INTERSECT operation not yet supported in CTEs".to_string(),
-            )),
-            SetOperator::Except => Err(YamlBaseError::NotImplemented(
-                "EXCEPT operation not yet supported in CTEs".to_string(),
-            )),
-        }
-    }
-
     // Execute JOIN operations without aggregation - returns joined data for later GROUP BY processing
     async fn execute_cte_join_without_aggregation(
         &self,
@@ -13092,6 +15750,7 @@ impl QueryExecutor {
             columns: result_columns.clone(),
             column_types: vec![crate::yaml::schema::SqlType::Text; result_columns.len()],
             rows: result_rows,
+            affected_rows: None,
         })
     }
 
@@ -13113,7 +15772,8 @@ impl QueryExecutor {
             }
         };
 
-        // Build groups based on GROUP BY expressions
+        // Build groups based on GROUP BY expressions, with text grouped under the
+        // server collation
         let mut groups: std::collections::HashMap<Vec<Value>, Vec<Vec<Value>>> =
             std::collections::HashMap::new();
 
@@ -13121,7 +15781,10 @@ impl QueryExecutor {
             // Evaluate GROUP BY expressions for this row
             let group_key: Vec<Value> = group_by_exprs
                 .iter()
-                .map(|expr| self.evaluate_expression_with_columns(expr, row, &data.columns))
+                .map(|expr| {
+                    self.evaluate_expression_with_columns(expr, row, &data.columns)
+                        .map(|value| Self::collation_key(self.collation, &value))
+                })
                 .collect::<Result<Vec<_>, _>>()?;
 
             groups.entry(group_key).or_default().push(row.clone());
@@ -13199,6 +15862,7 @@ impl QueryExecutor {
             columns: result_columns.clone(),
             column_types: vec![crate::yaml::schema::SqlType::Text; result_columns.len()],
             rows: result_rows,
+            affected_rows: None,
         })
     }
 
@@ -13210,48 +15874,40 @@ impl QueryExecutor {
         columns: &[String],
     ) -> crate::Result<Value> {
         match expr {
-            Expr::Function(Function { name, args, .. }) => {
+            Expr::Function(func @ Function { name, args, .. }) => {
                 let function_name = name
                     .0
                     .iter()
                     .map(|i| i.value.to_uppercase())
                     .collect::<Vec<_>>()
                     .join(".");
+                let group_rows =
+                    &*Self::apply_aggregate_filter(func, group_rows, |filter, row| {
+                        self.evaluate_where_condition_with_columns(filter, row, columns)
+                    })?;
 
                 match function_name.as_str() {
                     "COUNT" => {
-                        if let FunctionArguments::List(arg_list) = args {
-                            if arg_list.args.len() == 1 {
-                                if let FunctionArg::Unnamed(FunctionArgExpr::Wildcard) =
-                                    &arg_list.args[0]
-                                {
-                                    // COUNT(*)
-                                    Ok(Value::Integer(group_rows.len() as i64))
-                                } else if let FunctionArg::Unnamed(FunctionArgExpr::Expr(
-                                    arg_expr,
-                                )) = &arg_list.args[0]
-                                {
-                                    // COUNT(column) - count non-null values
-                                    let mut count = 0;
-                                    for row in group_rows {
-                                        let value = self.evaluate_expression_with_columns(
-                                            arg_expr, row, columns,
-                                        )?;
-                                        if !matches!(value, Value::Null) {
-                                            count += 1;
-                                        }
-                                    }
-                                    Ok(Value::Integer(count))
-                                } else {
-                                    Err(YamlBaseError::NotImplemented(
-                                        "Unsupported COUNT argument".to_string(),
-                                    ))
-                                }
-                            } else {
-                                Err(YamlBaseError::NotImplemented(
-                                    "COUNT requires exactly one argument".to_string(),
-                                ))
-                            }
+                        if matches!(args, FunctionArguments::List(arg_list) if !arg_list.args.is_empty())
+                        {
+                            let argument_values = Self::count_argument_exprs(func)?
+                                .into_iter()
+                                .map(|arg_expr| {
+                                    group_rows
+                                        .iter()
+                                        .map(|row| {
+                                            self.evaluate_expression_with_columns(
+                                                arg_expr, row, columns,
+                                            )
+                                        })
+                                        .collect::<crate::Result<Vec<_>>>()
+                                })
+                                .collect::<crate::Result<Vec<_>>>()?;
+                            Ok(Self::calculate_count(
+                                group_rows.len(),
+                                &argument_values,
+                                Self::is_distinct_aggregate(func).then_some(self.collation),
+                            ))
                         } else {
                             Err(YamlBaseError::NotImplemented(
                                 "COUNT requires function arguments".to_string(),
@@ -13268,10 +15924,15 @@ impl QueryExecutor {
                                     let mut sum_decimal = rust_decimal::Decimal::ZERO;
                                     let mut has_decimal = false;
 
-                                    for row in group_rows {
-                                        let value = self.evaluate_expression_with_columns(
-                                            arg_expr, row, columns,
-                                        )?;
+                                    let values = group_rows
+                                        .iter()
+                                        .map(|row| {
+                                            self.evaluate_expression_with_columns(
+                                                arg_expr, row, columns,
+                                            )
+                                        })
+                                        .collect::<crate::Result<Vec<_>>>()?;
+                                    for value in Self::apply_aggregate_distinct(func, values) {
                                         match value {
                                             Value::Integer(i) => {
                                                 sum_int += i;
@@ -13314,6 +15975,26 @@ impl QueryExecutor {
                             ))
                         }
                     }
+                    "AVG" => {
+                        if let FunctionArguments::List(arg_list) = args
+                            && arg_list.args.len() == 1
+                            && let FunctionArg::Unnamed(FunctionArgExpr::Expr(arg_expr)) =
+                                &arg_list.args[0]
+                        {
+                            let values = group_rows
+                                .iter()
+                                .map(|row| {
+                                    self.evaluate_expression_with_columns(arg_expr, row, columns)
+                                })
+                                .collect::<crate::Result<Vec<_>>>()?;
+                            self.calculate_avg(&Self::apply_aggregate_distinct(func, values))
+                        } else {
+                            Err(YamlBaseError::NotImplemented(
+                                "AVG requires exactly one column or expression argument"
+                                    .to_string(),
+                            ))
+                        }
+                    }
                     "MAX" => {
                         if let FunctionArguments::List(arg_list) = args {
                             if arg_list.args.len() == 1 {
@@ -13398,6 +16079,47 @@ impl QueryExecutor {
                             ))
                         }
                     }
+                    name if Self::is_statistical_aggregate(name) => {
+                        if let FunctionArguments::List(arg_list) = args
+                            && arg_list.args.len() == 1
+                            && let FunctionArg::Unnamed(FunctionArgExpr::Expr(arg_expr)) =
+                                &arg_list.args[0]
+                        {
+                            let values = group_rows
+                                .iter()
+                                .map(|row| {
+                                    self.evaluate_expression_with_columns(arg_expr, row, columns)
+                                })
+                                .collect::<crate::Result<Vec<_>>>()?;
+                            self.calculate_statistical_aggregate(name, &values)
+                        } else {
+                            Err(YamlBaseError::NotImplemented(format!(
+                                "{} requires exactly one column or expression argument",
+                                name
+                            )))
+                        }
+                    }
+                    name if Self::is_json_aggregate(name) => {
+                        Self::calculate_json_agg(name, func, |expr| {
+                            group_rows
+                                .iter()
+                                .map(|row| {
+                                    self.evaluate_expression_with_columns(expr, row, columns)
+                                })
+                                .collect()
+                        })
+                    }
+                    name if Self::is_ordered_set_aggregate(name) => {
+                        let (fraction, value_expr, descending) =
+                            self.ordered_set_aggregate_input(name, func)?;
+                        let values = group_rows
+                            .iter()
+                            .map(|row| {
+                                self.evaluate_expression_with_columns(value_expr, row, columns)
+                            })
+                            .collect::<crate::Result<Vec<_>>>()?;
+                        self.calculate_percentile(name, fraction, &values, descending)
+                    }
                     _ => Err(YamlBaseError::NotImplemented(format!(
                         "Aggregate function {} not yet implemented",
                         function_name
@@ -13524,17 +16246,7 @@ impl QueryExecutor {
                     })
                 }
             }
-            Expr::Value(sqlparser::ast::Value::Number(n, _)) => {
-                if let Ok(i) = n.parse::<i64>() {
-                    Ok(Value::Integer(i))
-                } else if let Ok(f) = n.parse::<f64>() {
-                    Ok(Value::Double(f))
-                } else {
-                    Err(YamlBaseError::Database {
-                        message: format!("Unable to parse number: {}", n),
-                    })
-                }
-            }
+            Expr::Value(sqlparser::ast::Value::Number(n, _)) => Self::parse_number_literal(n),
             Expr::Value(sqlparser::ast::Value::SingleQuotedString(s)) => Ok(Value::Text(s.clone())),
             _ => Err(YamlBaseError::NotImplemented(format!(
                 "Expression evaluation not implemented: {:?}",
@@ -13563,7 +16275,7 @@ impl QueryExecutor {
                 .unwrap_or_else(|| table_name.clone());
 
             // Check if this is a CTE reference
-            if let Some(cte_result) = context.cte_results.get(&table_name) {
+            if let Some(cte_result) = context.cte_results.get(&Self::cte_key(&table_name)) {
                 debug!(
                     "Found CTE '{}' with columns: {:?}",
                     table_name, cte_result.columns
@@ -13580,6 +16292,7 @@ impl QueryExecutor {
                     columns: qualified_columns,
                     column_types: cte_result.column_types.clone(),
                     rows: cte_result.rows.clone(),
+                    affected_rows: None,
                 });
             }
 
@@ -13598,6 +16311,7 @@ impl QueryExecutor {
                     columns: qualified_columns,
                     column_types,
                     rows,
+                    affected_rows: None,
                 });
             }
 
@@ -13659,7 +16373,208 @@ impl QueryExecutor {
         }
     }
 
-    // Apply WHERE, ORDER BY, LIMIT, and projection clauses to CTE query results
+    // Apply WHERE, ORDER BY, LIMIT, and projection clauses to CTE query results
+    /// The single result row of a CTE query with aggregates and no GROUP BY
+    fn cte_aggregate_row(
+        &self,
+        projection_items: &[CteProjectionItem],
+        rows: &[Vec<Value>],
+        columns: &[String],
+    ) -> Vec<Value> {
+        // Create column mapping for aggregate function evaluation
+        let column_map: std::collections::HashMap<String, usize> = columns
+            .iter()
+            .enumerate()
+            .map(|(i, col)| (col.clone(), i))
+            .collect();
+
+        // For aggregate functions without GROUP BY, return one row with aggregated values
+        projection_items
+            .iter()
+            .map(|item| match item {
+                CteProjectionItem::Column(_) => {
+                    // Can't mix aggregates with non-aggregate columns without GROUP BY
+                    Value::Null
+                }
+                CteProjectionItem::Expression(expr) => {
+                    match expr.as_ref() {
+                        Expr::Function(func) => {
+                            let func_name = func
+                                .name
+                                .0
+                                .iter()
+                                .map(|i| i.value.clone())
+                                .collect::<Vec<_>>()
+                                .join(".");
+                            let Ok(rows) =
+                                Self::apply_aggregate_filter(func, rows, |filter, row| {
+                                    self.evaluate_where_condition_with_columns(filter, row, columns)
+                                })
+                            else {
+                                return Value::Null;
+                            };
+                            match func_name.to_uppercase().as_str() {
+                                "COUNT" => Self::count_argument_exprs(func)
+                                    .and_then(|arg_exprs| {
+                                        arg_exprs
+                                            .into_iter()
+                                            .map(|arg_expr| {
+                                                self.extract_cte_column_values(
+                                                    arg_expr,
+                                                    &rows,
+                                                    &column_map,
+                                                )
+                                            })
+                                            .collect::<crate::Result<Vec<_>>>()
+                                    })
+                                    .map(|argument_values| {
+                                        Self::calculate_count(
+                                            rows.len(),
+                                            &argument_values,
+                                            Self::is_distinct_aggregate(func)
+                                                .then_some(self.collation),
+                                        )
+                                    })
+                                    .unwrap_or(Value::Null),
+                                "SUM" => {
+                                    // SUM(column_name)
+                                    if let FunctionArguments::List(ref args) = func.args {
+                                        if let Some(FunctionArg::Unnamed(FunctionArgExpr::Expr(
+                                            col_expr,
+                                        ))) = args.args.first()
+                                        {
+                                            let values = self
+                                                .extract_cte_column_values(
+                                                    col_expr,
+                                                    &rows,
+                                                    &column_map,
+                                                )
+                                                .unwrap_or_default();
+                                            let values =
+                                                Self::apply_aggregate_distinct(func, values);
+                                            self.calculate_sum(&values).unwrap_or(Value::Null)
+                                        } else {
+                                            Value::Null
+                                        }
+                                    } else {
+                                        Value::Null
+                                    }
+                                }
+                                "AVG" => {
+                                    // AVG(column_name)
+                                    if let FunctionArguments::List(ref args) = func.args {
+                                        if let Some(FunctionArg::Unnamed(FunctionArgExpr::Expr(
+                                            col_expr,
+                                        ))) = args.args.first()
+                                        {
+                                            let values = self
+                                                .extract_cte_column_values(
+                                                    col_expr,
+                                                    &rows,
+                                                    &column_map,
+                                                )
+                                                .unwrap_or_default();
+                                            let values =
+                                                Self::apply_aggregate_distinct(func, values);
+                                            self.calculate_avg(&values).unwrap_or(Value::Null)
+                                        } else {
+                                            Value::Null
+                                        }
+                                    } else {
+                                        Value::Null
+                                    }
+                                }
+                                "MIN" => {
+                                    // MIN(column_name)
+                                    if let FunctionArguments::List(ref args) = func.args {
+                                        if let Some(FunctionArg::Unnamed(FunctionArgExpr::Expr(
+                                            col_expr,
+                                        ))) = args.args.first()
+                                        {
+                                            let values = self
+                                                .extract_cte_column_values(
+                                                    col_expr,
+                                                    &rows,
+                                                    &column_map,
+                                                )
+                                                .unwrap_or_default();
+                                            self.calculate_min(&values).unwrap_or(Value::Null)
+                                        } else {
+                                            Value::Null
+                                        }
+                                    } else {
+                                        Value::Null
+                                    }
+                                }
+                                "MAX" => {
+                                    // MAX(column_name)
+                                    if let FunctionArguments::List(ref args) = func.args {
+                                        if let Some(FunctionArg::Unnamed(FunctionArgExpr::Expr(
+                                            col_expr,
+                                        ))) = args.args.first()
+                                        {
+                                            let values = self
+                                                .extract_cte_column_values(
+                                                    col_expr,
+                                                    &rows,
+                                                    &column_map,
+                                                )
+                                                .unwrap_or_default();
+                                            self.calculate_max(&values).unwrap_or(Value::Null)
+                                        } else {
+                                            Value::Null
+                                        }
+                                    } else {
+                                        Value::Null
+                                    }
+                                }
+                                name if Self::is_statistical_aggregate(name) => {
+                                    if let FunctionArguments::List(ref args) = func.args
+                                        && let Some(FunctionArg::Unnamed(FunctionArgExpr::Expr(
+                                            col_expr,
+                                        ))) = args.args.first()
+                                    {
+                                        let values = self
+                                            .extract_cte_column_values(col_expr, &rows, &column_map)
+                                            .unwrap_or_default();
+                                        self.calculate_statistical_aggregate(name, &values)
+                                            .unwrap_or(Value::Null)
+                                    } else {
+                                        Value::Null
+                                    }
+                                }
+                                name if Self::is_json_aggregate(name) => {
+                                    Self::calculate_json_agg(name, func, |expr| {
+                                        self.extract_cte_column_values(expr, &rows, &column_map)
+                                    })
+                                    .unwrap_or(Value::Null)
+                                }
+                                name if Self::is_ordered_set_aggregate(name) => self
+                                    .ordered_set_aggregate_input(name, func)
+                                    .and_then(|(fraction, value_expr, descending)| {
+                                        let values = self.extract_cte_column_values(
+                                            value_expr,
+                                            &rows,
+                                            &column_map,
+                                        )?;
+                                        self.calculate_percentile(
+                                            name, fraction, &values, descending,
+                                        )
+                                    })
+                                    .unwrap_or(Value::Null),
+                                _ => {
+                                    // For other functions, return null
+                                    Value::Null
+                                }
+                            }
+                        }
+                        _ => Value::Null, // Other expressions not yet supported
+                    }
+                }
+            })
+            .collect()
+    }
+
     async fn apply_cte_query_clauses(
         &self,
         mut rows: Vec<Vec<Value>>,
@@ -13674,14 +16589,12 @@ impl QueryExecutor {
 
         // Apply ORDER BY
         if let Some(order_by) = &query.order_by {
-            rows = self.sort_rows_with_columns(&rows, &columns, &order_by.exprs)?;
+            let order_exprs = Self::order_by_with_projected_exprs(select, &order_by.exprs);
+            rows = self.sort_rows_with_columns(&rows, &columns, &order_exprs)?;
         }
 
-        // Apply LIMIT
-        if let Some(Expr::Value(sqlparser::ast::Value::Number(n, _))) = &query.limit {
-            let limit_count = n.parse::<usize>().unwrap_or(0);
-            rows.truncate(limit_count);
-        }
+        // Apply LIMIT/OFFSET if present
+        rows = self.apply_query_limits(std::mem::take(&mut rows), query)?;
 
         // Apply projection (SELECT clause)
         debug!(
@@ -13698,7 +16611,7 @@ impl QueryExecutor {
                 if let Expr::Function(func) = expr.as_ref() {
                     if let Some(first_part) = func.name.0.first() {
                         let func_name = first_part.value.to_uppercase();
-                        matches!(func_name.as_str(), "COUNT" | "SUM" | "AVG" | "MIN" | "MAX")
+                        Self::is_aggregate_name(&func_name)
                     } else {
                         false
                     }
@@ -13711,136 +16624,7 @@ impl QueryExecutor {
         });
 
         let projected_rows: Vec<Vec<Value>> = if has_aggregates {
-            // Create column mapping for aggregate function evaluation
-            let column_map: std::collections::HashMap<String, usize> = columns
-                .iter()
-                .enumerate()
-                .map(|(i, col)| (col.clone(), i))
-                .collect();
-
-            // For aggregate functions without GROUP BY, return one row with aggregated values
-            let aggregated_row: Vec<Value> = projection_items
-                .iter()
-                .map(|item| match item {
-                    CteProjectionItem::Column(_) => {
-                        // Can't mix aggregates with non-aggregate columns without GROUP BY
-                        Value::Null
-                    }
-                    CteProjectionItem::Expression(expr) => {
-                        match expr.as_ref() {
-                            Expr::Function(func) => {
-                                let func_name = func
-                                    .name
-                                    .0
-                                    .iter()
-                                    .map(|i| i.value.clone())
-                                    .collect::<Vec<_>>()
-                                    .join(".");
-                                match func_name.to_uppercase().as_str() {
-                                    "COUNT" => {
-                                        // COUNT(*) or COUNT(column) - count all rows
-                                        Value::Integer(rows.len() as i64)
-                                    }
-                                    "SUM" => {
-                                        // SUM(column_name)
-                                        if let FunctionArguments::List(ref args) = func.args {
-                                            if let Some(FunctionArg::Unnamed(
-                                                FunctionArgExpr::Expr(col_expr),
-                                            )) = args.args.first()
-                                            {
-                                                let values = self
-                                                    .extract_cte_column_values(
-                                                        col_expr,
-                                                        &rows,
-                                                        &column_map,
-                                                    )
-                                                    .unwrap_or_default();
-                                                self.calculate_sum(&values).unwrap_or(Value::Null)
-                                            } else {
-                                                Value::Null
-                                            }
-                                        } else {
-                                            Value::Null
-                                        }
-                                    }
-                                    "AVG" => {
-                                        // AVG(column_name)
-                                        if let FunctionArguments::List(ref args) = func.args {
-                                            if let Some(FunctionArg::Unnamed(
-                                                FunctionArgExpr::Expr(col_expr),
-                                            )) = args.args.first()
-                                            {
-                                                let values = self
-                                                    .extract_cte_column_values(
-                                                        col_expr,
-                                                        &rows,
-                                                        &column_map,
-                                                    )
-                                                    .unwrap_or_default();
-                                                self.calculate_avg(&values).unwrap_or(Value::Null)
-                                            } else {
-                                                Value::Null
-                                            }
-                                        } else {
-                                            Value::Null
-                                        }
-                                    }
-                                    "MIN" => {
-                                        // MIN(column_name)
-                                        if let FunctionArguments::List(ref args) = func.args {
-                                            if let Some(FunctionArg::Unnamed(
-                                                FunctionArgExpr::Expr(col_expr),
-                                            )) = args.args.first()
-                                            {
-                                                let values = self
-                                                    .extract_cte_column_values(
-                                                        col_expr,
-                                                        &rows,
-                                                        &column_map,
-                                                    )
-                                                    .unwrap_or_default();
-                                                self.calculate_min(&values).unwrap_or(Value::Null)
-                                            } else {
-                                                Value::Null
-                                            }
-                                        } else {
-                                            Value::Null
-                                        }
-                                    }
-                                    "MAX" => {
-                                        // MAX(column_name)
-                                        if let FunctionArguments::List(ref args) = func.args {
-                                            if let Some(FunctionArg::Unnamed(
-                                                FunctionArgExpr::Expr(col_expr),
-                                            )) = args.args.first()
-                                            {
-                                                let values = self
-                                                    .extract_cte_column_values(
-                                                        col_expr,
-                                                        &rows,
-                                                        &column_map,
-                                                    )
-                                                    .unwrap_or_default();
-                                                self.calculate_max(&values).unwrap_or(Value::Null)
-                                            } else {
-                                                Value::Null
-                                            }
-                                        } else {
-                                            Value::Null
-                                        }
-                                    }
-                                    _ => {
-                                        // For other functions, return null
-                                        Value::Null
-                                    }
-                                }
-                            }
-                            _ => Value::Null, // Other expressions not yet supported
-                        }
-                    }
-                })
-                .collect();
-            vec![aggregated_row]
+            vec![self.cte_aggregate_row(&projection_items, &rows, &columns)]
         } else {
             // Non-aggregate projection - process each row
             rows.into_iter()
@@ -13878,7 +16662,7 @@ impl QueryExecutor {
                 })
                 .collect();
 
-            self.apply_distinct(projected_rows, &select.distinct, &projection_items)?
+            self.apply_distinct(projected_rows, &select.distinct, &projection_items, &[])?
         } else {
             projected_rows
         };
@@ -13897,6 +16681,7 @@ impl QueryExecutor {
             columns: selected_columns,
             column_types,
             rows: distinct_rows,
+            affected_rows: None,
         })
     }
 
@@ -13921,7 +16706,7 @@ impl QueryExecutor {
                 // None means no explicit quantifier, which defaults to DISTINCT
                 left_rows.extend(right_rows);
                 // For set operations, we just want standard DISTINCT behavior
-                self.apply_distinct(left_rows, &Some(Distinct::Distinct), &[])
+                self.apply_distinct(left_rows, &Some(Distinct::Distinct), &[], &[])
             }
         }
     }
@@ -13955,8 +16740,10 @@ impl QueryExecutor {
             | SetQuantifier::AllByName => {
                 // EXCEPT DISTINCT (default) - remove all occurrences
                 // None means no explicit quantifier, which defaults to DISTINCT
-                let mut result = self.apply_distinct(left_rows, &Some(Distinct::Distinct), &[])?;
-                let right_set = self.apply_distinct(right_rows, &Some(Distinct::Distinct), &[])?;
+                let mut result =
+                    self.apply_distinct(left_rows, &Some(Distinct::Distinct), &[], &[])?;
+                let right_set =
+                    self.apply_distinct(right_rows, &Some(Distinct::Distinct), &[], &[])?;
 
                 result.retain(|row| !right_set.contains(row));
                 Ok(result)
@@ -13991,8 +16778,10 @@ impl QueryExecutor {
             | SetQuantifier::AllByName => {
                 // INTERSECT DISTINCT (default) - keep only distinct common rows
                 // None means no explicit quantifier, which defaults to DISTINCT
-                let left_set = self.apply_distinct(left_rows, &Some(Distinct::Distinct), &[])?;
-                let right_set = self.apply_distinct(right_rows, &Some(Distinct::Distinct), &[])?;
+                let left_set =
+                    self.apply_distinct(left_rows, &Some(Distinct::Distinct), &[], &[])?;
+                let right_set =
+                    self.apply_distinct(right_rows, &Some(Distinct::Distinct), &[], &[])?;
 
                 let result = left_set
                     .into_iter()
@@ -14007,14 +16796,11 @@ impl QueryExecutor {
 // Helper struct for managing CTE execution context
 struct CteExecutionContext<'a> {
     db: &'a Database,
-    cte_results: &'a std::collections::HashMap<String, QueryResult>,
+    cte_results: &'a CteResults,
 }
 
 impl<'a> CteExecutionContext<'a> {
-    fn new(
-        db: &'a Database,
-        cte_results: &'a std::collections::HashMap<String, QueryResult>,
-    ) -> Self {
+    fn new(db: &'a Database, cte_results: &'a CteResults) -> Self {
         Self { db, cte_results }
     }
 }
@@ -14065,6 +16851,7 @@ impl QueryExecutor {
             columns: result_columns,
             column_types,
             rows: result_rows,
+            affected_rows: None,
         })
     }
 
@@ -14122,6 +16909,7 @@ impl QueryExecutor {
             columns: result_columns,
             column_types,
             rows: result_rows,
+            affected_rows: None,
         })
     }
 
@@ -14180,6 +16968,7 @@ impl QueryExecutor {
             columns: left_result.columns,
             column_types: left_result.column_types,
             rows: combined_rows,
+            affected_rows: None,
         })
     }
 
@@ -14213,6 +17002,7 @@ impl QueryExecutor {
             columns: combined_columns,
             column_types,
             rows: result_rows,
+            affected_rows: None,
         })
     }
 
@@ -14358,6 +17148,7 @@ mod tests {
                 unique: true,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "name".to_string(),
@@ -14367,6 +17158,7 @@ mod tests {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
         ];
 
@@ -14407,6 +17199,7 @@ mod tests {
             unique: primary_key,
             default: None,
             references: None,
+            collation: None,
         }
     }
 
@@ -14511,7 +17304,10 @@ mod tests {
 
         let stmt = parse_statement("SELECT -3.5");
         let result = executor.execute(&stmt).await.unwrap();
-        assert_eq!(result.rows[0][0], Value::Double(-3.5));
+        assert_eq!(
+            result.rows[0][0],
+            Value::Decimal(rust_decimal::Decimal::new(-35, 1))
+        );
     }
 
     #[tokio::test]
@@ -14559,6 +17355,7 @@ mod tests {
                     unique: true,
                     default: None,
                     references: None,
+                    collation: None,
                 },
                 Column {
                     name: "status".to_string(),
@@ -14568,6 +17365,7 @@ mod tests {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
             ];
 
@@ -14656,6 +17454,7 @@ mod tests {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
             ];
 
@@ -15316,6 +18115,7 @@ mod tests {
                 unique: true,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "name".to_string(),
@@ -15325,6 +18125,7 @@ mod tests {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
         ];
         let mut users = Table::new("users".to_string(), columns1);
@@ -15346,6 +18147,7 @@ mod tests {
                 unique: true,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "user_id".to_string(),
@@ -15355,6 +18157,7 @@ mod tests {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "amount".to_string(),
@@ -15364,6 +18167,7 @@ mod tests {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
         ];
         let mut orders = Table::new("orders".to_string(), columns2);
@@ -15579,6 +18383,7 @@ mod tests {
                 unique: true,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "department".to_string(),
@@ -15588,6 +18393,7 @@ mod tests {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "salary".to_string(),
@@ -15597,6 +18403,7 @@ mod tests {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "bonus".to_string(),
@@ -15606,6 +18413,7 @@ mod tests {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
         ];
 
@@ -15647,57 +18455,384 @@ mod tests {
         let db_arc = Arc::new(RwLock::new(db));
         let executor = create_test_executor_from_arc(db_arc).await;
 
-        // Test 1: COUNT DISTINCT
-        let stmt = parse_statement("SELECT COUNT(DISTINCT department) FROM employees");
+        // Test 1: COUNT DISTINCT
+        let stmt = parse_statement("SELECT COUNT(DISTINCT department) FROM employees");
+        let result = executor.execute(&stmt).await.unwrap();
+
+        assert_eq!(result.rows.len(), 1);
+        assert_eq!(result.rows[0][0], Value::Integer(2)); // 2 unique departments
+
+        // Test 2: AVG with NULL handling
+        let stmt = parse_statement("SELECT AVG(bonus) FROM employees");
+        let result = executor.execute(&stmt).await.unwrap();
+
+        assert_eq!(result.rows.len(), 1);
+        if let Value::Double(avg) = &result.rows[0][0] {
+            assert_eq!(*avg, 7333.333333333333); // (5000 + 7000 + 10000) / 3
+        } else {
+            panic!("Expected Double value for AVG");
+        }
+
+        // Test 3: MIN
+        let stmt = parse_statement("SELECT MIN(salary) FROM employees");
+        let result = executor.execute(&stmt).await.unwrap();
+
+        assert_eq!(result.rows.len(), 1);
+        assert_eq!(
+            result.rows[0][0],
+            Value::Decimal(Decimal::from_str("65000.00").unwrap())
+        );
+
+        // Test 4: MAX
+        let stmt = parse_statement("SELECT MAX(salary) FROM employees");
+        let result = executor.execute(&stmt).await.unwrap();
+
+        assert_eq!(result.rows.len(), 1);
+        assert_eq!(
+            result.rows[0][0],
+            Value::Decimal(Decimal::from_str("85000.00").unwrap())
+        );
+
+        // Test 5: Multiple aggregates in one query
+        let stmt =
+            parse_statement("SELECT COUNT(*), AVG(salary), MIN(bonus), MAX(bonus) FROM employees");
+        let result = executor.execute(&stmt).await.unwrap();
+
+        assert_eq!(result.rows.len(), 1);
+        assert_eq!(result.rows[0][0], Value::Integer(4)); // COUNT(*)
+        // AVG(salary) should be (75000 + 85000 + 65000 + 70000) / 4 = 73750
+        if let Value::Double(avg) = &result.rows[0][1] {
+            assert_eq!(*avg, 73750.0);
+        }
+        assert_eq!(result.rows[0][2], Value::Integer(5000)); // MIN(bonus)
+        assert_eq!(result.rows[0][3], Value::Integer(10000)); // MAX(bonus)
+    }
+
+    #[tokio::test]
+    async fn test_statistical_aggregate_functions() {
+        let mut db = Database::new("test_db".to_string());
+
+        let columns = vec![
+            create_column("id", crate::yaml::schema::SqlType::Integer, true),
+            create_column("sensor", crate::yaml::schema::SqlType::Varchar(10), false),
+            Column {
+                name: "reading".to_string(),
+                sql_type: crate::yaml::schema::SqlType::Integer,
+                primary_key: false,
+                nullable: true,
+                unique: false,
+                default: None,
+                references: None,
+                collation: None,
+            },
+        ];
+
+        let mut measurements = Table::new("measurements".to_string(), columns);
+        // Readings 2, 4, 4, 4, 5, 5, 7, 9 have a population standard deviation of exactly 2
+        for (id, reading) in [2, 4, 4, 4, 5, 5, 7, 9].iter().enumerate() {
+            measurements
+                .insert_row(vec![
+                    Value::Integer(id as i64 + 1),
+                    Value::Text("a".to_string()),
+                    Value::Integer(*reading),
+                ])
+                .unwrap();
+        }
+        measurements
+            .insert_row(vec![
+                Value::Integer(9),
+                Value::Text("a".to_string()),
+                Value::Null,
+            ])
+            .unwrap();
+        measurements
+            .insert_row(vec![
+                Value::Integer(10),
+                Value::Text("b".to_string()),
+                Value::Integer(3),
+            ])
+            .unwrap();
+        db.add_table(measurements).unwrap();
+
+        let db_arc = Arc::new(RwLock::new(db));
+        let executor = create_test_executor_from_arc(db_arc).await;
+
+        let stmt = parse_statement(
+            "SELECT STDDEV_POP(reading), VAR_POP(reading), STDDEV(reading), VARIANCE(reading), \
+             STDDEV_SAMP(reading), VAR_SAMP(reading) FROM measurements WHERE sensor = 'a'",
+        );
+        let result = executor.execute(&stmt).await.unwrap();
+        assert_eq!(result.rows.len(), 1);
+        assert_eq!(result.rows[0][0], Value::Double(2.0));
+        assert_eq!(result.rows[0][1], Value::Double(4.0));
+        let sample_variance: f64 = 32.0 / 7.0;
+        assert_eq!(result.rows[0][2], Value::Double(sample_variance.sqrt()));
+        assert_eq!(result.rows[0][3], Value::Double(sample_variance));
+        assert_eq!(result.rows[0][4], result.rows[0][2]);
+        assert_eq!(result.rows[0][5], result.rows[0][3]);
+        assert_eq!(result.column_types[0], crate::yaml::schema::SqlType::Double);
+
+        // A single non-NULL value has no sample deviation but a population deviation of zero
+        let stmt = parse_statement(
+            "SELECT STDDEV(reading), STDDEV_POP(reading) FROM measurements WHERE sensor = 'b'",
+        );
+        let result = executor.execute(&stmt).await.unwrap();
+        assert_eq!(result.rows[0][0], Value::Null);
+        assert_eq!(result.rows[0][1], Value::Double(0.0));
+
+        // GROUP BY path
+        let stmt = parse_statement(
+            "SELECT sensor, VAR_POP(reading) AS v FROM measurements GROUP BY sensor ORDER BY sensor",
+        );
+        let result = executor.execute(&stmt).await.unwrap();
+        assert_eq!(result.rows.len(), 2);
+        assert_eq!(result.rows[0][1], Value::Double(4.0));
+        assert_eq!(result.rows[1][1], Value::Double(0.0));
+
+        // Non-numeric input is rejected
+        let stmt = parse_statement("SELECT STDDEV(sensor) FROM measurements");
+        assert!(executor.execute(&stmt).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_ordered_set_aggregate_functions() {
+        let mut db = Database::new("test_db".to_string());
+
+        let columns = vec![
+            create_column("id", crate::yaml::schema::SqlType::Integer, true),
+            create_column("endpoint", crate::yaml::schema::SqlType::Varchar(20), false),
+            Column {
+                name: "latency_ms".to_string(),
+                sql_type: crate::yaml::schema::SqlType::Integer,
+                primary_key: false,
+                nullable: true,
+                unique: false,
+                default: None,
+                references: None,
+                collation: None,
+            },
+        ];
+
+        let mut requests = Table::new("requests".to_string(), columns);
+        let data = [
+            ("/api", Some(40)),
+            ("/api", Some(10)),
+            ("/api", Some(30)),
+            ("/api", Some(20)),
+            ("/api", None),
+            ("/health", Some(5)),
+            ("/health", Some(7)),
+            ("/health", Some(6)),
+        ];
+        for (id, (endpoint, latency)) in data.iter().enumerate() {
+            requests
+                .insert_row(vec![
+                    Value::Integer(id as i64 + 1),
+                    Value::Text(endpoint.to_string()),
+                    latency.map(Value::Integer).unwrap_or(Value::Null),
+                ])
+                .unwrap();
+        }
+        db.add_table(requests).unwrap();
+
+        let db_arc = Arc::new(RwLock::new(db));
+        let executor = create_test_executor_from_arc(db_arc).await;
+
+        let stmt = parse_statement(
+            "SELECT PERCENTILE_CONT(0.5) WITHIN GROUP (ORDER BY latency_ms), \
+             PERCENTILE_DISC(0.5) WITHIN GROUP (ORDER BY latency_ms), \
+             PERCENTILE_CONT(0.9) WITHIN GROUP (ORDER BY latency_ms), \
+             PERCENTILE_DISC(0.25) WITHIN GROUP (ORDER BY latency_ms DESC), \
+             MEDIAN(latency_ms) FROM requests WHERE endpoint = '/api'",
+        );
         let result = executor.execute(&stmt).await.unwrap();
-
         assert_eq!(result.rows.len(), 1);
-        assert_eq!(result.rows[0][0], Value::Integer(2)); // 2 unique departments
+        assert_eq!(result.rows[0][0], Value::Double(25.0));
+        assert_eq!(result.rows[0][1], Value::Integer(20));
+        assert_eq!(result.rows[0][2], Value::Double(37.0));
+        assert_eq!(result.rows[0][3], Value::Integer(40));
+        assert_eq!(result.rows[0][4], Value::Double(25.0));
 
-        // Test 2: AVG with NULL handling
-        let stmt = parse_statement("SELECT AVG(bonus) FROM employees");
+        let stmt = parse_statement(
+            "SELECT endpoint, PERCENTILE_CONT(0.5) WITHIN GROUP (ORDER BY latency_ms) AS p50 \
+             FROM requests GROUP BY endpoint ORDER BY endpoint",
+        );
         let result = executor.execute(&stmt).await.unwrap();
+        assert_eq!(result.rows.len(), 2);
+        assert_eq!(result.rows[0][1], Value::Double(25.0));
+        assert_eq!(result.rows[1][1], Value::Double(6.0));
 
-        assert_eq!(result.rows.len(), 1);
-        if let Value::Double(avg) = &result.rows[0][0] {
-            assert_eq!(*avg, 7333.333333333333); // (5000 + 7000 + 10000) / 3
-        } else {
-            panic!("Expected Double value for AVG");
+        // Fractions outside [0, 1] are rejected
+        let stmt = parse_statement(
+            "SELECT PERCENTILE_CONT(1.5) WITHIN GROUP (ORDER BY latency_ms) FROM requests",
+        );
+        assert!(executor.execute(&stmt).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_distinct_aggregate_functions() {
+        let mut db = Database::new("test_db".to_string());
+
+        let mut customers = Table::new(
+            "customers".to_string(),
+            vec![
+                create_column("id", crate::yaml::schema::SqlType::Integer, true),
+                create_column("region", crate::yaml::schema::SqlType::Varchar(10), false),
+            ],
+        );
+        for (id, region) in [(1, "north"), (2, "north"), (3, "south")] {
+            customers
+                .insert_row(vec![Value::Integer(id), Value::Text(region.to_string())])
+                .unwrap();
         }
+        db.add_table(customers).unwrap();
 
-        // Test 3: MIN
-        let stmt = parse_statement("SELECT MIN(salary) FROM employees");
-        let result = executor.execute(&stmt).await.unwrap();
+        let mut orders = Table::new(
+            "orders".to_string(),
+            vec![
+                create_column("id", crate::yaml::schema::SqlType::Integer, true),
+                create_column("customer_id", crate::yaml::schema::SqlType::Integer, false),
+                Column {
+                    name: "amount".to_string(),
+                    sql_type: crate::yaml::schema::SqlType::Integer,
+                    primary_key: false,
+                    nullable: true,
+                    unique: false,
+                    default: None,
+                    references: None,
+                    collation: None,
+                },
+            ],
+        );
+        let data = [
+            (1, Some(10)),
+            (1, Some(10)),
+            (2, Some(20)),
+            (2, None),
+            (3, Some(30)),
+        ];
+        for (id, (customer_id, amount)) in data.iter().enumerate() {
+            orders
+                .insert_row(vec![
+                    Value::Integer(id as i64 + 1),
+                    Value::Integer(*customer_id),
+                    amount.map(Value::Integer).unwrap_or(Value::Null),
+                ])
+                .unwrap();
+        }
+        db.add_table(orders).unwrap();
 
-        assert_eq!(result.rows.len(), 1);
-        assert_eq!(
-            result.rows[0][0],
-            Value::Decimal(Decimal::from_str("65000.00").unwrap())
+        let db_arc = Arc::new(RwLock::new(db));
+        let executor = create_test_executor_from_arc(db_arc).await;
+
+        let stmt = parse_statement(
+            "SELECT COUNT(DISTINCT amount), SUM(DISTINCT amount), AVG(DISTINCT amount), \
+             COUNT(amount), COUNT(*), COUNT(DISTINCT customer_id, amount) FROM orders",
         );
+        let result = executor.execute(&stmt).await.unwrap();
+        assert_eq!(result.columns[0], "COUNT(DISTINCT amount)");
+        assert_eq!(result.rows[0][0], Value::Integer(3));
+        assert_eq!(result.rows[0][1], Value::Double(60.0));
+        assert_eq!(result.rows[0][2], Value::Double(20.0));
+        assert_eq!(result.rows[0][3], Value::Integer(4));
+        assert_eq!(result.rows[0][4], Value::Integer(5));
+        assert_eq!(result.rows[0][5], Value::Integer(3));
 
-        // Test 4: MAX
-        let stmt = parse_statement("SELECT MAX(salary) FROM employees");
+        // DISTINCT over joined rows, with and without GROUP BY
+        let stmt = parse_statement(
+            "SELECT COUNT(DISTINCT o.customer_id), COUNT(o.amount) \
+             FROM orders o JOIN customers c ON o.customer_id = c.id",
+        );
         let result = executor.execute(&stmt).await.unwrap();
+        assert_eq!(result.rows[0][0], Value::Integer(3));
+        assert_eq!(result.rows[0][1], Value::Integer(4));
 
-        assert_eq!(result.rows.len(), 1);
+        let stmt = parse_statement(
+            "SELECT c.region, COUNT(DISTINCT o.customer_id), SUM(DISTINCT o.amount) \
+             FROM orders o JOIN customers c ON o.customer_id = c.id GROUP BY c.region",
+        );
+        let result = executor.execute(&stmt).await.unwrap();
+        let mut rows = result.rows.clone();
+        rows.sort_by(|a, b| a[0].to_string().cmp(&b[0].to_string()));
         assert_eq!(
-            result.rows[0][0],
-            Value::Decimal(Decimal::from_str("85000.00").unwrap())
+            rows,
+            vec![
+                vec![
+                    Value::Text("north".to_string()),
+                    Value::Integer(2),
+                    Value::Integer(30)
+                ],
+                vec![
+                    Value::Text("south".to_string()),
+                    Value::Integer(1),
+                    Value::Integer(30)
+                ],
+            ]
         );
+    }
 
-        // Test 5: Multiple aggregates in one query
-        let stmt =
-            parse_statement("SELECT COUNT(*), AVG(salary), MIN(bonus), MAX(bonus) FROM employees");
-        let result = executor.execute(&stmt).await.unwrap();
+    #[tokio::test]
+    async fn test_aggregate_filter_clause() {
+        let mut db = Database::new("test_db".to_string());
 
-        assert_eq!(result.rows.len(), 1);
-        assert_eq!(result.rows[0][0], Value::Integer(4)); // COUNT(*)
-        // AVG(salary) should be (75000 + 85000 + 65000 + 70000) / 4 = 73750
-        if let Value::Double(avg) = &result.rows[0][1] {
-            assert_eq!(*avg, 73750.0);
+        let mut accounts = Table::new(
+            "accounts".to_string(),
+            vec![
+                create_column("id", crate::yaml::schema::SqlType::Integer, true),
+                create_column("plan", crate::yaml::schema::SqlType::Varchar(10), false),
+                create_column("status", crate::yaml::schema::SqlType::Varchar(10), false),
+                create_column("balance", crate::yaml::schema::SqlType::Integer, false),
+            ],
+        );
+        let data = [
+            ("free", "active", 0),
+            ("free", "inactive", 0),
+            ("pro", "active", 100),
+            ("pro", "active", 50),
+            ("pro", "inactive", 25),
+        ];
+        for (id, (plan, status, balance)) in data.iter().enumerate() {
+            accounts
+                .insert_row(vec![
+                    Value::Integer(id as i64 + 1),
+                    Value::Text(plan.to_string()),
+                    Value::Text(status.to_string()),
+                    Value::Integer(*balance),
+                ])
+                .unwrap();
         }
-        assert_eq!(result.rows[0][2], Value::Integer(5000)); // MIN(bonus)
-        assert_eq!(result.rows[0][3], Value::Integer(10000)); // MAX(bonus)
+        db.add_table(accounts).unwrap();
+
+        let db_arc = Arc::new(RwLock::new(db));
+        let executor = create_test_executor_from_arc(db_arc).await;
+
+        let stmt = parse_statement(
+            "SELECT COUNT(*) FILTER (WHERE status = 'active'), \
+             SUM(balance) FILTER (WHERE status = 'active' AND plan = 'pro'), \
+             COUNT(*) FROM accounts",
+        );
+        let result = executor.execute(&stmt).await.unwrap();
+        assert_eq!(result.rows[0][0], Value::Integer(3));
+        assert_eq!(result.rows[0][1], Value::Double(150.0));
+        assert_eq!(result.rows[0][2], Value::Integer(5));
+
+        let stmt = parse_statement(
+            "SELECT plan, COUNT(*) FILTER (WHERE status = 'inactive') AS inactive \
+             FROM accounts GROUP BY plan ORDER BY plan",
+        );
+        let result = executor.execute(&stmt).await.unwrap();
+        assert_eq!(result.rows.len(), 2);
+        assert_eq!(result.rows[0][1], Value::Integer(1));
+        assert_eq!(result.rows[1][1], Value::Integer(1));
+
+        // A filter that matches nothing yields the aggregate over an empty set
+        let stmt = parse_statement(
+            "SELECT COUNT(*) FILTER (WHERE balance > 1000), MAX(balance) FILTER (WHERE balance > 1000) \
+             FROM accounts",
+        );
+        let result = executor.execute(&stmt).await.unwrap();
+        assert_eq!(result.rows[0][0], Value::Integer(0));
+        assert_eq!(result.rows[0][1], Value::Null);
     }
 
     #[tokio::test]
@@ -15857,6 +18992,7 @@ mod tests {
                 unique: true,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "name".to_string(),
@@ -15866,6 +19002,7 @@ mod tests {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
         ];
         let mut users = Table::new("users".to_string(), user_columns);
@@ -15887,6 +19024,7 @@ mod tests {
                 unique: true,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "user_id".to_string(),
@@ -15896,6 +19034,7 @@ mod tests {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "amount".to_string(),
@@ -15905,6 +19044,7 @@ mod tests {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
         ];
         let mut orders = Table::new("orders".to_string(), order_columns);
@@ -16067,6 +19207,7 @@ mod tests {
                 unique: true,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "name".to_string(),
@@ -16076,6 +19217,7 @@ mod tests {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "created_date".to_string(),
@@ -16085,6 +19227,7 @@ mod tests {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
         ];
 
@@ -16114,6 +19257,7 @@ mod tests {
                 unique: true,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "user_id".to_string(),
@@ -16123,6 +19267,7 @@ mod tests {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "activity_date".to_string(),
@@ -16132,6 +19277,7 @@ mod tests {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
         ];
 
@@ -16182,6 +19328,7 @@ mod tests {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
             ];
             let mut table = Table::new("test_table".to_string(), columns);
@@ -16234,6 +19381,7 @@ mod tests {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
             ];
             let mut table = Table::new("test_table".to_string(), columns);
@@ -16352,6 +19500,7 @@ mod tests {
                 unique: true,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "name".to_string(),
@@ -16361,6 +19510,7 @@ mod tests {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "description".to_string(),
@@ -16370,6 +19520,7 @@ mod tests {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
         ];
 
@@ -16593,6 +19744,7 @@ mod tests {
                 unique: true,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "text1".to_string(),
@@ -16602,6 +19754,7 @@ mod tests {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "text2".to_string(),
@@ -16611,6 +19764,7 @@ mod tests {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "num".to_string(),
@@ -16620,6 +19774,7 @@ mod tests {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
         ];
 
@@ -16672,6 +19827,7 @@ mod tests {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
             ];
             let mut table = Table::new("test_table".to_string(), columns);
@@ -17092,6 +20248,7 @@ mod tests {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
                 Column {
                     name: "nickname".to_string(),
@@ -17101,6 +20258,7 @@ mod tests {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
                 Column {
                     name: "status".to_string(),
@@ -17110,6 +20268,7 @@ mod tests {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
             ];
             let mut table = Table::new("users_with_nulls".to_string(), columns);
@@ -17221,6 +20380,7 @@ mod tests {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
             ];
             let mut table = Table::new("test_table".to_string(), columns);
@@ -17265,6 +20425,7 @@ mod tests {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
         ];
         let mut employees = Table::new("employees".to_string(), emp_columns);
@@ -17293,6 +20454,7 @@ mod tests {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "project".to_string(),
@@ -17302,6 +20464,7 @@ mod tests {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
         ];
         let mut assignments = Table::new("assignments".to_string(), assign_columns);
@@ -17365,6 +20528,7 @@ mod tests {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
                 Column {
                     name: "code".to_string(),
@@ -17374,6 +20538,7 @@ mod tests {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
                 Column {
                     name: "fixed_id".to_string(),
@@ -17383,6 +20548,7 @@ mod tests {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
             ];
             let mut table = Table::new("test_char".to_string(), columns);
@@ -17456,28 +20622,46 @@ mod tests {
         // Test ROUND - simpler case first
         let stmt = parse_statement("SELECT ROUND(3.24)");
         let result = executor.execute(&stmt).await.unwrap();
-        assert_eq!(result.rows[0][0], Value::Double(3.0));
+        assert_eq!(
+            result.rows[0][0],
+            Value::Decimal(rust_decimal::Decimal::new(3, 0))
+        );
 
         let stmt = parse_statement("SELECT ROUND(3.456, 2)");
         let result = executor.execute(&stmt).await.unwrap();
-        assert_eq!(result.rows[0][0], Value::Double(3.46));
+        assert_eq!(
+            result.rows[0][0],
+            Value::Decimal(rust_decimal::Decimal::new(346, 2))
+        );
 
         let stmt = parse_statement("SELECT ROUND(3.456789, 4)");
         let result = executor.execute(&stmt).await.unwrap();
-        assert_eq!(result.rows[0][0], Value::Double(3.4568));
+        assert_eq!(
+            result.rows[0][0],
+            Value::Decimal(rust_decimal::Decimal::new(34568, 4))
+        );
 
         let stmt = parse_statement("SELECT ROUND(3.789, 0)");
         let result = executor.execute(&stmt).await.unwrap();
-        assert_eq!(result.rows[0][0], Value::Double(4.0));
+        assert_eq!(
+            result.rows[0][0],
+            Value::Decimal(rust_decimal::Decimal::new(4, 0))
+        );
 
         // Test FLOOR
         let stmt = parse_statement("SELECT FLOOR(3.7)");
         let result = executor.execute(&stmt).await.unwrap();
-        assert_eq!(result.rows[0][0], Value::Double(3.0));
+        assert_eq!(
+            result.rows[0][0],
+            Value::Decimal(rust_decimal::Decimal::new(3, 0))
+        );
 
         let stmt = parse_statement("SELECT FLOOR(-3.7)");
         let result = executor.execute(&stmt).await.unwrap();
-        assert_eq!(result.rows[0][0], Value::Double(-4.0));
+        assert_eq!(
+            result.rows[0][0],
+            Value::Decimal(rust_decimal::Decimal::new(-4, 0))
+        );
 
         let stmt = parse_statement("SELECT FLOOR(5)");
         let result = executor.execute(&stmt).await.unwrap();
@@ -17486,11 +20670,17 @@ mod tests {
         // Test CEIL
         let stmt = parse_statement("SELECT CEIL(3.2)");
         let result = executor.execute(&stmt).await.unwrap();
-        assert_eq!(result.rows[0][0], Value::Double(4.0));
+        assert_eq!(
+            result.rows[0][0],
+            Value::Decimal(rust_decimal::Decimal::new(4, 0))
+        );
 
         let stmt = parse_statement("SELECT CEIL(-3.2)");
         let result = executor.execute(&stmt).await.unwrap();
-        assert_eq!(result.rows[0][0], Value::Double(-3.0));
+        assert_eq!(
+            result.rows[0][0],
+            Value::Decimal(rust_decimal::Decimal::new(-3, 0))
+        );
 
         let stmt = parse_statement("SELECT CEIL(5)");
         let result = executor.execute(&stmt).await.unwrap();
@@ -17507,7 +20697,10 @@ mod tests {
 
         let stmt = parse_statement("SELECT ABS(-3.5)");
         let result = executor.execute(&stmt).await.unwrap();
-        assert_eq!(result.rows[0][0], Value::Double(3.5));
+        assert_eq!(
+            result.rows[0][0],
+            Value::Decimal(rust_decimal::Decimal::new(35, 1))
+        );
 
         // Test MOD
         let stmt = parse_statement("SELECT MOD(10, 3)");
@@ -17524,7 +20717,10 @@ mod tests {
 
         let stmt = parse_statement("SELECT MOD(10.5, 3.0)");
         let result = executor.execute(&stmt).await.unwrap();
-        assert_eq!(result.rows[0][0], Value::Double(1.5));
+        assert_eq!(
+            result.rows[0][0],
+            Value::Decimal(rust_decimal::Decimal::new(15, 1))
+        );
     }
 
     #[tokio::test]
@@ -17548,7 +20744,10 @@ mod tests {
 
         match result {
             Ok(res) => {
-                assert_eq!(res.rows[0][0], Value::Double(3.0));
+                assert_eq!(
+                    res.rows[0][0],
+                    Value::Decimal(rust_decimal::Decimal::new(3, 0))
+                );
             }
             Err(e) => {
                 panic!("ROUND failed with error: {:?}", e);
@@ -17564,7 +20763,10 @@ mod tests {
         // First test a simple case
         let stmt = parse_statement("SELECT ROUND(3.14)");
         let result = executor.execute(&stmt).await.unwrap();
-        assert_eq!(result.rows[0][0], Value::Double(3.0));
+        assert_eq!(
+            result.rows[0][0],
+            Value::Decimal(rust_decimal::Decimal::new(3, 0))
+        );
 
         // Test NULL handling for each function
         let stmt = parse_statement("SELECT ROUND(NULL)");
@@ -17604,30 +20806,48 @@ mod tests {
         // ROUND edge cases
         let stmt = parse_statement("SELECT ROUND(2.5)"); // Banker's rounding
         let result = executor.execute(&stmt).await.unwrap();
-        // Note: Rust uses "round half away from zero", so 2.5 -> 3.0
-        assert_eq!(result.rows[0][0], Value::Double(3.0));
+        // Halves round away from zero, as in PostgreSQL and MySQL, so 2.5 -> 3
+        assert_eq!(
+            result.rows[0][0],
+            Value::Decimal(rust_decimal::Decimal::new(3, 0))
+        );
 
         let stmt = parse_statement("SELECT ROUND(-2.5)");
         let result = executor.execute(&stmt).await.unwrap();
-        assert_eq!(result.rows[0][0], Value::Double(-3.0));
+        assert_eq!(
+            result.rows[0][0],
+            Value::Decimal(rust_decimal::Decimal::new(-3, 0))
+        );
 
         // Negative precision (not common in SQL, but let's test the behavior)
         let stmt = parse_statement("SELECT ROUND(123.456, -1)");
         let result = executor.execute(&stmt).await.unwrap();
-        assert_eq!(result.rows[0][0], Value::Double(120.0));
+        assert_eq!(
+            result.rows[0][0],
+            Value::Decimal(rust_decimal::Decimal::new(120, 0))
+        );
 
         let stmt = parse_statement("SELECT ROUND(155.456, -2)");
         let result = executor.execute(&stmt).await.unwrap();
-        assert_eq!(result.rows[0][0], Value::Double(200.0));
+        assert_eq!(
+            result.rows[0][0],
+            Value::Decimal(rust_decimal::Decimal::new(200, 0))
+        );
 
         // Very small numbers
         let stmt = parse_statement("SELECT FLOOR(0.0000001)");
         let result = executor.execute(&stmt).await.unwrap();
-        assert_eq!(result.rows[0][0], Value::Double(0.0));
+        assert_eq!(
+            result.rows[0][0],
+            Value::Decimal(rust_decimal::Decimal::new(0, 0))
+        );
 
         let stmt = parse_statement("SELECT CEIL(0.0000001)");
         let result = executor.execute(&stmt).await.unwrap();
-        assert_eq!(result.rows[0][0], Value::Double(1.0));
+        assert_eq!(
+            result.rows[0][0],
+            Value::Decimal(rust_decimal::Decimal::new(1, 0))
+        );
 
         // MOD with zero divisor (should error)
         let stmt = parse_statement("SELECT MOD(10, 0)");
@@ -17664,6 +20884,7 @@ mod tests {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "name".to_string(),
@@ -17673,6 +20894,7 @@ mod tests {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "department".to_string(),
@@ -17682,6 +20904,7 @@ mod tests {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
         ];
 
@@ -17778,6 +21001,7 @@ mod tests {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "customer".to_string(),
@@ -17787,6 +21011,7 @@ mod tests {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "product".to_string(),
@@ -17796,6 +21021,7 @@ mod tests {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "quantity".to_string(),
@@ -17805,6 +21031,7 @@ mod tests {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
         ];
 
@@ -17935,6 +21162,7 @@ mod tests {
                 unique: true,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "name".to_string(),
@@ -17944,6 +21172,7 @@ mod tests {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "email".to_string(),
@@ -17953,6 +21182,7 @@ mod tests {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "age".to_string(),
@@ -17962,6 +21192,7 @@ mod tests {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "created_at".to_string(),
@@ -17971,6 +21202,7 @@ mod tests {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
         ];
 
@@ -18109,6 +21341,7 @@ mod tests {
                     unique: true,
                     default: None,
                     references: None,
+                    collation: None,
                 },
                 Column {
                     name: "product".to_string(),
@@ -18118,6 +21351,7 @@ mod tests {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
                 Column {
                     name: "price".to_string(),
@@ -18127,6 +21361,7 @@ mod tests {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
                 Column {
                     name: "quantity".to_string(),
@@ -18136,6 +21371,7 @@ mod tests {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
                 Column {
                     name: "category".to_string(),
@@ -18145,6 +21381,7 @@ mod tests {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
                 Column {
                     name: "created_date".to_string(),
@@ -18154,6 +21391,7 @@ mod tests {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
             ];
 
@@ -18322,6 +21560,7 @@ mod tests {
                 unique: true,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "value".to_string(),
@@ -18331,6 +21570,7 @@ mod tests {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "price".to_string(),
@@ -18340,6 +21580,7 @@ mod tests {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "name".to_string(),
@@ -18349,6 +21590,7 @@ mod tests {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "created_date".to_string(),
@@ -18358,6 +21600,7 @@ mod tests {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
         ];
 
@@ -18556,6 +21799,150 @@ mod tests {
         assert_eq!(result.rows[0][0], Value::Text("Alice".to_string()));
     }
 
+    #[tokio::test]
+    async fn test_cast_to_decimal_timestamp_time_and_boolean() {
+        let db = create_test_database().await;
+        let executor = create_test_executor_from_arc(db).await;
+
+        let stmt = parse_statement(
+            "SELECT CAST('12.345' AS DECIMAL(5,2)), CAST(2.5 AS NUMERIC(3)), \
+             CAST('7' AS NUMERIC), CAST(-1.005 AS DECIMAL(4,2))",
+        );
+        let result = executor.execute(&stmt).await.unwrap();
+        assert_eq!(
+            result.rows[0],
+            vec![
+                Value::Decimal(rust_decimal::Decimal::new(1235, 2)),
+                Value::Decimal(rust_decimal::Decimal::new(3, 0)),
+                Value::Decimal(rust_decimal::Decimal::new(7, 0)),
+                Value::Decimal(rust_decimal::Decimal::new(-101, 2)),
+            ]
+        );
+
+        // Too many integer digits for the precision
+        let stmt = parse_statement("SELECT CAST(1234.5 AS DECIMAL(5,2))");
+        let err = executor.execute(&stmt).await.unwrap_err();
+        assert!(err.to_string().contains("Numeric field overflow"));
+
+        let stmt = parse_statement(
+            "SELECT CAST('2025-07-15 13:45:30' AS TIMESTAMP), CAST('2025-07-15' AS TIMESTAMP), \
+             CAST('13:45' AS TIME), CAST(CAST('2025-07-15 13:45:30' AS TIMESTAMP) AS TIME)",
+        );
+        let result = executor.execute(&stmt).await.unwrap();
+        let date = NaiveDate::from_ymd_opt(2025, 7, 15).unwrap();
+        assert_eq!(
+            result.rows[0],
+            vec![
+                Value::Timestamp(date.and_hms_opt(13, 45, 30).unwrap()),
+                Value::Timestamp(date.and_hms_opt(0, 0, 0).unwrap()),
+                Value::Time(chrono::NaiveTime::from_hms_opt(13, 45, 0).unwrap()),
+                Value::Time(chrono::NaiveTime::from_hms_opt(13, 45, 30).unwrap()),
+            ]
+        );
+
+        let stmt =
+            parse_statement("SELECT CAST('yes' AS BOOLEAN), CAST('f' AS BOOLEAN), CAST(0 AS BOOL)");
+        let result = executor.execute(&stmt).await.unwrap();
+        assert_eq!(
+            result.rows[0],
+            vec![
+                Value::Boolean(true),
+                Value::Boolean(false),
+                Value::Boolean(false)
+            ]
+        );
+
+        for sql in [
+            "SELECT CAST('maybe' AS BOOLEAN)",
+            "SELECT CAST('abc' AS DECIMAL(5,2))",
+            "SELECT CAST('not a time' AS TIMESTAMP)",
+            "SELECT CAST('25:99' AS TIME)",
+        ] {
+            let stmt = parse_statement(sql);
+            assert!(executor.execute(&stmt).await.is_err(), "{sql} should fail");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cast_as_where_condition() {
+        let mut db = Database::new("test_db".to_string());
+        let mut flags = Table::new(
+            "flags".to_string(),
+            vec![
+                create_column("id", crate::yaml::schema::SqlType::Integer, true),
+                create_column("active", crate::yaml::schema::SqlType::Text, false),
+            ],
+        );
+        for (id, active) in [(1, "yes"), (2, "f"), (3, "true")] {
+            flags
+                .insert_row(vec![Value::Integer(id), Value::Text(active.to_string())])
+                .unwrap();
+        }
+        db.add_table(flags).unwrap();
+        let executor = create_test_executor_from_arc(Arc::new(RwLock::new(db))).await;
+
+        for (sql, expected) in [
+            (
+                "SELECT id FROM flags WHERE CAST('true' AS BOOLEAN)",
+                vec![1, 2, 3],
+            ),
+            ("SELECT id FROM flags WHERE CAST('0' AS BOOLEAN)", vec![]),
+            ("SELECT id FROM flags WHERE active::boolean", vec![1, 3]),
+            (
+                "SELECT f.id FROM flags f JOIN flags g ON g.id = f.id \
+                 WHERE CAST(f.active AS BOOLEAN) ORDER BY f.id",
+                vec![1, 3],
+            ),
+            (
+                "WITH c AS (SELECT id, active FROM flags) \
+                 SELECT id FROM c WHERE active::boolean ORDER BY id",
+                vec![1, 3],
+            ),
+        ] {
+            let result = executor.execute(&parse_statement(sql)).await.unwrap();
+            let ids: Vec<Value> = expected.into_iter().map(Value::Integer).collect();
+            assert_eq!(
+                result
+                    .rows
+                    .into_iter()
+                    .map(|row| row[0].clone())
+                    .collect::<Vec<_>>(),
+                ids,
+                "{sql}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_try_cast_and_safe_cast_return_null_on_failure() {
+        let db = create_test_database().await;
+        let executor = create_test_executor_from_arc(db).await;
+
+        let stmt = parse_statement(
+            "SELECT TRY_CAST('abc' AS INTEGER), TRY_CAST('42' AS INTEGER), \
+             SAFE_CAST('2025-13-45' AS DATE), SAFE_CAST('1.5' AS DECIMAL(3,1))",
+        );
+        let result = executor.execute(&stmt).await.unwrap();
+        assert_eq!(
+            result.rows[0],
+            vec![
+                Value::Null,
+                Value::Integer(42),
+                Value::Null,
+                Value::Decimal(rust_decimal::Decimal::new(15, 1)),
+            ]
+        );
+
+        // Row context: dirty text values become NULL instead of aborting the query
+        let stmt = parse_statement("SELECT TRY_CAST(name AS INTEGER) FROM users");
+        let result = executor.execute(&stmt).await.unwrap();
+        assert!(result.rows.iter().all(|row| row[0] == Value::Null));
+
+        // A plain CAST still fails
+        let stmt = parse_statement("SELECT CAST(name AS INTEGER) FROM users");
+        assert!(executor.execute(&stmt).await.is_err());
+    }
+
     #[tokio::test]
     async fn test_left_join_comprehensive() {
         let db = Arc::new(RwLock::new(Database::new("test_db".to_string())));
@@ -18772,6 +22159,7 @@ mod tests {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
             ];
             let mut table = Table::new("strings".to_string(), columns);
@@ -18855,6 +22243,7 @@ mod tests {
                 unique: true,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "name".to_string(),
@@ -18864,6 +22253,7 @@ mod tests {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
         ];
 
@@ -18887,6 +22277,7 @@ mod tests {
                 unique: true,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "user_id".to_string(),
@@ -18896,6 +22287,7 @@ mod tests {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "product".to_string(),
@@ -18905,6 +22297,7 @@ mod tests {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
         ];
 
@@ -19069,6 +22462,7 @@ mod tests {
                     unique: true,
                     default: None,
                     references: None,
+                    collation: None,
                 },
                 Column {
                     name: "value".to_string(),
@@ -19078,6 +22472,7 @@ mod tests {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
             ];
             let mut table = Table::new("test_except".to_string(), columns);
@@ -19139,6 +22534,68 @@ mod tests {
         assert_eq!(result.rows.len(), 0); // No common elements
     }
 
+    #[tokio::test]
+    async fn test_nested_set_operations() {
+        let db = create_test_database().await;
+        let executor = create_test_executor_from_arc(db).await;
+
+        // Chained and parenthesized set operations
+        let stmt = parse_statement(
+            "(SELECT id FROM users WHERE id <= 2
+              UNION
+              SELECT id FROM users WHERE id = 3)
+             EXCEPT
+             SELECT id FROM users WHERE id = 2
+             ORDER BY id",
+        );
+        let result = executor.execute(&stmt).await.unwrap();
+        assert_eq!(
+            result.rows,
+            vec![vec![Value::Integer(1)], vec![Value::Integer(3)]]
+        );
+
+        let stmt = parse_statement(
+            "SELECT id FROM users WHERE id = 1
+             UNION ALL
+             SELECT id FROM users WHERE id = 2
+             UNION ALL
+             SELECT id FROM users WHERE id = 1
+             ORDER BY id DESC",
+        );
+        let result = executor.execute(&stmt).await.unwrap();
+        let ids: Vec<_> = result.rows.iter().map(|row| row[0].clone()).collect();
+        assert_eq!(
+            ids,
+            vec![Value::Integer(2), Value::Integer(1), Value::Integer(1)]
+        );
+
+        // Each parenthesized side keeps its own ORDER BY and LIMIT
+        let stmt = parse_statement(
+            "(SELECT id, name FROM users ORDER BY id DESC LIMIT 1)
+             UNION ALL
+             (SELECT id, name FROM users ORDER BY id LIMIT 1)",
+        );
+        let result = executor.execute(&stmt).await.unwrap();
+        assert_eq!(result.rows.len(), 2);
+        assert_eq!(result.rows[0][1], Value::Text("Charlie".to_string()));
+        assert_eq!(result.rows[1][1], Value::Text("Alice".to_string()));
+
+        // Set operations in a CTE and in a main query that reads CTEs
+        let stmt = parse_statement(
+            "WITH picked AS (
+                SELECT id FROM users
+                EXCEPT
+                (SELECT id FROM users WHERE id = 1 UNION SELECT id FROM users WHERE id = 3)
+             )
+             SELECT id FROM picked
+             INTERSECT
+             SELECT id FROM users
+             ORDER BY id",
+        );
+        let result = executor.execute(&stmt).await.unwrap();
+        assert_eq!(result.rows, vec![vec![Value::Integer(2)]]);
+    }
+
     #[tokio::test]
     async fn test_union_column_mismatch() {
         let db = create_test_database().await;
@@ -19249,6 +22706,7 @@ mod tests {
                     unique: true,
                     default: None,
                     references: None,
+                    collation: None,
                 },
                 Column {
                     name: "event_date".to_string(),
@@ -19258,6 +22716,7 @@ mod tests {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
             ];
 
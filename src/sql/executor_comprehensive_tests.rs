@@ -23,6 +23,7 @@ mod comprehensive_tests {
                 unique: false,
                 primary_key: true,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "text_data".to_string(),
@@ -32,6 +33,7 @@ mod comprehensive_tests {
                 unique: false,
                 primary_key: false,
                 references: None,
+                collation: None,
             },
         ];
 
@@ -170,39 +172,84 @@ mod comprehensive_tests {
         let query =
             parse_sql("SELECT ROUND(3.14159), ROUND(3.14159, 2), ROUND(3.14159, 4)").unwrap();
         let result = executor.execute(&query[0]).await.unwrap();
-        assert_eq!(result.rows[0][0], Value::Double(3.0));
-        assert_eq!(result.rows[0][1], Value::Double(3.14));
-        assert_eq!(result.rows[0][2], Value::Double(3.1416));
+        assert_eq!(
+            result.rows[0][0],
+            Value::Decimal(rust_decimal::Decimal::new(3, 0))
+        );
+        assert_eq!(
+            result.rows[0][1],
+            Value::Decimal(rust_decimal::Decimal::new(314, 2))
+        );
+        assert_eq!(
+            result.rows[0][2],
+            Value::Decimal(rust_decimal::Decimal::new(31416, 4))
+        );
 
         // Test ROUND with negative numbers
         let query = parse_sql("SELECT ROUND(-2.5), ROUND(-2.6), ROUND(2.5)").unwrap();
         let result = executor.execute(&query[0]).await.unwrap();
-        assert_eq!(result.rows[0][0], Value::Double(-3.0)); // Rust uses round half away from zero 
-        assert_eq!(result.rows[0][1], Value::Double(-3.0));
-        assert_eq!(result.rows[0][2], Value::Double(3.0));
+        assert_eq!(
+            result.rows[0][0],
+            Value::Decimal(rust_decimal::Decimal::new(-3, 0))
+        ); // Halves round away from zero
+        assert_eq!(
+            result.rows[0][1],
+            Value::Decimal(rust_decimal::Decimal::new(-3, 0))
+        );
+        assert_eq!(
+            result.rows[0][2],
+            Value::Decimal(rust_decimal::Decimal::new(3, 0))
+        );
 
         // Test CEIL
         let query = parse_sql("SELECT CEIL(3.1), CEIL(3.9), CEIL(-3.1), CEIL(-3.9)").unwrap();
         let result = executor.execute(&query[0]).await.unwrap();
-        assert_eq!(result.rows[0][0], Value::Double(4.0));
-        assert_eq!(result.rows[0][1], Value::Double(4.0));
-        assert_eq!(result.rows[0][2], Value::Double(-3.0));
-        assert_eq!(result.rows[0][3], Value::Double(-3.0));
+        assert_eq!(
+            result.rows[0][0],
+            Value::Decimal(rust_decimal::Decimal::new(4, 0))
+        );
+        assert_eq!(
+            result.rows[0][1],
+            Value::Decimal(rust_decimal::Decimal::new(4, 0))
+        );
+        assert_eq!(
+            result.rows[0][2],
+            Value::Decimal(rust_decimal::Decimal::new(-3, 0))
+        );
+        assert_eq!(
+            result.rows[0][3],
+            Value::Decimal(rust_decimal::Decimal::new(-3, 0))
+        );
 
         // Test FLOOR
         let query = parse_sql("SELECT FLOOR(3.1), FLOOR(3.9), FLOOR(-3.1), FLOOR(-3.9)").unwrap();
         let result = executor.execute(&query[0]).await.unwrap();
-        assert_eq!(result.rows[0][0], Value::Double(3.0));
-        assert_eq!(result.rows[0][1], Value::Double(3.0));
-        assert_eq!(result.rows[0][2], Value::Double(-4.0));
-        assert_eq!(result.rows[0][3], Value::Double(-4.0));
+        assert_eq!(
+            result.rows[0][0],
+            Value::Decimal(rust_decimal::Decimal::new(3, 0))
+        );
+        assert_eq!(
+            result.rows[0][1],
+            Value::Decimal(rust_decimal::Decimal::new(3, 0))
+        );
+        assert_eq!(
+            result.rows[0][2],
+            Value::Decimal(rust_decimal::Decimal::new(-4, 0))
+        );
+        assert_eq!(
+            result.rows[0][3],
+            Value::Decimal(rust_decimal::Decimal::new(-4, 0))
+        );
 
         // Test ABS
         let query = parse_sql("SELECT ABS(-5), ABS(5), ABS(-3.14), ABS(0)").unwrap();
         let result = executor.execute(&query[0]).await.unwrap();
         assert_eq!(result.rows[0][0], Value::Integer(5));
         assert_eq!(result.rows[0][1], Value::Integer(5));
-        assert_eq!(result.rows[0][2], Value::Double(3.14));
+        assert_eq!(
+            result.rows[0][2],
+            Value::Decimal(rust_decimal::Decimal::new(314, 2))
+        );
         assert_eq!(result.rows[0][3], Value::Integer(0));
 
         // Test MOD
@@ -321,6 +368,7 @@ mod comprehensive_tests {
                 unique: false,
                 primary_key: true,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "name".to_string(),
@@ -330,6 +378,7 @@ mod comprehensive_tests {
                 unique: false,
                 primary_key: false,
                 references: None,
+                collation: None,
             },
         ];
 
@@ -349,6 +398,7 @@ mod comprehensive_tests {
                 unique: false,
                 primary_key: true,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "user_id".to_string(),
@@ -358,6 +408,7 @@ mod comprehensive_tests {
                 unique: false,
                 primary_key: false,
                 references: None,
+                collation: None,
             },
         ];
 
@@ -414,6 +465,7 @@ mod comprehensive_tests {
                 unique: false,
                 primary_key: true,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "status_code".to_string(),
@@ -423,6 +475,7 @@ mod comprehensive_tests {
                 unique: false,
                 primary_key: false,
                 references: None,
+                collation: None,
             },
         ];
 
@@ -452,6 +505,7 @@ mod comprehensive_tests {
                 unique: false,
                 primary_key: false,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "version_code".to_string(),
@@ -461,6 +515,7 @@ mod comprehensive_tests {
                 unique: false,
                 primary_key: false,
                 references: None,
+                collation: None,
             },
         ];
 
@@ -539,6 +594,7 @@ mod comprehensive_tests {
                 unique: false,
                 primary_key: true,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "PROJECT_NAME".to_string(),
@@ -548,6 +604,7 @@ mod comprehensive_tests {
                 unique: false,
                 primary_key: false,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "STATUS_CODE".to_string(),
@@ -557,6 +614,7 @@ mod comprehensive_tests {
                 unique: false,
                 primary_key: false,
                 references: None,
+                collation: None,
             },
         ];
 
@@ -666,6 +724,7 @@ mod comprehensive_tests {
                 unique: false,
                 primary_key: true,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "project_name".to_string(),
@@ -675,6 +734,7 @@ mod comprehensive_tests {
                 unique: false,
                 primary_key: false,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "status_code".to_string(),
@@ -684,6 +744,7 @@ mod comprehensive_tests {
                 unique: false,
                 primary_key: false,
                 references: None,
+                collation: None,
             },
         ];
 
@@ -716,6 +777,7 @@ mod comprehensive_tests {
                 unique: false,
                 primary_key: false,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "wbi_id".to_string(),
@@ -725,6 +787,7 @@ mod comprehensive_tests {
                 unique: false,
                 primary_key: false,
                 references: None,
+                collation: None,
             },
         ];
 
@@ -864,6 +927,7 @@ mod comprehensive_tests {
                 unique: false,
                 primary_key: true,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "product_id".to_string(),
@@ -873,6 +937,7 @@ mod comprehensive_tests {
                 unique: false,
                 primary_key: false,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "amount".to_string(),
@@ -882,6 +947,7 @@ mod comprehensive_tests {
                 unique: false,
                 primary_key: false,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "quantity".to_string(),
@@ -891,6 +957,7 @@ mod comprehensive_tests {
                 unique: false,
                 primary_key: false,
                 references: None,
+                collation: None,
             },
         ];
 
@@ -932,6 +999,7 @@ mod comprehensive_tests {
                 unique: false,
                 primary_key: true,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "name".to_string(),
@@ -941,6 +1009,7 @@ mod comprehensive_tests {
                 unique: false,
                 primary_key: false,
                 references: None,
+                collation: None,
             },
         ];
 
@@ -1062,6 +1131,7 @@ mod comprehensive_tests {
                 unique: false,
                 primary_key: true,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "color".to_string(),
@@ -1071,6 +1141,7 @@ mod comprehensive_tests {
                 unique: false,
                 primary_key: false,
                 references: None,
+                collation: None,
             },
         ];
 
@@ -1090,6 +1161,7 @@ mod comprehensive_tests {
                 unique: false,
                 primary_key: true,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "size".to_string(),
@@ -1099,6 +1171,7 @@ mod comprehensive_tests {
                 unique: false,
                 primary_key: false,
                 references: None,
+                collation: None,
             },
         ];
 
@@ -1181,6 +1254,7 @@ mod comprehensive_tests {
                 unique: false,
                 primary_key: true,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "event_date".to_string(),
@@ -1190,6 +1264,7 @@ mod comprehensive_tests {
                 unique: false,
                 primary_key: false,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "event_datetime".to_string(),
@@ -1199,6 +1274,7 @@ mod comprehensive_tests {
                 unique: false,
                 primary_key: false,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "event_text".to_string(),
@@ -1208,6 +1284,7 @@ mod comprehensive_tests {
                 unique: false,
                 primary_key: false,
                 references: None,
+                collation: None,
             },
         ];
 
@@ -1416,6 +1493,7 @@ mod comprehensive_tests {
                 unique: false,
                 primary_key: true,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "col".to_string(),
@@ -1425,6 +1503,7 @@ mod comprehensive_tests {
                 unique: false,
                 primary_key: false,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "date_col".to_string(),
@@ -1434,6 +1513,7 @@ mod comprehensive_tests {
                 unique: false,
                 primary_key: false,
                 references: None,
+                collation: None,
             },
         ];
 
@@ -1567,6 +1647,7 @@ mod comprehensive_tests {
                 unique: false,
                 primary_key: true,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "active".to_string(),
@@ -1576,6 +1657,7 @@ mod comprehensive_tests {
                 unique: false,
                 primary_key: false,
                 references: None,
+                collation: None,
             },
         ];
 
@@ -1595,6 +1677,7 @@ mod comprehensive_tests {
                 unique: false,
                 primary_key: true,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "status".to_string(),
@@ -1604,6 +1687,7 @@ mod comprehensive_tests {
                 unique: false,
                 primary_key: false,
                 references: None,
+                collation: None,
             },
         ];
 
@@ -0,0 +1,1070 @@
+// Scalar functions that only depend on their evaluated arguments. The constant, row and
+// JOIN evaluation paths all evaluate the arguments in their own context and then share
+// the implementations here.
+use crate::YamlBaseError;
+use crate::database::Value;
+use crate::database::timezone::parse_naive_timestamp;
+use crate::sql::SqlDialect;
+use crate::sql::date_format::{parse_date_time, template_to_chrono_format};
+use crate::sql::executor::QueryExecutor;
+use crate::sql::json::{json_operand, json_to_value, lookup_json, parse_json_path, value_to_json};
+use chrono::{Datelike, NaiveDate};
+use regex::{Regex, RegexBuilder};
+use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
+use sha1::Sha1;
+use sha2::{Digest, Sha224, Sha256, Sha384, Sha512};
+use sqlparser::ast::{
+    BinaryOperator, Expr, Function, FunctionArg, FunctionArgExpr, FunctionArguments,
+};
+use std::cmp::Ordering;
+
+const SCALAR_FUNCTIONS: &[&str] = &[
+    "REGEXP_LIKE",
+    "REGEXP_SUBSTR",
+    "REGEXP_REPLACE",
+    "SPLIT_PART",
+    "LPAD",
+    "RPAD",
+    "REPEAT",
+    "CONCAT_WS",
+    "REVERSE",
+    "INITCAP",
+    "TRANSLATE",
+    "INSTR",
+    "LOCATE",
+    "STRPOS",
+    "CHARINDEX",
+    "INDEX",
+    "POWER",
+    "POW",
+    "SQRT",
+    "EXP",
+    "LN",
+    "LOG",
+    "LOG10",
+    "LOG2",
+    "SIGN",
+    "TRUNC",
+    "TRUNCATE",
+    "GREATEST",
+    "LEAST",
+    "RAND",
+    "RANDOM",
+    "LAST_INSERT_ID",
+    "LASTVAL",
+    "CURRVAL",
+    "MD5",
+    "SHA1",
+    "SHA",
+    "SHA2",
+    "CRC32",
+    "JSON_EXTRACT",
+    "JSON_VALUE",
+    "JSON_OBJECT",
+    "JSON_BUILD_OBJECT",
+    "JSON_ARRAY",
+    "JSON_BUILD_ARRAY",
+    "TO_DATE",
+    "TO_TIMESTAMP",
+    "STR_TO_DATE",
+    "DAYNAME",
+    "MONTHNAME",
+    "WEEKDAY",
+    "DAYOFWEEK",
+];
+
+/// Upper bound for strings built by REPEAT and LPAD/RPAD
+const MAX_STRING_LENGTH: i64 = 64 * 1024 * 1024;
+
+/// Functions that only evaluate the arguments they need, so that e.g.
+/// `IF(qty = 0, 0, total / qty)` does not fail on the branch that is not taken
+const CONDITIONAL_FUNCTIONS: &[&str] = &["IF", "IFNULL", "ISNULL", "DECODE"];
+
+impl QueryExecutor {
+    pub(crate) fn is_scalar_function(name: &str) -> bool {
+        SCALAR_FUNCTIONS.contains(&name) || CONDITIONAL_FUNCTIONS.contains(&name)
+    }
+
+    /// Call one of the shared scalar functions, evaluating its arguments with `eval`
+    pub(crate) fn call_scalar_function(
+        &self,
+        name: &str,
+        func: &Function,
+        mut eval: impl FnMut(&Expr) -> crate::Result<Value>,
+    ) -> crate::Result<Value> {
+        if CONDITIONAL_FUNCTIONS.contains(&name) {
+            let args = Self::function_arg_exprs(func)?;
+            return Self::evaluate_conditional_function(name, &args, eval);
+        }
+        let args = Self::function_arg_exprs(func)?
+            .into_iter()
+            .map(&mut eval)
+            .collect::<crate::Result<Vec<_>>>()?;
+        self.evaluate_scalar_function(name, &args)
+    }
+
+    /// The positional argument expressions of a function call
+    pub(crate) fn function_arg_exprs(func: &Function) -> crate::Result<Vec<&Expr>> {
+        let args = match &func.args {
+            FunctionArguments::List(list) => &list.args,
+            FunctionArguments::None => return Ok(Vec::new()),
+            FunctionArguments::Subquery(_) => {
+                return Err(YamlBaseError::NotImplemented(format!(
+                    "Subquery arguments to {} are not supported",
+                    func.name
+                )));
+            }
+        };
+        args.iter()
+            .map(|arg| match arg {
+                FunctionArg::Unnamed(FunctionArgExpr::Expr(expr)) => Ok(expr),
+                _ => Err(YamlBaseError::Database {
+                    message: format!("Invalid argument for {}", func.name),
+                }),
+            })
+            .collect()
+    }
+
+    fn evaluate_conditional_function(
+        name: &str,
+        args: &[&Expr],
+        mut eval: impl FnMut(&Expr) -> crate::Result<Value>,
+    ) -> crate::Result<Value> {
+        match name {
+            "IF" => {
+                // MySQL IF(condition, then, else)
+                check_expr_count(name, args, 3, 3)?;
+                if is_true(name, &eval(args[0])?)? {
+                    eval(args[1])
+                } else {
+                    eval(args[2])
+                }
+            }
+            "IFNULL" => {
+                check_expr_count(name, args, 2, 2)?;
+                match eval(args[0])? {
+                    Value::Null => eval(args[1]),
+                    value => Ok(value),
+                }
+            }
+            "ISNULL" => {
+                // MySQL ISNULL(expr) tests for NULL, SQL Server ISNULL(expr, fallback) is IFNULL
+                check_expr_count(name, args, 1, 2)?;
+                let value = eval(args[0])?;
+                match args.get(1) {
+                    None => Ok(Value::Integer(matches!(value, Value::Null) as i64)),
+                    Some(fallback) if matches!(value, Value::Null) => eval(fallback),
+                    Some(_) => Ok(value),
+                }
+            }
+            "DECODE" => {
+                // Oracle/Teradata DECODE(expr, search1, result1, ... [, default]). Unlike
+                // CASE, a NULL search value matches a NULL expression.
+                if args.len() < 3 {
+                    return Err(YamlBaseError::Database {
+                        message: format!("{} requires at least 3 arguments", name),
+                    });
+                }
+                let value = eval(args[0])?;
+                let mut pairs = args[1..].chunks_exact(2);
+                for pair in pairs.by_ref() {
+                    let search = eval(pair[0])?;
+                    if value.compare(&search) == Some(Ordering::Equal) {
+                        return eval(pair[1]);
+                    }
+                }
+                match pairs.remainder() {
+                    [default] => eval(default),
+                    _ => Ok(Value::Null),
+                }
+            }
+            _ => Err(YamlBaseError::NotImplemented(format!(
+                "Function '{}' is not implemented",
+                name
+            ))),
+        }
+    }
+
+    /// Evaluate one of the `SCALAR_FUNCTIONS` on already evaluated arguments
+    pub(crate) fn evaluate_scalar_function(
+        &self,
+        name: &str,
+        args: &[Value],
+    ) -> crate::Result<Value> {
+        match name {
+            "REGEXP_LIKE" => {
+                check_arg_count(name, args, 2, 3)?;
+                let (Some(text), Some(pattern)) =
+                    (text_arg(name, args, 0)?, text_arg(name, args, 1)?)
+                else {
+                    return Ok(Value::Null);
+                };
+                let flags = text_arg(name, args, 2)?.unwrap_or_default();
+                let re = build_regex(&pattern, &flags)?;
+                Ok(Value::Boolean(re.is_match(&text)))
+            }
+            "REGEXP_SUBSTR" => {
+                // REGEXP_SUBSTR(text, pattern [, position [, occurrence [, flags [, group]]]])
+                check_arg_count(name, args, 2, 6)?;
+                let (Some(text), Some(pattern)) =
+                    (text_arg(name, args, 0)?, text_arg(name, args, 1)?)
+                else {
+                    return Ok(Value::Null);
+                };
+                let position = integer_arg(name, args, 2)?.unwrap_or(1);
+                let occurrence = integer_arg(name, args, 3)?.unwrap_or(1);
+                let flags = text_arg(name, args, 4)?.unwrap_or_default();
+                let group = integer_arg(name, args, 5)?.unwrap_or(0);
+                if position < 1 || occurrence < 1 || group < 0 {
+                    return Err(YamlBaseError::Database {
+                        message: format!(
+                            "{} position, occurrence and group must be positive",
+                            name
+                        ),
+                    });
+                }
+
+                let re = build_regex(&pattern, &flags)?;
+                let start = char_offset(&text, position);
+                let found = re
+                    .captures_iter(&text[start..])
+                    .nth(occurrence as usize - 1)
+                    .and_then(|captures| captures.get(group as usize))
+                    .map(|m| Value::Text(m.as_str().to_string()));
+                Ok(found.unwrap_or(Value::Null))
+            }
+            "REGEXP_REPLACE" => {
+                // PostgreSQL: REGEXP_REPLACE(text, pattern, replacement [, flags]) replaces
+                // the first match, or every match with the 'g' flag.
+                // MySQL/Oracle: REGEXP_REPLACE(text, pattern, replacement [, position
+                // [, occurrence [, flags]]]) where occurrence 0 (the default) replaces every match.
+                check_arg_count(name, args, 3, 6)?;
+                let (Some(text), Some(pattern), Some(replacement)) = (
+                    text_arg(name, args, 0)?,
+                    text_arg(name, args, 1)?,
+                    text_arg(name, args, 2)?,
+                ) else {
+                    return Ok(Value::Null);
+                };
+
+                let (position, occurrence, flags) = match args.get(3) {
+                    Some(Value::Text(flags)) if args.len() == 4 => {
+                        let occurrence = if flags.contains('g') { 0 } else { 1 };
+                        (1, occurrence, flags.replace('g', ""))
+                    }
+                    None if !matches!(self.dialect(), SqlDialect::MySQL) => (1, 1, String::new()),
+                    _ => (
+                        integer_arg(name, args, 3)?.unwrap_or(1),
+                        integer_arg(name, args, 4)?.unwrap_or(0),
+                        text_arg(name, args, 5)?.unwrap_or_default(),
+                    ),
+                };
+                if position < 1 || occurrence < 0 {
+                    return Err(YamlBaseError::Database {
+                        message: format!("{} position and occurrence must be positive", name),
+                    });
+                }
+
+                let re = build_regex(&pattern, &flags)?;
+                let replacement = sql_replacement_to_rust(&replacement);
+                let start = char_offset(&text, position);
+                let (head, tail) = text.split_at(start);
+                let replaced = if occurrence == 0 {
+                    re.replace_all(tail, replacement.as_str()).into_owned()
+                } else {
+                    replace_nth(&re, tail, occurrence as usize, &replacement)
+                };
+                Ok(Value::Text(format!("{}{}", head, replaced)))
+            }
+            "SPLIT_PART" => {
+                check_arg_count(name, args, 3, 3)?;
+                let (Some(text), Some(delimiter), Some(index)) = (
+                    text_arg(name, args, 0)?,
+                    text_arg(name, args, 1)?,
+                    integer_arg(name, args, 2)?,
+                ) else {
+                    return Ok(Value::Null);
+                };
+                if index == 0 {
+                    return Err(YamlBaseError::Database {
+                        message: "SPLIT_PART field position must not be zero".to_string(),
+                    });
+                }
+
+                let parts: Vec<&str> = if delimiter.is_empty() {
+                    vec![text.as_str()]
+                } else {
+                    text.split(delimiter.as_str()).collect()
+                };
+                // A negative position counts from the end
+                let idx = if index > 0 {
+                    index as usize - 1
+                } else {
+                    match parts.len().checked_sub(index.unsigned_abs() as usize) {
+                        Some(idx) => idx,
+                        None => return Ok(Value::Text(String::new())),
+                    }
+                };
+                Ok(Value::Text(
+                    parts.get(idx).copied().unwrap_or("").to_string(),
+                ))
+            }
+            "LPAD" | "RPAD" => {
+                check_arg_count(name, args, 2, 3)?;
+                let (Some(text), Some(length)) =
+                    (text_arg(name, args, 0)?, integer_arg(name, args, 1)?)
+                else {
+                    return Ok(Value::Null);
+                };
+                let Some(fill) = (match args.get(2) {
+                    Some(_) => text_arg(name, args, 2)?,
+                    None => Some(" ".to_string()),
+                }) else {
+                    return Ok(Value::Null);
+                };
+                check_string_length(name, length)?;
+
+                let length = length.max(0) as usize;
+                let chars: Vec<char> = text.chars().collect();
+                // Like PostgreSQL, a string longer than the target length is truncated
+                if chars.len() >= length || fill.is_empty() {
+                    return Ok(Value::Text(chars.iter().take(length).collect()));
+                }
+                let padding: String = fill.chars().cycle().take(length - chars.len()).collect();
+                if name == "LPAD" {
+                    Ok(Value::Text(format!("{}{}", padding, text)))
+                } else {
+                    Ok(Value::Text(format!("{}{}", text, padding)))
+                }
+            }
+            "REPEAT" => {
+                check_arg_count(name, args, 2, 2)?;
+                let (Some(text), Some(count)) =
+                    (text_arg(name, args, 0)?, integer_arg(name, args, 1)?)
+                else {
+                    return Ok(Value::Null);
+                };
+                let count = count.max(0);
+                check_string_length(name, (text.len() as i64).saturating_mul(count))?;
+                Ok(Value::Text(text.repeat(count as usize)))
+            }
+            "CONCAT_WS" => {
+                // CONCAT_WS(separator, value, ...) skips NULL values; a NULL separator gives
+                // NULL, in both MySQL and PostgreSQL
+                if args.len() < 2 {
+                    return Err(YamlBaseError::Database {
+                        message: "CONCAT_WS requires at least 2 arguments".to_string(),
+                    });
+                }
+                let Some(separator) = text_arg(name, args, 0)? else {
+                    return Ok(Value::Null);
+                };
+                let mut parts = Vec::with_capacity(args.len() - 1);
+                for idx in 1..args.len() {
+                    if let Some(part) = text_arg(name, args, idx)? {
+                        parts.push(part);
+                    }
+                }
+                let length = parts
+                    .iter()
+                    .map(|part| part.len() + separator.len())
+                    .sum::<usize>();
+                check_string_length(name, length as i64)?;
+                Ok(Value::Text(parts.join(&separator)))
+            }
+            "REVERSE" => {
+                check_arg_count(name, args, 1, 1)?;
+                Ok(match text_arg(name, args, 0)? {
+                    Some(text) => Value::Text(text.chars().rev().collect()),
+                    None => Value::Null,
+                })
+            }
+            "INITCAP" => {
+                // Upper-case the first letter of every word and lower-case the rest, where
+                // words are runs of alphanumeric characters
+                check_arg_count(name, args, 1, 1)?;
+                let Some(text) = text_arg(name, args, 0)? else {
+                    return Ok(Value::Null);
+                };
+                let mut result = String::with_capacity(text.len());
+                let mut in_word = false;
+                for c in text.chars() {
+                    if in_word {
+                        result.extend(c.to_lowercase());
+                    } else {
+                        result.extend(c.to_uppercase());
+                    }
+                    in_word = c.is_alphanumeric();
+                }
+                Ok(Value::Text(result))
+            }
+            "TRANSLATE" => {
+                // Characters of `from` are replaced by the character at the same position in
+                // `to`, or removed when `to` is shorter
+                check_arg_count(name, args, 3, 3)?;
+                let (Some(text), Some(from), Some(to)) = (
+                    text_arg(name, args, 0)?,
+                    text_arg(name, args, 1)?,
+                    text_arg(name, args, 2)?,
+                ) else {
+                    return Ok(Value::Null);
+                };
+                let from: Vec<char> = from.chars().collect();
+                let to: Vec<char> = to.chars().collect();
+                let translated = text
+                    .chars()
+                    .filter_map(|c| match from.iter().position(|f| *f == c) {
+                        Some(idx) => to.get(idx).copied(),
+                        None => Some(c),
+                    })
+                    .collect();
+                Ok(Value::Text(translated))
+            }
+            "INSTR" | "LOCATE" | "STRPOS" | "CHARINDEX" | "INDEX" => {
+                // Oracle/Teradata/MySQL INSTR(text, search [, position [, occurrence]]),
+                // PostgreSQL STRPOS(text, search) and Teradata INDEX(text, search) take the
+                // string first; MySQL LOCATE and SQL Server CHARINDEX take the search string
+                // first. All positions are 1-based and 0 means not found.
+                let (max_args, search_first) = match name {
+                    "INSTR" => (4, false),
+                    "LOCATE" | "CHARINDEX" => (3, true),
+                    _ => (2, false),
+                };
+                check_arg_count(name, args, 2, max_args)?;
+                let (text_idx, search_idx) = if search_first { (1, 0) } else { (0, 1) };
+                let (Some(text), Some(search)) = (
+                    text_arg(name, args, text_idx)?,
+                    text_arg(name, args, search_idx)?,
+                ) else {
+                    return Ok(Value::Null);
+                };
+                let position = match args.get(2) {
+                    Some(_) => match integer_arg(name, args, 2)? {
+                        Some(position) => position,
+                        None => return Ok(Value::Null),
+                    },
+                    None => 1,
+                };
+                let occurrence = integer_arg(name, args, 3)?.unwrap_or(1);
+                if occurrence < 1 {
+                    return Err(YamlBaseError::Database {
+                        message: format!("{} occurrence must be positive", name),
+                    });
+                }
+                if position == 0 {
+                    // MySQL LOCATE and Oracle INSTR both report no match for position 0
+                    return Ok(Value::Integer(0));
+                }
+                let found = if position < 0 && name == "INSTR" {
+                    // A negative position searches backwards from that many characters
+                    // before the end
+                    find_char_position_backwards(&text, &search, position, occurrence as usize)
+                } else {
+                    find_char_position(&text, &search, position.max(1), occurrence as usize)
+                };
+                Ok(Value::Integer(found.unwrap_or(0) as i64))
+            }
+            "POWER" | "POW" => {
+                check_arg_count(name, args, 2, 2)?;
+                let (Some(base), Some(exponent)) =
+                    (numeric_arg(name, args, 0)?, numeric_arg(name, args, 1)?)
+                else {
+                    return Ok(Value::Null);
+                };
+                if base == 0.0 && exponent < 0.0 {
+                    return Err(YamlBaseError::Database {
+                        message: "Zero raised to a negative power is undefined".to_string(),
+                    });
+                }
+                if base < 0.0 && exponent.fract() != 0.0 {
+                    return Err(YamlBaseError::Database {
+                        message: "A negative number raised to a non-integer power yields a complex result".to_string(),
+                    });
+                }
+                finite_double(base.powf(exponent))
+            }
+            "SQRT" => {
+                check_arg_count(name, args, 1, 1)?;
+                let Some(x) = numeric_arg(name, args, 0)? else {
+                    return Ok(Value::Null);
+                };
+                if x < 0.0 {
+                    return Err(YamlBaseError::Database {
+                        message: "Cannot take square root of a negative number".to_string(),
+                    });
+                }
+                finite_double(x.sqrt())
+            }
+            "EXP" => {
+                check_arg_count(name, args, 1, 1)?;
+                match numeric_arg(name, args, 0)? {
+                    Some(x) => finite_double(x.exp()),
+                    None => Ok(Value::Null),
+                }
+            }
+            "LN" | "LOG" | "LOG10" | "LOG2" => {
+                // LOG(x) is the base 10 logarithm as in PostgreSQL, LOG(b, x) uses base b
+                let max_args = if name == "LOG" { 2 } else { 1 };
+                check_arg_count(name, args, 1, max_args)?;
+                let mut values = Vec::with_capacity(args.len());
+                for idx in 0..args.len() {
+                    match numeric_arg(name, args, idx)? {
+                        Some(v) if v <= 0.0 => {
+                            return Err(YamlBaseError::Database {
+                                message: "Cannot take logarithm of zero or a negative number"
+                                    .to_string(),
+                            });
+                        }
+                        Some(v) => values.push(v),
+                        None => return Ok(Value::Null),
+                    }
+                }
+                let result = match (name, values.as_slice()) {
+                    ("LN", [x]) => x.ln(),
+                    ("LOG" | "LOG10", [x]) => x.log10(),
+                    ("LOG2", [x]) => x.log2(),
+                    (_, [base, _]) if *base == 1.0 => {
+                        return Err(YamlBaseError::Database {
+                            message: "Logarithm base must not be 1".to_string(),
+                        });
+                    }
+                    (_, [base, x]) => x.ln() / base.ln(),
+                    _ => unreachable!("argument count checked above"),
+                };
+                finite_double(result)
+            }
+            "SIGN" => {
+                check_arg_count(name, args, 1, 1)?;
+                match &args[0] {
+                    Value::Null => Ok(Value::Null),
+                    Value::Integer(i) => Ok(Value::Integer(i.signum())),
+                    Value::Decimal(d) if d.is_zero() => Ok(Value::Decimal(Decimal::ZERO)),
+                    Value::Decimal(d) if d.is_sign_negative() => {
+                        Ok(Value::Decimal(Decimal::NEGATIVE_ONE))
+                    }
+                    Value::Decimal(_) => Ok(Value::Decimal(Decimal::ONE)),
+                    Value::Double(d) if *d == 0.0 => Ok(Value::Double(0.0)),
+                    Value::Double(d) => Ok(Value::Double(d.signum())),
+                    Value::Float(f) if *f == 0.0 => Ok(Value::Float(0.0)),
+                    Value::Float(f) => Ok(Value::Float(f.signum())),
+                    _ => Err(YamlBaseError::Database {
+                        message: format!("{} requires a numeric argument", name),
+                    }),
+                }
+            }
+            "TRUNC" | "TRUNCATE" => {
+                // Unlike ROUND this always rounds towards zero. A negative digit count
+                // truncates to the left of the decimal point.
+                let min_args = if name == "TRUNCATE" { 2 } else { 1 };
+                check_arg_count(name, args, min_args, 2)?;
+                let digits = match args.get(1) {
+                    Some(_) => match integer_arg(name, args, 1)? {
+                        Some(digits) => digits.clamp(-28, 28) as i32,
+                        None => return Ok(Value::Null),
+                    },
+                    None => 0,
+                };
+                match &args[0] {
+                    Value::Null => Ok(Value::Null),
+                    Value::Integer(i) if digits >= 0 => Ok(Value::Integer(*i)),
+                    Value::Integer(i) => {
+                        let factor = 10i64.checked_pow(digits.unsigned_abs()).unwrap_or(i64::MAX);
+                        Ok(Value::Integer(i / factor * factor))
+                    }
+                    Value::Decimal(d) => Ok(Value::Decimal(truncate_decimal(*d, digits))),
+                    Value::Double(d) => Ok(Value::Double(truncate_f64(*d, digits))),
+                    Value::Float(f) => Ok(Value::Float(truncate_f64(*f as f64, digits) as f32)),
+                    _ => Err(YamlBaseError::Database {
+                        message: format!("{} requires a numeric argument", name),
+                    }),
+                }
+            }
+            "RAND" | "RANDOM" => {
+                check_arg_count(name, args, 0, 0)?;
+                Ok(Value::Double(self.next_random()))
+            }
+            "LAST_INSERT_ID" => {
+                check_arg_count(name, args, 0, 0)?;
+                Ok(Value::Integer(self.last_insert_id()))
+            }
+            "LASTVAL" => {
+                check_arg_count(name, args, 0, 0)?;
+                self.last_sequence_value()
+            }
+            "CURRVAL" => {
+                check_arg_count(name, args, 1, 1)?;
+                match text_arg(name, args, 0)? {
+                    Some(sequence) => self.current_sequence_value(&sequence),
+                    None => Ok(Value::Null),
+                }
+            }
+            "MD5" | "SHA1" | "SHA" | "CRC32" => {
+                check_arg_count(name, args, 1, 1)?;
+                let Some(text) = text_arg(name, args, 0)? else {
+                    return Ok(Value::Null);
+                };
+                Ok(match name {
+                    "MD5" => Value::Text(format!("{:x}", md5::compute(text.as_bytes()))),
+                    "CRC32" => Value::Integer(crc32fast::hash(text.as_bytes()) as i64),
+                    _ => Value::Text(hex::encode(Sha1::digest(text.as_bytes()))),
+                })
+            }
+            "SHA2" => {
+                // MySQL SHA2(text, bits): an unsupported bit length yields NULL
+                check_arg_count(name, args, 2, 2)?;
+                let (Some(text), Some(bits)) =
+                    (text_arg(name, args, 0)?, integer_arg(name, args, 1)?)
+                else {
+                    return Ok(Value::Null);
+                };
+                let bytes = text.as_bytes();
+                let digest = match bits {
+                    0 | 256 => hex::encode(Sha256::digest(bytes)),
+                    224 => hex::encode(Sha224::digest(bytes)),
+                    384 => hex::encode(Sha384::digest(bytes)),
+                    512 => hex::encode(Sha512::digest(bytes)),
+                    _ => return Ok(Value::Null),
+                };
+                Ok(Value::Text(digest))
+            }
+            "JSON_EXTRACT" | "JSON_VALUE" => {
+                // JSON_EXTRACT(json, path [, path ...]) returns scalars as SQL values and
+                // objects/arrays as JSON; several paths give an array of the matches.
+                // JSON_VALUE(json, path) only returns scalars, NULL for objects and arrays.
+                let max_args = if name == "JSON_VALUE" { 2 } else { usize::MAX };
+                check_arg_count(name, args, 2, max_args)?;
+                let Some(json) = json_operand(name, &args[0])? else {
+                    return Ok(Value::Null);
+                };
+                let mut found = Vec::with_capacity(args.len() - 1);
+                for idx in 1..args.len() {
+                    let Some(path) = text_arg(name, args, idx)? else {
+                        return Ok(Value::Null);
+                    };
+                    if let Some(value) = lookup_json(&json, &parse_json_path(&path)?) {
+                        found.push(value.clone());
+                    }
+                }
+                Ok(match (name, args.len(), found.as_slice()) {
+                    (_, _, []) => Value::Null,
+                    ("JSON_VALUE", _, [value]) => match json_to_value(value) {
+                        Value::Json(_) => Value::Null,
+                        scalar => scalar,
+                    },
+                    (_, 2, [value]) => json_to_value(value),
+                    _ => Value::Json(serde_json::Value::Array(found)),
+                })
+            }
+            "JSON_OBJECT" | "JSON_BUILD_OBJECT" => {
+                // JSON_OBJECT(key, value, ...) as in MySQL and PostgreSQL's json_build_object;
+                // keys must be non-NULL and later duplicates replace earlier ones
+                if !args.len().is_multiple_of(2) {
+                    return Err(YamlBaseError::Database {
+                        message: format!("{} requires an even number of arguments", name),
+                    });
+                }
+                let mut object = serde_json::Map::with_capacity(args.len() / 2);
+                for pair in args.chunks(2) {
+                    let key = match &pair[0] {
+                        Value::Null => {
+                            return Err(YamlBaseError::Database {
+                                message: format!("{} key cannot be NULL", name),
+                            });
+                        }
+                        Value::Text(key) => key.clone(),
+                        other => other.to_string(),
+                    };
+                    object.insert(key, value_to_json(&pair[1]));
+                }
+                Ok(Value::Json(serde_json::Value::Object(object)))
+            }
+            "JSON_ARRAY" | "JSON_BUILD_ARRAY" => Ok(Value::Json(serde_json::Value::Array(
+                args.iter().map(value_to_json).collect(),
+            ))),
+            "TO_DATE" | "TO_TIMESTAMP" | "STR_TO_DATE" => {
+                // TO_DATE/TO_TIMESTAMP(text, template) take PostgreSQL/Oracle templates and fail
+                // on text that does not match. STR_TO_DATE(text, format) takes MySQL specifiers,
+                // returns a date, time or datetime depending on the format and gives NULL for
+                // text that does not match, as MySQL does.
+                if name == "TO_TIMESTAMP" && args.len() == 1 {
+                    let Some(seconds) = numeric_arg(name, args, 0)? else {
+                        return Ok(Value::Null);
+                    };
+                    return chrono::DateTime::from_timestamp_micros((seconds * 1e6) as i64)
+                        .filter(|_| seconds.is_finite())
+                        .map(|timestamp| Value::Timestamp(timestamp.naive_utc()))
+                        .ok_or_else(|| YamlBaseError::Database {
+                            message: format!("TO_TIMESTAMP: timestamp out of range: {}", seconds),
+                        });
+                }
+                check_arg_count(name, args, 2, 2)?;
+                let (Some(text), Some(format)) =
+                    (text_arg(name, args, 0)?, text_arg(name, args, 1)?)
+                else {
+                    return Ok(Value::Null);
+                };
+                let chrono_format = if name == "STR_TO_DATE" {
+                    self.mysql_to_chrono_format(&format)
+                } else {
+                    template_to_chrono_format(&format)
+                };
+                match (name, parse_date_time(&text, &chrono_format)) {
+                    ("STR_TO_DATE", Ok((Some(date), Some(time)))) => {
+                        Ok(Value::Timestamp(date.and_time(time)))
+                    }
+                    ("STR_TO_DATE", Ok((Some(date), None))) => Ok(Value::Date(date)),
+                    ("STR_TO_DATE", Ok((None, Some(time)))) => Ok(Value::Time(time)),
+                    ("STR_TO_DATE", _) => Ok(Value::Null),
+                    ("TO_DATE", Ok((Some(date), _))) => Ok(Value::Date(date)),
+                    ("TO_TIMESTAMP", Ok((Some(date), time))) => Ok(Value::Timestamp(
+                        date.and_time(time.unwrap_or(chrono::NaiveTime::MIN)),
+                    )),
+                    (_, Ok(_)) => Err(YamlBaseError::Database {
+                        message: format!("{}: format '{}' does not contain a year", name, format),
+                    }),
+                    (_, Err(e)) => Err(YamlBaseError::Database {
+                        message: format!(
+                            "{}: cannot parse '{}' with format '{}': {}",
+                            name, text, format, e
+                        ),
+                    }),
+                }
+            }
+            "DAYNAME" | "MONTHNAME" | "WEEKDAY" | "DAYOFWEEK" => {
+                // Names are always English, whatever the locale. WEEKDAY counts from
+                // 0 = Monday, DAYOFWEEK (ODBC style) from 1 = Sunday.
+                check_arg_count(name, args, 1, 1)?;
+                let Some(date) = date_arg(name, args, 0)? else {
+                    return Ok(Value::Null);
+                };
+                Ok(match name {
+                    "DAYNAME" => Value::Text(date.format("%A").to_string()),
+                    "MONTHNAME" => Value::Text(date.format("%B").to_string()),
+                    "WEEKDAY" => Value::Integer(date.weekday().num_days_from_monday() as i64),
+                    _ => Value::Integer(date.weekday().number_from_sunday() as i64),
+                })
+            }
+            "GREATEST" | "LEAST" => {
+                // PostgreSQL ignores NULL arguments, MySQL returns NULL if any argument is NULL
+                if args.is_empty() {
+                    return Err(YamlBaseError::Database {
+                        message: format!("{} requires at least one argument", name),
+                    });
+                }
+                if matches!(self.dialect(), SqlDialect::MySQL) && args.contains(&Value::Null) {
+                    return Ok(Value::Null);
+                }
+                let wanted = if name == "GREATEST" {
+                    Ordering::Greater
+                } else {
+                    Ordering::Less
+                };
+                // Text arguments are read as the numbers or dates they are compared with
+                let typed = args
+                    .iter()
+                    .find(|v| !matches!(v, Value::Null | Value::Text(_)));
+                let args: Vec<Value> = args
+                    .iter()
+                    .map(|value| {
+                        typed
+                            .and_then(|typed| value.coerce_text_like(typed))
+                            .unwrap_or_else(|| value.clone())
+                    })
+                    .collect();
+                let mut best: Option<&Value> = None;
+                for value in args.iter().filter(|v| !matches!(v, Value::Null)) {
+                    best = match best {
+                        None => Some(value),
+                        Some(current) => match value.compare(current) {
+                            Some(ordering) if ordering == wanted => Some(value),
+                            Some(_) => Some(current),
+                            None => {
+                                return Err(YamlBaseError::Database {
+                                    message: format!(
+                                        "{} cannot compare {} with {}",
+                                        name, current, value
+                                    ),
+                                });
+                            }
+                        },
+                    };
+                }
+                Ok(best.cloned().unwrap_or(Value::Null))
+            }
+            _ => Err(YamlBaseError::NotImplemented(format!(
+                "Function '{}' is not implemented",
+                name
+            ))),
+        }
+    }
+
+    /// Evaluate the PostgreSQL regex operators `~`, `~*`, `!~` and `!~*`.
+    /// Returns None for any other operator.
+    pub(crate) fn evaluate_regex_operator(
+        left: &Value,
+        op: &BinaryOperator,
+        right: &Value,
+    ) -> Option<crate::Result<Value>> {
+        let (case_insensitive, negated) = match op {
+            BinaryOperator::PGRegexMatch => (false, false),
+            BinaryOperator::PGRegexIMatch => (true, false),
+            BinaryOperator::PGRegexNotMatch => (false, true),
+            BinaryOperator::PGRegexNotIMatch => (true, true),
+            _ => return None,
+        };
+        Some(Self::regex_matches(left, right, case_insensitive, negated))
+    }
+
+    /// Match a value against a regular expression, NULL if either side is NULL
+    pub(crate) fn regex_matches(
+        value: &Value,
+        pattern: &Value,
+        case_insensitive: bool,
+        negated: bool,
+    ) -> crate::Result<Value> {
+        let (text, pattern) = match (value, pattern) {
+            (Value::Null, _) | (_, Value::Null) => return Ok(Value::Null),
+            (value, Value::Text(pattern)) => (value.to_string(), pattern),
+            _ => {
+                return Err(YamlBaseError::Database {
+                    message: "Regular expression pattern must be a string".to_string(),
+                });
+            }
+        };
+        let flags = if case_insensitive { "i" } else { "" };
+        let matches = build_regex(pattern, flags)?.is_match(&text);
+        Ok(Value::Boolean(matches != negated))
+    }
+}
+
+fn check_arg_count(name: &str, args: &[Value], min: usize, max: usize) -> crate::Result<()> {
+    check_count(name, args.len(), min, max)
+}
+
+fn check_expr_count(name: &str, args: &[&Expr], min: usize, max: usize) -> crate::Result<()> {
+    check_count(name, args.len(), min, max)
+}
+
+fn check_count(name: &str, count: usize, min: usize, max: usize) -> crate::Result<()> {
+    if count < min || count > max {
+        let expected = if min == max {
+            format!("{}", min)
+        } else {
+            format!("{} to {}", min, max)
+        };
+        return Err(YamlBaseError::Database {
+            message: format!("{} requires {} arguments", name, expected),
+        });
+    }
+    Ok(())
+}
+
+fn check_string_length(name: &str, length: i64) -> crate::Result<()> {
+    if length > MAX_STRING_LENGTH {
+        return Err(YamlBaseError::Database {
+            message: format!(
+                "{} result exceeds the maximum string length of {} bytes",
+                name, MAX_STRING_LENGTH
+            ),
+        });
+    }
+    Ok(())
+}
+
+/// Truth value of a condition, treating NULL as false and numbers as true when non-zero
+/// like MySQL does
+fn is_true(name: &str, value: &Value) -> crate::Result<bool> {
+    match value {
+        Value::Null => Ok(false),
+        Value::Boolean(b) => Ok(*b),
+        Value::Integer(i) => Ok(*i != 0),
+        Value::Float(f) => Ok(*f != 0.0),
+        Value::Double(d) => Ok(*d != 0.0),
+        Value::Decimal(d) => Ok(!d.is_zero()),
+        other => Err(YamlBaseError::Database {
+            message: format!("{} condition must be a boolean, got {}", name, other),
+        }),
+    }
+}
+
+/// A string argument, None when it is NULL or not given. Other scalar values are
+/// converted to their text form like an implicit cast.
+fn text_arg(name: &str, args: &[Value], idx: usize) -> crate::Result<Option<String>> {
+    match args.get(idx) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::Text(s)) => Ok(Some(s.clone())),
+        Some(Value::Json(_)) => Err(YamlBaseError::Database {
+            message: format!("{} requires a string for argument {}", name, idx + 1),
+        }),
+        Some(other) => Ok(Some(other.to_string())),
+    }
+}
+
+/// An integer argument, None when it is NULL or not given
+fn integer_arg(name: &str, args: &[Value], idx: usize) -> crate::Result<Option<i64>> {
+    match args.get(idx) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::Integer(i)) => Ok(Some(*i)),
+        _ => Err(YamlBaseError::Database {
+            message: format!("{} requires an integer for argument {}", name, idx + 1),
+        }),
+    }
+}
+
+/// A date argument, None when it is NULL or text that is not a date. Timestamps give their
+/// date part, TIMESTAMPTZ values the date in their own offset.
+fn date_arg(name: &str, args: &[Value], idx: usize) -> crate::Result<Option<NaiveDate>> {
+    match args.get(idx) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::Date(d)) => Ok(Some(*d)),
+        Some(Value::Timestamp(ts)) => Ok(Some(ts.date())),
+        Some(Value::TimestampTz(ts)) => Ok(Some(ts.date_naive())),
+        Some(Value::Text(s)) => Ok(parse_naive_timestamp(s.trim()).map(|ts| ts.date())),
+        _ => Err(YamlBaseError::Database {
+            message: format!("{} requires a date for argument {}", name, idx + 1),
+        }),
+    }
+}
+
+/// A numeric argument as f64, None when it is NULL
+fn numeric_arg(name: &str, args: &[Value], idx: usize) -> crate::Result<Option<f64>> {
+    match args.get(idx) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::Integer(i)) => Ok(Some(*i as f64)),
+        Some(Value::Float(f)) => Ok(Some(*f as f64)),
+        Some(Value::Double(d)) => Ok(Some(*d)),
+        Some(Value::Decimal(d)) => Ok(d.to_f64()),
+        _ => Err(YamlBaseError::Database {
+            message: format!("{} requires a numeric value for argument {}", name, idx + 1),
+        }),
+    }
+}
+
+fn finite_double(value: f64) -> crate::Result<Value> {
+    if value.is_finite() {
+        Ok(Value::Double(value))
+    } else {
+        Err(YamlBaseError::Database {
+            message: "Numeric value out of range".to_string(),
+        })
+    }
+}
+
+fn truncate_f64(value: f64, digits: i32) -> f64 {
+    let factor = 10f64.powi(digits);
+    (value * factor).trunc() / factor
+}
+
+fn truncate_decimal(value: Decimal, digits: i32) -> Decimal {
+    if digits >= 0 {
+        return value.trunc_with_scale(digits as u32);
+    }
+    let factor = Decimal::from(10i64.pow(digits.unsigned_abs().min(18)));
+    (value / factor).trunc() * factor
+}
+
+/// Compile a pattern with MySQL/Oracle/PostgreSQL style match flags:
+/// `i` case-insensitive, `c` case-sensitive, `m` multi-line, `n`/`s` dot matches newline
+fn build_regex(pattern: &str, flags: &str) -> crate::Result<Regex> {
+    let mut builder = RegexBuilder::new(pattern);
+    for flag in flags.chars() {
+        match flag {
+            'i' => builder.case_insensitive(true),
+            'c' => builder.case_insensitive(false),
+            'm' => builder.multi_line(true),
+            'n' | 's' => builder.dot_matches_new_line(true),
+            _ => {
+                return Err(YamlBaseError::Database {
+                    message: format!("Invalid regular expression flag '{}'", flag),
+                });
+            }
+        };
+    }
+    builder.build().map_err(|e| YamlBaseError::Database {
+        message: format!("Invalid regular expression '{}': {}", pattern, e),
+    })
+}
+
+/// Byte offset of the 1-based character position `position`
+fn char_offset(text: &str, position: i64) -> usize {
+    text.char_indices()
+        .nth(position as usize - 1)
+        .map(|(offset, _)| offset)
+        .unwrap_or(text.len())
+}
+
+/// 1-based character position of the `occurrence`-th match of `search` at or after the
+/// 1-based character position `start`
+fn find_char_position(text: &str, search: &str, start: i64, occurrence: usize) -> Option<usize> {
+    let chars: Vec<char> = text.chars().collect();
+    let search: Vec<char> = search.chars().collect();
+    let start = start as usize - 1;
+    if start > chars.len() {
+        return None;
+    }
+    (start..=chars.len().checked_sub(search.len())?)
+        .filter(|&i| chars[i..].starts_with(&search))
+        .nth(occurrence - 1)
+        .map(|i| i + 1)
+}
+
+/// Like `find_char_position` but scanning leftwards from the negative position `start`,
+/// counted from the end of `text`
+fn find_char_position_backwards(
+    text: &str,
+    search: &str,
+    start: i64,
+    occurrence: usize,
+) -> Option<usize> {
+    let chars: Vec<char> = text.chars().collect();
+    let search: Vec<char> = search.chars().collect();
+    let start = chars.len().checked_sub(start.unsigned_abs() as usize)?;
+    let last = start.min(chars.len().checked_sub(search.len())?);
+    (0..=last)
+        .rev()
+        .filter(|&i| chars[i..].starts_with(&search))
+        .nth(occurrence - 1)
+        .map(|i| i + 1)
+}
+
+/// Translate `\1`-style back references into the `${1}` syntax of the regex crate,
+/// escaping literal dollar signs
+fn sql_replacement_to_rust(replacement: &str) -> String {
+    let mut result = String::with_capacity(replacement.len());
+    let mut chars = replacement.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.peek() {
+                Some(d) if d.is_ascii_digit() => {
+                    result.push_str(&format!("${{{}}}", d));
+                    chars.next();
+                }
+                Some('&') => {
+                    result.push_str("${0}");
+                    chars.next();
+                }
+                Some('\\') => {
+                    result.push('\\');
+                    chars.next();
+                }
+                _ => result.push('\\'),
+            },
+            '$' => result.push_str("$$"),
+            c => result.push(c),
+        }
+    }
+    result
+}
+
+fn replace_nth(re: &Regex, text: &str, occurrence: usize, replacement: &str) -> String {
+    match re.captures_iter(text).nth(occurrence - 1) {
+        Some(captures) => {
+            let m = captures.get(0).expect("group 0 always participates");
+            let mut expanded = String::new();
+            captures.expand(replacement, &mut expanded);
+            format!("{}{}{}", &text[..m.start()], expanded, &text[m.end()..])
+        }
+        None => text.to_string(),
+    }
+}
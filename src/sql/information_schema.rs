@@ -0,0 +1,427 @@
+// The information_schema views ORMs and drivers introspect a database with: `tables`,
+// `columns` and `key_column_usage`. They are built from the tables the connection sees when
+// a query reads them, in the shape of the connection's dialect: PostgreSQL puts the tables
+// of a database in schemas and names the views' columns in lower case, MySQL treats the
+// database as the schema and names them in upper case.
+use sqlparser::ast::{Query, visit_relations};
+use std::ops::ControlFlow;
+use std::sync::Arc;
+
+use crate::database::{Column, Database, Table, Value};
+use crate::sql::SqlDialect;
+use crate::sql::executor::QueryExecutor;
+use crate::sql::namespace::DEFAULT_SCHEMA;
+use crate::yaml::schema::SqlType;
+
+/// The schema the views are in
+pub const INFORMATION_SCHEMA: &str = "information_schema";
+
+impl QueryExecutor {
+    /// Whether the query reads an information_schema view, once its table names are resolved
+    pub(crate) fn reads_information_schema(query: &Query) -> bool {
+        visit_relations(query, |name| match name.0.as_slice() {
+            [key] if is_view_key(&key.value) => ControlFlow::Break(()),
+            _ => ControlFlow::Continue(()),
+        })
+        .is_break()
+    }
+
+    /// The database with the information_schema views describing its tables
+    pub(crate) fn with_information_schema(&self, db: &Database) -> Database {
+        let tables = self.visible_tables(db);
+        let views = [
+            self.tables_view(db, &tables),
+            self.columns_view(db, &tables),
+            self.key_column_usage_view(db, &tables),
+        ];
+        let mut database = db.clone();
+        for view in views {
+            database.tables.insert(
+                format!("{}.{}", INFORMATION_SCHEMA, view.name),
+                Arc::new(view),
+            );
+        }
+        database
+    }
+
+    /// The tables the views list: those the user may read, but for MySQL's temporary tables
+    pub(crate) fn visible_tables<'a>(&self, db: &'a Database) -> Vec<(&'a String, &'a Arc<Table>)> {
+        db.tables
+            .iter()
+            .filter(|(key, _)| self.grants().is_none_or(|grants| grants.may_read(key)))
+            .filter(|(key, _)| {
+                !matches!(self.dialect(), SqlDialect::MySQL) || !self.session().is_temporary(key)
+            })
+            .collect()
+    }
+
+    fn tables_view(&self, db: &Database, tables: &[(&String, &Arc<Table>)]) -> Table {
+        let mut view = self.view(
+            "tables",
+            &["table_catalog", "table_schema", "table_name", "table_type"],
+        );
+        for (key, _) in tables {
+            let (catalog, schema, name) = self.qualified_name(db, key);
+            let table_type = if db.materialized_views.contains_key(*key) {
+                "VIEW"
+            } else if self.session().is_temporary(key) {
+                "LOCAL TEMPORARY"
+            } else {
+                "BASE TABLE"
+            };
+            view.rows.push(vec![
+                Value::Text(catalog),
+                Value::Text(schema),
+                Value::Text(name),
+                Value::Text(table_type.to_string()),
+            ]);
+        }
+        view
+    }
+
+    fn columns_view(&self, db: &Database, tables: &[(&String, &Arc<Table>)]) -> Table {
+        let mysql = matches!(self.dialect(), SqlDialect::MySQL);
+        let mut names = vec![
+            "table_catalog",
+            "table_schema",
+            "table_name",
+            "column_name",
+            "ordinal_position",
+            "column_default",
+            "is_nullable",
+            "data_type",
+            "character_maximum_length",
+            "numeric_precision",
+            "numeric_scale",
+        ];
+        if mysql {
+            names.extend(["column_type", "column_key", "extra"]);
+        } else {
+            names.push("udt_name");
+        }
+        let mut view = self.view("columns", &names);
+
+        for (key, table) in tables {
+            let (catalog, schema, name) = self.qualified_name(db, key);
+            for (index, column) in table.columns.iter().enumerate() {
+                let auto_increment = table
+                    .auto_increment
+                    .is_some_and(|auto_increment| auto_increment.column == index);
+                let default = match &column.default {
+                    Some(default) => Value::Text(default.clone()),
+                    None if auto_increment && !mysql => {
+                        Value::Text(format!("nextval('{}_{}_seq'::regclass)", name, column.name))
+                    }
+                    None => Value::Null,
+                };
+                let (precision, scale) = numeric_precision(&column.sql_type, mysql);
+                let mut row = vec![
+                    Value::Text(catalog.clone()),
+                    Value::Text(schema.clone()),
+                    Value::Text(name.clone()),
+                    Value::Text(column.name.clone()),
+                    Value::Integer(index as i64 + 1),
+                    default,
+                    Value::Text(if column.nullable && !column.primary_key {
+                        "YES".to_string()
+                    } else {
+                        "NO".to_string()
+                    }),
+                    Value::Text(data_type(&column.sql_type, mysql).to_string()),
+                    character_maximum_length(&column.sql_type, mysql),
+                    precision,
+                    scale,
+                ];
+                if mysql {
+                    let column_key = if column.primary_key {
+                        "PRI"
+                    } else if column.unique {
+                        "UNI"
+                    } else if column.references.is_some() {
+                        "MUL"
+                    } else {
+                        ""
+                    };
+                    row.push(Value::Text(mysql_column_type(&column.sql_type)));
+                    row.push(Value::Text(column_key.to_string()));
+                    row.push(Value::Text(
+                        if auto_increment { "auto_increment" } else { "" }.to_string(),
+                    ));
+                } else {
+                    row.push(Value::Text(udt_name(&column.sql_type)));
+                }
+                view.rows.push(row);
+            }
+        }
+        view
+    }
+
+    fn key_column_usage_view(&self, db: &Database, tables: &[(&String, &Arc<Table>)]) -> Table {
+        let mysql = matches!(self.dialect(), SqlDialect::MySQL);
+        let mut names = vec![
+            "constraint_catalog",
+            "constraint_schema",
+            "constraint_name",
+            "table_catalog",
+            "table_schema",
+            "table_name",
+            "column_name",
+            "ordinal_position",
+            "position_in_unique_constraint",
+        ];
+        if mysql {
+            names.extend([
+                "referenced_table_schema",
+                "referenced_table_name",
+                "referenced_column_name",
+            ]);
+        }
+        let mut view = self.view("key_column_usage", &names);
+
+        for (key, table) in tables {
+            let (catalog, schema, name) = self.qualified_name(db, key);
+            let mut rows = Vec::new();
+            let primary_key = if mysql {
+                "PRIMARY".to_string()
+            } else {
+                format!("{}_pkey", name)
+            };
+            let primary_key_columns = table.columns.iter().filter(|column| column.primary_key);
+            for (position, column) in primary_key_columns.enumerate() {
+                rows.push((primary_key.clone(), column, position + 1, None));
+            }
+            for column in table.columns.iter().filter(|column| column.unique) {
+                let constraint = if mysql {
+                    column.name.clone()
+                } else {
+                    format!("{}_{}_key", name, column.name)
+                };
+                rows.push((constraint, column, 1, None));
+            }
+            let foreign_keys = table
+                .columns
+                .iter()
+                .filter_map(|column| Some((column, column.references.as_ref()?)));
+            for (number, (column, references)) in foreign_keys.enumerate() {
+                let constraint = if mysql {
+                    format!("{}_ibfk_{}", name, number + 1)
+                } else {
+                    format!("{}_{}_fkey", name, column.name)
+                };
+                rows.push((constraint, column, 1, Some(references)));
+            }
+
+            for (constraint, column, position, references) in rows {
+                let mut row = vec![
+                    Value::Text(catalog.clone()),
+                    Value::Text(schema.clone()),
+                    Value::Text(constraint),
+                    Value::Text(catalog.clone()),
+                    Value::Text(schema.clone()),
+                    Value::Text(name.clone()),
+                    Value::Text(column.name.clone()),
+                    Value::Integer(position as i64),
+                    match references {
+                        Some(_) => Value::Integer(1),
+                        None => Value::Null,
+                    },
+                ];
+                if mysql {
+                    match references {
+                        Some((referenced_table, referenced_column)) => {
+                            // References are kept as written, so name them as declared
+                            let (key, column) = match db
+                                .tables
+                                .iter()
+                                .find(|(key, _)| key.eq_ignore_ascii_case(referenced_table))
+                            {
+                                Some((key, table)) => (
+                                    key.as_str(),
+                                    table
+                                        .columns
+                                        .iter()
+                                        .find(|column| {
+                                            column.name.eq_ignore_ascii_case(referenced_column)
+                                        })
+                                        .map_or(referenced_column, |column| &column.name),
+                                ),
+                                None => (referenced_table.as_str(), referenced_column),
+                            };
+                            let (_, schema, name) = self.qualified_name(db, key);
+                            row.push(Value::Text(schema));
+                            row.push(Value::Text(name));
+                            row.push(Value::Text(column.clone()));
+                        }
+                        None => row.extend([Value::Null, Value::Null, Value::Null]),
+                    }
+                }
+                view.rows.push(row);
+            }
+        }
+        view
+    }
+
+    /// An empty view with text columns, but for the positions
+    fn view(&self, name: &str, columns: &[&str]) -> Table {
+        let mysql = matches!(self.dialect(), SqlDialect::MySQL);
+        let columns = columns
+            .iter()
+            .map(|column| Column {
+                name: if mysql {
+                    column.to_uppercase()
+                } else {
+                    column.to_string()
+                },
+                sql_type: if column.ends_with("position")
+                    || column.ends_with("_constraint")
+                    || column.starts_with("numeric_")
+                    || column.starts_with("character_")
+                {
+                    SqlType::BigInt
+                } else {
+                    SqlType::Text
+                },
+                primary_key: false,
+                nullable: true,
+                unique: false,
+                default: None,
+                references: None,
+                collation: None,
+            })
+            .collect();
+        let name = if mysql {
+            name.to_uppercase()
+        } else {
+            name.to_string()
+        };
+        Table::new(name, columns)
+    }
+
+    /// The catalog, schema and name a table is listed under
+    pub(crate) fn qualified_name(&self, db: &Database, key: &str) -> (String, String, String) {
+        let (schema, name) = match key.split_once('.') {
+            Some((schema, name)) => (schema.to_string(), name.to_string()),
+            None if matches!(self.dialect(), SqlDialect::MySQL) => {
+                (db.name.clone(), key.to_string())
+            }
+            None => (DEFAULT_SCHEMA.to_string(), key.to_string()),
+        };
+        let catalog = if matches!(self.dialect(), SqlDialect::MySQL) {
+            "def".to_string()
+        } else {
+            db.name.clone()
+        };
+        (catalog, schema, name)
+    }
+}
+
+fn is_view_key(key: &str) -> bool {
+    key.split_once('.').is_some_and(|(schema, view)| {
+        schema.eq_ignore_ascii_case(INFORMATION_SCHEMA)
+            && ["tables", "columns", "key_column_usage"]
+                .iter()
+                .any(|name| view.eq_ignore_ascii_case(name))
+    })
+}
+
+fn data_type(sql_type: &SqlType, mysql: bool) -> &'static str {
+    match (sql_type, mysql) {
+        (SqlType::Integer, false) => "integer",
+        (SqlType::Integer, true) => "int",
+        (SqlType::BigInt, _) => "bigint",
+        (SqlType::Char(_), false) => "character",
+        (SqlType::Char(_), true) => "char",
+        (SqlType::Varchar(_), false) => "character varying",
+        (SqlType::Varchar(_), true) => "varchar",
+        (SqlType::Text, _) => "text",
+        (SqlType::Timestamp, false) => "timestamp without time zone",
+        (SqlType::Timestamp, true) => "datetime",
+        (SqlType::TimestampTz, false) => "timestamp with time zone",
+        (SqlType::TimestampTz, true) => "timestamp",
+        (SqlType::Date, _) => "date",
+        (SqlType::Time, false) => "time without time zone",
+        (SqlType::Time, true) => "time",
+        (SqlType::Boolean, false) => "boolean",
+        (SqlType::Boolean, true) => "tinyint",
+        (SqlType::Decimal(_, _), false) => "numeric",
+        (SqlType::Decimal(_, _), true) => "decimal",
+        (SqlType::Float, false) => "real",
+        (SqlType::Float, true) => "float",
+        (SqlType::Double, false) => "double precision",
+        (SqlType::Double, true) => "double",
+        (SqlType::Uuid, false) => "uuid",
+        (SqlType::Uuid, true) => "char",
+        (SqlType::Json, false) => "jsonb",
+        (SqlType::Json, true) => "json",
+        (SqlType::Blob, false) => "bytea",
+        (SqlType::Blob, true) => "blob",
+        (SqlType::Array(_), false) => "ARRAY",
+        (SqlType::Array(_), true) => "json",
+    }
+}
+
+/// MySQL's COLUMN_TYPE, the data type with its length or precision
+pub(crate) fn mysql_column_type(sql_type: &SqlType) -> String {
+    match sql_type {
+        SqlType::Char(length) => format!("char({})", length),
+        SqlType::Varchar(length) => format!("varchar({})", length),
+        SqlType::Boolean => "tinyint(1)".to_string(),
+        SqlType::Decimal(precision, scale) => format!("decimal({},{})", precision, scale),
+        SqlType::Uuid => "char(36)".to_string(),
+        other => data_type(other, true).to_string(),
+    }
+}
+
+/// PostgreSQL's udt_name, the name of the type in pg_type
+fn udt_name(sql_type: &SqlType) -> String {
+    match sql_type {
+        SqlType::Integer => "int4".to_string(),
+        SqlType::BigInt => "int8".to_string(),
+        SqlType::Char(_) => "bpchar".to_string(),
+        SqlType::Varchar(_) => "varchar".to_string(),
+        SqlType::Text => "text".to_string(),
+        SqlType::Timestamp => "timestamp".to_string(),
+        SqlType::TimestampTz => "timestamptz".to_string(),
+        SqlType::Date => "date".to_string(),
+        SqlType::Time => "time".to_string(),
+        SqlType::Boolean => "bool".to_string(),
+        SqlType::Decimal(_, _) => "numeric".to_string(),
+        SqlType::Float => "float4".to_string(),
+        SqlType::Double => "float8".to_string(),
+        SqlType::Uuid => "uuid".to_string(),
+        SqlType::Json => "jsonb".to_string(),
+        SqlType::Blob => "bytea".to_string(),
+        SqlType::Array(element_type) => format!("_{}", udt_name(element_type)),
+    }
+}
+
+fn character_maximum_length(sql_type: &SqlType, mysql: bool) -> Value {
+    match sql_type {
+        SqlType::Char(length) | SqlType::Varchar(length) => Value::Integer(*length as i64),
+        SqlType::Uuid if mysql => Value::Integer(36),
+        SqlType::Text if mysql => Value::Integer(65535),
+        _ => Value::Null,
+    }
+}
+
+/// NUMERIC_PRECISION and NUMERIC_SCALE: PostgreSQL counts the precision of integers and
+/// floating point numbers in bits, MySQL in digits
+fn numeric_precision(sql_type: &SqlType, mysql: bool) -> (Value, Value) {
+    let (precision, scale) = match (sql_type, mysql) {
+        (SqlType::Integer, false) => (32, Some(0)),
+        (SqlType::Integer, true) => (10, Some(0)),
+        (SqlType::BigInt, false) => (64, Some(0)),
+        (SqlType::BigInt, true) => (19, Some(0)),
+        (SqlType::Boolean, true) => (3, Some(0)),
+        (SqlType::Decimal(precision, scale), _) => (*precision as i64, Some(*scale as i64)),
+        (SqlType::Float, false) => (24, None),
+        (SqlType::Float, true) => (12, None),
+        (SqlType::Double, false) => (53, None),
+        (SqlType::Double, true) => (22, None),
+        _ => return (Value::Null, Value::Null),
+    };
+    (
+        Value::Integer(precision),
+        scale.map_or(Value::Null, Value::Integer),
+    )
+}
@@ -0,0 +1,367 @@
+// INTERVAL literals and date/time arithmetic with them
+use crate::YamlBaseError;
+use crate::database::Value;
+use crate::sql::executor::QueryExecutor;
+use chrono::{Months, NaiveDate, NaiveDateTime, TimeDelta};
+use sqlparser::ast::{BinaryOperator, DateTimeField, Expr, Interval};
+use std::fmt;
+
+const MICROS_PER_SECOND: f64 = 1_000_000.0;
+const MICROS_PER_DAY: f64 = 86_400.0 * MICROS_PER_SECOND;
+
+/// An interval split into the same three parts PostgreSQL uses, since months and days
+/// do not have a fixed length and must be applied calendar-wise
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub(crate) struct SqlInterval {
+    pub months: i64,
+    pub days: i64,
+    pub micros: i64,
+}
+
+impl SqlInterval {
+    /// Parse the text of an interval literal such as `1 day`, `3 months 2 hours` or `01:30:00`.
+    /// A number without a unit takes `default_unit`, which comes from the qualifier in
+    /// `INTERVAL '3' MONTH` and falls back to seconds.
+    pub fn parse(text: &str, default_unit: Option<&DateTimeField>) -> crate::Result<Self> {
+        let invalid = || YamlBaseError::Database {
+            message: format!("Invalid interval value: '{}'", text),
+        };
+
+        let mut interval = SqlInterval::default();
+        let mut tokens = text.split_whitespace().peekable();
+        if tokens.peek().is_none() {
+            return Err(invalid());
+        }
+
+        while let Some(token) = tokens.next() {
+            if token.contains(':') {
+                interval.micros += Self::parse_clock(token).ok_or_else(invalid)?;
+                continue;
+            }
+
+            // Allow `1day` as well as `1 day`
+            let split = token
+                .find(|c: char| c.is_ascii_alphabetic())
+                .unwrap_or(token.len());
+            let (number, unit) = token.split_at(split);
+            let amount: f64 = number.parse().map_err(|_| invalid())?;
+
+            let unit = if !unit.is_empty() {
+                unit.to_string()
+            } else if let Some(next) =
+                tokens.next_if(|t| t.starts_with(|c: char| c.is_alphabetic()))
+            {
+                next.to_string()
+            } else {
+                match default_unit {
+                    Some(field) => field.to_string(),
+                    None => "second".to_string(),
+                }
+            };
+
+            interval.add_unit(amount, &unit).ok_or_else(invalid)?;
+        }
+
+        Ok(interval)
+    }
+
+    /// Parse `[-]HH:MM[:SS[.ffffff]]` into microseconds
+    fn parse_clock(token: &str) -> Option<i64> {
+        let (sign, clock) = match token.strip_prefix('-') {
+            Some(rest) => (-1.0, rest),
+            None => (1.0, token.strip_prefix('+').unwrap_or(token)),
+        };
+        let parts: Vec<&str> = clock.split(':').collect();
+        if parts.len() > 3 {
+            return None;
+        }
+        let hours: f64 = parts[0].parse().ok()?;
+        let minutes: f64 = parts[1].parse().ok()?;
+        let seconds: f64 = match parts.get(2) {
+            Some(s) => s.parse().ok()?,
+            None => 0.0,
+        };
+        let total = (hours * 3600.0 + minutes * 60.0 + seconds) * MICROS_PER_SECOND;
+        Some((sign * total).round() as i64)
+    }
+
+    fn add_unit(&mut self, amount: f64, unit: &str) -> Option<()> {
+        let unit = unit.to_lowercase();
+        let unit = match unit.as_str() {
+            "s" => "second",
+            "ms" => "millisecond",
+            "us" => "microsecond",
+            plural => plural.trim_end_matches('s'),
+        };
+        match unit {
+            "millennium" | "millennia" | "millenium" => self.add_months(amount * 12_000.0),
+            "century" | "centurie" => self.add_months(amount * 1200.0),
+            "decade" => self.add_months(amount * 120.0),
+            "year" | "yr" | "y" => self.add_months(amount * 12.0),
+            "quarter" => self.add_months(amount * 3.0),
+            "month" | "mon" => self.add_months(amount),
+            "week" | "w" => self.add_days(amount * 7.0),
+            "day" | "d" => self.add_days(amount),
+            "hour" | "hr" | "h" => self.add_micros(amount * 3600.0 * MICROS_PER_SECOND),
+            "minute" | "min" | "m" => self.add_micros(amount * 60.0 * MICROS_PER_SECOND),
+            "second" | "sec" => self.add_micros(amount * MICROS_PER_SECOND),
+            "millisecond" | "msec" => self.add_micros(amount * 1000.0),
+            "microsecond" | "usec" => self.add_micros(amount),
+            _ => return None,
+        }
+        Some(())
+    }
+
+    // Fractional months and days spill into the smaller units like PostgreSQL does,
+    // counting a month as 30 days
+    fn add_months(&mut self, months: f64) {
+        let whole = months.trunc();
+        self.months += whole as i64;
+        self.add_days((months - whole) * 30.0);
+    }
+
+    fn add_days(&mut self, days: f64) {
+        let whole = days.trunc();
+        self.days += whole as i64;
+        self.add_micros((days - whole) * MICROS_PER_DAY);
+    }
+
+    fn add_micros(&mut self, micros: f64) {
+        self.micros += micros.round() as i64;
+    }
+
+    pub fn negate(self) -> Self {
+        SqlInterval {
+            months: -self.months,
+            days: -self.days,
+            micros: -self.micros,
+        }
+    }
+
+    /// Add the interval to a date/time value. A date stays a date unless the interval
+    /// has a time part.
+    pub fn add_to(&self, value: &Value) -> crate::Result<Value> {
+        match value {
+            Value::Null => Ok(Value::Null),
+            Value::Date(date) if self.micros == 0 => Ok(Value::Date(self.add_to_date(*date)?)),
+            Value::Date(date) => Ok(Value::Timestamp(
+                self.add_to_timestamp(date.and_hms_opt(0, 0, 0).unwrap_or_default())?,
+            )),
+            Value::Timestamp(ts) => Ok(Value::Timestamp(self.add_to_timestamp(*ts)?)),
+            // Only the time part of the interval applies to a time of day, wrapping at midnight
+            Value::Time(time) => Ok(Value::Time(
+                time.overflowing_add_signed(TimeDelta::microseconds(self.micros))
+                    .0,
+            )),
+            Value::Text(s) => {
+                if let Ok(ts) = NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S") {
+                    self.add_to(&Value::Timestamp(ts))
+                } else if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+                    self.add_to(&Value::Date(date))
+                } else {
+                    Err(YamlBaseError::Database {
+                        message: format!("Cannot add an interval to '{}'", s),
+                    })
+                }
+            }
+            _ => Err(YamlBaseError::Database {
+                message: "Interval arithmetic requires a date, timestamp or time value".to_string(),
+            }),
+        }
+    }
+
+    fn add_to_date(&self, date: NaiveDate) -> crate::Result<NaiveDate> {
+        let date = if self.months >= 0 {
+            date.checked_add_months(Months::new(self.months as u32))
+        } else {
+            date.checked_sub_months(Months::new(self.months.unsigned_abs() as u32))
+        };
+        date.and_then(|d| d.checked_add_signed(TimeDelta::days(self.days)))
+            .ok_or_else(Self::overflow)
+    }
+
+    fn add_to_timestamp(&self, ts: NaiveDateTime) -> crate::Result<NaiveDateTime> {
+        let date = self.add_to_date(ts.date())?;
+        date.and_time(ts.time())
+            .checked_add_signed(TimeDelta::microseconds(self.micros))
+            .ok_or_else(Self::overflow)
+    }
+
+    fn overflow() -> YamlBaseError {
+        YamlBaseError::Database {
+            message: "Date arithmetic overflow".to_string(),
+        }
+    }
+}
+
+/// Formats like PostgreSQL's default interval output, e.g. `1 year 2 mons 3 days 04:05:06`
+impl fmt::Display for SqlInterval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let plural = |n: i64, unit: &str| {
+            if n.abs() == 1 {
+                format!("{} {}", n, unit)
+            } else {
+                format!("{} {}s", n, unit)
+            }
+        };
+
+        let mut parts = Vec::new();
+        let (years, months) = (self.months / 12, self.months % 12);
+        if years != 0 {
+            parts.push(plural(years, "year"));
+        }
+        if months != 0 {
+            parts.push(plural(months, "mon"));
+        }
+        if self.days != 0 {
+            parts.push(plural(self.days, "day"));
+        }
+        if self.micros != 0 || parts.is_empty() {
+            let sign = if self.micros < 0 { "-" } else { "" };
+            let micros = self.micros.unsigned_abs();
+            let seconds = micros / 1_000_000;
+            let mut clock = format!(
+                "{}{:02}:{:02}:{:02}",
+                sign,
+                seconds / 3600,
+                seconds % 3600 / 60,
+                seconds % 60
+            );
+            if !micros.is_multiple_of(1_000_000) {
+                clock.push_str(&format!(".{:06}", micros % 1_000_000));
+            }
+            parts.push(clock);
+        }
+        write!(f, "{}", parts.join(" "))
+    }
+}
+
+impl QueryExecutor {
+    /// Recognize `expr + INTERVAL ...`, `INTERVAL ... + expr` and `expr - INTERVAL ...`.
+    /// Returns the date/time operand, the interval and whether it is subtracted.
+    pub(crate) fn interval_operand<'a>(
+        left: &'a Expr,
+        op: &BinaryOperator,
+        right: &'a Expr,
+    ) -> Option<(&'a Expr, &'a Interval, bool)> {
+        match (left, op, right) {
+            (operand, BinaryOperator::Plus, Expr::Interval(interval)) => {
+                Some((operand, interval, false))
+            }
+            (Expr::Interval(interval), BinaryOperator::Plus, operand) => {
+                Some((operand, interval, false))
+            }
+            (operand, BinaryOperator::Minus, Expr::Interval(interval)) => {
+                Some((operand, interval, true))
+            }
+            _ => None,
+        }
+    }
+
+    /// Evaluate an INTERVAL literal. Both `INTERVAL '1 day'` and the qualified
+    /// `INTERVAL '3' MONTH` / `INTERVAL 3 MONTH` forms are accepted.
+    pub(crate) fn evaluate_interval(&self, interval: &Interval) -> crate::Result<SqlInterval> {
+        if interval.last_field.is_some() {
+            return Err(YamlBaseError::NotImplemented(
+                "INTERVAL ... TO ... qualifiers are not supported yet".to_string(),
+            ));
+        }
+        let text = match self.evaluate_constant_expr(&interval.value)? {
+            Value::Text(s) => s,
+            Value::Integer(n) => n.to_string(),
+            Value::Double(n) => n.to_string(),
+            other => {
+                return Err(YamlBaseError::Database {
+                    message: format!("Invalid interval value: {:?}", other),
+                });
+            }
+        };
+        SqlInterval::parse(&text, interval.leading_field.as_ref())
+    }
+
+    /// Add (or subtract) an INTERVAL literal to an already evaluated date/time value
+    pub(crate) fn apply_interval(
+        &self,
+        value: &Value,
+        interval: &Interval,
+        subtract: bool,
+    ) -> crate::Result<Value> {
+        let interval = self.evaluate_interval(interval)?;
+        let interval = if subtract {
+            interval.negate()
+        } else {
+            interval
+        };
+        interval.add_to(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_interval_text() {
+        assert_eq!(
+            SqlInterval::parse("1 day", None).unwrap(),
+            SqlInterval {
+                months: 0,
+                days: 1,
+                micros: 0
+            }
+        );
+        assert_eq!(
+            SqlInterval::parse("1 year 2 months 3 days 04:05:06", None).unwrap(),
+            SqlInterval {
+                months: 14,
+                days: 3,
+                micros: 14_706_000_000
+            }
+        );
+        assert_eq!(
+            SqlInterval::parse("3", Some(&DateTimeField::Month))
+                .unwrap()
+                .months,
+            3
+        );
+        assert_eq!(SqlInterval::parse("90", None).unwrap().micros, 90_000_000);
+        assert_eq!(SqlInterval::parse("2 weeks", None).unwrap().days, 14);
+        assert_eq!(SqlInterval::parse("-1 day", None).unwrap().days, -1);
+        assert!(SqlInterval::parse("1 fortnight", None).is_err());
+        assert!(SqlInterval::parse("", None).is_err());
+    }
+
+    #[test]
+    fn test_interval_display() {
+        let interval = SqlInterval::parse("1 year 2 mons 3 days 04:05:06", None).unwrap();
+        assert_eq!(interval.to_string(), "1 year 2 mons 3 days 04:05:06");
+        assert_eq!(SqlInterval::default().to_string(), "00:00:00");
+        assert_eq!(
+            SqlInterval::parse("-90 minutes", None).unwrap().to_string(),
+            "-01:30:00"
+        );
+    }
+
+    #[test]
+    fn test_add_interval_to_dates() {
+        let date = NaiveDate::from_ymd_opt(2024, 1, 31).unwrap();
+        let one_month = SqlInterval::parse("1 month", None).unwrap();
+        // Month arithmetic clamps to the end of the month
+        assert_eq!(
+            one_month.add_to(&Value::Date(date)).unwrap(),
+            Value::Date(NaiveDate::from_ymd_opt(2024, 2, 29).unwrap())
+        );
+        assert_eq!(
+            one_month.negate().add_to(&Value::Date(date)).unwrap(),
+            Value::Date(NaiveDate::from_ymd_opt(2023, 12, 31).unwrap())
+        );
+
+        let two_hours = SqlInterval::parse("2 hours", None).unwrap();
+        assert_eq!(
+            two_hours.add_to(&Value::Date(date)).unwrap(),
+            Value::Timestamp(date.and_hms_opt(2, 0, 0).unwrap())
+        );
+        assert_eq!(two_hours.add_to(&Value::Null).unwrap(), Value::Null);
+        assert!(two_hours.add_to(&Value::Integer(1)).is_err());
+    }
+}
@@ -0,0 +1,688 @@
+// Navigating JSON values with MySQL/SQL standard paths and the PostgreSQL operators,
+// converting SQL values to JSON, and turning JSON arrays into rows with JSON_TABLE and
+// jsonb_array_elements
+use crate::YamlBaseError;
+use crate::database::{Column, Table, Value};
+use crate::sql::executor::QueryExecutor;
+use crate::yaml::schema::{SqlType, YamlColumn};
+use serde_json::Value as JsonValue;
+use sqlparser::ast::{
+    BinaryOperator, Expr, FunctionArg, FunctionArgExpr, JoinConstraint, JoinOperator,
+    JsonTableColumn, JsonTableColumnErrorHandling, JsonTableNamedColumn, TableFactor,
+    Value as SqlValue,
+};
+use std::collections::HashMap;
+
+/// One step of a JSON path: an object key or an array index
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum JsonPathStep {
+    Key(String),
+    Index(i64),
+}
+
+impl JsonPathStep {
+    fn lookup<'a>(&self, json: &'a JsonValue) -> Option<&'a JsonValue> {
+        match (self, json) {
+            (JsonPathStep::Key(key), JsonValue::Object(map)) => map.get(key),
+            // PostgreSQL `#>` paths are text, so `{items,0}` indexes arrays by a numeric key
+            (JsonPathStep::Key(key), JsonValue::Array(_)) => key
+                .parse()
+                .ok()
+                .and_then(|idx| JsonPathStep::Index(idx).lookup(json)),
+            (JsonPathStep::Index(idx), JsonValue::Array(items)) => {
+                // Negative indexes count from the end like PostgreSQL's `-> -1`
+                let idx = if *idx < 0 {
+                    items.len().checked_sub(idx.unsigned_abs() as usize)?
+                } else {
+                    *idx as usize
+                };
+                items.get(idx)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Parse a MySQL/SQL standard path such as `$.items[0]."unit price"`
+pub(crate) fn parse_json_path(path: &str) -> crate::Result<Vec<JsonPathStep>> {
+    let invalid = |reason: &str| YamlBaseError::Database {
+        message: format!("Invalid JSON path '{}': {}", path, reason),
+    };
+
+    let mut chars = path.trim().chars().peekable();
+    if chars.next() != Some('$') {
+        return Err(invalid("must start with '$'"));
+    }
+
+    let mut steps = Vec::new();
+    while let Some(c) = chars.next() {
+        match c {
+            '.' => {
+                let mut key = String::new();
+                if chars.peek() == Some(&'"') {
+                    chars.next();
+                    loop {
+                        match chars.next() {
+                            Some('"') => break,
+                            Some('\\') => key.extend(chars.next()),
+                            Some(c) => key.push(c),
+                            None => return Err(invalid("unterminated quoted key")),
+                        }
+                    }
+                } else {
+                    while let Some(&c) = chars.peek() {
+                        if c == '.' || c == '[' {
+                            break;
+                        }
+                        key.push(c);
+                        chars.next();
+                    }
+                }
+                if key.is_empty() {
+                    return Err(invalid("empty key"));
+                }
+                if key == "*" {
+                    return Err(YamlBaseError::NotImplemented(
+                        "JSON path wildcards are not supported".to_string(),
+                    ));
+                }
+                steps.push(JsonPathStep::Key(key));
+            }
+            '[' => {
+                let mut index = String::new();
+                loop {
+                    match chars.next() {
+                        Some(']') => break,
+                        Some(c) => index.push(c),
+                        None => return Err(invalid("unterminated array index")),
+                    }
+                }
+                let index = index.trim();
+                if index == "*" {
+                    return Err(YamlBaseError::NotImplemented(
+                        "JSON path wildcards are not supported".to_string(),
+                    ));
+                }
+                let index = index
+                    .parse::<u32>()
+                    .map_err(|_| invalid("array index must be a non-negative integer"))?;
+                steps.push(JsonPathStep::Index(index as i64));
+            }
+            c if c.is_whitespace() => {}
+            _ => return Err(invalid("expected '.' or '['")),
+        }
+    }
+    Ok(steps)
+}
+
+/// Parse a PostgreSQL text array path such as `{items,0,name}`
+fn parse_pg_path(path: &str) -> crate::Result<Vec<JsonPathStep>> {
+    let inner = path
+        .trim()
+        .strip_prefix('{')
+        .and_then(|p| p.strip_suffix('}'))
+        .ok_or_else(|| YamlBaseError::Database {
+            message: format!("Malformed JSON path '{}': expected {{key,...}}", path),
+        })?;
+    if inner.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    Ok(inner
+        .split(',')
+        .map(|key| JsonPathStep::Key(key.trim().trim_matches('"').to_string()))
+        .collect())
+}
+
+pub(crate) fn lookup_json<'a>(
+    json: &'a JsonValue,
+    steps: &[JsonPathStep],
+) -> Option<&'a JsonValue> {
+    steps.iter().try_fold(json, |json, step| step.lookup(json))
+}
+
+/// The JSON document held by a value. JSON stored as text is parsed; None for NULL.
+pub(crate) fn json_operand(name: &str, value: &Value) -> crate::Result<Option<JsonValue>> {
+    match value {
+        Value::Null => Ok(None),
+        Value::Json(json) => Ok(Some(json.clone())),
+        Value::Text(text) => {
+            serde_json::from_str(text)
+                .map(Some)
+                .map_err(|e| YamlBaseError::Database {
+                    message: format!("{}: invalid JSON text: {}", name, e),
+                })
+        }
+        other => Err(YamlBaseError::Database {
+            message: format!("{} requires a JSON argument, got {}", name, other),
+        }),
+    }
+}
+
+/// Convert a JSON scalar to the matching SQL value; objects and arrays stay JSON
+pub(crate) fn json_to_value(json: &JsonValue) -> Value {
+    match json {
+        JsonValue::Null => Value::Null,
+        JsonValue::Bool(b) => Value::Boolean(*b),
+        JsonValue::Number(n) => match n.as_i64() {
+            Some(i) => Value::Integer(i),
+            None => n.as_f64().map(Value::Double).unwrap_or(Value::Null),
+        },
+        JsonValue::String(s) => Value::Text(s.clone()),
+        JsonValue::Array(_) | JsonValue::Object(_) => Value::Json(json.clone()),
+    }
+}
+
+/// The text form returned by `->>` and `#>>`: strings without quotes, JSON null as NULL
+fn json_to_text(json: &JsonValue) -> Value {
+    match json {
+        JsonValue::Null => Value::Null,
+        JsonValue::String(s) => Value::Text(s.clone()),
+        other => Value::Text(other.to_string()),
+    }
+}
+
+/// Convert a SQL value to JSON for JSON_OBJECT, JSON_ARRAY and JSON_AGG. Temporal values
+/// and UUIDs become strings; non-finite floats become null.
+pub(crate) fn value_to_json(value: &Value) -> JsonValue {
+    match value {
+        Value::Null => JsonValue::Null,
+        Value::Integer(i) => JsonValue::from(*i),
+        Value::Float(f) => float_to_json(*f as f64),
+        Value::Double(d) => float_to_json(*d),
+        Value::Decimal(d) => d
+            .normalize()
+            .to_string()
+            .parse::<serde_json::Number>()
+            .map(JsonValue::Number)
+            .unwrap_or(JsonValue::Null),
+        Value::Text(s) => JsonValue::String(s.clone()),
+        Value::Boolean(b) => JsonValue::Bool(*b),
+        Value::Timestamp(_)
+        | Value::TimestampTz(_)
+        | Value::Date(_)
+        | Value::Time(_)
+        | Value::Uuid(_)
+        | Value::Bytes(_) => JsonValue::String(value.to_string()),
+        Value::Json(json) => json.clone(),
+        Value::Array(items) => JsonValue::Array(items.iter().map(value_to_json).collect()),
+    }
+}
+
+fn float_to_json(f: f64) -> JsonValue {
+    serde_json::Number::from_f64(f)
+        .map(JsonValue::Number)
+        .unwrap_or(JsonValue::Null)
+}
+
+impl QueryExecutor {
+    /// Evaluate the PostgreSQL JSON operators `->`, `->>`, `#>` and `#>>`.
+    /// Returns None for any other operator.
+    pub(crate) fn evaluate_json_operator(
+        left: &Value,
+        op: &BinaryOperator,
+        right: &Value,
+    ) -> Option<crate::Result<Value>> {
+        let (as_text, path_operator) = match op {
+            BinaryOperator::Arrow => (false, false),
+            BinaryOperator::LongArrow => (true, false),
+            BinaryOperator::HashArrow => (false, true),
+            BinaryOperator::HashLongArrow => (true, true),
+            _ => return None,
+        };
+        Some(Self::json_operator(left, right, as_text, path_operator))
+    }
+
+    fn json_operator(
+        left: &Value,
+        right: &Value,
+        as_text: bool,
+        path_operator: bool,
+    ) -> crate::Result<Value> {
+        let Some(json) = json_operand("JSON operator", left)? else {
+            return Ok(Value::Null);
+        };
+        let steps = match (right, path_operator) {
+            (Value::Null, _) => return Ok(Value::Null),
+            (Value::Text(path), true) => parse_pg_path(path)?,
+            (Value::Text(key), false) => vec![JsonPathStep::Key(key.clone())],
+            (Value::Integer(idx), false) => vec![JsonPathStep::Index(*idx)],
+            (other, _) => {
+                return Err(YamlBaseError::Database {
+                    message: format!("Invalid JSON key or path: {}", other),
+                });
+            }
+        };
+        Ok(match lookup_json(&json, &steps) {
+            None => Value::Null,
+            Some(found) if as_text => json_to_text(found),
+            Some(found) => Value::Json(found.clone()),
+        })
+    }
+}
+
+/// A JSON set-returning function in FROM. It is evaluated once for every row of the
+/// tables to its left, which may therefore be referenced in its arguments.
+pub(crate) enum JsonTableFunction<'a> {
+    /// PostgreSQL `jsonb_array_elements(json)` and the `_text` variants
+    ArrayElements {
+        arg: &'a Expr,
+        as_text: bool,
+        alias: String,
+        column: String,
+    },
+    /// MySQL `JSON_TABLE(json, '$.path[*]' COLUMNS (...))`
+    JsonTable {
+        json_expr: &'a Expr,
+        path: String,
+        columns: &'a [JsonTableColumn],
+        alias: String,
+    },
+}
+
+impl QueryExecutor {
+    pub(crate) fn is_json_table_function(relation: &TableFactor) -> bool {
+        matches!(
+            relation,
+            TableFactor::Table { args: Some(_), .. }
+                | TableFactor::Function { .. }
+                | TableFactor::JsonTable { .. }
+        )
+    }
+
+    pub(crate) fn json_table_function(
+        relation: &TableFactor,
+    ) -> crate::Result<JsonTableFunction<'_>> {
+        let (name, args, alias) = match relation {
+            TableFactor::Table {
+                name,
+                args: Some(args),
+                alias,
+                ..
+            } => (name, &args.args, alias),
+            TableFactor::Function {
+                name, args, alias, ..
+            } => (name, args, alias),
+            TableFactor::JsonTable {
+                json_expr,
+                json_path,
+                columns,
+                alias,
+            } => {
+                let path = match json_path {
+                    SqlValue::SingleQuotedString(path) | SqlValue::DoubleQuotedString(path) => {
+                        path.clone()
+                    }
+                    other => {
+                        return Err(YamlBaseError::Database {
+                            message: format!("JSON_TABLE path must be a string, got {}", other),
+                        });
+                    }
+                };
+                return Ok(JsonTableFunction::JsonTable {
+                    json_expr,
+                    path,
+                    columns,
+                    alias: alias
+                        .as_ref()
+                        .map(|a| a.name.value.clone())
+                        .unwrap_or_else(|| "json_table".to_string()),
+                });
+            }
+            _ => {
+                return Err(YamlBaseError::NotImplemented(
+                    "This type of table reference is not yet supported".to_string(),
+                ));
+            }
+        };
+
+        let function_name = name.to_string().to_lowercase();
+        let as_text = match function_name.as_str() {
+            "jsonb_array_elements" | "json_array_elements" => false,
+            "jsonb_array_elements_text" | "json_array_elements_text" => true,
+            _ => {
+                return Err(YamlBaseError::NotImplemented(format!(
+                    "Table function '{}' is not supported",
+                    name
+                )));
+            }
+        };
+        let arg = match args.as_slice() {
+            [FunctionArg::Unnamed(FunctionArgExpr::Expr(arg))] => arg,
+            _ => {
+                return Err(YamlBaseError::Database {
+                    message: format!("{} requires exactly 1 argument", function_name),
+                });
+            }
+        };
+        // `AS e(item)` renames the `value` column
+        let column = alias
+            .as_ref()
+            .and_then(|a| a.columns.first())
+            .map(|c| c.value.clone())
+            .unwrap_or_else(|| "value".to_string());
+        Ok(JsonTableFunction::ArrayElements {
+            arg,
+            as_text,
+            alias: alias
+                .as_ref()
+                .map(|a| a.name.value.clone())
+                .unwrap_or(function_name),
+            column,
+        })
+    }
+
+    /// The alias and an empty table describing the columns a JSON table function produces
+    pub(crate) fn json_table_function_schema(
+        function: &JsonTableFunction,
+    ) -> crate::Result<(String, Table)> {
+        let column = |name: &str, sql_type: SqlType| Column {
+            name: name.to_string(),
+            sql_type,
+            primary_key: false,
+            nullable: true,
+            unique: false,
+            default: None,
+            references: None,
+            collation: None,
+        };
+
+        Ok(match function {
+            JsonTableFunction::ArrayElements {
+                as_text,
+                alias,
+                column: name,
+                ..
+            } => {
+                let sql_type = if *as_text {
+                    SqlType::Text
+                } else {
+                    SqlType::Json
+                };
+                let table = Table::new(alias.clone(), vec![column(name, sql_type)]);
+                (alias.clone(), table)
+            }
+            JsonTableFunction::JsonTable { columns, alias, .. } => {
+                let mut table_columns = Vec::with_capacity(columns.len());
+                for def in columns.iter() {
+                    table_columns.push(match def {
+                        JsonTableColumn::Named(named) => {
+                            let sql_type = YamlColumn::parse(
+                                named.name.value.clone(),
+                                &named.r#type.to_string(),
+                            )
+                            .and_then(|c| c.get_base_type())
+                            .unwrap_or(SqlType::Text);
+                            column(&named.name.value, sql_type)
+                        }
+                        JsonTableColumn::ForOrdinality(name) => {
+                            column(&name.value, SqlType::Integer)
+                        }
+                        JsonTableColumn::Nested(_) => {
+                            return Err(YamlBaseError::NotImplemented(
+                                "NESTED PATH columns in JSON_TABLE are not supported".to_string(),
+                            ));
+                        }
+                    });
+                }
+                (alias.clone(), Table::new(alias.clone(), table_columns))
+            }
+        })
+    }
+
+    /// Join every left row with the rows the function produces for it. `tables` ends with
+    /// the function's own schema table.
+    pub(crate) fn join_json_table_function(
+        &self,
+        left_rows: Vec<Vec<Value>>,
+        function: &JsonTableFunction,
+        join_operator: &JoinOperator,
+        tables: &[(String, &Table)],
+        table_aliases: &HashMap<String, String>,
+    ) -> crate::Result<Vec<Vec<Value>>> {
+        let (constraint, keep_unmatched) = match join_operator {
+            JoinOperator::CrossJoin => (None, false),
+            JoinOperator::Inner(constraint) => (Some(constraint), false),
+            JoinOperator::LeftOuter(constraint) => (Some(constraint), true),
+            _ => {
+                return Err(YamlBaseError::NotImplemented(
+                    "Only CROSS, INNER and LEFT joins are supported with JSON table functions"
+                        .to_string(),
+                ));
+            }
+        };
+        let Some(((_, function_table), left_tables)) = tables.split_last() else {
+            return Ok(Vec::new());
+        };
+
+        let mut result = Vec::new();
+        for left_row in left_rows {
+            let mut matched = false;
+            for right_row in
+                self.json_table_function_rows(function, &left_row, left_tables, table_aliases)?
+            {
+                let mut combined = left_row.clone();
+                combined.extend(right_row);
+                let matches = match constraint {
+                    None | Some(JoinConstraint::None) => true,
+                    Some(JoinConstraint::On(expr)) => {
+                        self.evaluate_join_condition(expr, &combined, tables, table_aliases)?
+                    }
+                    Some(_) => {
+                        return Err(YamlBaseError::NotImplemented(
+                            "Only ON conditions are supported with JSON table functions"
+                                .to_string(),
+                        ));
+                    }
+                };
+                if matches {
+                    result.push(combined);
+                    matched = true;
+                }
+            }
+            if keep_unmatched && !matched {
+                let mut combined = left_row;
+                combined.extend(std::iter::repeat_n(
+                    Value::Null,
+                    function_table.columns.len(),
+                ));
+                result.push(combined);
+            }
+        }
+        Ok(result)
+    }
+
+    fn json_table_function_rows(
+        &self,
+        function: &JsonTableFunction,
+        left_row: &[Value],
+        left_tables: &[(String, &Table)],
+        table_aliases: &HashMap<String, String>,
+    ) -> crate::Result<Vec<Vec<Value>>> {
+        match function {
+            JsonTableFunction::ArrayElements { arg, as_text, .. } => {
+                let value = self.get_join_expr_value(arg, left_row, left_tables, table_aliases)?;
+                let Some(json) = json_operand("jsonb_array_elements", &value)? else {
+                    return Ok(Vec::new());
+                };
+                let JsonValue::Array(items) = json else {
+                    return Err(YamlBaseError::Database {
+                        message: "Cannot extract elements from a JSON value that is not an array"
+                            .to_string(),
+                    });
+                };
+                Ok(items
+                    .iter()
+                    .map(|item| {
+                        vec![if *as_text {
+                            json_to_text(item)
+                        } else {
+                            Value::Json(item.clone())
+                        }]
+                    })
+                    .collect())
+            }
+            JsonTableFunction::JsonTable {
+                json_expr,
+                path,
+                columns,
+                ..
+            } => {
+                let value =
+                    self.get_join_expr_value(json_expr, left_row, left_tables, table_aliases)?;
+                let Some(json) = json_operand("JSON_TABLE", &value)? else {
+                    return Ok(Vec::new());
+                };
+                // A trailing `[*]` iterates over the array elements, otherwise the path
+                // selects a single row
+                let elements: Vec<&JsonValue> = match path.trim().strip_suffix("[*]") {
+                    Some(array_path) => match lookup_json(&json, &parse_json_path(array_path)?) {
+                        Some(JsonValue::Array(items)) => items.iter().collect(),
+                        _ => Vec::new(),
+                    },
+                    None => lookup_json(&json, &parse_json_path(path)?)
+                        .into_iter()
+                        .collect(),
+                };
+
+                let mut rows = Vec::with_capacity(elements.len());
+                for (ordinal, element) in elements.into_iter().enumerate() {
+                    let mut row = Vec::with_capacity(columns.len());
+                    for def in columns.iter() {
+                        row.push(match def {
+                            JsonTableColumn::Named(named) => {
+                                self.json_table_column_value(named, element)?
+                            }
+                            JsonTableColumn::ForOrdinality(_) => Value::Integer(ordinal as i64 + 1),
+                            JsonTableColumn::Nested(_) => {
+                                return Err(YamlBaseError::NotImplemented(
+                                    "NESTED PATH columns in JSON_TABLE are not supported"
+                                        .to_string(),
+                                ));
+                            }
+                        });
+                    }
+                    rows.push(row);
+                }
+                Ok(rows)
+            }
+        }
+    }
+
+    fn json_table_column_value(
+        &self,
+        column: &JsonTableNamedColumn,
+        element: &JsonValue,
+    ) -> crate::Result<Value> {
+        let path = match &column.path {
+            SqlValue::SingleQuotedString(path) | SqlValue::DoubleQuotedString(path) => path,
+            other => {
+                return Err(YamlBaseError::Database {
+                    message: format!("JSON_TABLE column path must be a string, got {}", other),
+                });
+            }
+        };
+        let found = lookup_json(element, &parse_json_path(path)?);
+        if column.exists {
+            return Ok(Value::Integer(found.is_some() as i64));
+        }
+
+        let value = match found.map(json_to_value) {
+            Some(Value::Null) | None => match &column.on_empty {
+                None | Some(JsonTableColumnErrorHandling::Null) => return Ok(Value::Null),
+                Some(JsonTableColumnErrorHandling::Default(default)) => {
+                    self.sql_value_to_db_value(default)?
+                }
+                Some(JsonTableColumnErrorHandling::Error) => {
+                    return Err(YamlBaseError::Database {
+                        message: format!(
+                            "Missing value for JSON_TABLE column '{}'",
+                            column.name.value
+                        ),
+                    });
+                }
+            },
+            Some(value) => value,
+        };
+        match self.cast_value(value, &column.r#type) {
+            Ok(value) => Ok(value),
+            Err(e) => match &column.on_error {
+                Some(JsonTableColumnErrorHandling::Null) => Ok(Value::Null),
+                Some(JsonTableColumnErrorHandling::Default(default)) => {
+                    self.sql_value_to_db_value(default)
+                }
+                _ => Err(e),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_json_path() {
+        assert_eq!(
+            parse_json_path("$.items[2].\"unit price\"").unwrap(),
+            vec![
+                JsonPathStep::Key("items".to_string()),
+                JsonPathStep::Index(2),
+                JsonPathStep::Key("unit price".to_string()),
+            ]
+        );
+        assert_eq!(parse_json_path("$").unwrap(), vec![]);
+        assert!(parse_json_path("items").is_err());
+        assert!(parse_json_path("$.items[x]").is_err());
+    }
+
+    #[test]
+    fn test_json_operators() {
+        let doc = Value::Json(json!({"a": {"b": [10, "x", null]}, "s": "text"}));
+        let arrow = |op, right: Value| {
+            QueryExecutor::evaluate_json_operator(&doc, &op, &right)
+                .unwrap()
+                .unwrap()
+        };
+
+        assert_eq!(
+            arrow(BinaryOperator::Arrow, Value::Text("a".to_string())),
+            Value::Json(json!({"b": [10, "x", null]}))
+        );
+        assert_eq!(
+            arrow(BinaryOperator::LongArrow, Value::Text("s".to_string())),
+            Value::Text("text".to_string())
+        );
+        assert_eq!(
+            arrow(
+                BinaryOperator::HashLongArrow,
+                Value::Text("{a,b,0}".to_string())
+            ),
+            Value::Text("10".to_string())
+        );
+        assert_eq!(
+            arrow(
+                BinaryOperator::HashLongArrow,
+                Value::Text("{a,b,2}".to_string())
+            ),
+            Value::Null
+        );
+        assert_eq!(
+            arrow(BinaryOperator::Arrow, Value::Text("missing".to_string())),
+            Value::Null
+        );
+    }
+
+    #[test]
+    fn test_value_to_json() {
+        let date = chrono::NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        let decimal = rust_decimal::Decimal::new(1950, 2);
+
+        assert_eq!(value_to_json(&Value::Integer(7)), json!(7));
+        assert_eq!(value_to_json(&Value::Decimal(decimal)), json!(19.5));
+        assert_eq!(value_to_json(&Value::Double(f64::NAN)), json!(null));
+        assert_eq!(value_to_json(&Value::Date(date)), json!("2024-03-01"));
+        assert_eq!(value_to_json(&Value::Text("{}".to_string())), json!("{}"));
+        assert_eq!(value_to_json(&Value::Json(json!([1]))), json!([1]));
+    }
+}
@@ -0,0 +1,85 @@
+// Materialized views declared in the YAML file. A view is stored as a table holding the
+// result of its query, computed when the file is loaded or reloaded and again on
+// REFRESH MATERIALIZED VIEW; clients cannot write to it.
+use sqlparser::ast::Statement;
+use std::sync::Arc;
+
+use crate::YamlBaseError;
+use crate::database::{Database, Storage, Table};
+use crate::sql::executor::QueryExecutor;
+use crate::sql::parse_sql;
+
+/// Compute the materialized views of the database in the order they are declared, so a
+/// view may query the views declared before it
+pub async fn compute_materialized_views(database: &mut Database) -> crate::Result<()> {
+    let views: Vec<(String, String)> = database
+        .materialized_views
+        .iter()
+        .map(|(name, query)| (name.clone(), query.clone()))
+        .collect();
+
+    for (name, query) in views {
+        if database.get_table(&name).is_some() {
+            return Err(YamlBaseError::Database {
+                message: format!("Materialized view '{}' has the same name as a table", name),
+            });
+        }
+        let table = compute_view(database, &name, &query).await?;
+        database.tables.insert(name, Arc::new(table));
+    }
+    Ok(())
+}
+
+impl QueryExecutor {
+    /// Recompute a materialized view from the shared data, for every connection
+    pub async fn refresh_materialized_view(&self, name: &str) -> crate::Result<()> {
+        let db_arc = self.storage().database();
+        let (view_name, query, snapshot) = {
+            let db = db_arc.read().await;
+            let view_name = db.materialized_view_name(name).cloned().ok_or_else(|| {
+                YamlBaseError::Database {
+                    message: format!("Materialized view '{}' does not exist", name),
+                }
+            })?;
+            if let Some(grants) = self.grants()
+                && !grants.may_write(&view_name)
+            {
+                return Err(YamlBaseError::PermissionDenied(format!(
+                    "materialized view {}",
+                    view_name
+                )));
+            }
+            let query = db.materialized_views[&view_name].clone();
+            (view_name, query, db.clone())
+        };
+
+        let table = compute_view(&snapshot, &view_name, &query).await?;
+        db_arc
+            .write()
+            .await
+            .tables
+            .insert(view_name, Arc::new(table));
+        self.storage().rebuild_indexes().await;
+        Ok(())
+    }
+}
+
+async fn compute_view(database: &Database, name: &str, query: &str) -> crate::Result<Table> {
+    let statements = parse_sql(query)?;
+    let [Statement::Query(query)] = statements.as_slice() else {
+        return Err(YamlBaseError::Database {
+            message: format!("Materialized view '{}' must be a single SELECT query", name),
+        });
+    };
+
+    let storage = Arc::new(Storage::new(database.clone()));
+    storage.rebuild_indexes().await;
+    let executor = QueryExecutor::new(storage).await?;
+    let result = executor
+        .execute_query(query)
+        .await
+        .map_err(|e| YamlBaseError::Database {
+            message: format!("Materialized view '{}' failed: {}", name, e),
+        })?;
+    executor.table_from_result(name.to_string(), result)
+}
@@ -1,8 +1,40 @@
+mod check;
+mod computed;
+mod date_format;
+mod ddl;
+mod dml;
 pub mod executor;
 mod executor_comprehensive_tests;
+mod functions;
+mod information_schema;
+mod interval;
+mod json;
+mod materialized_view;
+mod namespace;
+mod notify;
 pub mod parser;
+mod permissions;
 mod recursive_cte;
+mod sequence;
+mod settings;
+mod show;
+mod system_variables;
 mod tests_string_functions;
+mod transaction;
+mod type_inference;
+mod upsert;
 
-pub use executor::QueryExecutor;
-pub use parser::{SqlDialect, parse_sql, parse_sql_with_dialect};
+pub use check::{check_loaded_rows, parse_check};
+pub use computed::{compute_loaded_rows, parse_computed};
+pub use executor::{QueryExecutor, WriteMode};
+pub(crate) use json::value_to_json;
+pub use materialized_view::compute_materialized_views;
+pub use namespace::{DEFAULT_SCHEMA, SEARCH_PATH_SETTING};
+pub use parser::{
+    SqlDialect, is_reload_database, parse_refresh_materialized_view, parse_reset,
+    parse_set_system_variables, parse_sql, parse_sql_with_dialect, parse_unlisten,
+    split_statements,
+};
+pub use recursive_cte::RecursionLimits;
+pub use show::shown_database;
+pub use system_variables::MYSQL_VERSION;
@@ -0,0 +1,184 @@
+// Schemas. A table defined in YAML as `sales.orders` is the table `orders` of schema
+// `sales`, and is kept in the database under that full name; the other tables are in the
+// `public` schema. Before a statement runs, its table references are rewritten to the names
+// the database keeps tables under: `sales.orders` as written, an unqualified `orders`
+// through the session's search_path, and `public.users` or, as MySQL writes it,
+// `<database>.users` to `users`.
+use sqlparser::ast::{
+    Expr, Ident, ObjectName, Statement, TableAlias, TableFactor, VisitMut, VisitorMut,
+    visit_relations,
+};
+use std::ops::ControlFlow;
+
+use crate::YamlBaseError;
+use crate::database::{Database, Value};
+use crate::sql::executor::QueryExecutor;
+
+/// The schema of the tables whose names have no schema
+pub const DEFAULT_SCHEMA: &str = "public";
+
+/// Session setting name for the schemas unqualified table names are looked up in
+pub const SEARCH_PATH_SETTING: &str = "search_path";
+
+/// The default search_path
+pub(crate) fn default_search_path() -> Vec<String> {
+    vec![DEFAULT_SCHEMA.to_string()]
+}
+
+impl Database {
+    /// Whether any table is in this schema
+    pub fn has_schema(&self, schema: &str) -> bool {
+        schema.eq_ignore_ascii_case(DEFAULT_SCHEMA)
+            || self.tables.keys().any(|name| {
+                name.split_once('.')
+                    .is_some_and(|(prefix, _)| prefix.eq_ignore_ascii_case(schema))
+            })
+    }
+}
+
+impl QueryExecutor {
+    /// Apply `SET search_path TO a, b`, or `= 'a, b'`
+    pub(crate) fn execute_set_search_path(&self, value: &[Expr]) -> crate::Result<()> {
+        let mut schemas = Vec::new();
+        for expr in value {
+            let text = match expr {
+                Expr::Identifier(ident) => ident.value.clone(),
+                _ => match self.evaluate_constant_expr(expr)? {
+                    Value::Text(text) => text,
+                    other => {
+                        return Err(YamlBaseError::Database {
+                            message: format!("Invalid value for search_path: {}", other),
+                        });
+                    }
+                },
+            };
+            schemas.extend(
+                text.split(',')
+                    .map(|schema| schema.trim().trim_matches('"').to_string())
+                    .filter(|schema| !schema.is_empty()),
+            );
+        }
+        self.set_search_path(schemas);
+        Ok(())
+    }
+
+    /// The statement with its table references named as the database keeps the tables,
+    /// or `None` when they already are
+    pub(crate) async fn resolve_table_names(&self, statement: &Statement) -> Option<Statement> {
+        let search_path = self.search_path();
+        let qualified = visit_relations(statement, |name| {
+            if name.0.len() > 1 {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        })
+        .is_break();
+        if !qualified && search_path == default_search_path() {
+            return None;
+        }
+
+        let db_arc = self.storage().database();
+        let shared = db_arc.read().await;
+        let mut resolver = Resolver {
+            executor: self,
+            shared: &shared,
+            search_path,
+        };
+        let mut statement = statement.clone();
+        let _ = statement.visit(&mut resolver);
+        Some(statement)
+    }
+
+    /// The name the database keeps the table a possibly qualified name refers to under
+    pub(crate) fn table_key(&self, shared: &Database, name: &ObjectName) -> String {
+        Resolver {
+            executor: self,
+            shared,
+            search_path: self.search_path(),
+        }
+        .resolve(&name.0)
+    }
+}
+
+struct Resolver<'a> {
+    executor: &'a QueryExecutor,
+    shared: &'a Database,
+    search_path: Vec<String>,
+}
+
+impl Resolver<'_> {
+    fn exists(&self, key: &str) -> bool {
+        self.executor.session().table(self.shared, key).is_some()
+    }
+
+    fn resolve(&self, parts: &[Ident]) -> String {
+        let mut parts: Vec<&str> = parts.iter().map(|ident| ident.value.as_str()).collect();
+        // A database name in front, as in MySQL's `db.table` and `db.schema.table`
+        if parts.len() > 1 && parts[0].eq_ignore_ascii_case(&self.shared.name) {
+            let rest = parts[1..].join(".");
+            if parts.len() > 2 || !self.exists(&format!("{}.{}", parts[0], rest)) {
+                parts.remove(0);
+            }
+        }
+        match parts.as_slice() {
+            [table] => {
+                for schema in &self.search_path {
+                    if schema.eq_ignore_ascii_case(DEFAULT_SCHEMA) {
+                        if self.exists(table) {
+                            return table.to_string();
+                        }
+                    } else {
+                        let key = format!("{}.{}", schema, table);
+                        if self.exists(&key) {
+                            return key;
+                        }
+                    }
+                }
+                table.to_string()
+            }
+            [schema, table] if schema.eq_ignore_ascii_case(DEFAULT_SCHEMA) => table.to_string(),
+            _ => parts.join("."),
+        }
+    }
+}
+
+impl VisitorMut for Resolver<'_> {
+    type Break = ();
+
+    // A schema-qualified table keeps its own name as the name columns are qualified by
+    fn pre_visit_table_factor(&mut self, table_factor: &mut TableFactor) -> ControlFlow<()> {
+        if let TableFactor::Table {
+            name,
+            alias: alias @ None,
+            ..
+        } = table_factor
+            && name.0.len() > 1
+            && let Some(table) = name.0.last()
+        {
+            *alias = Some(TableAlias {
+                name: table.clone(),
+                columns: vec![],
+            });
+        }
+        ControlFlow::Continue(())
+    }
+
+    fn pre_visit_relation(&mut self, relation: &mut ObjectName) -> ControlFlow<()> {
+        let key = self.resolve(&relation.0);
+        if relation.0.len() > 1 || relation.0[0].value != key {
+            *relation = ObjectName(vec![Ident::new(key)]);
+        }
+        ControlFlow::Continue(())
+    }
+
+    // `schema.table.column` is qualified by the table alone
+    fn pre_visit_expr(&mut self, expr: &mut Expr) -> ControlFlow<()> {
+        if let Expr::CompoundIdentifier(parts) = expr
+            && parts.len() > 2
+        {
+            parts.drain(..parts.len() - 2);
+        }
+        ControlFlow::Continue(())
+    }
+}
@@ -0,0 +1,57 @@
+// LISTEN, UNLISTEN and NOTIFY. A notification goes to every connection of the database that
+// listens on its channel, the one that sent it included, and the protocol delivers it while
+// the connection waits for its next query. Unlike PostgreSQL, a NOTIFY in a transaction is
+// sent right away rather than at COMMIT.
+use sqlparser::ast::Ident;
+
+use crate::sql::executor::{QueryExecutor, QueryResult};
+
+impl QueryExecutor {
+    pub(crate) fn execute_listen(&self, channel: &Ident) -> crate::Result<QueryResult> {
+        self.listening().insert(channel_name(channel));
+        Ok(empty_result())
+    }
+
+    pub(crate) fn execute_notify(
+        &self,
+        channel: &Ident,
+        payload: Option<&str>,
+    ) -> crate::Result<QueryResult> {
+        self.storage()
+            .notify(&channel_name(channel), payload.unwrap_or_default());
+        Ok(empty_result())
+    }
+
+    /// Stop listening on a channel, or on all of them for `None` (`UNLISTEN *`)
+    pub fn unlisten(&self, channel: Option<&str>) {
+        let mut listening = self.listening();
+        match channel {
+            Some(channel) => {
+                listening.remove(channel);
+            }
+            None => listening.clear(),
+        }
+    }
+
+    /// Whether the connection listens on the channel, to deliver its notifications
+    pub fn is_listening(&self, channel: &str) -> bool {
+        self.listening().contains(channel)
+    }
+}
+
+/// The name of a channel, folded to lower case unless it is quoted
+pub(crate) fn channel_name(channel: &Ident) -> String {
+    match channel.quote_style {
+        Some(_) => channel.value.clone(),
+        None => channel.value.to_lowercase(),
+    }
+}
+
+fn empty_result() -> QueryResult {
+    QueryResult {
+        columns: vec![],
+        column_types: vec![],
+        rows: vec![],
+        affected_rows: None,
+    }
+}
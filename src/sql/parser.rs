@@ -1,6 +1,7 @@
-use sqlparser::ast::{Query, Statement};
-use sqlparser::dialect::{GenericDialect, PostgreSqlDialect};
-use sqlparser::parser::Parser;
+use sqlparser::ast::{Expr, Query, Statement};
+use sqlparser::dialect::{Dialect, GenericDialect, MsSqlDialect, MySqlDialect, PostgreSqlDialect};
+use sqlparser::parser::{Parser, ParserError};
+use sqlparser::tokenizer::{Token, TokenWithLocation, Tokenizer};
 use tracing::debug;
 
 #[derive(Debug, Clone, Copy, Default)]
@@ -8,6 +9,8 @@ pub enum SqlDialect {
     #[default]
     PostgreSQL,
     MySQL,
+    /// SQL Server's Transact-SQL, with `[bracketed]` identifiers and `SELECT TOP n`
+    TSql,
     Generic,
 }
 
@@ -19,20 +22,288 @@ pub fn parse_sql_with_dialect(sql: &str, dialect: SqlDialect) -> crate::Result<V
     debug!("Parsing SQL with dialect {:?}: {}", dialect, sql);
 
     let statements = match dialect {
-        SqlDialect::PostgreSQL => {
-            let dialect = PostgreSqlDialect {};
-            Parser::parse_sql(&dialect, sql)?
-        }
+        SqlDialect::PostgreSQL => parse_numbering_placeholders(&PostgreSqlDialect {}, sql)?,
         SqlDialect::MySQL | SqlDialect::Generic => {
             // Use GenericDialect for MySQL and generic SQL
-            let dialect = GenericDialect {};
-            Parser::parse_sql(&dialect, sql)?
+            parse_numbering_placeholders(&GenericDialect {}, sql)?
         }
+        SqlDialect::TSql => parse_numbering_placeholders(&MsSqlDialect {}, sql)?,
     };
 
     Ok(statements)
 }
 
+/// Parse SQL, renaming positional `?` placeholders to `$1`, `$2`, ... in the order they
+/// appear, so that the executor binds both placeholder styles the same way
+fn parse_numbering_placeholders(
+    dialect: &dyn Dialect,
+    sql: &str,
+) -> Result<Vec<Statement>, ParserError> {
+    let mut tokens = Tokenizer::new(dialect, sql).tokenize_with_location()?;
+    let mut position = 0;
+    for token in &mut tokens {
+        if let Token::Placeholder(name) = &mut token.token
+            && name == "?"
+        {
+            position += 1;
+            *name = format!("${}", position);
+        }
+    }
+
+    // sqlparser reads the rows of `COPY ... FROM STDIN` from the SQL after a semicolon, as
+    // in a pg_dump script. Clients send the rows separately, so the statement ends there.
+    let mut words = tokens
+        .iter()
+        .filter(|token| !matches!(token.token, Token::Whitespace(_)));
+    if matches!(words.next(), Some(TokenWithLocation { token: Token::Word(word), .. })
+            if word.value.eq_ignore_ascii_case("COPY"))
+        && words
+            .next_back()
+            .is_some_and(|token| token.token != Token::SemiColon)
+    {
+        tokens.push(TokenWithLocation::wrap(Token::SemiColon));
+    }
+
+    Parser::new(dialect)
+        .with_tokens_with_locations(tokens)
+        .parse_statements()
+}
+
+/// The view named by a `REFRESH MATERIALIZED VIEW name` statement, which sqlparser cannot
+/// parse, or `None` for any other SQL
+pub fn parse_refresh_materialized_view(sql: &str) -> Option<String> {
+    let tokens = Tokenizer::new(&GenericDialect {}, sql).tokenize().ok()?;
+    let mut tokens = tokens
+        .into_iter()
+        .filter(|token| !matches!(token, Token::Whitespace(_)));
+
+    for keyword in ["REFRESH", "MATERIALIZED", "VIEW"] {
+        match tokens.next()? {
+            Token::Word(word) if word.value.eq_ignore_ascii_case(keyword) => {}
+            _ => return None,
+        }
+    }
+
+    // A schema-qualified name refers to the view by its last part
+    let mut name = None;
+    let mut expect_name = true;
+    for token in tokens {
+        match token {
+            Token::Word(word)
+                if name.is_none()
+                    && word.quote_style.is_none()
+                    && word.value.eq_ignore_ascii_case("CONCURRENTLY") => {}
+            Token::Word(word) if expect_name => {
+                name = Some(word.value);
+                expect_name = false;
+            }
+            Token::Period if !expect_name => expect_name = true,
+            Token::SemiColon | Token::EOF if !expect_name => break,
+            _ => return None,
+        }
+    }
+    name.filter(|_| !expect_name)
+}
+
+/// The channel of an `UNLISTEN channel` statement, which sqlparser cannot parse, with
+/// `Some(None)` for `UNLISTEN *`, or `None` for any other SQL
+pub fn parse_unlisten(sql: &str) -> Option<Option<String>> {
+    let tokens = Tokenizer::new(&PostgreSqlDialect {}, sql).tokenize().ok()?;
+    let words: Vec<Token> = tokens
+        .into_iter()
+        .filter(|token| !matches!(token, Token::Whitespace(_) | Token::SemiColon | Token::EOF))
+        .collect();
+    match words.as_slice() {
+        [Token::Word(keyword), channel]
+            if keyword.quote_style.is_none() && keyword.value.eq_ignore_ascii_case("UNLISTEN") =>
+        {
+            match channel {
+                Token::Mul => Some(None),
+                Token::Word(word) if word.quote_style.is_some() => Some(Some(word.value.clone())),
+                Token::Word(word) => Some(Some(word.value.to_lowercase())),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// The parameter of a `RESET name` statement, which sqlparser cannot parse, with
+/// `Some(None)` for `RESET ALL`, or `None` for any other SQL
+pub fn parse_reset(sql: &str) -> Option<Option<String>> {
+    let tokens = Tokenizer::new(&PostgreSqlDialect {}, sql).tokenize().ok()?;
+    let words: Vec<Token> = tokens
+        .into_iter()
+        .filter(|token| !matches!(token, Token::Whitespace(_) | Token::SemiColon | Token::EOF))
+        .collect();
+    let [Token::Word(keyword), name @ ..] = words.as_slice() else {
+        return None;
+    };
+    if keyword.quote_style.is_some() || !keyword.value.eq_ignore_ascii_case("RESET") {
+        return None;
+    }
+    // `RESET TIME ZONE` names its parameter in two words, custom parameters with a dot
+    let mut parameter = String::new();
+    for token in name {
+        match token {
+            Token::Word(word) => {
+                if !parameter.is_empty() && !parameter.ends_with('.') {
+                    parameter.push(' ');
+                }
+                parameter.push_str(&word.value);
+            }
+            Token::Period => parameter.push('.'),
+            _ => return None,
+        }
+    }
+    match parameter.as_str() {
+        "" => None,
+        all if all.eq_ignore_ascii_case("ALL") => Some(None),
+        _ => Some(Some(parameter)),
+    }
+}
+
+/// The assignments of a MySQL `SET` of system variables, such as
+/// `SET GLOBAL sql_mode = 'ANSI', @@session.autocommit := 0`, by lower-cased variable name
+/// without `@@` or a scope, which sqlparser cannot parse when there are several or they are
+/// GLOBAL. Assignments to user variables (`@name`) are left out; `None` for any other SQL.
+pub fn parse_set_system_variables(sql: &str) -> Option<Vec<(String, Expr)>> {
+    let tokens = Tokenizer::new(&MySqlDialect {}, sql).tokenize().ok()?;
+    let mut tokens: Vec<Token> = tokens
+        .into_iter()
+        .filter(|token| !matches!(token, Token::Whitespace(_)))
+        .collect();
+    while matches!(tokens.last(), Some(Token::SemiColon | Token::EOF)) {
+        tokens.pop();
+    }
+    let [Token::Word(keyword), rest @ ..] = tokens.as_slice() else {
+        return None;
+    };
+    if keyword.quote_style.is_some() || !keyword.value.eq_ignore_ascii_case("SET") {
+        return None;
+    }
+
+    // Split the assignments at the commas outside parentheses
+    let mut assignments = vec![Vec::new()];
+    let mut depth = 0usize;
+    for token in rest {
+        match token {
+            Token::LParen => depth += 1,
+            Token::RParen => depth = depth.saturating_sub(1),
+            Token::Comma if depth == 0 => {
+                assignments.push(Vec::new());
+                continue;
+            }
+            _ => {}
+        }
+        assignments.last_mut()?.push(token.clone());
+    }
+
+    let mut variables = Vec::new();
+    for assignment in assignments {
+        let operator = assignment
+            .iter()
+            .position(|token| matches!(token, Token::Eq | Token::Assignment))?;
+        let (target, value) = (&assignment[..operator], &assignment[operator + 1..]);
+        let name = match target {
+            [Token::Word(scope), Token::Word(name)]
+                if scope.quote_style.is_none()
+                    && ["GLOBAL", "SESSION", "LOCAL", "PERSIST"]
+                        .iter()
+                        .any(|keyword| scope.value.eq_ignore_ascii_case(keyword)) =>
+            {
+                name.value.clone()
+            }
+            [Token::Word(scope), Token::Period, Token::Word(name)]
+                if ["@@GLOBAL", "@@SESSION", "@@LOCAL"]
+                    .iter()
+                    .any(|keyword| scope.value.eq_ignore_ascii_case(keyword)) =>
+            {
+                name.value.clone()
+            }
+            [Token::Word(name)] => match name.value.strip_prefix("@@") {
+                Some(name) => name.to_string(),
+                None if name.value.starts_with('@') => continue,
+                None => name.value.clone(),
+            },
+            _ => return None,
+        };
+        let mut parser = Parser::new(&MySqlDialect {}).with_tokens(value.to_vec());
+        let value = parser.parse_expr().ok()?;
+        if parser.peek_token().token != Token::EOF {
+            return None;
+        }
+        variables.push((name.to_lowercase(), value));
+    }
+    Some(variables)
+}
+
+/// Whether the SQL is the `RELOAD DATABASE` admin statement, which loads the database
+/// files again
+pub fn is_reload_database(sql: &str) -> bool {
+    let Ok(tokens) = Tokenizer::new(&GenericDialect {}, sql).tokenize() else {
+        return false;
+    };
+    let words: Vec<Token> = tokens
+        .into_iter()
+        .filter(|token| !matches!(token, Token::Whitespace(_) | Token::SemiColon | Token::EOF))
+        .collect();
+    matches!(words.as_slice(), [Token::Word(first), Token::Word(second)]
+        if first.quote_style.is_none()
+            && first.value.eq_ignore_ascii_case("RELOAD")
+            && second.quote_style.is_none()
+            && second.value.eq_ignore_ascii_case("DATABASE"))
+}
+
+/// Split a query string holding several statements at the semicolons between them,
+/// leaving out empty statements. Semicolons in strings, quoted identifiers and comments do
+/// not split; SQL that cannot be tokenized is returned whole, for the parser to report.
+pub fn split_statements(sql: &str, dialect: SqlDialect) -> Vec<&str> {
+    let tokens = match dialect {
+        SqlDialect::PostgreSQL => {
+            Tokenizer::new(&PostgreSqlDialect {}, sql).tokenize_with_location()
+        }
+        SqlDialect::MySQL => Tokenizer::new(&MySqlDialect {}, sql).tokenize_with_location(),
+        SqlDialect::TSql => Tokenizer::new(&MsSqlDialect {}, sql).tokenize_with_location(),
+        SqlDialect::Generic => Tokenizer::new(&GenericDialect {}, sql).tokenize_with_location(),
+    };
+    let Ok(tokens) = tokens else {
+        return vec![sql.trim()]
+            .into_iter()
+            .filter(|s| !s.is_empty())
+            .collect();
+    };
+
+    // Token locations are lines and columns counted in characters
+    let line_starts: Vec<usize> = std::iter::once(0)
+        .chain(sql.match_indices('\n').map(|(i, _)| i + 1))
+        .collect();
+    let offset = |line: u64, column: u64| {
+        let start = line_starts[line as usize - 1];
+        sql[start..]
+            .char_indices()
+            .nth(column as usize - 1)
+            .map_or(sql.len(), |(i, _)| start + i)
+    };
+
+    let mut statements = Vec::new();
+    let mut start = 0;
+    for token in &tokens {
+        if token.token == Token::SemiColon {
+            let end = offset(token.location.line, token.location.column);
+            statements.push(&sql[start..end]);
+            start = end + 1;
+        }
+    }
+    statements.push(&sql[start..]);
+
+    statements
+        .into_iter()
+        .map(str::trim)
+        .filter(|statement| !statement.is_empty())
+        .collect()
+}
+
 pub fn is_select_query(statement: &Statement) -> Option<&Query> {
     match statement {
         Statement::Query(query) => Some(query),
@@ -44,6 +315,69 @@ pub fn is_select_query(statement: &Statement) -> Option<&Query> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_is_reload_database() {
+        assert!(is_reload_database("reload database;"));
+        assert!(is_reload_database("RELOAD  DATABASE"));
+        assert!(!is_reload_database("RELOAD DATABASE shop"));
+        assert!(!is_reload_database("SELECT 1"));
+    }
+
+    #[test]
+    fn test_parse_refresh_materialized_view() {
+        assert_eq!(
+            parse_refresh_materialized_view("refresh materialized view Totals;"),
+            Some("Totals".to_string())
+        );
+        assert_eq!(
+            parse_refresh_materialized_view("REFRESH MATERIALIZED VIEW CONCURRENTLY public.\"t\""),
+            Some("t".to_string())
+        );
+        assert_eq!(
+            parse_refresh_materialized_view("REFRESH MATERIALIZED VIEW"),
+            None
+        );
+        assert_eq!(
+            parse_refresh_materialized_view("REFRESH MATERIALIZED VIEW a b"),
+            None
+        );
+        assert_eq!(parse_refresh_materialized_view("SELECT 1"), None);
+    }
+
+    #[test]
+    fn test_split_statements() {
+        assert_eq!(
+            split_statements(
+                "SELECT 1; SELECT ';' AS s;\n-- done;\n",
+                SqlDialect::PostgreSQL
+            ),
+            vec!["SELECT 1", "SELECT ';' AS s", "-- done;"]
+        );
+        assert_eq!(
+            split_statements(
+                "SELECT $$a;b$$; SELECT \"x;y\" FROM t",
+                SqlDialect::PostgreSQL
+            ),
+            vec!["SELECT $$a;b$$", "SELECT \"x;y\" FROM t"]
+        );
+        assert_eq!(
+            split_statements("SELECT 'it\\'s; ok', `a;b` ;;", SqlDialect::MySQL),
+            vec!["SELECT 'it\\'s; ok', `a;b`"]
+        );
+        assert_eq!(
+            split_statements("SELECT 'é';\nSELECT 'ü'", SqlDialect::PostgreSQL),
+            vec!["SELECT 'é'", "SELECT 'ü'"]
+        );
+        assert_eq!(
+            split_statements(" ; ", SqlDialect::PostgreSQL),
+            Vec::<&str>::new()
+        );
+        assert_eq!(
+            split_statements("SELECT 'open; SELECT 1", SqlDialect::PostgreSQL),
+            vec!["SELECT 'open; SELECT 1"]
+        );
+    }
+
     #[test]
     fn test_postgresql_dialect_parsing() {
         let sql = "SELECT * FROM users LIMIT 5";
@@ -88,4 +422,14 @@ mod tests {
         let statements = result.unwrap();
         assert_eq!(statements.len(), 1);
     }
+
+    #[test]
+    fn test_positional_placeholders_are_numbered() {
+        let sql = "SELECT * FROM users WHERE name = ? AND age > ? AND note <> '?'";
+        let statements = parse_sql_with_dialect(sql, SqlDialect::MySQL).unwrap();
+        assert_eq!(
+            statements[0].to_string(),
+            "SELECT * FROM users WHERE name = $1 AND age > $2 AND note <> '?'"
+        );
+    }
 }
@@ -0,0 +1,97 @@
+// Per-table permissions of the users of `auth.users` that have `grants`. Before a statement
+// runs, each table it changes has to be in the `write` list of the user and each table it
+// only reads in the `read` list. Names that are not tables, such as those of CTEs, are left
+// to resolve or fail as they would for any user.
+use sqlparser::ast::{FromTable, ObjectName, Statement, TableFactor, visit_relations};
+use std::ops::ControlFlow;
+
+use crate::YamlBaseError;
+use crate::sql::executor::QueryExecutor;
+use crate::sql::namespace::DEFAULT_SCHEMA;
+use crate::yaml::Grants;
+
+impl Grants {
+    /// Whether the user may read the table the database keeps under `key`
+    pub fn may_read(&self, key: &str) -> bool {
+        Self::names(&self.read, key)
+    }
+
+    /// Whether the user may change the table the database keeps under `key`
+    pub fn may_write(&self, key: &str) -> bool {
+        Self::names(&self.write, key)
+    }
+
+    fn names(list: &[String], key: &str) -> bool {
+        let (schema, table) = key.split_once('.').unwrap_or((DEFAULT_SCHEMA, key));
+        list.iter().any(|grant| {
+            let (grant_schema, grant_table) =
+                grant.split_once('.').unwrap_or((DEFAULT_SCHEMA, grant));
+            grant == "*"
+                || (grant_schema.eq_ignore_ascii_case(schema)
+                    && (grant_table == "*" || grant_table.eq_ignore_ascii_case(table)))
+        })
+    }
+}
+
+impl QueryExecutor {
+    /// Fail when the user of the session may not change a table the statement changes, or
+    /// read one it reads
+    pub(crate) async fn check_permissions(&self, statement: &Statement) -> crate::Result<()> {
+        let Some(grants) = self.grants() else {
+            return Ok(());
+        };
+        let db_arc = self.storage().database();
+        let shared = db_arc.read().await;
+
+        let written: Vec<String> = written_tables(statement)
+            .into_iter()
+            .map(|name| self.table_key(&shared, name))
+            .collect();
+        if let Some(key) = written.iter().find(|key| !grants.may_write(key)) {
+            return Err(YamlBaseError::PermissionDenied(format!("table {}", key)));
+        }
+
+        let denied = visit_relations(statement, |name| {
+            let key = self.table_key(&shared, name);
+            if written.contains(&key)
+                || self.session().table(&shared, &key).is_none()
+                || grants.may_read(&key)
+            {
+                ControlFlow::Continue(())
+            } else {
+                ControlFlow::Break(key)
+            }
+        });
+        match denied {
+            ControlFlow::Break(key) => {
+                Err(YamlBaseError::PermissionDenied(format!("table {}", key)))
+            }
+            ControlFlow::Continue(()) => Ok(()),
+        }
+    }
+}
+
+/// The tables a statement changes, creates or drops
+fn written_tables(statement: &Statement) -> Vec<&ObjectName> {
+    match statement {
+        Statement::Insert(insert) => vec![&insert.table_name],
+        Statement::Update { table, .. } => table_name(&table.relation).into_iter().collect(),
+        Statement::Delete(delete) => match &delete.from {
+            FromTable::WithFromKeyword(from) | FromTable::WithoutKeyword(from) => from
+                .iter()
+                .filter_map(|target| table_name(&target.relation))
+                .collect(),
+        },
+        Statement::Merge { table, .. } => table_name(table).into_iter().collect(),
+        Statement::CreateTable(create) => vec![&create.name],
+        Statement::Drop { names, .. } => names.iter().collect(),
+        _ => vec![],
+    }
+}
+
+fn table_name(factor: &TableFactor) -> Option<&ObjectName> {
+    match factor {
+        TableFactor::Table { name, .. } => Some(name),
+        _ => None,
+    }
+}
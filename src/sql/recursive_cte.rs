@@ -1,9 +1,46 @@
 // Implementation of RECURSIVE CTE support for yamlbase
 use crate::YamlBaseError;
 use crate::database::Database;
-use crate::sql::executor::{QueryExecutor, QueryResult};
+use crate::sql::executor::{CteResults, QueryExecutor, QueryResult};
 use sqlparser::ast::{Cte, SetExpr, SetOperator};
+use std::collections::hash_map::DefaultHasher;
 use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+
+/// Limits on the evaluation of a RECURSIVE CTE. Set server-wide with
+/// `--cte-max-recursion-depth`/`--cte-max-rows` and per session with
+/// `SET cte_max_recursion_depth = ...`/`SET cte_max_rows = ...`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecursionLimits {
+    /// Maximum number of times the recursive part is evaluated
+    pub max_iterations: usize,
+    /// Maximum number of rows the CTE may produce, base case included
+    pub max_rows: usize,
+}
+
+impl Default for RecursionLimits {
+    fn default() -> Self {
+        Self {
+            max_iterations: 1000,
+            max_rows: 1_000_000,
+        }
+    }
+}
+
+impl RecursionLimits {
+    /// Session setting names, as accepted by SET
+    pub const MAX_ITERATIONS_SETTING: &'static str = "cte_max_recursion_depth";
+    pub const MAX_ROWS_SETTING: &'static str = "cte_max_rows";
+}
+
+/// Order-independent fingerprint of the rows produced by one iteration
+fn working_set_fingerprint(rows: &[Vec<crate::database::Value>]) -> u64 {
+    let mut keys: Vec<String> = rows.iter().map(|row| format!("{:?}", row)).collect();
+    keys.sort_unstable();
+    let mut hasher = DefaultHasher::new();
+    keys.hash(&mut hasher);
+    hasher.finish()
+}
 
 impl QueryExecutor {
     /// Execute a RECURSIVE CTE
@@ -13,11 +50,15 @@ impl QueryExecutor {
     /// 2. Iteratively executing the recursive part using previous results
     /// 3. Continuing until no new rows are produced
     /// 4. Combining all results
+    ///
+    /// The session's `RecursionLimits` bound the number of iterations and rows. With
+    /// UNION ALL, an iteration that produces exactly the rows of an earlier one means the
+    /// data contains a cycle, which is reported instead of looping until a limit is hit.
     pub async fn execute_recursive_cte(
         &self,
         db: &Database,
         cte: &Cte,
-        cte_results: &HashMap<String, QueryResult>,
+        cte_results: &CteResults,
     ) -> crate::Result<QueryResult> {
         let cte_name = cte.alias.name.value.clone();
         eprintln!("DEBUG: Executing RECURSIVE CTE '{}'", cte_name);
@@ -44,9 +85,10 @@ impl QueryExecutor {
         let mut all_rows = Vec::new();
         let mut working_table = match base_query {
             SetExpr::Select(select) => {
-                let result = self
+                let mut result = self
                     .execute_select_with_cte_context(db, select, &cte.query, cte_results)
                     .await?;
+                Self::apply_cte_column_aliases(cte, &mut result)?;
                 all_rows.extend(result.rows.clone());
                 result
             }
@@ -58,8 +100,8 @@ impl QueryExecutor {
         };
 
         // Set up for recursive execution with enhanced protection
+        let limits = self.recursion_limits();
         let mut iteration = 0;
-        let max_iterations = 1000; // Prevent infinite loops
         let max_memory_bytes = 100_000_000; // 100MB memory limit for CTE results
         let mut estimated_memory_usage = 0usize;
         let mut seen_rows = if !is_union_all {
@@ -71,19 +113,33 @@ impl QueryExecutor {
         } else {
             None
         };
+        // Fingerprints of earlier working tables and the iteration that produced them, to
+        // detect cycles under UNION ALL
+        let mut seen_working_sets = if is_union_all {
+            HashMap::from([(working_set_fingerprint(&working_table.rows), 0)])
+        } else {
+            HashMap::new()
+        };
+        Self::check_recursive_row_limit(&cte_name, all_rows.len(), &limits)?;
 
         // Recursive execution
         loop {
             iteration += 1;
-            if iteration > max_iterations {
+            if iteration > limits.max_iterations {
                 return Err(YamlBaseError::Database {
-                    message: format!("RECURSIVE CTE '{}' exceeded maximum iterations", cte_name),
+                    message: format!(
+                        "RECURSIVE CTE '{}' exceeded the maximum recursion depth of {} \
+                         iterations; add a termination condition or raise {}",
+                        cte_name,
+                        limits.max_iterations,
+                        RecursionLimits::MAX_ITERATIONS_SETTING
+                    ),
                 });
             }
 
             // Create temporary CTE results including the working table
             let mut temp_cte_results = cte_results.clone();
-            temp_cte_results.insert(cte_name.clone(), working_table.clone());
+            temp_cte_results.insert(Self::cte_key(&cte_name), working_table.clone());
 
             // Execute recursive part
             let recursive_result = match recursive_query {
@@ -117,10 +173,13 @@ impl QueryExecutor {
                         crate::database::Value::Boolean(_) => 1,
                         crate::database::Value::Date(_) => 12, // NaiveDate size
                         crate::database::Value::Timestamp(_) => 16, // NaiveDateTime size
+                        crate::database::Value::TimestampTz(_) => 20, // plus its offset
                         crate::database::Value::Time(_) => 8,  // NaiveTime size
                         crate::database::Value::Uuid(_) => 16, // UUID size
                         crate::database::Value::Decimal(_) => 16, // Decimal size
                         crate::database::Value::Json(json) => json.to_string().len(),
+                        crate::database::Value::Bytes(bytes) => bytes.len(),
+                        crate::database::Value::Array(items) => items.len() * 16,
                         crate::database::Value::Null => 1,
                     })
                     .sum::<usize>();
@@ -158,6 +217,20 @@ impl QueryExecutor {
             if new_rows.is_empty() {
                 break; // No new unique rows
             }
+            Self::check_recursive_row_limit(&cte_name, all_rows.len(), &limits)?;
+            if is_union_all
+                && let Some(earlier) =
+                    seen_working_sets.insert(working_set_fingerprint(&new_rows), iteration)
+            {
+                return Err(YamlBaseError::Database {
+                    message: format!(
+                        "RECURSIVE CTE '{}' contains a cycle: iteration {} produced the same \
+                         rows as iteration {}; use UNION instead of UNION ALL or stop the \
+                         recursion at rows already visited",
+                        cte_name, iteration, earlier
+                    ),
+                });
+            }
             working_table.rows = new_rows;
         }
 
@@ -166,6 +239,26 @@ impl QueryExecutor {
             columns: working_table.columns,
             column_types: working_table.column_types,
             rows: all_rows,
+            affected_rows: None,
         })
     }
+
+    fn check_recursive_row_limit(
+        cte_name: &str,
+        rows: usize,
+        limits: &RecursionLimits,
+    ) -> crate::Result<()> {
+        if rows > limits.max_rows {
+            return Err(YamlBaseError::Database {
+                message: format!(
+                    "RECURSIVE CTE '{}' exceeded the maximum of {} rows; add a termination \
+                     condition or raise {}",
+                    cte_name,
+                    limits.max_rows,
+                    RecursionLimits::MAX_ROWS_SETTING
+                ),
+            });
+        }
+        Ok(())
+    }
 }
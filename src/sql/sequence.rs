@@ -0,0 +1,99 @@
+// AUTO_INCREMENT and SERIAL columns. The last value a column was given is kept with its
+// table, so it follows the table into the session overlay and into the shared database on
+// commit. The ids a connection generated are remembered for LAST_INSERT_ID(), currval()
+// and lastval().
+use std::collections::HashMap;
+
+use crate::YamlBaseError;
+use crate::database::{Table, Value};
+use crate::sql::executor::QueryExecutor;
+
+#[derive(Debug, Default)]
+pub(crate) struct GeneratedIds {
+    // The first id of the most recent INSERT that generated any
+    last_insert_id: Option<i64>,
+    // The last id of each sequence, named `<table>_<column>_seq` as PostgreSQL names them
+    sequences: HashMap<String, i64>,
+    // The last id of any sequence
+    last: Option<i64>,
+}
+
+impl QueryExecutor {
+    /// Give new rows without a value for the table's auto-increment column the next ids,
+    /// and move the sequence past ids that were given explicitly
+    pub(crate) fn generate_ids(&self, table: &mut Table, rows: &mut [Vec<Value>]) {
+        let Some(auto_increment) = table.auto_increment.as_mut() else {
+            return;
+        };
+
+        let column = auto_increment.column;
+        let mut generated = Vec::new();
+        for row in rows {
+            match row[column] {
+                Value::Null => {
+                    auto_increment.last += 1;
+                    row[column] = Value::Integer(auto_increment.last);
+                    generated.push(auto_increment.last);
+                }
+                Value::Integer(id) => auto_increment.last = auto_increment.last.max(id),
+                _ => {}
+            }
+        }
+
+        if let (Some(&first), Some(&last)) = (generated.first(), generated.last()) {
+            let sequence = sequence_name(&table.name, &table.columns[column].name);
+            let mut ids = self.generated_ids().lock().unwrap();
+            ids.last_insert_id = Some(first);
+            ids.sequences.insert(sequence, last);
+            ids.last = Some(last);
+        }
+    }
+
+    /// The first id generated by the connection's latest INSERT that generated any, as
+    /// MySQL's LAST_INSERT_ID() reports it; 0 before the first one
+    pub fn last_insert_id(&self) -> i64 {
+        self.generated_ids()
+            .lock()
+            .unwrap()
+            .last_insert_id
+            .unwrap_or(0)
+    }
+
+    /// PostgreSQL's currval(): the last id the connection generated from a sequence
+    pub(crate) fn current_sequence_value(&self, sequence: &str) -> crate::Result<Value> {
+        // Sequences are not in a schema, so `public.items_id_seq` is `items_id_seq`
+        let name = sequence
+            .rsplit('.')
+            .next()
+            .unwrap_or(sequence)
+            .to_lowercase();
+        self.generated_ids()
+            .lock()
+            .unwrap()
+            .sequences
+            .get(&name)
+            .map(|&id| Value::Integer(id))
+            .ok_or_else(|| YamlBaseError::Database {
+                message: format!(
+                    "currval of sequence \"{}\" is not yet defined in this session",
+                    name
+                ),
+            })
+    }
+
+    /// PostgreSQL's lastval(): the last id the connection generated from any sequence
+    pub(crate) fn last_sequence_value(&self) -> crate::Result<Value> {
+        self.generated_ids()
+            .lock()
+            .unwrap()
+            .last
+            .map(Value::Integer)
+            .ok_or_else(|| YamlBaseError::Database {
+                message: "lastval is not yet defined in this session".to_string(),
+            })
+    }
+}
+
+fn sequence_name(table: &str, column: &str) -> String {
+    format!("{}_{}_seq", table, column).to_lowercase()
+}
@@ -0,0 +1,446 @@
+// Session settings of PostgreSQL connections. Drivers set parameters such as
+// application_name, TimeZone and extra_float_digits when they connect and read others such as
+// server_version back with SHOW. Each connection keeps its own values; only the time zone,
+// as the zone TIMESTAMPTZ values are read and shown in, and statement_timeout change how
+// statements run.
+use sqlparser::ast::{Expr, Ident, ObjectName, Value as SqlValue};
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::YamlBaseError;
+use crate::database::{TimeZone, Value};
+use crate::sql::executor::{QueryExecutor, QueryResult};
+use crate::sql::namespace::SEARCH_PATH_SETTING;
+use crate::yaml::schema::SqlType;
+
+/// The PostgreSQL version clients are told the server is
+const SERVER_VERSION: &str = "14.0";
+
+/// The parameters a connection can set, as SHOW names them, and their defaults
+const PARAMETERS: &[(&str, &str)] = &[
+    ("application_name", ""),
+    ("bytea_output", "hex"),
+    ("client_encoding", "UTF8"),
+    ("client_min_messages", "notice"),
+    ("DateStyle", "ISO, MDY"),
+    ("default_transaction_isolation", "read committed"),
+    ("extra_float_digits", "1"),
+    ("idle_in_transaction_session_timeout", "0"),
+    ("IntervalStyle", "postgres"),
+    ("lock_timeout", "0"),
+    ("standard_conforming_strings", "on"),
+    // Shown as the configured --statement-timeout until the connection sets its own
+    ("statement_timeout", "0"),
+    ("transaction_isolation", "read committed"),
+];
+
+/// The parameters that can be shown but not set
+const READ_ONLY_PARAMETERS: &[(&str, &str)] = &[
+    ("integer_datetimes", "on"),
+    ("max_identifier_length", "63"),
+    ("server_encoding", "UTF8"),
+    ("server_version", SERVER_VERSION),
+    ("server_version_num", "140000"),
+];
+
+/// The parameters clients are sent a ParameterStatus for at startup and when they change
+const REPORTED_PARAMETERS: &[&str] = &[
+    "application_name",
+    "client_encoding",
+    "DateStyle",
+    "integer_datetimes",
+    "IntervalStyle",
+    "server_encoding",
+    "server_version",
+    "standard_conforming_strings",
+    TIME_ZONE_PARAMETER,
+];
+
+const TIME_ZONE_PARAMETER: &str = "TimeZone";
+const STATEMENT_TIMEOUT_PARAMETER: &str = "statement_timeout";
+
+/// The values a connection set, by lower-cased parameter name
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SessionSettings {
+    pub(crate) values: HashMap<String, String>,
+    pub(crate) time_zone: Option<TimeZone>,
+}
+
+impl QueryExecutor {
+    /// `SET name TO value` for any parameter but the engine's own settings
+    pub(crate) fn execute_set_parameter(
+        &self,
+        name: &ObjectName,
+        value: &[Expr],
+    ) -> crate::Result<()> {
+        let name = parameter_name(name);
+        if name == "timezone" {
+            return match value {
+                [expr] => self.execute_set_time_zone(expr),
+                _ => Err(invalid_value(TIME_ZONE_PARAMETER, value)),
+            };
+        }
+        let value = match value {
+            [Expr::Identifier(ident)]
+                if ident.quote_style.is_none() && ident.value.eq_ignore_ascii_case("DEFAULT") =>
+            {
+                None
+            }
+            _ => Some(self.parameter_value(&name, value)?),
+        };
+        self.set_parameter(&name, value)
+    }
+
+    /// `SET TIME ZONE`: a zone name, an offset in hours, or LOCAL or DEFAULT for the
+    /// server's zone
+    pub(crate) fn execute_set_time_zone(&self, value: &Expr) -> crate::Result<()> {
+        let zone = match value {
+            Expr::Identifier(ident)
+                if ident.quote_style.is_none()
+                    && ["LOCAL", "DEFAULT"]
+                        .iter()
+                        .any(|keyword| ident.value.eq_ignore_ascii_case(keyword)) =>
+            {
+                None
+            }
+            Expr::Identifier(ident) => Some(TimeZone::parse(&ident.value)?),
+            _ => match self.evaluate_constant_expr(value)? {
+                Value::Text(name) => Some(TimeZone::parse(&name)?),
+                Value::Integer(hours) => Some(TimeZone::parse(&format!("{:+03}", hours))?),
+                _ => {
+                    return Err(invalid_value(
+                        TIME_ZONE_PARAMETER,
+                        std::slice::from_ref(value),
+                    ));
+                }
+            },
+        };
+        self.settings().time_zone = zone;
+        Ok(())
+    }
+
+    /// `RESET name`, or `RESET ALL` for `None`. Returns the reported parameters that were
+    /// reset, with their values now.
+    pub fn reset_parameter(&self, name: Option<&str>) -> crate::Result<Vec<(String, String)>> {
+        let names: Vec<String> = match name {
+            Some(name) => vec![normalize(name)],
+            None => PARAMETERS
+                .iter()
+                .map(|(name, _)| name.to_lowercase())
+                .chain(["timezone".to_string(), SEARCH_PATH_SETTING.to_string()])
+                .chain(self.settings().values.keys().cloned())
+                .collect(),
+        };
+        for name in &names {
+            if name == "timezone" {
+                self.settings().time_zone = None;
+            } else if name == SEARCH_PATH_SETTING {
+                self.set_search_path(crate::sql::namespace::default_search_path());
+            } else {
+                self.set_parameter(name, None)?;
+            }
+        }
+        Ok(names
+            .iter()
+            .filter_map(|name| self.reported_parameter(name))
+            .collect())
+    }
+
+    /// Apply a parameter of the startup message, as PostgreSQL does for the parameters
+    /// that are not `user`, `database` or `options`
+    pub fn set_startup_parameter(&self, name: &str, value: &str) -> crate::Result<()> {
+        let name = normalize(name);
+        match name.as_str() {
+            "timezone" => {
+                self.settings().time_zone = Some(TimeZone::parse(value)?);
+                Ok(())
+            }
+            SEARCH_PATH_SETTING => {
+                self.set_search_path(
+                    value
+                        .split(',')
+                        .map(|schema| schema.trim().trim_matches('"').to_string())
+                        .filter(|schema| !schema.is_empty())
+                        .collect(),
+                );
+                Ok(())
+            }
+            _ => self.set_parameter(&name, Some(value.to_string())),
+        }
+    }
+
+    /// `SHOW name`, or `SHOW ALL` for every parameter
+    pub(crate) fn execute_show(&self, variable: &[Ident]) -> crate::Result<QueryResult> {
+        let words: Vec<&str> = variable.iter().map(|ident| ident.value.as_str()).collect();
+        // The parser splits `prefix.name` into words as well
+        let mut name = normalize(&words.join(" "));
+        if name.contains(' ') {
+            name = normalize(&words.join("."));
+        }
+        if name == "all" {
+            return Ok(self.show_all());
+        }
+        let (column, value) = self.show_parameter(&name).ok_or_else(|| {
+            YamlBaseError::NotImplemented(format!(
+                "Unrecognized configuration parameter \"{}\"",
+                name
+            ))
+        })?;
+        Ok(QueryResult {
+            columns: vec![column],
+            column_types: vec![SqlType::Text],
+            rows: vec![vec![Value::Text(value)]],
+            affected_rows: None,
+        })
+    }
+
+    /// `SHOW ALL`: the name and value of every parameter, sorted by name as PostgreSQL
+    /// sorts them
+    fn show_all(&self) -> QueryResult {
+        let custom: Vec<String> = self
+            .settings()
+            .values
+            .keys()
+            .filter(|name| name.contains('.'))
+            .cloned()
+            .collect();
+        let mut rows: Vec<Vec<Value>> = PARAMETERS
+            .iter()
+            .chain(READ_ONLY_PARAMETERS)
+            .map(|(name, _)| name.to_lowercase())
+            .chain(["timezone".to_string(), SEARCH_PATH_SETTING.to_string()])
+            .chain(custom)
+            .filter_map(|name| self.show_parameter(&name))
+            .map(|(name, value)| vec![Value::Text(name), Value::Text(value)])
+            .collect();
+        rows.sort_by_key(|row| row[0].to_string().to_lowercase());
+        QueryResult {
+            columns: vec!["name".to_string(), "setting".to_string()],
+            column_types: vec![SqlType::Text, SqlType::Text],
+            rows,
+            affected_rows: None,
+        }
+    }
+
+    /// The parameters sent in ParameterStatus messages when a connection starts
+    pub fn reported_parameters(&self) -> Vec<(String, String)> {
+        REPORTED_PARAMETERS
+            .iter()
+            .filter_map(|name| self.reported_parameter(&name.to_lowercase()))
+            .collect()
+    }
+
+    /// The reported parameter a successful SET changed, with its new value
+    pub fn changed_parameter(
+        &self,
+        statement: &sqlparser::ast::Statement,
+    ) -> Option<(String, String)> {
+        match statement {
+            sqlparser::ast::Statement::SetTimeZone { .. } => self.reported_parameter("timezone"),
+            sqlparser::ast::Statement::SetVariable {
+                variables: sqlparser::ast::OneOrManyWithParens::One(name),
+                ..
+            } => self.reported_parameter(&parameter_name(name)),
+            _ => None,
+        }
+    }
+
+    /// The zone the connection chose with SET TIME ZONE
+    pub(crate) fn session_time_zone(&self) -> Option<TimeZone> {
+        self.settings().time_zone.clone()
+    }
+
+    /// The statement timeout the connection chose with SET statement_timeout, zero for none
+    pub(crate) fn session_statement_timeout(&self) -> Option<Duration> {
+        let settings = self.settings();
+        parse_statement_timeout(settings.values.get(STATEMENT_TIMEOUT_PARAMETER)?)
+    }
+
+    /// Show TIMESTAMPTZ values in the session's time zone, whichever zone they were read in
+    pub(crate) fn in_session_time_zone(&self, result: &mut QueryResult) {
+        let Some(zone) = self.session_time_zone() else {
+            return;
+        };
+        for value in result.rows.iter_mut().flatten() {
+            if let Value::TimestampTz(timestamp) = value {
+                *timestamp = zone.from_instant(timestamp);
+            }
+        }
+    }
+
+    fn reported_parameter(&self, name: &str) -> Option<(String, String)> {
+        REPORTED_PARAMETERS
+            .iter()
+            .any(|reported| reported.eq_ignore_ascii_case(name))
+            .then(|| self.show_parameter(name))
+            .flatten()
+    }
+
+    /// The name SHOW gives a parameter and its value
+    fn show_parameter(&self, name: &str) -> Option<(String, String)> {
+        if name == "timezone" {
+            let zone = self
+                .session_time_zone()
+                .unwrap_or_else(TimeZone::server_default);
+            return Some((TIME_ZONE_PARAMETER.to_string(), zone.name().to_string()));
+        }
+        if name == SEARCH_PATH_SETTING {
+            return Some((name.to_string(), self.search_path().join(", ")));
+        }
+        if let Some((display, default)) = PARAMETERS
+            .iter()
+            .chain(READ_ONLY_PARAMETERS)
+            .find(|(parameter, _)| parameter.eq_ignore_ascii_case(name))
+        {
+            let value = self.settings().values.get(name).cloned();
+            let default = if name == STATEMENT_TIMEOUT_PARAMETER {
+                format_statement_timeout(self.query_timeout())
+            } else {
+                default.to_string()
+            };
+            return Some((display.to_string(), value.unwrap_or(default)));
+        }
+        // Custom `prefix.name` parameters exist once they are set
+        let value = self.settings().values.get(name).cloned()?;
+        Some((name.to_string(), value))
+    }
+
+    /// Set a parameter, or reset it to its default with `None`
+    fn set_parameter(&self, name: &str, value: Option<String>) -> crate::Result<()> {
+        if READ_ONLY_PARAMETERS
+            .iter()
+            .any(|(parameter, _)| parameter.eq_ignore_ascii_case(name))
+        {
+            return Err(YamlBaseError::Database {
+                message: format!("parameter \"{}\" cannot be changed", name),
+            });
+        }
+        let known = PARAMETERS
+            .iter()
+            .any(|(parameter, _)| parameter.eq_ignore_ascii_case(name));
+        if !known && !name.contains('.') {
+            return Err(YamlBaseError::NotImplemented(format!(
+                "Unrecognized configuration parameter \"{}\"",
+                name
+            )));
+        }
+        if name == STATEMENT_TIMEOUT_PARAMETER
+            && let Some(timeout) = &value
+            && parse_statement_timeout(timeout).is_none()
+        {
+            return Err(YamlBaseError::Database {
+                message: format!(
+                    "invalid value for parameter \"statement_timeout\": \"{}\"",
+                    timeout
+                ),
+            });
+        }
+        // Text is only sent as UTF-8
+        if name == "client_encoding"
+            && let Some(encoding) = &value
+            && !["UTF8", "UTF-8", "UNICODE"].contains(&encoding.to_uppercase().as_str())
+        {
+            return Err(YamlBaseError::Database {
+                message: format!(
+                    "invalid value for parameter \"client_encoding\": \"{}\"",
+                    encoding
+                ),
+            });
+        }
+
+        let mut settings = self.settings();
+        match value {
+            Some(value) => {
+                settings.values.insert(name.to_string(), value);
+            }
+            None => {
+                settings.values.remove(name);
+            }
+        }
+        Ok(())
+    }
+
+    /// A SET value as text: `a, b` lists are joined, names and literals taken as written
+    fn parameter_value(&self, name: &str, value: &[Expr]) -> crate::Result<String> {
+        let mut parts = Vec::new();
+        for expr in value {
+            parts.push(match expr {
+                Expr::Identifier(ident) => ident.value.clone(),
+                Expr::Value(SqlValue::Boolean(true)) => "on".to_string(),
+                Expr::Value(SqlValue::Boolean(false)) => "off".to_string(),
+                _ => match self.evaluate_constant_expr(expr)? {
+                    Value::Text(text) => text,
+                    Value::Null => return Err(invalid_value(name, value)),
+                    other => other.to_string(),
+                },
+            });
+        }
+        Ok(parts.join(", "))
+    }
+}
+
+/// A parameter name as settings are kept under: lower case, with SHOW's multi-word names
+/// (`TIME ZONE`, `TRANSACTION ISOLATION LEVEL`) mapped to the parameter
+fn normalize(name: &str) -> String {
+    match name.to_lowercase().as_str() {
+        "time zone" => "timezone".to_string(),
+        "transaction isolation level" => "transaction_isolation".to_string(),
+        name => name.to_string(),
+    }
+}
+
+fn parameter_name(name: &ObjectName) -> String {
+    let parts: Vec<&str> = name.0.iter().map(|ident| ident.value.as_str()).collect();
+    normalize(&parts.join("."))
+}
+
+/// A statement_timeout as PostgreSQL reads it: milliseconds, or a number with one of the
+/// units us, ms, s, min, h or d
+fn parse_statement_timeout(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(value.len());
+    let number: f64 = value[..split].parse().ok()?;
+    let milliseconds = match value[split..].trim() {
+        "" | "ms" => 1.0,
+        "us" => 0.001,
+        "s" => 1_000.0,
+        "min" => 60_000.0,
+        "h" => 3_600_000.0,
+        "d" => 86_400_000.0,
+        _ => return None,
+    };
+    Duration::try_from_secs_f64(number * milliseconds / 1_000.0).ok()
+}
+
+/// A statement_timeout as PostgreSQL shows it: in the largest unit that divides it
+fn format_statement_timeout(timeout: Duration) -> String {
+    let microseconds = timeout.as_micros();
+    if microseconds == 0 {
+        return "0".to_string();
+    }
+    [
+        ("d", 86_400_000_000),
+        ("h", 3_600_000_000),
+        ("min", 60_000_000),
+        ("s", 1_000_000),
+        ("ms", 1_000),
+        ("us", 1),
+    ]
+    .iter()
+    .find(|(_, unit)| microseconds.is_multiple_of(*unit))
+    .map(|(name, unit)| format!("{}{}", microseconds / unit, name))
+    .unwrap_or_default()
+}
+
+fn invalid_value(name: &str, value: &[Expr]) -> YamlBaseError {
+    let value: Vec<String> = value.iter().map(ToString::to_string).collect();
+    YamlBaseError::Database {
+        message: format!(
+            "invalid value for parameter \"{}\": {}",
+            name,
+            value.join(", ")
+        ),
+    }
+}
@@ -0,0 +1,423 @@
+// MySQL's SHOW statements, which command-line clients and GUI tools browse a server with:
+// SHOW DATABASES, SHOW [FULL] TABLES, SHOW [FULL] COLUMNS, which DESCRIBE is short for, and
+// SHOW INDEX. They list the tables the information_schema views list, in the columns MySQL
+// gives each statement, and are filtered by a LIKE on their first column or a WHERE on any.
+use sqlparser::ast::{Expr, Ident, ObjectName, ShowStatementFilter, Statement, Value as SqlValue};
+use std::sync::Arc;
+
+use crate::YamlBaseError;
+use crate::database::{Collation, Column, Database, Table, Value};
+use crate::sql::executor::{QueryExecutor, QueryResult};
+use crate::sql::information_schema::{INFORMATION_SCHEMA, mysql_column_type};
+use crate::sql::namespace::DEFAULT_SCHEMA;
+use crate::yaml::schema::SqlType;
+
+/// The columns of SHOW INDEX
+const INDEX_COLUMNS: &[&str] = &[
+    "Table",
+    "Non_unique",
+    "Key_name",
+    "Seq_in_index",
+    "Column_name",
+    "Collation",
+    "Cardinality",
+    "Sub_part",
+    "Packed",
+    "Null",
+    "Index_type",
+    "Comment",
+    "Index_comment",
+    "Visible",
+    "Expression",
+];
+
+impl QueryExecutor {
+    /// SHOW DATABASES: information_schema, the databases the server serves and the schemas
+    /// of the current one, which USE switches to as it does to a database
+    pub(crate) async fn execute_show_databases(
+        &self,
+        filter: Option<&ShowStatementFilter>,
+    ) -> crate::Result<QueryResult> {
+        let db_arc = self.storage().database();
+        let shared = db_arc.read().await;
+        let session = self.session().apply(&shared);
+        let db = session.as_ref().unwrap_or(&*shared);
+
+        let mut names = vec![INFORMATION_SCHEMA.to_string()];
+        names.extend(self.databases().iter().cloned());
+        for (key, _) in self.visible_tables(db) {
+            let (_, schema, _) = self.qualified_name(db, key);
+            if !names.iter().any(|name| name.eq_ignore_ascii_case(&schema)) {
+                names.push(schema);
+            }
+        }
+        names[1..].sort();
+        let rows = names
+            .into_iter()
+            .map(|name| vec![Value::Text(name)])
+            .collect();
+        self.show_result(&["Database"], rows, filter).await
+    }
+
+    /// SHOW [FULL] TABLES [FROM db]: the tables of a database or schema, the current one
+    /// unless another is named
+    pub(crate) async fn execute_show_tables(
+        &self,
+        full: bool,
+        db_name: Option<&Ident>,
+        filter: Option<&ShowStatementFilter>,
+    ) -> crate::Result<QueryResult> {
+        let db_arc = self.storage().database();
+        let shared = db_arc.read().await;
+        let session = self.session().apply(&shared);
+        let db = session.as_ref().unwrap_or(&*shared);
+        let schema = self.shown_schema(db, db_name.map(|ident| ident.value.as_str()))?;
+
+        let mut rows = Vec::new();
+        for (key, _) in self.visible_tables(db) {
+            let (_, table_schema, name) = self.qualified_name(db, key);
+            if !table_schema.eq_ignore_ascii_case(&schema) {
+                continue;
+            }
+            let mut row = vec![Value::Text(name)];
+            if full {
+                let table_type = if db.materialized_views.contains_key(key) {
+                    "VIEW"
+                } else {
+                    "BASE TABLE"
+                };
+                row.push(Value::Text(table_type.to_string()));
+            }
+            rows.push(row);
+        }
+        rows.sort_by(|a, b| compare_text(&a[0], &b[0]));
+
+        let first = format!("Tables_in_{}", schema);
+        let mut columns = vec![first.as_str()];
+        if full {
+            columns.push("Table_type");
+        }
+        self.show_result(&columns, rows, filter).await
+    }
+
+    /// SHOW [FULL] COLUMNS FROM table [FROM db]
+    pub(crate) async fn execute_show_columns(
+        &self,
+        full: bool,
+        table_name: &ObjectName,
+        filter: Option<&ShowStatementFilter>,
+    ) -> crate::Result<QueryResult> {
+        let db_arc = self.storage().database();
+        let shared = db_arc.read().await;
+        let session = self.session().apply(&shared);
+        let db = session.as_ref().unwrap_or(&*shared);
+        let table = self.shown_table(db, table_name)?;
+
+        let mut rows = Vec::new();
+        for (index, column) in table.columns.iter().enumerate() {
+            let auto_increment = table
+                .auto_increment
+                .is_some_and(|auto_increment| auto_increment.column == index);
+            let key = if column.primary_key {
+                "PRI"
+            } else if column.unique {
+                "UNI"
+            } else if column.references.is_some() {
+                "MUL"
+            } else {
+                ""
+            };
+            let nullable = column.nullable && !column.primary_key;
+            let mut row = vec![
+                Value::Text(column.name.clone()),
+                Value::Text(mysql_column_type(&column.sql_type)),
+            ];
+            if full {
+                row.push(if is_text(&column.sql_type) {
+                    Value::Text(mysql_collation(
+                        column.collation.unwrap_or(self.collation()),
+                    ))
+                } else {
+                    Value::Null
+                });
+            }
+            row.extend([
+                Value::Text(if nullable { "YES" } else { "NO" }.to_string()),
+                Value::Text(key.to_string()),
+                column
+                    .default
+                    .as_ref()
+                    .map_or(Value::Null, |default| Value::Text(default.clone())),
+                Value::Text(if auto_increment { "auto_increment" } else { "" }.to_string()),
+            ]);
+            if full {
+                row.push(Value::Text("select,insert,update,references".to_string()));
+                row.push(Value::Text(String::new()));
+            }
+            rows.push(row);
+        }
+
+        let columns: &[&str] = if full {
+            &[
+                "Field",
+                "Type",
+                "Collation",
+                "Null",
+                "Key",
+                "Default",
+                "Extra",
+                "Privileges",
+                "Comment",
+            ]
+        } else {
+            &["Field", "Type", "Null", "Key", "Default", "Extra"]
+        };
+        self.show_result(columns, rows, filter).await
+    }
+
+    /// Whether a SHOW the parser took for a variable is SHOW INDEX, INDEXES or KEYS
+    pub(crate) fn is_show_index(variable: &[Ident]) -> bool {
+        variable.first().is_some_and(|word| {
+            ["INDEX", "INDEXES", "KEYS"]
+                .iter()
+                .any(|keyword| word.value.eq_ignore_ascii_case(keyword))
+        })
+    }
+
+    /// SHOW INDEX FROM table [FROM db]: the primary key, unique columns and the columns of
+    /// foreign keys, which MySQL indexes
+    pub(crate) async fn execute_show_index(
+        &self,
+        variable: &[Ident],
+    ) -> crate::Result<QueryResult> {
+        let table_name = show_index_table(variable).ok_or_else(|| {
+            YamlBaseError::NotImplemented("SHOW INDEX needs FROM and a table name".to_string())
+        })?;
+        let db_arc = self.storage().database();
+        let shared = db_arc.read().await;
+        let session = self.session().apply(&shared);
+        let db = session.as_ref().unwrap_or(&*shared);
+        let table = self.shown_table(db, &table_name)?;
+        let name = table_name.0.last().map_or("", |ident| ident.value.as_str());
+        let row_count = table.rows.len() as i64;
+
+        let mut keys = Vec::new();
+        let primary_key = table.columns.iter().filter(|column| column.primary_key);
+        for (position, column) in primary_key.enumerate() {
+            keys.push(("PRIMARY".to_string(), false, position + 1, column));
+        }
+        for column in table.columns.iter().filter(|column| column.unique) {
+            keys.push((column.name.clone(), false, 1, column));
+        }
+        let foreign_keys = table
+            .columns
+            .iter()
+            .filter(|column| column.references.is_some() && !column.primary_key && !column.unique);
+        for column in foreign_keys {
+            keys.push((column.name.clone(), true, 1, column));
+        }
+
+        let rows = keys
+            .into_iter()
+            .map(|(key_name, non_unique, position, column)| {
+                let null = if column.nullable && !column.primary_key {
+                    "YES"
+                } else {
+                    ""
+                };
+                vec![
+                    Value::Text(name.to_string()),
+                    Value::Integer(non_unique as i64),
+                    Value::Text(key_name),
+                    Value::Integer(position as i64),
+                    Value::Text(column.name.clone()),
+                    Value::Text("A".to_string()),
+                    Value::Integer(row_count),
+                    Value::Null,
+                    Value::Null,
+                    Value::Text(null.to_string()),
+                    Value::Text("BTREE".to_string()),
+                    Value::Text(String::new()),
+                    Value::Text(String::new()),
+                    Value::Text("YES".to_string()),
+                    Value::Null,
+                ]
+            })
+            .collect();
+        self.show_result(INDEX_COLUMNS, rows, None).await
+    }
+
+    /// The schema a SHOW lists the tables of: the one named, or the first of the search path
+    /// that is not the default, or the database itself
+    fn shown_schema(&self, db: &Database, name: Option<&str>) -> crate::Result<String> {
+        let name = match name {
+            Some(name) => name.to_string(),
+            None => match self.search_path().first() {
+                Some(schema) if !schema.eq_ignore_ascii_case(DEFAULT_SCHEMA) => schema.clone(),
+                _ => return Ok(db.name.clone()),
+            },
+        };
+        if name.eq_ignore_ascii_case(&db.name) {
+            Ok(db.name.clone())
+        } else if db.has_schema(&name) {
+            Ok(name)
+        } else {
+            Err(YamlBaseError::Database {
+                message: format!("Unknown database '{}'", name),
+            })
+        }
+    }
+
+    /// The table a SHOW describes, if the user may see it
+    fn shown_table(&self, db: &Database, name: &ObjectName) -> crate::Result<Arc<Table>> {
+        let key = self.table_key(db, name);
+        self.visible_tables(db)
+            .into_iter()
+            .find(|(table_key, _)| table_key.eq_ignore_ascii_case(&key))
+            .map(|(_, table)| Arc::clone(table))
+            .ok_or_else(|| YamlBaseError::Database {
+                message: format!("Table '{}' not found", name),
+            })
+    }
+
+    /// The rows of a SHOW with the ones the filter leaves out taken away
+    pub(crate) async fn show_result(
+        &self,
+        columns: &[&str],
+        rows: Vec<Vec<Value>>,
+        filter: Option<&ShowStatementFilter>,
+    ) -> crate::Result<QueryResult> {
+        let mut table = Table::new(
+            "show".to_string(),
+            columns
+                .iter()
+                .enumerate()
+                .map(|(index, name)| Column {
+                    name: name.to_string(),
+                    sql_type: show_column_type(&rows, index),
+                    primary_key: false,
+                    nullable: true,
+                    unique: false,
+                    default: None,
+                    references: None,
+                    collation: None,
+                })
+                .collect(),
+        );
+        let condition = match filter {
+            None => None,
+            Some(ShowStatementFilter::Like(pattern)) => Some(Expr::Like {
+                negated: false,
+                any: false,
+                expr: Box::new(Expr::Identifier(Ident::new(columns[0]))),
+                pattern: Box::new(Expr::Value(SqlValue::SingleQuotedString(pattern.clone()))),
+                escape_char: None,
+            }),
+            Some(ShowStatementFilter::Where(expr)) => Some(expr.clone()),
+            Some(filter) => {
+                return Err(YamlBaseError::NotImplemented(format!(
+                    "SHOW ... {} is not supported, only LIKE and WHERE",
+                    filter
+                )));
+            }
+        };
+        for row in rows {
+            let matches = match &condition {
+                Some(condition) => self.evaluate_expr_async(condition, &row, &table).await?,
+                None => true,
+            };
+            if matches {
+                table.rows.push(row);
+            }
+        }
+
+        Ok(QueryResult {
+            columns: table
+                .columns
+                .iter()
+                .map(|column| column.name.clone())
+                .collect(),
+            column_types: table
+                .columns
+                .iter()
+                .map(|column| column.sql_type.clone())
+                .collect(),
+            rows: table.rows,
+            affected_rows: None,
+        })
+    }
+}
+
+/// The database a SHOW statement names, for a server to run it in that database's session
+pub fn shown_database(statement: &Statement) -> Option<String> {
+    let table_name = match statement {
+        Statement::ShowTables { db_name, .. } => {
+            return db_name.as_ref().map(|ident| ident.value.clone());
+        }
+        Statement::ShowColumns { table_name, .. } | Statement::ExplainTable { table_name, .. } => {
+            table_name.clone()
+        }
+        Statement::ShowVariable { variable } if QueryExecutor::is_show_index(variable) => {
+            show_index_table(variable)?
+        }
+        _ => return None,
+    };
+    match table_name.0.as_slice() {
+        [database, _] => Some(database.value.clone()),
+        _ => None,
+    }
+}
+
+/// The table of `INDEX FROM table`, `INDEX FROM db.table` or `INDEX FROM table FROM db`
+fn show_index_table(variable: &[Ident]) -> Option<ObjectName> {
+    let is_from = |word: &Ident| {
+        ["FROM", "IN"]
+            .iter()
+            .any(|keyword| word.value.eq_ignore_ascii_case(keyword))
+    };
+    let parts = match variable.get(1..)? {
+        [from, table] if is_from(from) => vec![table.clone()],
+        [from, database, table] if is_from(from) => vec![database.clone(), table.clone()],
+        [from, table, from_database, database] if is_from(from) && is_from(from_database) => {
+            vec![database.clone(), table.clone()]
+        }
+        _ => return None,
+    };
+    Some(ObjectName(parts))
+}
+
+/// Integers for the columns that have any, as SHOW INDEX's positions, text for the rest
+fn show_column_type(rows: &[Vec<Value>], index: usize) -> SqlType {
+    if rows
+        .iter()
+        .any(|row| matches!(row[index], Value::Integer(_)))
+    {
+        SqlType::BigInt
+    } else {
+        SqlType::Text
+    }
+}
+
+fn compare_text(a: &Value, b: &Value) -> std::cmp::Ordering {
+    match (a, b) {
+        (Value::Text(a), Value::Text(b)) => a.cmp(b),
+        _ => std::cmp::Ordering::Equal,
+    }
+}
+
+fn is_text(sql_type: &SqlType) -> bool {
+    matches!(
+        sql_type,
+        SqlType::Char(_) | SqlType::Varchar(_) | SqlType::Text | SqlType::Uuid
+    )
+}
+
+/// The MySQL name of a collation
+fn mysql_collation(collation: Collation) -> String {
+    match collation {
+        Collation::Binary => "utf8mb4_bin",
+        Collation::CiAscii => "ascii_general_ci",
+        Collation::CiUnicode => "utf8mb4_0900_ai_ci",
+    }
+    .to_string()
+}
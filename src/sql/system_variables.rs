@@ -0,0 +1,278 @@
+// MySQL system variables. Drivers read a burst of them when they connect (JDBC selects
+// @@sql_mode, @@time_zone, @@max_allowed_packet and more) and set others (autocommit,
+// sql_mode, character_set_results). A connection keeps the values it sets for as long as it
+// is open, whether they are set with SESSION or GLOBAL, and SHOW VARIABLES lists them with
+// the defaults of the rest.
+use sqlparser::ast::{
+    Expr, Ident, ObjectName, OneOrManyWithParens, ShowStatementFilter, Statement,
+    Value as SqlValue, VisitMut, VisitorMut,
+};
+use std::ops::ControlFlow;
+
+use crate::YamlBaseError;
+use crate::database::{TimeZone, Value};
+use crate::sql::RecursionLimits;
+use crate::sql::executor::{QueryExecutor, QueryResult};
+use crate::sql::namespace::SEARCH_PATH_SETTING;
+
+/// The MySQL version clients are told the server is
+pub const MYSQL_VERSION: &str = "8.0.35-yamlbase";
+
+/// The variables a connection can set, and their defaults
+const SYSTEM_VARIABLES: &[(&str, &str)] = &[
+    ("auto_increment_increment", "1"),
+    ("auto_increment_offset", "1"),
+    ("autocommit", "ON"),
+    ("character_set_client", "utf8mb4"),
+    ("character_set_connection", "utf8mb4"),
+    ("character_set_database", "utf8mb4"),
+    ("character_set_results", "utf8mb4"),
+    ("character_set_server", "utf8mb4"),
+    ("collation_connection", "utf8mb4_0900_ai_ci"),
+    ("collation_database", "utf8mb4_0900_ai_ci"),
+    ("collation_server", "utf8mb4_0900_ai_ci"),
+    ("foreign_key_checks", "ON"),
+    ("init_connect", ""),
+    ("interactive_timeout", "28800"),
+    ("lock_wait_timeout", "31536000"),
+    ("max_allowed_packet", "67108864"),
+    ("max_execution_time", "0"),
+    ("net_buffer_length", "16384"),
+    ("net_read_timeout", "30"),
+    ("net_write_timeout", "60"),
+    ("query_cache_size", "0"),
+    ("query_cache_type", "OFF"),
+    ("sql_auto_is_null", "OFF"),
+    ("sql_mode", DEFAULT_SQL_MODE),
+    ("sql_safe_updates", "OFF"),
+    ("sql_select_limit", "18446744073709551615"),
+    ("time_zone", "SYSTEM"),
+    ("transaction_isolation", "REPEATABLE-READ"),
+    ("transaction_read_only", "OFF"),
+    ("unique_checks", "ON"),
+    ("wait_timeout", "28800"),
+];
+
+/// The variables that can be read but not set
+const READ_ONLY_SYSTEM_VARIABLES: &[(&str, &str)] = &[
+    ("have_ssl", "YES"),
+    ("license", "GPL"),
+    ("lower_case_table_names", "0"),
+    ("performance_schema", "OFF"),
+    ("protocol_version", "10"),
+    ("system_time_zone", "UTC"),
+    ("version", MYSQL_VERSION),
+    ("version_comment", "1"),
+];
+
+/// The variables that are ON or OFF, which can be set to 1, 0, TRUE or FALSE as well
+const BOOLEAN_SYSTEM_VARIABLES: &[&str] = &[
+    "autocommit",
+    "foreign_key_checks",
+    "sql_auto_is_null",
+    "sql_safe_updates",
+    "transaction_read_only",
+    "unique_checks",
+];
+
+const DEFAULT_SQL_MODE: &str = "ONLY_FULL_GROUP_BY,STRICT_TRANS_TABLES,NO_ZERO_IN_DATE,\
+                                NO_ZERO_DATE,ERROR_FOR_DIVISION_BY_ZERO,NO_ENGINE_SUBSTITUTION";
+
+impl QueryExecutor {
+    /// The value of a system variable, named without `@@` or a scope, as SHOW VARIABLES
+    /// shows it. Variables that are neither known nor set have none.
+    pub fn system_variable(&self, name: &str) -> Option<String> {
+        let name = name.to_lowercase();
+        if let Some(value) = self.settings().values.get(&name) {
+            return Some(value.clone());
+        }
+        SYSTEM_VARIABLES
+            .iter()
+            .chain(READ_ONLY_SYSTEM_VARIABLES)
+            .find(|(variable, _)| *variable == name)
+            .map(|(_, default)| default.to_string())
+    }
+
+    /// The value `@@name` has in an expression: numbers and ON/OFF switches as integers,
+    /// the rest as text. Unknown variables read as '1'.
+    pub(crate) fn system_variable_value(&self, name: &str) -> Value {
+        let name = name.trim_start_matches('@');
+        let name = ["global.", "session.", "local."]
+            .iter()
+            .find_map(|scope| {
+                name.get(..scope.len())
+                    .filter(|prefix| prefix.eq_ignore_ascii_case(scope))
+                    .map(|_| &name[scope.len()..])
+            })
+            .unwrap_or(name);
+        let Some(value) = self.system_variable(name) else {
+            return Value::Text("1".to_string());
+        };
+        if is_boolean(name) {
+            return Value::Integer((value == "ON") as i64);
+        }
+        match value.parse::<i64>() {
+            Ok(number) => Value::Integer(number),
+            Err(_) => Value::Text(value),
+        }
+    }
+
+    /// Apply the assignments of a MySQL `SET`, in order
+    pub fn set_system_variables(&self, assignments: &[(String, Expr)]) -> crate::Result<()> {
+        for (name, value) in assignments {
+            self.set_system_variable(name, value)?;
+        }
+        Ok(())
+    }
+
+    fn set_system_variable(&self, name: &str, value: &Expr) -> crate::Result<()> {
+        // The engine's own settings, which SET applies in every dialect
+        if [
+            RecursionLimits::MAX_ITERATIONS_SETTING,
+            RecursionLimits::MAX_ROWS_SETTING,
+            SEARCH_PATH_SETTING,
+        ]
+        .contains(&name)
+        {
+            let statement = Statement::SetVariable {
+                local: false,
+                hivevar: false,
+                variables: OneOrManyWithParens::One(ObjectName(vec![Ident::new(name)])),
+                value: vec![value.clone()],
+            };
+            return self.execute_set_variable(&statement).map(|_| ());
+        }
+        if READ_ONLY_SYSTEM_VARIABLES
+            .iter()
+            .any(|(variable, _)| *variable == name)
+        {
+            return Err(YamlBaseError::Database {
+                message: format!("Variable '{}' is a read only variable", name),
+            });
+        }
+
+        let value = match value {
+            Expr::Identifier(ident)
+                if ident.quote_style.is_none() && ident.value.eq_ignore_ascii_case("DEFAULT") =>
+            {
+                self.settings().values.remove(name);
+                if name == "time_zone" {
+                    self.settings().time_zone = None;
+                }
+                return Ok(());
+            }
+            Expr::Identifier(ident) if !ident.value.starts_with('@') => ident.value.clone(),
+            Expr::Value(SqlValue::Boolean(true)) => "ON".to_string(),
+            Expr::Value(SqlValue::Boolean(false)) => "OFF".to_string(),
+            _ => {
+                let mut value = value.clone();
+                let _ = value.visit(&mut SystemVariableReader { executor: self });
+                match self.evaluate_constant_expr(&value)? {
+                    Value::Null => String::new(),
+                    Value::Text(text) => text,
+                    other => other.to_string(),
+                }
+            }
+        };
+        let value = match name {
+            _ if is_boolean(name) => match value.to_uppercase().as_str() {
+                "1" | "ON" | "TRUE" => "ON".to_string(),
+                "0" | "OFF" | "FALSE" => "OFF".to_string(),
+                _ => return Err(invalid_value(name, &value)),
+            },
+            "sql_mode" => value
+                .split(',')
+                .map(|mode| mode.trim().to_uppercase())
+                .filter(|mode| !mode.is_empty())
+                .collect::<Vec<_>>()
+                .join(","),
+            "time_zone" => {
+                self.settings().time_zone = if value.eq_ignore_ascii_case("SYSTEM") {
+                    None
+                } else {
+                    Some(
+                        TimeZone::parse(&value).map_err(|_| YamlBaseError::Database {
+                            message: format!("Unknown or incorrect time zone: '{}'", value),
+                        })?,
+                    )
+                };
+                value
+            }
+            _ => value,
+        };
+        self.settings().values.insert(name.to_string(), value);
+        Ok(())
+    }
+
+    /// SHOW [GLOBAL | SESSION] VARIABLES
+    pub(crate) async fn execute_show_variables(
+        &self,
+        filter: Option<&ShowStatementFilter>,
+    ) -> crate::Result<QueryResult> {
+        let mut names: Vec<String> = SYSTEM_VARIABLES
+            .iter()
+            .chain(READ_ONLY_SYSTEM_VARIABLES)
+            .map(|(name, _)| name.to_string())
+            .collect();
+        for name in self.settings().values.keys() {
+            if !names.contains(name) {
+                names.push(name.clone());
+            }
+        }
+        names.sort();
+        let rows = names
+            .into_iter()
+            .map(|name| {
+                let value = self.system_variable(&name).unwrap_or_default();
+                vec![Value::Text(name), Value::Text(value)]
+            })
+            .collect();
+        self.show_result(&["Variable_name", "Value"], rows, filter)
+            .await
+    }
+}
+
+/// Replaces the `@@name` a SET value reads, as in `CONCAT(@@sql_mode, ',ANSI_QUOTES')`,
+/// with the variable's value
+struct SystemVariableReader<'a> {
+    executor: &'a QueryExecutor,
+}
+
+impl VisitorMut for SystemVariableReader<'_> {
+    type Break = ();
+
+    fn pre_visit_expr(&mut self, expr: &mut Expr) -> ControlFlow<()> {
+        let name = match expr {
+            Expr::Identifier(ident) if ident.value.starts_with("@@") => ident.value.clone(),
+            Expr::CompoundIdentifier(parts)
+                if parts
+                    .first()
+                    .is_some_and(|part| part.value.starts_with("@@")) =>
+            {
+                let parts: Vec<&str> = parts.iter().map(|part| part.value.as_str()).collect();
+                parts.join(".")
+            }
+            _ => return ControlFlow::Continue(()),
+        };
+        *expr = Expr::Value(match self.executor.system_variable_value(&name) {
+            Value::Integer(number) => SqlValue::Number(number.to_string(), false),
+            value => SqlValue::SingleQuotedString(value.to_string()),
+        });
+        ControlFlow::Continue(())
+    }
+}
+
+fn is_boolean(name: &str) -> bool {
+    BOOLEAN_SYSTEM_VARIABLES
+        .iter()
+        .any(|variable| variable.eq_ignore_ascii_case(name))
+}
+
+fn invalid_value(name: &str, value: &str) -> YamlBaseError {
+    YamlBaseError::Database {
+        message: format!(
+            "Variable '{}' can't be set to the value of '{}'",
+            name, value
+        ),
+    }
+}
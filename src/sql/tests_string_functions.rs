@@ -1,8 +1,9 @@
 #[cfg(test)]
 mod tests {
     use crate::database::storage::Storage;
-    use crate::database::{Database, Value};
+    use crate::database::{Column, Database, Table, Value};
     use crate::sql::{QueryExecutor, parse_sql};
+    use crate::yaml::schema::SqlType;
     use std::sync::Arc;
 
     #[tokio::test]
@@ -79,4 +80,173 @@ mod tests {
         let query_result = result.unwrap();
         assert_eq!(query_result.rows[0][0], Value::Integer(9)); // "String" starts at position 9
     }
+
+    #[tokio::test]
+    async fn test_padding_and_splitting_functions() {
+        let mut db = Database::new("test_db".to_string());
+        let mut people = Table::new(
+            "people".to_string(),
+            vec![
+                Column {
+                    name: "id".to_string(),
+                    sql_type: SqlType::Integer,
+                    primary_key: true,
+                    nullable: false,
+                    unique: true,
+                    default: None,
+                    references: None,
+                    collation: None,
+                },
+                Column {
+                    name: "full_name".to_string(),
+                    sql_type: SqlType::Text,
+                    primary_key: false,
+                    nullable: true,
+                    unique: false,
+                    default: None,
+                    references: None,
+                    collation: None,
+                },
+            ],
+        );
+        people
+            .insert_row(vec![
+                Value::Integer(1),
+                Value::Text("ada LOVELACE".to_string()),
+            ])
+            .unwrap();
+        people
+            .insert_row(vec![Value::Integer(2), Value::Null])
+            .unwrap();
+        db.add_table(people).unwrap();
+        let executor = QueryExecutor::new(Arc::new(Storage::new(db)))
+            .await
+            .unwrap();
+
+        let queries = parse_sql(
+            "SELECT SPLIT_PART('a,b,c', ',', 2), SPLIT_PART('a,b,c', ',', 5), \
+             SPLIT_PART('a,b,c', ',', -1), LPAD('42', 5, '0'), RPAD('ab', 5, 'xy'), \
+             LPAD('hello', 3), REPEAT('ab', 3), REPEAT('ab', 0), REVERSE('héllo'), \
+             INITCAP('hello wORLD-foo_bar')",
+        )
+        .unwrap();
+        let result = executor.execute(&queries[0]).await.unwrap();
+        let expected = [
+            "b",
+            "",
+            "c",
+            "00042",
+            "abxyx",
+            "hel",
+            "ababab",
+            "",
+            "olléh",
+            "Hello World-Foo_Bar",
+        ];
+        assert_eq!(
+            result.rows[0],
+            expected
+                .iter()
+                .map(|s| Value::Text(s.to_string()))
+                .collect::<Vec<_>>()
+        );
+
+        // Per-row evaluation, with NULL propagation
+        let queries = parse_sql(
+            "SELECT INITCAP(full_name), SPLIT_PART(full_name, ' ', 2), RPAD(full_name, 4) \
+             FROM people ORDER BY id",
+        )
+        .unwrap();
+        let result = executor.execute(&queries[0]).await.unwrap();
+        assert_eq!(
+            result.rows,
+            vec![
+                vec![
+                    Value::Text("Ada Lovelace".to_string()),
+                    Value::Text("LOVELACE".to_string()),
+                    Value::Text("ada ".to_string()),
+                ],
+                vec![Value::Null, Value::Null, Value::Null],
+            ]
+        );
+
+        let queries =
+            parse_sql("SELECT id FROM people WHERE REVERSE(full_name) LIKE 'ECA%'").unwrap();
+        let result = executor.execute(&queries[0]).await.unwrap();
+        assert_eq!(result.rows, vec![vec![Value::Integer(1)]]);
+
+        let queries = parse_sql("SELECT SPLIT_PART('a,b', ',', 0)").unwrap();
+        assert!(executor.execute(&queries[0]).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_translate_and_position_functions() {
+        let db = Database::new("test_db".to_string());
+        let executor = QueryExecutor::new(Arc::new(Storage::new(db)))
+            .await
+            .unwrap();
+
+        let queries = parse_sql(
+            "SELECT TRANSLATE('12-34/56', '-/', '..'), TRANSLATE('hello', 'lo', 'L'), \
+             INSTR('banana', 'an'), INSTR('banana', 'an', 3), INSTR('banana', 'an', 1, 2), \
+             INSTR('banana', 'an', -1), INSTR('banana', 'x'), LOCATE('an', 'banana'), \
+             LOCATE('an', 'banana', 3), STRPOS('héllo', 'llo'), CHARINDEX('na', 'banana'), \
+             INDEX('banana', 'nan')",
+        )
+        .unwrap();
+        let result = executor.execute(&queries[0]).await.unwrap();
+        assert_eq!(
+            result.rows[0],
+            vec![
+                Value::Text("12.34.56".to_string()),
+                Value::Text("heLL".to_string()),
+                Value::Integer(2),
+                Value::Integer(4),
+                Value::Integer(4),
+                Value::Integer(4),
+                Value::Integer(0),
+                Value::Integer(2),
+                Value::Integer(4),
+                Value::Integer(3),
+                Value::Integer(3),
+                Value::Integer(3),
+            ]
+        );
+
+        let queries = parse_sql("SELECT INSTR(NULL, 'a'), LOCATE('a', 'abc', NULL)").unwrap();
+        let result = executor.execute(&queries[0]).await.unwrap();
+        assert_eq!(result.rows[0], vec![Value::Null, Value::Null]);
+    }
+
+    #[tokio::test]
+    async fn test_hash_functions() {
+        let db = Database::new("test_db".to_string());
+        let executor = QueryExecutor::new(Arc::new(Storage::new(db)))
+            .await
+            .unwrap();
+
+        let queries = parse_sql(
+            "SELECT MD5('hello'), SHA1('hello'), SHA2('hello', 256), CRC32('hello'), \
+             SHA2('hello', 100), MD5(NULL)",
+        )
+        .unwrap();
+        let result = executor.execute(&queries[0]).await.unwrap();
+        assert_eq!(
+            result.rows[0],
+            vec![
+                Value::Text("5d41402abc4b2a76b9719d911017c592".to_string()),
+                Value::Text("aaf4c61ddcc5e8a2dabede0f3b482cd9aea9434d".to_string()),
+                Value::Text(
+                    "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824".to_string()
+                ),
+                Value::Integer(907060870),
+                Value::Null,
+                Value::Null,
+            ]
+        );
+
+        let queries = parse_sql("SELECT SHA2('hello', 512)").unwrap();
+        let result = executor.execute(&queries[0]).await.unwrap();
+        assert!(matches!(&result.rows[0][0], Value::Text(s) if s.len() == 128));
+    }
 }
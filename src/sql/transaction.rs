@@ -0,0 +1,221 @@
+// BEGIN, COMMIT, ROLLBACK and savepoints. A transaction remembers the session overlay as it
+// was when it started and at each savepoint; rolling back puts that copy back. Outside a
+// transaction every statement commits on its own.
+use sqlparser::ast::{Ident, Statement};
+use tokio::sync::OwnedMutexGuard;
+use tracing::debug;
+
+use crate::YamlBaseError;
+use crate::database::OverlayTables;
+use crate::sql::SqlDialect;
+use crate::sql::executor::{QueryExecutor, QueryResult, WriteMode};
+
+#[derive(Default)]
+pub(crate) struct Transaction {
+    // The session overlay at BEGIN
+    start: OverlayTables,
+    savepoints: Vec<(Ident, OverlayTables)>,
+    // With --mutable, the shared write lock from the first write until COMMIT or ROLLBACK
+    writer: Option<OwnedMutexGuard<()>>,
+    // PostgreSQL refuses further statements after an error until the transaction ends
+    failed: bool,
+}
+
+impl QueryExecutor {
+    /// Whether a transaction started with BEGIN is still open
+    pub fn in_transaction(&self) -> bool {
+        self.transaction().lock().unwrap().is_some()
+    }
+
+    /// Whether the open transaction hit an error and only accepts ROLLBACK
+    pub fn transaction_failed(&self) -> bool {
+        self.transaction()
+            .lock()
+            .unwrap()
+            .as_ref()
+            .is_some_and(|transaction| transaction.failed)
+    }
+
+    /// Whether a statement starts or ends a transaction or works on its savepoints
+    pub fn is_transaction_statement(statement: &Statement) -> bool {
+        matches!(
+            statement,
+            Statement::StartTransaction { .. }
+                | Statement::Commit { .. }
+                | Statement::Rollback { .. }
+                | Statement::Savepoint { .. }
+                | Statement::ReleaseSavepoint { .. }
+        )
+    }
+
+    /// Start the transaction PostgreSQL runs the statements of a multi-statement query in,
+    /// so their changes are kept or discarded together. Returns false, starting nothing,
+    /// when a transaction is already open.
+    pub fn begin_implicit_transaction(&self) -> bool {
+        if self.in_transaction() {
+            return false;
+        }
+        self.begin();
+        true
+    }
+
+    /// End a transaction started by `begin_implicit_transaction`, committing it only if
+    /// every statement succeeded
+    pub async fn end_implicit_transaction(&self, succeeded: bool) -> crate::Result<()> {
+        if succeeded {
+            self.commit().await
+        } else {
+            self.rollback();
+            Ok(())
+        }
+    }
+
+    pub(crate) async fn execute_transaction_statement(
+        &self,
+        statement: &Statement,
+    ) -> crate::Result<QueryResult> {
+        match statement {
+            Statement::StartTransaction { .. } => self.begin(),
+            Statement::Commit { .. } => self.commit().await?,
+            Statement::Rollback {
+                savepoint: None, ..
+            } => self.rollback(),
+            Statement::Rollback {
+                savepoint: Some(name),
+                ..
+            } => self.rollback_to_savepoint(name)?,
+            Statement::Savepoint { name } => self.savepoint(name)?,
+            Statement::ReleaseSavepoint { name } => self.release_savepoint(name)?,
+            _ => unreachable!("not a transaction statement"),
+        }
+
+        Ok(QueryResult {
+            columns: vec![],
+            column_types: vec![],
+            rows: vec![],
+            affected_rows: None,
+        })
+    }
+
+    /// Fail statements sent after an error in a PostgreSQL transaction, as PostgreSQL does
+    pub(crate) fn check_transaction_usable(&self, statement: &Statement) -> crate::Result<()> {
+        let ends_transaction = matches!(
+            statement,
+            Statement::Commit { .. } | Statement::Rollback { .. }
+        );
+        if self.transaction_failed() && !ends_transaction {
+            return Err(YamlBaseError::TransactionAborted);
+        }
+        Ok(())
+    }
+
+    /// Note a failed statement; PostgreSQL then only accepts ROLLBACK in the transaction
+    pub(crate) fn fail_transaction(&self) {
+        if !matches!(self.dialect(), SqlDialect::PostgreSQL) {
+            return;
+        }
+        if let Some(transaction) = self.transaction().lock().unwrap().as_mut() {
+            transaction.failed = true;
+        }
+    }
+
+    /// Make sure an open transaction holds the shared write lock, so its changes cannot
+    /// overwrite those of another connection when it commits. Returns false outside a
+    /// transaction.
+    pub(crate) async fn hold_writer_for_transaction(&self) -> bool {
+        match self.transaction().lock().unwrap().as_ref() {
+            None => return false,
+            Some(transaction) if transaction.writer.is_some() => return true,
+            Some(_) => {}
+        }
+
+        let writer = self.storage().lock_writes().await;
+        if let Some(transaction) = self.transaction().lock().unwrap().as_mut() {
+            transaction.writer = Some(writer);
+        }
+        true
+    }
+
+    fn begin(&self) {
+        let mut transaction = self.transaction().lock().unwrap();
+        if transaction.is_some() {
+            debug!("BEGIN inside a transaction, keeping the open one");
+            return;
+        }
+        *transaction = Some(Transaction {
+            start: self.session().snapshot(),
+            ..Transaction::default()
+        });
+    }
+
+    async fn commit(&self) -> crate::Result<()> {
+        let Some(transaction) = self.transaction().lock().unwrap().take() else {
+            return Ok(());
+        };
+
+        if transaction.failed {
+            // COMMIT of a failed transaction rolls it back
+            self.session().restore(transaction.start);
+        } else if *self.write_mode() != WriteMode::Session {
+            // The write lock, if any, is released once the changes are in place
+            self.commit_session().await?;
+        }
+        Ok(())
+    }
+
+    fn rollback(&self) {
+        if let Some(transaction) = self.transaction().lock().unwrap().take() {
+            self.session().restore(transaction.start);
+        }
+    }
+
+    fn rollback_to_savepoint(&self, name: &Ident) -> crate::Result<()> {
+        let mut guard = self.transaction().lock().unwrap();
+        let transaction = open_transaction(&mut guard, "ROLLBACK TO SAVEPOINT")?;
+        let position = find_savepoint(transaction, name)?;
+
+        // The savepoint itself stays, so it can be rolled back to again
+        transaction.savepoints.truncate(position + 1);
+        transaction.failed = false;
+        self.session()
+            .restore(transaction.savepoints[position].1.clone());
+        Ok(())
+    }
+
+    fn savepoint(&self, name: &Ident) -> crate::Result<()> {
+        let mut guard = self.transaction().lock().unwrap();
+        let transaction = open_transaction(&mut guard, "SAVEPOINT")?;
+        transaction
+            .savepoints
+            .push((name.clone(), self.session().snapshot()));
+        Ok(())
+    }
+
+    fn release_savepoint(&self, name: &Ident) -> crate::Result<()> {
+        let mut guard = self.transaction().lock().unwrap();
+        let transaction = open_transaction(&mut guard, "RELEASE SAVEPOINT")?;
+        let position = find_savepoint(transaction, name)?;
+        transaction.savepoints.truncate(position);
+        Ok(())
+    }
+}
+
+fn open_transaction<'a>(
+    transaction: &'a mut Option<Transaction>,
+    statement: &str,
+) -> crate::Result<&'a mut Transaction> {
+    transaction.as_mut().ok_or_else(|| YamlBaseError::Database {
+        message: format!("{} can only be used in transaction blocks", statement),
+    })
+}
+
+// The most recent savepoint of that name, as a savepoint may be defined more than once
+fn find_savepoint(transaction: &Transaction, name: &Ident) -> crate::Result<usize> {
+    transaction
+        .savepoints
+        .iter()
+        .rposition(|(savepoint, _)| savepoint.value.eq_ignore_ascii_case(&name.value))
+        .ok_or_else(|| YamlBaseError::Database {
+            message: format!("Savepoint '{}' does not exist", name.value),
+        })
+}
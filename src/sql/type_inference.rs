@@ -0,0 +1,355 @@
+// Result types of projected expressions, derived from the types of the columns they read and
+// from operator and function signatures, so that drivers see e.g. `price * qty` as a number
+// rather than text.
+use crate::database::{Table, Value};
+use crate::sql::executor::QueryExecutor;
+use crate::yaml::schema::SqlType;
+use sqlparser::ast::{
+    BinaryOperator, DataType, ExactNumberInfo, Expr, TimezoneInfo, UnaryOperator, Value as SqlValue,
+};
+
+/// Looks up the type of a column given its optional table qualifier and its name
+pub(crate) type ColumnTypeLookup<'a> = dyn Fn(Option<&str>, &str) -> Option<SqlType> + 'a;
+
+/// How operands combine in arithmetic
+enum NumericClass {
+    Integer,
+    Decimal(u32, u32),
+    Float,
+}
+
+fn numeric_class(sql_type: &SqlType) -> Option<NumericClass> {
+    match sql_type {
+        SqlType::Integer | SqlType::BigInt => Some(NumericClass::Integer),
+        SqlType::Decimal(precision, scale) => Some(NumericClass::Decimal(*precision, *scale)),
+        SqlType::Float | SqlType::Double => Some(NumericClass::Float),
+        _ => None,
+    }
+}
+
+/// The precision and scale of a decimal literal's own digits, as `1.125` is DECIMAL(4, 3)
+fn decimal_literal_type(literal: &str) -> SqlType {
+    let digits = literal.trim_start_matches(['+', '-']);
+    let (integer, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+    let scale = fraction.len() as u32;
+    let precision = integer.trim_start_matches('0').len() as u32 + scale;
+    SqlType::Decimal(precision.max(1), scale)
+}
+
+fn is_integer(sql_type: &SqlType) -> bool {
+    matches!(sql_type, SqlType::Integer | SqlType::BigInt)
+}
+
+/// Whether a value can be sent to clients as a column of the given type
+fn value_fits(value: &Value, sql_type: &SqlType) -> bool {
+    match value {
+        Value::Null => true,
+        Value::Integer(i) => match sql_type {
+            SqlType::Integer => i32::try_from(*i).is_ok(),
+            SqlType::BigInt | SqlType::Decimal(..) | SqlType::Float | SqlType::Double => true,
+            _ => false,
+        },
+        Value::Float(_) | Value::Double(_) => {
+            matches!(sql_type, SqlType::Float | SqlType::Double)
+        }
+        Value::Decimal(_) => matches!(
+            sql_type,
+            SqlType::Decimal(..) | SqlType::Float | SqlType::Double
+        ),
+        Value::Text(_) => matches!(
+            sql_type,
+            SqlType::Text | SqlType::Varchar(_) | SqlType::Char(_)
+        ),
+        Value::Boolean(_) => *sql_type == SqlType::Boolean,
+        Value::Date(_) => *sql_type == SqlType::Date,
+        Value::Time(_) => *sql_type == SqlType::Time,
+        Value::Timestamp(_) => *sql_type == SqlType::Timestamp,
+        Value::TimestampTz(_) => *sql_type == SqlType::TimestampTz,
+        Value::Uuid(_) => matches!(sql_type, SqlType::Uuid | SqlType::Text),
+        Value::Json(_) => *sql_type == SqlType::Json,
+        Value::Bytes(_) => *sql_type == SqlType::Blob,
+        Value::Array(items) => match sql_type {
+            SqlType::Array(element_type) => items.iter().all(|item| value_fits(item, element_type)),
+            _ => false,
+        },
+    }
+}
+
+impl QueryExecutor {
+    /// The type `expr` evaluates to, or `None` when it depends on the values (or on
+    /// something this pass does not know about)
+    pub(crate) fn infer_expr_type(
+        expr: &Expr,
+        column_type: &ColumnTypeLookup<'_>,
+    ) -> Option<SqlType> {
+        let infer = |expr: &Expr| Self::infer_expr_type(expr, column_type);
+        match expr {
+            Expr::Identifier(ident) => column_type(None, &ident.value),
+            Expr::CompoundIdentifier(parts) => match parts.as_slice() {
+                [.., qualifier, column] => column_type(Some(&qualifier.value), &column.value),
+                [column] => column_type(None, &column.value),
+                [] => None,
+            },
+            Expr::Value(value) => match value {
+                SqlValue::Number(n, _) if n.contains(['e', 'E']) => Some(SqlType::Double),
+                SqlValue::Number(n, _) if n.contains('.') => Some(decimal_literal_type(n)),
+                SqlValue::Number(n, _) => match n.parse::<i32>() {
+                    Ok(_) => Some(SqlType::Integer),
+                    Err(_) => Some(SqlType::BigInt),
+                },
+                SqlValue::SingleQuotedString(_) => Some(SqlType::Text),
+                SqlValue::Boolean(_) => Some(SqlType::Boolean),
+                _ => None,
+            },
+            Expr::Nested(inner) => infer(inner),
+            Expr::Cast { data_type, .. } | Expr::TypedString { data_type, .. } => {
+                Self::data_type_to_sql_type(data_type)
+            }
+            Expr::UnaryOp { op, expr } => match op {
+                UnaryOperator::Not => Some(SqlType::Boolean),
+                UnaryOperator::Minus | UnaryOperator::Plus => {
+                    infer(expr).filter(|t| numeric_class(t).is_some())
+                }
+                _ => None,
+            },
+            Expr::BinaryOp { left, op, right } => match op {
+                BinaryOperator::Plus
+                | BinaryOperator::Minus
+                | BinaryOperator::Multiply
+                | BinaryOperator::Divide
+                | BinaryOperator::Modulo => {
+                    Self::arithmetic_type(&infer(left)?, op, &infer(right)?)
+                }
+                BinaryOperator::StringConcat => Some(SqlType::Text),
+                BinaryOperator::Eq
+                | BinaryOperator::NotEq
+                | BinaryOperator::Lt
+                | BinaryOperator::LtEq
+                | BinaryOperator::Gt
+                | BinaryOperator::GtEq
+                | BinaryOperator::And
+                | BinaryOperator::Or
+                | BinaryOperator::PGRegexMatch
+                | BinaryOperator::PGRegexIMatch
+                | BinaryOperator::PGRegexNotMatch
+                | BinaryOperator::PGRegexNotIMatch => Some(SqlType::Boolean),
+                _ => None,
+            },
+            Expr::IsNull(_)
+            | Expr::IsNotNull(_)
+            | Expr::IsTrue(_)
+            | Expr::IsNotTrue(_)
+            | Expr::IsFalse(_)
+            | Expr::IsNotFalse(_)
+            | Expr::InList { .. }
+            | Expr::InSubquery { .. }
+            | Expr::Between { .. }
+            | Expr::Like { .. }
+            | Expr::ILike { .. }
+            | Expr::RLike { .. }
+            | Expr::Exists { .. }
+            | Expr::AnyOp { .. }
+            | Expr::AllOp { .. } => Some(SqlType::Boolean),
+            Expr::Case {
+                results,
+                else_result,
+                ..
+            } => results
+                .iter()
+                .chain(else_result.as_deref())
+                .find_map(&infer),
+            Expr::Substring { .. } | Expr::Trim { .. } => Some(SqlType::Text),
+            Expr::Position { .. } => Some(SqlType::Integer),
+            Expr::Ceil { expr, .. } | Expr::Floor { expr, .. } => Self::rounded_type(&infer(expr)?),
+            Expr::Function(func) => {
+                let name = func
+                    .name
+                    .0
+                    .last()
+                    .map(|ident| ident.value.to_uppercase())
+                    .unwrap_or_default();
+                // PERCENTILE_DISC picks one of the values it orders
+                if name == "PERCENTILE_DISC" {
+                    return func
+                        .within_group
+                        .first()
+                        .and_then(|order| infer(&order.expr));
+                }
+                let args = Self::function_arg_exprs(func).unwrap_or_default();
+                let arg_type = |idx: usize| args.get(idx).and_then(|arg| infer(arg));
+                Self::function_type(&name, args.len(), arg_type)
+            }
+            _ => None,
+        }
+    }
+
+    /// Column types of a single table, whatever the qualifier
+    pub(crate) fn table_column_type(
+        table: &Table,
+    ) -> impl Fn(Option<&str>, &str) -> Option<SqlType> + '_ {
+        |_, name| {
+            table
+                .columns
+                .iter()
+                .find(|column| column.name.eq_ignore_ascii_case(name))
+                .map(|column| column.sql_type.clone())
+        }
+    }
+
+    /// The column type to report for a constant expression: the inferred type when the
+    /// value fits it, otherwise the type of the value
+    pub(crate) fn constant_expr_type(&self, expr: &Expr, value: &Value) -> SqlType {
+        Self::infer_expr_type(expr, &|_, _| None)
+            .filter(|t| value_fits(value, t))
+            .unwrap_or_else(|| self.constant_value_type(value))
+    }
+
+    /// The column type of a constant value: integers that fit in 32 bits are INTEGER
+    pub(crate) fn constant_value_type(&self, value: &Value) -> SqlType {
+        match value {
+            Value::Integer(i) if i32::try_from(*i).is_ok() => SqlType::Integer,
+            Value::Float(_) => SqlType::Double,
+            Value::Uuid(_) => SqlType::Uuid,
+            Value::Json(_) => SqlType::Text,
+            _ => self.infer_value_type(value),
+        }
+    }
+
+    /// The column type to report for a projected expression: the inferred type when every
+    /// value fits it, otherwise the type of the first non-NULL value
+    pub(crate) fn projected_expr_type<'a>(
+        &self,
+        inferred: Option<SqlType>,
+        values: impl IntoIterator<Item = &'a Value>,
+    ) -> SqlType {
+        let mut first_value = None;
+        for value in values {
+            if matches!(value, Value::Null) {
+                continue;
+            }
+            first_value.get_or_insert(value);
+            if inferred.as_ref().is_some_and(|t| !value_fits(value, t)) {
+                return self.infer_value_type(value);
+            }
+        }
+        inferred.unwrap_or_else(|| {
+            first_value.map_or(SqlType::Text, |value| self.infer_value_type(value))
+        })
+    }
+
+    fn arithmetic_type(left: &SqlType, op: &BinaryOperator, right: &SqlType) -> Option<SqlType> {
+        // Date arithmetic counts in days
+        match (left, op, right) {
+            (SqlType::Date, BinaryOperator::Plus | BinaryOperator::Minus, r) if is_integer(r) => {
+                return Some(SqlType::Date);
+            }
+            (l, BinaryOperator::Plus, SqlType::Date) if is_integer(l) => {
+                return Some(SqlType::Date);
+            }
+            (SqlType::Date, BinaryOperator::Minus, SqlType::Date) => return Some(SqlType::BigInt),
+            _ => {}
+        }
+
+        Some(match (numeric_class(left)?, numeric_class(right)?) {
+            (NumericClass::Integer, NumericClass::Integer) => match op {
+                BinaryOperator::Divide => SqlType::Double,
+                _ => SqlType::BigInt,
+            },
+            // A product has the digits of both factors, as 1.10 * 2.5 = 2.750
+            (NumericClass::Decimal(p1, s1), NumericClass::Decimal(p2, s2)) => match op {
+                BinaryOperator::Multiply => SqlType::Decimal(p1 + p2, s1 + s2),
+                _ => SqlType::Decimal(p1.max(p2), s1.max(s2)),
+            },
+            (NumericClass::Decimal(p, s), NumericClass::Integer)
+            | (NumericClass::Integer, NumericClass::Decimal(p, s)) => SqlType::Decimal(p, s),
+            (NumericClass::Float, _) | (_, NumericClass::Float) => SqlType::Double,
+        })
+    }
+
+    /// CEIL, FLOOR and ROUND without a precision keep integers and give doubles otherwise
+    fn rounded_type(arg: &SqlType) -> Option<SqlType> {
+        match numeric_class(arg)? {
+            NumericClass::Integer => Some(arg.clone()),
+            NumericClass::Decimal(..) | NumericClass::Float => Some(SqlType::Double),
+        }
+    }
+
+    fn function_type(
+        name: &str,
+        arg_count: usize,
+        arg_type: impl Fn(usize) -> Option<SqlType>,
+    ) -> Option<SqlType> {
+        match name {
+            "COUNT" => Some(SqlType::BigInt),
+            "SUM" | "AVG" | "PERCENTILE_CONT" | "MEDIAN" => Some(SqlType::Double),
+            name if Self::is_statistical_aggregate(name) => Some(SqlType::Double),
+            "MIN" | "MAX" | "ABS" | "NULLIF" => arg_type(0),
+            // MySQL ISNULL(expr) is 1 or 0, SQL Server ISNULL(expr, fallback) is IFNULL
+            "ISNULL" if arg_count == 1 => Some(SqlType::Integer),
+            "COALESCE" | "IFNULL" | "ISNULL" | "NVL" | "GREATEST" | "LEAST" => {
+                (0..arg_count).find_map(&arg_type)
+            }
+            "IF" => arg_type(1).or_else(|| arg_type(2)),
+            "CEIL" | "CEILING" | "FLOOR" => Self::rounded_type(&arg_type(0)?),
+            "ROUND" if arg_count == 1 => Self::rounded_type(&arg_type(0)?),
+            "ROUND" => match numeric_class(&arg_type(0)?)? {
+                // ROUND(n, 0) keeps integers, other precisions turn them into doubles
+                NumericClass::Integer => None,
+                NumericClass::Decimal(..) | NumericClass::Float => Some(SqlType::Double),
+            },
+            "UPPER" | "LOWER" | "TRIM" | "LTRIM" | "RTRIM" | "SUBSTRING" | "SUBSTR" | "CONCAT"
+            | "CONCAT_WS" | "LEFT" | "RIGHT" | "REPLACE" | "LPAD" | "RPAD" | "REPEAT"
+            | "REVERSE" | "INITCAP" | "TRANSLATE" | "SPLIT_PART" | "REGEXP_SUBSTR"
+            | "REGEXP_REPLACE" | "MD5" | "SHA1" | "SHA" | "SHA2" | "DAYNAME" | "MONTHNAME"
+            | "VERSION" => Some(SqlType::Text),
+            "LENGTH" | "CHAR_LENGTH" | "CHARACTER_LENGTH" | "POSITION" | "INSTR" | "LOCATE"
+            | "STRPOS" | "CHARINDEX" | "INDEX" | "YEAR" | "MONTH" | "DAY" | "WEEKDAY"
+            | "DAYOFWEEK" => Some(SqlType::Integer),
+            "MOD" | "CRC32" | "LAST_INSERT_ID" | "LASTVAL" | "CURRVAL" => Some(SqlType::BigInt),
+            "SQRT" | "EXP" | "LN" | "LOG" | "LOG10" | "LOG2" | "POWER" | "POW" | "RAND"
+            | "RANDOM" => Some(SqlType::Double),
+            "REGEXP_LIKE" => Some(SqlType::Boolean),
+            "CURRENT_DATE" | "TO_DATE" | "LAST_DAY" => Some(SqlType::Date),
+            "TO_TIMESTAMP" => Some(SqlType::Timestamp),
+            _ => None,
+        }
+    }
+
+    /// The column type of a CAST target or typed literal
+    fn data_type_to_sql_type(data_type: &DataType) -> Option<SqlType> {
+        Some(match data_type {
+            DataType::Int(_)
+            | DataType::Integer(_)
+            | DataType::SmallInt(_)
+            | DataType::TinyInt(_)
+            | DataType::Int2(_)
+            | DataType::Int4(_) => SqlType::Integer,
+            DataType::BigInt(_) | DataType::Int8(_) | DataType::Int64 => SqlType::BigInt,
+            DataType::Float(_) | DataType::Real | DataType::Float4 => SqlType::Float,
+            DataType::Double | DataType::DoublePrecision | DataType::Float8 | DataType::Float64 => {
+                SqlType::Double
+            }
+            DataType::Decimal(info) | DataType::Numeric(info) | DataType::Dec(info) => match info {
+                ExactNumberInfo::None => SqlType::Decimal(10, 2),
+                ExactNumberInfo::Precision(p) => SqlType::Decimal(*p as u32, 0),
+                ExactNumberInfo::PrecisionAndScale(p, s) => SqlType::Decimal(*p as u32, *s as u32),
+            },
+            DataType::Text
+            | DataType::String(_)
+            | DataType::Varchar(_)
+            | DataType::Char(_)
+            | DataType::Character(_)
+            | DataType::CharVarying(_)
+            | DataType::CharacterVarying(_)
+            | DataType::Nvarchar(_) => SqlType::Text,
+            DataType::Boolean | DataType::Bool => SqlType::Boolean,
+            DataType::Date => SqlType::Date,
+            DataType::Time(..) => SqlType::Time,
+            DataType::Timestamp(_, TimezoneInfo::WithTimeZone | TimezoneInfo::Tz) => {
+                SqlType::TimestampTz
+            }
+            DataType::Timestamp(..) | DataType::Datetime(_) => SqlType::Timestamp,
+            _ => return None,
+        })
+    }
+}
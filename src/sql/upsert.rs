@@ -0,0 +1,470 @@
+// Upserts: INSERT ... ON CONFLICT (PostgreSQL), INSERT ... ON DUPLICATE KEY UPDATE (MySQL)
+// and MERGE. A new row conflicts with an existing one when they share the value of the
+// primary key or another unique column; the existing row is then updated, or kept as it is.
+// Like the other writes, upserts change the connection's session overlay.
+use sqlparser::ast::{
+    Assignment, ConflictTarget, Expr, FunctionArg, FunctionArgExpr, FunctionArguments, Ident,
+    MergeAction, MergeClause, MergeClauseKind, MergeInsertExpr, MergeInsertKind, OnConflictAction,
+    OnInsert, TableFactor,
+};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use crate::YamlBaseError;
+use crate::database::{Table, Value};
+use crate::sql::SqlDialect;
+use crate::sql::dml::{assignment_columns, check_unique_columns, insert_targets};
+use crate::sql::executor::{QueryExecutor, QueryResult};
+
+// The name ON CONFLICT DO UPDATE gives the row that was proposed for insertion
+const EXCLUDED: &str = "excluded";
+
+impl QueryExecutor {
+    /// Insert rows, updating or keeping the existing rows they conflict with as the ON
+    /// clause says. Returns the affected rows as the dialect counts them, and the rows
+    /// inserted or updated.
+    pub(super) fn upsert_rows(
+        &self,
+        table: &mut Table,
+        on: &OnInsert,
+        alias: Option<&Ident>,
+        new_rows: Vec<Vec<Value>>,
+    ) -> crate::Result<(usize, Vec<Vec<Value>>)> {
+        let (keys, assignments, selection) = match on {
+            // MySQL's VALUES(column) is the value the row would have been inserted with
+            OnInsert::DuplicateKeyUpdate(assignments) => (
+                conflict_keys(table, None)?,
+                Some(assignments.iter().map(rewrite_values_function).collect()),
+                None,
+            ),
+            OnInsert::OnConflict(conflict) => {
+                let keys = match &conflict.conflict_target {
+                    None => conflict_keys(table, None)?,
+                    Some(ConflictTarget::Columns(columns)) => conflict_keys(table, Some(columns))?,
+                    Some(ConflictTarget::OnConstraint(_)) => {
+                        return Err(YamlBaseError::NotImplemented(
+                            "ON CONFLICT ON CONSTRAINT is not supported".to_string(),
+                        ));
+                    }
+                };
+                match &conflict.action {
+                    OnConflictAction::DoNothing => (keys, None, None),
+                    OnConflictAction::DoUpdate(update) => (
+                        keys,
+                        Some(update.assignments.clone()),
+                        update.selection.as_ref(),
+                    ),
+                }
+            }
+            _ => {
+                return Err(YamlBaseError::NotImplemented(format!(
+                    "INSERT ... {} is not supported",
+                    on
+                )));
+            }
+        };
+        let columns = assignments
+            .as_ref()
+            .map(|assignments: &Vec<Assignment>| assignment_columns(table, assignments))
+            .transpose()?;
+
+        let mut aliases = HashMap::new();
+        aliases.insert(EXCLUDED.to_uppercase(), EXCLUDED.to_string());
+        if let Some(alias) = alias {
+            aliases.insert(alias.value.clone(), table.name.clone());
+        }
+
+        // MySQL counts an updated row twice and an unchanged one not at all
+        let mysql = matches!(self.dialect(), SqlDialect::MySQL);
+        let mut affected = 0;
+        let mut written = Vec::new();
+        let mut touched = HashSet::new();
+        for new_row in new_rows {
+            let Some(index) = conflicting_row(table, &keys, &new_row) else {
+                check_unique_columns(table, table.rows.iter(), std::slice::from_ref(&new_row))?;
+                self.check_constraints(table, std::slice::from_ref(&new_row))?;
+                touched.insert(table.rows.len());
+                written.push(new_row.clone());
+                table.rows.push(new_row);
+                affected += 1;
+                continue;
+            };
+            let (Some(assignments), Some(columns)) = (&assignments, &columns) else {
+                continue;
+            };
+            if !mysql && !touched.insert(index) {
+                return Err(YamlBaseError::Database {
+                    message: "ON CONFLICT DO UPDATE cannot affect a row a second time".to_string(),
+                });
+            }
+
+            let updated = {
+                let existing = &table.rows[index];
+                let tables = [
+                    (table.name.clone(), &*table),
+                    (EXCLUDED.to_string(), &*table),
+                ];
+                let combined: Vec<Value> = existing.iter().chain(&new_row).cloned().collect();
+                if let Some(selection) = selection
+                    && !self.evaluate_join_condition(selection, &combined, &tables, &aliases)?
+                {
+                    continue;
+                }
+                self.assign_row(table, existing, columns, assignments, |expr| {
+                    self.get_join_expr_value(expr, &combined, &tables, &aliases)
+                })?
+            };
+            written.push(updated.clone());
+            if updated == table.rows[index] {
+                affected += usize::from(!mysql);
+                continue;
+            }
+
+            let others = table
+                .rows
+                .iter()
+                .enumerate()
+                .filter(|(other, _)| *other != index)
+                .map(|(_, row)| row);
+            check_unique_columns(table, others, std::slice::from_ref(&updated))?;
+            self.check_constraints(table, std::slice::from_ref(&updated))?;
+            table.rows[index] = updated;
+            affected += if mysql { 2 } else { 1 };
+        }
+        Ok((affected, written))
+    }
+
+    pub(super) async fn execute_merge(
+        &self,
+        target: &TableFactor,
+        source: &TableFactor,
+        on: &Expr,
+        clauses: &[MergeClause],
+    ) -> crate::Result<QueryResult> {
+        let TableFactor::Table {
+            name,
+            alias: target_alias,
+            ..
+        } = target
+        else {
+            return Err(YamlBaseError::NotImplemented(
+                "MERGE INTO only supports a table".to_string(),
+            ));
+        };
+        if clauses
+            .iter()
+            .any(|clause| clause.clause_kind == MergeClauseKind::NotMatchedBySource)
+        {
+            return Err(YamlBaseError::NotImplemented(
+                "WHEN NOT MATCHED BY SOURCE is not supported".to_string(),
+            ));
+        }
+        let table_name = last_name_part(&name.0);
+
+        // The source is read first, as a subquery takes the database lock itself
+        let db_arc = self.storage().database();
+        let (source_name, source_table) = match source {
+            TableFactor::Table { name, alias, .. } => {
+                let source_name = last_name_part(&name.0);
+                let table = {
+                    let shared = db_arc.read().await;
+                    self.session().table(&shared, &source_name)
+                }
+                .ok_or_else(|| YamlBaseError::Database {
+                    message: format!("Table '{}' not found", source_name),
+                })?;
+                let source_name = alias
+                    .as_ref()
+                    .map_or(source_name, |alias| alias.name.value.clone());
+                (source_name, table)
+            }
+            TableFactor::Derived {
+                subquery,
+                alias: Some(alias),
+                ..
+            } => {
+                let result = self.execute_query(subquery).await?;
+                let source_name = alias.name.value.clone();
+                let table = self.table_from_result(source_name.clone(), result)?;
+                (source_name, Arc::new(table))
+            }
+            _ => {
+                return Err(YamlBaseError::NotImplemented(
+                    "MERGE ... USING only supports a table or a subquery with an alias".to_string(),
+                ));
+            }
+        };
+
+        let shared = db_arc.read().await;
+        let table =
+            self.session()
+                .table(&shared, &table_name)
+                .ok_or_else(|| YamlBaseError::Database {
+                    message: format!("Table '{}' not found", table_name),
+                })?;
+        let tables = [
+            (table.name.clone(), table.as_ref()),
+            (source_name, source_table.as_ref()),
+        ];
+        let mut aliases = HashMap::new();
+        if let Some(alias) = target_alias {
+            aliases.insert(alias.name.value.clone(), table.name.clone());
+        }
+
+        // Changes are worked out on the rows as they were before the statement. A changed
+        // row of `None` is deleted.
+        let mut changes: HashMap<usize, Option<Vec<Value>>> = HashMap::new();
+        let mut inserts = Vec::new();
+        let unmatched = vec![Value::Null; table.columns.len()];
+        for source_row in &source_table.rows {
+            let mut matched = false;
+            for (index, row) in table.rows.iter().enumerate() {
+                let combined: Vec<Value> = row.iter().chain(source_row).cloned().collect();
+                if !self.evaluate_join_condition(on, &combined, &tables, &aliases)? {
+                    continue;
+                }
+                matched = true;
+
+                let Some(action) =
+                    self.merge_action(clauses, true, &combined, &tables, &aliases)?
+                else {
+                    continue;
+                };
+                if changes.contains_key(&index) {
+                    return Err(YamlBaseError::Database {
+                        message: "MERGE cannot affect a row a second time".to_string(),
+                    });
+                }
+                let change = match action {
+                    MergeAction::Update { assignments } => {
+                        let columns = assignment_columns(&table, assignments)?;
+                        Some(self.assign_row(&table, row, &columns, assignments, |expr| {
+                            self.get_join_expr_value(expr, &combined, &tables, &aliases)
+                        })?)
+                    }
+                    MergeAction::Delete => None,
+                    MergeAction::Insert(_) => {
+                        return Err(YamlBaseError::Database {
+                            message: "WHEN MATCHED cannot INSERT".to_string(),
+                        });
+                    }
+                };
+                changes.insert(index, change);
+            }
+
+            if !matched {
+                let combined: Vec<Value> = unmatched.iter().chain(source_row).cloned().collect();
+                match self.merge_action(clauses, false, &combined, &tables, &aliases)? {
+                    Some(MergeAction::Insert(insert)) => inserts.push(self.merge_insert_row(
+                        &table, insert, &combined, &tables, &aliases, source_row,
+                    )?),
+                    Some(_) => {
+                        return Err(YamlBaseError::Database {
+                            message: "WHEN NOT MATCHED can only INSERT".to_string(),
+                        });
+                    }
+                    None => {}
+                }
+            }
+        }
+
+        let affected = changes.len() + inserts.len();
+        if affected > 0 {
+            self.session().update(&shared, &table_name, |table| {
+                let mut inserts = inserts;
+                self.generate_ids(table, &mut inserts);
+                self.compute_columns(table, &mut inserts)?;
+                let mut rows = Vec::with_capacity(table.rows.len() + inserts.len());
+                let mut new_rows = Vec::new();
+                for (index, row) in table.rows.iter().enumerate() {
+                    match changes.remove(&index) {
+                        None => rows.push(row.clone()),
+                        Some(Some(updated)) => new_rows.push(updated),
+                        Some(None) => {}
+                    }
+                }
+                new_rows.extend(inserts);
+                check_unique_columns(table, rows.iter(), &new_rows)?;
+                self.check_constraints(table, &new_rows)?;
+
+                rows.extend(new_rows);
+                table.rows = rows;
+                Ok(())
+            })?;
+        }
+
+        Ok(QueryResult {
+            columns: vec![],
+            column_types: vec![],
+            rows: vec![],
+            affected_rows: Some(affected as u64),
+        })
+    }
+
+    /// The action of the first WHEN clause that applies to a matched or unmatched row
+    fn merge_action<'a>(
+        &self,
+        clauses: &'a [MergeClause],
+        matched: bool,
+        row: &[Value],
+        tables: &[(String, &Table)],
+        aliases: &HashMap<String, String>,
+    ) -> crate::Result<Option<&'a MergeAction>> {
+        for clause in clauses {
+            if (clause.clause_kind == MergeClauseKind::Matched) != matched {
+                continue;
+            }
+            let applies = match &clause.predicate {
+                Some(predicate) => self.evaluate_join_condition(predicate, row, tables, aliases)?,
+                None => true,
+            };
+            if applies {
+                return Ok(Some(&clause.action));
+            }
+        }
+        Ok(None)
+    }
+
+    fn merge_insert_row(
+        &self,
+        table: &Table,
+        insert: &MergeInsertExpr,
+        row: &[Value],
+        tables: &[(String, &Table)],
+        aliases: &HashMap<String, String>,
+        source_row: &[Value],
+    ) -> crate::Result<Vec<Value>> {
+        let targets = if insert.columns.is_empty() {
+            (0..table.columns.len()).collect()
+        } else {
+            insert_targets(table, &insert.columns)?
+        };
+        let values = match &insert.kind {
+            MergeInsertKind::Values(values) => {
+                let [exprs] = values.rows.as_slice() else {
+                    return Err(YamlBaseError::Database {
+                        message: "MERGE can only INSERT one row at a time".to_string(),
+                    });
+                };
+                exprs
+                    .iter()
+                    .map(|expr| match expr {
+                        Expr::Identifier(ident) if ident.value.eq_ignore_ascii_case("DEFAULT") => {
+                            Ok(None)
+                        }
+                        _ => self
+                            .get_join_expr_value(expr, row, tables, aliases)
+                            .map(Some),
+                    })
+                    .collect::<crate::Result<Vec<_>>>()?
+            }
+            MergeInsertKind::Row => source_row.iter().cloned().map(Some).collect(),
+        };
+        self.build_insert_row(table, &targets, values)
+    }
+}
+
+/// The columns a new row may conflict on: the one ON CONFLICT names, otherwise the
+/// primary key and unique columns
+fn conflict_keys(table: &Table, target: Option<&[Ident]>) -> crate::Result<Vec<usize>> {
+    let Some(target) = target else {
+        let keys: Vec<usize> = table
+            .columns
+            .iter()
+            .enumerate()
+            .filter(|(_, column)| column.primary_key || column.unique)
+            .map(|(index, _)| index)
+            .collect();
+        if keys.is_empty() {
+            return Err(YamlBaseError::Database {
+                message: format!(
+                    "Table '{}' has no primary key or unique column to detect conflicts on",
+                    table.name
+                ),
+            });
+        }
+        return Ok(keys);
+    };
+
+    let [column] = target else {
+        return Err(YamlBaseError::NotImplemented(
+            "ON CONFLICT over more than one column is not supported".to_string(),
+        ));
+    };
+    let index = table
+        .get_column_index(&column.value)
+        .ok_or_else(|| YamlBaseError::Database {
+            message: format!(
+                "Column '{}' not found in table '{}'",
+                column.value, table.name
+            ),
+        })?;
+    let column = &table.columns[index];
+    if !column.primary_key && !column.unique {
+        return Err(YamlBaseError::Database {
+            message: format!(
+                "ON CONFLICT ({}) does not name a primary key or unique column",
+                column.name
+            ),
+        });
+    }
+    Ok(vec![index])
+}
+
+fn conflicting_row(table: &Table, keys: &[usize], new_row: &[Value]) -> Option<usize> {
+    table.rows.iter().position(|row| {
+        keys.iter()
+            .any(|&key| !matches!(new_row[key], Value::Null) && row[key] == new_row[key])
+    })
+}
+
+fn rewrite_values_function(assignment: &Assignment) -> Assignment {
+    Assignment {
+        target: assignment.target.clone(),
+        value: excluded_references(&assignment.value),
+    }
+}
+
+// `VALUES(column)` turned into `excluded.column`
+fn excluded_references(expr: &Expr) -> Expr {
+    match expr {
+        Expr::Function(function) if function.name.to_string().eq_ignore_ascii_case("VALUES") => {
+            if let FunctionArguments::List(list) = &function.args
+                && let [FunctionArg::Unnamed(FunctionArgExpr::Expr(Expr::Identifier(column)))] =
+                    list.args.as_slice()
+            {
+                return Expr::CompoundIdentifier(vec![Ident::new(EXCLUDED), column.clone()]);
+            }
+            expr.clone()
+        }
+        Expr::Function(function) => {
+            let mut function = function.clone();
+            if let FunctionArguments::List(list) = &mut function.args {
+                for arg in &mut list.args {
+                    if let FunctionArg::Unnamed(FunctionArgExpr::Expr(arg)) = arg {
+                        *arg = excluded_references(arg);
+                    }
+                }
+            }
+            Expr::Function(function)
+        }
+        Expr::BinaryOp { left, op, right } => Expr::BinaryOp {
+            left: Box::new(excluded_references(left)),
+            op: op.clone(),
+            right: Box::new(excluded_references(right)),
+        },
+        Expr::UnaryOp { op, expr } => Expr::UnaryOp {
+            op: *op,
+            expr: Box::new(excluded_references(expr)),
+        },
+        Expr::Nested(inner) => Expr::Nested(Box::new(excluded_references(inner))),
+        _ => expr.clone(),
+    }
+}
+
+fn last_name_part(parts: &[Ident]) -> String {
+    parts
+        .last()
+        .map(|ident| ident.value.clone())
+        .unwrap_or_default()
+}
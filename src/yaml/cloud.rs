@@ -0,0 +1,38 @@
+// Database files in S3 or Google Cloud Storage, named by s3://, gs:// or gcs:// URLs. Only
+// built with the `object-store` feature. Credentials, region and endpoint come from the
+// environment, as the AWS and Google tools read them: AWS_ACCESS_KEY_ID,
+// AWS_SECRET_ACCESS_KEY, AWS_REGION and AWS_ENDPOINT, or GOOGLE_APPLICATION_CREDENTIALS,
+// falling back to the instance metadata service.
+use object_store::ObjectStore;
+use object_store::aws::AmazonS3Builder;
+use object_store::gcp::GoogleCloudStorageBuilder;
+use object_store::path::Path as ObjectPath;
+use std::io;
+
+/// The contents of the object at a URL
+pub(crate) async fn fetch_object(url: &str) -> io::Result<Vec<u8>> {
+    let error = |e: object_store::Error| io::Error::other(format!("Cannot fetch {}: {}", url, e));
+    let (scheme, location) = url.split_once("://").unwrap_or(("", url));
+    let (bucket, key) = location
+        .split_once('/')
+        .filter(|(bucket, key)| !bucket.is_empty() && !key.is_empty())
+        .ok_or_else(|| io::Error::other(format!("{} does not name a bucket and object", url)))?;
+
+    let store: Box<dyn ObjectStore> = if scheme.eq_ignore_ascii_case("s3") {
+        Box::new(
+            AmazonS3Builder::from_env()
+                .with_bucket_name(bucket)
+                .build()
+                .map_err(error)?,
+        )
+    } else {
+        Box::new(
+            GoogleCloudStorageBuilder::from_env()
+                .with_bucket_name(bucket)
+                .build()
+                .map_err(error)?,
+        )
+    };
+    let object = store.get(&ObjectPath::from(key)).await.map_err(error)?;
+    Ok(object.bytes().await.map_err(error)?.to_vec())
+}
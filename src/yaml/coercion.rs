@@ -0,0 +1,181 @@
+// How values of `data:` are converted when their YAML type is not their column's. Lenient,
+// the default, converts the values below; strict, chosen with --strict-types or a table's
+// `strict_types: true`, only takes values already of the column's type.
+//
+// Lenient conversions:
+// - a string holding a value of the column's type is read the way a CSV field is: "12" for
+//   INTEGER, "9.99" for DECIMAL, "yes" or "f" for BOOLEAN, JSON text for JSON, "{a,b}" or
+//   JSON text for arrays
+// - numbers and booleans in text columns become their text
+// - 0 and 1 in BOOLEAN columns are false and true
+//
+// Both modes take integers for FLOAT, DOUBLE and DECIMAL columns, strings for dates,
+// times, timestamps, UUIDs and BLOBs, mappings, lists, numbers and booleans for JSON, and
+// read values with environment variables as text.
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
+use std::sync::RwLock;
+
+use crate::YamlBaseError;
+use crate::database::Value as DbValue;
+use crate::yaml::parser::{parse_value, text_value};
+use crate::yaml::schema::SqlType;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum Coercion {
+    #[default]
+    Lenient,
+    Strict,
+}
+
+static DEFAULT_COERCION: RwLock<Coercion> = RwLock::new(Coercion::Lenient);
+
+impl Coercion {
+    /// The server-wide mode, for tables that do not choose their own. Lenient unless set
+    /// with `set_default`.
+    pub fn default_mode() -> Self {
+        *DEFAULT_COERCION.read().unwrap()
+    }
+
+    pub fn set_default(coercion: Coercion) {
+        *DEFAULT_COERCION.write().unwrap() = coercion;
+    }
+
+    /// The mode of a table with `strict_types:` set or not
+    pub fn for_table(strict_types: Option<bool>) -> Self {
+        match strict_types {
+            Some(true) => Coercion::Strict,
+            Some(false) => Coercion::Lenient,
+            None => Self::default_mode(),
+        }
+    }
+
+    /// A YAML value as a value of the column's type
+    pub(crate) fn convert(self, value: &Value, sql_type: &SqlType) -> crate::Result<DbValue> {
+        match self {
+            Coercion::Strict if !is_exact(value, sql_type) => {
+                Err(YamlBaseError::TypeConversion(format!(
+                    "{} is {}, not {:?}, and strict types do not convert it",
+                    serde_json::to_string(value).unwrap_or_else(|_| format!("{:?}", value)),
+                    kind(value),
+                    sql_type
+                )))
+            }
+            Coercion::Strict => parse_value(value, sql_type),
+            Coercion::Lenient => parse_value(value, sql_type)
+                .or_else(|error| lenient_value(value, sql_type).ok_or(error)),
+        }
+    }
+}
+
+/// Whether a YAML value is of a type a column takes without conversion
+fn is_exact(value: &Value, sql_type: &SqlType) -> bool {
+    match (value, sql_type) {
+        (Value::Null, _) => true,
+        (Value::Bool(_), SqlType::Boolean) => true,
+        (Value::Number(n), SqlType::Integer | SqlType::BigInt) => n.is_i64() || n.is_u64(),
+        (Value::Number(_), SqlType::Float | SqlType::Double | SqlType::Decimal(_, _)) => true,
+        (
+            Value::String(_),
+            SqlType::Char(_)
+            | SqlType::Varchar(_)
+            | SqlType::Text
+            | SqlType::Timestamp
+            | SqlType::TimestampTz
+            | SqlType::Date
+            | SqlType::Time
+            | SqlType::Uuid
+            | SqlType::Blob,
+        ) => true,
+        (
+            Value::Mapping(_)
+            | Value::Sequence(_)
+            | Value::Number(_)
+            | Value::Bool(_)
+            | Value::Tagged(_),
+            SqlType::Json,
+        ) => true,
+        (Value::Sequence(items), SqlType::Array(element_type)) => {
+            items.iter().all(|item| is_exact(item, element_type))
+        }
+        _ => false,
+    }
+}
+
+fn lenient_value(value: &Value, sql_type: &SqlType) -> Option<DbValue> {
+    let text = matches!(
+        sql_type,
+        SqlType::Char(_) | SqlType::Varchar(_) | SqlType::Text
+    );
+    match value {
+        Value::String(field) => text_value(field, sql_type).ok(),
+        Value::Number(n) if text => Some(DbValue::Text(n.to_string())),
+        Value::Bool(b) if text => Some(DbValue::Text(b.to_string())),
+        Value::Number(n) if *sql_type == SqlType::Boolean => match n.as_i64() {
+            Some(0) => Some(DbValue::Boolean(false)),
+            Some(1) => Some(DbValue::Boolean(true)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn kind(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Sequence(_) => "a list",
+        Value::Mapping(_) => "a mapping",
+        Value::Tagged(_) => "a tagged value",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn yaml(text: &str) -> Value {
+        serde_yaml::from_str(text).unwrap()
+    }
+
+    #[test]
+    fn test_lenient_and_strict() {
+        let cases = [
+            ("'12'", SqlType::Integer, Some(DbValue::Integer(12))),
+            ("12", SqlType::Text, Some(DbValue::Text("12".to_string()))),
+            (
+                "true",
+                SqlType::Varchar(10),
+                Some(DbValue::Text("true".to_string())),
+            ),
+            ("'yes'", SqlType::Boolean, Some(DbValue::Boolean(true))),
+            ("0", SqlType::Boolean, Some(DbValue::Boolean(false))),
+            (
+                "'[1, 2]'",
+                SqlType::Json,
+                Some(DbValue::Json(serde_json::json!([1, 2]))),
+            ),
+            ("1.5", SqlType::Integer, None),
+            ("'many'", SqlType::Integer, None),
+        ];
+        for (text, sql_type, expected) in cases {
+            let value = yaml(text);
+            let lenient = Coercion::Lenient.convert(&value, &sql_type).ok();
+            assert_eq!(lenient, expected, "{} as {:?}", text, sql_type);
+            assert!(Coercion::Strict.convert(&value, &sql_type).is_err());
+        }
+
+        for (text, sql_type) in [
+            ("12", SqlType::Integer),
+            ("12", SqlType::Double),
+            ("'2024-01-02'", SqlType::Date),
+            ("{a: 1}", SqlType::Json),
+            ("5", SqlType::Json),
+            ("~", SqlType::Integer),
+        ] {
+            assert!(Coercion::Strict.convert(&yaml(text), &sql_type).is_ok());
+        }
+    }
+}
@@ -0,0 +1,108 @@
+// Gzip and zstd compressed files, named with `.gz` or `.zst` after their own extension, as
+// in `fixtures.yaml.gz`. They are decompressed when read and compressed again when changes
+// are written back.
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+use crate::yaml::remote::{fetch, url};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Compression {
+    Gzip,
+    Zstd,
+}
+
+fn compression(path: &Path) -> Option<Compression> {
+    let extension = path.extension()?.to_string_lossy().to_lowercase();
+    match extension.as_str() {
+        "gz" => Some(Compression::Gzip),
+        "zst" => Some(Compression::Zstd),
+        _ => None,
+    }
+}
+
+/// The name of the file without the compression extension, whose extension tells what
+/// the file holds
+pub(crate) fn uncompressed_name(path: &Path) -> PathBuf {
+    match compression(path) {
+        Some(_) => path.with_extension(""),
+        None => path.to_path_buf(),
+    }
+}
+
+/// The contents of a file or URL, decompressed
+pub(crate) async fn read_file(path: &Path) -> io::Result<Vec<u8>> {
+    let bytes = match url(path) {
+        Some(url) => fetch(url).await?,
+        None => tokio::fs::read(path).await?,
+    };
+    decompress(path, bytes)
+}
+
+/// The text of a file or URL, decompressed
+pub(crate) async fn read_text(path: &Path) -> io::Result<String> {
+    utf8(read_file(path).await?)
+}
+
+/// The text of a file, decompressed, outside of async code
+pub(crate) fn read_text_sync(path: &Path) -> io::Result<String> {
+    utf8(decompress(path, std::fs::read(path)?)?)
+}
+
+/// A local file, read through a decompressor when it is compressed
+pub(crate) fn open_file(path: &Path) -> io::Result<Box<dyn Read>> {
+    let file = std::fs::File::open(path)?;
+    Ok(match compression(path) {
+        None => Box::new(file),
+        Some(Compression::Gzip) => Box::new(GzDecoder::new(file)),
+        Some(Compression::Zstd) => Box::new(zstd::Decoder::new(file)?),
+    })
+}
+
+fn utf8(bytes: Vec<u8>) -> io::Result<String> {
+    String::from_utf8(bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+fn decompress(path: &Path, bytes: Vec<u8>) -> io::Result<Vec<u8>> {
+    let mut output = Vec::new();
+    match compression(path) {
+        None => return Ok(bytes),
+        Some(Compression::Gzip) => {
+            GzDecoder::new(bytes.as_slice()).read_to_end(&mut output)?;
+        }
+        Some(Compression::Zstd) => output = zstd::decode_all(bytes.as_slice())?,
+    }
+    Ok(output)
+}
+
+/// The contents to write to a file, compressed as its name says
+pub(crate) fn compress(path: &Path, bytes: Vec<u8>) -> io::Result<Vec<u8>> {
+    match compression(path) {
+        None => Ok(bytes),
+        Some(Compression::Gzip) => {
+            let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(&bytes)?;
+            encoder.finish()
+        }
+        Some(Compression::Zstd) => zstd::encode_all(bytes.as_slice(), 0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let text = b"tables: {}\n".to_vec();
+        for name in ["db.yaml.gz", "db.yaml.ZST", "db.yaml"] {
+            let path = Path::new(name);
+            let compressed = compress(path, text.clone()).unwrap();
+            assert_eq!(compressed == text, name == "db.yaml");
+            assert_eq!(decompress(path, compressed).unwrap(), text);
+            assert_eq!(uncompressed_name(path), Path::new("db.yaml"));
+        }
+    }
+}
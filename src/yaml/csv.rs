@@ -0,0 +1,179 @@
+// The CSV files tables can keep their rows in, named by `data_file:`. The first record
+// names the columns. An empty unquoted field is NULL and a quoted empty field ("") an
+// empty string.
+use crate::database::{Table, Value};
+
+/// A CSV record: the line it starts on and its fields, `None` for NULL
+pub(crate) type CsvRecord = (usize, Vec<Option<String>>);
+
+/// The column names and the records of a CSV document. Errors name the line they are on.
+pub(crate) fn parse_csv(content: &str) -> Result<(Vec<String>, Vec<CsvRecord>), String> {
+    let content = content.strip_prefix('\u{feff}').unwrap_or(content);
+    let mut records: Vec<CsvRecord> = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    // Whether the current field was quoted, and whether its closing quote is still to come
+    let mut quoted = false;
+    let mut in_quotes = false;
+    let mut line = 1;
+    let mut record_line = 1;
+
+    let mut chars = content.chars().peekable();
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => in_quotes = false,
+                c => {
+                    if c == '\n' {
+                        line += 1;
+                    }
+                    field.push(c);
+                }
+            }
+            continue;
+        }
+
+        match c {
+            '"' if field.is_empty() && !quoted => {
+                in_quotes = true;
+                quoted = true;
+            }
+            ',' => record.push(take_field(&mut field, &mut quoted)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                record.push(take_field(&mut field, &mut quoted));
+                records.push((record_line, std::mem::take(&mut record)));
+                line += 1;
+                record_line = line;
+            }
+            '"' => return Err(format!("line {}: quote inside an unquoted field", line)),
+            _ if quoted => return Err(format!("line {}: text after a closing quote", line)),
+            c => field.push(c),
+        }
+    }
+    if in_quotes {
+        return Err(format!("line {}: unterminated quoted field", record_line));
+    }
+    if !record.is_empty() || !field.is_empty() || quoted {
+        record.push(take_field(&mut field, &mut quoted));
+        records.push((record_line, record));
+    }
+
+    // Blank lines hold no record
+    records.retain(|(_, fields)| !matches!(fields.as_slice(), [None]));
+
+    let mut records = records.into_iter();
+    let Some((_, header)) = records.next() else {
+        return Err("the file is empty, it needs a header line naming the columns".to_string());
+    };
+    let header = header
+        .into_iter()
+        .map(|name| name.map(|name| name.trim().to_string()))
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(|| "line 1: a column name is empty".to_string())?;
+
+    let records: Vec<CsvRecord> = records.collect();
+    for (line, fields) in &records {
+        if fields.len() != header.len() {
+            return Err(format!(
+                "line {}: {} fields where the header has {}",
+                line,
+                fields.len(),
+                header.len()
+            ));
+        }
+    }
+    Ok((header, records))
+}
+
+fn take_field(field: &mut String, quoted: &mut bool) -> Option<String> {
+    let value = std::mem::take(field);
+    let was_quoted = std::mem::replace(quoted, false);
+    if value.is_empty() && !was_quoted {
+        None
+    } else {
+        Some(value)
+    }
+}
+
+/// The rows of a table as a CSV document that `parse_csv` reads back into the same values
+pub(crate) fn render_csv(table: &Table) -> String {
+    // Computed columns are computed again when the file is loaded
+    let written: Vec<usize> = (0..table.columns.len())
+        .filter(|&index| !table.is_computed(index))
+        .collect();
+    let mut lines = vec![
+        written
+            .iter()
+            .map(|&index| csv_field(&table.columns[index].name))
+            .collect::<Vec<_>>()
+            .join(","),
+    ];
+    for row in &table.rows {
+        let fields: Vec<String> = written
+            .iter()
+            .map(|&index| match &row[index] {
+                Value::Null => String::new(),
+                value => csv_field(&value.to_string()),
+            })
+            .collect();
+        lines.push(fields.join(","));
+    }
+
+    let mut content = lines.join("\n");
+    content.push('\n');
+    content
+}
+
+fn csv_field(value: &str) -> String {
+    let needs_quotes =
+        value.is_empty() || value.trim() != value || value.contains([',', '"', '\n', '\r']);
+    if needs_quotes {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(value: &str) -> Option<String> {
+        Some(value.to_string())
+    }
+
+    #[test]
+    fn test_parse_csv() {
+        let (header, records) = parse_csv(
+            "\u{feff}id,name,note\r\n1,\"Smith, Ann\",\"said \"\"hi\"\"\"\r\n\r\n2,,\"\"\n3,\"two\nlines\",x",
+        )
+        .unwrap();
+        assert_eq!(header, vec!["id", "name", "note"]);
+        assert_eq!(
+            records,
+            vec![
+                (2, vec![text("1"), text("Smith, Ann"), text("said \"hi\"")]),
+                (4, vec![text("2"), None, text("")]),
+                (5, vec![text("3"), text("two\nlines"), text("x")]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_csv_errors() {
+        assert!(parse_csv("").unwrap_err().contains("empty"));
+        assert!(parse_csv("a,b\n1\n").unwrap_err().starts_with("line 2:"));
+        assert!(
+            parse_csv("a\n\"open\n")
+                .unwrap_err()
+                .contains("unterminated")
+        );
+        assert!(parse_csv("a\nx\"y\n").unwrap_err().contains("quote"));
+        assert!(parse_csv("a,\n").unwrap_err().contains("column name"));
+    }
+}
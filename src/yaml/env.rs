@@ -0,0 +1,136 @@
+// Environment variables in YAML values. `${NAME}` is replaced by the value of NAME and
+// `${NAME:-default}` by that value, or by the default when NAME is unset or empty. `$${`
+// stands for a literal `${`.
+use serde_yaml::Value;
+use std::borrow::Cow;
+
+use crate::YamlBaseError;
+use crate::yaml::schema::DatabaseInfo;
+
+/// The text with its `${...}` references replaced. Text without any is borrowed as it is.
+pub(crate) fn interpolate(text: &str) -> crate::Result<Cow<'_, str>> {
+    if !text.contains("${") {
+        return Ok(Cow::Borrowed(text));
+    }
+
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('$') {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+        if let Some(after) = rest.strip_prefix("$${") {
+            result.push_str("${");
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix("${") {
+            let end = after.find('}').ok_or_else(|| {
+                YamlBaseError::Config(format!("Unterminated '${{' in '{}'", text))
+            })?;
+            result.push_str(&lookup(&after[..end])?);
+            rest = &after[end + 1..];
+        } else {
+            result.push('$');
+            rest = &rest[1..];
+        }
+    }
+    result.push_str(rest);
+    Ok(Cow::Owned(result))
+}
+
+fn lookup(reference: &str) -> crate::Result<String> {
+    let (name, default) = match reference.split_once(":-") {
+        Some((name, default)) => (name, Some(default)),
+        None => (reference, None),
+    };
+    let name = name.trim();
+    let valid = !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid {
+        return Err(YamlBaseError::Config(format!(
+            "Invalid environment variable reference '${{{}}}'",
+            reference
+        )));
+    }
+
+    match (std::env::var(name), default) {
+        (Ok(value), Some(default)) if value.is_empty() => Ok(default.to_string()),
+        (Ok(value), _) => Ok(value),
+        (Err(_), Some(default)) => Ok(default.to_string()),
+        (Err(_), None) => Err(YamlBaseError::Config(format!(
+            "Environment variable '{}' is not set and has no default",
+            name
+        ))),
+    }
+}
+
+/// A list or mapping with the environment variables of the strings in it replaced
+pub(crate) fn interpolate_nested(value: &Value) -> crate::Result<Value> {
+    Ok(match value {
+        Value::String(text) => Value::String(interpolate(text)?.into_owned()),
+        Value::Sequence(items) => Value::Sequence(
+            items
+                .iter()
+                .map(interpolate_nested)
+                .collect::<crate::Result<_>>()?,
+        ),
+        Value::Mapping(mapping) => Value::Mapping(
+            mapping
+                .iter()
+                .map(|(key, value)| Ok((key.clone(), interpolate_nested(value)?)))
+                .collect::<crate::Result<_>>()?,
+        ),
+        value => value.clone(),
+    })
+}
+
+/// The database name and credentials with their environment variables replaced
+pub(crate) fn interpolate_info(mut info: DatabaseInfo) -> crate::Result<DatabaseInfo> {
+    info.name = interpolate(&info.name)?.into_owned();
+    if let Some(auth) = &mut info.auth {
+        auth.username = interpolate(&auth.username)?.into_owned();
+        auth.password = interpolate(&auth.password)?.into_owned();
+        if let Some(hash) = &mut auth.password_hash {
+            *hash = interpolate(hash)?.into_owned();
+        }
+        for user in &mut auth.users {
+            user.username = interpolate(&user.username)?.into_owned();
+            user.password = interpolate(&user.password)?.into_owned();
+            if let Some(hash) = &mut user.password_hash {
+                *hash = interpolate(hash)?.into_owned();
+            }
+        }
+    }
+    Ok(info)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interpolate() {
+        // SAFETY: no other test reads or writes these variables
+        unsafe {
+            std::env::set_var("YAMLBASE_TEST_HOST", "db.internal");
+            std::env::set_var("YAMLBASE_TEST_EMPTY", "");
+            std::env::remove_var("YAMLBASE_TEST_UNSET");
+        }
+
+        assert!(matches!(interpolate("plain $5").unwrap(), Cow::Borrowed(_)));
+        assert_eq!(
+            interpolate("http://${YAMLBASE_TEST_HOST}:${YAMLBASE_TEST_UNSET:-80}/").unwrap(),
+            "http://db.internal:80/"
+        );
+        assert_eq!(
+            interpolate("${YAMLBASE_TEST_EMPTY:-fallback}").unwrap(),
+            "fallback"
+        );
+        assert_eq!(interpolate("${YAMLBASE_TEST_UNSET:-}").unwrap(), "");
+        assert_eq!(
+            interpolate("$${HOME} costs $$").unwrap(),
+            "${HOME} costs $$"
+        );
+
+        assert!(interpolate("${YAMLBASE_TEST_UNSET}").is_err());
+        assert!(interpolate("${YAMLBASE_TEST_HOST").is_err());
+        assert!(interpolate("${not a name}").is_err());
+    }
+}
@@ -0,0 +1,81 @@
+// Realistic looking fake values for the `fake` generator, such as names, emails and
+// addresses. Only built with the `faker` feature.
+use fake::Fake;
+use fake::faker::address::en::{
+    BuildingNumber, CityName, CountryCode, CountryName, StateName, StreetName, ZipCode,
+};
+use fake::faker::company::en::{CatchPhrase, CompanyName, Industry};
+use fake::faker::currency::en::CurrencyCode;
+use fake::faker::internet::en::{IPv4, SafeEmail, UserAgent, Username};
+use fake::faker::job::en::Title;
+use fake::faker::lorem::en::{Paragraph, Sentence, Word};
+use fake::faker::name::en::{FirstName, LastName, Name};
+use fake::faker::phone_number::en::PhoneNumber;
+use rand::rngs::StdRng;
+
+use crate::YamlBaseError;
+
+/// The kinds of values the `fake` generator makes
+pub(crate) const FAKE_KINDS: &[&str] = &[
+    "first_name",
+    "last_name",
+    "name",
+    "email",
+    "username",
+    "phone_number",
+    "street_address",
+    "city",
+    "state",
+    "postcode",
+    "country",
+    "country_code",
+    "company",
+    "industry",
+    "catch_phrase",
+    "job_title",
+    "currency_code",
+    "ipv4",
+    "user_agent",
+    "word",
+    "sentence",
+    "paragraph",
+];
+
+/// A fake value of a kind
+pub(crate) fn fake_text(kind: &str, rng: &mut StdRng) -> crate::Result<String> {
+    Ok(match kind {
+        "first_name" => FirstName().fake_with_rng(rng),
+        "last_name" => LastName().fake_with_rng(rng),
+        "name" => Name().fake_with_rng(rng),
+        "email" => SafeEmail().fake_with_rng(rng),
+        "username" => Username().fake_with_rng(rng),
+        "phone_number" => PhoneNumber().fake_with_rng(rng),
+        "street_address" => {
+            let number: String = BuildingNumber().fake_with_rng(rng);
+            let street: String = StreetName().fake_with_rng(rng);
+            format!("{} {}", number, street)
+        }
+        "city" => CityName().fake_with_rng(rng),
+        "state" => StateName().fake_with_rng(rng),
+        "postcode" => ZipCode().fake_with_rng(rng),
+        "country" => CountryName().fake_with_rng(rng),
+        "country_code" => CountryCode().fake_with_rng(rng),
+        "company" => CompanyName().fake_with_rng(rng),
+        "industry" => Industry().fake_with_rng(rng),
+        "catch_phrase" => CatchPhrase().fake_with_rng(rng),
+        "job_title" => Title().fake_with_rng(rng),
+        "currency_code" => CurrencyCode().fake_with_rng(rng),
+        "ipv4" => IPv4().fake_with_rng(rng),
+        "user_agent" => UserAgent().fake_with_rng(rng),
+        "word" => Word().fake_with_rng(rng),
+        "sentence" => Sentence(4..10).fake_with_rng(rng),
+        "paragraph" => Paragraph(3..6).fake_with_rng(rng),
+        _ => {
+            return Err(YamlBaseError::TypeConversion(format!(
+                "unknown fake value '{}', expected one of: {}",
+                kind,
+                FAKE_KINDS.join(", ")
+            )));
+        }
+    })
+}
@@ -0,0 +1,150 @@
+// Rows made up from a table's `generate:` block. The random generators are seeded, so a
+// file makes the same rows every time it is loaded.
+use chrono::Duration;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rust_decimal::Decimal;
+use rust_decimal::prelude::FromPrimitive;
+
+use crate::YamlBaseError;
+use crate::database::{Table, Value};
+#[cfg(feature = "faker")]
+use crate::yaml::faker::fake_text;
+use crate::yaml::parser::{insert_data_row, parse_value, text_value};
+use crate::yaml::schema::{SqlType, YamlGenerate, YamlGenerator};
+
+/// Add the rows of a `generate:` block to a table, after the rows it already has
+pub(crate) fn generate_rows(table: &mut Table, generate: &YamlGenerate) -> crate::Result<()> {
+    let mut generators = Vec::new();
+    for (name, generator) in &generate.columns {
+        let position = table
+            .columns
+            .iter()
+            .position(|column| column.name.eq_ignore_ascii_case(name))
+            .ok_or_else(|| {
+                YamlBaseError::Config(format!(
+                    "Table '{}' generates column '{}', which it does not have",
+                    table.name, name
+                ))
+            })?;
+        generators.push((position, generator));
+    }
+
+    let mut rng = StdRng::seed_from_u64(generate.seed);
+    table.rows.reserve(generate.count);
+    for n in 0..generate.count {
+        let mut values = vec![None; table.columns.len()];
+        for &(position, generator) in &generators {
+            let column = &table.columns[position];
+            let value = generate_value(generator, n, &column.sql_type, &mut rng).map_err(|e| {
+                YamlBaseError::Config(format!(
+                    "Cannot generate column '{}' of table '{}': {}",
+                    column.name, table.name, e
+                ))
+            })?;
+            values[position] = Some(value);
+        }
+        // An auto-increment column without a generator numbers the rows
+        if let Some(auto_increment) = &mut table.auto_increment
+            && values[auto_increment.column].is_none()
+        {
+            auto_increment.last += 1;
+            values[auto_increment.column] = Some(Value::Integer(auto_increment.last));
+        }
+        insert_data_row(table, values)?;
+    }
+    // Generated ids count too for the next id an INSERT gets
+    if let Some(auto_increment) = table.auto_increment {
+        table.set_auto_increment(auto_increment.column)?;
+    }
+    Ok(())
+}
+
+/// The value of a generated column in row `n`, counting from 0
+fn generate_value(
+    generator: &YamlGenerator,
+    n: usize,
+    sql_type: &SqlType,
+    rng: &mut StdRng,
+) -> crate::Result<Value> {
+    let invalid = |message: &str| YamlBaseError::TypeConversion(message.to_string());
+    match generator {
+        YamlGenerator::Sequence { start, step } => {
+            let value = i64::try_from(n)
+                .ok()
+                .and_then(|n| n.checked_mul(*step))
+                .and_then(|offset| start.checked_add(offset))
+                .ok_or_else(|| invalid("the sequence is out of range"))?;
+            match sql_type {
+                SqlType::Integer | SqlType::BigInt => Ok(Value::Integer(value)),
+                _ => text_value(&value.to_string(), sql_type),
+            }
+        }
+        YamlGenerator::Choice(values) => {
+            if values.is_empty() {
+                return Err(invalid("choice needs at least one value"));
+            }
+            parse_value(&values[rng.gen_range(0..values.len())], sql_type)
+        }
+        YamlGenerator::Range { min, max } => {
+            if min.is_nan() || max.is_nan() || min > max {
+                return Err(invalid("range needs min to be at most max"));
+            }
+            match sql_type {
+                SqlType::Integer | SqlType::BigInt => {
+                    let (min, max) = (min.ceil() as i64, max.floor() as i64);
+                    if min > max {
+                        return Err(invalid("range holds no whole numbers"));
+                    }
+                    Ok(Value::Integer(rng.gen_range(min..=max)))
+                }
+                SqlType::Float => Ok(Value::Float(rng.gen_range(*min..=*max) as f32)),
+                SqlType::Double => Ok(Value::Double(rng.gen_range(*min..=*max))),
+                SqlType::Decimal(_, scale) => Decimal::from_f64(rng.gen_range(*min..=*max))
+                    .map(|value| Value::Decimal(value.round_dp(*scale)))
+                    .ok_or_else(|| invalid("range is out of range for a decimal")),
+                _ => Err(invalid("range needs a numeric column")),
+            }
+        }
+        YamlGenerator::DateRange { start, end } => {
+            let start = text_value(start, sql_type)?;
+            let end = text_value(end, sql_type)?;
+            match (start, end) {
+                (Value::Date(start), Value::Date(end)) if start <= end => {
+                    let days = (end - start).num_days();
+                    Ok(Value::Date(start + Duration::days(rng.gen_range(0..=days))))
+                }
+                (Value::Timestamp(start), Value::Timestamp(end)) if start <= end => {
+                    let seconds = (end - start).num_seconds();
+                    Ok(Value::Timestamp(
+                        start + Duration::seconds(rng.gen_range(0..=seconds)),
+                    ))
+                }
+                (Value::TimestampTz(start), Value::TimestampTz(end)) if start <= end => {
+                    let seconds = (end - start).num_seconds();
+                    Ok(Value::TimestampTz(
+                        start + Duration::seconds(rng.gen_range(0..=seconds)),
+                    ))
+                }
+                (Value::Date(_) | Value::Timestamp(_) | Value::TimestampTz(_), _) => {
+                    Err(invalid("date_range needs start to be at most end"))
+                }
+                _ => Err(invalid(
+                    "date_range needs a DATE, TIMESTAMP or TIMESTAMPTZ column",
+                )),
+            }
+        }
+        YamlGenerator::Format(format) => {
+            text_value(&format.replace("{n}", &(n + 1).to_string()), sql_type)
+        }
+        YamlGenerator::Value(value) => parse_value(value, sql_type),
+        YamlGenerator::Fake(kind) => text_value(&fake_text(kind, rng)?, sql_type),
+    }
+}
+
+#[cfg(not(feature = "faker"))]
+fn fake_text(_kind: &str, _rng: &mut StdRng) -> crate::Result<String> {
+    Err(YamlBaseError::TypeConversion(
+        "fake values need yamlbase built with the 'faker' feature".to_string(),
+    ))
+}
@@ -0,0 +1,180 @@
+// Errors for values of `data:` that cannot be loaded, pointing at the line of each value.
+// serde_yaml keeps no positions for the values it parses, so the lines of the rows and of
+// their keys are found in the source text, the way the writer finds the list it replaces.
+// The streaming loader knows the lines of the rows it reads and gives them itself.
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::YamlBaseError;
+use crate::yaml::compression::read_text_sync;
+use crate::yaml::writer::{data_block, indentation, is_content, key};
+
+/// A row of `data:`, or one of its values, that cannot be loaded
+pub(crate) struct DataError {
+    /// The index of the row in `data:`
+    pub row: usize,
+    pub column: Option<String>,
+    /// The line of the value, or of the row, when the loader knows it
+    pub line: Option<usize>,
+    pub error: YamlBaseError,
+}
+
+/// Where a row of a table was loaded from: its index in `data:` and its line, when the
+/// loader knows it
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct RowOrigin {
+    pub row: usize,
+    pub line: Option<usize>,
+}
+
+/// What a lenient load keeps besides the database: the errors of the rows it left out, and
+/// by table where each of the rows it loaded from `data:` came from
+#[derive(Default)]
+pub(crate) struct LoadReport {
+    pub errors: Vec<YamlBaseError>,
+    pub origins: HashMap<String, Vec<RowOrigin>>,
+}
+
+/// The line of each row of a table's `data:` in the file
+pub(crate) fn data_row_lines(file: &Path, table: &str) -> Vec<usize> {
+    row_lines(&read_text_sync(file).unwrap_or_default(), table)
+        .iter()
+        .map(|row| row.line)
+        .collect()
+}
+
+/// One error listing the invalid rows and values of a table, each with its line in the
+/// file when it can be found
+pub(crate) fn data_errors(file: &Path, table: &str, errors: &[DataError]) -> YamlBaseError {
+    let rows = if errors.iter().any(|error| error.line.is_none()) {
+        row_lines(&read_text_sync(file).unwrap_or_default(), table)
+    } else {
+        Vec::new()
+    };
+    let messages: Vec<String> = errors
+        .iter()
+        .map(|error| {
+            let line = error.line.or_else(|| {
+                rows.get(error.row).map(|row| {
+                    error
+                        .column
+                        .as_ref()
+                        .and_then(|column| row.keys.get(column))
+                        .copied()
+                        .unwrap_or(row.line)
+                })
+            });
+            let location = match line {
+                Some(line) => format!("{}:{}", file.display(), line),
+                None => file.display().to_string(),
+            };
+            let value = match &error.column {
+                Some(column) => format!("row {}, column '{}'", error.row + 1, column),
+                None => format!("row {}", error.row + 1),
+            };
+            format!(
+                "{}: table '{}', {}: {}",
+                location,
+                table,
+                value,
+                detail(&error.error)
+            )
+        })
+        .collect();
+    YamlBaseError::Config(messages.join("\n"))
+}
+
+/// The message of an error without the kind of error in front
+pub(crate) fn detail(error: &YamlBaseError) -> String {
+    match error {
+        YamlBaseError::Config(message)
+        | YamlBaseError::TypeConversion(message)
+        | YamlBaseError::NotImplemented(message)
+        | YamlBaseError::Protocol(message)
+        | YamlBaseError::Database { message } => message.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// The line of a row of `data:` and the lines of its keys, counting from 1
+struct RowLines {
+    line: usize,
+    keys: HashMap<String, usize>,
+}
+
+/// The lines of the rows of a table's `data:` list, when it is a block sequence
+fn row_lines(source: &str, table: &str) -> Vec<RowLines> {
+    let lines: Vec<&str> = source.lines().collect();
+    let Some(block) = data_block(&lines, table) else {
+        return Vec::new();
+    };
+
+    let mut rows: Vec<RowLines> = Vec::new();
+    let mut entry_indent = None;
+    // The indentation of the keys of the current row, once known
+    let mut key_indent = None;
+    for (index, line) in lines
+        .iter()
+        .enumerate()
+        .take(block.end)
+        .skip(block.start + 1)
+    {
+        if !is_content(line) {
+            continue;
+        }
+        let indent = indentation(line);
+        let trimmed = line.trim_start();
+        if (trimmed == "-" || trimmed.starts_with("- "))
+            && *entry_indent.get_or_insert(indent) == indent
+        {
+            // The first key of a row is on the line of its dash
+            let rest = trimmed[1..].trim_start();
+            key_indent = (!rest.is_empty()).then(|| line.len() - rest.len());
+            let mut keys = HashMap::new();
+            if let Some(key) = key(rest) {
+                keys.insert(key.to_string(), index + 1);
+            }
+            rows.push(RowLines {
+                line: index + 1,
+                keys,
+            });
+        } else if let Some(row) = rows.last_mut()
+            && *key_indent.get_or_insert(indent) == indent
+            && let Some(key) = key(line)
+        {
+            row.keys.entry(key.to_string()).or_insert(index + 1);
+        }
+    }
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_row_lines() {
+        let source = "tables:\n  items:\n    columns:\n      id: INTEGER\n    data:\n      - id: 1\n        # note\n        note: |\n          note: no key\n      -\n        id: 2\n      - { id: 3 }\n  other:\n    data:\n    - id: 4\n";
+        let rows = row_lines(source, "items");
+        let lines: Vec<(usize, Option<usize>, Option<usize>)> = rows
+            .iter()
+            .map(|row| {
+                (
+                    row.line,
+                    row.keys.get("id").copied(),
+                    row.keys.get("note").copied(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                (6, Some(6), Some(8)),
+                (10, Some(11), None),
+                (12, None, None),
+            ]
+        );
+        assert_eq!(row_lines(source, "other")[0].line, 15);
+        assert!(row_lines(source, "missing").is_empty());
+    }
+}
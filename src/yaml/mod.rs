@@ -1,12 +1,31 @@
+#[cfg(feature = "object-store")]
+mod cloud;
+pub mod coercion;
+mod compression;
+mod csv;
+mod env;
+#[cfg(feature = "faker")]
+mod faker;
+mod generate;
+mod location;
+#[cfg(feature = "parquet")]
+mod parquet;
 pub mod parser;
+pub mod remote;
 pub mod schema;
+mod stream;
+pub mod validate;
 pub mod watcher;
+pub mod writer;
 
 #[cfg(test)]
 mod tests;
 
-pub use parser::parse_yaml_database;
-pub use schema::{AuthConfig, YamlColumn, YamlDatabase, YamlTable};
+pub use coercion::Coercion;
+pub use parser::{parse_yaml_database, reload_yaml_files};
+pub use remote::UrlPoller;
+pub use schema::{AuthConfig, Grants, UserConfig, YamlColumn, YamlDatabase, YamlTable};
+pub(crate) use stream::read_top_entry;
 pub use watcher::FileWatcher;
 
 // For fuzzing
@@ -0,0 +1,87 @@
+// The Parquet files tables can load their rows from, named by a `data_file:` ending in
+// `.parquet`. The columns of the file are matched to the table's columns by name, and
+// nested columns become JSON. Only built with the `parquet` feature.
+use ::parquet::file::reader::{FileReader, SerializedFileReader};
+use ::parquet::record::Field;
+use chrono::DateTime;
+
+use crate::YamlBaseError;
+use crate::database::{TimeZone, Value};
+use crate::yaml::parser::text_value;
+use crate::yaml::schema::SqlType;
+
+/// The column names and the rows of a Parquet file
+pub(crate) fn parse_parquet(content: Vec<u8>) -> Result<(Vec<String>, Vec<Vec<Field>>), String> {
+    let reader =
+        SerializedFileReader::new(bytes::Bytes::from(content)).map_err(|e| e.to_string())?;
+    let header = reader
+        .metadata()
+        .file_metadata()
+        .schema_descr()
+        .root_schema()
+        .get_fields()
+        .iter()
+        .map(|field| field.name().to_string())
+        .collect();
+
+    let mut rows = Vec::new();
+    for row in reader.get_row_iter(None).map_err(|e| e.to_string())? {
+        let row = row.map_err(|e| e.to_string())?;
+        rows.push(
+            row.into_columns()
+                .into_iter()
+                .map(|(_, field)| field)
+                .collect(),
+        );
+    }
+    Ok((header, rows))
+}
+
+/// A Parquet field as a value of the column's type
+pub(crate) fn parquet_value(field: &Field, sql_type: &SqlType) -> crate::Result<Value> {
+    let timestamp = |micros: i64| {
+        DateTime::from_timestamp_micros(micros).ok_or_else(|| {
+            YamlBaseError::TypeConversion(format!("Timestamp out of range: {}", micros))
+        })
+    };
+    let instant = match field {
+        Field::Null => return Ok(Value::Null),
+        Field::TimestampMillis(millis) => timestamp(millis.saturating_mul(1000))?,
+        Field::TimestampMicros(micros) => timestamp(*micros)?,
+        Field::Date(days) if *sql_type == SqlType::Date => {
+            return DateTime::from_timestamp(i64::from(*days) * 86_400, 0)
+                .map(|date| Value::Date(date.date_naive()))
+                .ok_or_else(|| {
+                    YamlBaseError::TypeConversion(format!("Date out of range: {}", days))
+                });
+        }
+        Field::Float(value) if *sql_type == SqlType::Float => return Ok(Value::Float(*value)),
+        Field::Double(value) if *sql_type == SqlType::Double => return Ok(Value::Double(*value)),
+        Field::Str(value) => return text_value(value, sql_type),
+        Field::Bytes(value) if *sql_type == SqlType::Blob => {
+            return Ok(Value::Bytes(value.data().to_vec()));
+        }
+        Field::Bytes(value) => {
+            let text = value.as_utf8().map_err(|_| {
+                YamlBaseError::TypeConversion("Binary values are not supported".to_string())
+            })?;
+            return text_value(text, sql_type);
+        }
+        Field::Group(_) | Field::ListInternal(_) | Field::MapInternal(_) => {
+            return match sql_type {
+                SqlType::Json => Ok(Value::Json(field.to_json_value())),
+                _ => text_value(&field.to_json_value().to_string(), sql_type),
+            };
+        }
+        field => return text_value(&field.to_string(), sql_type),
+    };
+
+    match sql_type {
+        SqlType::Timestamp => Ok(Value::Timestamp(instant.naive_utc())),
+        SqlType::TimestampTz => Ok(Value::TimestampTz(
+            TimeZone::default_zone().from_instant(&instant),
+        )),
+        SqlType::Date => Ok(Value::Date(instant.date_naive())),
+        _ => text_value(&instant.to_rfc3339(), sql_type),
+    }
+}
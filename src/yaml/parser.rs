@@ -1,81 +1,715 @@
 use indexmap::IndexMap;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use tracing::{debug, info};
 
-use crate::database::{Column, Database, Table, Value as DbValue};
-use crate::yaml::schema::{AuthConfig, SqlType, YamlColumn, YamlDatabase};
+use crate::YamlBaseError;
 
+use crate::database::schema::parse_array_literal;
+use crate::database::timezone::parse_timestamptz;
+use crate::database::{Column, Database, Table, TimeZone, Value as DbValue};
+use crate::sql::{
+    check_loaded_rows, compute_loaded_rows, compute_materialized_views, parse_check, parse_computed,
+};
+use crate::yaml::coercion::Coercion;
+use crate::yaml::compression::{read_text, uncompressed_name};
+use crate::yaml::csv::parse_csv;
+use crate::yaml::env::{interpolate, interpolate_info, interpolate_nested};
+use crate::yaml::generate::generate_rows;
+use crate::yaml::location::{
+    DataError, LoadReport, RowOrigin, data_errors, data_row_lines, detail,
+};
+use crate::yaml::schema::{
+    AuthConfig, DatabaseInfo, SqlType, YamlColumn, YamlDatabase, YamlInclude, YamlMaterializedView,
+    YamlTable,
+};
+use crate::yaml::stream::{self, is_large};
+
+/// Load a YAML database from a file, or from a directory whose YAML files together hold
+/// the database
 pub async fn parse_yaml_database(path: &Path) -> crate::Result<(Database, Option<AuthConfig>)> {
+    parse_database(path, None).await
+}
+
+/// Load a YAML database as far as its rows can be loaded: the rows of `data:` with values
+/// that cannot be loaded are left out and reported, instead of failing the load
+pub(crate) async fn parse_yaml_database_leniently(
+    path: &Path,
+) -> crate::Result<(Database, LoadReport)> {
+    let mut report = LoadReport::default();
+    let (database, _) = parse_database(path, Some(&mut report)).await?;
+    Ok((database, report))
+}
+
+async fn parse_database(
+    path: &Path,
+    report: Option<&mut LoadReport>,
+) -> crate::Result<(Database, Option<AuthConfig>)> {
     info!("Parsing YAML database from: {}", path.display());
 
-    let content = tokio::fs::read_to_string(path).await?;
-    let yaml_db: YamlDatabase = serde_yaml::from_str(&content)?;
+    if path.is_dir() {
+        return parse_yaml_directory(path, report).await;
+    }
+
+    let yaml_db: YamlDatabase = if is_large(path) {
+        stream::load(path, |yaml_db: &mut YamlDatabase| &mut yaml_db.tables)?
+    } else {
+        serde_yaml::from_str(&read_text(path).await?)?
+    };
+    load_database(path, yaml_db, report).await
+}
+
+/// The database a parsed YAML file defines. With a report, rows that cannot be loaded are
+/// reported there instead of failing the load.
+pub(crate) async fn load_database(
+    path: &Path,
+    yaml_db: YamlDatabase,
+    report: Option<&mut LoadReport>,
+) -> crate::Result<(Database, Option<AuthConfig>)> {
+    let info = interpolate_info(yaml_db.database)?;
+    let auth_config = info.auth;
+    let mut database = Database::new(info.name);
+
+    let main_file = YamlInclude {
+        include: yaml_db.include,
+        tables: yaml_db.tables,
+        materialized_views: yaml_db.materialized_views,
+    };
+    add_files(&mut database, vec![(path.to_path_buf(), main_file)], report).await?;
+
+    info!(
+        "Successfully parsed database with {} tables",
+        database.tables.len()
+    );
+    Ok((database, auth_config))
+}
+
+/// Every YAML file of the directory defines tables and materialized views. One of them may
+/// hold the `database:` section; without one the database is named after the directory.
+async fn parse_yaml_directory(
+    dir: &Path,
+    report: Option<&mut LoadReport>,
+) -> crate::Result<(Database, Option<AuthConfig>)> {
+    let mut database_info: Option<(PathBuf, DatabaseInfo)> = None;
+    let mut files = Vec::new();
+    for path in yaml_files(dir)? {
+        let (info, file) = read_directory_file(&path).await?;
+        if let Some(info) = info {
+            if let Some((other, _)) = &database_info {
+                return Err(YamlBaseError::Config(format!(
+                    "Both {} and {} have a database: section",
+                    other.display(),
+                    path.display()
+                )));
+            }
+            database_info = Some((path.clone(), interpolate_info(info)?));
+        }
+        files.push((path, file));
+    }
+
+    let (name, auth_config) = match database_info {
+        Some((_, info)) => (info.name, info.auth),
+        None => (
+            dir.file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_else(|| "yamlbase".to_string()),
+            None,
+        ),
+    };
+    let mut database = Database::new(name);
+    add_files(&mut database, files, report).await?;
+
+    info!(
+        "Successfully parsed database with {} tables from {}",
+        database.tables.len(),
+        dir.display()
+    );
+    Ok((database, auth_config))
+}
+
+/// Reload the files of a directory database that changed, were added or were removed. The
+/// tables and views of other files are kept as they are; views are all recomputed. A
+/// changed file with `include:` reloads the whole directory.
+pub async fn reload_yaml_files(
+    database: &Database,
+    dir: &Path,
+    changed: &[PathBuf],
+) -> crate::Result<Database> {
+    // A changed CSV file reloads the files of the tables it holds the rows of
+    let mut changed = changed.to_vec();
+    for (table, data_file) in &database.data_files {
+        if changed.contains(data_file)
+            && let Some(file) = database.table_files.get(table)
+            && !changed.contains(file)
+        {
+            changed.push(file.clone());
+        }
+    }
+    changed.retain(|path| is_yaml_file(path));
+    let changed = changed.as_slice();
+
+    let mut files = Vec::new();
+    for path in changed.iter().filter(|path| path.exists()) {
+        let (_, file) = read_directory_file(path).await?;
+        if !file.include.is_empty() {
+            return parse_yaml_database(dir).await.map(|(database, _)| database);
+        }
+        files.push((path.clone(), file));
+    }
+
+    let mut reloaded = database.clone();
+    let views: Vec<String> = reloaded.materialized_views.keys().cloned().collect();
+    for view in views {
+        reloaded.tables.shift_remove(&view);
+    }
+    let removed: Vec<String> = reloaded
+        .table_files
+        .iter()
+        .filter(|(_, file)| changed.contains(file))
+        .map(|(name, _)| name.clone())
+        .collect();
+    for name in &removed {
+        reloaded.tables.shift_remove(name);
+        reloaded.materialized_views.shift_remove(name);
+        reloaded.table_files.remove(name);
+        reloaded.data_files.remove(name);
+        reloaded.generated_tables.remove(name);
+    }
+
+    add_files(&mut reloaded, files, None).await?;
+    info!("Reloaded {} changed file(s)", changed.len());
+    Ok(reloaded)
+}
+
+/// The YAML files of a directory database, in name order
+pub fn yaml_files(dir: &Path) -> crate::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_file() && is_yaml_file(&path) {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Whether a file is a YAML file, which may be compressed
+pub fn is_yaml_file(path: &Path) -> bool {
+    let hidden = path
+        .file_name()
+        .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+    !hidden
+        && uncompressed_name(path)
+            .extension()
+            .is_some_and(|extension| extension == "yaml" || extension == "yml")
+}
+
+/// A file of a directory database, and its `database:` section if it has one
+async fn read_directory_file(path: &Path) -> crate::Result<(Option<DatabaseInfo>, YamlInclude)> {
+    let error = |e: serde_yaml::Error| YamlBaseError::Config(format!("{}: {}", path.display(), e));
+    let (mut value, rows) = if is_large(path) {
+        stream::read(path)?
+    } else {
+        let content = read_text(path).await?;
+        (
+            serde_yaml::from_str(&content).map_err(error)?,
+            IndexMap::new(),
+        )
+    };
+    let info = match value
+        .as_mapping_mut()
+        .and_then(|mapping| mapping.remove("database"))
+    {
+        Some(info) => Some(serde_yaml::from_value(info).map_err(error)?),
+        None => None,
+    };
+    let mut file: YamlInclude = if value.is_null() {
+        YamlInclude::default()
+    } else {
+        serde_yaml::from_value(value).map_err(error)?
+    };
+    stream::attach(&mut file.tables, rows);
+    Ok((info, file))
+}
 
-    let auth_config = yaml_db.database.auth.clone();
-    let mut database = Database::new(yaml_db.database.name.clone());
+/// Add the tables and materialized views of files, and of the files they include, to the
+/// database, then compute its materialized views
+async fn add_files(
+    database: &mut Database,
+    files: Vec<(PathBuf, YamlInclude)>,
+    mut report: Option<&mut LoadReport>,
+) -> crate::Result<()> {
+    let (tables, views) = load_includes(files).await?;
+    // The errors of all tables are reported together
+    let mut errors = Vec::new();
 
-    for (table_name, yaml_table) in yaml_db.tables {
+    for (table_name, (file, yaml_table)) in tables {
         debug!("Parsing table: {}", table_name);
+        check_defined_once(&table_name, &file, database.table_files.iter())?;
+        let data_file = yaml_table
+            .data_file
+            .as_ref()
+            .map(|data_file| relative_to(&file, data_file));
+        let generated = yaml_table.generate.is_some();
+        let table = match build_table(
+            table_name,
+            yaml_table,
+            &file,
+            data_file.as_deref(),
+            report.as_deref_mut(),
+        )
+        .await
+        {
+            Ok(table) => table,
+            Err(error) => {
+                errors.push(error);
+                continue;
+            }
+        };
+        database.table_files.insert(table.name.clone(), file);
+        if generated {
+            database.generated_tables.insert(table.name.clone());
+        }
+        if let Some(data_file) = data_file {
+            database.data_files.insert(table.name.clone(), data_file);
+        }
+        database.add_table(table)?;
+    }
+    match errors.len() {
+        0 => {}
+        1 => return Err(errors.remove(0)),
+        _ => {
+            let messages: Vec<String> = errors.iter().map(detail).collect();
+            return Err(YamlBaseError::Config(messages.join("\n")));
+        }
+    }
 
-        let mut columns = Vec::new();
-        let mut column_map = IndexMap::new();
-
-        for (col_name, type_def) in &yaml_table.columns {
-            let yaml_column = YamlColumn::parse(col_name.clone(), type_def)?;
-            let sql_type = yaml_column.get_base_type()?;
-
-            let column = Column {
-                name: yaml_column.name.clone(),
-                sql_type,
-                primary_key: yaml_column.is_primary_key,
-                nullable: yaml_column.is_nullable,
-                unique: yaml_column.is_unique,
-                default: yaml_column.default_value,
-                references: yaml_column.references.map(|r| (r.table, r.column)),
-            };
+    for (view_name, (file, view)) in views {
+        check_defined_once(&view_name, &file, database.table_files.iter())?;
+        database.table_files.insert(view_name.clone(), file);
+        database.materialized_views.insert(view_name, view.query);
+    }
+    compute_materialized_views(database).await
+}
+
+async fn build_table(
+    table_name: String,
+    yaml_table: YamlTable,
+    file: &Path,
+    data_file: Option<&Path>,
+    report: Option<&mut LoadReport>,
+) -> crate::Result<Table> {
+    let mut columns = Vec::new();
+    let mut column_map = IndexMap::new();
+    let mut auto_increment = Vec::new();
+    let mut checks = Vec::new();
+
+    for (col_name, type_def) in &yaml_table.columns {
+        let yaml_column = YamlColumn::parse(col_name.clone(), type_def)?;
+        let sql_type = yaml_column.get_base_type()?;
+
+        let column = Column {
+            name: yaml_column.name.clone(),
+            sql_type,
+            primary_key: yaml_column.is_primary_key,
+            nullable: yaml_column.is_nullable,
+            unique: yaml_column.is_unique,
+            default: yaml_column.default_value,
+            references: yaml_column.references.map(|r| (r.table, r.column)),
+            collation: yaml_column.collation,
+        };
+
+        if yaml_column.is_auto_increment {
+            auto_increment.push(columns.len());
+        }
+        if let Some(check) = &yaml_column.check {
+            checks.push(parse_check(check)?);
+        }
+        column_map.insert(yaml_column.name.clone(), columns.len());
+        columns.push(column);
+    }
+
+    let mut table = Table::new(table_name, columns);
+    if let Some(check) = &yaml_table.check {
+        checks.push(parse_check(check)?);
+    }
+    table.checks = checks;
+    for (name, sql) in &yaml_table.computed {
+        let computed = parse_computed(&table, name, sql)?;
+        table.computed.push(computed);
+    }
 
-            column_map.insert(yaml_column.name.clone(), columns.len());
-            columns.push(column);
-        }
-
-        let mut table = Table::new(table_name.clone(), columns);
-
-        // Parse and insert data
-        for row_data in yaml_table.data {
-            let mut row = Vec::new();
-
-            for column in &table.columns {
-                let value = if let Some(yaml_value) = row_data.get(&column.name) {
-                    parse_value(yaml_value, &column.sql_type)?
-                } else if column.nullable {
-                    DbValue::Null
-                } else if let Some(default) = &column.default {
-                    parse_default_value(default, &column.sql_type)?
-                } else {
-                    return Err(crate::YamlBaseError::Database {
-                        message: format!(
-                            "Non-nullable column '{}' has no value and no default",
-                            column.name
-                        ),
+    if let Some(data_file) = data_file {
+        if !yaml_table.data.is_empty() || yaml_table.loaded.as_ref().is_some_and(|l| !l.is_empty())
+        {
+            return Err(YamlBaseError::Config(format!(
+                "Table '{}' has both data: and data_file:",
+                table.name
+            )));
+        }
+        load_data_file(&mut table, data_file).await?;
+    }
+
+    // Parse and insert data
+    let generate = yaml_table.generate;
+    let coercion = Coercion::for_table(yaml_table.strict_types);
+    // Every invalid value is reported, not just the first
+    let mut errors = Vec::new();
+    let mut origins = Vec::new();
+    let lines = match &report {
+        Some(_) if !yaml_table.data.is_empty() => data_row_lines(file, &table.name),
+        _ => Vec::new(),
+    };
+    for (row, row_data) in yaml_table.data.into_iter().enumerate() {
+        let mut values = Vec::new();
+        let mut valid = true;
+
+        for column in &table.columns {
+            let Some(yaml_value) = row_data.get(&column.name) else {
+                values.push(None);
+                continue;
+            };
+            match data_value(yaml_value, &column.sql_type, coercion) {
+                Ok(value) => values.push(Some(value)),
+                Err(error) => {
+                    valid = false;
+                    errors.push(DataError {
+                        row,
+                        column: Some(column.name.clone()),
+                        line: None,
+                        error,
                     });
-                };
-                row.push(value);
+                }
             }
+        }
 
-            table.insert_row(row)?;
+        if !valid {
+            continue;
+        }
+        match insert_data_row(&mut table, values) {
+            Ok(()) => origins.push(RowOrigin {
+                row,
+                line: lines.get(row).copied(),
+            }),
+            Err(error) => errors.push(DataError {
+                row,
+                column: None,
+                line: None,
+                error,
+            }),
+        }
+    }
+    if let Some(loaded) = yaml_table.loaded {
+        origins.extend(loaded.insert_into(&mut table, &mut errors));
+    }
+    match report {
+        Some(report) => {
+            if !errors.is_empty() {
+                report.errors.push(data_errors(file, &table.name, &errors));
+            }
+            report.origins.insert(table.name.clone(), origins);
         }
+        None if !errors.is_empty() => return Err(data_errors(file, &table.name, &errors)),
+        None => {}
+    }
+    for column in auto_increment {
+        table.set_auto_increment(column)?;
+    }
+    if let Some(generate) = &generate {
+        generate_rows(&mut table, generate)?;
+    }
+    compute_loaded_rows(&mut table).await?;
+    check_loaded_rows(&table).await?;
 
-        database.add_table(table)?;
+    Ok(table)
+}
+
+/// Add the rows of a data file to a table: a Parquet file when the name ends in `.parquet`,
+/// otherwise a CSV file
+async fn load_data_file(table: &mut Table, path: &Path) -> crate::Result<()> {
+    if is_parquet_file(path) {
+        load_parquet_rows(table, path).await
+    } else {
+        load_csv_rows(table, path).await
     }
+}
 
-    info!(
-        "Successfully parsed database with {} tables",
-        database.tables.len()
-    );
-    Ok((database, auth_config))
+/// Whether a data file is a Parquet file, which may be compressed
+pub(crate) fn is_parquet_file(path: &Path) -> bool {
+    uncompressed_name(path)
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("parquet"))
+}
+
+/// Add the rows of a CSV file to a table. The header names the columns; columns it leaves
+/// out get their default, as in `data:`.
+async fn load_csv_rows(table: &mut Table, path: &Path) -> crate::Result<()> {
+    let error = |message: String| YamlBaseError::Config(format!("{}: {}", path.display(), message));
+    let content = read_text(path).await.map_err(|e| error(e.to_string()))?;
+    let (header, records) = parse_csv(&content).map_err(error)?;
+    let positions = data_file_columns(table, &header).map_err(error)?;
+
+    for (line, fields) in records {
+        let line_error = |e: YamlBaseError| error(format!("line {}: {}", line, e));
+        let mut values: Vec<Option<DbValue>> = vec![None; table.columns.len()];
+        for (&position, field) in positions.iter().zip(fields) {
+            let column = &table.columns[position];
+            values[position] = Some(match field {
+                Some(field) => text_value(&field, &column.sql_type).map_err(line_error)?,
+                None => DbValue::Null,
+            });
+        }
+        insert_data_row(table, values).map_err(line_error)?;
+    }
+    Ok(())
+}
+
+/// Add the rows of a Parquet file to a table, matching its columns by name
+#[cfg(feature = "parquet")]
+async fn load_parquet_rows(table: &mut Table, path: &Path) -> crate::Result<()> {
+    use crate::yaml::compression::read_file;
+    use crate::yaml::parquet::{parquet_value, parse_parquet};
+
+    let error = |message: String| YamlBaseError::Config(format!("{}: {}", path.display(), message));
+    let content = read_file(path).await.map_err(|e| error(e.to_string()))?;
+    let (header, rows) = parse_parquet(content).map_err(error)?;
+    let positions = data_file_columns(table, &header).map_err(error)?;
+
+    for (index, fields) in rows.into_iter().enumerate() {
+        let row_error = |e: YamlBaseError| error(format!("row {}: {}", index + 1, e));
+        let mut values: Vec<Option<DbValue>> = vec![None; table.columns.len()];
+        for (&position, field) in positions.iter().zip(&fields) {
+            let column = &table.columns[position];
+            values[position] = Some(parquet_value(field, &column.sql_type).map_err(row_error)?);
+        }
+        insert_data_row(table, values).map_err(row_error)?;
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "parquet"))]
+async fn load_parquet_rows(_table: &mut Table, path: &Path) -> crate::Result<()> {
+    Err(YamlBaseError::Config(format!(
+        "{}: reading Parquet files needs yamlbase built with the 'parquet' feature",
+        path.display()
+    )))
+}
+
+/// The position in the table of each column a data file names
+fn data_file_columns(table: &Table, header: &[String]) -> Result<Vec<usize>, String> {
+    header
+        .iter()
+        .map(|name| {
+            table
+                .columns
+                .iter()
+                .position(|column| column.name.eq_ignore_ascii_case(name))
+                .ok_or_else(|| {
+                    format!(
+                        "column '{}' is not a column of table '{}'",
+                        name, table.name
+                    )
+                })
+        })
+        .collect()
+}
+
+/// Insert a row of `data:` or a data file, giving the columns it has no value for their default.
+/// Computed columns are left NULL until the table is complete.
+pub(crate) fn insert_data_row(
+    table: &mut Table,
+    values: Vec<Option<DbValue>>,
+) -> crate::Result<()> {
+    let mut row = Vec::with_capacity(values.len());
+    for (index, (value, column)) in values.into_iter().zip(&table.columns).enumerate() {
+        row.push(match value {
+            Some(_) if table.is_computed(index) => {
+                return Err(YamlBaseError::Config(format!(
+                    "Column '{}' of table '{}' is computed and cannot be given a value",
+                    column.name, table.name
+                )));
+            }
+            Some(value) => value,
+            None if table.is_computed(index) => DbValue::Null,
+            None => column_default(column)?,
+        });
+    }
+    table.insert_row(row)
+}
+
+/// A value of `data:` as a value of the column's type. A value with environment variables
+/// is read like a CSV field, and the strings of a list or mapping have theirs replaced.
+pub(crate) fn data_value(
+    yaml_value: &serde_yaml::Value,
+    sql_type: &SqlType,
+    coercion: Coercion,
+) -> crate::Result<DbValue> {
+    match yaml_value {
+        serde_yaml::Value::String(text) if text.contains("${") => {
+            interpolate(text).and_then(|text| text_value(&text, sql_type))
+        }
+        serde_yaml::Value::Sequence(_) | serde_yaml::Value::Mapping(_) => {
+            coercion.convert(&interpolate_nested(yaml_value)?, sql_type)
+        }
+        _ => coercion.convert(yaml_value, sql_type),
+    }
+}
+
+/// A field of a data file, as text, as a value of the column's type
+pub(crate) fn text_value(field: &str, sql_type: &SqlType) -> crate::Result<DbValue> {
+    let invalid =
+        || YamlBaseError::TypeConversion(format!("Cannot convert '{}' to {:?}", field, sql_type));
+    let trimmed = field.trim();
+    match sql_type {
+        SqlType::Boolean => match trimmed.to_lowercase().as_str() {
+            "true" | "t" | "yes" | "y" | "1" => Ok(DbValue::Boolean(true)),
+            "false" | "f" | "no" | "n" | "0" => Ok(DbValue::Boolean(false)),
+            _ => Err(invalid()),
+        },
+        SqlType::Float => trimmed.parse().map(DbValue::Float).map_err(|_| invalid()),
+        SqlType::Double => trimmed.parse().map(DbValue::Double).map_err(|_| invalid()),
+        SqlType::Decimal(_, _) => trimmed.parse().map(DbValue::Decimal).map_err(|_| invalid()),
+        SqlType::Json => serde_json::from_str(field)
+            .map(DbValue::Json)
+            .map_err(|_| invalid()),
+        SqlType::Char(_) | SqlType::Varchar(_) | SqlType::Text => {
+            Ok(DbValue::Text(field.to_string()))
+        }
+        // As PostgreSQL writes arrays, `{a,b}`, or as a JSON array
+        SqlType::Array(_) if trimmed.starts_with('[') => {
+            let items: serde_yaml::Value = serde_json::from_str(trimmed).map_err(|_| invalid())?;
+            parse_value(&items, sql_type).map_err(|_| invalid())
+        }
+        SqlType::Array(element_type) => parse_array_literal(trimmed)
+            .ok_or_else(invalid)?
+            .into_iter()
+            .map(|element| match element {
+                Some(element) => text_value(&element, element_type),
+                None => Ok(DbValue::Null),
+            })
+            .collect::<crate::Result<_>>()
+            .map(DbValue::Array),
+        _ => parse_value(&serde_yaml::Value::String(trimmed.to_string()), sql_type),
+    }
+}
+
+/// The value of a column a row leaves out
+fn column_default(column: &Column) -> crate::Result<DbValue> {
+    if column.nullable {
+        Ok(DbValue::Null)
+    } else if let Some(default) = &column.default {
+        parse_default_value(default, &column.sql_type)
+    } else {
+        Err(crate::YamlBaseError::Database {
+            message: format!(
+                "Non-nullable column '{}' has no value and no default",
+                column.name
+            ),
+        })
+    }
+}
+
+/// A path written in a YAML file, which is relative to that file
+fn relative_to(file: &Path, path: &str) -> PathBuf {
+    file.parent()
+        .map_or_else(|| PathBuf::from(path), |dir| dir.join(path))
+}
+
+type LoadedTables = IndexMap<String, (PathBuf, YamlTable)>;
+type LoadedViews = IndexMap<String, (PathBuf, YamlMaterializedView)>;
+
+/// The tables and materialized views, each with the file defining it, of files and of the
+/// files they include, in the order they appear. A name may only be defined once and a
+/// file only included once.
+async fn load_includes(
+    files: Vec<(PathBuf, YamlInclude)>,
+) -> crate::Result<(LoadedTables, LoadedViews)> {
+    let mut tables: LoadedTables = IndexMap::new();
+    let mut views: LoadedViews = IndexMap::new();
+    let mut loaded: Vec<PathBuf> = files.iter().map(|(path, _)| canonical_path(path)).collect();
+
+    // Files still to load, the next one last, so included files follow their includer
+    let mut pending: Vec<_> = files.into_iter().rev().collect();
+    while let Some((path, file)) = pending.pop() {
+        for (name, table) in file.tables {
+            check_defined_once(&name, &path, tables.iter().map(|(n, (f, _))| (n, f)))?;
+            tables.insert(name, (path.clone(), table));
+        }
+        for (name, view) in file.materialized_views {
+            check_defined_once(&name, &path, views.iter().map(|(n, (f, _))| (n, f)))?;
+            views.insert(name, (path.clone(), view));
+        }
+
+        let mut included = Vec::new();
+        for include in &file.include {
+            let include_path = relative_to(&path, include);
+            let canonical = canonical_path(&include_path);
+            if loaded.contains(&canonical) {
+                return Err(YamlBaseError::Config(format!(
+                    "'{}' is included more than once",
+                    include_path.display()
+                )));
+            }
+            loaded.push(canonical);
+
+            debug!("Including YAML file: {}", include_path.display());
+            if is_large(&include_path) {
+                let include_file =
+                    stream::load(&include_path, |file: &mut YamlInclude| &mut file.tables)?;
+                included.push((include_path, include_file));
+                continue;
+            }
+            let content = read_text(&include_path).await.map_err(|e| {
+                YamlBaseError::Config(format!(
+                    "Cannot read included file '{}': {}",
+                    include_path.display(),
+                    e
+                ))
+            })?;
+            let include_file: YamlInclude = serde_yaml::from_str(&content)
+                .map_err(|e| YamlBaseError::Config(format!("{}: {}", include_path.display(), e)))?;
+            included.push((include_path, include_file));
+        }
+        pending.extend(included.into_iter().rev());
+    }
+
+    Ok((tables, views))
+}
+
+fn check_defined_once<'a>(
+    name: &str,
+    path: &Path,
+    defined: impl Iterator<Item = (&'a String, &'a PathBuf)>,
+) -> crate::Result<()> {
+    // Names are looked up ignoring case, so `Users` and `users` would clash
+    for (other, other_path) in defined {
+        if other.eq_ignore_ascii_case(name) && other_path == path {
+            return Err(YamlBaseError::Config(format!(
+                "'{}' is defined twice in {}",
+                name,
+                path.display()
+            )));
+        }
+        if other.eq_ignore_ascii_case(name) {
+            return Err(YamlBaseError::Config(format!(
+                "'{}' is defined in both {} and {}",
+                name,
+                other_path.display(),
+                path.display()
+            )));
+        }
+    }
+    Ok(())
+}
+
+fn canonical_path(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
 }
 
-fn parse_value(yaml_value: &serde_yaml::Value, sql_type: &SqlType) -> crate::Result<DbValue> {
+pub(crate) fn parse_value(
+    yaml_value: &serde_yaml::Value,
+    sql_type: &SqlType,
+) -> crate::Result<DbValue> {
     use serde_yaml::Value;
 
     match (yaml_value, sql_type) {
@@ -147,6 +781,15 @@ fn parse_value(yaml_value: &serde_yaml::Value, sql_type: &SqlType) -> crate::Res
             }
         }
 
+        (Value::String(s), SqlType::TimestampTz) => parse_timestamptz(s, &TimeZone::default_zone())
+            .map(DbValue::TimestampTz)
+            .ok_or_else(|| {
+                crate::YamlBaseError::TypeConversion(format!(
+                    "Cannot parse timestamp with time zone: {}",
+                    s
+                ))
+            }),
+
         (Value::String(s), SqlType::Date) => {
             match chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d") {
                 Ok(d) => Ok(DbValue::Date(d)),
@@ -175,6 +818,13 @@ fn parse_value(yaml_value: &serde_yaml::Value, sql_type: &SqlType) -> crate::Res
             ))),
         },
 
+        (Value::String(s), SqlType::Blob) => parse_bytes(s).map(DbValue::Bytes).ok_or_else(|| {
+            crate::YamlBaseError::TypeConversion(format!(
+                "Cannot decode binary value, expected base64 or hex after \\x: {}",
+                s
+            ))
+        }),
+
         (Value::String(s), SqlType::Integer) => match s.parse::<i64>() {
             Ok(i) => Ok(DbValue::Integer(i)),
             Err(_) => Err(crate::YamlBaseError::TypeConversion(format!(
@@ -183,27 +833,107 @@ fn parse_value(yaml_value: &serde_yaml::Value, sql_type: &SqlType) -> crate::Res
             ))),
         },
 
-        (Value::Mapping(_) | Value::Sequence(_), SqlType::Json) => {
-            let json_str = serde_json::to_string(yaml_value).map_err(|e| {
+        (Value::Sequence(items), SqlType::Array(element_type)) => items
+            .iter()
+            .map(|item| parse_value(item, element_type))
+            .collect::<crate::Result<_>>()
+            .map(DbValue::Array),
+
+        (Value::String(s), SqlType::Array(_)) => text_value(s, sql_type),
+
+        // Strings are JSON text, which is read when converting leniently
+        (
+            Value::Mapping(_)
+            | Value::Sequence(_)
+            | Value::Number(_)
+            | Value::Bool(_)
+            | Value::Tagged(_),
+            SqlType::Json,
+        ) => {
+            let mut yaml_value = yaml_value.clone();
+            yaml_value.apply_merge().map_err(|e| {
                 crate::YamlBaseError::TypeConversion(format!("Cannot convert to JSON: {}", e))
             })?;
-            Ok(DbValue::Json(serde_json::from_str(&json_str).map_err(
-                |e| crate::YamlBaseError::TypeConversion(format!("Invalid JSON structure: {}", e)),
-            )?))
+            yaml_json(&yaml_value).map(DbValue::Json)
         }
 
+        // The value as it could be written in the file
         _ => Err(crate::YamlBaseError::TypeConversion(format!(
-            "Cannot convert {:?} to {:?}",
-            yaml_value, sql_type
+            "Cannot convert {} to {:?}",
+            serde_json::to_string(yaml_value).unwrap_or_else(|_| format!("{:?}", yaml_value)),
+            sql_type
         ))),
     }
 }
 
-fn parse_default_value(default: &str, sql_type: &SqlType) -> crate::Result<DbValue> {
+/// A YAML value as JSON. Keys that are numbers or booleans become their text and tagged
+/// values are their value without the tag.
+fn yaml_json(yaml_value: &serde_yaml::Value) -> crate::Result<serde_json::Value> {
+    use serde_json::Value as Json;
+    use serde_yaml::Value;
+
+    let invalid = |reason: &str| {
+        crate::YamlBaseError::TypeConversion(format!(
+            "Cannot convert {} to JSON, {}",
+            serde_yaml::to_string(yaml_value).unwrap_or_default().trim(),
+            reason
+        ))
+    };
+    Ok(match yaml_value {
+        Value::Null => Json::Null,
+        Value::Bool(b) => Json::Bool(*b),
+        Value::Number(n) => match (n.as_i64(), n.as_u64(), n.as_f64()) {
+            (Some(i), _, _) => Json::from(i),
+            (_, Some(u), _) => Json::from(u),
+            (_, _, Some(f)) => serde_json::Number::from_f64(f)
+                .map(Json::Number)
+                .ok_or_else(|| invalid("which has no infinite or NaN numbers"))?,
+            _ => return Err(invalid("which has no such numbers")),
+        },
+        Value::String(s) => Json::String(s.clone()),
+        Value::Sequence(items) => {
+            Json::Array(items.iter().map(yaml_json).collect::<Result<_, _>>()?)
+        }
+        Value::Mapping(mapping) => {
+            let mut object = serde_json::Map::new();
+            for (key, value) in mapping {
+                let key = match key {
+                    Value::String(s) => s.clone(),
+                    Value::Number(n) => n.to_string(),
+                    Value::Bool(b) => b.to_string(),
+                    Value::Null => "null".to_string(),
+                    _ => return Err(invalid("whose keys must be scalars")),
+                };
+                object.insert(key, yaml_json(value)?);
+            }
+            Json::Object(object)
+        }
+        Value::Tagged(tagged) => yaml_json(&tagged.value)?,
+    })
+}
+
+/// The bytes of a BLOB value, written as hex after `\x` or `0x`, or as base64
+pub(crate) fn parse_bytes(text: &str) -> Option<Vec<u8>> {
+    use base64::Engine;
+
+    let text = text.trim();
+    match text.strip_prefix("\\x").or_else(|| text.strip_prefix("0x")) {
+        Some(digits) => hex::decode(digits).ok(),
+        None => {
+            let text: String = text.split_whitespace().collect();
+            base64::engine::general_purpose::STANDARD.decode(&text).ok()
+        }
+    }
+}
+
+pub(crate) fn parse_default_value(default: &str, sql_type: &SqlType) -> crate::Result<DbValue> {
     match default.to_uppercase().as_str() {
         "NULL" => Ok(DbValue::Null),
         "TRUE" => Ok(DbValue::Boolean(true)),
         "FALSE" => Ok(DbValue::Boolean(false)),
+        "CURRENT_TIMESTAMP" if *sql_type == SqlType::TimestampTz => Ok(DbValue::TimestampTz(
+            TimeZone::default_zone().from_instant(&chrono::Utc::now()),
+        )),
         "CURRENT_TIMESTAMP" => Ok(DbValue::Timestamp(chrono::Local::now().naive_local())),
         _ => {
             // Try to parse as the specific type
@@ -0,0 +1,180 @@
+// Database files served over HTTP(S) or kept in object storage. A URL given as the database
+// file, and the `include:` and `data_file:` entries relative to it, are fetched instead of
+// read from disk: http(s):// URLs with the headers set by `set_headers` (such as an
+// Authorization header), s3:// and gs:// URLs with the `object-store` feature. With hot
+// reload the URLs are fetched again periodically and the database reloaded when one of
+// them changed.
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{OnceLock, RwLock};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+use crate::YamlBaseError;
+#[cfg(feature = "object-store")]
+use crate::yaml::cloud::fetch_object;
+
+static HEADERS: RwLock<Vec<(String, String)>> = RwLock::new(Vec::new());
+static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+
+/// Send these headers with every request for a database file
+pub fn set_headers(headers: Vec<(String, String)>) {
+    *HEADERS.write().unwrap() = headers;
+}
+
+/// A header written as `Name: value`
+pub fn parse_header(header: &str) -> crate::Result<(String, String)> {
+    match header.split_once(':') {
+        Some((name, value)) if !name.trim().is_empty() => {
+            Ok((name.trim().to_string(), value.trim().to_string()))
+        }
+        _ => Err(YamlBaseError::Config(format!(
+            "Invalid header '{}', expected 'Name: value'",
+            header
+        ))),
+    }
+}
+
+/// The URL schemes a database file can be fetched from
+const SCHEMES: &[&str] = &["http", "https", "s3", "gs", "gcs"];
+
+/// Whether a database file is a URL to fetch it from
+pub fn is_url(path: &Path) -> bool {
+    url(path).is_some()
+}
+
+/// The URL a path stands for. A URL joined with a relative path keeps its `//`, since
+/// paths only split on separators.
+pub(crate) fn url(path: &Path) -> Option<&str> {
+    let text = path.to_str()?;
+    let scheme = text.split_once("://")?.0;
+    SCHEMES
+        .iter()
+        .any(|known| scheme.eq_ignore_ascii_case(known))
+        .then_some(text)
+}
+
+/// The contents of the file at a URL
+pub(crate) async fn fetch(url: &str) -> io::Result<Vec<u8>> {
+    let scheme = url.split_once("://").map_or("", |(scheme, _)| scheme);
+    if scheme.eq_ignore_ascii_case("http") || scheme.eq_ignore_ascii_case("https") {
+        fetch_http(url).await
+    } else {
+        fetch_object(url).await
+    }
+}
+
+#[cfg(not(feature = "object-store"))]
+async fn fetch_object(url: &str) -> io::Result<Vec<u8>> {
+    Err(io::Error::other(format!(
+        "Reading {} needs yamlbase built with the 'object-store' feature",
+        url
+    )))
+}
+
+/// The body of a successful GET of the URL
+async fn fetch_http(url: &str) -> io::Result<Vec<u8>> {
+    let client = CLIENT.get_or_init(reqwest::Client::new);
+    let mut request = client.get(url);
+    for (name, value) in HEADERS.read().unwrap().iter() {
+        request = request.header(name, value);
+    }
+    let error = |e: reqwest::Error| io::Error::other(format!("Cannot fetch {}: {}", url, e));
+    let response = request
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(error)?;
+    Ok(response.bytes().await.map_err(error)?.to_vec())
+}
+
+/// Fetches the URLs of a database at an interval, the way `FileWatcher` watches files
+pub struct UrlPoller {
+    urls: Vec<PathBuf>,
+    interval: Duration,
+    tx: mpsc::Sender<Vec<PathBuf>>,
+}
+
+impl UrlPoller {
+    /// Poll the URLs a database was loaded from. The receiver gets the URLs whose
+    /// contents changed since they were last fetched.
+    pub fn new(urls: Vec<PathBuf>, interval: Duration) -> (Self, mpsc::Receiver<Vec<PathBuf>>) {
+        let (tx, rx) = mpsc::channel(10);
+        (Self { urls, interval, tx }, rx)
+    }
+
+    pub fn start(self) {
+        tokio::spawn(async move {
+            for url in &self.urls {
+                info!(
+                    "Fetching {} every {:?} for changes",
+                    url.display(),
+                    self.interval
+                );
+            }
+            let mut fingerprints = vec![None; self.urls.len()];
+            poll(&self.urls, &mut fingerprints).await;
+            loop {
+                tokio::time::sleep(self.interval).await;
+                let changed = poll(&self.urls, &mut fingerprints).await;
+                if !changed.is_empty() {
+                    info!("URL changed, triggering reload");
+                    if self.tx.send(changed).await.is_err() {
+                        break;
+                    }
+                }
+            }
+        });
+    }
+}
+
+/// Fetch the URLs, giving those whose contents differ from the last fetch. A URL that
+/// cannot be fetched counts as unchanged.
+async fn poll(urls: &[PathBuf], fingerprints: &mut [Option<u64>]) -> Vec<PathBuf> {
+    let mut changed = Vec::new();
+    for (path, fingerprint) in urls.iter().zip(fingerprints) {
+        let Some(url) = url(path) else {
+            continue;
+        };
+        match fetch(url).await {
+            Ok(body) => {
+                let mut hasher = DefaultHasher::new();
+                body.hash(&mut hasher);
+                let new = Some(hasher.finish());
+                if fingerprint.is_some() && *fingerprint != new {
+                    changed.push(path.clone());
+                }
+                *fingerprint = new;
+            }
+            Err(e) => warn!("{}", e),
+        }
+    }
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_urls_and_headers() {
+        let base = Path::new("https://fixtures.example/app/db.yaml");
+        assert_eq!(
+            url(&base.parent().unwrap().join("orders.csv")),
+            Some("https://fixtures.example/app/orders.csv")
+        );
+        assert!(is_url(Path::new("HTTP://localhost/db.yaml")));
+        assert!(!is_url(Path::new("/data/db.yaml")));
+        assert!(is_url(Path::new("s3://bucket/db.yaml")));
+        assert!(!is_url(Path::new("ftp://host/db.yaml")));
+
+        assert_eq!(
+            parse_header("Authorization: Bearer abc:def").unwrap(),
+            ("Authorization".to_string(), "Bearer abc:def".to_string())
+        );
+        assert!(parse_header("no colon").is_err());
+    }
+}
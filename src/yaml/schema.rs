@@ -2,10 +2,32 @@ use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 use serde_yaml::Value;
 
+use crate::database::Collation;
+use crate::yaml::stream::LoadedRows;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct YamlDatabase {
     pub database: DatabaseInfo,
+    /// Files holding more tables and materialized views, relative to this file
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+    #[serde(default)]
+    pub tables: IndexMap<String, YamlTable>,
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub materialized_views: IndexMap<String, YamlMaterializedView>,
+}
+
+/// A file listed under `include:`. It adds tables and materialized views to the database
+/// of the file including it and may include further files.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct YamlInclude {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+    #[serde(default)]
     pub tables: IndexMap<String, YamlTable>,
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub materialized_views: IndexMap<String, YamlMaterializedView>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -18,7 +40,36 @@ pub struct DatabaseInfo {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AuthConfig {
     pub username: String,
+    #[serde(default)]
     pub password: String,
+    /// A bcrypt or argon2 hash of the password, checked instead of `password`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password_hash: Option<String>,
+    /// Users besides `username`, which may be limited to some tables
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub users: Vec<UserConfig>,
+}
+
+/// A user of the `auth.users` list. A user without `grants` may use every table.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserConfig {
+    pub username: String,
+    #[serde(default)]
+    pub password: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password_hash: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub grants: Option<Grants>,
+}
+
+/// The tables a user may read and those it may change, each named as `table`,
+/// `schema.table`, `schema.*` for the tables of a schema or `*` for all tables
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Grants {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub read: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub write: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -26,6 +77,77 @@ pub struct YamlTable {
     pub columns: IndexMap<String, String>,
     #[serde(default)]
     pub data: Vec<IndexMap<String, Value>>,
+    /// A CSV or Parquet file holding the rows instead of `data:`, relative to this file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data_file: Option<String>,
+    /// A CHECK constraint over the columns of each row
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub check: Option<String>,
+    /// Columns computed from the other columns of each row, by the SQL expression giving
+    /// their value
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub computed: IndexMap<String, String>,
+    /// Whether the values of `data:` must already be of their column's type, overriding
+    /// --strict-types for this table
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub strict_types: Option<bool>,
+    /// Rows made up when the file is loaded, added after the rows of `data:`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub generate: Option<YamlGenerate>,
+    /// The rows of `data:` when the file was streamed, already converted
+    #[serde(skip)]
+    pub(crate) loaded: Option<LoadedRows>,
+}
+
+/// How many rows to generate and how to fill their columns. Columns without a generator
+/// get their default.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct YamlGenerate {
+    pub count: usize,
+    /// Seed of the random generators, so that every load makes the same rows
+    #[serde(default)]
+    pub seed: u64,
+    /// Each generator is a map with one key naming it, such as `{ sequence: { start: 1 } }`
+    #[serde(default, with = "serde_yaml::with::singleton_map_recursive")]
+    pub columns: IndexMap<String, YamlGenerator>,
+}
+
+/// The values of a generated column
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum YamlGenerator {
+    /// `start`, `start + step`, ...
+    Sequence {
+        #[serde(default = "one")]
+        start: i64,
+        #[serde(default = "one")]
+        step: i64,
+    },
+    /// One of the values, picked at random
+    Choice(Vec<Value>),
+    /// A random number from `min` to `max`
+    Range { min: f64, max: f64 },
+    /// A random date or timestamp from `start` to `end`
+    DateRange { start: String, end: String },
+    /// Text with `{n}` replaced by the row number, counting from 1
+    Format(String),
+    /// The same value in every row
+    Value(Value),
+    /// A realistic looking fake value of a kind such as `name` or `email`, with the
+    /// `faker` feature
+    Fake(String),
+}
+
+fn one() -> i64 {
+    1
+}
+
+/// A table holding the result of a query, computed when the file is loaded and again on
+/// `REFRESH MATERIALIZED VIEW`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct YamlMaterializedView {
+    pub query: String,
 }
 
 #[derive(Debug, Clone)]
@@ -37,6 +159,10 @@ pub struct YamlColumn {
     pub is_unique: bool,
     pub default_value: Option<String>,
     pub references: Option<ForeignKeyRef>,
+    pub collation: Option<Collation>,
+    pub is_auto_increment: bool,
+    /// The expression of a `CHECK (...)` in the definition
+    pub check: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -47,7 +173,8 @@ pub struct ForeignKeyRef {
 
 impl YamlColumn {
     pub fn parse(name: String, type_def: &str) -> crate::Result<Self> {
-        let type_def_upper = type_def.to_uppercase();
+        let (rest, check) = split_check(type_def)?;
+        let type_def_upper = rest.to_uppercase();
         let parts: Vec<&str> = type_def_upper.split_whitespace().collect();
 
         let mut column = YamlColumn {
@@ -58,8 +185,20 @@ impl YamlColumn {
             is_unique: false,
             default_value: None,
             references: None,
+            collation: None,
+            is_auto_increment: false,
+            check,
         };
 
+        // SERIAL is an integer whose values are generated, as in PostgreSQL
+        if matches!(
+            parts.first(),
+            Some(&"SERIAL" | &"BIGSERIAL" | &"SMALLSERIAL")
+        ) {
+            column.is_auto_increment = true;
+            column.is_nullable = false;
+        }
+
         let mut i = 0;
         while i < parts.len() {
             match parts[i] {
@@ -80,6 +219,10 @@ impl YamlColumn {
                     column.is_unique = true;
                     i += 1;
                 }
+                "AUTO_INCREMENT" | "AUTOINCREMENT" => {
+                    column.is_auto_increment = true;
+                    i += 1;
+                }
                 "DEFAULT" if i + 1 < parts.len() => {
                     if i + 2 < parts.len() && parts[i + 1] == "CURRENT_TIMESTAMP" {
                         column.default_value = Some("CURRENT_TIMESTAMP".to_string());
@@ -91,15 +234,19 @@ impl YamlColumn {
                 }
                 "REFERENCES" if i + 1 < parts.len() => {
                     let ref_str = parts[i + 1];
-                    if let Some(open_paren) = ref_str.find('(') {
-                        if let Some(close_paren) = ref_str.find(')') {
-                            let table = ref_str[..open_paren].to_string();
-                            let col = ref_str[open_paren + 1..close_paren].to_string();
-                            column.references = Some(ForeignKeyRef { table, column: col });
-                        }
+                    if let Some(open_paren) = ref_str.find('(')
+                        && let Some(close_paren) = ref_str.find(')')
+                    {
+                        let table = ref_str[..open_paren].to_string();
+                        let col = ref_str[open_paren + 1..close_paren].to_string();
+                        column.references = Some(ForeignKeyRef { table, column: col });
                     }
                     i += 2;
                 }
+                "COLLATE" if i + 1 < parts.len() => {
+                    column.collation = Some(Collation::parse(parts[i + 1])?);
+                    i += 2;
+                }
                 _ => i += 1,
             }
         }
@@ -110,38 +257,61 @@ impl YamlColumn {
     pub fn get_base_type(&self) -> crate::Result<SqlType> {
         let type_upper = self.type_def.to_uppercase();
         let base_type = type_upper.split_whitespace().next().unwrap_or("");
+        sql_type(base_type, &type_upper)
+    }
+}
 
-        Ok(match base_type {
-            "INTEGER" | "INT" | "BIGINT" | "SMALLINT" => SqlType::Integer,
-            s if s.starts_with("CHAR") && !s.starts_with("CHARACTER") => {
-                let size = extract_size(s).unwrap_or(1);
-                SqlType::Char(size)
-            }
-            s if s.starts_with("VARCHAR") => {
-                let size = extract_size(s).unwrap_or(255);
-                SqlType::Varchar(size)
-            }
-            "TEXT" | "CLOB" => SqlType::Text,
-            "TIMESTAMP" | "DATETIME" => SqlType::Timestamp,
-            "DATE" => SqlType::Date,
-            "TIME" => SqlType::Time,
-            "BOOLEAN" | "BOOL" => SqlType::Boolean,
-            s if s.starts_with("DECIMAL") || s.starts_with("NUMERIC") => {
-                let (precision, scale) = extract_decimal_params(s).unwrap_or((10, 2));
-                SqlType::Decimal(precision, scale)
-            }
-            "FLOAT" | "REAL" => SqlType::Float,
-            "DOUBLE" => SqlType::Double,
-            "UUID" => SqlType::Uuid,
-            "JSON" | "JSONB" => SqlType::Json,
-            _ => {
-                return Err(crate::YamlBaseError::TypeConversion(format!(
-                    "Unknown SQL type: {}",
-                    base_type
-                )));
-            }
-        })
+/// The type named by the first word of a column definition
+fn sql_type(base_type: &str, type_upper: &str) -> crate::Result<SqlType> {
+    // `TEXT[]` and `VARCHAR(20)[]` are arrays of the type before the brackets
+    if let Some(element_type) = base_type.strip_suffix("[]") {
+        return Ok(SqlType::Array(Box::new(sql_type(
+            element_type,
+            element_type,
+        )?)));
     }
+
+    Ok(match base_type {
+        "INTEGER" | "INT" | "BIGINT" | "SMALLINT" | "SERIAL" | "BIGSERIAL" | "SMALLSERIAL" => {
+            SqlType::Integer
+        }
+        s if s.starts_with("CHAR") && !s.starts_with("CHARACTER") => {
+            let size = extract_size(s).unwrap_or(1);
+            SqlType::Char(size)
+        }
+        s if s.starts_with("VARCHAR") => {
+            let size = extract_size(s).unwrap_or(255);
+            SqlType::Varchar(size)
+        }
+        "TEXT" | "CLOB" => SqlType::Text,
+        "TIMESTAMP"
+            if type_upper.contains("WITH TIME ZONE")
+                && !type_upper.contains("WITHOUT TIME ZONE") =>
+        {
+            SqlType::TimestampTz
+        }
+        "TIMESTAMPTZ" => SqlType::TimestampTz,
+        "TIMESTAMP" | "DATETIME" => SqlType::Timestamp,
+        "DATE" => SqlType::Date,
+        "TIME" => SqlType::Time,
+        "BOOLEAN" | "BOOL" => SqlType::Boolean,
+        s if s.starts_with("DECIMAL") || s.starts_with("NUMERIC") => {
+            let (precision, scale) = extract_decimal_params(s).unwrap_or((10, 2));
+            SqlType::Decimal(precision, scale)
+        }
+        "FLOAT" | "REAL" => SqlType::Float,
+        "DOUBLE" => SqlType::Double,
+        "UUID" => SqlType::Uuid,
+        "JSON" | "JSONB" => SqlType::Json,
+        "BLOB" | "TINYBLOB" | "MEDIUMBLOB" | "LONGBLOB" | "BYTEA" => SqlType::Blob,
+        s if s.starts_with("BINARY") || s.starts_with("VARBINARY") => SqlType::Blob,
+        _ => {
+            return Err(crate::YamlBaseError::TypeConversion(format!(
+                "Unknown SQL type: {}",
+                base_type
+            )));
+        }
+    })
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -152,6 +322,7 @@ pub enum SqlType {
     Varchar(usize),
     Text,
     Timestamp,
+    TimestampTz,
     Date,
     Time,
     Boolean,
@@ -160,42 +331,80 @@ pub enum SqlType {
     Double,
     Uuid,
     Json,
+    Blob,
+    /// A one-dimensional array of values of the element type
+    Array(Box<SqlType>),
+}
+
+/// A column definition without its `CHECK (<expression>)`, and the expression
+fn split_check(type_def: &str) -> crate::Result<(String, Option<String>)> {
+    let upper = type_def.to_uppercase();
+    let Some(start) = upper.match_indices("CHECK").map(|(i, _)| i).find(|&i| {
+        let word_start = i == 0 || upper.as_bytes()[i - 1].is_ascii_whitespace();
+        word_start && upper[i + 5..].trim_start().starts_with('(')
+    }) else {
+        return Ok((type_def.to_string(), None));
+    };
+
+    let open = start + 5 + (upper[start + 5..].len() - upper[start + 5..].trim_start().len());
+    let mut depth = 0;
+    let mut in_string = false;
+    for (offset, c) in type_def[open..].char_indices() {
+        match c {
+            '\'' => in_string = !in_string,
+            '(' if !in_string => depth += 1,
+            ')' if !in_string => {
+                depth -= 1;
+                if depth == 0 {
+                    let close = open + offset;
+                    let expression = type_def[open + 1..close].trim().to_string();
+                    let rest = format!("{} {}", &type_def[..start], &type_def[close + 1..]);
+                    return Ok((rest, Some(expression)));
+                }
+            }
+            _ => {}
+        }
+    }
+    Err(crate::YamlBaseError::Config(format!(
+        "Unbalanced parentheses in CHECK of '{}'",
+        type_def
+    )))
 }
 
 #[cfg(test)]
 pub(super) fn extract_size(type_str: &str) -> Option<usize> {
-    if let Some(start) = type_str.find('(') {
-        if let Some(end) = type_str.find(')') {
-            return type_str[start + 1..end].parse().ok();
-        }
+    if let Some(start) = type_str.find('(')
+        && let Some(end) = type_str.find(')')
+    {
+        return type_str[start + 1..end].parse().ok();
     }
     None
 }
 
 #[cfg(not(test))]
 fn extract_size(type_str: &str) -> Option<usize> {
-    if let Some(start) = type_str.find('(') {
-        if let Some(end) = type_str.find(')') {
-            return type_str[start + 1..end].parse().ok();
-        }
+    if let Some(start) = type_str.find('(')
+        && let Some(end) = type_str.find(')')
+    {
+        return type_str[start + 1..end].parse().ok();
     }
     None
 }
 
 fn extract_decimal_params(type_str: &str) -> Option<(u32, u32)> {
-    if let Some(start) = type_str.find('(') {
-        if let Some(end) = type_str.find(')') {
-            let params = &type_str[start + 1..end];
-            let parts: Vec<&str> = params.split(',').collect();
-            if parts.len() == 2 {
-                if let (Ok(p), Ok(s)) = (parts[0].trim().parse(), parts[1].trim().parse()) {
-                    return Some((p, s));
-                }
-            } else if parts.len() == 1 {
-                if let Ok(p) = parts[0].trim().parse() {
-                    return Some((p, 0));
-                }
+    if let Some(start) = type_str.find('(')
+        && let Some(end) = type_str.find(')')
+    {
+        let params = &type_str[start + 1..end];
+        let parts: Vec<&str> = params.split(',').collect();
+        if parts.len() == 2 {
+            if let (Ok(p), Ok(s)) = (parts[0].trim().parse(), parts[1].trim().parse()) {
+                return Some((p, s));
             }
+        } else if parts.len() == 1
+            && let Ok(p) = parts[0].trim().parse()
+        {
+            return Some((p, 0));
         }
     }
     None
@@ -0,0 +1,690 @@
+// Streaming of large YAML files. serde_yaml holds the whole document in memory, and each
+// row of `data:` becomes a map of YAML values before it is converted, so a file of
+// gigabytes needs many times its size. A file of STREAMING_SIZE or more is read instead as
+// a stream of parser events, and each row is converted to the column types as soon as it is
+// read: only the resulting values are kept. The rest of the file is small and is put
+// together as a serde_yaml value, to be deserialized as usual.
+//
+// The rows of a table are streamed when its `columns:` come before its `data:`, as they
+// usually do. Otherwise they are kept as YAML values like those of a small file.
+use indexmap::IndexMap;
+use serde::de::DeserializeOwned;
+use serde_yaml::{Mapping, Value};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::{self, Read};
+use std::path::Path;
+use std::rc::Rc;
+use yaml_rust2::parser::{Event, Parser, Tag};
+use yaml_rust2::scanner::{Marker, TScalarStyle};
+
+use crate::YamlBaseError;
+use crate::database::{Table, Value as DbValue};
+use crate::yaml::coercion::Coercion;
+use crate::yaml::compression::open_file;
+use crate::yaml::location::{DataError, RowOrigin, detail};
+use crate::yaml::parser::{data_value, insert_data_row};
+use crate::yaml::remote::url;
+use crate::yaml::schema::{SqlType, YamlColumn, YamlTable};
+
+/// Files of this size or more are streamed
+pub(crate) const STREAMING_SIZE: u64 = 64 * 1024 * 1024;
+
+/// The rows of a table's `data:`, converted to the column types as the file was read
+#[derive(Debug, Clone, Default)]
+pub(crate) struct LoadedRows {
+    /// The values of each row in column order, `None` for the columns it leaves out
+    rows: Vec<Vec<Option<DbValue>>>,
+    /// The line of each row
+    lines: Vec<usize>,
+    /// The values that could not be converted, in row order
+    errors: Vec<ValueError>,
+}
+
+#[derive(Debug, Clone)]
+struct ValueError {
+    row: usize,
+    column: String,
+    line: usize,
+    message: String,
+}
+
+impl LoadedRows {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// Insert the rows into the table, adding the errors of their values and of the rows
+    /// that cannot be inserted. Returns where the inserted rows came from.
+    pub(crate) fn insert_into(
+        self,
+        table: &mut Table,
+        errors: &mut Vec<DataError>,
+    ) -> Vec<RowOrigin> {
+        let mut origins = Vec::with_capacity(self.rows.len());
+        let mut value_errors = self.errors.into_iter().peekable();
+        for (row, (values, line)) in self.rows.into_iter().zip(self.lines).enumerate() {
+            let mut valid = true;
+            while let Some(error) = value_errors.next_if(|error| error.row == row) {
+                valid = false;
+                errors.push(DataError {
+                    row,
+                    column: Some(error.column),
+                    line: Some(error.line),
+                    error: YamlBaseError::TypeConversion(error.message),
+                });
+            }
+            if !valid {
+                continue;
+            }
+            match insert_data_row(table, values) {
+                Ok(()) => origins.push(RowOrigin {
+                    row,
+                    line: Some(line),
+                }),
+                Err(error) => errors.push(DataError {
+                    row,
+                    column: None,
+                    line: Some(line),
+                    error,
+                }),
+            }
+        }
+        origins
+    }
+}
+
+/// Whether a file is streamed: a local file of STREAMING_SIZE or more
+pub(crate) fn is_large(path: &Path) -> bool {
+    url(path).is_none()
+        && std::fs::metadata(path).is_ok_and(|metadata| metadata.len() >= STREAMING_SIZE)
+}
+
+/// Read a file as `T`, streaming the rows of the tables `tables` gives
+pub(crate) fn load<T: DeserializeOwned>(
+    path: &Path,
+    tables: impl FnOnce(&mut T) -> &mut IndexMap<String, YamlTable>,
+) -> crate::Result<T> {
+    let (value, rows) = read(path)?;
+    let mut loaded: T = serde_yaml::from_value(value)
+        .map_err(|e| YamlBaseError::Config(format!("{}: {}", path.display(), e)))?;
+    attach(tables(&mut loaded), rows);
+    Ok(loaded)
+}
+
+/// Give tables the rows streamed for them
+pub(crate) fn attach(tables: &mut IndexMap<String, YamlTable>, rows: IndexMap<String, LoadedRows>) {
+    for (name, rows) in rows {
+        if let Some(table) = tables.get_mut(&name) {
+            table.loaded = Some(rows);
+        }
+    }
+}
+
+/// The file as a YAML value without the `data:` of the tables whose rows were streamed, and
+/// those rows by table
+pub(crate) fn read(path: &Path) -> crate::Result<(Value, IndexMap<String, LoadedRows>)> {
+    let (mut loader, read_error) = Loader::open(path)?;
+    let value = loader.document();
+    let value = finish(path, value, read_error)?;
+    Ok((value, loader.rows))
+}
+
+/// The value of an entry of the file's top-level mapping, reading the file no further than
+/// that entry. `None` when the file has no such entry.
+pub(crate) fn read_top_entry(path: &Path, key: &str) -> crate::Result<Option<Value>> {
+    let (mut loader, read_error) = Loader::open(path)?;
+    let value = loader.top_entry_value(key);
+    finish(path, value, read_error)
+}
+
+/// The result of reading a file, or the error that stopped its reading
+fn finish<T>(
+    path: &Path,
+    result: Result<T, String>,
+    read_error: Rc<RefCell<Option<io::Error>>>,
+) -> crate::Result<T> {
+    if let Some(error) = read_error.take() {
+        return Err(YamlBaseError::Io(error));
+    }
+    result.map_err(|e| YamlBaseError::Config(format!("{}: {}", path.display(), e)))
+}
+
+struct Loader<I: Iterator<Item = char>> {
+    parser: Parser<I>,
+    anchors: HashMap<usize, Value>,
+    rows: IndexMap<String, LoadedRows>,
+}
+
+impl Loader<Chars<Box<dyn Read>>> {
+    /// A loader of the file, and where an error reading it is kept
+    fn open(path: &Path) -> io::Result<(Self, Rc<RefCell<Option<io::Error>>>)> {
+        let read_error = Rc::new(RefCell::new(None));
+        let chars = Chars {
+            reader: open_file(path)?,
+            pending: Vec::new(),
+            text: String::new(),
+            position: 0,
+            error: read_error.clone(),
+        };
+        let loader = Loader {
+            parser: Parser::new(chars),
+            anchors: HashMap::new(),
+            rows: IndexMap::new(),
+        };
+        Ok((loader, read_error))
+    }
+}
+
+impl<I: Iterator<Item = char>> Loader<I> {
+    fn next(&mut self) -> Result<(Event, Marker), String> {
+        self.parser.next_token().map_err(|e| e.to_string())
+    }
+
+    fn next_is(&mut self, start: fn(&Event) -> bool) -> Result<bool, String> {
+        let (event, _) = self.parser.peek().map_err(|e| e.to_string())?;
+        Ok(start(event))
+    }
+
+    /// The value of the single document of the file
+    fn document(&mut self) -> Result<Value, String> {
+        let mut value = Value::Null;
+        let mut documents = 0;
+        loop {
+            let (event, marker) = self.next()?;
+            match event {
+                Event::StreamStart | Event::DocumentEnd => {}
+                Event::StreamEnd => return Ok(value),
+                Event::DocumentStart => {
+                    documents += 1;
+                    if documents > 1 {
+                        return Err("a file may only hold one YAML document".to_string());
+                    }
+                }
+                Event::MappingStart(0, _) => value = self.mapping(Self::top_entry)?,
+                event => value = self.value(event, marker)?,
+            }
+        }
+    }
+
+    /// The value of the entry `wanted` of the top-level mapping. The entries before it are
+    /// skipped, and those after it are not read.
+    fn top_entry_value(&mut self, wanted: &str) -> Result<Option<Value>, String> {
+        loop {
+            let (event, _) = self.next()?;
+            match event {
+                Event::StreamStart | Event::DocumentStart => {}
+                Event::MappingStart(..) => break,
+                _ => return Ok(None),
+            }
+        }
+        loop {
+            let (event, marker) = self.next()?;
+            if let Event::MappingEnd = event {
+                return Ok(None);
+            }
+            let key = self.value(event, marker)?;
+            let (event, marker) = self.next()?;
+            if key.as_str() == Some(wanted) {
+                return self.value(event, marker).map(Some);
+            }
+            self.skip(event, marker)?;
+        }
+    }
+
+    /// Read past the value an event starts, keeping only the values of its anchors
+    fn skip(&mut self, event: Event, marker: Marker) -> Result<(), String> {
+        match &event {
+            Event::Scalar(_, _, anchor, _)
+            | Event::SequenceStart(anchor, _)
+            | Event::MappingStart(anchor, _)
+                if *anchor > 0 =>
+            {
+                self.value(event, marker).map(drop)
+            }
+            Event::SequenceStart(..) | Event::MappingStart(..) => loop {
+                let (event, marker) = self.next()?;
+                if let Event::SequenceEnd | Event::MappingEnd = event {
+                    return Ok(());
+                }
+                self.skip(event, marker)?;
+            },
+            _ => Ok(()),
+        }
+    }
+
+    /// The entries of a mapping up to its end, each added by `entry` from its key
+    fn mapping(
+        &mut self,
+        entry: fn(&mut Self, Value, &mut Mapping) -> Result<(), String>,
+    ) -> Result<Value, String> {
+        let mut mapping = Mapping::new();
+        loop {
+            let (event, marker) = self.next()?;
+            if let Event::MappingEnd = event {
+                return Ok(Value::Mapping(mapping));
+            }
+            let key = self.value(event, marker)?;
+            entry(self, key, &mut mapping)?;
+        }
+    }
+
+    fn entry(&mut self, key: Value, mapping: &mut Mapping) -> Result<(), String> {
+        let (event, marker) = self.next()?;
+        let value = self.value(event, marker)?;
+        mapping.insert(key, value);
+        Ok(())
+    }
+
+    fn top_entry(&mut self, key: Value, mapping: &mut Mapping) -> Result<(), String> {
+        if key.as_str() == Some("tables") && self.next_is(is_plain_mapping)? {
+            self.next()?;
+            let tables = self.mapping(Self::table_entry)?;
+            mapping.insert(key, tables);
+            Ok(())
+        } else {
+            self.entry(key, mapping)
+        }
+    }
+
+    fn table_entry(&mut self, key: Value, tables: &mut Mapping) -> Result<(), String> {
+        let Some(name) = key.as_str().map(str::to_string) else {
+            return self.entry(key, tables);
+        };
+        if !self.next_is(is_plain_mapping)? {
+            return self.entry(key, tables);
+        }
+        self.next()?;
+
+        let mut table = Mapping::new();
+        let mut streamed = None;
+        loop {
+            let (event, marker) = self.next()?;
+            if let Event::MappingEnd = event {
+                break;
+            }
+            let field = self.value(event, marker)?;
+            if field.as_str() == Some("data")
+                && let Some(columns) = table.get("columns").and_then(column_types)
+                && self.next_is(|event| matches!(event, Event::SequenceStart(0, _)))?
+            {
+                self.next()?;
+                let coercion = table_coercion(&table);
+                let rows = self.rows(&columns, coercion)?;
+                self.rows.insert(name.clone(), rows);
+                streamed = Some(coercion);
+            } else {
+                self.entry(field, &mut table)?;
+            }
+        }
+        // The rows were converted before the table's own mode was known
+        if let Some(coercion) = streamed
+            && coercion != table_coercion(&table)
+        {
+            return Err(format!(
+                "table '{}': strict_types: must come before data: in a file this large",
+                name
+            ));
+        }
+        tables.insert(key, Value::Mapping(table));
+        Ok(())
+    }
+
+    /// The rows of `data:` up to the end of the list, converted to the types of the columns
+    fn rows(
+        &mut self,
+        columns: &[(String, SqlType)],
+        coercion: Coercion,
+    ) -> Result<LoadedRows, String> {
+        let positions: HashMap<&str, usize> = columns
+            .iter()
+            .enumerate()
+            .map(|(position, (name, _))| (name.as_str(), position))
+            .collect();
+        let position = |key: &Value| key.as_str().and_then(|key| positions.get(key).copied());
+        let mut loaded = LoadedRows::default();
+        loop {
+            let (event, marker) = self.next()?;
+            let mut row = Row::new(loaded.rows.len(), columns, coercion);
+            match event {
+                Event::SequenceEnd => return Ok(loaded),
+                Event::MappingStart(0, _) => loop {
+                    let (event, key_marker) = self.next()?;
+                    if let Event::MappingEnd = event {
+                        break;
+                    }
+                    let key = self.value(event, key_marker)?;
+                    let (event, marker) = self.next()?;
+                    let value = self.value(event, marker)?;
+                    if let Some(position) = position(&key) {
+                        row.set(position, &value, key_marker.line(), &mut loaded.errors);
+                    }
+                },
+                // A row with an anchor, or an alias of one, is read whole
+                event => match self.value(event, marker)? {
+                    Value::Mapping(mapping) => {
+                        for (key, value) in &mapping {
+                            if let Some(position) = position(key) {
+                                row.set(position, value, marker.line(), &mut loaded.errors);
+                            }
+                        }
+                    }
+                    _ => {
+                        return Err(format!(
+                            "line {}: a row of data: must be a mapping of column names to values",
+                            marker.line()
+                        ));
+                    }
+                },
+            }
+            loaded.rows.push(row.values);
+            loaded.lines.push(marker.line());
+        }
+    }
+
+    /// The value an event starts, read to its end
+    fn value(&mut self, event: Event, marker: Marker) -> Result<Value, String> {
+        let (value, anchor) = match event {
+            Event::Scalar(text, style, anchor, tag) => (scalar(text, style, tag), anchor),
+            Event::SequenceStart(anchor, _) => {
+                let mut sequence = Vec::new();
+                loop {
+                    let (event, marker) = self.next()?;
+                    if let Event::SequenceEnd = event {
+                        break;
+                    }
+                    sequence.push(self.value(event, marker)?);
+                }
+                (Value::Sequence(sequence), anchor)
+            }
+            Event::MappingStart(anchor, _) => (self.mapping(Self::entry)?, anchor),
+            Event::Alias(anchor) => {
+                return self
+                    .anchors
+                    .get(&anchor)
+                    .cloned()
+                    .ok_or_else(|| format!("line {}: unknown alias", marker.line()));
+            }
+            event => return Err(format!("line {}: unexpected {:?}", marker.line(), event)),
+        };
+        if anchor > 0 {
+            self.anchors.insert(anchor, value.clone());
+        }
+        Ok(value)
+    }
+}
+
+/// A row of `data:` being read
+struct Row<'a> {
+    index: usize,
+    columns: &'a [(String, SqlType)],
+    coercion: Coercion,
+    values: Vec<Option<DbValue>>,
+}
+
+impl<'a> Row<'a> {
+    fn new(index: usize, columns: &'a [(String, SqlType)], coercion: Coercion) -> Self {
+        Self {
+            index,
+            columns,
+            coercion,
+            values: vec![None; columns.len()],
+        }
+    }
+
+    /// Convert the value of the column at a position, or add the error converting it
+    fn set(&mut self, position: usize, value: &Value, line: usize, errors: &mut Vec<ValueError>) {
+        let (column, sql_type) = &self.columns[position];
+        match data_value(value, sql_type, self.coercion) {
+            Ok(value) => self.values[position] = Some(value),
+            Err(error) => errors.push(ValueError {
+                row: self.index,
+                column: column.clone(),
+                line,
+                message: detail(&error),
+            }),
+        }
+    }
+}
+
+/// A mapping without an anchor, whose entries are read one by one
+fn is_plain_mapping(event: &Event) -> bool {
+    matches!(event, Event::MappingStart(0, _))
+}
+
+/// The coercion mode of a table, from its entries read so far
+fn table_coercion(table: &Mapping) -> Coercion {
+    Coercion::for_table(table.get("strict_types").and_then(Value::as_bool))
+}
+
+/// The names and types of the columns of a table, when they can all be parsed
+fn column_types(columns: &Value) -> Option<Vec<(String, SqlType)>> {
+    let columns: IndexMap<String, String> = serde_yaml::from_value(columns.clone()).ok()?;
+    columns
+        .into_iter()
+        .map(|(name, type_def)| {
+            let column = YamlColumn::parse(name, &type_def).ok()?;
+            let sql_type = column.get_base_type().ok()?;
+            Some((column.name, sql_type))
+        })
+        .collect()
+}
+
+/// A scalar as serde_yaml reads it. Plain scalars may be null, booleans and numbers; all
+/// others are strings.
+fn scalar(text: String, style: TScalarStyle, tag: Option<Tag>) -> Value {
+    if style != TScalarStyle::Plain || tag.is_some_and(|tag| tag.suffix == "str") {
+        return Value::String(text);
+    }
+    match text.as_str() {
+        "" | "~" | "null" | "Null" | "NULL" => return Value::Null,
+        "true" | "True" | "TRUE" => return Value::Bool(true),
+        "false" | "False" | "FALSE" => return Value::Bool(false),
+        _ => {}
+    }
+    if !text.starts_with(|c: char| c.is_ascii_digit() || matches!(c, '+' | '-' | '.')) {
+        return Value::String(text);
+    }
+    // Digits after a leading zero are a string, as in YAML 1.2
+    let digits = text.strip_prefix(['+', '-']).unwrap_or(&text);
+    if digits.len() > 1 && digits.starts_with('0') && digits.bytes().all(|b| b.is_ascii_digit()) {
+        return Value::String(text);
+    }
+    if let Ok(int) = text.parse::<u64>() {
+        return Value::Number(int.into());
+    }
+    if let Ok(int) = text.parse::<i64>() {
+        return Value::Number(int.into());
+    }
+    if let Ok(float) = text.parse::<f64>()
+        && float.is_finite()
+    {
+        return Value::Number(float.into());
+    }
+    // Hexadecimal, octal and binary numbers, infinity and NaN
+    match serde_yaml::from_str(&text) {
+        Ok(Value::Number(number)) => Value::Number(number),
+        _ => Value::String(text),
+    }
+}
+
+/// The characters of a UTF-8 reader, decoded a block at a time. A read error ends them and
+/// is kept to be reported instead of the parse error it causes.
+struct Chars<R> {
+    reader: R,
+    /// Bytes read but not decoded yet, the start of a character split between blocks
+    pending: Vec<u8>,
+    text: String,
+    position: usize,
+    error: Rc<RefCell<Option<io::Error>>>,
+}
+
+impl<R: Read> Chars<R> {
+    /// Decode the next block, false at the end of the input or on an error
+    fn fill(&mut self) -> bool {
+        let mut block = vec![0; 64 * 1024];
+        let read = loop {
+            match self.reader.read(&mut block) {
+                Ok(0) if self.pending.is_empty() => return false,
+                Ok(0) => return self.fail(invalid_utf8()),
+                Ok(read) => break read,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return self.fail(e),
+            }
+        };
+        self.pending.extend_from_slice(&block[..read]);
+        let valid = match std::str::from_utf8(&self.pending) {
+            Ok(text) => text.len(),
+            Err(e) if e.error_len().is_some() => return self.fail(invalid_utf8()),
+            Err(e) => e.valid_up_to(),
+        };
+        self.text.clear();
+        self.position = 0;
+        if let Ok(text) = std::str::from_utf8(&self.pending[..valid]) {
+            self.text.push_str(text);
+        }
+        self.pending.drain(..valid);
+        true
+    }
+
+    fn fail(&mut self, error: io::Error) -> bool {
+        *self.error.borrow_mut() = Some(error);
+        false
+    }
+}
+
+impl<R: Read> Iterator for Chars<R> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        loop {
+            if let Some(c) = self.text[self.position..].chars().next() {
+                self.position += c.len_utf8();
+                return Some(c);
+            }
+            if !self.fill() {
+                return None;
+            }
+        }
+    }
+}
+
+fn invalid_utf8() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        "stream did not contain valid UTF-8",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::yaml::parser::{load_database, parse_yaml_database};
+    use crate::yaml::schema::YamlDatabase;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    const YAML: &str = r#"
+database:
+  name: "big"
+tables:
+  items:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+      name: "TEXT"
+      price: "DECIMAL(10,2)"
+      weight: "DOUBLE"
+      active: "BOOLEAN"
+      added: "DATE"
+      tags: "JSON"
+    data:
+      - id: 1
+        name: "first"
+        price: 9.99
+        weight: -0.5
+        active: true
+        added: "2024-01-02"
+        tags: { color: red, sizes: [1, 2] }
+      - &second
+        id: 2
+        name: '0012'
+        price: 10
+        active: False
+        tags: ~
+      - { id: 0x10, name: plain text, weight: 1e3 }
+  notes:
+    data:
+      - id: 1
+    columns:
+      id: "INTEGER"
+"#;
+
+    fn yaml_file(content: &str) -> NamedTempFile {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(content.as_bytes()).unwrap();
+        file.flush().unwrap();
+        file
+    }
+
+    async fn streamed(path: &Path) -> crate::Result<crate::database::Database> {
+        let yaml_db = load(path, |yaml_db: &mut YamlDatabase| &mut yaml_db.tables)?;
+        Ok(load_database(path, yaml_db, None).await?.0)
+    }
+
+    #[tokio::test]
+    async fn test_streamed_rows_match_parsed_rows() {
+        let file = yaml_file(YAML);
+        let (value, rows) = read(file.path()).unwrap();
+        // The rows of `notes` come before its columns and are not streamed
+        assert_eq!(rows.keys().collect::<Vec<_>>(), vec!["items"]);
+        assert!(value["tables"]["items"].get("data").is_none());
+        assert!(value["tables"]["notes"].get("data").is_some());
+
+        let streamed = streamed(file.path()).await.unwrap();
+        let (parsed, _) = parse_yaml_database(file.path()).await.unwrap();
+        assert_eq!(streamed.name, "big");
+        for name in ["items", "notes"] {
+            assert_eq!(streamed.tables[name].rows, parsed.tables[name].rows);
+        }
+        assert_eq!(streamed.tables["items"].rows.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_streamed_errors_have_lines() {
+        let file = yaml_file(
+            "database:\n  name: big\ntables:\n  items:\n    columns:\n      id: INTEGER PRIMARY KEY\n      day: DATE NOT NULL\n    data:\n      - id: 1\n        day: someday\n      - id: 2\n",
+        );
+        let error = streamed(file.path()).await.unwrap_err().to_string();
+        let path = file.path().display();
+        assert!(
+            error.contains(&format!("{}:10: table 'items', row 1, column 'day'", path)),
+            "{}",
+            error
+        );
+        assert!(
+            error.contains(&format!("{}:11: table 'items', row 2", path)),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn test_plain_scalars() {
+        for text in [
+            "12", "-7", "+3", "007", "-0", "1.5", "1e3", ".5", "0x1F", "0o17", ".inf", "-.Inf",
+            "1_000", "true", "False", "null", "~", "yes", "1.2.3", "+inf",
+        ] {
+            let expected: Value = serde_yaml::from_str(text).unwrap();
+            assert_eq!(
+                scalar(text.to_string(), TScalarStyle::Plain, None),
+                expected,
+                "{}",
+                text
+            );
+        }
+        assert_eq!(
+            scalar("12".to_string(), TScalarStyle::DoubleQuoted, None),
+            Value::String("12".to_string())
+        );
+    }
+}
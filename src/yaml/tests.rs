@@ -67,6 +67,8 @@ fn test_auth_config_serialization() {
     let auth = AuthConfig {
         username: "user".to_string(),
         password: "pass".to_string(),
+        password_hash: None,
+        users: vec![],
     };
 
     let serialized = serde_yaml::to_string(&auth).unwrap();
@@ -112,6 +114,8 @@ fn test_auth_override_behavior() {
         auth: Some(AuthConfig {
             username: "yaml_user".to_string(),
             password: "yaml_pass".to_string(),
+            password_hash: None,
+            users: vec![],
         }),
     };
 
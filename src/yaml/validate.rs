@@ -0,0 +1,279 @@
+// `yamlbase validate`: load database files as the server would and check what loading
+// leaves unchecked, the uniqueness of keys and that foreign keys point at existing rows.
+// Rows with values that cannot be loaded are reported and left out, and the keys of the
+// rest are still checked.
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::database::{Column, Database, Table, Value};
+use crate::yaml::location::{RowOrigin, detail};
+use crate::yaml::parser::parse_yaml_database_leniently;
+
+/// What validating a database file found
+pub struct Validation {
+    pub file: PathBuf,
+    /// The name of the database, when the file could be loaded
+    pub database: Option<String>,
+    pub tables: usize,
+    pub rows: usize,
+    pub problems: Vec<String>,
+}
+
+impl Validation {
+    pub fn is_valid(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+impl fmt::Display for Validation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for problem in &self.problems {
+            writeln!(f, "{}", problem)?;
+        }
+        match &self.database {
+            Some(name) if self.is_valid() => write!(
+                f,
+                "{}: OK, database '{}' with {} tables and {} rows",
+                self.file.display(),
+                name,
+                self.tables,
+                self.rows
+            ),
+            _ => write!(
+                f,
+                "{}: {} problem{}",
+                self.file.display(),
+                self.problems.len(),
+                if self.problems.len() == 1 { "" } else { "s" }
+            ),
+        }
+    }
+}
+
+/// Load a database file, or directory, and check its keys
+pub async fn validate(file: &Path) -> Validation {
+    let mut validation = Validation {
+        file: file.to_path_buf(),
+        database: None,
+        tables: 0,
+        rows: 0,
+        problems: Vec::new(),
+    };
+    match parse_yaml_database_leniently(file).await {
+        Ok((database, report)) => {
+            validation.tables = database.tables.len();
+            validation.rows = database.tables.values().map(|table| table.rows.len()).sum();
+            validation.problems = report
+                .errors
+                .iter()
+                .flat_map(|error| {
+                    detail(error)
+                        .lines()
+                        .map(str::to_string)
+                        .collect::<Vec<_>>()
+                })
+                .chain(
+                    check_keys(&database, &report.origins)
+                        .into_iter()
+                        .map(|problem| problem.message),
+                )
+                .collect();
+            validation.database = Some(database.name);
+        }
+        Err(error) => {
+            let message = detail(&error);
+            validation.problems = message.lines().map(str::to_string).collect();
+        }
+    }
+    validation
+}
+
+/// A key problem. `identity` names it by table, columns and values rather than by row, so
+/// that it is recognised in a reloaded database where rows were added above it.
+struct KeyProblem {
+    identity: (String, String, String),
+    message: String,
+}
+
+/// The key problems a reloaded database has that the one it replaces did not, so that a
+/// reload is only held back by what the edit broke
+pub(crate) fn new_key_problems(previous: &Database, database: &Database) -> Vec<String> {
+    let mut existing: HashMap<(String, String, String), usize> = HashMap::new();
+    for problem in check_keys(previous, &HashMap::new()) {
+        *existing.entry(problem.identity).or_default() += 1;
+    }
+    check_keys(database, &HashMap::new())
+        .into_iter()
+        .filter(|problem| match existing.get_mut(&problem.identity) {
+            Some(count) if *count > 0 => {
+                *count -= 1;
+                false
+            }
+            _ => true,
+        })
+        .map(|problem| problem.message)
+        .collect()
+}
+
+/// The rows repeating a primary key or a UNIQUE value, and the foreign keys without the row
+/// they refer to. `origins` gives the row of `data:`, and its line, of the rows of a table.
+fn check_keys(database: &Database, origins: &HashMap<String, Vec<RowOrigin>>) -> Vec<KeyProblem> {
+    let mut problems = Vec::new();
+    for (name, table) in &database.tables {
+        if database.materialized_views.contains_key(name) {
+            continue;
+        }
+        let location = RowLocation {
+            file: database.table_files.get(name).map(PathBuf::as_path),
+            table: name,
+            origins: origins.get(name).map_or(&[], Vec::as_slice),
+        };
+
+        let key: Vec<usize> = positions(table, |column| column.primary_key);
+        if !key.is_empty() {
+            check_unique(table, &key, "primary key", &location, &mut problems);
+        }
+        for position in positions(table, |column| column.unique && !column.primary_key) {
+            check_unique(table, &[position], "UNIQUE value", &location, &mut problems);
+        }
+
+        for (position, column) in table.columns.iter().enumerate() {
+            let Some((target, target_column)) = &column.references else {
+                continue;
+            };
+            let identity = |value: String| (name.clone(), column.name.clone(), value);
+            let Some(referenced) = database.get_table(target) else {
+                problems.push(KeyProblem {
+                    identity: identity(String::new()),
+                    message: format!(
+                        "{}: column '{}' references missing table '{}'",
+                        location.table(),
+                        column.name,
+                        target
+                    ),
+                });
+                continue;
+            };
+            let Some(target_position) = referenced.get_column_index(target_column) else {
+                problems.push(KeyProblem {
+                    identity: identity(String::new()),
+                    message: format!(
+                        "{}: column '{}' references missing column '{}.{}'",
+                        location.table(),
+                        column.name,
+                        target,
+                        target_column
+                    ),
+                });
+                continue;
+            };
+            let existing: HashSet<&Value> = referenced
+                .rows
+                .iter()
+                .map(|row| &row[target_position])
+                .collect();
+            for (index, row) in table.rows.iter().enumerate() {
+                let value = &row[position];
+                if !matches!(value, Value::Null) && !existing.contains(value) {
+                    problems.push(KeyProblem {
+                        identity: identity(value.to_string()),
+                        message: format!(
+                            "{}: {} {} is not in {}.{}",
+                            location.row(index),
+                            column.name,
+                            value,
+                            referenced.name,
+                            referenced.columns[target_position].name
+                        ),
+                    });
+                }
+            }
+        }
+    }
+    problems
+}
+
+/// Where a table's problems are, in its file
+struct RowLocation<'a> {
+    file: Option<&'a Path>,
+    table: &'a str,
+    origins: &'a [RowOrigin],
+}
+
+impl RowLocation<'_> {
+    /// `file: table 'name'`
+    fn table(&self) -> String {
+        match self.file {
+            Some(file) => format!("{}: table '{}'", file.display(), self.table),
+            None => format!("table '{}'", self.table),
+        }
+    }
+
+    /// `file:line: table 'name', row 3`, the row counted in `data:` when the table row was
+    /// loaded from it
+    fn row(&self, index: usize) -> String {
+        let row = self.row_number(index);
+        let line = self.origins.get(index).and_then(|origin| origin.line);
+        match (self.file, line) {
+            (Some(file), Some(line)) => format!(
+                "{}:{}: table '{}', row {}",
+                file.display(),
+                line,
+                self.table,
+                row
+            ),
+            _ => format!("{}, row {}", self.table(), row),
+        }
+    }
+
+    /// The number of a table row as `row` counts it
+    fn row_number(&self, index: usize) -> usize {
+        self.origins.get(index).map_or(index, |origin| origin.row) + 1
+    }
+}
+
+/// The positions of the columns `which` picks
+fn positions(table: &Table, which: impl Fn(&Column) -> bool) -> Vec<usize> {
+    (0..table.columns.len())
+        .filter(|&position| which(&table.columns[position]))
+        .collect()
+}
+
+/// Report the rows whose values in the columns repeat those of an earlier row. Rows with a
+/// NULL among them are not compared, as in SQL.
+fn check_unique(
+    table: &Table,
+    columns: &[usize],
+    what: &str,
+    location: &RowLocation,
+    problems: &mut Vec<KeyProblem>,
+) {
+    let mut seen: HashMap<Vec<&Value>, usize> = HashMap::new();
+    for (index, row) in table.rows.iter().enumerate() {
+        let values: Vec<&Value> = columns.iter().map(|&position| &row[position]).collect();
+        if values.iter().any(|value| matches!(value, Value::Null)) {
+            continue;
+        }
+        if let Some(first) = seen.get(&values) {
+            let names: Vec<&str> = columns
+                .iter()
+                .map(|&position| table.columns[position].name.as_str())
+                .collect();
+            let shown: Vec<String> = values.iter().map(|value| value.to_string()).collect();
+            problems.push(KeyProblem {
+                identity: (table.name.clone(), names.join(", "), shown.join(", ")),
+                message: format!(
+                    "{}: duplicate {} ({}) = ({}), as in row {}",
+                    location.row(index),
+                    what,
+                    names.join(", "),
+                    shown.join(", "),
+                    location.row_number(*first)
+                ),
+            });
+        } else {
+            seen.insert(values, index);
+        }
+    }
+}
@@ -5,25 +5,30 @@ use std::time::Duration;
 use tokio::sync::mpsc;
 use tracing::{error, info};
 
+use crate::yaml::parser::is_yaml_file;
+
 pub struct FileWatcher {
-    path: PathBuf,
-    tx: mpsc::Sender<()>,
+    paths: Vec<PathBuf>,
+    tx: mpsc::Sender<Vec<PathBuf>>,
 }
 
 impl FileWatcher {
-    pub fn new(path: PathBuf) -> (Self, mpsc::Receiver<()>) {
+    /// Watch the files of a database: the file it was loaded from and the files that one
+    /// includes, or the directory it was loaded from. The receiver gets the files that
+    /// changed, including YAML files added to or removed from a watched directory.
+    pub fn new(paths: Vec<PathBuf>) -> (Self, mpsc::Receiver<Vec<PathBuf>>) {
         let (tx, rx) = mpsc::channel(10);
 
-        let watcher = Self { path, tx };
+        let watcher = Self { paths, tx };
         (watcher, rx)
     }
 
     pub fn start(self) -> anyhow::Result<()> {
-        let path = self.path.clone();
+        let paths = self.paths.clone();
         let tx = self.tx.clone();
 
         std::thread::spawn(move || {
-            if let Err(e) = watch_file(path, tx) {
+            if let Err(e) = watch_files(paths, tx) {
                 error!("File watcher error: {}", e);
             }
         });
@@ -32,29 +37,39 @@ impl FileWatcher {
     }
 }
 
-fn watch_file(path: PathBuf, tx: mpsc::Sender<()>) -> anyhow::Result<()> {
+fn watch_files(paths: Vec<PathBuf>, tx: mpsc::Sender<Vec<PathBuf>>) -> anyhow::Result<()> {
     let (tx_debounced, rx_debounced) = std::sync::mpsc::channel();
 
     let mut debouncer = new_debouncer(Duration::from_secs(1), tx_debounced)?;
 
-    debouncer
-        .watcher()
-        .watch(&path, RecursiveMode::NonRecursive)?;
+    for path in &paths {
+        debouncer
+            .watcher()
+            .watch(path, RecursiveMode::NonRecursive)?;
 
-    info!("Watching for changes to: {}", path.display());
+        info!("Watching for changes to: {}", path.display());
+    }
 
     for event in rx_debounced {
         match event {
             Ok(events) => {
+                let mut changed: Vec<PathBuf> = Vec::new();
                 for e in events {
-                    if e.path == path {
-                        info!("File changed, triggering reload");
-                        let tx = tx.clone();
-                        tokio::spawn(async move {
-                            let _ = tx.send(()).await;
-                        });
+                    let in_directory = e
+                        .path
+                        .parent()
+                        .is_some_and(|dir| paths.iter().any(|path| path == dir));
+                    let watched =
+                        paths.contains(&e.path) || (in_directory && is_yaml_file(&e.path));
+                    if watched && !changed.contains(&e.path) {
+                        changed.push(e.path);
                     }
                 }
+                // This thread is outside the runtime, so it cannot spawn a task to send
+                if !changed.is_empty() {
+                    info!("File changed, triggering reload");
+                    let _ = tx.blocking_send(changed);
+                }
             }
             Err(e) => error!("Watch error: {:?}", e),
         }
@@ -0,0 +1,332 @@
+// Writing changed rows back into the YAML file. Only the `data:` list of each changed table
+// is replaced; comments, column definitions and key order elsewhere in the file are kept.
+// Tables with a `data_file:` are written to that CSV file instead.
+use std::path::Path;
+use tracing::info;
+
+use crate::YamlBaseError;
+use crate::database::{Table, Value};
+use crate::yaml::compression::{compress, read_text};
+use crate::yaml::csv::render_csv;
+use crate::yaml::remote::is_url;
+
+/// Replace the data of `tables` in the YAML file at `path` with their current rows
+pub async fn write_table_data(path: &Path, tables: &[&Table]) -> crate::Result<()> {
+    let mut content = read_text(path).await?;
+    for table in tables {
+        content = replace_table_data(&content, table)?;
+    }
+    replace_file(path, content).await?;
+
+    info!(
+        "Wrote {} changed table(s) to {}",
+        tables.len(),
+        path.display()
+    );
+    Ok(())
+}
+
+/// Replace the CSV file at `path` with the current rows of `table`
+pub async fn write_table_csv(path: &Path, table: &Table) -> crate::Result<()> {
+    replace_file(path, render_csv(table)).await?;
+    info!("Wrote table '{}' to {}", table.name, path.display());
+    Ok(())
+}
+
+// Write next to the file and rename, so an interrupted write cannot truncate it. A
+// compressed file is compressed again.
+async fn replace_file(path: &Path, content: String) -> crate::Result<()> {
+    if is_url(path) {
+        return Err(YamlBaseError::Config(format!(
+            "Cannot write to {}, which is a URL",
+            path.display()
+        )));
+    }
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(".tmp");
+    tokio::fs::write(&temporary, compress(path, content.into_bytes())?).await?;
+    tokio::fs::rename(&temporary, path).await?;
+    Ok(())
+}
+
+/// The YAML document with the `data:` list of `table` replaced by its current rows
+pub fn replace_table_data(source: &str, table: &Table) -> crate::Result<String> {
+    let lines: Vec<&str> = source.lines().collect();
+    let block = data_block(&lines, &table.name).ok_or_else(|| YamlBaseError::Database {
+        message: format!("Cannot find table '{}' in the YAML file", table.name),
+    })?;
+
+    let mut output: Vec<String> = lines[..block.start]
+        .iter()
+        .map(|line| line.to_string())
+        .collect();
+    output.extend(render_data(table, block.indent));
+    output.extend(lines[block.end..].iter().map(|line| line.to_string()));
+
+    let mut result = output.join("\n");
+    if source.ends_with('\n') {
+        result.push('\n');
+    }
+    Ok(result)
+}
+
+/// Where the `data:` list of a table is in the lines of a YAML document
+pub(super) struct DataBlock {
+    /// The line of the `data:` key, or where to add one when the table has none
+    pub start: usize,
+    /// The index after the last line of the list
+    pub end: usize,
+    /// The indentation of the table's keys
+    pub indent: usize,
+}
+
+/// The `data:` list of the table under the top-level `tables:` mapping
+pub(super) fn data_block(lines: &[&str], table: &str) -> Option<DataBlock> {
+    let tables_line = lines
+        .iter()
+        .position(|line| indentation(line) == 0 && key(line) == Some("tables"))?;
+    let mut table_indent = None;
+    let mut table_line = None;
+    for (index, line) in lines.iter().enumerate().skip(tables_line + 1) {
+        if !is_content(line) {
+            continue;
+        }
+        let indent = indentation(line);
+        if indent == 0 {
+            break;
+        }
+        if *table_indent.get_or_insert(indent) == indent && key(line) == Some(table) {
+            table_line = Some(index);
+            break;
+        }
+    }
+    let (table_line, table_indent) = (table_line?, table_indent?);
+    let table_end = block_end(lines, table_line, |indent, _| indent <= table_indent);
+
+    // The table's `data:` key, or where to add one after the table's other keys
+    let indent = lines[table_line + 1..table_end]
+        .iter()
+        .find(|line| is_content(line))
+        .map(|line| indentation(line))
+        .unwrap_or(table_indent + 2);
+    let data_line = (table_line + 1..table_end)
+        .find(|&index| indentation(lines[index]) == indent && key(lines[index]) == Some("data"));
+    let (start, end) = match data_line {
+        // Entries of a block sequence may be indented as far as its key
+        Some(data_line) => (
+            data_line,
+            block_end(lines, data_line, |line_indent, line| {
+                line_indent < indent || (line_indent == indent && !line.starts_with('-'))
+            }),
+        ),
+        None => (table_end, table_end),
+    };
+    Some(DataBlock { start, end, indent })
+}
+
+/// The index after the last content line of the block that starts at `start`. The block
+/// ends at the first content line for which `ends` is true; comments and blank lines
+/// between the block and that line stay outside it.
+fn block_end(lines: &[&str], start: usize, ends: impl Fn(usize, &str) -> bool) -> usize {
+    let mut end = start + 1;
+    for (index, line) in lines.iter().enumerate().skip(start + 1) {
+        if !is_content(line) {
+            continue;
+        }
+        if ends(indentation(line), line.trim_start()) {
+            break;
+        }
+        end = index + 1;
+    }
+    end
+}
+
+fn render_data(table: &Table, indent: usize) -> Vec<String> {
+    let pad = " ".repeat(indent);
+    if table.rows.is_empty() {
+        return vec![format!("{}data: []", pad)];
+    }
+
+    let mut lines = vec![format!("{}data:", pad)];
+    for row in &table.rows {
+        // Computed columns are computed again when the file is loaded
+        let written = table
+            .columns
+            .iter()
+            .zip(row)
+            .enumerate()
+            .filter(|(index, _)| !table.is_computed(*index))
+            .map(|(_, written)| written);
+        for (position, (column, value)) in written.enumerate() {
+            let marker = if position == 0 { "- " } else { "  " };
+            lines.push(format!(
+                "{}  {}{}: {}",
+                pad,
+                marker,
+                yaml_key(&column.name),
+                yaml_scalar(value)
+            ));
+        }
+    }
+    lines
+}
+
+fn yaml_key(name: &str) -> String {
+    let plain = name
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if plain {
+        name.to_string()
+    } else {
+        serde_json::to_string(name).unwrap()
+    }
+}
+
+/// A value as the YAML the parser reads back into the same value
+fn yaml_scalar(value: &Value) -> String {
+    let float = |f: f64| {
+        if f.is_nan() {
+            ".nan".to_string()
+        } else if f.is_infinite() {
+            if f > 0.0 { ".inf" } else { "-.inf" }.to_string()
+        } else {
+            f.to_string()
+        }
+    };
+
+    match value {
+        Value::Null => "null".to_string(),
+        Value::Boolean(b) => b.to_string(),
+        Value::Integer(i) => i.to_string(),
+        Value::Float(f) => float(*f as f64),
+        Value::Double(d) => float(*d),
+        Value::Decimal(d) => d.to_string(),
+        Value::Json(json) => json_flow(json),
+        Value::Bytes(bytes) => {
+            use base64::Engine;
+            format!(
+                "\"{}\"",
+                base64::engine::general_purpose::STANDARD.encode(bytes)
+            )
+        }
+        Value::Array(items) => format!(
+            "[{}]",
+            items.iter().map(yaml_scalar).collect::<Vec<_>>().join(", ")
+        ),
+        // JSON strings are valid double-quoted YAML scalars
+        _ => serde_json::to_string(&value.to_string()).unwrap(),
+    }
+}
+
+/// JSON in YAML flow style, with the spaces after separators YAML parsers expect
+fn json_flow(json: &serde_json::Value) -> String {
+    match json {
+        serde_json::Value::Array(items) => format!(
+            "[{}]",
+            items.iter().map(json_flow).collect::<Vec<_>>().join(", ")
+        ),
+        serde_json::Value::Object(entries) => format!(
+            "{{{}}}",
+            entries
+                .iter()
+                .map(|(key, value)| format!(
+                    "{}: {}",
+                    serde_json::to_string(key).unwrap(),
+                    json_flow(value)
+                ))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        scalar => scalar.to_string(),
+    }
+}
+
+pub(super) fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+pub(super) fn is_content(line: &str) -> bool {
+    let trimmed = line.trim();
+    !trimmed.is_empty() && !trimmed.starts_with('#')
+}
+
+/// The key of a `key: value` line, without quotes
+pub(super) fn key(line: &str) -> Option<&str> {
+    let trimmed = line.trim();
+    let end = trimmed
+        .char_indices()
+        .find(|&(index, c)| {
+            c == ':' && trimmed[index + 1..].chars().next().is_none_or(|c| c == ' ')
+        })?
+        .0;
+    let key = trimmed[..end].trim();
+    Some(
+        key.strip_prefix('"')
+            .and_then(|key| key.strip_suffix('"'))
+            .or_else(|| {
+                key.strip_prefix('\'')
+                    .and_then(|key| key.strip_suffix('\''))
+            })
+            .unwrap_or(key),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Column;
+    use crate::yaml::schema::SqlType;
+
+    fn table(rows: Vec<Vec<Value>>) -> Table {
+        let column = |name: &str, sql_type| Column {
+            name: name.to_string(),
+            sql_type,
+            primary_key: false,
+            nullable: true,
+            unique: false,
+            default: None,
+            references: None,
+            collation: None,
+        };
+        let mut table = Table::new(
+            "items".to_string(),
+            vec![
+                column("id", SqlType::Integer),
+                column("note", SqlType::Text),
+            ],
+        );
+        for row in rows {
+            table.insert_row(row).unwrap();
+        }
+        table
+    }
+
+    #[test]
+    fn test_replaces_only_the_data_block() {
+        let source = "tables:\n  items:\n    columns:\n      id: INTEGER\n      note: TEXT\n    data:\n    - id: 1\n      note: old\n\n  # next\n  other:\n    data: []\n";
+        let updated = replace_table_data(
+            source,
+            &table(vec![vec![
+                Value::Integer(2),
+                Value::Text("it's: new".to_string()),
+            ]]),
+        )
+        .unwrap();
+        assert_eq!(
+            updated,
+            "tables:\n  items:\n    columns:\n      id: INTEGER\n      note: TEXT\n    data:\n      - id: 2\n        note: \"it's: new\"\n\n  # next\n  other:\n    data: []\n"
+        );
+    }
+
+    #[test]
+    fn test_adds_a_missing_data_block() {
+        let source = "tables:\n  \"items\":\n    columns:\n      id: INTEGER\n      note: TEXT\n";
+        let updated = replace_table_data(source, &table(vec![])).unwrap();
+        assert_eq!(updated, format!("{}    data: []\n", source));
+
+        let missing = replace_table_data("tables:\n  other:\n    columns: {}\n", &table(vec![]));
+        assert!(missing.is_err());
+    }
+}
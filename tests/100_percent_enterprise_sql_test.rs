@@ -23,6 +23,7 @@ async fn test_100_percent_enterprise_sql_achievement() {
             unique: true,
             default: None,
             references: None,
+            collation: None,
         },
         Column {
             name: "project_name".to_string(),
@@ -32,6 +33,7 @@ async fn test_100_percent_enterprise_sql_achievement() {
             unique: false,
             default: None,
             references: None,
+            collation: None,
         },
         Column {
             name: "budget".to_string(),
@@ -41,6 +43,7 @@ async fn test_100_percent_enterprise_sql_achievement() {
             unique: false,
             default: None,
             references: None,
+            collation: None,
         },
         Column {
             name: "start_date".to_string(),
@@ -50,6 +53,7 @@ async fn test_100_percent_enterprise_sql_achievement() {
             unique: false,
             default: None,
             references: None,
+            collation: None,
         },
         Column {
             name: "active".to_string(),
@@ -59,6 +63,7 @@ async fn test_100_percent_enterprise_sql_achievement() {
             unique: false,
             default: None,
             references: None,
+            collation: None,
         },
     ];
 
@@ -101,6 +106,7 @@ async fn test_100_percent_enterprise_sql_achievement() {
             unique: true,
             default: None,
             references: None,
+            collation: None,
         },
         Column {
             name: "name".to_string(),
@@ -110,6 +116,7 @@ async fn test_100_percent_enterprise_sql_achievement() {
             unique: false,
             default: None,
             references: None,
+            collation: None,
         },
         Column {
             name: "salary".to_string(),
@@ -119,6 +126,7 @@ async fn test_100_percent_enterprise_sql_achievement() {
             unique: false,
             default: None,
             references: None,
+            collation: None,
         },
     ];
 
@@ -155,6 +163,7 @@ async fn test_100_percent_enterprise_sql_achievement() {
             unique: false,
             default: None,
             references: None,
+            collation: None,
         },
         Column {
             name: "employee_id".to_string(),
@@ -164,6 +173,7 @@ async fn test_100_percent_enterprise_sql_achievement() {
             unique: false,
             default: None,
             references: None,
+            collation: None,
         },
         Column {
             name: "hours".to_string(),
@@ -173,6 +183,7 @@ async fn test_100_percent_enterprise_sql_achievement() {
             unique: false,
             default: None,
             references: None,
+            collation: None,
         },
     ];
 
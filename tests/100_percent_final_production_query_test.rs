@@ -22,6 +22,7 @@ async fn test_100_percent_enterprise_production_query() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "month_number".to_string(),
@@ -31,6 +32,7 @@ async fn test_100_percent_enterprise_production_query() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "version_code".to_string(),
@@ -40,6 +42,7 @@ async fn test_100_percent_enterprise_production_query() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "assignment_type".to_string(),
@@ -49,6 +52,7 @@ async fn test_100_percent_enterprise_production_query() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "planned_effort_hours".to_string(),
@@ -58,6 +62,7 @@ async fn test_100_percent_enterprise_production_query() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "actual_effort_hours".to_string(),
@@ -67,6 +72,7 @@ async fn test_100_percent_enterprise_production_query() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
         ],
     );
@@ -22,6 +22,7 @@ async fn test_advanced_date_arithmetic_functions() {
             unique: true,
             default: None,
             references: None,
+            collation: None,
         },
         Column {
             name: "event_name".to_string(),
@@ -31,6 +32,7 @@ async fn test_advanced_date_arithmetic_functions() {
             unique: false,
             default: None,
             references: None,
+            collation: None,
         },
         Column {
             name: "start_date".to_string(),
@@ -40,6 +42,7 @@ async fn test_advanced_date_arithmetic_functions() {
             unique: false,
             default: None,
             references: None,
+            collation: None,
         },
         Column {
             name: "end_date".to_string(),
@@ -49,6 +52,7 @@ async fn test_advanced_date_arithmetic_functions() {
             unique: false,
             default: None,
             references: None,
+            collation: None,
         },
     ];
 
@@ -254,6 +258,7 @@ async fn test_interval_arithmetic_patterns() {
             unique: true,
             default: None,
             references: None,
+            collation: None,
         },
         Column {
             name: "base_date".to_string(),
@@ -263,6 +268,7 @@ async fn test_interval_arithmetic_patterns() {
             unique: false,
             default: None,
             references: None,
+            collation: None,
         },
     ];
 
@@ -21,6 +21,7 @@ async fn test_advanced_join_types() {
                 unique: true,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "name".to_string(),
@@ -30,6 +31,7 @@ async fn test_advanced_join_types() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "department_id".to_string(),
@@ -39,6 +41,7 @@ async fn test_advanced_join_types() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
         ],
     );
@@ -77,6 +80,7 @@ async fn test_advanced_join_types() {
                 unique: true,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "dept_name".to_string(),
@@ -86,6 +90,7 @@ async fn test_advanced_join_types() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
         ],
     );
@@ -202,6 +207,7 @@ async fn test_complex_join_scenarios() {
                 unique: true,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "username".to_string(),
@@ -211,6 +217,7 @@ async fn test_complex_join_scenarios() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
         ],
     );
@@ -234,6 +241,7 @@ async fn test_complex_join_scenarios() {
                 unique: true,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "user_id".to_string(),
@@ -243,6 +251,7 @@ async fn test_complex_join_scenarios() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "amount".to_string(),
@@ -252,6 +261,7 @@ async fn test_complex_join_scenarios() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
         ],
     );
@@ -0,0 +1,220 @@
+use mysql::prelude::Queryable;
+use yamlbase::database::Value;
+use yamlbase::yaml::parse_yaml_database;
+
+mod common;
+use common::{TestServer, connect_mysql, connect_postgres, new_executor, query, yaml_file};
+
+const YAML: &str = r#"
+database:
+  name: "posts"
+  auth:
+    username: "yamlbase"
+    password: "password"
+
+tables:
+  posts:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+      tags: "TEXT[]"
+      scores: "INTEGER[]"
+    data:
+      - id: 1
+        tags: [rust, "a b", null]
+        scores: [1, 2, 3]
+      - id: 2
+        tags: []
+        scores: "{4,5}"
+      - id: 3
+        tags: null
+        scores: null
+"#;
+
+fn text(s: &str) -> Value {
+    Value::Text(s.to_string())
+}
+
+#[tokio::test]
+async fn test_sequences_and_array_literals() {
+    let file = yaml_file(YAML);
+    let (database, _) = parse_yaml_database(file.path()).await.unwrap();
+    let rows = &database.tables["posts"].rows;
+    assert_eq!(
+        rows[0][1],
+        Value::Array(vec![text("rust"), text("a b"), Value::Null])
+    );
+    assert_eq!(rows[0][1].to_string(), r#"{rust,"a b",NULL}"#);
+    assert_eq!(rows[1][1], Value::Array(vec![]));
+    assert_eq!(
+        rows[1][2],
+        Value::Array(vec![Value::Integer(4), Value::Integer(5)])
+    );
+    assert_eq!(rows[2][1], Value::Null);
+
+    let file = yaml_file(&YAML.replace("[1, 2, 3]", "[1, two]"));
+    let error = parse_yaml_database(file.path())
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("two"), "{}", error);
+}
+
+fn ids(values: &[i64]) -> Vec<Vec<Value>> {
+    values.iter().map(|id| vec![Value::Integer(*id)]).collect()
+}
+
+#[tokio::test]
+async fn test_array_subscripts() {
+    let file = yaml_file(YAML);
+    let (database, _) = parse_yaml_database(file.path()).await.unwrap();
+    let executor = new_executor(database).await;
+
+    // Subscripts count from 1 and give NULL out of range
+    assert_eq!(
+        query(
+            &executor,
+            "SELECT tags[1], tags[3], tags[4], tags[0] FROM posts WHERE id = 1"
+        )
+        .await,
+        vec![vec![text("rust"), Value::Null, Value::Null, Value::Null]]
+    );
+    assert_eq!(
+        query(
+            &executor,
+            "SELECT scores[2:3], scores[:1] FROM posts WHERE id = 1"
+        )
+        .await,
+        vec![vec![
+            Value::Array(vec![Value::Integer(2), Value::Integer(3)]),
+            Value::Array(vec![Value::Integer(1)]),
+        ]]
+    );
+    assert_eq!(
+        query(&executor, "SELECT tags[1] FROM posts WHERE id = 3").await,
+        vec![vec![Value::Null]]
+    );
+    assert_eq!(
+        query(&executor, "SELECT id FROM posts WHERE scores[1] = 4").await,
+        ids(&[2])
+    );
+    assert_eq!(
+        query(&executor, "SELECT (ARRAY[10, 20])[2]").await,
+        vec![vec![Value::Integer(20)]]
+    );
+}
+
+#[tokio::test]
+async fn test_any_and_all_over_arrays() {
+    let file = yaml_file(YAML);
+    let (database, _) = parse_yaml_database(file.path()).await.unwrap();
+    let executor = new_executor(database).await;
+
+    assert_eq!(
+        query(&executor, "SELECT id FROM posts WHERE 'rust' = ANY(tags)").await,
+        ids(&[1])
+    );
+    assert_eq!(
+        query(
+            &executor,
+            "SELECT id FROM posts WHERE 6 > ALL(scores) ORDER BY id"
+        )
+        .await,
+        ids(&[1, 2])
+    );
+    // ALL holds for an empty array, and a NULL element or array leaves it unknown
+    assert_eq!(
+        query(&executor, "SELECT id FROM posts WHERE 'x' <> ALL(tags)").await,
+        ids(&[2])
+    );
+    assert_eq!(
+        query(
+            &executor,
+            "SELECT id FROM posts WHERE 2 = ANY(ARRAY[id, 5])"
+        )
+        .await,
+        ids(&[2])
+    );
+    assert_eq!(
+        query(
+            &executor,
+            "SELECT p.id FROM posts p JOIN posts q ON p.id = q.id WHERE 5 = ANY(q.scores)",
+        )
+        .await,
+        ids(&[2])
+    );
+}
+
+#[tokio::test]
+async fn test_any_and_all_over_arrays_in_select_list() {
+    let file = yaml_file(YAML);
+    let (database, _) = parse_yaml_database(file.path()).await.unwrap();
+    let executor = new_executor(database).await;
+
+    assert_eq!(
+        query(
+            &executor,
+            "SELECT id, 'rust' = ANY(tags), 6 > ALL(scores) FROM posts ORDER BY id"
+        )
+        .await,
+        vec![
+            vec![
+                Value::Integer(1),
+                Value::Boolean(true),
+                Value::Boolean(true)
+            ],
+            vec![
+                Value::Integer(2),
+                Value::Boolean(false),
+                Value::Boolean(true)
+            ],
+            vec![Value::Integer(3), Value::Null, Value::Null],
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_postgres_arrays() {
+    let file = yaml_file(YAML);
+    let server = TestServer::start_postgres(file.path().to_str().unwrap());
+    let client = connect_postgres(server.port(), "yamlbase", "password", "posts")
+        .await
+        .unwrap();
+
+    let row = client
+        .query_one("SELECT tags, scores FROM posts WHERE id = 1", &[])
+        .await
+        .unwrap();
+    assert_eq!(
+        row.get::<_, Vec<Option<String>>>(0),
+        vec![Some("rust".to_string()), Some("a b".to_string()), None]
+    );
+    assert_eq!(row.get::<_, Vec<i32>>(1), vec![1, 2, 3]);
+
+    client
+        .simple_query("INSERT INTO posts VALUES (4, ARRAY['x', 'y'], '{7,8}')")
+        .await
+        .unwrap();
+    let rows = client
+        .simple_query("SELECT tags, scores FROM posts WHERE id = 4")
+        .await
+        .unwrap();
+    let tokio_postgres::SimpleQueryMessage::Row(row) = &rows[1] else {
+        panic!("expected a row, got {:?}", rows[1]);
+    };
+    assert_eq!(row.get(0), Some("{x,y}"));
+    assert_eq!(row.get(1), Some("{7,8}"));
+}
+
+#[test]
+fn test_mysql_json_arrays() {
+    let file = yaml_file(YAML);
+    let server = TestServer::start_mysql(file.path().to_str().unwrap());
+    let mut conn = connect_mysql(server.port(), "yamlbase", "password", "posts").unwrap();
+
+    let (tags, scores): (String, String) = conn
+        .query_first("SELECT tags, scores FROM posts WHERE id = 1")
+        .unwrap()
+        .unwrap();
+    assert_eq!(tags, r#"["rust","a b",null]"#);
+    assert_eq!(scores, "[1,2,3]");
+}
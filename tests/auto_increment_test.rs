@@ -0,0 +1,184 @@
+use tokio_postgres::SimpleQueryMessage;
+use yamlbase::database::Value;
+use yamlbase::sql::{QueryExecutor, SqlDialect, parse_sql};
+
+mod common;
+use common::{TestServer, connect_postgres, try_query, yaml_executor, yaml_file};
+
+const YAML: &str = r#"
+database:
+  name: "test_db"
+  auth:
+    username: "yamlbase"
+    password: "password"
+
+tables:
+  users:
+    columns:
+      id: "INTEGER PRIMARY KEY AUTO_INCREMENT"
+      name: "VARCHAR(20) NOT NULL"
+    data:
+      - id: 1
+        name: "ann"
+      - id: 7
+        name: "bob"
+
+  tags:
+    columns:
+      id: "SERIAL PRIMARY KEY"
+      label: "TEXT"
+"#;
+
+async fn ids(executor: &QueryExecutor, table: &str) -> Vec<Value> {
+    try_query(executor, &format!("SELECT id FROM {} ORDER BY id", table))
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|mut row| row.remove(0))
+        .collect()
+}
+
+fn integers(values: &[i64]) -> Vec<Value> {
+    values.iter().map(|&value| Value::Integer(value)).collect()
+}
+
+#[tokio::test]
+async fn test_auto_increment() {
+    let executor = yaml_executor(YAML).await.with_dialect(SqlDialect::MySQL);
+    assert_eq!(
+        try_query(&executor, "SELECT LAST_INSERT_ID()")
+            .await
+            .unwrap(),
+        vec![vec![Value::Integer(0)]]
+    );
+
+    // Ids continue after the largest one in the file
+    try_query(&executor, "INSERT INTO users (name) VALUES ('cy')")
+        .await
+        .unwrap();
+    assert_eq!(ids(&executor, "users").await, integers(&[1, 7, 8]));
+
+    // An explicit id moves the sequence past it, and 0 asks for the next id in MySQL
+    try_query(&executor, "INSERT INTO users VALUES (20, 'dee')")
+        .await
+        .unwrap();
+    try_query(
+        &executor,
+        "INSERT INTO users (id, name) VALUES (0, 'eve'), (NULL, 'fay')",
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        ids(&executor, "users").await,
+        integers(&[1, 7, 8, 20, 21, 22])
+    );
+
+    // LAST_INSERT_ID() is the first id of the latest insert that generated any
+    assert_eq!(executor.last_insert_id(), 21);
+    assert_eq!(
+        try_query(&executor, "SELECT LAST_INSERT_ID()")
+            .await
+            .unwrap(),
+        vec![vec![Value::Integer(21)]]
+    );
+}
+
+#[tokio::test]
+async fn test_serial_sequences() {
+    let executor = yaml_executor(YAML).await;
+    assert!(
+        try_query(&executor, "SELECT currval('tags_id_seq')")
+            .await
+            .is_err()
+    );
+    assert!(try_query(&executor, "SELECT lastval()").await.is_err());
+
+    try_query(&executor, "INSERT INTO tags (label) VALUES ('a'), ('b')")
+        .await
+        .unwrap();
+    try_query(&executor, "INSERT INTO users (name) VALUES ('cy')")
+        .await
+        .unwrap();
+    assert_eq!(ids(&executor, "tags").await, integers(&[1, 2]));
+    assert_eq!(
+        try_query(
+            &executor,
+            "SELECT currval('public.tags_id_seq'), currval('users_id_seq'), lastval()"
+        )
+        .await
+        .unwrap(),
+        vec![integers(&[2, 8, 8])]
+    );
+
+    // SERIAL columns of tables created by the connection generate ids too
+    try_query(&executor, "CREATE TEMP TABLE notes (id SERIAL, body TEXT)")
+        .await
+        .unwrap();
+    try_query(&executor, "INSERT INTO notes (body) VALUES ('x'), ('y')")
+        .await
+        .unwrap();
+    assert_eq!(ids(&executor, "notes").await, integers(&[1, 2]));
+    assert!(
+        try_query(&executor, "INSERT INTO tags (label) VALUES (NULL)")
+            .await
+            .is_ok()
+    );
+}
+
+#[tokio::test]
+async fn test_insert_returning() {
+    let executor = yaml_executor(YAML).await;
+
+    let rows = try_query(
+        &executor,
+        "INSERT INTO users (name) VALUES ('cy'), ('dee') RETURNING id, upper(name) AS label",
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        rows,
+        vec![
+            vec![Value::Integer(8), Value::Text("CY".to_string())],
+            vec![Value::Integer(9), Value::Text("DEE".to_string())],
+        ]
+    );
+
+    let statements = parse_sql("INSERT INTO tags (label) VALUES ('a') RETURNING *").unwrap();
+    let result = executor.execute(&statements[0]).await.unwrap();
+    assert_eq!(result.columns, vec!["id", "label"]);
+    assert_eq!(result.affected_rows, Some(1));
+}
+
+#[tokio::test]
+async fn test_returning_over_postgres() {
+    let file = yaml_file(YAML);
+    let server = TestServer::start_postgres(file.path().to_str().unwrap());
+
+    let client = connect_postgres(server.port(), "yamlbase", "password", "test_db")
+        .await
+        .unwrap();
+
+    let rows = client
+        .query(
+            "INSERT INTO tags (label) VALUES ('a'), ('b') RETURNING id, label",
+            &[],
+        )
+        .await
+        .unwrap();
+    let ids: Vec<i32> = rows.iter().map(|row| row.get(0)).collect();
+    assert_eq!(ids, vec![1, 2]);
+    assert_eq!(rows[1].get::<_, String>("label"), "b");
+
+    let messages = client
+        .simple_query("SELECT currval('tags_id_seq')")
+        .await
+        .unwrap();
+    let currval = messages
+        .iter()
+        .find_map(|message| match message {
+            SimpleQueryMessage::Row(row) => Some(row.get(0).unwrap().to_string()),
+            _ => None,
+        })
+        .unwrap();
+    assert_eq!(currval, "2");
+}
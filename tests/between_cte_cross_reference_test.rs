@@ -18,6 +18,7 @@ async fn setup_test_db() -> Arc<QueryExecutor> {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "month_number".to_string(),
@@ -27,6 +28,7 @@ async fn setup_test_db() -> Arc<QueryExecutor> {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "version_code".to_string(),
@@ -36,6 +38,7 @@ async fn setup_test_db() -> Arc<QueryExecutor> {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "assignment_type".to_string(),
@@ -45,6 +48,7 @@ async fn setup_test_db() -> Arc<QueryExecutor> {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "planned_effort_hours".to_string(),
@@ -54,6 +58,7 @@ async fn setup_test_db() -> Arc<QueryExecutor> {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "actual_effort_hours".to_string(),
@@ -63,6 +68,7 @@ async fn setup_test_db() -> Arc<QueryExecutor> {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
         ],
     );
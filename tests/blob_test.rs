@@ -0,0 +1,84 @@
+use mysql::prelude::Queryable;
+use yamlbase::database::Value;
+use yamlbase::yaml::parse_yaml_database;
+
+mod common;
+use common::{TestServer, connect_mysql, connect_postgres, yaml_file};
+
+const YAML: &str = r#"
+database:
+  name: "files"
+  auth:
+    username: "yamlbase"
+    password: "password"
+
+tables:
+  files:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+      content: "BLOB"
+    data:
+      - id: 1
+        content: "iVBORw0KGgo="
+      - id: 2
+        content: "\\xdeadbeef"
+      - id: 3
+        content: null
+"#;
+
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a];
+
+#[tokio::test]
+async fn test_base64_and_hex_values() {
+    let file = yaml_file(YAML);
+    let (database, _) = parse_yaml_database(file.path()).await.unwrap();
+    let rows = &database.tables["files"].rows;
+    assert_eq!(rows[0][1], Value::Bytes(PNG_SIGNATURE.to_vec()));
+    assert_eq!(rows[1][1], Value::Bytes(vec![0xde, 0xad, 0xbe, 0xef]));
+    assert_eq!(rows[1][1].to_string(), "\\xdeadbeef");
+    assert_eq!(rows[2][1], Value::Null);
+
+    let file = yaml_file(&YAML.replace("iVBORw0KGgo=", "not base64!"));
+    let error = parse_yaml_database(file.path())
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("Cannot decode binary value"), "{}", error);
+}
+
+#[tokio::test]
+async fn test_postgres_bytea() {
+    let file = yaml_file(YAML);
+    let server = TestServer::start_postgres(file.path().to_str().unwrap());
+    let client = connect_postgres(server.port(), "yamlbase", "password", "files")
+        .await
+        .unwrap();
+
+    let row = client
+        .query_one("SELECT content FROM files WHERE id = 1", &[])
+        .await
+        .unwrap();
+    assert_eq!(row.get::<_, Vec<u8>>(0), PNG_SIGNATURE);
+
+    client
+        .simple_query("INSERT INTO files VALUES (4, '\\x0102')")
+        .await
+        .unwrap();
+    let row = client
+        .query_one("SELECT content FROM files WHERE id = 4", &[])
+        .await
+        .unwrap();
+    assert_eq!(row.get::<_, Vec<u8>>(0), vec![1, 2]);
+}
+
+#[test]
+fn test_mysql_blob() {
+    let file = yaml_file(YAML);
+    let server = TestServer::start_mysql(file.path().to_str().unwrap());
+    let mut conn = connect_mysql(server.port(), "yamlbase", "password", "files").unwrap();
+
+    let content: Option<Vec<u8>> = conn
+        .query_first("SELECT content FROM files WHERE id = 1")
+        .unwrap();
+    assert_eq!(content.as_deref(), Some(&PNG_SIGNATURE[..]));
+}
@@ -0,0 +1,169 @@
+use tokio_postgres::error::SqlState;
+use yamlbase::database::Value;
+use yamlbase::sql::{QueryExecutor, parse_sql};
+use yamlbase::yaml::parse_yaml_database;
+
+mod common;
+use common::{TestServer, connect_postgres, yaml_executor, yaml_file};
+
+const YAML: &str = r#"
+database:
+  name: "test_db"
+
+tables:
+  products:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+      price: "DECIMAL(10,2) NOT NULL CHECK (price >= 0)"
+      discount: "DECIMAL(10,2)"
+      stock: "INTEGER CHECK (stock BETWEEN 0 AND 1000)"
+    check: "discount IS NULL OR discount < price"
+    data:
+      - id: 1
+        price: 10.00
+        discount: 2.50
+        stock: 5
+      - id: 2
+        price: 0
+        stock: null
+"#;
+
+async fn execute(executor: &QueryExecutor, sql: &str) -> yamlbase::Result<u64> {
+    let statements = parse_sql(sql).unwrap();
+    let result = executor.execute(&statements[0]).await?;
+    Ok(result.affected_rows.unwrap_or(0))
+}
+
+async fn query_value(executor: &QueryExecutor, sql: &str) -> Value {
+    let statements = parse_sql(sql).unwrap();
+    let result = executor.execute(&statements[0]).await.unwrap();
+    result.rows[0][0].clone()
+}
+
+#[tokio::test]
+async fn test_check_loaded_rows() {
+    let error = parse_yaml_database(yaml_file(&YAML.replace("price: 0", "price: -1")).path())
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(
+        error.contains("Row 2 of table 'products' violates CHECK (price >= 0)"),
+        "{}",
+        error
+    );
+
+    let error =
+        parse_yaml_database(yaml_file(&YAML.replace("discount: 2.50", "discount: 20")).path())
+            .await
+            .unwrap_err()
+            .to_string();
+    assert!(
+        error.contains("discount IS NULL OR discount < price"),
+        "{}",
+        error
+    );
+
+    let error = parse_yaml_database(yaml_file(&YAML.replace("(price >= 0)", "(price >= 0")).path())
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("CHECK"));
+}
+
+#[tokio::test]
+async fn test_check_on_writes() {
+    let executor = yaml_executor(YAML).await;
+
+    let error = execute(&executor, "INSERT INTO products VALUES (3, -5, NULL, 1)")
+        .await
+        .unwrap_err();
+    assert!(
+        error.to_string().contains("CHECK (price >= 0)"),
+        "{}",
+        error
+    );
+    assert!(matches!(
+        execute(&executor, "UPDATE products SET stock = stock + 2000").await,
+        Err(yamlbase::YamlBaseError::CheckViolation { ref table, .. }) if table == "products"
+    ));
+    assert!(
+        execute(&executor, "UPDATE products SET discount = 11 WHERE id = 1")
+            .await
+            .is_err()
+    );
+    assert!(
+        execute(
+            &executor,
+            "INSERT INTO products VALUES (1, 1, NULL, 1) \
+             ON CONFLICT (id) DO UPDATE SET price = -1",
+        )
+        .await
+        .is_err()
+    );
+    assert_eq!(
+        query_value(&executor, "SELECT stock FROM products ORDER BY id LIMIT 1").await,
+        Value::Integer(5)
+    );
+
+    // A NULL result passes, as in SQL
+    assert_eq!(
+        execute(&executor, "INSERT INTO products VALUES (3, 4, NULL, NULL)")
+            .await
+            .unwrap(),
+        1
+    );
+    assert_eq!(
+        execute(&executor, "UPDATE products SET stock = 1000 WHERE id = 3")
+            .await
+            .unwrap(),
+        1
+    );
+}
+
+#[tokio::test]
+async fn test_check_in_create_table() {
+    let executor = yaml_executor(YAML).await;
+    execute(
+        &executor,
+        "CREATE TABLE accounts (id INTEGER PRIMARY KEY, balance INTEGER CHECK (balance >= 0), \
+         kind TEXT, CHECK (kind IN ('personal', 'business')))",
+    )
+    .await
+    .unwrap();
+
+    execute(&executor, "INSERT INTO accounts VALUES (1, 10, 'personal')")
+        .await
+        .unwrap();
+    assert!(
+        execute(&executor, "INSERT INTO accounts VALUES (2, -1, 'personal')")
+            .await
+            .is_err()
+    );
+    let error = execute(&executor, "INSERT INTO accounts VALUES (2, 1, 'other')")
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("kind IN"), "{}", error);
+}
+
+#[tokio::test]
+async fn test_check_violation_over_postgres() {
+    let yaml = "database:\n  name: \"test_db\"\n\ntables:\n  notes:\n    columns:\n      id: \"INTEGER PRIMARY KEY\"\n";
+    let (db, _) = parse_yaml_database(yaml_file(yaml).path()).await.unwrap();
+    let server = TestServer::new_postgres(db).await;
+
+    let client = connect_postgres(server.port(), "yamlbase", "password", "test_db")
+        .await
+        .unwrap();
+
+    // The test server only passes column types on, so the CHECK is declared here
+    client
+        .batch_execute(
+            "CREATE TABLE accounts (id INTEGER PRIMARY KEY, balance INTEGER CHECK (balance >= 0))",
+        )
+        .await
+        .unwrap();
+    let error = client
+        .execute("INSERT INTO accounts VALUES (1, -1)", &[])
+        .await
+        .unwrap_err();
+    assert_eq!(error.code(), Some(&SqlState::CHECK_VIOLATION));
+}
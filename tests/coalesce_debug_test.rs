@@ -21,6 +21,7 @@ async fn test_coalesce_diagnostics() -> Result<(), Box<dyn std::error::Error>> {
                 unique: true,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "project_name".to_string(),
@@ -30,6 +31,7 @@ async fn test_coalesce_diagnostics() -> Result<(), Box<dyn std::error::Error>> {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "status_code".to_string(),
@@ -39,6 +41,7 @@ async fn test_coalesce_diagnostics() -> Result<(), Box<dyn std::error::Error>> {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "end_date".to_string(),
@@ -48,6 +51,7 @@ async fn test_coalesce_diagnostics() -> Result<(), Box<dyn std::error::Error>> {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "parent_id".to_string(),
@@ -57,6 +61,7 @@ async fn test_coalesce_diagnostics() -> Result<(), Box<dyn std::error::Error>> {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
         ],
     );
@@ -0,0 +1,284 @@
+use std::sync::Arc;
+use yamlbase::database::{Collation, Storage, Value};
+use yamlbase::sql::QueryExecutor;
+use yamlbase::yaml::parse_yaml_database;
+
+mod common;
+use common::{query, yaml_file};
+
+const YAML: &str = r#"
+database:
+  name: "test_db"
+
+tables:
+  users:
+    columns:
+      username: "VARCHAR(50) PRIMARY KEY COLLATE utf8mb4_general_ci"
+      name: "VARCHAR(50) COLLATE ci_ascii"
+      code: "VARCHAR(10)"
+      city: "TEXT COLLATE binary"
+    data:
+      - username: "Alice"
+        name: "Alice Smith"
+        code: "AB1"
+        city: "Paris"
+      - username: "bob"
+        name: "Bob Jones"
+        code: "cd2"
+        city: "paris"
+  orders:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+      customer: "VARCHAR(50)"
+    data:
+      - id: 1
+        customer: "ALICE"
+      - id: 2
+        customer: "Bob"
+"#;
+
+async fn load(yaml: &str) -> yamlbase::Result<Arc<Storage>> {
+    let file = yaml_file(yaml);
+    let (db, _) = parse_yaml_database(file.path()).await?;
+    Ok(Arc::new(Storage::new(db)))
+}
+
+fn texts(values: &[&str]) -> Vec<Vec<Value>> {
+    values
+        .iter()
+        .map(|value| vec![Value::Text(value.to_string())])
+        .collect()
+}
+
+#[tokio::test]
+async fn test_column_collations() {
+    let executor = QueryExecutor::new(load(YAML).await.unwrap()).await.unwrap();
+
+    assert_eq!(
+        query(
+            &executor,
+            "SELECT code FROM users WHERE name = 'alice smith'"
+        )
+        .await,
+        texts(&["AB1"])
+    );
+    assert_eq!(
+        query(&executor, "SELECT code FROM users WHERE 'BOB JONES' = name").await,
+        texts(&["cd2"])
+    );
+    assert_eq!(
+        query(
+            &executor,
+            "SELECT code FROM users WHERE name IN ('ALICE SMITH', 'nobody') OR name LIKE 'bob%'",
+        )
+        .await,
+        texts(&["AB1", "cd2"])
+    );
+    assert_eq!(
+        query(&executor, "SELECT code FROM users WHERE name < 'b' ").await,
+        texts(&["AB1"])
+    );
+
+    // A case-insensitive primary key is not looked up through the exact-match index
+    assert_eq!(
+        query(&executor, "SELECT code FROM users WHERE username = 'ALICE'").await,
+        texts(&["AB1"])
+    );
+
+    // Columns without a collation compare exactly under the default binary collation
+    assert!(
+        query(&executor, "SELECT name FROM users WHERE code = 'ab1'")
+            .await
+            .is_empty()
+    );
+    assert_eq!(
+        query(
+            &executor,
+            "SELECT o.id FROM orders o JOIN users u ON u.username = o.customer ORDER BY o.id",
+        )
+        .await,
+        vec![vec![Value::Integer(1)], vec![Value::Integer(2)]]
+    );
+}
+
+#[tokio::test]
+async fn test_server_collation() {
+    let executor = QueryExecutor::new(load(YAML).await.unwrap())
+        .await
+        .unwrap()
+        .with_collation(Collation::CiUnicode);
+
+    assert_eq!(
+        query(&executor, "SELECT name FROM users WHERE code = 'ab1'").await,
+        texts(&["Alice Smith"])
+    );
+    assert_eq!(
+        query(
+            &executor,
+            "SELECT id FROM orders WHERE customer IN ('alice', 'bob') ORDER BY id"
+        )
+        .await,
+        vec![vec![Value::Integer(1)], vec![Value::Integer(2)]]
+    );
+
+    // A column's own collation wins over the server default
+    assert_eq!(
+        query(&executor, "SELECT code FROM users WHERE city = 'paris'").await,
+        texts(&["cd2"])
+    );
+}
+
+#[tokio::test]
+async fn test_unknown_collation() {
+    let yaml = YAML.replace("COLLATE ci_ascii", "COLLATE klingon");
+    let Err(error) = load(&yaml).await else {
+        panic!("an unknown collation should be rejected");
+    };
+    assert!(error.to_string().contains("Unknown collation"));
+}
+
+const COLORS: &str = r#"
+database:
+  name: "test_db"
+
+tables:
+  colors:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+      label: "VARCHAR(20) COLLATE ci_unicode"
+      raw: "VARCHAR(20)"
+    data:
+      - id: 1
+        label: "Red"
+        raw: "Red"
+      - id: 2
+        label: "Blue"
+        raw: "Blue"
+      - id: 3
+        label: "red"
+        raw: "red"
+      - id: 4
+        label: "green"
+        raw: "green"
+      - id: 5
+        label: "blue"
+        raw: "blue"
+      - id: 6
+        label: "RED"
+        raw: "RED"
+"#;
+
+#[tokio::test]
+async fn test_collated_group_by() {
+    let executor = QueryExecutor::new(load(COLORS).await.unwrap())
+        .await
+        .unwrap();
+
+    // Each group reports the value of its first row
+    let mut rows = query(
+        &executor,
+        "SELECT label, COUNT(*) FROM colors GROUP BY label",
+    )
+    .await;
+    rows.sort_by_key(|row| row[1].to_string());
+    assert_eq!(
+        rows,
+        vec![
+            vec![Value::Text("green".to_string()), Value::Integer(1)],
+            vec![Value::Text("Blue".to_string()), Value::Integer(2)],
+            vec![Value::Text("Red".to_string()), Value::Integer(3)],
+        ]
+    );
+
+    assert_eq!(
+        query(&executor, "SELECT raw FROM colors GROUP BY raw")
+            .await
+            .len(),
+        6
+    );
+
+    let rows = query(
+        &executor,
+        "SELECT c.label, COUNT(*) FROM colors c JOIN colors d ON c.id = d.id GROUP BY c.label",
+    )
+    .await;
+    assert_eq!(rows.len(), 3);
+}
+
+#[tokio::test]
+async fn test_collated_distinct() {
+    let executor = QueryExecutor::new(load(COLORS).await.unwrap())
+        .await
+        .unwrap();
+
+    assert_eq!(
+        query(
+            &executor,
+            "SELECT DISTINCT label FROM colors ORDER BY label"
+        )
+        .await,
+        texts(&["Blue", "green", "Red"])
+    );
+    assert_eq!(
+        query(&executor, "SELECT DISTINCT raw FROM colors")
+            .await
+            .len(),
+        6
+    );
+    assert_eq!(
+        query(
+            &executor,
+            "SELECT DISTINCT c.label FROM colors c JOIN colors d ON c.id = d.id ORDER BY c.label",
+        )
+        .await,
+        texts(&["Blue", "green", "Red"])
+    );
+}
+
+#[tokio::test]
+async fn test_collated_count_distinct() {
+    let executor = QueryExecutor::new(load(COLORS).await.unwrap())
+        .await
+        .unwrap();
+
+    assert_eq!(
+        query(&executor, "SELECT COUNT(DISTINCT label) FROM colors").await,
+        vec![vec![Value::Integer(3)]]
+    );
+    assert_eq!(
+        query(&executor, "SELECT COUNT(DISTINCT raw) FROM colors").await,
+        vec![vec![Value::Integer(6)]]
+    );
+
+    // The server collation applies to columns without their own
+    let executor = executor.with_collation(Collation::CiAscii);
+    assert_eq!(
+        query(&executor, "SELECT COUNT(DISTINCT raw) FROM colors").await,
+        vec![vec![Value::Integer(3)]]
+    );
+}
+
+#[tokio::test]
+async fn test_collated_order_by() {
+    let executor = QueryExecutor::new(load(COLORS).await.unwrap())
+        .await
+        .unwrap();
+
+    // Equal labels keep their table order
+    assert_eq!(
+        query(&executor, "SELECT label FROM colors ORDER BY label").await,
+        texts(&["Blue", "blue", "green", "Red", "red", "RED"])
+    );
+    assert_eq!(
+        query(&executor, "SELECT raw FROM colors ORDER BY raw").await,
+        texts(&["Blue", "RED", "Red", "blue", "green", "red"])
+    );
+    assert_eq!(
+        query(
+            &executor,
+            "SELECT c.label FROM colors c JOIN colors d ON c.id = d.id ORDER BY c.label DESC",
+        )
+        .await,
+        texts(&["Red", "red", "RED", "green", "Blue", "blue"])
+    );
+}
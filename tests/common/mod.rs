@@ -9,7 +9,65 @@ use std::sync::atomic::{AtomicU16, Ordering};
 use std::time::Duration;
 use tempfile::NamedTempFile;
 use yamlbase::config::{Config, Protocol};
-use yamlbase::database::Database;
+use yamlbase::database::{Column, Database, Storage, Value};
+use yamlbase::sql::{QueryExecutor, parse_sql};
+use yamlbase::yaml::parse_yaml_database;
+use yamlbase::yaml::schema::SqlType;
+
+/// A column of a table built in a test. A primary key is unique, other columns are nullable.
+#[allow(dead_code)]
+pub fn column(name: &str, sql_type: SqlType, primary_key: bool) -> Column {
+    Column {
+        name: name.to_string(),
+        sql_type,
+        primary_key,
+        nullable: !primary_key,
+        unique: primary_key,
+        default: None,
+        references: None,
+        collation: None,
+    }
+}
+
+/// An executor over a database built in a test. Its primary key indexes are built up
+/// front, as `Storage::new` only starts building them.
+#[allow(dead_code)]
+pub async fn new_executor(db: Database) -> QueryExecutor {
+    let storage = Arc::new(Storage::new(db));
+    storage.rebuild_indexes().await;
+    QueryExecutor::new(storage).await.unwrap()
+}
+
+/// An executor over the database of a YAML file with `content`
+#[allow(dead_code)]
+pub async fn yaml_executor(content: &str) -> QueryExecutor {
+    let file = yaml_file(content);
+    let (db, _) = parse_yaml_database(file.path()).await.unwrap();
+    new_executor(db).await
+}
+
+/// A temporary file holding `content`, such as the YAML of a database
+#[allow(dead_code)]
+pub fn yaml_file(content: &str) -> NamedTempFile {
+    let mut file = NamedTempFile::new().unwrap();
+    file.write_all(content.as_bytes()).unwrap();
+    file.flush().unwrap();
+    file
+}
+
+/// The rows of the first statement of `sql`, which has to succeed
+#[allow(dead_code)]
+pub async fn query(executor: &QueryExecutor, sql: &str) -> Vec<Vec<Value>> {
+    let statements = parse_sql(sql).unwrap();
+    executor.execute(&statements[0]).await.unwrap().rows
+}
+
+/// The rows of the first statement of `sql`, or the error it fails with
+#[allow(dead_code)]
+pub async fn try_query(executor: &QueryExecutor, sql: &str) -> yamlbase::Result<Vec<Vec<Value>>> {
+    let statements = parse_sql(sql).unwrap();
+    Ok(executor.execute(&statements[0]).await?.rows)
+}
 
 // Start from a high port to avoid conflicts with common services
 static NEXT_PORT: AtomicU16 = AtomicU16::new(40000);
@@ -62,6 +120,17 @@ fn get_free_port() -> u16 {
     }
 }
 
+/// The name `--protocol` knows a protocol by
+fn protocol_name(protocol: Protocol) -> &'static str {
+    match protocol {
+        Protocol::Postgres => "postgres",
+        Protocol::Mysql => "mysql",
+        Protocol::Sqlserver => "sqlserver",
+        Protocol::Http => "http",
+        Protocol::Grpc => "grpc",
+    }
+}
+
 /// Wait for a port to be available
 fn wait_for_port(port: u16, timeout: Duration) -> bool {
     let start = std::time::Instant::now();
@@ -88,66 +157,90 @@ pub struct TestServer {
 #[allow(dead_code)]
 impl TestServer {
     pub fn start_mysql(yaml_file: &str) -> Self {
-        let port = get_free_port();
+        Self::start_mysql_with_args(yaml_file, &[])
+    }
 
-        let (cmd, mut args) = get_yamlbase_command();
-        args.extend(vec![
-            "-f".to_string(),
-            yaml_file.to_string(),
-            "--protocol".to_string(),
-            "mysql".to_string(),
-            "-p".to_string(),
-            port.to_string(),
-        ]);
+    /// Start a MySQL server with extra command line options, such as another `-f`
+    pub fn start_mysql_with_args(yaml_file: &str, extra_args: &[&str]) -> Self {
+        Self::start_with_protocol(yaml_file, Protocol::Mysql, extra_args)
+    }
 
-        let process = Command::new(&cmd)
-            .args(&args)
-            .stdout(std::process::Stdio::null())
-            .stderr(std::process::Stdio::null())
-            .spawn()
-            .expect("Failed to start server");
+    pub fn start_postgres(yaml_file: &str) -> Self {
+        Self::start_postgres_with_args(yaml_file, &[])
+    }
 
-        // Wait for server to be ready
-        wait_for_port(port, Duration::from_secs(10));
+    /// Start a PostgreSQL server with extra command line options, such as `--hot-reload`
+    pub fn start_postgres_with_args(yaml_file: &str, extra_args: &[&str]) -> Self {
+        Self::start_with_protocol(yaml_file, Protocol::Postgres, extra_args)
+    }
 
-        let config = Arc::new(Config {
-            file: PathBuf::from(yaml_file),
-            port: Some(port),
-            bind_address: "127.0.0.1".to_string(),
-            protocol: Protocol::Mysql,
-            username: "root".to_string(),
-            password: "password".to_string(),
-            verbose: false,
-            hot_reload: false,
-            log_level: "info".to_string(),
-            database: None,
-            allow_anonymous: false,
-            max_connections: None,
-            connection_timeout: None,
-            idle_timeout: None,
-            enable_keepalive: false,
-        });
+    pub fn start_sqlserver(yaml_file: &str) -> Self {
+        Self::start_sqlserver_with_args(yaml_file, &[])
+    }
 
-        Self {
-            port,
-            config,
-            process: Some(process),
-            _temp_file: None,
-        }
+    /// Start a SQL Server server with extra command line options, such as another `-f`
+    pub fn start_sqlserver_with_args(yaml_file: &str, extra_args: &[&str]) -> Self {
+        Self::start_with_protocol(yaml_file, Protocol::Sqlserver, extra_args)
     }
 
-    pub fn start_postgres(yaml_file: &str) -> Self {
-        let port = get_free_port();
+    pub fn start_http(yaml_file: &str) -> Self {
+        Self::start_http_with_args(yaml_file, &[])
+    }
 
-        let (cmd, mut args) = get_yamlbase_command();
-        args.extend(vec![
-            "-f".to_string(),
-            yaml_file.to_string(),
+    /// Start an HTTP server with extra command line options, such as `--hot-reload`
+    pub fn start_http_with_args(yaml_file: &str, extra_args: &[&str]) -> Self {
+        Self::start_with_protocol(yaml_file, Protocol::Http, extra_args)
+    }
+
+    pub fn start_grpc(yaml_file: &str) -> Self {
+        Self::start_with_protocol(yaml_file, Protocol::Grpc, &[])
+    }
+
+    /// Start a server for the protocol with extra command line options
+    fn start_with_protocol(yaml_file: &str, protocol: Protocol, extra_args: &[&str]) -> Self {
+        let port = get_free_port();
+        let mut args = vec![
             "--protocol".to_string(),
-            "postgres".to_string(),
+            protocol_name(protocol).to_string(),
             "-p".to_string(),
             port.to_string(),
-        ]);
+        ];
+        args.extend(extra_args.iter().map(|arg| arg.to_string()));
+        Self::spawn(yaml_file, &[(protocol, port)], args)
+    }
+
+    /// Start a server with a listener for each of the protocols, as `--postgres-port` and
+    /// the like ask for, returning it with the port of each. The server's own port is that
+    /// of the first protocol.
+    pub fn start_listeners(
+        yaml_file: &str,
+        protocols: &[Protocol],
+        extra_args: &[&str],
+    ) -> (Self, Vec<u16>) {
+        let listeners: Vec<(Protocol, u16)> = protocols
+            .iter()
+            .map(|&protocol| (protocol, get_free_port()))
+            .collect();
+        let mut args: Vec<String> = listeners
+            .iter()
+            .flat_map(|(protocol, port)| {
+                [
+                    format!("--{}-port", protocol_name(*protocol)),
+                    port.to_string(),
+                ]
+            })
+            .collect();
+        args.extend(extra_args.iter().map(|arg| arg.to_string()));
+        let server = Self::spawn(yaml_file, &listeners, args);
+        (server, listeners.iter().map(|(_, port)| *port).collect())
+    }
+
+    /// Run the server with the options and wait until all of its listeners are up
+    fn spawn(yaml_file: &str, listeners: &[(Protocol, u16)], options: Vec<String>) -> Self {
+        let (protocol, port) = listeners[0];
+        let (cmd, mut args) = get_yamlbase_command();
+        args.extend(vec!["-f".to_string(), yaml_file.to_string()]);
+        args.extend(options);
 
         let process = Command::new(&cmd)
             .args(&args)
@@ -157,24 +250,51 @@ impl TestServer {
             .expect("Failed to start server");
 
         // Wait for server to be ready
-        wait_for_port(port, Duration::from_secs(10));
+        for (_, port) in listeners {
+            wait_for_port(*port, Duration::from_secs(10));
+        }
 
         let config = Arc::new(Config {
-            file: PathBuf::from(yaml_file),
+            command: None,
+            files: vec![PathBuf::from(yaml_file)],
+            config: None,
             port: Some(port),
             bind_address: "127.0.0.1".to_string(),
-            protocol: Protocol::Postgres,
-            username: "yamlbase".to_string(),
+            protocol,
+            listener_ports: Default::default(),
+            username: "admin".to_string(),
             password: "password".to_string(),
             verbose: false,
+            auth_method: None,
             hot_reload: false,
+            reload_interval: None,
+            url_headers: Vec::new(),
             log_level: "info".to_string(),
             database: None,
             allow_anonymous: false,
+            random_seed: None,
+            timezone: None,
+            cte_max_recursion_depth: None,
+            cte_max_rows: None,
+            collation: None,
+            strict_types: false,
+            mutable: false,
+            persist: false,
+            tls_cert: None,
+            tls_key: None,
+            tls_client_ca: None,
+            tls_require_client_cert: false,
+            proxy_protocol: false,
             max_connections: None,
-            connection_timeout: None,
+            connect_timeout: None,
             idle_timeout: None,
+            statement_timeout: None,
+            rate_limit_qps: None,
+            rate_limit_rows: None,
+            rate_limit_by: Default::default(),
             enable_keepalive: false,
+            users: Vec::new(),
+            password_hash: None,
         });
 
         Self {
@@ -217,13 +337,18 @@ impl TestServer {
                         yamlbase::yaml::schema::SqlType::Boolean => "BOOLEAN".to_string(),
                         yamlbase::yaml::schema::SqlType::Date => "DATE".to_string(),
                         yamlbase::yaml::schema::SqlType::Timestamp => "TIMESTAMP".to_string(),
+                        yamlbase::yaml::schema::SqlType::TimestampTz => "TIMESTAMPTZ".to_string(),
                         yamlbase::yaml::schema::SqlType::Float => "FLOAT".to_string(),
                         yamlbase::yaml::schema::SqlType::Double => "DOUBLE".to_string(),
                         yamlbase::yaml::schema::SqlType::Decimal(p, s) => format!("DECIMAL({},{})", p, s),
                         yamlbase::yaml::schema::SqlType::BigInt => "BIGINT".to_string(),
                         yamlbase::yaml::schema::SqlType::Time => "TIME".to_string(),
                         yamlbase::yaml::schema::SqlType::Uuid => "UUID".to_string(),
+                        yamlbase::yaml::schema::SqlType::Blob => "BLOB".to_string(),
                         yamlbase::yaml::schema::SqlType::Json => "JSON".to_string(),
+                        yamlbase::yaml::schema::SqlType::Array(element) => {
+                            format!("{:?}[]", element).to_uppercase()
+                        }
                     };
 
                     let mut col_def = type_str.to_string();
@@ -288,21 +413,46 @@ impl TestServer {
             wait_for_port(port, Duration::from_secs(10));
 
             let config = Arc::new(Config {
-                file: PathBuf::from(yaml_path),
+                command: None,
+                files: vec![PathBuf::from(yaml_path)],
+                config: None,
                 port: Some(port),
                 bind_address: "127.0.0.1".to_string(),
                 protocol: Protocol::Postgres,
+                listener_ports: Default::default(),
                 username: "yamlbase".to_string(),
                 password: "password".to_string(),
                 verbose: false,
+                auth_method: None,
                 hot_reload: false,
+                reload_interval: None,
+                url_headers: Vec::new(),
                 log_level: "info".to_string(),
                 database: None,
                 allow_anonymous: false,
+                random_seed: None,
+                timezone: None,
+                cte_max_recursion_depth: None,
+                cte_max_rows: None,
+                collation: None,
+                strict_types: false,
+                mutable: false,
+                persist: false,
+                tls_cert: None,
+                tls_key: None,
+                tls_client_ca: None,
+                tls_require_client_cert: false,
+                proxy_protocol: false,
                 max_connections: None,
-                connection_timeout: None,
+                connect_timeout: None,
                 idle_timeout: None,
+                statement_timeout: None,
+                rate_limit_qps: None,
+                rate_limit_rows: None,
+                rate_limit_by: Default::default(),
                 enable_keepalive: false,
+                users: Vec::new(),
+                password_hash: None,
             });
 
             Self { port, config, process: Some(process), _temp_file: Some(temp_file) }
@@ -313,11 +463,53 @@ impl TestServer {
         self.port
     }
 
+    /// The process id of the server, to send it signals
+    pub fn pid(&self) -> Option<u32> {
+        self.process.as_ref().map(Child::id)
+    }
+
     pub fn connect(&self) -> TcpStream {
         TcpStream::connect(format!("127.0.0.1:{}", self.port)).expect("Failed to connect to server")
     }
 }
 
+/// A PostgreSQL client logged in to `dbname` on the server at `port`
+#[allow(dead_code)]
+pub async fn connect_postgres(
+    port: u16,
+    user: &str,
+    password: &str,
+    dbname: &str,
+) -> Result<tokio_postgres::Client, tokio_postgres::Error> {
+    let (client, connection) = tokio_postgres::Config::new()
+        .host("127.0.0.1")
+        .port(port)
+        .user(user)
+        .password(password)
+        .dbname(dbname)
+        .connect(tokio_postgres::NoTls)
+        .await?;
+    tokio::spawn(connection);
+    Ok(client)
+}
+
+/// A MySQL connection to `dbname` on the server at `port`
+#[allow(dead_code)]
+pub fn connect_mysql(
+    port: u16,
+    user: &str,
+    password: &str,
+    dbname: &str,
+) -> mysql::Result<mysql::Conn> {
+    let opts = mysql::OptsBuilder::new()
+        .ip_or_hostname(Some("127.0.0.1"))
+        .tcp_port(port)
+        .user(Some(user))
+        .pass(Some(password))
+        .db_name(Some(dbname));
+    mysql::Conn::new(opts)
+}
+
 impl Drop for TestServer {
     fn drop(&mut self) {
         if let Some(mut process) = self.process.take() {
@@ -418,7 +610,28 @@ pub fn _mysql_connect_and_auth(server: &TestServer, username: &str, password: &s
     stream
 }
 
-fn _mysql_native_password_auth(auth_data: &[u8], password: &str) -> Vec<u8> {
+/// The payload of the next MySQL packet
+#[allow(dead_code)]
+pub fn read_packet(stream: &mut TcpStream) -> Vec<u8> {
+    let mut header = [0u8; 4];
+    stream.read_exact(&mut header).unwrap();
+    let length = u32::from_le_bytes([header[0], header[1], header[2], 0]) as usize;
+    let mut payload = vec![0u8; length];
+    stream.read_exact(&mut payload).unwrap();
+    payload
+}
+
+/// Send a MySQL packet with the sequence id
+#[allow(dead_code)]
+pub fn write_packet(stream: &mut TcpStream, sequence_id: u8, payload: &[u8]) {
+    let mut packet = (payload.len() as u32).to_le_bytes()[..3].to_vec();
+    packet.push(sequence_id);
+    packet.extend_from_slice(payload);
+    stream.write_all(&packet).unwrap();
+}
+
+/// The mysql_native_password scramble of a password
+pub fn _mysql_native_password_auth(auth_data: &[u8], password: &str) -> Vec<u8> {
     use sha1::{Digest, Sha1};
 
     if password.is_empty() {
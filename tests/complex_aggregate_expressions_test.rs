@@ -23,6 +23,7 @@ async fn test_complex_aggregate_expressions() {
             unique: true,
             default: None,
             references: None,
+            collation: None,
         },
         Column {
             name: "product".to_string(),
@@ -32,6 +33,7 @@ async fn test_complex_aggregate_expressions() {
             unique: false,
             default: None,
             references: None,
+            collation: None,
         },
         Column {
             name: "price".to_string(),
@@ -41,6 +43,7 @@ async fn test_complex_aggregate_expressions() {
             unique: false,
             default: None,
             references: None,
+            collation: None,
         },
         Column {
             name: "quantity".to_string(),
@@ -50,6 +53,7 @@ async fn test_complex_aggregate_expressions() {
             unique: false,
             default: None,
             references: None,
+            collation: None,
         },
         Column {
             name: "discount_rate".to_string(),
@@ -59,6 +63,7 @@ async fn test_complex_aggregate_expressions() {
             unique: false,
             default: None,
             references: None,
+            collation: None,
         },
     ];
 
@@ -247,6 +252,7 @@ async fn test_previous_complex_aggregate_error_fixed() {
             unique: true,
             default: None,
             references: None,
+            collation: None,
         },
         Column {
             name: "amount".to_string(),
@@ -256,6 +262,7 @@ async fn test_previous_complex_aggregate_error_fixed() {
             unique: false,
             default: None,
             references: None,
+            collation: None,
         },
         Column {
             name: "multiplier".to_string(),
@@ -265,6 +272,7 @@ async fn test_previous_complex_aggregate_error_fixed() {
             unique: false,
             default: None,
             references: None,
+            collation: None,
         },
     ];
 
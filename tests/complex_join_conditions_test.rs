@@ -22,6 +22,7 @@ async fn test_complex_join_conditions() {
                 unique: true,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "parent_id".to_string(),
@@ -31,6 +32,7 @@ async fn test_complex_join_conditions() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "version".to_string(),
@@ -40,6 +42,7 @@ async fn test_complex_join_conditions() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "status".to_string(),
@@ -49,6 +52,7 @@ async fn test_complex_join_conditions() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "is_active".to_string(),
@@ -58,6 +62,7 @@ async fn test_complex_join_conditions() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "is_locked".to_string(),
@@ -67,6 +72,7 @@ async fn test_complex_join_conditions() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "project_type".to_string(),
@@ -76,6 +82,7 @@ async fn test_complex_join_conditions() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
         ],
     );
@@ -302,6 +309,7 @@ async fn test_complex_where_with_not_in_regression() {
                 unique: true,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "project_name".to_string(),
@@ -311,6 +319,7 @@ async fn test_complex_where_with_not_in_regression() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "version_code".to_string(),
@@ -320,6 +329,7 @@ async fn test_complex_where_with_not_in_regression() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "status_code".to_string(),
@@ -329,6 +339,7 @@ async fn test_complex_where_with_not_in_regression() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "active_flag".to_string(),
@@ -338,6 +349,7 @@ async fn test_complex_where_with_not_in_regression() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "project_structure".to_string(),
@@ -347,6 +359,7 @@ async fn test_complex_where_with_not_in_regression() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
         ],
     );
@@ -387,6 +400,7 @@ async fn test_complex_where_with_not_in_regression() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "wbi_id".to_string(),
@@ -396,6 +410,7 @@ async fn test_complex_where_with_not_in_regression() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "version_code".to_string(),
@@ -405,6 +420,7 @@ async fn test_complex_where_with_not_in_regression() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "assignment_type".to_string(),
@@ -414,6 +430,7 @@ async fn test_complex_where_with_not_in_regression() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "project_status_code".to_string(),
@@ -423,6 +440,7 @@ async fn test_complex_where_with_not_in_regression() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "planned_effort_hours".to_string(),
@@ -432,6 +450,7 @@ async fn test_complex_where_with_not_in_regression() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "actual_effort_hours".to_string(),
@@ -441,6 +460,7 @@ async fn test_complex_where_with_not_in_regression() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
         ],
     );
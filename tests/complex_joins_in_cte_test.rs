@@ -25,6 +25,7 @@ mod complex_joins_in_cte_test {
                     unique: true,
                     default: None,
                     references: None,
+                    collation: None,
                 },
                 Column {
                     name: "project_name".to_string(),
@@ -34,6 +35,7 @@ mod complex_joins_in_cte_test {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
                 Column {
                     name: "version_code".to_string(),
@@ -43,6 +45,7 @@ mod complex_joins_in_cte_test {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
                 Column {
                     name: "status_code".to_string(),
@@ -52,6 +55,7 @@ mod complex_joins_in_cte_test {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
                 Column {
                     name: "active_flag".to_string(),
@@ -61,6 +65,7 @@ mod complex_joins_in_cte_test {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
                 Column {
                     name: "project_structure".to_string(),
@@ -70,6 +75,7 @@ mod complex_joins_in_cte_test {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
                 Column {
                     name: "hierarchy_parent_sap_id".to_string(),
@@ -79,6 +85,7 @@ mod complex_joins_in_cte_test {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
             ],
         );
@@ -211,6 +218,7 @@ mod complex_joins_in_cte_test {
                     unique: true,
                     default: None,
                     references: None,
+                    collation: None,
                 },
                 Column {
                     name: "project_name".to_string(),
@@ -220,6 +228,7 @@ mod complex_joins_in_cte_test {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
                 Column {
                     name: "version_code".to_string(),
@@ -229,6 +238,7 @@ mod complex_joins_in_cte_test {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
             ],
         );
@@ -245,6 +255,7 @@ mod complex_joins_in_cte_test {
                     unique: true,
                     default: None,
                     references: None,
+                    collation: None,
                 },
                 Column {
                     name: "sap_project_id".to_string(),
@@ -254,6 +265,7 @@ mod complex_joins_in_cte_test {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
                 Column {
                     name: "wbi_id".to_string(),
@@ -263,6 +275,7 @@ mod complex_joins_in_cte_test {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
                 Column {
                     name: "version_code".to_string(),
@@ -272,6 +285,7 @@ mod complex_joins_in_cte_test {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
                 Column {
                     name: "assignment_type".to_string(),
@@ -281,6 +295,7 @@ mod complex_joins_in_cte_test {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
                 Column {
                     name: "project_status_code".to_string(),
@@ -290,6 +305,7 @@ mod complex_joins_in_cte_test {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
                 Column {
                     name: "planned_effort_hours".to_string(),
@@ -299,6 +315,7 @@ mod complex_joins_in_cte_test {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
                 Column {
                     name: "actual_effort_hours".to_string(),
@@ -308,6 +325,7 @@ mod complex_joins_in_cte_test {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
             ],
         );
@@ -450,6 +468,7 @@ mod complex_joins_in_cte_test {
                     unique: true,
                     default: None,
                     references: None,
+                    collation: None,
                 },
                 Column {
                     name: "project_name".to_string(),
@@ -459,6 +478,7 @@ mod complex_joins_in_cte_test {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
                 Column {
                     name: "version_code".to_string(),
@@ -468,6 +488,7 @@ mod complex_joins_in_cte_test {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
                 Column {
                     name: "status_code".to_string(),
@@ -477,6 +498,7 @@ mod complex_joins_in_cte_test {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
                 Column {
                     name: "active_flag".to_string(),
@@ -486,6 +508,7 @@ mod complex_joins_in_cte_test {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
                 Column {
                     name: "closed_for_time_entry".to_string(),
@@ -495,6 +518,7 @@ mod complex_joins_in_cte_test {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
                 Column {
                     name: "project_structure".to_string(),
@@ -504,6 +528,7 @@ mod complex_joins_in_cte_test {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
                 Column {
                     name: "hierarchy_parent_sap_id".to_string(),
@@ -513,6 +538,7 @@ mod complex_joins_in_cte_test {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
             ],
         );
@@ -23,6 +23,7 @@ async fn test_complex_multitable_cte_queries() {
             unique: true,
             default: None,
             references: None,
+            collation: None,
         },
         Column {
             name: "username".to_string(),
@@ -32,6 +33,7 @@ async fn test_complex_multitable_cte_queries() {
             unique: false,
             default: None,
             references: None,
+            collation: None,
         },
         Column {
             name: "department_id".to_string(),
@@ -41,6 +43,7 @@ async fn test_complex_multitable_cte_queries() {
             unique: false,
             default: None,
             references: None,
+            collation: None,
         },
     ];
 
@@ -77,6 +80,7 @@ async fn test_complex_multitable_cte_queries() {
             unique: true,
             default: None,
             references: None,
+            collation: None,
         },
         Column {
             name: "dept_name".to_string(),
@@ -86,6 +90,7 @@ async fn test_complex_multitable_cte_queries() {
             unique: false,
             default: None,
             references: None,
+            collation: None,
         },
     ];
 
@@ -113,6 +118,7 @@ async fn test_complex_multitable_cte_queries() {
             unique: true,
             default: None,
             references: None,
+            collation: None,
         },
         Column {
             name: "project_name".to_string(),
@@ -122,6 +128,7 @@ async fn test_complex_multitable_cte_queries() {
             unique: false,
             default: None,
             references: None,
+            collation: None,
         },
         Column {
             name: "budget".to_string(),
@@ -131,6 +138,7 @@ async fn test_complex_multitable_cte_queries() {
             unique: false,
             default: None,
             references: None,
+            collation: None,
         },
     ];
 
@@ -267,6 +275,7 @@ async fn test_previous_notimplemented_scenario() {
             unique: true,
             default: None,
             references: None,
+            collation: None,
         },
         Column {
             name: "name".to_string(),
@@ -276,6 +285,7 @@ async fn test_previous_notimplemented_scenario() {
             unique: false,
             default: None,
             references: None,
+            collation: None,
         },
     ];
 
@@ -296,6 +306,7 @@ async fn test_previous_notimplemented_scenario() {
             unique: true,
             default: None,
             references: None,
+            collation: None,
         },
         Column {
             name: "value".to_string(),
@@ -305,6 +316,7 @@ async fn test_previous_notimplemented_scenario() {
             unique: false,
             default: None,
             references: None,
+            collation: None,
         },
     ];
 
@@ -88,6 +88,7 @@ async fn create_comprehensive_test_database() -> Arc<Storage> {
                 unique: true,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "first_name".to_string(),
@@ -97,6 +98,7 @@ async fn create_comprehensive_test_database() -> Arc<Storage> {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "last_name".to_string(),
@@ -106,6 +108,7 @@ async fn create_comprehensive_test_database() -> Arc<Storage> {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "hire_date".to_string(),
@@ -115,6 +118,7 @@ async fn create_comprehensive_test_database() -> Arc<Storage> {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "salary".to_string(),
@@ -124,6 +128,7 @@ async fn create_comprehensive_test_database() -> Arc<Storage> {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "department_id".to_string(),
@@ -133,6 +138,7 @@ async fn create_comprehensive_test_database() -> Arc<Storage> {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "email".to_string(),
@@ -142,6 +148,7 @@ async fn create_comprehensive_test_database() -> Arc<Storage> {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "is_active".to_string(),
@@ -151,6 +158,7 @@ async fn create_comprehensive_test_database() -> Arc<Storage> {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
         ],
     );
@@ -193,6 +201,7 @@ async fn create_comprehensive_test_database() -> Arc<Storage> {
                 unique: true,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "department_name".to_string(),
@@ -202,6 +211,7 @@ async fn create_comprehensive_test_database() -> Arc<Storage> {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "budget".to_string(),
@@ -211,6 +221,7 @@ async fn create_comprehensive_test_database() -> Arc<Storage> {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
         ],
     );
@@ -236,6 +247,7 @@ async fn create_comprehensive_test_database() -> Arc<Storage> {
                 unique: true,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "project_name".to_string(),
@@ -245,6 +257,7 @@ async fn create_comprehensive_test_database() -> Arc<Storage> {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "start_date".to_string(),
@@ -254,6 +267,7 @@ async fn create_comprehensive_test_database() -> Arc<Storage> {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "end_date".to_string(),
@@ -263,6 +277,7 @@ async fn create_comprehensive_test_database() -> Arc<Storage> {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "budget".to_string(),
@@ -272,6 +287,7 @@ async fn create_comprehensive_test_database() -> Arc<Storage> {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
         ],
     );
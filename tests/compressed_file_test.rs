@@ -0,0 +1,128 @@
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+use tempfile::TempDir;
+use yamlbase::database::{Storage, Value};
+use yamlbase::sql::{QueryExecutor, WriteMode, parse_sql};
+use yamlbase::yaml::parse_yaml_database;
+
+const MAIN: &str = r#"
+database:
+  name: "test_db"
+
+include:
+  - orders.yaml.zst
+
+tables:
+  users:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+      name: "TEXT"
+    data:
+      - id: 1
+        name: "Alice"
+"#;
+
+const ORDERS: &str = r#"
+tables:
+  orders:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+      user_id: "INTEGER"
+    data_file: orders.csv.gz
+"#;
+
+fn gzip(text: &str) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(text.as_bytes()).unwrap();
+    encoder.finish().unwrap()
+}
+
+fn gunzip(path: &Path) -> String {
+    let mut text = String::new();
+    std::io::Read::read_to_string(
+        &mut flate2::read::GzDecoder::new(std::fs::File::open(path).unwrap()),
+        &mut text,
+    )
+    .unwrap();
+    text
+}
+
+fn write_files(dir: &TempDir) {
+    std::fs::write(dir.path().join("db.yaml.gz"), gzip(MAIN)).unwrap();
+    std::fs::write(
+        dir.path().join("orders.yaml.zst"),
+        zstd::encode_all(ORDERS.as_bytes(), 0).unwrap(),
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join("orders.csv.gz"),
+        gzip("id,user_id\n1,1\n2,1\n"),
+    )
+    .unwrap();
+}
+
+#[tokio::test]
+async fn test_load_compressed_files() {
+    let dir = TempDir::new().unwrap();
+    write_files(&dir);
+
+    let (db, _) = parse_yaml_database(&dir.path().join("db.yaml.gz"))
+        .await
+        .unwrap();
+    assert_eq!(db.get_table("users").unwrap().rows.len(), 1);
+    assert_eq!(db.get_table("orders").unwrap().rows.len(), 2);
+
+    // Compressed files are part of a directory database too
+    std::fs::remove_file(dir.path().join("orders.yaml.zst")).unwrap();
+    std::fs::write(
+        dir.path().join("db.yaml.gz"),
+        gzip(&MAIN.replace("include:\n  - orders.yaml.zst\n", "")),
+    )
+    .unwrap();
+    std::fs::write(
+        dir.path().join("more.yml.zst"),
+        zstd::encode_all(ORDERS.as_bytes(), 0).unwrap(),
+    )
+    .unwrap();
+    let (db, _) = parse_yaml_database(dir.path()).await.unwrap();
+    assert_eq!(db.get_table("orders").unwrap().rows.len(), 2);
+
+    std::fs::write(dir.path().join("db.yaml.gz"), b"not gzip").unwrap();
+    assert!(
+        parse_yaml_database(&dir.path().join("db.yaml.gz"))
+            .await
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn test_write_compressed_files() {
+    let dir = TempDir::new().unwrap();
+    write_files(&dir);
+    let path = dir.path().join("db.yaml.gz");
+    let (db, _) = parse_yaml_database(&path).await.unwrap();
+    let executor = QueryExecutor::new(Arc::new(Storage::new(db)))
+        .await
+        .unwrap()
+        .with_write_mode(WriteMode::Persistent(path.clone()));
+
+    for sql in [
+        "INSERT INTO users VALUES (2, 'Bob')",
+        "INSERT INTO orders VALUES (3, 2)",
+    ] {
+        executor.execute(&parse_sql(sql).unwrap()[0]).await.unwrap();
+    }
+
+    // Changes are written compressed, as the files were
+    assert!(gunzip(&path).contains("Bob"));
+    assert!(gunzip(&dir.path().join("orders.csv.gz")).ends_with("3,2\n"));
+    let (db, _) = parse_yaml_database(&path).await.unwrap();
+    assert_eq!(
+        db.get_table("users").unwrap().rows[1][1],
+        Value::Text("Bob".to_string())
+    );
+    assert_eq!(db.get_table("orders").unwrap().rows.len(), 3);
+}
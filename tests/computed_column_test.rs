@@ -0,0 +1,131 @@
+use std::sync::Arc;
+use yamlbase::database::{Storage, Value};
+use yamlbase::sql::{QueryExecutor, WriteMode};
+use yamlbase::yaml::parse_yaml_database;
+
+mod common;
+use common::{try_query, yaml_file};
+
+const YAML: &str = r#"
+database:
+  name: "test_db"
+
+tables:
+  people:
+    columns:
+      id: "INTEGER PRIMARY KEY AUTO_INCREMENT"
+      first_name: "VARCHAR(50) NOT NULL"
+      last_name: "VARCHAR(50) NOT NULL"
+      full_name: "VARCHAR(101) NOT NULL"
+      hours: "INTEGER"
+      rate: "INTEGER"
+      pay: "INTEGER"
+    computed:
+      full_name: "first_name || ' ' || last_name"
+      pay: "hours * rate"
+    data:
+      - id: 1
+        first_name: "Ada"
+        last_name: "Lovelace"
+        hours: 10
+        rate: 3
+      - id: 2
+        first_name: "Alan"
+        last_name: "Turing"
+"#;
+
+async fn load_error(yaml: &str) -> String {
+    let file = yaml_file(yaml);
+    parse_yaml_database(file.path())
+        .await
+        .unwrap_err()
+        .to_string()
+}
+
+fn text(value: &str) -> Value {
+    Value::Text(value.to_string())
+}
+
+#[tokio::test]
+async fn test_computed_on_load() {
+    let file = yaml_file(YAML);
+    let (db, _) = parse_yaml_database(file.path()).await.unwrap();
+    let people = db.get_table("people").unwrap();
+    assert_eq!(people.rows[0][3], text("Ada Lovelace"));
+    assert_eq!(people.rows[0][6], Value::Integer(30));
+    assert_eq!(people.rows[1][3], text("Alan Turing"));
+    assert_eq!(people.rows[1][6], Value::Null);
+
+    let error = load_error(&YAML.replace("        hours: 10", "        pay: 5")).await;
+    assert!(error.contains("'pay'"), "{}", error);
+    assert!(error.contains("computed"), "{}", error);
+
+    let error = load_error(&YAML.replace("      pay: \"hours", "      wage: \"hours")).await;
+    assert!(error.contains("'wage'"), "{}", error);
+
+    let error = load_error(&YAML.replace("hours * rate", "hours *")).await;
+    assert!(error.contains("hours *"), "{}", error);
+}
+
+#[tokio::test]
+async fn test_computed_on_writes() {
+    let file = yaml_file(YAML);
+    let (db, _) = parse_yaml_database(file.path()).await.unwrap();
+    let executor = QueryExecutor::new(Arc::new(Storage::new(db)))
+        .await
+        .unwrap()
+        .with_write_mode(WriteMode::Persistent(file.path().to_path_buf()));
+
+    let rows = try_query(
+        &executor,
+        "INSERT INTO people (first_name, last_name, hours, rate) \
+         VALUES ('Grace', 'Hopper', 2, 4) RETURNING id, full_name, pay",
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        rows,
+        vec![vec![
+            Value::Integer(3),
+            text("Grace Hopper"),
+            Value::Integer(8)
+        ]]
+    );
+
+    try_query(
+        &executor,
+        "UPDATE people SET rate = 5, last_name = 'Byron' WHERE hours = 10",
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        try_query(
+            &executor,
+            "SELECT full_name, pay FROM people ORDER BY id LIMIT 1"
+        )
+        .await
+        .unwrap(),
+        vec![vec![text("Ada Byron"), Value::Integer(50)]]
+    );
+
+    let error = try_query(&executor, "UPDATE people SET pay = 1")
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("computed"), "{}", error);
+    let error = try_query(
+        &executor,
+        "INSERT INTO people (first_name, last_name, full_name) VALUES ('A', 'B', 'C')",
+    )
+    .await
+    .unwrap_err();
+    assert!(error.to_string().contains("computed"), "{}", error);
+
+    // Computed values are not written to the file, and are computed again on load
+    let content = std::fs::read_to_string(file.path()).unwrap();
+    assert!(!content.contains("Ada Byron"), "{}", content);
+    assert!(content.contains("Byron"), "{}", content);
+    let (db, _) = parse_yaml_database(file.path()).await.unwrap();
+    let people = db.get_table("people").unwrap();
+    assert_eq!(people.rows[0][3], text("Ada Byron"));
+    assert_eq!(people.rows[2][6], Value::Integer(8));
+}
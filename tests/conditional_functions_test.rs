@@ -0,0 +1,235 @@
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use yamlbase::database::{Database, Table, Value};
+use yamlbase::sql::{QueryExecutor, SqlDialect, parse_sql};
+use yamlbase::yaml::schema::SqlType;
+
+mod common;
+use common::{column, new_executor, query};
+
+fn date(s: &str) -> Value {
+    Value::Date(NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap())
+}
+
+fn text(s: &str) -> Value {
+    Value::Text(s.to_string())
+}
+
+fn create_database() -> Database {
+    let mut db = Database::new("test_db".to_string());
+
+    let mut orders = Table::new(
+        "orders".to_string(),
+        vec![
+            column("id", SqlType::Integer, true),
+            column("status", SqlType::Varchar(10), false),
+            column("quantity", SqlType::Integer, false),
+            column("shipped_on", SqlType::Date, false),
+        ],
+    );
+    let rows = [
+        (1, Some("N"), Some(5), Some("2024-03-01")),
+        (2, Some("S"), None, Some("2024-01-15")),
+        (3, None, Some(12), None),
+    ];
+    for (id, status, quantity, shipped_on) in rows {
+        orders
+            .insert_row(vec![
+                Value::Integer(id),
+                status.map(text).unwrap_or(Value::Null),
+                quantity.map(Value::Integer).unwrap_or(Value::Null),
+                shipped_on.map(date).unwrap_or(Value::Null),
+            ])
+            .unwrap();
+    }
+    db.add_table(orders).unwrap();
+    db
+}
+
+async fn create_executor() -> QueryExecutor {
+    new_executor(create_database()).await
+}
+
+#[tokio::test]
+async fn test_greatest_and_least() {
+    let executor = create_executor().await;
+
+    let rows = query(
+        &executor,
+        "SELECT GREATEST(3, 7.5, 1), LEAST(3, 7.5, 1), GREATEST('apple', 'pear'), \
+         GREATEST(NULL, 2), LEAST(NULL, NULL)",
+    )
+    .await;
+    assert_eq!(
+        rows[0],
+        vec![
+            Value::Decimal(Decimal::new(75, 1)),
+            Value::Integer(1),
+            text("pear"),
+            Value::Integer(2),
+            Value::Null,
+        ]
+    );
+
+    let rows = query(
+        &executor,
+        "SELECT GREATEST(quantity, 10), LEAST(shipped_on, DATE '2024-02-01') \
+         FROM orders ORDER BY id",
+    )
+    .await;
+    assert_eq!(
+        rows,
+        vec![
+            vec![Value::Integer(10), date("2024-02-01")],
+            vec![Value::Integer(10), date("2024-01-15")],
+            vec![Value::Integer(12), date("2024-02-01")],
+        ]
+    );
+
+    let statements = parse_sql("SELECT GREATEST(1, 'a')").unwrap();
+    assert!(executor.execute(&statements[0]).await.is_err());
+}
+
+#[tokio::test]
+async fn test_greatest_and_least_coerce_text_like_comparisons() {
+    let executor = create_executor().await;
+
+    // Text is read as the number or date it is compared with, as `'10' > 9` is
+    let rows = query(
+        &executor,
+        "SELECT GREATEST('10', 9), LEAST('2.5', 3), GREATEST(NULL, '7', 2), '10' > 9",
+    )
+    .await;
+    assert_eq!(
+        rows[0],
+        vec![
+            Value::Integer(10),
+            Value::Decimal(Decimal::new(25, 1)),
+            Value::Integer(7),
+            Value::Boolean(true),
+        ]
+    );
+
+    let rows = query(
+        &executor,
+        "SELECT GREATEST(shipped_on, '2024-02-01'), LEAST('2024-02-01', shipped_on) \
+         FROM orders WHERE id < 3 ORDER BY id",
+    )
+    .await;
+    assert_eq!(
+        rows,
+        vec![
+            vec![date("2024-03-01"), date("2024-02-01")],
+            vec![date("2024-02-01"), date("2024-01-15")],
+        ]
+    );
+    assert_eq!(
+        query(
+            &executor,
+            "SELECT id FROM orders WHERE shipped_on > '2024-02-01'"
+        )
+        .await,
+        vec![vec![Value::Integer(1)]]
+    );
+}
+
+#[tokio::test]
+async fn test_greatest_and_least_mysql_null_handling() {
+    let executor = new_executor(create_database())
+        .await
+        .with_dialect(SqlDialect::MySQL);
+
+    let rows = query(&executor, "SELECT GREATEST(NULL, 2), LEAST(1, 2)").await;
+    assert_eq!(rows[0], vec![Value::Null, Value::Integer(1)]);
+}
+
+#[tokio::test]
+async fn test_mysql_conditional_functions() {
+    let executor = create_executor().await;
+
+    let rows = query(
+        &executor,
+        "SELECT IF(1 > 2, 'yes', 'no'), IFNULL(NULL, 'fallback'), IFNULL('value', 'fallback'), \
+         ISNULL(NULL), ISNULL(5), ISNULL(NULL, 0)",
+    )
+    .await;
+    assert_eq!(
+        rows[0],
+        vec![
+            text("no"),
+            text("fallback"),
+            text("value"),
+            Value::Integer(1),
+            Value::Integer(0),
+            Value::Integer(0),
+        ]
+    );
+
+    let rows = query(
+        &executor,
+        "SELECT IF(status = 'S', 'shipped', 'open'), IFNULL(quantity, 0), ISNULL(shipped_on) \
+         FROM orders ORDER BY id",
+    )
+    .await;
+    assert_eq!(
+        rows,
+        vec![
+            vec![text("open"), Value::Integer(5), Value::Integer(0)],
+            vec![text("shipped"), Value::Integer(0), Value::Integer(0)],
+            vec![text("open"), Value::Integer(12), Value::Integer(1)],
+        ]
+    );
+
+    // Only the branch that is taken is evaluated
+    let rows = query(
+        &executor,
+        "SELECT IF(IFNULL(quantity, 0) = 0, 0, 60 / quantity) FROM orders ORDER BY id",
+    )
+    .await;
+    assert_eq!(
+        rows,
+        vec![
+            vec![Value::Double(12.0)],
+            vec![Value::Integer(0)],
+            vec![Value::Double(5.0)],
+        ]
+    );
+
+    let rows = query(
+        &executor,
+        "SELECT id FROM orders WHERE IFNULL(status, 'N') = 'N' ORDER BY id",
+    )
+    .await;
+    assert_eq!(rows, vec![vec![Value::Integer(1)], vec![Value::Integer(3)]]);
+}
+
+#[tokio::test]
+async fn test_decode() {
+    let executor = create_executor().await;
+
+    let rows = query(
+        &executor,
+        "SELECT DECODE(status, 'N', 'New', 'S', 'Shipped', NULL, 'Unknown', 'Other') \
+         FROM orders ORDER BY id",
+    )
+    .await;
+    assert_eq!(
+        rows,
+        vec![
+            vec![text("New")],
+            vec![text("Shipped")],
+            vec![text("Unknown")],
+        ]
+    );
+
+    // Without a default an unmatched value yields NULL; numeric types compare by value
+    let rows = query(
+        &executor,
+        "SELECT DECODE(2, 1, 'one', 2.0, 'two'), DECODE(3, 1, 'one'), DECODE(1, 1, 'one', 1 / 0)",
+    )
+    .await;
+    assert_eq!(rows[0], vec![text("two"), Value::Null, text("one")]);
+
+    let statements = parse_sql("SELECT DECODE(1, 1)").unwrap();
+    assert!(executor.execute(&statements[0]).await.is_err());
+}
@@ -0,0 +1,209 @@
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use std::io::Write;
+use std::time::Duration;
+use tempfile::TempDir;
+use yamlbase::config::{Config, Protocol};
+
+mod common;
+use common::{TestServer, connect_postgres};
+
+const PRODUCTS: &str = r#"
+database:
+  name: "shop"
+
+tables:
+  products:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+    data:
+      - id: 1
+"#;
+
+fn write(directory: &TempDir, name: &str, content: &str) -> String {
+    let path = directory.path().join(name);
+    std::fs::write(&path, content).unwrap();
+    path.to_str().unwrap().to_string()
+}
+
+fn load(args: &[&str]) -> yamlbase::Result<Config> {
+    Config::try_load_from(["yamlbase"].iter().chain(args))
+}
+
+#[test]
+fn test_toml_settings() {
+    let directory = TempDir::new().unwrap();
+    write(&directory, "shop.yaml", PRODUCTS);
+    let settings = write(
+        &directory,
+        "yamlbase.toml",
+        r#"
+files = ["shop.yaml"]
+postgres_port = 15432
+mysql_port = 13306
+statement_timeout = "5s"
+tls-client-ca = "certs/ca.pem"
+
+[[users]]
+username = "reporting"
+password = "reports"
+grants = { read = ["products"] }
+"#,
+    );
+
+    let config = load(&["--config", &settings]).unwrap();
+    assert_eq!(config.files, vec![directory.path().join("shop.yaml")]);
+    assert_eq!(
+        config.listeners(),
+        vec![(Protocol::Postgres, 15432), (Protocol::Mysql, 13306)]
+    );
+    assert_eq!(config.statement_timeout(), Duration::from_secs(5));
+    assert_eq!(
+        config.tls_client_ca.as_deref(),
+        Some(directory.path().join("certs/ca.pem").as_path())
+    );
+    assert!(config.user("reporting").unwrap().1.is_some());
+    // Settings the file leaves out keep their defaults
+    assert_eq!(config.username, "admin");
+    assert_eq!(config.connect_timeout(), Duration::from_secs(30));
+}
+
+#[test]
+fn test_command_line_overrides_settings() {
+    let directory = TempDir::new().unwrap();
+    let database = write(&directory, "shop.yaml", PRODUCTS);
+    let settings = write(
+        &directory,
+        "yamlbase.toml",
+        r#"
+postgres_port = 15432
+statement_timeout = "5s"
+username = "tester"
+"#,
+    );
+
+    let config = load(&[
+        "-f",
+        &database,
+        "--config",
+        &settings,
+        "--statement-timeout",
+        "1s",
+        "--protocol",
+        "mysql",
+    ])
+    .unwrap();
+    assert_eq!(config.statement_timeout(), Duration::from_secs(1));
+    assert_eq!(config.username, "tester");
+    // Listeners on the command line replace those of the file
+    assert_eq!(config.listeners(), vec![(Protocol::Mysql, 3306)]);
+}
+
+#[test]
+fn test_server_section() {
+    let directory = TempDir::new().unwrap();
+    let database = write(
+        &directory,
+        "shop.yaml",
+        &format!(
+            "server:\n  max_connections: 5\n  idle_timeout: 5min\n  protocol: mysql\n{}",
+            PRODUCTS
+        ),
+    );
+    let settings = write(&directory, "yamlbase.toml", "max_connections = 7\n");
+
+    let config = load(&["-f", &database]).unwrap();
+    assert_eq!(config.max_connections(), 5);
+    assert_eq!(config.idle_timeout, Some(Duration::from_secs(300)));
+    assert_eq!(config.listeners(), vec![(Protocol::Mysql, 3306)]);
+
+    // The TOML file takes precedence over the section
+    let config = load(&["-f", &database, "--config", &settings]).unwrap();
+    assert_eq!(config.max_connections(), 7);
+    assert_eq!(config.idle_timeout, Some(Duration::from_secs(300)));
+}
+
+#[test]
+fn test_server_section_of_compressed_file() {
+    let directory = TempDir::new().unwrap();
+    let path = directory.path().join("shop.yaml.gz");
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    write!(encoder, "server:\n  max_connections: 5\n{}", PRODUCTS).unwrap();
+    std::fs::write(&path, encoder.finish().unwrap()).unwrap();
+
+    let config = load(&["-f", path.to_str().unwrap()]).unwrap();
+    assert_eq!(config.max_connections(), 5);
+}
+
+#[test]
+fn test_server_section_read_errors() {
+    let directory = TempDir::new().unwrap();
+
+    // The file is read no further than the section
+    let database = write(
+        &directory,
+        "shop.yaml",
+        "server:\n  max_connections: 5\ntables: [unclosed\n",
+    );
+    assert_eq!(load(&["-f", &database]).unwrap().max_connections(), 5);
+
+    let database = write(
+        &directory,
+        "broken.yaml",
+        "tables: [unclosed\nserver:\n  max_connections: 5\n",
+    );
+    let error = load(&["-f", &database]).unwrap_err();
+    assert!(error.to_string().contains("broken.yaml"), "{}", error);
+
+    let database = write(&directory, "corrupt.yaml.gz", "not gzip");
+    assert!(load(&["-f", &database]).is_err());
+}
+
+#[test]
+fn test_invalid_settings() {
+    let directory = TempDir::new().unwrap();
+    let database = write(&directory, "shop.yaml", PRODUCTS);
+
+    let settings = write(&directory, "unknown.toml", "statement_timeot = \"5s\"\n");
+    let error = load(&["-f", &database, "--config", &settings]).unwrap_err();
+    assert!(
+        error
+            .to_string()
+            .contains("unknown setting `statement_timeot`")
+    );
+
+    let settings = write(&directory, "invalid.toml", "max_connections = \"many\"\n");
+    let error = load(&["-f", &database, "--config", &settings]).unwrap_err();
+    assert!(error.to_string().contains("invalid.toml"), "{}", error);
+
+    let missing = directory.path().join("missing.toml");
+    assert!(load(&["-f", &database, "--config", missing.to_str().unwrap()]).is_err());
+
+    // Without -f the settings have to name the database files
+    let settings = write(&directory, "empty.toml", "");
+    assert!(load(&["--config", &settings]).is_err());
+}
+
+#[tokio::test]
+async fn test_server_with_settings_file() {
+    let directory = TempDir::new().unwrap();
+    let database = write(&directory, "shop.yaml", PRODUCTS);
+    let settings = write(
+        &directory,
+        "yamlbase.toml",
+        r#"
+username = "tester"
+password = "secret"
+"#,
+    );
+    let server = TestServer::start_postgres_with_args(&database, &["--config", &settings]);
+
+    let client = connect_postgres(server.port(), "tester", "secret", "shop")
+        .await
+        .unwrap();
+    let row = client
+        .query_one("SELECT COUNT(*) FROM products", &[])
+        .await
+        .unwrap();
+    assert_eq!(row.get::<_, i64>(0), 1);
+}
@@ -0,0 +1,150 @@
+use rust_decimal::Decimal;
+use std::path::Path;
+use std::sync::Arc;
+use tempfile::TempDir;
+use yamlbase::database::{Storage, Value};
+use yamlbase::sql::{QueryExecutor, WriteMode, parse_sql};
+use yamlbase::yaml::{parse_yaml_database, reload_yaml_files};
+
+const YAML: &str = r#"
+database:
+  name: "test_db"
+
+tables:
+  users:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+      name: "VARCHAR(50) NOT NULL"
+      active: "BOOLEAN"
+      balance: "DECIMAL(10,2)"
+      joined: "DATE"
+      points: "INTEGER NOT NULL DEFAULT 0"
+    data_file: data/users.csv
+"#;
+
+const USERS: &str = "id,name,active,balance,joined\n\
+                     1,\"Smith, Ann\",true,10.50,2024-01-15\n\
+                     2,bob,f,,\n\
+                     3,\"\",1,-3.25,2024-03-01\n";
+
+fn write_files(dir: &Path, files: &[(&str, &str)]) {
+    for (name, content) in files {
+        let path = dir.join(name);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+}
+
+fn database_dir(users: &str) -> TempDir {
+    let dir = TempDir::new().unwrap();
+    write_files(dir.path(), &[("db.yaml", YAML), ("data/users.csv", users)]);
+    dir
+}
+
+fn text(value: &str) -> Value {
+    Value::Text(value.to_string())
+}
+
+#[tokio::test]
+async fn test_load_csv_rows() {
+    let dir = database_dir(USERS);
+    let (db, _) = parse_yaml_database(&dir.path().join("db.yaml"))
+        .await
+        .unwrap();
+    let users = db.get_table("users").unwrap();
+
+    assert_eq!(users.rows.len(), 3);
+    assert_eq!(
+        users.rows[0],
+        vec![
+            Value::Integer(1),
+            text("Smith, Ann"),
+            Value::Boolean(true),
+            Value::Decimal(Decimal::new(1050, 2)),
+            Value::Date(chrono::NaiveDate::from_ymd_opt(2024, 1, 15).unwrap()),
+            Value::Integer(0),
+        ]
+    );
+    // Empty fields are NULL and quoted empty fields empty strings
+    assert_eq!(users.rows[1][2], Value::Boolean(false));
+    assert_eq!(users.rows[1][3], Value::Null);
+    assert_eq!(users.rows[2][1], text(""));
+    assert_eq!(db.data_files["users"], dir.path().join("data/users.csv"));
+}
+
+#[tokio::test]
+async fn test_csv_errors() {
+    let load = |users: &'static str| async move {
+        let dir = database_dir(users);
+        parse_yaml_database(&dir.path().join("db.yaml"))
+            .await
+            .unwrap_err()
+            .to_string()
+    };
+
+    let error = load("id,name\n1,ann\nx,bob\n").await;
+    assert!(error.contains("users.csv: line 3"), "{}", error);
+    assert!(load("id,nickname\n1,ann\n").await.contains("nickname"));
+    assert!(load("id,name\n1\n").await.contains("line 2"));
+    // NOT NULL columns need a value
+    assert!(load("id,name\n1,\n").await.contains("line 2"));
+
+    let dir = database_dir(USERS);
+    let both = YAML.replace(
+        "    data_file: data/users.csv",
+        "    data_file: data/users.csv\n    data:\n      - id: 9\n        name: x",
+    );
+    write_files(dir.path(), &[("db.yaml", &both)]);
+    let error = parse_yaml_database(&dir.path().join("db.yaml"))
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("both data: and data_file:"));
+}
+
+#[tokio::test]
+async fn test_persist_to_csv() {
+    let dir = database_dir(USERS);
+    let yaml_path = dir.path().join("db.yaml");
+    let (db, _) = parse_yaml_database(&yaml_path).await.unwrap();
+    let executor = QueryExecutor::new(Arc::new(Storage::new(db)))
+        .await
+        .unwrap()
+        .with_write_mode(WriteMode::Persistent(yaml_path.clone()));
+
+    let statements =
+        parse_sql("INSERT INTO users (id, name, active) VALUES (4, 'say \"hi\"', false)").unwrap();
+    executor.execute(&statements[0]).await.unwrap();
+
+    // The rows go to the CSV file, and the YAML file is left alone
+    assert_eq!(std::fs::read_to_string(&yaml_path).unwrap(), YAML);
+    let csv = std::fs::read_to_string(dir.path().join("data/users.csv")).unwrap();
+    assert!(csv.starts_with("id,name,active,balance,joined,points\n"));
+    assert!(csv.contains("4,\"say \"\"hi\"\"\",false,,,0\n"));
+
+    let (reloaded, _) = parse_yaml_database(&yaml_path).await.unwrap();
+    let users = reloaded.get_table("users").unwrap();
+    let original = executor.storage().database();
+    let original = original.read().await;
+    assert_eq!(users.rows, original.get_table("users").unwrap().rows);
+}
+
+#[tokio::test]
+async fn test_reload_changed_csv() {
+    let dir = database_dir(USERS);
+    let (db, _) = parse_yaml_database(dir.path()).await.unwrap();
+
+    let csv = dir.path().join("data/users.csv");
+    std::fs::write(&csv, "id,name\n7,cy\n").unwrap();
+    let reloaded = reload_yaml_files(&db, dir.path(), &[csv]).await.unwrap();
+    assert_eq!(
+        reloaded.get_table("users").unwrap().rows,
+        vec![vec![
+            Value::Integer(7),
+            text("cy"),
+            Value::Null,
+            Value::Null,
+            Value::Null,
+            Value::Integer(0),
+        ]]
+    );
+}
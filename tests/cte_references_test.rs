@@ -0,0 +1,140 @@
+use yamlbase::database::{Database, Table, Value};
+use yamlbase::sql::{QueryExecutor, parse_sql};
+use yamlbase::yaml::schema::SqlType;
+
+mod common;
+use common::{column, new_executor, query};
+
+async fn create_executor() -> QueryExecutor {
+    let mut db = Database::new("test_db".to_string());
+
+    let mut employees = Table::new(
+        "employees".to_string(),
+        vec![
+            column("id", SqlType::Integer, true),
+            column("dept", SqlType::Text, false),
+            column("salary", SqlType::Integer, false),
+            column("manager_id", SqlType::Integer, false),
+        ],
+    );
+    let rows = [
+        (1, "eng", 100, None),
+        (2, "eng", 80, Some(1)),
+        (3, "ops", 60, Some(1)),
+        (4, "ops", 70, Some(3)),
+    ];
+    for (id, dept, salary, manager_id) in rows {
+        employees
+            .insert_row(vec![
+                Value::Integer(id),
+                Value::Text(dept.to_string()),
+                Value::Integer(salary),
+                manager_id.map_or(Value::Null, Value::Integer),
+            ])
+            .unwrap();
+    }
+    db.add_table(employees).unwrap();
+
+    new_executor(db).await
+}
+
+fn ids(ids: &[i64]) -> Vec<Vec<Value>> {
+    ids.iter().map(|id| vec![Value::Integer(*id)]).collect()
+}
+
+#[tokio::test]
+async fn test_subqueries_read_ctes() {
+    let executor = create_executor().await;
+
+    // IN subquery over a CTE that itself reads an earlier CTE
+    assert_eq!(
+        query(
+            &executor,
+            "WITH staff AS (SELECT id, dept FROM employees), \
+                  engineers AS (SELECT id FROM staff WHERE dept = 'eng') \
+             SELECT id FROM employees WHERE id IN (SELECT id FROM engineers) ORDER BY id",
+        )
+        .await,
+        ids(&[1, 2])
+    );
+
+    // Scalar subquery over the same CTE as the outer query
+    assert_eq!(
+        query(
+            &executor,
+            "WITH pay AS (SELECT id, salary FROM employees) \
+             SELECT id FROM pay WHERE salary > (SELECT AVG(salary) FROM pay) ORDER BY id",
+        )
+        .await,
+        ids(&[1, 2])
+    );
+
+    // Derived table inside a CTE reading an earlier CTE
+    assert_eq!(
+        query(
+            &executor,
+            "WITH staff AS (SELECT id, dept FROM employees), \
+                  ops AS (SELECT d.id FROM (SELECT id FROM staff WHERE dept = 'ops') d) \
+             SELECT id FROM ops ORDER BY id",
+        )
+        .await,
+        ids(&[3, 4])
+    );
+}
+
+#[tokio::test]
+async fn test_cte_names_and_column_lists() {
+    let executor = create_executor().await;
+
+    // CTE names are case-insensitive like table names
+    assert_eq!(
+        query(
+            &executor,
+            "WITH Totals AS (SELECT dept, COUNT(*) AS headcount FROM employees GROUP BY dept) \
+             SELECT dept, headcount FROM totals ORDER BY dept",
+        )
+        .await,
+        vec![
+            vec![Value::Text("eng".to_string()), Value::Integer(2)],
+            vec![Value::Text("ops".to_string()), Value::Integer(2)],
+        ]
+    );
+
+    let rows = query(
+        &executor,
+        "WITH pay(employee, amount) AS (SELECT id, salary FROM employees) \
+         SELECT employee FROM pay WHERE amount >= 80 ORDER BY employee",
+    )
+    .await;
+    assert_eq!(rows, ids(&[1, 2]));
+
+    let statements =
+        parse_sql("WITH pay(a, b, c) AS (SELECT id, salary FROM employees) SELECT a FROM pay")
+            .unwrap();
+    assert!(executor.execute(&statements[0]).await.is_err());
+}
+
+#[tokio::test]
+async fn test_with_recursive_mixes_plain_ctes() {
+    let executor = create_executor().await;
+
+    // Only `chain` reads itself; `staff` is a plain CTE in the same WITH RECURSIVE list
+    assert_eq!(
+        query(
+            &executor,
+            "WITH RECURSIVE staff AS (SELECT id, manager_id FROM employees), \
+             chain AS ( \
+               SELECT id, 0 AS depth FROM staff WHERE manager_id IS NULL \
+               UNION ALL \
+               SELECT s.id, c.depth + 1 FROM staff s JOIN chain c ON s.manager_id = c.id \
+             ) SELECT id, depth FROM chain ORDER BY id",
+        )
+        .await,
+        vec![
+            vec![Value::Integer(1), Value::Integer(0)],
+            vec![Value::Integer(2), Value::Integer(1)],
+            vec![Value::Integer(3), Value::Integer(1)],
+            vec![Value::Integer(4), Value::Integer(2)],
+        ]
+    );
+}
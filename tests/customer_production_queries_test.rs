@@ -21,6 +21,7 @@ async fn test_customer_production_queries() {
             unique: true,
             default: None,
             references: None,
+            collation: None,
         }],
     );
 
@@ -21,6 +21,7 @@ async fn test_basic_date_arithmetic() {
             unique: true,
             default: None,
             references: None,
+            collation: None,
         }],
     );
 
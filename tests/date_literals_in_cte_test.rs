@@ -24,6 +24,7 @@ mod date_literals_in_cte_test {
                     unique: true,
                     default: None,
                     references: None,
+                    collation: None,
                 },
                 Column {
                     name: "project_name".to_string(),
@@ -33,6 +34,7 @@ mod date_literals_in_cte_test {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
                 Column {
                     name: "start_date".to_string(),
@@ -42,6 +44,7 @@ mod date_literals_in_cte_test {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
             ],
         );
@@ -122,6 +125,7 @@ mod date_literals_in_cte_test {
                     unique: true,
                     default: None,
                     references: None,
+                    collation: None,
                 },
                 Column {
                     name: "project_name".to_string(),
@@ -131,6 +135,7 @@ mod date_literals_in_cte_test {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
                 Column {
                     name: "start_date".to_string(),
@@ -140,6 +145,7 @@ mod date_literals_in_cte_test {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
             ],
         );
@@ -220,6 +226,7 @@ mod date_literals_in_cte_test {
                     unique: true,
                     default: None,
                     references: None,
+                    collation: None,
                 },
                 Column {
                     name: "project_name".to_string(),
@@ -229,6 +236,7 @@ mod date_literals_in_cte_test {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
                 Column {
                     name: "start_date".to_string(),
@@ -238,6 +246,7 @@ mod date_literals_in_cte_test {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
             ],
         );
@@ -328,6 +337,7 @@ mod date_literals_in_cte_test {
                     unique: true,
                     default: None,
                     references: None,
+                    collation: None,
                 },
                 Column {
                     name: "project_name".to_string(),
@@ -337,6 +347,7 @@ mod date_literals_in_cte_test {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
                 Column {
                     name: "start_date".to_string(),
@@ -346,6 +357,7 @@ mod date_literals_in_cte_test {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
                 Column {
                     name: "project_structure".to_string(),
@@ -355,6 +367,7 @@ mod date_literals_in_cte_test {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
             ],
         );
@@ -0,0 +1,168 @@
+use chrono::{NaiveDate, NaiveTime};
+use yamlbase::database::{Database, Table, Value};
+use yamlbase::sql::{QueryExecutor, parse_sql};
+use yamlbase::yaml::schema::SqlType;
+
+mod common;
+use common::{column, new_executor, query};
+
+fn date(y: i32, m: u32, d: u32) -> Value {
+    Value::Date(NaiveDate::from_ymd_opt(y, m, d).unwrap())
+}
+
+fn timestamp(y: i32, m: u32, d: u32, h: u32, mi: u32, s: u32) -> Value {
+    Value::Timestamp(
+        NaiveDate::from_ymd_opt(y, m, d)
+            .unwrap()
+            .and_hms_opt(h, mi, s)
+            .unwrap(),
+    )
+}
+
+async fn create_executor() -> QueryExecutor {
+    let mut db = Database::new("test_db".to_string());
+
+    let mut events = Table::new(
+        "events".to_string(),
+        vec![
+            column("id", SqlType::Integer, true),
+            column("happened", SqlType::Text, false),
+        ],
+    );
+    let rows = ["03/01/2024 14:05", "28/02/2024 09:30", "not a date"];
+    for (id, happened) in (1..).zip(rows) {
+        events
+            .insert_row(vec![Value::Integer(id), Value::Text(happened.to_string())])
+            .unwrap();
+    }
+    db.add_table(events).unwrap();
+
+    new_executor(db).await
+}
+
+#[tokio::test]
+async fn test_to_date_and_to_timestamp() {
+    let executor = create_executor().await;
+
+    let rows = query(
+        &executor,
+        "SELECT TO_DATE('05 Mar 2024', 'DD Mon YYYY'), TO_DATE('2024-03-05 10:00', 'YYYY-MM-DD'), \
+         TO_TIMESTAMP('2024-03-05 07:08:09 pm', 'YYYY-MM-DD HH12:MI:SS AM'), \
+         TO_TIMESTAMP('20240305', 'YYYYMMDD'), TO_TIMESTAMP(0), TO_DATE(NULL, 'YYYY')",
+    )
+    .await;
+    assert_eq!(
+        rows[0],
+        vec![
+            date(2024, 3, 5),
+            date(2024, 3, 5),
+            timestamp(2024, 3, 5, 19, 8, 9),
+            timestamp(2024, 3, 5, 0, 0, 0),
+            timestamp(1970, 1, 1, 0, 0, 0),
+            Value::Null,
+        ]
+    );
+
+    for sql in [
+        "SELECT TO_DATE('2024-02-30', 'YYYY-MM-DD')",
+        "SELECT TO_DATE('12:30', 'HH24:MI')",
+        "SELECT TO_DATE('2024-03-05')",
+    ] {
+        let statements = parse_sql(sql).unwrap();
+        assert!(executor.execute(&statements[0]).await.is_err(), "{}", sql);
+    }
+}
+
+#[tokio::test]
+async fn test_str_to_date() {
+    let executor = create_executor().await;
+
+    let rows = query(
+        &executor,
+        "SELECT STR_TO_DATE('March 5, 2024', '%M %e, %Y'), STR_TO_DATE('05.03.2024 23:59', \
+         '%d.%m.%Y %H:%i'), STR_TO_DATE('10:15:30', '%T'), STR_TO_DATE('2024-13-01', '%Y-%m-%d')",
+    )
+    .await;
+    assert_eq!(
+        rows[0],
+        vec![
+            date(2024, 3, 5),
+            timestamp(2024, 3, 5, 23, 59, 0),
+            Value::Time(NaiveTime::from_hms_opt(10, 15, 30).unwrap()),
+            Value::Null,
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_parsing_text_columns() {
+    let executor = create_executor().await;
+
+    let rows = query(
+        &executor,
+        "SELECT id, STR_TO_DATE(happened, '%d/%m/%Y %H:%i') FROM events ORDER BY id",
+    )
+    .await;
+    assert_eq!(
+        rows,
+        vec![
+            vec![Value::Integer(1), timestamp(2024, 1, 3, 14, 5, 0)],
+            vec![Value::Integer(2), timestamp(2024, 2, 28, 9, 30, 0)],
+            vec![Value::Integer(3), Value::Null],
+        ]
+    );
+
+    let rows = query(
+        &executor,
+        "SELECT id FROM events WHERE STR_TO_DATE(happened, '%d/%m/%Y') >= DATE '2024-02-01'",
+    )
+    .await;
+    assert_eq!(rows, vec![vec![Value::Integer(2)]]);
+
+    // TO_DATE fails on text that does not match, where STR_TO_DATE gives NULL
+    let statements = parse_sql("SELECT TO_DATE(happened, 'DD/MM/YYYY') FROM events").unwrap();
+    assert!(executor.execute(&statements[0]).await.is_err());
+}
+
+#[tokio::test]
+async fn test_day_and_month_names() {
+    let executor = create_executor().await;
+
+    // 2024-03-03 is a Sunday
+    let rows = query(
+        &executor,
+        "SELECT DAYNAME(DATE '2024-03-03'), MONTHNAME(DATE '2024-03-03'), \
+         WEEKDAY(DATE '2024-03-03'), DAYOFWEEK(DATE '2024-03-03'), \
+         DAYNAME(TIMESTAMP '2024-03-04 23:00:00'), WEEKDAY('2024-03-04'), \
+         DAYOFWEEK('2024-03-09 10:00:00'), MONTHNAME('not a date'), DAYNAME(NULL)",
+    )
+    .await;
+    assert_eq!(
+        rows[0],
+        vec![
+            Value::Text("Sunday".to_string()),
+            Value::Text("March".to_string()),
+            Value::Integer(6),
+            Value::Integer(1),
+            Value::Text("Monday".to_string()),
+            Value::Integer(0),
+            Value::Integer(7),
+            Value::Null,
+            Value::Null,
+        ]
+    );
+
+    let rows = query(
+        &executor,
+        "SELECT id, DAYNAME(STR_TO_DATE(happened, '%d/%m/%Y')) FROM events ORDER BY id",
+    )
+    .await;
+    assert_eq!(
+        rows,
+        vec![
+            vec![Value::Integer(1), Value::Text("Wednesday".to_string())],
+            vec![Value::Integer(2), Value::Text("Wednesday".to_string())],
+            vec![Value::Integer(3), Value::Null],
+        ]
+    );
+}
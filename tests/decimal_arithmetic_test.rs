@@ -0,0 +1,165 @@
+use rust_decimal::Decimal;
+use std::str::FromStr;
+use yamlbase::database::{Database, Table, Value};
+use yamlbase::sql::{QueryExecutor, parse_sql};
+use yamlbase::yaml::schema::SqlType;
+
+mod common;
+use common::{column, new_executor, query};
+
+fn dec(s: &str) -> Value {
+    Value::Decimal(Decimal::from_str(s).unwrap())
+}
+
+async fn create_executor() -> QueryExecutor {
+    let mut db = Database::new("test_db".to_string());
+
+    let mut items = Table::new(
+        "items".to_string(),
+        vec![
+            column("id", SqlType::Integer, true),
+            column("price", SqlType::Decimal(10, 2), false),
+            column("discount", SqlType::Decimal(10, 3), false),
+            column("qty", SqlType::Integer, false),
+        ],
+    );
+    let rows = [(1, "19.99", "0.125", 3), (2, "0.10", "0.000", 7)];
+    for (id, price, discount, qty) in rows {
+        items
+            .insert_row(vec![
+                Value::Integer(id),
+                dec(price),
+                dec(discount),
+                Value::Integer(qty),
+            ])
+            .unwrap();
+    }
+    db.add_table(items).unwrap();
+
+    new_executor(db).await
+}
+
+#[tokio::test]
+async fn test_decimal_arithmetic_keeps_exact_values() {
+    let executor = create_executor().await;
+
+    let rows = query(
+        &executor,
+        "SELECT price * qty, price - discount, price + price, -price FROM items ORDER BY id",
+    )
+    .await;
+    assert_eq!(
+        rows,
+        vec![
+            vec![dec("59.97"), dec("19.865"), dec("39.98"), dec("-19.99")],
+            vec![dec("0.70"), dec("0.100"), dec("0.20"), dec("-0.10")],
+        ]
+    );
+
+    // Repeated addition stays exact, unlike DOUBLE
+    let rows = query(
+        &executor,
+        "SELECT price + price + price FROM items WHERE qty = 7",
+    )
+    .await;
+    assert_eq!(rows, vec![vec![dec("0.30")]]);
+}
+
+#[tokio::test]
+async fn test_decimal_division_and_mixed_types() {
+    let executor = create_executor().await;
+
+    let rows = query(
+        &executor,
+        "SELECT price / 4, price % 2, price * 0.5 FROM items WHERE id < 2",
+    )
+    .await;
+    assert_eq!(rows[0][0], dec("4.9975"));
+    assert_eq!(rows[0][1], dec("1.99"));
+    assert_eq!(rows[0][2], dec("9.995"));
+
+    let statements = parse_sql("SELECT price / (qty - qty) FROM items").unwrap();
+    assert!(executor.execute(&statements[0]).await.is_err());
+}
+
+#[tokio::test]
+async fn test_decimal_literals() {
+    let executor = create_executor().await;
+
+    // A literal with a decimal point is a DECIMAL, so it stays exact
+    let rows = query(&executor, "SELECT 0.1 + 0.2, 1.10 * 3, 7 / 2.0").await;
+    assert_eq!(rows, vec![vec![dec("0.3"), dec("3.30"), dec("3.5")]]);
+
+    let rows = query(&executor, "SELECT price * 1.005 FROM items WHERE qty = 3").await;
+    assert_eq!(rows, vec![vec![dec("20.08995")]]);
+
+    let rows = query(&executor, "SELECT id FROM items WHERE price - 0.1 = 0.0").await;
+    assert_eq!(rows, vec![vec![Value::Integer(2)]]);
+
+    // An exponent makes it a DOUBLE
+    let rows = query(
+        &executor,
+        "SELECT 1.5e2, price * 1e1 FROM items WHERE qty = 7",
+    )
+    .await;
+    assert_eq!(rows[0][0], Value::Double(150.0));
+    assert!(matches!(rows[0][1], Value::Double(d) if (d - 1.0).abs() < 1e-9));
+}
+
+#[tokio::test]
+async fn test_decimal_rounding_functions_stay_exact() {
+    let executor = create_executor().await;
+
+    // Halves round away from zero, which a DOUBLE near 1.005 would not do
+    let rows = query(
+        &executor,
+        "SELECT ROUND(1.005, 2), ROUND(-2.5), ROUND(1250.0, -2)",
+    )
+    .await;
+    assert_eq!(rows, vec![vec![dec("1.01"), dec("-3"), dec("1300")]]);
+
+    let rows = query(
+        &executor,
+        "SELECT ROUND(price * 1.005, 2), ROUND(price, 1) FROM items ORDER BY id",
+    )
+    .await;
+    assert_eq!(
+        rows,
+        vec![
+            vec![dec("20.09"), dec("20.0")],
+            vec![dec("0.10"), dec("0.1")],
+        ]
+    );
+
+    let rows = query(&executor, "SELECT FLOOR(-1.5), CEIL(1.0001), FLOOR(2.99)").await;
+    assert_eq!(rows, vec![vec![dec("-2"), dec("2"), dec("2")]]);
+}
+
+#[tokio::test]
+async fn test_decimal_sum_and_avg_stay_exact() {
+    let executor = create_executor().await;
+
+    let rows = query(
+        &executor,
+        "SELECT SUM(DISTINCT price), SUM(price * qty), AVG(price) FROM items",
+    )
+    .await;
+    assert_eq!(rows, vec![vec![dec("20.09"), dec("60.67"), dec("10.045")]]);
+
+    let rows = query(
+        &executor,
+        "SELECT qty, SUM(price), AVG(discount) FROM items GROUP BY qty ORDER BY qty",
+    )
+    .await;
+    assert_eq!(
+        rows,
+        vec![
+            vec![Value::Integer(3), dec("19.99"), dec("0.125")],
+            vec![Value::Integer(7), dec("0.10"), dec("0.000")],
+        ]
+    );
+
+    // A DOUBLE among the values makes the sum a DOUBLE
+    let rows = query(&executor, "SELECT SUM(price * 1e0) FROM items").await;
+    assert!(matches!(rows[0][0], Value::Double(d) if (d - 20.09).abs() < 1e-9));
+}
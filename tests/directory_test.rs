@@ -0,0 +1,201 @@
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tempfile::TempDir;
+use tokio_postgres::SimpleQueryMessage;
+use yamlbase::database::Value;
+use yamlbase::yaml::{parse_yaml_database, reload_yaml_files};
+
+mod common;
+use common::{TestServer, connect_postgres};
+
+const DATABASE: &str = r#"
+database:
+  name: "fixtures"
+  auth:
+    username: "yamlbase"
+    password: "password"
+
+materialized_views:
+  user_count:
+    query: "SELECT COUNT(*) AS n FROM users"
+"#;
+
+const USERS: &str = r#"
+tables:
+  users:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+      name: "TEXT"
+    data:
+      - id: 1
+        name: "ann"
+"#;
+
+const ORDERS: &str = r#"
+tables:
+  orders:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+    data:
+      - id: 10
+  order_lines:
+    columns:
+      order_id: "INTEGER"
+"#;
+
+fn write_files(dir: &Path, files: &[(&str, &str)]) {
+    for (name, content) in files {
+        std::fs::write(dir.join(name), content).unwrap();
+    }
+}
+
+fn fixtures() -> TempDir {
+    let dir = TempDir::new().unwrap();
+    write_files(
+        dir.path(),
+        &[
+            ("database.yaml", DATABASE),
+            ("users.yaml", USERS),
+            ("orders.yml", ORDERS),
+            ("notes.txt", "not: yaml"),
+            (".draft.yaml", "broken: ["),
+        ],
+    );
+    dir
+}
+
+#[tokio::test]
+async fn test_load_directory() {
+    let dir = fixtures();
+    let (db, auth) = parse_yaml_database(dir.path()).await.unwrap();
+
+    assert_eq!(db.name, "fixtures");
+    assert_eq!(auth.unwrap().username, "yamlbase");
+    let mut names: Vec<&str> = db.tables.keys().map(|name| name.as_str()).collect();
+    names.sort();
+    assert_eq!(names, vec!["order_lines", "orders", "user_count", "users"]);
+    assert_eq!(db.table_files["orders"], dir.path().join("orders.yml"));
+    assert_eq!(
+        db.get_table("user_count").unwrap().rows,
+        vec![vec![Value::Integer(1)]]
+    );
+
+    // Without a database: section the database is named after the directory
+    std::fs::remove_file(dir.path().join("database.yaml")).unwrap();
+    let (db, auth) = parse_yaml_database(dir.path()).await.unwrap();
+    let dir_name = dir.path().file_name().unwrap().to_string_lossy();
+    assert_eq!(db.name, dir_name);
+    assert!(auth.is_none());
+}
+
+#[tokio::test]
+async fn test_directory_conflicts() {
+    let dir = fixtures();
+    write_files(dir.path(), &[("more.yaml", USERS)]);
+    let error = parse_yaml_database(dir.path()).await.unwrap_err();
+    assert!(error.to_string().contains("'users' is defined in both"));
+
+    let dir = fixtures();
+    write_files(dir.path(), &[("other.yaml", "database:\n  name: other\n")]);
+    let error = parse_yaml_database(dir.path()).await.unwrap_err();
+    assert!(error.to_string().contains("database: section"));
+}
+
+#[tokio::test]
+async fn test_reload_changed_files() {
+    let dir = fixtures();
+    let (db, _) = parse_yaml_database(dir.path()).await.unwrap();
+
+    write_files(
+        dir.path(),
+        &[
+            (
+                "users.yaml",
+                &USERS.replace("name: \"ann\"", "name: \"bob\""),
+            ),
+            (
+                "tags.yaml",
+                "tables:\n  tags:\n    columns:\n      name: \"TEXT\"\n",
+            ),
+        ],
+    );
+    std::fs::remove_file(dir.path().join("orders.yml")).unwrap();
+    let changed = [
+        dir.path().join("users.yaml"),
+        dir.path().join("tags.yaml"),
+        dir.path().join("orders.yml"),
+    ];
+    let reloaded = reload_yaml_files(&db, dir.path(), &changed).await.unwrap();
+
+    assert_eq!(
+        reloaded.get_table("users").unwrap().rows,
+        vec![vec![Value::Integer(1), Value::Text("bob".to_string())]]
+    );
+    assert!(reloaded.get_table("tags").is_some());
+    assert!(reloaded.get_table("orders").is_none());
+    assert!(reloaded.get_table("order_lines").is_none());
+    assert!(!reloaded.table_files.contains_key("orders"));
+
+    // Tables of files that did not change are kept as they are
+    let (db, _) = parse_yaml_database(dir.path()).await.unwrap();
+    write_files(dir.path(), &[("users.yaml", USERS)]);
+    let reloaded = reload_yaml_files(&db, dir.path(), &[dir.path().join("users.yaml")])
+        .await
+        .unwrap();
+    assert!(Arc::ptr_eq(&db.tables["tags"], &reloaded.tables["tags"]));
+    assert_eq!(
+        reloaded.get_table("user_count").unwrap().rows,
+        vec![vec![Value::Integer(1)]]
+    );
+
+    // A reload that would define a table twice fails
+    write_files(dir.path(), &[("copy.yaml", USERS)]);
+    assert!(
+        reload_yaml_files(&db, dir.path(), &[dir.path().join("copy.yaml")])
+            .await
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn test_hot_reload_directory() {
+    let dir = fixtures();
+    let server =
+        TestServer::start_postgres_with_args(dir.path().to_str().unwrap(), &["--hot-reload"]);
+
+    let client = connect_postgres(server.port(), "yamlbase", "password", "fixtures")
+        .await
+        .unwrap();
+
+    let names = || async {
+        let messages = client
+            .simple_query("SELECT name FROM users ORDER BY id")
+            .await
+            .unwrap();
+        messages
+            .iter()
+            .filter_map(|message| match message {
+                SimpleQueryMessage::Row(row) => Some(row.get(0).unwrap().to_string()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(names().await, vec!["ann"]);
+
+    let more_users = USERS.replace(
+        "        name: \"ann\"",
+        "        name: \"ann\"\n      - id: 2\n        name: \"cy\"",
+    );
+    write_files(dir.path(), &[("users.yaml", &more_users)]);
+
+    let start = Instant::now();
+    while names().await.len() < 2 {
+        assert!(
+            start.elapsed() < Duration::from_secs(10),
+            "the change was not reloaded"
+        );
+        tokio::time::sleep(Duration::from_millis(200)).await;
+    }
+    assert_eq!(names().await, vec!["ann", "cy"]);
+}
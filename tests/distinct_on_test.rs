@@ -3,6 +3,9 @@ use yamlbase::database::{Column, Database, Storage, Table, Value};
 use yamlbase::sql::{QueryExecutor, parse_sql};
 use yamlbase::yaml::schema::SqlType;
 
+mod common;
+use common::{column, query};
+
 #[tokio::test]
 async fn test_distinct_on() {
     println!("=== DISTINCT ON TEST ===");
@@ -22,6 +25,7 @@ async fn test_distinct_on() {
                 unique: true,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "name".to_string(),
@@ -31,6 +35,7 @@ async fn test_distinct_on() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "department".to_string(),
@@ -40,6 +45,7 @@ async fn test_distinct_on() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "salary".to_string(),
@@ -49,6 +55,7 @@ async fn test_distinct_on() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "hire_date".to_string(),
@@ -58,6 +65,7 @@ async fn test_distinct_on() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
         ],
     );
@@ -233,3 +241,131 @@ async fn test_distinct_on() {
 
     println!("\n✅ All DISTINCT ON tests completed!");
 }
+
+#[tokio::test]
+async fn test_distinct_on_returns_latest_row_per_key() {
+    let mut db = Database::new("test_db".to_string());
+
+    let mut events_table = Table::new(
+        "events".to_string(),
+        vec![
+            column("id", SqlType::Integer, true),
+            column("user_id", SqlType::Integer, false),
+            column("action", SqlType::Varchar(20), false),
+            column("ts", SqlType::Timestamp, false),
+        ],
+    );
+
+    let ts = |day: u32, hour: u32| {
+        chrono::NaiveDate::from_ymd_opt(2024, 5, day)
+            .unwrap()
+            .and_hms_opt(hour, 0, 0)
+            .unwrap()
+    };
+    let events = [
+        (1, 1, "login", ts(1, 9)),
+        (2, 2, "login", ts(1, 10)),
+        (3, 1, "purchase", ts(2, 12)),
+        (4, 2, "logout", ts(3, 8)),
+        (5, 1, "logout", ts(1, 18)),
+    ];
+    for (id, user_id, action, at) in events {
+        events_table
+            .insert_row(vec![
+                Value::Integer(id),
+                Value::Integer(user_id),
+                Value::Text(action.to_string()),
+                Value::Timestamp(at),
+            ])
+            .unwrap();
+    }
+
+    db.add_table(events_table).unwrap();
+    let storage = Storage::new(db);
+    let executor = QueryExecutor::new(Arc::new(storage)).await.unwrap();
+
+    // The ORDER BY decides which row of each group survives, so DISTINCT ON must run after it
+    let parsed =
+        parse_sql("SELECT DISTINCT ON (user_id) * FROM events ORDER BY user_id, ts DESC").unwrap();
+    let result = executor.execute(&parsed[0]).await.unwrap();
+    assert_eq!(result.rows.len(), 2);
+    assert_eq!(result.rows[0][0], Value::Integer(3));
+    assert_eq!(result.rows[0][2], Value::Text("purchase".to_string()));
+    assert_eq!(result.rows[1][0], Value::Integer(4));
+    assert_eq!(result.rows[1][2], Value::Text("logout".to_string()));
+
+    let parsed = parse_sql(
+        "SELECT DISTINCT ON (user_id) user_id, action FROM events ORDER BY user_id, ts ASC",
+    )
+    .unwrap();
+    let result = executor.execute(&parsed[0]).await.unwrap();
+    assert_eq!(
+        result.rows,
+        vec![
+            vec![Value::Integer(1), Value::Text("login".to_string())],
+            vec![Value::Integer(2), Value::Text("login".to_string())],
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_distinct_on_keys_that_are_not_projected() {
+    let mut db = Database::new("test_db".to_string());
+
+    let mut items_table = Table::new(
+        "items".to_string(),
+        vec![
+            column("id", SqlType::Integer, true),
+            column("grp", SqlType::Integer, false),
+            column("name", SqlType::Varchar(20), false),
+        ],
+    );
+    for (id, grp, name) in [
+        (1, 2, "Apple"),
+        (2, 1, "apple"),
+        (3, 2, "Pear"),
+        (4, 1, "PEAR"),
+    ] {
+        items_table
+            .insert_row(vec![
+                Value::Integer(id),
+                Value::Integer(grp),
+                Value::Text(name.to_string()),
+            ])
+            .unwrap();
+    }
+
+    db.add_table(items_table).unwrap();
+    let storage = Storage::new(db);
+    let executor = QueryExecutor::new(Arc::new(storage)).await.unwrap();
+    let ids = |ids: &[i64]| -> Vec<Vec<Value>> {
+        ids.iter().map(|id| vec![Value::Integer(*id)]).collect()
+    };
+
+    // The key is evaluated against the table row, not the projected one
+    assert_eq!(
+        query(
+            &executor,
+            "SELECT DISTINCT ON (grp) id FROM items ORDER BY grp, id"
+        )
+        .await,
+        ids(&[2, 1])
+    );
+    assert_eq!(
+        query(
+            &executor,
+            "SELECT DISTINCT ON (LOWER(name)) id FROM items ORDER BY LOWER(name), id DESC"
+        )
+        .await,
+        ids(&[2, 4])
+    );
+    assert_eq!(
+        query(
+            &executor,
+            "SELECT DISTINCT ON (a.grp) b.id FROM items a JOIN items b ON a.id = b.id \
+             ORDER BY a.grp, b.id DESC"
+        )
+        .await,
+        ids(&[4, 3])
+    );
+}
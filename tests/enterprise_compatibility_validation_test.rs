@@ -79,6 +79,7 @@ async fn create_enterprise_test_database() -> Arc<Storage> {
                 unique: true,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "first_name".to_string(),
@@ -88,6 +89,7 @@ async fn create_enterprise_test_database() -> Arc<Storage> {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "last_name".to_string(),
@@ -97,6 +99,7 @@ async fn create_enterprise_test_database() -> Arc<Storage> {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "hire_date".to_string(),
@@ -106,6 +109,7 @@ async fn create_enterprise_test_database() -> Arc<Storage> {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "salary".to_string(),
@@ -115,6 +119,7 @@ async fn create_enterprise_test_database() -> Arc<Storage> {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "department_id".to_string(),
@@ -124,6 +129,7 @@ async fn create_enterprise_test_database() -> Arc<Storage> {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "email".to_string(),
@@ -133,6 +139,7 @@ async fn create_enterprise_test_database() -> Arc<Storage> {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "is_active".to_string(),
@@ -142,6 +149,7 @@ async fn create_enterprise_test_database() -> Arc<Storage> {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
         ],
     );
@@ -184,6 +192,7 @@ async fn create_enterprise_test_database() -> Arc<Storage> {
                 unique: true,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "department_name".to_string(),
@@ -193,6 +202,7 @@ async fn create_enterprise_test_database() -> Arc<Storage> {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "budget".to_string(),
@@ -202,6 +212,7 @@ async fn create_enterprise_test_database() -> Arc<Storage> {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
         ],
     );
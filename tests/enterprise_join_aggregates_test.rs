@@ -22,6 +22,7 @@ async fn test_enterprise_multi_table_join_aggregates() {
             unique: true,
             default: None,
             references: None,
+            collation: None,
         },
         Column {
             name: "project_name".to_string(),
@@ -31,6 +32,7 @@ async fn test_enterprise_multi_table_join_aggregates() {
             unique: false,
             default: None,
             references: None,
+            collation: None,
         },
         Column {
             name: "department_id".to_string(),
@@ -40,6 +42,7 @@ async fn test_enterprise_multi_table_join_aggregates() {
             unique: false,
             default: None,
             references: None,
+            collation: None,
         },
         Column {
             name: "budget".to_string(),
@@ -49,6 +52,7 @@ async fn test_enterprise_multi_table_join_aggregates() {
             unique: false,
             default: None,
             references: None,
+            collation: None,
         },
     ];
 
@@ -96,6 +100,7 @@ async fn test_enterprise_multi_table_join_aggregates() {
             unique: true,
             default: None,
             references: None,
+            collation: None,
         },
         Column {
             name: "employee_name".to_string(),
@@ -105,6 +110,7 @@ async fn test_enterprise_multi_table_join_aggregates() {
             unique: false,
             default: None,
             references: None,
+            collation: None,
         },
         Column {
             name: "department_id".to_string(),
@@ -114,6 +120,7 @@ async fn test_enterprise_multi_table_join_aggregates() {
             unique: false,
             default: None,
             references: None,
+            collation: None,
         },
         Column {
             name: "salary".to_string(),
@@ -123,6 +130,7 @@ async fn test_enterprise_multi_table_join_aggregates() {
             unique: false,
             default: None,
             references: None,
+            collation: None,
         },
     ];
 
@@ -178,6 +186,7 @@ async fn test_enterprise_multi_table_join_aggregates() {
             unique: true,
             default: None,
             references: None,
+            collation: None,
         },
         Column {
             name: "department_name".to_string(),
@@ -187,6 +196,7 @@ async fn test_enterprise_multi_table_join_aggregates() {
             unique: false,
             default: None,
             references: None,
+            collation: None,
         },
         Column {
             name: "location".to_string(),
@@ -196,6 +206,7 @@ async fn test_enterprise_multi_table_join_aggregates() {
             unique: false,
             default: None,
             references: None,
+            collation: None,
         },
     ];
 
@@ -232,6 +243,7 @@ async fn test_enterprise_multi_table_join_aggregates() {
             unique: true,
             default: None,
             references: None,
+            collation: None,
         },
         Column {
             name: "project_id".to_string(),
@@ -241,6 +253,7 @@ async fn test_enterprise_multi_table_join_aggregates() {
             unique: false,
             default: None,
             references: None,
+            collation: None,
         },
         Column {
             name: "employee_id".to_string(),
@@ -250,6 +263,7 @@ async fn test_enterprise_multi_table_join_aggregates() {
             unique: false,
             default: None,
             references: None,
+            collation: None,
         },
         Column {
             name: "hours_allocated".to_string(),
@@ -259,6 +273,7 @@ async fn test_enterprise_multi_table_join_aggregates() {
             unique: false,
             default: None,
             references: None,
+            collation: None,
         },
     ];
 
@@ -428,6 +443,7 @@ async fn test_join_aggregate_performance() {
             unique: true,
             default: None,
             references: None,
+            collation: None,
         },
         Column {
             name: "category".to_string(),
@@ -437,6 +453,7 @@ async fn test_join_aggregate_performance() {
             unique: false,
             default: None,
             references: None,
+            collation: None,
         },
         Column {
             name: "value".to_string(),
@@ -446,6 +463,7 @@ async fn test_join_aggregate_performance() {
             unique: false,
             default: None,
             references: None,
+            collation: None,
         },
     ];
 
@@ -471,6 +489,7 @@ async fn test_join_aggregate_performance() {
             unique: true,
             default: None,
             references: None,
+            collation: None,
         },
         Column {
             name: "category_name".to_string(),
@@ -480,6 +499,7 @@ async fn test_join_aggregate_performance() {
             unique: false,
             default: None,
             references: None,
+            collation: None,
         },
     ];
 
@@ -27,6 +27,7 @@ async fn test_large_result_set_performance() {
                 unique: true,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "name".to_string(),
@@ -36,6 +37,7 @@ async fn test_large_result_set_performance() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "email".to_string(),
@@ -45,6 +47,7 @@ async fn test_large_result_set_performance() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "age".to_string(),
@@ -54,6 +57,7 @@ async fn test_large_result_set_performance() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "salary".to_string(),
@@ -63,6 +67,7 @@ async fn test_large_result_set_performance() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "department".to_string(),
@@ -72,6 +77,7 @@ async fn test_large_result_set_performance() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "created_at".to_string(),
@@ -81,6 +87,7 @@ async fn test_large_result_set_performance() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "is_active".to_string(),
@@ -90,6 +97,7 @@ async fn test_large_result_set_performance() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
         ],
     );
@@ -223,6 +231,7 @@ async fn test_memory_efficient_processing() {
                 unique: i == 0,
                 default: None,
                 references: None,
+                collation: None,
             })
             .collect(),
     );
@@ -281,6 +290,7 @@ async fn test_concurrent_large_queries() {
                 unique: true,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "data".to_string(),
@@ -290,6 +300,7 @@ async fn test_concurrent_large_queries() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
         ],
     );
@@ -367,6 +378,7 @@ async fn test_complex_join_performance() {
                 unique: true,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "username".to_string(),
@@ -376,6 +388,7 @@ async fn test_complex_join_performance() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "department_id".to_string(),
@@ -385,6 +398,7 @@ async fn test_complex_join_performance() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
         ],
     );
@@ -411,6 +425,7 @@ async fn test_complex_join_performance() {
                 unique: true,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "dept_name".to_string(),
@@ -420,6 +435,7 @@ async fn test_complex_join_performance() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
         ],
     );
@@ -445,6 +461,7 @@ async fn test_complex_join_performance() {
                 unique: true,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "user_id".to_string(),
@@ -454,6 +471,7 @@ async fn test_complex_join_performance() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "amount".to_string(),
@@ -463,6 +481,7 @@ async fn test_complex_join_performance() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
         ],
     );
@@ -547,6 +566,7 @@ async fn test_pagination_performance() {
                 unique: true,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "title".to_string(),
@@ -556,6 +576,7 @@ async fn test_pagination_performance() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "content".to_string(),
@@ -565,6 +586,7 @@ async fn test_pagination_performance() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "score".to_string(),
@@ -574,6 +596,7 @@ async fn test_pagination_performance() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
         ],
     );
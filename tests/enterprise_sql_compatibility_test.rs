@@ -26,6 +26,7 @@ fn create_test_database() -> Database {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "PROJECT_NAME".to_string(),
@@ -35,6 +36,7 @@ fn create_test_database() -> Database {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "DESCRIPTION".to_string(),
@@ -44,6 +46,7 @@ fn create_test_database() -> Database {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "STATUS_CODE".to_string(),
@@ -53,6 +56,7 @@ fn create_test_database() -> Database {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "ACTIVE_FLAG".to_string(),
@@ -62,6 +66,7 @@ fn create_test_database() -> Database {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "PROJECT_STRUCTURE".to_string(),
@@ -71,6 +76,7 @@ fn create_test_database() -> Database {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "START_DATE".to_string(),
@@ -80,6 +86,7 @@ fn create_test_database() -> Database {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "MANAGER_ID".to_string(),
@@ -89,6 +96,7 @@ fn create_test_database() -> Database {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "HIERARCHY_PARENT_ID".to_string(),
@@ -98,6 +106,7 @@ fn create_test_database() -> Database {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
         ],
     );
@@ -157,6 +166,7 @@ fn create_test_database() -> Database {
                 unique: false,
                 default: None,
                 references: Some(("PROJECT_MASTER".to_string(), "PROJECT_ID".to_string())),
+                collation: None,
             },
             Column {
                 name: "RESOURCE_ID".to_string(),
@@ -166,6 +176,7 @@ fn create_test_database() -> Database {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "ALLOCATION_PERCENT".to_string(),
@@ -175,6 +186,7 @@ fn create_test_database() -> Database {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "ALLOCATION_HOURS".to_string(),
@@ -184,6 +196,7 @@ fn create_test_database() -> Database {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "MONTH_PERIOD".to_string(),
@@ -193,6 +206,7 @@ fn create_test_database() -> Database {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
         ],
     );
@@ -240,6 +254,7 @@ fn create_test_database() -> Database {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "FIRST_NAME".to_string(),
@@ -249,6 +264,7 @@ fn create_test_database() -> Database {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "LAST_NAME".to_string(),
@@ -258,6 +274,7 @@ fn create_test_database() -> Database {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "EMAIL".to_string(),
@@ -267,6 +284,7 @@ fn create_test_database() -> Database {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "ROLE".to_string(),
@@ -276,6 +294,7 @@ fn create_test_database() -> Database {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
         ],
     );
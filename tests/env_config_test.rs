@@ -0,0 +1,68 @@
+use std::time::Duration;
+use tempfile::TempDir;
+use yamlbase::config::{Config, Protocol};
+
+const PRODUCTS: &str = r#"
+database:
+  name: "shop"
+
+tables:
+  products:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+"#;
+
+fn load(args: &[&str]) -> yamlbase::Result<Config> {
+    Config::try_load_from(["yamlbase"].iter().chain(args))
+}
+
+// The variables are set by one test, as the environment is shared by the threads of the
+// tests in this file
+#[test]
+fn test_options_from_environment() {
+    let directory = TempDir::new().unwrap();
+    let database = directory.path().join("shop.yaml");
+    std::fs::write(&database, PRODUCTS).unwrap();
+    let settings = directory.path().join("yamlbase.toml");
+    std::fs::write(&settings, "max_connections = 3\nidle_timeout = \"5min\"\n").unwrap();
+
+    // SAFETY: no other test in this binary reads or writes the environment
+    unsafe {
+        std::env::set_var("YAMLBASE_FILE", &database);
+        std::env::set_var("YAMLBASE_MYSQL_PORT", "13306");
+        std::env::set_var("YAMLBASE_STATEMENT_TIMEOUT", "5s");
+        std::env::set_var("YAMLBASE_PASSWORD", "secret");
+        std::env::set_var("YAMLBASE_MUTABLE", "true");
+        std::env::set_var("YAMLBASE_MAX_CONNECTIONS", "7");
+    }
+
+    let config = load(&[]).unwrap();
+    assert_eq!(config.files, vec![database.clone()]);
+    assert_eq!(config.listeners(), vec![(Protocol::Mysql, 13306)]);
+    assert_eq!(config.statement_timeout(), Duration::from_secs(5));
+    assert_eq!(config.password, "secret");
+    assert!(config.mutable);
+
+    // The command line overrides the environment, which overrides the settings file
+    let config = load(&[
+        "--statement-timeout",
+        "1s",
+        "--config",
+        settings.to_str().unwrap(),
+    ])
+    .unwrap();
+    assert_eq!(config.statement_timeout(), Duration::from_secs(1));
+    assert_eq!(config.max_connections(), 7);
+    assert_eq!(config.idle_timeout, Some(Duration::from_secs(300)));
+
+    // SAFETY: as above
+    unsafe {
+        std::env::set_var("YAMLBASE_MUTABLE", "false");
+        std::env::set_var("YAMLBASE_MAX_CONNECTIONS", "many");
+    }
+    let error = load(&[]).unwrap_err();
+    assert!(error.to_string().contains("many"), "{}", error);
+    let config = load(&["--max-connections", "2"]).unwrap();
+    assert!(!config.mutable);
+    assert_eq!(config.max_connections(), 2);
+}
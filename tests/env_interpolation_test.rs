@@ -0,0 +1,70 @@
+use yamlbase::database::Value;
+use yamlbase::yaml::parse_yaml_database;
+
+mod common;
+use common::yaml_file;
+
+const YAML: &str = r#"
+database:
+  name: "app_${ENV_TEST_STAGE:-dev}"
+  auth:
+    username: "${ENV_TEST_USER}"
+    password: "${ENV_TEST_PASSWORD:-secret}"
+
+tables:
+  settings:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+      url: "TEXT"
+      port: "INTEGER"
+      enabled: "BOOLEAN"
+    data:
+      - id: 1
+        url: "https://${ENV_TEST_HOST:-localhost}/api"
+        port: "${ENV_TEST_PORT:-8080}"
+        enabled: "${ENV_TEST_ENABLED:-true}"
+      - id: 2
+        url: "literal $${ENV_TEST_HOST}"
+        port: 443
+        enabled: false
+"#;
+
+#[tokio::test]
+async fn test_environment_variables() {
+    // SAFETY: the variables are only used by this test
+    unsafe {
+        std::env::set_var("ENV_TEST_USER", "ci");
+        std::env::set_var("ENV_TEST_HOST", "api.test");
+        std::env::set_var("ENV_TEST_PORT", "9000");
+        std::env::remove_var("ENV_TEST_STAGE");
+    }
+
+    let file = yaml_file(YAML);
+    let (db, auth) = parse_yaml_database(file.path()).await.unwrap();
+    assert_eq!(db.name, "app_dev");
+    let auth = auth.unwrap();
+    assert_eq!(
+        (auth.username.as_str(), auth.password.as_str()),
+        ("ci", "secret")
+    );
+
+    let settings = db.get_table("settings").unwrap();
+    assert_eq!(
+        settings.rows[0],
+        vec![
+            Value::Integer(1),
+            Value::Text("https://api.test/api".to_string()),
+            Value::Integer(9000),
+            Value::Boolean(true),
+        ]
+    );
+    assert_eq!(
+        settings.rows[1][1],
+        Value::Text("literal ${ENV_TEST_HOST}".to_string())
+    );
+
+    // A variable without a default must be set
+    let file = yaml_file(&YAML.replace("ENV_TEST_USER", "ENV_TEST_MISSING"));
+    let error = parse_yaml_database(file.path()).await.unwrap_err();
+    assert!(error.to_string().contains("ENV_TEST_MISSING"));
+}
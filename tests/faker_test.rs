@@ -0,0 +1,63 @@
+use yamlbase::yaml::parse_yaml_database;
+
+mod common;
+use common::yaml_file;
+
+const YAML: &str = r#"
+database:
+  name: "demo"
+
+tables:
+  customers:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+      name: "VARCHAR(100)"
+      email: "VARCHAR(100)"
+      address: "TEXT"
+      company: "TEXT"
+    generate:
+      count: 50
+      seed: 3
+      columns:
+        id: { sequence: {} }
+        name: { fake: name }
+        email: { fake: email }
+        address: { fake: street_address }
+        company: { fake: company }
+"#;
+
+#[cfg(not(feature = "faker"))]
+#[tokio::test]
+async fn test_fake_needs_feature() {
+    let file = yaml_file(YAML);
+    let error = parse_yaml_database(file.path()).await.unwrap_err();
+    assert!(error.to_string().contains("'faker' feature"), "{}", error);
+}
+
+#[cfg(feature = "faker")]
+#[tokio::test]
+async fn test_fake_values() {
+    use yamlbase::database::Value;
+
+    let file = yaml_file(YAML);
+    let (db, _) = parse_yaml_database(file.path()).await.unwrap();
+    let customers = db.get_table("customers").unwrap();
+    assert_eq!(customers.rows.len(), 50);
+    for row in &customers.rows {
+        for value in &row[1..] {
+            assert!(matches!(value, Value::Text(text) if !text.is_empty()));
+        }
+        assert!(matches!(&row[2], Value::Text(email) if email.contains('@')));
+    }
+
+    // The seed makes the same values on every load, and another seed other values
+    let (again, _) = parse_yaml_database(file.path()).await.unwrap();
+    assert_eq!(again.get_table("customers").unwrap().rows, customers.rows);
+    let file = yaml_file(&YAML.replace("seed: 3", "seed: 4"));
+    let (other, _) = parse_yaml_database(file.path()).await.unwrap();
+    assert_ne!(other.get_table("customers").unwrap().rows, customers.rows);
+
+    let file = yaml_file(&YAML.replace("fake: company", "fake: spaceship"));
+    let error = parse_yaml_database(file.path()).await.unwrap_err();
+    assert!(error.to_string().contains("'spaceship'"), "{}", error);
+}
@@ -0,0 +1,149 @@
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use std::sync::Arc;
+use yamlbase::database::{Storage, Value};
+use yamlbase::sql::{QueryExecutor, WriteMode, parse_sql};
+use yamlbase::yaml::parse_yaml_database;
+
+mod common;
+use common::yaml_file;
+
+const YAML: &str = r#"
+database:
+  name: "test_db"
+
+tables:
+  orders:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+      reference: "VARCHAR(20)"
+      status: "VARCHAR(10)"
+      amount: "DECIMAL(10,2)"
+      quantity: "INTEGER"
+      placed: "DATE"
+      region: "VARCHAR(2) NOT NULL DEFAULT EU"
+    generate:
+      count: 10000
+      seed: 7
+      columns:
+        id: { sequence: { start: 100, step: 2 } }
+        reference: { format: "ORD-{n}" }
+        status: { choice: [new, paid, shipped] }
+        amount: { range: { min: 1, max: 500 } }
+        quantity: { range: { min: 1, max: 5 } }
+        placed: { date_range: { start: "2024-01-01", end: "2024-03-31" } }
+  users:
+    columns:
+      id: "INTEGER PRIMARY KEY AUTO_INCREMENT"
+      name: "TEXT"
+      active: "BOOLEAN"
+    data:
+      - id: 1
+        name: "admin"
+        active: true
+    generate:
+      count: 3
+      columns:
+        name: { format: "user{n}" }
+        active: { value: false }
+"#;
+
+async fn load_error(yaml: &str) -> String {
+    let file = yaml_file(yaml);
+    parse_yaml_database(file.path())
+        .await
+        .unwrap_err()
+        .to_string()
+}
+
+#[tokio::test]
+async fn test_generate_rows() {
+    let file = yaml_file(YAML);
+    let (db, _) = parse_yaml_database(file.path()).await.unwrap();
+    let orders = db.get_table("orders").unwrap();
+
+    assert_eq!(orders.rows.len(), 10000);
+    assert_eq!(orders.rows[0][0], Value::Integer(100));
+    assert_eq!(orders.rows[9999][0], Value::Integer(100 + 2 * 9999));
+    assert_eq!(orders.rows[41][1], Value::Text("ORD-42".to_string()));
+
+    let (first, last) = (
+        NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+        NaiveDate::from_ymd_opt(2024, 3, 31).unwrap(),
+    );
+    for row in &orders.rows {
+        assert!(
+            matches!(&row[2], Value::Text(s) if ["new", "paid", "shipped"].contains(&s.as_str()))
+        );
+        assert!(
+            matches!(row[3], Value::Decimal(d) if d >= Decimal::ONE && d <= Decimal::new(500, 0) && d.scale() <= 2)
+        );
+        assert!(matches!(row[4], Value::Integer(1..=5)));
+        assert!(matches!(row[5], Value::Date(d) if d >= first && d <= last));
+        assert_eq!(row[6], Value::Text("EU".to_string()));
+    }
+
+    // The same seed makes the same rows
+    let (again, _) = parse_yaml_database(file.path()).await.unwrap();
+    assert_eq!(again.get_table("orders").unwrap().rows, orders.rows);
+}
+
+#[tokio::test]
+async fn test_generate_after_data() {
+    let file = yaml_file(YAML);
+    let (db, _) = parse_yaml_database(file.path()).await.unwrap();
+    let users = db.get_table("users").unwrap();
+    let names: Vec<(Value, Value)> = users
+        .rows
+        .iter()
+        .map(|row| (row[0].clone(), row[1].clone()))
+        .collect();
+    assert_eq!(
+        names,
+        vec![
+            (Value::Integer(1), Value::Text("admin".to_string())),
+            (Value::Integer(2), Value::Text("user1".to_string())),
+            (Value::Integer(3), Value::Text("user2".to_string())),
+            (Value::Integer(4), Value::Text("user3".to_string())),
+        ]
+    );
+
+    // Generated tables can be changed in memory but are not written back
+    let executor = QueryExecutor::new(Arc::new(Storage::new(db)))
+        .await
+        .unwrap();
+    let insert = parse_sql("INSERT INTO users (name) VALUES ('new') RETURNING id").unwrap();
+    let result = executor.execute(&insert[0]).await.unwrap();
+    assert_eq!(result.rows, vec![vec![Value::Integer(5)]]);
+
+    let (db, _) = parse_yaml_database(file.path()).await.unwrap();
+    let executor = QueryExecutor::new(Arc::new(Storage::new(db)))
+        .await
+        .unwrap()
+        .with_write_mode(WriteMode::Persistent(file.path().to_path_buf()));
+    let error = executor.execute(&insert[0]).await.unwrap_err();
+    assert!(error.to_string().contains("generated rows"));
+}
+
+#[tokio::test]
+async fn test_generate_errors() {
+    let error = load_error(&YAML.replace("        status: {", "        missing: {")).await;
+    assert!(error.contains("'missing'"), "{}", error);
+
+    let error = load_error(&YAML.replace(
+        "reference: { format: \"ORD-{n}\" }",
+        "reference: { range: { min: 1, max: 2 } }",
+    ))
+    .await;
+    assert!(error.contains("column 'reference'"), "{}", error);
+    assert!(error.contains("numeric"), "{}", error);
+
+    let error = load_error(&YAML.replace("min: 1, max: 500", "min: 500, max: 1")).await;
+    assert!(error.contains("at most"), "{}", error);
+
+    assert!(
+        load_error(&YAML.replace("choice: [new, paid, shipped]", "pick: [new]"))
+            .await
+            .contains("pick")
+    );
+}
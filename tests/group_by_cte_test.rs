@@ -22,6 +22,7 @@ async fn test_group_by_with_cte_columns() {
                 unique: true,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "customer_id".to_string(),
@@ -31,6 +32,7 @@ async fn test_group_by_with_cte_columns() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "product".to_string(),
@@ -40,6 +42,7 @@ async fn test_group_by_with_cte_columns() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "amount".to_string(),
@@ -49,6 +52,7 @@ async fn test_group_by_with_cte_columns() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "order_date".to_string(),
@@ -58,6 +62,7 @@ async fn test_group_by_with_cte_columns() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
         ],
     );
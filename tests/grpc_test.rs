@@ -0,0 +1,256 @@
+use tonic::metadata::MetadataValue;
+use tonic::transport::Channel;
+use tonic::{Code, Request};
+use yamlbase::protocol::grpc::proto::value::Kind;
+use yamlbase::protocol::grpc::proto::yamlbase_client::YamlbaseClient;
+use yamlbase::protocol::grpc::proto::{
+    DataType, ExecuteQueryRequest, ForeignKey, GetSchemaRequest, ListTablesRequest,
+};
+
+mod common;
+use common::{TestServer, yaml_file};
+
+/// "admin:password" in basic authentication
+const ADMIN: &str = "Basic YWRtaW46cGFzc3dvcmQ=";
+
+const SHOP: &str = r#"
+database:
+  name: "shop"
+  auth:
+    username: "admin"
+    password: "password"
+    users:
+      - username: "reader"
+        password: "reader"
+        grants:
+          read: ["products"]
+
+tables:
+  products:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+      name: "VARCHAR(100) NOT NULL"
+      price: "DECIMAL(10, 2)"
+      added: "DATE"
+    data:
+      - id: 1
+        name: "Widget"
+        price: 9.99
+        added: "2024-01-15"
+      - id: 2
+        name: "Gadget"
+        price: 24.5
+        added: null
+  orders:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+      product_id: "INTEGER REFERENCES products(id)"
+      quantity: "INTEGER DEFAULT 1"
+    data:
+      - id: 1
+        product_id: 2
+        quantity: 3
+"#;
+
+async fn connect(server: &TestServer) -> YamlbaseClient<Channel> {
+    YamlbaseClient::connect(format!("http://127.0.0.1:{}", server.port()))
+        .await
+        .unwrap()
+}
+
+/// A request with the credentials of `authorization`
+fn request<T>(message: T, authorization: &str) -> Request<T> {
+    let mut request = Request::new(message);
+    let value: MetadataValue<_> = authorization.parse().unwrap();
+    request.metadata_mut().insert("authorization", value);
+    request
+}
+
+fn query(sql: &str) -> ExecuteQueryRequest {
+    ExecuteQueryRequest {
+        sql: sql.to_string(),
+        database: String::new(),
+    }
+}
+
+#[tokio::test]
+async fn test_grpc_execute_query() {
+    let file = yaml_file(SHOP);
+    let server = TestServer::start_grpc(file.path().to_str().unwrap());
+    let mut client = connect(&server).await;
+
+    let response = client
+        .execute_query(request(
+            query("SELECT id, name, price, added FROM products ORDER BY id"),
+            ADMIN,
+        ))
+        .await
+        .unwrap()
+        .into_inner();
+    let columns: Vec<(&str, DataType)> = response
+        .columns
+        .iter()
+        .map(|column| (column.name.as_str(), column.r#type()))
+        .collect();
+    assert_eq!(
+        columns,
+        [
+            ("id", DataType::Integer),
+            ("name", DataType::Varchar),
+            ("price", DataType::Decimal),
+            ("added", DataType::Date),
+        ]
+    );
+    let rows: Vec<Vec<Kind>> = response
+        .rows
+        .iter()
+        .map(|row| {
+            row.values
+                .iter()
+                .map(|value| value.kind.clone().unwrap())
+                .collect()
+        })
+        .collect();
+    assert_eq!(
+        rows,
+        [
+            vec![
+                Kind::Integer(1),
+                Kind::Text("Widget".to_string()),
+                Kind::Decimal("9.99".to_string()),
+                Kind::Date("2024-01-15".to_string()),
+            ],
+            vec![
+                Kind::Integer(2),
+                Kind::Text("Gadget".to_string()),
+                Kind::Decimal("24.5".to_string()),
+                Kind::Null(true),
+            ],
+        ]
+    );
+    assert_eq!(response.affected_rows, None);
+
+    let response = client
+        .execute_query(request(
+            query("UPDATE products SET price = 10 WHERE id = 1"),
+            ADMIN,
+        ))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(response.affected_rows, Some(1));
+    assert!(response.columns.is_empty());
+
+    let error = client
+        .execute_query(request(query("SELECT * FROM missing"), ADMIN))
+        .await
+        .unwrap_err();
+    assert_eq!(error.code(), Code::InvalidArgument);
+    assert!(error.message().contains("missing"));
+
+    // "admin:wrong"
+    let error = client
+        .execute_query(request(query("SELECT 1"), "Basic YWRtaW46d3Jvbmc="))
+        .await
+        .unwrap_err();
+    assert_eq!(error.code(), Code::Unauthenticated);
+}
+
+#[tokio::test]
+async fn test_grpc_list_tables_and_schema() {
+    let file = yaml_file(SHOP);
+    let server = TestServer::start_grpc(file.path().to_str().unwrap());
+    let mut client = connect(&server).await;
+
+    let tables = client
+        .list_tables(request(ListTablesRequest::default(), ADMIN))
+        .await
+        .unwrap()
+        .into_inner()
+        .tables;
+    assert_eq!(tables, ["products", "orders"]);
+
+    let schema = client
+        .get_schema(request(
+            GetSchemaRequest {
+                database: "shop".to_string(),
+                tables: vec!["orders".to_string()],
+            },
+            ADMIN,
+        ))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(schema.database, "shop");
+    assert_eq!(schema.tables.len(), 1);
+    let columns = &schema.tables[0].columns;
+    assert_eq!(columns[0].name, "id");
+    assert!(columns[0].primary_key);
+    assert_eq!(
+        columns[1].references,
+        Some(ForeignKey {
+            table: "products".to_string(),
+            column: "id".to_string(),
+        })
+    );
+    assert_eq!(columns[2].default.as_deref(), Some("1"));
+
+    let schema = client
+        .get_schema(request(GetSchemaRequest::default(), ADMIN))
+        .await
+        .unwrap()
+        .into_inner();
+    let price = &schema.tables[0].columns[2];
+    assert_eq!(price.r#type(), DataType::Decimal);
+    assert_eq!((price.precision, price.scale), (10, 2));
+    let name = &schema.tables[0].columns[1];
+    assert_eq!(name.length, 100);
+    assert!(!name.nullable);
+
+    let error = client
+        .get_schema(request(
+            GetSchemaRequest {
+                database: String::new(),
+                tables: vec!["missing".to_string()],
+            },
+            ADMIN,
+        ))
+        .await
+        .unwrap_err();
+    assert_eq!(error.code(), Code::NotFound);
+}
+
+#[tokio::test]
+async fn test_grpc_grants() {
+    let file = yaml_file(SHOP);
+    let server = TestServer::start_grpc(file.path().to_str().unwrap());
+    let mut client = connect(&server).await;
+    // "reader:reader"
+    let reader = "Basic cmVhZGVyOnJlYWRlcg==";
+
+    let tables = client
+        .list_tables(request(ListTablesRequest::default(), reader))
+        .await
+        .unwrap()
+        .into_inner()
+        .tables;
+    assert_eq!(tables, ["products"]);
+
+    let error = client
+        .execute_query(request(query("SELECT * FROM orders"), reader))
+        .await
+        .unwrap_err();
+    assert_eq!(error.code(), Code::PermissionDenied);
+
+    let error = client
+        .get_schema(request(
+            GetSchemaRequest {
+                database: String::new(),
+                tables: vec!["orders".to_string()],
+            },
+            reader,
+        ))
+        .await
+        .unwrap_err();
+    assert_eq!(error.code(), Code::NotFound);
+}
@@ -0,0 +1,106 @@
+use argon2::Argon2;
+use argon2::password_hash::{PasswordHasher, SaltString};
+use mysql::prelude::Queryable;
+use mysql::{Conn, OptsBuilder};
+use yamlbase::protocol::password::{Password, check_hash};
+
+mod common;
+use common::{TestServer, connect_postgres, yaml_file};
+
+/// The YAML of the database, whose passwords are hashed
+fn yaml() -> String {
+    let bcrypt_hash = bcrypt::hash("secret", 4).unwrap();
+    let salt = SaltString::encode_b64(b"yamlbase salt").unwrap();
+    let argon2_hash = Argon2::default()
+        .hash_password(b"read", &salt)
+        .unwrap()
+        .to_string();
+    format!(
+        r#"
+database:
+  name: "shop"
+  auth:
+    username: "admin"
+    password_hash: '{}'
+    users:
+      - username: "reader"
+        password_hash: '{}'
+
+tables:
+  products:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+    data:
+      - id: 1
+"#,
+        bcrypt_hash, argon2_hash
+    )
+}
+
+#[test]
+fn test_verify_hashes() {
+    let hash = bcrypt::hash("secret", 4).unwrap();
+    assert!(Password::Hash(&hash).verify("secret"));
+    assert!(!Password::Hash(&hash).verify("wrong"));
+    assert!(Password::new("secret", None).verify("secret"));
+    check_hash(&hash).unwrap();
+
+    let error = check_hash("secret").unwrap_err().to_string();
+    assert!(
+        error.contains("is not a bcrypt or argon2 hash"),
+        "{}",
+        error
+    );
+    assert!(check_hash("$argon2id$v=19$broken").is_err());
+}
+
+#[tokio::test]
+async fn test_postgres_hashed_password() {
+    let file = yaml_file(&yaml());
+    let server = TestServer::start_postgres(file.path().to_str().unwrap());
+
+    let admin = connect_postgres(server.port(), "admin", "secret", "shop")
+        .await
+        .unwrap();
+    admin.simple_query("SELECT * FROM products").await.unwrap();
+    let reader = connect_postgres(server.port(), "reader", "read", "shop")
+        .await
+        .unwrap();
+    reader.simple_query("SELECT * FROM products").await.unwrap();
+
+    assert!(
+        connect_postgres(server.port(), "admin", "wrong", "shop")
+            .await
+            .is_err()
+    );
+    assert!(
+        connect_postgres(server.port(), "reader", "secret", "shop")
+            .await
+            .is_err()
+    );
+}
+
+#[test]
+fn test_mysql_hashed_password() {
+    let file = yaml_file(&yaml());
+    let server = TestServer::start_mysql(file.path().to_str().unwrap());
+
+    let opts = |user: &str, password: &str| {
+        OptsBuilder::new()
+            .ip_or_hostname(Some("127.0.0.1"))
+            .tcp_port(server.port())
+            .user(Some(user))
+            .pass(Some(password))
+            .db_name(Some("shop"))
+            .enable_cleartext_plugin(true)
+    };
+
+    let mut admin = Conn::new(opts("admin", "secret")).unwrap();
+    let ids: Vec<i64> = admin.query("SELECT id FROM products").unwrap();
+    assert_eq!(ids, vec![1]);
+    let mut reader = Conn::new(opts("reader", "read")).unwrap();
+    let ids: Vec<i64> = reader.query("SELECT id FROM products").unwrap();
+    assert_eq!(ids, vec![1]);
+
+    assert!(Conn::new(opts("admin", "wrong")).is_err());
+}
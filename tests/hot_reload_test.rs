@@ -0,0 +1,231 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio_postgres::SimpleQueryMessage;
+use yamlbase::database::{Storage, TableChanges, Value};
+use yamlbase::yaml::parse_yaml_database;
+
+mod common;
+use common::{TestServer, connect_postgres, yaml_file};
+
+const YAML: &str = r#"
+database:
+  name: "shop"
+
+tables:
+  users:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+      name: "TEXT"
+    data:
+      - id: 1
+        name: "ann"
+  products:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+    data:
+      - id: 10
+  orders:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+"#;
+
+#[tokio::test]
+async fn test_reload_keeps_unchanged_tables() {
+    let file = yaml_file(YAML);
+    let (database, _) = parse_yaml_database(file.path()).await.unwrap();
+    let storage = Storage::new(database);
+    storage.rebuild_indexes().await;
+    let products = Arc::clone(&storage.database().read().await.tables["products"]);
+
+    let (same, _) = parse_yaml_database(file.path()).await.unwrap();
+    let changes = storage.reload(same).await;
+    assert!(changes.is_empty());
+    assert_eq!(changes.to_string(), "no tables changed");
+
+    let edited = YAML
+        .replace("name: \"ann\"", "name: \"bob\"")
+        .replace("  orders:", "  invoices:");
+    let file = yaml_file(&edited);
+    let (edited, _) = parse_yaml_database(file.path()).await.unwrap();
+    let changes = storage.reload(edited).await;
+    assert_eq!(
+        changes,
+        TableChanges {
+            changed: vec!["users".to_string()],
+            added: vec!["invoices".to_string()],
+            removed: vec!["orders".to_string()],
+        }
+    );
+    assert_eq!(
+        changes.to_string(),
+        "tables changed users; added invoices; removed orders"
+    );
+
+    let db = storage.database();
+    assert!(Arc::ptr_eq(&db.read().await.tables["products"], &products));
+    assert_eq!(
+        storage
+            .find_by_primary_key("users", &Value::Integer(1))
+            .await,
+        Some(vec![Value::Integer(1), Value::Text("bob".to_string())])
+    );
+    assert!(
+        storage
+            .find_by_primary_key("orders", &Value::Integer(1))
+            .await
+            .is_none()
+    );
+}
+
+const ORDERS: &str = r#"
+database:
+  name: "shop"
+  auth:
+    username: "yamlbase"
+    password: "password"
+
+tables:
+  users:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+      name: "TEXT"
+    data:
+      - id: 1
+        name: "ann"
+  orders:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+      user_id: "INTEGER REFERENCES users(id)"
+    data:
+      - id: 1
+        user_id: 1
+"#;
+
+#[tokio::test]
+async fn test_reload_rejects_broken_foreign_key() {
+    let file = yaml_file(ORDERS);
+    let server =
+        TestServer::start_postgres_with_args(file.path().to_str().unwrap(), &["--hot-reload"]);
+    let client = connect_postgres(server.port(), "yamlbase", "password", "shop")
+        .await
+        .unwrap();
+
+    let status = || async {
+        let messages = client.simple_query("SHOW RELOAD STATUS").await.unwrap();
+        match &messages[1] {
+            SimpleQueryMessage::Row(row) => (
+                row.get(0).unwrap().to_string(),
+                row.get(2).unwrap().to_string(),
+            ),
+            other => panic!("expected a row, got {:?}", other),
+        }
+    };
+    assert_eq!(status().await.0, "loaded");
+
+    // The user is renamed, but the new order refers to a user that does not exist
+    let broken = ORDERS.replace("name: \"ann\"", "name: \"bob\"").replace(
+        "        user_id: 1\n",
+        "        user_id: 1\n      - id: 2\n        user_id: 9\n",
+    );
+    std::fs::write(file.path(), broken).unwrap();
+
+    let start = Instant::now();
+    while status().await.0 == "loaded" {
+        assert!(
+            start.elapsed() < Duration::from_secs(10),
+            "the change was not reloaded"
+        );
+        tokio::time::sleep(Duration::from_millis(200)).await;
+    }
+    let (outcome, detail) = status().await;
+    assert_eq!(outcome, "rejected, keeping previous snapshot");
+    assert!(
+        detail.contains("user_id 9 is not in users.id"),
+        "{}",
+        detail
+    );
+
+    assert_eq!(user_name(&client).await, "ann");
+}
+
+async fn user_name(client: &tokio_postgres::Client) -> String {
+    let messages = client.simple_query("SELECT name FROM users").await.unwrap();
+    match &messages[1] {
+        SimpleQueryMessage::Row(row) => row.get(0).unwrap().to_string(),
+        other => panic!("expected a row, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_reload_database_statement() {
+    let file = yaml_file(ORDERS);
+    let server = TestServer::start_postgres(file.path().to_str().unwrap());
+    let client = connect_postgres(server.port(), "yamlbase", "password", "shop")
+        .await
+        .unwrap();
+
+    // Without --hot-reload the edit is only loaded when asked for
+    std::fs::write(file.path(), ORDERS.replace("\"ann\"", "\"bob\"")).unwrap();
+    client.simple_query("RELOAD DATABASE").await.unwrap();
+    assert_eq!(user_name(&client).await, "bob");
+
+    let broken = ORDERS.replace("user_id: 1", "user_id: 9");
+    std::fs::write(file.path(), broken).unwrap();
+    let error = client.simple_query("RELOAD DATABASE").await.unwrap_err();
+    assert!(
+        format!("{:?}", error).contains("user_id 9 is not in users.id"),
+        "{:?}",
+        error
+    );
+    assert_eq!(user_name(&client).await, "bob");
+}
+
+#[tokio::test]
+async fn test_reload_keeps_existing_key_problems() {
+    // The file already has an order referring to a missing user
+    let dangling = ORDERS.replace(
+        "        user_id: 1\n",
+        "        user_id: 1\n      - id: 2\n        user_id: 9\n",
+    );
+    let file = yaml_file(&dangling);
+    let server = TestServer::start_postgres(file.path().to_str().unwrap());
+    let client = connect_postgres(server.port(), "yamlbase", "password", "shop")
+        .await
+        .unwrap();
+
+    // A row added above it moves the problem to another row, but it is not a new one
+    let edited = dangling.replace("\"ann\"", "\"bob\"").replace(
+        "      - id: 1\n        user_id: 1\n",
+        "      - id: 1\n        user_id: 1\n      - id: 3\n        user_id: 1\n",
+    );
+    std::fs::write(file.path(), edited).unwrap();
+    client.simple_query("RELOAD DATABASE").await.unwrap();
+    assert_eq!(user_name(&client).await, "bob");
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_sighup_reloads() {
+    let file = yaml_file(ORDERS);
+    let server = TestServer::start_postgres(file.path().to_str().unwrap());
+    let client = connect_postgres(server.port(), "yamlbase", "password", "shop")
+        .await
+        .unwrap();
+
+    std::fs::write(file.path(), ORDERS.replace("\"ann\"", "\"cy\"")).unwrap();
+    let pid = server.pid().unwrap().to_string();
+    let killed = std::process::Command::new("kill")
+        .args(["-HUP", &pid])
+        .status()
+        .unwrap();
+    assert!(killed.success());
+
+    let start = Instant::now();
+    while user_name(&client).await != "cy" {
+        assert!(
+            start.elapsed() < Duration::from_secs(10),
+            "SIGHUP did not reload the database"
+        );
+        tokio::time::sleep(Duration::from_millis(200)).await;
+    }
+}
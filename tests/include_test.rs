@@ -0,0 +1,192 @@
+use std::path::Path;
+use std::sync::Arc;
+use tempfile::TempDir;
+use yamlbase::database::{Storage, Value};
+use yamlbase::sql::{QueryExecutor, WriteMode, parse_sql};
+use yamlbase::yaml::parse_yaml_database;
+
+const MAIN: &str = r#"
+database:
+  name: "shop"
+
+include:
+  - catalog/products.yaml
+  - orders.yaml
+
+tables:
+  customers:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+      name: "TEXT"
+    data:
+      - id: 1
+        name: "ann"
+"#;
+
+const PRODUCTS: &str = r#"
+include:
+  - categories.yaml
+
+tables:
+  products:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+      category: "VARCHAR(20)"
+    data:
+      - id: 10
+        category: "tools"
+"#;
+
+const CATEGORIES: &str = r#"
+tables:
+  categories:
+    columns:
+      name: "VARCHAR(20) PRIMARY KEY"
+    data:
+      - name: "tools"
+"#;
+
+const ORDERS: &str = r#"
+# Orders of the last week
+tables:
+  orders:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+      customer_id: "INTEGER"
+      product_id: "INTEGER"
+    data:
+      - id: 100
+        customer_id: 1
+        product_id: 10
+
+materialized_views:
+  order_count:
+    query: "SELECT COUNT(*) AS n FROM orders"
+"#;
+
+fn write_files(files: &[(&str, &str)]) -> TempDir {
+    let dir = TempDir::new().unwrap();
+    for (name, content) in files {
+        let path = dir.path().join(name);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+    dir
+}
+
+fn shop() -> TempDir {
+    write_files(&[
+        ("main.yaml", MAIN),
+        ("catalog/products.yaml", PRODUCTS),
+        ("catalog/categories.yaml", CATEGORIES),
+        ("orders.yaml", ORDERS),
+    ])
+}
+
+async fn load_error(dir: &Path) -> String {
+    parse_yaml_database(&dir.join("main.yaml"))
+        .await
+        .unwrap_err()
+        .to_string()
+}
+
+#[tokio::test]
+async fn test_include_files() {
+    let dir = shop();
+    let (db, _) = parse_yaml_database(&dir.path().join("main.yaml"))
+        .await
+        .unwrap();
+
+    let names: Vec<&str> = db.tables.keys().map(|name| name.as_str()).collect();
+    assert_eq!(
+        names,
+        vec![
+            "customers",
+            "products",
+            "categories",
+            "orders",
+            "order_count"
+        ]
+    );
+    assert_eq!(
+        db.table_files["categories"],
+        dir.path().join("catalog/categories.yaml")
+    );
+    assert_eq!(
+        db.get_table("order_count").unwrap().rows,
+        vec![vec![Value::Integer(1)]]
+    );
+}
+
+#[tokio::test]
+async fn test_include_conflicts() {
+    // A table may be defined only once across all files
+    let clash = ORDERS.replace("  orders:", "  customers:");
+    let dir = write_files(&[
+        ("main.yaml", MAIN),
+        ("catalog/products.yaml", PRODUCTS),
+        ("catalog/categories.yaml", CATEGORIES),
+        ("orders.yaml", &clash),
+    ]);
+    let error = load_error(dir.path()).await;
+    assert!(
+        error.contains("'customers' is defined in both"),
+        "{}",
+        error
+    );
+    assert!(error.contains("orders.yaml"), "{}", error);
+
+    // Including a file twice, or in a cycle, is an error
+    let cycle = PRODUCTS.replace("categories.yaml", "../main.yaml");
+    let dir = write_files(&[
+        ("main.yaml", MAIN),
+        ("catalog/products.yaml", &cycle),
+        ("orders.yaml", ORDERS),
+    ]);
+    assert!(load_error(dir.path()).await.contains("more than once"));
+
+    // Included files hold tables and views, not database settings
+    let dir = write_files(&[
+        ("main.yaml", MAIN),
+        ("catalog/products.yaml", "database:\n  name: other\n"),
+        ("orders.yaml", ORDERS),
+    ]);
+    assert!(load_error(dir.path()).await.contains("products.yaml"));
+
+    let dir = write_files(&[("main.yaml", MAIN)]);
+    assert!(
+        load_error(dir.path())
+            .await
+            .contains("Cannot read included file")
+    );
+}
+
+#[tokio::test]
+async fn test_persist_to_included_file() {
+    let dir = shop();
+    let main_path = dir.path().join("main.yaml");
+    let (db, _) = parse_yaml_database(&main_path).await.unwrap();
+    let executor = QueryExecutor::new(Arc::new(Storage::new(db)))
+        .await
+        .unwrap()
+        .with_write_mode(WriteMode::Persistent(main_path.clone()));
+
+    for sql in [
+        "INSERT INTO orders VALUES (101, 1, 10)",
+        "UPDATE customers SET name = 'bob'",
+    ] {
+        let statements = parse_sql(sql).unwrap();
+        executor.execute(&statements[0]).await.unwrap();
+    }
+
+    // Each table was written to the file defining it
+    let orders = std::fs::read_to_string(dir.path().join("orders.yaml")).unwrap();
+    assert!(orders.starts_with("\n# Orders of the last week"));
+    assert!(orders.contains("id: 101"));
+    let main = std::fs::read_to_string(&main_path).unwrap();
+    assert!(main.contains("name: \"bob\""));
+    assert!(!main.contains("101"));
+
+    let (reloaded, _) = parse_yaml_database(&main_path).await.unwrap();
+    assert_eq!(reloaded.get_table("orders").unwrap().rows.len(), 2);
+}
@@ -19,6 +19,7 @@ async fn test_primary_key_index_performance() {
             unique: true,
             default: None,
             references: None,
+            collation: None,
         },
         Column {
             name: "data".to_string(),
@@ -28,6 +29,7 @@ async fn test_primary_key_index_performance() {
             unique: false,
             default: None,
             references: None,
+            collation: None,
         },
     ];
 
@@ -104,6 +106,7 @@ async fn test_index_with_different_types() {
             unique: true,
             default: None,
             references: None,
+            collation: None,
         },
         Column {
             name: "email".to_string(),
@@ -113,6 +116,7 @@ async fn test_index_with_different_types() {
             unique: false,
             default: None,
             references: None,
+            collation: None,
         },
     ];
 
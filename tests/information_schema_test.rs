@@ -0,0 +1,279 @@
+use mysql::prelude::Queryable;
+
+mod common;
+use common::{TestServer, connect_mysql, connect_postgres, yaml_file};
+
+const YAML: &str = r#"
+database:
+  name: "shop"
+  auth:
+    username: "admin"
+    password: "secret"
+    users:
+      - username: "reader"
+        password: "read"
+        grants:
+          read: [products]
+
+tables:
+  products:
+    columns:
+      id: "INTEGER PRIMARY KEY AUTO_INCREMENT"
+      sku: "VARCHAR(20) UNIQUE NOT NULL"
+      price: "DECIMAL(10,2)"
+    data:
+      - id: 1
+        sku: "PEN-1"
+        price: 1.5
+  orders:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+      product_id: "INTEGER REFERENCES products(id)"
+      placed_at: "TIMESTAMP"
+    data:
+      - id: 1
+        product_id: 1
+        placed_at: "2024-05-01 12:00:00"
+  sales.totals:
+    columns:
+      day: "DATE PRIMARY KEY"
+      amount: "DECIMAL(12,2)"
+    data: []
+"#;
+
+async fn rows(client: &tokio_postgres::Client, sql: &str) -> Vec<Vec<Option<String>>> {
+    client
+        .simple_query(sql)
+        .await
+        .unwrap()
+        .into_iter()
+        .filter_map(|message| match message {
+            tokio_postgres::SimpleQueryMessage::Row(row) => Some(
+                (0..row.len())
+                    .map(|index| row.get(index).map(str::to_string))
+                    .collect(),
+            ),
+            _ => None,
+        })
+        .collect()
+}
+
+fn row(values: &[Option<&str>]) -> Vec<Option<String>> {
+    values
+        .iter()
+        .map(|value| value.map(str::to_string))
+        .collect()
+}
+
+#[tokio::test]
+async fn test_postgres_information_schema() {
+    let file = yaml_file(YAML);
+    let server = TestServer::start_postgres(file.path().to_str().unwrap());
+    let client = connect_postgres(server.port(), "admin", "secret", "shop")
+        .await
+        .unwrap();
+
+    assert_eq!(
+        rows(
+            &client,
+            "SELECT table_catalog, table_schema, table_name, table_type \
+             FROM information_schema.tables ORDER BY table_schema, table_name"
+        )
+        .await,
+        vec![
+            row(&[
+                Some("shop"),
+                Some("public"),
+                Some("orders"),
+                Some("BASE TABLE")
+            ]),
+            row(&[
+                Some("shop"),
+                Some("public"),
+                Some("products"),
+                Some("BASE TABLE")
+            ]),
+            row(&[
+                Some("shop"),
+                Some("sales"),
+                Some("totals"),
+                Some("BASE TABLE")
+            ]),
+        ]
+    );
+
+    assert_eq!(
+        rows(
+            &client,
+            "SELECT column_name, column_default, is_nullable, data_type, udt_name, \
+             character_maximum_length, numeric_precision, numeric_scale \
+             FROM information_schema.columns \
+             WHERE table_schema = 'public' AND table_name = 'products' \
+             ORDER BY ordinal_position"
+        )
+        .await,
+        vec![
+            row(&[
+                Some("id"),
+                Some("nextval('products_id_seq'::regclass)"),
+                Some("NO"),
+                Some("integer"),
+                Some("int4"),
+                None,
+                Some("32"),
+                Some("0"),
+            ]),
+            row(&[
+                Some("sku"),
+                None,
+                Some("NO"),
+                Some("character varying"),
+                Some("varchar"),
+                Some("20"),
+                None,
+                None,
+            ]),
+            row(&[
+                Some("price"),
+                None,
+                Some("YES"),
+                Some("numeric"),
+                Some("numeric"),
+                None,
+                Some("10"),
+                Some("2"),
+            ]),
+        ]
+    );
+
+    // Through the extended protocol, and joined with the tables
+    let rows = client
+        .query(
+            "SELECT k.constraint_name, k.column_name, k.position_in_unique_constraint \
+             FROM information_schema.key_column_usage k \
+             JOIN information_schema.tables t ON t.table_name = k.table_name \
+             WHERE t.table_schema = $1 \
+             ORDER BY k.constraint_name",
+            &[&"public"],
+        )
+        .await
+        .unwrap();
+    let constraints: Vec<(String, String, Option<String>)> = rows
+        .iter()
+        .map(|row| (row.get(0), row.get(1), row.get(2)))
+        .collect();
+    assert_eq!(
+        constraints,
+        vec![
+            ("orders_pkey".to_string(), "id".to_string(), None),
+            (
+                "orders_product_id_fkey".to_string(),
+                "product_id".to_string(),
+                Some("1".to_string())
+            ),
+            ("products_pkey".to_string(), "id".to_string(), None),
+            ("products_sku_key".to_string(), "sku".to_string(), None),
+        ]
+    );
+
+    // Tables the connection created are listed to it alone
+    client
+        .batch_execute("CREATE TEMP TABLE scratch (note TEXT)")
+        .await
+        .unwrap();
+    let query = "SELECT table_type FROM information_schema.tables WHERE table_name = 'scratch'";
+    assert_eq!(
+        self::rows(&client, query).await,
+        vec![row(&[Some("LOCAL TEMPORARY")])]
+    );
+    let other = connect_postgres(server.port(), "admin", "secret", "shop")
+        .await
+        .unwrap();
+    assert!(self::rows(&other, query).await.is_empty());
+
+    // A user with grants only sees the tables it may read
+    let reader = connect_postgres(server.port(), "reader", "read", "shop")
+        .await
+        .unwrap();
+    assert_eq!(
+        self::rows(&reader, "SELECT table_name FROM information_schema.tables").await,
+        vec![row(&[Some("products")])]
+    );
+}
+
+#[test]
+fn test_mysql_information_schema() {
+    let file = yaml_file(YAML);
+    let server = TestServer::start_mysql(file.path().to_str().unwrap());
+    let mut conn = connect_mysql(server.port(), "admin", "secret", "shop").unwrap();
+
+    let tables: Vec<(String, String, String)> = conn
+        .query(
+            "SELECT TABLE_CATALOG, TABLE_SCHEMA, TABLE_NAME FROM information_schema.TABLES \
+             WHERE TABLE_SCHEMA = DATABASE() ORDER BY TABLE_NAME",
+        )
+        .unwrap();
+    assert_eq!(
+        tables,
+        vec![
+            ("def".to_string(), "shop".to_string(), "orders".to_string()),
+            (
+                "def".to_string(),
+                "shop".to_string(),
+                "products".to_string()
+            ),
+        ]
+    );
+
+    let columns: Vec<(String, String, String, String, String)> = conn
+        .query(
+            "SELECT COLUMN_NAME, DATA_TYPE, COLUMN_TYPE, COLUMN_KEY, EXTRA \
+             FROM information_schema.COLUMNS \
+             WHERE TABLE_SCHEMA = 'shop' AND TABLE_NAME = 'products' \
+             ORDER BY ORDINAL_POSITION",
+        )
+        .unwrap();
+    assert_eq!(
+        columns,
+        vec![
+            (
+                "id".to_string(),
+                "int".to_string(),
+                "int".to_string(),
+                "PRI".to_string(),
+                "auto_increment".to_string()
+            ),
+            (
+                "sku".to_string(),
+                "varchar".to_string(),
+                "varchar(20)".to_string(),
+                "UNI".to_string(),
+                String::new()
+            ),
+            (
+                "price".to_string(),
+                "decimal".to_string(),
+                "decimal(10,2)".to_string(),
+                String::new(),
+                String::new()
+            ),
+        ]
+    );
+
+    let foreign_keys: Vec<(String, String, String, String)> = conn
+        .query(
+            "SELECT CONSTRAINT_NAME, COLUMN_NAME, REFERENCED_TABLE_NAME, REFERENCED_COLUMN_NAME \
+             FROM information_schema.KEY_COLUMN_USAGE \
+             WHERE TABLE_SCHEMA = DATABASE() AND REFERENCED_TABLE_NAME IS NOT NULL",
+        )
+        .unwrap();
+    assert_eq!(
+        foreign_keys,
+        vec![(
+            "orders_ibfk_1".to_string(),
+            "product_id".to_string(),
+            "products".to_string(),
+            "id".to_string()
+        )]
+    );
+}
@@ -0,0 +1,279 @@
+use std::sync::Arc;
+use yamlbase::database::{Column, Database, Storage, Value};
+use yamlbase::sql::{QueryExecutor, SqlDialect, parse_sql, parse_sql_with_dialect};
+use yamlbase::yaml::parse_yaml_database;
+
+mod common;
+use common::{TestServer, connect_postgres, query, yaml_file};
+
+const YAML: &str = r#"
+database:
+  name: "test_db"
+
+tables:
+  users:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+      email: "VARCHAR(20) UNIQUE NOT NULL"
+      active: "BOOLEAN DEFAULT true"
+      score: "DECIMAL(5,2)"
+      joined: "DATE"
+    data:
+      - id: 1
+        email: "ann@example.com"
+        active: true
+        score: 10.5
+        joined: "2024-01-01"
+"#;
+
+async fn create_storage() -> Arc<Storage> {
+    let file = yaml_file(YAML);
+    let (db, _) = parse_yaml_database(file.path()).await.unwrap();
+    Arc::new(Storage::new(db))
+}
+
+async fn execute(executor: &QueryExecutor, sql: &str) -> yamlbase::Result<u64> {
+    let statements = parse_sql(sql).unwrap();
+    let result = executor.execute(&statements[0]).await?;
+    Ok(result.affected_rows.unwrap())
+}
+
+fn ids(values: &[i64]) -> Vec<Vec<Value>> {
+    values.iter().map(|id| vec![Value::Integer(*id)]).collect()
+}
+
+#[tokio::test]
+async fn test_insert_values() {
+    let executor = QueryExecutor::new(create_storage().await).await.unwrap();
+
+    assert_eq!(
+        execute(
+            &executor,
+            "INSERT INTO users VALUES (2, 'bob@example.com', false, 7, '2024-02-03'), \
+             (3, 'cy@example.com', DEFAULT, NULL, NULL)",
+        )
+        .await
+        .unwrap(),
+        2
+    );
+
+    // Columns left out get their default, or NULL
+    assert_eq!(
+        execute(
+            &executor,
+            "INSERT INTO users (email, id) VALUES ('di@example.com', 4)"
+        )
+        .await
+        .unwrap(),
+        1
+    );
+
+    let rows = query(
+        &executor,
+        "SELECT id, active, score, joined FROM users ORDER BY id",
+    )
+    .await;
+    assert_eq!(rows.len(), 4);
+    assert_eq!(
+        rows[1],
+        vec![
+            Value::Integer(2),
+            Value::Boolean(false),
+            Value::Decimal("7.00".parse().unwrap()),
+            Value::Date(chrono::NaiveDate::from_ymd_opt(2024, 2, 3).unwrap()),
+        ]
+    );
+    assert_eq!(rows[2][1], Value::Boolean(true));
+    assert_eq!(
+        rows[3][1..],
+        [Value::Boolean(true), Value::Null, Value::Null]
+    );
+
+    // Inserted rows take part in filters, joins and aggregates like loaded ones
+    assert_eq!(
+        query(&executor, "SELECT id FROM users WHERE id = 3").await,
+        ids(&[3])
+    );
+    assert_eq!(
+        query(&executor, "SELECT COUNT(*) FROM users WHERE active = true").await,
+        vec![vec![Value::Integer(3)]]
+    );
+}
+
+#[tokio::test]
+async fn test_insert_select_and_parameters() {
+    let executor = QueryExecutor::new(create_storage().await).await.unwrap();
+
+    assert_eq!(
+        execute(
+            &executor,
+            "INSERT INTO users (id, email) SELECT id + 10, 'copy' || id || '@x.io' FROM users",
+        )
+        .await
+        .unwrap(),
+        1
+    );
+    assert_eq!(
+        query(&executor, "SELECT email FROM users WHERE id = 11").await,
+        vec![vec![Value::Text("copy1@x.io".to_string())]]
+    );
+
+    let statements = parse_sql_with_dialect(
+        "INSERT INTO users (id, email) VALUES (?, ?)",
+        SqlDialect::MySQL,
+    )
+    .unwrap();
+    let result = executor
+        .execute_with_parameters(
+            &statements[0],
+            &[Value::Integer(20), Value::Text("p@x.io".to_string())],
+        )
+        .await
+        .unwrap();
+    assert_eq!(result.affected_rows, Some(1));
+    assert_eq!(
+        query(&executor, "SELECT id FROM users ORDER BY id").await,
+        ids(&[1, 11, 20])
+    );
+}
+
+#[tokio::test]
+async fn test_insert_constraints() {
+    let executor = QueryExecutor::new(create_storage().await).await.unwrap();
+
+    for sql in [
+        // Duplicate primary key and unique values, also within one statement
+        "INSERT INTO users (id, email) VALUES (1, 'new@example.com')",
+        "INSERT INTO users (id, email) VALUES (5, 'ann@example.com')",
+        "INSERT INTO users (id, email) VALUES (5, 'a@x.io'), (6, 'a@x.io')",
+        // NOT NULL, types and lengths
+        "INSERT INTO users (id) VALUES (5)",
+        "INSERT INTO users (id, email) VALUES ('five', 'a@x.io')",
+        "INSERT INTO users (id, email) VALUES (5, 'much-too-long@example.com')",
+        "INSERT INTO users (id, email, joined) VALUES (5, 'a@x.io', 'soon')",
+        // Shape of the statement
+        "INSERT INTO users (id, email) VALUES (5)",
+        "INSERT INTO users (id, id) VALUES (5, 6)",
+        "INSERT INTO users (id, nickname) VALUES (5, 'x')",
+        "INSERT INTO missing VALUES (1)",
+    ] {
+        assert!(execute(&executor, sql).await.is_err(), "{}", sql);
+    }
+    assert!(matches!(
+        execute(&executor, "INSERT INTO users (id, email) VALUES (1, 'new@example.com')").await,
+        Err(yamlbase::YamlBaseError::UniqueViolation { ref column, .. }) if column == "id"
+    ));
+
+    // A failed statement inserts nothing
+    assert_eq!(query(&executor, "SELECT id FROM users").await, ids(&[1]));
+}
+
+#[tokio::test]
+async fn test_inserts_are_private_to_the_connection() {
+    let storage = create_storage().await;
+    let writer = QueryExecutor::new(Arc::clone(&storage)).await.unwrap();
+    let reader = QueryExecutor::new(Arc::clone(&storage)).await.unwrap();
+
+    execute(
+        &writer,
+        "INSERT INTO users (id, email) VALUES (2, 'b@x.io')",
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(
+        query(&writer, "SELECT id FROM users ORDER BY id").await,
+        ids(&[1, 2])
+    );
+    assert_eq!(query(&reader, "SELECT id FROM users").await, ids(&[1]));
+    assert_eq!(
+        storage
+            .database()
+            .read()
+            .await
+            .get_table("users")
+            .unwrap()
+            .rows
+            .len(),
+        1
+    );
+
+    // Each connection checks uniqueness against its own rows
+    execute(
+        &reader,
+        "INSERT INTO users (id, email) VALUES (2, 'c@x.io')",
+    )
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn test_insert_over_postgres() {
+    let mut db = Database::new("test_db".to_string());
+    let columns = ["id", "name"]
+        .iter()
+        .map(|name| Column {
+            name: name.to_string(),
+            sql_type: if *name == "id" {
+                yamlbase::yaml::schema::SqlType::Integer
+            } else {
+                yamlbase::yaml::schema::SqlType::Text
+            },
+            primary_key: *name == "id",
+            nullable: *name != "id",
+            unique: *name == "id",
+            default: None,
+            references: None,
+            collation: None,
+        })
+        .collect();
+    db.add_table(yamlbase::database::Table::new("items".to_string(), columns))
+        .unwrap();
+    let server = TestServer::new_postgres(db).await;
+
+    let writer = connect_postgres(server.port(), "yamlbase", "password", "test_db")
+        .await
+        .unwrap();
+    let reader = connect_postgres(server.port(), "yamlbase", "password", "test_db")
+        .await
+        .unwrap();
+
+    let inserted = writer
+        .execute("INSERT INTO items VALUES (1, 'one'), (2, 'two')", &[])
+        .await
+        .unwrap();
+    assert_eq!(inserted, 2);
+    let messages = writer
+        .simple_query("INSERT INTO items (id) VALUES (3)")
+        .await
+        .unwrap();
+    assert!(matches!(
+        messages.last(),
+        Some(tokio_postgres::SimpleQueryMessage::CommandComplete(1))
+    ));
+    let error = writer
+        .execute("INSERT INTO items VALUES (1, 'uno')", &[])
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error.code(),
+        Some(&tokio_postgres::error::SqlState::UNIQUE_VIOLATION)
+    );
+
+    let count = |client: tokio_postgres::Client| async move {
+        let rows = client
+            .simple_query("SELECT COUNT(*) FROM items")
+            .await
+            .unwrap();
+        rows.iter()
+            .find_map(|message| match message {
+                tokio_postgres::SimpleQueryMessage::Row(row) => {
+                    Some(row.get(0).unwrap().to_string())
+                }
+                _ => None,
+            })
+            .unwrap()
+    };
+    assert_eq!(count(writer).await, "3");
+    assert_eq!(count(reader).await, "0");
+}
@@ -28,10 +28,12 @@ tables:
     // Start server in background task
     let server_handle = tokio::spawn(async move {
         let config = Config {
-            file: yaml_path.into(),
+            command: None,
+            files: vec![yaml_path.into()],
             port: Some(25432), // Use different port to avoid conflicts
             bind_address: "127.0.0.1".to_string(),
             protocol: Protocol::Postgres,
+            listener_ports: Default::default(),
             username: "admin".to_string(),
             password: "password".to_string(),
             hot_reload: false,
@@ -93,10 +95,12 @@ tables:
         // Start server
         let server_handle = tokio::spawn(async move {
             let config = Config {
-                file: yaml_path.into(),
+                command: None,
+                files: vec![yaml_path.into()],
                 port: Some(port),
                 bind_address: "127.0.0.1".to_string(),
                 protocol: protocol_enum,
+                listener_ports: Default::default(),
                 username: "admin".to_string(),
                 password: "password".to_string(),
                 hot_reload: false,
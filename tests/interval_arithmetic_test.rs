@@ -0,0 +1,118 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use yamlbase::database::{Database, Table, Value};
+use yamlbase::sql::{QueryExecutor, parse_sql};
+use yamlbase::yaml::schema::SqlType;
+
+mod common;
+use common::{column, new_executor, query};
+
+fn timestamp(s: &str) -> NaiveDateTime {
+    NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").unwrap()
+}
+
+fn date(s: &str) -> NaiveDate {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+}
+
+async fn create_executor() -> QueryExecutor {
+    let mut db = Database::new("test_db".to_string());
+
+    let mut events = Table::new(
+        "events".to_string(),
+        vec![
+            column("id", SqlType::Integer, true),
+            column("created_at", SqlType::Timestamp, false),
+        ],
+    );
+    let rows = [
+        (1, "2024-01-31 10:00:00"),
+        (2, "2024-03-15 08:30:00"),
+        (3, "2024-06-01 00:00:00"),
+    ];
+    for (id, created_at) in rows {
+        events
+            .insert_row(vec![
+                Value::Integer(id),
+                Value::Timestamp(timestamp(created_at)),
+            ])
+            .unwrap();
+    }
+    db.add_table(events).unwrap();
+
+    new_executor(db).await
+}
+
+#[tokio::test]
+async fn test_interval_arithmetic_on_constants() {
+    let executor = create_executor().await;
+
+    let rows = query(
+        &executor,
+        "SELECT DATE '2024-01-31' + INTERVAL '1 month', \
+         DATE '2024-03-01' - INTERVAL '3 months', \
+         DATE '2024-03-01' + INTERVAL '36 hours', \
+         INTERVAL '2 days' + DATE '2024-03-01'",
+    )
+    .await;
+    assert_eq!(
+        rows[0],
+        vec![
+            Value::Date(date("2024-02-29")),
+            Value::Date(date("2023-12-01")),
+            Value::Timestamp(timestamp("2024-03-02 12:00:00")),
+            Value::Date(date("2024-03-03")),
+        ]
+    );
+
+    // MySQL style unit qualifier
+    let rows = query(
+        &executor,
+        "SELECT DATE '2024-03-01' + INTERVAL 3 DAY, DATE '2024-03-01' - INTERVAL '1' YEAR",
+    )
+    .await;
+    assert_eq!(
+        rows[0],
+        vec![
+            Value::Date(date("2024-03-04")),
+            Value::Date(date("2023-03-01")),
+        ]
+    );
+
+    let rows = query(&executor, "SELECT INTERVAL '1 year 2 months 3 days'").await;
+    assert_eq!(rows[0][0], Value::Text("1 year 2 mons 3 days".to_string()));
+
+    let statements = parse_sql("SELECT DATE '2024-03-01' + INTERVAL '1 fortnight'").unwrap();
+    assert!(executor.execute(&statements[0]).await.is_err());
+}
+
+#[tokio::test]
+async fn test_interval_arithmetic_on_columns() {
+    let executor = create_executor().await;
+
+    let rows = query(
+        &executor,
+        "SELECT created_at + INTERVAL '1 day 2 hours' FROM events WHERE id < 2",
+    )
+    .await;
+    assert_eq!(
+        rows,
+        vec![vec![Value::Timestamp(timestamp("2024-02-01 12:00:00"))]]
+    );
+
+    let rows = query(
+        &executor,
+        "SELECT id FROM events \
+         WHERE created_at >= DATE '2024-06-16' - INTERVAL '3 months' ORDER BY id",
+    )
+    .await;
+    assert_eq!(rows, vec![vec![Value::Integer(3)]]);
+
+    let rows = query(
+        &executor,
+        "SELECT e.id FROM events e JOIN events f ON e.id = f.id \
+         WHERE e.created_at - INTERVAL '30 minutes' <= TIMESTAMP '2024-03-15 08:00:00' \
+         ORDER BY e.id",
+    )
+    .await;
+    assert_eq!(rows, vec![vec![Value::Integer(1)], vec![Value::Integer(2)]]);
+}
@@ -0,0 +1,63 @@
+use serde_json::json;
+use yamlbase::database::Value;
+use yamlbase::yaml::parse_yaml_database;
+
+mod common;
+use common::yaml_file;
+
+const YAML: &str = r#"
+database:
+  name: "settings"
+
+tables:
+  settings:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+      doc: "JSON"
+    data:
+      - id: 1
+        doc:
+          theme: dark
+          sizes: [1, 2.5, {unit: "${JSON_TEST_UNIT:-px}"}]
+          1: one
+          true: yes
+      - id: 2
+        doc:
+          <<: {theme: light, size: 1}
+          size: 2
+      - id: 3
+        doc: !custom [a, b]
+      - id: 4
+        doc: 5
+      - id: 5
+        doc: '{"from": "text"}'
+"#;
+
+#[tokio::test]
+async fn test_nested_values_become_json() {
+    let file = yaml_file(YAML);
+    let (db, _) = parse_yaml_database(file.path()).await.unwrap();
+    let docs: Vec<&Value> = db.tables["settings"].rows.iter().map(|r| &r[1]).collect();
+    assert_eq!(
+        docs,
+        [
+            &Value::Json(json!({
+                "theme": "dark",
+                "sizes": [1, 2.5, {"unit": "px"}],
+                "1": "one",
+                "true": "yes"
+            })),
+            &Value::Json(json!({"theme": "light", "size": 2})),
+            &Value::Json(json!(["a", "b"])),
+            &Value::Json(json!(5)),
+            &Value::Json(json!({"from": "text"})),
+        ]
+    );
+
+    let file = yaml_file(&YAML.replace("doc: 5", "doc: .inf"));
+    let error = parse_yaml_database(file.path())
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("Cannot convert .inf to JSON"), "{}", error);
+}
@@ -0,0 +1,279 @@
+use serde_json::json;
+use yamlbase::database::{Database, Table, Value};
+use yamlbase::sql::{QueryExecutor, parse_sql};
+use yamlbase::yaml::schema::SqlType;
+
+mod common;
+use common::{column, new_executor, query};
+
+fn text(s: &str) -> Value {
+    Value::Text(s.to_string())
+}
+
+async fn create_executor() -> QueryExecutor {
+    let mut db = Database::new("test_db".to_string());
+
+    let mut orders = Table::new(
+        "orders".to_string(),
+        vec![
+            column("id", SqlType::Integer, true),
+            column("details", SqlType::Json, false),
+        ],
+    );
+    let documents = [
+        json!({"customer": {"name": "Ada", "vip": true}, "items": [{"sku": "A1", "qty": 2}], "total": 19.5}),
+        json!({"customer": {"name": "Bob", "vip": false}, "items": [{"sku": "B7", "qty": 1}, {"sku": "C3", "qty": 4}], "total": 42}),
+    ];
+    for (id, details) in (1..).zip(documents) {
+        orders
+            .insert_row(vec![Value::Integer(id), Value::Json(details)])
+            .unwrap();
+    }
+    orders
+        .insert_row(vec![Value::Integer(3), Value::Null])
+        .unwrap();
+    db.add_table(orders).unwrap();
+
+    new_executor(db).await
+}
+
+#[tokio::test]
+async fn test_postgres_json_operators() {
+    let executor = create_executor().await;
+
+    let rows = query(
+        &executor,
+        "SELECT details->'customer'->>'name', details #>> '{items,0,sku}', details->'items'->1 \
+         FROM orders ORDER BY id",
+    )
+    .await;
+    assert_eq!(
+        rows,
+        vec![
+            vec![text("Ada"), text("A1"), Value::Null],
+            vec![
+                text("Bob"),
+                text("B7"),
+                Value::Json(json!({"sku": "C3", "qty": 4}))
+            ],
+            vec![Value::Null, Value::Null, Value::Null],
+        ]
+    );
+
+    let rows = query(
+        &executor,
+        "SELECT id FROM orders WHERE details->'customer'->>'name' = 'Bob'",
+    )
+    .await;
+    assert_eq!(rows, vec![vec![Value::Integer(2)]]);
+}
+
+#[tokio::test]
+async fn test_json_extract_and_json_value() {
+    let executor = create_executor().await;
+
+    let rows = query(
+        &executor,
+        "SELECT JSON_EXTRACT(details, '$.customer.vip'), JSON_VALUE(details, '$.total'), \
+         JSON_EXTRACT(details, '$.items[0]'), JSON_VALUE(details, '$.items') \
+         FROM orders WHERE id < 2",
+    )
+    .await;
+    assert_eq!(
+        rows[0],
+        vec![
+            Value::Boolean(true),
+            Value::Double(19.5),
+            Value::Json(json!({"sku": "A1", "qty": 2})),
+            Value::Null,
+        ]
+    );
+
+    // Typed values can be filtered on directly
+    let rows = query(
+        &executor,
+        "SELECT id FROM orders WHERE JSON_VALUE(details, '$.total') > 20",
+    )
+    .await;
+    assert_eq!(rows, vec![vec![Value::Integer(2)]]);
+
+    // JSON held in text, and several paths at once
+    let rows = query(
+        &executor,
+        "SELECT JSON_EXTRACT('{\"a\": [1, 2, 3], \"b\": \"x\"}', '$.a[2]', '$.b')",
+    )
+    .await;
+    assert_eq!(rows[0][0], Value::Json(json!([3, "x"])));
+
+    let statements = parse_sql("SELECT JSON_EXTRACT('{}', 'a.b')").unwrap();
+    assert!(executor.execute(&statements[0]).await.is_err());
+}
+
+#[tokio::test]
+async fn test_jsonb_array_elements_explodes_rows() {
+    let executor = create_executor().await;
+
+    let rows = query(
+        &executor,
+        "SELECT o.id, item->>'sku' FROM orders o \
+         CROSS JOIN LATERAL jsonb_array_elements(o.details->'items') AS i(item) \
+         ORDER BY o.id",
+    )
+    .await;
+    assert_eq!(
+        rows,
+        vec![
+            vec![Value::Integer(1), text("A1")],
+            vec![Value::Integer(2), text("B7")],
+            vec![Value::Integer(2), text("C3")],
+        ]
+    );
+
+    // Comma syntax, filtering on the exploded values
+    let rows = query(
+        &executor,
+        "SELECT o.id FROM orders o, jsonb_array_elements(o.details->'items') AS e \
+         WHERE e.value->>'sku' = 'C3'",
+    )
+    .await;
+    assert_eq!(rows, vec![vec![Value::Integer(2)]]);
+
+    let rows = query(
+        &executor,
+        "SELECT value FROM jsonb_array_elements_text('[\"a\", 1, null]')",
+    )
+    .await;
+    assert_eq!(
+        rows,
+        vec![vec![text("a")], vec![text("1")], vec![Value::Null]]
+    );
+}
+
+#[tokio::test]
+async fn test_json_table() {
+    let executor = create_executor().await;
+
+    let rows = query(
+        &executor,
+        "SELECT o.id, jt.n, jt.sku, jt.qty FROM orders o, \
+         JSON_TABLE(o.details, '$.items[*]' COLUMNS ( \
+             n FOR ORDINALITY, \
+             sku VARCHAR(10) PATH '$.sku', \
+             qty INT PATH '$.qty')) AS jt \
+         WHERE jt.qty > 1 ORDER BY o.id",
+    )
+    .await;
+    assert_eq!(
+        rows,
+        vec![
+            vec![
+                Value::Integer(1),
+                Value::Integer(1),
+                text("A1"),
+                Value::Integer(2)
+            ],
+            vec![
+                Value::Integer(2),
+                Value::Integer(2),
+                text("C3"),
+                Value::Integer(4)
+            ],
+        ]
+    );
+
+    let rows = query(
+        &executor,
+        "SELECT o.id, c.name FROM orders o \
+         LEFT JOIN JSON_TABLE(o.details, '$.customer' COLUMNS ( \
+             name TEXT PATH '$.name', \
+             vip INT EXISTS PATH '$.vip')) AS c ON c.vip = 1 \
+         ORDER BY o.id",
+    )
+    .await;
+    assert_eq!(
+        rows,
+        vec![
+            vec![Value::Integer(1), text("Ada")],
+            vec![Value::Integer(2), text("Bob")],
+            vec![Value::Integer(3), Value::Null],
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_json_object_and_json_array() {
+    let executor = create_executor().await;
+
+    let rows = query(
+        &executor,
+        "SELECT JSON_OBJECT('id', id, 'name', details->'customer'->>'name', \
+         'tags', JSON_ARRAY(1, 'x', NULL), 'items', details->'items') \
+         FROM orders WHERE id < 2",
+    )
+    .await;
+    assert_eq!(
+        rows[0][0],
+        Value::Json(json!({
+            "id": 1,
+            "name": "Ada",
+            "tags": [1, "x", null],
+            "items": [{"sku": "A1", "qty": 2}],
+        }))
+    );
+
+    let rows = query(
+        &executor,
+        "SELECT json_build_object('total', 19.5, 'ok', TRUE), json_build_array()",
+    )
+    .await;
+    assert_eq!(
+        rows[0],
+        vec![
+            Value::Json(json!({"total": 19.5, "ok": true})),
+            Value::Json(json!([]))
+        ]
+    );
+
+    for sql in ["SELECT JSON_OBJECT('a')", "SELECT JSON_OBJECT(NULL, 1)"] {
+        let statements = parse_sql(sql).unwrap();
+        assert!(executor.execute(&statements[0]).await.is_err(), "{}", sql);
+    }
+}
+
+#[tokio::test]
+async fn test_json_agg() {
+    let executor = create_executor().await;
+
+    let rows = query(
+        &executor,
+        "SELECT JSON_AGG(JSON_OBJECT('id', id, 'total', JSON_VALUE(details, '$.total')) \
+         ORDER BY id DESC) FROM orders WHERE id < 3",
+    )
+    .await;
+    assert_eq!(
+        rows[0][0],
+        Value::Json(json!([{"id": 2, "total": 42}, {"id": 1, "total": 19.5}]))
+    );
+
+    let rows = query(
+        &executor,
+        "SELECT id, JSON_AGG(details->'customer'->>'name') FROM orders GROUP BY id ORDER BY id",
+    )
+    .await;
+    assert_eq!(
+        rows,
+        vec![
+            vec![Value::Integer(1), Value::Json(json!(["Ada"]))],
+            vec![Value::Integer(2), Value::Json(json!(["Bob"]))],
+            vec![Value::Integer(3), Value::Json(json!([null]))],
+        ]
+    );
+
+    // Like PostgreSQL, aggregating no rows gives NULL rather than an empty array
+    let rows = query(
+        &executor,
+        "SELECT JSON_ARRAYAGG(id) FROM orders WHERE id > 10",
+    )
+    .await;
+    assert_eq!(rows, vec![vec![Value::Null]]);
+}
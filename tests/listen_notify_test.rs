@@ -0,0 +1,142 @@
+use futures::StreamExt;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio_postgres::{AsyncMessage, NoTls, Notification};
+
+mod common;
+use common::{TestServer, yaml_file};
+
+const YAML: &str = r#"
+database:
+  name: "shop"
+  auth:
+    username: "yamlbase"
+    password: "password"
+
+tables:
+  products:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+      name: "TEXT"
+    data:
+      - id: 1
+        name: "pen"
+"#;
+
+// A client whose notifications arrive on the receiver
+async fn connect(
+    server: &TestServer,
+) -> (
+    tokio_postgres::Client,
+    mpsc::UnboundedReceiver<Notification>,
+) {
+    let (client, mut connection) = tokio_postgres::Config::new()
+        .host("127.0.0.1")
+        .port(server.port())
+        .user("yamlbase")
+        .password("password")
+        .dbname("shop")
+        .connect(NoTls)
+        .await
+        .unwrap();
+    let (tx, rx) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        let mut messages = futures::stream::poll_fn(move |cx| connection.poll_message(cx));
+        while let Some(Ok(message)) = messages.next().await {
+            if let AsyncMessage::Notification(notification) = message {
+                let _ = tx.send(notification);
+            }
+        }
+    });
+    (client, rx)
+}
+
+async fn next(notifications: &mut mpsc::UnboundedReceiver<Notification>) -> (String, String) {
+    let notification = tokio::time::timeout(Duration::from_secs(10), notifications.recv())
+        .await
+        .expect("no notification arrived")
+        .unwrap();
+    (
+        notification.channel().to_string(),
+        notification.payload().to_string(),
+    )
+}
+
+#[tokio::test]
+async fn test_listen_notify() {
+    let file = yaml_file(YAML);
+    let server = TestServer::start_postgres(file.path().to_str().unwrap());
+    let (listener, mut notifications) = connect(&server).await;
+    let (sender, _) = connect(&server).await;
+
+    listener
+        .batch_execute("LISTEN Orders; LISTEN \"Mixed\"")
+        .await
+        .unwrap();
+    sender
+        .batch_execute("NOTIFY other, 'not listened to'; NOTIFY orders, 'order 1'")
+        .await
+        .unwrap();
+    assert_eq!(
+        next(&mut notifications).await,
+        ("orders".to_string(), "order 1".to_string())
+    );
+    sender.execute("NOTIFY \"Mixed\"", &[]).await.unwrap();
+    assert_eq!(
+        next(&mut notifications).await,
+        ("Mixed".to_string(), String::new())
+    );
+
+    // A connection also gets its own notifications
+    listener
+        .batch_execute("NOTIFY orders, 'mine'")
+        .await
+        .unwrap();
+    assert_eq!(
+        next(&mut notifications).await,
+        ("orders".to_string(), "mine".to_string())
+    );
+
+    listener.batch_execute("UNLISTEN orders").await.unwrap();
+    sender
+        .batch_execute("NOTIFY orders, 'order 2'; NOTIFY \"Mixed\", 'still'")
+        .await
+        .unwrap();
+    assert_eq!(
+        next(&mut notifications).await,
+        ("Mixed".to_string(), "still".to_string())
+    );
+
+    listener.batch_execute("UNLISTEN *").await.unwrap();
+    sender
+        .batch_execute("NOTIFY \"Mixed\", 'gone'")
+        .await
+        .unwrap();
+    listener.batch_execute("SELECT 1").await.unwrap();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(notifications.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn test_reload_notification() {
+    let file = yaml_file(YAML);
+    let server =
+        TestServer::start_postgres_with_args(file.path().to_str().unwrap(), &["--hot-reload"]);
+    let (client, mut notifications) = connect(&server).await;
+    client.execute("LISTEN yamlbase_reload", &[]).await.unwrap();
+
+    std::fs::write(file.path(), YAML.replace("\"pen\"", "\"ink\"")).unwrap();
+    assert_eq!(
+        next(&mut notifications).await,
+        (
+            "yamlbase_reload".to_string(),
+            "tables changed products".to_string()
+        )
+    );
+    let name: String = client
+        .query_one("SELECT name FROM products", &[])
+        .await
+        .unwrap()
+        .get(0);
+    assert_eq!(name, "ink");
+}
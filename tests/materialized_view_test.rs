@@ -0,0 +1,173 @@
+use std::sync::Arc;
+use tokio_postgres::SimpleQueryMessage;
+use yamlbase::database::{Storage, Value};
+use yamlbase::sql::{QueryExecutor, WriteMode};
+use yamlbase::yaml::parse_yaml_database;
+
+mod common;
+use common::{TestServer, connect_postgres, try_query, yaml_file};
+
+const YAML: &str = r#"
+database:
+  name: "test_db"
+  auth:
+    username: "yamlbase"
+    password: "password"
+
+tables:
+  orders:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+      customer: "VARCHAR(20) NOT NULL"
+      amount: "INTEGER"
+    data:
+      - id: 1
+        customer: "ann"
+        amount: 10
+      - id: 2
+        customer: "bob"
+        amount: 4
+      - id: 3
+        customer: "ann"
+        amount: 7
+
+materialized_views:
+  customer_totals:
+    query: |
+      SELECT customer, SUM(amount) AS total, COUNT(*) AS orders
+      FROM orders GROUP BY customer ORDER BY customer
+  big_customers:
+    query: "SELECT customer FROM customer_totals WHERE total > 10"
+"#;
+
+fn text(value: &str) -> Value {
+    Value::Text(value.to_string())
+}
+
+#[tokio::test]
+async fn test_views_are_computed_at_load() {
+    let file = yaml_file(YAML);
+    let (db, _) = parse_yaml_database(file.path()).await.unwrap();
+    let totals = db.get_table("customer_totals").unwrap();
+    assert_eq!(totals.columns.len(), 3);
+    assert_eq!(totals.rows.len(), 2);
+
+    let executor = QueryExecutor::new(Arc::new(Storage::new(db)))
+        .await
+        .unwrap();
+    assert_eq!(
+        try_query(
+            &executor,
+            "SELECT customer, orders FROM customer_totals ORDER BY customer"
+        )
+        .await
+        .unwrap(),
+        vec![
+            vec![text("ann"), Value::Integer(2)],
+            vec![text("bob"), Value::Integer(1)],
+        ]
+    );
+    // Views can build on the views declared before them
+    assert_eq!(
+        try_query(&executor, "SELECT * FROM big_customers")
+            .await
+            .unwrap(),
+        vec![vec![text("ann")]]
+    );
+
+    assert!(
+        try_query(&executor, "DELETE FROM customer_totals")
+            .await
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn test_refresh_materialized_view() {
+    let file = yaml_file(YAML);
+    let (db, _) = parse_yaml_database(file.path()).await.unwrap();
+    let storage = Arc::new(Storage::new(db));
+    let executor = QueryExecutor::new(storage.clone())
+        .await
+        .unwrap()
+        .with_write_mode(WriteMode::Shared);
+
+    try_query(&executor, "INSERT INTO orders VALUES (4, 'bob', 20)")
+        .await
+        .unwrap();
+    // The view keeps its result until it is refreshed
+    assert_eq!(
+        try_query(&executor, "SELECT * FROM big_customers")
+            .await
+            .unwrap(),
+        vec![vec![text("ann")]]
+    );
+
+    executor
+        .refresh_materialized_view("CUSTOMER_TOTALS")
+        .await
+        .unwrap();
+    executor
+        .refresh_materialized_view("big_customers")
+        .await
+        .unwrap();
+    let other = QueryExecutor::new(storage).await.unwrap();
+    assert_eq!(
+        try_query(&other, "SELECT * FROM big_customers ORDER BY customer")
+            .await
+            .unwrap(),
+        vec![vec![text("ann")], vec![text("bob")]]
+    );
+
+    assert!(executor.refresh_materialized_view("orders").await.is_err());
+}
+
+#[tokio::test]
+async fn test_invalid_views() {
+    let clash = YAML.replace("  big_customers:", "  orders:");
+    let file = yaml_file(&clash);
+    assert!(parse_yaml_database(file.path()).await.is_err());
+
+    let broken = YAML.replace("FROM customer_totals", "FROM missing");
+    let file = yaml_file(&broken);
+    let error = parse_yaml_database(file.path()).await.unwrap_err();
+    assert!(error.to_string().contains("big_customers"));
+}
+
+#[tokio::test]
+async fn test_refresh_over_postgres() {
+    let file = yaml_file(YAML);
+    let server = TestServer::start_postgres(file.path().to_str().unwrap());
+
+    let client = connect_postgres(server.port(), "yamlbase", "password", "test_db")
+        .await
+        .unwrap();
+
+    let messages = client
+        .simple_query("REFRESH MATERIALIZED VIEW customer_totals;")
+        .await
+        .unwrap();
+    assert!(matches!(
+        messages.last(),
+        Some(SimpleQueryMessage::CommandComplete(0))
+    ));
+    assert!(
+        client
+            .simple_query("REFRESH MATERIALIZED VIEW missing")
+            .await
+            .is_err()
+    );
+
+    let rows = client
+        .simple_query("SELECT total FROM customer_totals WHERE customer = 'ann'")
+        .await
+        .unwrap();
+    let total = rows
+        .iter()
+        .find_map(|message| match message {
+            SimpleQueryMessage::Row(row) => Some(row.get(0).unwrap().to_string()),
+            _ => None,
+        })
+        .unwrap();
+    assert_eq!(total, "17");
+}
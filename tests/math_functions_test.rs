@@ -0,0 +1,126 @@
+use rust_decimal::Decimal;
+use std::str::FromStr;
+use yamlbase::database::{Database, Table, Value};
+use yamlbase::sql::{QueryExecutor, parse_sql};
+use yamlbase::yaml::schema::SqlType;
+
+mod common;
+use common::{column, new_executor, query};
+
+fn dec(s: &str) -> Value {
+    Value::Decimal(Decimal::from_str(s).unwrap())
+}
+
+async fn create_executor() -> QueryExecutor {
+    let mut db = Database::new("test_db".to_string());
+
+    let mut measurements = Table::new(
+        "measurements".to_string(),
+        vec![
+            column("id", SqlType::Integer, true),
+            column("amount", SqlType::Decimal(10, 3), false),
+            column("reading", SqlType::Double, false),
+        ],
+    );
+    let rows = [(1, "1234.567", 16.0), (2, "-8.999", -2.75)];
+    for (id, amount, reading) in rows {
+        measurements
+            .insert_row(vec![
+                Value::Integer(id),
+                dec(amount),
+                Value::Double(reading),
+            ])
+            .unwrap();
+    }
+    db.add_table(measurements).unwrap();
+
+    new_executor(db).await
+}
+
+fn assert_double(value: &Value, expected: f64) {
+    match value {
+        Value::Double(d) => assert!((d - expected).abs() < 1e-9, "{} != {}", d, expected),
+        other => panic!("expected a double, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_math_functions_on_constants() {
+    let executor = create_executor().await;
+
+    let rows = query(
+        &executor,
+        "SELECT POWER(2, 10), POW(9, 0.5), SQRT(2), EXP(1), LN(EXP(2)), \
+         LOG(1000), LOG(2, 8), LOG10(0.01), LOG2(32)",
+    )
+    .await;
+    let expected = [
+        1024.0,
+        3.0,
+        std::f64::consts::SQRT_2,
+        std::f64::consts::E,
+        2.0,
+        3.0,
+        3.0,
+        -2.0,
+        5.0,
+    ];
+    for (value, expected) in rows[0].iter().zip(expected) {
+        assert_double(value, expected);
+    }
+
+    let rows = query(
+        &executor,
+        "SELECT SIGN(-7), SIGN(0), TRUNC(2.789, 1), TRUNC(-2.789), TRUNCATE(1234, -2), \
+         SQRT(NULL)",
+    )
+    .await;
+    assert_eq!(
+        rows[0],
+        vec![
+            Value::Integer(-1),
+            Value::Integer(0),
+            dec("2.7"),
+            dec("-2"),
+            Value::Integer(1200),
+            Value::Null,
+        ]
+    );
+
+    for sql in [
+        "SELECT SQRT(-1)",
+        "SELECT LN(0)",
+        "SELECT POWER(0, -1)",
+        "SELECT EXP(1000)",
+    ] {
+        let statements = parse_sql(sql).unwrap();
+        assert!(executor.execute(&statements[0]).await.is_err(), "{}", sql);
+    }
+}
+
+#[tokio::test]
+async fn test_math_functions_on_columns() {
+    let executor = create_executor().await;
+
+    // TRUNC rounds towards zero where ROUND rounds to nearest
+    let rows = query(
+        &executor,
+        "SELECT TRUNC(amount, 2), ROUND(reading), TRUNC(reading), SIGN(amount), SQRT(ABS(reading)) \
+         FROM measurements ORDER BY id",
+    )
+    .await;
+    assert_eq!(rows[0][0], dec("1234.56"));
+    assert_eq!(rows[1][0], dec("-8.99"));
+    assert_eq!(rows[1][1], Value::Double(-3.0));
+    assert_eq!(rows[1][2], Value::Double(-2.0));
+    assert_eq!(rows[0][3], dec("1"));
+    assert_eq!(rows[1][3], dec("-1"));
+    assert_double(&rows[0][4], 4.0);
+
+    let rows = query(
+        &executor,
+        "SELECT id FROM measurements WHERE POWER(reading, 2) > 100",
+    )
+    .await;
+    assert_eq!(rows, vec![vec![Value::Integer(1)]]);
+}
@@ -0,0 +1,119 @@
+use clap::Parser;
+use std::time::Duration;
+use tiberius::{AuthMethod, Client, EncryptionLevel};
+use tokio::net::TcpStream;
+use tokio_postgres::error::SqlState;
+use tokio_util::compat::TokioAsyncWriteCompatExt;
+use yamlbase::config::Config;
+
+mod common;
+use common::{TestServer, connect_mysql, connect_postgres, yaml_file};
+
+const PRODUCTS: &str = r#"
+database:
+  name: "shop"
+  auth:
+    username: "sa"
+    password: "password"
+
+tables:
+  products:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+    data:
+      - id: 1
+"#;
+
+/// Retry `connect` while the connection that waited for the server to listen still holds
+/// the only place
+async fn when_free<T, E, F: Future<Output = Result<T, E>>>(connect: impl Fn() -> F) -> T {
+    for _ in 0..50 {
+        if let Ok(connection) = connect().await {
+            return connection;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    panic!("No connection was admitted");
+}
+
+#[tokio::test]
+async fn test_postgres_connection_limit() {
+    let file = yaml_file(PRODUCTS);
+    let server = TestServer::start_postgres_with_args(
+        file.path().to_str().unwrap(),
+        &["--max-connections", "1"],
+    );
+
+    let first = when_free(|| connect_postgres(server.port(), "sa", "password", "shop")).await;
+    let error = connect_postgres(server.port(), "sa", "password", "shop")
+        .await
+        .err()
+        .unwrap();
+    assert_eq!(error.code(), Some(&SqlState::TOO_MANY_CONNECTIONS));
+
+    // The place is free again once the first client leaves
+    drop(first);
+    when_free(|| connect_postgres(server.port(), "sa", "password", "shop")).await;
+}
+
+#[tokio::test]
+async fn test_mysql_connection_limit() {
+    let file = yaml_file(PRODUCTS);
+    let server = TestServer::start_mysql_with_args(
+        file.path().to_str().unwrap(),
+        &["--max-connections", "1"],
+    );
+    let port = server.port();
+
+    let _first = when_free(|| async move {
+        tokio::task::spawn_blocking(move || connect_mysql(port, "sa", "password", "shop"))
+            .await
+            .unwrap()
+    })
+    .await;
+    tokio::task::spawn_blocking(
+        move || match connect_mysql(port, "sa", "password", "shop") {
+            Err(mysql::Error::MySqlError(error)) => {
+                assert_eq!(error.code, 1040);
+                assert_eq!(error.message, "Too many connections");
+            }
+            other => panic!("expected too many connections, got {:?}", other.map(|_| ())),
+        },
+    )
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn test_sqlserver_connection_limit() {
+    let file = yaml_file(PRODUCTS);
+    let server = TestServer::start_sqlserver_with_args(
+        file.path().to_str().unwrap(),
+        &["--max-connections", "1"],
+    );
+
+    let mut config = tiberius::Config::new();
+    config.host("127.0.0.1");
+    config.port(server.port());
+    config.authentication(AuthMethod::sql_server("sa", "password"));
+    config.encryption(EncryptionLevel::NotSupported);
+    let connect = || async {
+        let tcp = TcpStream::connect(config.get_addr()).await.unwrap();
+        Client::connect(config.clone(), tcp.compat_write()).await
+    };
+
+    let _first = when_free(connect).await;
+    match connect().await {
+        Err(tiberius::error::Error::Server(error)) => assert_eq!(error.code(), 17809),
+        other => panic!("expected too many connections, got {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn test_max_connections_option() {
+    let config =
+        Config::try_parse_from(["yamlbase", "-f", "db.yaml", "--max-connections", "5"]).unwrap();
+    assert_eq!(config.max_connections(), 5);
+    let config = Config::try_parse_from(["yamlbase", "-f", "db.yaml"]).unwrap();
+    assert_eq!(config.max_connections(), 1000);
+}
@@ -0,0 +1,179 @@
+use mysql::prelude::*;
+use tokio_postgres::{Client, SimpleQueryMessage};
+
+mod common;
+use common::{
+    _mysql_connect_and_auth, TestServer, connect_mysql, connect_postgres, read_packet,
+    write_packet, yaml_file,
+};
+
+const YAML: &str = r#"
+database:
+  name: "test_db"
+  auth:
+    username: "yamlbase"
+    password: "password"
+
+tables:
+  items:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+      name: "VARCHAR(20)"
+    data:
+      - id: 1
+        name: "one"
+"#;
+
+// The command counts and the first column of each row, in the order they arrived
+fn summarize(messages: &[SimpleQueryMessage]) -> Vec<String> {
+    messages
+        .iter()
+        .filter_map(|message| match message {
+            SimpleQueryMessage::CommandComplete(count) => Some(format!("complete {}", count)),
+            SimpleQueryMessage::Row(row) => Some(row.get(0).unwrap_or("NULL").to_string()),
+            _ => None,
+        })
+        .collect()
+}
+
+async fn count_items(client: &Client) -> String {
+    let messages = client
+        .simple_query("SELECT COUNT(*) FROM items")
+        .await
+        .unwrap();
+    summarize(&messages)[0].clone()
+}
+
+#[tokio::test]
+async fn test_postgres_batch() {
+    let file = yaml_file(YAML);
+    let server = TestServer::start_postgres(file.path().to_str().unwrap());
+    let client = connect_postgres(server.port(), "yamlbase", "password", "test_db")
+        .await
+        .unwrap();
+
+    let messages = client
+        .simple_query(
+            "INSERT INTO items VALUES (2, 'two'), (3, 'three');\n\
+             UPDATE items SET name = 'a;b' WHERE id = 2;\n\
+             SELECT name FROM items WHERE id >= 2 ORDER BY id;",
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        summarize(&messages),
+        vec!["complete 2", "complete 1", "a;b", "three", "complete 2"]
+    );
+
+    // A failing statement undoes the statements before it and skips the rest
+    assert!(
+        client
+            .simple_query(
+                "DELETE FROM items WHERE id = 3; INSERT INTO items VALUES (1, 'dup'); \
+                 DELETE FROM items",
+            )
+            .await
+            .is_err()
+    );
+    assert_eq!(count_items(&client).await, "3");
+
+    // A syntax error anywhere means nothing runs
+    assert!(
+        client
+            .simple_query("DELETE FROM items; SELEC 1")
+            .await
+            .is_err()
+    );
+    assert_eq!(count_items(&client).await, "3");
+
+    // Statements that manage the transaction themselves are left to do so
+    client
+        .simple_query("BEGIN; DELETE FROM items WHERE id = 1")
+        .await
+        .unwrap();
+    client.simple_query("ROLLBACK").await.unwrap();
+    assert_eq!(count_items(&client).await, "3");
+
+    // An empty query completes without doing anything
+    let messages = client.simple_query(" ; ").await.unwrap();
+    assert_eq!(summarize(&messages), vec!["complete 0"]);
+}
+
+#[test]
+fn test_mysql_batch() {
+    let file = yaml_file(YAML);
+    let server = TestServer::start_mysql(file.path().to_str().unwrap());
+
+    let mut conn = connect_mysql(server.port(), "yamlbase", "password", "test_db").unwrap();
+
+    let mut result = conn
+        .query_iter(
+            "SET NAMES utf8mb4; INSERT INTO items VALUES (4, 'four'); \
+             SELECT id FROM items ORDER BY id; SELECT @@version_comment",
+        )
+        .unwrap();
+    let mut sets = Vec::new();
+    while let Some(set) = result.iter() {
+        let affected = set.affected_rows();
+        let rows: Vec<String> = set
+            .map(|row| row.unwrap().get::<String, _>(0).unwrap())
+            .collect();
+        sets.push((affected, rows));
+    }
+    drop(result);
+    assert_eq!(
+        sets,
+        vec![
+            (0, vec![]),
+            (1, vec![]),
+            (0, vec!["1".to_string(), "4".to_string()]),
+            (0, vec!["1".to_string()]),
+        ]
+    );
+
+    // The first error ends the query
+    let mut result = conn
+        .query_iter("DELETE FROM items WHERE id = 4; SELECT * FROM missing; DELETE FROM items")
+        .unwrap();
+    assert_eq!(result.iter().unwrap().affected_rows(), 1);
+    assert!(result.iter().unwrap().next().unwrap().is_err());
+    drop(result);
+    let count: Option<i64> = conn.query_first("SELECT COUNT(*) FROM items").unwrap();
+    assert_eq!(count, Some(1));
+}
+
+// COM_SET_OPTION turns multi-statement queries off and on, and each result but the last
+// has SERVER_MORE_RESULTS_EXISTS in the status of its final EOF
+#[test]
+fn test_mysql_multi_statements_option() {
+    const COM_QUERY: u8 = 0x03;
+    const COM_SET_OPTION: u8 = 0x1b;
+    const SERVER_MORE_RESULTS_EXISTS: u16 = 0x0008;
+
+    let file = yaml_file(YAML);
+    let server = TestServer::start_mysql(file.path().to_str().unwrap());
+    let mut stream = _mysql_connect_and_auth(&server, "yamlbase", "password");
+
+    write_packet(&mut stream, 0, &[COM_SET_OPTION, 1, 0]);
+    assert_eq!(read_packet(&mut stream)[0], 0xfe);
+    let mut query = vec![COM_QUERY];
+    query.extend_from_slice(b"SELECT 1; SELECT 2");
+    write_packet(&mut stream, 0, &query);
+    let error = read_packet(&mut stream);
+    assert_eq!(error[0], 0xff);
+    assert_eq!(u16::from_le_bytes([error[1], error[2]]), 1064);
+
+    write_packet(&mut stream, 0, &[COM_SET_OPTION, 0, 0]);
+    assert_eq!(read_packet(&mut stream)[0], 0xfe);
+    write_packet(&mut stream, 0, &query);
+    // Column count, column definition, EOF, row and EOF for each result
+    let mut statuses = Vec::new();
+    for value in [b"1", b"2"] {
+        let packets: Vec<Vec<u8>> = (0..5).map(|_| read_packet(&mut stream)).collect();
+        assert_eq!(&packets[3][1..], value);
+        assert_eq!(packets[4][0], 0xfe);
+        statuses.push(u16::from_le_bytes([packets[4][3], packets[4][4]]));
+    }
+    assert_ne!(statuses[0] & SERVER_MORE_RESULTS_EXISTS, 0);
+    assert_eq!(statuses[1] & SERVER_MORE_RESULTS_EXISTS, 0);
+}
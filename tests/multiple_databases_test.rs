@@ -0,0 +1,115 @@
+use mysql::prelude::Queryable;
+use tokio_postgres::SimpleQueryMessage;
+
+mod common;
+use common::{TestServer, connect_mysql, connect_postgres, yaml_file};
+
+const SALES: &str = r#"
+database:
+  name: "sales"
+  auth:
+    username: "yamlbase"
+    password: "password"
+
+tables:
+  orders:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+    data:
+      - id: 1
+      - id: 2
+"#;
+
+const HR: &str = r#"
+database:
+  name: "hr"
+
+tables:
+  employees:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+      name: "TEXT"
+    data:
+      - id: 1
+        name: "ann"
+"#;
+
+async fn first_value(client: &tokio_postgres::Client, query: &str) -> String {
+    let messages = client.simple_query(query).await.unwrap();
+    messages
+        .iter()
+        .find_map(|message| match message {
+            SimpleQueryMessage::Row(row) => Some(row.get(0).unwrap().to_string()),
+            _ => None,
+        })
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_postgres_database_parameter() {
+    let sales = yaml_file(SALES);
+    let hr = yaml_file(HR);
+    let server = TestServer::start_postgres_with_args(
+        sales.path().to_str().unwrap(),
+        &["-f", hr.path().to_str().unwrap()],
+    );
+
+    let client = connect_postgres(server.port(), "yamlbase", "password", "sales")
+        .await
+        .unwrap();
+    assert_eq!(
+        first_value(&client, "SELECT COUNT(*) FROM orders").await,
+        "2"
+    );
+    assert!(
+        client
+            .simple_query("SELECT * FROM employees")
+            .await
+            .is_err()
+    );
+
+    let client = connect_postgres(server.port(), "yamlbase", "password", "HR")
+        .await
+        .unwrap();
+    assert_eq!(
+        first_value(&client, "SELECT name FROM employees").await,
+        "ann"
+    );
+
+    let error = connect_postgres(server.port(), "yamlbase", "password", "finance")
+        .await
+        .err()
+        .unwrap();
+    assert!(error.to_string().contains("does not exist"), "{}", error);
+}
+
+#[test]
+fn test_mysql_use() {
+    let sales = yaml_file(SALES);
+    let hr = yaml_file(HR);
+    let server = TestServer::start_mysql_with_args(
+        sales.path().to_str().unwrap(),
+        &["-f", hr.path().to_str().unwrap()],
+    );
+
+    let mut conn = connect_mysql(server.port(), "yamlbase", "password", "hr").unwrap();
+
+    let name: Option<String> = conn.query_first("SELECT name FROM employees").unwrap();
+    assert_eq!(name.as_deref(), Some("ann"));
+
+    // Each database keeps the changes the connection made to it
+    conn.query_drop("INSERT INTO employees VALUES (2, 'bo')")
+        .unwrap();
+    conn.query_drop("USE sales").unwrap();
+    let count: Option<i64> = conn.query_first("SELECT COUNT(*) FROM orders").unwrap();
+    assert_eq!(count, Some(2));
+    let database: Option<String> = conn.query_first("SELECT DATABASE()").unwrap();
+    assert_eq!(database.as_deref(), Some("sales"));
+
+    conn.select_db("hr").unwrap();
+    let count: Option<i64> = conn.query_first("SELECT COUNT(*) FROM employees").unwrap();
+    assert_eq!(count, Some(2));
+
+    assert!(conn.query_drop("USE finance").is_err());
+    assert!(conn.select_db("finance").is_err());
+}
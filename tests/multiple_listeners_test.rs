@@ -0,0 +1,102 @@
+use clap::Parser;
+use mysql::prelude::Queryable;
+use tonic::Request;
+use yamlbase::config::{Config, Protocol};
+use yamlbase::protocol::grpc::proto::ExecuteQueryRequest;
+use yamlbase::protocol::grpc::proto::value::Kind;
+use yamlbase::protocol::grpc::proto::yamlbase_client::YamlbaseClient;
+
+mod common;
+use common::{TestServer, connect_mysql, connect_postgres, yaml_file};
+
+const SHOP: &str = r#"
+database:
+  name: "shop"
+  auth:
+    username: "admin"
+    password: "password"
+
+tables:
+  products:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+      name: "VARCHAR(100)"
+    data:
+      - id: 1
+        name: "Widget"
+"#;
+
+#[tokio::test]
+async fn test_listeners_share_databases() {
+    let file = yaml_file(SHOP);
+    let (_server, ports) = TestServer::start_listeners(
+        file.path().to_str().unwrap(),
+        &[Protocol::Postgres, Protocol::Mysql, Protocol::Grpc],
+        &["--mutable"],
+    );
+
+    let client = connect_postgres(ports[0], "admin", "password", "shop")
+        .await
+        .unwrap();
+    client
+        .execute("INSERT INTO products (id, name) VALUES (2, 'Gadget')", &[])
+        .await
+        .unwrap();
+
+    // The row written over PostgreSQL is seen over MySQL and gRPC
+    let mysql_port = ports[1];
+    let names: Vec<String> = tokio::task::spawn_blocking(move || {
+        let mut conn = connect_mysql(mysql_port, "admin", "password", "shop").unwrap();
+        conn.query("SELECT name FROM products ORDER BY id").unwrap()
+    })
+    .await
+    .unwrap();
+    assert_eq!(names, ["Widget", "Gadget"]);
+
+    let mut grpc = YamlbaseClient::connect(format!("http://127.0.0.1:{}", ports[2]))
+        .await
+        .unwrap();
+    let mut request = Request::new(ExecuteQueryRequest {
+        sql: "SELECT name FROM products WHERE id = 2".to_string(),
+        database: String::new(),
+    });
+    // "admin:password"
+    request.metadata_mut().insert(
+        "authorization",
+        "Basic YWRtaW46cGFzc3dvcmQ=".parse().unwrap(),
+    );
+    let response = grpc.execute_query(request).await.unwrap().into_inner();
+    assert_eq!(
+        response.rows[0].values[0].kind,
+        Some(Kind::Text("Gadget".to_string()))
+    );
+}
+
+#[test]
+fn test_listener_options() {
+    let config = Config::try_parse_from([
+        "yamlbase",
+        "-f",
+        "db.yaml",
+        "--mysql-port",
+        "3307",
+        "--http-port",
+        "8081",
+    ])
+    .unwrap();
+    assert_eq!(
+        config.listeners(),
+        [(Protocol::Mysql, 3307), (Protocol::Http, 8081)]
+    );
+
+    let config =
+        Config::try_parse_from(["yamlbase", "-f", "db.yaml", "--protocol", "mysql"]).unwrap();
+    assert_eq!(config.listeners(), [(Protocol::Mysql, 3306)]);
+
+    // --protocol and --port name the single listener that the options replace
+    for conflicting in [["--port", "5433"], ["--protocol", "postgres"]] {
+        let mut args = vec!["yamlbase", "-f", "db.yaml", "--postgres-port", "5432"];
+        args.extend(conflicting);
+        assert!(Config::try_parse_from(args).is_err());
+    }
+}
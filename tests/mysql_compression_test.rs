@@ -0,0 +1,182 @@
+use flate2::write::ZlibEncoder;
+use mysql::prelude::Queryable;
+use mysql::{Conn, OptsBuilder};
+use std::io::{Read, Write};
+use std::net::TcpStream;
+
+mod common;
+use common::{_mysql_native_password_auth, TestServer, read_packet, write_packet, yaml_file};
+
+const YAML: &str = r#"
+database:
+  name: "shop"
+  auth:
+    username: "yamlbase"
+    password: "password"
+
+tables:
+  products:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+      description: "TEXT"
+    data:
+      - id: 1
+        description: "A ballpoint pen with blue ink that writes for two kilometres"
+      - id: 2
+        description: "A pad of squared paper"
+"#;
+
+const DESCRIPTION: &str = "A ballpoint pen with blue ink that writes for two kilometres";
+
+const CLIENT_CONNECT_WITH_DB: u32 = 0x00000008;
+const CLIENT_COMPRESS: u32 = 0x00000020;
+const CLIENT_PROTOCOL_41: u32 = 0x00000200;
+const CLIENT_SECURE_CONNECTION: u32 = 0x00008000;
+const CLIENT_PLUGIN_AUTH: u32 = 0x00080000;
+const CLIENT_ZSTD_COMPRESSION_ALGORITHM: u32 = 0x04000000;
+
+#[test]
+fn test_zlib_compression() {
+    let file = yaml_file(YAML);
+    let server = TestServer::start_mysql(file.path().to_str().unwrap());
+    let opts = OptsBuilder::new()
+        .ip_or_hostname(Some("127.0.0.1"))
+        .tcp_port(server.port())
+        .user(Some("yamlbase"))
+        .pass(Some("password"))
+        .db_name(Some("shop"))
+        .compress(Some(mysql::Compression::default()));
+    let mut conn = Conn::new(opts).unwrap();
+
+    // Packets long enough to be compressed, and short ones sent as they are
+    let descriptions: Vec<String> = conn
+        .query("SELECT description FROM products ORDER BY id")
+        .unwrap();
+    assert_eq!(descriptions, vec![DESCRIPTION, "A pad of squared paper"]);
+    let one: Option<i64> = conn.query_first("SELECT 1").unwrap();
+    assert_eq!(one, Some(1));
+
+    // A result larger than a compressed packet can hold uncompressed
+    let large: Option<String> = conn.query_first("SELECT REPEAT('pen', 6000000)").unwrap();
+    assert_eq!(large.map(|text| text.len()), Some(18_000_000));
+
+    let description: Option<String> = conn
+        .exec_first("SELECT description FROM products WHERE id = ?", (1,))
+        .unwrap();
+    assert_eq!(description.as_deref(), Some(DESCRIPTION));
+}
+
+// The zstd algorithm, which the mysql crate does not offer, over the raw protocol
+#[test]
+fn test_zstd_compression() {
+    let file = yaml_file(YAML);
+    let server = TestServer::start_mysql(file.path().to_str().unwrap());
+    let mut stream = login(&server, CLIENT_ZSTD_COMPRESSION_ALGORITHM);
+
+    let mut query = vec![0x03];
+    query.extend_from_slice(b"SELECT description FROM products WHERE id = 1 ORDER BY id");
+    let mut packet = (query.len() as u32).to_le_bytes()[..3].to_vec();
+    packet.push(0);
+    packet.extend_from_slice(&query);
+    let compressed = zstd::bulk::compress(&packet, 3).unwrap();
+    let mut frame = (compressed.len() as u32).to_le_bytes()[..3].to_vec();
+    frame.push(0);
+    frame.extend_from_slice(&(packet.len() as u32).to_le_bytes()[..3]);
+    frame.extend_from_slice(&compressed);
+    stream.write_all(&frame).unwrap();
+
+    // Column count, column definition, EOF, the row and EOF, in compressed packets
+    // numbered on from the client's
+    let mut data = Vec::new();
+    let mut packets = Vec::new();
+    let mut sequence_ids = Vec::new();
+    while packets.len() < 5 {
+        let mut header = [0u8; 7];
+        stream.read_exact(&mut header).unwrap();
+        sequence_ids.push(header[3]);
+        let length = u32::from_le_bytes([header[0], header[1], header[2], 0]) as usize;
+        let uncompressed_length = u32::from_le_bytes([header[4], header[5], header[6], 0]);
+        let mut payload = vec![0u8; length];
+        stream.read_exact(&mut payload).unwrap();
+        if uncompressed_length == 0 {
+            data.extend_from_slice(&payload);
+        } else {
+            data.extend(zstd::bulk::decompress(&payload, uncompressed_length as usize).unwrap());
+        }
+        while data.len() >= 4 {
+            let length = u32::from_le_bytes([data[0], data[1], data[2], 0]) as usize;
+            if data.len() < 4 + length {
+                break;
+            }
+            packets.push(data[4..4 + length].to_vec());
+            data.drain(..4 + length);
+        }
+    }
+    assert_eq!(sequence_ids[0], 1);
+    assert_eq!(packets[3][0] as usize, DESCRIPTION.len());
+    assert_eq!(&packets[3][1..], DESCRIPTION.as_bytes());
+    assert_eq!(packets[4][0], 0xfe);
+}
+
+// A zlib packet that inflates past the length it declares is refused without inflating it all
+#[test]
+fn test_zlib_packet_longer_than_declared() {
+    let file = yaml_file(YAML);
+    let server = TestServer::start_mysql(file.path().to_str().unwrap());
+    let mut stream = login(&server, CLIENT_COMPRESS);
+
+    let mut encoder = ZlibEncoder::new(Vec::new(), flate2::Compression::best());
+    encoder.write_all(&vec![0u8; 64 * 1024 * 1024]).unwrap();
+    let compressed = encoder.finish().unwrap();
+    let mut frame = (compressed.len() as u32).to_le_bytes()[..3].to_vec();
+    frame.push(0);
+    frame.extend_from_slice(&64u32.to_le_bytes()[..3]);
+    frame.extend_from_slice(&compressed);
+    stream.write_all(&frame).unwrap();
+
+    // The server drops the connection
+    let mut response = Vec::new();
+    let _ = stream.read_to_end(&mut response);
+    assert!(response.is_empty(), "{:?}", response);
+}
+
+/// Connect over the raw protocol and log in with the compression of `compression_flag`
+fn login(server: &TestServer, compression_flag: u32) -> TcpStream {
+    let mut stream = TcpStream::connect(("127.0.0.1", server.port())).unwrap();
+
+    let handshake = read_packet(&mut stream);
+    let version_end = 1 + handshake[1..].iter().position(|&b| b == 0).unwrap();
+    let mut auth_data = handshake[version_end + 5..version_end + 13].to_vec();
+    let capabilities_low = &handshake[version_end + 14..version_end + 16];
+    let capabilities_high = &handshake[version_end + 19..version_end + 21];
+    let capabilities = u32::from_le_bytes([
+        capabilities_low[0],
+        capabilities_low[1],
+        capabilities_high[0],
+        capabilities_high[1],
+    ]);
+    assert_ne!(capabilities & compression_flag, 0);
+    auth_data.extend_from_slice(&handshake[version_end + 32..version_end + 44]);
+
+    let flags = CLIENT_CONNECT_WITH_DB
+        | CLIENT_PROTOCOL_41
+        | CLIENT_SECURE_CONNECTION
+        | CLIENT_PLUGIN_AUTH
+        | compression_flag;
+    let mut response = flags.to_le_bytes().to_vec();
+    response.extend_from_slice(&0x0100_0000u32.to_le_bytes());
+    response.push(33);
+    response.extend_from_slice(&[0; 23]);
+    response.extend_from_slice(b"yamlbase\0");
+    response.push(20);
+    response.extend_from_slice(&_mysql_native_password_auth(&auth_data, "password"));
+    response.extend_from_slice(b"shop\0mysql_native_password\0");
+    if compression_flag == CLIENT_ZSTD_COMPRESSION_ALGORITHM {
+        // The zstd compression level
+        response.push(3);
+    }
+    write_packet(&mut stream, 1, &response);
+    // The OK of the login is the last packet sent uncompressed
+    assert_eq!(read_packet(&mut stream)[0], 0x00);
+    stream
+}
@@ -21,6 +21,7 @@ async fn test_mysql_large_result_set_no_connection_loss() {
             unique: true,
             default: None,
             references: None,
+            collation: None,
         },
         Column {
             name: "description".to_string(),
@@ -30,6 +31,7 @@ async fn test_mysql_large_result_set_no_connection_loss() {
             unique: false,
             default: None,
             references: None,
+            collation: None,
         },
         Column {
             name: "data".to_string(),
@@ -39,6 +41,7 @@ async fn test_mysql_large_result_set_no_connection_loss() {
             unique: false,
             default: None,
             references: None,
+            collation: None,
         },
         Column {
             name: "category".to_string(),
@@ -48,6 +51,7 @@ async fn test_mysql_large_result_set_no_connection_loss() {
             unique: false,
             default: None,
             references: None,
+            collation: None,
         },
     ];
 
@@ -134,6 +138,7 @@ async fn test_mysql_packet_splitting_edge_cases() {
             unique: true,
             default: None,
             references: None,
+            collation: None,
         },
         Column {
             name: "huge_text".to_string(),
@@ -143,6 +148,7 @@ async fn test_mysql_packet_splitting_edge_cases() {
             unique: false,
             default: None,
             references: None,
+            collation: None,
         },
     ];
 
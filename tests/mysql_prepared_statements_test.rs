@@ -0,0 +1,155 @@
+use mysql::prelude::Queryable;
+use mysql::{Row, Value};
+
+mod common;
+use common::{TestServer, connect_mysql, yaml_file};
+
+const YAML: &str = r#"
+database:
+  name: "shop"
+  auth:
+    username: "admin"
+    password: "secret"
+
+tables:
+  products:
+    columns:
+      id: "INTEGER PRIMARY KEY AUTO_INCREMENT"
+      sku: "VARCHAR(20)"
+      price: "DECIMAL(10,2)"
+      weight: "DOUBLE"
+      active: "BOOLEAN"
+      released: "DATE"
+      updated_at: "TIMESTAMP"
+      notes: "TEXT"
+    data:
+      - id: 1
+        sku: "PEN-1"
+        price: 1.5
+        weight: 0.25
+        active: true
+        released: "2024-01-01"
+        updated_at: "2024-05-01 12:30:00"
+      - id: 2
+        sku: "INK-1"
+        price: 12.0
+        weight: 0.5
+        active: false
+        released: "2024-02-01"
+        updated_at: "2024-05-02 08:00:00"
+        notes: "refill"
+"#;
+
+#[test]
+fn test_typed_rows() {
+    let file = yaml_file(YAML);
+    let server = TestServer::start_mysql(file.path().to_str().unwrap());
+    let mut conn = connect_mysql(server.port(), "admin", "secret", "shop").unwrap();
+
+    let row: Row = conn
+        .exec_first(
+            "SELECT id, sku, price, weight, active, released, updated_at, notes \
+             FROM products WHERE sku = ?",
+            ("PEN-1",),
+        )
+        .unwrap()
+        .unwrap();
+    assert_eq!(row[0], Value::Int(1));
+    assert_eq!(row[1], Value::Bytes(b"PEN-1".to_vec()));
+    assert_eq!(row.get::<f64, _>(2), Some(1.5));
+    assert_eq!(row[3], Value::Double(0.25));
+    assert_eq!(row[4], Value::Int(1));
+    assert_eq!(row[5], Value::Date(2024, 1, 1, 0, 0, 0, 0));
+    assert_eq!(row[6], Value::Date(2024, 5, 1, 12, 30, 0, 0));
+    assert_eq!(row[7], Value::NULL);
+}
+
+#[test]
+fn test_typed_parameters() {
+    let file = yaml_file(YAML);
+    let server = TestServer::start_mysql(file.path().to_str().unwrap());
+    let mut conn = connect_mysql(server.port(), "admin", "secret", "shop").unwrap();
+
+    let ids: Vec<i64> = conn
+        .exec(
+            "SELECT id FROM products WHERE released > ? AND price < ? ORDER BY id",
+            (Value::Date(2024, 1, 15, 0, 0, 0, 0), 20.0),
+        )
+        .unwrap();
+    assert_eq!(ids, vec![2]);
+    let ids: Vec<i64> = conn
+        .exec(
+            "SELECT id FROM products WHERE updated_at < ? OR id IN (?, ?)",
+            (Value::Date(2024, 5, 1, 18, 0, 0, 0), 7u64, -1i32),
+        )
+        .unwrap();
+    assert_eq!(ids, vec![1]);
+    let count: Option<i64> = conn
+        .exec_first(
+            "SELECT COUNT(*) FROM products WHERE notes = ? OR ? IS NULL",
+            ("refill", None::<String>),
+        )
+        .unwrap();
+    assert_eq!(count, Some(2));
+}
+
+#[test]
+fn test_writes() {
+    let file = yaml_file(YAML);
+    let server = TestServer::start_mysql(file.path().to_str().unwrap());
+    let mut conn = connect_mysql(server.port(), "admin", "secret", "shop").unwrap();
+
+    conn.exec_drop(
+        "INSERT INTO products (sku, price, active) VALUES (?, ?, ?)",
+        ("PAD-1", 3.25, true),
+    )
+    .unwrap();
+    assert_eq!(conn.affected_rows(), 1);
+    assert_eq!(conn.last_insert_id(), 3);
+
+    // One prepared statement, executed once for each set of parameters
+    let statement = conn
+        .prep("UPDATE products SET notes = ? WHERE id = ?")
+        .unwrap();
+    conn.exec_batch(&statement, [("first", 1), ("third", 3)])
+        .unwrap();
+    let notes: Vec<(i64, Option<String>)> = conn
+        .query("SELECT id, notes FROM products ORDER BY id")
+        .unwrap();
+    assert_eq!(
+        notes,
+        vec![
+            (1, Some("first".to_string())),
+            (2, Some("refill".to_string())),
+            (3, Some("third".to_string())),
+        ]
+    );
+}
+
+#[test]
+fn test_close_and_errors() {
+    let file = yaml_file(YAML);
+    let server = TestServer::start_mysql(file.path().to_str().unwrap());
+    let mut conn = connect_mysql(server.port(), "admin", "secret", "shop").unwrap();
+
+    let error = conn.prep("SELEC id FROM products").unwrap_err();
+    assert!(matches!(error, mysql::Error::MySqlError(ref e) if e.code == 1064));
+    let error = conn.prep("SELECT 1; SELECT 2").unwrap_err();
+    assert!(matches!(error, mysql::Error::MySqlError(ref e) if e.code == 1064));
+
+    let statement = conn.prep("SELECT sku FROM products WHERE id = ?").unwrap();
+    assert_eq!(statement.num_params(), 1);
+    assert_eq!(statement.columns()[0].name_str(), "sku");
+    let error = conn
+        .exec_drop("SELECT sku FROM missing WHERE id = ?", (1,))
+        .unwrap_err();
+    assert!(matches!(error, mysql::Error::MySqlError(_)));
+    let sku: Option<String> = conn.exec_first(&statement, (2,)).unwrap();
+    assert_eq!(sku.as_deref(), Some("INK-1"));
+
+    conn.close(statement.clone()).unwrap();
+    let error = conn
+        .exec_first::<String, _, _>(&statement, (2,))
+        .unwrap_err();
+    assert!(matches!(error, mysql::Error::MySqlError(ref e) if e.code == 1243));
+}
@@ -0,0 +1,164 @@
+use rsa::pkcs8::DecodePublicKey;
+use rsa::{Oaep, RsaPublicKey};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use std::net::TcpStream;
+
+mod common;
+use common::{TestServer, read_packet, write_packet, yaml_file};
+
+const CLIENT_CONNECT_WITH_DB: u32 = 0x00000008;
+const CLIENT_PROTOCOL_41: u32 = 0x00000200;
+const CLIENT_SECURE_CONNECTION: u32 = 0x00008000;
+const CLIENT_PLUGIN_AUTH: u32 = 0x00080000;
+
+/// The YAML of the database, with a user whose password is hashed
+fn yaml() -> String {
+    format!(
+        r#"
+database:
+  name: "shop"
+  auth:
+    username: "root"
+    password: "password"
+    users:
+      - username: "admin"
+        password_hash: '{}'
+
+tables:
+  products:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+    data:
+      - id: 1
+"#,
+        bcrypt::hash("secret", 4).unwrap()
+    )
+}
+
+/// How a caching_sha2_password login went: whether the server asked for the full
+/// authentication, and the packet that ended it
+struct Login {
+    stream: TcpStream,
+    full_auth: bool,
+    result: Vec<u8>,
+}
+
+// Log in with caching_sha2_password, answering the switch with the scramble of `scrambled`
+// and, when the server asks for the password itself, sending `password` encrypted with the
+// public key it sends
+fn login(server: &TestServer, user: &str, scrambled: &str, password: &str) -> Login {
+    let mut stream = TcpStream::connect(("127.0.0.1", server.port())).unwrap();
+    read_packet(&mut stream);
+
+    let flags =
+        CLIENT_CONNECT_WITH_DB | CLIENT_PROTOCOL_41 | CLIENT_SECURE_CONNECTION | CLIENT_PLUGIN_AUTH;
+    let mut response = flags.to_le_bytes().to_vec();
+    response.extend_from_slice(&0x0100_0000u32.to_le_bytes());
+    response.push(33);
+    response.extend_from_slice(&[0; 23]);
+    response.extend_from_slice(user.as_bytes());
+    response.extend_from_slice(&[0, 0]);
+    response.extend_from_slice(b"shop\0caching_sha2_password\0");
+    write_packet(&mut stream, 1, &response);
+
+    let switch = read_packet(&mut stream);
+    assert_eq!(switch[0], 0xfe);
+    let plugin_end = 1 + switch[1..].iter().position(|&b| b == 0).unwrap();
+    assert_eq!(&switch[1..plugin_end], b"caching_sha2_password");
+    let scramble = &switch[plugin_end + 1..switch.len() - 1];
+    write_packet(&mut stream, 3, &scramble_password(scrambled, scramble));
+
+    let status = read_packet(&mut stream);
+    if status == [0x01, 0x03] {
+        let result = read_packet(&mut stream);
+        return Login {
+            stream,
+            full_auth: false,
+            result,
+        };
+    }
+    assert_eq!(status, [0x01, 0x04]);
+
+    // Without TLS, ask for the public key rather than send the password in plain text
+    write_packet(&mut stream, 5, &[0x02]);
+    let public_key = read_packet(&mut stream);
+    assert_eq!(public_key[0], 0x01);
+    let pem = std::str::from_utf8(&public_key[1..]).unwrap();
+    assert!(pem.starts_with("-----BEGIN PUBLIC KEY-----"));
+    let public_key = RsaPublicKey::from_public_key_pem(pem).unwrap();
+
+    let mut plain = password.as_bytes().to_vec();
+    plain.push(0);
+    let xored: Vec<u8> = plain
+        .iter()
+        .zip(scramble.iter().cycle())
+        .map(|(byte, scramble)| byte ^ scramble)
+        .collect();
+    let encrypted = public_key
+        .encrypt(&mut rand::thread_rng(), Oaep::new::<Sha1>(), &xored)
+        .unwrap();
+    write_packet(&mut stream, 7, &encrypted);
+
+    let result = read_packet(&mut stream);
+    Login {
+        stream,
+        full_auth: true,
+        result,
+    }
+}
+
+#[test]
+fn test_full_authentication_with_rsa() {
+    let file = yaml_file(&yaml());
+    let server = TestServer::start_mysql(file.path().to_str().unwrap());
+
+    // The scramble of the right password is enough
+    let login_fast = login(&server, "root", "password", "");
+    assert!(!login_fast.full_auth);
+    assert_eq!(login_fast.result[0], 0x00);
+
+    // Otherwise the password is sent encrypted
+    let mut login_full = login(&server, "root", "wrong", "password");
+    assert!(login_full.full_auth);
+    assert_eq!(login_full.result[0], 0x00);
+    write_packet(&mut login_full.stream, 0, b"\x03SELECT id FROM products");
+    assert_eq!(read_packet(&mut login_full.stream), [0x01]);
+
+    let login_wrong = login(&server, "root", "wrong", "wrong");
+    assert_eq!(login_wrong.result[0], 0xff);
+    assert_eq!(
+        u16::from_le_bytes([login_wrong.result[1], login_wrong.result[2]]),
+        1045
+    );
+}
+
+// A hashed password cannot be checked against a scramble, so it always takes the full
+// authentication
+#[test]
+fn test_full_authentication_of_a_hash() {
+    let file = yaml_file(&yaml());
+    let server = TestServer::start_mysql(file.path().to_str().unwrap());
+
+    let login_hash = login(&server, "admin", "secret", "secret");
+    assert!(login_hash.full_auth);
+    assert_eq!(login_hash.result[0], 0x00);
+
+    let login_wrong = login(&server, "admin", "secret", "wrong");
+    assert!(login_wrong.full_auth);
+    assert_eq!(login_wrong.result[0], 0xff);
+}
+
+fn scramble_password(password: &str, scramble: &[u8]) -> Vec<u8> {
+    let stage1 = Sha256::digest(password.as_bytes());
+    let stage2 = Sha256::digest(stage1);
+    let mut hasher = Sha256::new();
+    hasher.update(stage2);
+    hasher.update(scramble);
+    let stage3 = hasher.finalize();
+    stage1
+        .iter()
+        .zip(stage3.iter())
+        .map(|(a, b)| a ^ b)
+        .collect()
+}
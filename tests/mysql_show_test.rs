@@ -0,0 +1,241 @@
+use mysql::Row;
+use mysql::prelude::Queryable;
+
+mod common;
+use common::{TestServer, connect_mysql, yaml_file};
+
+const SHOP: &str = r#"
+database:
+  name: "shop"
+  auth:
+    username: "yamlbase"
+    password: "password"
+
+tables:
+  customers:
+    columns:
+      id: "INTEGER PRIMARY KEY AUTO_INCREMENT"
+      email: "VARCHAR(100) UNIQUE NOT NULL"
+      name: "TEXT"
+    data:
+      - id: 1
+        email: "ann@example.com"
+        name: "Ann"
+  orders:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+      customer_id: "INTEGER REFERENCES customers(id)"
+      total: "DECIMAL(10,2) DEFAULT 0"
+    data:
+      - id: 1
+        customer_id: 1
+        total: 9.5
+  archive.orders:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+"#;
+
+const HR: &str = r#"
+database:
+  name: "hr"
+
+tables:
+  employees:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+"#;
+
+#[test]
+fn test_show_databases_and_tables() {
+    let shop = yaml_file(SHOP);
+    let hr = yaml_file(HR);
+    let server = TestServer::start_mysql_with_args(
+        shop.path().to_str().unwrap(),
+        &["-f", hr.path().to_str().unwrap()],
+    );
+    let mut conn = connect_mysql(server.port(), "yamlbase", "password", "shop").unwrap();
+
+    // The databases served and the schemas of the current one
+    let databases: Vec<String> = conn.query("SHOW DATABASES").unwrap();
+    assert_eq!(
+        databases,
+        vec!["information_schema", "archive", "hr", "shop"]
+    );
+    let databases: Vec<String> = conn.query("SHOW DATABASES LIKE 's%'").unwrap();
+    assert_eq!(databases, vec!["shop"]);
+
+    let result = conn.query_iter("SHOW TABLES").unwrap();
+    assert_eq!(result.columns().as_ref()[0].name_str(), "Tables_in_shop");
+    drop(result);
+    let tables: Vec<String> = conn.query("SHOW TABLES").unwrap();
+    assert_eq!(tables, vec!["customers", "orders"]);
+    let tables: Vec<String> = conn.query("SHOW TABLES LIKE 'cust%'").unwrap();
+    assert_eq!(tables, vec!["customers"]);
+    let tables: Vec<(String, String)> = conn
+        .query("SHOW FULL TABLES WHERE Tables_in_shop <> 'customers'")
+        .unwrap();
+    assert_eq!(
+        tables,
+        vec![("orders".to_string(), "BASE TABLE".to_string())]
+    );
+
+    // Another database or a schema, named or switched to
+    let tables: Vec<String> = conn.query("SHOW TABLES FROM hr").unwrap();
+    assert_eq!(tables, vec!["employees"]);
+    let tables: Vec<String> = conn.query("SHOW TABLES IN archive").unwrap();
+    assert_eq!(tables, vec!["orders"]);
+    conn.query_drop("USE archive").unwrap();
+    let tables: Vec<String> = conn.query("SHOW TABLES").unwrap();
+    assert_eq!(tables, vec!["orders"]);
+
+    let error = conn.query_drop("SHOW TABLES FROM missing").unwrap_err();
+    assert!(error.to_string().contains("Unknown database 'missing'"));
+}
+
+#[test]
+fn test_show_columns() {
+    let shop = yaml_file(SHOP);
+    let server = TestServer::start_mysql(shop.path().to_str().unwrap());
+    let mut conn = connect_mysql(server.port(), "yamlbase", "password", "shop").unwrap();
+
+    type ColumnRow = (String, String, String, String, Option<String>, String);
+    let columns: Vec<ColumnRow> = conn.query("SHOW COLUMNS FROM customers").unwrap();
+    assert_eq!(
+        columns,
+        vec![
+            (
+                "id".to_string(),
+                "int".to_string(),
+                "NO".to_string(),
+                "PRI".to_string(),
+                None,
+                "auto_increment".to_string()
+            ),
+            (
+                "email".to_string(),
+                "varchar(100)".to_string(),
+                "NO".to_string(),
+                "UNI".to_string(),
+                None,
+                String::new()
+            ),
+            (
+                "name".to_string(),
+                "text".to_string(),
+                "YES".to_string(),
+                String::new(),
+                None,
+                String::new()
+            ),
+        ]
+    );
+
+    let columns: Vec<ColumnRow> = conn
+        .query("SHOW COLUMNS FROM orders FROM shop WHERE Field LIKE '%total%'")
+        .unwrap();
+    assert_eq!(columns[0].1, "decimal(10,2)");
+    assert_eq!(columns[0].4.as_deref(), Some("0"));
+
+    let row: Row = conn
+        .query_first("SHOW FULL COLUMNS FROM shop.customers LIKE 'email'")
+        .unwrap()
+        .unwrap();
+    assert_eq!(row.columns_ref().len(), 9);
+    assert_eq!(row.get::<String, _>("Collation").unwrap(), "utf8mb4_bin");
+    assert_eq!(row.get::<String, _>("Key").unwrap(), "UNI");
+
+    let error = conn.query_drop("SHOW COLUMNS FROM missing").unwrap_err();
+    assert!(error.to_string().contains("Table 'missing' not found"));
+}
+
+#[test]
+fn test_show_index() {
+    let shop = yaml_file(SHOP);
+    let server = TestServer::start_mysql(shop.path().to_str().unwrap());
+    let mut conn = connect_mysql(server.port(), "yamlbase", "password", "shop").unwrap();
+
+    let indexes: Vec<(String, i64, String, i64, String)> = conn
+        .query("SHOW INDEX FROM customers")
+        .unwrap()
+        .into_iter()
+        .map(|row: Row| {
+            (
+                row.get("Table").unwrap(),
+                row.get("Non_unique").unwrap(),
+                row.get("Key_name").unwrap(),
+                row.get("Seq_in_index").unwrap(),
+                row.get("Column_name").unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        indexes,
+        vec![
+            (
+                "customers".to_string(),
+                0,
+                "PRIMARY".to_string(),
+                1,
+                "id".to_string()
+            ),
+            (
+                "customers".to_string(),
+                0,
+                "email".to_string(),
+                1,
+                "email".to_string()
+            ),
+        ]
+    );
+
+    // Foreign keys are indexed, but not uniquely
+    let indexes: Vec<Row> = conn.query("SHOW KEYS FROM orders FROM shop").unwrap();
+    assert_eq!(indexes.len(), 2);
+    assert_eq!(
+        indexes[1].get::<String, _>("Key_name").unwrap(),
+        "customer_id"
+    );
+    assert_eq!(indexes[1].get::<i64, _>("Non_unique").unwrap(), 1);
+    let indexes: Vec<Row> = conn.query("SHOW INDEXES IN archive.orders").unwrap();
+    assert_eq!(indexes.len(), 1);
+}
+
+#[test]
+fn test_describe() {
+    let shop = yaml_file(SHOP);
+    let server = TestServer::start_mysql(shop.path().to_str().unwrap());
+    let mut conn = connect_mysql(server.port(), "yamlbase", "password", "shop").unwrap();
+
+    let show: Vec<Row> = conn.query("SHOW COLUMNS FROM orders").unwrap();
+    for query in [
+        "DESCRIBE orders",
+        "DESC orders",
+        "EXPLAIN orders",
+        "DESC `shop`.`orders`",
+    ] {
+        let describe: Vec<Row> = conn.query(query).unwrap();
+        assert_eq!(describe.len(), 3, "{}", query);
+        for (described, shown) in describe.iter().zip(&show) {
+            assert_eq!(
+                described.clone().unwrap(),
+                shown.clone().unwrap(),
+                "{}",
+                query
+            );
+        }
+    }
+    let first: Row = conn.query_first("DESCRIBE customers").unwrap().unwrap();
+    let names: Vec<String> = first
+        .columns_ref()
+        .iter()
+        .map(|column| column.name_str().to_string())
+        .collect();
+    assert_eq!(names, ["Field", "Type", "Null", "Key", "Default", "Extra"]);
+    assert_eq!(first.get::<String, _>("Key").unwrap(), "PRI");
+
+    let columns: Vec<Row> = conn.query("DESCRIBE archive.orders").unwrap();
+    assert_eq!(columns.len(), 1);
+    assert_eq!(columns[0].get::<String, _>("Field").unwrap(), "id");
+    let error = conn.query_drop("DESCRIBE missing").unwrap_err();
+    assert!(error.to_string().contains("Table 'missing' not found"));
+}
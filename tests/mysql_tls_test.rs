@@ -0,0 +1,80 @@
+use mysql::prelude::Queryable;
+use mysql::{Conn, OptsBuilder, SslOpts};
+
+mod common;
+use common::{TestServer, yaml_file};
+
+const YAML: &str = r#"
+database:
+  name: "shop"
+  auth:
+    username: "yamlbase"
+    password: "password"
+
+tables:
+  products:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+      name: "TEXT"
+    data:
+      - id: 1
+        name: "pen"
+"#;
+
+fn connect(server: &TestServer, ssl_opts: Option<SslOpts>) -> mysql::Result<Conn> {
+    let opts = OptsBuilder::new()
+        .ip_or_hostname(Some("localhost"))
+        .tcp_port(server.port())
+        .user(Some("yamlbase"))
+        .pass(Some("password"))
+        .db_name(Some("shop"))
+        .ssl_opts(ssl_opts);
+    Conn::new(opts)
+}
+
+fn product_name(conn: &mut Conn) -> String {
+    conn.query_first("SELECT name FROM products")
+        .unwrap()
+        .unwrap()
+}
+
+#[test]
+fn test_tls_with_configured_certificate() {
+    let certified = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+    let cert = yaml_file(&certified.cert.pem());
+    let key = yaml_file(&certified.key_pair.serialize_pem());
+    let yaml = yaml_file(YAML);
+    let server = TestServer::start_mysql_with_args(
+        yaml.path().to_str().unwrap(),
+        &[
+            "--tls-cert",
+            cert.path().to_str().unwrap(),
+            "--tls-key",
+            key.path().to_str().unwrap(),
+        ],
+    );
+
+    // The client trusts the configured certificate alone, so the connection is encrypted
+    // with it
+    let ssl_opts = SslOpts::default().with_root_cert_path(Some(cert.path().to_path_buf()));
+    let mut conn = connect(&server, Some(ssl_opts)).unwrap();
+    assert_eq!(product_name(&mut conn), "pen");
+
+    // Clients that do not ask for TLS can still connect
+    let mut conn = connect(&server, None).unwrap();
+    assert_eq!(product_name(&mut conn), "pen");
+}
+
+#[test]
+fn test_tls_with_self_signed_certificate() {
+    let yaml = yaml_file(YAML);
+    let server = TestServer::start_mysql(yaml.path().to_str().unwrap());
+
+    // Accepts any certificate, as clients with ssl-mode=REQUIRED do
+    let ssl_opts = SslOpts::default().with_danger_accept_invalid_certs(true);
+    let mut conn = connect(&server, Some(ssl_opts)).unwrap();
+    assert_eq!(product_name(&mut conn), "pen");
+
+    // A certificate the client does not trust fails the handshake
+    assert!(connect(&server, Some(SslOpts::default())).is_err());
+}
@@ -0,0 +1,120 @@
+use mysql::prelude::Queryable;
+
+mod common;
+use common::{TestServer, connect_mysql, yaml_file};
+
+const SHOP: &str = r#"
+database:
+  name: "shop"
+  auth:
+    username: "yamlbase"
+    password: "password"
+
+tables:
+  orders:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+"#;
+
+const HR: &str = r#"
+database:
+  name: "hr"
+
+tables:
+  employees:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+"#;
+
+#[test]
+fn test_show_variables() {
+    let shop = yaml_file(SHOP);
+    let server = TestServer::start_mysql(shop.path().to_str().unwrap());
+    let mut conn = connect_mysql(server.port(), "yamlbase", "password", "shop").unwrap();
+
+    let variables: Vec<(String, String)> = conn.query("SHOW VARIABLES").unwrap();
+    assert!(variables.contains(&("autocommit".to_string(), "ON".to_string())));
+    assert!(variables.contains(&("max_allowed_packet".to_string(), "67108864".to_string())));
+
+    let variables: Vec<(String, String)> = conn
+        .query("SHOW SESSION VARIABLES LIKE 'character_set_c%'")
+        .unwrap();
+    assert_eq!(
+        variables,
+        vec![
+            ("character_set_client".to_string(), "utf8mb4".to_string()),
+            (
+                "character_set_connection".to_string(),
+                "utf8mb4".to_string()
+            ),
+        ]
+    );
+    let variables: Vec<(String, String)> = conn
+        .query("SHOW GLOBAL VARIABLES WHERE Variable_name = 'time_zone'")
+        .unwrap();
+    assert_eq!(
+        variables,
+        vec![("time_zone".to_string(), "SYSTEM".to_string())]
+    );
+}
+
+#[test]
+fn test_set_variables() {
+    let shop = yaml_file(SHOP);
+    let hr = yaml_file(HR);
+    let server = TestServer::start_mysql_with_args(
+        shop.path().to_str().unwrap(),
+        &["-f", hr.path().to_str().unwrap()],
+    );
+    let mut conn = connect_mysql(server.port(), "yamlbase", "password", "shop").unwrap();
+
+    // The assignments a JDBC driver sends when it connects, in one statement
+    conn.query_drop(
+        "SET autocommit = 0, sql_mode = 'strict_trans_tables, ansi_quotes', \
+         @@session.time_zone := '+02:00', @last = 1",
+    )
+    .unwrap();
+    let (autocommit, sql_mode, time_zone): (i64, String, String) = conn
+        .query_first("SELECT @@autocommit, @@sql_mode, @@session.time_zone")
+        .unwrap()
+        .unwrap();
+    assert_eq!(autocommit, 0);
+    assert_eq!(sql_mode, "STRICT_TRANS_TABLES,ANSI_QUOTES");
+    assert_eq!(time_zone, "+02:00");
+
+    // GLOBAL applies to the connection too, and values read other variables
+    conn.query_drop("SET GLOBAL autocommit = ON").unwrap();
+    conn.query_drop("SET @@GLOBAL.sql_mode = CONCAT(@@sql_mode, ',NO_ZERO_DATE')")
+        .unwrap();
+    let variables: Vec<(String, String)> = conn.query("SHOW VARIABLES LIKE 'sql_mode'").unwrap();
+    assert_eq!(
+        variables[0].1,
+        "STRICT_TRANS_TABLES,ANSI_QUOTES,NO_ZERO_DATE"
+    );
+
+    // The variables stay set in another database of the connection
+    conn.query_drop("USE hr").unwrap();
+    let autocommit: Option<String> = conn.query_first("SELECT @@autocommit").unwrap();
+    assert_eq!(autocommit.as_deref(), Some("1"));
+    conn.query_drop("SET time_zone = DEFAULT").unwrap();
+    let time_zone: Option<String> = conn.query_first("SELECT @@time_zone").unwrap();
+    assert_eq!(time_zone.as_deref(), Some("SYSTEM"));
+
+    // Another connection has the defaults
+    let mut other = connect_mysql(server.port(), "yamlbase", "password", "shop").unwrap();
+    let sql_mode: Option<String> = other.query_first("SELECT @@sql_mode").unwrap();
+    assert!(sql_mode.unwrap().starts_with("ONLY_FULL_GROUP_BY"));
+
+    let error = conn.query_drop("SET autocommit = 'maybe'").unwrap_err();
+    assert!(
+        error
+            .to_string()
+            .contains("can't be set to the value of 'maybe'")
+    );
+    let error = conn.query_drop("SET @@version = '9'").unwrap_err();
+    assert!(error.to_string().contains("read only variable"));
+    let error = conn
+        .query_drop("SET time_zone = 'Nowhere/Else'")
+        .unwrap_err();
+    assert!(error.to_string().contains("Unknown or incorrect time zone"));
+}
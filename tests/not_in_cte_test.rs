@@ -22,6 +22,7 @@ async fn test_not_in_cte_simple() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "project_status_code".to_string(),
@@ -31,6 +32,7 @@ async fn test_not_in_cte_simple() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "wbi_id".to_string(),
@@ -40,6 +42,7 @@ async fn test_not_in_cte_simple() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "planned_effort_hours".to_string(),
@@ -49,6 +52,7 @@ async fn test_not_in_cte_simple() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
         ],
     );
@@ -0,0 +1,86 @@
+use std::path::Path;
+use yamlbase::yaml::parse_yaml_database;
+
+#[cfg(not(feature = "object-store"))]
+#[tokio::test]
+async fn test_object_store_needs_feature() {
+    let error = parse_yaml_database(Path::new("s3://fixtures/db.yaml"))
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("'object-store' feature"), "{}", error);
+}
+
+#[cfg(feature = "object-store")]
+#[tokio::test]
+async fn test_load_from_s3() {
+    use std::collections::HashMap;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    let files = HashMap::from([
+        (
+            "/fixtures/app/db.yaml",
+            "database:\n  name: \"s3_db\"\ntables:\n  orders:\n    columns:\n      id: \"INTEGER PRIMARY KEY\"\n    data_file: orders.csv\n",
+        ),
+        ("/fixtures/app/orders.csv", "id\n1\n2\n"),
+    ]);
+
+    // A stand-in for S3 answering path-style GETs of the objects above
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        loop {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let files = files.clone();
+            tokio::spawn(async move {
+                let mut request = Vec::new();
+                let mut buffer = [0; 1024];
+                while !request.ends_with(b"\r\n\r\n") {
+                    let n = stream.read(&mut buffer).await.unwrap();
+                    if n == 0 {
+                        return;
+                    }
+                    request.extend_from_slice(&buffer[..n]);
+                }
+                let request = String::from_utf8_lossy(&request);
+                let path = request.split_whitespace().nth(1).unwrap_or("");
+                let response = match files.get(path) {
+                    Some(body) => format!(
+                        "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nETag: \"1\"\r\n\
+                         Last-Modified: Thu, 01 Jan 2026 00:00:00 GMT\r\n\
+                         Connection: close\r\n\r\n{}",
+                        body.len(),
+                        body
+                    ),
+                    None => {
+                        "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                            .to_string()
+                    }
+                };
+                let _ = stream.write_all(response.as_bytes()).await;
+            });
+        }
+    });
+
+    // Credentials and endpoint come from the environment
+    unsafe {
+        std::env::set_var("AWS_ENDPOINT", format!("http://{}", address));
+        std::env::set_var("AWS_ALLOW_HTTP", "true");
+        std::env::set_var("AWS_REGION", "us-east-1");
+        std::env::set_var("AWS_ACCESS_KEY_ID", "test");
+        std::env::set_var("AWS_SECRET_ACCESS_KEY", "test");
+    }
+
+    let (db, _) = parse_yaml_database(Path::new("s3://fixtures/app/db.yaml"))
+        .await
+        .unwrap();
+    assert_eq!(db.name, "s3_db");
+    assert_eq!(db.get_table("orders").unwrap().rows.len(), 2);
+
+    assert!(
+        parse_yaml_database(Path::new("s3://fixtures/app/missing.yaml"))
+            .await
+            .is_err()
+    );
+}
@@ -0,0 +1,168 @@
+use yamlbase::database::{Database, Table, Value};
+use yamlbase::sql::{QueryExecutor, parse_sql};
+use yamlbase::yaml::schema::SqlType;
+
+mod common;
+use common::{column, new_executor};
+
+async fn create_executor() -> QueryExecutor {
+    let mut db = Database::new("test_db".to_string());
+
+    let mut products = Table::new(
+        "products".to_string(),
+        vec![
+            column("id", SqlType::Integer, true),
+            column("name", SqlType::Varchar(50), false),
+            column("category", SqlType::Varchar(50), false),
+            column("price", SqlType::Integer, false),
+            column("shipping", SqlType::Integer, false),
+            column("rating", SqlType::Integer, false),
+        ],
+    );
+    let rows = [
+        (1, "banana", "fruit", 3, 5, Some(4)),
+        (2, "Apple", "fruit", 4, 1, None),
+        (3, "cherry", "fruit", 10, 2, Some(5)),
+        (4, "Broccoli", "vegetable", 2, 1, Some(3)),
+    ];
+    for (id, name, category, price, shipping, rating) in rows {
+        products
+            .insert_row(vec![
+                Value::Integer(id),
+                Value::Text(name.to_string()),
+                Value::Text(category.to_string()),
+                Value::Integer(price),
+                Value::Integer(shipping),
+                rating.map(Value::Integer).unwrap_or(Value::Null),
+            ])
+            .unwrap();
+    }
+    db.add_table(products).unwrap();
+
+    let mut categories = Table::new(
+        "categories".to_string(),
+        vec![
+            column("name", SqlType::Varchar(50), true),
+            column("priority", SqlType::Integer, false),
+        ],
+    );
+    categories
+        .insert_row(vec![Value::Text("fruit".to_string()), Value::Integer(2)])
+        .unwrap();
+    categories
+        .insert_row(vec![
+            Value::Text("vegetable".to_string()),
+            Value::Integer(1),
+        ])
+        .unwrap();
+    db.add_table(categories).unwrap();
+
+    new_executor(db).await
+}
+
+async fn query_ids(executor: &QueryExecutor, sql: &str) -> Vec<Value> {
+    let statements = parse_sql(sql).unwrap();
+    let result = executor.execute(&statements[0]).await.unwrap();
+    result.rows.into_iter().map(|row| row[0].clone()).collect()
+}
+
+fn ids(values: &[i64]) -> Vec<Value> {
+    values.iter().map(|v| Value::Integer(*v)).collect()
+}
+
+#[tokio::test]
+async fn test_order_by_function_and_arithmetic_expressions() {
+    let executor = create_executor().await;
+
+    assert_eq!(
+        query_ids(&executor, "SELECT id FROM products ORDER BY LOWER(name)").await,
+        ids(&[2, 1, 4, 3])
+    );
+    assert_eq!(
+        query_ids(
+            &executor,
+            "SELECT id, name FROM products ORDER BY price + shipping DESC"
+        )
+        .await,
+        ids(&[3, 1, 2, 4])
+    );
+}
+
+#[tokio::test]
+async fn test_order_by_column_positions() {
+    let executor = create_executor().await;
+
+    assert_eq!(
+        query_ids(&executor, "SELECT id, price FROM products ORDER BY 2 DESC").await,
+        ids(&[3, 2, 1, 4])
+    );
+    assert_eq!(
+        query_ids(
+            &executor,
+            "SELECT id, category, price FROM products ORDER BY 2, 3"
+        )
+        .await,
+        ids(&[1, 2, 3, 4])
+    );
+
+    let statements = parse_sql("SELECT id FROM products ORDER BY 3").unwrap();
+    assert!(executor.execute(&statements[0]).await.is_err());
+}
+
+#[tokio::test]
+async fn test_order_by_alias_and_unprojected_column() {
+    let executor = create_executor().await;
+
+    assert_eq!(
+        query_ids(
+            &executor,
+            "SELECT id, price * 2 AS doubled FROM products ORDER BY doubled"
+        )
+        .await,
+        ids(&[4, 1, 2, 3])
+    );
+    assert_eq!(
+        query_ids(
+            &executor,
+            "SELECT id FROM products ORDER BY shipping, id DESC"
+        )
+        .await,
+        ids(&[4, 2, 3, 1])
+    );
+    assert_eq!(
+        query_ids(
+            &executor,
+            "SELECT id FROM products ORDER BY rating DESC NULLS LAST"
+        )
+        .await,
+        ids(&[3, 1, 4, 2])
+    );
+}
+
+#[tokio::test]
+async fn test_order_by_on_aggregate_and_join_results() {
+    let executor = create_executor().await;
+
+    let statements = parse_sql(
+        "SELECT category, COUNT(*) AS cnt FROM products GROUP BY category ORDER BY COUNT(*) DESC",
+    )
+    .unwrap();
+    let result = executor.execute(&statements[0]).await.unwrap();
+    assert_eq!(result.rows[0][0], Value::Text("fruit".to_string()));
+
+    let statements =
+        parse_sql("SELECT category, SUM(price) FROM products GROUP BY category ORDER BY 2")
+            .unwrap();
+    let result = executor.execute(&statements[0]).await.unwrap();
+    assert_eq!(result.rows[0][0], Value::Text("vegetable".to_string()));
+
+    assert_eq!(
+        query_ids(
+            &executor,
+            "SELECT p.id, c.priority FROM products p JOIN categories c ON p.category = c.name \
+             ORDER BY c.priority, p.price DESC"
+        )
+        .await,
+        ids(&[4, 3, 2, 1])
+    );
+}
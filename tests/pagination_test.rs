@@ -0,0 +1,165 @@
+use yamlbase::database::{Database, Table, Value};
+use yamlbase::sql::{QueryExecutor, parse_sql};
+use yamlbase::yaml::schema::SqlType;
+
+mod common;
+use common::{column, new_executor};
+
+async fn create_executor() -> QueryExecutor {
+    let mut db = Database::new("test_db".to_string());
+
+    let mut items = Table::new(
+        "items".to_string(),
+        vec![
+            column("id", SqlType::Integer, true),
+            column("category", SqlType::Varchar(20), false),
+        ],
+    );
+    for id in 1..=10 {
+        let category = if id % 2 == 0 { "even" } else { "odd" };
+        items
+            .insert_row(vec![Value::Integer(id), Value::Text(category.to_string())])
+            .unwrap();
+    }
+    db.add_table(items).unwrap();
+
+    new_executor(db).await
+}
+
+async fn query_ids(executor: &QueryExecutor, sql: &str) -> Vec<i64> {
+    let statements = parse_sql(sql).unwrap();
+    let result = executor.execute(&statements[0]).await.unwrap();
+    result
+        .rows
+        .into_iter()
+        .map(|row| match row[0] {
+            Value::Integer(id) => id,
+            ref other => panic!("unexpected value {other:?}"),
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn test_limit_with_offset() {
+    let executor = create_executor().await;
+
+    assert_eq!(
+        query_ids(
+            &executor,
+            "SELECT id FROM items ORDER BY id LIMIT 3 OFFSET 4"
+        )
+        .await,
+        vec![5, 6, 7]
+    );
+    assert_eq!(
+        query_ids(&executor, "SELECT id FROM items ORDER BY id OFFSET 8").await,
+        vec![9, 10]
+    );
+    assert!(
+        query_ids(
+            &executor,
+            "SELECT id FROM items ORDER BY id LIMIT 5 OFFSET 20"
+        )
+        .await
+        .is_empty()
+    );
+}
+
+#[tokio::test]
+async fn test_fetch_first_rows_only() {
+    let executor = create_executor().await;
+
+    // Hibernate's SQL Server / Oracle 12c pagination style
+    assert_eq!(
+        query_ids(
+            &executor,
+            "SELECT id FROM items ORDER BY id DESC OFFSET 2 ROWS FETCH NEXT 3 ROWS ONLY"
+        )
+        .await,
+        vec![8, 7, 6]
+    );
+    assert_eq!(
+        query_ids(
+            &executor,
+            "SELECT id FROM items ORDER BY id FETCH FIRST 2 ROWS ONLY"
+        )
+        .await,
+        vec![1, 2]
+    );
+    assert_eq!(
+        query_ids(
+            &executor,
+            "SELECT id FROM items ORDER BY id FETCH FIRST ROW ONLY"
+        )
+        .await,
+        vec![1]
+    );
+    assert_eq!(
+        query_ids(
+            &executor,
+            "SELECT id FROM items ORDER BY id FETCH FIRST 25 PERCENT ROWS ONLY"
+        )
+        .await,
+        vec![1, 2, 3]
+    );
+}
+
+#[tokio::test]
+async fn test_offset_fetch_on_aggregates_and_joins() {
+    let executor = create_executor().await;
+
+    let statements = parse_sql(
+        "SELECT category, COUNT(*) FROM items GROUP BY category ORDER BY category \
+         OFFSET 1 ROWS FETCH NEXT 1 ROWS ONLY",
+    )
+    .unwrap();
+    let result = executor.execute(&statements[0]).await.unwrap();
+    assert_eq!(result.rows.len(), 1);
+    assert_eq!(result.rows[0][0], Value::Text("odd".to_string()));
+
+    assert_eq!(
+        query_ids(
+            &executor,
+            "SELECT a.id FROM items a JOIN items b ON a.id = b.id ORDER BY a.id \
+             LIMIT 2 OFFSET 3"
+        )
+        .await,
+        vec![4, 5]
+    );
+
+    let statements = parse_sql("SELECT id FROM items OFFSET -1").unwrap();
+    assert!(executor.execute(&statements[0]).await.is_err());
+}
+
+#[tokio::test]
+async fn test_select_top() {
+    let executor = create_executor().await;
+
+    assert_eq!(
+        query_ids(&executor, "SELECT TOP 3 id FROM items ORDER BY id DESC").await,
+        vec![10, 9, 8]
+    );
+    assert_eq!(
+        query_ids(&executor, "SELECT TOP (2) id FROM items ORDER BY id").await,
+        vec![1, 2]
+    );
+    assert_eq!(
+        query_ids(&executor, "SELECT TOP 20 PERCENT id FROM items ORDER BY id").await,
+        vec![1, 2]
+    );
+    assert_eq!(
+        query_ids(
+            &executor,
+            "SELECT DISTINCT TOP 1 a.id FROM items a JOIN items b ON a.id = b.id ORDER BY a.id"
+        )
+        .await,
+        vec![1]
+    );
+
+    let statements =
+        parse_sql("SELECT TOP 1 category, COUNT(*) FROM items GROUP BY category ORDER BY category")
+            .unwrap();
+    let result = executor.execute(&statements[0]).await.unwrap();
+    assert_eq!(result.rows.len(), 1);
+    assert_eq!(result.rows[0][0], Value::Text("even".to_string()));
+}
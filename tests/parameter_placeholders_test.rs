@@ -0,0 +1,134 @@
+use yamlbase::database::{Database, Table, Value};
+use yamlbase::sql::{QueryExecutor, SqlDialect, parse_sql, parse_sql_with_dialect};
+use yamlbase::yaml::schema::SqlType;
+
+mod common;
+use common::{column, new_executor};
+
+async fn create_executor() -> QueryExecutor {
+    let mut db = Database::new("test_db".to_string());
+
+    let mut products = Table::new(
+        "products".to_string(),
+        vec![
+            column("id", SqlType::Integer, true),
+            column("name", SqlType::Text, false),
+            column("price", SqlType::Double, false),
+        ],
+    );
+    for (id, name, price) in [(1, "pen", 1.5), (2, "book", 12.0), (3, "lamp", 30.0)] {
+        products
+            .insert_row(vec![
+                Value::Integer(id),
+                Value::Text(name.to_string()),
+                Value::Double(price),
+            ])
+            .unwrap();
+    }
+    db.add_table(products).unwrap();
+
+    new_executor(db).await
+}
+
+async fn query(
+    executor: &QueryExecutor,
+    sql: &str,
+    dialect: SqlDialect,
+    parameters: &[Value],
+) -> yamlbase::Result<Vec<Vec<Value>>> {
+    let statements = parse_sql_with_dialect(sql, dialect).unwrap();
+    executor
+        .execute_with_parameters(&statements[0], parameters)
+        .await
+        .map(|result| result.rows)
+}
+
+fn text(value: &str) -> Value {
+    Value::Text(value.to_string())
+}
+
+#[tokio::test]
+async fn test_numbered_placeholders() {
+    let executor = create_executor().await;
+
+    let rows = query(
+        &executor,
+        "SELECT name, price * $2 FROM products WHERE price > $1 ORDER BY id",
+        SqlDialect::PostgreSQL,
+        &[Value::Double(10.0), Value::Integer(2)],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        rows,
+        vec![
+            vec![text("book"), Value::Double(24.0)],
+            vec![text("lamp"), Value::Double(60.0)],
+        ]
+    );
+
+    // A parameter can be used more than once, in subqueries and in LIMIT
+    let rows = query(
+        &executor,
+        "SELECT name FROM products WHERE name IN ($1, $2) \
+         OR price > (SELECT MAX(price) FROM products WHERE name <> $1) - $3 ORDER BY id LIMIT $4",
+        SqlDialect::PostgreSQL,
+        &[
+            text("pen"),
+            text("it's"),
+            Value::Integer(1),
+            Value::Integer(2),
+        ],
+    )
+    .await
+    .unwrap();
+    assert_eq!(rows, vec![vec![text("pen")], vec![text("lamp")]]);
+}
+
+#[tokio::test]
+async fn test_positional_placeholders() {
+    let executor = create_executor().await;
+
+    let rows = query(
+        &executor,
+        "SELECT id FROM products WHERE price BETWEEN ? AND ? AND name LIKE ? ORDER BY id",
+        SqlDialect::MySQL,
+        &[Value::Integer(1), Value::Integer(20), text("%o%")],
+    )
+    .await
+    .unwrap();
+    assert_eq!(rows, vec![vec![Value::Integer(2)]]);
+
+    let rows = query(
+        &executor,
+        "SELECT COALESCE(?, 'none'), ? + 1",
+        SqlDialect::MySQL,
+        &[Value::Null, Value::Integer(41)],
+    )
+    .await
+    .unwrap();
+    assert_eq!(rows, vec![vec![text("none"), Value::Integer(42)]]);
+}
+
+#[tokio::test]
+async fn test_missing_parameters() {
+    let executor = create_executor().await;
+
+    let error = query(
+        &executor,
+        "SELECT id FROM products WHERE price > $2",
+        SqlDialect::PostgreSQL,
+        &[Value::Integer(1)],
+    )
+    .await
+    .unwrap_err();
+    assert!(
+        error
+            .to_string()
+            .contains("No value supplied for parameter $2")
+    );
+
+    // Without bound values a placeholder is an error rather than NULL
+    let statements = parse_sql("SELECT $1").unwrap();
+    assert!(executor.execute(&statements[0]).await.is_err());
+}
@@ -0,0 +1,182 @@
+use tempfile::TempDir;
+use yamlbase::yaml::parse_yaml_database;
+
+const YAML: &str = r#"
+database:
+  name: "warehouse"
+
+tables:
+  events:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+      name: "TEXT"
+      score: "DOUBLE"
+      created: "TIMESTAMP"
+      active: "BOOLEAN"
+      note: "TEXT"
+    data_file: events.parquet
+"#;
+
+fn database_dir(yaml: &str) -> TempDir {
+    let dir = TempDir::new().unwrap();
+    std::fs::write(dir.path().join("db.yaml"), yaml).unwrap();
+    dir
+}
+
+#[cfg(not(feature = "parquet"))]
+#[tokio::test]
+async fn test_parquet_needs_feature() {
+    let dir = database_dir(YAML);
+    std::fs::write(dir.path().join("events.parquet"), b"PAR1").unwrap();
+    let error = parse_yaml_database(&dir.path().join("db.yaml"))
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("'parquet' feature"));
+}
+
+#[cfg(feature = "parquet")]
+mod parquet_feature {
+    use super::*;
+    use parquet::data_type::{BoolType, ByteArray, ByteArrayType, DoubleType, Int64Type};
+    use parquet::file::writer::SerializedFileWriter;
+    use parquet::schema::parser::parse_message_type;
+    use std::path::Path;
+    use std::sync::Arc;
+    use yamlbase::database::{Storage, Value};
+    use yamlbase::sql::{QueryExecutor, WriteMode, parse_sql};
+
+    const SCHEMA: &str = "
+        message events {
+            REQUIRED INT64 id;
+            OPTIONAL BYTE_ARRAY name (STRING);
+            OPTIONAL DOUBLE score;
+            OPTIONAL INT64 created (TIMESTAMP(MICROS, true));
+            OPTIONAL BOOLEAN active;
+        }
+    ";
+
+    // Two events, the second with only an id and a score
+    fn write_events(path: &Path) {
+        let schema = Arc::new(parse_message_type(SCHEMA).unwrap());
+        let file = std::fs::File::create(path).unwrap();
+        let mut writer = SerializedFileWriter::new(file, schema, Default::default()).unwrap();
+        let mut row_group = writer.next_row_group().unwrap();
+
+        let mut column = row_group.next_column().unwrap().unwrap();
+        column
+            .typed::<Int64Type>()
+            .write_batch(&[1, 2], None, None)
+            .unwrap();
+        column.close().unwrap();
+        let mut column = row_group.next_column().unwrap().unwrap();
+        column
+            .typed::<ByteArrayType>()
+            .write_batch(&[ByteArray::from("signup")], Some(&[1, 0]), None)
+            .unwrap();
+        column.close().unwrap();
+        let mut column = row_group.next_column().unwrap().unwrap();
+        column
+            .typed::<DoubleType>()
+            .write_batch(&[1.5, 0.25], Some(&[1, 1]), None)
+            .unwrap();
+        column.close().unwrap();
+        let mut column = row_group.next_column().unwrap().unwrap();
+        column
+            .typed::<Int64Type>()
+            .write_batch(&[1_700_000_000_123_456], Some(&[1, 0]), None)
+            .unwrap();
+        column.close().unwrap();
+        let mut column = row_group.next_column().unwrap().unwrap();
+        column
+            .typed::<BoolType>()
+            .write_batch(&[true], Some(&[1, 0]), None)
+            .unwrap();
+        column.close().unwrap();
+
+        row_group.close().unwrap();
+        writer.close().unwrap();
+    }
+
+    fn events_dir(yaml: &str) -> TempDir {
+        let dir = database_dir(yaml);
+        write_events(&dir.path().join("events.parquet"));
+        dir
+    }
+
+    #[tokio::test]
+    async fn test_load_parquet_rows() {
+        let dir = events_dir(YAML);
+        let (db, _) = parse_yaml_database(&dir.path().join("db.yaml"))
+            .await
+            .unwrap();
+        let events = db.get_table("events").unwrap();
+
+        let created = chrono::DateTime::from_timestamp_micros(1_700_000_000_123_456)
+            .unwrap()
+            .naive_utc();
+        assert_eq!(
+            events.rows,
+            vec![
+                vec![
+                    Value::Integer(1),
+                    Value::Text("signup".to_string()),
+                    Value::Double(1.5),
+                    Value::Timestamp(created),
+                    Value::Boolean(true),
+                    Value::Null,
+                ],
+                vec![
+                    Value::Integer(2),
+                    Value::Null,
+                    Value::Double(0.25),
+                    Value::Null,
+                    Value::Null,
+                    Value::Null,
+                ],
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_parquet_errors() {
+        let load = |yaml: String| async move {
+            let dir = events_dir(&yaml);
+            parse_yaml_database(&dir.path().join("db.yaml"))
+                .await
+                .unwrap_err()
+                .to_string()
+        };
+
+        let error = load(YAML.replace("      score: \"DOUBLE\"\n", "")).await;
+        assert!(error.contains("column 'score'"), "{}", error);
+        let error = load(YAML.replace("name: \"TEXT\"", "name: \"INTEGER\"")).await;
+        assert!(error.contains("events.parquet: row 1"), "{}", error);
+
+        let dir = database_dir(YAML);
+        std::fs::write(dir.path().join("events.parquet"), "id\n1\n").unwrap();
+        assert!(
+            parse_yaml_database(&dir.path().join("db.yaml"))
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_parquet_tables_are_not_persisted() {
+        let dir = events_dir(YAML);
+        let yaml_path = dir.path().join("db.yaml");
+        let (db, _) = parse_yaml_database(&yaml_path).await.unwrap();
+        let executor = QueryExecutor::new(Arc::new(Storage::new(db)))
+            .await
+            .unwrap()
+            .with_write_mode(WriteMode::Persistent(yaml_path));
+
+        let statements = parse_sql("DELETE FROM events WHERE id = 2").unwrap();
+        let error = executor.execute(&statements[0]).await.unwrap_err();
+        assert!(error.to_string().contains("Parquet file"));
+
+        let statements = parse_sql("SELECT COUNT(*) FROM events").unwrap();
+        let result = executor.execute(&statements[0]).await.unwrap();
+        assert_eq!(result.rows, vec![vec![Value::Integer(2)]]);
+    }
+}
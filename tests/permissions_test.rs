@@ -0,0 +1,142 @@
+use mysql::prelude::Queryable;
+use tokio_postgres::NoTls;
+use yamlbase::yaml::Grants;
+
+mod common;
+use common::{TestServer, connect_mysql, connect_postgres, yaml_file};
+
+const YAML: &str = r#"
+database:
+  name: "shop"
+  auth:
+    username: "admin"
+    password: "secret"
+    users:
+      - username: "reader"
+        password: "read"
+        grants:
+          read: [products, "sales.*"]
+      - username: "clerk"
+        password: "clerk"
+        grants:
+          read: [products]
+          write: [orders]
+      - username: "auditor"
+        password: "audit"
+
+tables:
+  products:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+      name: "TEXT"
+    data:
+      - id: 1
+        name: "pen"
+  orders:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+      product_id: "INTEGER"
+    data:
+      - id: 1
+        product_id: 1
+  sales.totals:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+    data:
+      - id: 1
+"#;
+
+#[test]
+fn test_grants_name_tables_and_schemas() {
+    let grants = Grants {
+        read: vec!["Products".to_string(), "sales.*".to_string()],
+        write: vec!["*".to_string()],
+    };
+    assert!(grants.may_read("products"));
+    assert!(grants.may_read("sales.totals"));
+    assert!(!grants.may_read("orders"));
+    assert!(!grants.may_read("hr.salaries"));
+    assert!(grants.may_write("hr.salaries"));
+    assert!(!Grants::default().may_read("products"));
+}
+
+#[tokio::test]
+async fn test_postgres_permission_denied() {
+    let file = yaml_file(YAML);
+    let server = TestServer::start_postgres(file.path().to_str().unwrap());
+
+    let reader = connect_postgres(server.port(), "reader", "read", "shop")
+        .await
+        .unwrap();
+    reader.simple_query("SELECT * FROM products").await.unwrap();
+    reader
+        .simple_query("SELECT * FROM sales.totals")
+        .await
+        .unwrap();
+    let error = reader
+        .simple_query("SELECT * FROM products WHERE id IN (SELECT product_id FROM orders)")
+        .await
+        .unwrap_err();
+    let db_error = error.as_db_error().unwrap();
+    assert_eq!(db_error.code().code(), "42501");
+    assert_eq!(db_error.message(), "permission denied for table orders");
+    reader
+        .query("SELECT name FROM products WHERE id = $1", &[&1i32])
+        .await
+        .unwrap();
+    let error = reader
+        .execute("DELETE FROM products", &[])
+        .await
+        .unwrap_err();
+    assert_eq!(error.code().unwrap().code(), "42501");
+    assert!(reader.simple_query("RELOAD DATABASE").await.is_err());
+
+    let clerk = connect_postgres(server.port(), "clerk", "clerk", "shop")
+        .await
+        .unwrap();
+    clerk
+        .simple_query("INSERT INTO orders VALUES (2, 1)")
+        .await
+        .unwrap();
+    let error = clerk
+        .simple_query("INSERT INTO products VALUES (2, 'ink')")
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error.as_db_error().unwrap().message(),
+        "permission denied for table products"
+    );
+
+    // A user without grants, like the main user, may use every table
+    let auditor = connect_postgres(server.port(), "auditor", "audit", "shop")
+        .await
+        .unwrap();
+    auditor.simple_query("SELECT * FROM orders").await.unwrap();
+
+    let wrong = tokio_postgres::Config::new()
+        .host("127.0.0.1")
+        .port(server.port())
+        .user("reader")
+        .password("secret")
+        .dbname("shop")
+        .connect(NoTls)
+        .await;
+    assert!(wrong.is_err());
+}
+
+#[test]
+fn test_mysql_permission_denied() {
+    let file = yaml_file(YAML);
+    let server = TestServer::start_mysql(file.path().to_str().unwrap());
+    let mut conn = connect_mysql(server.port(), "reader", "read", "shop").unwrap();
+
+    let name: Option<String> = conn.query_first("SELECT name FROM products").unwrap();
+    assert_eq!(name.as_deref(), Some("pen"));
+    match conn.query_drop("SELECT * FROM orders") {
+        Err(mysql::Error::MySqlError(error)) => {
+            assert_eq!(error.code, 1142);
+            assert_eq!(error.message, "permission denied for table orders");
+        }
+        other => panic!("expected permission denied, got {:?}", other),
+    }
+}
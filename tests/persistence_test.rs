@@ -0,0 +1,148 @@
+use std::sync::Arc;
+use tempfile::NamedTempFile;
+use yamlbase::database::{Storage, Value};
+use yamlbase::sql::{QueryExecutor, WriteMode, parse_sql};
+use yamlbase::yaml::parse_yaml_database;
+
+mod common;
+use common::{query, yaml_file};
+
+const YAML: &str = r#"# Test fixture, edited by hand
+database:
+  name: "test_db"
+
+tables:
+  users:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+      name: "VARCHAR(50) NOT NULL"
+      joined: "TIMESTAMP"
+      profile: "JSON"
+    # One user to start with
+    data:
+      - id: 1
+        name: "Ann"
+        joined: "2024-01-01 10:00:00"
+        profile: {"theme": "dark"}
+
+  # Read-only lookup table
+  roles:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+      title: "TEXT"
+    data:
+      - id: 1
+        title: "admin"
+"#;
+
+async fn create_storage(file: &NamedTempFile) -> Arc<Storage> {
+    let (db, _) = parse_yaml_database(file.path()).await.unwrap();
+    Arc::new(Storage::new(db))
+}
+
+async fn executor(storage: &Arc<Storage>, write_mode: WriteMode) -> QueryExecutor {
+    QueryExecutor::new(storage.clone())
+        .await
+        .unwrap()
+        .with_write_mode(write_mode)
+}
+
+async fn execute(executor: &QueryExecutor, sql: &str) -> yamlbase::Result<u64> {
+    let statements = parse_sql(sql).unwrap();
+    let result = executor.execute(&statements[0]).await?;
+    Ok(result.affected_rows.unwrap())
+}
+
+#[tokio::test]
+async fn test_shared_writes() {
+    let file = yaml_file(YAML);
+    let storage = create_storage(&file).await;
+    let first = executor(&storage, WriteMode::Shared).await;
+    let second = executor(&storage, WriteMode::Shared).await;
+
+    execute(&first, "INSERT INTO users (id, name) VALUES (2, 'Bob')")
+        .await
+        .unwrap();
+    execute(&second, "UPDATE users SET name = 'Anna' WHERE id = 1")
+        .await
+        .unwrap();
+    assert!(first.session().is_empty());
+
+    for executor in [&first, &second] {
+        assert_eq!(
+            query(executor, "SELECT id, name FROM users ORDER BY id").await,
+            vec![
+                vec![Value::Integer(1), Value::Text("Anna".to_string())],
+                vec![Value::Integer(2), Value::Text("Bob".to_string())],
+            ]
+        );
+    }
+
+    // The primary key index follows the shared data
+    let row = storage
+        .find_by_primary_key("users", &Value::Integer(2))
+        .await
+        .unwrap();
+    assert_eq!(row[1], Value::Text("Bob".to_string()));
+
+    // A failed statement changes nothing, and the connection keeps working
+    assert!(
+        execute(
+            &first,
+            "INSERT INTO users (id, name) VALUES (3, 'Cy'), (2, 'Dup')"
+        )
+        .await
+        .is_err()
+    );
+    assert!(first.session().is_empty());
+    assert_eq!(
+        query(&second, "SELECT COUNT(*) FROM users").await,
+        vec![vec![Value::Integer(2)]]
+    );
+
+    // Without --persist the file on disk is never touched
+    assert_eq!(std::fs::read_to_string(file.path()).unwrap(), YAML);
+}
+
+#[tokio::test]
+async fn test_persisted_writes() {
+    let file = yaml_file(YAML);
+    let storage = create_storage(&file).await;
+    let executor = executor(&storage, WriteMode::Persistent(file.path().to_path_buf())).await;
+
+    execute(
+        &executor,
+        "INSERT INTO users VALUES (2, 'Bob \"the\" builder', NULL, '{\"tags\": [1, 2]}')",
+    )
+    .await
+    .unwrap();
+    execute(&executor, "DELETE FROM users WHERE id = 1")
+        .await
+        .unwrap();
+
+    let content = std::fs::read_to_string(file.path()).unwrap();
+    assert!(content.starts_with("# Test fixture, edited by hand\n"));
+    assert!(content.contains("    # One user to start with\n    data:\n      - id: 2\n"));
+    assert!(content.contains("  # Read-only lookup table\n  roles:\n"));
+    assert!(!content.contains("Ann"));
+
+    let (db, _) = parse_yaml_database(file.path()).await.unwrap();
+    let users = db.get_table("users").unwrap();
+    assert_eq!(users.rows.len(), 1);
+    assert_eq!(users.rows[0][0], Value::Integer(2));
+    assert_eq!(
+        users.rows[0][1],
+        Value::Text("Bob \"the\" builder".to_string())
+    );
+    assert_eq!(users.rows[0][2], Value::Null);
+    assert_eq!(
+        users.rows[0][3],
+        Value::Json(serde_json::json!({"tags": [1, 2]}))
+    );
+    assert_eq!(db.get_table("roles").unwrap().rows.len(), 1);
+
+    // Deleting every row leaves an empty list behind
+    execute(&executor, "DELETE FROM users").await.unwrap();
+    let (db, _) = parse_yaml_database(file.path()).await.unwrap();
+    assert!(db.get_table("users").unwrap().rows.is_empty());
+}
@@ -0,0 +1,117 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use rust_decimal::Decimal;
+use std::str::FromStr;
+use uuid::Uuid;
+
+mod common;
+use common::{TestServer, connect_postgres, yaml_file};
+
+const YAML: &str = r#"
+database:
+  name: "shop"
+  auth:
+    username: "yamlbase"
+    password: "password"
+
+tables:
+  items:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+      price: "DECIMAL(12,4)"
+      weight: "REAL"
+      ratio: "DOUBLE"
+      active: "BOOLEAN"
+      added: "DATE"
+      opens: "TIME"
+      updated: "TIMESTAMP"
+      seen: "TIMESTAMPTZ"
+      code: "UUID"
+      doc: "JSON"
+    data:
+      - id: 1
+        price: 12345.6789
+        weight: 1.5
+        ratio: 0.25
+        active: true
+        added: "1999-12-31"
+        opens: "08:30:15"
+        updated: "2024-02-29 13:45:10"
+        seen: "2024-01-15 10:00:00+02:00"
+        code: "a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11"
+        doc: {"tags": ["x"]}
+      - id: 2
+        price: -0.0001
+        weight: -2
+        ratio: 1e10
+        active: false
+        added: "2000-01-01"
+        opens: "00:00:00"
+        updated: "1970-01-01 00:00:00"
+        seen: "2000-01-01 00:00:00+00:00"
+        code: "00000000-0000-0000-0000-000000000000"
+        doc: 5
+"#;
+
+#[tokio::test]
+async fn test_binary_results() {
+    let file = yaml_file(YAML);
+    let server = TestServer::start_postgres(file.path().to_str().unwrap());
+    let client = connect_postgres(server.port(), "yamlbase", "password", "shop")
+        .await
+        .unwrap();
+
+    // rust-postgres asks for every result column in binary format
+    let rows = client
+        .query("SELECT * FROM items WHERE id > $1 ORDER BY id", &[&0i32])
+        .await
+        .unwrap();
+    let date = |y, m, d| NaiveDate::from_ymd_opt(y, m, d).unwrap();
+
+    let row = &rows[0];
+    assert_eq!(row.get::<_, i32>("id"), 1);
+    assert_eq!(
+        row.get::<_, Decimal>("price"),
+        Decimal::from_str("12345.6789").unwrap()
+    );
+    assert_eq!(row.get::<_, f32>("weight"), 1.5);
+    assert_eq!(row.get::<_, f64>("ratio"), 0.25);
+    assert!(row.get::<_, bool>("active"));
+    assert_eq!(row.get::<_, NaiveDate>("added"), date(1999, 12, 31));
+    assert_eq!(
+        row.get::<_, NaiveTime>("opens"),
+        NaiveTime::from_hms_opt(8, 30, 15).unwrap()
+    );
+    assert_eq!(
+        row.get::<_, NaiveDateTime>("updated"),
+        date(2024, 2, 29).and_hms_opt(13, 45, 10).unwrap()
+    );
+    assert_eq!(
+        row.get::<_, DateTime<Utc>>("seen"),
+        date(2024, 1, 15).and_hms_opt(8, 0, 0).unwrap().and_utc()
+    );
+    assert_eq!(
+        row.get::<_, Uuid>("code"),
+        Uuid::parse_str("a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11").unwrap()
+    );
+    assert_eq!(
+        row.get::<_, serde_json::Value>("doc"),
+        serde_json::json!({"tags": ["x"]})
+    );
+
+    let row = &rows[1];
+    assert_eq!(
+        row.get::<_, Decimal>("price"),
+        Decimal::from_str("-0.0001").unwrap()
+    );
+    assert_eq!(row.get::<_, f32>("weight"), -2.0);
+    assert_eq!(row.get::<_, f64>("ratio"), 1e10);
+    assert!(!row.get::<_, bool>("active"));
+    assert_eq!(row.get::<_, NaiveDate>("added"), date(2000, 1, 1));
+    assert_eq!(row.get::<_, NaiveTime>("opens"), NaiveTime::MIN);
+    assert_eq!(
+        row.get::<_, NaiveDateTime>("updated"),
+        date(1970, 1, 1).and_time(NaiveTime::MIN)
+    );
+    assert_eq!(row.get::<_, Uuid>("code"), Uuid::nil());
+    assert_eq!(row.get::<_, serde_json::Value>("doc"), serde_json::json!(5));
+}
@@ -0,0 +1,270 @@
+use bytes::{BufMut, BytesMut};
+use futures::{SinkExt, TryStreamExt};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+mod common;
+use common::{TestServer, connect_postgres, yaml_file};
+
+const YAML: &str = r#"
+database:
+  name: "shop"
+  auth:
+    username: "yamlbase"
+    password: "password"
+
+tables:
+  products:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+      name: "TEXT"
+      price: "DECIMAL(10,2)"
+    data:
+      - id: 1
+        name: "pen"
+        price: 1.50
+      - id: 2
+        name: "ink, blue"
+        price: null
+      - id: 3
+        name: "say \"hi\"\tnow"
+        price: 3
+"#;
+
+async fn copy_in(
+    client: &tokio_postgres::Client,
+    sql: &str,
+    data: &str,
+) -> Result<u64, tokio_postgres::Error> {
+    let sink = client.copy_in(sql).await?;
+    futures::pin_mut!(sink);
+    sink.send(bytes::Bytes::from(data.to_string())).await?;
+    sink.finish().await
+}
+
+async fn copy_out(client: &tokio_postgres::Client, sql: &str) -> String {
+    let chunks: Vec<_> = client
+        .copy_out(sql)
+        .await
+        .unwrap()
+        .try_collect()
+        .await
+        .unwrap();
+    String::from_utf8(chunks.concat()).unwrap()
+}
+
+#[tokio::test]
+async fn test_copy_to_stdout() {
+    let file = yaml_file(YAML);
+    let server = TestServer::start_postgres(file.path().to_str().unwrap());
+    let client = connect_postgres(server.port(), "yamlbase", "password", "shop")
+        .await
+        .unwrap();
+
+    assert_eq!(
+        copy_out(
+            &client,
+            "COPY (SELECT id, name, price FROM products ORDER BY id) TO STDOUT WITH (FORMAT csv, HEADER)"
+        )
+        .await,
+        "id,name,price\n1,pen,1.5\n2,\"ink, blue\",\n3,\"say \"\"hi\"\"\tnow\",3\n"
+    );
+    assert_eq!(
+        copy_out(&client, "COPY products (id, name) TO STDOUT").await,
+        "1\tpen\n2\tink, blue\n3\tsay \"hi\"\\tnow\n"
+    );
+    assert_eq!(
+        copy_out(
+            &client,
+            "COPY (SELECT id, price FROM products WHERE id = 2) TO STDOUT WITH (DELIMITER '|', NULL 'none')"
+        )
+        .await,
+        "2|none\n"
+    );
+
+    let error = client
+        .copy_out("COPY products TO '/tmp/products.csv'")
+        .await
+        .err()
+        .unwrap();
+    assert_eq!(error.code().unwrap().code(), "0A000");
+}
+
+#[tokio::test]
+async fn test_copy_from_stdin() {
+    let file = yaml_file(YAML);
+    let server = TestServer::start_postgres(file.path().to_str().unwrap());
+    let client = connect_postgres(server.port(), "yamlbase", "password", "shop")
+        .await
+        .unwrap();
+
+    let rows = copy_in(
+        &client,
+        "COPY products FROM STDIN",
+        "4\tbrush\\tfine\t2.25\n5\t\\N\t\\N\n",
+    )
+    .await
+    .unwrap();
+    assert_eq!(rows, 2);
+    let rows = copy_in(
+        &client,
+        "COPY products (name, id) FROM STDIN WITH (FORMAT csv, HEADER)",
+        "name,id\n\"pad, \"\"A4\"\"\nruled\",6\r\n,7\n\"\",8\n",
+    )
+    .await
+    .unwrap();
+    assert_eq!(rows, 3);
+
+    let rows = client
+        .query(
+            "SELECT id, name, price::text FROM products WHERE id > 3 ORDER BY id",
+            &[],
+        )
+        .await
+        .unwrap();
+    let rows: Vec<(i32, Option<String>, Option<String>)> = rows
+        .iter()
+        .map(|row| (row.get(0), row.get(1), row.get(2)))
+        .collect();
+    assert_eq!(
+        rows,
+        vec![
+            (4, Some("brush\tfine".to_string()), Some("2.25".to_string())),
+            (5, None, None),
+            (6, Some("pad, \"A4\"\nruled".to_string()), None),
+            (7, None, None),
+            (8, Some(String::new()), None),
+        ]
+    );
+
+    // A bad row fails the whole COPY, and the connection stays usable
+    let error = copy_in(
+        &client,
+        "COPY products FROM STDIN",
+        "9\tok\t1\nten\tbad\t1\n",
+    )
+    .await
+    .unwrap_err();
+    assert!(error.as_db_error().is_some(), "{}", error);
+    let error = copy_in(&client, "COPY products FROM STDIN", "1\tduplicate\t1\n")
+        .await
+        .unwrap_err();
+    assert!(error.as_db_error().is_some(), "{}", error);
+    let count: i64 = client
+        .query_one("SELECT COUNT(*) FROM products", &[])
+        .await
+        .unwrap()
+        .get(0);
+    assert_eq!(count, 8);
+
+    // The rows are only visible to the connection that copied them
+    let other = connect_postgres(server.port(), "yamlbase", "password", "shop")
+        .await
+        .unwrap();
+    let count: i64 = other
+        .query_one("SELECT COUNT(*) FROM products", &[])
+        .await
+        .unwrap()
+        .get(0);
+    assert_eq!(count, 3);
+
+    let error = client
+        .copy_in::<_, bytes::Bytes>("COPY missing FROM STDIN")
+        .await
+        .err()
+        .unwrap();
+    assert!(error.as_db_error().is_some(), "{}", error);
+}
+
+// psql sends COPY as a simple query
+#[tokio::test]
+async fn test_copy_in_simple_query() {
+    let file = yaml_file(YAML);
+    let server = TestServer::start_postgres_with_args(
+        file.path().to_str().unwrap(),
+        &["--auth-method", "password"],
+    );
+    let mut stream = TcpStream::connect(("127.0.0.1", server.port()))
+        .await
+        .unwrap();
+
+    let mut buf = BytesMut::new();
+    buf.put_u32(0);
+    buf.put_u32(196608);
+    buf.put_slice(b"user\0yamlbase\0database\0shop\0\0");
+    let len = buf.len() as u32;
+    buf[0..4].copy_from_slice(&len.to_be_bytes());
+    stream.write_all(&buf).await.unwrap();
+    let mut response = [0u8; 9];
+    stream.read_exact(&mut response).await.unwrap();
+    assert_eq!(response[0], b'R');
+
+    let mut buf = BytesMut::new();
+    buf.put_u8(b'p');
+    buf.put_u32(4 + 9);
+    buf.put_slice(b"password\0");
+    stream.write_all(&buf).await.unwrap();
+    read_until_ready(&mut stream).await;
+
+    let query = b"COPY (SELECT id FROM products WHERE id < 3 ORDER BY id) TO STDOUT\0";
+    let mut buf = BytesMut::new();
+    buf.put_u8(b'Q');
+    buf.put_u32(4 + query.len() as u32);
+    buf.put_slice(query);
+    stream.write_all(&buf).await.unwrap();
+
+    let messages = read_until_ready(&mut stream).await;
+    let types: Vec<u8> = messages.iter().map(|(kind, _)| *kind).collect();
+    assert_eq!(types, b"HddcCZ");
+    assert_eq!(messages[1].1, b"1\n");
+    assert_eq!(messages[2].1, b"2\n");
+    assert_eq!(messages[4].1, b"COPY 2\0");
+
+    let query = b"COPY products (id) FROM STDIN\0";
+    let mut buf = BytesMut::new();
+    buf.put_u8(b'Q');
+    buf.put_u32(4 + query.len() as u32);
+    buf.put_slice(query);
+    stream.write_all(&buf).await.unwrap();
+    let mut response = [0u8; 10];
+    stream.read_exact(&mut response).await.unwrap();
+    assert_eq!(response, [b'G', 0, 0, 0, 9, 0, 0, 1, 0, 0]);
+
+    // Rows may be split across CopyData messages anywhere
+    let mut buf = BytesMut::new();
+    for data in [&b"1"[..], b"0\n1", b"1\n\\.\n"] {
+        buf.put_u8(b'd');
+        buf.put_u32(4 + data.len() as u32);
+        buf.put_slice(data);
+    }
+    buf.put_u8(b'c');
+    buf.put_u32(4);
+    stream.write_all(&buf).await.unwrap();
+    let messages = read_until_ready(&mut stream).await;
+    let types: Vec<u8> = messages.iter().map(|(kind, _)| *kind).collect();
+    assert_eq!(types, b"CZ");
+    assert_eq!(messages[0].1, b"COPY 2\0");
+}
+
+// The messages up to and including ReadyForQuery
+async fn read_until_ready(stream: &mut TcpStream) -> Vec<(u8, Vec<u8>)> {
+    let mut data = Vec::new();
+    let mut messages = Vec::new();
+    loop {
+        while data.len() >= 5 {
+            let length = u32::from_be_bytes([data[1], data[2], data[3], data[4]]) as usize;
+            if data.len() < length + 1 {
+                break;
+            }
+            let message: Vec<u8> = data.drain(..length + 1).collect();
+            messages.push((message[0], message[5..].to_vec()));
+            if message[0] == b'Z' {
+                return messages;
+            }
+        }
+        let mut chunk = [0u8; 4096];
+        let n = stream.read(&mut chunk).await.unwrap();
+        assert!(n > 0, "connection closed");
+        data.extend_from_slice(&chunk[..n]);
+    }
+}
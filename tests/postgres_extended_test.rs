@@ -21,6 +21,7 @@ async fn test_postgres_extended_query_protocol() {
             unique: true,
             default: None,
             references: None,
+            collation: None,
         },
         Column {
             name: "name".to_string(),
@@ -30,6 +31,7 @@ async fn test_postgres_extended_query_protocol() {
             unique: false,
             default: None,
             references: None,
+            collation: None,
         },
         Column {
             name: "age".to_string(),
@@ -39,6 +41,7 @@ async fn test_postgres_extended_query_protocol() {
             unique: false,
             default: None,
             references: None,
+            collation: None,
         },
     ];
 
@@ -148,6 +151,7 @@ async fn test_postgres_prepared_statement_reuse() {
         unique: false,
         default: None,
         references: None,
+        collation: None,
     }];
 
     let mut table = Table::new("numbers".to_string(), columns);
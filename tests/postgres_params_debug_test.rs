@@ -79,6 +79,9 @@ tables:
             "postgres".to_string(),
             "-p".to_string(),
             port.to_string(),
+            // This test sends the password in clear text
+            "--auth-method".to_string(),
+            "password".to_string(),
             "--log-level".to_string(),
             "debug".to_string(),
         ]);
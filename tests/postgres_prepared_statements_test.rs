@@ -0,0 +1,282 @@
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio_postgres::error::SqlState;
+use tokio_postgres::types::Type;
+
+mod common;
+use common::{TestServer, connect_postgres, yaml_file};
+
+const YAML: &str = r#"
+database:
+  name: "shop"
+  auth:
+    username: "yamlbase"
+    password: "password"
+
+tables:
+  products:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+      sku: "VARCHAR(20)"
+      stock: "INTEGER"
+      released: "DATE"
+    data:
+      - id: 1
+        sku: "PEN-1"
+        stock: 10
+        released: "2024-01-01"
+      - id: 2
+        sku: "PEN-2"
+        stock: 20
+        released: "2024-02-01"
+      - id: 3
+        sku: "INK-1"
+        stock: 30
+        released: "2024-03-01"
+      - id: 4
+        sku: "INK-2"
+        stock: 40
+        released: "2024-04-01"
+      - id: 5
+        sku: "PAD-1"
+        stock: 50
+        released: "2024-05-01"
+"#;
+
+#[tokio::test]
+async fn test_parameter_types_from_columns() {
+    let file = yaml_file(YAML);
+    let server = TestServer::start_postgres(file.path().to_str().unwrap());
+    let client = connect_postgres(server.port(), "yamlbase", "password", "shop")
+        .await
+        .unwrap();
+
+    let cases: [(&str, &[Type]); 4] = [
+        (
+            "SELECT p.id FROM products p WHERE p.sku IN ($1, $2)",
+            &[Type::VARCHAR, Type::VARCHAR],
+        ),
+        (
+            "INSERT INTO products (sku, stock) VALUES ($1, $2)",
+            &[Type::VARCHAR, Type::INT4],
+        ),
+        (
+            "UPDATE products SET stock = $1 WHERE sku = $2",
+            &[Type::INT4, Type::VARCHAR],
+        ),
+        (
+            "DELETE FROM products WHERE released BETWEEN $1 AND $2",
+            &[Type::DATE, Type::DATE],
+        ),
+    ];
+    for (sql, types) in cases {
+        let statement = client.prepare(sql).await.unwrap();
+        assert_eq!(statement.params(), types, "{}", sql);
+    }
+
+    let statement = client
+        .prepare("UPDATE products SET stock = $1 WHERE sku = $2")
+        .await
+        .unwrap();
+    for (stock, sku) in [(11i32, "PEN-1"), (21, "PEN-2")] {
+        assert_eq!(
+            client.execute(&statement, &[&stock, &sku]).await.unwrap(),
+            1
+        );
+    }
+    let statement = client
+        .prepare("SELECT id FROM products WHERE stock = $1")
+        .await
+        .unwrap();
+    let id: i32 = client
+        .query_one(&statement, &[&21i32])
+        .await
+        .unwrap()
+        .get(0);
+    assert_eq!(id, 2);
+}
+
+#[tokio::test]
+async fn test_errors_leave_the_connection_usable() {
+    let file = yaml_file(YAML);
+    let server = TestServer::start_postgres(file.path().to_str().unwrap());
+    let client = connect_postgres(server.port(), "yamlbase", "password", "shop")
+        .await
+        .unwrap();
+
+    // A failing Parse, Bind and Execute each end in an error, not a closed connection
+    assert!(client.prepare("SELEC id FROM products").await.is_err());
+    assert!(client.query("SELECT id FROM missing", &[]).await.is_err());
+    let statement = client
+        .prepare("SELECT id FROM products WHERE id = $1")
+        .await
+        .unwrap();
+    assert!(client.query(&statement, &[&"one"]).await.is_err());
+
+    let row = client.query_one(&statement, &[&3i32]).await.unwrap();
+    assert_eq!(row.get::<_, i32>(0), 3);
+}
+
+#[tokio::test]
+async fn test_row_limits() {
+    let file = yaml_file(YAML);
+    let server = TestServer::start_postgres(file.path().to_str().unwrap());
+    let mut client = connect_postgres(server.port(), "yamlbase", "password", "shop")
+        .await
+        .unwrap();
+
+    let transaction = client.transaction().await.unwrap();
+    let statement = transaction
+        .prepare("SELECT id FROM products WHERE stock > $1 ORDER BY id")
+        .await
+        .unwrap();
+    let portal = transaction.bind(&statement, &[&0i32]).await.unwrap();
+    let mut batches = Vec::new();
+    loop {
+        let rows = transaction.query_portal(&portal, 2).await.unwrap();
+        let ids: Vec<i32> = rows.iter().map(|row| row.get(0)).collect();
+        if ids.is_empty() {
+            break;
+        }
+        batches.push(ids);
+    }
+    assert_eq!(batches, vec![vec![1, 2], vec![3, 4], vec![5]]);
+    transaction.commit().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_deallocate_all() {
+    let file = yaml_file(YAML);
+    let server = TestServer::start_postgres(file.path().to_str().unwrap());
+    let client = connect_postgres(server.port(), "yamlbase", "password", "shop")
+        .await
+        .unwrap();
+
+    let statement = client.prepare("SELECT 1").await.unwrap();
+    assert_eq!(client.query(&statement, &[]).await.unwrap().len(), 1);
+    client.batch_execute("DEALLOCATE ALL").await.unwrap();
+    let error = client.query(&statement, &[]).await.unwrap_err();
+    assert_eq!(error.code(), Some(&SqlState::INVALID_SQL_STATEMENT_NAME));
+
+    let error = client
+        .batch_execute("DEALLOCATE PREPARE missing")
+        .await
+        .unwrap_err();
+    assert_eq!(error.code(), Some(&SqlState::INVALID_SQL_STATEMENT_NAME));
+}
+
+// Statements named by the client, which tokio-postgres does not let a test choose
+#[tokio::test]
+async fn test_named_statements() {
+    let file = yaml_file(YAML);
+    let server = TestServer::start_postgres_with_args(
+        file.path().to_str().unwrap(),
+        &["--auth-method", "password"],
+    );
+    let mut stream = TcpStream::connect(("127.0.0.1", server.port()))
+        .await
+        .unwrap();
+    startup(&mut stream).await;
+
+    // A name in use must be closed before it is parsed again
+    let parse = message(
+        b'P',
+        b"by_sku\0SELECT id FROM products WHERE sku = $1\0\0\0",
+    );
+    stream.write_all(&parse).await.unwrap();
+    stream.write_all(&parse).await.unwrap();
+    stream.write_all(&message(b'S', b"")).await.unwrap();
+    let messages = read_until_ready(&mut stream).await;
+    assert_eq!(kinds(&messages), b"1EZ");
+    assert!(contains(&messages[1].1, b"C42P05\0"));
+
+    // The statement serves any number of Binds
+    for sku in ["INK-1", "PAD-1"] {
+        let mut bind = b"\0by_sku\0\0\0\0\x01".to_vec();
+        bind.extend_from_slice(&(sku.len() as u32).to_be_bytes());
+        bind.extend_from_slice(sku.as_bytes());
+        bind.extend_from_slice(b"\0\0");
+        stream.write_all(&message(b'B', &bind)).await.unwrap();
+        stream
+            .write_all(&message(b'E', b"\0\0\0\0\0"))
+            .await
+            .unwrap();
+        stream.write_all(&message(b'S', b"")).await.unwrap();
+        assert_eq!(kinds(&read_until_ready(&mut stream).await), b"2DCZ");
+    }
+
+    stream
+        .write_all(&message(b'Q', b"DEALLOCATE by_sku\0"))
+        .await
+        .unwrap();
+    assert_eq!(kinds(&read_until_ready(&mut stream).await), b"CZ");
+
+    // Once deallocated, Bind fails and the messages up to Sync are skipped
+    stream
+        .write_all(&message(b'B', b"\0by_sku\0\0\0\0\0\0\0"))
+        .await
+        .unwrap();
+    stream
+        .write_all(&message(b'E', b"\0\0\0\0\0"))
+        .await
+        .unwrap();
+    stream.write_all(&message(b'S', b"")).await.unwrap();
+    let messages = read_until_ready(&mut stream).await;
+    assert_eq!(kinds(&messages), b"EZ");
+    assert!(contains(&messages[0].1, b"C26000\0"));
+}
+
+fn message(kind: u8, body: &[u8]) -> Vec<u8> {
+    let mut message = vec![kind];
+    message.extend_from_slice(&(body.len() as u32 + 4).to_be_bytes());
+    message.extend_from_slice(body);
+    message
+}
+
+fn kinds(messages: &[(u8, Vec<u8>)]) -> Vec<u8> {
+    messages.iter().map(|(kind, _)| *kind).collect()
+}
+
+fn contains(body: &[u8], field: &[u8]) -> bool {
+    body.windows(field.len()).any(|window| window == field)
+}
+
+async fn startup(stream: &mut TcpStream) {
+    let mut startup = Vec::new();
+    startup.extend_from_slice(&196608u32.to_be_bytes());
+    startup.extend_from_slice(b"user\0yamlbase\0database\0shop\0\0");
+    let mut buf = (startup.len() as u32 + 4).to_be_bytes().to_vec();
+    buf.extend_from_slice(&startup);
+    stream.write_all(&buf).await.unwrap();
+    let mut response = [0u8; 9];
+    stream.read_exact(&mut response).await.unwrap();
+    assert_eq!(response[0], b'R');
+    stream
+        .write_all(&message(b'p', b"password\0"))
+        .await
+        .unwrap();
+    read_until_ready(stream).await;
+}
+
+// The messages up to and including ReadyForQuery
+async fn read_until_ready(stream: &mut TcpStream) -> Vec<(u8, Vec<u8>)> {
+    let mut data = Vec::new();
+    let mut messages = Vec::new();
+    loop {
+        while data.len() >= 5 {
+            let length = u32::from_be_bytes([data[1], data[2], data[3], data[4]]) as usize;
+            if data.len() < length + 1 {
+                break;
+            }
+            let message: Vec<u8> = data.drain(..length + 1).collect();
+            messages.push((message[0], message[5..].to_vec()));
+            if message[0] == b'Z' {
+                return messages;
+            }
+        }
+        let mut chunk = [0u8; 4096];
+        let n = stream.read(&mut chunk).await.unwrap();
+        assert!(n > 0, "connection closed");
+        data.extend_from_slice(&chunk[..n]);
+    }
+}
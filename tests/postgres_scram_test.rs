@@ -0,0 +1,82 @@
+use bytes::{BufMut, BytesMut};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+mod common;
+use common::{TestServer, connect_postgres, yaml_file};
+
+const YAML: &str = r#"
+database:
+  name: "shop"
+  auth:
+    username: "yamlbase"
+    password: "pässword"
+
+tables:
+  products:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+    data:
+      - id: 1
+"#;
+
+// The authentication request the server answers a startup message with
+async fn auth_request(server: &TestServer, user: &str) -> Vec<u8> {
+    let mut stream = TcpStream::connect(("127.0.0.1", server.port()))
+        .await
+        .unwrap();
+    let mut buf = BytesMut::new();
+    buf.put_u32(0);
+    buf.put_u32(196608);
+    buf.put_slice(format!("user\0{}\0\0", user).as_bytes());
+    let len = buf.len() as u32;
+    buf[0..4].copy_from_slice(&len.to_be_bytes());
+    stream.write_all(&buf).await.unwrap();
+
+    let mut response = vec![0u8; 1024];
+    let n = stream.read(&mut response).await.unwrap();
+    response.truncate(n);
+    response
+}
+
+#[tokio::test]
+async fn test_scram_sha_256() {
+    let file = yaml_file(YAML);
+    let server = TestServer::start_postgres(file.path().to_str().unwrap());
+
+    // AuthenticationSASL offering SCRAM-SHA-256, also for users that do not exist
+    for user in ["yamlbase", "nobody"] {
+        let response = auth_request(&server, user).await;
+        assert_eq!(response[0], b'R');
+        assert_eq!(response[5..9], 10u32.to_be_bytes());
+        assert_eq!(&response[9..], b"SCRAM-SHA-256\0\0");
+    }
+
+    let client = connect_postgres(server.port(), "yamlbase", "pässword", "shop")
+        .await
+        .unwrap();
+    client.simple_query("SELECT * FROM products").await.unwrap();
+
+    for (user, password) in [("yamlbase", "password"), ("nobody", "pässword")] {
+        let error = connect_postgres(server.port(), user, password, "shop")
+            .await
+            .unwrap_err();
+        assert_eq!(error.code().unwrap().code(), "28P01");
+    }
+}
+
+#[tokio::test]
+async fn test_auth_method_password() {
+    let file = yaml_file(YAML);
+    let server = TestServer::start_postgres_with_args(
+        file.path().to_str().unwrap(),
+        &["--auth-method", "password"],
+    );
+
+    let response = auth_request(&server, "yamlbase").await;
+    assert_eq!(response[5..9], 3u32.to_be_bytes());
+    let client = connect_postgres(server.port(), "yamlbase", "pässword", "shop")
+        .await
+        .unwrap();
+    client.simple_query("SELECT * FROM products").await.unwrap();
+}
@@ -0,0 +1,252 @@
+use bytes::{BufMut, BytesMut};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio_postgres::{NoTls, SimpleQueryMessage};
+
+mod common;
+use common::{TestServer, yaml_file};
+
+const YAML: &str = r#"
+database:
+  name: "shop"
+  auth:
+    username: "yamlbase"
+    password: "password"
+
+tables:
+  events:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+      at: "TIMESTAMPTZ"
+    data:
+      - id: 1
+        at: "2024-05-01 12:00:00+00"
+"#;
+
+fn config(server: &TestServer) -> tokio_postgres::Config {
+    let mut config = tokio_postgres::Config::new();
+    config
+        .host("127.0.0.1")
+        .port(server.port())
+        .user("yamlbase")
+        .password("password")
+        .dbname("shop");
+    config
+}
+
+async fn connect(config: &tokio_postgres::Config) -> tokio_postgres::Client {
+    let (client, connection) = config.connect(NoTls).await.unwrap();
+    tokio::spawn(connection);
+    client
+}
+
+async fn value(client: &tokio_postgres::Client, sql: &str) -> String {
+    let messages = client.simple_query(sql).await.unwrap();
+    messages
+        .iter()
+        .find_map(|message| match message {
+            SimpleQueryMessage::Row(row) => row.get(0).map(str::to_string),
+            _ => None,
+        })
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_set_and_show() {
+    let file = yaml_file(YAML);
+    let server = TestServer::start_postgres(file.path().to_str().unwrap());
+    let mut config = config(&server);
+    config
+        .application_name("orders-service")
+        .options("-c statement_timeout=5000");
+    let client = connect(&config).await;
+
+    // Parameters from the startup message
+    assert_eq!(
+        value(&client, "SHOW application_name").await,
+        "orders-service"
+    );
+    assert_eq!(value(&client, "SHOW statement_timeout").await, "5000");
+    assert_eq!(value(&client, "SHOW server_version").await, "14.0");
+    assert_eq!(value(&client, "SHOW TIME ZONE").await, "UTC");
+
+    client
+        .batch_execute("SET application_name = 'reporting'; SET extra_float_digits TO 3")
+        .await
+        .unwrap();
+    assert_eq!(value(&client, "SHOW application_name").await, "reporting");
+    assert_eq!(value(&client, "SHOW extra_float_digits").await, "3");
+    client
+        .batch_execute("SET myapp.tenant = 'acme'")
+        .await
+        .unwrap();
+    assert_eq!(value(&client, "SHOW myapp.tenant").await, "acme");
+
+    // SHOW ALL lists every parameter by name, custom ones included
+    let settings: Vec<(String, String)> = client
+        .simple_query("SHOW ALL")
+        .await
+        .unwrap()
+        .iter()
+        .filter_map(|message| match message {
+            SimpleQueryMessage::Row(row) => Some((
+                row.get(0).unwrap().to_string(),
+                row.get(1).unwrap().to_string(),
+            )),
+            _ => None,
+        })
+        .collect();
+    for expected in [
+        ("application_name", "reporting"),
+        ("myapp.tenant", "acme"),
+        ("search_path", "public"),
+        ("server_version", "14.0"),
+        ("statement_timeout", "5000"),
+        ("TimeZone", "UTC"),
+    ] {
+        assert!(
+            settings.contains(&(expected.0.to_string(), expected.1.to_string())),
+            "{:?} not in {:?}",
+            expected,
+            settings
+        );
+    }
+    let names: Vec<String> = settings
+        .iter()
+        .map(|(name, _)| name.to_lowercase())
+        .collect();
+    assert!(names.is_sorted());
+
+    // SHOW through the extended protocol
+    let row = client.query_one("SHOW search_path", &[]).await.unwrap();
+    assert_eq!(row.get::<_, String>(0), "public");
+    assert_eq!(row.columns()[0].name(), "search_path");
+
+    // Settings are per connection
+    let other = connect(&self::config(&server)).await;
+    assert_eq!(value(&other, "SHOW application_name").await, "");
+    // The server's --statement-timeout, 60 seconds by default
+    assert_eq!(value(&other, "SHOW statement_timeout").await, "1min");
+
+    client
+        .batch_execute("RESET application_name")
+        .await
+        .unwrap();
+    assert_eq!(value(&client, "SHOW application_name").await, "");
+    client.batch_execute("RESET ALL").await.unwrap();
+    assert_eq!(value(&client, "SHOW extra_float_digits").await, "1");
+
+    for sql in [
+        "SET no_such_parameter = 1",
+        "SET server_version = '16.0'",
+        "SET client_encoding = 'LATIN1'",
+        "SHOW no_such_parameter",
+    ] {
+        let error = client.batch_execute(sql).await.unwrap_err();
+        assert!(error.as_db_error().is_some(), "{}: {}", sql, error);
+    }
+}
+
+#[tokio::test]
+async fn test_set_time_zone() {
+    let file = yaml_file(YAML);
+    let server = TestServer::start_postgres(file.path().to_str().unwrap());
+    let client = connect(&config(&server)).await;
+
+    client.batch_execute("SET TIME ZONE 2").await.unwrap();
+    assert_eq!(value(&client, "SHOW TimeZone").await, "+02");
+    // Stored values are shown in the session's zone, and values without an offset are
+    // read in it
+    assert_eq!(
+        value(&client, "SELECT at FROM events").await,
+        "2024-05-01 14:00:00+02"
+    );
+    assert_eq!(
+        value(
+            &client,
+            "SELECT CAST('2024-05-01 12:00:00' AS TIMESTAMPTZ) AT TIME ZONE 'UTC'"
+        )
+        .await,
+        "2024-05-01 10:00:00"
+    );
+
+    // Other connections keep the server's zone
+    let other = connect(&config(&server)).await;
+    assert_eq!(
+        value(&other, "SELECT at FROM events").await,
+        "2024-05-01 12:00:00+00"
+    );
+
+    client
+        .batch_execute("SET timezone TO DEFAULT")
+        .await
+        .unwrap();
+    assert_eq!(value(&client, "SHOW TIME ZONE").await, "UTC");
+}
+
+// The client is sent a ParameterStatus when a reported parameter changes
+#[tokio::test]
+async fn test_parameter_status_on_set() {
+    let file = yaml_file(YAML);
+    let server = TestServer::start_postgres_with_args(
+        file.path().to_str().unwrap(),
+        &["--auth-method", "password"],
+    );
+    let mut stream = TcpStream::connect(("127.0.0.1", server.port()))
+        .await
+        .unwrap();
+
+    let mut buf = BytesMut::new();
+    buf.put_u32(0);
+    buf.put_u32(196608);
+    buf.put_slice(b"user\0yamlbase\0database\0shop\0application_name\0psql\0\0");
+    let len = buf.len() as u32;
+    buf[0..4].copy_from_slice(&len.to_be_bytes());
+    stream.write_all(&buf).await.unwrap();
+    let mut response = [0u8; 9];
+    stream.read_exact(&mut response).await.unwrap();
+    assert_eq!(response[0], b'R');
+
+    let mut buf = BytesMut::new();
+    buf.put_u8(b'p');
+    buf.put_u32(4 + 9);
+    buf.put_slice(b"password\0");
+    stream.write_all(&buf).await.unwrap();
+    let messages = read_until_ready(&mut stream).await;
+    assert!(messages.contains(&(b'S', b"application_name\0psql\0".to_vec())));
+    assert!(messages.contains(&(b'S', b"TimeZone\0UTC\0".to_vec())));
+
+    let query = b"SET application_name TO 'batch'; SET extra_float_digits = 2\0";
+    let mut buf = BytesMut::new();
+    buf.put_u8(b'Q');
+    buf.put_u32(4 + query.len() as u32);
+    buf.put_slice(query);
+    stream.write_all(&buf).await.unwrap();
+    let messages = read_until_ready(&mut stream).await;
+    let types: Vec<u8> = messages.iter().map(|(kind, _)| *kind).collect();
+    assert_eq!(types, b"SCCZ");
+    assert_eq!(messages[0].1, b"application_name\0batch\0");
+}
+
+// The messages up to and including ReadyForQuery
+async fn read_until_ready(stream: &mut TcpStream) -> Vec<(u8, Vec<u8>)> {
+    let mut data = Vec::new();
+    let mut messages = Vec::new();
+    loop {
+        while data.len() >= 5 {
+            let length = u32::from_be_bytes([data[1], data[2], data[3], data[4]]) as usize;
+            if data.len() < length + 1 {
+                break;
+            }
+            let message: Vec<u8> = data.drain(..length + 1).collect();
+            messages.push((message[0], message[5..].to_vec()));
+            if message[0] == b'Z' {
+                return messages;
+            }
+        }
+        let mut chunk = [0u8; 4096];
+        let n = stream.read(&mut chunk).await.unwrap();
+        assert!(n > 0, "connection closed");
+        data.extend_from_slice(&chunk[..n]);
+    }
+}
@@ -3,7 +3,7 @@
 use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::net::{TcpListener, TcpStream};
-use yamlbase::config::{Config, Protocol};
+use yamlbase::config::{AuthMethod, Config, Protocol};
 use yamlbase::database::{Column, Database, Storage, Table, Value};
 use yamlbase::protocol::Connection;
 use yamlbase::yaml::schema::SqlType;
@@ -23,6 +23,7 @@ async fn test_postgres_simple_protocol() {
             unique: true,
             default: None,
             references: None,
+            collation: None,
         },
         Column {
             name: "name".to_string(),
@@ -32,6 +33,7 @@ async fn test_postgres_simple_protocol() {
             unique: false,
             default: None,
             references: None,
+            collation: None,
         },
     ];
 
@@ -51,21 +53,46 @@ async fn test_postgres_simple_protocol() {
     // Create storage and config
     let storage = Arc::new(Storage::new(db));
     let config = Arc::new(Config {
-        file: PathBuf::from("test.yaml"),
+        command: None,
+        files: vec![PathBuf::from("test.yaml")],
+        config: None,
         port: Some(0), // Let OS assign port
         bind_address: "127.0.0.1".to_string(),
         protocol: Protocol::Postgres,
+        listener_ports: Default::default(),
         username: "yamlbase".to_string(),
         password: "password".to_string(),
         verbose: false,
+        auth_method: Some(AuthMethod::Password),
         hot_reload: false,
+        reload_interval: None,
+        url_headers: Vec::new(),
         log_level: "info".to_string(),
         database: Some("test_db".to_string()),
         allow_anonymous: false,
+        random_seed: None,
+        timezone: None,
+        cte_max_recursion_depth: None,
+        cte_max_rows: None,
+        collation: None,
+        strict_types: false,
+        mutable: false,
+        persist: false,
+        tls_cert: None,
+        tls_key: None,
+        tls_client_ca: None,
+        tls_require_client_cert: false,
+        proxy_protocol: false,
         max_connections: None,
-        connection_timeout: None,
+        connect_timeout: None,
         idle_timeout: None,
+        statement_timeout: None,
+        rate_limit_qps: None,
+        rate_limit_rows: None,
+        rate_limit_by: Default::default(),
         enable_keepalive: false,
+        users: Vec::new(),
+        password_hash: None,
     });
 
     // Start server
@@ -78,7 +105,7 @@ async fn test_postgres_simple_protocol() {
     // Spawn server task
     tokio::spawn(async move {
         while let Ok((stream, _)) = listener.accept().await {
-            let connection = Connection::new(server_config.clone(), server_storage.clone());
+            let connection = Connection::new(server_config.clone(), server_storage.clone().into());
             tokio::spawn(async move {
                 if let Err(e) = connection.handle(stream).await {
                     eprintln!("Connection error: {}", e);
@@ -0,0 +1,266 @@
+use rcgen::{BasicConstraints, CertificateParams, DnType, ExtendedKeyUsagePurpose, IsCa, KeyPair};
+use std::net::{IpAddr, Ipv4Addr};
+use std::sync::Arc;
+use tokio_postgres::config::SslMode;
+use tokio_postgres::error::SqlState;
+use tokio_postgres::{NoTls, SimpleQueryMessage};
+use tokio_postgres_rustls::MakeRustlsConnect;
+use tokio_rustls::rustls::client::danger::{
+    HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier,
+};
+use tokio_rustls::rustls::crypto::{CryptoProvider, ring};
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivatePkcs8KeyDer, ServerName, UnixTime};
+use tokio_rustls::rustls::{ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme};
+
+mod common;
+use common::{TestServer, connect_postgres, yaml_file};
+
+const YAML: &str = r#"
+database:
+  name: "shop"
+  auth:
+    username: "yamlbase"
+    password: "password"
+
+tables:
+  products:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+      name: "TEXT"
+    data:
+      - id: 1
+        name: "pen"
+"#;
+
+fn provider() -> Arc<CryptoProvider> {
+    Arc::new(ring::default_provider())
+}
+
+async fn connect(
+    server: &TestServer,
+    tls: ClientConfig,
+) -> Result<tokio_postgres::Client, tokio_postgres::Error> {
+    let (client, connection) = tokio_postgres::Config::new()
+        .host("localhost")
+        .hostaddr(IpAddr::V4(Ipv4Addr::LOCALHOST))
+        .port(server.port())
+        .user("yamlbase")
+        .password("password")
+        .dbname("shop")
+        .ssl_mode(SslMode::Require)
+        .connect(MakeRustlsConnect::new(tls))
+        .await?;
+    tokio::spawn(connection);
+    Ok(client)
+}
+
+async fn product_name(client: &tokio_postgres::Client) -> String {
+    let messages = client
+        .simple_query("SELECT name FROM products")
+        .await
+        .unwrap();
+    match &messages[1] {
+        SimpleQueryMessage::Row(row) => row.get(0).unwrap().to_string(),
+        other => panic!("expected a row, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_tls_with_configured_certificate() {
+    let certified = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+    let cert = yaml_file(&certified.cert.pem());
+    let key = yaml_file(&certified.key_pair.serialize_pem());
+    let yaml = yaml_file(YAML);
+    let server = TestServer::start_postgres_with_args(
+        yaml.path().to_str().unwrap(),
+        &[
+            "--tls-cert",
+            cert.path().to_str().unwrap(),
+            "--tls-key",
+            key.path().to_str().unwrap(),
+        ],
+    );
+
+    let mut roots = RootCertStore::empty();
+    roots.add(certified.cert.der().clone()).unwrap();
+    let tls = ClientConfig::builder_with_provider(provider())
+        .with_safe_default_protocol_versions()
+        .unwrap()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    let client = connect(&server, tls).await.unwrap();
+    assert_eq!(product_name(&client).await, "pen");
+
+    // Clients that do not ask for TLS can still connect
+    let client = connect_postgres(server.port(), "yamlbase", "password", "shop")
+        .await
+        .unwrap();
+    assert_eq!(product_name(&client).await, "pen");
+}
+
+// Accepts any certificate, as clients with sslmode=require do
+#[derive(Debug)]
+struct AnyCertificate(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for AnyCertificate {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, tokio_rustls::rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, tokio_rustls::rustls::Error> {
+        let algorithms = &self.0.signature_verification_algorithms;
+        tokio_rustls::rustls::crypto::verify_tls12_signature(message, cert, dss, algorithms)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, tokio_rustls::rustls::Error> {
+        let algorithms = &self.0.signature_verification_algorithms;
+        tokio_rustls::rustls::crypto::verify_tls13_signature(message, cert, dss, algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+#[tokio::test]
+async fn test_tls_with_self_signed_certificate() {
+    let yaml = yaml_file(YAML);
+    let server = TestServer::start_postgres(yaml.path().to_str().unwrap());
+
+    let tls = ClientConfig::builder_with_provider(provider())
+        .with_safe_default_protocol_versions()
+        .unwrap()
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(AnyCertificate(provider())))
+        .with_no_client_auth();
+    let client = connect(&server, tls).await.unwrap();
+    assert_eq!(product_name(&client).await, "pen");
+}
+
+/// A client certificate for `user`, signed by a new CA, and the CA certificate
+fn client_certificate(user: &str) -> (ClientConfig, String) {
+    let ca_key = KeyPair::generate().unwrap();
+    let mut params = CertificateParams::new(Vec::<String>::new()).unwrap();
+    params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+    let ca = params.self_signed(&ca_key).unwrap();
+
+    let key = KeyPair::generate().unwrap();
+    let mut params = CertificateParams::new(Vec::<String>::new()).unwrap();
+    params.distinguished_name.push(DnType::CommonName, user);
+    params.extended_key_usages = vec![ExtendedKeyUsagePurpose::ClientAuth];
+    let cert = params.signed_by(&key, &ca, &ca_key).unwrap();
+
+    let tls = ClientConfig::builder_with_provider(provider())
+        .with_safe_default_protocol_versions()
+        .unwrap()
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(AnyCertificate(provider())))
+        .with_client_auth_cert(
+            vec![cert.der().clone()],
+            PrivatePkcs8KeyDer::from(key.serialize_der()).into(),
+        )
+        .unwrap();
+    (tls, ca.pem())
+}
+
+async fn connect_as(
+    server: &TestServer,
+    user: &str,
+    password: &str,
+    tls: ClientConfig,
+) -> Result<tokio_postgres::Client, tokio_postgres::Error> {
+    let (client, connection) = tokio_postgres::Config::new()
+        .host("127.0.0.1")
+        .port(server.port())
+        .user(user)
+        .password(password)
+        .dbname("shop")
+        .ssl_mode(SslMode::Require)
+        .connect(MakeRustlsConnect::new(tls))
+        .await?;
+    tokio::spawn(connection);
+    Ok(client)
+}
+
+#[tokio::test]
+async fn test_client_certificate_login() {
+    let (tls, ca) = client_certificate("yamlbase");
+    let ca = yaml_file(&ca);
+    let yaml = yaml_file(YAML);
+    let server = TestServer::start_postgres_with_args(
+        yaml.path().to_str().unwrap(),
+        &["--tls-client-ca", ca.path().to_str().unwrap()],
+    );
+
+    // The certificate names the user, so the password is not checked
+    let client = connect_as(&server, "yamlbase", "wrong", tls.clone())
+        .await
+        .unwrap();
+    assert_eq!(product_name(&client).await, "pen");
+
+    // It does not log in as anyone else
+    assert!(connect_as(&server, "admin", "wrong", tls).await.is_err());
+
+    // Clients without a certificate still log in with their password
+    let client = connect_postgres(server.port(), "yamlbase", "password", "shop")
+        .await
+        .unwrap();
+    assert_eq!(product_name(&client).await, "pen");
+}
+
+#[tokio::test]
+async fn test_required_client_certificate() {
+    let (tls, ca) = client_certificate("yamlbase");
+    let ca = yaml_file(&ca);
+    let yaml = yaml_file(YAML);
+    let server = TestServer::start_postgres_with_args(
+        yaml.path().to_str().unwrap(),
+        &[
+            "--tls-client-ca",
+            ca.path().to_str().unwrap(),
+            "--tls-require-client-cert",
+        ],
+    );
+
+    let client = connect_as(&server, "yamlbase", "", tls).await.unwrap();
+    assert_eq!(product_name(&client).await, "pen");
+
+    // The password alone is not enough, over TLS or without it
+    let (other_tls, _) = client_certificate("yamlbase");
+    assert!(
+        connect_as(&server, "yamlbase", "password", other_tls)
+            .await
+            .is_err()
+    );
+    let error = tokio_postgres::Config::new()
+        .host("127.0.0.1")
+        .port(server.port())
+        .user("yamlbase")
+        .password("password")
+        .dbname("shop")
+        .connect(NoTls)
+        .await
+        .err()
+        .unwrap();
+    assert_eq!(
+        error.code(),
+        Some(&SqlState::INVALID_AUTHORIZATION_SPECIFICATION)
+    );
+}
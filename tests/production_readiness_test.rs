@@ -25,6 +25,7 @@ mod production_readiness_tests {
                     unique: true,
                     default: None,
                     references: None,
+                    collation: None,
                 },
                 Column {
                     name: "project_name".to_string(),
@@ -34,6 +35,7 @@ mod production_readiness_tests {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
                 Column {
                     name: "start_date".to_string(),
@@ -43,6 +45,7 @@ mod production_readiness_tests {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
                 Column {
                     name: "end_date".to_string(),
@@ -52,6 +55,7 @@ mod production_readiness_tests {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
                 Column {
                     name: "version_code".to_string(),
@@ -61,6 +65,7 @@ mod production_readiness_tests {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
                 Column {
                     name: "status_code".to_string(),
@@ -70,6 +75,7 @@ mod production_readiness_tests {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
                 Column {
                     name: "active_flag".to_string(),
@@ -79,6 +85,7 @@ mod production_readiness_tests {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
                 Column {
                     name: "closed_for_time_entry".to_string(),
@@ -88,6 +95,7 @@ mod production_readiness_tests {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
                 Column {
                     name: "project_structure".to_string(),
@@ -97,6 +105,7 @@ mod production_readiness_tests {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
                 Column {
                     name: "parent_project_id".to_string(),
@@ -106,6 +115,7 @@ mod production_readiness_tests {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
             ],
         );
@@ -200,6 +210,7 @@ mod production_readiness_tests {
                     unique: true,
                     default: None,
                     references: None,
+                    collation: None,
                 },
                 Column {
                     name: "project_id".to_string(),
@@ -209,6 +220,7 @@ mod production_readiness_tests {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
                 Column {
                     name: "wbi_id".to_string(),
@@ -218,6 +230,7 @@ mod production_readiness_tests {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
                 Column {
                     name: "version_code".to_string(),
@@ -227,6 +240,7 @@ mod production_readiness_tests {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
                 Column {
                     name: "assignment_type".to_string(),
@@ -236,6 +250,7 @@ mod production_readiness_tests {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
                 Column {
                     name: "project_status_code".to_string(),
@@ -245,6 +260,7 @@ mod production_readiness_tests {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
                 Column {
                     name: "planned_effort_hours".to_string(),
@@ -254,6 +270,7 @@ mod production_readiness_tests {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
                 Column {
                     name: "actual_effort_hours".to_string(),
@@ -263,6 +280,7 @@ mod production_readiness_tests {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
                 Column {
                     name: "month_number".to_string(),
@@ -272,6 +290,7 @@ mod production_readiness_tests {
                     unique: false,
                     default: None,
                     references: None,
+                    collation: None,
                 },
             ],
         );
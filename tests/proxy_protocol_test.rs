@@ -0,0 +1,74 @@
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpStream;
+use tokio_postgres::NoTls;
+
+mod common;
+use common::{TestServer, yaml_file};
+
+const PRODUCTS: &str = r#"
+database:
+  name: "shop"
+  auth:
+    username: "admin"
+    password: "password"
+
+tables:
+  products:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+    data:
+      - id: 1
+      - id: 2
+"#;
+
+const SIGNATURE: &[u8; 12] = b"\r\n\r\n\0\r\nQUIT\n";
+
+fn postgres_config(server: &TestServer) -> tokio_postgres::Config {
+    let mut config = tokio_postgres::Config::new();
+    config
+        .host("127.0.0.1")
+        .port(server.port())
+        .user("admin")
+        .password("password")
+        .dbname("shop");
+    config
+}
+
+/// Count the products over a connection that starts with `header`
+async fn count_after(server: &TestServer, header: &[u8]) -> i64 {
+    let mut stream = TcpStream::connect(("127.0.0.1", server.port()))
+        .await
+        .unwrap();
+    stream.write_all(header).await.unwrap();
+    let (client, connection) = postgres_config(server)
+        .connect_raw(stream, NoTls)
+        .await
+        .unwrap();
+    tokio::spawn(connection);
+    let row = client
+        .query_one("SELECT COUNT(*) FROM products", &[])
+        .await
+        .unwrap();
+    row.get(0)
+}
+
+#[tokio::test]
+async fn test_proxy_protocol_header() {
+    let file = yaml_file(PRODUCTS);
+    let server =
+        TestServer::start_postgres_with_args(file.path().to_str().unwrap(), &["--proxy-protocol"]);
+
+    // PROXY over TCP4, from 203.0.113.7:51282 to 10.0.0.2:5432
+    let mut proxied = SIGNATURE.to_vec();
+    proxied.extend([0x21, 0x11, 0x00, 0x0c]);
+    proxied.extend([203, 0, 113, 7, 10, 0, 0, 2, 0xc8, 0x52, 0x15, 0x38]);
+    assert_eq!(count_after(&server, &proxied).await, 2);
+
+    // A health check of the load balancer itself
+    let mut local = SIGNATURE.to_vec();
+    local.extend([0x20, 0x00, 0x00, 0x00]);
+    assert_eq!(count_after(&server, &local).await, 2);
+
+    // Connections without a header are refused
+    assert!(postgres_config(&server).connect(NoTls).await.is_err());
+}
@@ -0,0 +1,94 @@
+use yamlbase::database::{Database, Table, Value};
+use yamlbase::sql::QueryExecutor;
+use yamlbase::yaml::schema::SqlType;
+
+mod common;
+use common::{column, new_executor, query};
+
+async fn create_executor() -> QueryExecutor {
+    let mut db = Database::new("test_db".to_string());
+
+    let mut sales = Table::new(
+        "sales".to_string(),
+        vec![
+            column("id", SqlType::Integer, true),
+            column("region", SqlType::Varchar(20), false),
+            column("rep", SqlType::Varchar(20), false),
+            column("amount", SqlType::Integer, false),
+        ],
+    );
+    let rows = [
+        (1, "east", "ann", 500),
+        (2, "east", "bob", 700),
+        (3, "east", "cid", 300),
+        (4, "west", "dee", 900),
+        (5, "west", "eve", 400),
+    ];
+    for (id, region, rep, amount) in rows {
+        sales
+            .insert_row(vec![
+                Value::Integer(id),
+                Value::Text(region.to_string()),
+                Value::Text(rep.to_string()),
+                Value::Integer(amount),
+            ])
+            .unwrap();
+    }
+    db.add_table(sales).unwrap();
+
+    new_executor(db).await
+}
+
+#[tokio::test]
+async fn test_qualify_row_number_keeps_top_row_per_partition() {
+    let executor = create_executor().await;
+
+    let rows = query(
+        &executor,
+        "SELECT region, rep FROM sales \
+         QUALIFY ROW_NUMBER() OVER (PARTITION BY region ORDER BY amount DESC) = 1 \
+         ORDER BY region",
+    )
+    .await;
+    assert_eq!(
+        rows,
+        vec![
+            vec![
+                Value::Text("east".to_string()),
+                Value::Text("bob".to_string())
+            ],
+            vec![
+                Value::Text("west".to_string()),
+                Value::Text("dee".to_string())
+            ],
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_qualify_on_projected_window_alias() {
+    let executor = create_executor().await;
+
+    let rows = query(
+        &executor,
+        "SELECT rep, RANK() OVER (ORDER BY amount DESC) AS rnk FROM sales \
+         WHERE region = 'east' QUALIFY rnk <= 2 ORDER BY rnk",
+    )
+    .await;
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0][0], Value::Text("bob".to_string()));
+    assert_eq!(rows[1][0], Value::Text("ann".to_string()));
+}
+
+#[tokio::test]
+async fn test_qualify_combined_with_table_column_condition() {
+    let executor = create_executor().await;
+
+    let rows = query(
+        &executor,
+        "SELECT id FROM sales \
+         QUALIFY ROW_NUMBER() OVER (PARTITION BY region ORDER BY amount) = 1 AND amount > 350",
+    )
+    .await;
+    assert_eq!(rows, vec![vec![Value::Integer(5)]]);
+}
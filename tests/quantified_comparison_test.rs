@@ -0,0 +1,198 @@
+use yamlbase::database::{Database, Table, Value};
+use yamlbase::sql::{QueryExecutor, parse_sql};
+use yamlbase::yaml::schema::SqlType;
+
+mod common;
+use common::{column, new_executor, query};
+
+async fn create_executor() -> QueryExecutor {
+    let mut db = Database::new("test_db".to_string());
+
+    let mut products = Table::new(
+        "products".to_string(),
+        vec![
+            column("id", SqlType::Integer, true),
+            column("category", SqlType::Text, false),
+            column("price", SqlType::Integer, false),
+        ],
+    );
+    let rows = [
+        (1, "books", Some(10)),
+        (2, "books", Some(25)),
+        (3, "games", Some(40)),
+        (4, "games", Some(60)),
+        (5, "toys", None),
+    ];
+    for (id, category, price) in rows {
+        products
+            .insert_row(vec![
+                Value::Integer(id),
+                Value::Text(category.to_string()),
+                price.map_or(Value::Null, Value::Integer),
+            ])
+            .unwrap();
+    }
+    db.add_table(products).unwrap();
+
+    let mut categories = Table::new(
+        "categories".to_string(),
+        vec![
+            column("name", SqlType::Text, true),
+            column("budget", SqlType::Integer, false),
+        ],
+    );
+    for (name, budget) in [("books", 30), ("games", 50)] {
+        categories
+            .insert_row(vec![Value::Text(name.to_string()), Value::Integer(budget)])
+            .unwrap();
+    }
+    db.add_table(categories).unwrap();
+
+    new_executor(db).await
+}
+
+async fn query_ids(executor: &QueryExecutor, sql: &str) -> Vec<i64> {
+    let statements = parse_sql(sql).unwrap();
+    let result = executor.execute(&statements[0]).await.unwrap();
+    result
+        .rows
+        .iter()
+        .map(|row| match row[0] {
+            Value::Integer(id) => id,
+            ref other => panic!("unexpected id {:?}", other),
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn test_any_and_all_subqueries() {
+    let executor = create_executor().await;
+
+    assert_eq!(
+        query_ids(
+            &executor,
+            "SELECT id FROM products \
+             WHERE price > ALL (SELECT price FROM products WHERE category = 'books') ORDER BY id",
+        )
+        .await,
+        vec![3, 4]
+    );
+    assert_eq!(
+        query_ids(
+            &executor,
+            "SELECT id FROM products \
+             WHERE price = ANY (SELECT budget - 5 FROM categories) ORDER BY id",
+        )
+        .await,
+        vec![2]
+    );
+    assert_eq!(
+        query_ids(
+            &executor,
+            "SELECT id FROM products \
+             WHERE price < SOME (SELECT budget FROM categories) AND category <> 'games' \
+             ORDER BY id",
+        )
+        .await,
+        vec![1, 2]
+    );
+
+    // ALL over no rows holds, even for NULL; ANY over no rows does not
+    assert_eq!(
+        query_ids(
+            &executor,
+            "SELECT id FROM products \
+             WHERE price > ALL (SELECT budget FROM categories WHERE budget > 100) ORDER BY id",
+        )
+        .await,
+        vec![1, 2, 3, 4, 5]
+    );
+    assert!(
+        query_ids(
+            &executor,
+            "SELECT id FROM products \
+             WHERE price > ANY (SELECT budget FROM categories WHERE budget > 100)",
+        )
+        .await
+        .is_empty()
+    );
+
+    // A NULL in the subquery leaves ALL unknown unless some row fails the comparison
+    assert_eq!(
+        query_ids(
+            &executor,
+            "SELECT id FROM products WHERE price >= ALL (SELECT price FROM products) ORDER BY id",
+        )
+        .await,
+        Vec::<i64>::new()
+    );
+    assert_eq!(
+        query_ids(
+            &executor,
+            "SELECT id FROM products WHERE price <> ALL (SELECT price FROM products WHERE id < 3) \
+             ORDER BY id",
+        )
+        .await,
+        vec![3, 4]
+    );
+}
+
+#[tokio::test]
+async fn test_any_and_all_subqueries_in_select_list() {
+    let executor = create_executor().await;
+
+    let flags = |above_books: Option<bool>, matches_budget: Option<bool>| {
+        vec![
+            above_books.map_or(Value::Null, Value::Boolean),
+            matches_budget.map_or(Value::Null, Value::Boolean),
+        ]
+    };
+    assert_eq!(
+        query(
+            &executor,
+            "SELECT price > ALL (SELECT price FROM products WHERE category = 'books'), \
+             price = ANY (SELECT budget - 5 FROM categories) FROM products ORDER BY id",
+        )
+        .await,
+        vec![
+            flags(Some(false), Some(false)),
+            flags(Some(false), Some(true)),
+            flags(Some(true), Some(false)),
+            flags(Some(true), Some(false)),
+            flags(None, None),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_quantified_comparisons_with_joins_and_ctes() {
+    let executor = create_executor().await;
+
+    assert_eq!(
+        query_ids(
+            &executor,
+            "SELECT p.id FROM products p JOIN categories c ON p.category = c.name \
+             WHERE p.price > ALL (SELECT budget FROM categories WHERE budget < 40) \
+             ORDER BY p.id",
+        )
+        .await,
+        vec![3, 4]
+    );
+    assert_eq!(
+        query_ids(
+            &executor,
+            "WITH priced AS (SELECT id, price FROM products WHERE price IS NOT NULL) \
+             SELECT id FROM priced WHERE price = ANY (SELECT price FROM priced WHERE id > 3)",
+        )
+        .await,
+        vec![4]
+    );
+
+    for sql in [
+        "SELECT id FROM products WHERE price > ALL (SELECT id, price FROM products)",
+        "SELECT id FROM products WHERE price = ANY (1)",
+    ] {
+        let statements = parse_sql(sql).unwrap();
+        assert!(executor.execute(&statements[0]).await.is_err(), "{}", sql);
+    }
+}
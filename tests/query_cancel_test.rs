@@ -0,0 +1,106 @@
+use mysql::prelude::Queryable;
+use std::time::Duration;
+use tokio_postgres::NoTls;
+
+mod common;
+use common::{TestServer, connect_mysql, connect_postgres, yaml_file};
+
+const YAML: &str = r#"
+database:
+  name: "shop"
+  auth:
+    username: "yamlbase"
+    password: "password"
+
+tables:
+  products:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+      name: "TEXT"
+    data:
+      - id: 1
+        name: "pen"
+"#;
+
+// With --mutable a transaction that writes holds up other writers, so the INSERT of a
+// second connection waits until it is cancelled
+#[tokio::test]
+async fn test_postgres_cancel_request() {
+    let file = yaml_file(YAML);
+    let server =
+        TestServer::start_postgres_with_args(file.path().to_str().unwrap(), &["--mutable"]);
+    let writer = connect_postgres(server.port(), "yamlbase", "password", "shop")
+        .await
+        .unwrap();
+    writer
+        .batch_execute("BEGIN; INSERT INTO products VALUES (2, 'ink')")
+        .await
+        .unwrap();
+
+    let client = connect_postgres(server.port(), "yamlbase", "password", "shop")
+        .await
+        .unwrap();
+    let cancel_token = client.cancel_token();
+    let waiting = tokio::spawn(async move {
+        let result = client
+            .execute("INSERT INTO products VALUES (3, 'pad')", &[])
+            .await;
+        (client, result)
+    });
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    assert!(!waiting.is_finished());
+
+    cancel_token.cancel_query(NoTls).await.unwrap();
+    let (client, result) = tokio::time::timeout(Duration::from_secs(5), waiting)
+        .await
+        .expect("the query was not cancelled")
+        .unwrap();
+    let error = result.unwrap_err();
+    assert_eq!(error.code().unwrap().code(), "57014");
+
+    // The connection stays usable, and the cancelled INSERT changed nothing
+    writer.batch_execute("COMMIT").await.unwrap();
+    let count: i64 = client
+        .query_one("SELECT COUNT(*) FROM products", &[])
+        .await
+        .unwrap()
+        .get(0);
+    assert_eq!(count, 2);
+}
+
+#[test]
+fn test_mysql_kill_query() {
+    let file = yaml_file(YAML);
+    let server = TestServer::start_mysql_with_args(file.path().to_str().unwrap(), &["--mutable"]);
+    let mut writer = connect_mysql(server.port(), "yamlbase", "password", "shop").unwrap();
+    writer.query_drop("START TRANSACTION").unwrap();
+    writer
+        .query_drop("INSERT INTO products VALUES (2, 'ink')")
+        .unwrap();
+
+    let mut conn = connect_mysql(server.port(), "yamlbase", "password", "shop").unwrap();
+    let id = conn.connection_id();
+    let waiting = std::thread::spawn(move || {
+        let result = conn.query_drop("INSERT INTO products VALUES (3, 'pad')");
+        (conn, result)
+    });
+    std::thread::sleep(Duration::from_millis(500));
+    assert!(!waiting.is_finished());
+
+    let mut killer = connect_mysql(server.port(), "yamlbase", "password", "shop").unwrap();
+    killer.query_drop(format!("KILL QUERY {}", id)).unwrap();
+    let (mut conn, result) = waiting.join().unwrap();
+    match result {
+        Err(mysql::Error::MySqlError(error)) => assert_eq!(error.code, 1317),
+        other => panic!("expected the query to be killed, got {:?}", other),
+    }
+
+    match killer.query_drop("KILL QUERY 999999") {
+        Err(mysql::Error::MySqlError(error)) => assert_eq!(error.code, 1094),
+        other => panic!("expected an unknown thread id, got {:?}", other),
+    }
+
+    writer.query_drop("COMMIT").unwrap();
+    let count: Option<i64> = conn.query_first("SELECT COUNT(*) FROM products").unwrap();
+    assert_eq!(count, Some(2));
+}
@@ -25,6 +25,7 @@ async fn test_query_timeout_handling() {
             unique: true,
             default: None,
             references: None,
+            collation: None,
         },
         Column {
             name: "customer_id".to_string(),
@@ -34,6 +35,7 @@ async fn test_query_timeout_handling() {
             unique: false,
             default: None,
             references: None,
+            collation: None,
         },
         Column {
             name: "order_date".to_string(),
@@ -43,6 +45,7 @@ async fn test_query_timeout_handling() {
             unique: false,
             default: None,
             references: None,
+            collation: None,
         },
         Column {
             name: "total_amount".to_string(),
@@ -52,6 +55,7 @@ async fn test_query_timeout_handling() {
             unique: false,
             default: None,
             references: None,
+            collation: None,
         },
     ];
 
@@ -82,6 +86,7 @@ async fn test_query_timeout_handling() {
             unique: true,
             default: None,
             references: None,
+            collation: None,
         },
         Column {
             name: "customer_name".to_string(),
@@ -91,6 +96,7 @@ async fn test_query_timeout_handling() {
             unique: false,
             default: None,
             references: None,
+            collation: None,
         },
         Column {
             name: "region".to_string(),
@@ -100,6 +106,7 @@ async fn test_query_timeout_handling() {
             unique: false,
             default: None,
             references: None,
+            collation: None,
         },
     ];
 
@@ -284,6 +291,7 @@ async fn test_timeout_edge_cases() {
             unique: true,
             default: None,
             references: None,
+            collation: None,
         },
         Column {
             name: "value".to_string(),
@@ -293,6 +301,7 @@ async fn test_timeout_edge_cases() {
             unique: false,
             default: None,
             references: None,
+            collation: None,
         },
     ];
 
@@ -0,0 +1,52 @@
+use yamlbase::database::{Database, Table, Value};
+use yamlbase::sql::{QueryExecutor, parse_sql};
+use yamlbase::yaml::schema::SqlType;
+
+mod common;
+use common::{column, new_executor, query};
+
+async fn create_executor(seed: Option<u64>) -> QueryExecutor {
+    let mut db = Database::new("test_db".to_string());
+
+    let mut items = Table::new(
+        "items".to_string(),
+        vec![column("id", SqlType::Integer, true)],
+    );
+    for id in 1..=20 {
+        items.insert_row(vec![Value::Integer(id)]).unwrap();
+    }
+    db.add_table(items).unwrap();
+
+    let executor = new_executor(db).await;
+    match seed {
+        Some(seed) => executor.with_random_seed(seed),
+        None => executor,
+    }
+}
+
+#[tokio::test]
+async fn test_random_values_are_in_unit_range() {
+    let executor = create_executor(None).await;
+
+    let rows = query(&executor, "SELECT RANDOM(), RAND() FROM items").await;
+    assert_eq!(rows.len(), 20);
+    for value in rows.iter().flatten() {
+        assert!(matches!(value, Value::Double(d) if (0.0..1.0).contains(d)));
+    }
+
+    let statements = parse_sql("SELECT RAND(1, 2)").unwrap();
+    assert!(executor.execute(&statements[0]).await.is_err());
+}
+
+#[tokio::test]
+async fn test_random_seed_makes_sampling_reproducible() {
+    let sample = "SELECT id FROM items WHERE RANDOM() < 0.5";
+
+    let first = query(&create_executor(Some(42)).await, sample).await;
+    let second = query(&create_executor(Some(42)).await, sample).await;
+    assert_eq!(first, second);
+    assert!(!first.is_empty() && first.len() < 20);
+
+    let other = query(&create_executor(Some(7)).await, sample).await;
+    assert_ne!(first, other);
+}
@@ -0,0 +1,103 @@
+use std::time::Duration;
+use tokio_postgres::Client;
+use tokio_postgres::error::SqlState;
+
+mod common;
+use common::{TestServer, connect_postgres, yaml_file};
+
+const PRODUCTS: &str = r#"
+database:
+  name: "shop"
+  auth:
+    username: "admin"
+    password: "password"
+    users:
+      - username: "reporting"
+        password: "reports"
+
+tables:
+  products:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+    data:
+      - id: 1
+      - id: 2
+      - id: 3
+      - id: 4
+      - id: 5
+"#;
+
+async fn count(client: &Client) -> Result<i64, tokio_postgres::Error> {
+    let row = client
+        .query_one("SELECT COUNT(*) FROM products", &[])
+        .await?;
+    Ok(row.get(0))
+}
+
+fn is_rate_limited(error: tokio_postgres::Error) -> bool {
+    error.code() == Some(&SqlState::CONFIGURATION_LIMIT_EXCEEDED)
+}
+
+#[tokio::test]
+async fn test_statements_per_second() {
+    let file = yaml_file(PRODUCTS);
+    let server = TestServer::start_postgres_with_args(
+        file.path().to_str().unwrap(),
+        &["--rate-limit-qps", "2"],
+    );
+
+    // Connections from the same address share the quota
+    let first = connect_postgres(server.port(), "admin", "password", "shop")
+        .await
+        .unwrap();
+    let second = connect_postgres(server.port(), "reporting", "reports", "shop")
+        .await
+        .unwrap();
+    assert_eq!(count(&first).await.unwrap(), 5);
+    assert_eq!(count(&second).await.unwrap(), 5);
+    assert!(is_rate_limited(count(&first).await.unwrap_err()));
+
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    assert_eq!(count(&first).await.unwrap(), 5);
+}
+
+#[tokio::test]
+async fn test_rows_per_minute() {
+    let file = yaml_file(PRODUCTS);
+    let server = TestServer::start_postgres_with_args(
+        file.path().to_str().unwrap(),
+        &["--rate-limit-rows", "3"],
+    );
+
+    let client = connect_postgres(server.port(), "admin", "password", "shop")
+        .await
+        .unwrap();
+    // A result is sent whole, even past the quota, and the next statement is refused
+    let rows = client.query("SELECT id FROM products", &[]).await.unwrap();
+    assert_eq!(rows.len(), 5);
+    let error = count(&client).await.unwrap_err();
+    assert!(error.to_string().contains("more than 3 rows a minute"));
+    assert!(is_rate_limited(error));
+}
+
+#[tokio::test]
+async fn test_rate_limit_by_user() {
+    let file = yaml_file(PRODUCTS);
+    let server = TestServer::start_postgres_with_args(
+        file.path().to_str().unwrap(),
+        &["--rate-limit-qps", "1", "--rate-limit-by", "user"],
+    );
+
+    let admin = connect_postgres(server.port(), "admin", "password", "shop")
+        .await
+        .unwrap();
+    let other_admin = connect_postgres(server.port(), "admin", "password", "shop")
+        .await
+        .unwrap();
+    let reporting = connect_postgres(server.port(), "reporting", "reports", "shop")
+        .await
+        .unwrap();
+    assert_eq!(count(&admin).await.unwrap(), 5);
+    assert!(is_rate_limited(count(&other_admin).await.unwrap_err()));
+    assert_eq!(count(&reporting).await.unwrap(), 5);
+}
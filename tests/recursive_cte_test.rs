@@ -0,0 +1,140 @@
+use yamlbase::database::{Database, Table, Value};
+use yamlbase::sql::{QueryExecutor, RecursionLimits, parse_sql};
+use yamlbase::yaml::schema::SqlType;
+
+mod common;
+use common::{column, new_executor};
+
+// Links 1 -> 2 -> 3 -> 1 form a cycle, 3 -> 4 leads out of it
+async fn create_executor() -> QueryExecutor {
+    let mut db = Database::new("test_db".to_string());
+
+    let mut links = Table::new(
+        "links".to_string(),
+        vec![
+            column("id", SqlType::Integer, true),
+            column("from_node", SqlType::Integer, false),
+            column("to_node", SqlType::Integer, false),
+        ],
+    );
+    for (id, (from, to)) in (1..).zip([(1, 2), (2, 3), (3, 1), (3, 4)]) {
+        links
+            .insert_row(vec![
+                Value::Integer(id),
+                Value::Integer(from),
+                Value::Integer(to),
+            ])
+            .unwrap();
+    }
+    db.add_table(links).unwrap();
+
+    new_executor(db).await
+}
+
+async fn execute(executor: &QueryExecutor, sql: &str) -> yamlbase::Result<Vec<Vec<Value>>> {
+    let statements = parse_sql(sql).unwrap();
+    executor
+        .execute(&statements[0])
+        .await
+        .map(|result| result.rows)
+}
+
+fn reachable_query(union: &str) -> String {
+    format!(
+        "WITH RECURSIVE reachable AS ( \
+           SELECT to_node AS node FROM links WHERE from_node = 1 \
+           {} \
+           SELECT l.to_node AS node FROM links l INNER JOIN reachable r ON l.from_node = r.node \
+         ) SELECT node FROM reachable ORDER BY node",
+        union
+    )
+}
+
+#[tokio::test]
+async fn test_recursive_cte_cycles() {
+    let executor = create_executor().await;
+
+    // UNION drops rows that were already produced, so the recursion ends
+    assert_eq!(
+        execute(&executor, &reachable_query("UNION")).await.unwrap(),
+        vec![
+            vec![Value::Integer(1)],
+            vec![Value::Integer(2)],
+            vec![Value::Integer(3)],
+            vec![Value::Integer(4)],
+        ]
+    );
+
+    // UNION ALL would go around the cycle forever
+    let error = execute(&executor, &reachable_query("UNION ALL"))
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("contains a cycle"), "{}", error);
+}
+
+#[tokio::test]
+async fn test_recursion_depth_setting() {
+    let executor = create_executor().await;
+    let path_query = "WITH RECURSIVE paths AS ( \
+           SELECT from_node, to_node, 1 AS depth FROM links WHERE from_node = 1 \
+           UNION ALL \
+           SELECT p.from_node, l.to_node, p.depth + 1 FROM links l \
+           INNER JOIN paths p ON l.from_node = p.to_node \
+         ) SELECT depth FROM paths";
+
+    // Rows differ by depth so no iteration repeats an earlier one; the depth limit stops it
+    execute(&executor, "SET cte_max_recursion_depth = 20")
+        .await
+        .unwrap();
+    assert_eq!(executor.recursion_limits().max_iterations, 20);
+    let error = execute(&executor, path_query)
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(
+        error.contains("maximum recursion depth of 20")
+            && error.contains("cte_max_recursion_depth"),
+        "{}",
+        error
+    );
+
+    for sql in [
+        "SET cte_max_recursion_depth = 0",
+        "SET cte_max_recursion_depth = 'deep'",
+        "SET some_other_setting = 1",
+    ] {
+        assert!(execute(&executor, sql).await.is_err(), "{}", sql);
+    }
+    assert_eq!(executor.recursion_limits().max_iterations, 20);
+}
+
+#[tokio::test]
+async fn test_recursion_row_limit() {
+    let executor = create_executor()
+        .await
+        .with_recursion_limits(RecursionLimits {
+            max_iterations: 1000,
+            max_rows: 10,
+        });
+    let numbers = |limit: i64| {
+        format!(
+            "WITH RECURSIVE numbers AS ( \
+               SELECT 1 AS n FROM links WHERE from_node = 2 \
+               UNION ALL \
+               SELECT n + 1 FROM numbers WHERE n < {} \
+             ) SELECT n FROM numbers",
+            limit
+        )
+    };
+
+    assert_eq!(execute(&executor, &numbers(10)).await.unwrap().len(), 10);
+    let error = execute(&executor, &numbers(11))
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("maximum of 10 rows"), "{}", error);
+
+    execute(&executor, "SET cte_max_rows = 100").await.unwrap();
+    assert_eq!(execute(&executor, &numbers(11)).await.unwrap().len(), 11);
+}
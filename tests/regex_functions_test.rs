@@ -0,0 +1,148 @@
+use yamlbase::database::{Database, Table, Value};
+use yamlbase::sql::{QueryExecutor, SqlDialect, parse_sql};
+use yamlbase::yaml::schema::SqlType;
+
+mod common;
+use common::{column, new_executor, query};
+
+async fn create_executor() -> QueryExecutor {
+    let mut db = Database::new("test_db".to_string());
+
+    let mut users = Table::new(
+        "users".to_string(),
+        vec![
+            column("id", SqlType::Integer, true),
+            column("email", SqlType::Varchar(100), false),
+            column("phone", SqlType::Varchar(20), false),
+        ],
+    );
+    let rows = [
+        (1, "alice@example.com", "555-123-4567"),
+        (2, "Bob@Example.org", "(555) 987 6543"),
+        (3, "carol@test.example.com", "555.222.3333"),
+    ];
+    for (id, email, phone) in rows {
+        users
+            .insert_row(vec![
+                Value::Integer(id),
+                Value::Text(email.to_string()),
+                Value::Text(phone.to_string()),
+            ])
+            .unwrap();
+    }
+    db.add_table(users).unwrap();
+
+    new_executor(db).await
+}
+
+fn ids(rows: &[i64]) -> Vec<Vec<Value>> {
+    rows.iter().map(|id| vec![Value::Integer(*id)]).collect()
+}
+
+fn text(s: &str) -> Value {
+    Value::Text(s.to_string())
+}
+
+#[tokio::test]
+async fn test_regex_operators_in_where() {
+    let executor = create_executor().await;
+
+    let rows = query(
+        &executor,
+        "SELECT id FROM users WHERE email ~ '\\.com$' ORDER BY id",
+    )
+    .await;
+    assert_eq!(rows, ids(&[1, 3]));
+
+    // `~` is case-sensitive, `~*` is not
+    let rows = query(
+        &executor,
+        "SELECT id FROM users WHERE email ~ 'example\\.org'",
+    )
+    .await;
+    assert!(rows.is_empty());
+    let rows = query(
+        &executor,
+        "SELECT id FROM users WHERE email ~* 'EXAMPLE\\.ORG'",
+    )
+    .await;
+    assert_eq!(rows, ids(&[2]));
+
+    let rows = query(
+        &executor,
+        "SELECT id FROM users WHERE phone !~ '^[0-9]{3}-' ORDER BY id",
+    )
+    .await;
+    assert_eq!(rows, ids(&[2, 3]));
+
+    let rows = query(
+        &executor,
+        "SELECT id FROM users WHERE email REGEXP '^[a-c]' ORDER BY id",
+    )
+    .await;
+    assert_eq!(rows, ids(&[1, 2, 3]));
+    let rows = query(
+        &executor,
+        "SELECT id FROM users WHERE email NOT RLIKE 'test' ORDER BY id",
+    )
+    .await;
+    assert_eq!(rows, ids(&[1, 2]));
+}
+
+#[tokio::test]
+async fn test_regex_functions() {
+    let executor = create_executor().await;
+
+    let rows = query(
+        &executor,
+        "SELECT id FROM users WHERE REGEXP_LIKE(email, '^bob', 'i')",
+    )
+    .await;
+    assert_eq!(rows, ids(&[2]));
+
+    let rows = query(
+        &executor,
+        "SELECT REGEXP_SUBSTR(email, '@(.*)$', 1, 1, '', 1), \
+         REGEXP_REPLACE(phone, '[^0-9]', '', 'g') \
+         FROM users ORDER BY id",
+    )
+    .await;
+    assert_eq!(
+        rows,
+        vec![
+            vec![text("example.com"), text("5551234567")],
+            vec![text("Example.org"), text("5559876543")],
+            vec![text("test.example.com"), text("5552223333")],
+        ]
+    );
+
+    // PostgreSQL replaces the first match unless the 'g' flag is given
+    let rows = query(
+        &executor,
+        "SELECT REGEXP_REPLACE('aaa', 'a', 'b'), REGEXP_REPLACE('a-b-c', '-', '+', 'i'), \
+         REGEXP_REPLACE('a-b-c', '-', '+', 'g'), \
+         REGEXP_REPLACE('john smith', '(\\w+) (\\w+)', '\\2, \\1', 'g')",
+    )
+    .await;
+    assert_eq!(
+        rows[0],
+        vec![
+            text("baa"),
+            text("a+b-c"),
+            text("a+b+c"),
+            text("smith, john")
+        ]
+    );
+
+    // MySQL replaces every match by default
+    let mysql = create_executor().await.with_dialect(SqlDialect::MySQL);
+    let rows = query(
+        &mysql,
+        "SELECT REGEXP_REPLACE('aaa', 'a', 'b'), REGEXP_REPLACE('a-b-c', '-', '+', 1, 2)",
+    )
+    .await;
+    assert_eq!(rows[0], vec![text("bbb"), text("a-b+c")]);
+
+    let statements = parse_sql("SELECT REGEXP_LIKE('abc', '(')").unwrap();
+    assert!(executor.execute(&statements[0]).await.is_err());
+}
@@ -0,0 +1,120 @@
+use yamlbase::database::Value;
+use yamlbase::sql::SqlDialect;
+
+mod common;
+use common::{try_query, yaml_executor};
+
+const YAML: &str = r#"
+database:
+  name: "shop"
+
+tables:
+  users:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+      name: "TEXT"
+    data:
+      - id: 1
+        name: "ann"
+  sales.orders:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+      user_id: "INTEGER"
+      total: "INTEGER"
+    data:
+      - id: 10
+        user_id: 1
+        total: 25
+  archive.orders:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+      user_id: "INTEGER"
+      total: "INTEGER"
+    data:
+      - id: 1
+        user_id: 1
+        total: 5
+      - id: 2
+        user_id: 1
+        total: 7
+"#;
+
+#[tokio::test]
+async fn test_schema_qualified_names() {
+    let executor = yaml_executor(YAML).await;
+
+    let rows = try_query(
+        &executor,
+        "SELECT u.name, orders.total FROM public.users u \
+         JOIN sales.orders ON orders.user_id = u.id",
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        rows,
+        vec![vec![Value::Text("ann".to_string()), Value::Integer(25)]]
+    );
+
+    let rows = try_query(
+        &executor,
+        "SELECT MAX(archive.orders.total) FROM archive.orders",
+    )
+    .await
+    .unwrap();
+    assert_eq!(rows, vec![vec![Value::Integer(7)]]);
+
+    // A table in a schema is not found without it
+    assert!(try_query(&executor, "SELECT * FROM orders").await.is_err());
+    assert!(
+        try_query(&executor, "SELECT * FROM finance.orders")
+            .await
+            .is_err()
+    );
+
+    try_query(&executor, "INSERT INTO sales.orders VALUES (11, 1, 3)")
+        .await
+        .unwrap();
+    let rows = try_query(&executor, "SELECT COUNT(*) FROM sales.orders")
+        .await
+        .unwrap();
+    assert_eq!(rows, vec![vec![Value::Integer(2)]]);
+}
+
+#[tokio::test]
+async fn test_search_path() {
+    let executor = yaml_executor(YAML).await;
+
+    try_query(&executor, "SET search_path TO archive, public")
+        .await
+        .unwrap();
+    let rows = try_query(&executor, "SELECT COUNT(*) FROM orders")
+        .await
+        .unwrap();
+    assert_eq!(rows, vec![vec![Value::Integer(2)]]);
+    let rows = try_query(&executor, "SELECT name FROM users")
+        .await
+        .unwrap();
+    assert_eq!(rows, vec![vec![Value::Text("ann".to_string())]]);
+
+    try_query(&executor, "SET search_path = 'sales'")
+        .await
+        .unwrap();
+    let rows = try_query(&executor, "SELECT total FROM orders")
+        .await
+        .unwrap();
+    assert_eq!(rows, vec![vec![Value::Integer(25)]]);
+}
+
+#[tokio::test]
+async fn test_mysql_database_prefix() {
+    let executor = yaml_executor(YAML).await.with_dialect(SqlDialect::MySQL);
+
+    let rows = try_query(&executor, "SELECT shop.users.name FROM shop.users")
+        .await
+        .unwrap();
+    assert_eq!(rows, vec![vec![Value::Text("ann".to_string())]]);
+    let rows = try_query(&executor, "SELECT COUNT(*) FROM shop.archive.orders")
+        .await
+        .unwrap();
+    assert_eq!(rows, vec![vec![Value::Integer(2)]]);
+}
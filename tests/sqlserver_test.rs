@@ -0,0 +1,234 @@
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use std::str::FromStr;
+use tiberius::{AuthMethod, Client, Config, EncryptionLevel};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio_util::compat::{Compat, TokioAsyncWriteCompatExt};
+
+mod common;
+use common::{TestServer, yaml_file};
+
+/// The YAML of a database named `name`
+fn yaml(name: &str) -> String {
+    format!(
+        r#"
+database:
+  name: "{}"
+  auth:
+    username: "sa"
+    password: "Passw0rd"
+
+tables:
+  products:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+      name: "VARCHAR(100) NOT NULL"
+      price: "DECIMAL(10,2)"
+      in_stock: "BOOLEAN"
+      added: "DATE"
+      rating: "DOUBLE"
+    data:
+      - id: 1
+        name: "Widget"
+        price: 9.99
+        in_stock: true
+        added: "2024-01-15"
+        rating: 4.5
+      - id: 2
+        name: "Gadget"
+        price: 24.50
+        in_stock: false
+        added: "2024-03-01"
+        rating: null
+"#,
+        name
+    )
+}
+
+async fn connect(
+    server: &TestServer,
+    password: &str,
+    database: Option<&str>,
+) -> tiberius::Result<Client<Compat<TcpStream>>> {
+    let mut config = Config::new();
+    config.host("127.0.0.1");
+    config.port(server.port());
+    config.authentication(AuthMethod::sql_server("sa", password));
+    config.encryption(EncryptionLevel::NotSupported);
+    if let Some(database) = database {
+        config.database(database);
+    }
+    let tcp = TcpStream::connect(config.get_addr()).await.unwrap();
+    tcp.set_nodelay(true).unwrap();
+    Client::connect(config, tcp.compat_write()).await
+}
+
+#[tokio::test]
+async fn test_sqlserver_result_sets() {
+    let file = yaml_file(&yaml("shop"));
+    let server = TestServer::start_sqlserver(file.path().to_str().unwrap());
+    let mut client = connect(&server, "Passw0rd", None).await.unwrap();
+
+    let rows = client
+        .simple_query("SELECT id, name, price, in_stock, added, rating FROM [products] ORDER BY id")
+        .await
+        .unwrap()
+        .into_first_result()
+        .await
+        .unwrap();
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0].get::<i64, _>("id"), Some(1));
+    assert_eq!(rows[0].get::<&str, _>("name"), Some("Widget"));
+    assert_eq!(
+        rows[0].get::<Decimal, _>("price"),
+        Some(Decimal::from_str("9.99").unwrap())
+    );
+    assert_eq!(rows[0].get::<bool, _>("in_stock"), Some(true));
+    assert_eq!(
+        rows[0].get::<NaiveDate, _>("added"),
+        NaiveDate::from_ymd_opt(2024, 1, 15)
+    );
+    assert_eq!(rows[0].get::<f64, _>("rating"), Some(4.5));
+    assert_eq!(rows[1].get::<bool, _>("in_stock"), Some(false));
+    assert_eq!(rows[1].get::<f64, _>("rating"), None);
+
+    // T-SQL's TOP and ISNULL
+    let row = client
+        .simple_query(
+            "SELECT TOP 1 name, ISNULL(rating, 0) AS rating FROM products ORDER BY id DESC",
+        )
+        .await
+        .unwrap()
+        .into_row()
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(row.get::<&str, _>("name"), Some("Gadget"));
+    assert_eq!(row.get::<i64, _>("rating"), Some(0));
+
+    // Text longer than NVARCHAR(4000) is sent as NVARCHAR(MAX), over several packets
+    let row = client
+        .simple_query("SELECT REPEAT('x', 10000)")
+        .await
+        .unwrap()
+        .into_row()
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(row.get::<&str, _>(0).map(str::len), Some(10000));
+}
+
+#[tokio::test]
+async fn test_sqlserver_batches() {
+    let file = yaml_file(&yaml("shop"));
+    let server = TestServer::start_sqlserver(file.path().to_str().unwrap());
+    let mut client = connect(&server, "Passw0rd", None).await.unwrap();
+
+    // Each statement of a batch has its own result, and the SET options drivers send are
+    // accepted
+    let results = client
+        .simple_query(
+            "SET NOCOUNT ON; SELECT COUNT(*) FROM products; SELECT name FROM products WHERE id = 2",
+        )
+        .await
+        .unwrap()
+        .into_results()
+        .await
+        .unwrap();
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0][0].get::<i64, _>(0), Some(2));
+    assert_eq!(results[1][0].get::<&str, _>(0), Some("Gadget"));
+
+    let error = client
+        .simple_query("SELECT * FROM missing")
+        .await
+        .err()
+        .unwrap();
+    assert!(error.to_string().contains("missing"), "{}", error);
+
+    // Parameters come in a remote procedure call, which is not supported
+    assert!(
+        client
+            .query("SELECT name FROM products WHERE id = @P1", &[&1i32])
+            .await
+            .is_err()
+    );
+
+    // The connection is still usable after errors
+    let row = client
+        .simple_query("SELECT 'still here'")
+        .await
+        .unwrap()
+        .into_row()
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(row.get::<&str, _>(0), Some("still here"));
+}
+
+#[tokio::test]
+async fn test_sqlserver_login() {
+    let shop = yaml_file(&yaml("shop"));
+    let archive = yaml_file(&yaml("archive"));
+    let server = TestServer::start_sqlserver_with_args(
+        shop.path().to_str().unwrap(),
+        &["-f", archive.path().to_str().unwrap()],
+    );
+
+    let error = connect(&server, "wrong", None).await.err().unwrap();
+    assert!(error.to_string().contains("Login failed"), "{}", error);
+    let error = connect(&server, "Passw0rd", Some("missing"))
+        .await
+        .err()
+        .unwrap();
+    assert!(error.to_string().contains("does not exist"), "{}", error);
+
+    // The database of the login, and those USE switches to
+    let mut client = connect(&server, "Passw0rd", Some("archive")).await.unwrap();
+    let database = |rows: Vec<tiberius::Row>| rows[0].get::<&str, _>(0).unwrap().to_string();
+    let rows = client
+        .simple_query("SELECT DATABASE()")
+        .await
+        .unwrap()
+        .into_first_result()
+        .await
+        .unwrap();
+    assert_eq!(database(rows), "archive");
+
+    client.simple_query("USE shop").await.unwrap();
+    let rows = client
+        .simple_query("SELECT DATABASE()")
+        .await
+        .unwrap()
+        .into_first_result()
+        .await
+        .unwrap();
+    assert_eq!(database(rows), "shop");
+}
+
+#[tokio::test]
+async fn test_sqlserver_message_size_limit() {
+    let file = yaml_file(&yaml("shop"));
+    let server = TestServer::start_sqlserver(file.path().to_str().unwrap());
+
+    // A PRELOGIN message that never ends: its packets stop being read past 4096 packets
+    // of the default size, 4096 bytes
+    let mut stream = TcpStream::connect(("127.0.0.1", server.port()))
+        .await
+        .unwrap();
+    let mut packet = vec![0u8; u16::MAX as usize];
+    packet[..4].copy_from_slice(&[0x12, 0x00, 0xff, 0xff]);
+    for _ in 0..(4096 * 4096 / packet.len() + 1) {
+        stream.write_all(&packet).await.unwrap();
+    }
+
+    // The error ends the connection
+    let mut header = [0u8; 8];
+    stream.read_exact(&mut header).await.unwrap();
+    assert_eq!(header[0], 0x04);
+    let mut payload = vec![0u8; u16::from_be_bytes([header[2], header[3]]) as usize - 8];
+    stream.read_exact(&mut payload).await.unwrap();
+    assert_eq!(payload[0], 0xaa);
+    assert_eq!(stream.read(&mut header).await.unwrap(), 0);
+}
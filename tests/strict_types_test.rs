@@ -0,0 +1,93 @@
+use std::process::Command;
+use tempfile::NamedTempFile;
+use yamlbase::database::Value;
+use yamlbase::yaml::parse_yaml_database;
+
+mod common;
+use common::yaml_file;
+
+const YAML: &str = r#"
+database:
+  name: "shop"
+
+tables:
+  items:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+      code: "TEXT"
+      active: "BOOLEAN"
+    data:
+      - id: "1"
+        code: 42
+        active: "yes"
+      - id: 2
+        code: "B7"
+        active: 0
+"#;
+
+fn validate(args: &[&str], file: &NamedTempFile) -> (bool, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_yamlbase"))
+        .args(args)
+        .arg("validate")
+        .arg("-f")
+        .arg(file.path())
+        .output()
+        .unwrap();
+    (
+        output.status.success(),
+        String::from_utf8_lossy(&output.stdout).into_owned(),
+    )
+}
+
+#[tokio::test]
+async fn test_lenient_conversions() {
+    let file = yaml_file(YAML);
+    let (database, _) = parse_yaml_database(file.path()).await.unwrap();
+    assert_eq!(
+        database.tables["items"].rows,
+        vec![
+            vec![
+                Value::Integer(1),
+                Value::Text("42".to_string()),
+                Value::Boolean(true)
+            ],
+            vec![
+                Value::Integer(2),
+                Value::Text("B7".to_string()),
+                Value::Boolean(false)
+            ],
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_strict_table() {
+    let file = yaml_file(&YAML.replace("    columns:", "    strict_types: true\n    columns:"));
+    let error = parse_yaml_database(file.path())
+        .await
+        .unwrap_err()
+        .to_string();
+    for column in ["id", "code", "active"] {
+        assert!(error.contains(&format!("column '{}'", column)), "{}", error);
+    }
+    assert!(
+        error.contains("\"1\" is a string, not Integer, and strict types do not convert it"),
+        "{}",
+        error
+    );
+    assert_eq!(error.lines().count(), 4, "{}", error);
+}
+
+#[test]
+fn test_strict_types_flag() {
+    let file = yaml_file(YAML);
+    assert!(validate(&[], &file).0);
+    let (success, output) = validate(&["--strict-types"], &file);
+    assert!(!success);
+    assert!(output.contains("4 problems"), "{}", output);
+
+    // A table can keep converting under --strict-types
+    let file = yaml_file(&YAML.replace("    columns:", "    strict_types: false\n    columns:"));
+    let (success, output) = validate(&["--strict-types"], &file);
+    assert!(success, "{}", output);
+}
@@ -0,0 +1,159 @@
+use yamlbase::database::{Database, Table, Value};
+use yamlbase::sql::{QueryExecutor, parse_sql};
+use yamlbase::yaml::schema::SqlType;
+
+mod common;
+use common::{column, new_executor, query};
+
+fn text(s: &str) -> Value {
+    Value::Text(s.to_string())
+}
+
+async fn create_executor() -> QueryExecutor {
+    let mut db = Database::new("test_db".to_string());
+
+    let mut people = Table::new(
+        "people".to_string(),
+        vec![
+            column("id", SqlType::Integer, true),
+            column("first_name", SqlType::Text, false),
+            column("last_name", SqlType::Text, false),
+            column("score", SqlType::Double, false),
+        ],
+    );
+    people
+        .insert_row(vec![
+            Value::Integer(1),
+            text("Ada"),
+            text("Lovelace"),
+            Value::Double(1.5),
+        ])
+        .unwrap();
+    people
+        .insert_row(vec![
+            Value::Integer(2),
+            text("Alan"),
+            Value::Null,
+            Value::Double(2.0),
+        ])
+        .unwrap();
+
+    let mut orders = Table::new(
+        "orders".to_string(),
+        vec![
+            column("id", SqlType::Integer, true),
+            column("person_id", SqlType::Integer, false),
+        ],
+    );
+    orders
+        .insert_row(vec![Value::Integer(10), Value::Integer(1)])
+        .unwrap();
+
+    db.add_table(people).unwrap();
+    db.add_table(orders).unwrap();
+    new_executor(db).await
+}
+
+#[tokio::test]
+async fn test_concat_operator() {
+    let executor = create_executor().await;
+
+    assert_eq!(
+        query(&executor, "SELECT 'a' || 'b' || 1 || 2.5").await,
+        vec![vec![text("ab12.5")]]
+    );
+
+    // NULL on either side gives NULL; numbers are concatenated in their text form
+    assert_eq!(
+        query(
+            &executor,
+            "SELECT first_name || ' ' || last_name, first_name || ':' || score \
+             FROM people ORDER BY id",
+        )
+        .await,
+        vec![
+            vec![text("Ada Lovelace"), text("Ada:1.5")],
+            vec![Value::Null, text("Alan:2")],
+        ]
+    );
+
+    assert_eq!(
+        query(
+            &executor,
+            "SELECT id FROM people WHERE first_name || last_name = 'AdaLovelace'",
+        )
+        .await,
+        vec![vec![Value::Integer(1)]]
+    );
+
+    assert_eq!(
+        query(
+            &executor,
+            "SELECT p.first_name || '#' || o.id FROM people p \
+             JOIN orders o ON o.person_id = p.id",
+        )
+        .await,
+        vec![vec![text("Ada#10")]]
+    );
+
+    assert_eq!(
+        query(
+            &executor,
+            "SELECT first_name, first_name || '!', COUNT(*) FROM people GROUP BY first_name \
+             ORDER BY first_name",
+        )
+        .await,
+        vec![
+            vec![text("Ada"), text("Ada!"), Value::Integer(1)],
+            vec![text("Alan"), text("Alan!"), Value::Integer(1)],
+        ]
+    );
+
+    assert_eq!(
+        query(
+            &executor,
+            "WITH names AS (SELECT id, first_name || '?' AS name FROM people) \
+             SELECT name || '!' FROM names ORDER BY id",
+        )
+        .await,
+        vec![vec![text("Ada?!")], vec![text("Alan?!")]]
+    );
+}
+
+#[tokio::test]
+async fn test_concat_ws() {
+    let executor = create_executor().await;
+
+    // NULL values are skipped, a NULL separator gives NULL
+    assert_eq!(
+        query(
+            &executor,
+            "SELECT CONCAT_WS(', ', 'a', NULL, 'b'), CONCAT_WS(NULL, 'a', 'b'), \
+             CONCAT_WS('-', NULL)",
+        )
+        .await,
+        vec![vec![text("a, b"), Value::Null, text("")]]
+    );
+
+    assert_eq!(
+        query(
+            &executor,
+            "SELECT CONCAT_WS(' ', first_name, last_name, id) FROM people ORDER BY id",
+        )
+        .await,
+        vec![vec![text("Ada Lovelace 1")], vec![text("Alan 2")]]
+    );
+
+    assert_eq!(
+        query(
+            &executor,
+            "SELECT p.id FROM people p JOIN orders o ON o.person_id = p.id \
+             WHERE CONCAT_WS(' ', p.first_name, p.last_name) = 'Ada Lovelace'",
+        )
+        .await,
+        vec![vec![Value::Integer(1)]]
+    );
+
+    let statements = parse_sql("SELECT CONCAT_WS(',')").unwrap();
+    assert!(executor.execute(&statements[0]).await.is_err());
+}
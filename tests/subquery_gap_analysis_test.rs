@@ -21,6 +21,7 @@ async fn test_subquery_gap_analysis() {
                 unique: true,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "value".to_string(),
@@ -30,6 +31,7 @@ async fn test_subquery_gap_analysis() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
         ],
     );
@@ -22,6 +22,7 @@ async fn test_comprehensive_subquery_validation() {
                 unique: true,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "name".to_string(),
@@ -31,6 +32,7 @@ async fn test_comprehensive_subquery_validation() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "department_id".to_string(),
@@ -40,6 +42,7 @@ async fn test_comprehensive_subquery_validation() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "salary".to_string(),
@@ -49,6 +52,7 @@ async fn test_comprehensive_subquery_validation() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
         ],
     );
@@ -98,6 +102,7 @@ async fn test_comprehensive_subquery_validation() {
                 unique: true,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "name".to_string(),
@@ -107,6 +112,7 @@ async fn test_comprehensive_subquery_validation() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
         ],
     );
@@ -216,13 +222,12 @@ async fn test_comprehensive_subquery_validation() {
                 "   ✅ Scalar subquery in SELECT works! Got {} rows",
                 result.rows.len()
             );
-            if !result.rows.is_empty() {
-                if let (Value::Text(name), Value::Integer(salary), Value::Integer(max_sal)) =
+            if !result.rows.is_empty()
+                && let (Value::Text(name), Value::Integer(salary), Value::Integer(max_sal)) =
                     (&result.rows[0][0], &result.rows[0][1], &result.rows[0][2])
-                {
-                    println!("      - {name}: ${salary} (max: ${max_sal})");
-                    assert_eq!(*max_sal, 80000, "Max salary should be 80000");
-                }
+            {
+                println!("      - {name}: ${salary} (max: ${max_sal})");
+                assert_eq!(*max_sal, 80000, "Max salary should be 80000");
             }
         }
         Err(e) => {
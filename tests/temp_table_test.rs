@@ -0,0 +1,275 @@
+use std::sync::Arc;
+use tokio_postgres::SimpleQueryMessage;
+use yamlbase::database::{Storage, Value};
+use yamlbase::sql::{QueryExecutor, WriteMode, parse_sql};
+use yamlbase::yaml::parse_yaml_database;
+
+mod common;
+use common::{TestServer, connect_postgres, yaml_file};
+
+const YAML: &str = r#"
+database:
+  name: "test_db"
+
+tables:
+  orders:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+      customer: "VARCHAR(20) NOT NULL"
+      amount: "DECIMAL(10,2)"
+      placed: "DATE"
+    data:
+      - id: 1
+        customer: "ann"
+        amount: 10.50
+        placed: "2024-01-05"
+      - id: 2
+        customer: "bob"
+        amount: 4.00
+        placed: "2024-01-06"
+      - id: 3
+        customer: "ann"
+        amount: 7.25
+        placed: "2024-02-01"
+"#;
+
+async fn create_storage() -> Arc<Storage> {
+    let file = yaml_file(YAML);
+    let (db, _) = parse_yaml_database(file.path()).await.unwrap();
+    Arc::new(Storage::new(db))
+}
+
+async fn execute(
+    executor: &QueryExecutor,
+    sql: &str,
+) -> yamlbase::Result<yamlbase::sql::executor::QueryResult> {
+    let statements = parse_sql(sql).unwrap();
+    executor.execute(&statements[0]).await
+}
+
+async fn query(executor: &QueryExecutor, sql: &str) -> Vec<Vec<Value>> {
+    execute(executor, sql).await.unwrap().rows
+}
+
+fn text(value: &str) -> Value {
+    Value::Text(value.to_string())
+}
+
+#[tokio::test]
+async fn test_create_temporary_table() {
+    let storage = create_storage().await;
+    let executor = QueryExecutor::new(storage.clone()).await.unwrap();
+
+    execute(
+        &executor,
+        "CREATE TEMPORARY TABLE notes (id INTEGER PRIMARY KEY, order_id INTEGER NOT NULL, \
+         body VARCHAR(10) DEFAULT 'none', flagged BOOLEAN DEFAULT false)",
+    )
+    .await
+    .unwrap();
+    execute(
+        &executor,
+        "INSERT INTO notes (id, order_id) VALUES (1, 1), (2, 3)",
+    )
+    .await
+    .unwrap();
+    execute(&executor, "UPDATE notes SET body = 'late' WHERE id = 2")
+        .await
+        .unwrap();
+
+    assert_eq!(
+        query(
+            &executor,
+            "SELECT o.customer, n.body, n.flagged FROM orders o \
+             JOIN notes n ON n.order_id = o.id ORDER BY n.id",
+        )
+        .await,
+        vec![
+            vec![text("ann"), text("none"), Value::Boolean(false)],
+            vec![text("ann"), text("late"), Value::Boolean(false)],
+        ]
+    );
+
+    // Constraints of the definition apply
+    assert!(
+        execute(&executor, "INSERT INTO notes (id, order_id) VALUES (1, 2)")
+            .await
+            .is_err()
+    );
+    assert!(
+        execute(&executor, "INSERT INTO notes (id, body) VALUES (3, 'x')")
+            .await
+            .is_err()
+    );
+
+    // Other connections do not see the table
+    let other = QueryExecutor::new(storage).await.unwrap();
+    assert!(execute(&other, "SELECT * FROM notes").await.is_err());
+
+    assert!(
+        execute(&executor, "CREATE TABLE notes (id INTEGER)")
+            .await
+            .is_err()
+    );
+    execute(&executor, "CREATE TABLE IF NOT EXISTS notes (id INTEGER)")
+        .await
+        .unwrap();
+    execute(&executor, "DROP TABLE notes").await.unwrap();
+    assert!(execute(&executor, "SELECT * FROM notes").await.is_err());
+    assert!(execute(&executor, "DROP TABLE notes").await.is_err());
+    execute(&executor, "DROP TABLE IF EXISTS notes")
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_create_table_as_select() {
+    let executor = QueryExecutor::new(create_storage().await).await.unwrap();
+
+    let result = execute(
+        &executor,
+        "CREATE TEMP TABLE totals AS SELECT customer, SUM(amount) AS total, COUNT(*) AS orders \
+         FROM orders GROUP BY customer",
+    )
+    .await
+    .unwrap();
+    assert_eq!(result.affected_rows, Some(2));
+
+    let result = execute(&executor, "SELECT * FROM totals ORDER BY customer")
+        .await
+        .unwrap();
+    assert_eq!(result.columns, vec!["customer", "total", "orders"]);
+    assert_eq!(result.rows.len(), 2);
+    assert_eq!(result.rows[0][0], text("ann"));
+    assert_eq!(result.rows[0][2], Value::Integer(2));
+    assert!(!matches!(result.rows[0][1], Value::Text(_) | Value::Null));
+
+    // The next step of the workflow builds on the previous one
+    execute(
+        &executor,
+        "CREATE TABLE big_spenders (name TEXT) AS SELECT customer FROM totals WHERE total > 10",
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        query(&executor, "SELECT name FROM big_spenders").await,
+        vec![vec![text("ann")]]
+    );
+
+    execute(
+        &executor,
+        "CREATE OR REPLACE TEMPORARY TABLE big_spenders AS SELECT customer FROM orders \
+         WHERE amount < 0",
+    )
+    .await
+    .unwrap();
+    assert!(
+        query(&executor, "SELECT * FROM big_spenders")
+            .await
+            .is_empty()
+    );
+}
+
+#[tokio::test]
+async fn test_yaml_tables_stay() {
+    let executor = QueryExecutor::new(create_storage().await).await.unwrap();
+
+    assert!(execute(&executor, "DROP TABLE orders").await.is_err());
+    assert!(
+        execute(&executor, "CREATE TABLE orders (id INTEGER)")
+            .await
+            .is_err()
+    );
+
+    // A temporary table hides the YAML table until it is dropped
+    execute(
+        &executor,
+        "CREATE TEMPORARY TABLE orders AS SELECT id FROM orders WHERE customer = 'bob'",
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        query(&executor, "SELECT * FROM orders").await,
+        vec![vec![Value::Integer(2)]]
+    );
+    execute(&executor, "DROP TABLE orders").await.unwrap();
+    assert_eq!(
+        query(&executor, "SELECT COUNT(*) FROM orders").await,
+        vec![vec![Value::Integer(3)]]
+    );
+}
+
+#[tokio::test]
+async fn test_temporary_tables_in_transactions() {
+    let storage = create_storage().await;
+    let executor = QueryExecutor::new(storage.clone())
+        .await
+        .unwrap()
+        .with_write_mode(WriteMode::Shared);
+
+    execute(&executor, "BEGIN").await.unwrap();
+    execute(&executor, "CREATE TEMP TABLE scratch (id INTEGER)")
+        .await
+        .unwrap();
+    execute(&executor, "ROLLBACK").await.unwrap();
+    assert!(execute(&executor, "SELECT * FROM scratch").await.is_err());
+
+    // Writes to temporary tables are never shared
+    execute(&executor, "CREATE TEMP TABLE scratch (id INTEGER)")
+        .await
+        .unwrap();
+    execute(&executor, "INSERT INTO scratch VALUES (1)")
+        .await
+        .unwrap();
+    execute(&executor, "DELETE FROM orders WHERE id = 1")
+        .await
+        .unwrap();
+    assert_eq!(
+        query(&executor, "SELECT COUNT(*) FROM scratch").await,
+        vec![vec![Value::Integer(1)]]
+    );
+    let db_arc = storage.database();
+    let db = db_arc.read().await;
+    assert!(db.get_table("scratch").is_none());
+    assert_eq!(db.get_table("orders").unwrap().rows.len(), 2);
+}
+
+#[tokio::test]
+async fn test_temporary_tables_over_postgres() {
+    let yaml = YAML.replace(
+        "  name: \"test_db\"\n",
+        "  name: \"test_db\"\n  auth:\n    username: \"yamlbase\"\n    password: \"password\"\n",
+    );
+    let file = yaml_file(&yaml);
+    let server = TestServer::start_postgres(file.path().to_str().unwrap());
+
+    let client = connect_postgres(server.port(), "yamlbase", "password", "test_db")
+        .await
+        .unwrap();
+
+    let messages = client
+        .simple_query("CREATE TEMP TABLE ann_orders AS SELECT * FROM orders WHERE customer = 'ann'")
+        .await
+        .unwrap();
+    assert!(matches!(
+        messages.last(),
+        Some(SimpleQueryMessage::CommandComplete(2))
+    ));
+
+    for sql in ["CREATE TEMP TABLE tags (name TEXT)", "DROP TABLE tags"] {
+        client.batch_execute(sql).await.unwrap();
+    }
+
+    let rows = client
+        .simple_query("SELECT COUNT(*) FROM ann_orders")
+        .await
+        .unwrap();
+    let count = rows
+        .iter()
+        .find_map(|message| match message {
+            SimpleQueryMessage::Row(row) => Some(row.get(0).unwrap().to_string()),
+            _ => None,
+        })
+        .unwrap();
+    assert_eq!(count, "2");
+}
@@ -21,6 +21,7 @@ async fn test_add_months_and_last_day() {
             unique: true,
             default: None,
             references: None,
+            collation: None,
         }],
     );
 
@@ -0,0 +1,170 @@
+use clap::Parser;
+use std::time::Duration;
+use tokio::io::AsyncReadExt;
+use tokio::net::TcpStream;
+use yamlbase::config::Config;
+
+mod common;
+use common::{TestServer, connect_postgres, yaml_file};
+
+const PRODUCTS: &str = r#"
+database:
+  name: "shop"
+  auth:
+    username: "admin"
+    password: "password"
+
+tables:
+  products:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+    data:
+      - id: 1
+"#;
+
+#[tokio::test]
+async fn test_statement_timeout() {
+    let file = yaml_file(PRODUCTS);
+    let server = TestServer::start_postgres_with_args(
+        file.path().to_str().unwrap(),
+        &["--mutable", "--statement-timeout", "300ms"],
+    );
+
+    // A transaction holds the writer lock until it ends, so other writes wait for it
+    let writer = connect_postgres(server.port(), "admin", "password", "shop")
+        .await
+        .unwrap();
+    writer.batch_execute("BEGIN").await.unwrap();
+    writer
+        .execute("INSERT INTO products (id) VALUES (2)", &[])
+        .await
+        .unwrap();
+
+    let waiting = connect_postgres(server.port(), "admin", "password", "shop")
+        .await
+        .unwrap();
+    // SHOW reports the configured timeout until the connection sets its own
+    let row = waiting
+        .query_one("SHOW statement_timeout", &[])
+        .await
+        .unwrap();
+    assert_eq!(row.get::<_, String>(0), "300ms");
+    let error = waiting
+        .execute("INSERT INTO products (id) VALUES (3)", &[])
+        .await
+        .unwrap_err();
+    assert!(
+        error.to_string().contains("timeout after 300ms"),
+        "{}",
+        error
+    );
+
+    waiting
+        .batch_execute("SET statement_timeout = '100ms'")
+        .await
+        .unwrap();
+    let row = waiting
+        .query_one("SHOW statement_timeout", &[])
+        .await
+        .unwrap();
+    assert_eq!(row.get::<_, String>(0), "100ms");
+    let error = waiting
+        .execute("INSERT INTO products (id) VALUES (3)", &[])
+        .await
+        .unwrap_err();
+    assert!(
+        error.to_string().contains("timeout after 100ms"),
+        "{}",
+        error
+    );
+
+    assert!(
+        waiting
+            .batch_execute("SET statement_timeout = 'soon'")
+            .await
+            .is_err()
+    );
+    waiting
+        .batch_execute("RESET statement_timeout")
+        .await
+        .unwrap();
+    let row = waiting
+        .query_one("SHOW statement_timeout", &[])
+        .await
+        .unwrap();
+    assert_eq!(row.get::<_, String>(0), "300ms");
+    writer.batch_execute("ROLLBACK").await.unwrap();
+}
+
+#[tokio::test]
+async fn test_idle_timeout() {
+    let file = yaml_file(PRODUCTS);
+    let server = TestServer::start_postgres_with_args(
+        file.path().to_str().unwrap(),
+        &["--idle-timeout", "300ms"],
+    );
+
+    let client = connect_postgres(server.port(), "admin", "password", "shop")
+        .await
+        .unwrap();
+    client
+        .query_one("SELECT COUNT(*) FROM products", &[])
+        .await
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(800)).await;
+    assert!(
+        client
+            .query_one("SELECT COUNT(*) FROM products", &[])
+            .await
+            .is_err()
+    );
+    assert!(client.is_closed());
+}
+
+#[tokio::test]
+async fn test_connect_timeout() {
+    let file = yaml_file(PRODUCTS);
+    let server = TestServer::start_postgres_with_args(
+        file.path().to_str().unwrap(),
+        &["--connect-timeout", "300ms"],
+    );
+
+    // A client that never sends its startup message is disconnected
+    let mut stream = TcpStream::connect(("127.0.0.1", server.port()))
+        .await
+        .unwrap();
+    let mut buffer = [0u8; 16];
+    let read = tokio::time::timeout(Duration::from_secs(5), stream.read(&mut buffer)).await;
+    assert!(matches!(read, Ok(Ok(0)) | Ok(Err(_))));
+
+    // Sessions that logged in outlive it
+    let client = connect_postgres(server.port(), "admin", "password", "shop")
+        .await
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(600)).await;
+    client
+        .query_one("SELECT COUNT(*) FROM products", &[])
+        .await
+        .unwrap();
+}
+
+#[test]
+fn test_timeout_options() {
+    let config = Config::try_parse_from([
+        "yamlbase",
+        "-f",
+        "db.yaml",
+        "--idle-timeout",
+        "5min",
+        "--statement-timeout",
+        "0s",
+    ])
+    .unwrap();
+    assert_eq!(config.idle_timeout, Some(Duration::from_secs(300)));
+    assert_eq!(config.statement_timeout(), Duration::ZERO);
+    assert_eq!(config.connect_timeout(), Duration::from_secs(30));
+
+    assert!(
+        Config::try_parse_from(["yamlbase", "-f", "db.yaml", "--connect-timeout", "soon"]).is_err()
+    );
+}
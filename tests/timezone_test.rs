@@ -0,0 +1,109 @@
+use yamlbase::database::{TimeZone, Value};
+use yamlbase::sql::{QueryExecutor, parse_sql};
+
+mod common;
+use common::yaml_executor;
+
+const YAML: &str = r#"
+database:
+  name: "test_db"
+
+tables:
+  events:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+      happened_at: "TIMESTAMP WITH TIME ZONE"
+    data:
+      - id: 1
+        happened_at: "2024-03-01T12:00:00Z"
+      - id: 2
+        happened_at: "2024-07-01 12:00:00"
+      - id: 3
+        happened_at: "2024-07-01 12:00:00-04:00"
+"#;
+
+// Every test in this file uses the same server-wide default zone
+async fn create_executor() -> QueryExecutor {
+    TimeZone::set_default(TimeZone::parse("Europe/Amsterdam").unwrap());
+
+    yaml_executor(YAML).await
+}
+
+async fn query_text(executor: &QueryExecutor, sql: &str) -> Vec<Vec<String>> {
+    let statements = parse_sql(sql).unwrap();
+    let rows = executor.execute(&statements[0]).await.unwrap().rows;
+    rows.iter()
+        .map(|row| row.iter().map(|value| value.to_string()).collect())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_timestamptz_columns_use_the_default_zone() {
+    let executor = create_executor().await;
+
+    let statements = parse_sql("SELECT happened_at FROM events ORDER BY id").unwrap();
+    let result = executor.execute(&statements[0]).await.unwrap();
+    assert!(matches!(result.rows[0][0], Value::TimestampTz(_)));
+    assert_eq!(
+        query_text(&executor, "SELECT happened_at FROM events ORDER BY id").await,
+        vec![
+            vec!["2024-03-01 13:00:00+01"],
+            vec!["2024-07-01 12:00:00+02"],
+            vec!["2024-07-01 18:00:00+02"],
+        ]
+    );
+
+    // Comparisons are between instants, whatever offset a literal is written with
+    assert_eq!(
+        query_text(
+            &executor,
+            "SELECT id FROM events \
+             WHERE happened_at > TIMESTAMPTZ '2024-07-01 11:00:00+01' ORDER BY id",
+        )
+        .await,
+        vec![vec!["3"]]
+    );
+}
+
+#[tokio::test]
+async fn test_at_time_zone() {
+    let executor = create_executor().await;
+
+    // TIMESTAMPTZ AT TIME ZONE gives the wall-clock time in that zone
+    assert_eq!(
+        query_text(
+            &executor,
+            "SELECT happened_at AT TIME ZONE 'UTC', happened_at AT TIME ZONE 'America/New_York' \
+             FROM events ORDER BY id",
+        )
+        .await,
+        vec![
+            vec!["2024-03-01 12:00:00", "2024-03-01 07:00:00"],
+            vec!["2024-07-01 10:00:00", "2024-07-01 06:00:00"],
+            vec!["2024-07-01 16:00:00", "2024-07-01 12:00:00"],
+        ]
+    );
+
+    // TIMESTAMP AT TIME ZONE reads the wall-clock time in that zone; a time skipped by the
+    // start of daylight saving uses the offset from before the jump
+    assert_eq!(
+        query_text(
+            &executor,
+            "SELECT TIMESTAMP '2024-01-15 09:00:00' AT TIME ZONE 'Asia/Kolkata', \
+             TIMESTAMP '2024-03-31 02:30:00' AT TIME ZONE 'Europe/Amsterdam', \
+             CAST('2024-01-01 00:00:00+05' AS TIMESTAMP WITH TIME ZONE), \
+             CAST(TIMESTAMPTZ '2024-01-01 00:00:00+05' AS DATE)",
+        )
+        .await,
+        vec![vec![
+            "2024-01-15 04:30:00+01",
+            "2024-03-31 03:30:00+02",
+            "2023-12-31 20:00:00+01",
+            "2023-12-31",
+        ]]
+    );
+
+    let statements =
+        parse_sql("SELECT TIMESTAMP '2024-01-01' AT TIME ZONE 'Mars/Olympus'").unwrap();
+    assert!(executor.execute(&statements[0]).await.is_err());
+}
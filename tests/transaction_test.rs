@@ -0,0 +1,255 @@
+use std::sync::Arc;
+use tokio_postgres::error::SqlState;
+use yamlbase::database::{Storage, Value};
+use yamlbase::sql::{QueryExecutor, SqlDialect, WriteMode, parse_sql, parse_sql_with_dialect};
+use yamlbase::yaml::parse_yaml_database;
+
+mod common;
+use common::{TestServer, connect_postgres, yaml_file};
+
+const YAML: &str = r#"
+database:
+  name: "test_db"
+
+tables:
+  items:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+      name: "TEXT"
+    data:
+      - id: 1
+        name: "one"
+"#;
+
+async fn create_storage() -> Arc<Storage> {
+    let file = yaml_file(YAML);
+    let (db, _) = parse_yaml_database(file.path()).await.unwrap();
+    Arc::new(Storage::new(db))
+}
+
+async fn execute(executor: &QueryExecutor, sql: &str) -> yamlbase::Result<()> {
+    let statements = parse_sql(sql).unwrap();
+    executor.execute(&statements[0]).await.map(|_| ())
+}
+
+async fn ids(executor: &QueryExecutor) -> Vec<i64> {
+    let statements = parse_sql("SELECT id FROM items ORDER BY id").unwrap();
+    let result = executor.execute(&statements[0]).await.unwrap();
+    result
+        .rows
+        .iter()
+        .map(|row| match row[0] {
+            Value::Integer(id) => id,
+            ref other => panic!("unexpected id {:?}", other),
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn test_commit_and_rollback() {
+    let executor = QueryExecutor::new(create_storage().await).await.unwrap();
+
+    execute(&executor, "BEGIN").await.unwrap();
+    assert!(executor.in_transaction());
+    execute(&executor, "INSERT INTO items VALUES (2, 'two')")
+        .await
+        .unwrap();
+    assert_eq!(ids(&executor).await, vec![1, 2]);
+    execute(&executor, "ROLLBACK").await.unwrap();
+    assert!(!executor.in_transaction());
+    assert_eq!(ids(&executor).await, vec![1]);
+
+    execute(&executor, "START TRANSACTION").await.unwrap();
+    execute(&executor, "DELETE FROM items").await.unwrap();
+    execute(&executor, "COMMIT").await.unwrap();
+    assert!(ids(&executor).await.is_empty());
+
+    // Outside a transaction every statement stands on its own
+    execute(&executor, "INSERT INTO items VALUES (3, 'three')")
+        .await
+        .unwrap();
+    execute(&executor, "ROLLBACK").await.unwrap();
+    assert_eq!(ids(&executor).await, vec![3]);
+}
+
+#[tokio::test]
+async fn test_savepoints() {
+    let executor = QueryExecutor::new(create_storage().await).await.unwrap();
+
+    assert!(execute(&executor, "SAVEPOINT a").await.is_err());
+
+    execute(&executor, "BEGIN").await.unwrap();
+    execute(&executor, "INSERT INTO items VALUES (2, 'two')")
+        .await
+        .unwrap();
+    execute(&executor, "SAVEPOINT a").await.unwrap();
+    execute(&executor, "INSERT INTO items VALUES (3, 'three')")
+        .await
+        .unwrap();
+    execute(&executor, "SAVEPOINT b").await.unwrap();
+    execute(&executor, "DELETE FROM items WHERE id = 1")
+        .await
+        .unwrap();
+
+    execute(&executor, "ROLLBACK TO SAVEPOINT b").await.unwrap();
+    assert_eq!(ids(&executor).await, vec![1, 2, 3]);
+    execute(&executor, "ROLLBACK TO a").await.unwrap();
+    assert_eq!(ids(&executor).await, vec![1, 2]);
+
+    execute(&executor, "RELEASE SAVEPOINT a").await.unwrap();
+    execute(&executor, "COMMIT").await.unwrap();
+    assert_eq!(ids(&executor).await, vec![1, 2]);
+
+    // Rolling back to a savepoint drops the ones after it
+    execute(&executor, "BEGIN").await.unwrap();
+    execute(&executor, "SAVEPOINT a").await.unwrap();
+    execute(&executor, "SAVEPOINT b").await.unwrap();
+    execute(&executor, "ROLLBACK TO SAVEPOINT a").await.unwrap();
+    assert!(execute(&executor, "RELEASE SAVEPOINT b").await.is_err());
+    execute(&executor, "ROLLBACK").await.unwrap();
+}
+
+#[tokio::test]
+async fn test_failed_transaction() {
+    let executor = QueryExecutor::new(create_storage().await).await.unwrap();
+
+    execute(&executor, "BEGIN").await.unwrap();
+    execute(&executor, "INSERT INTO items VALUES (2, 'two')")
+        .await
+        .unwrap();
+    execute(&executor, "SAVEPOINT a").await.unwrap();
+    assert!(
+        execute(&executor, "INSERT INTO items VALUES (1, 'dup')")
+            .await
+            .is_err()
+    );
+    assert!(executor.transaction_failed());
+    assert!(matches!(
+        execute(&executor, "SELECT 1").await,
+        Err(yamlbase::YamlBaseError::TransactionAborted)
+    ));
+
+    // Rolling back to a savepoint recovers the transaction
+    execute(&executor, "ROLLBACK TO SAVEPOINT a").await.unwrap();
+    assert_eq!(ids(&executor).await, vec![1, 2]);
+
+    assert!(
+        execute(&executor, "SELECT missing FROM items")
+            .await
+            .is_err()
+    );
+    // COMMIT of a failed transaction rolls it back
+    execute(&executor, "COMMIT").await.unwrap();
+    assert_eq!(ids(&executor).await, vec![1]);
+
+    // MySQL only undoes the failed statement
+    let executor = QueryExecutor::new(create_storage().await)
+        .await
+        .unwrap()
+        .with_dialect(SqlDialect::MySQL);
+    for sql in [
+        "START TRANSACTION",
+        "INSERT INTO items VALUES (2, 'two')",
+        "INSERT INTO items VALUES (3, 'three'), (1, 'dup')",
+    ] {
+        let statements = parse_sql_with_dialect(sql, SqlDialect::MySQL).unwrap();
+        let _ = executor.execute(&statements[0]).await;
+    }
+    assert!(!executor.transaction_failed());
+    assert_eq!(ids(&executor).await, vec![1, 2]);
+}
+
+#[tokio::test]
+async fn test_shared_writes_commit_together() {
+    let storage = create_storage().await;
+    let writer = QueryExecutor::new(storage.clone())
+        .await
+        .unwrap()
+        .with_write_mode(WriteMode::Shared);
+    let reader = QueryExecutor::new(storage.clone())
+        .await
+        .unwrap()
+        .with_write_mode(WriteMode::Shared);
+
+    execute(&writer, "BEGIN").await.unwrap();
+    execute(&writer, "INSERT INTO items VALUES (2, 'two')")
+        .await
+        .unwrap();
+    execute(&writer, "UPDATE items SET name = 'uno' WHERE id = 1")
+        .await
+        .unwrap();
+    assert_eq!(ids(&writer).await, vec![1, 2]);
+    assert_eq!(ids(&reader).await, vec![1]);
+
+    // Other writers wait for the transaction to end
+    let blocked = tokio::spawn({
+        let reader = reader.clone();
+        async move { execute(&reader, "INSERT INTO items VALUES (3, 'three')").await }
+    });
+    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    assert!(!blocked.is_finished());
+
+    execute(&writer, "COMMIT").await.unwrap();
+    blocked.await.unwrap().unwrap();
+    assert_eq!(ids(&reader).await, vec![1, 2, 3]);
+
+    execute(&writer, "BEGIN").await.unwrap();
+    execute(&writer, "DELETE FROM items").await.unwrap();
+    execute(&writer, "ROLLBACK").await.unwrap();
+    assert_eq!(ids(&reader).await, vec![1, 2, 3]);
+}
+
+#[tokio::test]
+async fn test_transactions_over_postgres() {
+    let file = yaml_file(YAML);
+    let (db, _) = parse_yaml_database(file.path()).await.unwrap();
+    let server = TestServer::new_postgres(db).await;
+
+    let mut client = connect_postgres(server.port(), "yamlbase", "password", "test_db")
+        .await
+        .unwrap();
+
+    let transaction = client.transaction().await.unwrap();
+    transaction
+        .execute("INSERT INTO items VALUES (2, 'two')", &[])
+        .await
+        .unwrap();
+    transaction.rollback().await.unwrap();
+
+    let transaction = client.transaction().await.unwrap();
+    transaction
+        .execute("INSERT INTO items VALUES (3, 'three')", &[])
+        .await
+        .unwrap();
+    let savepoint_result = transaction.simple_query("SAVEPOINT s").await.unwrap();
+    assert!(matches!(
+        savepoint_result.last(),
+        Some(tokio_postgres::SimpleQueryMessage::CommandComplete(0))
+    ));
+    transaction.commit().await.unwrap();
+
+    // After an error only the end of the transaction is accepted
+    let transaction = client.transaction().await.unwrap();
+    assert!(
+        transaction
+            .execute("SELECT missing FROM items", &[])
+            .await
+            .is_err()
+    );
+    let error = transaction.simple_query("SELECT 1").await.unwrap_err();
+    assert_eq!(error.code(), Some(&SqlState::IN_FAILED_SQL_TRANSACTION));
+    transaction.rollback().await.unwrap();
+
+    let rows = client
+        .simple_query("SELECT id FROM items ORDER BY id")
+        .await
+        .unwrap();
+    let ids: Vec<String> = rows
+        .iter()
+        .filter_map(|message| match message {
+            tokio_postgres::SimpleQueryMessage::Row(row) => Some(row.get(0).unwrap().to_string()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(ids, vec!["1", "3"]);
+}
@@ -0,0 +1,238 @@
+use rust_decimal::Decimal;
+use yamlbase::database::{Database, Table, Value};
+use yamlbase::sql::{QueryExecutor, parse_sql};
+use yamlbase::yaml::schema::SqlType;
+
+mod common;
+use common::{column, new_executor};
+
+async fn create_executor() -> QueryExecutor {
+    let mut db = Database::new("test_db".to_string());
+
+    let mut items = Table::new(
+        "items".to_string(),
+        vec![
+            column("id", SqlType::Integer, true),
+            column("name", SqlType::Varchar(50), false),
+            column("qty", SqlType::Integer, false),
+            column("price", SqlType::Decimal(10, 2), false),
+            column("weight", SqlType::Double, false),
+        ],
+    );
+    let rows = [(1, "bolt", 10, "0.25", 0.5), (2, "nut", 4, "0.10", 0.25)];
+    for (id, name, qty, price, weight) in rows {
+        items
+            .insert_row(vec![
+                Value::Integer(id),
+                Value::Text(name.to_string()),
+                Value::Integer(qty),
+                Value::Decimal(price.parse::<Decimal>().unwrap()),
+                Value::Double(weight),
+            ])
+            .unwrap();
+    }
+    db.add_table(items).unwrap();
+
+    let mut stock = Table::new(
+        "stock".to_string(),
+        vec![
+            column("item_id", SqlType::Integer, false),
+            column("shelf", SqlType::Text, false),
+        ],
+    );
+    for (item_id, shelf) in [(1, "A1"), (2, "B2")] {
+        stock
+            .insert_row(vec![
+                Value::Integer(item_id),
+                Value::Text(shelf.to_string()),
+            ])
+            .unwrap();
+    }
+    db.add_table(stock).unwrap();
+
+    new_executor(db).await
+}
+
+async fn column_types(executor: &QueryExecutor, sql: &str) -> Vec<SqlType> {
+    let statements = parse_sql(sql).unwrap();
+    executor.execute(&statements[0]).await.unwrap().column_types
+}
+
+#[tokio::test]
+async fn test_projected_expression_types() {
+    let executor = create_executor().await;
+
+    assert_eq!(
+        column_types(
+            &executor,
+            "SELECT qty + 1, qty / 4, price * qty, weight * 2, -qty, UPPER(name), \
+             name || '!', LENGTH(name), qty > 5, CAST(qty AS DECIMAL(8, 3)), \
+             CASE WHEN qty > 5 THEN weight ELSE 0.0 END, COALESCE(NULL, qty), ROUND(weight, 1) \
+             FROM items",
+        )
+        .await,
+        vec![
+            SqlType::BigInt,
+            SqlType::Double,
+            SqlType::Decimal(10, 2),
+            SqlType::Double,
+            SqlType::Integer,
+            SqlType::Text,
+            SqlType::Text,
+            SqlType::Integer,
+            SqlType::Boolean,
+            SqlType::Decimal(8, 3),
+            SqlType::Double,
+            SqlType::Integer,
+            SqlType::Double,
+        ]
+    );
+
+    // Types come from the operands, so they are known without any rows
+    assert_eq!(
+        column_types(
+            &executor,
+            "SELECT qty * 2, weight + qty FROM items WHERE name = 'washer'"
+        )
+        .await,
+        vec![SqlType::BigInt, SqlType::Double]
+    );
+
+    // Where the operands do not decide the type, the values do
+    assert_eq!(
+        column_types(&executor, "SELECT ROUND(qty, 0), NOW() FROM items").await,
+        vec![SqlType::BigInt, SqlType::Text]
+    );
+}
+
+#[tokio::test]
+async fn test_expression_types_in_joins_aggregates_and_ctes() {
+    let executor = create_executor().await;
+
+    assert_eq!(
+        column_types(
+            &executor,
+            "SELECT i.qty * 2, s.shelf || '-' || i.name, i.weight / s.item_id \
+             FROM items i JOIN stock s ON s.item_id = i.id",
+        )
+        .await,
+        vec![SqlType::BigInt, SqlType::Text, SqlType::Double]
+    );
+    assert_eq!(
+        column_types(
+            &executor,
+            "SELECT MIN(price), MAX(name), COUNT(*), AVG(qty) FROM items"
+        )
+        .await,
+        vec![
+            SqlType::Decimal(10, 2),
+            SqlType::Varchar(50),
+            SqlType::BigInt,
+            SqlType::Double,
+        ]
+    );
+    assert_eq!(
+        column_types(
+            &executor,
+            "WITH heavy AS (SELECT id, qty, weight FROM items WHERE weight > 0.3) \
+             SELECT qty * weight, id FROM heavy",
+        )
+        .await,
+        vec![SqlType::Double, SqlType::Integer]
+    );
+}
+
+/// The column types and the only row of `sql`
+async fn typed_row(executor: &QueryExecutor, sql: &str) -> (Vec<SqlType>, Vec<Value>) {
+    let statements = parse_sql(sql).unwrap();
+    let mut result = executor.execute(&statements[0]).await.unwrap();
+    (result.column_types, result.rows.remove(0))
+}
+
+fn decimal(text: &str) -> Value {
+    Value::Decimal(text.parse().unwrap())
+}
+
+#[tokio::test]
+async fn test_decimal_literal_types_follow_their_digits() {
+    let executor = create_executor().await;
+
+    for sql in [
+        "SELECT 1.125, 1.10 * 2.5, 0.5 + 1.25",
+        "SELECT 1.125, 1.10 * 2.5, 0.5 + 1.25 FROM items WHERE id = 1",
+    ] {
+        let (types, row) = typed_row(&executor, sql).await;
+        assert_eq!(
+            types,
+            vec![
+                SqlType::Decimal(4, 3),
+                SqlType::Decimal(5, 3),
+                SqlType::Decimal(3, 2),
+            ]
+        );
+        assert_eq!(
+            row,
+            vec![decimal("1.125"), decimal("2.750"), decimal("1.75")]
+        );
+    }
+
+    let (types, row) = typed_row(&executor, "SELECT price * 1.5 FROM items WHERE id = 1").await;
+    assert_eq!(types, vec![SqlType::Decimal(12, 3)]);
+    assert_eq!(row, vec![decimal("0.375")]);
+}
+
+#[tokio::test]
+async fn test_function_types_follow_their_arguments() {
+    let executor = create_executor().await;
+
+    let (types, row) = typed_row(
+        &executor,
+        "SELECT TRY_CAST('42' AS INTEGER), TRY_CAST('x' AS INTEGER), TRY_CAST('2.5' AS DOUBLE)",
+    )
+    .await;
+    assert_eq!(
+        types,
+        vec![SqlType::Integer, SqlType::Integer, SqlType::Double]
+    );
+    assert_eq!(
+        row,
+        vec![Value::Integer(42), Value::Null, Value::Double(2.5)]
+    );
+
+    let (types, row) = typed_row(
+        &executor,
+        "SELECT PERCENTILE_DISC(0.5) WITHIN GROUP (ORDER BY qty), \
+         PERCENTILE_DISC(0.5) WITHIN GROUP (ORDER BY name) FROM items",
+    )
+    .await;
+    assert_eq!(types, vec![SqlType::Integer, SqlType::Varchar(50)]);
+    assert_eq!(
+        row,
+        vec![Value::Integer(4), Value::Text("bolt".to_string())]
+    );
+
+    let (types, row) = typed_row(
+        &executor,
+        "SELECT ISNULL(qty, 0), ISNULL(name, 'none'), ISNULL(price, 1.5), ISNULL(qty) \
+         FROM items WHERE id = 2",
+    )
+    .await;
+    assert_eq!(
+        types,
+        vec![
+            SqlType::Integer,
+            SqlType::Varchar(50),
+            SqlType::Decimal(10, 2),
+            SqlType::Integer,
+        ]
+    );
+    assert_eq!(
+        row,
+        vec![
+            Value::Integer(4),
+            Value::Text("nut".to_string()),
+            decimal("0.10"),
+            Value::Integer(0),
+        ]
+    );
+}
@@ -0,0 +1,221 @@
+use std::sync::Arc;
+use yamlbase::database::{Storage, Value};
+use yamlbase::sql::{QueryExecutor, SqlDialect, parse_sql};
+use yamlbase::yaml::parse_yaml_database;
+
+mod common;
+use common::{TestServer, connect_postgres, query, yaml_file};
+
+const YAML: &str = r#"
+database:
+  name: "test_db"
+
+tables:
+  tasks:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+      title: "VARCHAR(30) UNIQUE NOT NULL"
+      done: "BOOLEAN DEFAULT false"
+      estimate: "INTEGER"
+    data:
+      - id: 1
+        title: "write"
+        done: false
+        estimate: 3
+      - id: 2
+        title: "review"
+        done: true
+        estimate: 1
+      - id: 3
+        title: "ship"
+        done: false
+        estimate: 2
+"#;
+
+async fn create_storage() -> Arc<Storage> {
+    let file = yaml_file(YAML);
+    let (db, _) = parse_yaml_database(file.path()).await.unwrap();
+    Arc::new(Storage::new(db))
+}
+
+async fn execute(executor: &QueryExecutor, sql: &str) -> yamlbase::Result<u64> {
+    let statements = parse_sql(sql).unwrap();
+    let result = executor.execute(&statements[0]).await?;
+    Ok(result.affected_rows.unwrap())
+}
+
+#[tokio::test]
+async fn test_update() {
+    let executor = QueryExecutor::new(create_storage().await).await.unwrap();
+
+    assert_eq!(
+        execute(
+            &executor,
+            "UPDATE tasks SET estimate = estimate * 2, done = true WHERE estimate > 1",
+        )
+        .await
+        .unwrap(),
+        2
+    );
+    assert_eq!(
+        query(
+            &executor,
+            "SELECT id, done, estimate FROM tasks ORDER BY id"
+        )
+        .await,
+        vec![
+            vec![Value::Integer(1), Value::Boolean(true), Value::Integer(6)],
+            vec![Value::Integer(2), Value::Boolean(true), Value::Integer(1)],
+            vec![Value::Integer(3), Value::Boolean(true), Value::Integer(4)],
+        ]
+    );
+
+    // Assignments read the row as it was, so values can be swapped
+    execute(
+        &executor,
+        "UPDATE tasks SET id = estimate, estimate = id WHERE id = 3",
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        query(
+            &executor,
+            "SELECT id, estimate FROM tasks WHERE title = 'ship'"
+        )
+        .await,
+        vec![vec![Value::Integer(4), Value::Integer(3)]]
+    );
+
+    // DEFAULT, and no WHERE at all
+    assert_eq!(
+        execute(&executor, "UPDATE tasks SET done = DEFAULT")
+            .await
+            .unwrap(),
+        3
+    );
+    assert_eq!(
+        query(&executor, "SELECT COUNT(*) FROM tasks WHERE done = false").await,
+        vec![vec![Value::Integer(3)]]
+    );
+}
+
+#[tokio::test]
+async fn test_update_constraints() {
+    let executor = QueryExecutor::new(create_storage().await).await.unwrap();
+
+    for sql in [
+        "UPDATE tasks SET id = 2 WHERE id = 1",
+        "UPDATE tasks SET title = 'same'",
+        "UPDATE tasks SET title = NULL WHERE id = 1",
+        "UPDATE tasks SET estimate = 'lots'",
+        "UPDATE tasks SET nickname = 'x'",
+        "UPDATE tasks SET estimate = 1, estimate = 2",
+        "UPDATE missing SET id = 1",
+    ] {
+        assert!(execute(&executor, sql).await.is_err(), "{}", sql);
+    }
+
+    // Swapping unique values in one statement is fine
+    execute(&executor, "UPDATE tasks SET id = 3 - id WHERE id < 3")
+        .await
+        .unwrap();
+    assert_eq!(
+        query(&executor, "SELECT id, title FROM tasks ORDER BY id").await,
+        vec![
+            vec![Value::Integer(1), Value::Text("review".to_string())],
+            vec![Value::Integer(2), Value::Text("write".to_string())],
+            vec![Value::Integer(3), Value::Text("ship".to_string())],
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_delete() {
+    let storage = create_storage().await;
+    let executor = QueryExecutor::new(Arc::clone(&storage)).await.unwrap();
+    let other = QueryExecutor::new(Arc::clone(&storage)).await.unwrap();
+
+    assert_eq!(
+        execute(
+            &executor,
+            "DELETE FROM tasks WHERE done = false AND estimate < 3"
+        )
+        .await
+        .unwrap(),
+        1
+    );
+    assert_eq!(
+        execute(&executor, "DELETE FROM tasks WHERE id = 99")
+            .await
+            .unwrap(),
+        0
+    );
+    assert_eq!(
+        query(&executor, "SELECT id FROM tasks ORDER BY id").await,
+        vec![vec![Value::Integer(1)], vec![Value::Integer(2)]]
+    );
+
+    // The deleted primary key can be used again, and other connections still see it
+    execute(
+        &executor,
+        "INSERT INTO tasks (id, title) VALUES (3, 'again')",
+    )
+    .await
+    .unwrap();
+    assert_eq!(execute(&executor, "DELETE FROM tasks").await.unwrap(), 3);
+    assert_eq!(
+        query(&other, "SELECT COUNT(*) FROM tasks").await,
+        vec![vec![Value::Integer(3)]]
+    );
+}
+
+#[tokio::test]
+async fn test_mysql_counts_changed_rows() {
+    let executor = QueryExecutor::new(create_storage().await)
+        .await
+        .unwrap()
+        .with_dialect(SqlDialect::MySQL);
+
+    // Only one of the two matched rows gets a new value
+    assert_eq!(
+        execute(&executor, "UPDATE tasks SET done = true WHERE id <= 2")
+            .await
+            .unwrap(),
+        1
+    );
+}
+
+#[tokio::test]
+async fn test_update_and_delete_over_postgres() {
+    let yaml = YAML.replacen(
+        "  name: \"test_db\"\n",
+        "  name: \"test_db\"\n  auth:\n    username: \"yamlbase\"\n    password: \"password\"\n",
+        1,
+    );
+    let file = yaml_file(&yaml);
+    let server = TestServer::start_postgres(file.path().to_str().unwrap());
+
+    let client = connect_postgres(server.port(), "yamlbase", "password", "test_db")
+        .await
+        .unwrap();
+
+    assert_eq!(
+        client
+            .execute("UPDATE tasks SET done = true WHERE id <= 2", &[])
+            .await
+            .unwrap(),
+        2
+    );
+    assert_eq!(
+        client
+            .execute("DELETE FROM tasks WHERE done = true", &[])
+            .await
+            .unwrap(),
+        2
+    );
+    let messages = client.simple_query("DELETE FROM tasks").await.unwrap();
+    assert!(matches!(
+        messages.last(),
+        Some(tokio_postgres::SimpleQueryMessage::CommandComplete(1))
+    ));
+}
@@ -0,0 +1,218 @@
+use yamlbase::database::Value;
+use yamlbase::sql::{QueryExecutor, SqlDialect, parse_sql};
+
+mod common;
+use common::yaml_executor;
+
+const YAML: &str = r#"
+database:
+  name: "test_db"
+
+tables:
+  stock:
+    columns:
+      sku: "VARCHAR(10) PRIMARY KEY"
+      name: "TEXT NOT NULL"
+      quantity: "INTEGER DEFAULT 0"
+    data:
+      - sku: "a1"
+        name: "bolt"
+        quantity: 10
+      - sku: "b2"
+        name: "nut"
+        quantity: 5
+
+  deliveries:
+    columns:
+      sku: "VARCHAR(10) PRIMARY KEY"
+      name: "TEXT"
+      quantity: "INTEGER"
+    data:
+      - sku: "a1"
+        name: "bolt"
+        quantity: 3
+      - sku: "b2"
+        name: "nut"
+        quantity: -5
+      - sku: "c3"
+        name: "washer"
+        quantity: 7
+"#;
+
+async fn execute(executor: &QueryExecutor, sql: &str) -> yamlbase::Result<u64> {
+    let statements = parse_sql(sql).unwrap();
+    let result = executor.execute(&statements[0]).await?;
+    Ok(result.affected_rows.unwrap())
+}
+
+async fn stock(executor: &QueryExecutor) -> Vec<(String, i64)> {
+    let statements = parse_sql("SELECT sku, quantity FROM stock ORDER BY sku").unwrap();
+    let result = executor.execute(&statements[0]).await.unwrap();
+    result
+        .rows
+        .into_iter()
+        .map(|row| match (&row[0], &row[1]) {
+            (Value::Text(sku), Value::Integer(quantity)) => (sku.clone(), *quantity),
+            other => panic!("unexpected row {:?}", other),
+        })
+        .collect()
+}
+
+fn rows(expected: &[(&str, i64)]) -> Vec<(String, i64)> {
+    expected
+        .iter()
+        .map(|(sku, quantity)| (sku.to_string(), *quantity))
+        .collect()
+}
+
+#[tokio::test]
+async fn test_on_conflict_do_update() {
+    let executor = yaml_executor(YAML).await;
+
+    let affected = execute(
+        &executor,
+        "INSERT INTO stock AS s (sku, name, quantity) VALUES ('a1', 'bolt', 2), ('d4', 'pin', 1) \
+         ON CONFLICT (sku) DO UPDATE SET quantity = s.quantity + EXCLUDED.quantity",
+    )
+    .await
+    .unwrap();
+    assert_eq!(affected, 2);
+    assert_eq!(
+        stock(&executor).await,
+        rows(&[("a1", 12), ("b2", 5), ("d4", 1)])
+    );
+
+    // The WHERE clause decides which conflicting rows are updated
+    let affected = execute(
+        &executor,
+        "INSERT INTO stock VALUES ('a1', 'bolt', 0), ('b2', 'nut', 0) \
+         ON CONFLICT (sku) DO UPDATE SET quantity = excluded.quantity WHERE stock.quantity < 10",
+    )
+    .await
+    .unwrap();
+    assert_eq!(affected, 1);
+    assert_eq!(
+        stock(&executor).await,
+        rows(&[("a1", 12), ("b2", 0), ("d4", 1)])
+    );
+
+    // A row cannot be updated twice by one statement
+    assert!(
+        execute(
+            &executor,
+            "INSERT INTO stock VALUES ('a1', 'bolt', 1), ('a1', 'bolt', 2) \
+             ON CONFLICT (sku) DO UPDATE SET quantity = excluded.quantity",
+        )
+        .await
+        .is_err()
+    );
+    assert!(
+        execute(
+            &executor,
+            "INSERT INTO stock VALUES ('a1', 'bolt', 1) \
+             ON CONFLICT (name) DO UPDATE SET quantity = 1",
+        )
+        .await
+        .is_err()
+    );
+    // Updating a conflicting row onto another row's key is a unique violation
+    assert!(matches!(
+        execute(
+            &executor,
+            "INSERT INTO stock VALUES ('a1', 'bolt', 1) \
+             ON CONFLICT (sku) DO UPDATE SET sku = 'b2'",
+        )
+        .await,
+        Err(yamlbase::YamlBaseError::UniqueViolation { .. })
+    ));
+    assert_eq!(stock(&executor).await[0], ("a1".to_string(), 12));
+}
+
+#[tokio::test]
+async fn test_on_conflict_do_nothing() {
+    let executor = yaml_executor(YAML).await;
+
+    let affected = execute(
+        &executor,
+        "INSERT INTO stock (sku, name) VALUES ('a1', 'other'), ('e5', 'clip') \
+         ON CONFLICT DO NOTHING",
+    )
+    .await
+    .unwrap();
+    assert_eq!(affected, 1);
+    assert_eq!(
+        stock(&executor).await,
+        rows(&[("a1", 10), ("b2", 5), ("e5", 0)])
+    );
+}
+
+#[tokio::test]
+async fn test_on_duplicate_key_update() {
+    let executor = yaml_executor(YAML).await.with_dialect(SqlDialect::MySQL);
+
+    // MySQL counts 1 per inserted row and 2 per updated row
+    let affected = execute(
+        &executor,
+        "INSERT INTO stock (sku, name, quantity) VALUES ('a1', 'bolt', 4), ('f6', 'gear', 2) \
+         ON DUPLICATE KEY UPDATE quantity = quantity + VALUES(quantity)",
+    )
+    .await
+    .unwrap();
+    assert_eq!(affected, 3);
+    assert_eq!(
+        stock(&executor).await,
+        rows(&[("a1", 14), ("b2", 5), ("f6", 2)])
+    );
+
+    // Unchanged rows do not count
+    let affected = execute(
+        &executor,
+        "INSERT INTO stock VALUES ('b2', 'nut', 5) ON DUPLICATE KEY UPDATE quantity = 5",
+    )
+    .await
+    .unwrap();
+    assert_eq!(affected, 0);
+}
+
+#[tokio::test]
+async fn test_merge() {
+    let executor = yaml_executor(YAML).await;
+
+    let affected = execute(
+        &executor,
+        "MERGE INTO stock t USING deliveries d ON t.sku = d.sku \
+         WHEN MATCHED AND t.quantity + d.quantity <= 0 THEN DELETE \
+         WHEN MATCHED THEN UPDATE SET quantity = t.quantity + d.quantity \
+         WHEN NOT MATCHED THEN INSERT (sku, name, quantity) VALUES (d.sku, d.name, d.quantity)",
+    )
+    .await
+    .unwrap();
+    assert_eq!(affected, 3);
+    assert_eq!(stock(&executor).await, rows(&[("a1", 13), ("c3", 7)]));
+
+    let affected = execute(
+        &executor,
+        "MERGE INTO stock USING (SELECT 'z9' AS sku, 'spring' AS name) AS src \
+         ON stock.sku = src.sku \
+         WHEN NOT MATCHED THEN INSERT (sku, name, quantity) VALUES (src.sku, src.name, DEFAULT)",
+    )
+    .await
+    .unwrap();
+    assert_eq!(affected, 1);
+    assert_eq!(
+        stock(&executor).await,
+        rows(&[("a1", 13), ("c3", 7), ("z9", 0)])
+    );
+
+    // A failed MERGE changes nothing
+    assert!(
+        execute(
+            &executor,
+            "MERGE INTO stock USING deliveries d ON stock.sku = d.sku \
+             WHEN NOT MATCHED THEN INSERT (sku) VALUES (d.sku)",
+        )
+        .await
+        .is_err()
+    );
+    assert_eq!(stock(&executor).await.len(), 3);
+}
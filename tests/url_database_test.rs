@@ -0,0 +1,133 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use yamlbase::yaml::UrlPoller;
+use yamlbase::yaml::parse_yaml_database;
+use yamlbase::yaml::remote::set_headers;
+
+type Files = Arc<Mutex<HashMap<String, String>>>;
+
+const DB: &str = r#"
+database:
+  name: "remote_db"
+
+include:
+  - orders.yaml
+
+tables:
+  users:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+      name: "TEXT"
+    data:
+      - id: 1
+        name: "Alice"
+"#;
+
+const ORDERS: &str = r#"
+tables:
+  orders:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+      user_id: "INTEGER"
+    data_file: orders.csv
+"#;
+
+/// Serve the files over HTTP, answering 401 to requests without the bearer token
+async fn serve(files: Files) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        loop {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let files = files.clone();
+            tokio::spawn(async move {
+                let mut request = Vec::new();
+                let mut buffer = [0; 1024];
+                while !request.ends_with(b"\r\n\r\n") {
+                    let n = stream.read(&mut buffer).await.unwrap();
+                    if n == 0 {
+                        return;
+                    }
+                    request.extend_from_slice(&buffer[..n]);
+                }
+                let request = String::from_utf8_lossy(&request).to_lowercase();
+                let path = request.split_whitespace().nth(1).unwrap_or("").to_string();
+                let body = files.lock().unwrap().get(&path).cloned();
+                let (status, body) = if !request.contains("authorization: bearer secret") {
+                    ("401 Unauthorized", String::new())
+                } else {
+                    match body {
+                        Some(body) => ("200 OK", body),
+                        None => ("404 Not Found", String::new()),
+                    }
+                };
+                let response = format!(
+                    "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+                let _ = stream.write_all(response.as_bytes()).await;
+            });
+        }
+    });
+    format!("http://{}", address)
+}
+
+async fn serve_fixtures() -> (Files, String) {
+    set_headers(vec![(
+        "Authorization".to_string(),
+        "Bearer secret".to_string(),
+    )]);
+    let files: Files = Arc::new(Mutex::new(HashMap::from([
+        ("/app/db.yaml".to_string(), DB.to_string()),
+        ("/app/orders.yaml".to_string(), ORDERS.to_string()),
+        (
+            "/app/orders.csv".to_string(),
+            "id,user_id\n1,1\n2,1\n".to_string(),
+        ),
+    ])));
+    let base = serve(files.clone()).await;
+    (files, base)
+}
+
+#[tokio::test]
+async fn test_load_from_url() {
+    let (_files, base) = serve_fixtures().await;
+
+    let url = PathBuf::from(format!("{}/app/db.yaml", base));
+    let (db, _) = parse_yaml_database(&url).await.unwrap();
+    assert_eq!(db.name, "remote_db");
+    assert_eq!(db.get_table("users").unwrap().rows.len(), 1);
+    assert_eq!(db.get_table("orders").unwrap().rows.len(), 2);
+
+    let missing = format!("{}/app/missing.yaml", base);
+    let error = parse_yaml_database(Path::new(&missing))
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("404"), "{}", error);
+}
+
+#[tokio::test]
+async fn test_poll_url_for_changes() {
+    let (files, base) = serve_fixtures().await;
+    let url = PathBuf::from(format!("{}/app/orders.csv", base));
+    let (poller, mut rx) = UrlPoller::new(vec![url.clone()], Duration::from_millis(50));
+    poller.start();
+
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    files.lock().unwrap().insert(
+        "/app/orders.csv".to_string(),
+        "id,user_id\n1,1\n".to_string(),
+    );
+    let changed = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(changed, vec![url]);
+}
@@ -0,0 +1,151 @@
+use std::process::Command;
+use tempfile::NamedTempFile;
+
+mod common;
+use common::yaml_file;
+
+const VALID: &str = r#"
+database:
+  name: "shop"
+
+tables:
+  users:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+      email: "TEXT UNIQUE"
+    data:
+      - id: 1
+        email: "ann@example.com"
+      - id: 2
+  orders:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+      user_id: "INTEGER REFERENCES users(id)"
+    data:
+      - id: 10
+        user_id: 1
+      - id: 11
+"#;
+
+const INVALID: &str = r#"
+database:
+  name: "shop"
+
+tables:
+  users:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+      email: "TEXT UNIQUE"
+    data:
+      - id: 1
+        email: "ann@example.com"
+      - id: 1
+        email: "ann@example.com"
+  orders:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+      user_id: "INTEGER REFERENCES users(id)"
+    data:
+      - id: 10
+        user_id: 7
+"#;
+
+fn validate(files: &[&NamedTempFile]) -> (bool, String) {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_yamlbase"));
+    command.arg("validate");
+    for file in files {
+        command.arg("-f").arg(file.path());
+    }
+    let output = command.output().unwrap();
+    (
+        output.status.success(),
+        String::from_utf8_lossy(&output.stdout).into_owned(),
+    )
+}
+
+#[test]
+fn test_validate_valid_file() {
+    let file = yaml_file(VALID);
+    let (success, output) = validate(&[&file]);
+    assert!(success, "{}", output);
+    assert!(
+        output.contains("OK, database 'shop' with 2 tables and 4 rows"),
+        "{}",
+        output
+    );
+}
+
+#[test]
+fn test_validate_reports_keys() {
+    let valid = yaml_file(VALID);
+    let invalid = yaml_file(INVALID);
+    let (success, output) = validate(&[&valid, &invalid]);
+    assert!(!success);
+    assert!(output.contains("OK, database 'shop'"), "{}", output);
+    // Key problems point at the line of their row
+    let path = invalid.path().display();
+    assert!(
+        output.contains(&format!(
+            "{}:13: table 'users', row 2: duplicate primary key (id) = (1), as in row 1",
+            path
+        )),
+        "{}",
+        output
+    );
+    assert!(
+        output.contains("table 'users', row 2: duplicate UNIQUE value (email)"),
+        "{}",
+        output
+    );
+    assert!(
+        output.contains("table 'orders', row 1: user_id 7 is not in users.id"),
+        "{}",
+        output
+    );
+    assert!(output.contains("3 problems"), "{}", output);
+}
+
+#[test]
+fn test_validate_reports_load_errors() {
+    let file = yaml_file(
+        "database:\n  name: shop\ntables:\n  users:\n    columns:\n      id: INTEGER\n    data:\n      - id: one\n",
+    );
+    let (success, output) = validate(&[&file]);
+    assert!(!success);
+    assert!(output.contains("row 1, column 'id'"), "{}", output);
+    assert!(output.contains("1 problem"), "{}", output);
+}
+
+// Rows with values that cannot be loaded are left out, and the keys of the others checked
+#[test]
+fn test_validate_checks_keys_despite_type_errors() {
+    let file = yaml_file(
+        "database:\n  name: shop\ntables:\n  users:\n    columns:\n      id: INTEGER PRIMARY KEY\n      age: INTEGER\n    data:\n      - id: 1\n      - id: 2\n        age: old\n      - id: 1\n  orders:\n    columns:\n      id: INTEGER PRIMARY KEY\n      user_id: INTEGER REFERENCES users(id)\n    data:\n      - id: 10\n        user_id: 2\n",
+    );
+    let (success, output) = validate(&[&file]);
+    assert!(!success);
+    let path = file.path().display();
+    assert!(
+        output.contains(&format!("{}:11: table 'users', row 2, column 'age'", path)),
+        "{}",
+        output
+    );
+    // Rows are counted and located in data:, the row that was left out included
+    assert!(
+        output.contains(&format!(
+            "{}:12: table 'users', row 3: duplicate primary key (id) = (1), as in row 1",
+            path
+        )),
+        "{}",
+        output
+    );
+    assert!(
+        output.contains(&format!(
+            "{}:18: table 'orders', row 1: user_id 2 is not in users.id",
+            path
+        )),
+        "{}",
+        output
+    );
+    assert!(output.contains("3 problems"), "{}", output);
+}
@@ -0,0 +1,84 @@
+use yamlbase::yaml::parse_yaml_database;
+
+mod common;
+use common::yaml_file;
+
+const YAML: &str = r#"database:
+  name: "test_db"
+
+tables:
+  users:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+      name: "VARCHAR(50) NOT NULL"
+      age: "INTEGER"
+      joined: "DATE"
+    data:
+      - id: 1
+        name: "Alice"
+        age: 30
+        joined: "2024-01-15"
+      - id: 2
+        name: "Bob"
+        age: "thirty"
+        joined: "15/01/2024"
+      - id: 3
+        age: 25
+  orders:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+      total: "DECIMAL(10,2)"
+    data:
+      - { id: 1, total: "lots" }
+"#;
+
+#[tokio::test]
+async fn test_all_invalid_values_with_lines() {
+    let file = yaml_file(YAML);
+    let path = file.path().display().to_string();
+    let error = parse_yaml_database(file.path())
+        .await
+        .unwrap_err()
+        .to_string();
+
+    let expected = [
+        format!("{}:18: table 'users', row 2, column 'age': ", path),
+        format!("{}:19: table 'users', row 2, column 'joined': ", path),
+        format!("{}:20: table 'users', row 3: ", path),
+        format!("{}:27: table 'orders', row 1, column 'total': ", path),
+    ];
+    for expected in &expected {
+        assert!(error.contains(expected.as_str()), "{}", error);
+    }
+    assert!(error.contains("Cannot parse integer: thirty"), "{}", error);
+    assert!(error.contains("Cannot parse date: 15/01/2024"), "{}", error);
+    assert!(error.contains("'name'"), "{}", error);
+    assert!(error.contains("Cannot convert \"lots\""), "{}", error);
+    assert_eq!(error.lines().count(), 4, "{}", error);
+}
+
+#[tokio::test]
+async fn test_single_invalid_value() {
+    let yaml = YAML
+        .replace("\"thirty\"", "31")
+        .replace("\"15/01/2024\"", "\"2024-01-15\"")
+        .replace("        age: 25\n", "        name: \"Carol\"\n")
+        .replace("\"lots\"", "9.99");
+    let file = yaml_file(&yaml);
+    assert!(parse_yaml_database(file.path()).await.is_ok());
+
+    let file = yaml_file(&yaml.replace("age: 31", "age: [31]"));
+    let error = parse_yaml_database(file.path())
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(
+        error.contains(&format!(
+            "{}:18: table 'users', row 2, column 'age'",
+            file.path().display()
+        )),
+        "{}",
+        error
+    );
+    assert_eq!(error.lines().count(), 1, "{}", error);
+}
@@ -0,0 +1,179 @@
+use futures::{SinkExt, StreamExt};
+use serde_json::{Value, json};
+use std::time::{Duration, Instant};
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::header::AUTHORIZATION;
+use tokio_tungstenite::tungstenite::{Error, Message};
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+mod common;
+use common::{TestServer, yaml_file};
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// "admin:password" in basic authentication
+const ADMIN: &str = "YWRtaW46cGFzc3dvcmQ=";
+
+const PRODUCTS: &str = r#"
+database:
+  name: "shop"
+  auth:
+    username: "admin"
+    password: "password"
+
+tables:
+  products:
+    columns:
+      id: "INTEGER PRIMARY KEY"
+      name: "VARCHAR(100)"
+    data:
+      - id: 1
+        name: "Widget"
+      - id: 2
+        name: "Gadget"
+      - id: 3
+        name: "Gizmo"
+"#;
+
+async fn connect(server: &TestServer, path: &str, credentials: &str) -> Result<Socket, Error> {
+    let url = format!("ws://127.0.0.1:{}{}", server.port(), path);
+    let mut request = url.into_client_request().unwrap();
+    request.headers_mut().insert(
+        AUTHORIZATION,
+        format!("Basic {}", credentials).parse().unwrap(),
+    );
+    tokio_tungstenite::connect_async(request)
+        .await
+        .map(|(socket, _)| socket)
+}
+
+async fn send(socket: &mut Socket, request: Value) {
+    socket
+        .send(Message::Text(request.to_string()))
+        .await
+        .unwrap();
+}
+
+async fn receive(socket: &mut Socket) -> Value {
+    loop {
+        let message = tokio::time::timeout(Duration::from_secs(10), socket.next())
+            .await
+            .expect("no frame within 10 seconds")
+            .unwrap()
+            .unwrap();
+        if let Message::Text(text) = message {
+            return serde_json::from_str(&text).unwrap();
+        }
+    }
+}
+
+/// The rows of a query, whatever reload events come in between
+async fn query(socket: &mut Socket, sql: &str) -> Value {
+    send(socket, json!({ "query": sql })).await;
+    let mut rows = Vec::new();
+    loop {
+        let frame = receive(socket).await;
+        match frame["type"].as_str() {
+            Some("rows") => rows.extend(frame["rows"].as_array().unwrap().iter().cloned()),
+            Some("done") => return Value::Array(rows),
+            Some("columns" | "reload") => {}
+            _ => panic!("unexpected frame {}", frame),
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_websocket_streams_rows() {
+    let file = yaml_file(PRODUCTS);
+    let server = TestServer::start_http(file.path().to_str().unwrap());
+    let mut socket = connect(&server, "/ws", ADMIN).await.unwrap();
+
+    send(
+        &mut socket,
+        json!({"query": "SELECT id, name FROM products ORDER BY id", "id": 7, "batch_size": 2}),
+    )
+    .await;
+    assert_eq!(
+        receive(&mut socket).await,
+        json!({"type": "columns", "columns": ["id", "name"], "id": 7})
+    );
+    assert_eq!(
+        receive(&mut socket).await,
+        json!({"type": "rows", "rows": [[1, "Widget"], [2, "Gadget"]], "id": 7})
+    );
+    assert_eq!(
+        receive(&mut socket).await,
+        json!({"type": "rows", "rows": [[3, "Gizmo"]], "id": 7})
+    );
+    assert_eq!(
+        receive(&mut socket).await,
+        json!({"type": "done", "rows": 3, "id": 7})
+    );
+
+    send(&mut socket, json!({"query": "SELECT * FROM missing"})).await;
+    let error = receive(&mut socket).await;
+    assert_eq!(error["type"], "error");
+    assert!(error["message"].as_str().unwrap().contains("missing"));
+
+    send(&mut socket, json!({"select": "everything"})).await;
+    assert_eq!(receive(&mut socket).await["type"], "error");
+
+    // The connection is still usable after errors
+    let rows = query(&mut socket, "SELECT COUNT(*) AS n FROM products").await;
+    assert_eq!(rows, json!([[3]]));
+}
+
+#[tokio::test]
+async fn test_websocket_handshake() {
+    let file = yaml_file(PRODUCTS);
+    let server = TestServer::start_http(file.path().to_str().unwrap());
+
+    let status = |result: Result<Socket, Error>| match result {
+        Err(Error::Http(response)) => response.status().as_u16(),
+        other => panic!("expected an HTTP error, got {:?}", other.map(|_| ())),
+    };
+    // "admin:wrong"
+    assert_eq!(
+        status(connect(&server, "/ws", "YWRtaW46d3Jvbmc=").await),
+        401
+    );
+    assert_eq!(status(connect(&server, "/other", ADMIN).await), 404);
+
+    let mut socket = connect(&server, "/ws/shop", ADMIN).await.unwrap();
+    let rows = query(&mut socket, "SELECT name FROM products WHERE id = 2").await;
+    assert_eq!(rows, json!([["Gadget"]]));
+}
+
+#[tokio::test]
+async fn test_websocket_reload_events() {
+    let file = yaml_file(PRODUCTS);
+    let server = TestServer::start_http_with_args(file.path().to_str().unwrap(), &["--hot-reload"]);
+    let mut socket = connect(&server, "/ws", ADMIN).await.unwrap();
+
+    send(&mut socket, json!({"subscribe": "nothing"})).await;
+    assert_eq!(receive(&mut socket).await["type"], "error");
+    send(&mut socket, json!({"subscribe": "reload"})).await;
+    assert_eq!(
+        receive(&mut socket).await,
+        json!({"type": "subscribed", "events": "reload"})
+    );
+
+    std::fs::write(file.path(), PRODUCTS.replace("Gizmo", "Doohickey")).unwrap();
+    let event = receive(&mut socket).await;
+    assert_eq!(event["type"], "reload");
+    assert_eq!(event["database"], "shop");
+    assert!(event["changes"].as_str().unwrap().contains("products"));
+
+    // The watcher may have seen the file half written, and reload again
+    let start = Instant::now();
+    while query(&mut socket, "SELECT name FROM products WHERE id = 3").await
+        != json!([["Doohickey"]])
+    {
+        assert!(
+            start.elapsed() < Duration::from_secs(10),
+            "the reload was not applied"
+        );
+        tokio::time::sleep(Duration::from_millis(200)).await;
+    }
+}
@@ -22,6 +22,7 @@ async fn test_wildcard_projection_cte() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "version_code".to_string(),
@@ -31,6 +32,7 @@ async fn test_wildcard_projection_cte() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "wbi_id".to_string(),
@@ -40,6 +42,7 @@ async fn test_wildcard_projection_cte() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "planned_effort_hours".to_string(),
@@ -49,6 +52,7 @@ async fn test_wildcard_projection_cte() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
         ],
     );
@@ -93,6 +97,7 @@ async fn test_wildcard_projection_cte() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "project_name".to_string(),
@@ -102,6 +107,7 @@ async fn test_wildcard_projection_cte() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
         ],
     );
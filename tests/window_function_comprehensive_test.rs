@@ -29,25 +29,25 @@ fn test_window_function_parsing() {
                 assert_eq!(statements.len(), 1, "Should parse to one statement");
 
                 // Verify it's a SELECT statement with window functions
-                if let sqlparser::ast::Statement::Query(query) = &statements[0] {
-                    if let sqlparser::ast::SetExpr::Select(select) = &*query.body {
-                        // Check that we have window functions in projection
-                        let has_window_function = select.projection.iter().any(|item| {
-                            if let sqlparser::ast::SelectItem::ExprWithAlias {
-                                expr: sqlparser::ast::Expr::Function(func),
-                                ..
-                            } = item
-                            {
-                                return func.over.is_some();
-                            }
-                            false
-                        });
+                if let sqlparser::ast::Statement::Query(query) = &statements[0]
+                    && let sqlparser::ast::SetExpr::Select(select) = &*query.body
+                {
+                    // Check that we have window functions in projection
+                    let has_window_function = select.projection.iter().any(|item| {
+                        if let sqlparser::ast::SelectItem::ExprWithAlias {
+                            expr: sqlparser::ast::Expr::Function(func),
+                            ..
+                        } = item
+                        {
+                            return func.over.is_some();
+                        }
+                        false
+                    });
 
-                        assert!(
-                            has_window_function,
-                            "Query should contain window function: {sql}"
-                        );
-                    }
+                    assert!(
+                        has_window_function,
+                        "Query should contain window function: {sql}"
+                    );
                 }
             }
             Err(e) => {
@@ -65,28 +65,27 @@ fn test_window_function_with_partition_by_parsing() {
     assert!(result.is_ok(), "Should parse successfully");
 
     let statements = result.unwrap();
-    if let sqlparser::ast::Statement::Query(query) = &statements[0] {
-        if let sqlparser::ast::SetExpr::Select(select) = &*query.body {
-            // Find the window function
-            for item in &select.projection {
-                if let sqlparser::ast::SelectItem::ExprWithAlias { expr, alias } = item {
-                    if alias.value == "row_num" {
-                        if let sqlparser::ast::Expr::Function(func) = expr {
-                            assert!(func.over.is_some(), "Should have OVER clause");
+    if let sqlparser::ast::Statement::Query(query) = &statements[0]
+        && let sqlparser::ast::SetExpr::Select(select) = &*query.body
+    {
+        // Find the window function
+        for item in &select.projection {
+            if let sqlparser::ast::SelectItem::ExprWithAlias { expr, alias } = item
+                && alias.value == "row_num"
+                && let sqlparser::ast::Expr::Function(func) = expr
+            {
+                assert!(func.over.is_some(), "Should have OVER clause");
 
-                            if let Some(sqlparser::ast::WindowType::WindowSpec(spec)) = &func.over {
-                                assert!(!spec.partition_by.is_empty(), "Should have PARTITION BY");
-                                assert_eq!(
-                                    spec.partition_by.len(),
-                                    1,
-                                    "Should have one partition column"
-                                );
+                if let Some(sqlparser::ast::WindowType::WindowSpec(spec)) = &func.over {
+                    assert!(!spec.partition_by.is_empty(), "Should have PARTITION BY");
+                    assert_eq!(
+                        spec.partition_by.len(),
+                        1,
+                        "Should have one partition column"
+                    );
 
-                                assert!(!spec.order_by.is_empty(), "Should have ORDER BY");
-                                assert_eq!(spec.order_by.len(), 1, "Should have one order column");
-                            }
-                        }
-                    }
+                    assert!(!spec.order_by.is_empty(), "Should have ORDER BY");
+                    assert_eq!(spec.order_by.len(), 1, "Should have one order column");
                 }
             }
         }
@@ -105,24 +104,24 @@ fn test_multiple_window_functions_in_query() {
     assert!(result.is_ok(), "Should parse successfully");
 
     let statements = result.unwrap();
-    if let sqlparser::ast::Statement::Query(query) = &statements[0] {
-        if let sqlparser::ast::SetExpr::Select(select) = &*query.body {
-            let window_function_count = select
-                .projection
-                .iter()
-                .filter(|item| {
-                    if let sqlparser::ast::SelectItem::ExprWithAlias {
-                        expr: sqlparser::ast::Expr::Function(func),
-                        ..
-                    } = item
-                    {
-                        return func.over.is_some();
-                    }
-                    false
-                })
-                .count();
+    if let sqlparser::ast::Statement::Query(query) = &statements[0]
+        && let sqlparser::ast::SetExpr::Select(select) = &*query.body
+    {
+        let window_function_count = select
+            .projection
+            .iter()
+            .filter(|item| {
+                if let sqlparser::ast::SelectItem::ExprWithAlias {
+                    expr: sqlparser::ast::Expr::Function(func),
+                    ..
+                } = item
+                {
+                    return func.over.is_some();
+                }
+                false
+            })
+            .count();
 
-            assert_eq!(window_function_count, 2, "Should have two window functions");
-        }
+        assert_eq!(window_function_count, 2, "Should have two window functions");
     }
 }
@@ -22,6 +22,7 @@ async fn test_basic_window_functions() {
                 unique: true,
                 default: None,
                 references: None,
+                collation: None,
             },
             Column {
                 name: "username".to_string(),
@@ -31,6 +32,7 @@ async fn test_basic_window_functions() {
                 unique: false,
                 default: None,
                 references: None,
+                collation: None,
             },
         ],
     );
@@ -22,6 +22,7 @@ async fn test_window_functions() {
             unique: true,
             default: None,
             references: None,
+            collation: None,
         },
         Column {
             name: "salesperson".to_string(),
@@ -31,6 +32,7 @@ async fn test_window_functions() {
             unique: false,
             default: None,
             references: None,
+            collation: None,
         },
         Column {
             name: "region".to_string(),
@@ -40,6 +42,7 @@ async fn test_window_functions() {
             unique: false,
             default: None,
             references: None,
+            collation: None,
         },
         Column {
             name: "sales_amount".to_string(),
@@ -49,6 +52,7 @@ async fn test_window_functions() {
             unique: false,
             default: None,
             references: None,
+            collation: None,
         },
         Column {
             name: "quarter".to_string(),
@@ -58,6 +62,7 @@ async fn test_window_functions() {
             unique: false,
             default: None,
             references: None,
+            collation: None,
         },
     ];
 
@@ -344,6 +349,7 @@ async fn test_window_function_errors() {
             unique: true,
             default: None,
             references: None,
+            collation: None,
         },
         Column {
             name: "value".to_string(),
@@ -353,6 +359,7 @@ async fn test_window_function_errors() {
             unique: false,
             default: None,
             references: None,
+            collation: None,
         },
     ];
 